@@ -1,11 +1,17 @@
 //! Handler 模块
 
-use axum::{extract::State, Json};
+use axum::extract::State;
+use axum::http::HeaderMap;
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 use utoipa::ToSchema;
 
+use common::admin;
+use common::config::ConfigReload;
 use common::errors::AppError;
+use common::extract::Json;
+use common::middleware::auth::principal;
+use common::probes::{Liveness, Readiness};
 use common::response::ApiResponse;
 
 use crate::models::{
@@ -31,13 +37,15 @@ use crate::state::AppState;
 )]
 pub async fn natural_query(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<NaturalQueryRequest>,
 ) -> Result<Json<ApiResponse<NaturalQueryResponse>>, AppError> {
     let service = AiQueryService::new(
         state.ai_config.clone(),
         state.service_urls.clone(),
         state.http_client.clone(),
-    );
+    )
+    .with_principal(principal(&headers));
 
     let result = service.process_natural_query(req).await?;
     Ok(Json(ApiResponse::ok_with_service(result, "ai-service")))
@@ -59,13 +67,15 @@ pub async fn natural_query(
 )]
 pub async fn clarify(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<ClarifyRequest>,
 ) -> Result<Json<ApiResponse<ClarifyResponse>>, AppError> {
     let service = AiQueryService::new(
         state.ai_config.clone(),
         state.service_urls.clone(),
         state.http_client.clone(),
-    );
+    )
+    .with_principal(principal(&headers));
 
     let result = service.process_clarification(req).await?;
     Ok(Json(ApiResponse::ok_with_service(result, "ai-service")))
@@ -98,6 +108,55 @@ pub async fn validate_sql(
     Ok(Json(ApiResponse::ok_with_service(result, "ai-service")))
 }
 
+/// 存活探针：进程能响应请求即返回 200
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, description = "服务存活", body = Liveness)
+    )
+)]
+pub async fn healthz() -> Json<Liveness> {
+    Json(Liveness::new("ai-service"))
+}
+
+/// 就绪探针：本服务不依赖需要预先检查的存储，启动完成即就绪
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "服务就绪", body = Readiness)
+    )
+)]
+pub async fn readyz() -> Readiness {
+    Readiness::new("ai-service", Vec::new())
+}
+
+/// 重新加载配置（.env 文件与命令行覆盖），需要 X-Admin-Token
+///
+/// LLM 相关配置在启动时读取，重新加载后不变。
+#[utoipa::path(
+    post,
+    path = "/api/admin/config/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "已重新加载，返回变更的配置项与需重启才生效的配置项", body = ApiResponse<ConfigReload>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 500, description = "新配置无效，保留当前配置")
+    )
+)]
+pub async fn reload_config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<ConfigReload>>, AppError> {
+    admin::authorize(&headers)?;
+    let reload = state.config.reload()?;
+    Ok(Json(ApiResponse::ok_with_service(reload, "ai-service")))
+}
+
 /// 健康检查端点
 #[utoipa::path(
     get,
@@ -12,15 +12,19 @@ mod routes;
 mod service;
 mod state;
 
-use axum::{middleware, routing::get, Json, Router};
+use std::sync::Arc;
+
+use axum::{extract::DefaultBodyLimit, middleware, routing::get, Json, Router};
 use common::config::AppConfig;
+use common::fallback::with_json_fallbacks;
+use common::middleware::cors::cors_layer;
 use common::middleware::request_id::request_id_middleware;
+use common::middleware::signing::{signature_middleware, SignatureVerifier};
+use common::openapi::ResponseExamples;
 use state::AppState;
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::OpenApi;
 
 const SERVICE_NAME: &str = "ai-service";
@@ -38,6 +42,9 @@ const DEFAULT_PORT: u16 = 8083;
         handlers::clarify,
         handlers::validate_sql,
         handlers::health_check,
+        handlers::healthz,
+        handlers::readyz,
+        handlers::reload_config,
     ),
     components(schemas(
         models::NaturalQueryRequest,
@@ -48,58 +55,68 @@ const DEFAULT_PORT: u16 = 8083;
         models::ValidateSqlResponse,
         models::SqlReference,
         handlers::HealthResponse,
+        common::probes::Liveness,
+        common::probes::Readiness,
+        common::probes::ProbeCheck,
+        common::config::ConfigReload,
     )),
     tags(
         (name = "ai-query", description = "AI 智能查询端点"),
-        (name = "health", description = "健康检查端点")
-    )
+        (name = "health", description = "健康检查端点"),
+        (name = "admin", description = "配置重新加载")
+    ),
+    modifiers(&ResponseExamples)
 )]
 struct ApiDoc;
 
 #[tokio::main]
 async fn main() {
-    // 加载 .env 文件
-    load_dotenv();
-
-    // 初始化日志追踪
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".into()),
-        )
-        .init();
-
-    // 加载配置
-    let mut config = AppConfig::load_with_service(SERVICE_NAME);
-    config.port = std::env::var("SERVER_PORT")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(DEFAULT_PORT);
+    // 加载配置（.env 文件、环境变量、命令行覆盖），配置无效时列出问题并退出
+    let config = Arc::new(
+        AppConfig::loader(SERVICE_NAME)
+            .default_port(DEFAULT_PORT)
+            .startup_only(&["MAX_BODY_BYTES"])
+            .load_shared_or_exit(),
+    );
+
+    // 初始化日志追踪（LOG_FORMAT=json 时输出 JSON）
+    common::logging::init(&config.get());
 
     // 创建应用状态
     let state = AppState::new(config.clone());
+    // 收到 SIGHUP 时重新加载配置
+    config.reload_on_sighup();
 
     // 创建路由
     let app = create_router(state);
 
     // 启动服务
-    let addr = format!("{}:{}", config.host, config.port);
+    let addr = config.get().addr();
     info!(service = SERVICE_NAME, address = %addr, "启动服务");
 
     let listener = TcpListener::bind(&addr).await.expect("绑定地址失败");
-    axum::serve(listener, app).await.expect("服务启动失败");
+    // 配置 SERVICE_ADVERTISE_URL 时向网关注册并定期发送心跳
+    common::discovery::spawn_registration(SERVICE_NAME);
+    // 配置 TLS_CERT_PATH 与 TLS_KEY_PATH 时以 HTTPS 提供服务
+    common::tls::serve(listener, app, config.get().tls.as_ref())
+        .await
+        .expect("服务启动失败");
 }
 
 fn create_router(state: AppState) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // 跨域设置只在启动时读取
+    let cors = cors_layer(&state.config.get().cors);
+
+    let body_limit = DefaultBodyLimit::max(state.config.get().max_body_bytes);
+    let signatures = Arc::new(SignatureVerifier::from_env());
 
-    Router::new()
+    let router = Router::new()
         .merge(routes::router())
-        .route("/api-docs/openapi.json", get(openapi_json))
+        .route("/api-docs/openapi.json", get(openapi_json));
+
+    with_json_fallbacks(router, "ai-service")
+        .layer(middleware::from_fn_with_state(signatures, signature_middleware))
+        .layer(body_limit)
         .layer(middleware::from_fn(request_id_middleware))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
@@ -109,24 +126,3 @@ fn create_router(state: AppState) -> Router {
 async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
     Json(ApiDoc::openapi())
 }
-
-fn load_dotenv() {
-    let env_path = std::path::Path::new(".env");
-    if env_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(env_path) {
-            for line in content.lines() {
-                let line = line.trim();
-                if line.is_empty() || line.starts_with('#') {
-                    continue;
-                }
-                if let Some((key, value)) = line.split_once('=') {
-                    let key = key.trim();
-                    let value = value.trim();
-                    if std::env::var(key).is_err() {
-                        std::env::set_var(key, value);
-                    }
-                }
-            }
-        }
-    }
-}
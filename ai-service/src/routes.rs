@@ -15,5 +15,9 @@ pub fn router() -> Router<AppState> {
         .route("/api/ai/clarify", post(handlers::clarify))
         .route("/api/ai/validate", post(handlers::validate_sql))
         // 健康检查
+        .route("/healthz", get(handlers::healthz))
+        .route("/readyz", get(handlers::readyz))
         .route("/api/health", get(handlers::health_check))
+        // 管理
+        .route("/api/admin/config/reload", post(handlers::reload_config))
 }
@@ -6,6 +6,7 @@ use uuid::Uuid;
 
 use common::config::ServiceUrls;
 use common::errors::{AppError, AppResult};
+use common::middleware::auth::PRINCIPAL_HEADER;
 use common::models::database::TableSchema;
 use common::response::ApiResponse;
 use common::utils::SqlValidator;
@@ -88,6 +89,8 @@ pub struct AiQueryService {
     ai_config: AiConfig,
     service_urls: ServiceUrls,
     http_client: reqwest::Client,
+    /// 发起请求的调用方，读取表结构时转发给连接服务
+    principal: Option<String>,
 }
 
 impl AiQueryService {
@@ -101,9 +104,16 @@ impl AiQueryService {
             ai_config,
             service_urls,
             http_client,
+            principal: None,
         }
     }
 
+    /// 以 `principal` 的名义读取连接的表结构（只能读取调用方可见的连接）
+    pub fn with_principal(mut self, principal: Option<&str>) -> Self {
+        self.principal = principal.map(str::to_string);
+        self
+    }
+
     /// 处理自然语言查询 - 核心 Text2SQL 流程
     pub async fn process_natural_query(
         &self,
@@ -414,9 +424,11 @@ impl AiQueryService {
 
         info!(url = %url, "获取数据库 Schema");
 
-        let response = self
-            .http_client
-            .get(&url)
+        let mut request = self.http_client.get(&url);
+        if let Some(principal) = &self.principal {
+            request = request.header(PRINCIPAL_HEADER, principal);
+        }
+        let response = request
             .send()
             .await
             .map_err(|e| AppError::ExternalService(format!("获取 Schema 失败: {}", e)))?;
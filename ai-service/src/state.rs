@@ -1,6 +1,8 @@
 //! AI 服务应用状态
 
-use common::config::{AppConfig, ServiceUrls};
+use std::sync::Arc;
+
+use common::config::{ServiceUrls, SharedConfig};
 
 /// AI 服务配置
 #[derive(Clone)]
@@ -15,12 +17,14 @@ pub struct AiConfig {
     pub default_model: String,
 
     /// 高精度模型
+    #[allow(dead_code)]
     pub high_precision_model: String,
 
     /// 最大 Token 数
     pub max_tokens: u32,
 
     /// 置信度阈值（低于此值触发澄清）
+    #[allow(dead_code)]
     pub confidence_threshold: f64,
 }
 
@@ -50,7 +54,7 @@ impl Default for AiConfig {
 #[derive(Clone)]
 pub struct AppState {
     /// 通用配置
-    pub config: AppConfig,
+    pub config: Arc<SharedConfig>,
 
     /// AI 配置
     pub ai_config: AiConfig,
@@ -64,7 +68,7 @@ pub struct AppState {
 
 impl AppState {
     /// 创建新的应用状态
-    pub fn new(config: AppConfig) -> Self {
+    pub fn new(config: Arc<SharedConfig>) -> Self {
         Self {
             config,
             ai_config: AiConfig::default(),
@@ -0,0 +1,77 @@
+//! Client error types.
+
+use common::response::ErrorResponse;
+use reqwest::StatusCode;
+use thiserror::Error;
+
+/// Error returned by [`Client`](crate::Client) calls.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The base URL cannot be parsed or cannot have a path.
+    #[error("invalid base URL: {0}")]
+    InvalidUrl(String),
+
+    /// The request could not be sent or its response not read; the cause is
+    /// the error's `source`.
+    #[error("request failed")]
+    Http(#[from] reqwest::Error),
+
+    /// The service answered with an error.
+    #[error("{message} ({code}, HTTP {status})")]
+    Api {
+        /// HTTP status.
+        status: u16,
+        /// Error code, e.g. `CONNECTION_NOT_FOUND`.
+        code: String,
+        /// Error message.
+        message: String,
+        /// Error details, e.g. the confirmation token of a dangerous statement.
+        details: Option<serde_json::Value>,
+    },
+
+    /// A successful response could not be decoded; the cause is the error's `source`.
+    #[error("invalid response")]
+    Decode(#[from] serde_json::Error),
+
+    /// A successful response carried no `data`.
+    #[error("response carried no data")]
+    MissingData,
+}
+
+impl ClientError {
+    /// Builds the error for a non-success response from its body, which is
+    /// an [`ErrorResponse`] unless a proxy in front of the gateway answered.
+    pub(crate) fn from_response(status: StatusCode, body: &[u8]) -> Self {
+        match serde_json::from_slice::<ErrorResponse>(body) {
+            Ok(response) => ClientError::Api {
+                status: status.as_u16(),
+                code: response.error.code,
+                message: response.error.message,
+                details: response.error.details,
+            },
+            Err(_) => ClientError::Api {
+                status: status.as_u16(),
+                code: format!("HTTP_{}", status.as_u16()),
+                message: String::from_utf8_lossy(body).trim().to_string(),
+                details: None,
+            },
+        }
+    }
+
+    /// HTTP status of an error response.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            ClientError::Http(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Error code of an error response.
+    pub fn code(&self) -> Option<&str> {
+        match self {
+            ClientError::Api { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+}
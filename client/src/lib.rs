@@ -0,0 +1,336 @@
+//! Typed Rust client for the database management services.
+//!
+//! Calls go through the gateway, which routes them to the connection, query
+//! and AI services, and use the request and response models of `common`:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), client::ClientError> {
+//! use client::Client;
+//! use common::models::{CreateConnectionRequest, DbType, QueryRequest};
+//!
+//! let client = Client::new("http://localhost:8080")?.with_api_key("dbm_...");
+//! let connection = client
+//!     .create_connection(&CreateConnectionRequest {
+//!         name: "local".to_string(),
+//!         db_type: Some(DbType::SQLite),
+//!         file_path: Some("./data/app.db".to_string()),
+//!         ..Default::default()
+//!     })
+//!     .await?;
+//! let result = client
+//!     .execute_query(&QueryRequest::new(connection.id, "SELECT 1"))
+//!     .await?;
+//! println!("{} rows", result.row_count);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every call returns the `data` of the service's `ApiResponse`; error
+//! responses become [`ClientError::Api`] with the service's error code.
+
+mod error;
+
+use reqwest::{Method, RequestBuilder, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use common::middleware::auth::{API_KEY_HEADER, WORKSPACE_HEADER};
+use common::models::analysis::IndexAdvice;
+use common::models::auth::{AuthTokens, LoginRequest, RefreshRequest};
+use common::models::connection::{ConnectionItem, ConnectionView, CreateConnectionRequest};
+use common::models::diagnostics::ConnectionTestResult;
+use common::models::monitor::DatabaseInfo;
+use common::models::query::{
+    BatchQueryRequest, BatchQueryResult, FormatSqlRequest, FormattedSql, QueryJob, QueryRequest, QueryResult,
+};
+use common::models::session::Session;
+use common::response::ApiResponse;
+
+pub use error::ClientError;
+
+/// Result of a client call.
+pub type ClientResult<T> = Result<T, ClientError>;
+
+/// Client for the gateway's HTTP API.
+///
+/// Cheap to clone; clones share the underlying connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: Url,
+    token: Option<String>,
+    api_key: Option<String>,
+    workspace: Option<String>,
+}
+
+impl Client {
+    /// Creates a client for the gateway at `base_url`, e.g. `http://localhost:8080`.
+    ///
+    /// # Errors
+    /// Returns `ClientError::InvalidUrl` if `base_url` is not an HTTP(S) URL.
+    pub fn new(base_url: &str) -> ClientResult<Self> {
+        let base_url = Url::parse(base_url).map_err(|e| ClientError::InvalidUrl(format!("{}: {}", base_url, e)))?;
+        if base_url.cannot_be_a_base() || !matches!(base_url.scheme(), "http" | "https") {
+            return Err(ClientError::InvalidUrl(base_url.to_string()));
+        }
+        Ok(Self {
+            http: reqwest::Client::new(),
+            base_url,
+            token: None,
+            api_key: None,
+            workspace: None,
+        })
+    }
+
+    /// Uses a preconfigured HTTP client, e.g. with timeouts or a proxy.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Sends an access token (from [`Client::login`]) as `Authorization: Bearer`.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Sends an API key as `X-Api-Key`.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Sends `X-Workspace-Id`, limiting connection and snapshot lists to a workspace.
+    pub fn with_workspace(mut self, workspace_id: impl Into<String>) -> Self {
+        self.workspace = Some(workspace_id.into());
+        self
+    }
+
+    /// The gateway URL.
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    // ==================== Authentication ====================
+
+    /// Logs in with a username and password.
+    ///
+    /// The client keeps its credentials; pass the returned access token to
+    /// [`Client::with_token`].
+    pub async fn login(&self, username: &str, password: &str) -> ClientResult<AuthTokens> {
+        let req = LoginRequest {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        self.send(self.request(Method::POST, &["api", "auth", "login"])?.json(&req)).await
+    }
+
+    /// Exchanges a refresh token for new tokens; the refresh token is rotated.
+    pub async fn refresh_token(&self, refresh_token: &str) -> ClientResult<AuthTokens> {
+        let req = RefreshRequest {
+            refresh_token: refresh_token.to_string(),
+        };
+        self.send(self.request(Method::POST, &["api", "auth", "refresh"])?.json(&req)).await
+    }
+
+    /// Revokes the session of the client's access token.
+    pub async fn logout(&self) -> ClientResult<Session> {
+        self.send(self.request(Method::POST, &["api", "auth", "logout"])?).await
+    }
+
+    // ==================== Connections ====================
+
+    /// Lists the connections visible to the caller; `view` selects the
+    /// caller's favorite or recently used connections.
+    pub async fn list_connections(&self, view: Option<ConnectionView>) -> ClientResult<Vec<ConnectionItem>> {
+        let mut req = self.request(Method::GET, &["api", "connections"])?;
+        if let Some(view) = view {
+            req = req.query(&[("view", view)]);
+        }
+        self.send(req).await
+    }
+
+    /// Gets a connection.
+    pub async fn get_connection(&self, id: &str) -> ClientResult<ConnectionItem> {
+        self.send(self.request(Method::GET, &["api", "connections", id])?).await
+    }
+
+    /// Saves a connection.
+    pub async fn create_connection(&self, req: &CreateConnectionRequest) -> ClientResult<ConnectionItem> {
+        self.send(self.request(Method::POST, &["api", "connections"])?.json(req)).await
+    }
+
+    /// Deletes a connection and closes its pool.
+    pub async fn delete_connection(&self, id: &str) -> ClientResult<bool> {
+        self.send(self.request(Method::DELETE, &["api", "connections", id])?).await
+    }
+
+    /// Tests a saved connection stage by stage.
+    pub async fn test_connection(&self, id: &str) -> ClientResult<ConnectionTestResult> {
+        self.send(self.request(Method::GET, &["api", "connections", id, "test"])?).await
+    }
+
+    /// Tests a connection configuration without saving it.
+    pub async fn test_unsaved_connection(&self, req: &CreateConnectionRequest) -> ClientResult<ConnectionTestResult> {
+        self.send(self.request(Method::POST, &["api", "connections", "test"])?.json(req)).await
+    }
+
+    /// Lists the databases on a connection's server.
+    pub async fn list_databases(&self, id: &str) -> ClientResult<Vec<DatabaseInfo>> {
+        self.send(self.request(Method::GET, &["api", "connections", id, "databases"])?).await
+    }
+
+    // ==================== Queries ====================
+
+    /// Executes a statement.
+    ///
+    /// UPDATE/DELETE without WHERE and DDL fail with HTTP 428 until repeated
+    /// with the `confirmation_token` from the error's details.
+    pub async fn execute_query(&self, req: &QueryRequest) -> ClientResult<QueryResult> {
+        self.send(self.request(Method::POST, &["api", "query"])?.json(req)).await
+    }
+
+    /// Executes several read-only statements, possibly on different
+    /// connections, in one request.
+    ///
+    /// Each statement succeeds or fails on its own; the results are in request order.
+    pub async fn execute_batch(&self, req: &BatchQueryRequest) -> ClientResult<BatchQueryResult> {
+        self.send(self.request(Method::POST, &["api", "query", "batch"])?.json(req)).await
+    }
+
+    /// Suggests indexes for a statement from its execution plan.
+    pub async fn analyze_query(&self, req: &QueryRequest) -> ClientResult<IndexAdvice> {
+        self.send(self.request(Method::POST, &["api", "query", "analyze"])?.json(req)).await
+    }
+
+    /// Formats SQL text.
+    pub async fn format_sql(&self, req: &FormatSqlRequest) -> ClientResult<FormattedSql> {
+        self.send(self.request(Method::POST, &["api", "query", "format"])?.json(req)).await
+    }
+
+    /// Submits a statement to run in the background.
+    pub async fn submit_async_query(&self, req: &QueryRequest) -> ClientResult<QueryJob> {
+        self.send(self.request(Method::POST, &["api", "query", "async"])?.json(req)).await
+    }
+
+    /// Gets a background query, with its result once it finished.
+    pub async fn get_query_job(&self, id: &str) -> ClientResult<QueryJob> {
+        self.send(self.request(Method::GET, &["api", "query", "jobs", id])?).await
+    }
+
+    // ==================== Requests ====================
+
+    /// Builds a request to the path made of `segments`, each percent-encoded,
+    /// with the client's credentials.
+    fn request(&self, method: Method, segments: &[&str]) -> ClientResult<RequestBuilder> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| ClientError::InvalidUrl(self.base_url.to_string()))?
+            .pop_if_empty()
+            .extend(segments);
+
+        let mut req = self.http.request(method, url);
+        if let Some(token) = &self.token {
+            req = req.bearer_auth(token);
+        }
+        if let Some(api_key) = &self.api_key {
+            req = req.header(API_KEY_HEADER, api_key);
+        }
+        if let Some(workspace) = &self.workspace {
+            req = req.header(WORKSPACE_HEADER, workspace);
+        }
+        Ok(req)
+    }
+
+    /// Sends a request and returns the `data` of its response.
+    async fn send<T: Serialize + DeserializeOwned>(&self, req: RequestBuilder) -> ClientResult<T> {
+        let response = req.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            return Err(ClientError::from_response(status, &body));
+        }
+        let response: ApiResponse<T> = serde_json::from_slice(&body)?;
+        response.data.ok_or(ClientError::MissingData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::Path;
+    use axum::http::HeaderMap;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use common::errors::AppError;
+
+    use super::*;
+
+    /// Serves `router` on a local port and returns a client for it.
+    async fn serve(router: Router) -> Client {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        Client::new(&format!("http://{}", addr)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn decodes_data_and_sends_credentials() {
+        let router = Router::new()
+            .route(
+                "/api/connections/{id}/test",
+                get(|Path(id): Path<String>, headers: HeaderMap| async move {
+                    assert_eq!(headers["authorization"], "Bearer token");
+                    assert_eq!(headers[API_KEY_HEADER], "key");
+                    assert_eq!(headers[WORKSPACE_HEADER], "ws");
+                    Json(ApiResponse::ok(ConnectionTestResult::from_stages(id, Vec::new())))
+                }),
+            )
+            .route(
+                "/api/query",
+                post(|Json(req): Json<QueryRequest>| async move {
+                    let mut result = QueryResult::empty();
+                    result.rows = vec![vec![req.sql.into()]];
+                    result.row_count = 1;
+                    Json(ApiResponse::ok(result))
+                }),
+            );
+        let client = serve(router).await.with_token("token").with_api_key("key").with_workspace("ws");
+
+        // Path segments are percent-encoded
+        let tested = client.test_connection("a/b").await.unwrap();
+        assert_eq!(tested.id, "a/b");
+        assert!(tested.success);
+
+        let result = client.execute_query(&QueryRequest::new("c1", "SELECT 1")).await.unwrap();
+        assert_eq!(result.rows, vec![vec![serde_json::json!("SELECT 1")]]);
+    }
+
+    #[tokio::test]
+    async fn maps_error_responses() {
+        let router = Router::new()
+            .route(
+                "/api/connections/{id}",
+                get(|Path(id): Path<String>| async move { Err::<Json<ApiResponse<bool>>, _>(AppError::ConnectionNotFound(id)) }),
+            )
+            .route("/api/connections", get(|| async { (axum::http::StatusCode::BAD_GATEWAY, "upstream down") }));
+        let client = serve(router).await;
+
+        let err = client.get_connection("missing").await.unwrap_err();
+        assert_eq!(err.status(), Some(404));
+        assert_eq!(err.code(), Some("CONNECTION_NOT_FOUND"));
+
+        let err = client.list_connections(None).await.unwrap_err();
+        assert_eq!(err.status(), Some(502));
+        assert_eq!(err.code(), Some("HTTP_502"));
+        assert!(err.to_string().contains("upstream down"));
+    }
+
+    #[test]
+    fn rejects_invalid_base_urls() {
+        assert!(matches!(Client::new("localhost:8080"), Err(ClientError::InvalidUrl(_))));
+        assert!(matches!(Client::new("mailto:ops@example.com"), Err(ClientError::InvalidUrl(_))));
+        let client = Client::new("http://gateway.local/dbm/").unwrap();
+        let req = client.request(Method::GET, &["api", "connections", "a b"]).unwrap().build().unwrap();
+        assert_eq!(req.url().as_str(), "http://gateway.local/dbm/api/connections/a%20b");
+    }
+}
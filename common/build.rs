@@ -0,0 +1,15 @@
+//! Generates the internal gRPC interface (`proto/connection.proto`) when the
+//! `grpc` feature is enabled.
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        // Use the bundled protoc so builds don't need one installed
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("bundled protoc is available for this platform");
+        std::env::set_var("PROTOC", protoc);
+        tonic_prost_build::configure()
+            .build_transport(false)
+            .compile_protos(&["proto/connection.proto"], &["proto"])
+            .expect("failed to compile proto/connection.proto");
+    }
+}
@@ -0,0 +1,37 @@
+//! Admin endpoint authorization.
+//!
+//! Admin endpoints (metadata export/import, API key and policy management,
+//! configuration reload) are disabled unless `METADATA_ADMIN_TOKEN` is set;
+//! requests must then carry it in the `X-Admin-Token` header.
+
+use axum::http::HeaderMap;
+
+use crate::errors::{AppError, AppResult};
+
+/// Header carrying the admin token.
+pub const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Checks the admin token of a request.
+///
+/// # Errors
+/// `AppError::Forbidden` if admin endpoints are disabled, `AppError::Unauthorized`
+/// if the token is missing or wrong.
+pub fn authorize(headers: &HeaderMap) -> AppResult<()> {
+    let expected = std::env::var("METADATA_ADMIN_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| AppError::Forbidden("admin endpoints are disabled".to_string()))?;
+    let provided = headers
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        Ok(())
+    } else {
+        Err(AppError::Unauthorized)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
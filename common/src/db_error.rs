@@ -0,0 +1,497 @@
+//! Structured database driver errors.
+//!
+//! Classifies raw sqlx / redis errors into categories clients can act on
+//! (authentication failure, unknown database, syntax error, constraint
+//! violation, timeout) and extracts the engine-specific details: SQLSTATE,
+//! MySQL error number, syntax error position and the violated constraint.
+//! The details are returned in `ApiError.details`.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sqlx::error::ErrorKind;
+use sqlx::mysql::MySqlDatabaseError;
+use sqlx::postgres::{PgDatabaseError, PgErrorPosition};
+use utoipa::ToSchema;
+
+/// Category of a database driver error.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DbErrorCategory {
+    /// Wrong credentials or insufficient privileges to connect.
+    AuthFailed,
+    /// The requested database / catalog does not exist.
+    UnknownDatabase,
+    /// SQL syntax error; see `position` / `line`.
+    Syntax,
+    /// Unique, foreign key, not-null or check constraint violated.
+    ConstraintViolation,
+    /// Statement, lock or pool timeout.
+    Timeout,
+    /// Network or protocol failure talking to the server.
+    Connection,
+    /// Any other error reported by the database.
+    Other,
+}
+
+impl DbErrorCategory {
+    /// Returns the error code string used in API responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DbErrorCategory::AuthFailed => "DATABASE_AUTH_FAILED",
+            DbErrorCategory::UnknownDatabase => "UNKNOWN_DATABASE",
+            DbErrorCategory::Syntax => "SQL_SYNTAX_ERROR",
+            DbErrorCategory::ConstraintViolation => "CONSTRAINT_VIOLATION",
+            DbErrorCategory::Timeout => "DATABASE_TIMEOUT",
+            DbErrorCategory::Connection => "DATABASE_CONNECTION_ERROR",
+            DbErrorCategory::Other => "DATABASE_QUERY_ERROR",
+        }
+    }
+}
+
+/// Details of a classified database error, returned in `ApiError.details`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DbErrorDetails {
+    /// Error category.
+    pub category: DbErrorCategory,
+    /// SQLSTATE reported by the server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sqlstate: Option<String>,
+    /// Engine-specific error number (MySQL).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendor_code: Option<u32>,
+    /// 1-based character offset of the error in the statement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    /// 1-based line of the error in the statement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Statement fragment where the syntax error was detected (MySQL).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub near: Option<String>,
+    /// Violated constraint or index name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constraint: Option<String>,
+    /// Table the error refers to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    /// Column the error refers to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    /// What was violated, for constraint violations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub violation: Option<ConstraintViolation>,
+    /// Driver error message.
+    pub message: String,
+}
+
+/// Kind of a violated constraint.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstraintKind {
+    /// Unique key or primary key.
+    Unique,
+    /// Foreign key.
+    ForeignKey,
+    /// NOT NULL column.
+    NotNull,
+    /// CHECK constraint.
+    Check,
+}
+
+/// Constraint violation parsed from the engine error, so data editors can
+/// point the user at the offending column and value.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConstraintViolation {
+    /// Constraint kind.
+    pub kind: ConstraintKind,
+    /// Columns covered by the constraint, when reported.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    /// Conflicting value as reported by the engine.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Parent table a foreign key points to (failed insert/update of a child row).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referenced_table: Option<String>,
+    /// Parent columns a foreign key points to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub referenced_columns: Vec<String>,
+    /// Child table still referencing the row (failed delete/update of a parent row).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referencing_table: Option<String>,
+}
+
+impl ConstraintViolation {
+    fn new(kind: ConstraintKind) -> Self {
+        Self {
+            kind,
+            columns: Vec::new(),
+            value: None,
+            referenced_table: None,
+            referenced_columns: Vec::new(),
+            referencing_table: None,
+        }
+    }
+}
+
+impl fmt::Display for DbErrorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = match self.category {
+            DbErrorCategory::AuthFailed => "database authentication failed",
+            DbErrorCategory::UnknownDatabase => "unknown database",
+            DbErrorCategory::Syntax => "SQL syntax error",
+            DbErrorCategory::ConstraintViolation => "constraint violation",
+            DbErrorCategory::Timeout => "database timeout",
+            DbErrorCategory::Connection => "database connection failed",
+            DbErrorCategory::Other => "database query failed",
+        };
+        write!(f, "{}: {}", prefix, self.message)
+    }
+}
+
+impl DbErrorDetails {
+    fn new(category: DbErrorCategory, message: impl Into<String>) -> Self {
+        Self {
+            category,
+            sqlstate: None,
+            vendor_code: None,
+            position: None,
+            line: None,
+            near: None,
+            constraint: None,
+            table: None,
+            column: None,
+            violation: None,
+            message: message.into(),
+        }
+    }
+
+    /// Classifies a sqlx error; `None` for errors that are not driver failures
+    /// (e.g. `RowNotFound`, decode or configuration errors).
+    pub fn from_sqlx(err: &sqlx::Error) -> Option<Self> {
+        match err {
+            sqlx::Error::Database(db) => {
+                if let Some(mysql) = db.try_downcast_ref::<MySqlDatabaseError>() {
+                    return Some(Self::from_mysql(
+                        mysql.number(),
+                        mysql.code().map(str::to_string),
+                        mysql.message(),
+                    ));
+                }
+                if let Some(pg) = db.try_downcast_ref::<PgDatabaseError>() {
+                    return Some(Self::from_postgres(pg));
+                }
+                let kind = match db.kind() {
+                    ErrorKind::UniqueViolation => Some(ConstraintKind::Unique),
+                    ErrorKind::ForeignKeyViolation => Some(ConstraintKind::ForeignKey),
+                    ErrorKind::NotNullViolation => Some(ConstraintKind::NotNull),
+                    ErrorKind::CheckViolation => Some(ConstraintKind::Check),
+                    _ => None,
+                };
+                let category = match kind {
+                    Some(_) => DbErrorCategory::ConstraintViolation,
+                    None => DbErrorCategory::Other,
+                };
+                let mut details = Self::new(category, db.message());
+                details.constraint = db.constraint().map(str::to_string);
+                details.table = db.table().map(str::to_string);
+                details.violation = kind.map(ConstraintViolation::new);
+                Some(details)
+            }
+            sqlx::Error::PoolTimedOut => Some(Self::new(
+                DbErrorCategory::Timeout,
+                "timed out waiting for a pooled connection",
+            )),
+            sqlx::Error::Io(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                Some(Self::new(DbErrorCategory::Timeout, e.to_string()))
+            }
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::Protocol(_)
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => Some(Self::new(DbErrorCategory::Connection, err.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Classifies a MySQL server error by its error number.
+    pub fn from_mysql(number: u16, sqlstate: Option<String>, message: &str) -> Self {
+        let category = match number {
+            1044 | 1045 | 1698 => DbErrorCategory::AuthFailed,
+            1049 => DbErrorCategory::UnknownDatabase,
+            1064 | 1149 => DbErrorCategory::Syntax,
+            1048 | 1062 | 1216 | 1217 | 1451 | 1452 | 1557 | 1586 | 3819 => {
+                DbErrorCategory::ConstraintViolation
+            }
+            1205 | 3024 => DbErrorCategory::Timeout,
+            _ => DbErrorCategory::Other,
+        };
+        let mut details = Self::new(category, message);
+        details.sqlstate = sqlstate;
+        details.vendor_code = Some(number as u32);
+
+        match category {
+            DbErrorCategory::Syntax => {
+                details.near = quoted_after(message, "near ");
+                details.line = message
+                    .rsplit_once(" at line ")
+                    .and_then(|(_, line)| line.trim().parse().ok());
+            }
+            DbErrorCategory::ConstraintViolation => parse_mysql_constraint(number, message, &mut details),
+            _ => {}
+        }
+        details
+    }
+
+    fn from_postgres(pg: &PgDatabaseError) -> Self {
+        let sqlstate = pg.code();
+        let category = match sqlstate {
+            "28000" | "28P01" => DbErrorCategory::AuthFailed,
+            "3D000" => DbErrorCategory::UnknownDatabase,
+            "42601" => DbErrorCategory::Syntax,
+            "57014" | "55P03" => DbErrorCategory::Timeout,
+            s if s.starts_with("23") => DbErrorCategory::ConstraintViolation,
+            s if s.starts_with("08") => DbErrorCategory::Connection,
+            _ => DbErrorCategory::Other,
+        };
+        let mut details = Self::new(category, pg.message());
+        details.sqlstate = Some(sqlstate.to_string());
+        details.position = match pg.position() {
+            Some(PgErrorPosition::Original(position)) => Some(position),
+            _ => None,
+        };
+        details.constraint = pg.constraint().map(str::to_string);
+        details.table = pg.table().map(str::to_string);
+        details.column = pg.column().map(str::to_string);
+        details.violation = pg_violation(sqlstate, pg.detail(), details.column.as_deref());
+        details
+    }
+
+    /// Classifies a redis error; `None` for errors not worth a dedicated category.
+    pub fn from_redis(err: &redis::RedisError) -> Option<Self> {
+        let category = if err.kind() == redis::ErrorKind::AuthenticationFailed
+            || err.code() == Some("NOAUTH")
+            || err.code() == Some("WRONGPASS")
+        {
+            DbErrorCategory::AuthFailed
+        } else if err.is_timeout() {
+            DbErrorCategory::Timeout
+        } else if err.is_connection_refusal() || err.is_connection_dropped() || err.is_io_error() {
+            DbErrorCategory::Connection
+        } else {
+            return None;
+        };
+        let mut details = Self::new(category, err.to_string());
+        details.sqlstate = err.code().map(str::to_string);
+        Some(details)
+    }
+
+    /// Fills in the missing one of `position` / `line` from the executed statement,
+    /// so editors can jump to the error.
+    pub fn locate(&mut self, sql: &str) {
+        if self.category != DbErrorCategory::Syntax {
+            return;
+        }
+        if let Some(position) = self.position {
+            let prefix: String = sql.chars().take(position.saturating_sub(1)).collect();
+            self.line = Some(prefix.matches('\n').count() + 1);
+            return;
+        }
+        let (Some(line), Some(near)) = (self.line, self.near.as_deref()) else {
+            return;
+        };
+        let line_start: usize = sql
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(|l| l.chars().count())
+            .sum();
+        let rest: String = sql.chars().skip(line_start).collect();
+        // MySQL reports an empty fragment when the statement ended unexpectedly.
+        let offset = if near.is_empty() {
+            rest.chars().count()
+        } else {
+            match rest.find(near) {
+                Some(byte_idx) => rest[..byte_idx].chars().count(),
+                None => return,
+            }
+        };
+        self.position = Some(line_start + offset + 1);
+    }
+}
+
+/// Returns the single-quoted text following `marker`, e.g. `near 'FORM t' at line 1`.
+fn quoted_after(message: &str, marker: &str) -> Option<String> {
+    let start = message.find(marker)? + marker.len();
+    let rest = message[start..].strip_prefix('\'')?;
+    let end = rest.rfind('\'')?;
+    Some(rest[..end].to_string())
+}
+
+/// Returns the text between the first `open` after `marker` and the following `close`.
+fn delimited_after(message: &str, marker: &str, open: char, close: char) -> Option<String> {
+    let start = message.find(marker)? + marker.len();
+    let rest = &message[start..];
+    let begin = rest.find(open)? + open.len_utf8();
+    let end = rest[begin..].find(close)? + begin;
+    Some(rest[begin..end].to_string())
+}
+
+fn parse_mysql_constraint(number: u16, message: &str, details: &mut DbErrorDetails) {
+    match number {
+        // Duplicate entry 'x' for key 'users.uk_email' (8.0 prefixes the table)
+        1062 | 1586 | 1557 => {
+            if let Some(key) = quoted_after(message, "for key ") {
+                match key.split_once('.') {
+                    Some((table, name)) => {
+                        details.table = Some(table.to_string());
+                        details.constraint = Some(name.to_string());
+                    }
+                    None => details.constraint = Some(key),
+                }
+            }
+            let mut violation = ConstraintViolation::new(ConstraintKind::Unique);
+            violation.value = between(message, "Duplicate entry '", "' for key");
+            details.violation = Some(violation);
+        }
+        // ... fails (`db`.`orders`, CONSTRAINT `fk_user` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`))
+        1216 | 1217 | 1451 | 1452 => {
+            details.constraint = delimited_after(message, "CONSTRAINT ", '`', '`');
+            details.column = delimited_after(message, "FOREIGN KEY ", '`', '`');
+            details.table = message
+                .split_once("fails (")
+                .and_then(|(_, rest)| rest.split_once(','))
+                .and_then(|(qualified, _)| qualified.rsplit('.').next())
+                .map(|table| table.trim_matches('`').to_string());
+
+            let mut violation = ConstraintViolation::new(ConstraintKind::ForeignKey);
+            violation.columns = delimited_after(message, "FOREIGN KEY ", '(', ')')
+                .map(|list| identifier_list(&list))
+                .unwrap_or_default();
+            violation.referenced_table = delimited_after(message, "REFERENCES ", '`', '`');
+            violation.referenced_columns = message
+                .split_once("REFERENCES ")
+                .and_then(|(_, rest)| delimited_after(rest, "` ", '(', ')'))
+                .map(|list| identifier_list(&list))
+                .unwrap_or_default();
+            // Deleting or updating a parent row: the child table still references it.
+            if matches!(number, 1217 | 1451) {
+                violation.referencing_table = details.table.clone();
+            }
+            details.violation = Some(violation);
+        }
+        // Column 'name' cannot be null
+        1048 => {
+            details.column = quoted_after(message, "Column ");
+            let mut violation = ConstraintViolation::new(ConstraintKind::NotNull);
+            violation.columns = details.column.iter().cloned().collect();
+            details.violation = Some(violation);
+        }
+        // Check constraint 'chk_price' is violated.
+        3819 => {
+            details.constraint = quoted_after(message, "constraint ");
+            details.violation = Some(ConstraintViolation::new(ConstraintKind::Check));
+        }
+        _ => {}
+    }
+}
+
+/// Builds the violation from a PostgreSQL integrity error and its `DETAIL` line,
+/// e.g. `Key (email)=(a@b.c) already exists.`
+fn pg_violation(sqlstate: &str, detail: Option<&str>, column: Option<&str>) -> Option<ConstraintViolation> {
+    let kind = match sqlstate {
+        "23505" => ConstraintKind::Unique,
+        "23503" => ConstraintKind::ForeignKey,
+        "23502" => ConstraintKind::NotNull,
+        "23514" => ConstraintKind::Check,
+        _ => return None,
+    };
+    let mut violation = ConstraintViolation::new(kind);
+    violation.columns = column.map(|c| vec![c.to_string()]).unwrap_or_default();
+
+    if let Some(detail) = detail {
+        if let Some(columns) = between(detail, "Key (", ")=(") {
+            violation.columns = columns.split(", ").map(|c| c.trim_matches('"').to_string()).collect();
+        }
+        violation.value = detail
+            .split_once(")=(")
+            .and_then(|(_, rest)| rest.rsplit_once(") "))
+            .map(|(value, _)| value.to_string());
+        violation.referenced_table = between(detail, "is not present in table \"", "\"");
+        violation.referencing_table = between(detail, "is still referenced from table \"", "\"");
+    }
+    Some(violation)
+}
+
+/// Returns the text between `start` and the next `end`.
+fn between(text: &str, start: &str, end: &str) -> Option<String> {
+    let (_, rest) = text.split_once(start)?;
+    let (inner, _) = rest.split_once(end)?;
+    Some(inner.to_string())
+}
+
+/// Splits a backquoted identifier list such as `` `a`, `b` ``.
+fn identifier_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|c| c.trim().trim_matches('`').to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_mysql_syntax_errors() {
+        let sql = "SELECT id\nFORM users";
+        let mut details = DbErrorDetails::from_mysql(
+            1064,
+            Some("42000".into()),
+            "You have an error in your SQL syntax; check the manual that corresponds to your \
+             MySQL server version for the right syntax to use near 'FORM users' at line 2",
+        );
+        assert_eq!(details.category, DbErrorCategory::Syntax);
+        assert_eq!(details.near.as_deref(), Some("FORM users"));
+        assert_eq!(details.line, Some(2));
+        details.locate(sql);
+        assert_eq!(details.position, Some(11));
+    }
+
+    #[test]
+    fn extracts_mysql_constraint_names() {
+        let dup = DbErrorDetails::from_mysql(1062, None, "Duplicate entry 'a@b.c' for key 'users.uk_email'");
+        assert_eq!(dup.category, DbErrorCategory::ConstraintViolation);
+        assert_eq!(dup.table.as_deref(), Some("users"));
+        assert_eq!(dup.constraint.as_deref(), Some("uk_email"));
+
+        let fk = DbErrorDetails::from_mysql(
+            1452,
+            None,
+            "Cannot add or update a child row: a foreign key constraint fails (`shop`.`orders`, \
+             CONSTRAINT `fk_user` FOREIGN KEY (`user_id`) REFERENCES `users` (`id`))",
+        );
+        assert_eq!(fk.table.as_deref(), Some("orders"));
+        assert_eq!(fk.constraint.as_deref(), Some("fk_user"));
+        assert_eq!(fk.column.as_deref(), Some("user_id"));
+        let violation = fk.violation.unwrap();
+        assert_eq!(violation.kind, ConstraintKind::ForeignKey);
+        assert_eq!(violation.referenced_table.as_deref(), Some("users"));
+        assert_eq!(violation.referenced_columns, vec!["id"]);
+        assert!(violation.referencing_table.is_none());
+        assert_eq!(dup.violation.unwrap().value.as_deref(), Some("a@b.c"));
+
+        let pg = pg_violation(
+            "23503",
+            Some("Key (user_id)=(42) is not present in table \"users\"."),
+            None,
+        )
+        .unwrap();
+        assert_eq!(pg.columns, vec!["user_id"]);
+        assert_eq!(pg.value.as_deref(), Some("42"));
+        assert_eq!(pg.referenced_table.as_deref(), Some("users"));
+
+        let auth = DbErrorDetails::from_mysql(1045, None, "Access denied for user 'root'@'localhost'");
+        assert_eq!(auth.category, DbErrorCategory::AuthFailed);
+    }
+}
@@ -0,0 +1,91 @@
+//! Service self-registration with the gateway.
+//!
+//! A service started with `SERVICE_ADVERTISE_URL` announces itself to the
+//! gateway (`POST {GATEWAY_URL}/internal/registry`) on startup and then every
+//! `SERVICE_HEARTBEAT_SECS`; the gateway routes to and health-checks the
+//! instances whose heartbeat is recent. Registrations are signed like other
+//! internal requests, and the gateway only accepts them when
+//! `INTERNAL_SIGNING_SECRET` is configured.
+//!
+//! Configuration:
+//! - `SERVICE_ADVERTISE_URL` - base URL the gateway reaches this instance at;
+//!   when unset the service does not register
+//! - `SERVICE_HEARTBEAT_SECS` - interval between heartbeats (default: 10)
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::ServiceUrls;
+use crate::middleware::{RequestSigner, SendSigned};
+
+const DEFAULT_HEARTBEAT_SECS: u64 = 10;
+
+/// Default health check path of the services.
+pub const DEFAULT_HEALTH_PATH: &str = "/api/health";
+
+/// A service instance announcing itself to the gateway.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ServiceRegistration {
+    /// Service name, e.g. `query-service`.
+    pub name: String,
+    /// Base URL of the instance.
+    pub url: String,
+    /// Health check path (default: `/api/health`).
+    #[serde(default = "default_health_path")]
+    pub health_path: String,
+}
+
+fn default_health_path() -> String {
+    DEFAULT_HEALTH_PATH.to_string()
+}
+
+/// Registers the service with the gateway and keeps sending heartbeats in
+/// the background; does nothing without `SERVICE_ADVERTISE_URL`.
+pub fn spawn_registration(service: &str) {
+    let Some(url) = std::env::var("SERVICE_ADVERTISE_URL").ok().filter(|u| !u.is_empty()) else {
+        return;
+    };
+    let interval = std::env::var("SERVICE_HEARTBEAT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_HEARTBEAT_SECS)
+        .max(1);
+    let registration = ServiceRegistration {
+        name: service.to_string(),
+        url: url.trim_end_matches('/').to_string(),
+        health_path: default_health_path(),
+    };
+    let endpoint = format!("{}/internal/registry", ServiceUrls::load().gateway);
+    let signer = RequestSigner::from_env(service);
+    let client = reqwest::Client::new();
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+        // Last outcome, so only changes are logged
+        let mut registered: Option<bool> = None;
+        loop {
+            ticker.tick().await;
+            let result = client
+                .post(&endpoint)
+                .timeout(Duration::from_secs(interval))
+                .json(&registration)
+                .send_signed(&signer)
+                .await
+                .and_then(|r| r.error_for_status());
+            match result {
+                Ok(_) if registered != Some(true) => {
+                    tracing::info!(gateway = %endpoint, url = %registration.url, "Registered with the gateway");
+                    registered = Some(true);
+                }
+                Ok(_) => {}
+                Err(e) if registered != Some(false) => {
+                    tracing::warn!(gateway = %endpoint, error = %e, "Gateway registration failed, retrying");
+                    registered = Some(false);
+                }
+                Err(e) => tracing::debug!(gateway = %endpoint, error = %e, "Gateway registration failed"),
+            }
+        }
+    });
+}
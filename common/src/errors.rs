@@ -3,6 +3,7 @@
 //! Defines custom error types with automatic HTTP response conversion.
 
 use axum::{
+    extract::rejection::JsonRejection,
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
@@ -11,6 +12,12 @@ use serde_json::json;
 use thiserror::Error;
 use tracing::{error, warn};
 
+use crate::db_error::{DbErrorCategory, DbErrorDetails};
+use crate::models::monitor::TargetHealth;
+use crate::models::query::ConfirmationRequired;
+use crate::models::auth::LoginLocked;
+use crate::models::usage::QuotaExceeded;
+
 /// Application error enumeration.
 ///
 /// Each variant automatically converts to an appropriate HTTP status code
@@ -51,6 +58,22 @@ pub enum AppError {
     #[error("unsafe SQL: {0}")]
     UnsafeSql(String),
 
+    /// Request body exceeds the configured size limit.
+    #[error("payload too large: {0}")]
+    PayloadTooLarge(String),
+
+    /// Request body is not valid JSON for the endpoint.
+    #[error("invalid JSON body: {0}")]
+    InvalidJson(String),
+
+    /// Daily usage quota is used up; the quota details are returned to the client.
+    #[error("daily {} quota exceeded for {}: {} of {}", .0.limit, .0.principal, .0.used, .0.allowed)]
+    QuotaExceeded(Box<QuotaExceeded>),
+
+    /// Too many failed logins; further attempts are refused until the lockout ends.
+    #[error("too many failed login attempts, try again in {} seconds", .0.retry_after_secs)]
+    LoginLocked(Box<LoginLocked>),
+
     // ============== Server Errors (5xx) ==============
 
     /// Database connection error.
@@ -61,6 +84,10 @@ pub enum AppError {
     #[error("database query failed: {0}")]
     DatabaseQuery(String),
 
+    /// Classified database driver error; details are returned to the client.
+    #[error("{0}")]
+    Database(Box<DbErrorDetails>),
+
     /// Redis connection error.
     #[error("redis connection failed: {0}")]
     RedisConnection(String),
@@ -92,6 +119,14 @@ pub enum AppError {
     /// Unsupported database type.
     #[error("unsupported database type: {0}")]
     UnsupportedDatabaseType(String),
+
+    /// Target database is degraded; the health details are returned to the client.
+    #[error("target database {} is degraded: {}", .0.connection_id, .0.reasons.join("; "))]
+    DegradedTarget(Box<TargetHealth>),
+
+    /// Dangerous statement was not executed; the confirmation details are returned to the client.
+    #[error("statement requires confirmation: {}", .0.reason)]
+    ConfirmationRequired(Box<ConfirmationRequired>),
 }
 
 impl AppError {
@@ -107,9 +142,14 @@ impl AppError {
             AppError::Forbidden(_) => "FORBIDDEN",
             AppError::Conflict(_) => "CONFLICT",
             AppError::UnsafeSql(_) => "UNSAFE_SQL",
+            AppError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            AppError::InvalidJson(_) => "INVALID_JSON",
+            AppError::QuotaExceeded(_) => "QUOTA_EXCEEDED",
+            AppError::LoginLocked(_) => "LOGIN_LOCKED",
             // Server errors
             AppError::DatabaseConnection(_) => "DATABASE_CONNECTION_ERROR",
             AppError::DatabaseQuery(_) => "DATABASE_QUERY_ERROR",
+            AppError::Database(d) => d.category.code(),
             AppError::RedisConnection(_) => "REDIS_CONNECTION_ERROR",
             AppError::RedisOperation(_) => "REDIS_OPERATION_ERROR",
             AppError::Internal(_) => "INTERNAL_ERROR",
@@ -118,6 +158,8 @@ impl AppError {
             AppError::Timeout(_) => "TIMEOUT",
             AppError::ServiceUnavailable(_) => "SERVICE_UNAVAILABLE",
             AppError::UnsupportedDatabaseType(_) => "UNSUPPORTED_DATABASE_TYPE",
+            AppError::DegradedTarget(_) => "DEGRADED_TARGET",
+            AppError::ConfirmationRequired(_) => "REQUIRES_CONFIRMATION",
         }
     }
 
@@ -133,10 +175,23 @@ impl AppError {
             AppError::Forbidden(_) => StatusCode::FORBIDDEN,
             AppError::Conflict(_) => StatusCode::CONFLICT,
             AppError::UnsafeSql(_) => StatusCode::BAD_REQUEST,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::InvalidJson(_) => StatusCode::BAD_REQUEST,
+            AppError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::LoginLocked(_) => StatusCode::TOO_MANY_REQUESTS,
             AppError::UnsupportedDatabaseType(_) => StatusCode::BAD_REQUEST,
+            AppError::ConfirmationRequired(_) => StatusCode::PRECONDITION_REQUIRED,
             // Server errors (5xx)
             AppError::DatabaseConnection(_) => StatusCode::BAD_GATEWAY,
             AppError::DatabaseQuery(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Database(d) => match d.category {
+                DbErrorCategory::Syntax => StatusCode::BAD_REQUEST,
+                DbErrorCategory::UnknownDatabase => StatusCode::NOT_FOUND,
+                DbErrorCategory::ConstraintViolation => StatusCode::CONFLICT,
+                DbErrorCategory::Timeout => StatusCode::GATEWAY_TIMEOUT,
+                DbErrorCategory::AuthFailed | DbErrorCategory::Connection => StatusCode::BAD_GATEWAY,
+                DbErrorCategory::Other => StatusCode::INTERNAL_SERVER_ERROR,
+            },
             AppError::RedisConnection(_) => StatusCode::BAD_GATEWAY,
             AppError::RedisOperation(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -144,6 +199,7 @@ impl AppError {
             AppError::ExternalService(_) => StatusCode::BAD_GATEWAY,
             AppError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
             AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::DegradedTarget(_) => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
 
@@ -153,6 +209,8 @@ impl AppError {
         match self {
             // 客户端错误 (4xx)
             AppError::InvalidInput(_) => code::BAD_REQUEST,
+            AppError::InvalidJson(_) => code::BAD_REQUEST,
+            AppError::PayloadTooLarge(_) => code::PAYLOAD_TOO_LARGE,
             AppError::Validation(_) => code::VALIDATION_ERROR,
             AppError::Unauthorized => code::UNAUTHORIZED,
             AppError::Forbidden(_) => code::FORBIDDEN,
@@ -160,6 +218,9 @@ impl AppError {
             // 业务异常 (7xx)
             AppError::NotFound(_) => code::DATA_NOT_FOUND,
             AppError::Conflict(_) => code::DATA_ALREADY_EXISTS,
+            AppError::ConfirmationRequired(_) => code::CONFIRMATION_REQUIRED,
+            AppError::QuotaExceeded(_) => code::QUOTA_EXCEEDED,
+            AppError::LoginLocked(_) => code::LOGIN_LOCKED,
             
             // 数据库相关 (8xx)
             AppError::ConnectionNotFound(_) => code::DB_CONNECTION_NOT_FOUND,
@@ -167,6 +228,16 @@ impl AppError {
             AppError::UnsafeSql(_) => code::DB_UNSAFE_SQL,
             AppError::DatabaseConnection(_) => code::DB_CONNECTION_ERROR,
             AppError::DatabaseQuery(_) => code::DB_QUERY_ERROR,
+            AppError::Database(d) => match d.category {
+                DbErrorCategory::AuthFailed => code::DB_AUTH_FAILED,
+                DbErrorCategory::UnknownDatabase => code::DB_UNKNOWN_DATABASE,
+                DbErrorCategory::Syntax => code::DB_SQL_SYNTAX_ERROR,
+                DbErrorCategory::ConstraintViolation => code::DB_CONSTRAINT_VIOLATION,
+                DbErrorCategory::Timeout => code::DB_QUERY_TIMEOUT,
+                DbErrorCategory::Connection => code::DB_CONNECTION_ERROR,
+                DbErrorCategory::Other => code::DB_QUERY_ERROR,
+            },
+            AppError::DegradedTarget(_) => code::DB_TARGET_DEGRADED,
             AppError::RedisConnection(_) => code::REDIS_CONNECTION_ERROR,
             AppError::RedisOperation(_) => code::REDIS_OPERATION_ERROR,
             
@@ -181,42 +252,76 @@ impl AppError {
         }
     }
 
+    /// Fills in the syntax error position from the executed statement.
+    pub fn with_sql(mut self, sql: &str) -> Self {
+        if let AppError::Database(details) = &mut self {
+            details.locate(sql);
+        }
+        self
+    }
+
+    /// Returns structured error details for the response, if any.
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::Database(d) => serde_json::to_value(d).ok(),
+            AppError::DegradedTarget(h) => serde_json::to_value(h).ok(),
+            AppError::ConfirmationRequired(c) => serde_json::to_value(c).ok(),
+            AppError::QuotaExceeded(q) => serde_json::to_value(q).ok(),
+            AppError::LoginLocked(l) => serde_json::to_value(l).ok(),
+            _ => None,
+        }
+    }
+
     /// Returns whether this error should be logged as an error or warning.
     fn is_server_error(&self) -> bool {
         self.status_code().is_server_error()
     }
-}
-
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        // Log the error appropriately
-        if self.is_server_error() {
-            error!(error_code = %self.code(), error = %self, "Server error occurred");
-        } else {
-            warn!(error_code = %self.code(), error = %self, "Client error occurred");
-        }
 
+    /// Builds the JSON error response body.
+    pub fn response_body(&self) -> serde_json::Value {
         // Don't expose internal error details to clients
-        let message = match &self {
+        let message = match self {
             AppError::Internal(_) => "服务器内部错误".to_string(),
             AppError::Configuration(_) => "配置错误".to_string(),
             e => e.to_string(),
         };
 
-        let body = Json(json!({
+        let mut error = json!({
+            "code": self.code(),
+            "message": message
+        });
+        if let Some(details) = self.details() {
+            error["details"] = details;
+        }
+
+        json!({
             "code": self.response_code(),
             "message": message,
             "success": false,
-            "error": {
-                "code": self.code(),
-                "message": message
-            },
+            "error": error,
             "meta": {
                 "timestamp": chrono::Utc::now().to_rfc3339()
             }
-        }));
+        })
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        // Log the error appropriately
+        if self.is_server_error() {
+            error!(error_code = %self.code(), error = %self, "Server error occurred");
+        } else {
+            warn!(error_code = %self.code(), error = %self, "Client error occurred");
+        }
 
-        (self.status_code(), body).into_response()
+        let mut response = (self.status_code(), Json(self.response_body())).into_response();
+        if let AppError::LoginLocked(locked) = &self {
+            response
+                .headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, locked.retry_after_secs.into());
+        }
+        response
     }
 }
 
@@ -226,17 +331,22 @@ impl From<sqlx::Error> for AppError {
     fn from(err: sqlx::Error) -> Self {
         match err {
             sqlx::Error::RowNotFound => AppError::NotFound("Database record not found".into()),
-            sqlx::Error::PoolTimedOut => {
-                AppError::Timeout("Database connection pool timeout".into())
-            }
             sqlx::Error::Configuration(e) => AppError::Configuration(e.to_string()),
-            _ => AppError::DatabaseQuery(err.to_string()),
+            _ => match DbErrorDetails::from_sqlx(&err) {
+                Some(details) => AppError::Database(Box::new(details)),
+                None => AppError::DatabaseQuery(err.to_string()),
+            },
         }
     }
 }
 
 impl From<redis::RedisError> for AppError {
     fn from(err: redis::RedisError) -> Self {
+        if let Some(details) = DbErrorDetails::from_redis(&err) {
+            if details.category != DbErrorCategory::Connection {
+                return AppError::Database(Box::new(details));
+            }
+        }
         if err.is_connection_dropped() || err.is_io_error() {
             AppError::RedisConnection(err.to_string())
         } else {
@@ -251,6 +361,18 @@ impl From<serde_json::Error> for AppError {
     }
 }
 
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::BytesRejection(r) if r.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+                AppError::PayloadTooLarge(r.body_text())
+            }
+            JsonRejection::BytesRejection(r) => AppError::InvalidInput(r.body_text()),
+            r => AppError::InvalidJson(r.body_text()),
+        }
+    }
+}
+
 impl From<std::io::Error> for AppError {
     fn from(err: std::io::Error) -> Self {
         AppError::Internal(format!("IO error: {}", err))
@@ -288,6 +410,12 @@ impl From<reqwest::Error> for AppError {
     }
 }
 
+impl From<crate::config::ConfigError> for AppError {
+    fn from(err: crate::config::ConfigError) -> Self {
+        AppError::Configuration(err.to_string())
+    }
+}
+
 impl From<std::env::VarError> for AppError {
     fn from(err: std::env::VarError) -> Self {
         AppError::Configuration(format!("Environment variable error: {}", err))
@@ -0,0 +1,243 @@
+//! Event bus between services.
+//!
+//! Services publish domain events (`connection.created`, `query.executed`,
+//! `backup.completed`, ...) without knowing who consumes them; new services
+//! such as audit or alerting subscribe to the kinds they care about instead
+//! of being called over HTTP.
+//!
+//! Events are JSON [`Event`] envelopes published on Redis pub/sub, one channel
+//! per kind (`dbm:events:<kind>`). Delivery is at-most-once: subscribers only
+//! see events published while they are subscribed, and publishing never blocks
+//! or fails the request that produced the event.
+//!
+//! Configuration:
+//! - `EVENT_BUS_REDIS_URL` (falls back to `REDIS_URL`) - events are dropped if unset
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{AppError, AppResult};
+
+/// Channel prefix of every event kind.
+const CHANNEL_PREFIX: &str = "dbm:events:";
+
+/// Event kinds published by the services.
+pub mod kinds {
+    /// A connection was created (connection-service).
+    pub const CONNECTION_CREATED: &str = "connection.created";
+    /// A connection's settings (allowlist, masking, timeout, pool options,
+    /// password, ...) were changed (connection-service).
+    pub const CONNECTION_UPDATED: &str = "connection.updated";
+    /// A connection was deleted (connection-service).
+    pub const CONNECTION_DELETED: &str = "connection.deleted";
+    /// A query or confirmed change was executed (query-service).
+    pub const QUERY_EXECUTED: &str = "query.executed";
+    /// A backup finished successfully (connection-service).
+    pub const BACKUP_COMPLETED: &str = "backup.completed";
+    /// A backup failed (connection-service).
+    pub const BACKUP_FAILED: &str = "backup.failed";
+}
+
+/// Envelope of an event on the bus.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Unique event ID.
+    pub id: String,
+    /// Event kind, e.g. `backup.completed`.
+    pub kind: String,
+    /// Service that published the event.
+    pub source: String,
+    /// Publication time (RFC 3339, UTC).
+    pub occurred_at: String,
+    /// Kind-specific payload.
+    pub data: serde_json::Value,
+}
+
+impl Event {
+    /// Creates an event with a fresh ID, timestamped now.
+    pub fn new(kind: impl Into<String>, source: impl Into<String>, data: serde_json::Value) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: kind.into(),
+            source: source.into(),
+            occurred_at: chrono::Utc::now().to_rfc3339(),
+            data,
+        }
+    }
+}
+
+/// Stream of events received by a subscriber.
+pub type EventStream = Pin<Box<dyn Stream<Item = Event> + Send>>;
+
+/// Transport carrying events between services.
+#[async_trait]
+pub trait EventBus: Send + Sync {
+    /// Transport name, used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Publishes an event to every current subscriber of its kind.
+    async fn publish(&self, event: &Event) -> AppResult<()>;
+
+    /// Subscribes to the kinds matching `pattern`, e.g. `backup.*` or `*`.
+    ///
+    /// Messages that are not valid events are skipped.
+    async fn subscribe(&self, pattern: &str) -> AppResult<EventStream>;
+}
+
+/// Event bus on Redis pub/sub.
+pub struct RedisEventBus {
+    client: redis::Client,
+    publisher: ConnectionManager,
+}
+
+impl RedisEventBus {
+    /// Connects to Redis at `url`.
+    ///
+    /// # Errors
+    /// Returns `AppError::Internal` when the URL is invalid or Redis is unreachable.
+    pub async fn connect(url: &str) -> AppResult<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| AppError::Internal(format!("Invalid event bus URL: {}", e)))?;
+        let publisher = ConnectionManager::new(client.clone())
+            .await
+            .map_err(|e| AppError::Internal(format!("Event bus unavailable: {}", e)))?;
+        Ok(Self { client, publisher })
+    }
+}
+
+#[async_trait]
+impl EventBus for RedisEventBus {
+    fn name(&self) -> &'static str {
+        "redis"
+    }
+
+    async fn publish(&self, event: &Event) -> AppResult<()> {
+        let payload = serde_json::to_string(event)
+            .map_err(|e| AppError::Internal(format!("Failed to encode event: {}", e)))?;
+        let mut publisher = self.publisher.clone();
+        publisher
+            .publish::<_, _, ()>(channel(&event.kind), payload)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to publish event: {}", e)))
+    }
+
+    async fn subscribe(&self, pattern: &str) -> AppResult<EventStream> {
+        let mut pubsub = self
+            .client
+            .get_async_pubsub()
+            .await
+            .map_err(|e| AppError::Internal(format!("Event bus unavailable: {}", e)))?;
+        pubsub
+            .psubscribe(channel(pattern))
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to subscribe to events: {}", e)))?;
+        let events = pubsub.into_on_message().filter_map(|msg| async move {
+            let payload: String = msg.get_payload().ok()?;
+            match serde_json::from_str::<Event>(&payload) {
+                Ok(event) => Some(event),
+                Err(e) => {
+                    tracing::warn!(channel = msg.get_channel_name(), error = %e, "Skipping malformed event");
+                    None
+                }
+            }
+        });
+        Ok(Box::pin(events))
+    }
+}
+
+/// Publishing side of the bus shared by a service.
+///
+/// Publishing is fire-and-forget: the event is sent on a background task and
+/// failures are only logged. Without a bus, events are dropped.
+pub struct EventPublisher {
+    bus: Option<Arc<dyn EventBus>>,
+    source: String,
+}
+
+impl EventPublisher {
+    /// Creates a publisher on `bus` for the service `source`.
+    pub fn new(bus: Arc<dyn EventBus>, source: impl Into<String>) -> Self {
+        Self { bus: Some(bus), source: source.into() }
+    }
+
+    /// Creates a publisher that drops every event.
+    pub fn disabled(source: impl Into<String>) -> Self {
+        Self { bus: None, source: source.into() }
+    }
+
+    /// Creates a publisher from `EVENT_BUS_REDIS_URL` / `REDIS_URL`.
+    ///
+    /// Without a URL, or when Redis is unreachable at startup, the publisher
+    /// is disabled.
+    pub async fn from_env(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let url = std::env::var("EVENT_BUS_REDIS_URL")
+            .or_else(|_| std::env::var("REDIS_URL"))
+            .ok()
+            .filter(|u| !u.is_empty());
+        let Some(url) = url else {
+            return Self::disabled(source);
+        };
+        match RedisEventBus::connect(&url).await {
+            Ok(bus) => {
+                tracing::info!("Event bus enabled (Redis)");
+                Self::new(Arc::new(bus), source)
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Event bus disabled: Redis unavailable");
+                Self::disabled(source)
+            }
+        }
+    }
+
+    /// The underlying bus, for subscribers in the same process.
+    pub fn bus(&self) -> Option<&Arc<dyn EventBus>> {
+        self.bus.as_ref()
+    }
+
+    /// Publishes an event of `kind` in the background.
+    pub fn publish(&self, kind: &str, data: serde_json::Value) {
+        let Some(bus) = self.bus.clone() else {
+            return;
+        };
+        let event = Event::new(kind, self.source.clone(), data);
+        tokio::spawn(async move {
+            if let Err(e) = bus.publish(&event).await {
+                tracing::warn!(bus = bus.name(), kind = %event.kind, error = %e, "Event publication failed");
+            }
+        });
+    }
+}
+
+/// Redis channel (or channel pattern) of an event kind (or kind pattern).
+fn channel(kind: &str) -> String {
+    format!("{}{}", CHANNEL_PREFIX, kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn events_round_trip_as_json() {
+        let event = Event::new(kinds::BACKUP_COMPLETED, "connection-service", json!({ "backup_id": "b1" }));
+        let decoded: Event = serde_json::from_str(&serde_json::to_string(&event).unwrap()).unwrap();
+        assert_eq!(decoded.id, event.id);
+        assert_eq!(decoded.kind, "backup.completed");
+        assert_eq!(decoded.source, "connection-service");
+        assert_eq!(decoded.data["backup_id"], "b1");
+    }
+
+    #[test]
+    fn kinds_map_to_prefixed_channels() {
+        assert_eq!(channel(kinds::QUERY_EXECUTED), "dbm:events:query.executed");
+        assert_eq!(channel("backup.*"), "dbm:events:backup.*");
+    }
+}
@@ -0,0 +1,91 @@
+//! Request extractors.
+//!
+//! [`Json`] is a drop-in replacement for `axum::Json` whose rejections use the
+//! standard error response: bodies over the service limit (see
+//! `AppConfig::max_body_bytes`) return `PAYLOAD_TOO_LARGE`, and malformed
+//! JSON, a missing `Content-Type: application/json` header or a body that does
+//! not match the request type return `INVALID_JSON`, instead of axum's
+//! plain-text rejections. As a response it serializes exactly like
+//! `axum::Json`.
+
+use axum::extract::{FromRequest, Request};
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::errors::AppError;
+
+/// JSON extractor / response with `AppError` rejections.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::<T>::from_request(req, state).await?;
+        Ok(Json(value))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::DefaultBodyLimit;
+    use axum::http::{header, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    #[derive(serde::Deserialize, Serialize)]
+    struct Payload {
+        name: String,
+    }
+
+    async fn echo(Json(payload): Json<Payload>) -> Json<Payload> {
+        Json(payload)
+    }
+
+    async fn send(body: &'static str, content_type: &str) -> (StatusCode, serde_json::Value) {
+        let app = Router::new().route("/", post(echo)).layer(DefaultBodyLimit::max(32));
+        let request = Request::post("/")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn rejections_use_the_standard_error_shape() {
+        let (status, body) = send(r#"{"name":"a"}"#, "application/json").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["name"], "a");
+
+        let (status, body) = send(r#"{"name":"#, "application/json").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"]["code"], "INVALID_JSON");
+
+        let (_, body) = send(r#"{"name":"a"}"#, "text/plain").await;
+        assert_eq!(body["error"]["code"], "INVALID_JSON");
+
+        let (status, body) = send(r#"{"name":"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}"#, "application/json").await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["code"], 413);
+        assert_eq!(body["error"]["code"], "PAYLOAD_TOO_LARGE");
+    }
+}
@@ -0,0 +1,109 @@
+//! Router-level fallbacks.
+//!
+//! [`with_json_fallbacks`] replaces axum's empty 404 / 405 responses with the
+//! standard response envelope (`NOT_FOUND` / `METHOD_NOT_ALLOWED`), carrying
+//! the request ID and service name in `meta`. Apply it after all routes are
+//! merged and before `request_id_middleware` is layered, so the request ID is
+//! available to the fallbacks. 405 responses keep axum's `Allow` header.
+
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+
+use crate::middleware::RequestId;
+use crate::response::{code, ApiResponse};
+
+/// Adds JSON 404 and 405 fallbacks to `router`.
+pub fn with_json_fallbacks<S>(router: Router<S>, service: &'static str) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router
+        .method_not_allowed_fallback(move |req: Request| async move { method_not_allowed(service, &req) })
+        .fallback(move |req: Request| async move { not_found(service, &req) })
+}
+
+/// Response for a path that matches no route.
+pub fn not_found(service: &str, req: &Request) -> Response {
+    let message = format!("未找到路由: {}", req.uri().path());
+    fallback_response(StatusCode::NOT_FOUND, code::NOT_FOUND, "NOT_FOUND", message, service, req)
+}
+
+/// Response for a route that does not accept the request method.
+pub fn method_not_allowed(service: &str, req: &Request) -> Response {
+    let message = format!("{} 不支持 {} 方法", req.uri().path(), req.method());
+    fallback_response(
+        StatusCode::METHOD_NOT_ALLOWED,
+        code::METHOD_NOT_ALLOWED,
+        "METHOD_NOT_ALLOWED",
+        message,
+        service,
+        req,
+    )
+}
+
+fn fallback_response(
+    status: StatusCode,
+    response_code: i32,
+    error_code: &str,
+    message: String,
+    service: &str,
+    req: &Request,
+) -> Response {
+    let mut body = ApiResponse::err_with_code(response_code, error_code, message).with_service(service);
+    if let Some(request_id) = req.extensions().get::<RequestId>() {
+        body = body.with_request_id(request_id.as_str());
+    }
+    (status, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header;
+    use axum::middleware;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    use crate::middleware::{request_id_middleware, REQUEST_ID_HEADER};
+
+    async fn send(method: &str, uri: &str) -> (StatusCode, Option<String>, serde_json::Value) {
+        let router = Router::new().route("/api/items", get(|| async { "ok" }));
+        let app = with_json_fallbacks(router, "test-service").layer(middleware::from_fn(request_id_middleware));
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(&REQUEST_ID_HEADER, "req-1")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let allow = response
+            .headers()
+            .get(header::ALLOW)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, allow, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn unknown_routes_and_methods_use_the_standard_envelope() {
+        let (status, _, body) = send("GET", "/api/missing").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], 404);
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"]["code"], "NOT_FOUND");
+        assert_eq!(body["meta"]["request_id"], "req-1");
+        assert_eq!(body["meta"]["service"], "test-service");
+
+        let (status, allow, body) = send("DELETE", "/api/items").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        assert!(allow.is_some_and(|allow| allow.contains("GET")));
+        assert_eq!(body["code"], 405);
+        assert_eq!(body["error"]["code"], "METHOD_NOT_ALLOWED");
+        assert_eq!(body["meta"]["request_id"], "req-1");
+    }
+}
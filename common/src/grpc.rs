@@ -0,0 +1,443 @@
+//! Internal gRPC interface between query-service and connection-service.
+//!
+//! [`proto`] holds the code generated from `proto/connection.proto`. This
+//! module converts query results between the API models and their protobuf
+//! form, carries [`AppError`]s across the wire as [`Status`] and signs
+//! outgoing calls like internal HTTP requests (see
+//! [`crate::middleware::signing`]), so the receiving service checks them with
+//! the same middleware.
+//!
+//! An error's status carries the `error` object of the HTTP error response
+//! (`code`, `message`, `details`) as JSON in its details, so the caller can
+//! restore structured errors such as database errors and exceeded quotas.
+
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::http::{self, StatusCode};
+use futures::future::BoxFuture;
+use http_body_util::{BodyExt, Full};
+use tonic::body::Body;
+use tonic::codegen::{Bytes, Service, StdError};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Status};
+
+use crate::db_error::DbErrorDetails;
+use crate::errors::AppError;
+use crate::middleware::RequestSigner;
+use crate::models::query as model;
+use crate::models::usage::QuotaExceeded;
+
+/// Code generated from `proto/connection.proto`.
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("dbm.connection.v1");
+}
+
+/// Client of the connection service's gRPC interface.
+pub type ConnectionServiceClient = proto::connection_service_client::ConnectionServiceClient<SignedChannel>;
+
+/// Time allowed to establish the connection to the other service.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Channel that signs every call with the internal signature headers.
+///
+/// The request message is buffered before it is signed, which suits the
+/// unary calls of the internal interface.
+#[derive(Clone)]
+pub struct SignedChannel {
+    channel: Channel,
+    signer: RequestSigner,
+}
+
+impl SignedChannel {
+    /// Creates a channel to `url` that connects on first use; `https://` URLs
+    /// are verified against the system roots.
+    ///
+    /// # Errors
+    /// Returns an error if `url` is not a valid URI.
+    pub fn lazy(url: &str, signer: RequestSigner) -> Result<Self, tonic::transport::Error> {
+        let mut endpoint = Endpoint::from_shared(url.to_string())?.connect_timeout(CONNECT_TIMEOUT);
+        if url.starts_with("https://") {
+            endpoint = endpoint.tls_config(ClientTlsConfig::new().with_native_roots())?;
+        }
+        Ok(Self {
+            channel: endpoint.connect_lazy(),
+            signer,
+        })
+    }
+}
+
+impl Service<http::Request<Body>> for SignedChannel {
+    type Response = http::Response<Body>;
+    type Error = StdError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.channel.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        // The polled channel is the ready one, keep a fresh clone for the next call
+        let clone = self.channel.clone();
+        let mut channel = std::mem::replace(&mut self.channel, clone);
+        let signer = self.signer.clone();
+        Box::pin(async move {
+            let (mut parts, body) = request.into_parts();
+            let body = body.collect().await?.to_bytes();
+            let path_and_query = parts.uri.path_and_query().map_or("/", |pq| pq.as_str()).to_string();
+            signer.sign_headers(parts.method.as_str(), &path_and_query, &body, &mut parts.headers);
+            let request = http::Request::from_parts(parts, Body::new(Full::new(body)));
+            channel.call(request).await.map_err(Into::into)
+        })
+    }
+}
+
+/// Connects lazily to the connection service at `url`.
+///
+/// # Errors
+/// Returns an error if `url` is not a valid URI.
+pub fn connection_client(url: &str, signer: RequestSigner) -> Result<ConnectionServiceClient, tonic::transport::Error> {
+    Ok(ConnectionServiceClient::new(SignedChannel::lazy(url, signer)?))
+}
+
+impl From<AppError> for Status {
+    fn from(e: AppError) -> Self {
+        if e.status_code().is_server_error() {
+            tracing::error!(error_code = %e.code(), error = %e, "Server error occurred");
+        } else {
+            tracing::warn!(error_code = %e.code(), error = %e, "Client error occurred");
+        }
+        let code = match e.status_code() {
+            StatusCode::BAD_REQUEST | StatusCode::PAYLOAD_TOO_LARGE => Code::InvalidArgument,
+            StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+            StatusCode::FORBIDDEN => Code::PermissionDenied,
+            StatusCode::NOT_FOUND => Code::NotFound,
+            StatusCode::CONFLICT => Code::AlreadyExists,
+            StatusCode::PRECONDITION_REQUIRED => Code::FailedPrecondition,
+            StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+            StatusCode::GATEWAY_TIMEOUT => Code::DeadlineExceeded,
+            _ => Code::Internal,
+        };
+        let error = e.response_body()["error"].take();
+        let message = error["message"].as_str().unwrap_or_default().to_string();
+        Status::with_details(code, message, Bytes::from(error.to_string()))
+    }
+}
+
+/// The `error` object carried by `status`; failures that did not come from
+/// the service (e.g. the service is unreachable) only have a message.
+pub fn error_body(status: &Status) -> serde_json::Value {
+    serde_json::from_slice::<serde_json::Value>(status.details())
+        .ok()
+        .filter(serde_json::Value::is_object)
+        .unwrap_or_else(|| serde_json::json!({ "message": status.message() }))
+}
+
+/// Restores the error a call failed with.
+///
+/// Exceeded quotas and database errors are restored from their details,
+/// other errors by status code. Failures that did not come from the service
+/// become [`AppError::ExternalService`].
+pub fn app_error(status: &Status) -> AppError {
+    let error = error_body(status);
+    let message = error["message"].as_str().unwrap_or("查询失败").to_string();
+    if error.get("code").is_none() {
+        return AppError::ExternalService(format!("调用连接服务失败: {}", status.message()));
+    }
+    if error["code"] == "QUOTA_EXCEEDED" {
+        if let Ok(quota) = serde_json::from_value::<QuotaExceeded>(error["details"].clone()) {
+            return AppError::QuotaExceeded(Box::new(quota));
+        }
+    }
+    if let Some(details) = error
+        .get("details")
+        .and_then(|d| serde_json::from_value::<DbErrorDetails>(d.clone()).ok())
+    {
+        return AppError::Database(Box::new(details));
+    }
+    match status.code() {
+        Code::InvalidArgument => AppError::InvalidInput(message),
+        Code::PermissionDenied => AppError::Forbidden(message),
+        Code::NotFound => AppError::NotFound(message),
+        _ => AppError::DatabaseQuery(message),
+    }
+}
+
+impl From<model::QueryLanguage> for proto::QueryLanguage {
+    fn from(language: model::QueryLanguage) -> Self {
+        match language {
+            model::QueryLanguage::Sql => proto::QueryLanguage::Sql,
+            model::QueryLanguage::Cypher => proto::QueryLanguage::Cypher,
+        }
+    }
+}
+
+impl From<proto::QueryLanguage> for model::QueryLanguage {
+    fn from(language: proto::QueryLanguage) -> Self {
+        match language {
+            proto::QueryLanguage::Sql => model::QueryLanguage::Sql,
+            proto::QueryLanguage::Cypher => model::QueryLanguage::Cypher,
+        }
+    }
+}
+
+impl From<Option<model::ValueKind>> for proto::ValueKind {
+    fn from(kind: Option<model::ValueKind>) -> Self {
+        use model::ValueKind as K;
+        match kind {
+            None => proto::ValueKind::Unspecified,
+            Some(K::Integer) => proto::ValueKind::Integer,
+            Some(K::Float) => proto::ValueKind::Float,
+            Some(K::Boolean) => proto::ValueKind::Boolean,
+            Some(K::Decimal) => proto::ValueKind::Decimal,
+            Some(K::Text) => proto::ValueKind::Text,
+            Some(K::Binary) => proto::ValueKind::Binary,
+            Some(K::Date) => proto::ValueKind::Date,
+            Some(K::Time) => proto::ValueKind::Time,
+            Some(K::DateTime) => proto::ValueKind::DateTime,
+            Some(K::DateTimeTz) => proto::ValueKind::DateTimeTz,
+            Some(K::Json) => proto::ValueKind::Json,
+            Some(K::Geometry) => proto::ValueKind::Geometry,
+            Some(K::Uuid) => proto::ValueKind::Uuid,
+        }
+    }
+}
+
+impl From<proto::ValueKind> for Option<model::ValueKind> {
+    fn from(kind: proto::ValueKind) -> Self {
+        use model::ValueKind as K;
+        Some(match kind {
+            proto::ValueKind::Unspecified => return None,
+            proto::ValueKind::Integer => K::Integer,
+            proto::ValueKind::Float => K::Float,
+            proto::ValueKind::Boolean => K::Boolean,
+            proto::ValueKind::Decimal => K::Decimal,
+            proto::ValueKind::Text => K::Text,
+            proto::ValueKind::Binary => K::Binary,
+            proto::ValueKind::Date => K::Date,
+            proto::ValueKind::Time => K::Time,
+            proto::ValueKind::DateTime => K::DateTime,
+            proto::ValueKind::DateTimeTz => K::DateTimeTz,
+            proto::ValueKind::Json => K::Json,
+            proto::ValueKind::Geometry => K::Geometry,
+            proto::ValueKind::Uuid => K::Uuid,
+        })
+    }
+}
+
+impl From<model::QueryResult> for proto::QueryResult {
+    fn from(result: model::QueryResult) -> Self {
+        Self {
+            columns: result
+                .columns
+                .into_iter()
+                .map(|column| proto::ColumnInfo {
+                    kind: proto::ValueKind::from(column.kind) as i32,
+                    name: column.name,
+                    data_type: column.data_type,
+                    nullable: column.nullable,
+                })
+                .collect(),
+            rows: result
+                .rows
+                .iter()
+                .map(|row| proto::Row {
+                    values: row.iter().map(serde_json::Value::to_string).collect(),
+                })
+                .collect(),
+            row_count: result.row_count as u64,
+            affected_rows: result.affected_rows,
+            execution_time_ms: result.execution_time_ms,
+            truncated: result.truncated,
+            truncated_cells: result
+                .truncated_cells
+                .into_iter()
+                .map(|cell| proto::TruncatedCell {
+                    row: cell.row as u64,
+                    column: cell.column as u64,
+                    original_bytes: cell.original_bytes as u64,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::QueryResult> for model::QueryResult {
+    type Error = serde_json::Error;
+
+    /// Fails when a cell is not valid JSON text.
+    fn try_from(result: proto::QueryResult) -> Result<Self, Self::Error> {
+        Ok(Self {
+            columns: result
+                .columns
+                .into_iter()
+                .map(|column| model::ColumnInfo {
+                    kind: column.kind().into(),
+                    name: column.name,
+                    data_type: column.data_type,
+                    nullable: column.nullable,
+                })
+                .collect(),
+            rows: result
+                .rows
+                .iter()
+                .map(|row| row.values.iter().map(|v| serde_json::from_str(v)).collect())
+                .collect::<Result<_, _>>()?,
+            row_count: result.row_count as usize,
+            affected_rows: result.affected_rows,
+            execution_time_ms: result.execution_time_ms,
+            truncated: result.truncated,
+            truncated_cells: result
+                .truncated_cells
+                .into_iter()
+                .map(|cell| model::TruncatedCell {
+                    row: cell.row as usize,
+                    column: cell.column as usize,
+                    original_bytes: cell.original_bytes as usize,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use axum::{middleware, Router};
+    use proto::connection_service_server::{ConnectionService, ConnectionServiceServer, SERVICE_NAME};
+    use serde_json::json;
+    use tokio::net::TcpListener;
+    use tonic::{Request, Response};
+
+    use crate::middleware::auth::PRINCIPAL_HEADER;
+    use crate::middleware::{signature_middleware, SignatureVerifier};
+
+    /// 回显调用方主体与语句的服务
+    struct Echo;
+
+    #[tonic::async_trait]
+    impl ConnectionService for Echo {
+        async fn get_pool_info(&self, request: Request<proto::PoolInfoRequest>) -> Result<Response<proto::PoolInfo>, Status> {
+            let id = request.into_inner().connection_id;
+            if id == "missing" {
+                return Err(AppError::ConnectionNotFound(id).into());
+            }
+            Ok(Response::new(proto::PoolInfo { id, db_type: "mysql".into(), ..Default::default() }))
+        }
+
+        async fn execute_query(&self, request: Request<proto::QueryRequest>) -> Result<Response<proto::QueryResult>, Status> {
+            let principal = request.metadata().get(PRINCIPAL_HEADER).map(|v| v.to_str().unwrap().to_string());
+            let mut result = model::QueryResult::empty();
+            result.rows = vec![vec![json!(principal), json!(request.into_inner().sql)]];
+            result.row_count = 1;
+            Ok(Response::new(result.into()))
+        }
+
+        async fn execute_change(&self, _request: Request<proto::QueryRequest>) -> Result<Response<proto::QueryResult>, Status> {
+            Err(AppError::InvalidInput("仅支持执行单条 UPDATE / DELETE 语句".into()).into())
+        }
+
+        async fn health(&self, _request: Request<proto::HealthRequest>) -> Result<Response<proto::HealthResponse>, Status> {
+            Ok(Response::new(proto::HealthResponse { ready: true }))
+        }
+    }
+
+    /// 在签名校验中间件之后提供 gRPC 服务，返回地址
+    async fn serve() -> String {
+        let verifier = Arc::new(SignatureVerifier::new(Some("secret"), 300));
+        let app = Router::new()
+            .route_service(&format!("/{}/{{*method}}", SERVICE_NAME), ConnectionServiceServer::new(Echo))
+            .layer(middleware::from_fn_with_state(verifier, signature_middleware));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn signed_calls_pass_the_signature_middleware() {
+        let url = serve().await;
+        let mut client = connection_client(&url, RequestSigner::new("query-service", Some("secret"))).unwrap();
+
+        let info = client
+            .get_pool_info(proto::PoolInfoRequest { connection_id: "c1".into() })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!((info.id.as_str(), info.db_type.as_str()), ("c1", "mysql"));
+
+        let mut request = Request::new(proto::QueryRequest { sql: "SELECT 1".into(), ..Default::default() });
+        request.metadata_mut().insert(PRINCIPAL_HEADER, "user:alice".parse().unwrap());
+        let result = model::QueryResult::try_from(client.execute_query(request).await.unwrap().into_inner()).unwrap();
+        assert_eq!(result.rows, vec![vec![json!("user:alice"), json!("SELECT 1")]]);
+
+        // 服务返回的错误保留错误码
+        let status = client
+            .get_pool_info(proto::PoolInfoRequest { connection_id: "missing".into() })
+            .await
+            .unwrap_err();
+        assert_eq!((status.code(), error_body(&status)["code"].as_str()), (Code::NotFound, Some("CONNECTION_NOT_FOUND")));
+        let status = client.execute_change(proto::QueryRequest::default()).await.unwrap_err();
+        assert!(matches!(app_error(&status), AppError::InvalidInput(_)));
+    }
+
+    #[tokio::test]
+    async fn unsigned_and_unlisted_callers_are_rejected() {
+        let url = serve().await;
+        for signer in [
+            RequestSigner::new("query-service", None),
+            RequestSigner::new("query-service", Some("guess")),
+            // 网关的签名不能调用内部 gRPC 接口
+            RequestSigner::new("gateway", Some("secret")),
+        ] {
+            let mut client = connection_client(&url, signer).unwrap();
+            // 中间件以 HTTP 401 拒绝，不是服务返回的错误
+            let status = client.health(proto::HealthRequest {}).await.unwrap_err();
+            assert!(error_body(&status).get("code").is_none(), "{:?}", status);
+            assert!(matches!(app_error(&status), AppError::ExternalService(_)));
+        }
+    }
+
+    #[test]
+    fn query_results_round_trip() {
+        let mut result = model::QueryResult::empty();
+        result.columns = vec![
+            model::ColumnInfo { name: "id".into(), data_type: "INT".into(), nullable: Some(false), kind: Some(model::ValueKind::Integer) },
+            model::ColumnInfo { name: "doc".into(), data_type: "JSON".into(), nullable: None, kind: None },
+        ];
+        result.rows = vec![vec![json!(1), json!({"a": [1, "x"]})], vec![json!(2), json!(null)]];
+        result.row_count = 2;
+        result.truncated_cells = vec![model::TruncatedCell { row: 1, column: 1, original_bytes: 70000 }];
+
+        let restored = model::QueryResult::try_from(proto::QueryResult::from(result.clone())).unwrap();
+        assert_eq!(serde_json::to_value(&restored).unwrap(), serde_json::to_value(&result).unwrap());
+    }
+
+    #[test]
+    fn errors_keep_their_details_across_the_wire() {
+        let quota = QuotaExceeded {
+            principal: "user:alice".into(),
+            limit: "queries".into(),
+            used: 10,
+            allowed: 10,
+            resets_at: chrono::Utc::now(),
+        };
+        let status = Status::from(AppError::QuotaExceeded(Box::new(quota)));
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert!(matches!(app_error(&status), AppError::QuotaExceeded(q) if q.principal == "user:alice"));
+
+        let status = Status::from(AppError::InvalidInput("bad".into()));
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(error_body(&status)["code"], "INVALID_INPUT");
+        assert!(matches!(app_error(&status), AppError::InvalidInput(m) if m == "invalid input: bad"));
+
+        // 不是服务返回的错误（如连接不上）
+        let status = Status::unavailable("connection refused");
+        assert_eq!(error_body(&status)["message"], "connection refused");
+        assert!(matches!(app_error(&status), AppError::ExternalService(_)));
+    }
+}
@@ -0,0 +1,316 @@
+//! Job tracking shared by the services.
+//!
+//! Long-running work (backups, restores / imports, data transfers, ...) is
+//! registered with a [`JobStore`] as a generic [`Job`]. The store persists
+//! jobs to the `jobs` metadata table, keeps the running ones in memory, and
+//! hands out a [`CancellationToken`] for work that can be stopped, so jobs of
+//! every kind can be listed, inspected and cancelled through one API.
+//!
+//! Progress is written at most once per whole percent. Failures to persist a
+//! running job's progress are logged and do not stop the job.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+pub use tokio_util::sync::CancellationToken;
+
+use crate::errors::{AppError, AppResult};
+use crate::meta::{self, MetaPool, Statement};
+use crate::meta_query;
+use crate::models::job::{Job, JobKind};
+
+/// Maximum jobs returned by [`JobStore::list`].
+pub const MAX_LIST_LIMIT: u32 = 1000;
+
+/// Finished jobs older than this are deleted at startup.
+pub const RETENTION: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// Row from the `jobs` metadata table.
+#[derive(sqlx::FromRow)]
+struct JobRow {
+    id: String,
+    kind: String,
+    status: String,
+    progress: f64,
+    result_ref: Option<String>,
+    error: Option<String>,
+    created_at: String,
+    updated_at: String,
+    finished_at: Option<String>,
+}
+
+impl JobRow {
+    fn into_job(self) -> Option<Job> {
+        Some(Job {
+            id: self.id,
+            kind: self.kind.parse().ok()?,
+            status: self.status.parse().ok()?,
+            progress: self.progress,
+            result_ref: self.result_ref,
+            error: self.error,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            finished_at: self.finished_at,
+        })
+    }
+}
+
+const SELECT_JOB: Statement = Statement {
+    mysql: "SELECT `id`, `kind`, `status`, `progress`, `result_ref`, `error`, \
+         CAST(`created_at` AS CHAR) AS created_at, CAST(`updated_at` AS CHAR) AS updated_at, \
+         CAST(`finished_at` AS CHAR) AS finished_at FROM `jobs`",
+    postgres: r#"SELECT "id", "kind", "status", "progress", "result_ref", "error",
+         "created_at", "updated_at", "finished_at" FROM "jobs""#,
+    sqlite: r#"SELECT "id", "kind", "status", "progress", "result_ref", "error",
+         "created_at", "updated_at", "finished_at" FROM "jobs""#,
+};
+
+/// A running job and its cancellation token, if it can be cancelled.
+struct Active {
+    job: Job,
+    cancel: Option<CancellationToken>,
+}
+
+/// Persists jobs and tracks the running ones.
+pub struct JobStore {
+    pool: MetaPool,
+    active: Mutex<HashMap<String, Active>>,
+}
+
+impl JobStore {
+    /// Creates the store over the `jobs` table, which the metadata
+    /// migrations create.
+    ///
+    /// Jobs left running by a previous process are marked as failed, and
+    /// finished jobs older than [`RETENTION`] are deleted.
+    pub async fn new(pool: MetaPool) -> AppResult<Self> {
+        let reset = pool.sql(&Statement {
+            mysql: "UPDATE `jobs` SET `status` = 'failed', `error` = 'interrupted by service restart', \
+                 `updated_at` = UTC_TIMESTAMP(), `finished_at` = UTC_TIMESTAMP() WHERE `status` = 'running'",
+            postgres: r#"UPDATE "jobs" SET "status" = 'failed', "error" = 'interrupted by service restart',
+                 "updated_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'), "finished_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') WHERE "status" = 'running'"#,
+            sqlite: r#"UPDATE "jobs" SET "status" = 'failed', "error" = 'interrupted by service restart',
+                 "updated_at" = CURRENT_TIMESTAMP, "finished_at" = CURRENT_TIMESTAMP WHERE "status" = 'running'"#,
+        });
+        meta_query!(&pool, |p| sqlx::query(reset).execute(p).await.map(|_| ()))
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to reset running jobs: {}", e)))?;
+
+        let statement = pool.sql(&Statement {
+            mysql: "DELETE FROM `jobs` WHERE `finished_at` < ?",
+            postgres: r#"DELETE FROM "jobs" WHERE "finished_at" < $1"#,
+            sqlite: r#"DELETE FROM "jobs" WHERE "finished_at" < ?"#,
+        });
+        let purged = meta_query!(&pool, |p| {
+            sqlx::query(statement)
+                .bind(meta::timestamp(Utc::now() - RETENTION))
+                .execute(p)
+                .await
+                .map(|r| r.rows_affected())
+        })
+        .map_err(|e| AppError::DatabaseQuery(format!("Failed to purge old jobs: {}", e)))?;
+
+        tracing::info!(purged, "Old jobs purged");
+        Ok(Self { pool, active: Mutex::new(HashMap::new()) })
+    }
+
+    /// Registers a job that runs to completion.
+    pub async fn start(&self, job: Job) -> AppResult<()> {
+        self.register(job, None).await
+    }
+
+    /// Registers a job and returns the token its work must watch to stop early.
+    pub async fn start_cancellable(&self, job: Job) -> AppResult<CancellationToken> {
+        let token = CancellationToken::new();
+        self.register(job, Some(token.clone())).await?;
+        Ok(token)
+    }
+
+    async fn register(&self, job: Job, cancel: Option<CancellationToken>) -> AppResult<()> {
+        let statement = self.pool.sql(&Statement {
+            mysql: "INSERT INTO `jobs` (`id`, `kind`, `status`, `progress`, `created_at`, `updated_at`) \
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "jobs" ("id", "kind", "status", "progress", "created_at", "updated_at")
+                 VALUES ($1, $2, $3, $4, $5, $6)"#,
+            sqlite: r#"INSERT INTO "jobs" ("id", "kind", "status", "progress", "created_at", "updated_at")
+                 VALUES (?, ?, ?, ?, ?, ?)"#,
+        });
+        meta_query!(&self.pool, |pool| {
+            sqlx::query(statement)
+                .bind(&job.id)
+                .bind(job.kind.as_str())
+                .bind(job.status.as_str())
+                .bind(job.progress)
+                .bind(timestamp(&job.created_at))
+                .bind(timestamp(&job.updated_at))
+                .execute(pool)
+                .await
+                .map(|_| ())
+        })?;
+        self.lock().insert(job.id.clone(), Active { job, cancel });
+        Ok(())
+    }
+
+    /// Records the progress of a running job.
+    pub async fn progress(&self, job_id: &str, progress: f64) {
+        let job = {
+            let mut active = self.lock();
+            let Some(entry) = active.get_mut(job_id) else {
+                return;
+            };
+            if !entry.job.set_progress(progress) {
+                return;
+            }
+            entry.job.clone()
+        };
+        self.save(&job).await;
+    }
+
+    /// Ends a running job with the state set by `f` (see [`Job::complete`],
+    /// [`Job::fail`] and [`Job::cancel`]).
+    pub async fn finish(&self, job_id: &str, f: impl FnOnce(&mut Job)) {
+        let Some(Active { mut job, .. }) = self.lock().remove(job_id) else {
+            return;
+        };
+        f(&mut job);
+        if !job.status.is_finished() {
+            job.fail("job ended without a result");
+        }
+        self.save(&job).await;
+    }
+
+    /// Asks a running job to stop; the job reports `cancelled` once its work has stopped.
+    pub async fn cancel(&self, job_id: &str) -> AppResult<Job> {
+        {
+            let active = self.lock();
+            if let Some(entry) = active.get(job_id) {
+                let Some(token) = &entry.cancel else {
+                    return Err(AppError::Conflict(format!(
+                        "{} jobs cannot be cancelled",
+                        entry.job.kind
+                    )));
+                };
+                token.cancel();
+                return Ok(entry.job.clone());
+            }
+        }
+        let job = self.get(job_id).await?;
+        Err(AppError::Conflict(format!(
+            "job is {} and can no longer be cancelled",
+            job.status.as_str()
+        )))
+    }
+
+    /// Gets a job by ID.
+    pub async fn get(&self, job_id: &str) -> AppResult<Job> {
+        if let Some(entry) = self.lock().get(job_id) {
+            return Ok(entry.job.clone());
+        }
+        let statement = self.pool.concat(&[
+            &SELECT_JOB,
+            &Statement {
+                mysql: "WHERE `id` = ?",
+                postgres: r#"WHERE "id" = $1"#,
+                sqlite: r#"WHERE "id" = ?"#,
+            },
+        ]);
+        let row: Option<JobRow> = meta_query!(&self.pool, |pool| {
+            sqlx::query_as(&statement)
+                .bind(job_id)
+                .fetch_optional(pool)
+                .await
+        })?;
+        row.and_then(JobRow::into_job)
+            .ok_or_else(|| AppError::NotFound(format!("job {}", job_id)))
+    }
+
+    /// Lists jobs, newest first, optionally of one kind.
+    pub async fn list(&self, kind: Option<JobKind>, limit: u32) -> AppResult<Vec<Job>> {
+        let limit = limit.clamp(1, MAX_LIST_LIMIT);
+        let rows: Vec<JobRow> = match kind {
+            Some(kind) => {
+                let statement = self.pool.concat(&[
+                    &SELECT_JOB,
+                    &Statement {
+                        mysql: "WHERE `kind` = ? ORDER BY `created_at` DESC LIMIT ?",
+                        postgres: r#"WHERE "kind" = $1 ORDER BY "created_at" DESC LIMIT $2"#,
+                        sqlite: r#"WHERE "kind" = ? ORDER BY "created_at" DESC LIMIT ?"#,
+                    },
+                ]);
+                meta_query!(&self.pool, |pool| {
+                    sqlx::query_as(&statement)
+                        .bind(kind.as_str())
+                        .bind(i64::from(limit))
+                        .fetch_all(pool)
+                        .await
+                })?
+            }
+            None => {
+                let statement = self.pool.concat(&[
+                    &SELECT_JOB,
+                    &Statement {
+                        mysql: "ORDER BY `created_at` DESC LIMIT ?",
+                        postgres: r#"ORDER BY "created_at" DESC LIMIT $1"#,
+                        sqlite: r#"ORDER BY "created_at" DESC LIMIT ?"#,
+                    },
+                ]);
+                meta_query!(&self.pool, |pool| {
+                    sqlx::query_as(&statement)
+                        .bind(i64::from(limit))
+                        .fetch_all(pool)
+                        .await
+                })?
+            }
+        };
+        // Running jobs are reported with their in-memory progress
+        let active = self.lock();
+        Ok(rows
+            .into_iter()
+            .filter_map(JobRow::into_job)
+            .map(|job| match active.get(&job.id) {
+                Some(entry) => entry.job.clone(),
+                None => job,
+            })
+            .collect())
+    }
+
+    async fn save(&self, job: &Job) {
+        let statement = self.pool.sql(&Statement {
+            mysql: "UPDATE `jobs` SET `status` = ?, `progress` = ?, `result_ref` = ?, `error` = ?, \
+                 `updated_at` = ?, `finished_at` = ? WHERE `id` = ?",
+            postgres: r#"UPDATE "jobs" SET "status" = $1, "progress" = $2, "result_ref" = $3, "error" = $4,
+                 "updated_at" = $5, "finished_at" = $6 WHERE "id" = $7"#,
+            sqlite: r#"UPDATE "jobs" SET "status" = ?, "progress" = ?, "result_ref" = ?, "error" = ?,
+                 "updated_at" = ?, "finished_at" = ? WHERE "id" = ?"#,
+        });
+        let result = meta_query!(&self.pool, |pool| {
+            sqlx::query(statement)
+                .bind(job.status.as_str())
+                .bind(job.progress)
+                .bind(&job.result_ref)
+                .bind(&job.error)
+                .bind(timestamp(&job.updated_at))
+                .bind(job.finished_at.as_deref().map(timestamp))
+                .bind(&job.id)
+                .execute(pool)
+                .await
+                .map(|_| ())
+        });
+        if let Err(e) = result {
+            tracing::warn!(job_id = %job.id, error = %e, "Failed to record job state");
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Active>> {
+        self.active.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Converts a job timestamp (RFC 3339) for a DATETIME column.
+fn timestamp(value: &str) -> String {
+    let value = DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now());
+    meta::timestamp(value)
+}
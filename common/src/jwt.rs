@@ -0,0 +1,183 @@
+//! Session tokens (JWT, HS256).
+//!
+//! Users authenticate to the gateway with `Authorization: Bearer <token>`.
+//! Tokens are HS256 JWTs signed with a secret shared by the service that
+//! issues them and the gateway that verifies them. Each token names its
+//! session (`sid`); the session record in the metadata database is what
+//! makes a token revocable before it expires. Users logged in through single
+//! sign-on also carry the local roles mapped from their identity provider, and
+//! have principals of their own (`sso:<issuer>:<subject>`) rather than those
+//! of local users.
+//!
+//! Configuration:
+//! - `JWT_SECRET` - signing secret; when unset, bearer tokens are not accepted
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::errors::{AppError, AppResult};
+
+/// Principal prefix of users authenticated with a session token.
+pub const USER_PRINCIPAL_PREFIX: &str = "user:";
+
+/// Principal prefix of users authenticated by the single sign-on identity provider.
+pub const SSO_PRINCIPAL_PREFIX: &str = "sso:";
+
+/// User ID, and principal, of the identity provider account `subject` of `issuer`.
+///
+/// Local usernames cannot contain `:`, so an identity provider account never
+/// shares the connections, API keys or snapshots of a local user of the same name.
+pub fn sso_user_id(issuer: &str, subject: &str) -> String {
+    format!("{}{}:{}", SSO_PRINCIPAL_PREFIX, issuer, subject)
+}
+
+/// User ID of a session token's principal; `None` for other principals.
+pub fn principal_user_id(principal: &str) -> Option<&str> {
+    principal
+        .strip_prefix(USER_PRINCIPAL_PREFIX)
+        .or_else(|| principal.starts_with(SSO_PRINCIPAL_PREFIX).then_some(principal))
+}
+
+/// Encoded header of every token: `{"alg":"HS256","typ":"JWT"}`.
+const HEADER: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9";
+
+/// Claims carried by a session token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenClaims {
+    /// User ID.
+    pub sub: String,
+    /// Session ID.
+    pub sid: String,
+    /// Issued at (Unix seconds).
+    pub iat: i64,
+    /// Expires at (Unix seconds).
+    pub exp: i64,
+    /// Local roles of the user, matched by `role:<name>` policy principals.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+impl TokenClaims {
+    /// Principal of the token's user, e.g. `user:alice`, or the user ID of
+    /// single sign-on users (see [`sso_user_id`]).
+    pub fn principal(&self) -> String {
+        if self.sub.starts_with(SSO_PRINCIPAL_PREFIX) {
+            return self.sub.clone();
+        }
+        format!("{}{}", USER_PRINCIPAL_PREFIX, self.sub)
+    }
+}
+
+/// Signs and verifies session tokens.
+#[derive(Clone)]
+pub struct JwtKeys {
+    secret: Vec<u8>,
+}
+
+impl JwtKeys {
+    /// Creates the keys from a signing secret.
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self { secret: secret.into() }
+    }
+
+    /// Reads the secret from `JWT_SECRET`; `None` when unset or empty.
+    pub fn from_env() -> Option<Self> {
+        std::env::var("JWT_SECRET")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(Self::new)
+    }
+
+    /// Encodes and signs the claims.
+    pub fn encode(&self, claims: &TokenClaims) -> String {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).expect("claims serialize to JSON"));
+        let signing_input = format!("{}.{}", HEADER, payload);
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&signing_input).finalize().into_bytes());
+        format!("{}.{}", signing_input, signature)
+    }
+
+    /// Verifies the signature and expiry of a token and returns its claims.
+    ///
+    /// # Errors
+    /// Returns `AppError::Unauthorized` for malformed, forged or expired tokens.
+    pub fn decode(&self, token: &str) -> AppResult<TokenClaims> {
+        let (signing_input, signature) = token.rsplit_once('.').ok_or(AppError::Unauthorized)?;
+        let (header, payload) = signing_input.split_once('.').ok_or(AppError::Unauthorized)?;
+        // Only the fixed HS256 header is accepted, so `alg` cannot be downgraded
+        if header != HEADER {
+            return Err(AppError::Unauthorized);
+        }
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| AppError::Unauthorized)?;
+        self.mac(signing_input)
+            .verify_slice(&signature)
+            .map_err(|_| AppError::Unauthorized)?;
+
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| AppError::Unauthorized)?;
+        let claims: TokenClaims = serde_json::from_slice(&payload).map_err(|_| AppError::Unauthorized)?;
+        if claims.exp <= chrono::Utc::now().timestamp() {
+            return Err(AppError::Unauthorized);
+        }
+        Ok(claims)
+    }
+
+    fn mac(&self, signing_input: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(signing_input.as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(exp_offset: i64) -> TokenClaims {
+        let now = chrono::Utc::now().timestamp();
+        TokenClaims {
+            sub: "alice".to_string(),
+            sid: "s1".to_string(),
+            iat: now,
+            exp: now + exp_offset,
+            roles: vec!["analyst".to_string()],
+        }
+    }
+
+    #[test]
+    fn tokens_round_trip_and_reject_tampering() {
+        let keys = JwtKeys::new("secret");
+        let valid = claims(60);
+        let token = keys.encode(&valid);
+        assert_eq!(keys.decode(&token).unwrap(), valid);
+        assert_eq!(keys.decode(&token).unwrap().principal(), "user:alice");
+
+        // Signed with another secret
+        assert!(JwtKeys::new("other").decode(&token).is_err());
+        // Payload swapped for another user's
+        let forged_payload = URL_SAFE_NO_PAD.encode(br#"{"sub":"root","sid":"s1","iat":0,"exp":9999999999}"#);
+        let parts: Vec<&str> = token.split('.').collect();
+        assert!(keys.decode(&format!("{}.{}.{}", parts[0], forged_payload, parts[2])).is_err());
+        // Unsigned token
+        let none_header = URL_SAFE_NO_PAD.encode(br#"{"alg":"none","typ":"JWT"}"#);
+        assert!(keys.decode(&format!("{}.{}.", none_header, parts[1])).is_err());
+        // Expired
+        assert!(keys.decode(&keys.encode(&claims(-1))).is_err());
+        assert!(keys.decode("not-a-token").is_err());
+    }
+
+    #[test]
+    fn sso_users_do_not_share_local_principals() {
+        let local = claims(60);
+        let sso = TokenClaims {
+            sub: sso_user_id("https://idp.example.com", "alice"),
+            ..claims(60)
+        };
+        assert_eq!(local.principal(), "user:alice");
+        assert_eq!(sso.principal(), "sso:https://idp.example.com:alice");
+        assert_eq!(principal_user_id(&local.principal()), Some("alice"));
+        assert_eq!(principal_user_id(&sso.principal()), Some(sso.sub.as_str()));
+        assert_eq!(principal_user_id("key:k1"), None);
+    }
+}
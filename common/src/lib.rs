@@ -3,18 +3,53 @@
 //! This crate provides shared functionality including:
 //! - Error handling and result types
 //! - API response models
-//! - Configuration management
+//! - JSON extractor with standard error responses
+//! - Router fallbacks with standard 404 / 405 responses
+//! - Configuration management and reload
+//! - Admin endpoint authorization
+//! - Session tokens (JWT)
 //! - Middleware components
+//! - Log output setup (text or JSON)
+//! - OpenAPI response examples
+//! - External secrets backends for connection passwords
+//! - Notification channels (webhook, Slack, email)
+//! - Event bus between services (Redis pub/sub)
+//! - Internal gRPC interface between services (feature `grpc`)
+//! - Liveness and readiness probes
+//! - Service self-registration with the gateway
+//! - Optional TLS termination
+//! - Progress events of long-running jobs (Server-Sent Events)
+//! - Job tracking and cancellation for long-running work
+//! - Metadata database backends (MySQL or SQLite)
 //! - Utility functions
 
+pub mod admin;
 pub mod config;
+pub mod db_error;
+pub mod discovery;
 pub mod errors;
+pub mod events;
+pub mod extract;
+pub mod fallback;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod jobs;
+pub mod jwt;
+pub mod logging;
+pub mod meta;
 pub mod middleware;
 pub mod models;
+pub mod notify;
+pub mod openapi;
+pub mod probes;
+pub mod progress;
 pub mod response;
+pub mod secrets;
+pub mod tls;
 pub mod utils;
 
 // Re-export commonly used types
 pub use config::AppConfig;
+pub use db_error::{ConstraintKind, ConstraintViolation, DbErrorCategory, DbErrorDetails};
 pub use errors::{AppError, AppResult};
-pub use response::{ApiResponse, ApiError, ResponseMeta, Pagination, PaginatedData, code as ResponseCode};
+pub use response::{ApiResponse, ApiError, ResponseMeta, CacheInfo, RetryInfo, Pagination, PaginatedData, code as ResponseCode};
@@ -0,0 +1,50 @@
+//! Log output setup.
+//!
+//! [`init`] installs the tracing subscriber of a service: human-readable
+//! lines by default, or one JSON object per line with `LOG_FORMAT=json` so
+//! logs can be shipped to ELK or Loki without parsing. Every request runs in
+//! the `request` span opened by [`crate::middleware::request_id_middleware`],
+//! whose fields (`request_id`, `method`, `route`, `user`, `connection_id`)
+//! are attached to the events logged while handling it; the middleware ends
+//! each request with a `request completed` event carrying `status` and
+//! `latency_ms`.
+//!
+//! JSON lines look like:
+//!
+//! ```json
+//! {"timestamp":"2024-01-15T10:30:00.123Z","level":"INFO","message":"request completed","status":200,"latency_ms":12,"span":{"request_id":"…","method":"POST","route":"/api/query","user":"key:k_1","connection_id":"conn_001","name":"request"},"target":"common::middleware::request_id"}
+//! ```
+
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::config::{AppConfig, LogFormat};
+
+/// Installs the global tracing subscriber with the configured level
+/// (`RUST_LOG`) and format (`LOG_FORMAT`).
+pub fn init(config: &AppConfig) {
+    let filter = EnvFilter::try_new(&config.log_level).unwrap_or_else(|_| EnvFilter::new("info"));
+    let registry = tracing_subscriber::registry().with(filter);
+    match config.log_format {
+        LogFormat::Text => registry.with(fmt::layer()).init(),
+        LogFormat::Json => registry
+            .with(
+                fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(true)
+                    .with_span_list(false),
+            )
+            .init(),
+    }
+}
+
+/// Records the connection the current request works on, for requests that
+/// name it in the body rather than the path.
+pub fn record_connection_id(connection_id: &str) {
+    tracing::Span::current().record("connection_id", connection_id);
+}
+
+/// Records the principal of the current request once it is authenticated.
+pub fn record_user(user: &str) {
+    tracing::Span::current().record("user", user);
+}
@@ -4,11 +4,32 @@
 
 use axum::{
     body::Body,
-    http::{Request, StatusCode},
+    http::{HeaderMap, Request, StatusCode},
     middleware::Next,
     response::Response,
 };
 
+/// Header carrying a gateway API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Header carrying the principal the gateway authenticated (e.g. `key:<id>`).
+///
+/// The gateway removes it from client requests and sets it after verifying
+/// the API key; services read it with [`principal`].
+pub const PRINCIPAL_HEADER: &str = "x-principal";
+
+/// Header carrying the session of a user authenticated with a session token.
+///
+/// Set by the gateway next to [`PRINCIPAL_HEADER`]; services read it with
+/// [`session_id`] to tell the caller's own session apart.
+pub const SESSION_HEADER: &str = "x-session-id";
+
+/// Header carrying the workspace the client has selected.
+///
+/// Sent by the client; list endpoints that support workspaces read it with
+/// [`workspace`] and only return the resources grouped in that workspace.
+pub const WORKSPACE_HEADER: &str = "x-workspace-id";
+
 /// Authentication middleware handler.
 ///
 /// Validates authentication tokens and authorizes requests.
@@ -38,3 +59,39 @@ pub fn extract_bearer_token(req: &Request<Body>) -> Option<&str> {
         .and_then(|v| v.to_str().ok())
         .and_then(|v| v.strip_prefix("Bearer "))
 }
+
+/// Extract the authenticated principal from the `X-Principal` header.
+pub fn principal(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(PRINCIPAL_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Extract the caller's session ID from the `X-Session-Id` header.
+pub fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Extract the active workspace from the `X-Workspace-Id` header.
+pub fn workspace(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(WORKSPACE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Extract the API key from the `X-Api-Key` header.
+pub fn extract_api_key(req: &Request<Body>) -> Option<&str> {
+    req.headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
@@ -0,0 +1,52 @@
+//! CORS layer built from the configured origins, methods and headers.
+
+use std::time::Duration;
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::CorsConfig;
+use crate::middleware::request_id::REQUEST_ID_HEADER;
+
+/// Response headers browsers may read besides the CORS-safelisted ones.
+const EXPOSED_HEADERS: &[&str] = &["etag", "retry-after", "x-execution-time-ms", "x-row-count", "x-truncated"];
+
+/// Builds the CORS layer of a service.
+///
+/// An empty origin list allows any origin. Invalid entries are skipped; the
+/// configuration loader already reports them.
+pub fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    let origins = if config.allowed_origins.is_empty() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+    let methods: Vec<Method> = config
+        .allowed_methods
+        .iter()
+        .filter_map(|method| Method::from_bytes(method.as_bytes()).ok())
+        .collect();
+    let headers: Vec<HeaderName> = config
+        .allowed_headers
+        .iter()
+        .filter_map(|name| HeaderName::from_bytes(name.as_bytes()).ok())
+        .collect();
+    let exposed = EXPOSED_HEADERS
+        .iter()
+        .map(|name| HeaderName::from_static(name))
+        .chain(std::iter::once(REQUEST_ID_HEADER.clone()))
+        .collect::<Vec<_>>();
+
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .expose_headers(exposed)
+        .allow_credentials(config.allow_credentials && !config.allowed_origins.is_empty())
+        .max_age(Duration::from_secs(config.max_age_secs))
+}
@@ -0,0 +1,133 @@
+//! Session cookies and double-submit CSRF tokens.
+//!
+//! With [`SessionCookieConfig`] set, login responses carry two cookies: an
+//! `HttpOnly` cookie with the access token and a readable cookie with a random
+//! CSRF token. Browsers send both cookies with every request, including forged
+//! cross-site ones, but only pages of an allowed origin can read the CSRF
+//! cookie and echo it in the CSRF header. The gateway therefore accepts a
+//! cookie-authenticated request that changes state only if the header matches
+//! the cookie.
+
+use axum::http::{header, HeaderMap, Method};
+use uuid::Uuid;
+
+use crate::config::SessionCookieConfig;
+use crate::errors::{AppError, AppResult};
+
+/// `Set-Cookie` values that store an access token and a new CSRF token for `max_age_secs`.
+pub fn session_cookies(config: &SessionCookieConfig, access_token: &str, max_age_secs: u64) -> [String; 2] {
+    [
+        cookie(config, &config.name, access_token, max_age_secs, true),
+        cookie(config, &config.csrf_cookie_name, &new_csrf_token(), max_age_secs, false),
+    ]
+}
+
+/// `Set-Cookie` values that delete both cookies.
+pub fn clear_session_cookies(config: &SessionCookieConfig) -> [String; 2] {
+    [
+        cookie(config, &config.name, "", 0, true),
+        cookie(config, &config.csrf_cookie_name, "", 0, false),
+    ]
+}
+
+/// Access token from the session cookie.
+pub fn session_cookie_token<'a>(config: &SessionCookieConfig, headers: &'a HeaderMap) -> Option<&'a str> {
+    cookie_value(headers, &config.name).filter(|token| !token.is_empty())
+}
+
+/// Checks the CSRF header of a cookie-authenticated request; safe methods need none.
+///
+/// # Errors
+/// Returns `AppError::Forbidden` if the header is missing or does not match the CSRF cookie.
+pub fn verify_csrf(config: &SessionCookieConfig, method: &Method, headers: &HeaderMap) -> AppResult<()> {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(());
+    }
+    let expected = cookie_value(headers, &config.csrf_cookie_name).filter(|token| !token.is_empty());
+    let provided = headers
+        .get(config.csrf_header_name.as_str())
+        .and_then(|v| v.to_str().ok());
+    match (expected, provided) {
+        (Some(expected), Some(provided)) if constant_time_eq(expected.as_bytes(), provided.as_bytes()) => Ok(()),
+        _ => Err(AppError::Forbidden(format!(
+            "CSRF token missing or invalid: send the {} cookie value in the {} header",
+            config.csrf_cookie_name, config.csrf_header_name
+        ))),
+    }
+}
+
+/// Value of a request cookie.
+pub fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn new_csrf_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn cookie(config: &SessionCookieConfig, name: &str, value: &str, max_age_secs: u64, http_only: bool) -> String {
+    let mut cookie = format!(
+        "{}={}; Path=/; Max-Age={}; SameSite={}",
+        name,
+        value,
+        max_age_secs,
+        config.same_site.as_str()
+    );
+    if http_only {
+        cookie.push_str("; HttpOnly");
+    }
+    if config.secure {
+        cookie.push_str("; Secure");
+    }
+    cookie
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CookieSameSite;
+    use axum::http::HeaderValue;
+
+    fn config() -> SessionCookieConfig {
+        SessionCookieConfig {
+            name: "dbm_session".to_string(),
+            csrf_cookie_name: "dbm_csrf".to_string(),
+            csrf_header_name: "x-csrf-token".to_string(),
+            secure: true,
+            same_site: CookieSameSite::Lax,
+        }
+    }
+
+    #[test]
+    fn csrf_header_must_match_cookie() {
+        let config = config();
+        let [session, csrf] = session_cookies(&config, "tok", 900);
+        assert_eq!(session, "dbm_session=tok; Path=/; Max-Age=900; SameSite=Lax; HttpOnly; Secure");
+        let token = csrf.split(';').next().unwrap().trim_start_matches("dbm_csrf=").to_string();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_str(&format!("dbm_session=tok; dbm_csrf={}", token)).unwrap(),
+        );
+        assert_eq!(session_cookie_token(&config, &headers), Some("tok"));
+        assert!(verify_csrf(&config, &Method::GET, &headers).is_ok());
+        assert!(verify_csrf(&config, &Method::POST, &headers).is_err());
+
+        headers.insert("x-csrf-token", HeaderValue::from_static("forged"));
+        assert!(verify_csrf(&config, &Method::DELETE, &headers).is_err());
+        headers.insert("x-csrf-token", HeaderValue::from_str(&token).unwrap());
+        assert!(verify_csrf(&config, &Method::DELETE, &headers).is_ok());
+    }
+}
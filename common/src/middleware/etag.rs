@@ -0,0 +1,141 @@
+//! Conditional GET support.
+//!
+//! [`etag_middleware`] tags successful GET responses with a weak `ETag`
+//! derived from the response body and answers `304 Not Modified` when the
+//! request's `If-None-Match` already names it, so clients polling unchanged
+//! resources (connection lists, schemas) skip the body.
+//!
+//! The `meta` of [`crate::response::ApiResponse`] bodies (timestamp, request
+//! ID, duration) differs on every request and is left out of the hash. The
+//! tag is weak because compression may change the bytes on the wire.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{
+        header::{ETAG, IF_NONE_MATCH},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+
+/// Adds an `ETag` to successful GET responses and answers matching
+/// `If-None-Match` requests with `304 Not Modified`.
+pub async fn etag_middleware(req: Request, next: Next) -> Response {
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        return next.run(req).await;
+    }
+    let if_none_match = req.headers().get(IF_NONE_MATCH).cloned();
+
+    let response = next.run(req).await;
+    if response.status() != StatusCode::OK || response.headers().contains_key(ETAG) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let tag = etag(&bytes);
+    let Ok(value) = HeaderValue::from_str(&tag) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    if if_none_match.is_some_and(|header| matches(&header, &tag)) {
+        let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+        copy_cache_headers(&parts.headers, not_modified.headers_mut());
+        not_modified.headers_mut().insert(ETAG, value);
+        return not_modified;
+    }
+
+    parts.headers.insert(ETAG, value);
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Weak entity tag of a response body, ignoring the `meta` of JSON bodies.
+pub fn etag(body: &[u8]) -> String {
+    let digest = match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(mut object)) => {
+            object.remove("meta");
+            Sha256::digest(serde_json::to_vec(&object).unwrap_or_default())
+        }
+        _ => Sha256::digest(body),
+    };
+    format!("W/\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Whether an `If-None-Match` header names `tag` (weak comparison).
+fn matches(header: &HeaderValue, tag: &str) -> bool {
+    let Ok(header) = header.to_str() else {
+        return false;
+    };
+    let opaque = |t: &str| t.trim().trim_start_matches("W/").to_string();
+    let tag = opaque(tag);
+    header
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == tag)
+}
+
+/// Headers a 304 response repeats from the full response.
+fn copy_cache_headers(from: &HeaderMap, to: &mut HeaderMap) {
+    for name in ["cache-control", "content-location", "expires", "vary"] {
+        if let Some(value) = from.get(name) {
+            to.insert(name, value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+    use axum::{middleware, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/items",
+                get(|| async {
+                    let now = chrono::Utc::now().to_rfc3339();
+                    format!(r#"{{"code":200,"data":[1,2],"meta":{{"timestamp":"{}"}}}}"#, now)
+                }),
+            )
+            .layer(middleware::from_fn(etag_middleware))
+    }
+
+    #[tokio::test]
+    async fn unchanged_responses_are_not_modified() {
+        let first = app().oneshot(Request::get("/items").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        let tag = first.headers()[ETAG].clone();
+        assert!(tag.to_str().unwrap().starts_with("W/\""));
+
+        let conditional = Request::get("/items")
+            .header(IF_NONE_MATCH, tag.clone())
+            .body(Body::empty())
+            .unwrap();
+        let second = app().oneshot(conditional).await.unwrap();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(second.headers()[ETAG], tag);
+
+        let stale = Request::get("/items")
+            .header(IF_NONE_MATCH, "W/\"0123\"")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(app().oneshot(stale).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        let tag = etag(br#"{"data":1}"#);
+        let strong = tag.trim_start_matches("W/");
+        assert!(matches(&HeaderValue::from_str(strong).unwrap(), &tag));
+        assert!(matches(&HeaderValue::from_static("\"x\", *"), &tag));
+        assert!(!matches(&HeaderValue::from_static("\"x\""), &tag));
+        assert_ne!(etag(br#"{"data":1}"#), etag(br#"{"data":2}"#));
+    }
+}
@@ -1,8 +1,17 @@
 //! Middleware components for all services.
 
 pub mod auth;
+pub mod cors;
+pub mod csrf;
+pub mod etag;
 pub mod request_id;
+pub mod security_headers;
+pub mod signing;
 
 // Re-export commonly used types
 pub use auth::auth_middleware;
+pub use cors::cors_layer;
+pub use etag::etag_middleware;
 pub use request_id::{request_id_middleware, RequestId, REQUEST_ID_HEADER};
+pub use security_headers::SecurityHeadersLayer;
+pub use signing::{signature_middleware, RequestSigner, SendSigned, SignatureVerifier};
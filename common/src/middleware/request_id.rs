@@ -1,15 +1,27 @@
 //! Request ID middleware.
 //!
-//! Generates and attaches unique request IDs for request tracing and logging.
+//! Generates and attaches unique request IDs for request tracing and logging,
+//! and logs each request with its route, principal, target connection,
+//! status and latency (see [`crate::logging`]).
+
+use std::time::Instant;
 
 use axum::{
     body::Body,
+    extract::MatchedPath,
     http::{header::HeaderName, HeaderValue, Request},
     middleware::Next,
     response::Response,
 };
+use tracing::Instrument;
 use uuid::Uuid;
 
+use crate::middleware::auth::principal;
+use crate::models::api_key::path_connection_id;
+
+/// Probe paths, logged at debug level so orchestrator polling does not flood the logs.
+const PROBE_PATHS: &[&str] = &["/healthz", "/readyz"];
+
 /// Header name for request ID.
 pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
 
@@ -19,6 +31,8 @@ pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id
 /// the request extensions and response headers.
 ///
 /// If the request already has an X-Request-ID header, it will be used instead.
+/// The request is handled inside a `request` span and ends with a
+/// `request completed` event.
 ///
 /// # Arguments
 /// * `req` - The incoming HTTP request
@@ -38,17 +52,44 @@ pub async fn request_id_middleware(mut req: Request<Body>, next: Next) -> Respon
     // Store in request extensions for handlers to access
     req.extensions_mut().insert(RequestId(request_id.clone()));
 
-    // Create a tracing span with request ID
+    // Create a tracing span with request ID; the route is the matched
+    // template when the router matched one
+    let path = req.uri().path().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(path.as_str(), MatchedPath::as_str)
+        .to_string();
     let span = tracing::info_span!(
         "request",
         request_id = %request_id,
         method = %req.method(),
         uri = %req.uri(),
+        route = %route,
+        user = tracing::field::Empty,
+        connection_id = tracing::field::Empty,
     );
-    let _guard = span.enter();
+    if let Some(user) = principal(req.headers()) {
+        span.record("user", user);
+    }
+    if let Some(id) = connection_id(&path) {
+        span.record("connection_id", id);
+    }
 
     // Process request
-    let mut response = next.run(req).await;
+    let start = Instant::now();
+    let mut response = next.run(req).instrument(span.clone()).await;
+    let latency_ms = start.elapsed().as_millis() as u64;
+    let status = response.status().as_u16();
+    span.in_scope(|| {
+        if PROBE_PATHS.contains(&path.as_str()) {
+            tracing::debug!(status, latency_ms, "request completed");
+        } else if status >= 500 {
+            tracing::error!(status, latency_ms, "request completed");
+        } else {
+            tracing::info!(status, latency_ms, "request completed");
+        }
+    });
 
     // Add request ID to response headers
     if let Ok(value) = HeaderValue::from_str(&request_id) {
@@ -58,6 +99,18 @@ pub async fn request_id_middleware(mut req: Request<Body>, next: Next) -> Respon
     response
 }
 
+/// Connection a request path refers to.
+fn connection_id(path: &str) -> Option<&str> {
+    path_connection_id(path)
+        .or_else(|| {
+            ["/internal/connections/", "/internal/pools/"]
+                .iter()
+                .find_map(|prefix| path.strip_prefix(prefix))
+                .and_then(|rest| rest.split('/').next())
+        })
+        .filter(|id| !id.is_empty() && *id != "test")
+}
+
 /// Request ID wrapper for storing in request extensions.
 #[derive(Clone, Debug)]
 pub struct RequestId(pub String);
@@ -97,3 +150,18 @@ impl From<&str> for RequestId {
         Self(s.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_id_is_read_from_connection_paths() {
+        assert_eq!(connection_id("/api/connections/conn_1/schema"), Some("conn_1"));
+        assert_eq!(connection_id("/internal/connections/conn_2/execute"), Some("conn_2"));
+        assert_eq!(connection_id("/internal/pools/conn_3"), Some("conn_3"));
+        assert_eq!(connection_id("/api/connections/test"), None);
+        assert_eq!(connection_id("/api/connections"), None);
+        assert_eq!(connection_id("/api/query"), None);
+    }
+}
@@ -0,0 +1,131 @@
+//! Security response headers.
+//!
+//! [`SecurityHeadersLayer`] adds `Strict-Transport-Security`,
+//! `X-Content-Type-Options`, `X-Frame-Options`, `Referrer-Policy` and the
+//! configured `Content-Security-Policy` to every response. Headers a handler
+//! already set are kept.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::http::{header, HeaderName, HeaderValue, Request, Response};
+use tower::{Layer, Service};
+
+use crate::config::SecurityHeadersConfig;
+
+/// Layer adding security headers to responses.
+#[derive(Clone)]
+pub struct SecurityHeadersLayer {
+    headers: Arc<Vec<(HeaderName, HeaderValue)>>,
+}
+
+impl SecurityHeadersLayer {
+    /// Builds the headers from the configuration; an invalid policy is left out.
+    pub fn new(config: &SecurityHeadersConfig) -> Self {
+        let mut headers = vec![
+            (header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+            (header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY")),
+            (header::REFERRER_POLICY, HeaderValue::from_static("no-referrer")),
+        ];
+        if config.hsts_max_age_secs > 0 {
+            let value = format!("max-age={}", config.hsts_max_age_secs);
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.push((header::STRICT_TRANSPORT_SECURITY, value));
+            }
+        }
+        if let Ok(value) = HeaderValue::from_str(&config.content_security_policy) {
+            headers.push((header::CONTENT_SECURITY_POLICY, value));
+        }
+        Self {
+            headers: Arc::new(headers),
+        }
+    }
+}
+
+impl<S> Layer<S> for SecurityHeadersLayer {
+    type Service = SecurityHeaders<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SecurityHeaders {
+            inner,
+            headers: self.headers.clone(),
+        }
+    }
+}
+
+/// Service adding security headers to the responses of the inner service.
+#[derive(Clone)]
+pub struct SecurityHeaders<S> {
+    inner: S,
+    headers: Arc<Vec<(HeaderName, HeaderValue)>>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for SecurityHeaders<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let future = self.inner.call(req);
+        let headers = self.headers.clone();
+        Box::pin(async move {
+            let mut response = future.await?;
+            for (name, value) in headers.iter() {
+                response
+                    .headers_mut()
+                    .entry(name)
+                    .or_insert_with(|| value.clone());
+            }
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn adds_headers_without_replacing_existing_ones() {
+        let config = SecurityHeadersConfig {
+            hsts_max_age_secs: 600,
+            content_security_policy: "default-src 'none'".to_string(),
+        };
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route("/frame", get(|| async { ([(header::X_FRAME_OPTIONS, "SAMEORIGIN")], "ok") }))
+            .layer(SecurityHeadersLayer::new(&config));
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let headers = response.headers();
+        assert_eq!(headers[header::STRICT_TRANSPORT_SECURITY], "max-age=600");
+        assert_eq!(headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert_eq!(headers[header::X_FRAME_OPTIONS], "DENY");
+        assert_eq!(headers[header::REFERRER_POLICY], "no-referrer");
+        assert_eq!(headers[header::CONTENT_SECURITY_POLICY], "default-src 'none'");
+
+        let response = app
+            .oneshot(Request::get("/frame").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::X_FRAME_OPTIONS], "SAMEORIGIN");
+    }
+}
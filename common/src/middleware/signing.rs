@@ -0,0 +1,401 @@
+//! Internal request signing.
+//!
+//! Requests between services (gateway → services, query-service →
+//! connection-service) carry an HMAC-SHA256 signature over the method, path
+//! and query, caller, timestamp, nonce and body hash, keyed by a secret shared
+//! by all services. The receiving service rejects requests that are unsigned,
+//! signed with another key, older than the allowed clock skew or replayed
+//! (nonces are remembered until their timestamp expires), so internal APIs
+//! cannot be called by other workloads on the same network.
+//!
+//! Configuration:
+//! - `INTERNAL_SIGNING_SECRET` - shared secret; when unset, requests are not
+//!   signed and signatures are not checked
+//! - `INTERNAL_SIGNING_MAX_SKEW_SECS` - accepted timestamp skew in seconds (default: 300)
+//!
+//! Health checks (`/api/health`, `/healthz`, `/readyz`) and API documentation (`/api-docs/`) are
+//! accepted without a signature. Internal endpoints (`/internal/` and the
+//! internal gRPC services) are further limited to the services that call
+//! them, see [`INTERNAL_ROUTES`].
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+
+/// Header carrying the calling service name.
+pub const CALLER_HEADER: &str = "x-internal-caller";
+/// Header carrying the signing time (Unix seconds).
+pub const TIMESTAMP_HEADER: &str = "x-internal-timestamp";
+/// Header carrying the single-use nonce.
+pub const NONCE_HEADER: &str = "x-internal-nonce";
+/// Header carrying the hex SHA-256 of the request body.
+pub const CONTENT_SHA256_HEADER: &str = "x-internal-content-sha256";
+/// Header carrying the hex HMAC-SHA256 signature.
+pub const SIGNATURE_HEADER: &str = "x-internal-signature";
+
+const DEFAULT_MAX_SKEW_SECS: i64 = 300;
+
+/// Nonce count above which expired nonces are pruned.
+const NONCE_PRUNE_THRESHOLD: usize = 1024;
+
+/// Paths accepted without a signature.
+const EXEMPT_PREFIXES: &[&str] = &["/api/health", "/healthz", "/readyz", "/api-docs/"];
+
+/// Path prefix of the internal gRPC services (package `dbm.*`).
+const GRPC_PREFIX: &str = "/dbm.";
+
+/// Internal endpoints and the callers allowed to use them.
+///
+/// The gateway signs every request it forwards, so its signature must not
+/// open the internal endpoints it does not call itself to external clients.
+/// Internal paths not listed here are rejected.
+pub const INTERNAL_ROUTES: &[(&str, &[&str])] = &[
+    ("/internal/api-keys/", &["gateway"]),
+    ("/internal/authz/", &["gateway"]),
+    ("/internal/sessions/", &["gateway"]),
+    ("/internal/auth/", &["gateway"]),
+    ("/internal/pools/", &["query-service"]),
+    ("/internal/connections/", &["query-service"]),
+    ("/internal/registry", &["connection-service", "query-service", "ai-service"]),
+    ("/dbm.connection.v1.ConnectionService/", &["query-service"]),
+];
+
+/// Whether `caller` may call `path`; paths outside `/internal/` and the gRPC services are open to every caller.
+fn caller_allowed(caller: &str, path: &str) -> bool {
+    if !path.starts_with("/internal/") && !path.starts_with(GRPC_PREFIX) {
+        return true;
+    }
+    INTERNAL_ROUTES
+        .iter()
+        .find(|(prefix, _)| path.starts_with(prefix))
+        .is_some_and(|(_, callers)| callers.contains(&caller))
+}
+
+fn signing_secret() -> Option<Arc<[u8]>> {
+    std::env::var("INTERNAL_SIGNING_SECRET")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(|s| Arc::from(s.into_bytes()))
+}
+
+fn mac(secret: &[u8]) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length")
+}
+
+/// The signed fields, one per line.
+fn string_to_sign(
+    method: &str,
+    path_and_query: &str,
+    caller: &str,
+    timestamp: &str,
+    nonce: &str,
+    content_sha256: &str,
+) -> String {
+    format!("{}\n{}\n{}\n{}\n{}\n{}", method, path_and_query, caller, timestamp, nonce, content_sha256)
+}
+
+/// Signs outgoing internal requests.
+#[derive(Clone)]
+pub struct RequestSigner {
+    caller: String,
+    secret: Option<Arc<[u8]>>,
+}
+
+impl RequestSigner {
+    /// Creates a signer for `caller`; `None` disables signing.
+    pub fn new(caller: impl Into<String>, secret: Option<&str>) -> Self {
+        Self {
+            caller: caller.into(),
+            secret: secret.map(|s| Arc::from(s.as_bytes())),
+        }
+    }
+
+    /// Creates a signer for `caller` with the secret from `INTERNAL_SIGNING_SECRET`.
+    pub fn from_env(caller: impl Into<String>) -> Self {
+        Self {
+            caller: caller.into(),
+            secret: signing_secret(),
+        }
+    }
+
+    /// Adds the signature headers to `request`; does nothing when signing is disabled.
+    ///
+    /// Only buffered bodies are hashed, so streaming bodies are signed as empty
+    /// and rejected by the receiver.
+    pub fn sign(&self, request: &mut reqwest::Request) {
+        if self.secret.is_none() {
+            return;
+        }
+        let url = request.url();
+        let path_and_query = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let body = request.body().and_then(|b| b.as_bytes()).unwrap_or_default();
+        let mut headers = HeaderMap::new();
+        self.sign_headers(request.method().as_str(), &path_and_query, body, &mut headers);
+        request.headers_mut().extend(headers);
+    }
+
+    /// Adds the signature headers for a request with the given method, path
+    /// and query and body to `headers`; does nothing when signing is disabled.
+    pub fn sign_headers(&self, method: &str, path_and_query: &str, body: &[u8], headers: &mut HeaderMap) {
+        let Some(secret) = &self.secret else {
+            return;
+        };
+        let content_sha256 = hex::encode(Sha256::digest(body));
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let nonce = Uuid::new_v4().simple().to_string();
+
+        let mut mac = mac(secret);
+        mac.update(
+            string_to_sign(
+                method,
+                path_and_query,
+                &self.caller,
+                &timestamp,
+                &nonce,
+                &content_sha256,
+            )
+            .as_bytes(),
+        );
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        for (name, value) in [
+            (CALLER_HEADER, self.caller.as_str()),
+            (TIMESTAMP_HEADER, &timestamp),
+            (NONCE_HEADER, &nonce),
+            (CONTENT_SHA256_HEADER, &content_sha256),
+            (SIGNATURE_HEADER, &signature),
+        ] {
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.insert(name, value);
+            }
+        }
+    }
+}
+
+/// Sends a request with the internal signature headers.
+pub trait SendSigned {
+    /// Like `RequestBuilder::send`, signing the request first.
+    fn send_signed(self, signer: &RequestSigner) -> impl Future<Output = reqwest::Result<reqwest::Response>> + Send;
+}
+
+impl SendSigned for reqwest::RequestBuilder {
+    fn send_signed(self, signer: &RequestSigner) -> impl Future<Output = reqwest::Result<reqwest::Response>> + Send {
+        let (client, request) = self.build_split();
+        let request = request.map(|mut request| {
+            signer.sign(&mut request);
+            request
+        });
+        async move { client.execute(request?).await }
+    }
+}
+
+/// Checks signatures of incoming internal requests.
+pub struct SignatureVerifier {
+    secret: Option<Arc<[u8]>>,
+    max_skew_secs: i64,
+    nonces: Mutex<HashMap<String, i64>>,
+}
+
+impl SignatureVerifier {
+    /// Creates a verifier; `None` disables signature checks.
+    pub fn new(secret: Option<&str>, max_skew_secs: i64) -> Self {
+        Self {
+            secret: secret.map(|s| Arc::from(s.as_bytes())),
+            max_skew_secs: max_skew_secs.max(1),
+            nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a verifier from `INTERNAL_SIGNING_SECRET` and `INTERNAL_SIGNING_MAX_SKEW_SECS`.
+    pub fn from_env() -> Self {
+        let secret = signing_secret();
+        if secret.is_none() {
+            tracing::warn!("未设置 INTERNAL_SIGNING_SECRET，不校验内部请求签名");
+        }
+        Self {
+            secret,
+            max_skew_secs: std::env::var("INTERNAL_SIGNING_MAX_SKEW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_MAX_SKEW_SECS)
+                .max(1),
+            nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether signatures are checked (`INTERNAL_SIGNING_SECRET` is set).
+    pub fn is_enabled(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Checks the signature headers and records the nonce.
+    ///
+    /// Returns the signed body hash, which the caller compares with the body.
+    ///
+    /// # Errors
+    /// Returns `AppError::Unauthorized` when a header is missing, the
+    /// signature does not match, the caller may not call the internal path,
+    /// the timestamp is outside the allowed skew or the nonce was already used.
+    async fn check_headers(&self, secret: &[u8], method: &str, path_and_query: &str, headers: &HeaderMap) -> AppResult<String> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| reject(&format!("缺少 {} 头", name)))
+        };
+        let caller = header(CALLER_HEADER)?;
+        let timestamp = header(TIMESTAMP_HEADER)?;
+        let nonce = header(NONCE_HEADER)?;
+        let content_sha256 = header(CONTENT_SHA256_HEADER)?;
+        let signature = hex::decode(header(SIGNATURE_HEADER)?).map_err(|_| reject("签名格式无效"))?;
+
+        let mut mac = mac(secret);
+        mac.update(string_to_sign(method, path_and_query, caller, timestamp, nonce, content_sha256).as_bytes());
+        mac.verify_slice(&signature).map_err(|_| reject("签名不匹配"))?;
+
+        let path = path_and_query.split('?').next().unwrap_or_default();
+        if !caller_allowed(caller, path) {
+            return Err(reject(&format!("{} 无权调用内部接口 {}", caller, path)));
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let signed_at: i64 = timestamp.parse().map_err(|_| reject("时间戳无效"))?;
+        if (now - signed_at).abs() > self.max_skew_secs {
+            return Err(reject("时间戳超出允许偏差"));
+        }
+
+        let mut nonces = self.nonces.lock().await;
+        if nonces.len() >= NONCE_PRUNE_THRESHOLD {
+            nonces.retain(|_, expires_at| *expires_at >= now);
+        }
+        if nonces.insert(nonce.to_string(), signed_at + self.max_skew_secs).is_some() {
+            return Err(reject("重复的请求（nonce 已使用）"));
+        }
+        tracing::debug!(caller = %caller, "内部请求签名校验通过");
+        Ok(content_sha256.to_string())
+    }
+
+    /// Verifies `req`, returning it with the buffered body.
+    async fn verify(&self, secret: &[u8], req: Request) -> AppResult<Request> {
+        let path_and_query = req.uri().path_and_query().map_or("/", |pq| pq.as_str()).to_string();
+        let content_sha256 = self
+            .check_headers(secret, req.method().as_str(), &path_and_query, req.headers())
+            .await?;
+
+        // 签名已验证，调用方可信，再读取请求体核对摘要
+        let (parts, body) = req.into_parts();
+        let bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .map_err(|e| AppError::InvalidInput(format!("读取请求体失败: {}", e)))?;
+        if hex::encode(Sha256::digest(&bytes)) != content_sha256 {
+            return Err(reject("请求体与签名不一致"));
+        }
+        Ok(Request::from_parts(parts, Body::from(bytes)))
+    }
+}
+
+fn reject(reason: &str) -> AppError {
+    tracing::warn!(reason = %reason, "内部请求签名校验失败");
+    AppError::Unauthorized
+}
+
+/// Rejects internal requests without a valid signature.
+pub async fn signature_middleware(State(verifier): State<Arc<SignatureVerifier>>, req: Request, next: Next) -> Response {
+    let Some(secret) = verifier.secret.clone() else {
+        return next.run(req).await;
+    };
+    if EXEMPT_PREFIXES.iter().any(|prefix| req.uri().path().starts_with(prefix)) {
+        return next.run(req).await;
+    }
+    match verifier.verify(&secret, req).await {
+        Ok(req) => next.run(req).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{middleware, Router};
+    use tower::ServiceExt;
+
+    fn signed(signer: &RequestSigner, path: &str, body: &'static str) -> reqwest::Request {
+        let mut request = reqwest::Client::new()
+            .post(format!("http://connection-service{}", path))
+            .body(body)
+            .build()
+            .unwrap();
+        signer.sign(&mut request);
+        request
+    }
+
+    fn to_axum(request: &reqwest::Request, path: &str, body: &'static str) -> Request {
+        let mut builder = Request::post(path);
+        for (name, value) in request.headers() {
+            builder = builder.header(name, value);
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    #[test]
+    fn limits_internal_routes_to_their_callers() {
+        assert!(caller_allowed("gateway", "/api/connections/c1"));
+        assert!(caller_allowed("gateway", "/internal/api-keys/verify"));
+        assert!(!caller_allowed("gateway", "/internal/connections/c1/execute"));
+        assert!(caller_allowed("query-service", "/internal/connections/c1/execute"));
+        assert!(!caller_allowed("query-service", "/internal/unknown"));
+        assert!(caller_allowed("query-service", "/dbm.connection.v1.ConnectionService/ExecuteQuery"));
+        assert!(!caller_allowed("gateway", "/dbm.connection.v1.ConnectionService/ExecuteQuery"));
+        assert!(!caller_allowed("query-service", "/dbm.other.v1.Service/Call"));
+    }
+
+    #[tokio::test]
+    async fn rejects_unsigned_forged_and_replayed_requests() {
+        let verifier = Arc::new(SignatureVerifier::new(Some("secret"), 300));
+        let app = Router::new()
+            .route("/internal/pools/{id}", post(|body: String| async move { body }))
+            .layer(middleware::from_fn_with_state(verifier, signature_middleware));
+        let send = |req: Request| {
+            let app = app.clone();
+            async move { app.oneshot(req).await.unwrap().status() }
+        };
+
+        let signer = RequestSigner::new("query-service", Some("secret"));
+        let path = "/internal/pools/c1?refresh=true";
+        let request = signed(&signer, path, "{}");
+        assert_eq!(send(to_axum(&request, path, "{}")).await, StatusCode::OK);
+        assert_eq!(send(to_axum(&request, path, "{}")).await, StatusCode::UNAUTHORIZED);
+
+        let request = signed(&signer, path, "{}");
+        assert_eq!(send(to_axum(&request, "/internal/pools/c2", "{}")).await, StatusCode::UNAUTHORIZED);
+        let request = signed(&signer, path, "{}");
+        assert_eq!(send(to_axum(&request, path, "{\"x\":1}")).await, StatusCode::UNAUTHORIZED);
+
+        // 网关的签名不能访问它自己不调用的内部接口
+        let request = signed(&RequestSigner::new("gateway", Some("secret")), path, "{}");
+        assert_eq!(send(to_axum(&request, path, "{}")).await, StatusCode::UNAUTHORIZED);
+
+        let request = signed(&RequestSigner::new("other", Some("guess")), path, "{}");
+        assert_eq!(send(to_axum(&request, path, "{}")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(send(Request::post(path).body(Body::from("{}")).unwrap()).await, StatusCode::UNAUTHORIZED);
+    }
+}
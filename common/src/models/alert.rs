@@ -0,0 +1,281 @@
+//! Query alert models.
+//!
+//! Contains models for alert rules: a saved read-only query evaluated on a
+//! cron schedule, a condition on its result and the channels notified when
+//! the condition starts to hold.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::notification::NotificationTarget;
+use crate::models::query::QueryResult;
+
+/// Value of the query result a condition looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSubject {
+    /// First column of the first row, read as a number.
+    FirstCell,
+    /// Number of rows returned.
+    RowCount,
+}
+
+/// Comparison between the subject and the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertOperator {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Eq,
+    Ne,
+}
+
+impl AlertOperator {
+    /// Returns the operator symbol.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            AlertOperator::Gt => ">",
+            AlertOperator::Gte => ">=",
+            AlertOperator::Lt => "<",
+            AlertOperator::Lte => "<=",
+            AlertOperator::Eq => "==",
+            AlertOperator::Ne => "!=",
+        }
+    }
+}
+
+/// Condition on a query result, e.g. `first_cell > 100` or `row_count == 0`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AlertCondition {
+    /// Value compared.
+    pub subject: AlertSubject,
+    /// Comparison.
+    pub operator: AlertOperator,
+    /// Value compared against.
+    pub threshold: f64,
+}
+
+impl AlertCondition {
+    /// Reads the subject from `result`.
+    ///
+    /// Returns `None` for `first_cell` when there are no rows or the cell is NULL.
+    ///
+    /// # Errors
+    /// Returns `AppError::InvalidInput` when the first cell is not a number.
+    pub fn value(&self, result: &QueryResult) -> AppResult<Option<f64>> {
+        match self.subject {
+            AlertSubject::RowCount => Ok(Some(result.rows.len() as f64)),
+            AlertSubject::FirstCell => match result.rows.first().and_then(|row| row.first()) {
+                None | Some(serde_json::Value::Null) => Ok(None),
+                Some(serde_json::Value::Number(n)) => Ok(n.as_f64()),
+                Some(serde_json::Value::Bool(b)) => Ok(Some(if *b { 1.0 } else { 0.0 })),
+                Some(serde_json::Value::String(s)) => s
+                    .trim()
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| AppError::InvalidInput(format!("first cell `{}` is not a number", s))),
+                Some(other) => Err(AppError::InvalidInput(format!("first cell `{}` is not a number", other))),
+            },
+        }
+    }
+
+    /// Whether `value` satisfies the condition.
+    pub fn is_met(&self, value: f64) -> bool {
+        match self.operator {
+            AlertOperator::Gt => value > self.threshold,
+            AlertOperator::Gte => value >= self.threshold,
+            AlertOperator::Lt => value < self.threshold,
+            AlertOperator::Lte => value <= self.threshold,
+            AlertOperator::Eq => value == self.threshold,
+            AlertOperator::Ne => value != self.threshold,
+        }
+    }
+
+    /// Human-readable form, e.g. `row_count == 0`.
+    pub fn describe(&self) -> String {
+        let subject = match self.subject {
+            AlertSubject::FirstCell => "first_cell",
+            AlertSubject::RowCount => "row_count",
+        };
+        format!("{} {} {}", subject, self.operator.symbol(), self.threshold)
+    }
+}
+
+/// State of an alert rule after its last evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    /// Not evaluated yet, or the condition does not hold.
+    Ok,
+    /// The condition holds.
+    Triggered,
+    /// The query failed or its result could not be read; see `last_error`.
+    Error,
+}
+
+impl AlertState {
+    /// Returns the string stored in the metadata database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertState::Ok => "ok",
+            AlertState::Triggered => "triggered",
+            AlertState::Error => "error",
+        }
+    }
+
+    /// Parses the string stored in the metadata database.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ok" => Some(AlertState::Ok),
+            "triggered" => Some(AlertState::Triggered),
+            "error" => Some(AlertState::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Request body for creating an alert rule.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateAlertRuleRequest {
+    /// Display name.
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+    /// Connection the query runs on.
+    #[validate(length(min = 1, message = "Connection ID is required"))]
+    pub connection_id: String,
+    /// Database on the connection's server to run the query in.
+    #[validate(length(min = 1, message = "Database must not be empty"))]
+    pub database: Option<String>,
+    /// Read-only SQL statement.
+    #[validate(length(min = 1, message = "SQL statement is required"))]
+    pub sql: String,
+    /// Positional bind parameters.
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+    /// Cron expression in UTC, 5 fields (`min hour dom mon dow`) or 6 with seconds.
+    #[validate(length(min = 1, max = 100, message = "Cron expression is required"))]
+    pub cron: String,
+    /// Condition that triggers the alert.
+    pub condition: AlertCondition,
+    /// Channels notified when the alert triggers.
+    #[validate(length(min = 1, max = 10, message = "Between 1 and 10 channels are required"))]
+    pub channels: Vec<NotificationTarget>,
+    /// Whether the rule starts enabled (default: true).
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Alert rule with its latest evaluation.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AlertRule {
+    /// Rule ID.
+    pub id: String,
+    /// Display name.
+    pub name: String,
+    /// Connection ID.
+    pub connection_id: String,
+    /// Database the query runs in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// SQL statement.
+    pub sql: String,
+    /// Positional bind parameters.
+    pub params: Vec<serde_json::Value>,
+    /// Cron expression (UTC).
+    pub cron: String,
+    /// Trigger condition.
+    pub condition: AlertCondition,
+    /// Notification channels.
+    pub channels: Vec<NotificationTarget>,
+    /// Whether the rule is enabled.
+    pub enabled: bool,
+    /// State after the last evaluation.
+    pub state: AlertState,
+    /// Subject value read by the last evaluation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_value: Option<f64>,
+    /// Error of the last evaluation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Last evaluation (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_evaluated_at: Option<String>,
+    /// Last time the rule changed to triggered (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_triggered_at: Option<String>,
+    /// Next planned evaluation (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<String>,
+    /// Creation timestamp.
+    pub created_at: String,
+}
+
+/// Outcome of evaluating an alert rule.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AlertEvaluation {
+    /// Rule ID.
+    pub rule_id: String,
+    /// State after the evaluation.
+    pub state: AlertState,
+    /// Subject value read from the result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    /// Rows returned by the query.
+    pub row_count: u64,
+    /// Channel types notified; only set when the rule changed to triggered.
+    pub notified: Vec<String>,
+    /// Query or notification errors.
+    pub errors: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(rows: Vec<Vec<serde_json::Value>>) -> QueryResult {
+        QueryResult {
+            columns: Vec::new(),
+            row_count: rows.len(),
+            rows,
+            affected_rows: None,
+            execution_time_ms: 0,
+            truncated: false,
+            truncated_cells: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reads_condition_subjects() {
+        let first_cell = AlertCondition { subject: AlertSubject::FirstCell, operator: AlertOperator::Gt, threshold: 100.0 };
+        assert_eq!(first_cell.value(&result(vec![vec![json!(150)]])).unwrap(), Some(150.0));
+        assert_eq!(first_cell.value(&result(vec![vec![json!("99.5")]])).unwrap(), Some(99.5));
+        assert_eq!(first_cell.value(&result(vec![vec![json!(null)]])).unwrap(), None);
+        assert_eq!(first_cell.value(&result(Vec::new())).unwrap(), None);
+        assert!(first_cell.value(&result(vec![vec![json!("n/a")]])).is_err());
+
+        let row_count = AlertCondition { subject: AlertSubject::RowCount, operator: AlertOperator::Eq, threshold: 0.0 };
+        assert_eq!(row_count.value(&result(Vec::new())).unwrap(), Some(0.0));
+        assert_eq!(row_count.describe(), "row_count == 0");
+    }
+
+    #[test]
+    fn compares_values_with_threshold() {
+        let condition = |operator| AlertCondition { subject: AlertSubject::FirstCell, operator, threshold: 10.0 };
+        assert!(condition(AlertOperator::Gt).is_met(11.0));
+        assert!(!condition(AlertOperator::Gt).is_met(10.0));
+        assert!(condition(AlertOperator::Gte).is_met(10.0));
+        assert!(condition(AlertOperator::Lt).is_met(9.0));
+        assert!(condition(AlertOperator::Lte).is_met(10.0));
+        assert!(condition(AlertOperator::Eq).is_met(10.0));
+        assert!(condition(AlertOperator::Ne).is_met(3.0));
+    }
+}
@@ -0,0 +1,66 @@
+//! Query analysis models.
+//!
+//! Contains the index advisor result: problems found in the execution plan and
+//! the indexes suggested to fix them.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::query::QueryResult;
+
+/// Kind of problem found in an execution plan.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanFindingKind {
+    /// Every row of the table is read.
+    FullScan,
+    /// Every entry of an index is read.
+    FullIndexScan,
+    /// Rows are sorted after being read instead of coming from an index.
+    Sort,
+    /// An internal temporary table is created.
+    TemporaryTable,
+}
+
+/// Problem found in an execution plan.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PlanFinding {
+    /// Kind of problem.
+    pub kind: PlanFindingKind,
+    /// Table the problem applies to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    /// Rows the optimizer expects to read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_rows: Option<u64>,
+    /// Explanation.
+    pub detail: String,
+}
+
+/// Suggested index.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IndexSuggestion {
+    /// Table to index.
+    pub table: String,
+    /// Index columns in order: equality predicates, then a range predicate or the sort order.
+    pub columns: Vec<String>,
+    /// `CREATE INDEX` statement.
+    pub statement: String,
+    /// Why the index helps.
+    pub reason: String,
+}
+
+/// Index advisor result for one statement.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IndexAdvice {
+    /// Database type of the connection.
+    pub db_type: String,
+    /// EXPLAIN statement that was run.
+    pub explain_sql: String,
+    /// Raw EXPLAIN output.
+    pub plan: QueryResult,
+    /// Problems found in the plan.
+    pub findings: Vec<PlanFinding>,
+    /// Suggested indexes; empty when none would help.
+    pub suggestions: Vec<IndexSuggestion>,
+}
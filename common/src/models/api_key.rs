@@ -0,0 +1,367 @@
+//! API key models.
+//!
+//! Contains models for gateway API keys and the check of what a key may access.
+//! Guest links are API keys limited to one connection, read-only queries and
+//! optionally a set of schemas, with a short expiry.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::connection::ConnectionAllowlist;
+use crate::utils::sql_lexer::Dialect;
+use crate::utils::{ChangePreviewSql, SqlValidator};
+
+/// Prefix of the principal of requests made with an API key.
+pub const KEY_PRINCIPAL_PREFIX: &str = "key:";
+
+/// Endpoints that only read data even though they are called with POST.
+const READ_POST_PATHS: [&str; 8] = [
+    "/api/query",
+    "/api/query/async",
+    "/api/query/fanout",
+    "/api/query/batch",
+    "/api/query/diff",
+    "/api/snapshots/compare",
+    "/api/databases",
+    "/api/schema/diff",
+];
+
+/// Connection sub-resources that only read data even though they are called with POST.
+const READ_POST_CONNECTION_ACTIONS: [&str; 2] = ["query", "sample"];
+
+/// Endpoints a guest link may call besides the connection's `query` / `sample`.
+const GUEST_PATHS: [&str; 2] = ["/api/query", "/api/query/async"];
+
+/// Request body for issuing an API key.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// Display name.
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+    /// Whether the key may only read (default: true).
+    #[serde(default = "default_read_only")]
+    pub read_only: bool,
+    /// Connections the key may access (empty = all connections).
+    #[serde(default)]
+    pub connection_ids: Vec<String>,
+    /// Days until the key expires (absent = never).
+    #[validate(range(min = 1, max = 3650, message = "Expiry must be 1-3650 days"))]
+    pub expires_in_days: Option<u32>,
+}
+
+fn default_read_only() -> bool {
+    true
+}
+
+/// Request body for creating a guest link to a connection.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateGuestLinkRequest {
+    /// Who the link is for, e.g. the on-call engineer or contractor.
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+    /// MySQL databases / PostgreSQL schemas the guest may query (empty = all).
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    /// Hours until the link expires (default: 24).
+    #[serde(default = "default_guest_expiry_hours")]
+    #[validate(range(min = 1, max = 168, message = "Expiry must be 1-168 hours"))]
+    pub expires_in_hours: u32,
+}
+
+fn default_guest_expiry_hours() -> u32 {
+    24
+}
+
+/// Issued API key (without the secret).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKey {
+    /// Key ID.
+    pub id: String,
+    /// Display name.
+    pub name: String,
+    /// First characters of the key, to recognise it.
+    pub prefix: String,
+    /// Whether the key may only read.
+    pub read_only: bool,
+    /// Connections the key may access (empty = all connections).
+    pub connection_ids: Vec<String>,
+    /// Whether the key is a guest link, limited to query endpoints.
+    #[serde(default)]
+    pub guest: bool,
+    /// Databases / schemas queries may reference (empty = all).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schemas: Vec<String>,
+    /// Namespace unqualified table names resolve to, filled in on verification
+    /// of schema-limited keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_namespace: Option<String>,
+    /// Creation timestamp (UTC).
+    pub created_at: String,
+    /// Expiry timestamp (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    /// Revocation timestamp (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<String>,
+    /// Last successful verification (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<String>,
+}
+
+/// Newly issued API key; the secret is only returned once.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatedApiKey {
+    /// The key to send in the `X-Api-Key` header.
+    pub key: String,
+    /// Key details.
+    pub api_key: ApiKey,
+}
+
+/// Newly created guest link; the key is only returned once.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatedGuestLink {
+    /// The key to send in the `X-Api-Key` header.
+    pub key: String,
+    /// Shareable link carrying the key in its fragment (when `GUEST_LINK_BASE_URL` is set).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Key details.
+    pub api_key: ApiKey,
+}
+
+/// Request body for verifying an API key.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VerifyApiKeyRequest {
+    /// The key sent by the client.
+    pub key: String,
+}
+
+impl ApiKey {
+    /// Principal of requests made with the key, as used by policies and connection ownership.
+    pub fn principal(&self) -> String {
+        format!("{}{}", KEY_PRINCIPAL_PREFIX, self.id)
+    }
+
+    /// Whether the key may access a connection.
+    pub fn allows_connection(&self, connection_id: &str) -> bool {
+        self.connection_ids.is_empty() || self.connection_ids.iter().any(|c| c == connection_id)
+    }
+
+    /// Checks whether the key may make a request.
+    ///
+    /// `body_connection_id` is the `connection_id` field of the request body,
+    /// used when the path does not name the connection.
+    ///
+    /// # Errors
+    /// Returns `AppError::Forbidden` if the request is outside the key's scope.
+    pub fn check_access(&self, method: &str, path: &str, body_connection_id: Option<&str>) -> AppResult<()> {
+        let path_connection_id = path_connection_id(path);
+
+        if self.read_only && !is_read_request(method, path, path_connection_id.is_some()) {
+            return Err(AppError::Forbidden(format!(
+                "API key {} is read-only: {} {} is not allowed",
+                self.prefix, method, path
+            )));
+        }
+        if self.guest && !is_guest_request(method, path) {
+            return Err(AppError::Forbidden(format!(
+                "guest link {} may only run queries: {} {} is not allowed",
+                self.prefix, method, path
+            )));
+        }
+
+        if self.connection_ids.is_empty() {
+            return Ok(());
+        }
+        match path_connection_id.or(body_connection_id) {
+            Some(id) if self.allows_connection(id) => Ok(()),
+            Some(id) => Err(AppError::Forbidden(format!(
+                "API key {} may not access connection {}",
+                self.prefix, id
+            ))),
+            // Job IDs are unguessable and health checks expose nothing.
+            None if path.starts_with("/api/query/jobs/") || path.starts_with("/api/health") => Ok(()),
+            None => Err(AppError::Forbidden(format!(
+                "API key {} is limited to specific connections: {} {} is not allowed",
+                self.prefix, method, path
+            ))),
+        }
+    }
+
+    /// Rejects data and schema changes for read-only keys and guest links.
+    ///
+    /// Uses the classification query-service routes to confirmed execution
+    /// (`UPDATE` / `DELETE` and DDL), so such keys never receive a
+    /// confirmation token for a change.
+    ///
+    /// # Errors
+    /// Returns `AppError::Forbidden` if the key is read-only and `sql` changes data or schema.
+    pub fn check_sql_read_only(&self, sql: &str) -> AppResult<()> {
+        if (self.read_only || self.guest) && (ChangePreviewSql::is_change(sql, Dialect::ANY) || SqlValidator::is_ddl(sql)) {
+            return Err(AppError::Forbidden(format!(
+                "API key {} is read-only: data and schema changes are not allowed",
+                self.prefix
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks the tables referenced by a query against the key's schemas;
+    /// unqualified names resolve to `default_namespace`.
+    ///
+    /// # Errors
+    /// Returns `AppError::Forbidden` if a table is outside the schemas.
+    pub fn check_sql_schemas(&self, sql: &str) -> AppResult<()> {
+        match self.schema_allowlist() {
+            Some(allowlist) => allowlist
+                .check_sql(sql, self.default_namespace.as_deref())
+                .map_err(|_| self.schema_error()),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the database named by a sample or query request (absent = the
+    /// default namespace) against the key's schemas.
+    ///
+    /// # Errors
+    /// Returns `AppError::Forbidden` if the database is outside the schemas.
+    pub fn check_database_schema(&self, database: Option<&str>) -> AppResult<()> {
+        let Some(allowlist) = self.schema_allowlist() else {
+            return Ok(());
+        };
+        match database.or(self.default_namespace.as_deref()) {
+            Some(database) if allowlist.allows_database(database) => Ok(()),
+            _ => Err(self.schema_error()),
+        }
+    }
+
+    fn schema_allowlist(&self) -> Option<ConnectionAllowlist> {
+        (!self.schemas.is_empty()).then(|| ConnectionAllowlist {
+            databases: self.schemas.clone(),
+            tables: vec![],
+        })
+    }
+
+    fn schema_error(&self) -> AppError {
+        AppError::Forbidden(format!(
+            "API key {} is limited to schemas {}",
+            self.prefix,
+            self.schemas.join(", ")
+        ))
+    }
+}
+
+/// Returns the connection ID of a `/api/connections/{id}/...` path.
+pub fn path_connection_id(path: &str) -> Option<&str> {
+    path.strip_prefix("/api/connections/")
+        .and_then(|rest| rest.split('/').next())
+        .filter(|id| !id.is_empty())
+}
+
+pub(crate) fn is_read_request(method: &str, path: &str, is_connection_path: bool) -> bool {
+    match method {
+        "GET" | "HEAD" | "OPTIONS" => true,
+        "POST" => {
+            READ_POST_PATHS.contains(&path)
+                || path.starts_with("/api/ai/")
+                || (is_connection_path
+                    && path
+                        .rsplit('/')
+                        .next()
+                        .is_some_and(|action| READ_POST_CONNECTION_ACTIONS.contains(&action)))
+        }
+        _ => false,
+    }
+}
+
+fn is_guest_request(method: &str, path: &str) -> bool {
+    match method {
+        "GET" => path.starts_with("/api/query/jobs/") || path.starts_with("/api/health"),
+        "POST" => {
+            GUEST_PATHS.contains(&path)
+                || (path_connection_id(path).is_some()
+                    && path
+                        .rsplit('/')
+                        .next()
+                        .is_some_and(|action| READ_POST_CONNECTION_ACTIONS.contains(&action)))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(read_only: bool, connection_ids: &[&str]) -> ApiKey {
+        ApiKey {
+            id: "k1".to_string(),
+            name: "ci".to_string(),
+            prefix: "dbm_12345678".to_string(),
+            read_only,
+            connection_ids: connection_ids.iter().map(|c| c.to_string()).collect(),
+            guest: false,
+            schemas: vec![],
+            default_namespace: None,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            expires_at: None,
+            revoked_at: None,
+            last_used_at: None,
+        }
+    }
+
+    #[test]
+    fn read_only_key_allows_reads_and_queries() {
+        let k = key(true, &[]);
+        assert!(k.check_access("GET", "/api/connections", None).is_ok());
+        assert!(k.check_access("POST", "/api/query", Some("c1")).is_ok());
+        assert!(k.check_access("POST", "/api/connections/c1/query", None).is_ok());
+        assert!(k.check_access("POST", "/api/connections", None).is_err());
+        assert!(k.check_access("DELETE", "/api/connections/c1", None).is_err());
+        assert!(k.check_access("POST", "/api/connections/c1/backups", None).is_err());
+        assert!(key(false, &[]).check_access("DELETE", "/api/connections/c1", None).is_ok());
+    }
+
+    #[test]
+    fn read_only_key_rejects_changes() {
+        let k = key(true, &[]);
+        assert!(k.check_sql_read_only("SELECT * FROM t WHERE note = 'DELETE FROM t'").is_ok());
+        assert!(k.check_sql_read_only("DELETE FROM t").is_err());
+        assert!(k.check_sql_read_only("/* x */ UPDATE t SET a = 1").is_err());
+        assert!(k.check_sql_read_only("DROP TABLE t").is_err());
+        assert!(key(false, &[]).check_sql_read_only("DROP TABLE t").is_ok());
+    }
+
+    #[test]
+    fn connection_scoped_key_checks_path_and_body() {
+        let k = key(false, &["c1"]);
+        assert!(k.check_access("GET", "/api/connections/c1/schema", None).is_ok());
+        assert!(k.check_access("GET", "/api/connections/c2/schema", None).is_err());
+        assert!(k.check_access("POST", "/api/query", Some("c1")).is_ok());
+        assert!(k.check_access("POST", "/api/query", Some("c2")).is_err());
+        assert!(k.check_access("GET", "/api/connections", None).is_err());
+        assert!(k.check_access("GET", "/api/query/jobs/j1", None).is_ok());
+    }
+
+    #[test]
+    fn guest_link_is_limited_to_queries_on_its_schemas() {
+        let k = ApiKey {
+            guest: true,
+            schemas: vec!["sales".to_string()],
+            default_namespace: Some("sales".to_string()),
+            ..key(true, &["c1"])
+        };
+        assert!(k.check_access("POST", "/api/query", Some("c1")).is_ok());
+        assert!(k.check_access("POST", "/api/connections/c1/sample", None).is_ok());
+        assert!(k.check_access("GET", "/api/connections/c1/schema", None).is_err());
+        assert!(k.check_access("POST", "/api/query/preview", Some("c1")).is_err());
+
+        assert!(k.check_sql_schemas("SELECT * FROM orders o JOIN sales.items i ON i.order_id = o.id").is_ok());
+        assert!(k.check_sql_schemas("SELECT * FROM hr.salaries").is_err());
+        assert!(k.check_database_schema(None).is_ok());
+        assert!(k.check_database_schema(Some("hr")).is_err());
+        assert!(key(true, &[]).check_sql_schemas("SELECT * FROM hr.salaries").is_ok());
+    }
+}
@@ -0,0 +1,91 @@
+//! Login models.
+//!
+//! Users log in with a username and password and receive a short-lived
+//! access token (a JWT, see `crate::jwt`) plus a refresh token for their
+//! session. Refreshing rotates the refresh token; logging out revokes the
+//! session, which invalidates both.
+//!
+//! Repeated failed logins for a username or from an address lock further
+//! attempts out for a growing period.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Request body for logging in.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    /// Username.
+    pub username: String,
+    /// Password.
+    pub password: String,
+}
+
+/// Request body for exchanging a refresh token for new tokens.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    /// Refresh token from the last login or refresh.
+    pub refresh_token: String,
+}
+
+/// Tokens issued by a login or refresh.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuthTokens {
+    /// Access token, sent as `Authorization: Bearer <token>`.
+    pub access_token: String,
+    /// Always `Bearer`.
+    pub token_type: String,
+    /// Seconds until the access token expires.
+    pub expires_in: i64,
+    /// Refresh token; valid once, replaced by every refresh.
+    pub refresh_token: String,
+    /// When the session, and with it the refresh token, expires (UTC).
+    pub refresh_expires_at: String,
+    /// Session ID.
+    pub session_id: String,
+    /// Logged-in user.
+    pub username: String,
+}
+
+/// A user who can log in.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct User {
+    /// Username, also the user ID in session tokens (`user:<username>`).
+    pub username: String,
+    /// When the user was created (UTC).
+    pub created_at: String,
+    /// When the password was last set (UTC).
+    pub updated_at: String,
+}
+
+/// Request body for creating a user or resetting their password.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SetPasswordRequest {
+    /// New password.
+    #[validate(length(min = 8, max = 256, message = "Password must be 8-256 characters"))]
+    pub password: String,
+}
+
+/// Failed login attempts tracked for a username or client address.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoginLockout {
+    /// What the attempts are counted for: `user:<username>` or `ip:<address>`.
+    pub key: String,
+    /// Consecutive failed attempts.
+    pub failures: u32,
+    /// Last failed attempt.
+    pub last_failure_at: DateTime<Utc>,
+    /// Until when logins are refused, if locked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// Details of a login refused because of too many failed attempts.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoginLocked {
+    /// Seconds until logins are accepted again.
+    pub retry_after_secs: u64,
+    /// When logins are accepted again.
+    pub locked_until: DateTime<Utc>,
+}
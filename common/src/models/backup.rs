@@ -0,0 +1,186 @@
+//! Backup models.
+//!
+//! Contains models for creating, tracking and restoring database dumps.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::db_error::DbErrorDetails;
+
+/// How a backup is produced.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupMethod {
+    /// DDL + INSERT statements generated by the service from queries.
+    #[default]
+    Logical,
+    /// Invoke the configured native tool (mysqldump / pg_dump).
+    Native,
+}
+
+impl BackupMethod {
+    /// Returns the string stored in the metadata database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupMethod::Logical => "logical",
+            BackupMethod::Native => "native",
+        }
+    }
+}
+
+/// Backup job status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStatus {
+    /// Dump in progress.
+    Running,
+    /// Dump stored successfully.
+    Completed,
+    /// Dump failed; see `error`.
+    Failed,
+}
+
+impl BackupStatus {
+    /// Returns the string stored in the metadata database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupStatus::Running => "running",
+            BackupStatus::Completed => "completed",
+            BackupStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Request body for creating a backup.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateBackupRequest {
+    /// MySQL database or PostgreSQL schema (defaults to the connection database / "public").
+    pub database: Option<String>,
+    /// Tables to dump (default: all tables).
+    #[serde(default)]
+    pub tables: Vec<String>,
+    /// Dump method (default: logical).
+    #[serde(default)]
+    pub method: BackupMethod,
+    /// Include table data, not only the schema (default: true).
+    #[serde(default = "default_include_data")]
+    pub include_data: bool,
+}
+
+fn default_include_data() -> bool {
+    true
+}
+
+/// Backup record tracked in the metadata database.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackupRecord {
+    /// Backup ID.
+    pub id: String,
+    /// Connection ID.
+    pub connection_id: String,
+    /// Dumped database / schema.
+    pub database: String,
+    /// Dump method.
+    pub method: BackupMethod,
+    /// Current status.
+    pub status: BackupStatus,
+    /// Storage backend ("local" or "s3").
+    pub storage: String,
+    /// File path or `s3://bucket/key` of the dump.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// Dump size in bytes.
+    pub size_bytes: u64,
+    /// Number of tables dumped.
+    pub table_count: u32,
+    /// Error message when the backup failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Creation timestamp.
+    pub created_at: String,
+    /// Completion timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+/// Request body for restoring a dump into a connection.
+///
+/// Exactly one of `backup_id` and `sql` must be set.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RestoreRequest {
+    /// ID of a previously created backup.
+    pub backup_id: Option<String>,
+    /// Uploaded SQL script.
+    pub sql: Option<String>,
+    /// Database / schema to restore into (default: the backup's database, or the connection default).
+    pub database: Option<String>,
+    /// Statements executed between progress updates (default: 100).
+    #[validate(range(min = 1, max = 10000, message = "Batch size must be 1-10000"))]
+    pub batch_size: Option<u32>,
+    /// Abort the restore at the first failing statement (default: false).
+    #[serde(default)]
+    pub stop_on_error: bool,
+}
+
+/// Restore job status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreStatus {
+    /// Statements are being executed.
+    Running,
+    /// All statements were executed (some may have failed; see `errors`).
+    Completed,
+    /// Restore aborted; see `error`.
+    Failed,
+    /// Restore cancelled by the user; statements executed before stay applied.
+    Cancelled,
+}
+
+/// Statement that failed during a restore.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RestoreStatementError {
+    /// Zero-based statement index in the script.
+    pub index: usize,
+    /// Statement text (truncated).
+    pub statement: String,
+    /// Database error message.
+    pub error: String,
+    /// Classified error, including the violated constraint, column and value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<DbErrorDetails>,
+}
+
+/// Restore job state.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RestoreJob {
+    /// Job ID.
+    pub id: String,
+    /// Target connection ID.
+    pub connection_id: String,
+    /// Source backup ID (absent for uploaded scripts).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup_id: Option<String>,
+    /// Target database / schema, if one was selected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// Current status.
+    pub status: RestoreStatus,
+    /// Total statements in the script.
+    pub statements_total: usize,
+    /// Statements executed so far (including failed ones).
+    pub statements_executed: usize,
+    /// Statements that failed.
+    pub statements_failed: usize,
+    /// Progress percentage (0-100).
+    pub progress: f64,
+    /// Failed statements (the first 100 are kept).
+    pub errors: Vec<RestoreStatementError>,
+    /// Error that aborted the restore.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Creation timestamp.
+    pub created_at: String,
+    /// Last update timestamp.
+    pub updated_at: String,
+}
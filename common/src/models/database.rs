@@ -78,3 +78,95 @@ pub struct ColumnDetail {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key: Option<String>,
 }
+
+/// Size and statistics of a single table, from the database catalog.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TableStats {
+    /// MySQL database / PostgreSQL schema.
+    pub database: String,
+    /// Table name.
+    pub table: String,
+    /// Estimated row count from the catalog (not an exact `COUNT(*)`).
+    pub estimated_rows: u64,
+    /// Size of the table data in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_bytes: Option<u64>,
+    /// Size of all indexes in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_bytes: Option<u64>,
+    /// Next auto-increment / sequence value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_increment: Option<u64>,
+    /// Last data change (MySQL `UPDATE_TIME`; not tracked by PostgreSQL).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    /// Last (auto) analyze; PostgreSQL only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analyzed_at: Option<String>,
+    /// Indexes with their estimated cardinality.
+    pub indexes: Vec<IndexStats>,
+}
+
+/// Statistics of one index.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IndexStats {
+    /// Index name.
+    pub name: String,
+    /// Indexed columns (or expressions) in order.
+    pub columns: Vec<String>,
+    /// Whether the index enforces uniqueness.
+    pub unique: bool,
+    /// Whether this is the primary key.
+    pub primary: bool,
+    /// Estimated number of distinct keys.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cardinality: Option<u64>,
+}
+
+/// Compact catalog for SQL editor autocompletion.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AutocompleteCatalog {
+    /// Database type the keywords and functions belong to.
+    pub db_type: String,
+    /// Database / schema the tables are listed from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_schema: Option<String>,
+    /// MySQL databases / PostgreSQL schemas.
+    pub schemas: Vec<String>,
+    /// Tables of the default database / schema.
+    pub tables: Vec<AutocompleteTable>,
+    /// Reserved words of the dialect.
+    pub keywords: Vec<String>,
+    /// Built-in functions of the dialect.
+    pub functions: Vec<String>,
+    /// When the catalog was built (RFC 3339).
+    pub generated_at: String,
+}
+
+/// Table entry of an [`AutocompleteCatalog`].
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AutocompleteTable {
+    /// Table name.
+    pub name: String,
+    /// Columns in ordinal order.
+    pub columns: Vec<AutocompleteColumn>,
+}
+
+/// Column entry of an [`AutocompleteTable`].
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AutocompleteColumn {
+    /// Column name.
+    pub name: String,
+    /// Data type.
+    #[serde(rename = "type")]
+    pub data_type: String,
+}
+
+/// Node label or relationship type of a graph database, with its element count.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GraphElementType {
+    /// Label / relationship type name.
+    pub name: String,
+    /// Number of nodes with the label / relationships of the type.
+    pub count: u64,
+}
@@ -0,0 +1,77 @@
+//! Connection test models.
+//!
+//! A connection test runs its stages in order and reports each one, so a
+//! failed test shows where it failed; see `connection-service`'s
+//! `diagnostics` module for what the stages check.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Diagnostic stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStage {
+    Dns,
+    TcpConnect,
+    Tls,
+    Authentication,
+    Query,
+}
+
+/// Outcome of a stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StageStatus {
+    Ok,
+    Failed,
+    Skipped,
+}
+
+/// Result of one diagnostic stage.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StageResult {
+    pub stage: TestStage,
+    pub status: StageStatus,
+    /// Time spent in the stage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// What the stage found, e.g. resolved addresses or the server version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of a connection test.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConnectionTestResult {
+    /// Connection ID (absent when testing an unsaved configuration).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    pub success: bool,
+    /// Latency of the query stage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Error of the first failed stage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Results of the stages.
+    pub stages: Vec<StageResult>,
+}
+
+impl ConnectionTestResult {
+    /// Summarizes the stage results of a test.
+    pub fn from_stages(id: String, stages: Vec<StageResult>) -> Self {
+        let failed = stages.iter().find(|s| s.status == StageStatus::Failed);
+        Self {
+            id,
+            success: failed.is_none(),
+            latency_ms: stages
+                .iter()
+                .find(|s| s.stage == TestStage::Query && s.status == StageStatus::Ok)
+                .and_then(|s| s.latency_ms),
+            error: failed.and_then(|s| s.error.clone()),
+            stages,
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Generic job models.
+//!
+//! Every long-running operation (async query, backup, restore / import, data
+//! transfer) is also tracked as a [`Job`] with a common lifecycle, so jobs of
+//! all kinds can be listed and cancelled the same way. The operation-specific
+//! models ([`super::TransferJob`], [`super::RestoreJob`], ...) keep the details.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Kind of work a job performs.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Async query (query-service).
+    Query,
+    /// Database backup.
+    Backup,
+    /// Restore of a backup or uploaded SQL script.
+    Restore,
+    /// Row copy between connections.
+    Transfer,
+}
+
+impl JobKind {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Query => "query",
+            JobKind::Backup => "backup",
+            JobKind::Restore => "restore",
+            JobKind::Transfer => "transfer",
+        }
+    }
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for JobKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "query" => Ok(JobKind::Query),
+            "backup" => Ok(JobKind::Backup),
+            "restore" => Ok(JobKind::Restore),
+            "transfer" => Ok(JobKind::Transfer),
+            _ => Err(format!("unknown job kind: {}", s)),
+        }
+    }
+}
+
+/// Job lifecycle status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Work is in progress.
+    Running,
+    /// Work finished successfully.
+    Completed,
+    /// Work aborted; see `error`.
+    Failed,
+    /// Cancelled on request.
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Returns whether the job has reached a terminal state.
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, JobStatus::Running)
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "running" => Ok(JobStatus::Running),
+            "completed" => Ok(JobStatus::Completed),
+            "failed" => Ok(JobStatus::Failed),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            _ => Err(format!("unknown job status: {}", s)),
+        }
+    }
+}
+
+/// Common state of a long-running job.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Job {
+    /// Job ID, shared with the operation-specific job (e.g. the transfer or backup ID).
+    pub id: String,
+    /// Kind of work.
+    pub kind: JobKind,
+    /// Current status.
+    pub status: JobStatus,
+    /// Completion percentage (0-100).
+    pub progress: f64,
+    /// Where the outcome can be found, e.g. the backup location or the restored connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_ref: Option<String>,
+    /// Error message when the job failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Creation timestamp.
+    pub created_at: String,
+    /// Last change timestamp.
+    pub updated_at: String,
+    /// Completion timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+impl Job {
+    /// Creates a running job.
+    pub fn new(id: impl Into<String>, kind: JobKind) -> Self {
+        let now = chrono::Utc::now().to_rfc3339();
+        Self {
+            id: id.into(),
+            kind,
+            status: JobStatus::Running,
+            progress: 0.0,
+            result_ref: None,
+            error: None,
+            created_at: now.clone(),
+            updated_at: now,
+            finished_at: None,
+        }
+    }
+
+    /// Sets the progress; returns whether it moved to another whole percent,
+    /// so callers can persist at most about a hundred updates per job.
+    pub fn set_progress(&mut self, progress: f64) -> bool {
+        let progress = progress.clamp(0.0, 100.0);
+        let moved = progress.floor() != self.progress.floor();
+        self.progress = progress;
+        self.touch();
+        moved
+    }
+
+    /// Marks the job as completed.
+    pub fn complete(&mut self, result_ref: Option<String>) {
+        self.progress = 100.0;
+        self.result_ref = result_ref;
+        self.finish(JobStatus::Completed);
+    }
+
+    /// Marks the job as failed.
+    pub fn fail(&mut self, error: impl Into<String>) {
+        self.error = Some(error.into());
+        self.finish(JobStatus::Failed);
+    }
+
+    /// Marks the job as cancelled.
+    pub fn cancel(&mut self) {
+        self.finish(JobStatus::Cancelled);
+    }
+
+    fn finish(&mut self, status: JobStatus) {
+        self.status = status;
+        self.touch();
+        self.finished_at = Some(self.updated_at.clone());
+    }
+
+    fn touch(&mut self) {
+        self.updated_at = chrono::Utc::now().to_rfc3339();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_the_job_lifecycle() {
+        let mut job = Job::new("j1", JobKind::Transfer);
+        assert!(!job.status.is_finished());
+        assert!(!job.set_progress(0.4));
+        assert!(job.set_progress(1.2));
+        assert!(!job.set_progress(1.9));
+        assert!(job.set_progress(250.0));
+        assert_eq!(job.progress, 100.0);
+
+        job.fail("boom");
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.error.as_deref(), Some("boom"));
+        assert!(job.finished_at.is_some());
+        assert_eq!("transfer".parse::<JobKind>(), Ok(JobKind::Transfer));
+        assert_eq!("cancelled".parse::<JobStatus>(), Ok(JobStatus::Cancelled));
+    }
+}
@@ -0,0 +1,53 @@
+//! Key-value browser models.
+//!
+//! Contains models for reading and writing single keys of key-value stores
+//! (Memcached).
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// How a value is represented in JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueEncoding {
+    /// UTF-8 text.
+    #[default]
+    Text,
+    /// Base64 encoded bytes.
+    Base64,
+}
+
+/// Value stored under a key.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct KeyValueEntry {
+    /// Key.
+    pub key: String,
+    /// Value; base64 encoded when it is not valid UTF-8.
+    pub value: String,
+    /// Encoding of `value`.
+    pub encoding: ValueEncoding,
+    /// Size of the value in bytes.
+    pub size_bytes: u64,
+    /// Opaque client flags stored with the value.
+    pub flags: u32,
+    /// Compare-and-swap token of the current value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cas: Option<u64>,
+}
+
+/// Request body for storing a value under a key.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SetKeyValueRequest {
+    /// Value to store.
+    pub value: String,
+    /// Encoding of `value` (default: `text`).
+    #[serde(default)]
+    pub encoding: ValueEncoding,
+    /// Opaque client flags stored with the value (default: 0).
+    #[serde(default)]
+    pub flags: u32,
+    /// Seconds until the key expires (default: never).
+    #[validate(range(min = 1, max = 2592000, message = "ttl_secs must be 1-2592000"))]
+    pub ttl_secs: Option<u32>,
+}
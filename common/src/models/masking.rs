@@ -0,0 +1,229 @@
+//! Result masking models.
+//!
+//! Contains the per-connection rules that mask sensitive columns in query
+//! results for principals that are not exempt.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::query::{QueryResult, ValueKind};
+
+/// Replacement returned by the `redact` strategy.
+const REDACTED: &str = "***";
+/// Hex digits kept from the SHA-256 digest of a hashed value.
+const HASH_LEN: usize = 16;
+
+/// How the values of a matching column are masked. NULL stays NULL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MaskingStrategy {
+    /// First 16 hex digits of the SHA-256 of the value; equal values stay
+    /// equal, so masked columns can still be grouped and compared.
+    Hash,
+    /// Keeps a few characters: the domain and first character of an email,
+    /// the last 4 characters of values of 8+ characters, otherwise the
+    /// first and last character.
+    Partial,
+    /// Replaces the value with `***`.
+    Redact,
+}
+
+/// Masks the columns whose name matches `pattern`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MaskingRule {
+    /// Column name pattern, case-insensitive; `*` matches any characters,
+    /// e.g. `*password*`, `email`, `card_*`.
+    pub pattern: String,
+    /// How matching values are masked.
+    pub strategy: MaskingStrategy,
+}
+
+/// Masking rules of a connection, applied to query results before they are
+/// returned. The first matching rule of a column wins.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ConnectionMasking {
+    /// Rules in priority order.
+    #[serde(default)]
+    pub rules: Vec<MaskingRule>,
+    /// Principals that see unmasked results, e.g. `key:<api key id>`.
+    #[serde(default)]
+    pub exempt_principals: Vec<String>,
+}
+
+impl ConnectionMasking {
+    /// Whether the masking has no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Checks that every rule has a pattern.
+    ///
+    /// # Errors
+    /// Returns `AppError::InvalidInput` for an empty pattern.
+    pub fn validate_rules(&self) -> AppResult<()> {
+        if self.rules.iter().any(|r| r.pattern.trim().is_empty()) {
+            return Err(AppError::InvalidInput("masking rule pattern must not be empty".into()));
+        }
+        Ok(())
+    }
+
+    /// Whether results are masked for `principal`; requests without a
+    /// principal are always masked.
+    pub fn applies_to(&self, principal: Option<&str>) -> bool {
+        !self.is_empty()
+            && !principal.is_some_and(|p| self.exempt_principals.iter().any(|e| e == p))
+    }
+
+    /// Strategy of the first rule matching `column`.
+    pub fn strategy_for(&self, column: &str) -> Option<MaskingStrategy> {
+        self.rules
+            .iter()
+            .find(|r| glob_match(&r.pattern.trim().to_lowercase(), &column.to_lowercase()))
+            .map(|r| r.strategy)
+    }
+
+    /// Masks the matching columns of `result` in place; masked columns become text.
+    /// Returns the names of the masked columns.
+    pub fn apply(&self, result: &mut QueryResult) -> Vec<String> {
+        let masked: Vec<(usize, MaskingStrategy)> = result
+            .columns
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| self.strategy_for(&c.name).map(|s| (i, s)))
+            .collect();
+        for (index, _) in &masked {
+            result.columns[*index].kind = Some(ValueKind::Text);
+        }
+        for row in &mut result.rows {
+            for (index, strategy) in &masked {
+                if let Some(value) = row.get_mut(*index) {
+                    *value = mask_value(value, *strategy);
+                }
+            }
+        }
+        masked.into_iter().map(|(i, _)| result.columns[i].name.clone()).collect()
+    }
+}
+
+/// Masks one value with `strategy`.
+pub fn mask_value(value: &serde_json::Value, strategy: MaskingStrategy) -> serde_json::Value {
+    let text = match value {
+        serde_json::Value::Null => return serde_json::Value::Null,
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let masked = match strategy {
+        MaskingStrategy::Hash => hex::encode(Sha256::digest(text.as_bytes()))[..HASH_LEN].to_string(),
+        MaskingStrategy::Partial if value.is_array() || value.is_object() => REDACTED.to_string(),
+        MaskingStrategy::Partial => match text.rsplit_once('@') {
+            Some((local, domain)) if !local.is_empty() && !domain.is_empty() => {
+                format!("{}@{}", partial(local), domain)
+            }
+            _ => partial(&text),
+        },
+        MaskingStrategy::Redact => REDACTED.to_string(),
+    };
+    serde_json::Value::String(masked)
+}
+
+fn partial(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let n = chars.len();
+    match n {
+        0..=2 => "*".repeat(n.max(1)),
+        3..=7 => format!("{}{}{}", chars[0], "*".repeat(n - 2), chars[n - 1]),
+        _ => format!("{}{}", "*".repeat(n - 4), chars[n - 4..].iter().collect::<String>()),
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` matches any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || text.len() < first.len() + last.len() || !text.ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::query::ColumnInfo;
+    use serde_json::json;
+
+    fn masking() -> ConnectionMasking {
+        ConnectionMasking {
+            rules: vec![
+                MaskingRule { pattern: "*password*".into(), strategy: MaskingStrategy::Redact },
+                MaskingRule { pattern: "*EMAIL*".into(), strategy: MaskingStrategy::Partial },
+                MaskingRule { pattern: "phone".into(), strategy: MaskingStrategy::Partial },
+                MaskingRule { pattern: "ssn".into(), strategy: MaskingStrategy::Hash },
+            ],
+            exempt_principals: vec!["key:admin".into()],
+        }
+    }
+
+    #[test]
+    fn matches_column_patterns() {
+        let masking = masking();
+        assert_eq!(masking.strategy_for("password_hash"), Some(MaskingStrategy::Redact));
+        assert_eq!(masking.strategy_for("User_Email"), Some(MaskingStrategy::Partial));
+        assert_eq!(masking.strategy_for("phone"), Some(MaskingStrategy::Partial));
+        assert_eq!(masking.strategy_for("phone_type"), None);
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("a*b*c", "acb"));
+        assert!(!glob_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn masks_result_columns_unless_exempt() {
+        let masking = masking();
+        assert!(masking.applies_to(None));
+        assert!(masking.applies_to(Some("key:other")));
+        assert!(!masking.applies_to(Some("key:admin")));
+
+        let column = |name: &str, kind| ColumnInfo { name: name.into(), data_type: "varchar".into(), nullable: None, kind };
+        let mut result = QueryResult {
+            columns: vec![
+                column("id", Some(ValueKind::Integer)),
+                column("email", Some(ValueKind::Text)),
+                column("phone", Some(ValueKind::Text)),
+                column("ssn", Some(ValueKind::Integer)),
+                column("password", Some(ValueKind::Text)),
+            ],
+            rows: vec![
+                vec![json!(1), json!("alice@example.com"), json!("13812345678"), json!(123456789), json!("secret")],
+                vec![json!(2), json!(null), json!("abc"), json!(123456789), json!("x")],
+            ],
+            row_count: 2,
+            affected_rows: None,
+            execution_time_ms: 0,
+            truncated: false,
+            truncated_cells: Vec::new(),
+        };
+        let masked = masking.apply(&mut result);
+        assert_eq!(masked, ["email", "phone", "ssn", "password"]);
+        assert_eq!(result.rows[0][0], json!(1));
+        assert_eq!(result.rows[0][1], json!("a***e@example.com"));
+        assert_eq!(result.rows[0][2], json!("*******5678"));
+        assert_eq!(result.rows[0][4], json!("***"));
+        assert_eq!(result.rows[1][1], json!(null));
+        assert_eq!(result.rows[1][2], json!("a*c"));
+        assert_eq!(result.rows[0][3], result.rows[1][3]);
+        assert_eq!(result.rows[0][3].as_str().map(str::len), Some(HASH_LEN));
+        assert_eq!(result.columns[3].kind, Some(ValueKind::Text));
+    }
+}
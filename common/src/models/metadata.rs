@@ -0,0 +1,212 @@
+//! Metadata archive and migration models.
+//!
+//! Contains the versioned archive used to export the management metadata of one
+//! deployment and import it into another, and the state of the metadata schema
+//! migrations.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::connection::{ConnectionAllowlist, ConnectionConfig, ConnectionPoolOptions, DbType, StatementPolicy};
+use super::masking::ConnectionMasking;
+use super::scheduler::ScheduledJob;
+
+/// Current archive format version.
+pub const METADATA_ARCHIVE_VERSION: u32 = 1;
+
+/// Export of the management metadata.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MetadataArchive {
+    /// Archive format version.
+    pub version: u32,
+    /// Export timestamp (UTC, RFC 3339).
+    pub exported_at: String,
+    /// Whether connection passwords are included.
+    #[serde(default)]
+    pub includes_secrets: bool,
+    /// Saved connections.
+    #[serde(default)]
+    pub connections: Vec<ArchivedConnection>,
+    /// Scheduled jobs.
+    #[serde(default)]
+    pub scheduled_jobs: Vec<ScheduledJob>,
+}
+
+/// Connection as stored in an archive (password only with secrets included).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ArchivedConnection {
+    /// Connection ID.
+    pub id: String,
+    /// Display name.
+    pub name: String,
+    /// Database type.
+    pub db_type: DbType,
+    /// Database host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// Database port.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Database username.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Database password.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// External secret holding the password (always archived, it holds no secret).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_ref: Option<String>,
+    /// Default database name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// SQLite file path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    /// InfluxDB 2 organization.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org: Option<String>,
+    /// Databases / tables visible through the service.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowlist: Option<ConnectionAllowlist>,
+    /// Column masking applied to query results.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub masking: Option<ConnectionMasking>,
+    /// Statement types that may be executed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement_policy: Option<StatementPolicy>,
+    /// Default query timeout in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_timeout_ms: Option<u64>,
+    /// Whether the connection is warmed at service start.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Pool sizing overrides.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_options: Option<ConnectionPoolOptions>,
+    /// Principal that owns the connection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_id: Option<String>,
+    /// Creation timestamp in the source deployment.
+    pub created_at: String,
+}
+
+impl ArchivedConnection {
+    /// Archives a connection, dropping the password unless `include_secrets` is set.
+    pub fn from_config(config: ConnectionConfig, include_secrets: bool) -> Self {
+        Self {
+            id: config.id,
+            name: config.name,
+            db_type: config.db_type,
+            host: config.host,
+            port: config.port,
+            username: config.username,
+            password: config.password.filter(|_| include_secrets),
+            password_ref: config.password_ref,
+            database: config.database,
+            file_path: config.file_path,
+            org: config.org,
+            allowlist: config.allowlist,
+            masking: config.masking,
+            statement_policy: config.statement_policy,
+            query_timeout_ms: config.query_timeout_ms,
+            pinned: config.pinned,
+            pool_options: config.pool_options,
+            owner_id: config.owner_id,
+            created_at: config.created_at,
+        }
+    }
+}
+
+impl From<ArchivedConnection> for ConnectionConfig {
+    fn from(archived: ArchivedConnection) -> Self {
+        Self {
+            id: archived.id,
+            name: archived.name,
+            db_type: archived.db_type,
+            host: archived.host,
+            port: archived.port,
+            username: archived.username,
+            password: archived.password,
+            password_ref: archived.password_ref,
+            database: archived.database,
+            file_path: archived.file_path,
+            org: archived.org,
+            allowlist: archived.allowlist,
+            masking: archived.masking,
+            statement_policy: archived.statement_policy,
+            query_timeout_ms: archived.query_timeout_ms,
+            pinned: archived.pinned,
+            pool_options: archived.pool_options,
+            owner_id: archived.owner_id,
+            created_at: archived.created_at,
+        }
+    }
+}
+
+/// What to do with archive entries whose ID already exists.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportConflictPolicy {
+    /// Keep the existing entry.
+    #[default]
+    Skip,
+    /// Replace the existing entry.
+    Overwrite,
+}
+
+/// Result of a metadata import.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct MetadataImportReport {
+    /// Connections created or replaced.
+    pub connections_imported: usize,
+    /// Connections left unchanged because they already existed.
+    pub connections_skipped: usize,
+    /// Scheduled jobs created or replaced.
+    pub scheduled_jobs_imported: usize,
+    /// Scheduled jobs skipped (already existing or invalid).
+    pub scheduled_jobs_skipped: usize,
+    /// Entries that need attention (missing passwords, invalid jobs, ...).
+    pub warnings: Vec<String>,
+}
+
+/// State of a metadata schema migration.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    /// Applied successfully.
+    Applied,
+    /// Not applied yet.
+    Pending,
+    /// Started but did not complete; the service does not start until it is fixed.
+    Failed,
+}
+
+/// Metadata schema migration embedded in the service.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MigrationStatus {
+    /// Migration version.
+    pub version: i64,
+    /// Description taken from the script name.
+    pub description: String,
+    /// Whether the migration has been applied.
+    pub state: MigrationState,
+    /// When the migration was applied (UTC).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_on: Option<String>,
+    /// Time taken to apply the migration, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_time_ms: Option<u64>,
+}
+
+/// Migration state of the metadata database.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MigrationReport {
+    /// Metadata database backend (`mysql`, `postgres` or `sqlite`).
+    pub backend: String,
+    /// Latest applied version, if any.
+    pub current_version: Option<i64>,
+    /// Number of migrations not applied yet.
+    pub pending: usize,
+    /// Embedded migrations, oldest first.
+    pub migrations: Vec<MigrationStatus>,
+}
@@ -1,12 +1,99 @@
 //! Shared data models for all microservices.
 
+pub mod alert;
+pub mod analysis;
+pub mod api_key;
+pub mod auth;
+pub mod backup;
 pub mod connection;
+pub mod masking;
 pub mod database;
+pub mod diagnostics;
+pub mod job;
+pub mod key_value;
+pub mod metadata;
 pub mod monitor;
+pub mod notification;
+pub mod policy;
 pub mod query;
+pub mod scheduler;
+pub mod schema_change;
+pub mod schema_diff;
+pub mod schema_graph;
+pub mod seed;
+pub mod session;
+pub mod snapshot;
+pub mod transfer;
+pub mod usage;
+pub mod workload;
+pub mod workspace;
 
 // Re-export commonly used types
-pub use connection::{ConnectionConfig, ConnectionItem, CreateConnectionRequest, DbType};
-pub use database::{ColumnDetail, DatabaseItem, ListDatabasesRequest, TableInfo, TableSchema};
-pub use monitor::{ConnectionPoolStats, DatabaseInfo, DatabaseStats, MonitorOverview, ProcessInfo};
-pub use query::{ColumnInfo, QueryRequest, QueryResult};
+pub use alert::{
+    AlertCondition, AlertEvaluation, AlertOperator, AlertRule, AlertState, AlertSubject,
+    CreateAlertRuleRequest,
+};
+pub use analysis::{IndexAdvice, IndexSuggestion, PlanFinding, PlanFindingKind};
+pub use api_key::{
+    ApiKey, CreateApiKeyRequest, CreateGuestLinkRequest, CreatedApiKey, CreatedGuestLink,
+    VerifyApiKeyRequest,
+};
+pub use auth::{
+    AuthTokens, LoginLocked, LoginLockout, LoginRequest, RefreshRequest, SetPasswordRequest, User,
+};
+pub use backup::{
+    BackupMethod, BackupRecord, BackupStatus, CreateBackupRequest, RestoreJob, RestoreRequest,
+    RestoreStatementError, RestoreStatus,
+};
+pub use connection::{
+    ConnectionAllowlist, ConnectionConfig, ConnectionItem, ConnectionPoolOptions, ConnectionRevision, ConnectionView, CreateConnectionRequest, DbType,
+    DialectFeatures, FieldChange, PinnedSettings, QueryTimeoutSettings, RevisionAction, RotatePasswordRequest, StatementPolicy,
+};
+pub use database::{
+    AutocompleteCatalog, AutocompleteColumn, AutocompleteTable, ColumnDetail, DatabaseItem,
+    GraphElementType, IndexStats, ListDatabasesRequest, TableInfo, TableSchema, TableStats,
+};
+pub use diagnostics::{ConnectionTestResult, StageResult, StageStatus, TestStage};
+pub use job::{Job, JobKind, JobStatus};
+pub use key_value::{KeyValueEntry, SetKeyValueRequest, ValueEncoding};
+pub use masking::{ConnectionMasking, MaskingRule, MaskingStrategy};
+pub use metadata::{
+    ArchivedConnection, ImportConflictPolicy, MetadataArchive, MetadataImportReport,
+    MigrationReport, MigrationState, MigrationStatus, METADATA_ARCHIVE_VERSION,
+};
+pub use monitor::{
+    CachedPool, ConnectionPoolStats, DatabaseInfo, DatabaseStats, MonitorOverview, PoolState, PoolStatus, ProcessInfo,
+    TargetHealth, TargetHealthStatus, WarmupEntry, WarmupState, WarmupStatus,
+};
+pub use notification::NotificationTarget;
+pub use policy::{
+    AuthzDecision, AuthzRequest, Policy, PolicyDecisionLog, PolicyEffect, PolicyRequest,
+};
+pub use query::{
+    BatchQueryEntry, BatchQueryRequest, BatchQueryResult, ChangePreview, ChangedRow, ColumnInfo, ConfirmationRequired, DangerousStatementKind, FanOutEntry,
+    FanOutQueryRequest, FanOutResult, FormatSqlRequest, FormattedSql, QueryDiffRequest, QueryDiffResult, QueryDiffSide, QueryJob, QueryJobStatus, QueryLanguage, QueryRequest, QueryResult, SampleMethod, SampleRequest,
+    SampleResult, TruncatedCell, ValueKind,
+};
+pub use scheduler::{
+    CreateScheduledJobRequest, JobRun, JobRunStatus, ScheduledJob, ScheduledTaskKind,
+};
+pub use schema_change::{
+    SchemaChangeJob, SchemaChangeRequest, SchemaChangeStatus, SchemaChangeStrategy,
+};
+pub use schema_diff::{
+    ColumnChange, ColumnDef, ForeignKeyDef, IndexDef, SchemaDiff, SchemaDiffRequest, SchemaRef,
+    TableDef, TableDiff,
+};
+pub use schema_graph::{GraphColumn, GraphEdge, GraphNode, SchemaGraph};
+pub use seed::{SeedColumn, SeedRequest, SeedResult};
+pub use session::{CreateSsoSessionRequest, RevokedSessions, Session, VerifySessionRequest};
+pub use snapshot::{CompareSnapshotsRequest, CreateSnapshotRequest, QuerySnapshot};
+pub use transfer::{TransferJob, TransferRequest, TransferStatus};
+pub use usage::{QuotaExceeded, UsageCounters, UsageQuota, UsageReport, UserUsage};
+pub use workload::{
+    StatementType, StatementTypeStats, TableWorkloadStats, WorkloadBreakdown, WorkloadBucket,
+};
+pub use workspace::{
+    AddWorkspaceMemberRequest, CreateWorkspaceRequest, UpdateWorkspaceRequest, Workspace, WorkspaceDetail,
+    WorkspaceMember, WorkspaceResourceKind, WorkspaceRole,
+};
@@ -97,6 +97,54 @@ pub struct ConnectionPoolStats {
     pub max_size: u32,
     /// Whether the pool is connected.
     pub is_connected: bool,
+    /// Self-healing state (absent until the pool was first opened).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<PoolStatus>,
+}
+
+/// A connection pool held by connection-service, with its usage and state.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CachedPool {
+    /// Connection ID.
+    pub connection_id: String,
+    /// Connection name.
+    pub connection_name: String,
+    /// Database type.
+    pub db_type: String,
+    /// Usage and self-healing state of the connection's pool.
+    pub stats: ConnectionPoolStats,
+    /// Databases with a separate pool on the connection's server.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub databases: Vec<String>,
+}
+
+/// Self-healing state of a connection pool.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolState {
+    /// The pool answers health probes.
+    Connected,
+    /// The pool was lost; reconnect attempts back off exponentially.
+    Reconnecting,
+    /// Reconnecting failed repeatedly; attempts continue at the longest interval.
+    Failed,
+}
+
+/// Pool state with its reconnect bookkeeping.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PoolStatus {
+    /// Current state.
+    pub state: PoolState,
+    /// Consecutive failed probes and reconnect attempts.
+    pub failures: u32,
+    /// Error of the last failed probe or attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Time of the next reconnect attempt (UTC, RFC 3339).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_attempt_at: Option<String>,
+    /// When the pool entered the current state (UTC, RFC 3339).
+    pub since: String,
 }
 
 /// Aggregated monitoring overview for a single connection.
@@ -115,3 +163,108 @@ pub struct MonitorOverview {
     /// Timestamp of this snapshot.
     pub timestamp: String,
 }
+
+/// Health classification of a target database.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetHealthStatus {
+    /// No problem detected.
+    Healthy,
+    /// Overloaded or replication broken / lagging; heavy queries should be avoided.
+    Degraded,
+    /// The checks could not run.
+    Unknown,
+}
+
+impl TargetHealthStatus {
+    /// Returns the serialized status name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TargetHealthStatus::Healthy => "healthy",
+            TargetHealthStatus::Degraded => "degraded",
+            TargetHealthStatus::Unknown => "unknown",
+        }
+    }
+}
+
+/// Health of a target database as seen by the monitoring checks.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TargetHealth {
+    /// Connection ID.
+    pub connection_id: String,
+    /// Overall status.
+    pub status: TargetHealthStatus,
+    /// Why the target is degraded or unknown.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+    /// Server connections in use as a fraction of the maximum.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_usage: Option<f64>,
+    /// Whether replication threads / the WAL receiver run (replicas only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replication_running: Option<bool>,
+    /// Replication lag in seconds (replicas only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replication_lag_secs: Option<u64>,
+    /// Check timestamp (UTC, RFC 3339).
+    pub checked_at: String,
+}
+
+impl TargetHealth {
+    /// Whether the target is marked degraded.
+    pub fn is_degraded(&self) -> bool {
+        self.status == TargetHealthStatus::Degraded
+    }
+}
+
+/// Progress of warming a pinned connection at service start.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmupState {
+    /// Not started yet.
+    Pending,
+    /// Opening the pool and loading schema metadata.
+    Warming,
+    /// Pool open and schema metadata loaded.
+    Ready,
+    /// The pool or the schema metadata could not be loaded.
+    Failed,
+}
+
+/// Warm-up result of one pinned connection.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WarmupEntry {
+    /// Connection ID.
+    pub connection_id: String,
+    /// Connection display name.
+    pub name: String,
+    /// Current state.
+    pub state: WarmupState,
+    /// Number of tables loaded into the schema cache.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tables: Option<usize>,
+    /// Time spent warming in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Why warming failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Startup warm-up progress, reported by the readiness endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WarmupStatus {
+    /// Whether every pinned connection has been warmed (or has failed).
+    pub ready: bool,
+    /// Pinned connections warmed so far, failed ones included.
+    pub completed: usize,
+    /// Number of pinned connections.
+    pub total: usize,
+    /// Warm-up start (UTC, RFC 3339).
+    pub started_at: String,
+    /// Warm-up end (UTC, RFC 3339).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+    /// Per-connection progress.
+    pub connections: Vec<WarmupEntry>,
+}
@@ -0,0 +1,30 @@
+//! Notification models.
+//!
+//! Contains the destinations notifications (alerts, backup and health
+//! events) are delivered to.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Where a notification is sent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationTarget {
+    /// POSTs the notification as JSON to `url`.
+    Webhook { url: String },
+    /// Posts a message to a Slack incoming webhook.
+    Slack { webhook_url: String },
+    /// Sends an email through the configured SMTP relay.
+    Email { to: Vec<String> },
+}
+
+impl NotificationTarget {
+    /// Returns the channel type name.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NotificationTarget::Webhook { .. } => "webhook",
+            NotificationTarget::Slack { .. } => "slack",
+            NotificationTarget::Email { .. } => "email",
+        }
+    }
+}
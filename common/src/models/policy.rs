@@ -0,0 +1,255 @@
+//! Authorization policy models.
+//!
+//! Policies are declarative allow/deny rules over principals (who), actions
+//! (what) and resources (which connection). Patterns are exact values, `*`,
+//! or a prefix ending in `*` (e.g. `key:*`, `prod-*`). Principal patterns
+//! `role:<name>` match users holding that role (see `crate::jwt`).
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::models::api_key::{is_read_request, path_connection_id};
+
+/// Action of requests that only read data.
+pub const ACTION_READ: &str = "read";
+/// Action of requests that change data or configuration.
+pub const ACTION_WRITE: &str = "write";
+/// Action of admin endpoints.
+pub const ACTION_ADMIN: &str = "admin";
+
+/// Principal of requests without credentials.
+pub const ANONYMOUS_PRINCIPAL: &str = "anonymous";
+
+/// Principal prefix matching the roles of a user, e.g. `role:admin`.
+pub const ROLE_PRINCIPAL_PREFIX: &str = "role:";
+
+/// Effect of a matching policy.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyEffect {
+    Allow,
+    Deny,
+}
+
+impl PolicyEffect {
+    /// Stored name of the effect.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyEffect::Allow => "allow",
+            PolicyEffect::Deny => "deny",
+        }
+    }
+}
+
+/// Request body for creating or replacing a policy.
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+pub struct PolicyRequest {
+    /// Display name.
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+    /// Description.
+    pub description: Option<String>,
+    /// Effect when the policy matches.
+    pub effect: PolicyEffect,
+    /// Principal patterns, e.g. `key:<id>`, `user:<name>`, `role:<name>`, `anonymous`, `*`.
+    #[validate(length(min = 1, message = "At least one principal is required"))]
+    pub principals: Vec<String>,
+    /// Action patterns: `read`, `write`, `admin` or `*`.
+    #[validate(length(min = 1, message = "At least one action is required"))]
+    pub actions: Vec<String>,
+    /// Connection ID patterns; `*` also matches requests without a connection.
+    #[validate(length(min = 1, message = "At least one resource is required"))]
+    pub resources: Vec<String>,
+    /// Whether the policy is evaluated (default: true).
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Stored authorization policy.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Policy {
+    /// Policy ID.
+    pub id: String,
+    /// Display name.
+    pub name: String,
+    /// Description.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Effect when the policy matches.
+    pub effect: PolicyEffect,
+    /// Principal patterns.
+    pub principals: Vec<String>,
+    /// Action patterns.
+    pub actions: Vec<String>,
+    /// Connection ID patterns.
+    pub resources: Vec<String>,
+    /// Whether the policy is evaluated.
+    pub enabled: bool,
+    /// Creation timestamp (UTC).
+    pub created_at: String,
+    /// Last update timestamp (UTC).
+    pub updated_at: String,
+}
+
+impl Policy {
+    /// Whether the policy applies to a request.
+    pub fn matches(&self, request: &AuthzRequest) -> bool {
+        self.enabled
+            && self.principals.iter().any(|p| {
+                pattern_matches(p, &request.principal)
+                    || request
+                        .roles
+                        .iter()
+                        .any(|role| pattern_matches(p, &format!("{}{}", ROLE_PRINCIPAL_PREFIX, role)))
+            })
+            && self.actions.iter().any(|a| pattern_matches(a, &request.action))
+            && match &request.resource {
+                Some(resource) => self.resources.iter().any(|r| pattern_matches(r, resource)),
+                None => self.resources.iter().any(|r| r == "*"),
+            }
+    }
+}
+
+/// Authorization question: may `principal` perform `action` on `resource`?
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuthzRequest {
+    /// Who is asking, e.g. `key:<id>` or `anonymous`.
+    pub principal: String,
+    /// Roles of the principal, matched by `role:<name>` patterns.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    /// Action: `read`, `write` or `admin`.
+    pub action: String,
+    /// Target connection ID, if the request names one.
+    pub resource: Option<String>,
+    /// Request method, recorded in the decision log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// Request path, recorded in the decision log.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl AuthzRequest {
+    /// Builds the authorization question for an HTTP request.
+    pub fn for_http(principal: impl Into<String>, method: &str, path: &str, body_connection_id: Option<&str>) -> Self {
+        Self {
+            principal: principal.into(),
+            roles: Vec::new(),
+            action: request_action(method, path).to_string(),
+            resource: path_connection_id(path).or(body_connection_id).map(str::to_string),
+            method: Some(method.to_string()),
+            path: Some(path.to_string()),
+        }
+    }
+
+    /// Sets the roles of the principal.
+    pub fn with_roles(mut self, roles: Vec<String>) -> Self {
+        self.roles = roles;
+        self
+    }
+}
+
+/// Authorization decision.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuthzDecision {
+    /// Whether the request is allowed.
+    pub allowed: bool,
+    /// Policy that decided, absent when the default decision applied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_id: Option<String>,
+    /// Human-readable reason.
+    pub reason: String,
+}
+
+/// Logged authorization decision.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PolicyDecisionLog {
+    /// Log entry ID.
+    pub id: u64,
+    /// Who asked.
+    pub principal: String,
+    /// Requested action.
+    pub action: String,
+    /// Target connection ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    /// Request method.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// Request path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Whether the request was allowed.
+    pub allowed: bool,
+    /// Policy that decided.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_id: Option<String>,
+    /// Reason of the decision.
+    pub reason: String,
+    /// Decision timestamp (UTC).
+    pub decided_at: String,
+}
+
+/// Returns the policy action of an HTTP request.
+pub fn request_action(method: &str, path: &str) -> &'static str {
+    if path.starts_with("/api/admin/") {
+        ACTION_ADMIN
+    } else if is_read_request(method, path, path_connection_id(path).is_some()) {
+        ACTION_READ
+    } else {
+        ACTION_WRITE
+    }
+}
+
+/// Matches a value against an exact, `*` or `prefix*` pattern.
+pub fn pattern_matches(pattern: &str, value: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => pattern == value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_matches_patterns_and_http_requests() {
+        let policy = Policy {
+            id: "p1".to_string(),
+            name: "ci reads prod".to_string(),
+            description: None,
+            effect: PolicyEffect::Allow,
+            principals: vec!["key:*".to_string()],
+            actions: vec![ACTION_READ.to_string()],
+            resources: vec!["prod-*".to_string()],
+            enabled: true,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            updated_at: "2024-01-01 00:00:00".to_string(),
+        };
+
+        assert!(policy.matches(&AuthzRequest::for_http("key:k1", "GET", "/api/connections/prod-1/schema", None)));
+        assert!(policy.matches(&AuthzRequest::for_http("key:k1", "POST", "/api/query", Some("prod-2"))));
+        assert!(!policy.matches(&AuthzRequest::for_http("key:k1", "DELETE", "/api/connections/prod-1", None)));
+        assert!(!policy.matches(&AuthzRequest::for_http("key:k1", "GET", "/api/connections/dev-1/schema", None)));
+        assert!(!policy.matches(&AuthzRequest::for_http(ANONYMOUS_PRINCIPAL, "GET", "/api/connections/prod-1", None)));
+        // Requests without a connection only match `*` resources
+        assert!(!policy.matches(&AuthzRequest::for_http("key:k1", "GET", "/api/connections", None)));
+
+        // Role patterns match users holding the role
+        let mut by_role = policy.clone();
+        by_role.principals = vec!["role:analyst".to_string()];
+        let request = AuthzRequest::for_http("user:alice", "GET", "/api/connections/prod-1/schema", None);
+        assert!(!by_role.matches(&request));
+        assert!(by_role.matches(&request.with_roles(vec!["analyst".to_string()])));
+
+        assert_eq!(request_action("GET", "/api/admin/keys"), ACTION_ADMIN);
+        assert_eq!(request_action("POST", "/api/connections/c1/backups"), ACTION_WRITE);
+    }
+}
@@ -2,12 +2,17 @@
 //!
 //! Contains models for SQL query execution.
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
 
+use super::connection::DbType;
+use crate::response::CacheInfo;
+
 /// Request body for executing a SQL query.
-#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct QueryRequest {
     /// ID of the connection to use.
     #[validate(length(min = 1, message = "Connection ID is required"))]
@@ -17,17 +22,88 @@ pub struct QueryRequest {
     #[validate(length(min = 1, message = "SQL statement is required"))]
     pub sql: String,
 
-    /// Maximum number of rows to return (default: 1000).
+    /// Database on the connection's server to run the statement in
+    /// (default: the connection's database). MySQL and PostgreSQL only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(length(min = 1, message = "Database must not be empty"))]
+    pub database: Option<String>,
+
+    /// Maximum number of rows to return (default: 1000, at most `QUERY_MAX_ROWS`).
     #[serde(default = "default_limit")]
     pub limit: Option<u32>,
+
+    /// Positional bind parameters (`?` for MySQL/SQLite, `$1` for PostgreSQL).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<serde_json::Value>,
+
+    /// Named bind parameters for `:name` placeholders (not combined with `params`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    pub named_params: BTreeMap<String, serde_json::Value>,
+
+    /// Query timeout in milliseconds (default: the connection's default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1, message = "Timeout must be positive"))]
+    pub timeout_ms: Option<u64>,
+
+    /// Cache the result for this many seconds (absent or 0 = no caching).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_ttl_secs: Option<u64>,
+
+    /// Token from a change preview or a confirmation request, required to
+    /// execute UPDATE/DELETE and DDL statements.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation_token: Option<String>,
+
+    /// Language of `sql` (default: `sql`); `cypher` runs a read-only Cypher
+    /// query on a Neo4j connection, with `named_params` as `$name` parameters.
+    #[serde(default, skip_serializing_if = "QueryLanguage::is_sql")]
+    pub query_language: QueryLanguage,
+}
+
+impl QueryRequest {
+    /// Creates a request with the same defaults as a request body carrying
+    /// only `connection_id` and `sql`.
+    pub fn new(connection_id: impl Into<String>, sql: impl Into<String>) -> Self {
+        Self {
+            connection_id: connection_id.into(),
+            sql: sql.into(),
+            database: None,
+            limit: default_limit(),
+            params: Vec::new(),
+            named_params: BTreeMap::new(),
+            timeout_ms: None,
+            cache_ttl_secs: None,
+            confirmation_token: None,
+            query_language: QueryLanguage::Sql,
+        }
+    }
 }
 
 fn default_limit() -> Option<u32> {
     Some(1000)
 }
 
+/// Language of a query statement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryLanguage {
+    /// SQL, or the native language of a non-SQL connection (Query DSL, Flux, InfluxQL).
+    #[default]
+    Sql,
+    /// Cypher (Neo4j).
+    Cypher,
+}
+
+impl QueryLanguage {
+    /// Whether this is the default language.
+    pub fn is_sql(&self) -> bool {
+        *self == QueryLanguage::Sql
+    }
+}
+
 /// Result of a SQL query execution.
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct QueryResult {
     /// Column information.
     pub columns: Vec<ColumnInfo>,
@@ -46,10 +122,32 @@ pub struct QueryResult {
     /// Query execution time in milliseconds.
     #[serde(default)]
     pub execution_time_ms: u64,
+
+    /// Whether cells were clipped or rows dropped to stay within the size limits.
+    #[serde(default)]
+    pub truncated: bool,
+
+    /// Cells whose values were clipped.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated_cells: Vec<TruncatedCell>,
+}
+
+/// A result cell clipped to the per-cell size limit.
+///
+/// Text is cut at a character boundary; JSON arrays and objects are replaced
+/// with their clipped JSON text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TruncatedCell {
+    /// Row index.
+    pub row: usize,
+    /// Column index.
+    pub column: usize,
+    /// Size of the original value in bytes.
+    pub original_bytes: usize,
 }
 
 /// Column information in query result.
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ColumnInfo {
     /// Column name.
     pub name: String,
@@ -60,6 +158,42 @@ pub struct ColumnInfo {
     /// Whether the column is nullable.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nullable: Option<bool>,
+
+    /// How the column's values are represented in `rows`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ValueKind>,
+}
+
+/// Representation of a column's values in a query result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueKind {
+    /// JSON integer.
+    Integer,
+    /// JSON number.
+    Float,
+    /// JSON boolean.
+    Boolean,
+    /// Exact number as a string, e.g. `"12345.678"`.
+    Decimal,
+    /// String.
+    Text,
+    /// Base64 encoded bytes.
+    Binary,
+    /// Date, e.g. `"2024-01-31"`.
+    Date,
+    /// Time of day or duration, e.g. `"13:45:00"`.
+    Time,
+    /// Date and time without a time zone, e.g. `"2024-01-31T13:45:00"`.
+    DateTime,
+    /// Instant in RFC 3339 UTC, e.g. `"2024-01-31T13:45:00Z"`.
+    DateTimeTz,
+    /// Nested JSON value.
+    Json,
+    /// Geometry in WKT, e.g. `"POINT(1 2)"`.
+    Geometry,
+    /// UUID string.
+    Uuid,
 }
 
 impl QueryResult {
@@ -71,6 +205,8 @@ impl QueryResult {
             row_count: 0,
             affected_rows: None,
             execution_time_ms: 0,
+            truncated: false,
+            truncated_cells: Vec::new(),
         }
     }
 
@@ -82,6 +218,549 @@ impl QueryResult {
             row_count: 0,
             affected_rows: Some(affected),
             execution_time_ms,
+            truncated: false,
+            truncated_cells: Vec::new(),
+        }
+    }
+
+    /// Clips text and JSON cells larger than `max_bytes`, recording them in
+    /// `truncated_cells`.
+    pub fn clip_cells(&mut self, max_bytes: usize) {
+        for (r, row) in self.rows.iter_mut().enumerate() {
+            for (c, value) in row.iter_mut().enumerate() {
+                let text = match value {
+                    serde_json::Value::String(s) if s.len() > max_bytes => std::mem::take(s),
+                    serde_json::Value::Array(_) | serde_json::Value::Object(_) => match value.to_string() {
+                        s if s.len() > max_bytes => s,
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                let mut end = max_bytes;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                self.truncated_cells.push(TruncatedCell { row: r, column: c, original_bytes: text.len() });
+                *value = serde_json::Value::String(text[..end].to_string());
+            }
+        }
+        if !self.truncated_cells.is_empty() {
+            self.truncated = true;
+        }
+    }
+
+    /// Drops the rows after the first `limit`, for limits the database could
+    /// not apply (e.g. given as a bind parameter); returns whether rows were dropped.
+    pub fn limit_rows(&mut self, limit: usize) -> bool {
+        if self.rows.len() <= limit {
+            return false;
         }
+        self.rows.truncate(limit);
+        self.row_count = limit;
+        self.truncated_cells.retain(|cell| cell.row < limit);
+        self.truncated = true;
+        true
+    }
+
+    /// Drops trailing rows so the serialized columns and rows stay within
+    /// `max_bytes`; returns whether rows were dropped.
+    pub fn clip_rows(&mut self, max_bytes: usize) -> bool {
+        let mut total = serde_json::to_vec(&self.columns).map(|v| v.len()).unwrap_or(0);
+        let keep = self.rows.iter().position(|row| {
+            total += serde_json::to_vec(row).map(|v| v.len() + 1).unwrap_or(0);
+            total > max_bytes
+        });
+        let Some(keep) = keep else {
+            return false;
+        };
+        self.rows.truncate(keep);
+        self.row_count = keep;
+        self.truncated_cells.retain(|cell| cell.row < keep);
+        self.truncated = true;
+        true
+    }
+}
+
+/// Rows an UPDATE/DELETE would modify, returned before it is executed.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChangePreview {
+    /// SELECT statement used to find the rows.
+    pub preview_sql: String,
+    /// Matching rows (at most the preview row cap).
+    pub result: QueryResult,
+    /// Whether more rows match than were returned.
+    pub truncated: bool,
+    /// Token to send as `confirmation_token` to execute the statement.
+    pub confirmation_token: String,
+    /// Token expiry timestamp.
+    pub expires_at: String,
+}
+
+/// Kind of statement that must be confirmed before it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DangerousStatementKind {
+    /// UPDATE/DELETE without a `WHERE` clause.
+    UnfilteredChange,
+    /// Schema change (CREATE, ALTER, DROP, TRUNCATE, RENAME).
+    Ddl,
+}
+
+/// Returned instead of running a dangerous statement; resubmit the same
+/// statement with `confirmation_token` to execute it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConfirmationRequired {
+    /// Always `true`.
+    pub requires_confirmation: bool,
+    /// Why the statement needs confirmation.
+    pub kind: DangerousStatementKind,
+    /// Human-readable description of the risk.
+    pub reason: String,
+    /// Estimated number of rows modified or dropped (`null` when unknown).
+    pub estimated_affected_rows: Option<u64>,
+    /// Token to send as `confirmation_token` to execute the statement.
+    pub confirmation_token: String,
+    /// Token expiry timestamp.
+    pub expires_at: String,
+}
+
+/// Request body for formatting SQL.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct FormatSqlRequest {
+    /// SQL text; may contain several statements.
+    #[validate(length(min = 1, max = 1048576, message = "SQL must be 1-1048576 bytes"))]
+    pub sql: String,
+    /// Dialect whose quoting and comment rules split the statements
+    /// (default: standard SQL as in PostgreSQL).
+    #[serde(default)]
+    pub dialect: Option<DbType>,
+    /// Spaces per indentation level (default: 2).
+    #[serde(default = "default_format_indent")]
+    #[validate(range(min = 1, max = 8, message = "Indent must be 1-8 spaces"))]
+    pub indent: u8,
+    /// Indent with tabs instead of spaces.
+    #[serde(default)]
+    pub use_tabs: bool,
+    /// Upper-case reserved keywords (default: true).
+    #[serde(default = "default_format_uppercase")]
+    pub uppercase: bool,
+}
+
+fn default_format_indent() -> u8 {
+    2
+}
+
+fn default_format_uppercase() -> bool {
+    true
+}
+
+/// Formatted SQL.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FormattedSql {
+    /// Formatted text; statements are separated by a blank line.
+    pub sql: String,
+    /// Number of statements formatted.
+    pub statement_count: usize,
+}
+
+/// Request body for fetching a random sample of a table.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SampleRequest {
+    /// MySQL database or PostgreSQL schema (defaults to the connection database / "public").
+    pub database: Option<String>,
+    /// Table to sample.
+    #[validate(length(min = 1, max = 128, message = "Table name must be 1-128 characters"))]
+    pub table: String,
+    /// Number of rows to return (default: 100).
+    #[serde(default = "default_sample_rows")]
+    #[validate(range(min = 1, max = 10000, message = "Sample size must be 1-10000 rows"))]
+    pub rows: u32,
+}
+
+fn default_sample_rows() -> u32 {
+    100
+}
+
+/// Strategy used to draw a sample.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleMethod {
+    /// PostgreSQL `TABLESAMPLE SYSTEM` (random pages).
+    TablesampleSystem,
+    /// PostgreSQL `TABLESAMPLE BERNOULLI` (random rows).
+    TablesampleBernoulli,
+    /// `ORDER BY RAND()/random() LIMIT n`, used for small tables only.
+    OrderByRandom,
+    /// MySQL `WHERE RAND() < fraction LIMIT n` with an execution time cap, used for large tables.
+    RandomFilter,
+}
+
+/// Random sample of a table.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SampleResult {
+    /// Strategy used.
+    pub method: SampleMethod,
+    /// Row count estimated from table statistics.
+    pub estimated_rows: u64,
+    /// Sampled rows.
+    pub result: QueryResult,
+}
+
+/// Async query job status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryJobStatus {
+    /// Query is executing.
+    Running,
+    /// Query finished; `result` holds the rows.
+    Completed,
+    /// Query failed; see `error`.
+    Failed,
+}
+
+/// Long-running query executed in the background.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QueryJob {
+    /// Job ID.
+    pub id: String,
+    /// Connection ID.
+    pub connection_id: String,
+    /// Executed SQL statement.
+    pub sql: String,
+    /// Current status.
+    pub status: QueryJobStatus,
+    /// Query result (present once completed).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<QueryResult>,
+    /// Whether rows were dropped to keep the stored result within the size limit.
+    pub truncated: bool,
+    /// Error message when the query failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Structured error details reported by the connection service.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_details: Option<serde_json::Value>,
+    /// Creation timestamp.
+    pub created_at: String,
+    /// Completion timestamp.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
+
+/// Request to run one read-only statement on several connections.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct FanOutQueryRequest {
+    /// IDs of the connections to run the statement on; duplicates run once.
+    #[validate(length(min = 1, max = 50, message = "Between 1 and 50 connection IDs are required"))]
+    pub connection_ids: Vec<String>,
+
+    /// SQL statement to execute (read-only).
+    #[validate(length(min = 1, message = "SQL statement is required"))]
+    pub sql: String,
+
+    /// Database to run the statement in on each connection's server.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(length(min = 1, message = "Database must not be empty"))]
+    pub database: Option<String>,
+
+    /// Maximum number of rows to return per connection (default: 1000).
+    #[serde(default = "default_limit")]
+    pub limit: Option<u32>,
+
+    /// Positional bind parameters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<serde_json::Value>,
+
+    /// Named bind parameters for `:name` placeholders.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    pub named_params: BTreeMap<String, serde_json::Value>,
+
+    /// Per-connection timeout in milliseconds (default: each connection's default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1, message = "Timeout must be positive"))]
+    pub timeout_ms: Option<u64>,
+}
+
+impl FanOutQueryRequest {
+    /// Connection IDs in request order without duplicates.
+    pub fn unique_connection_ids(&self) -> Vec<&str> {
+        let mut seen = std::collections::HashSet::new();
+        self.connection_ids
+            .iter()
+            .map(String::as_str)
+            .filter(|id| seen.insert(*id))
+            .collect()
+    }
+
+    /// The query to run on one of the connections.
+    pub fn for_connection(&self, connection_id: &str) -> QueryRequest {
+        QueryRequest {
+            connection_id: connection_id.to_string(),
+            sql: self.sql.clone(),
+            database: self.database.clone(),
+            limit: self.limit,
+            params: self.params.clone(),
+            named_params: self.named_params.clone(),
+            timeout_ms: self.timeout_ms,
+            cache_ttl_secs: None,
+            confirmation_token: None,
+            query_language: QueryLanguage::Sql,
+        }
+    }
+}
+
+/// Outcome of a fan-out query on one connection.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FanOutEntry {
+    /// Connection ID.
+    pub connection_id: String,
+    /// Query result when the statement succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<QueryResult>,
+    /// Error code when the statement failed (e.g. `"TIMEOUT"`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// Error message when the statement failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Structured error details reported by the connection service.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_details: Option<serde_json::Value>,
+    /// Warning for a degraded target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    /// Wall time spent on this connection, in milliseconds.
+    pub duration_ms: u64,
+}
+
+/// Per-connection results of a fan-out query, in request order.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FanOutResult {
+    /// One entry per connection.
+    pub results: Vec<FanOutEntry>,
+    /// Number of connections the statement succeeded on.
+    pub succeeded: usize,
+    /// Number of connections the statement failed on.
+    pub failed: usize,
+}
+
+/// Request to run several read-only queries in one round trip, e.g. the
+/// panels of a dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct BatchQueryRequest {
+    /// Queries to run, each with its own connection and options; an invalid
+    /// query fails on its own.
+    #[validate(length(min = 1, max = 50, message = "Between 1 and 50 queries are required"))]
+    pub queries: Vec<QueryRequest>,
+}
+
+/// Outcome of one query of a batch.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchQueryEntry {
+    /// Connection ID of the query.
+    pub connection_id: String,
+    /// Query result when the statement succeeded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<QueryResult>,
+    /// Result cache information when the query asked for caching.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheInfo>,
+    /// Error code when the query failed (e.g. `"TIMEOUT"`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// Error message when the query failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Structured error details reported by the connection service.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_details: Option<serde_json::Value>,
+    /// Warning for a degraded target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    /// Wall time spent on this query, in milliseconds.
+    pub duration_ms: u64,
+}
+
+/// Per-query results of a batch, in request order.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchQueryResult {
+    /// One entry per query.
+    pub results: Vec<BatchQueryEntry>,
+    /// Number of queries that succeeded.
+    pub succeeded: usize,
+    /// Number of queries that failed.
+    pub failed: usize,
+}
+
+/// One side of a query diff.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct QueryDiffSide {
+    /// ID of the connection to run the query on.
+    #[validate(length(min = 1, message = "Connection ID is required"))]
+    pub connection_id: String,
+
+    /// SQL statement to execute (read-only).
+    #[validate(length(min = 1, message = "SQL statement is required"))]
+    pub sql: String,
+
+    /// Database on the connection's server to run the statement in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(length(min = 1, message = "Database must not be empty"))]
+    pub database: Option<String>,
+
+    /// Positional bind parameters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<serde_json::Value>,
+
+    /// Named bind parameters for `:name` placeholders.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Object)]
+    pub named_params: BTreeMap<String, serde_json::Value>,
+}
+
+/// Request to compare the rows of two queries.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct QueryDiffRequest {
+    /// Query giving the old rows (e.g. before a deployment).
+    #[validate(nested)]
+    pub left: QueryDiffSide,
+
+    /// Query giving the new rows.
+    #[validate(nested)]
+    pub right: QueryDiffSide,
+
+    /// Columns that identify a row on both sides; names are case-insensitive.
+    #[validate(length(min = 1, max = 16, message = "Between 1 and 16 key columns are required"))]
+    pub key_columns: Vec<String>,
+
+    /// Maximum number of rows read per side (default: 10000).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1, max = 100000, message = "Limit must be 1-100000"))]
+    pub limit: Option<u32>,
+
+    /// Per-query timeout in milliseconds (default: each connection's default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(range(min = 1, message = "Timeout must be positive"))]
+    pub timeout_ms: Option<u64>,
+}
+
+impl QueryDiffRequest {
+    /// Default number of rows read per side.
+    pub const DEFAULT_LIMIT: u32 = 10_000;
+
+    /// The query to run for one side.
+    pub fn query(&self, side: &QueryDiffSide) -> QueryRequest {
+        QueryRequest {
+            connection_id: side.connection_id.clone(),
+            sql: side.sql.clone(),
+            database: side.database.clone(),
+            limit: Some(self.limit.unwrap_or(Self::DEFAULT_LIMIT)),
+            params: side.params.clone(),
+            named_params: side.named_params.clone(),
+            timeout_ms: self.timeout_ms,
+            cache_ttl_secs: None,
+            confirmation_token: None,
+            query_language: QueryLanguage::Sql,
+        }
+    }
+}
+
+/// A row present on both sides with different values.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChangedRow {
+    /// Key column values.
+    pub key: Vec<serde_json::Value>,
+    /// Shared columns whose values differ.
+    pub changed_columns: Vec<String>,
+    /// Left row, in `left_columns` order.
+    pub left: Vec<serde_json::Value>,
+    /// Right row, in `right_columns` order.
+    pub right: Vec<serde_json::Value>,
+}
+
+/// Row-level differences between two queries.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QueryDiffResult {
+    /// Key columns the rows were aligned by.
+    pub key_columns: Vec<String>,
+    /// Columns of the left query.
+    pub left_columns: Vec<String>,
+    /// Columns of the right query.
+    pub right_columns: Vec<String>,
+    /// Rows only returned by the right query, in `right_columns` order.
+    pub added: Vec<Vec<serde_json::Value>>,
+    /// Rows only returned by the left query, in `left_columns` order.
+    pub removed: Vec<Vec<serde_json::Value>>,
+    /// Rows on both sides whose shared columns differ.
+    pub changed: Vec<ChangedRow>,
+    /// Number of rows equal on both sides.
+    pub unchanged: usize,
+    /// Rows read from the left query.
+    pub left_rows: usize,
+    /// Rows read from the right query.
+    pub right_rows: usize,
+    /// Whether either side hit the row limit; rows past it were not compared.
+    pub truncated: bool,
+    /// Wall time in milliseconds.
+    pub duration_ms: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn fan_out_runs_each_connection_once() {
+        let req: FanOutQueryRequest = serde_json::from_value(json!({
+            "connection_ids": ["dev", "prod", "dev"],
+            "sql": "SELECT COUNT(*) FROM users",
+            "database": "shop",
+        }))
+        .unwrap();
+        assert_eq!(req.unique_connection_ids(), ["dev", "prod"]);
+
+        let query = req.for_connection("prod");
+        assert_eq!(query.connection_id, "prod");
+        assert_eq!(query.database.as_deref(), Some("shop"));
+        assert_eq!(query.limit, Some(1000));
+        assert!(query.confirmation_token.is_none());
+    }
+
+    #[test]
+    fn new_request_matches_a_minimal_body() {
+        let parsed: QueryRequest = serde_json::from_value(json!({"connection_id": "c1", "sql": "SELECT 1"})).unwrap();
+        assert_eq!(
+            serde_json::to_value(QueryRequest::new("c1", "SELECT 1")).unwrap(),
+            serde_json::to_value(parsed).unwrap()
+        );
+    }
+
+    #[test]
+    fn clips_large_cells_and_rows() {
+        let mut result = QueryResult::empty();
+        result.rows = vec![
+            vec![json!(1), json!("短文本"), json!({"k": "v".repeat(40)})],
+            vec![json!(2), json!("汉字".repeat(10)), json!(null)],
+        ];
+        result.row_count = 2;
+
+        result.clip_cells(16);
+        assert!(result.truncated);
+        assert_eq!(
+            result.truncated_cells,
+            [
+                TruncatedCell { row: 0, column: 2, original_bytes: 48 },
+                TruncatedCell { row: 1, column: 1, original_bytes: 60 },
+            ]
+        );
+        // 16 bytes fall inside the sixth character; the clip keeps five whole ones
+        assert_eq!(result.rows[1][1], json!("汉字汉字汉"));
+
+        assert!(!result.clone().clip_rows(usize::MAX));
+        assert!(result.clip_rows(60));
+        assert_eq!((result.row_count, result.rows.len()), (1, 1));
+        assert_eq!(result.truncated_cells.len(), 1);
     }
 }
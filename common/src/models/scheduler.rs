@@ -0,0 +1,148 @@
+//! Scheduled job models.
+//!
+//! Contains models for recurring tasks (backups, health checks, queries) driven by cron expressions.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Task executed by a scheduled job.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledTaskKind {
+    /// Create a backup; `params` is a backup request body.
+    Backup,
+    /// Test the connection.
+    HealthCheck,
+    /// Run a read-only query; `params` is `{"sql": "...", "limit": 1000}`.
+    Query,
+}
+
+impl ScheduledTaskKind {
+    /// Returns the string stored in the metadata database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduledTaskKind::Backup => "backup",
+            ScheduledTaskKind::HealthCheck => "health_check",
+            ScheduledTaskKind::Query => "query",
+        }
+    }
+
+    /// Parses the string stored in the metadata database.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "backup" => Some(ScheduledTaskKind::Backup),
+            "health_check" => Some(ScheduledTaskKind::HealthCheck),
+            "query" => Some(ScheduledTaskKind::Query),
+            _ => None,
+        }
+    }
+}
+
+/// Request body for creating a scheduled job.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateScheduledJobRequest {
+    /// Display name.
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+    /// Connection the task runs against.
+    #[validate(length(min = 1, message = "Connection ID is required"))]
+    pub connection_id: String,
+    /// Cron expression in UTC, 5 fields (`min hour dom mon dow`) or 6 with seconds.
+    #[validate(length(min = 1, max = 100, message = "Cron expression is required"))]
+    pub cron: String,
+    /// Task to run.
+    pub kind: ScheduledTaskKind,
+    /// Task parameters.
+    #[serde(default)]
+    pub params: serde_json::Value,
+    /// Whether the job starts enabled (default: true).
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Scheduled job definition.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScheduledJob {
+    /// Job ID.
+    pub id: String,
+    /// Display name.
+    pub name: String,
+    /// Connection ID.
+    pub connection_id: String,
+    /// Cron expression (UTC).
+    pub cron: String,
+    /// Task to run.
+    pub kind: ScheduledTaskKind,
+    /// Task parameters.
+    pub params: serde_json::Value,
+    /// Whether the job is enabled.
+    pub enabled: bool,
+    /// Next planned run (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<String>,
+    /// Last run start (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<String>,
+    /// Status of the last run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_status: Option<JobRunStatus>,
+    /// Creation timestamp.
+    pub created_at: String,
+}
+
+/// Outcome of a job run.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobRunStatus {
+    /// Task is running.
+    Running,
+    /// Task finished successfully.
+    Succeeded,
+    /// Task failed; see `message`.
+    Failed,
+}
+
+impl JobRunStatus {
+    /// Returns the string stored in the metadata database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobRunStatus::Running => "running",
+            JobRunStatus::Succeeded => "succeeded",
+            JobRunStatus::Failed => "failed",
+        }
+    }
+
+    /// Parses the string stored in the metadata database.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "running" => Some(JobRunStatus::Running),
+            "succeeded" => Some(JobRunStatus::Succeeded),
+            "failed" => Some(JobRunStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// One execution of a scheduled job.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobRun {
+    /// Run ID.
+    pub id: String,
+    /// Scheduled job ID.
+    pub job_id: String,
+    /// Run status.
+    pub status: JobRunStatus,
+    /// Result summary or error message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Start timestamp (UTC).
+    pub started_at: String,
+    /// Finish timestamp (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
+}
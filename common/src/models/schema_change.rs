@@ -0,0 +1,112 @@
+//! Online schema change models.
+//!
+//! Contains models for running ALTER TABLE on large tables without long locks.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Strategy used to apply a schema change.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaChangeStrategy {
+    /// Run the ALTER statement directly on the table.
+    Direct,
+    /// Shadow table + triggers + chunked copy, followed by an atomic rename (gh-ost style).
+    #[default]
+    Online,
+}
+
+/// Schema change job status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaChangeStatus {
+    /// Job created, not started yet.
+    Pending,
+    /// Copying rows into the shadow table.
+    Copying,
+    /// Copy finished, waiting for a cutover request.
+    ReadyForCutover,
+    /// Swapping the shadow table with the original table.
+    CuttingOver,
+    /// Schema change applied.
+    Completed,
+    /// Job failed; see `error`.
+    Failed,
+    /// Job cancelled by the user.
+    Cancelled,
+}
+
+impl SchemaChangeStatus {
+    /// Returns whether the job has reached a terminal state.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            SchemaChangeStatus::Completed | SchemaChangeStatus::Failed | SchemaChangeStatus::Cancelled
+        )
+    }
+}
+
+/// Request body for starting a schema change.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SchemaChangeRequest {
+    /// Target database (defaults to the connection's database).
+    pub database: Option<String>,
+    /// Table to alter.
+    #[validate(length(min = 1, max = 64, message = "Table name must be 1-64 characters"))]
+    pub table: String,
+    /// ALTER clause without the `ALTER TABLE <name>` prefix, e.g. `ADD COLUMN age INT`.
+    #[validate(length(min = 1, message = "Alter clause is required"))]
+    pub alter: String,
+    /// Strategy (default: online).
+    #[serde(default)]
+    pub strategy: SchemaChangeStrategy,
+    /// Rows copied per chunk (default: 1000).
+    pub chunk_size: Option<u32>,
+    /// Pause between chunks in milliseconds, to throttle load (default: 0).
+    pub chunk_sleep_ms: Option<u64>,
+    /// Perform the cutover automatically once the copy finishes (default: false).
+    #[serde(default)]
+    pub auto_cutover: bool,
+    /// Drop the original table after cutover instead of keeping it as a backup (default: false).
+    #[serde(default)]
+    pub drop_old_table: bool,
+}
+
+/// Schema change job state.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SchemaChangeJob {
+    /// Job ID.
+    pub id: String,
+    /// Connection ID.
+    pub connection_id: String,
+    /// Database name.
+    pub database: String,
+    /// Table being altered.
+    pub table: String,
+    /// ALTER clause.
+    pub alter: String,
+    /// Strategy in use.
+    pub strategy: SchemaChangeStrategy,
+    /// Current status.
+    pub status: SchemaChangeStatus,
+    /// Rows copied so far.
+    pub rows_copied: u64,
+    /// Estimated total rows.
+    pub rows_total: u64,
+    /// Progress percentage (0-100).
+    pub progress: f64,
+    /// Shadow table name (online strategy).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shadow_table: Option<String>,
+    /// Name the original table was renamed to on cutover (if kept).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_table: Option<String>,
+    /// Error message when the job failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Creation timestamp.
+    pub created_at: String,
+    /// Last update timestamp.
+    pub updated_at: String,
+}
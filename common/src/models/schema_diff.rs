@@ -0,0 +1,148 @@
+//! Schema comparison models.
+//!
+//! Contains the detailed schema snapshot used for comparison and the diff result.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Column definition in a schema snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct ColumnDef {
+    /// Column name.
+    pub name: String,
+    /// Full data type (e.g., "varchar(255)", "numeric(10,2)").
+    pub data_type: String,
+    /// Whether the column is nullable.
+    pub nullable: bool,
+    /// Default value as a SQL expression.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Extra attributes (e.g., "auto_increment").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra: Option<String>,
+}
+
+/// Index definition in a schema snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct IndexDef {
+    /// Index name.
+    pub name: String,
+    /// Indexed columns in order.
+    pub columns: Vec<String>,
+    /// Whether the index is unique.
+    pub unique: bool,
+    /// Whether this is the primary key.
+    pub primary: bool,
+}
+
+/// Foreign key constraint definition in a schema snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct ForeignKeyDef {
+    /// Constraint name.
+    pub name: String,
+    /// Referencing columns.
+    pub columns: Vec<String>,
+    /// Referenced table.
+    pub referenced_table: String,
+    /// Referenced columns.
+    pub referenced_columns: Vec<String>,
+}
+
+/// Table definition in a schema snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TableDef {
+    /// Table name.
+    pub name: String,
+    /// Columns in ordinal order.
+    pub columns: Vec<ColumnDef>,
+    /// Indexes (including the primary key).
+    pub indexes: Vec<IndexDef>,
+    /// Foreign key constraints.
+    pub foreign_keys: Vec<ForeignKeyDef>,
+}
+
+/// One side of a schema comparison.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SchemaRef {
+    /// Connection ID.
+    #[validate(length(min = 1, message = "Connection ID is required"))]
+    pub connection_id: String,
+    /// MySQL database or PostgreSQL schema (defaults to the connection database / "public").
+    pub database: Option<String>,
+}
+
+/// Request body for comparing two schemas.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SchemaDiffRequest {
+    /// Desired schema.
+    #[validate(nested)]
+    pub source: SchemaRef,
+    /// Schema to migrate towards the source.
+    #[validate(nested)]
+    pub target: SchemaRef,
+    /// Generate migration SQL for the target (default: false).
+    #[serde(default)]
+    pub generate_sql: bool,
+}
+
+/// Column whose definition differs between source and target.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ColumnChange {
+    /// Column name.
+    pub name: String,
+    /// Definition in the source schema.
+    pub source: ColumnDef,
+    /// Definition in the target schema.
+    pub target: ColumnDef,
+}
+
+/// Differences of a table present on both sides.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct TableDiff {
+    /// Table name.
+    pub table: String,
+    /// Columns only in the source.
+    pub columns_added: Vec<ColumnDef>,
+    /// Columns only in the target.
+    pub columns_removed: Vec<ColumnDef>,
+    /// Columns with different definitions.
+    pub columns_changed: Vec<ColumnChange>,
+    /// Indexes only in the source (or redefined).
+    pub indexes_added: Vec<IndexDef>,
+    /// Indexes only in the target (or redefined).
+    pub indexes_removed: Vec<IndexDef>,
+    /// Foreign keys only in the source (or redefined).
+    pub foreign_keys_added: Vec<ForeignKeyDef>,
+    /// Foreign keys only in the target (or redefined).
+    pub foreign_keys_removed: Vec<ForeignKeyDef>,
+}
+
+impl TableDiff {
+    /// Returns whether the table is identical on both sides.
+    pub fn is_empty(&self) -> bool {
+        self.columns_added.is_empty()
+            && self.columns_removed.is_empty()
+            && self.columns_changed.is_empty()
+            && self.indexes_added.is_empty()
+            && self.indexes_removed.is_empty()
+            && self.foreign_keys_added.is_empty()
+            && self.foreign_keys_removed.is_empty()
+    }
+}
+
+/// Structured schema comparison result.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SchemaDiff {
+    /// Whether both schemas are identical.
+    pub identical: bool,
+    /// Tables only in the source.
+    pub tables_added: Vec<TableDef>,
+    /// Tables only in the target.
+    pub tables_removed: Vec<TableDef>,
+    /// Tables present on both sides with differences.
+    pub tables_changed: Vec<TableDiff>,
+    /// Migration statements that bring the target in line with the source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub migration_sql: Option<Vec<String>>,
+}
@@ -0,0 +1,65 @@
+//! Foreign key relationship graph models.
+//!
+//! Tables are the nodes of the graph and foreign keys its edges, for
+//! rendering ER diagrams.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Foreign key graph of one MySQL database / PostgreSQL schema.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SchemaGraph {
+    /// MySQL database / PostgreSQL schema.
+    pub database: String,
+    /// Tables, sorted by name.
+    pub nodes: Vec<GraphNode>,
+    /// Foreign keys between the tables.
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Table in the graph.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GraphNode {
+    /// Table name.
+    pub table: String,
+    /// Columns in ordinal order.
+    pub columns: Vec<GraphColumn>,
+}
+
+/// Column of a graph node.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GraphColumn {
+    /// Column name.
+    pub name: String,
+    /// Full data type.
+    pub data_type: String,
+    /// Whether the column is nullable.
+    pub nullable: bool,
+    /// Whether the column is part of the primary key.
+    pub primary_key: bool,
+    /// Whether the column is part of a foreign key.
+    pub foreign_key: bool,
+}
+
+/// Foreign key from `from_table` to `to_table`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GraphEdge {
+    /// Constraint name.
+    pub name: String,
+    /// Referencing table.
+    pub from_table: String,
+    /// Referencing columns.
+    pub from_columns: Vec<String>,
+    /// Referenced table.
+    pub to_table: String,
+    /// Referenced database / schema when it differs from the graph's; the
+    /// referenced table then has no node.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_database: Option<String>,
+    /// Referenced columns, matching `from_columns` by position.
+    pub to_columns: Vec<String>,
+    /// Action on delete of the referenced row (e.g. "CASCADE", "RESTRICT", "NO ACTION").
+    pub on_delete: String,
+    /// Action on update of the referenced key.
+    pub on_update: String,
+}
@@ -0,0 +1,57 @@
+//! Seed data models.
+//!
+//! Contains models for generating fake rows into a table.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Request body for generating seed rows.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct SeedRequest {
+    /// MySQL database / PostgreSQL schema (default: the connection's database / `public`).
+    pub database: Option<String>,
+    /// Number of rows to generate.
+    #[validate(range(min = 1, max = 10000, message = "Rows must be 1-10000"))]
+    pub rows: u32,
+    /// Rows inserted per transaction (default: 500).
+    #[validate(range(min = 1, max = 5000, message = "Batch size must be 1-5000"))]
+    pub batch_size: Option<u32>,
+    /// Generate and return a preview without inserting anything.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Random seed; the same seed generates the same rows (default: random).
+    pub seed: Option<u64>,
+}
+
+/// How values of a column are generated.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SeedColumn {
+    /// Column name.
+    pub name: String,
+    /// Column type.
+    pub data_type: String,
+    /// Generator, e.g. `email`, `integer(1..100000)`, `reference(customers.id)`.
+    pub generator: String,
+}
+
+/// Result of a seed run.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SeedResult {
+    /// Table (qualified with its database / schema).
+    pub table: String,
+    /// Generated columns, in insert order.
+    pub columns: Vec<SeedColumn>,
+    /// Columns left to the database (auto-increment, identity, defaults).
+    pub skipped_columns: Vec<String>,
+    /// First generated rows, values in the order of `columns`.
+    pub preview: Vec<Vec<serde_json::Value>>,
+    /// Rows inserted (0 for a dry run).
+    pub rows_inserted: u64,
+    /// Whether this was a dry run.
+    pub dry_run: bool,
+    /// Seed used; pass it again to reproduce the rows.
+    pub seed: u64,
+    /// Duration in milliseconds.
+    pub duration_ms: u64,
+}
@@ -0,0 +1,72 @@
+//! Login session models.
+//!
+//! Every session token names a session record; revoking the record rejects
+//! the token at the gateway even though it has not expired.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A login session of a user.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Session {
+    /// Session ID (the token's `sid` claim).
+    pub id: String,
+    /// User ID (the token's `sub` claim).
+    pub user_id: String,
+    /// When the session was created (UTC).
+    pub created_at: String,
+    /// When a token of the session was last verified (UTC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seen_at: Option<String>,
+    /// When the session expires (UTC).
+    pub expires_at: String,
+    /// When the session was revoked (UTC), if it was.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<String>,
+    /// Client user agent at login.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Client IP address at login.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<String>,
+    /// Local roles granted at login (single sign-on).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    /// Whether this is the session of the request listing it.
+    #[serde(default)]
+    pub current: bool,
+}
+
+/// Request body for checking that a session is still active.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifySessionRequest {
+    /// Session ID from the token.
+    pub session_id: String,
+}
+
+/// Request body for starting the session of a user authenticated by the identity provider.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateSsoSessionRequest {
+    /// Issuer of the ID token.
+    pub issuer: String,
+    /// Subject (`sub`) of the ID token, the account's stable ID at the issuer.
+    pub subject: String,
+    /// Username taken from the ID token, for display only.
+    pub username: String,
+    /// Local roles mapped from the ID token claims.
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Client user agent.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Client IP address.
+    #[serde(default)]
+    pub ip_address: Option<String>,
+}
+
+/// Result of revoking several sessions.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RevokedSessions {
+    /// Number of sessions revoked.
+    pub revoked: u64,
+}
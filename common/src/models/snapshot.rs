@@ -0,0 +1,92 @@
+//! Query snapshot models.
+//!
+//! Contains models for saving query results as named snapshots and comparing
+//! them over time.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::query::QueryResult;
+
+/// Request body for saving a query result as a snapshot.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateSnapshotRequest {
+    /// Snapshot name; snapshots with the same name form a series.
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+    /// Connection to run the query on.
+    #[validate(length(min = 1, message = "Connection ID is required"))]
+    pub connection_id: String,
+    /// Read-only SQL statement.
+    #[validate(length(min = 1, message = "SQL statement is required"))]
+    pub sql: String,
+    /// Database on the connection's server to run the statement in.
+    #[validate(length(min = 1, message = "Database must not be empty"))]
+    pub database: Option<String>,
+    /// Maximum number of rows saved (default: 10000).
+    #[validate(range(min = 1, max = 100000, message = "Limit must be 1-100000"))]
+    pub limit: Option<u32>,
+    /// Positional bind parameters.
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+    /// Named bind parameters for `:name` placeholders.
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub named_params: BTreeMap<String, serde_json::Value>,
+    /// Days the snapshot is kept (default: `SNAPSHOT_RETENTION_DAYS`).
+    #[validate(range(min = 1, max = 3650, message = "Retention must be 1-3650 days"))]
+    pub retention_days: Option<u32>,
+    /// Keep only this many newest snapshots of the name on the connection;
+    /// older ones are deleted when this one is saved.
+    #[validate(range(min = 1, max = 1000, message = "keep_last must be 1-1000"))]
+    pub keep_last: Option<u32>,
+}
+
+/// Saved query snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuerySnapshot {
+    /// Snapshot ID.
+    pub id: String,
+    /// Snapshot name.
+    pub name: String,
+    /// Connection the query ran on.
+    pub connection_id: String,
+    /// Database the query ran in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// SQL statement.
+    pub sql: String,
+    /// Number of rows saved.
+    pub row_count: u64,
+    /// Whether rows were dropped to stay within the row or size limit.
+    pub truncated: bool,
+    /// Size of the saved result in bytes.
+    pub size_bytes: u64,
+    /// Principal that saved the snapshot.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// Creation timestamp.
+    pub created_at: String,
+    /// Time after which the snapshot is deleted.
+    pub expires_at: String,
+    /// Saved result; only returned when a single snapshot is fetched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<QueryResult>,
+}
+
+/// Request body for comparing two snapshots.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CompareSnapshotsRequest {
+    /// Older snapshot.
+    #[validate(length(min = 1, message = "left_id is required"))]
+    pub left_id: String,
+    /// Newer snapshot.
+    #[validate(length(min = 1, message = "right_id is required"))]
+    pub right_id: String,
+    /// Columns that identify a row in both snapshots.
+    #[validate(length(min = 1, max = 16, message = "Between 1 and 16 key columns are required"))]
+    pub key_columns: Vec<String>,
+}
@@ -0,0 +1,90 @@
+//! Data transfer models.
+//!
+//! Contains models for copying table rows between connections, including
+//! across database types.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Data transfer job status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStatus {
+    /// Rows are being copied.
+    Running,
+    /// All rows were copied.
+    Completed,
+    /// Transfer aborted; see `error`. Batches committed before the failure stay.
+    Failed,
+    /// Transfer cancelled by the user. Batches committed before stay.
+    Cancelled,
+}
+
+impl TransferStatus {
+    /// Returns whether the job has reached a terminal state.
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, TransferStatus::Running)
+    }
+}
+
+/// Request body for starting a data transfer.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct TransferRequest {
+    /// Connection to read from.
+    #[validate(length(min = 1, message = "Source connection ID is required"))]
+    pub source_connection_id: String,
+    /// Source MySQL database / PostgreSQL schema (default: the connection's database / `public`).
+    pub source_database: Option<String>,
+    /// Table to read.
+    #[validate(length(min = 1, max = 128, message = "Source table must be 1-128 characters"))]
+    pub source_table: String,
+    /// Row filter without the `WHERE` keyword, e.g. `created_at >= '2024-01-01'`.
+    pub filter: Option<String>,
+    /// Connection to write to.
+    #[validate(length(min = 1, message = "Target connection ID is required"))]
+    pub target_connection_id: String,
+    /// Target MySQL database / PostgreSQL schema (default: the connection's database / `public`).
+    pub target_database: Option<String>,
+    /// Existing table to insert into; columns are matched by name.
+    #[validate(length(min = 1, max = 128, message = "Target table must be 1-128 characters"))]
+    pub target_table: String,
+    /// Rows inserted per transaction (default: 1000).
+    #[validate(range(min = 1, max = 10000, message = "Batch size must be 1-10000"))]
+    pub batch_size: Option<u32>,
+    /// Delete all rows of the target table before copying (default: false).
+    #[serde(default)]
+    pub truncate_target: bool,
+}
+
+/// Data transfer job state.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TransferJob {
+    /// Job ID.
+    pub id: String,
+    /// Source connection ID.
+    pub source_connection_id: String,
+    /// Source table (qualified when a database was given).
+    pub source_table: String,
+    /// Target connection ID.
+    pub target_connection_id: String,
+    /// Target table (qualified when a database was given).
+    pub target_table: String,
+    /// Columns copied (present in both tables).
+    pub columns: Vec<String>,
+    /// Current status.
+    pub status: TransferStatus,
+    /// Rows matching the filter when the job started.
+    pub rows_total: u64,
+    /// Rows inserted and committed so far.
+    pub rows_copied: u64,
+    /// Progress percentage (0-100).
+    pub progress: f64,
+    /// Error that aborted the transfer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Creation timestamp.
+    pub created_at: String,
+    /// Last update timestamp.
+    pub updated_at: String,
+}
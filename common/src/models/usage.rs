@@ -0,0 +1,125 @@
+//! Per-user usage and quota models.
+//!
+//! Usage is counted per principal and UTC day: executed statements, rows
+//! scanned (returned plus affected rows) and execution time. A quota limits
+//! any of the three; unset limits are unlimited.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Usage counters of one principal on one day.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct UsageCounters {
+    /// Executed statements.
+    pub queries: u64,
+    /// Rows returned plus rows affected.
+    pub rows_scanned: u64,
+    /// Total execution time in milliseconds.
+    pub execution_ms: u64,
+}
+
+/// Daily limits of a principal (absent = unlimited).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct UsageQuota {
+    /// Statements per day.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_queries: Option<u64>,
+    /// Rows scanned per day.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rows_scanned: Option<u64>,
+    /// Execution time per day in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_execution_ms: Option<u64>,
+}
+
+impl UsageQuota {
+    /// Whether no limit is set.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_queries.is_none() && self.max_rows_scanned.is_none() && self.max_execution_ms.is_none()
+    }
+
+    /// Returns the first limit the usage has reached, as `(name, used, limit)`.
+    pub fn exceeded(&self, usage: &UsageCounters) -> Option<(&'static str, u64, u64)> {
+        [
+            ("queries", usage.queries, self.max_queries),
+            ("rows_scanned", usage.rows_scanned, self.max_rows_scanned),
+            ("execution_ms", usage.execution_ms, self.max_execution_ms),
+        ]
+        .into_iter()
+        .find_map(|(name, used, limit)| limit.filter(|&limit| used >= limit).map(|limit| (name, used, limit)))
+    }
+}
+
+/// Usage of a principal on one day with the quota that applies to it.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserUsage {
+    /// Principal, e.g. `key:<id>`.
+    pub principal: String,
+    /// UTC day.
+    pub date: NaiveDate,
+    /// Usage so far.
+    pub usage: UsageCounters,
+    /// Effective quota: the principal's own quota, else the default.
+    pub quota: UsageQuota,
+    /// Whether the quota is the principal's own rather than the default.
+    pub custom_quota: bool,
+    /// When the counters reset (next UTC midnight).
+    pub resets_at: DateTime<Utc>,
+}
+
+/// Usage of all principals on one day.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UsageReport {
+    /// UTC day.
+    pub date: NaiveDate,
+    /// Quota of principals without their own.
+    pub default_quota: UsageQuota,
+    /// Principals with usage that day, most queries first.
+    pub users: Vec<UserUsage>,
+    /// Sum over all principals.
+    pub total: UsageCounters,
+}
+
+/// Details of a request rejected because a daily quota is used up.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QuotaExceeded {
+    /// Principal whose quota is used up.
+    pub principal: String,
+    /// Limit reached: `queries`, `rows_scanned` or `execution_ms`.
+    pub limit: String,
+    /// Usage of that limit today.
+    pub used: u64,
+    /// Daily allowance.
+    pub allowed: u64,
+    /// When the counters reset (next UTC midnight).
+    pub resets_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_reached_limit_is_reported() {
+        let quota = UsageQuota {
+            max_queries: Some(10),
+            max_rows_scanned: Some(1000),
+            max_execution_ms: None,
+        };
+        let mut usage = UsageCounters {
+            queries: 9,
+            rows_scanned: 999,
+            execution_ms: u64::MAX,
+        };
+        assert_eq!(quota.exceeded(&usage), None);
+
+        usage.rows_scanned = 1000;
+        assert_eq!(quota.exceeded(&usage), Some(("rows_scanned", 1000, 1000)));
+        usage.queries = 10;
+        assert_eq!(quota.exceeded(&usage), Some(("queries", 10, 10)));
+
+        assert!(UsageQuota::default().is_unlimited());
+        assert_eq!(UsageQuota::default().exceeded(&usage), None);
+    }
+}
@@ -0,0 +1,156 @@
+//! Workload statistics models.
+//!
+//! Contains models for the per-connection breakdown of executed statements by type and table.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Statement category used for workload statistics.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementType {
+    /// SELECT / WITH / SHOW / EXPLAIN and other reads.
+    Select,
+    /// INSERT / REPLACE / COPY.
+    Insert,
+    /// UPDATE.
+    Update,
+    /// DELETE / TRUNCATE.
+    Delete,
+    /// CREATE / ALTER / DROP / RENAME / COMMENT.
+    Ddl,
+    /// Anything else (SET, transaction control, ...).
+    Other,
+}
+
+impl StatementType {
+    /// All statement types, in display order.
+    pub const ALL: [StatementType; 6] = [
+        StatementType::Select,
+        StatementType::Insert,
+        StatementType::Update,
+        StatementType::Delete,
+        StatementType::Ddl,
+        StatementType::Other,
+    ];
+
+    /// Classifies a statement by its leading keyword, skipping comments and
+    /// opening parentheses.
+    pub fn classify(sql: &str) -> Self {
+        let mut rest = sql.trim_start();
+        loop {
+            if let Some(after) = rest.strip_prefix("--") {
+                rest = after.split_once('\n').map_or("", |(_, r)| r).trim_start();
+            } else if let Some(after) = rest.strip_prefix("/*") {
+                rest = after.split_once("*/").map_or("", |(_, r)| r).trim_start();
+            } else if let Some(after) = rest.strip_prefix('(') {
+                rest = after.trim_start();
+            } else {
+                break;
+            }
+        }
+        let keyword: String = rest
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect::<String>()
+            .to_ascii_uppercase();
+        match keyword.as_str() {
+            "SELECT" | "WITH" | "SHOW" | "EXPLAIN" | "DESCRIBE" | "DESC" | "VALUES" | "TABLE" => {
+                StatementType::Select
+            }
+            "INSERT" | "REPLACE" | "COPY" | "LOAD" => StatementType::Insert,
+            "UPDATE" => StatementType::Update,
+            "DELETE" | "TRUNCATE" => StatementType::Delete,
+            "CREATE" | "ALTER" | "DROP" | "RENAME" | "COMMENT" => StatementType::Ddl,
+            _ => StatementType::Other,
+        }
+    }
+
+    /// Returns the string stored in the metadata database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatementType::Select => "select",
+            StatementType::Insert => "insert",
+            StatementType::Update => "update",
+            StatementType::Delete => "delete",
+            StatementType::Ddl => "ddl",
+            StatementType::Other => "other",
+        }
+    }
+
+    /// Parses the string stored in the metadata database.
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == s)
+    }
+}
+
+/// Execution counters for one statement type.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StatementTypeStats {
+    /// Statement type.
+    pub statement_type: StatementType,
+    /// Statements executed.
+    pub executions: u64,
+    /// Statements that failed.
+    pub errors: u64,
+    /// Total execution time in milliseconds.
+    pub total_time_ms: u64,
+    /// Average execution time in milliseconds.
+    pub avg_time_ms: f64,
+}
+
+/// Execution counters for one table and statement type.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TableWorkloadStats {
+    /// Table name, qualified when the statement qualified it.
+    pub table: String,
+    /// Statement type.
+    pub statement_type: StatementType,
+    /// Statements that referenced the table.
+    pub executions: u64,
+    /// Total execution time of those statements in milliseconds.
+    pub total_time_ms: u64,
+}
+
+/// Statement counts within one time bucket.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkloadBucket {
+    /// Bucket start (UTC, `YYYY-MM-DD HH:MM:SS`).
+    pub bucket_start: String,
+    /// Counters per statement type within the bucket.
+    pub by_type: Vec<StatementTypeStats>,
+}
+
+/// Workload breakdown of a connection over a time window.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkloadBreakdown {
+    /// Connection ID.
+    pub connection_id: String,
+    /// Window start (UTC).
+    pub from: String,
+    /// Window end (UTC).
+    pub to: String,
+    /// Bucket granularity: "hour" or "day".
+    pub granularity: String,
+    /// Totals per statement type.
+    pub by_type: Vec<StatementTypeStats>,
+    /// Most frequently referenced tables.
+    pub by_table: Vec<TableWorkloadStats>,
+    /// Totals per time bucket.
+    pub timeline: Vec<WorkloadBucket>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_statements_by_leading_keyword() {
+        assert_eq!(StatementType::classify("  -- note\n/* x */ (SELECT 1)"), StatementType::Select);
+        assert_eq!(StatementType::classify("with t as (select 1) select * from t"), StatementType::Select);
+        assert_eq!(StatementType::classify("INSERT INTO t VALUES (1)"), StatementType::Insert);
+        assert_eq!(StatementType::classify("truncate t"), StatementType::Delete);
+        assert_eq!(StatementType::classify("ALTER TABLE t ADD c INT"), StatementType::Ddl);
+        assert_eq!(StatementType::classify("SET NAMES utf8mb4"), StatementType::Other);
+    }
+}
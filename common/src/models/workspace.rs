@@ -0,0 +1,140 @@
+//! Workspace models.
+//!
+//! A workspace groups the connections and saved queries (query snapshots) a
+//! team works with. Principals are added as members; owners manage the
+//! workspace and its members. List endpoints can be narrowed to the active
+//! workspace sent in the `X-Workspace-Id` header.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Role of a principal in a workspace.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceRole {
+    /// May rename and delete the workspace and manage its members.
+    Owner,
+    /// May add and remove the workspace's connections and saved queries.
+    #[default]
+    Member,
+}
+
+impl WorkspaceRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkspaceRole::Owner => "owner",
+            WorkspaceRole::Member => "member",
+        }
+    }
+}
+
+impl FromStr for WorkspaceRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(WorkspaceRole::Owner),
+            "member" => Ok(WorkspaceRole::Member),
+            _ => Err(format!("unknown workspace role: {}", s)),
+        }
+    }
+}
+
+/// Kind of resource grouped in a workspace.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceResourceKind {
+    /// Saved connection.
+    Connection,
+    /// Saved query (query snapshot).
+    Snapshot,
+}
+
+impl WorkspaceResourceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkspaceResourceKind::Connection => "connection",
+            WorkspaceResourceKind::Snapshot => "snapshot",
+        }
+    }
+}
+
+/// Request body for creating a workspace.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateWorkspaceRequest {
+    /// Display name.
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+    /// Description.
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    pub description: Option<String>,
+}
+
+/// Request body for renaming or describing a workspace; absent fields are kept.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateWorkspaceRequest {
+    /// New display name.
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: Option<String>,
+    /// New description; an empty string clears it.
+    #[validate(length(max = 500, message = "Description must be at most 500 characters"))]
+    pub description: Option<String>,
+}
+
+/// Request body for adding a member or changing a member's role.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AddWorkspaceMemberRequest {
+    /// Principal to add, e.g. `user:<id>` or `key:<id>`.
+    #[validate(length(min = 1, max = 128, message = "Principal must be 1-128 characters"))]
+    pub principal: String,
+    /// Role in the workspace (default: member).
+    #[serde(default)]
+    pub role: WorkspaceRole,
+}
+
+/// Workspace.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Workspace {
+    /// Workspace ID.
+    pub id: String,
+    /// Display name.
+    pub name: String,
+    /// Description.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Principal that created the workspace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// Creation timestamp.
+    pub created_at: String,
+    /// Last change timestamp.
+    pub updated_at: String,
+}
+
+/// Workspace with its members and grouped resources.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkspaceDetail {
+    /// Workspace.
+    #[serde(flatten)]
+    pub workspace: Workspace,
+    /// Members, owners first.
+    pub members: Vec<WorkspaceMember>,
+    /// IDs of the connections in the workspace.
+    pub connection_ids: Vec<String>,
+    /// IDs of the saved queries (query snapshots) in the workspace.
+    pub snapshot_ids: Vec<String>,
+}
+
+/// Member of a workspace.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkspaceMember {
+    /// Member principal.
+    pub principal: String,
+    /// Role in the workspace.
+    pub role: WorkspaceRole,
+    /// Time the principal was added.
+    pub added_at: String,
+}
@@ -0,0 +1,337 @@
+//! Notification channels.
+//!
+//! Every [`NotificationTarget`] maps to a [`NotificationChannel`]
+//! implementation: webhooks receive the notification as JSON, Slack
+//! channels a message through an incoming webhook, and email channels a
+//! plain-text mail sent through the SMTP relay of [`NotificationConfig`].
+//! New channel types are added by implementing [`NotificationChannel`] and
+//! mapping them in [`Notifier::channel`].
+//!
+//! Services send to explicit targets (e.g. the channels of an alert rule)
+//! with [`Notifier::send`], and publish service events (backup results,
+//! health changes) to the channels configured in `NOTIFY_*` with
+//! [`Notifier::publish`].
+//!
+//! The SMTP client is deliberately minimal: plain SMTP without TLS or
+//! authentication, meant for a relay on the internal network.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::Engine;
+use serde::Serialize;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::config::{NotificationConfig, SmtpConfig};
+use crate::errors::{AppError, AppResult};
+use crate::models::notification::NotificationTarget;
+
+/// Timeout of one notification, including connecting.
+const SEND_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Notification delivered to channels.
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    /// Event name, e.g. `alert.triggered`, `backup.failed`.
+    pub event: String,
+    /// One-line summary, used as the mail subject.
+    pub title: String,
+    /// Plain-text details.
+    pub text: String,
+    /// Event-specific fields, sent to webhooks as is.
+    pub data: serde_json::Value,
+    /// Time the event happened (UTC, RFC 3339).
+    pub sent_at: String,
+}
+
+impl Notification {
+    /// Creates a notification timestamped now.
+    pub fn new(event: &str, title: impl Into<String>, text: impl Into<String>, data: serde_json::Value) -> Self {
+        Self {
+            event: event.to_string(),
+            title: title.into(),
+            text: text.into(),
+            data,
+            sent_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Delivers notifications to one destination.
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Channel type name, recorded in logs and results.
+    fn kind(&self) -> &'static str;
+
+    /// Sends the notification.
+    async fn send(&self, notification: &Notification) -> AppResult<()>;
+}
+
+/// POSTs the notification as JSON.
+pub struct WebhookChannel {
+    http: reqwest::Client,
+    url: String,
+}
+
+#[async_trait]
+impl NotificationChannel for WebhookChannel {
+    fn kind(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send(&self, notification: &Notification) -> AppResult<()> {
+        post_json(&self.http, &self.url, notification).await
+    }
+}
+
+/// Posts a message to a Slack incoming webhook.
+pub struct SlackChannel {
+    http: reqwest::Client,
+    webhook_url: String,
+}
+
+#[async_trait]
+impl NotificationChannel for SlackChannel {
+    fn kind(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn send(&self, notification: &Notification) -> AppResult<()> {
+        let text = format!("*{}*\n{}", notification.title, notification.text);
+        post_json(&self.http, &self.webhook_url, &serde_json::json!({ "text": text })).await
+    }
+}
+
+/// Sends a plain-text mail through the SMTP relay.
+pub struct EmailChannel {
+    smtp: SmtpConfig,
+    to: Vec<String>,
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    fn kind(&self) -> &'static str {
+        "email"
+    }
+
+    async fn send(&self, notification: &Notification) -> AppResult<()> {
+        let message = mail_message(&self.smtp.from, &self.to, &notification.title, &notification.text);
+        tokio::time::timeout(SEND_TIMEOUT, send_mail(&self.smtp, &self.to, &message))
+            .await
+            .map_err(|_| AppError::Internal(format!("SMTP relay {} timed out", self.smtp.host)))?
+    }
+}
+
+/// Builds notification channels and sends notifications.
+pub struct Notifier {
+    http: reqwest::Client,
+    smtp: Option<SmtpConfig>,
+    events: Vec<NotificationTarget>,
+}
+
+impl Notifier {
+    /// Creates the notifier from the service configuration.
+    pub fn new(config: &NotificationConfig) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            smtp: config.smtp.clone(),
+            events: config.events.clone(),
+        }
+    }
+
+    /// Checks a target before it is saved.
+    ///
+    /// # Errors
+    /// Returns `AppError::InvalidInput` for URLs that are not http(s), invalid
+    /// recipient lists, or email targets without an SMTP relay.
+    pub fn check(&self, target: &NotificationTarget) -> AppResult<()> {
+        match target {
+            NotificationTarget::Webhook { url } | NotificationTarget::Slack { webhook_url: url } => {
+                let parsed = reqwest::Url::parse(url)
+                    .map_err(|e| AppError::InvalidInput(format!("invalid {} URL `{}`: {}", target.kind(), url, e)))?;
+                if !matches!(parsed.scheme(), "http" | "https") {
+                    return Err(AppError::InvalidInput(format!("{} URL must be http or https", target.kind())));
+                }
+                Ok(())
+            }
+            NotificationTarget::Email { to } => {
+                if to.is_empty() || to.iter().any(|a| !a.contains('@') || a.contains(['\r', '\n', '<', '>'])) {
+                    return Err(AppError::InvalidInput("email channel needs valid recipient addresses".to_string()));
+                }
+                if self.smtp.is_none() {
+                    return Err(AppError::InvalidInput("email channels need NOTIFY_SMTP_HOST".to_string()));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Builds the channel implementation for `target`.
+    pub fn channel(&self, target: &NotificationTarget) -> AppResult<Box<dyn NotificationChannel>> {
+        Ok(match target {
+            NotificationTarget::Webhook { url } => Box::new(WebhookChannel { http: self.http.clone(), url: url.clone() }),
+            NotificationTarget::Slack { webhook_url } => Box::new(SlackChannel {
+                http: self.http.clone(),
+                webhook_url: webhook_url.clone(),
+            }),
+            NotificationTarget::Email { to } => Box::new(EmailChannel {
+                smtp: self
+                    .smtp
+                    .clone()
+                    .ok_or_else(|| AppError::InvalidInput("email channels need NOTIFY_SMTP_HOST".to_string()))?,
+                to: to.clone(),
+            }),
+        })
+    }
+
+    /// Sends a notification to every target; one failing target does not
+    /// stop the others. Returns the outcome per target, in order.
+    pub async fn send(&self, targets: &[NotificationTarget], notification: &Notification) -> Vec<AppResult<()>> {
+        let mut outcomes = Vec::with_capacity(targets.len());
+        for target in targets {
+            let outcome = match self.channel(target) {
+                Ok(channel) => channel.send(notification).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = &outcome {
+                tracing::warn!(event = %notification.event, channel = target.kind(), error = %e, "Notification failed");
+            }
+            outcomes.push(outcome);
+        }
+        outcomes
+    }
+
+    /// Publishes a service event to the configured event channels in the
+    /// background; does nothing when none are configured.
+    pub fn publish(self: &Arc<Self>, notification: Notification) {
+        if self.events.is_empty() {
+            return;
+        }
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            notifier.send(&notifier.events, &notification).await;
+        });
+    }
+}
+
+async fn post_json<T: Serialize + ?Sized>(http: &reqwest::Client, url: &str, body: &T) -> AppResult<()> {
+    let response = http
+        .post(url)
+        .timeout(SEND_TIMEOUT)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to call {}: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::Internal(format!("{} returned {}", url, response.status())));
+    }
+    Ok(())
+}
+
+/// Builds a mail with headers; the subject is RFC 2047 encoded.
+fn mail_message(from: &str, to: &[String], subject: &str, body: &str) -> String {
+    let subject = base64::engine::general_purpose::STANDARD.encode(subject);
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: =?UTF-8?B?{}?=\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+        from,
+        to.join(", "),
+        subject,
+        chrono::Utc::now().to_rfc2822()
+    );
+    for line in body.lines() {
+        // Dot-stuffing: a leading dot would otherwise end the DATA section.
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message
+}
+
+async fn send_mail(smtp: &SmtpConfig, to: &[String], message: &str) -> AppResult<()> {
+    let stream = TcpStream::connect((smtp.host.as_str(), smtp.port))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to connect to SMTP relay {}: {}", smtp.host, e)))?;
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    expect_reply(&mut reader, &[220]).await?;
+    command(&mut reader, &mut writer, "EHLO localhost", &[250]).await?;
+    command(&mut reader, &mut writer, &format!("MAIL FROM:<{}>", smtp.from), &[250]).await?;
+    for recipient in to {
+        command(&mut reader, &mut writer, &format!("RCPT TO:<{}>", recipient), &[250, 251]).await?;
+    }
+    command(&mut reader, &mut writer, "DATA", &[354]).await?;
+    writer.write_all(message.as_bytes()).await.map_err(smtp_io)?;
+    command(&mut reader, &mut writer, ".", &[250]).await?;
+    command(&mut reader, &mut writer, "QUIT", &[221]).await?;
+    Ok(())
+}
+
+async fn command<R, W>(reader: &mut R, writer: &mut W, line: &str, expected: &[u16]) -> AppResult<()>
+where
+    R: AsyncBufReadExt + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    writer.write_all(format!("{}\r\n", line).as_bytes()).await.map_err(smtp_io)?;
+    writer.flush().await.map_err(smtp_io)?;
+    expect_reply(reader, expected).await
+}
+
+/// Reads a (possibly multi-line) reply and checks its code.
+async fn expect_reply<R: AsyncBufReadExt + Unpin>(reader: &mut R, expected: &[u16]) -> AppResult<()> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.map_err(smtp_io)? == 0 {
+            return Err(AppError::Internal("SMTP relay closed the connection".to_string()));
+        }
+        let code: u16 = line
+            .get(..3)
+            .and_then(|c| c.parse().ok())
+            .ok_or_else(|| AppError::Internal(format!("Invalid SMTP reply: {}", line.trim_end())))?;
+        // `250-` continues a multi-line reply, `250 ` ends it.
+        if line.as_bytes().get(3) == Some(&b'-') {
+            continue;
+        }
+        if !expected.contains(&code) {
+            return Err(AppError::Internal(format!("SMTP relay rejected the mail: {}", line.trim_end())));
+        }
+        return Ok(());
+    }
+}
+
+fn smtp_io(e: std::io::Error) -> AppError {
+    AppError::Internal(format!("SMTP I/O error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_mail_with_dot_stuffing() {
+        let message = mail_message("alerts@example.com", &["ops@example.com".to_string()], "磁盘告警", "line one\n.hidden\n");
+        assert!(message.contains("To: ops@example.com\r\n"));
+        assert!(message.contains("Subject: =?UTF-8?B?"));
+        assert!(message.ends_with("line one\r\n..hidden\r\n"));
+    }
+
+    #[tokio::test]
+    async fn reads_multi_line_smtp_replies() {
+        let mut reply = BufReader::new(&b"250-relay\r\n250-SIZE 1000\r\n250 OK\r\n550 no\r\n"[..]);
+        assert!(expect_reply(&mut reply, &[250]).await.is_ok());
+        assert!(expect_reply(&mut reply, &[250]).await.is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_targets() {
+        let notifier = Notifier::new(&NotificationConfig::default());
+        assert!(notifier.check(&NotificationTarget::Webhook { url: "https://ops.example.com/hook".into() }).is_ok());
+        assert!(notifier.check(&NotificationTarget::Slack { webhook_url: "ftp://x".into() }).is_err());
+        assert!(notifier.check(&NotificationTarget::Email { to: vec!["dba@example.com".into()] }).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+//! OpenAPI response examples.
+//!
+//! [`ResponseExamples`] is a utoipa modifier shared by all services. It fills
+//! in example payloads so the generated documentation shows what clients
+//! actually receive:
+//!
+//! - success responses get an example generated from their schema, wrapped
+//!   in the `ApiResponse` envelope where the endpoint uses it;
+//! - error responses (4xx / 5xx) get the `ErrorResponse` schema and one
+//!   example per error code returned with that HTTP status, rendered by
+//!   [`AppError::response_body`] itself.
+
+use serde_json::{json, Map, Value};
+use utoipa::openapi::example::ExampleBuilder;
+use utoipa::openapi::path::Operation;
+use utoipa::openapi::{ContentBuilder, OpenApi, Ref, RefOr};
+use utoipa::{Modify, PartialSchema, ToSchema};
+
+use crate::db_error::DbErrorDetails;
+use crate::errors::AppError;
+use crate::models::monitor::{TargetHealth, TargetHealthStatus};
+use crate::models::query::{ConfirmationRequired, DangerousStatementKind};
+use crate::models::auth::LoginLocked;
+use crate::models::usage::QuotaExceeded;
+use crate::response::{code, ErrorResponse};
+
+const JSON_CONTENT: &str = "application/json";
+
+/// Timestamp used in examples, so the document is stable between builds.
+const EXAMPLE_TIMESTAMP: &str = "2024-01-01T00:00:00Z";
+
+/// Nesting limit when expanding schemas, guarding against recursive types.
+const MAX_DEPTH: usize = 8;
+
+/// Adds success and error examples to every operation.
+pub struct ResponseExamples;
+
+impl Modify for ResponseExamples {
+    fn modify(&self, openapi: &mut OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        let mut schemas = vec![(ErrorResponse::name().into_owned(), ErrorResponse::schema())];
+        ErrorResponse::schemas(&mut schemas);
+        for (name, schema) in schemas {
+            components.schemas.entry(name).or_insert(schema);
+        }
+        let definitions = serde_json::to_value(&components.schemas)
+            .ok()
+            .and_then(|v| v.as_object().cloned())
+            .unwrap_or_default();
+
+        let errors = sample_errors();
+        for item in openapi.paths.paths.values_mut() {
+            for operation in [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+                &mut item.head,
+                &mut item.options,
+                &mut item.trace,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                add_examples(operation, &definitions, &errors);
+            }
+        }
+    }
+}
+
+fn add_examples(operation: &mut Operation, definitions: &Map<String, Value>, errors: &[AppError]) {
+    for (status, response) in operation.responses.responses.iter_mut() {
+        let RefOr::T(response) = response else {
+            continue;
+        };
+
+        if status.starts_with('2') {
+            for content in response.content.values_mut() {
+                if content.example.is_some() || !content.examples.is_empty() {
+                    continue;
+                }
+                if let Some(schema) = content.schema.as_ref().and_then(|s| serde_json::to_value(s).ok()) {
+                    content.example = Some(success_example(&schema, definitions));
+                }
+            }
+            continue;
+        }
+
+        let Ok(status) = status.parse::<u16>() else {
+            continue;
+        };
+        if status < 400 || !response.content.is_empty() {
+            continue;
+        }
+        let mut seen = Vec::new();
+        let examples: Vec<_> = errors
+            .iter()
+            .filter(|e| e.status_code().as_u16() == status)
+            .filter(|e| {
+                let new = !seen.contains(&e.code());
+                seen.push(e.code());
+                new
+            })
+            .map(|e| {
+                let mut body = e.response_body();
+                body["meta"]["timestamp"] = json!(EXAMPLE_TIMESTAMP);
+                (
+                    e.code(),
+                    ExampleBuilder::new().summary(e.code()).value(Some(body)).build(),
+                )
+            })
+            .collect();
+        response.content.insert(
+            JSON_CONTENT.to_string(),
+            ContentBuilder::new()
+                .schema(Some(Ref::from_schema_name(ErrorResponse::name())))
+                .examples_from_iter(examples)
+                .build(),
+        );
+    }
+}
+
+/// Builds a success example; `ApiResponse` envelopes get realistic envelope fields.
+fn success_example(schema: &Value, definitions: &Map<String, Value>) -> Value {
+    let resolved = resolve(schema, definitions);
+    let properties = &resolved["properties"];
+    if properties.get("success").is_some() && properties.get("data").is_some() {
+        return json!({
+            "code": code::SUCCESS,
+            "message": "操作成功",
+            "success": true,
+            "data": example(&properties["data"], definitions, 0),
+            "meta": { "timestamp": EXAMPLE_TIMESTAMP }
+        });
+    }
+    example(schema, definitions, 0)
+}
+
+/// Follows a `$ref` to its component schema.
+fn resolve<'a>(schema: &'a Value, definitions: &'a Map<String, Value>) -> &'a Value {
+    schema["$ref"]
+        .as_str()
+        .and_then(|r| r.strip_prefix("#/components/schemas/"))
+        .and_then(|name| definitions.get(name))
+        .unwrap_or(schema)
+}
+
+/// Generates an example value from a JSON schema.
+pub fn example(schema: &Value, definitions: &Map<String, Value>, depth: usize) -> Value {
+    if depth > MAX_DEPTH {
+        return Value::Null;
+    }
+    let schema = resolve(schema, definitions);
+    if let Some(example) = schema.get("example") {
+        return example.clone();
+    }
+    if let Some(example) = schema["examples"].as_array().and_then(|e| e.first()) {
+        return example.clone();
+    }
+    if let Some(default) = schema.get("default") {
+        return default.clone();
+    }
+    if let Some(first) = schema["enum"].as_array().and_then(|e| e.first()) {
+        return first.clone();
+    }
+    for composite in ["oneOf", "anyOf"] {
+        if let Some(variants) = schema[composite].as_array() {
+            let variant = variants
+                .iter()
+                .find(|v| v["type"] != "null")
+                .or_else(|| variants.first());
+            return variant.map_or(Value::Null, |v| example(v, definitions, depth + 1));
+        }
+    }
+    if let Some(parts) = schema["allOf"].as_array() {
+        let mut merged = Map::new();
+        for part in parts {
+            match example(part, definitions, depth + 1) {
+                Value::Object(fields) => merged.extend(fields),
+                other if parts.len() == 1 => return other,
+                _ => {}
+            }
+        }
+        return Value::Object(merged);
+    }
+
+    let schema_type = match &schema["type"] {
+        Value::String(t) => t.as_str(),
+        Value::Array(types) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null")
+            .unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ => return Value::Null,
+    };
+    match schema_type {
+        "object" => {
+            let mut object = Map::new();
+            if let Some(properties) = schema["properties"].as_object() {
+                for (name, property) in properties {
+                    object.insert(name.clone(), example(property, definitions, depth + 1));
+                }
+            } else if let Some(values) = schema.get("additionalProperties").filter(|v| v.is_object()) {
+                object.insert("key".to_string(), example(values, definitions, depth + 1));
+            }
+            Value::Object(object)
+        }
+        "array" => json!([example(&schema["items"], definitions, depth + 1)]),
+        "string" => match schema["format"].as_str() {
+            Some("date-time") => json!(EXAMPLE_TIMESTAMP),
+            Some("date") => json!("2024-01-01"),
+            Some("uuid") => json!("3fa85f64-5717-4562-b3fc-2c963f66afa6"),
+            _ => json!("string"),
+        },
+        "integer" => json!(0),
+        "number" => json!(0.0),
+        "boolean" => json!(true),
+        _ => Value::Null,
+    }
+}
+
+/// One error per `AppError` variant, as documented in error examples.
+fn sample_errors() -> Vec<AppError> {
+    vec![
+        AppError::InvalidInput("limit must be between 1 and 10000".into()),
+        AppError::Validation("name: Name must be 1-100 characters".into()),
+        AppError::NotFound("Policy 5f0c…".into()),
+        AppError::ConnectionNotFound("conn_001".into()),
+        AppError::Unauthorized,
+        AppError::Forbidden("table orders is not in the connection allowlist".into()),
+        AppError::Conflict("connection conn_001 already exists".into()),
+        AppError::UnsafeSql("DROP statements are not allowed".into()),
+        AppError::PayloadTooLarge("Failed to buffer the request body: length limit exceeded".into()),
+        AppError::InvalidJson(
+            "Failed to parse the request body as JSON: EOF while parsing an object at line 1 column 12".into(),
+        ),
+        AppError::QuotaExceeded(Box::new(QuotaExceeded {
+            principal: "key:k_1".into(),
+            limit: "queries".into(),
+            used: 1000,
+            allowed: 1000,
+            resets_at: EXAMPLE_TIMESTAMP.parse().unwrap_or_default(),
+        })),
+        AppError::LoginLocked(Box::new(LoginLocked {
+            retry_after_secs: 120,
+            locked_until: EXAMPLE_TIMESTAMP.parse().unwrap_or_default(),
+        })),
+        AppError::DatabaseConnection("Connection refused".into()),
+        AppError::DatabaseQuery("Lost connection to server during query".into()),
+        AppError::Database(Box::new(DbErrorDetails::from_mysql(
+            1064,
+            Some("42000".into()),
+            "You have an error in your SQL syntax; check the manual that corresponds to your MySQL server version for the right syntax to use near 'FORM users' at line 1",
+        ))),
+        AppError::Database(Box::new(DbErrorDetails::from_mysql(
+            1062,
+            Some("23000".into()),
+            "Duplicate entry 'alice@example.com' for key 'users.uk_email'",
+        ))),
+        AppError::RedisConnection("Connection refused".into()),
+        AppError::RedisOperation("WRONGTYPE Operation against a key holding the wrong kind of value".into()),
+        AppError::Internal("unexpected state".into()),
+        AppError::Configuration("missing setting".into()),
+        AppError::ExternalService("connection service returned an invalid response".into()),
+        AppError::Timeout("query exceeded the 30000 ms timeout".into()),
+        AppError::ServiceUnavailable("connection service unavailable".into()),
+        AppError::UnsupportedDatabaseType("This operation is only supported for MySQL connections".into()),
+        AppError::DegradedTarget(Box::new(TargetHealth {
+            connection_id: "conn_001".into(),
+            status: TargetHealthStatus::Degraded,
+            reasons: vec!["replication lag 600 s".into()],
+            connection_usage: Some(0.42),
+            replication_running: Some(true),
+            replication_lag_secs: Some(600),
+            checked_at: EXAMPLE_TIMESTAMP.into(),
+        })),
+        AppError::ConfirmationRequired(Box::new(ConfirmationRequired {
+            requires_confirmation: true,
+            kind: DangerousStatementKind::UnfilteredChange,
+            reason: "DELETE without a WHERE clause modifies every row of the table".into(),
+            estimated_affected_rows: Some(12840),
+            confirmation_token: "3fa85f64-5717-4562-b3fc-2c963f66afa6".into(),
+            expires_at: EXAMPLE_TIMESTAMP.into(),
+        })),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utoipa::OpenApi as _;
+
+    use crate::response::ApiResponse;
+
+    #[derive(serde::Serialize, ToSchema)]
+    struct Item {
+        id: String,
+        count: u32,
+        tags: Vec<String>,
+        note: Option<String>,
+    }
+
+    /// 获取条目
+    #[utoipa::path(
+        get,
+        path = "/items/{id}",
+        responses(
+            (status = 200, description = "条目", body = ApiResponse<Item>),
+            (status = 404, description = "未找到")
+        )
+    )]
+    #[allow(dead_code)]
+    fn get_item() {}
+
+    #[derive(utoipa::OpenApi)]
+    #[openapi(paths(get_item), components(schemas(Item)), modifiers(&ResponseExamples))]
+    struct Doc;
+
+    #[test]
+    fn operations_get_success_and_error_examples() {
+        let doc = serde_json::to_value(Doc::openapi()).unwrap();
+        let responses = &doc["paths"]["/items/{id}"]["get"]["responses"];
+
+        let success = &responses["200"]["content"]["application/json"]["example"];
+        assert_eq!(success["code"], 200);
+        assert_eq!(success["data"]["id"], "string");
+        assert_eq!(success["data"]["tags"], json!(["string"]));
+
+        let not_found = &responses["404"]["content"]["application/json"];
+        assert_eq!(not_found["schema"]["$ref"], "#/components/schemas/ErrorResponse");
+        assert_eq!(not_found["examples"]["NOT_FOUND"]["value"]["error"]["code"], "NOT_FOUND");
+        assert!(not_found["examples"]["CONNECTION_NOT_FOUND"].is_object());
+        assert!(doc["components"]["schemas"]["ErrorResponse"].is_object());
+    }
+}
@@ -0,0 +1,126 @@
+//! Liveness and readiness probes.
+//!
+//! Every service serves `/healthz` (liveness: the process answers requests)
+//! and `/readyz` (readiness: the dependencies the service needs to handle
+//! traffic respond). Readiness runs the service's [`ProbeCheck`]s and answers
+//! 503 with the failed checks when any of them fails, so an orchestrator such
+//! as Kubernetes stops routing to the instance without restarting it.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Time a single readiness check may take before it counts as failed.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Liveness probe response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Liveness {
+    /// Always `ok`.
+    pub status: String,
+    /// Service name.
+    pub service: String,
+    /// Service version.
+    pub version: String,
+}
+
+impl Liveness {
+    /// Liveness of `service`.
+    pub fn new(service: &str) -> Self {
+        Self {
+            status: "ok".to_string(),
+            service: service.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// Result of one readiness check.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ProbeCheck {
+    /// Checked dependency, e.g. `metadata_db`.
+    pub name: String,
+    /// Whether the dependency responded.
+    pub ok: bool,
+    /// Time the check took.
+    pub latency_ms: u64,
+    /// Why the check failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ProbeCheck {
+    /// Runs `check`, failing it after [`CHECK_TIMEOUT`].
+    pub async fn run<F>(name: &str, check: F) -> Self
+    where
+        F: Future<Output = Result<(), String>>,
+    {
+        let start = Instant::now();
+        let result = tokio::time::timeout(CHECK_TIMEOUT, check)
+            .await
+            .unwrap_or_else(|_| Err(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())));
+        Self {
+            name: name.to_string(),
+            ok: result.is_ok(),
+            latency_ms: start.elapsed().as_millis() as u64,
+            error: result.err(),
+        }
+    }
+}
+
+/// Readiness probe response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Readiness {
+    /// Whether every check passed.
+    pub ready: bool,
+    /// Service name.
+    pub service: String,
+    /// Time of the probe.
+    pub timestamp: DateTime<Utc>,
+    /// Results of the checks.
+    pub checks: Vec<ProbeCheck>,
+}
+
+impl Readiness {
+    /// Readiness of `service` from its check results.
+    pub fn new(service: &str, checks: Vec<ProbeCheck>) -> Self {
+        Self {
+            ready: checks.iter().all(|c| c.ok),
+            service: service.to_string(),
+            timestamp: Utc::now(),
+            checks,
+        }
+    }
+}
+
+/// 200 when ready, 503 otherwise.
+impl IntoResponse for Readiness {
+    fn into_response(self) -> Response {
+        let status = if self.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+        (status, Json(self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn failed_checks_make_the_service_unready() {
+        let ok = ProbeCheck::run("metadata_db", async { Ok(()) }).await;
+        assert!(ok.ok && ok.error.is_none());
+        assert_eq!(Readiness::new("svc", vec![ok.clone()]).into_response().status(), StatusCode::OK);
+
+        let failed = ProbeCheck::run("upstream", async { Err("connection refused".to_string()) }).await;
+        let readiness = Readiness::new("svc", vec![ok, failed]);
+        assert!(!readiness.ready);
+        assert_eq!(readiness.checks[1].error.as_deref(), Some("connection refused"));
+        assert_eq!(readiness.into_response().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
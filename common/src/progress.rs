@@ -0,0 +1,230 @@
+//! Progress channel for long-running jobs.
+//!
+//! Job managers (async queries, backups, restores, data transfers) publish a
+//! [`ProgressEvent`] to the [`ProgressHub`] whenever a job changes. A
+//! [`ProgressSubscription`] answers `GET /api/jobs/{id}/events` as a
+//! Server-Sent Events stream: the job's latest event first, then every later
+//! one, ending after the event that finishes the job. Events carry the whole
+//! job state, so a subscriber that falls behind skips to newer events instead
+//! of replaying the missed ones, and a reconnecting client catches up from the
+//! first event it receives.
+//!
+//! Channels live in memory in the service running the job. Finished jobs stay
+//! in the hub for [`FINISHED_RETENTION`]; after that the services answer with
+//! a single event built from the stored job.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use futures::{Stream, StreamExt};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+/// Events buffered per job for slow subscribers.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// How long a finished job's channel is kept.
+pub const FINISHED_RETENTION: Duration = Duration::from_secs(300);
+
+/// Job kinds reporting progress, as named by [`JobKind`](crate::models::job::JobKind).
+pub mod kinds {
+    use crate::models::job::JobKind;
+
+    /// Async query (query-service).
+    pub const QUERY: &str = JobKind::Query.as_str();
+    /// Backup (connection-service).
+    pub const BACKUP: &str = JobKind::Backup.as_str();
+    /// Restore / SQL import (connection-service).
+    pub const RESTORE: &str = JobKind::Restore.as_str();
+    /// Data transfer between connections (connection-service).
+    pub const TRANSFER: &str = JobKind::Transfer.as_str();
+}
+
+/// A change of a job, sent as one SSE event (`progress`, or `finished` for the last one).
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProgressEvent {
+    /// Job ID.
+    pub job_id: String,
+    /// Job kind (see [`kinds`]).
+    pub kind: String,
+    /// Job status, e.g. `running` or `completed`.
+    pub status: String,
+    /// Completion percentage, when the job reports one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<f64>,
+    /// What the job is working on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Whether the job has ended; no further events follow.
+    pub finished: bool,
+    /// Job state as returned by the job's own endpoint.
+    #[schema(value_type = Object)]
+    pub job: serde_json::Value,
+    /// Event time (RFC 3339, UTC).
+    pub occurred_at: String,
+}
+
+impl ProgressEvent {
+    /// Creates an event from a job; `status` and `progress` are read from its fields.
+    pub fn new<T: Serialize>(kind: &str, job_id: &str, job: &T, finished: bool) -> Self {
+        let job = serde_json::to_value(job).unwrap_or_default();
+        Self {
+            job_id: job_id.to_string(),
+            kind: kind.to_string(),
+            status: job["status"].as_str().unwrap_or_default().to_string(),
+            progress: job["progress"].as_f64(),
+            detail: None,
+            finished,
+            job,
+            occurred_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    fn to_sse(&self) -> Event {
+        let name = if self.finished { "finished" } else { "progress" };
+        Event::default()
+            .event(name)
+            .json_data(self)
+            .unwrap_or_else(|_| Event::default().comment("unserializable event"))
+    }
+}
+
+struct Channel {
+    sender: broadcast::Sender<ProgressEvent>,
+    latest: ProgressEvent,
+    finished_at: Option<Instant>,
+}
+
+/// Per-job progress channels of a service.
+#[derive(Default)]
+pub struct ProgressHub {
+    channels: Mutex<HashMap<String, Channel>>,
+}
+
+impl ProgressHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publishes a job change to its subscribers.
+    pub fn publish(&self, event: ProgressEvent) {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        channels.retain(|_, c| c.finished_at.is_none_or(|at| at.elapsed() < FINISHED_RETENTION));
+        let channel = channels.entry(event.job_id.clone()).or_insert_with(|| Channel {
+            sender: broadcast::channel(CHANNEL_CAPACITY).0,
+            latest: event.clone(),
+            finished_at: None,
+        });
+        if event.finished {
+            channel.finished_at = Some(Instant::now());
+        }
+        channel.latest = event.clone();
+        // No subscribers is not an error
+        let _ = channel.sender.send(event);
+    }
+
+    /// Reports progress of a running job between snapshots, keeping its last published state.
+    pub fn advance(&self, job_id: &str, progress: Option<f64>, detail: Option<String>) {
+        let mut channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(channel) = channels.get_mut(job_id).filter(|c| c.finished_at.is_none()) else {
+            return;
+        };
+        let mut event = channel.latest.clone();
+        event.progress = progress;
+        event.detail = detail;
+        event.occurred_at = chrono::Utc::now().to_rfc3339();
+        channel.latest = event.clone();
+        let _ = channel.sender.send(event);
+    }
+
+    /// Subscribes to a job's events; `None` if the job is not (or no longer) in the hub.
+    pub fn subscribe(&self, job_id: &str) -> Option<ProgressSubscription> {
+        let channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        let channel = channels.get(job_id)?;
+        Some(ProgressSubscription {
+            latest: channel.latest.clone(),
+            receiver: Some(channel.sender.subscribe()),
+        })
+    }
+}
+
+/// Events of one job, answered as a Server-Sent Events stream.
+pub struct ProgressSubscription {
+    latest: ProgressEvent,
+    receiver: Option<broadcast::Receiver<ProgressEvent>>,
+}
+
+impl ProgressSubscription {
+    /// A stream of a single event, for jobs that are no longer tracked by the hub.
+    pub fn once(event: ProgressEvent) -> Self {
+        Self { latest: event, receiver: None }
+    }
+
+    /// The events in order, ending after the finishing event.
+    pub fn into_stream(self) -> impl Stream<Item = ProgressEvent> + Send {
+        futures::stream::unfold((Some(self.latest), self.receiver), |(pending, receiver)| async move {
+            if let Some(event) = pending {
+                let receiver = receiver.filter(|_| !event.finished);
+                return Some((event, (None, receiver)));
+            }
+            let mut receiver = receiver?;
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        let receiver = (!event.finished).then_some(receiver);
+                        return Some((event, (None, receiver)));
+                    }
+                    // Events carry the whole state; the next one supersedes the missed ones
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
+
+impl IntoResponse for ProgressSubscription {
+    fn into_response(self) -> Response {
+        let events = self.into_stream().map(|event| Ok::<_, Infallible>(event.to_sse()));
+        Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(status: &str, finished: bool) -> ProgressEvent {
+        ProgressEvent::new(kinds::TRANSFER, "job-1", &json!({ "status": status, "progress": 0.0 }), finished)
+    }
+
+    #[tokio::test]
+    async fn streams_events_until_the_job_finishes() {
+        let hub = ProgressHub::new();
+        assert!(hub.subscribe("job-1").is_none());
+
+        hub.publish(event("running", false));
+        let subscription = hub.subscribe("job-1").unwrap();
+        hub.advance("job-1", Some(50.0), Some("batch 2".to_string()));
+        hub.publish(event("completed", true));
+        hub.advance("job-1", Some(60.0), None);
+
+        let events: Vec<ProgressEvent> = subscription.into_stream().collect().await;
+        let seen: Vec<_> = events.iter().map(|e| (e.status.as_str(), e.progress, e.finished)).collect();
+        assert_eq!(
+            seen,
+            vec![("running", Some(0.0), false), ("running", Some(50.0), false), ("completed", Some(0.0), true)]
+        );
+
+        // Late subscribers get the final state only
+        let late: Vec<ProgressEvent> = hub.subscribe("job-1").unwrap().into_stream().collect().await;
+        assert_eq!(late.len(), 1);
+        assert!(late[0].finished);
+    }
+}
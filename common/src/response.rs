@@ -39,6 +39,8 @@ pub mod code {
     pub const METHOD_NOT_ALLOWED: i32 = 405;
     /// 资源冲突（如重复创建）
     pub const CONFLICT: i32 = 409;
+    /// 请求体过大
+    pub const PAYLOAD_TOO_LARGE: i32 = 413;
     /// 参数校验失败
     pub const VALIDATION_ERROR: i32 = 422;
     /// 请求过于频繁
@@ -69,6 +71,12 @@ pub mod code {
     pub const OPERATION_NOT_ALLOWED: i32 = 704;
     /// 配置错误
     pub const CONFIG_ERROR: i32 = 705;
+    /// 危险语句需要确认后执行
+    pub const CONFIRMATION_REQUIRED: i32 = 706;
+    /// 用量配额已用尽
+    pub const QUOTA_EXCEEDED: i32 = 707;
+    /// 登录失败次数过多，暂时锁定
+    pub const LOGIN_LOCKED: i32 = 708;
 
     // ==================== 数据库相关 (8xx) ====================
     /// 数据库连接失败
@@ -81,6 +89,10 @@ pub mod code {
     pub const DB_CONNECTION_TEST_FAILED: i32 = 803;
     /// 不支持的数据库类型
     pub const DB_UNSUPPORTED_TYPE: i32 = 804;
+    /// 数据库认证失败
+    pub const DB_AUTH_FAILED: i32 = 805;
+    /// 数据库不存在
+    pub const DB_UNKNOWN_DATABASE: i32 = 806;
     /// SQL 执行错误
     pub const DB_QUERY_ERROR: i32 = 810;
     /// SQL 语法错误
@@ -91,6 +103,10 @@ pub mod code {
     pub const DB_QUERY_TIMEOUT: i32 = 813;
     /// 数据库连接池耗尽
     pub const DB_POOL_EXHAUSTED: i32 = 814;
+    /// 违反约束（唯一键、外键、非空、检查约束）
+    pub const DB_CONSTRAINT_VIOLATION: i32 = 815;
+    /// 目标数据库处于降级状态（高负载、复制中断）
+    pub const DB_TARGET_DEGRADED: i32 = 816;
     /// Redis 连接失败
     pub const REDIS_CONNECTION_ERROR: i32 = 820;
     /// Redis 操作失败
@@ -147,6 +163,25 @@ pub struct ApiError {
     pub details: Option<serde_json::Value>,
 }
 
+/// Error response body, as rendered by `AppError` (for API documentation).
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    /// 响应状态码（4xx=客户端错误，5xx=服务器错误，7xx=业务异常，8xx=数据库错误，9xx=外部服务错误）
+    pub code: i32,
+
+    /// 错误消息
+    pub message: String,
+
+    /// Always false.
+    pub success: bool,
+
+    /// Error details.
+    pub error: ApiError,
+
+    /// Response metadata.
+    pub meta: ResponseMeta,
+}
+
 /// Response metadata.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ResponseMeta {
@@ -164,6 +199,49 @@ pub struct ResponseMeta {
     /// Service name that handled the request.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub service: Option<String>,
+
+    /// Result cache information (for cacheable requests).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CacheInfo>,
+
+    /// Warnings about a request that succeeded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+
+    /// Gateway retry information (set when the gateway retried the upstream request).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryInfo>,
+}
+
+/// Gateway retry information attached to a response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RetryInfo {
+    /// Number of upstream attempts, including the first one.
+    pub attempts: u32,
+
+    /// Total time spent waiting between attempts in milliseconds.
+    pub backoff_ms: u64,
+
+    /// Why each retry happened ("connect", "timeout", "502", "503").
+    pub reasons: Vec<String>,
+}
+
+/// Result cache information attached to a response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CacheInfo {
+    /// Whether the result was served from the cache.
+    pub hit: bool,
+
+    /// Cache layer that served the result ("memory" or "redis").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+
+    /// When the cached result was produced.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_at: Option<DateTime<Utc>>,
+
+    /// Time-to-live of the cache entry in seconds.
+    pub ttl_secs: u64,
 }
 
 impl Default for ResponseMeta {
@@ -173,6 +251,9 @@ impl Default for ResponseMeta {
             timestamp: Utc::now(),
             duration_ms: None,
             service: None,
+            cache: None,
+            warnings: Vec::new(),
+            retry: None,
         }
     }
 }
@@ -311,6 +392,18 @@ impl<T: Serialize> ApiResponse<T> {
         }
     }
 
+    /// Sets the result cache information on the response.
+    pub fn with_cache(mut self, cache: CacheInfo) -> Self {
+        self.meta.cache = Some(cache);
+        self
+    }
+
+    /// Adds a warning to the response.
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.meta.warnings.push(warning.into());
+        self
+    }
+
     /// Sets the request ID on the response.
     pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
         self.meta.request_id = Some(request_id.into());
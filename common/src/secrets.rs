@@ -0,0 +1,532 @@
+//! External secrets backends.
+//!
+//! A connection may reference its password instead of storing it, e.g.
+//! `vault:kv/db/prod#password`. References have the form
+//! `<backend>:<path>[#<field>]`:
+//! - `vault:<mount>/<path>` - HashiCorp Vault KV secret
+//! - `aws:<secret-id>` - AWS Secrets Manager secret
+//! - `env:<VARIABLE>` - environment variable of the service
+//!
+//! Each backend only serves paths under its allowed prefixes, so a connection
+//! cannot reference the service's own credentials or another team's secrets.
+//! References outside them are rejected when the connection is saved (see
+//! [`SecretResolver::check`]) and again when they are resolved.
+//!
+//! Secrets whose value is a JSON object need a `#field`; plain string secrets
+//! must not have one. Backends implement [`SecretsProvider`]; the
+//! [`SecretResolver`] picks one by prefix and caches fetched secrets.
+//!
+//! Configuration:
+//! - `VAULT_ADDR`, `VAULT_TOKEN` - Vault server and token (backend disabled if unset)
+//! - `VAULT_NAMESPACE` - Vault Enterprise namespace (optional)
+//! - `VAULT_KV_VERSION` - KV engine version, 1 or 2 (default: 2)
+//! - `AWS_REGION`, `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` - AWS credentials
+//!   (backend disabled if unset), plus `AWS_SESSION_TOKEN` for temporary credentials
+//! - `AWS_SECRETS_MANAGER_ENDPOINT` - endpoint override (default: the regional endpoint)
+//! - `SECRETS_CACHE_TTL_SECS` - how long fetched secrets are cached (default: 300)
+//! - `SECRETS_ENV_PREFIX` - prefix of the variables `env:` may read (default: `DBM_SECRET_`)
+//! - `VAULT_ALLOWED_PATHS` - comma-separated Vault path prefixes, e.g. `kv/dbm/`
+//!   (no `vault:` reference is accepted if unset)
+//! - `AWS_SECRETS_ALLOWED_PREFIXES` - comma-separated secret name or ARN
+//!   prefixes (no `aws:` reference is accepted if unset)
+//!
+//! Rotation: cached secrets expire after the TTL, and callers invalidate a
+//! reference (see [`SecretResolver::invalidate`]) when the resolved value is
+//! rejected, so the next resolution fetches the current version.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use crate::errors::{AppError, AppResult};
+
+const DEFAULT_CACHE_TTL_SECS: u64 = 300;
+
+/// Prefix of the environment variables `env:` references may read by default.
+pub const DEFAULT_ENV_PREFIX: &str = "DBM_SECRET_";
+
+/// Parsed secret reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretRef {
+    /// Backend prefix (`vault`, `aws`, `env`).
+    pub backend: String,
+    /// Backend-specific secret path.
+    pub path: String,
+    /// Field of a JSON object secret.
+    pub field: Option<String>,
+}
+
+impl SecretRef {
+    /// Parses `<backend>:<path>[#<field>]`.
+    ///
+    /// # Errors
+    /// `AppError::Validation` if the backend or path is missing.
+    pub fn parse(reference: &str) -> AppResult<Self> {
+        let invalid = || {
+            AppError::Validation(format!(
+                "invalid secret reference '{}', expected <backend>:<path>[#<field>]",
+                reference
+            ))
+        };
+        let (backend, rest) = reference.trim().split_once(':').ok_or_else(invalid)?;
+        let (path, field) = match rest.rsplit_once('#') {
+            Some((path, field)) if !field.is_empty() => (path, Some(field.to_string())),
+            Some(_) => return Err(invalid()),
+            None => (rest, None),
+        };
+        if backend.is_empty() || path.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            backend: backend.to_ascii_lowercase(),
+            path: path.to_string(),
+            field,
+        })
+    }
+
+    /// Cache key of the secret (fields of one secret share an entry).
+    fn cache_key(&self) -> String {
+        format!("{}:{}", self.backend, self.path)
+    }
+
+    /// Extracts the referenced value from a fetched secret.
+    fn select(&self, secret: &Value) -> AppResult<String> {
+        match (&self.field, secret) {
+            (None, Value::String(s)) => Ok(s.clone()),
+            (None, _) => Err(AppError::Configuration(format!(
+                "secret '{}' is not a plain string, reference a field with #<field>",
+                self
+            ))),
+            (Some(field), Value::Object(fields)) => match fields.get(field) {
+                Some(Value::String(s)) => Ok(s.clone()),
+                Some(Value::Number(n)) => Ok(n.to_string()),
+                _ => Err(AppError::Configuration(format!("secret '{}' has no string field '{}'", self, field))),
+            },
+            (Some(_), _) => Err(AppError::Configuration(format!("secret '{}' has no fields", self))),
+        }
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.backend, self.path)?;
+        if let Some(field) = &self.field {
+            write!(f, "#{}", field)?;
+        }
+        Ok(())
+    }
+}
+
+/// A secrets backend.
+#[async_trait]
+pub trait SecretsProvider: Send + Sync {
+    /// Fetches the secret at `path`: a JSON object of fields or a plain string.
+    async fn fetch(&self, path: &str) -> AppResult<Value>;
+}
+
+/// Resolves secret references through the registered backends, caching
+/// fetched secrets for a TTL.
+pub struct SecretResolver {
+    providers: HashMap<String, Arc<dyn SecretsProvider>>,
+    /// Path prefixes of restricted backends; other backends serve any path.
+    allowed: HashMap<String, Vec<String>>,
+    cache: RwLock<HashMap<String, (Instant, Value)>>,
+    ttl: Duration,
+}
+
+impl SecretResolver {
+    /// Creates a resolver without backends.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            providers: HashMap::new(),
+            allowed: HashMap::new(),
+            cache: RwLock::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Creates a resolver with the backends configured in the environment,
+    /// each limited to its allowed prefixes; `env:` is always available for
+    /// the variables starting with `SECRETS_ENV_PREFIX`.
+    pub fn from_env() -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+        let ttl = var("SECRETS_CACHE_TTL_SECS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_TTL_SECS);
+        let prefixes = |key: &str| {
+            var(key)
+                .map(|v| v.split(',').map(str::trim).filter(|p| !p.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+        let env_prefix = var("SECRETS_ENV_PREFIX").unwrap_or_else(|| DEFAULT_ENV_PREFIX.to_string());
+
+        let mut resolver = Self::new(Duration::from_secs(ttl))
+            .with_provider("env", EnvProvider)
+            .with_allowed_prefixes("env", vec![env_prefix]);
+        if let Some(vault) = VaultProvider::from_env() {
+            resolver = resolver
+                .with_provider("vault", vault)
+                .with_allowed_prefixes("vault", prefixes("VAULT_ALLOWED_PATHS"));
+        }
+        if let Some(aws) = AwsSecretsManagerProvider::from_env() {
+            resolver = resolver
+                .with_provider("aws", aws)
+                .with_allowed_prefixes("aws", prefixes("AWS_SECRETS_ALLOWED_PREFIXES"));
+        }
+        resolver
+    }
+
+    /// Registers a backend under a reference prefix.
+    pub fn with_provider(mut self, backend: &str, provider: impl SecretsProvider + 'static) -> Self {
+        self.providers.insert(backend.to_ascii_lowercase(), Arc::new(provider));
+        self
+    }
+
+    /// Limits a backend to paths starting with one of `prefixes`; an empty
+    /// list rejects every path.
+    pub fn with_allowed_prefixes(mut self, backend: &str, prefixes: Vec<String>) -> Self {
+        self.allowed.insert(backend.to_ascii_lowercase(), prefixes);
+        self
+    }
+
+    /// Checks that a reference may be used: well formed, naming a configured
+    /// backend and a path under its allowed prefixes. Called when a
+    /// connection is saved, without fetching the secret.
+    ///
+    /// # Errors
+    /// `AppError::Validation` describing why the reference is rejected.
+    pub fn check(&self, reference: &str) -> AppResult<SecretRef> {
+        let reference = SecretRef::parse(reference)?;
+        if !self.providers.contains_key(&reference.backend) {
+            return Err(AppError::Validation(format!(
+                "secrets backend '{}' is not configured",
+                reference.backend
+            )));
+        }
+        if let Some(prefixes) = self.allowed.get(&reference.backend) {
+            let path = reference.path.trim_start_matches('/');
+            // `..` would let Vault resolve a path outside the prefix
+            let traverses = path.split('/').any(|segment| segment == ".." || segment == ".");
+            if traverses || !prefixes.iter().any(|prefix| path.starts_with(prefix.as_str())) {
+                return Err(AppError::Validation(format!(
+                    "secret reference '{}' is outside the allowed {} paths",
+                    reference, reference.backend
+                )));
+            }
+        }
+        Ok(reference)
+    }
+
+    /// Resolves a reference to its current value.
+    ///
+    /// # Errors
+    /// `AppError::Validation` for malformed or disallowed references (see
+    /// [`SecretResolver::check`]), `AppError::Configuration` if the secret
+    /// lacks the field, and the backend's error if fetching fails.
+    pub async fn resolve(&self, reference: &str) -> AppResult<String> {
+        let reference = self.check(reference)?;
+        let key = reference.cache_key();
+        if let Some((fetched_at, secret)) = self.cache.read().await.get(&key) {
+            if fetched_at.elapsed() < self.ttl {
+                return reference.select(secret);
+            }
+        }
+
+        let provider = &self.providers[&reference.backend];
+        let secret = provider.fetch(&reference.path).await?;
+        let value = reference.select(&secret)?;
+        self.cache.write().await.insert(key, (Instant::now(), secret));
+        Ok(value)
+    }
+
+    /// Drops the cached secret of a reference, e.g. after its value was
+    /// rejected because the secret has been rotated.
+    pub async fn invalidate(&self, reference: &str) {
+        if let Ok(reference) = SecretRef::parse(reference) {
+            self.cache.write().await.remove(&reference.cache_key());
+        }
+    }
+}
+
+/// Reads secrets from environment variables of the service.
+pub struct EnvProvider;
+
+#[async_trait]
+impl SecretsProvider for EnvProvider {
+    async fn fetch(&self, path: &str) -> AppResult<Value> {
+        std::env::var(path)
+            .map(Value::String)
+            .map_err(|_| AppError::Configuration(format!("environment variable {} is not set", path)))
+    }
+}
+
+/// HashiCorp Vault KV backend; paths are `<mount>/<secret path>`.
+pub struct VaultProvider {
+    http: reqwest::Client,
+    addr: String,
+    token: String,
+    namespace: Option<String>,
+    kv_version: u8,
+}
+
+impl VaultProvider {
+    /// Loads settings from `VAULT_ADDR` / `VAULT_TOKEN`; `None` if either is unset.
+    pub fn from_env() -> Option<Self> {
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        Some(Self {
+            http: reqwest::Client::new(),
+            addr: var("VAULT_ADDR")?.trim_end_matches('/').to_string(),
+            token: var("VAULT_TOKEN")?,
+            namespace: var("VAULT_NAMESPACE"),
+            kv_version: if var("VAULT_KV_VERSION").as_deref() == Some("1") { 1 } else { 2 },
+        })
+    }
+
+    fn url(&self, path: &str) -> AppResult<String> {
+        let (mount, secret) = path
+            .trim_matches('/')
+            .split_once('/')
+            .ok_or_else(|| AppError::Validation(format!("Vault path '{}' must be <mount>/<path>", path)))?;
+        Ok(match self.kv_version {
+            1 => format!("{}/v1/{}/{}", self.addr, mount, secret),
+            _ => format!("{}/v1/{}/data/{}", self.addr, mount, secret),
+        })
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for VaultProvider {
+    async fn fetch(&self, path: &str) -> AppResult<Value> {
+        let mut request = self.http.get(self.url(path)?).header("X-Vault-Token", &self.token);
+        if let Some(namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(AppError::ExternalService(format!("Vault returned {} for {}", status, path)));
+        }
+        let mut body: Value = response.json().await?;
+        let data = match self.kv_version {
+            1 => body["data"].take(),
+            _ => body["data"]["data"].take(),
+        };
+        if data.is_null() {
+            return Err(AppError::ExternalService(format!("Vault secret {} has no data", path)));
+        }
+        Ok(data)
+    }
+}
+
+/// AWS Secrets Manager backend; paths are secret names or ARNs.
+pub struct AwsSecretsManagerProvider {
+    http: reqwest::Client,
+    endpoint: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl AwsSecretsManagerProvider {
+    /// Loads settings from the standard AWS variables; `None` if the region
+    /// or the static credentials are unset.
+    pub fn from_env() -> Option<Self> {
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let region = var("AWS_REGION").or_else(|| var("AWS_DEFAULT_REGION"))?;
+        Some(Self {
+            http: reqwest::Client::new(),
+            endpoint: var("AWS_SECRETS_MANAGER_ENDPOINT")
+                .unwrap_or_else(|| format!("https://secretsmanager.{}.amazonaws.com", region)),
+            region,
+            access_key_id: var("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: var("AWS_SECRET_ACCESS_KEY")?,
+            session_token: var("AWS_SESSION_TOKEN"),
+        })
+    }
+
+    /// SigV4 `Authorization` header of a `GetSecretValue` call.
+    fn authorization(&self, host: &str, body: &str, now: chrono::DateTime<Utc>) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1"),
+            ("host", host),
+            ("x-amz-date", amz_date.as_str()),
+            ("x-amz-target", "secretsmanager.GetSecretValue"),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.insert(3, ("x-amz-security-token", token.as_str()));
+        }
+        let canonical_headers: String = headers.iter().map(|(k, v)| format!("{}:{}\n", k, v)).collect();
+        let signed_headers = headers.iter().map(|(k, _)| *k).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "POST\n/\n\n{}\n{}\n{}",
+            canonical_headers,
+            signed_headers,
+            hex::encode(Sha256::digest(body.as_bytes()))
+        );
+        let scope = format!("{}/{}/secretsmanager/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&self.secret_access_key, &date, &self.region, "secretsmanager");
+        let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        )
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for AwsSecretsManagerProvider {
+    async fn fetch(&self, path: &str) -> AppResult<Value> {
+        let url = reqwest::Url::parse(&self.endpoint)
+            .map_err(|e| AppError::Configuration(format!("invalid AWS_SECRETS_MANAGER_ENDPOINT: {}", e)))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(h), Some(p)) => format!("{}:{}", h, p),
+            (Some(h), None) => h.to_string(),
+            (None, _) => return Err(AppError::Configuration("AWS_SECRETS_MANAGER_ENDPOINT has no host".into())),
+        };
+        let body = serde_json::json!({ "SecretId": path }).to_string();
+        let now = Utc::now();
+
+        let mut request = self
+            .http
+            .post(url)
+            .header("content-type", "application/x-amz-json-1.1")
+            .header("host", &host)
+            .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+            .header("x-amz-target", "secretsmanager.GetSecretValue")
+            .header("authorization", self.authorization(&host, &body, now));
+        if let Some(token) = &self.session_token {
+            request = request.header("x-amz-security-token", token);
+        }
+        let response = request.body(body).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalService(format!(
+                "Secrets Manager returned {} for {}: {}",
+                status, path, message
+            )));
+        }
+        let body: Value = response.json().await?;
+        let secret = body["SecretString"]
+            .as_str()
+            .ok_or_else(|| AppError::ExternalService(format!("secret {} has no SecretString", path)))?;
+        // JSON secrets (key/value pairs) expose their fields, others are plain strings
+        Ok(serde_json::from_str::<Value>(secret)
+            .ok()
+            .filter(Value::is_object)
+            .unwrap_or_else(|| Value::String(secret.to_string())))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Derives the SigV4 signing key for a date, region and service.
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let k_date = hmac_sha256(format!("AWS4{}", secret_access_key).as_bytes(), date);
+    let k_region = hmac_sha256(&k_date, region);
+    let k_service = hmac_sha256(&k_region, service);
+    hmac_sha256(&k_service, "aws4_request")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counting(std::sync::atomic::AtomicUsize);
+
+    #[async_trait]
+    impl SecretsProvider for Counting {
+        async fn fetch(&self, _path: &str) -> AppResult<Value> {
+            let n = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(serde_json::json!({ "password": format!("v{}", n), "user": "app" }))
+        }
+    }
+
+    #[test]
+    fn parses_references() {
+        let reference = SecretRef::parse("vault:kv/db/prod#password").unwrap();
+        assert_eq!(reference.backend, "vault");
+        assert_eq!(reference.path, "kv/db/prod");
+        assert_eq!(reference.field.as_deref(), Some("password"));
+        assert_eq!(reference.to_string(), "vault:kv/db/prod#password");
+        assert_eq!(SecretRef::parse("env:DB_PASSWORD").unwrap().field, None);
+        assert!(SecretRef::parse("kv/db/prod").is_err());
+        assert!(SecretRef::parse("vault:kv/db#").is_err());
+    }
+
+    #[tokio::test]
+    async fn caches_secrets_until_invalidated() {
+        let resolver = SecretResolver::new(Duration::from_secs(60))
+            .with_provider("test", Counting(Default::default()));
+        assert_eq!(resolver.resolve("test:db#password").await.unwrap(), "v0");
+        assert_eq!(resolver.resolve("test:db#user").await.unwrap(), "app");
+        assert_eq!(resolver.resolve("test:db#password").await.unwrap(), "v0");
+
+        resolver.invalidate("test:db#password").await;
+        assert_eq!(resolver.resolve("test:db#password").await.unwrap(), "v1");
+        assert!(resolver.resolve("test:db").await.is_err());
+        assert!(resolver.resolve("vault:kv/db#password").await.is_err());
+    }
+
+    #[test]
+    fn limits_backends_to_allowed_prefixes() {
+        let resolver = SecretResolver::new(Duration::from_secs(60))
+            .with_provider("env", EnvProvider)
+            .with_allowed_prefixes("env", vec![DEFAULT_ENV_PREFIX.to_string()])
+            .with_provider("vault", Counting(Default::default()))
+            .with_allowed_prefixes("vault", vec!["kv/dbm/".to_string()])
+            .with_provider("aws", Counting(Default::default()))
+            .with_allowed_prefixes("aws", Vec::new());
+
+        assert!(resolver.check("env:DBM_SECRET_PROD").is_ok());
+        assert!(resolver.check("env:VAULT_TOKEN").is_err());
+        assert!(resolver.check("env:AWS_SECRET_ACCESS_KEY").is_err());
+        assert!(resolver.check("vault:kv/dbm/prod#password").is_ok());
+        assert!(resolver.check("vault:/kv/dbm/prod#password").is_ok());
+        assert!(resolver.check("vault:kv/payments/prod#password").is_err());
+        assert!(resolver.check("vault:kv/dbm/../payments/prod#password").is_err());
+        assert!(resolver.check("aws:dbm/prod").is_err());
+        assert!(resolver.check("gcp:projects/p/secrets/s").is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_disallowed_references_on_resolution() {
+        std::env::set_var("SECRETS_TEST_ONLY_TOKEN", "s3cret");
+        let resolver = SecretResolver::new(Duration::from_secs(60))
+            .with_provider("env", EnvProvider)
+            .with_allowed_prefixes("env", vec![DEFAULT_ENV_PREFIX.to_string()]);
+        assert!(matches!(
+            resolver.resolve("env:SECRETS_TEST_ONLY_TOKEN").await,
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn derives_sigv4_signing_key() {
+        // Example from the AWS Signature Version 4 documentation
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex::encode(key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+    }
+}
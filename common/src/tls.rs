@@ -0,0 +1,180 @@
+//! Optional TLS termination in the services.
+//!
+//! [`serve`] runs a router over plain TCP, or over TLS (rustls) when
+//! [`TlsConfig`] is set, so a service can be exposed directly without a
+//! terminating load balancer. TLS handshakes run off the accept loop with a
+//! timeout, so a slow or stalled client does not hold up other connections.
+//! [`https_redirect`] answers plain HTTP requests with a redirect to HTTPS.
+//! Handlers can read the peer address via `ConnectInfo<SocketAddr>`.
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::Request;
+use axum::http::{header, uri::Authority, StatusCode, Uri};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::serve::ListenerExt;
+use axum::Router;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{self, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::TlsConfig;
+
+/// Time a client has to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Completed handshakes waiting to be served.
+const ACCEPT_QUEUE: usize = 128;
+
+/// Serves the router on the listener, over TLS when configured.
+///
+/// Requests carry the peer address as `ConnectInfo<SocketAddr>`.
+///
+/// # Errors
+/// Returns an error if the certificate or key cannot be loaded, or the server fails.
+pub async fn serve(listener: TcpListener, app: Router, tls: Option<&TlsConfig>) -> io::Result<()> {
+    match tls {
+        Some(tls) => {
+            let acceptor = acceptor(tls)?;
+            tracing::info!(cert = %tls.cert_path, "TLS enabled");
+            // axum only provides `ConnectInfo` for `TcpListener` and tapped listeners
+            let listener = TlsListener::new(listener, acceptor)?.tap_io(|_| {});
+            axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await
+        }
+        None => axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await,
+    }
+}
+
+/// Router redirecting every request to the same host and path over HTTPS on `https_port`.
+pub fn https_redirect(https_port: u16) -> Router {
+    Router::new().fallback(move |req: Request| async move { redirect_target(&req, https_port) })
+}
+
+fn redirect_target(req: &Request, https_port: u16) -> Response {
+    let Some(authority) = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|host| host.parse::<Authority>().ok())
+    else {
+        return (StatusCode::BAD_REQUEST, "missing or invalid Host header").into_response();
+    };
+    let host = match https_port {
+        443 => authority.host().to_string(),
+        port => format!("{}:{}", authority.host(), port),
+    };
+    let path = req.uri().path_and_query().map_or("/", |p| p.as_str());
+    match Uri::builder().scheme("https").authority(host).path_and_query(path).build() {
+        // 308 keeps the method and body of non-GET requests
+        Ok(uri) => Redirect::permanent(&uri.to_string()).into_response(),
+        Err(_) => (StatusCode::BAD_REQUEST, "invalid request target").into_response(),
+    }
+}
+
+/// Builds the TLS acceptor from the PEM certificate chain and private key.
+fn acceptor(tls: &TlsConfig) -> io::Result<TlsAcceptor> {
+    let invalid = |what: &str, path: &str, e: &dyn std::fmt::Display| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("cannot load TLS {} {}: {}", what, path, e))
+    };
+    let certs = CertificateDer::pem_file_iter(&tls.cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| invalid("certificate", &tls.cert_path, &e))?;
+    if certs.is_empty() {
+        return Err(invalid("certificate", &tls.cert_path, &"no certificate found"));
+    }
+    let key = PrivateKeyDer::from_pem_file(&tls.key_path).map_err(|e| invalid("key", &tls.key_path, &e))?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| invalid("key", &tls.key_path, &e))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Listener yielding connections whose TLS handshake completed.
+struct TlsListener {
+    local_addr: SocketAddr,
+    incoming: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+}
+
+impl TlsListener {
+    fn new(listener: TcpListener, acceptor: TlsAcceptor) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (sender, incoming) = mpsc::channel(ACCEPT_QUEUE);
+        tokio::spawn(async move {
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        // Usually out of file descriptors; retrying at once would spin
+                        tracing::warn!(error = %e, "Failed to accept connection");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                };
+                let acceptor = acceptor.clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = sender.send((stream, addr)).await;
+                        }
+                        Ok(Err(e)) => tracing::debug!(client = %addr, error = %e, "TLS handshake failed"),
+                        Err(_) => tracing::debug!(client = %addr, "TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+        Ok(Self { local_addr, incoming })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(accepted) => accepted,
+            // The accept task never ends while the listener is alive
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    async fn location(host: &str, uri: &str, https_port: u16) -> String {
+        let response = https_redirect(https_port)
+            .oneshot(Request::post(uri).header(header::HOST, host).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        response.headers()[header::LOCATION].to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn redirects_to_https_on_the_same_host() {
+        assert_eq!(
+            location("dbm.example.com", "/api/query?x=1", 443).await,
+            "https://dbm.example.com/api/query?x=1"
+        );
+        assert_eq!(location("dbm.example.com:8080", "/", 8443).await, "https://dbm.example.com:8443/");
+        assert_eq!(location("[::1]:80", "/docs", 443).await, "https://[::1]/docs");
+    }
+}
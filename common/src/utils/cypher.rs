@@ -0,0 +1,253 @@
+//! Cypher statement analysis.
+//!
+//! A lightweight tokenizer for the Cypher queries run on Neo4j connections:
+//! it rejects clauses that write to the graph and recovers the column order of
+//! the final `RETURN` clause, which the Bolt driver does not report. String
+//! literals, backtick-quoted names and comments are never taken for keywords.
+
+use crate::errors::{AppError, AppResult};
+
+/// Read-only analysis of Cypher statements.
+pub struct CypherAnalyzer;
+
+/// Clauses that modify the graph, the schema or read external files.
+const WRITE_CLAUSES: &[&str] = &["CREATE", "MERGE", "DELETE", "DETACH", "SET", "REMOVE", "DROP", "FOREACH", "LOAD"];
+
+/// Procedures that only read the catalog.
+const READ_PROCEDURES: &[&str] = &[
+    "db.labels",
+    "db.relationshiptypes",
+    "db.propertykeys",
+    "db.indexes",
+    "db.constraints",
+    "db.info",
+];
+
+/// Keywords that end the item list of a `RETURN` clause.
+const RETURN_END_WORDS: &[&str] = &["ORDER", "SKIP", "OFFSET", "LIMIT", "UNION"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier or keyword (dotted names such as `n.name` are one word).
+    Word { text: String, quoted: bool, start: usize, end: usize },
+    Symbol { ch: char, start: usize },
+    /// String or number literal.
+    Literal { start: usize, end: usize },
+}
+
+impl Token {
+    fn keyword(&self) -> Option<String> {
+        match self {
+            Token::Word { text, quoted: false, .. } => Some(text.to_ascii_uppercase()),
+            _ => None,
+        }
+    }
+
+    fn is_symbol(&self, c: char) -> bool {
+        matches!(self, Token::Symbol { ch, .. } if *ch == c)
+    }
+
+    fn span(&self) -> (usize, usize) {
+        match self {
+            Token::Word { start, end, .. } | Token::Literal { start, end } => (*start, *end),
+            Token::Symbol { ch, start } => (*start, start + ch.len_utf8()),
+        }
+    }
+}
+
+impl CypherAnalyzer {
+    /// Checks that a query only reads the graph.
+    ///
+    /// Write clauses (`CREATE`, `MERGE`, `SET`, `DELETE`, `REMOVE`, `DROP`,
+    /// `FOREACH`, `LOAD CSV`) are rejected, and `CALL` is limited to catalog
+    /// procedures (`db.labels()`, `db.schema.*`, ...) and subqueries.
+    ///
+    /// # Errors
+    /// Returns `AppError::InvalidInput` naming the offending clause or procedure.
+    pub fn validate_read_only(cypher: &str) -> AppResult<()> {
+        let tokens = tokenize(cypher);
+        for (i, token) in tokens.iter().enumerate() {
+            let Some(keyword) = token.keyword() else {
+                continue;
+            };
+            // Labels, relationship types and map keys may reuse keyword names
+            let label = i > 0 && tokens[i - 1].is_symbol(':');
+            let map_key = tokens.get(i + 1).is_some_and(|t| t.is_symbol(':'));
+            if label || map_key {
+                continue;
+            }
+            if WRITE_CLAUSES.contains(&keyword.as_str()) {
+                return Err(AppError::InvalidInput(format!("Cypher {} clauses are not allowed, only read queries", keyword)));
+            }
+            if keyword == "CALL" {
+                match tokens.get(i + 1) {
+                    Some(t) if t.is_symbol('{') => {}
+                    Some(Token::Word { text, .. }) => {
+                        let name = text.to_ascii_lowercase();
+                        if !READ_PROCEDURES.contains(&name.as_str()) && !name.starts_with("db.schema.") {
+                            return Err(AppError::InvalidInput(format!("Procedure {} is not allowed in read queries", text)));
+                        }
+                    }
+                    _ => return Err(AppError::InvalidInput("Invalid CALL clause".into())),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Column names of the final `RETURN` clause in order: the alias of each
+    /// item, or its text as written (`n.name`, `count(*)`).
+    ///
+    /// Returns an empty list for `RETURN *` or queries without `RETURN`.
+    pub fn return_columns(cypher: &str) -> Vec<String> {
+        let tokens = tokenize(cypher);
+        let mut depth = 0usize;
+        let mut last_return = None;
+        for (i, token) in tokens.iter().enumerate() {
+            match token {
+                Token::Symbol { ch: '(' | '[' | '{', .. } => depth += 1,
+                Token::Symbol { ch: ')' | ']' | '}', .. } => depth = depth.saturating_sub(1),
+                _ if depth == 0 && token.keyword().as_deref() == Some("RETURN") => last_return = Some(i),
+                _ => {}
+            }
+        }
+        let Some(start) = last_return else {
+            return Vec::new();
+        };
+
+        let mut items: Vec<Vec<&Token>> = vec![Vec::new()];
+        let mut depth = 0usize;
+        for token in &tokens[start + 1..] {
+            match token {
+                Token::Symbol { ch: '(' | '[' | '{', .. } => depth += 1,
+                Token::Symbol { ch: ')' | ']' | '}', .. } => depth = depth.saturating_sub(1),
+                Token::Symbol { ch: ';', .. } if depth == 0 => break,
+                Token::Symbol { ch: ',', .. } if depth == 0 => {
+                    items.push(Vec::new());
+                    continue;
+                }
+                _ if depth == 0 && token.keyword().is_some_and(|k| RETURN_END_WORDS.contains(&k.as_str())) => break,
+                _ => {}
+            }
+            if let Some(item) = items.last_mut() {
+                item.push(token);
+            }
+        }
+        if let Some(first) = items.first_mut() {
+            if first.first().and_then(|t| t.keyword()).as_deref() == Some("DISTINCT") {
+                first.remove(0);
+            }
+        }
+
+        let mut columns = Vec::new();
+        for item in items {
+            let (Some(first), Some(last)) = (item.first(), item.last()) else {
+                continue;
+            };
+            if first.is_symbol('*') && item.len() == 1 {
+                return Vec::new();
+            }
+            let aliased = item.len() >= 3 && item[item.len() - 2].keyword().as_deref() == Some("AS");
+            match (aliased, last) {
+                (true, Token::Word { text, .. }) => columns.push(text.clone()),
+                _ => columns.push(cypher[first.span().0..last.span().1].trim().to_string()),
+            }
+        }
+        columns
+    }
+}
+
+fn tokenize(cypher: &str) -> Vec<Token> {
+    let chars: Vec<(usize, char)> = cypher.char_indices().collect();
+    let end_of = |idx: usize| chars.get(idx).map_or(cypher.len(), |(pos, _)| *pos);
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '/' if chars.get(i + 1).map(|(_, c)| *c) == Some('/') => {
+                while i < chars.len() && chars[i].1 != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1).map(|(_, c)| *c) == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i].1 == '*' && chars.get(i + 1).map(|(_, c)| *c) == Some('/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '\'' | '"' => {
+                i += 1;
+                while i < chars.len() && chars[i].1 != c {
+                    if chars[i].1 == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+                tokens.push(Token::Literal { start: pos, end: end_of(i.min(chars.len())) });
+            }
+            '`' => {
+                let mut text = String::new();
+                i += 1;
+                while i < chars.len() && chars[i].1 != '`' {
+                    text.push(chars[i].1);
+                    i += 1;
+                }
+                i += 1;
+                tokens.push(Token::Word { text, quoted: true, start: pos, end: end_of(i.min(chars.len())) });
+            }
+            c if c.is_ascii_digit() => {
+                while i < chars.len() && (chars[i].1.is_ascii_alphanumeric() || chars[i].1 == '.') {
+                    i += 1;
+                }
+                tokens.push(Token::Literal { start: pos, end: end_of(i) });
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' => {
+                let mut text = String::new();
+                while i < chars.len() && (chars[i].1.is_alphanumeric() || matches!(chars[i].1, '_' | '.' | '$')) {
+                    text.push(chars[i].1);
+                    i += 1;
+                }
+                tokens.push(Token::Word { text, quoted: false, start: pos, end: end_of(i) });
+            }
+            c => {
+                tokens.push(Token::Symbol { ch: c, start: pos });
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_write_clauses() {
+        assert!(CypherAnalyzer::validate_read_only("MATCH (n:Person)-[:KNOWS]->(m) RETURN n, m LIMIT 10").is_ok());
+        assert!(CypherAnalyzer::validate_read_only("MATCH (n:Set {set: 'CREATE'}) RETURN n.merge").is_ok());
+        assert!(CypherAnalyzer::validate_read_only("CALL db.labels() YIELD label RETURN label").is_ok());
+        assert!(CypherAnalyzer::validate_read_only("MATCH (n) CALL { WITH n RETURN 1 AS x } RETURN x").is_ok());
+        assert!(CypherAnalyzer::validate_read_only("match (n) detach delete n").is_err());
+        assert!(CypherAnalyzer::validate_read_only("MATCH (n) SET n.x = 1").is_err());
+        assert!(CypherAnalyzer::validate_read_only("LOAD CSV FROM 'file:///x' AS row RETURN row").is_err());
+        assert!(CypherAnalyzer::validate_read_only("CALL apoc.periodic.iterate('a', 'b', {})").is_err());
+    }
+
+    #[test]
+    fn reads_return_columns_in_order() {
+        assert_eq!(
+            CypherAnalyzer::return_columns("MATCH (n)-[r]->(m) RETURN DISTINCT n.name, count(r) AS degree, m ORDER BY degree"),
+            vec!["n.name", "degree", "m"]
+        );
+        assert_eq!(
+            CypherAnalyzer::return_columns("CALL { MATCH (a) RETURN a } RETURN a.`full name` AS `full name`, {k: 1, v: 2}"),
+            vec!["full name", "{k: 1, v: 2}"]
+        );
+        assert!(CypherAnalyzer::return_columns("MATCH (n) RETURN *").is_empty());
+    }
+}
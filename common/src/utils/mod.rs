@@ -1,8 +1,28 @@
 //! Utility functions and helpers.
 
+pub mod cypher;
+pub mod dsn;
 pub mod id_generator;
+pub mod result_diff;
+pub mod sql_ident;
+pub mod sql_lexer;
+pub mod sql_limit;
+pub mod sql_params;
+pub mod sql_preview;
+pub mod sql_splitter;
+pub mod sql_tables;
 pub mod sql_validator;
 
 // Re-export commonly used types
+pub use cypher::CypherAnalyzer;
+pub use dsn::Dsn;
 pub use id_generator::IdGenerator;
+pub use result_diff::ResultDiff;
+pub use sql_ident::quote_ident;
+pub use sql_lexer::SqlLexer;
+pub use sql_limit::SqlLimit;
+pub use sql_params::{PlaceholderStyle, SqlParams};
+pub use sql_preview::ChangePreviewSql;
+pub use sql_splitter::SqlSplitter;
+pub use sql_tables::{SqlTableExtractor, TableRef};
 pub use sql_validator::SqlValidator;
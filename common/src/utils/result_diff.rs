@@ -0,0 +1,214 @@
+//! Row-level comparison of two query results.
+//!
+//! Rows are aligned by key columns that must exist on both sides and be
+//! unique within each side; the non-key columns both sides share are
+//! compared. Numbers compare by value, so `1` and `1.0` from different
+//! databases are equal.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::query::{ChangedRow, QueryDiffResult, QueryResult};
+
+/// Compares query results row by row.
+pub struct ResultDiff;
+
+impl ResultDiff {
+    /// Aligns the rows of `left` and `right` by `key_columns` (case-insensitive)
+    /// and returns the added, removed and changed rows.
+    ///
+    /// `truncated` and `duration_ms` of the result are left for the caller.
+    ///
+    /// # Errors
+    /// Returns `AppError::InvalidInput` if a key column is missing on either
+    /// side or a key is not unique.
+    pub fn compare(left: &QueryResult, right: &QueryResult, key_columns: &[String]) -> AppResult<QueryDiffResult> {
+        let left_columns: Vec<String> = left.columns.iter().map(|c| c.name.clone()).collect();
+        let right_columns: Vec<String> = right.columns.iter().map(|c| c.name.clone()).collect();
+        let left_keys = key_indexes(&left_columns, key_columns, "左")?;
+        let right_keys = key_indexes(&right_columns, key_columns, "右")?;
+
+        // Non-key columns on both sides: (name, left index, right index)
+        let shared: Vec<(&str, usize, usize)> = left_columns
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !left_keys.contains(i))
+            .filter_map(|(i, name)| {
+                position(&right_columns, name).map(|j| (name.as_str(), i, j))
+            })
+            .collect();
+
+        let right_index = index_rows(&right.rows, &right_keys, "右")?;
+        let mut matched = vec![false; right.rows.len()];
+        let mut seen = HashMap::new();
+        let mut removed = Vec::new();
+        let mut changed = Vec::new();
+        let mut unchanged = 0;
+        for (row_index, row) in left.rows.iter().enumerate() {
+            let key = row_key(row, &left_keys);
+            if let Some(previous) = seen.insert(key.clone(), row_index) {
+                return Err(duplicate_key("左", &left.rows[previous], &left_keys));
+            }
+            let Some(&right_row_index) = right_index.get(&key) else {
+                removed.push(row.clone());
+                continue;
+            };
+            matched[right_row_index] = true;
+            let right_row = &right.rows[right_row_index];
+            let changed_columns: Vec<String> = shared
+                .iter()
+                .filter(|(_, i, j)| !same_value(cell(row, *i), cell(right_row, *j)))
+                .map(|(name, _, _)| name.to_string())
+                .collect();
+            if changed_columns.is_empty() {
+                unchanged += 1;
+            } else {
+                changed.push(ChangedRow {
+                    key: left_keys.iter().map(|i| cell(row, *i).clone()).collect(),
+                    changed_columns,
+                    left: row.clone(),
+                    right: right_row.clone(),
+                });
+            }
+        }
+        let added = right
+            .rows
+            .iter()
+            .zip(&matched)
+            .filter(|(_, matched)| !**matched)
+            .map(|(row, _)| row.clone())
+            .collect();
+
+        Ok(QueryDiffResult {
+            key_columns: key_columns.to_vec(),
+            left_columns,
+            right_columns,
+            added,
+            removed,
+            changed,
+            unchanged,
+            left_rows: left.rows.len(),
+            right_rows: right.rows.len(),
+            truncated: false,
+            duration_ms: 0,
+        })
+    }
+}
+
+fn position(columns: &[String], name: &str) -> Option<usize> {
+    columns.iter().position(|c| c.eq_ignore_ascii_case(name))
+}
+
+fn key_indexes(columns: &[String], key_columns: &[String], side: &str) -> AppResult<Vec<usize>> {
+    key_columns
+        .iter()
+        .map(|key| {
+            position(columns, key)
+                .ok_or_else(|| AppError::InvalidInput(format!("{}侧查询结果中没有键列 {}", side, key)))
+        })
+        .collect()
+}
+
+fn index_rows(
+    rows: &[Vec<Value>],
+    keys: &[usize],
+    side: &str,
+) -> AppResult<HashMap<Vec<String>, usize>> {
+    let mut index = HashMap::with_capacity(rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        if index.insert(row_key(row, keys), i).is_some() {
+            return Err(duplicate_key(side, row, keys));
+        }
+    }
+    Ok(index)
+}
+
+fn duplicate_key(side: &str, row: &[Value], keys: &[usize]) -> AppError {
+    let key: Vec<String> = keys.iter().map(|i| cell(row, *i).to_string()).collect();
+    AppError::InvalidInput(format!(
+        "键列在{}侧查询结果中不唯一: ({})",
+        side,
+        key.join(", ")
+    ))
+}
+
+fn cell(row: &[Value], index: usize) -> &Value {
+    row.get(index).unwrap_or(&Value::Null)
+}
+
+fn row_key(row: &[Value], keys: &[usize]) -> Vec<String> {
+    keys.iter().map(|i| canonical(cell(row, *i))).collect()
+}
+
+fn same_value(a: &Value, b: &Value) -> bool {
+    a == b || canonical(a) == canonical(b)
+}
+
+/// Canonical form of a value; `1` and `1.0` map to the same string.
+fn canonical(value: &Value) -> String {
+    match value {
+        Value::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(i), _, _) => i.to_string(),
+            (_, Some(u), _) => u.to_string(),
+            (_, _, Some(f)) if f.fract() == 0.0 && f.abs() < 1e15 => (f as i64).to_string(),
+            _ => n.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::query::ColumnInfo;
+    use serde_json::json;
+
+    fn result(columns: &[&str], rows: Vec<Vec<serde_json::Value>>) -> QueryResult {
+        let mut result = QueryResult::empty();
+        result.columns = columns
+            .iter()
+            .map(|name| ColumnInfo { name: name.to_string(), data_type: "text".into(), nullable: None, kind: None })
+            .collect();
+        result.row_count = rows.len();
+        result.rows = rows;
+        result
+    }
+
+    #[test]
+    fn aligns_rows_by_key() {
+        let left = result(
+            &["id", "name", "price"],
+            vec![
+                vec![json!(1), json!("apple"), json!(3)],
+                vec![json!(2), json!("pear"), json!(4)],
+                vec![json!(3), json!("plum"), json!(5)],
+            ],
+        );
+        let right = result(
+            &["ID", "price", "name", "stock"],
+            vec![
+                vec![json!(4), json!(6), json!("fig"), json!(10)],
+                vec![json!(2), json!(4.5), json!("pear"), json!(7)],
+                vec![json!(1), json!(3.0), json!("apple"), json!(0)],
+            ],
+        );
+        let diff = ResultDiff::compare(&left, &right, &["id".to_string()]).unwrap();
+        assert_eq!(diff.removed, [vec![json!(3), json!("plum"), json!(5)]]);
+        assert_eq!(diff.added, [vec![json!(4), json!(6), json!("fig"), json!(10)]]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].key, [json!(2)]);
+        assert_eq!(diff.changed[0].changed_columns, ["price"]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!((diff.left_rows, diff.right_rows), (3, 3));
+    }
+
+    #[test]
+    fn rejects_missing_or_duplicate_keys() {
+        let left = result(&["id", "v"], vec![vec![json!(1), json!("a")], vec![json!(1), json!("b")]]);
+        let right = result(&["id", "v"], vec![vec![json!(1), json!("a")]]);
+        assert!(ResultDiff::compare(&left, &right, &["id".to_string()]).is_err());
+        assert!(ResultDiff::compare(&right, &right, &["missing".to_string()]).is_err());
+    }
+}
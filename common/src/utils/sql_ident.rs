@@ -0,0 +1,30 @@
+//! Identifier quoting for generated SQL.
+//!
+//! Statements the services build themselves (migrations, backups, transfers,
+//! seeding, row estimates, index suggestions) quote table, column and index
+//! names here, so every generator escapes them the same way.
+
+use crate::models::connection::DbType;
+
+/// Quotes `ident` for `db_type`: backticks in MySQL and MariaDB, double quotes
+/// (standard SQL) elsewhere, with embedded quote characters doubled.
+pub fn quote_ident(db_type: &DbType, ident: &str) -> String {
+    if db_type.is_mysql_family() {
+        format!("`{}`", ident.replace('`', "``"))
+    } else {
+        format!("\"{}\"", ident.replace('"', "\"\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_and_escapes_identifiers_per_dialect() {
+        assert_eq!(quote_ident(&DbType::MySQL, "order`s"), "`order``s`");
+        assert_eq!(quote_ident(&DbType::MariaDB, "users"), "`users`");
+        assert_eq!(quote_ident(&DbType::Postgres, "Say \"hi\""), "\"Say \"\"hi\"\"\"");
+        assert_eq!(quote_ident(&DbType::SQLite, "t"), "\"t\"");
+    }
+}
@@ -0,0 +1,215 @@
+//! SQL lexical rules shared by the statement utilities.
+//!
+//! The splitter, the limit rewriter, the table extractor, the parameter binder,
+//! the change preview rewriter and the query-service predicate scanner and
+//! cache key normalizer all need to step over string
+//! literals, quoted identifiers, comments and PostgreSQL dollar-quoted bodies.
+//! They recognise that text here so the rules cannot drift between them.
+//! Positions are byte indices into the statement; every returned index lies
+//! on a character boundary.
+
+use crate::models::connection::DbType;
+
+/// Lexical differences between the supported dialects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    /// Backslash escapes in quoted text and `#` line comments (MySQL, MariaDB).
+    pub mysql: bool,
+    /// Dollar-quoted strings (PostgreSQL).
+    pub postgres: bool,
+    /// `[name]` identifiers (SQL Server).
+    pub brackets: bool,
+}
+
+impl Dialect {
+    /// Rules for SQL whose dialect is unknown: MySQL escapes and comments and
+    /// SQL Server brackets, so that text any dialect treats as quoted is skipped.
+    pub const ANY: Dialect = Dialect { mysql: true, postgres: false, brackets: true };
+
+    /// Rules of `db_type`.
+    pub fn of(db_type: &DbType) -> Self {
+        Self {
+            mysql: matches!(db_type, DbType::MySQL | DbType::MariaDB),
+            postgres: *db_type == DbType::Postgres,
+            brackets: *db_type == DbType::SqlServer,
+        }
+    }
+
+    /// Rules of the database type named `db_type` (`"postgres"`, `"mysql"`,
+    /// ...), or [`Dialect::ANY`] for an unknown name.
+    pub fn named(db_type: &str) -> Self {
+        db_type.parse::<DbType>().map_or(Self::ANY, |t| Self::of(&t))
+    }
+}
+
+/// Kind of text that is not SQL code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Skip {
+    /// `-- ...`, `# ...` (MySQL) or `/* ... */`.
+    Comment,
+    /// `'...'` or `$tag$...$tag$` (PostgreSQL).
+    String,
+    /// `"..."`, `` `...` `` or `[...]` (SQL Server); MySQL's double-quoted
+    /// strings lex the same way.
+    Identifier,
+}
+
+/// Lexer for the quoted and commented parts of SQL statements.
+pub struct SqlLexer;
+
+impl SqlLexer {
+    /// Returns the kind of the comment, string or quoted identifier starting
+    /// at byte `i` and the index just past it, or `None` when code starts there.
+    ///
+    /// Line comments end before their newline. Unterminated text runs to the
+    /// end of `sql`.
+    pub fn skip_at(sql: &str, i: usize, dialect: Dialect) -> Option<(Skip, usize)> {
+        let b = sql.as_bytes();
+        match *b.get(i)? {
+            b'-' if b.get(i + 1) == Some(&b'-') => Some((Skip::Comment, line_end(b, i))),
+            b'#' if dialect.mysql => Some((Skip::Comment, line_end(b, i))),
+            b'/' if b.get(i + 1) == Some(&b'*') => {
+                let end = b[i + 2..].windows(2).position(|w| w == b"*/").map_or(b.len(), |p| i + 2 + p + 2);
+                Some((Skip::Comment, end))
+            }
+            b'\'' => Some((Skip::String, quoted_end(b, i, b'\'', dialect.mysql))),
+            b'"' => Some((Skip::Identifier, quoted_end(b, i, b'"', dialect.mysql))),
+            b'`' => Some((Skip::Identifier, quoted_end(b, i, b'`', false))),
+            b'[' if dialect.brackets => {
+                Some((Skip::Identifier, b[i..].iter().position(|&c| c == b']').map_or(b.len(), |p| i + p + 1)))
+            }
+            b'$' if dialect.postgres => dollar_quoted_end(sql, i).map(|end| (Skip::String, end)),
+            _ => None,
+        }
+    }
+
+    /// Content of a quoted string or identifier, without its delimiters and
+    /// with doubled quotes (and backslash escapes in MySQL) resolved.
+    pub fn unquote(text: &str, dialect: Dialect) -> String {
+        let mut chars = text.chars();
+        let (open, close) = match chars.next() {
+            Some('[') => ('[', ']'),
+            Some('$') => {
+                let tag = text[1..].find('$').map_or(text.len(), |p| p + 2);
+                let body = &text[tag.min(text.len())..];
+                return body.strip_suffix(&text[..tag.min(text.len())]).unwrap_or(body).to_string();
+            }
+            Some(quote) => (quote, quote),
+            None => return String::new(),
+        };
+        let mut content = String::new();
+        while let Some(c) = chars.next() {
+            if c == '\\' && dialect.mysql && open != '`' && open != '[' {
+                content.extend(chars.next());
+            } else if c == close {
+                match chars.clone().next() {
+                    Some(next) if next == close && open != '[' => {
+                        content.push(close);
+                        chars.next();
+                    }
+                    _ => break,
+                }
+            } else {
+                content.push(c);
+            }
+        }
+        content
+    }
+}
+
+fn line_end(b: &[u8], i: usize) -> usize {
+    b[i..].iter().position(|&c| c == b'\n').map_or(b.len(), |p| i + p)
+}
+
+/// Returns the index after the closing quote; doubled quotes are escapes.
+fn quoted_end(b: &[u8], i: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut j = i + 1;
+    while j < b.len() {
+        if backslash_escapes && b[j] == b'\\' {
+            j += 2;
+            continue;
+        }
+        if b[j] == quote {
+            if b.get(j + 1) == Some(&quote) {
+                j += 2;
+                continue;
+            }
+            return j + 1;
+        }
+        j += 1;
+    }
+    b.len()
+}
+
+/// Returns the index after a `$tag$ ... $tag$` body starting at `i`, if one starts there.
+fn dollar_quoted_end(sql: &str, i: usize) -> Option<usize> {
+    let b = sql.as_bytes();
+    if i > 0 && (b[i - 1].is_ascii_alphanumeric() || b[i - 1] == b'_') {
+        return None;
+    }
+    let tag_len = b[i + 1..].iter().position(|&c| c == b'$')?;
+    let tag = &sql[i..i + tag_len + 2];
+    if !tag[1..tag.len() - 1].bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_')
+        || tag.as_bytes().get(1).is_some_and(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let body = i + tag.len();
+    Some(sql[body..].find(tag).map_or(b.len(), |p| body + p + tag.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skips(sql: &str, dialect: Dialect) -> Vec<(Skip, &str)> {
+        let mut found = Vec::new();
+        let mut i = 0;
+        while i < sql.len() {
+            match SqlLexer::skip_at(sql, i, dialect) {
+                Some((kind, end)) => {
+                    found.push((kind, &sql[i..end]));
+                    i = end;
+                }
+                None => i += sql[i..].chars().next().map_or(1, char::len_utf8),
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn skips_strings_identifiers_and_comments() {
+        let mysql = Dialect::of(&DbType::MySQL);
+        assert_eq!(
+            skips("SELECT 'it''s', 'a\\'b', `t``x` # note\n/* c */ -- end", mysql),
+            vec![
+                (Skip::String, "'it''s'"),
+                (Skip::String, "'a\\'b'"),
+                (Skip::Identifier, "`t``x`"),
+                (Skip::Comment, "# note"),
+                (Skip::Comment, "/* c */"),
+                (Skip::Comment, "-- end"),
+            ]
+        );
+
+        let postgres = Dialect::of(&DbType::Postgres);
+        assert_eq!(
+            skips("SELECT 'a\\', x # y, $1, $f$ it's $f$, \"é\"", postgres),
+            vec![(Skip::String, "'a\\'"), (Skip::String, "$f$ it's $f$"), (Skip::Identifier, "\"é\"")]
+        );
+        assert_eq!(
+            skips("SELECT [a]; 'open", Dialect::of(&DbType::SqlServer)),
+            vec![(Skip::Identifier, "[a]"), (Skip::String, "'open")]
+        );
+    }
+
+    #[test]
+    fn unquotes_strings_and_identifiers() {
+        assert_eq!(SqlLexer::unquote("'it''s'", Dialect::ANY), "it's");
+        assert_eq!(SqlLexer::unquote("'a\\'b'", Dialect::ANY), "a'b");
+        assert_eq!(SqlLexer::unquote("'a\\'", Dialect::of(&DbType::Postgres)), "a\\");
+        assert_eq!(SqlLexer::unquote("`t``x`", Dialect::ANY), "t`x");
+        assert_eq!(SqlLexer::unquote("[a b]", Dialect::ANY), "a b");
+        assert_eq!(SqlLexer::unquote("$f$ body $f$", Dialect::ANY), " body ");
+    }
+}
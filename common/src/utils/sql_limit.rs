@@ -0,0 +1,249 @@
+//! Row limit enforcement for SELECT statements.
+//!
+//! Rewrites a read query so the database returns at most a given number of
+//! rows. Only the outermost query is considered: clauses inside parentheses
+//! (subqueries, CTE bodies, parenthesized UNION members) and in quoted
+//! strings or comments are ignored, and a LIMIT after a UNION applies to the
+//! whole compound query, as it does in the database.
+//!
+//! - A statement without a row limit gets `LIMIT n`, placed before a trailing
+//!   `OFFSET` or locking clause (`FOR UPDATE`, `LOCK IN SHARE MODE`) and
+//!   before trailing comments and semicolons.
+//! - A literal `LIMIT` (MySQL / SQLite `LIMIT offset, count` included) or
+//!   PostgreSQL `FETCH FIRST n ROWS` above `n` is lowered to `n`; PostgreSQL
+//!   `LIMIT ALL` becomes `LIMIT n`.
+//! - A limit given as a bind parameter or expression is left as is.
+//! - Other statements (SHOW, EXPLAIN, DML, ...) are returned unchanged.
+
+use crate::models::connection::DbType;
+use crate::utils::sql_lexer::{Dialect, Skip, SqlLexer};
+
+/// Enforces row limits on SELECT statements.
+pub struct SqlLimit;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Word,
+    Number,
+    Symbol(u8),
+}
+
+/// Token outside parentheses, as a byte range of the statement.
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: Kind,
+    start: usize,
+    end: usize,
+}
+
+/// Top-level tokens of the first statement and the end of its last
+/// significant character (before trailing comments and the semicolon).
+struct Scan {
+    tokens: Vec<Token>,
+    end: usize,
+}
+
+impl SqlLimit {
+    /// Returns `sql` rewritten to return at most `limit` rows.
+    pub fn enforce(sql: &str, limit: u32, db_type: &DbType) -> String {
+        let dialect = Dialect::of(db_type);
+        let scan = scan(sql, dialect);
+        let tokens = &scan.tokens;
+        let word = |t: &Token, w: &str| t.kind == Kind::Word && sql[t.start..t.end].eq_ignore_ascii_case(w);
+        let word_at = |i: usize, w: &str| tokens.get(i).is_some_and(|t| word(t, w));
+
+        if !is_select(sql, tokens, dialect) {
+            return sql.to_string();
+        }
+
+        if let Some(i) = tokens.iter().position(|t| word(t, "LIMIT")) {
+            return match tokens.get(i + 1) {
+                // MySQL / SQLite `LIMIT offset, count`
+                Some(count) if count.kind == Kind::Number && tokens.get(i + 2).is_some_and(|t| t.kind == Kind::Symbol(b',')) => {
+                    match tokens.get(i + 3) {
+                        Some(t) if t.kind == Kind::Number => cap(sql, t, limit),
+                        _ => sql.to_string(),
+                    }
+                }
+                Some(count) if count.kind == Kind::Number => cap(sql, count, limit),
+                Some(all) if dialect.postgres && word(all, "ALL") => replace(sql, all, limit),
+                _ => sql.to_string(),
+            };
+        }
+
+        if dialect.postgres {
+            if let Some(i) = tokens.iter().position(|t| word(t, "FETCH")) {
+                // FETCH FIRST | NEXT [n] ROW | ROWS ...; without a count it is one row
+                return match tokens.get(i + 2) {
+                    Some(count) if count.kind == Kind::Number => cap(sql, count, limit),
+                    _ => sql.to_string(),
+                };
+            }
+        }
+
+        // LIMIT goes before OFFSET and locking clauses
+        let clause = tokens.iter().enumerate().find(|(i, t)| {
+            word(t, "OFFSET")
+                || (word(t, "FOR")
+                    && ["UPDATE", "SHARE", "NO", "KEY"].iter().any(|w| word_at(i + 1, w)))
+                || (dialect.mysql && word(t, "LOCK") && word_at(i + 1, "IN"))
+        });
+        match clause {
+            Some((_, t)) => format!("{}LIMIT {} {}", &sql[..t.start], limit, &sql[t.start..]),
+            None => format!("{} LIMIT {}{}", &sql[..scan.end], limit, &sql[scan.end..]),
+        }
+    }
+}
+
+/// Whether the statement is a query: SELECT / VALUES / TABLE, possibly
+/// parenthesized, or a WITH whose main statement is one of those.
+fn is_select(sql: &str, tokens: &[Token], dialect: Dialect) -> bool {
+    let mut leading = sql.trim_start();
+    loop {
+        if let Some((Skip::Comment, end)) = SqlLexer::skip_at(leading, 0, dialect) {
+            leading = leading[end..].trim_start();
+        } else if let Some(rest) = leading.strip_prefix('(') {
+            leading = rest.trim_start();
+        } else {
+            break;
+        }
+    }
+    let keyword: String = leading.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    let is_query = |w: &str| ["SELECT", "VALUES", "TABLE"].iter().any(|k| k.eq_ignore_ascii_case(w));
+    if is_query(&keyword) {
+        return true;
+    }
+    if !keyword.eq_ignore_ascii_case("WITH") {
+        return false;
+    }
+    tokens
+        .iter()
+        .filter(|t| t.kind == Kind::Word)
+        .map(|t| &sql[t.start..t.end])
+        .find(|w| is_query(w) || ["INSERT", "UPDATE", "DELETE", "MERGE"].iter().any(|k| k.eq_ignore_ascii_case(w)))
+        .is_some_and(is_query)
+}
+
+/// Lowers the number in `token` to `limit` when it is larger.
+fn cap(sql: &str, token: &Token, limit: u32) -> String {
+    match sql[token.start..token.end].parse::<u64>() {
+        Ok(n) if n <= u64::from(limit) => sql.to_string(),
+        _ => replace(sql, token, limit),
+    }
+}
+
+fn replace(sql: &str, token: &Token, limit: u32) -> String {
+    format!("{}{}{}", &sql[..token.start], limit, &sql[token.end..])
+}
+
+fn scan(sql: &str, dialect: Dialect) -> Scan {
+    let b = sql.as_bytes();
+    let is_word = |c: u8| c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80;
+    let mut tokens = Vec::new();
+    let mut depth = 0usize;
+    let mut end = 0;
+    let mut i = 0;
+
+    while i < b.len() {
+        let start = i;
+        if let Some((kind, skipped)) = SqlLexer::skip_at(sql, i, dialect) {
+            i = skipped;
+            if kind != Skip::Comment {
+                end = i;
+            }
+            continue;
+        }
+        match b[i] {
+            c if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b';' if depth == 0 => break,
+            b'(' => {
+                if depth == 0 {
+                    tokens.push(Token { kind: Kind::Symbol(b'('), start, end: i + 1 });
+                }
+                depth += 1;
+                i += 1;
+            }
+            b')' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            c if is_word(c) => {
+                while i < b.len() && is_word(b[i]) {
+                    i += 1;
+                }
+                if depth == 0 {
+                    let kind = if b[start..i].iter().all(u8::is_ascii_digit) { Kind::Number } else { Kind::Word };
+                    tokens.push(Token { kind, start, end: i });
+                }
+            }
+            c => {
+                if depth == 0 {
+                    tokens.push(Token { kind: Kind::Symbol(c), start, end: i + 1 });
+                }
+                i += 1;
+            }
+        }
+        end = i.min(b.len());
+    }
+    Scan { tokens, end }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mysql(sql: &str) -> String {
+        SqlLimit::enforce(sql, 100, &DbType::MySQL)
+    }
+
+    fn postgres(sql: &str) -> String {
+        SqlLimit::enforce(sql, 100, &DbType::Postgres)
+    }
+
+    #[test]
+    fn appends_limit_to_unbounded_queries() {
+        assert_eq!(mysql("SELECT * FROM t;"), "SELECT * FROM t LIMIT 100;");
+        assert_eq!(mysql("SELECT * FROM t -- note"), "SELECT * FROM t LIMIT 100 -- note");
+        assert_eq!(
+            mysql("SELECT * FROM (SELECT * FROM t LIMIT 5) x WHERE note = 'LIMIT 1'"),
+            "SELECT * FROM (SELECT * FROM t LIMIT 5) x WHERE note = 'LIMIT 1' LIMIT 100"
+        );
+        assert_eq!(
+            mysql("(SELECT a FROM t LIMIT 5) UNION (SELECT a FROM u)"),
+            "(SELECT a FROM t LIMIT 5) UNION (SELECT a FROM u) LIMIT 100"
+        );
+        assert_eq!(
+            postgres("WITH x AS (SELECT 1 LIMIT 1) SELECT * FROM x OFFSET 5"),
+            "WITH x AS (SELECT 1 LIMIT 1) SELECT * FROM x LIMIT 100 OFFSET 5"
+        );
+        assert_eq!(mysql("SELECT * FROM t FOR UPDATE"), "SELECT * FROM t LIMIT 100 FOR UPDATE");
+    }
+
+    #[test]
+    fn caps_literal_limits() {
+        assert_eq!(mysql("SELECT * FROM t LIMIT 5"), "SELECT * FROM t LIMIT 5");
+        assert_eq!(mysql("SELECT * FROM t LIMIT 5000 OFFSET 10"), "SELECT * FROM t LIMIT 100 OFFSET 10");
+        assert_eq!(mysql("SELECT * FROM t LIMIT 10, 5000"), "SELECT * FROM t LIMIT 10, 100");
+        assert_eq!(mysql("SELECT a FROM t UNION SELECT a FROM u LIMIT 500"), "SELECT a FROM t UNION SELECT a FROM u LIMIT 100");
+        assert_eq!(mysql("SELECT * FROM t LIMIT ?"), "SELECT * FROM t LIMIT ?");
+        assert_eq!(postgres("SELECT * FROM t LIMIT ALL"), "SELECT * FROM t LIMIT 100");
+        assert_eq!(
+            postgres("SELECT * FROM t FETCH FIRST 500 ROWS ONLY"),
+            "SELECT * FROM t FETCH FIRST 100 ROWS ONLY"
+        );
+    }
+
+    #[test]
+    fn leaves_other_statements_unchanged() {
+        assert_eq!(mysql("SHOW TABLES"), "SHOW TABLES");
+        assert_eq!(mysql("EXPLAIN SELECT * FROM t"), "EXPLAIN SELECT * FROM t");
+        assert_eq!(
+            postgres("WITH x AS (SELECT id FROM t) DELETE FROM u WHERE id IN (SELECT id FROM x)"),
+            "WITH x AS (SELECT id FROM t) DELETE FROM u WHERE id IN (SELECT id FROM x)"
+        );
+        assert_eq!(postgres("SELECT $$ LIMIT 1 $$"), "SELECT $$ LIMIT 1 $$ LIMIT 100");
+    }
+}
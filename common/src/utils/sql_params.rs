@@ -0,0 +1,137 @@
+//! Named SQL parameter rewriting.
+//!
+//! sqlx binds parameters positionally, so statements written with named
+//! placeholders (`:name`) are rewritten to the driver's positional syntax and
+//! the values are ordered to match. Placeholders inside string literals,
+//! quoted identifiers and comments are left alone, as are PostgreSQL `::`
+//! casts; the target dialect's quoting rules follow from the placeholder
+//! style (`?` uses MySQL's).
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+
+use crate::errors::{AppError, AppResult};
+use crate::models::connection::DbType;
+use crate::utils::sql_lexer::{Dialect, SqlLexer};
+
+/// Positional placeholder syntax of the target driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceholderStyle {
+    /// `?` (MySQL, SQLite).
+    Question,
+    /// `$1`, `$2`, ... (PostgreSQL).
+    Dollar,
+    /// `:1`, `:2`, ... (Oracle).
+    Colon,
+}
+
+/// Rewrites named SQL placeholders to positional ones.
+pub struct SqlParams;
+
+impl SqlParams {
+    /// Replaces every `:name` in `sql` with a positional placeholder and
+    /// returns the rewritten SQL with the values in binding order.
+    ///
+    /// With `Dollar` and `Colon` styles a name used several times maps to one
+    /// parameter; with `Question` style its value is repeated.
+    ///
+    /// # Errors
+    /// Returns `AppError::InvalidInput` if a placeholder has no value.
+    pub fn bind_named(
+        sql: &str,
+        named: &BTreeMap<String, Value>,
+        style: PlaceholderStyle,
+    ) -> AppResult<(String, Vec<Value>)> {
+        let dialect = match style {
+            PlaceholderStyle::Question => Dialect::of(&DbType::MySQL),
+            PlaceholderStyle::Dollar => Dialect::of(&DbType::Postgres),
+            PlaceholderStyle::Colon => Dialect::of(&DbType::Oracle),
+        };
+        let is_name = |c: char| c.is_alphanumeric() || c == '_';
+        let mut out = String::with_capacity(sql.len());
+        let mut values: Vec<Value> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let mut i = 0;
+
+        while let Some(c) = sql[i..].chars().next() {
+            if let Some((_, end)) = SqlLexer::skip_at(sql, i, dialect) {
+                out.push_str(&sql[i..end]);
+                i = end;
+                continue;
+            }
+            match c {
+                ':' if sql[i + 1..].starts_with(':') => {
+                    out.push_str("::");
+                    i += 2;
+                }
+                ':' if sql[i + 1..].starts_with(|c: char| c.is_alphabetic() || c == '_') => {
+                    let start = i + 1;
+                    let end = sql[start..].find(|c: char| !is_name(c)).map_or(sql.len(), |p| start + p);
+                    let name = sql[start..end].to_string();
+                    let value = named
+                        .get(&name)
+                        .ok_or_else(|| AppError::InvalidInput(format!("缺少命名参数 :{}", name)))?;
+                    match style {
+                        PlaceholderStyle::Question => {
+                            values.push(value.clone());
+                            out.push('?');
+                        }
+                        PlaceholderStyle::Dollar | PlaceholderStyle::Colon => {
+                            let index = *positions.entry(name).or_insert_with(|| {
+                                values.push(value.clone());
+                                values.len()
+                            });
+                            let prefix = if style == PlaceholderStyle::Dollar { '$' } else { ':' };
+                            out.push_str(&format!("{}{}", prefix, index));
+                        }
+                    }
+                    i = end;
+                }
+                c => {
+                    out.push(c);
+                    i += c.len_utf8();
+                }
+            }
+        }
+
+        Ok((out, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn named(pairs: &[(&str, Value)]) -> BTreeMap<String, Value> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn rewrites_named_placeholders() {
+        let params = named(&[("id", json!(7)), ("name", json!("bob"))]);
+        let sql = "SELECT id::text, ':id' FROM t WHERE id = :id OR name = :name OR owner = :id -- :skip";
+
+        let (pg, values) = SqlParams::bind_named(sql, &params, PlaceholderStyle::Dollar).unwrap();
+        assert_eq!(
+            pg,
+            "SELECT id::text, ':id' FROM t WHERE id = $1 OR name = $2 OR owner = $1 -- :skip"
+        );
+        assert_eq!(values, vec![json!(7), json!("bob")]);
+
+        let (my, values) = SqlParams::bind_named(sql, &params, PlaceholderStyle::Question).unwrap();
+        assert!(my.contains("id = ? OR name = ? OR owner = ?"));
+        assert_eq!(values, vec![json!(7), json!("bob"), json!(7)]);
+
+        let (ora, values) = SqlParams::bind_named("SELECT * FROM t WHERE id = :id OR owner = :id", &params, PlaceholderStyle::Colon).unwrap();
+        assert_eq!(ora, "SELECT * FROM t WHERE id = :1 OR owner = :1");
+        assert_eq!(values, vec![json!(7)]);
+    }
+
+    #[test]
+    fn rejects_missing_named_parameter() {
+        let err = SqlParams::bind_named("SELECT :missing", &BTreeMap::new(), PlaceholderStyle::Question);
+        assert!(matches!(err, Err(AppError::InvalidInput(_))));
+    }
+}
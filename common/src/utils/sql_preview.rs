@@ -0,0 +1,253 @@
+//! Change preview rewriting.
+//!
+//! Turns an `UPDATE` / `DELETE` statement into the `SELECT` that returns the
+//! rows it would modify, so they can be reviewed before the change runs. The
+//! target tables, joins, `WHERE`, `ORDER BY` and `LIMIT` are kept; the `SET`
+//! list and `RETURNING` clause are dropped. Keywords inside quoted strings,
+//! quoted identifiers, comments and parentheses are ignored; what is quoted
+//! or commented follows the [`Dialect`] of the target database.
+
+use crate::utils::sql_lexer::{Dialect, SqlLexer};
+
+/// Rewrites data-changing statements into preview queries.
+pub struct ChangePreviewSql;
+
+/// Modifiers that may follow `UPDATE` / `DELETE` before the table list.
+const MODIFIERS: &[&str] = &["LOW_PRIORITY", "QUICK", "IGNORE"];
+
+/// Clauses that follow the table list and are kept in the preview.
+const FILTER_CLAUSES: &[&str] = &["WHERE", "ORDER", "LIMIT"];
+
+impl ChangePreviewSql {
+    /// Returns whether `sql` is an `UPDATE` or `DELETE` statement.
+    pub fn is_change(sql: &str, dialect: Dialect) -> bool {
+        let words = top_level_words(sql, dialect);
+        words
+            .first()
+            .is_some_and(|&(s, e)| matches!(sql[s..e].to_ascii_uppercase().as_str(), "UPDATE" | "DELETE"))
+    }
+
+    /// Returns whether `sql` is an `UPDATE` or `DELETE` without a `WHERE`
+    /// clause, i.e. one that modifies every row of its tables.
+    pub fn is_unfiltered(sql: &str, dialect: Dialect) -> bool {
+        let words = top_level_words(sql, dialect);
+        Self::is_change(sql, dialect) && !words.iter().any(|&(s, e)| sql[s..e].eq_ignore_ascii_case("WHERE"))
+    }
+
+    /// Returns whether `sql` is an `UPDATE` or `DELETE` with a `RETURNING` clause.
+    pub fn has_returning(sql: &str, dialect: Dialect) -> bool {
+        let words = top_level_words(sql, dialect);
+        Self::is_change(sql, dialect) && words.iter().any(|&(s, e)| sql[s..e].eq_ignore_ascii_case("RETURNING"))
+    }
+
+    /// Returns a query counting the rows `sql` would modify, as
+    /// `affected_rows`; `None` where [`to_select`](Self::to_select) is.
+    ///
+    /// The preview is wrapped with a single selected column, so joins whose
+    /// tables share column names stay valid as a derived table.
+    pub fn to_count(sql: &str, dialect: Dialect) -> Option<String> {
+        let preview = Self::to_select(sql, dialect)?;
+        let words = top_level_words(&preview, dialect);
+        let from = words.iter().find(|&&(s, e)| preview[s..e].eq_ignore_ascii_case("FROM"))?;
+        Some(format!(
+            "SELECT COUNT(*) AS affected_rows FROM (SELECT 1 AS matched {}) AS matched_rows",
+            &preview[from.0..]
+        ))
+    }
+
+    /// Returns the `SELECT` listing the rows `sql` would modify.
+    ///
+    /// Returns `None` for anything but a single `UPDATE` / `DELETE` statement,
+    /// and for forms that have no equivalent query (statements led by a
+    /// `WITH` clause, MySQL's `DELETE FROM t1, t2 USING ...`).
+    pub fn to_select(sql: &str, dialect: Dialect) -> Option<String> {
+        let sql = sql.trim().trim_end_matches(';').trim_end();
+        let words = top_level_words(sql, dialect);
+        let upper: Vec<String> = words.iter().map(|&(s, e)| sql[s..e].to_ascii_uppercase()).collect();
+        if upper.iter().any(|w| w == ";") {
+            return None;
+        }
+        let find = |from: usize, keywords: &[&str]| {
+            (from..upper.len()).find(|&i| keywords.contains(&upper[i].as_str()))
+        };
+
+        let mut i = 1;
+        while upper.get(i).is_some_and(|w| MODIFIERS.contains(&w.as_str())) {
+            i += 1;
+        }
+        let start = words.get(i)?.0;
+        match upper.first()?.as_str() {
+            "UPDATE" => {
+                let set = find(i, &["SET"])?;
+                let targets = sql[start..words[set].0].trim();
+                // PostgreSQL `UPDATE ... SET ... FROM other`
+                let from = find(set + 1, &["FROM"]);
+                let filter = find(from.unwrap_or(set) + 1, FILTER_CLAUSES);
+                let returning = find(set + 1, &["RETURNING"]);
+                let end = returning.map_or(sql.len(), |r| words[r].0);
+                let mut tables = targets.to_string();
+                if let Some(from) = from {
+                    let list_end = filter.map(|f| words[f].0).unwrap_or(end);
+                    tables = format!("{}, {}", tables, sql[words[from].1..list_end].trim());
+                }
+                Some(select(&tables, "*", filter.map(|f| &sql[words[f].0..end])))
+            }
+            "DELETE" => {
+                let from = find(i, &["FROM"])?;
+                // MySQL multi-table `DELETE t1, t2 FROM ...` names the tables to delete from
+                let columns = if from > i {
+                    sql[start..words[from].0]
+                        .split(',')
+                        .map(|t| format!("{}.*", t.trim()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                } else {
+                    "*".to_string()
+                };
+                let filter = find(from + 1, FILTER_CLAUSES);
+                let returning = find(from + 1, &["RETURNING"]);
+                let end = returning.map_or(sql.len(), |r| words[r].0);
+                let list_end = filter.map(|f| words[f].0).unwrap_or(end);
+                let mut tables = sql[words[from].1..list_end].trim().to_string();
+                // PostgreSQL `DELETE FROM t USING other`; `JOIN ... USING (col)` is a join condition
+                let using = (from + 1..upper.len()).find(|&u| {
+                    upper[u] == "USING"
+                        && words[u].0 < list_end
+                        && !sql[words[u].1..].trim_start().starts_with('(')
+                });
+                if let Some(using) = using {
+                    let targets = sql[words[from].1..words[using].0].trim();
+                    if targets.contains(',') {
+                        return None;
+                    }
+                    tables = format!("{}, {}", targets, sql[words[using].1..list_end].trim());
+                }
+                Some(select(&tables, &columns, filter.map(|f| &sql[words[f].0..end])))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn select(tables: &str, columns: &str, filter: Option<&str>) -> String {
+    match filter {
+        Some(filter) => format!("SELECT {} FROM {} {}", columns, tables, filter.trim()),
+        None => format!("SELECT {} FROM {}", columns, tables),
+    }
+}
+
+/// Byte ranges of the words outside quotes, comments and parentheses.
+///
+/// Statement separators are returned as `;` words.
+fn top_level_words(sql: &str, dialect: Dialect) -> Vec<(usize, usize)> {
+    let b = sql.as_bytes();
+    let is_word = |c: u8| c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c >= 0x80;
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < b.len() {
+        if let Some((_, end)) = SqlLexer::skip_at(sql, i, dialect) {
+            i = end;
+            continue;
+        }
+        match b[i] {
+            b'(' => {
+                depth += 1;
+                i += 1;
+            }
+            b')' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            b';' => {
+                words.push((i, i + 1));
+                i += 1;
+            }
+            c if is_word(c) => {
+                let start = i;
+                while i < b.len() && is_word(b[i]) {
+                    i += 1;
+                }
+                if depth == 0 {
+                    words.push((start, i));
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::connection::DbType;
+
+    #[test]
+    fn rewrites_updates_keeping_filters() {
+        assert_eq!(
+            ChangePreviewSql::to_select("UPDATE users SET name = 'WHERE x', age = (SELECT 1 FROM t WHERE y) WHERE id > 10 ORDER BY id LIMIT 5;", Dialect::ANY).as_deref(),
+            Some("SELECT * FROM users WHERE id > 10 ORDER BY id LIMIT 5")
+        );
+        assert_eq!(
+            ChangePreviewSql::to_select("UPDATE LOW_PRIORITY a JOIN b ON a.id = b.a_id SET a.x = b.x", Dialect::ANY).as_deref(),
+            Some("SELECT * FROM a JOIN b ON a.id = b.a_id")
+        );
+        assert_eq!(
+            ChangePreviewSql::to_select("UPDATE t SET x = o.x FROM other o WHERE o.id = t.id RETURNING t.id", Dialect::ANY).as_deref(),
+            Some("SELECT * FROM t, other o WHERE o.id = t.id")
+        );
+    }
+
+    #[test]
+    fn rewrites_deletes_and_rejects_other_statements() {
+        assert_eq!(
+            ChangePreviewSql::to_select("DELETE FROM logs WHERE created_at < :cutoff", Dialect::ANY).as_deref(),
+            Some("SELECT * FROM logs WHERE created_at < :cutoff")
+        );
+        assert_eq!(
+            ChangePreviewSql::to_select("DELETE t1 FROM t1 JOIN t2 USING (id) WHERE t2.x = 1", Dialect::ANY).as_deref(),
+            Some("SELECT t1.* FROM t1 JOIN t2 USING (id) WHERE t2.x = 1")
+        );
+        assert_eq!(
+            ChangePreviewSql::to_select("DELETE FROM t USING other o WHERE o.id = t.id", Dialect::ANY).as_deref(),
+            Some("SELECT * FROM t, other o WHERE o.id = t.id")
+        );
+        assert_eq!(ChangePreviewSql::to_select("DELETE FROM t1, t2 USING t1 JOIN t2", Dialect::ANY), None);
+        assert_eq!(ChangePreviewSql::to_select("SELECT * FROM t", Dialect::ANY), None);
+        assert_eq!(ChangePreviewSql::to_select("DELETE FROM t WHERE id = 1; DROP TABLE t", Dialect::ANY), None);
+        assert!(ChangePreviewSql::is_change("  update t set x = 1", Dialect::ANY));
+        assert!(!ChangePreviewSql::is_change("INSERT INTO t VALUES (1)", Dialect::ANY));
+    }
+
+    #[test]
+    fn detects_unfiltered_changes_and_counts_rows() {
+        assert!(ChangePreviewSql::is_unfiltered("DELETE FROM logs", Dialect::ANY));
+        assert!(ChangePreviewSql::is_unfiltered("UPDATE t SET x = (SELECT y FROM u WHERE u.id = 1)", Dialect::ANY));
+        assert!(!ChangePreviewSql::is_unfiltered("UPDATE t SET x = 1 WHERE id = 2", Dialect::ANY));
+        assert!(!ChangePreviewSql::is_unfiltered("SELECT * FROM t", Dialect::ANY));
+        assert!(ChangePreviewSql::has_returning("DELETE FROM t WHERE id = 1 RETURNING id", Dialect::ANY));
+        assert!(!ChangePreviewSql::has_returning("UPDATE t SET note = 'returning' WHERE id = 1", Dialect::ANY));
+        assert_eq!(
+            ChangePreviewSql::to_count("DELETE t1 FROM t1 JOIN t2 USING (id) LIMIT 10", Dialect::ANY).as_deref(),
+            Some("SELECT COUNT(*) AS affected_rows FROM (SELECT 1 AS matched FROM t1 JOIN t2 USING (id) LIMIT 10) AS matched_rows")
+        );
+    }
+
+    #[test]
+    fn follows_the_quoting_and_comments_of_the_dialect() {
+        let postgres = Dialect::of(&DbType::Postgres);
+        let mysql = Dialect::of(&DbType::MySQL);
+        // `#` is an operator in PostgreSQL and a comment in MySQL
+        let xor = "UPDATE t SET flags = flags # 1 WHERE id = 2";
+        assert!(!ChangePreviewSql::is_unfiltered(xor, postgres));
+        assert!(ChangePreviewSql::is_unfiltered(xor, mysql));
+        let dollar = "UPDATE t SET body = $$ WHERE x $$";
+        assert!(ChangePreviewSql::is_unfiltered(dollar, postgres));
+        assert_eq!(
+            ChangePreviewSql::to_select("DELETE FROM t WHERE note = $q$ ; $q$", postgres).as_deref(),
+            Some("SELECT * FROM t WHERE note = $q$ ; $q$")
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! SQL script splitter.
+//!
+//! Splits SQL scripts (dumps, migration files) into individual statements.
+
+use crate::models::connection::DbType;
+use crate::utils::sql_lexer::{Dialect, Skip, SqlLexer};
+
+/// Splits SQL scripts into statements.
+pub struct SqlSplitter;
+
+impl SqlSplitter {
+    /// Splits a script into statements, without the trailing delimiter.
+    ///
+    /// Delimiters inside quoted strings, quoted identifiers and comments are
+    /// ignored. MySQL scripts honour backslash escapes, `#` comments and
+    /// `DELIMITER` directives (as emitted by mysqldump for routines and
+    /// triggers); PostgreSQL scripts honour dollar-quoted bodies.
+    /// Statements consisting only of comments are dropped.
+    pub fn split(sql: &str, db_type: &DbType) -> Vec<String> {
+        let (mut statements, rest) = Self::split_terminated(sql, db_type);
+        let rest = rest.trim();
+        if !rest.is_empty() {
+            statements.push(rest.to_string());
+        }
+        statements
+    }
+
+    /// Splits the statements terminated by a delimiter off the start of a
+    /// script, returning them with the unterminated rest, which is empty or
+    /// only whitespace and comments when the script ends with a delimiter.
+    ///
+    /// Interactive shells use it to run each statement as soon as its
+    /// delimiter is typed; a delimiter inside an unclosed quote keeps the
+    /// statement in the rest.
+    pub fn split_terminated<'a>(sql: &'a str, db_type: &DbType) -> (Vec<String>, &'a str) {
+        let dialect = Dialect::of(db_type);
+        let b = sql.as_bytes();
+        let mut statements = Vec::new();
+        let mut delimiter = String::from(";");
+        let mut start = 0;
+        let mut has_code = false;
+        let mut i = 0;
+
+        while i < b.len() {
+            if dialect.mysql && !has_code && (i == 0 || b[i - 1] == b'\n') {
+                if let Some(new_delimiter) = delimiter_directive(&sql[i..]) {
+                    delimiter = new_delimiter.to_string();
+                    i = line_end(b, i);
+                    start = i;
+                    continue;
+                }
+            }
+
+            if let Some((kind, end)) = SqlLexer::skip_at(sql, i, dialect) {
+                // MySQL executable comments (`/*!40101 ... */`) are code.
+                if kind != Skip::Comment || (dialect.mysql && b[i..].starts_with(b"/*!")) {
+                    has_code = true;
+                }
+                i = end;
+                continue;
+            }
+
+            if b[i..].starts_with(delimiter.as_bytes()) {
+                if has_code {
+                    statements.push(sql[start..i].trim().to_string());
+                }
+                i += delimiter.len();
+                start = i;
+                has_code = false;
+                continue;
+            }
+            if !b[i].is_ascii_whitespace() {
+                has_code = true;
+            }
+            i += 1;
+        }
+
+        let rest = if has_code { &sql[start..] } else { "" };
+        (statements, rest)
+    }
+}
+
+/// Parses a `DELIMITER <token>` line, returning the new delimiter.
+fn delimiter_directive(line: &str) -> Option<&str> {
+    let line = line.lines().next()?;
+    let (keyword, rest) = line.split_once(|c: char| c.is_ascii_whitespace())?;
+    if !keyword.eq_ignore_ascii_case("DELIMITER") {
+        return None;
+    }
+    let token = rest.trim();
+    (!token.is_empty()).then_some(token)
+}
+
+fn line_end(b: &[u8], i: usize) -> usize {
+    b[i..].iter().position(|&c| c == b'\n').map_or(b.len(), |p| i + p + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_delimiters_in_strings_and_comments() {
+        let sql = "-- header; comment\nINSERT INTO t VALUES ('a;b', 'it''s', 'x\\';y');\n/* c; */ SELECT 1;";
+        assert_eq!(
+            SqlSplitter::split(sql, &DbType::MySQL),
+            vec![
+                "-- header; comment\nINSERT INTO t VALUES ('a;b', 'it''s', 'x\\';y')",
+                "/* c; */ SELECT 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn honours_mysql_delimiter_directive() {
+        let sql = "DELIMITER ;;\nCREATE TRIGGER t BEFORE INSERT ON x FOR EACH ROW BEGIN SET @a = 1; END ;;\nDELIMITER ;\nSELECT 2;";
+        let statements = SqlSplitter::split(sql, &DbType::MySQL);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("SET @a = 1; END"));
+        assert_eq!(statements[1], "SELECT 2");
+    }
+
+    #[test]
+    fn separates_the_unterminated_rest() {
+        let (statements, rest) = SqlSplitter::split_terminated("SELECT 1; SELECT 'a;\n", &DbType::Postgres);
+        assert_eq!(statements, vec!["SELECT 1"]);
+        assert_eq!(rest, " SELECT 'a;\n");
+
+        let (statements, rest) = SqlSplitter::split_terminated("SELECT 'a;\nb';\n-- done\n", &DbType::Postgres);
+        assert_eq!(statements, vec!["SELECT 'a;\nb'"]);
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn keeps_postgres_dollar_quoted_bodies() {
+        let sql = "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; $body$ LANGUAGE sql;\nSELECT '$1;';";
+        let statements = SqlSplitter::split(sql, &DbType::Postgres);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("LANGUAGE sql"));
+    }
+}
@@ -0,0 +1,270 @@
+//! SQL table reference extractor.
+//!
+//! Finds the tables a statement reads or writes, for access checks. This is a
+//! lightweight tokenizer rather than a full parser: it looks at the names
+//! following `FROM`, `JOIN`, `INTO`, `UPDATE`, `TABLE` and `TRUNCATE` (and
+//! the new name of a rename), skips aliases, subqueries, table functions and
+//! CTE names, and ignores quoted strings and comments.
+
+use std::collections::HashSet;
+
+use crate::utils::sql_lexer::{Dialect, Skip, SqlLexer};
+
+/// Table referenced by a statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRef {
+    /// Database / schema qualifier, if the name was qualified.
+    pub database: Option<String>,
+    /// Table name.
+    pub table: String,
+}
+
+/// Extracts table references from SQL statements.
+pub struct SqlTableExtractor;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Identifier or keyword; `true` when it was quoted.
+    Word(String, bool),
+    Symbol(char),
+}
+
+/// Keywords that end a table list or can never be a table name.
+const STOP_WORDS: &[&str] = &[
+    "AS", "ON", "USING", "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "OFFSET", "FETCH", "WINDOW",
+    "UNION", "EXCEPT", "INTERSECT", "JOIN", "INNER", "LEFT", "RIGHT", "FULL", "OUTER", "CROSS",
+    "NATURAL", "STRAIGHT_JOIN", "SET", "VALUES", "VALUE", "SELECT", "RETURNING", "FOR", "LOCK",
+    "PARTITION", "USE", "FORCE", "IGNORE", "TABLESAMPLE", "WITH", "DEFAULT", "ORDINALITY", "TO",
+];
+
+/// Keywords that may sit between the introducing keyword and the table name.
+const SKIP_WORDS: &[&str] = &[
+    "ONLY", "LATERAL", "IF", "NOT", "EXISTS", "IGNORE", "LOW_PRIORITY", "DELAYED", "TABLE",
+];
+
+/// Keywords that close a `FROM` / `UPDATE` table list.
+const CLAUSE_END_WORDS: &[&str] = &[
+    "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "UNION", "EXCEPT", "INTERSECT", "WINDOW", "SET",
+    "VALUES", "RETURNING", "FOR",
+];
+
+impl SqlTableExtractor {
+    /// Returns the distinct tables referenced by `sql`, in order of appearance.
+    pub fn extract(sql: &str) -> Vec<TableRef> {
+        let tokens = tokenize(sql);
+        let ctes = cte_names(&tokens);
+        let mut tables: Vec<TableRef> = Vec::new();
+        let mut depth = 0usize;
+        // Paren depths with an open `FROM` / `UPDATE` list, where a comma introduces another table.
+        let mut lists: Vec<usize> = Vec::new();
+        // Paren depths inside a `SELECT` / `DELETE`; elsewhere `FROM` belongs to
+        // a function such as `EXTRACT(YEAR FROM col)`.
+        let mut selects: Vec<usize> = Vec::new();
+        // `DROP TABLE a, b` and `RENAME TABLE a TO b, c TO d` list several tables.
+        let leading = match tokens.first() {
+            Some(Token::Word(w, false)) => w.to_ascii_uppercase(),
+            _ => String::new(),
+        };
+        let ddl_list = matches!(leading.as_str(), "DROP" | "RENAME");
+
+        let mut i = 0;
+        while i < tokens.len() {
+            match &tokens[i] {
+                Token::Symbol('(') => depth += 1,
+                Token::Symbol(')') => {
+                    lists.retain(|d| *d < depth);
+                    selects.retain(|d| *d < depth);
+                    depth = depth.saturating_sub(1);
+                }
+                Token::Symbol(',') if lists.last() == Some(&depth) => {
+                    i = read_table(&tokens, i + 1, true, &ctes, &mut tables);
+                    continue;
+                }
+                Token::Word(w, false) => {
+                    let keyword = w.to_ascii_uppercase();
+                    if is_one_of(&keyword, CLAUSE_END_WORDS) {
+                        lists.retain(|d| *d != depth);
+                    }
+                    if matches!(keyword.as_str(), "SELECT" | "DELETE") {
+                        selects.push(depth);
+                    }
+                    if keyword == "FROM" && !selects.contains(&depth) {
+                        i += 1;
+                        continue;
+                    }
+                    // `RENAME TABLE a TO b`, `ALTER TABLE a RENAME TO b`
+                    let renamed = keyword == "TO"
+                        && (leading == "RENAME"
+                            || matches!(tokens.get(i.wrapping_sub(1)), Some(Token::Word(w, false)) if w.eq_ignore_ascii_case("RENAME")));
+                    if matches!(keyword.as_str(), "FROM" | "JOIN" | "INTO" | "UPDATE" | "TABLE" | "TRUNCATE") || renamed {
+                        if matches!(keyword.as_str(), "FROM" | "UPDATE" | "TRUNCATE") || (keyword == "TABLE" && ddl_list) {
+                            lists.push(depth);
+                        }
+                        let from = matches!(keyword.as_str(), "FROM" | "JOIN");
+                        i = read_table(&tokens, i + 1, from, &ctes, &mut tables);
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        tables
+    }
+}
+
+/// Reads one table reference (with its alias) at `i`; returns the next index.
+///
+/// In `FROM` / `JOIN` position a name followed by `(` is a table function.
+fn read_table(tokens: &[Token], mut i: usize, from: bool, ctes: &HashSet<String>, tables: &mut Vec<TableRef>) -> usize {
+    while matches!(tokens.get(i), Some(Token::Word(w, false)) if is_one_of(w, SKIP_WORDS)) {
+        i += 1;
+    }
+    let Some((name, next)) = qualified_name(tokens, i) else {
+        return i;
+    };
+    if from && tokens.get(next) == Some(&Token::Symbol('(')) {
+        return next;
+    }
+    let is_cte = name.database.is_none() && ctes.contains(&name.table.to_ascii_lowercase());
+    if !is_cte && !name.table.eq_ignore_ascii_case("dual") && !tables.contains(&name) {
+        tables.push(name);
+    }
+    if from {
+        skip_alias(tokens, next)
+    } else {
+        next
+    }
+}
+
+fn is_one_of(word: &str, set: &[&str]) -> bool {
+    set.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+/// Reads `name` or `db.name` at `i`; returns the reference and the next index.
+fn qualified_name(tokens: &[Token], i: usize) -> Option<(TableRef, usize)> {
+    let first = match tokens.get(i)? {
+        Token::Word(w, quoted) if *quoted || !is_one_of(w, STOP_WORDS) => w.clone(),
+        _ => return None,
+    };
+    if tokens.get(i + 1) == Some(&Token::Symbol('.')) {
+        if let Some(Token::Word(second, _)) = tokens.get(i + 2) {
+            // `catalog.schema.table`: keep the last two parts.
+            if tokens.get(i + 3) == Some(&Token::Symbol('.')) {
+                if let Some(Token::Word(third, _)) = tokens.get(i + 4) {
+                    let table = TableRef { database: Some(second.clone()), table: third.clone() };
+                    return Some((table, i + 5));
+                }
+            }
+            let table = TableRef { database: Some(first), table: second.clone() };
+            return Some((table, i + 3));
+        }
+    }
+    Some((TableRef { database: None, table: first }, i + 1))
+}
+
+/// Skips `AS alias` or a bare alias.
+fn skip_alias(tokens: &[Token], mut i: usize) -> usize {
+    if matches!(tokens.get(i), Some(Token::Word(w, false)) if w.eq_ignore_ascii_case("AS")) {
+        i += 1;
+    }
+    match tokens.get(i) {
+        Some(Token::Word(_, true)) => i + 1,
+        Some(Token::Word(w, false)) if !is_one_of(w, STOP_WORDS) => i + 1,
+        _ => i,
+    }
+}
+
+/// Names defined by `WITH name AS (...)` (lowercased).
+fn cte_names(tokens: &[Token]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for window in tokens.windows(3) {
+        if let [Token::Word(name, _), Token::Word(as_kw, false), Token::Symbol('(')] = window {
+            if as_kw.eq_ignore_ascii_case("AS") {
+                names.insert(name.to_ascii_lowercase());
+            }
+        }
+    }
+    names
+}
+
+fn tokenize(sql: &str) -> Vec<Token> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while let Some(c) = sql[i..].chars().next() {
+        if let Some((kind, end)) = SqlLexer::skip_at(sql, i, Dialect::ANY) {
+            // Strings and comments cannot name a table.
+            if kind == Skip::Identifier {
+                tokens.push(Token::Word(SqlLexer::unquote(&sql[i..end], Dialect::ANY), true));
+            }
+            i = end;
+            continue;
+        }
+        match c {
+            c if c.is_whitespace() => i += c.len_utf8(),
+            c if is_word(c) => {
+                let end = sql[i..].find(|c: char| !is_word(c)).map_or(sql.len(), |p| i + p);
+                tokens.push(Token::Word(sql[i..end].to_string(), false));
+                i = end;
+            }
+            c => {
+                tokens.push(Token::Symbol(c));
+                i += c.len_utf8();
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(sql: &str) -> Vec<String> {
+        SqlTableExtractor::extract(sql)
+            .into_iter()
+            .map(|t| match t.database {
+                Some(db) => format!("{}.{}", db, t.table),
+                None => t.table,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn extracts_joined_and_qualified_tables() {
+        assert_eq!(
+            names("SELECT * FROM users u JOIN `shop`.`orders` AS o ON o.user_id = u.id, items WHERE x = 'FROM secret'"),
+            vec!["users", "shop.orders", "items"]
+        );
+        assert_eq!(
+            names("SELECT * FROM (SELECT id FROM a) t LEFT JOIN b USING (id)"),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn skips_ctes_and_table_functions() {
+        assert_eq!(
+            names("WITH recent AS (SELECT * FROM events) SELECT * FROM recent, generate_series(1, 3)"),
+            vec!["events"]
+        );
+        assert_eq!(names("INSERT INTO logs (msg) SELECT msg FROM staging"), vec!["logs", "staging"]);
+        assert_eq!(names("SELECT 1 FROM dual"), Vec::<String>::new());
+        assert_eq!(
+            names("SELECT EXTRACT(YEAR FROM created_at), TRIM(BOTH ' ' FROM name) FROM people"),
+            vec!["people"]
+        );
+    }
+
+    #[test]
+    fn extracts_every_table_of_ddl() {
+        assert_eq!(names("DROP TABLE IF EXISTS a, shop.b CASCADE"), vec!["a", "shop.b"]);
+        assert_eq!(names("TRUNCATE logs, events RESTART IDENTITY"), vec!["logs", "events"]);
+        assert_eq!(names("TRUNCATE TABLE logs"), vec!["logs"]);
+        assert_eq!(names("RENAME TABLE a TO b, c TO d"), vec!["a", "b", "c", "d"]);
+        assert_eq!(names("ALTER TABLE a RENAME TO b"), vec!["a", "b"]);
+        assert_eq!(names("ALTER TABLE a ADD COLUMN x INT, ADD COLUMN y INT"), vec!["a"]);
+    }
+}
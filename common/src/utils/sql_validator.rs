@@ -3,6 +3,7 @@
 //! Provides security validation for SQL statements.
 
 use crate::errors::AppError;
+use crate::utils::sql_lexer::{Dialect, SqlLexer};
 
 /// Validates SQL statements for security.
 pub struct SqlValidator;
@@ -10,6 +11,19 @@ pub struct SqlValidator;
 /// List of forbidden SQL keywords for security.
 const FORBIDDEN_KEYWORDS: [&str; 4] = ["DROP ", "TRUNCATE ", "DELETE FROM", "ALTER "];
 
+/// Leading keywords of schema-changing statements.
+const DDL_KEYWORDS: [&str; 5] = ["CREATE", "ALTER", "DROP", "TRUNCATE", "RENAME"];
+
+/// Leading keywords of read-only statements.
+const READ_KEYWORDS: [&str; 7] = ["SELECT", "WITH", "SHOW", "DESCRIBE", "DESC", "EXPLAIN", "VALUES"];
+
+/// Keywords that write data, change the schema or grant access wherever they
+/// appear, e.g. inside a CTE or after `;`. `INTO` covers `SELECT ... INTO`.
+const WRITE_KEYWORDS: [&str; 17] = [
+    "INSERT", "UPDATE", "DELETE", "MERGE", "UPSERT", "INTO", "CREATE", "ALTER", "DROP", "TRUNCATE", "RENAME",
+    "GRANT", "REVOKE", "CALL", "EXEC", "EXECUTE", "COPY",
+];
+
 impl SqlValidator {
     /// Validates a SQL statement for forbidden operations.
     ///
@@ -22,8 +36,49 @@ impl SqlValidator {
     /// # Errors
     /// Returns `AppError::UnsafeSql` if the SQL contains forbidden keywords.
     pub fn validate(sql: &str) -> Result<(), AppError> {
+        Self::reject_keywords(sql, &FORBIDDEN_KEYWORDS)
+    }
+
+    /// Validates a previewed UPDATE/DELETE statement before execution.
+    ///
+    /// The change was confirmed after reviewing the affected rows, so
+    /// `DELETE FROM` is allowed; the other forbidden operations are not.
+    ///
+    /// # Errors
+    /// Returns `AppError::UnsafeSql` if the SQL contains forbidden keywords.
+    pub fn validate_change(sql: &str) -> Result<(), AppError> {
+        let keywords: Vec<&str> = FORBIDDEN_KEYWORDS
+            .into_iter()
+            .filter(|k| *k != "DELETE FROM")
+            .collect();
+        Self::reject_keywords(sql, &keywords)
+    }
+
+    /// Validates that a statement only reads.
+    ///
+    /// Strings, quoted identifiers and comments of `dialect` are skipped, so
+    /// `/* x */ DELETE ...` is classified by `DELETE`. The first keyword must
+    /// start a read, and no keyword that writes may appear anywhere, so a
+    /// data-modifying CTE such as `WITH d AS (DELETE ... RETURNING *) SELECT`
+    /// is rejected as well. Names qualified with `.` are not keywords.
+    ///
+    /// # Errors
+    /// Returns `AppError::UnsafeSql` naming the offending keyword.
+    pub fn validate_read_only(sql: &str, dialect: Dialect) -> Result<(), AppError> {
+        let words = keywords(sql, dialect);
+        let first = words.first().map(String::as_str).unwrap_or_default();
+        if !READ_KEYWORDS.contains(&first) {
+            return Err(AppError::UnsafeSql(format!("not a read-only statement: {}", first)));
+        }
+        match words.iter().find(|w| WRITE_KEYWORDS.contains(&w.as_str())) {
+            Some(keyword) => Err(AppError::UnsafeSql(format!("forbidden operation: {}", keyword))),
+            None => Ok(()),
+        }
+    }
+
+    fn reject_keywords(sql: &str, keywords: &[&str]) -> Result<(), AppError> {
         let sql_upper = sql.to_uppercase();
-        for keyword in FORBIDDEN_KEYWORDS {
+        for keyword in keywords {
             if sql_upper.contains(keyword) {
                 return Err(AppError::UnsafeSql(format!(
                     "forbidden operation: {}",
@@ -39,6 +94,16 @@ impl SqlValidator {
         sql.trim().to_uppercase().starts_with("SELECT")
     }
 
+    /// Checks if the SQL is a DDL statement (CREATE/ALTER/DROP/TRUNCATE/RENAME).
+    pub fn is_ddl(sql: &str) -> bool {
+        let first = sql
+            .trim_start()
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        DDL_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(first))
+    }
+
     /// Checks if the SQL is a modification query (INSERT/UPDATE/DELETE).
     pub fn is_modification(sql: &str) -> bool {
         let sql_upper = sql.trim().to_uppercase();
@@ -48,6 +113,35 @@ impl SqlValidator {
     }
 }
 
+/// Upper-cased bare words of `sql` outside strings, quoted identifiers and
+/// comments, skipping words that follow `.`.
+fn keywords(sql: &str, dialect: Dialect) -> Vec<String> {
+    let b = sql.as_bytes();
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < b.len() {
+        if let Some((_, end)) = SqlLexer::skip_at(sql, i, dialect) {
+            i = end;
+        } else if b[i].is_ascii_alphabetic() || b[i] == b'_' {
+            let start = i;
+            while i < b.len() && (b[i].is_ascii_alphanumeric() || b[i] == b'_' || b[i] == b'$') {
+                i += 1;
+            }
+            if sql[..start].trim_end().ends_with('.') {
+                continue;
+            }
+            words.push(sql[start..i].to_ascii_uppercase());
+        } else if b[i].is_ascii_digit() {
+            while i < b.len() && (b[i].is_ascii_alphanumeric() || b[i] == b'_') {
+                i += 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    words
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -62,9 +156,41 @@ mod tests {
         assert!(SqlValidator::validate("DROP TABLE users").is_err());
     }
 
+    #[test]
+    fn test_confirmed_delete_is_allowed() {
+        assert!(SqlValidator::validate_change("DELETE FROM users WHERE id = 1").is_ok());
+        assert!(SqlValidator::validate_change("DELETE FROM users; DROP TABLE users").is_err());
+    }
+
     #[test]
     fn test_is_select() {
         assert!(SqlValidator::is_select("SELECT * FROM users"));
         assert!(!SqlValidator::is_select("INSERT INTO users"));
     }
+
+    #[test]
+    fn test_validate_read_only() {
+        let ok = |sql: &str| SqlValidator::validate_read_only(sql, Dialect::ANY).is_ok();
+        assert!(ok("SELECT * FROM users WHERE note = 'DELETE FROM users'"));
+        assert!(ok("-- DELETE FROM users\nSELECT t.update, \"insert\" FROM t"));
+        assert!(ok("WITH recent AS (SELECT * FROM orders) SELECT * FROM recent ORDER BY id DESC"));
+        assert!(ok("EXPLAIN SELECT 1"));
+
+        assert!(!ok("/* x */ DELETE FROM users"));
+        assert!(!ok("/* SELECT */ UPDATE users SET name = 'x'"));
+        assert!(!ok("WITH d AS (DELETE FROM users RETURNING *) SELECT * FROM d"));
+        assert!(!ok("WITH u AS (UPDATE users SET name = 'x' RETURNING id) SELECT * FROM u"));
+        assert!(!ok("SELECT 1; DROP TABLE users"));
+        assert!(!ok("SELECT * INTO backup FROM users"));
+        assert!(!ok("PRAGMA journal_mode = WAL"));
+        assert!(!ok(""));
+    }
+
+    #[test]
+    fn test_is_ddl() {
+        assert!(SqlValidator::is_ddl("  drop table users"));
+        assert!(SqlValidator::is_ddl("TRUNCATE logs"));
+        assert!(!SqlValidator::is_ddl("CREATED_AT"));
+        assert!(!SqlValidator::is_ddl("SELECT 'DROP TABLE x'"));
+    }
 }
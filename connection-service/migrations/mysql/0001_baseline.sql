@@ -0,0 +1,300 @@
+-- Baseline metadata schema for MySQL: the tables created by
+-- connection-service before migrations were introduced.
+
+-- Saved connections
+CREATE TABLE IF NOT EXISTS `connections` (
+    `id`            VARCHAR(64)   NOT NULL,
+    `name`          VARCHAR(100)  NOT NULL,
+    `db_type`       VARCHAR(32)   NOT NULL,
+    `host`          VARCHAR(255)  DEFAULT NULL,
+    `port`          SMALLINT UNSIGNED DEFAULT NULL,
+    `username`      VARCHAR(128)  DEFAULT NULL,
+    `password`      VARCHAR(512)  DEFAULT NULL,
+    `password_ref`  VARCHAR(512)  DEFAULT NULL,
+    `database_name` VARCHAR(128)  DEFAULT NULL,
+    `file_path`     VARCHAR(512)  DEFAULT NULL,
+    `org`           VARCHAR(128)  DEFAULT NULL,
+    `allowlist`     TEXT          DEFAULT NULL,
+    `masking`       TEXT          DEFAULT NULL,
+    `statement_policy` TEXT       DEFAULT NULL,
+    `query_timeout_ms` INT UNSIGNED DEFAULT NULL,
+    `pinned`        TINYINT(1)    NOT NULL DEFAULT 0,
+    `pool_max_connections`      INT UNSIGNED DEFAULT NULL,
+    `pool_min_connections`      INT UNSIGNED DEFAULT NULL,
+    `pool_acquire_timeout_secs` INT UNSIGNED DEFAULT NULL,
+    `pool_idle_timeout_secs`    INT UNSIGNED DEFAULT NULL,
+    `owner_id`      VARCHAR(128)  DEFAULT NULL,
+    `created_at`    DATETIME      NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    `updated_at`    DATETIME      NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (`id`),
+    KEY `idx_db_type` (`db_type`),
+    KEY `idx_created_at` (`created_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Connection change history
+CREATE TABLE IF NOT EXISTS `connection_revisions` (
+    `id`            BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    `connection_id` VARCHAR(64)     NOT NULL,
+    `revision`      INT UNSIGNED    NOT NULL,
+    `action`        VARCHAR(16)     NOT NULL,
+    `actor`         VARCHAR(128)    DEFAULT NULL,
+    `changes`       MEDIUMTEXT      NOT NULL,
+    `snapshot`      MEDIUMTEXT      DEFAULT NULL,
+    `restored_from` INT UNSIGNED    DEFAULT NULL,
+    `created_at`    DATETIME        NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_connection_revision` (`connection_id`, `revision`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Favorite and recently used connections
+CREATE TABLE IF NOT EXISTS `user_connections` (
+    `principal`     VARCHAR(128)    NOT NULL,
+    `connection_id` VARCHAR(64)     NOT NULL,
+    `favorite`      TINYINT(1)      NOT NULL DEFAULT 0,
+    `favorited_at`  DATETIME        DEFAULT NULL,
+    `last_used_at`  DATETIME        DEFAULT NULL,
+    `use_count`     BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (`principal`, `connection_id`),
+    KEY `idx_connection` (`connection_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Workspaces
+CREATE TABLE IF NOT EXISTS `workspaces` (
+    `id`          VARCHAR(64)  NOT NULL,
+    `name`        VARCHAR(100) NOT NULL,
+    `description` VARCHAR(500) DEFAULT NULL,
+    `created_by`  VARCHAR(128) DEFAULT NULL,
+    `created_at`  DATETIME     NOT NULL,
+    `updated_at`  DATETIME     NOT NULL,
+    PRIMARY KEY (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS `workspace_members` (
+    `workspace_id` VARCHAR(64)  NOT NULL,
+    `principal`    VARCHAR(128) NOT NULL,
+    `role`         VARCHAR(16)  NOT NULL,
+    `added_at`     DATETIME     NOT NULL,
+    PRIMARY KEY (`workspace_id`, `principal`),
+    KEY `idx_principal` (`principal`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS `workspace_resources` (
+    `workspace_id` VARCHAR(64) NOT NULL,
+    `kind`         VARCHAR(16) NOT NULL,
+    `resource_id`  VARCHAR(64) NOT NULL,
+    `added_at`     DATETIME    NOT NULL,
+    PRIMARY KEY (`workspace_id`, `kind`, `resource_id`),
+    KEY `idx_resource` (`kind`, `resource_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Long-running jobs (backups, restores, transfers)
+CREATE TABLE IF NOT EXISTS `jobs` (
+    `id`          VARCHAR(64)   NOT NULL,
+    `kind`        VARCHAR(16)   NOT NULL,
+    `status`      VARCHAR(16)   NOT NULL,
+    `progress`    DOUBLE        NOT NULL DEFAULT 0,
+    `result_ref`  VARCHAR(1024) DEFAULT NULL,
+    `error`       TEXT          DEFAULT NULL,
+    `created_at`  DATETIME      NOT NULL,
+    `updated_at`  DATETIME      NOT NULL,
+    `finished_at` DATETIME      DEFAULT NULL,
+    PRIMARY KEY (`id`),
+    KEY `idx_kind_created` (`kind`, `created_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Backups
+CREATE TABLE IF NOT EXISTS `backups` (
+    `id`            VARCHAR(64)   NOT NULL,
+    `connection_id` VARCHAR(64)   NOT NULL,
+    `database_name` VARCHAR(128)  NOT NULL,
+    `method`        VARCHAR(16)   NOT NULL,
+    `status`        VARCHAR(16)   NOT NULL,
+    `storage`       VARCHAR(16)   NOT NULL,
+    `location`      VARCHAR(1024) DEFAULT NULL,
+    `size_bytes`    BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    `table_count`   INT UNSIGNED  NOT NULL DEFAULT 0,
+    `error`         TEXT          DEFAULT NULL,
+    `created_at`    DATETIME      NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    `finished_at`   DATETIME      DEFAULT NULL,
+    PRIMARY KEY (`id`),
+    KEY `idx_connection_id` (`connection_id`, `created_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Scheduled jobs
+CREATE TABLE IF NOT EXISTS `scheduled_jobs` (
+    `id`            VARCHAR(64)   NOT NULL,
+    `name`          VARCHAR(100)  NOT NULL,
+    `connection_id` VARCHAR(64)   NOT NULL,
+    `cron_expr`     VARCHAR(100)  NOT NULL,
+    `kind`          VARCHAR(32)   NOT NULL,
+    `params`        TEXT          DEFAULT NULL,
+    `enabled`       TINYINT(1)    NOT NULL DEFAULT 1,
+    `next_run_at`   DATETIME      DEFAULT NULL,
+    `last_run_at`   DATETIME      DEFAULT NULL,
+    `last_status`   VARCHAR(16)   DEFAULT NULL,
+    `created_at`    DATETIME      NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    `updated_at`    DATETIME      NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (`id`),
+    KEY `idx_due` (`enabled`, `next_run_at`),
+    KEY `idx_connection_id` (`connection_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS `scheduled_job_runs` (
+    `id`            VARCHAR(64)   NOT NULL,
+    `job_id`        VARCHAR(64)   NOT NULL,
+    `status`        VARCHAR(16)   NOT NULL,
+    `message`       TEXT          DEFAULT NULL,
+    `started_at`    DATETIME      NOT NULL,
+    `finished_at`   DATETIME      DEFAULT NULL,
+    PRIMARY KEY (`id`),
+    KEY `idx_job_started` (`job_id`, `started_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Alert rules
+CREATE TABLE IF NOT EXISTS `alert_rules` (
+    `id`                VARCHAR(64)   NOT NULL,
+    `name`              VARCHAR(100)  NOT NULL,
+    `connection_id`     VARCHAR(64)   NOT NULL,
+    `database_name`     VARCHAR(255)  DEFAULT NULL,
+    `sql_text`          TEXT          NOT NULL,
+    `params`            TEXT          NOT NULL,
+    `cron_expr`         VARCHAR(100)  NOT NULL,
+    `condition_spec`    TEXT          NOT NULL,
+    `channels`          TEXT          NOT NULL,
+    `enabled`           TINYINT(1)    NOT NULL DEFAULT 1,
+    `state`             VARCHAR(16)   NOT NULL DEFAULT 'ok',
+    `last_value`        DOUBLE        DEFAULT NULL,
+    `last_error`        TEXT          DEFAULT NULL,
+    `last_evaluated_at` DATETIME      DEFAULT NULL,
+    `last_triggered_at` DATETIME      DEFAULT NULL,
+    `next_run_at`       DATETIME      DEFAULT NULL,
+    `created_at`        DATETIME      NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (`id`),
+    KEY `idx_due` (`enabled`, `next_run_at`),
+    KEY `idx_connection_id` (`connection_id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Query result snapshots
+CREATE TABLE IF NOT EXISTS `query_snapshots` (
+    `id`            VARCHAR(64)     NOT NULL,
+    `name`          VARCHAR(100)    NOT NULL,
+    `connection_id` VARCHAR(64)     NOT NULL,
+    `database_name` VARCHAR(128)    DEFAULT NULL,
+    `sql_text`      TEXT            NOT NULL,
+    `result`        LONGTEXT        NOT NULL,
+    `row_count`     BIGINT UNSIGNED NOT NULL,
+    `truncated`     TINYINT(1)      NOT NULL DEFAULT 0,
+    `size_bytes`    BIGINT UNSIGNED NOT NULL,
+    `created_by`    VARCHAR(128)    DEFAULT NULL,
+    `created_at`    DATETIME(3)     NOT NULL,
+    `expires_at`    DATETIME(3)     NOT NULL,
+    PRIMARY KEY (`id`),
+    KEY `idx_series` (`connection_id`, `name`, `created_at`),
+    KEY `idx_expires_at` (`expires_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Workload statistics per hour
+CREATE TABLE IF NOT EXISTS `workload_stats` (
+    `connection_id`  VARCHAR(64)  NOT NULL,
+    `bucket_start`   DATETIME     NOT NULL,
+    `statement_type` VARCHAR(16)  NOT NULL,
+    `table_name`     VARCHAR(255) NOT NULL DEFAULT '',
+    `executions`     BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    `errors`         BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    `total_ms`       BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (`connection_id`, `bucket_start`, `statement_type`, `table_name`),
+    KEY `idx_workload_bucket` (`bucket_start`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Daily usage and quotas
+CREATE TABLE IF NOT EXISTS `user_usage` (
+    `principal`    VARCHAR(128)    NOT NULL,
+    `usage_date`   DATE            NOT NULL,
+    `queries`      BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    `rows_scanned` BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    `execution_ms` BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (`principal`, `usage_date`),
+    KEY `idx_usage_date` (`usage_date`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS `user_quotas` (
+    `principal`        VARCHAR(128)    NOT NULL,
+    `max_queries`      BIGINT UNSIGNED DEFAULT NULL,
+    `max_rows_scanned` BIGINT UNSIGNED DEFAULT NULL,
+    `max_execution_ms` BIGINT UNSIGNED DEFAULT NULL,
+    `updated_at`       DATETIME        NOT NULL,
+    PRIMARY KEY (`principal`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Users, sessions and API keys
+CREATE TABLE IF NOT EXISTS `users` (
+    `username`      VARCHAR(64)   NOT NULL,
+    `password_hash` VARCHAR(255)  NOT NULL,
+    `created_at`    DATETIME      NOT NULL,
+    `updated_at`    DATETIME      NOT NULL,
+    PRIMARY KEY (`username`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS `user_sessions` (
+    `id`           VARCHAR(64)   NOT NULL,
+    `user_id`      VARCHAR(128)  NOT NULL,
+    `created_at`   DATETIME      NOT NULL,
+    `last_seen_at` DATETIME      DEFAULT NULL,
+    `expires_at`   DATETIME      NOT NULL,
+    `revoked_at`   DATETIME      DEFAULT NULL,
+    `user_agent`   VARCHAR(255)  DEFAULT NULL,
+    `ip_address`   VARCHAR(64)   DEFAULT NULL,
+    `refresh_token_hash` CHAR(64) DEFAULT NULL,
+    `roles`        TEXT          DEFAULT NULL,
+    PRIMARY KEY (`id`),
+    KEY `idx_user_id` (`user_id`),
+    KEY `idx_expires_at` (`expires_at`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS `api_keys` (
+    `id`             VARCHAR(64)   NOT NULL,
+    `name`           VARCHAR(100)  NOT NULL,
+    `prefix`         VARCHAR(16)   NOT NULL,
+    `key_hash`       CHAR(64)      NOT NULL,
+    `read_only`      TINYINT(1)    NOT NULL DEFAULT 1,
+    `connection_ids` TEXT          DEFAULT NULL,
+    `guest`          TINYINT(1)    NOT NULL DEFAULT 0,
+    `schemas`        TEXT          DEFAULT NULL,
+    `created_at`     DATETIME      NOT NULL,
+    `expires_at`     DATETIME      DEFAULT NULL,
+    `revoked_at`     DATETIME      DEFAULT NULL,
+    `last_used_at`   DATETIME      DEFAULT NULL,
+    PRIMARY KEY (`id`),
+    UNIQUE KEY `uk_key_hash` (`key_hash`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+-- Authorization policies and decision log
+CREATE TABLE IF NOT EXISTS `authz_policies` (
+    `id`          VARCHAR(64)   NOT NULL,
+    `name`        VARCHAR(100)  NOT NULL,
+    `description` TEXT          DEFAULT NULL,
+    `effect`      VARCHAR(10)   NOT NULL,
+    `principals`  TEXT          NOT NULL,
+    `actions`     TEXT          NOT NULL,
+    `resources`   TEXT          NOT NULL,
+    `enabled`     TINYINT(1)    NOT NULL DEFAULT 1,
+    `created_at`  DATETIME      NOT NULL,
+    `updated_at`  DATETIME      NOT NULL,
+    PRIMARY KEY (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS `authz_decisions` (
+    `id`          BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    `principal`   VARCHAR(128)    NOT NULL,
+    `action`      VARCHAR(32)     NOT NULL,
+    `resource`    VARCHAR(64)     DEFAULT NULL,
+    `method`      VARCHAR(10)     DEFAULT NULL,
+    `path`        VARCHAR(512)    DEFAULT NULL,
+    `allowed`     TINYINT(1)      NOT NULL,
+    `policy_id`   VARCHAR(64)     DEFAULT NULL,
+    `reason`      VARCHAR(255)    NOT NULL,
+    `decided_at`  DATETIME        NOT NULL,
+    PRIMARY KEY (`id`),
+    KEY `idx_decided_at` (`decided_at`),
+    KEY `idx_principal` (`principal`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
@@ -0,0 +1,301 @@
+-- Baseline metadata schema for PostgreSQL: the tables created by
+-- connection-service before migrations were introduced. DATETIME values are
+-- stored as `YYYY-MM-DD HH:MM:SS` text, as on the other backends.
+
+-- Saved connections
+CREATE TABLE IF NOT EXISTS "connections" (
+    "id" VARCHAR(64) NOT NULL,
+    "name" VARCHAR(100) NOT NULL,
+    "db_type" VARCHAR(32) NOT NULL,
+    "host" VARCHAR(255) DEFAULT NULL,
+    "port" BIGINT DEFAULT NULL,
+    "username" VARCHAR(128) DEFAULT NULL,
+    "password" VARCHAR(512) DEFAULT NULL,
+    "password_ref" VARCHAR(512) DEFAULT NULL,
+    "database_name" VARCHAR(128) DEFAULT NULL,
+    "file_path" VARCHAR(512) DEFAULT NULL,
+    "org" VARCHAR(128) DEFAULT NULL,
+    "allowlist" TEXT DEFAULT NULL,
+    "masking" TEXT DEFAULT NULL,
+    "statement_policy" TEXT DEFAULT NULL,
+    "query_timeout_ms" BIGINT DEFAULT NULL,
+    "pinned" BOOLEAN NOT NULL DEFAULT FALSE,
+    "pool_max_connections" BIGINT DEFAULT NULL,
+    "pool_min_connections" BIGINT DEFAULT NULL,
+    "pool_acquire_timeout_secs" BIGINT DEFAULT NULL,
+    "pool_idle_timeout_secs" BIGINT DEFAULT NULL,
+    "owner_id" VARCHAR(128) DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    "updated_at" TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "connections_idx_db_type" ON "connections" ("db_type");
+CREATE INDEX IF NOT EXISTS "connections_idx_created_at" ON "connections" ("created_at");
+
+-- Connection change history
+CREATE TABLE IF NOT EXISTS "connection_revisions" (
+    "id" BIGINT NOT NULL GENERATED BY DEFAULT AS IDENTITY,
+    "connection_id" VARCHAR(64) NOT NULL,
+    "revision" BIGINT NOT NULL,
+    "action" VARCHAR(16) NOT NULL,
+    "actor" VARCHAR(128) DEFAULT NULL,
+    "changes" TEXT NOT NULL,
+    "snapshot" TEXT DEFAULT NULL,
+    "restored_from" BIGINT DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    PRIMARY KEY ("id"),
+    UNIQUE ("connection_id", "revision")
+);
+
+-- Favorite and recently used connections
+CREATE TABLE IF NOT EXISTS "user_connections" (
+    "principal" VARCHAR(128) NOT NULL,
+    "connection_id" VARCHAR(64) NOT NULL,
+    "favorite" BOOLEAN NOT NULL DEFAULT FALSE,
+    "favorited_at" TEXT DEFAULT NULL,
+    "last_used_at" TEXT DEFAULT NULL,
+    "use_count" BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY ("principal", "connection_id")
+);
+CREATE INDEX IF NOT EXISTS "user_connections_idx_connection" ON "user_connections" ("connection_id");
+
+-- Workspaces
+CREATE TABLE IF NOT EXISTS "workspaces" (
+    "id" VARCHAR(64) NOT NULL,
+    "name" VARCHAR(100) NOT NULL,
+    "description" VARCHAR(500) DEFAULT NULL,
+    "created_by" VARCHAR(128) DEFAULT NULL,
+    "created_at" TEXT NOT NULL,
+    "updated_at" TEXT NOT NULL,
+    PRIMARY KEY ("id")
+);
+
+CREATE TABLE IF NOT EXISTS "workspace_members" (
+    "workspace_id" VARCHAR(64) NOT NULL,
+    "principal" VARCHAR(128) NOT NULL,
+    "role" VARCHAR(16) NOT NULL,
+    "added_at" TEXT NOT NULL,
+    PRIMARY KEY ("workspace_id", "principal")
+);
+CREATE INDEX IF NOT EXISTS "workspace_members_idx_principal" ON "workspace_members" ("principal");
+
+CREATE TABLE IF NOT EXISTS "workspace_resources" (
+    "workspace_id" VARCHAR(64) NOT NULL,
+    "kind" VARCHAR(16) NOT NULL,
+    "resource_id" VARCHAR(64) NOT NULL,
+    "added_at" TEXT NOT NULL,
+    PRIMARY KEY ("workspace_id", "kind", "resource_id")
+);
+CREATE INDEX IF NOT EXISTS "workspace_resources_idx_resource" ON "workspace_resources" ("kind", "resource_id");
+
+-- Long-running jobs (backups, restores, transfers)
+CREATE TABLE IF NOT EXISTS "jobs" (
+    "id" VARCHAR(64) NOT NULL,
+    "kind" VARCHAR(16) NOT NULL,
+    "status" VARCHAR(16) NOT NULL,
+    "progress" DOUBLE PRECISION NOT NULL DEFAULT 0,
+    "result_ref" VARCHAR(1024) DEFAULT NULL,
+    "error" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL,
+    "updated_at" TEXT NOT NULL,
+    "finished_at" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "jobs_idx_kind_created" ON "jobs" ("kind", "created_at");
+
+-- Backups
+CREATE TABLE IF NOT EXISTS "backups" (
+    "id" VARCHAR(64) NOT NULL,
+    "connection_id" VARCHAR(64) NOT NULL,
+    "database_name" VARCHAR(128) NOT NULL,
+    "method" VARCHAR(16) NOT NULL,
+    "status" VARCHAR(16) NOT NULL,
+    "storage" VARCHAR(16) NOT NULL,
+    "location" VARCHAR(1024) DEFAULT NULL,
+    "size_bytes" BIGINT NOT NULL DEFAULT 0,
+    "table_count" BIGINT NOT NULL DEFAULT 0,
+    "error" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    "finished_at" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "backups_idx_connection_id" ON "backups" ("connection_id", "created_at");
+
+-- Scheduled jobs
+CREATE TABLE IF NOT EXISTS "scheduled_jobs" (
+    "id" VARCHAR(64) NOT NULL,
+    "name" VARCHAR(100) NOT NULL,
+    "connection_id" VARCHAR(64) NOT NULL,
+    "cron_expr" VARCHAR(100) NOT NULL,
+    "kind" VARCHAR(32) NOT NULL,
+    "params" TEXT DEFAULT NULL,
+    "enabled" BOOLEAN NOT NULL DEFAULT TRUE,
+    "next_run_at" TEXT DEFAULT NULL,
+    "last_run_at" TEXT DEFAULT NULL,
+    "last_status" VARCHAR(16) DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    "updated_at" TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "scheduled_jobs_idx_due" ON "scheduled_jobs" ("enabled", "next_run_at");
+CREATE INDEX IF NOT EXISTS "scheduled_jobs_idx_connection_id" ON "scheduled_jobs" ("connection_id");
+
+CREATE TABLE IF NOT EXISTS "scheduled_job_runs" (
+    "id" VARCHAR(64) NOT NULL,
+    "job_id" VARCHAR(64) NOT NULL,
+    "status" VARCHAR(16) NOT NULL,
+    "message" TEXT DEFAULT NULL,
+    "started_at" TEXT NOT NULL,
+    "finished_at" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "scheduled_job_runs_idx_job_started" ON "scheduled_job_runs" ("job_id", "started_at");
+
+-- Alert rules
+CREATE TABLE IF NOT EXISTS "alert_rules" (
+    "id" VARCHAR(64) NOT NULL,
+    "name" VARCHAR(100) NOT NULL,
+    "connection_id" VARCHAR(64) NOT NULL,
+    "database_name" VARCHAR(255) DEFAULT NULL,
+    "sql_text" TEXT NOT NULL,
+    "params" TEXT NOT NULL,
+    "cron_expr" VARCHAR(100) NOT NULL,
+    "condition_spec" TEXT NOT NULL,
+    "channels" TEXT NOT NULL,
+    "enabled" BOOLEAN NOT NULL DEFAULT TRUE,
+    "state" VARCHAR(16) NOT NULL DEFAULT 'ok',
+    "last_value" DOUBLE PRECISION DEFAULT NULL,
+    "last_error" TEXT DEFAULT NULL,
+    "last_evaluated_at" TEXT DEFAULT NULL,
+    "last_triggered_at" TEXT DEFAULT NULL,
+    "next_run_at" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "alert_rules_idx_due" ON "alert_rules" ("enabled", "next_run_at");
+CREATE INDEX IF NOT EXISTS "alert_rules_idx_connection_id" ON "alert_rules" ("connection_id");
+
+-- Query result snapshots
+CREATE TABLE IF NOT EXISTS "query_snapshots" (
+    "id" VARCHAR(64) NOT NULL,
+    "name" VARCHAR(100) NOT NULL,
+    "connection_id" VARCHAR(64) NOT NULL,
+    "database_name" VARCHAR(128) DEFAULT NULL,
+    "sql_text" TEXT NOT NULL,
+    "result" TEXT NOT NULL,
+    "row_count" BIGINT NOT NULL,
+    "truncated" BOOLEAN NOT NULL DEFAULT FALSE,
+    "size_bytes" BIGINT NOT NULL,
+    "created_by" VARCHAR(128) DEFAULT NULL,
+    "created_at" TEXT NOT NULL,
+    "expires_at" TEXT NOT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "query_snapshots_idx_series" ON "query_snapshots" ("connection_id", "name", "created_at");
+CREATE INDEX IF NOT EXISTS "query_snapshots_idx_expires_at" ON "query_snapshots" ("expires_at");
+
+-- Workload statistics per hour
+CREATE TABLE IF NOT EXISTS "workload_stats" (
+    "connection_id" VARCHAR(64) NOT NULL,
+    "bucket_start" TEXT NOT NULL,
+    "statement_type" VARCHAR(16) NOT NULL,
+    "table_name" VARCHAR(255) NOT NULL DEFAULT '',
+    "executions" BIGINT NOT NULL DEFAULT 0,
+    "errors" BIGINT NOT NULL DEFAULT 0,
+    "total_ms" BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY ("connection_id", "bucket_start", "statement_type", "table_name")
+);
+CREATE INDEX IF NOT EXISTS "workload_stats_idx_workload_bucket" ON "workload_stats" ("bucket_start");
+
+-- Daily usage and quotas
+CREATE TABLE IF NOT EXISTS "user_usage" (
+    "principal" VARCHAR(128) NOT NULL,
+    "usage_date" TEXT NOT NULL,
+    "queries" BIGINT NOT NULL DEFAULT 0,
+    "rows_scanned" BIGINT NOT NULL DEFAULT 0,
+    "execution_ms" BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY ("principal", "usage_date")
+);
+CREATE INDEX IF NOT EXISTS "user_usage_idx_usage_date" ON "user_usage" ("usage_date");
+
+CREATE TABLE IF NOT EXISTS "user_quotas" (
+    "principal" VARCHAR(128) NOT NULL,
+    "max_queries" BIGINT DEFAULT NULL,
+    "max_rows_scanned" BIGINT DEFAULT NULL,
+    "max_execution_ms" BIGINT DEFAULT NULL,
+    "updated_at" TEXT NOT NULL,
+    PRIMARY KEY ("principal")
+);
+
+-- Users, sessions and API keys
+CREATE TABLE IF NOT EXISTS "users" (
+    "username" VARCHAR(64) NOT NULL,
+    "password_hash" VARCHAR(255) NOT NULL,
+    "created_at" TEXT NOT NULL,
+    "updated_at" TEXT NOT NULL,
+    PRIMARY KEY ("username")
+);
+
+CREATE TABLE IF NOT EXISTS "user_sessions" (
+    "id" VARCHAR(64) NOT NULL,
+    "user_id" VARCHAR(128) NOT NULL,
+    "created_at" TEXT NOT NULL,
+    "last_seen_at" TEXT DEFAULT NULL,
+    "expires_at" TEXT NOT NULL,
+    "revoked_at" TEXT DEFAULT NULL,
+    "user_agent" VARCHAR(255) DEFAULT NULL,
+    "ip_address" VARCHAR(64) DEFAULT NULL,
+    "refresh_token_hash" CHAR(64) DEFAULT NULL,
+    "roles" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "user_sessions_idx_user_id" ON "user_sessions" ("user_id");
+CREATE INDEX IF NOT EXISTS "user_sessions_idx_expires_at" ON "user_sessions" ("expires_at");
+
+CREATE TABLE IF NOT EXISTS "api_keys" (
+    "id" VARCHAR(64) NOT NULL,
+    "name" VARCHAR(100) NOT NULL,
+    "prefix" VARCHAR(16) NOT NULL,
+    "key_hash" CHAR(64) NOT NULL,
+    "read_only" BOOLEAN NOT NULL DEFAULT TRUE,
+    "connection_ids" TEXT DEFAULT NULL,
+    "guest" BOOLEAN NOT NULL DEFAULT FALSE,
+    "schemas" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL,
+    "expires_at" TEXT DEFAULT NULL,
+    "revoked_at" TEXT DEFAULT NULL,
+    "last_used_at" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id"),
+    UNIQUE ("key_hash")
+);
+
+-- Authorization policies and decision log
+CREATE TABLE IF NOT EXISTS "authz_policies" (
+    "id" VARCHAR(64) NOT NULL,
+    "name" VARCHAR(100) NOT NULL,
+    "description" TEXT DEFAULT NULL,
+    "effect" VARCHAR(10) NOT NULL,
+    "principals" TEXT NOT NULL,
+    "actions" TEXT NOT NULL,
+    "resources" TEXT NOT NULL,
+    "enabled" BOOLEAN NOT NULL DEFAULT TRUE,
+    "created_at" TEXT NOT NULL,
+    "updated_at" TEXT NOT NULL,
+    PRIMARY KEY ("id")
+);
+
+CREATE TABLE IF NOT EXISTS "authz_decisions" (
+    "id" BIGINT NOT NULL GENERATED BY DEFAULT AS IDENTITY,
+    "principal" VARCHAR(128) NOT NULL,
+    "action" VARCHAR(32) NOT NULL,
+    "resource" VARCHAR(64) DEFAULT NULL,
+    "method" VARCHAR(10) DEFAULT NULL,
+    "path" VARCHAR(512) DEFAULT NULL,
+    "allowed" BOOLEAN NOT NULL,
+    "policy_id" VARCHAR(64) DEFAULT NULL,
+    "reason" VARCHAR(255) NOT NULL,
+    "decided_at" TEXT NOT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "authz_decisions_idx_decided_at" ON "authz_decisions" ("decided_at");
+CREATE INDEX IF NOT EXISTS "authz_decisions_idx_principal" ON "authz_decisions" ("principal");
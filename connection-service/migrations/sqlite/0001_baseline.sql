@@ -0,0 +1,299 @@
+-- Baseline metadata schema for SQLite: the tables created by
+-- connection-service before migrations were introduced. Timestamps are
+-- stored as `YYYY-MM-DD HH:MM:SS` text and flags as 0/1 integers.
+
+-- Saved connections
+CREATE TABLE IF NOT EXISTS "connections" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "db_type" TEXT NOT NULL,
+    "host" TEXT DEFAULT NULL,
+    "port" INTEGER DEFAULT NULL,
+    "username" TEXT DEFAULT NULL,
+    "password" TEXT DEFAULT NULL,
+    "password_ref" TEXT DEFAULT NULL,
+    "database_name" TEXT DEFAULT NULL,
+    "file_path" TEXT DEFAULT NULL,
+    "org" TEXT DEFAULT NULL,
+    "allowlist" TEXT DEFAULT NULL,
+    "masking" TEXT DEFAULT NULL,
+    "statement_policy" TEXT DEFAULT NULL,
+    "query_timeout_ms" INTEGER DEFAULT NULL,
+    "pinned" INTEGER NOT NULL DEFAULT 0,
+    "pool_max_connections" INTEGER DEFAULT NULL,
+    "pool_min_connections" INTEGER DEFAULT NULL,
+    "pool_acquire_timeout_secs" INTEGER DEFAULT NULL,
+    "pool_idle_timeout_secs" INTEGER DEFAULT NULL,
+    "owner_id" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "connections_idx_db_type" ON "connections" ("db_type");
+CREATE INDEX IF NOT EXISTS "connections_idx_created_at" ON "connections" ("created_at");
+
+-- Connection change history
+CREATE TABLE IF NOT EXISTS "connection_revisions" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "connection_id" TEXT NOT NULL,
+    "revision" INTEGER NOT NULL,
+    "action" TEXT NOT NULL,
+    "actor" TEXT DEFAULT NULL,
+    "changes" TEXT NOT NULL,
+    "snapshot" TEXT DEFAULT NULL,
+    "restored_from" INTEGER DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE ("connection_id", "revision")
+);
+
+-- Favorite and recently used connections
+CREATE TABLE IF NOT EXISTS "user_connections" (
+    "principal" TEXT NOT NULL,
+    "connection_id" TEXT NOT NULL,
+    "favorite" INTEGER NOT NULL DEFAULT 0,
+    "favorited_at" TEXT DEFAULT NULL,
+    "last_used_at" TEXT DEFAULT NULL,
+    "use_count" INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY ("principal", "connection_id")
+);
+CREATE INDEX IF NOT EXISTS "user_connections_idx_connection" ON "user_connections" ("connection_id");
+
+-- Workspaces
+CREATE TABLE IF NOT EXISTS "workspaces" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "description" TEXT DEFAULT NULL,
+    "created_by" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL,
+    "updated_at" TEXT NOT NULL,
+    PRIMARY KEY ("id")
+);
+
+CREATE TABLE IF NOT EXISTS "workspace_members" (
+    "workspace_id" TEXT NOT NULL,
+    "principal" TEXT NOT NULL,
+    "role" TEXT NOT NULL,
+    "added_at" TEXT NOT NULL,
+    PRIMARY KEY ("workspace_id", "principal")
+);
+CREATE INDEX IF NOT EXISTS "workspace_members_idx_principal" ON "workspace_members" ("principal");
+
+CREATE TABLE IF NOT EXISTS "workspace_resources" (
+    "workspace_id" TEXT NOT NULL,
+    "kind" TEXT NOT NULL,
+    "resource_id" TEXT NOT NULL,
+    "added_at" TEXT NOT NULL,
+    PRIMARY KEY ("workspace_id", "kind", "resource_id")
+);
+CREATE INDEX IF NOT EXISTS "workspace_resources_idx_resource" ON "workspace_resources" ("kind", "resource_id");
+
+-- Long-running jobs (backups, restores, transfers)
+CREATE TABLE IF NOT EXISTS "jobs" (
+    "id" TEXT NOT NULL,
+    "kind" TEXT NOT NULL,
+    "status" TEXT NOT NULL,
+    "progress" REAL NOT NULL DEFAULT 0,
+    "result_ref" TEXT DEFAULT NULL,
+    "error" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL,
+    "updated_at" TEXT NOT NULL,
+    "finished_at" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "jobs_idx_kind_created" ON "jobs" ("kind", "created_at");
+
+-- Backups
+CREATE TABLE IF NOT EXISTS "backups" (
+    "id" TEXT NOT NULL,
+    "connection_id" TEXT NOT NULL,
+    "database_name" TEXT NOT NULL,
+    "method" TEXT NOT NULL,
+    "status" TEXT NOT NULL,
+    "storage" TEXT NOT NULL,
+    "location" TEXT DEFAULT NULL,
+    "size_bytes" INTEGER NOT NULL DEFAULT 0,
+    "table_count" INTEGER NOT NULL DEFAULT 0,
+    "error" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "finished_at" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "backups_idx_connection_id" ON "backups" ("connection_id", "created_at");
+
+-- Scheduled jobs
+CREATE TABLE IF NOT EXISTS "scheduled_jobs" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "connection_id" TEXT NOT NULL,
+    "cron_expr" TEXT NOT NULL,
+    "kind" TEXT NOT NULL,
+    "params" TEXT DEFAULT NULL,
+    "enabled" INTEGER NOT NULL DEFAULT 1,
+    "next_run_at" TEXT DEFAULT NULL,
+    "last_run_at" TEXT DEFAULT NULL,
+    "last_status" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "scheduled_jobs_idx_due" ON "scheduled_jobs" ("enabled", "next_run_at");
+CREATE INDEX IF NOT EXISTS "scheduled_jobs_idx_connection_id" ON "scheduled_jobs" ("connection_id");
+
+CREATE TABLE IF NOT EXISTS "scheduled_job_runs" (
+    "id" TEXT NOT NULL,
+    "job_id" TEXT NOT NULL,
+    "status" TEXT NOT NULL,
+    "message" TEXT DEFAULT NULL,
+    "started_at" TEXT NOT NULL,
+    "finished_at" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "scheduled_job_runs_idx_job_started" ON "scheduled_job_runs" ("job_id", "started_at");
+
+-- Alert rules
+CREATE TABLE IF NOT EXISTS "alert_rules" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "connection_id" TEXT NOT NULL,
+    "database_name" TEXT DEFAULT NULL,
+    "sql_text" TEXT NOT NULL,
+    "params" TEXT NOT NULL,
+    "cron_expr" TEXT NOT NULL,
+    "condition_spec" TEXT NOT NULL,
+    "channels" TEXT NOT NULL,
+    "enabled" INTEGER NOT NULL DEFAULT 1,
+    "state" TEXT NOT NULL DEFAULT 'ok',
+    "last_value" REAL DEFAULT NULL,
+    "last_error" TEXT DEFAULT NULL,
+    "last_evaluated_at" TEXT DEFAULT NULL,
+    "last_triggered_at" TEXT DEFAULT NULL,
+    "next_run_at" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "alert_rules_idx_due" ON "alert_rules" ("enabled", "next_run_at");
+CREATE INDEX IF NOT EXISTS "alert_rules_idx_connection_id" ON "alert_rules" ("connection_id");
+
+-- Query result snapshots
+CREATE TABLE IF NOT EXISTS "query_snapshots" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "connection_id" TEXT NOT NULL,
+    "database_name" TEXT DEFAULT NULL,
+    "sql_text" TEXT NOT NULL,
+    "result" TEXT NOT NULL,
+    "row_count" INTEGER NOT NULL,
+    "truncated" INTEGER NOT NULL DEFAULT 0,
+    "size_bytes" INTEGER NOT NULL,
+    "created_by" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL,
+    "expires_at" TEXT NOT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "query_snapshots_idx_series" ON "query_snapshots" ("connection_id", "name", "created_at");
+CREATE INDEX IF NOT EXISTS "query_snapshots_idx_expires_at" ON "query_snapshots" ("expires_at");
+
+-- Workload statistics per hour
+CREATE TABLE IF NOT EXISTS "workload_stats" (
+    "connection_id" TEXT NOT NULL,
+    "bucket_start" TEXT NOT NULL,
+    "statement_type" TEXT NOT NULL,
+    "table_name" TEXT NOT NULL DEFAULT '',
+    "executions" INTEGER NOT NULL DEFAULT 0,
+    "errors" INTEGER NOT NULL DEFAULT 0,
+    "total_ms" INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY ("connection_id", "bucket_start", "statement_type", "table_name")
+);
+CREATE INDEX IF NOT EXISTS "workload_stats_idx_workload_bucket" ON "workload_stats" ("bucket_start");
+
+-- Daily usage and quotas
+CREATE TABLE IF NOT EXISTS "user_usage" (
+    "principal" TEXT NOT NULL,
+    "usage_date" TEXT NOT NULL,
+    "queries" INTEGER NOT NULL DEFAULT 0,
+    "rows_scanned" INTEGER NOT NULL DEFAULT 0,
+    "execution_ms" INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY ("principal", "usage_date")
+);
+CREATE INDEX IF NOT EXISTS "user_usage_idx_usage_date" ON "user_usage" ("usage_date");
+
+CREATE TABLE IF NOT EXISTS "user_quotas" (
+    "principal" TEXT NOT NULL,
+    "max_queries" INTEGER DEFAULT NULL,
+    "max_rows_scanned" INTEGER DEFAULT NULL,
+    "max_execution_ms" INTEGER DEFAULT NULL,
+    "updated_at" TEXT NOT NULL,
+    PRIMARY KEY ("principal")
+);
+
+-- Users, sessions and API keys
+CREATE TABLE IF NOT EXISTS "users" (
+    "username" TEXT NOT NULL,
+    "password_hash" TEXT NOT NULL,
+    "created_at" TEXT NOT NULL,
+    "updated_at" TEXT NOT NULL,
+    PRIMARY KEY ("username")
+);
+
+CREATE TABLE IF NOT EXISTS "user_sessions" (
+    "id" TEXT NOT NULL,
+    "user_id" TEXT NOT NULL,
+    "created_at" TEXT NOT NULL,
+    "last_seen_at" TEXT DEFAULT NULL,
+    "expires_at" TEXT NOT NULL,
+    "revoked_at" TEXT DEFAULT NULL,
+    "user_agent" TEXT DEFAULT NULL,
+    "ip_address" TEXT DEFAULT NULL,
+    "refresh_token_hash" TEXT DEFAULT NULL,
+    "roles" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id")
+);
+CREATE INDEX IF NOT EXISTS "user_sessions_idx_user_id" ON "user_sessions" ("user_id");
+CREATE INDEX IF NOT EXISTS "user_sessions_idx_expires_at" ON "user_sessions" ("expires_at");
+
+CREATE TABLE IF NOT EXISTS "api_keys" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "prefix" TEXT NOT NULL,
+    "key_hash" TEXT NOT NULL,
+    "read_only" INTEGER NOT NULL DEFAULT 1,
+    "connection_ids" TEXT DEFAULT NULL,
+    "guest" INTEGER NOT NULL DEFAULT 0,
+    "schemas" TEXT DEFAULT NULL,
+    "created_at" TEXT NOT NULL,
+    "expires_at" TEXT DEFAULT NULL,
+    "revoked_at" TEXT DEFAULT NULL,
+    "last_used_at" TEXT DEFAULT NULL,
+    PRIMARY KEY ("id"),
+    UNIQUE ("key_hash")
+);
+
+-- Authorization policies and decision log
+CREATE TABLE IF NOT EXISTS "authz_policies" (
+    "id" TEXT NOT NULL,
+    "name" TEXT NOT NULL,
+    "description" TEXT DEFAULT NULL,
+    "effect" TEXT NOT NULL,
+    "principals" TEXT NOT NULL,
+    "actions" TEXT NOT NULL,
+    "resources" TEXT NOT NULL,
+    "enabled" INTEGER NOT NULL DEFAULT 1,
+    "created_at" TEXT NOT NULL,
+    "updated_at" TEXT NOT NULL,
+    PRIMARY KEY ("id")
+);
+
+CREATE TABLE IF NOT EXISTS "authz_decisions" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "principal" TEXT NOT NULL,
+    "action" TEXT NOT NULL,
+    "resource" TEXT DEFAULT NULL,
+    "method" TEXT DEFAULT NULL,
+    "path" TEXT DEFAULT NULL,
+    "allowed" INTEGER NOT NULL,
+    "policy_id" TEXT DEFAULT NULL,
+    "reason" TEXT NOT NULL,
+    "decided_at" TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS "authz_decisions_idx_decided_at" ON "authz_decisions" ("decided_at");
+CREATE INDEX IF NOT EXISTS "authz_decisions_idx_principal" ON "authz_decisions" ("principal");
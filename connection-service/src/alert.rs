@@ -0,0 +1,431 @@
+//! Scheduled query alerts.
+//!
+//! Alert rules live in the `alert_rules` metadata table. Each rule runs a
+//! read-only query on a cron schedule (UTC), reads a value from the result
+//! (the first cell or the row count) and compares it with a threshold.
+//! When the condition starts to hold, the rule's channels are notified
+//! through the shared [`Notifier`]; while it keeps holding no further
+//! notifications are sent, so a rule alerts once per incident.
+//!
+//! Due rules are claimed like scheduled jobs, by advancing `next_run_at`
+//! with a conditional update, so several instances never evaluate the same
+//! tick twice.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::meta::Statement;
+use common::meta_query;
+use common::models::alert::{AlertEvaluation, AlertRule, AlertState, CreateAlertRuleRequest};
+use common::notify::{Notification, Notifier};
+use common::utils::sql_lexer::Dialect;
+use common::utils::{ChangePreviewSql, SqlValidator};
+use crate::pool_manager::PoolManager;
+use crate::scheduler::{format_datetime, next_run, parse_cron};
+
+/// How often due rules are looked for.
+const TICK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Rows read per evaluation; `row_count` conditions see at most this many.
+const MAX_ROWS: u32 = 10_000;
+
+const SELECT_RULE: Statement = Statement {
+    mysql: "SELECT `id`, `name`, `connection_id`, `database_name`, `sql_text`, `params`, `cron_expr`, \
+         `condition_spec`, `channels`, `enabled`, `state`, `last_value`, `last_error`, \
+         CAST(`last_evaluated_at` AS CHAR) AS last_evaluated_at, CAST(`last_triggered_at` AS CHAR) AS last_triggered_at, \
+         CAST(`next_run_at` AS CHAR) AS next_run_at, CAST(`created_at` AS CHAR) AS created_at FROM `alert_rules`",
+    postgres: r#"SELECT "id", "name", "connection_id", "database_name", "sql_text", "params", "cron_expr",
+         "condition_spec", "channels", "enabled", "state", "last_value", "last_error",
+         "last_evaluated_at", "last_triggered_at",
+         "next_run_at", "created_at" FROM "alert_rules""#,
+    sqlite: r#"SELECT "id", "name", "connection_id", "database_name", "sql_text", "params", "cron_expr",
+         "condition_spec", "channels", "enabled", "state", "last_value", "last_error",
+         "last_evaluated_at", "last_triggered_at",
+         "next_run_at", "created_at" FROM "alert_rules""#,
+};
+
+/// Row from the `alert_rules` metadata table.
+#[derive(sqlx::FromRow)]
+struct AlertRuleRow {
+    id: String,
+    name: String,
+    connection_id: String,
+    database_name: Option<String>,
+    sql_text: String,
+    params: String,
+    cron_expr: String,
+    condition_spec: String,
+    channels: String,
+    enabled: bool,
+    state: String,
+    last_value: Option<f64>,
+    last_error: Option<String>,
+    last_evaluated_at: Option<String>,
+    last_triggered_at: Option<String>,
+    next_run_at: Option<String>,
+    created_at: String,
+}
+
+impl AlertRuleRow {
+    fn into_rule(self) -> AppResult<AlertRule> {
+        let corrupted = |e: serde_json::Error| AppError::Internal(format!("Alert rule {} is corrupted: {}", self.id, e));
+        Ok(AlertRule {
+            params: serde_json::from_str(&self.params).map_err(corrupted)?,
+            condition: serde_json::from_str(&self.condition_spec).map_err(corrupted)?,
+            channels: serde_json::from_str(&self.channels).map_err(corrupted)?,
+            state: AlertState::parse(&self.state).unwrap_or(AlertState::Ok),
+            id: self.id,
+            name: self.name,
+            connection_id: self.connection_id,
+            database: self.database_name,
+            sql: self.sql_text,
+            cron: self.cron_expr,
+            enabled: self.enabled,
+            last_value: self.last_value,
+            last_error: self.last_error,
+            last_evaluated_at: self.last_evaluated_at,
+            last_triggered_at: self.last_triggered_at,
+            next_run_at: self.next_run_at,
+            created_at: self.created_at,
+        })
+    }
+}
+
+/// Stores alert rules, evaluates them when due and sends notifications.
+pub struct AlertManager {
+    pool_manager: Arc<PoolManager>,
+    notifier: Arc<Notifier>,
+}
+
+impl AlertManager {
+    /// Creates the manager.
+    pub fn new(pool_manager: Arc<PoolManager>, notifier: Arc<Notifier>) -> Self {
+        Self { pool_manager, notifier }
+    }
+
+    /// Spawns the evaluation loop.
+    pub fn spawn(self: &Arc<Self>) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = manager.run_due_rules().await {
+                    tracing::warn!(error = %e, "Alert evaluation tick failed");
+                }
+            }
+        });
+    }
+
+    /// Creates an alert rule.
+    pub async fn create(&self, req: CreateAlertRuleRequest) -> AppResult<AlertRule> {
+        let schedule = parse_cron(&req.cron)?;
+        if ChangePreviewSql::is_change(&req.sql, Dialect::ANY) || SqlValidator::is_ddl(&req.sql) {
+            return Err(AppError::InvalidInput("alert queries must be read-only".to_string()));
+        }
+        SqlValidator::validate(&req.sql)?;
+        for channel in &req.channels {
+            self.notifier.check(channel)?;
+        }
+        let config = self
+            .pool_manager
+            .get_connection(&req.connection_id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(req.connection_id.clone()))?;
+        if let Some(allowlist) = &config.allowlist {
+            allowlist.check_sql(&req.sql, req.database.as_deref().or(config.default_namespace()))?;
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let next_run_at = if req.enabled { next_run(&schedule, Utc::now()) } else { None };
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `alert_rules` (`id`, `name`, `connection_id`, `database_name`, `sql_text`, `params`, \
+                 `cron_expr`, `condition_spec`, `channels`, `enabled`, `next_run_at`) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "alert_rules" ("id", "name", "connection_id", "database_name", "sql_text", "params",
+                 "cron_expr", "condition_spec", "channels", "enabled", "next_run_at")
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"#,
+            sqlite: r#"INSERT INTO "alert_rules" ("id", "name", "connection_id", "database_name", "sql_text", "params",
+                 "cron_expr", "condition_spec", "channels", "enabled", "next_run_at")
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&id)
+                .bind(&req.name)
+                .bind(&req.connection_id)
+                .bind(&req.database)
+                .bind(&req.sql)
+                .bind(serde_json::Value::from(req.params.clone()).to_string())
+                .bind(req.cron.trim())
+                .bind(to_json(&req.condition)?)
+                .bind(to_json(&req.channels)?)
+                .bind(req.enabled)
+                .bind(next_run_at)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+
+        tracing::info!(rule_id = %id, name = %req.name, condition = %req.condition.describe(), "Alert rule created");
+        self.get(&id).await
+    }
+
+    /// Lists all alert rules.
+    pub async fn list(&self) -> AppResult<Vec<AlertRule>> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_RULE,
+            &Statement {
+                mysql: "ORDER BY `created_at` DESC",
+                postgres: r#"ORDER BY "created_at" DESC"#,
+                sqlite: r#"ORDER BY "created_at" DESC"#,
+            },
+        ]);
+        let rows: Vec<AlertRuleRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .fetch_all(pool)
+                .await
+        })?;
+        rows.into_iter().map(AlertRuleRow::into_rule).collect()
+    }
+
+    /// Gets an alert rule by ID.
+    pub async fn get(&self, rule_id: &str) -> AppResult<AlertRule> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_RULE,
+            &Statement {
+                mysql: "WHERE `id` = ?",
+                postgres: r#"WHERE "id" = $1"#,
+                sqlite: r#"WHERE "id" = ?"#,
+            },
+        ]);
+        let row: Option<AlertRuleRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .bind(rule_id)
+                .fetch_optional(pool)
+                .await
+        })?;
+        row.ok_or_else(|| AppError::NotFound(format!("alert rule {}", rule_id)))?
+            .into_rule()
+    }
+
+    /// Deletes an alert rule.
+    pub async fn delete(&self, rule_id: &str) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `alert_rules` WHERE `id` = ?",
+            postgres: r#"DELETE FROM "alert_rules" WHERE "id" = $1"#,
+            sqlite: r#"DELETE FROM "alert_rules" WHERE "id" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(rule_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result == 0 {
+            return Err(AppError::NotFound(format!("alert rule {}", rule_id)));
+        }
+        Ok(())
+    }
+
+    /// Enables or disables a rule; enabling reschedules it from now.
+    pub async fn set_enabled(&self, rule_id: &str, enabled: bool) -> AppResult<AlertRule> {
+        let rule = self.get(rule_id).await?;
+        let next_run_at = if enabled { next_run(&parse_cron(&rule.cron)?, Utc::now()) } else { None };
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "UPDATE `alert_rules` SET `enabled` = ?, `next_run_at` = ? WHERE `id` = ?",
+            postgres: r#"UPDATE "alert_rules" SET "enabled" = $1, "next_run_at" = $2 WHERE "id" = $3"#,
+            sqlite: r#"UPDATE "alert_rules" SET "enabled" = ?, "next_run_at" = ? WHERE "id" = ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(enabled)
+                .bind(next_run_at)
+                .bind(rule_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        tracing::info!(rule_id, enabled, "Alert rule toggled");
+        self.get(rule_id).await
+    }
+
+    /// Evaluates a rule immediately, outside its schedule.
+    pub async fn evaluate_now(&self, rule_id: &str) -> AppResult<AlertEvaluation> {
+        let rule = self.get(rule_id).await?;
+        self.evaluate(&rule).await
+    }
+
+    /// Claims and evaluates every enabled rule whose `next_run_at` has passed.
+    async fn run_due_rules(self: &Arc<Self>) -> AppResult<()> {
+        let now = Utc::now();
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_RULE,
+            &Statement {
+                mysql: "WHERE `enabled` = TRUE AND `next_run_at` <= ?",
+                postgres: r#"WHERE "enabled" = TRUE AND "next_run_at" <= $1"#,
+                sqlite: r#"WHERE "enabled" = TRUE AND "next_run_at" <= ?"#,
+            },
+        ]);
+        let due: Vec<AlertRuleRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .bind(format_datetime(now))
+                .fetch_all(pool)
+                .await
+        })?;
+
+        for row in due {
+            let rule = match row.into_rule() {
+                Ok(rule) => rule,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Skipping unreadable alert rule");
+                    continue;
+                }
+            };
+            let next = match parse_cron(&rule.cron) {
+                Ok(schedule) => next_run(&schedule, now),
+                Err(e) => {
+                    tracing::warn!(rule_id = %rule.id, error = %e, "Disabling alert rule with invalid cron expression");
+                    None
+                }
+            };
+
+            // Only the instance that moves next_run_at forward evaluates this tick.
+            let statement = self.pool_manager.meta_pool().sql(&Statement {
+                mysql: "UPDATE `alert_rules` SET `enabled` = ?, `next_run_at` = ? WHERE `id` = ? AND `next_run_at` = ?",
+                postgres: r#"UPDATE "alert_rules" SET "enabled" = $1, "next_run_at" = $2 WHERE "id" = $3 AND "next_run_at" = $4"#,
+                sqlite: r#"UPDATE "alert_rules" SET "enabled" = ?, "next_run_at" = ? WHERE "id" = ? AND "next_run_at" = ?"#,
+            });
+            let claimed = meta_query!(self.pool_manager.meta_pool(), |pool| {
+                sqlx::query(statement)
+                    .bind(next.is_some())
+                    .bind(&next)
+                    .bind(&rule.id)
+                    .bind(&rule.next_run_at)
+                    .execute(pool)
+                    .await
+                    .map(|r| r.rows_affected())
+            })? == 1;
+            if !claimed {
+                continue;
+            }
+
+            let manager = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = manager.evaluate(&rule).await {
+                    tracing::error!(rule_id = %rule.id, error = %e, "Failed to record alert evaluation");
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Runs the rule's query, updates its state and notifies on a change to triggered.
+    async fn evaluate(&self, rule: &AlertRule) -> AppResult<AlertEvaluation> {
+        let now = format_datetime(Utc::now());
+        let mut evaluation = AlertEvaluation {
+            rule_id: rule.id.clone(),
+            state: AlertState::Ok,
+            value: None,
+            row_count: 0,
+            notified: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        match self.read_value(rule).await {
+            Ok((value, row_count)) => {
+                evaluation.value = value;
+                evaluation.row_count = row_count;
+                if value.is_some_and(|v| rule.condition.is_met(v)) {
+                    evaluation.state = AlertState::Triggered;
+                }
+            }
+            Err(e) => {
+                tracing::warn!(rule_id = %rule.id, error = %e, "Alert query failed");
+                evaluation.state = AlertState::Error;
+                evaluation.errors.push(e.to_string());
+            }
+        }
+
+        let newly_triggered = evaluation.state == AlertState::Triggered && rule.state != AlertState::Triggered;
+        if newly_triggered {
+            let value = evaluation.value.map_or_else(|| "NULL".to_string(), |v| v.to_string());
+            let notification = Notification::new(
+                "alert.triggered",
+                format!("Alert `{}` triggered: {} (value {})", rule.name, rule.condition.describe(), value),
+                format!(
+                    "Rule: {} ({})\nConnection: {}\nRows: {}\nTriggered at: {} UTC",
+                    rule.name, rule.id, rule.connection_id, evaluation.row_count, now
+                ),
+                serde_json::json!({
+                    "rule_id": rule.id,
+                    "rule_name": rule.name,
+                    "connection_id": rule.connection_id,
+                    "condition": rule.condition.describe(),
+                    "value": evaluation.value,
+                    "row_count": evaluation.row_count,
+                }),
+            );
+            let outcomes = self.notifier.send(&rule.channels, &notification).await;
+            for (channel, outcome) in rule.channels.iter().zip(outcomes) {
+                match outcome {
+                    Ok(()) => evaluation.notified.push(channel.kind().to_string()),
+                    Err(e) => evaluation.errors.push(format!("{}: {}", channel.kind(), e)),
+                }
+            }
+            tracing::info!(rule_id = %rule.id, value = ?evaluation.value, notified = ?evaluation.notified, "Alert triggered");
+        }
+
+        let last_error = (evaluation.state == AlertState::Error).then(|| evaluation.errors.join("; "));
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "UPDATE `alert_rules` SET `state` = ?, `last_value` = ?, `last_error` = ?, `last_evaluated_at` = ?, \
+                 `last_triggered_at` = CASE WHEN ? THEN ? ELSE `last_triggered_at` END WHERE `id` = ?",
+            postgres: r#"UPDATE "alert_rules" SET "state" = $1, "last_value" = $2, "last_error" = $3, "last_evaluated_at" = $4,
+                 "last_triggered_at" = CASE WHEN $5 THEN $6 ELSE "last_triggered_at" END WHERE "id" = $7"#,
+            sqlite: r#"UPDATE "alert_rules" SET "state" = ?, "last_value" = ?, "last_error" = ?, "last_evaluated_at" = ?,
+                 "last_triggered_at" = CASE WHEN ? THEN ? ELSE "last_triggered_at" END WHERE "id" = ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(evaluation.state.as_str())
+                .bind(evaluation.value)
+                .bind(last_error)
+                .bind(&now)
+                .bind(newly_triggered)
+                .bind(&now)
+                .bind(&rule.id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+
+        Ok(evaluation)
+    }
+
+    /// Runs the query and returns the condition subject and the row count.
+    async fn read_value(&self, rule: &AlertRule) -> AppResult<(Option<f64>, u64)> {
+        let config = self
+            .pool_manager
+            .get_connection(&rule.connection_id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(rule.connection_id.clone()))?;
+        if let Some(allowlist) = &config.allowlist {
+            allowlist.check_sql(&rule.sql, rule.database.as_deref().or(config.default_namespace()))?;
+        }
+        let timeout = self.pool_manager.query_timeout(&config, None);
+        let result = self
+            .pool_manager
+            .execute_query(&rule.connection_id, rule.database.as_deref(), &rule.sql, MAX_ROWS, &rule.params, Some(timeout))
+            .await?;
+        Ok((rule.condition.value(&result)?, result.rows.len() as u64))
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> AppResult<String> {
+    serde_json::to_string(value).map_err(|e| AppError::Internal(format!("Failed to serialize alert rule: {}", e)))
+}
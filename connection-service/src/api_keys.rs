@@ -0,0 +1,350 @@
+//! Gateway API keys.
+//!
+//! Keys live in the `api_keys` metadata table. Only the SHA-256 hash of a key
+//! is stored; the key itself is returned once when issued. The gateway
+//! verifies the keys it receives through the internal verify endpoint and
+//! enforces their scope (read-only, allowed connections). Revoked or expired
+//! keys fail verification.
+//!
+//! Guest links are keys issued for a single connection: read-only, limited to
+//! query endpoints and optionally to some schemas, expiring within hours.
+//! Verification fills in the connection's default namespace so the gateway
+//! can resolve unqualified table names against the schemas.
+//!
+//! Configuration:
+//! - `GUEST_LINK_BASE_URL` - page that opens guest links; the key is appended
+//!   as the URL fragment (unset = no link URL, only the key)
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::meta::Statement;
+use common::meta_query;
+use common::models::api_key::{
+    ApiKey, CreateApiKeyRequest, CreateGuestLinkRequest, CreatedApiKey, CreatedGuestLink, KEY_PRINCIPAL_PREFIX,
+};
+use crate::pool_manager::PoolManager;
+
+/// Prefix of every issued key, to make leaked keys easy to recognise.
+const KEY_PREFIX: &str = "dbm_";
+
+/// Characters of the key kept in clear for display.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// MySQL DATETIME format used for stored timestamps (UTC).
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const SELECT_KEY: Statement = Statement {
+    mysql: "SELECT `id`, `name`, `prefix`, `read_only`, `connection_ids`, `guest`, `schemas`, \
+         CAST(`created_at` AS CHAR) AS created_at, CAST(`expires_at` AS CHAR) AS expires_at, \
+         CAST(`revoked_at` AS CHAR) AS revoked_at, CAST(`last_used_at` AS CHAR) AS last_used_at FROM `api_keys`",
+    postgres: r#"SELECT "id", "name", "prefix", "read_only", "connection_ids", "guest", "schemas",
+         "created_at", "expires_at",
+         "revoked_at", "last_used_at" FROM "api_keys""#,
+    sqlite: r#"SELECT "id", "name", "prefix", "read_only", "connection_ids", "guest", "schemas",
+         "created_at", "expires_at",
+         "revoked_at", "last_used_at" FROM "api_keys""#,
+};
+
+/// Row from the `api_keys` metadata table.
+#[derive(sqlx::FromRow)]
+struct ApiKeyRow {
+    id: String,
+    name: String,
+    prefix: String,
+    read_only: bool,
+    connection_ids: Option<String>,
+    guest: bool,
+    schemas: Option<String>,
+    created_at: String,
+    expires_at: Option<String>,
+    revoked_at: Option<String>,
+    last_used_at: Option<String>,
+}
+
+impl ApiKeyRow {
+    fn into_key(self) -> ApiKey {
+        ApiKey {
+            id: self.id,
+            name: self.name,
+            prefix: self.prefix,
+            read_only: self.read_only,
+            connection_ids: self
+                .connection_ids
+                .and_then(|c| serde_json::from_str(&c).ok())
+                .unwrap_or_default(),
+            guest: self.guest,
+            schemas: self
+                .schemas
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            default_namespace: None,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            revoked_at: self.revoked_at,
+            last_used_at: self.last_used_at,
+        }
+    }
+}
+
+/// Issues, lists, revokes and verifies API keys.
+pub struct ApiKeyStore {
+    pool_manager: Arc<PoolManager>,
+}
+
+impl ApiKeyStore {
+    /// Creates the store.
+    pub fn new(pool_manager: Arc<PoolManager>) -> Self {
+        Self { pool_manager }
+    }
+
+    /// Issues a key; the returned secret is not stored.
+    pub async fn create(&self, req: CreateApiKeyRequest) -> AppResult<CreatedApiKey> {
+        for connection_id in &req.connection_ids {
+            if self.pool_manager.get_connection(connection_id).await.is_none() {
+                return Err(AppError::ConnectionNotFound(connection_id.clone()));
+            }
+        }
+
+        let expires_at = req
+            .expires_in_days
+            .map(|days| Utc::now() + ChronoDuration::days(i64::from(days)));
+        let (key, api_key) = self
+            .insert(&req.name, req.read_only, &req.connection_ids, false, &[], expires_at)
+            .await?;
+        tracing::info!(key_id = %api_key.id, name = %req.name, read_only = req.read_only, "API key issued");
+        Ok(CreatedApiKey { key, api_key })
+    }
+
+    /// Issues a guest link to a connection: a read-only key limited to query
+    /// endpoints and the requested schemas, expiring after the requested hours.
+    pub async fn create_guest_link(
+        &self,
+        connection_id: &str,
+        req: CreateGuestLinkRequest,
+    ) -> AppResult<CreatedGuestLink> {
+        if self.pool_manager.get_connection(connection_id).await.is_none() {
+            return Err(AppError::ConnectionNotFound(connection_id.to_string()));
+        }
+
+        let expires_at = Utc::now() + ChronoDuration::hours(i64::from(req.expires_in_hours));
+        let connection_ids = [connection_id.to_string()];
+        let (key, api_key) = self
+            .insert(&req.name, true, &connection_ids, true, &req.schemas, Some(expires_at))
+            .await?;
+        let url = std::env::var("GUEST_LINK_BASE_URL")
+            .ok()
+            .filter(|base| !base.is_empty())
+            .map(|base| format!("{}#key={}", base, key));
+        tracing::info!(
+            key_id = %api_key.id,
+            connection_id = %connection_id,
+            schemas = ?req.schemas,
+            expires_at = %expires_at,
+            "Guest link issued"
+        );
+        Ok(CreatedGuestLink { key, url, api_key })
+    }
+
+    /// Stores a new key and returns it with its details.
+    async fn insert(
+        &self,
+        name: &str,
+        read_only: bool,
+        connection_ids: &[String],
+        guest: bool,
+        schemas: &[String],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> AppResult<(String, ApiKey)> {
+        let id = Uuid::new_v4().to_string();
+        let key = format!("{}{}{}", KEY_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `api_keys` (`id`, `name`, `prefix`, `key_hash`, `read_only`, `connection_ids`, `guest`, `schemas`, `created_at`, `expires_at`) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "api_keys" ("id", "name", "prefix", "key_hash", "read_only", "connection_ids", "guest", "schemas", "created_at", "expires_at")
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"#,
+            sqlite: r#"INSERT INTO "api_keys" ("id", "name", "prefix", "key_hash", "read_only", "connection_ids", "guest", "schemas", "created_at", "expires_at")
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&id)
+                .bind(name)
+                .bind(&key[..DISPLAY_PREFIX_LEN])
+                .bind(hash_key(&key))
+                .bind(read_only)
+                .bind(serde_json::to_string(connection_ids).unwrap_or_else(|_| "[]".to_string()))
+                .bind(guest)
+                .bind((!schemas.is_empty()).then(|| serde_json::to_string(schemas).unwrap_or_default()))
+                .bind(Utc::now().format(DATETIME_FORMAT).to_string())
+                .bind(expires_at.map(|t| t.format(DATETIME_FORMAT).to_string()))
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+
+        Ok((key, self.get(&id).await?))
+    }
+
+    /// Lists all keys, newest first.
+    pub async fn list(&self) -> AppResult<Vec<ApiKey>> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_KEY,
+            &Statement {
+                mysql: "ORDER BY `created_at` DESC",
+                postgres: r#"ORDER BY "created_at" DESC"#,
+                sqlite: r#"ORDER BY "created_at" DESC"#,
+            },
+        ]);
+        let rows: Vec<ApiKeyRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(ApiKeyRow::into_key).collect())
+    }
+
+    /// Lists the guest links of a connection, newest first.
+    pub async fn list_guest_links(&self, connection_id: &str) -> AppResult<Vec<ApiKey>> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_KEY,
+            &Statement {
+                mysql: "WHERE `guest` = TRUE ORDER BY `created_at` DESC",
+                postgres: r#"WHERE "guest" = TRUE ORDER BY "created_at" DESC"#,
+                sqlite: r#"WHERE "guest" = TRUE ORDER BY "created_at" DESC"#,
+            },
+        ]);
+        let rows: Vec<ApiKeyRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows
+            .into_iter()
+            .map(ApiKeyRow::into_key)
+            .filter(|key| key.allows_connection(connection_id))
+            .collect())
+    }
+
+    /// Revokes a guest link of a connection.
+    ///
+    /// # Errors
+    /// Returns `AppError::NotFound` if the key is not a guest link to the connection.
+    pub async fn revoke_guest_link(&self, connection_id: &str, id: &str) -> AppResult<ApiKey> {
+        let key = self.get(id).await?;
+        if !key.guest || key.connection_ids.iter().all(|c| c != connection_id) {
+            return Err(AppError::NotFound(format!("guest link {} of connection {}", id, connection_id)));
+        }
+        self.revoke(id).await
+    }
+
+    /// Gets a key by ID.
+    pub async fn get(&self, id: &str) -> AppResult<ApiKey> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_KEY,
+            &Statement {
+                mysql: "WHERE `id` = ?",
+                postgres: r#"WHERE "id" = $1"#,
+                sqlite: r#"WHERE "id" = ?"#,
+            },
+        ]);
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as::<_, ApiKeyRow>(&statement)
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+        })?
+            .map(ApiKeyRow::into_key)
+            .ok_or_else(|| AppError::NotFound(format!("API key {}", id)))
+    }
+
+    /// Whether `principal` is an unrevoked API key an administrator scoped to
+    /// the connection (including guest links), which may use the connection
+    /// whoever owns it.
+    pub async fn grants(&self, principal: Option<&str>, connection_id: &str) -> bool {
+        let Some(id) = principal.and_then(|p| p.strip_prefix(KEY_PRINCIPAL_PREFIX)) else {
+            return false;
+        };
+        self.get(id)
+            .await
+            .is_ok_and(|key| key.revoked_at.is_none() && key.connection_ids.iter().any(|c| c == connection_id))
+    }
+
+    /// Revokes a key; revoking an already revoked key keeps the first revocation time.
+    pub async fn revoke(&self, id: &str) -> AppResult<ApiKey> {
+        let statement = self
+            .pool_manager
+            .meta_pool()
+            .sql(&Statement {
+                mysql: "UPDATE `api_keys` SET `revoked_at` = UTC_TIMESTAMP() WHERE `id` = ? AND `revoked_at` IS NULL",
+                postgres: r#"UPDATE "api_keys" SET "revoked_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') WHERE "id" = $1 AND "revoked_at" IS NULL"#,
+                sqlite: r#"UPDATE "api_keys" SET "revoked_at" = CURRENT_TIMESTAMP WHERE "id" = ? AND "revoked_at" IS NULL"#,
+            });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        let key = self.get(id).await?;
+        tracing::info!(key_id = %id, "API key revoked");
+        Ok(key)
+    }
+
+    /// Verifies a key and records its use.
+    ///
+    /// # Errors
+    /// Returns `AppError::Unauthorized` if the key is unknown, revoked or expired.
+    pub async fn verify(&self, key: &str) -> AppResult<ApiKey> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_KEY,
+            &Statement {
+                mysql: "WHERE `key_hash` = ? AND `revoked_at` IS NULL \
+                 AND (`expires_at` IS NULL OR `expires_at` > UTC_TIMESTAMP())",
+                postgres: r#"WHERE "key_hash" = $1 AND "revoked_at" IS NULL
+                 AND ("expires_at" IS NULL OR "expires_at" > to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'))"#,
+                sqlite: r#"WHERE "key_hash" = ? AND "revoked_at" IS NULL
+                 AND ("expires_at" IS NULL OR "expires_at" > CURRENT_TIMESTAMP)"#,
+            },
+        ]);
+        let row: Option<ApiKeyRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement).bind(hash_key(key)).fetch_optional(pool).await
+        })?;
+        let mut key = row.map(ApiKeyRow::into_key).ok_or(AppError::Unauthorized)?;
+        if !key.schemas.is_empty() {
+            if let Some(connection_id) = key.connection_ids.first() {
+                key.default_namespace = self
+                    .pool_manager
+                    .get_connection(connection_id)
+                    .await
+                    .and_then(|c| c.default_namespace().map(str::to_string));
+            }
+        }
+
+        let statement = self
+            .pool_manager
+            .meta_pool()
+            .sql(&Statement {
+                mysql: "UPDATE `api_keys` SET `last_used_at` = UTC_TIMESTAMP() WHERE `id` = ?",
+                postgres: r#"UPDATE "api_keys" SET "last_used_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') WHERE "id" = $1"#,
+                sqlite: r#"UPDATE "api_keys" SET "last_used_at" = CURRENT_TIMESTAMP WHERE "id" = ?"#,
+            });
+        let recorded = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement).bind(&key.id).execute(pool).await.map(|_| ())
+        });
+        if let Err(e) = recorded {
+            tracing::warn!(key_id = %key.id, error = %e, "Failed to record API key use");
+        }
+        Ok(key)
+    }
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
@@ -0,0 +1,403 @@
+//! Password login and session token issuance.
+//!
+//! Users live in the `users` metadata table with argon2 password hashes.
+//! Logging in creates a session (see `sessions`) and returns a short-lived
+//! access token, verified by the gateway, plus the session's refresh token.
+//! Refreshing rotates the refresh token and issues a new access token;
+//! logging out revokes the session. Access tokens never outlive their session.
+//!
+//! Admins create users and reset passwords; a reset revokes the user's
+//! sessions.
+//!
+//! Users authenticated by an external identity provider (the gateway runs the
+//! single sign-on flow) get a session without a `users` row, under a user ID
+//! of their own (`sso:<issuer>:<subject>`); their roles, mapped from the
+//! identity provider's claims, go into the session and tokens.
+//!
+//! Password logins are throttled per username and client address (see
+//! `login_throttle`).
+//!
+//! Configuration:
+//! - `JWT_SECRET` - token signing secret, shared with the gateway (unset = login disabled)
+//! - `AUTH_ACCESS_TOKEN_TTL_SECS` - access token lifetime (default: 900)
+//! - `AUTH_SESSION_TTL_HOURS` - session and refresh token lifetime (default: 168)
+
+use std::sync::{Arc, OnceLock};
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+
+use common::errors::{AppError, AppResult};
+use common::jwt::{sso_user_id, JwtKeys, TokenClaims, SSO_PRINCIPAL_PREFIX};
+use common::meta::Statement;
+use common::meta_query;
+use common::models::auth::{AuthTokens, LoginRequest, User};
+use common::models::session::{CreateSsoSessionRequest, Session};
+use crate::login_throttle::LoginThrottle;
+use crate::pool_manager::PoolManager;
+use crate::sessions::SessionStore;
+
+const DEFAULT_ACCESS_TOKEN_TTL_SECS: i64 = 900;
+const DEFAULT_SESSION_TTL_HOURS: i64 = 168;
+
+/// Longest username accepted.
+const MAX_USERNAME_LEN: usize = 64;
+
+/// Longest user ID of single sign-on users, the width of the principal columns.
+const MAX_PRINCIPAL_LEN: usize = 128;
+
+/// MySQL DATETIME format used for stored timestamps (UTC).
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const SELECT_USER: Statement = Statement {
+    mysql: "SELECT `username`, CAST(`created_at` AS CHAR) AS created_at, \
+         CAST(`updated_at` AS CHAR) AS updated_at FROM `users`",
+    postgres: r#"SELECT "username", "created_at",
+         "updated_at" FROM "users""#,
+    sqlite: r#"SELECT "username", "created_at",
+         "updated_at" FROM "users""#,
+};
+
+/// Hash checked for unknown usernames, so failed logins take the same time either way.
+static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+/// Row from the `users` metadata table.
+#[derive(sqlx::FromRow)]
+struct UserRow {
+    username: String,
+    created_at: String,
+    updated_at: String,
+}
+
+impl From<UserRow> for User {
+    fn from(row: UserRow) -> Self {
+        User {
+            username: row.username,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Logs users in and issues their tokens.
+pub struct AuthService {
+    pool_manager: Arc<PoolManager>,
+    sessions: Arc<SessionStore>,
+    throttle: Arc<LoginThrottle>,
+    keys: Option<JwtKeys>,
+    access_ttl: ChronoDuration,
+    session_ttl: ChronoDuration,
+}
+
+impl AuthService {
+    /// Creates the service.
+    pub fn new(pool_manager: Arc<PoolManager>, sessions: Arc<SessionStore>, throttle: Arc<LoginThrottle>) -> Self {
+        let keys = JwtKeys::from_env();
+        if keys.is_none() {
+            tracing::info!("JWT_SECRET not set, login disabled");
+        }
+        let access_ttl_secs = std::env::var("AUTH_ACCESS_TOKEN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_ACCESS_TOKEN_TTL_SECS);
+        let session_ttl_hours = std::env::var("AUTH_SESSION_TTL_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SESSION_TTL_HOURS);
+        Self {
+            pool_manager,
+            sessions,
+            throttle,
+            keys,
+            access_ttl: ChronoDuration::seconds(access_ttl_secs),
+            session_ttl: ChronoDuration::hours(session_ttl_hours),
+        }
+    }
+
+    /// Checks a username and password and starts a session.
+    ///
+    /// # Errors
+    /// Returns `AppError::Unauthorized` for an unknown user or wrong password, and
+    /// `AppError::LoginLocked` after too many failed attempts for the user or address.
+    pub async fn login(
+        &self,
+        req: LoginRequest,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> AppResult<AuthTokens> {
+        let keys = self.keys()?;
+        let mut throttle_keys = vec![LoginThrottle::user_key(&req.username)];
+        if let Some(ip) = ip_address {
+            throttle_keys.push(LoginThrottle::ip_key(ip));
+        }
+        self.throttle.check(&throttle_keys).await?;
+
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `password_hash` FROM `users` WHERE `username` = ?",
+            postgres: r#"SELECT "password_hash" FROM "users" WHERE "username" = $1"#,
+            sqlite: r#"SELECT "password_hash" FROM "users" WHERE "username" = ?"#,
+        });
+        let hash: Option<String> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_scalar(statement)
+                .bind(&req.username)
+                .fetch_optional(pool)
+                .await
+        })?;
+        if !check_password(req.password, hash).await? {
+            tracing::info!(username = %req.username, "Login failed");
+            self.throttle.record_failure(&throttle_keys).await;
+            return Err(AppError::Unauthorized);
+        }
+        if let Err(e) = self.throttle.clear(&throttle_keys[0]).await {
+            tracing::warn!(error = %e, "Failed to clear login failures");
+        }
+
+        let expires_at = Utc::now() + self.session_ttl;
+        let (session, refresh_token) = self
+            .sessions
+            .create(&req.username, &[], expires_at, user_agent, ip_address)
+            .await?;
+        issue(keys, self.access_ttl, session, refresh_token)
+    }
+
+    /// Starts a session for a user the identity provider authenticated.
+    ///
+    /// The user is identified by the token's issuer and subject
+    /// (`sso:<issuer>:<subject>`, see [`sso_user_id`]), never by the username,
+    /// so the account cannot act as a local user of the same name.
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` if the issuer or subject is empty or the
+    /// resulting user ID is too long to store.
+    pub async fn sso_login(&self, req: CreateSsoSessionRequest) -> AppResult<AuthTokens> {
+        let keys = self.keys()?;
+        let user_id = sso_user_id(&req.issuer, &req.subject);
+        if req.issuer.is_empty() || req.subject.is_empty() || user_id.len() > MAX_PRINCIPAL_LEN {
+            return Err(AppError::Validation(format!(
+                "ID token issuer and subject must be set and together at most {} characters",
+                MAX_PRINCIPAL_LEN - SSO_PRINCIPAL_PREFIX.len() - 1
+            )));
+        }
+        let expires_at = Utc::now() + self.session_ttl;
+        let (session, refresh_token) = self
+            .sessions
+            .create(
+                &user_id,
+                &req.roles,
+                expires_at,
+                req.user_agent.as_deref(),
+                req.ip_address.as_deref(),
+            )
+            .await?;
+        tracing::info!(user_id = %user_id, username = %req.username, roles = ?req.roles, "Single sign-on login");
+        issue(keys, self.access_ttl, session, refresh_token)
+    }
+
+    /// Exchanges a refresh token for a new access token and refresh token.
+    ///
+    /// # Errors
+    /// Returns `AppError::Unauthorized` if the refresh token is invalid, replaced or its session ended.
+    pub async fn refresh(&self, refresh_token: &str) -> AppResult<AuthTokens> {
+        let keys = self.keys()?;
+        let (session, refresh_token) = self.sessions.rotate(refresh_token).await?;
+        issue(keys, self.access_ttl, session, refresh_token)
+    }
+
+    /// Ends a user's session.
+    pub async fn logout(&self, user_id: &str, session_id: &str) -> AppResult<Session> {
+        self.sessions.revoke(session_id, Some(user_id)).await
+    }
+
+    /// Lists users by name.
+    pub async fn list_users(&self) -> AppResult<Vec<User>> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_USER,
+            &Statement {
+                mysql: "ORDER BY `username`",
+                postgres: r#"ORDER BY "username""#,
+                sqlite: r#"ORDER BY "username""#,
+            },
+        ]);
+        let rows: Vec<UserRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(User::from).collect())
+    }
+
+    /// Creates a user or resets their password, revoking their sessions.
+    ///
+    /// # Errors
+    /// Returns `AppError::Validation` for an invalid username.
+    pub async fn set_password(&self, username: &str, password: String) -> AppResult<User> {
+        validate_username(username)?;
+        let hash = tokio::task::spawn_blocking(move || hash_password(&password))
+            .await
+            .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))??;
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `users` (`username`, `password_hash`, `created_at`, `updated_at`) \
+                 VALUES (?, ?, UTC_TIMESTAMP(), UTC_TIMESTAMP()) \
+                 ON DUPLICATE KEY UPDATE `password_hash` = VALUES(`password_hash`), `updated_at` = UTC_TIMESTAMP()",
+            postgres: r#"INSERT INTO "users" ("username", "password_hash", "created_at", "updated_at")
+                 VALUES ($1, $2, to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'), to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'))
+                 ON CONFLICT ("username") DO UPDATE SET "password_hash" = EXCLUDED."password_hash", "updated_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')"#,
+            sqlite: r#"INSERT INTO "users" ("username", "password_hash", "created_at", "updated_at")
+                 VALUES (?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                 ON CONFLICT ("username") DO UPDATE SET "password_hash" = excluded."password_hash", "updated_at" = CURRENT_TIMESTAMP"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(username)
+                .bind(hash)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        self.sessions.revoke_all(username, None).await?;
+        tracing::info!(username, "Password set");
+        self.get_user(username).await
+    }
+
+    /// Deletes a user and revokes their sessions.
+    pub async fn delete_user(&self, username: &str) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `users` WHERE `username` = ?",
+            postgres: r#"DELETE FROM "users" WHERE "username" = $1"#,
+            sqlite: r#"DELETE FROM "users" WHERE "username" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(username)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result == 0 {
+            return Err(AppError::NotFound(format!("User {}", username)));
+        }
+        self.sessions.revoke_all(username, None).await?;
+        tracing::info!(username, "User deleted");
+        Ok(())
+    }
+
+    async fn get_user(&self, username: &str) -> AppResult<User> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_USER,
+            &Statement {
+                mysql: "WHERE `username` = ?",
+                postgres: r#"WHERE "username" = $1"#,
+                sqlite: r#"WHERE "username" = ?"#,
+            },
+        ]);
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as::<_, UserRow>(&statement)
+                .bind(username)
+                .fetch_optional(pool)
+                .await
+        })?
+            .map(User::from)
+            .ok_or_else(|| AppError::NotFound(format!("User {}", username)))
+    }
+
+    fn keys(&self) -> AppResult<&JwtKeys> {
+        self.keys
+            .as_ref()
+            .ok_or_else(|| AppError::ServiceUnavailable("Login is disabled: JWT_SECRET is not set".to_string()))
+    }
+}
+
+/// Signs an access token for the session, expiring no later than the session.
+fn issue(keys: &JwtKeys, access_ttl: ChronoDuration, session: Session, refresh_token: String) -> AppResult<AuthTokens> {
+    let session_expires = NaiveDateTime::parse_from_str(&session.expires_at, DATETIME_FORMAT)
+        .map_err(|e| AppError::Internal(format!("Invalid session expiry {}: {}", session.expires_at, e)))?
+        .and_utc()
+        .timestamp();
+    let now = Utc::now().timestamp();
+    let claims = TokenClaims {
+        sub: session.user_id.clone(),
+        sid: session.id.clone(),
+        iat: now,
+        exp: (now + access_ttl.num_seconds()).min(session_expires),
+        roles: session.roles.clone(),
+    };
+    Ok(AuthTokens {
+        access_token: keys.encode(&claims),
+        token_type: "Bearer".to_string(),
+        expires_in: claims.exp - now,
+        refresh_token,
+        refresh_expires_at: session.expires_at,
+        session_id: session.id,
+        username: session.user_id,
+    })
+}
+
+/// Usernames become principals (`user:<name>`), so they are kept to a plain character set.
+fn validate_username(username: &str) -> AppResult<()> {
+    let valid = !username.is_empty()
+        && username.len() <= MAX_USERNAME_LEN
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '@'));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::Validation(format!(
+            "Username must be 1-{} characters of letters, digits, '.', '_', '-' or '@'",
+            MAX_USERNAME_LEN
+        )))
+    }
+}
+
+fn hash_password(password: &str) -> AppResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+}
+
+/// Verifies a password off the async runtime; a missing hash never matches.
+async fn check_password(password: String, hash: Option<String>) -> AppResult<bool> {
+    tokio::task::spawn_blocking(move || match hash {
+        Some(hash) => verify_password(&password, &hash),
+        None => {
+            let dummy = DUMMY_HASH.get_or_init(|| hash_password("dummy-password").unwrap_or_default());
+            verify_password(&password, dummy);
+            false
+        }
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Password check failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passwords_verify_against_their_hash_only() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("wrong horse", &hash));
+        assert!(!verify_password("correct horse", "not-a-hash"));
+    }
+
+    #[test]
+    fn usernames_are_restricted() {
+        assert!(validate_username("alice").is_ok());
+        assert!(validate_username("ops.bot-1@example.com").is_ok());
+        assert!(validate_username("").is_err());
+        assert!(validate_username("key:alice").is_err());
+        // Local users can never take the principal of a single sign-on user
+        assert!(validate_username(&sso_user_id("https://idp.example.com", "alice")).is_err());
+        assert!(validate_username(&"a".repeat(MAX_USERNAME_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+//! SQL editor autocomplete catalog.
+//!
+//! Builds a compact [`AutocompleteCatalog`] per connection: the databases /
+//! schemas of the server, the tables and columns of the default database
+//! (from the [`SchemaCache`]) and the keywords and built-in functions of the
+//! dialect. Catalogs are kept in memory until the TTL expires or the
+//! connection's cache is invalidated. The cached catalog is complete; the
+//! connection allowlist is applied when it is read.
+//!
+//! Configuration:
+//! - `AUTOCOMPLETE_CACHE_TTL_SECS` - catalog TTL (default: 300)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use sqlx::Row;
+use tokio::sync::RwLock;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::DbType;
+use common::models::database::{AutocompleteCatalog, AutocompleteColumn, AutocompleteTable};
+use dbm_core::DatabasePool;
+use dbm_core::drivers::mysql::row_string;
+use crate::pool_manager::PoolManager;
+use crate::schema_cache::SchemaCache;
+
+const DEFAULT_TTL_SECS: u64 = 300;
+
+/// Keywords shared by all SQL dialects.
+const COMMON_KEYWORDS: &[&str] = &[
+    "ADD", "ALL", "ALTER", "AND", "ANY", "AS", "ASC", "BETWEEN", "BY", "CASE", "CAST", "CHECK", "COLUMN",
+    "CONSTRAINT", "CREATE", "CROSS", "DEFAULT", "DELETE", "DESC", "DISTINCT", "DROP", "ELSE", "END", "EXCEPT",
+    "EXISTS", "EXPLAIN", "FALSE", "FOREIGN", "FROM", "FULL", "GROUP", "HAVING", "IN", "INDEX", "INNER",
+    "INSERT", "INTERSECT", "INTO", "IS", "JOIN", "KEY", "LEFT", "LIKE", "LIMIT", "NOT", "NULL", "OFFSET", "ON",
+    "OR", "ORDER", "OUTER", "OVER", "PARTITION", "PRIMARY", "REFERENCES", "RIGHT", "SELECT", "SET", "TABLE",
+    "THEN", "TRUE", "UNION", "UNIQUE", "UPDATE", "USING", "VALUES", "VIEW", "WHEN", "WHERE", "WINDOW", "WITH",
+];
+
+const MYSQL_KEYWORDS: &[&str] = &[
+    "AUTO_INCREMENT", "DATABASE", "DATABASES", "DESCRIBE", "DUPLICATE", "ENGINE", "FORCE", "IGNORE",
+    "INTERVAL", "LOCK", "REGEXP", "REPLACE", "SHOW", "STRAIGHT_JOIN", "TABLES", "UNSIGNED", "USE", "ZEROFILL",
+];
+
+const POSTGRES_KEYWORDS: &[&str] = &[
+    "ANALYZE", "ARRAY", "CONFLICT", "DO", "FETCH", "FILTER", "ILIKE", "INTERVAL", "LATERAL", "MATERIALIZED",
+    "NOTHING", "NULLS", "FIRST", "LAST", "RETURNING", "SCHEMA", "SEQUENCE", "SIMILAR", "TABLESAMPLE",
+];
+
+/// Functions shared by all SQL dialects.
+const COMMON_FUNCTIONS: &[&str] = &[
+    "ABS", "AVG", "CEIL", "COALESCE", "COUNT", "CURRENT_DATE", "CURRENT_TIMESTAMP", "FLOOR", "LAG", "LEAD",
+    "LENGTH", "LOWER", "MAX", "MIN", "NULLIF", "RANK", "ROUND", "ROW_NUMBER", "DENSE_RANK", "SUBSTRING", "SUM",
+    "TRIM", "UPPER",
+];
+
+const MYSQL_FUNCTIONS: &[&str] = &[
+    "CONCAT", "CONCAT_WS", "DATE_ADD", "DATE_FORMAT", "DATE_SUB", "DATEDIFF", "FROM_UNIXTIME", "GROUP_CONCAT",
+    "IF", "IFNULL", "JSON_ARRAYAGG", "JSON_EXTRACT", "JSON_OBJECT", "JSON_UNQUOTE", "LAST_INSERT_ID", "NOW",
+    "RAND", "STR_TO_DATE", "SUBSTRING_INDEX", "UNIX_TIMESTAMP", "UUID",
+];
+
+const POSTGRES_FUNCTIONS: &[&str] = &[
+    "AGE", "ARRAY_AGG", "ARRAY_LENGTH", "CONCAT", "DATE_PART", "DATE_TRUNC", "EXTRACT", "GEN_RANDOM_UUID",
+    "GENERATE_SERIES", "JSONB_AGG", "JSONB_BUILD_OBJECT", "JSONB_EXTRACT_PATH", "NOW", "RANDOM",
+    "REGEXP_REPLACE", "STRING_AGG", "TO_CHAR", "TO_DATE", "TO_TIMESTAMP", "UNNEST",
+];
+
+const SQLITE_FUNCTIONS: &[&str] = &[
+    "DATE", "DATETIME", "GROUP_CONCAT", "IFNULL", "INSTR", "JSON_EXTRACT", "RANDOM", "STRFTIME", "TIME",
+];
+
+/// Per-connection autocomplete catalog cache.
+pub struct AutocompleteCache {
+    pool_manager: Arc<PoolManager>,
+    schema_cache: Arc<SchemaCache>,
+    ttl: Duration,
+    entries: RwLock<HashMap<String, (Instant, AutocompleteCatalog)>>,
+}
+
+impl AutocompleteCache {
+    pub fn new(pool_manager: Arc<PoolManager>, schema_cache: Arc<SchemaCache>) -> Self {
+        let ttl = std::env::var("AUTOCOMPLETE_CACHE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TTL_SECS);
+        Self {
+            pool_manager,
+            schema_cache,
+            ttl: Duration::from_secs(ttl),
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the catalog of a connection, rebuilding it when expired or `refresh` is set.
+    pub async fn get(&self, connection_id: &str, refresh: bool) -> AppResult<AutocompleteCatalog> {
+        if !refresh {
+            if let Some((built_at, catalog)) = self.entries.read().await.get(connection_id) {
+                if built_at.elapsed() < self.ttl {
+                    return Ok(catalog.clone());
+                }
+            }
+        }
+
+        let catalog = self.build(connection_id, refresh).await?;
+        self.entries
+            .write()
+            .await
+            .insert(connection_id.to_string(), (Instant::now(), catalog.clone()));
+        Ok(catalog)
+    }
+
+    /// Drops the catalog and the cached schema of a connection.
+    pub async fn invalidate(&self, connection_id: &str) {
+        self.entries.write().await.remove(connection_id);
+        self.schema_cache.invalidate(connection_id).await;
+    }
+
+    async fn build(&self, connection_id: &str, refresh: bool) -> AppResult<AutocompleteCatalog> {
+        let config = self
+            .pool_manager
+            .get_connection(connection_id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+        let pool = self.pool_manager.get_or_create_pool(connection_id).await?;
+
+        let schemas = match &pool {
+            DatabasePool::MySQL(p) => {
+                sqlx::query("SELECT SCHEMA_NAME FROM information_schema.SCHEMATA ORDER BY SCHEMA_NAME")
+                    .fetch_all(p)
+                    .await?
+                    .iter()
+                    .map(|row| row_string(row, "SCHEMA_NAME"))
+                    .collect()
+            }
+            DatabasePool::Postgres(p) => sqlx::query(
+                "SELECT nspname FROM pg_catalog.pg_namespace
+                 WHERE nspname NOT LIKE 'pg\\_%' AND nspname <> 'information_schema'
+                 ORDER BY nspname",
+            )
+            .fetch_all(p)
+            .await?
+            .iter()
+            .filter_map(|row| row.try_get::<String, _>("nspname").ok())
+            .collect(),
+            _ => config.database.iter().cloned().collect(),
+        };
+
+        let tables = self
+            .schema_cache
+            .get_table_schema(connection_id, refresh)
+            .await?
+            .tables
+            .into_iter()
+            .map(|table| AutocompleteTable {
+                name: table.name,
+                columns: table
+                    .columns
+                    .into_iter()
+                    .map(|c| AutocompleteColumn { name: c.name, data_type: c.data_type })
+                    .collect(),
+            })
+            .collect();
+
+        let (keywords, functions) = dialect_words(&config.db_type);
+        Ok(AutocompleteCatalog {
+            db_type: config.db_type.to_string(),
+            default_schema: config.default_namespace().map(str::to_string),
+            schemas,
+            tables,
+            keywords,
+            functions,
+            generated_at: Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+/// Keywords and functions of a dialect, sorted and without duplicates.
+fn dialect_words(db_type: &DbType) -> (Vec<String>, Vec<String>) {
+    let (keywords, functions): (&[&str], &[&str]) = match db_type {
+        DbType::MySQL | DbType::MariaDB => (MYSQL_KEYWORDS, MYSQL_FUNCTIONS),
+        DbType::Postgres => (POSTGRES_KEYWORDS, POSTGRES_FUNCTIONS),
+        DbType::SQLite => (&[], SQLITE_FUNCTIONS),
+        _ => (&[], &[]),
+    };
+    let merge = |common: &[&str], extra: &[&str]| {
+        let mut words: Vec<String> = common.iter().chain(extra).map(|w| w.to_string()).collect();
+        words.sort();
+        words.dedup();
+        words
+    };
+    (merge(COMMON_KEYWORDS, keywords), merge(COMMON_FUNCTIONS, functions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dialect_words_are_merged_and_sorted() {
+        let (keywords, functions) = dialect_words(&DbType::Postgres);
+        assert!(keywords.contains(&"ILIKE".to_string()) && keywords.contains(&"SELECT".to_string()));
+        assert!(!keywords.contains(&"STRAIGHT_JOIN".to_string()));
+        assert!(functions.windows(2).all(|w| w[0] < w[1]));
+
+        let (_, functions) = dialect_words(&DbType::MariaDB);
+        assert!(functions.contains(&"GROUP_CONCAT".to_string()));
+    }
+}
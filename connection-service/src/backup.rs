@@ -0,0 +1,738 @@
+//! Database backups.
+//!
+//! Produces SQL dumps of MySQL and PostgreSQL connections, either generated
+//! from queries (logical) or by running the configured mysqldump / pg_dump
+//! binary (native). Jobs run in the background and are tracked in the
+//! `backups` metadata table; finished dumps are handed to [`BackupStorage`]
+//! and the outcome is published as a `backup.completed` / `backup.failed` event.
+//!
+//! Logical PostgreSQL dumps contain tables, indexes, foreign keys and data;
+//! sequences, views and functions require the native method.
+
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use axum::body::Body;
+use chrono::Utc;
+use futures::TryStreamExt;
+use sqlx::{Column, MySqlPool, PgPool, Row, TypeInfo};
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::events::{kinds, EventPublisher};
+use common::jobs::JobStore;
+use common::meta::{self, Statement};
+use common::meta_query;
+use common::models::backup::{BackupMethod, BackupRecord, BackupStatus, CreateBackupRequest};
+use common::models::connection::{ConnectionConfig, DbType};
+use common::models::job::{Job, JobKind};
+use common::notify::{Notification, Notifier};
+use common::progress::{self, ProgressEvent, ProgressHub};
+use dbm_core::DatabasePool;
+use dbm_core::drivers::mysql::row_string;
+use crate::backup_storage::BackupStorage;
+use crate::introspection;
+use crate::pool_manager::PoolManager;
+use crate::schema_diff::Dialect;
+
+/// Rows per generated INSERT statement.
+const INSERT_BATCH_ROWS: usize = 100;
+
+/// Row from the `backups` metadata table.
+#[derive(sqlx::FromRow)]
+struct BackupRow {
+    id: String,
+    connection_id: String,
+    database_name: String,
+    method: String,
+    status: String,
+    storage: String,
+    location: Option<String>,
+    size_bytes: meta::Unsigned<u64>,
+    table_count: meta::Unsigned<u32>,
+    error: Option<String>,
+    created_at: String,
+    finished_at: Option<String>,
+}
+
+impl BackupRow {
+    fn into_record(self) -> BackupRecord {
+        BackupRecord {
+            id: self.id,
+            connection_id: self.connection_id,
+            database: self.database_name,
+            method: match self.method.as_str() {
+                "native" => BackupMethod::Native,
+                _ => BackupMethod::Logical,
+            },
+            status: match self.status.as_str() {
+                "completed" => BackupStatus::Completed,
+                "failed" => BackupStatus::Failed,
+                _ => BackupStatus::Running,
+            },
+            storage: self.storage,
+            location: self.location,
+            size_bytes: self.size_bytes.0,
+            table_count: self.table_count.0,
+            error: self.error,
+            created_at: self.created_at,
+            finished_at: self.finished_at,
+        }
+    }
+}
+
+const SELECT_BACKUP: Statement = Statement {
+    mysql: "SELECT `id`, `connection_id`, `database_name`, `method`, `status`, `storage`, `location`, \
+         `size_bytes`, `table_count`, `error`, CAST(`created_at` AS CHAR) AS created_at, \
+         CAST(`finished_at` AS CHAR) AS finished_at FROM `backups`",
+    postgres: r#"SELECT "id", "connection_id", "database_name", "method", "status", "storage", "location",
+         "size_bytes", "table_count", "error", "created_at",
+         "finished_at" FROM "backups""#,
+    sqlite: r#"SELECT "id", "connection_id", "database_name", "method", "status", "storage", "location",
+         "size_bytes", "table_count", "error", "created_at",
+         "finished_at" FROM "backups""#,
+};
+
+/// Runs backup jobs and tracks them in the metadata database.
+pub struct BackupManager {
+    pool_manager: Arc<PoolManager>,
+    storage: BackupStorage,
+    notifier: Arc<Notifier>,
+    events: Arc<EventPublisher>,
+    progress: Arc<ProgressHub>,
+    job_store: Arc<JobStore>,
+}
+
+impl BackupManager {
+    /// Creates the backup manager.
+    ///
+    /// Jobs left running by a previous process are marked as failed.
+    pub async fn new(
+        pool_manager: Arc<PoolManager>,
+        storage: BackupStorage,
+        notifier: Arc<Notifier>,
+        events: Arc<EventPublisher>,
+        progress: Arc<ProgressHub>,
+        job_store: Arc<JobStore>,
+    ) -> AppResult<Self> {
+        let mgr = Self { pool_manager, storage, notifier, events, progress, job_store };
+
+        let statement = mgr.pool_manager.meta_pool().sql(&Statement {
+            mysql: "UPDATE `backups` SET `status` = 'failed', `error` = 'interrupted by service restart', \
+                 `finished_at` = CURRENT_TIMESTAMP WHERE `status` = 'running'",
+            postgres: r#"UPDATE "backups" SET "status" = 'failed', "error" = 'interrupted by service restart',
+                 "finished_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') WHERE "status" = 'running'"#,
+            sqlite: r#"UPDATE "backups" SET "status" = 'failed', "error" = 'interrupted by service restart',
+                 "finished_at" = CURRENT_TIMESTAMP WHERE "status" = 'running'"#,
+        });
+        meta_query!(mgr.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })
+        .map_err(|e| AppError::DatabaseQuery(format!("Failed to reset running backups: {}", e)))?;
+
+        Ok(mgr)
+    }
+
+    /// Starts a backup job; the dump runs in the background.
+    pub async fn start(self: &Arc<Self>, connection_id: &str, req: CreateBackupRequest) -> AppResult<BackupRecord> {
+        let config = self
+            .pool_manager
+            .get_connection(connection_id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+        if !matches!(config.db_type, DbType::MySQL | DbType::MariaDB | DbType::Postgres) {
+            return Err(AppError::UnsupportedDatabaseType(format!(
+                "backups are not supported for {}",
+                config.db_type
+            )));
+        }
+        let database = introspection::resolve_schema(&config, req.database.as_deref())?;
+        if req.method == BackupMethod::Native {
+            native_tool(self.storage.config(), &config.db_type)?;
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `backups` (`id`, `connection_id`, `database_name`, `method`, `status`, `storage`) \
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "backups" ("id", "connection_id", "database_name", "method", "status", "storage")
+                 VALUES ($1, $2, $3, $4, $5, $6)"#,
+            sqlite: r#"INSERT INTO "backups" ("id", "connection_id", "database_name", "method", "status", "storage")
+                 VALUES (?, ?, ?, ?, ?, ?)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&id)
+                .bind(connection_id)
+                .bind(&database)
+                .bind(req.method.as_str())
+                .bind(BackupStatus::Running.as_str())
+                .bind(self.storage.kind())
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        self.job_store.start(Job::new(&id, JobKind::Backup)).await?;
+
+        tracing::info!(backup_id = %id, connection_id, database = %database, method = req.method.as_str(), "Backup started");
+
+        let record = self.get(&id).await?;
+        self.progress.publish(ProgressEvent::new(progress::kinds::BACKUP, &id, &record, false));
+
+        let mgr = self.clone();
+        let backup_id = id.clone();
+        tokio::spawn(async move {
+            let result = mgr.run(&backup_id, &config, &database, &req).await;
+            mgr.finish(&backup_id, &config, &database, result).await;
+        });
+
+        Ok(record)
+    }
+
+    /// Lists backups of a connection, newest first.
+    pub async fn list(&self, connection_id: &str) -> AppResult<Vec<BackupRecord>> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_BACKUP,
+            &Statement {
+                mysql: "WHERE `connection_id` = ? ORDER BY `created_at` DESC",
+                postgres: r#"WHERE "connection_id" = $1 ORDER BY "created_at" DESC"#,
+                sqlite: r#"WHERE "connection_id" = ? ORDER BY "created_at" DESC"#,
+            },
+        ]);
+        let rows: Vec<BackupRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .bind(connection_id)
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(BackupRow::into_record).collect())
+    }
+
+    /// Gets a backup by ID.
+    pub async fn get(&self, backup_id: &str) -> AppResult<BackupRecord> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_BACKUP,
+            &Statement {
+                mysql: "WHERE `id` = ?",
+                postgres: r#"WHERE "id" = $1"#,
+                sqlite: r#"WHERE "id" = ?"#,
+            },
+        ]);
+        let row: Option<BackupRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .bind(backup_id)
+                .fetch_optional(pool)
+                .await
+        })?;
+        row.map(BackupRow::into_record)
+            .ok_or_else(|| AppError::NotFound(format!("backup {}", backup_id)))
+    }
+
+    /// Opens a completed backup for download.
+    pub async fn open(&self, backup_id: &str) -> AppResult<(BackupRecord, Body)> {
+        let (record, location) = self.completed(backup_id).await?;
+        let body = self.storage.open(&location).await?;
+        Ok((record, body))
+    }
+
+    /// Reads the dump of a completed backup into memory.
+    pub async fn read(&self, backup_id: &str) -> AppResult<(BackupRecord, Vec<u8>)> {
+        let (record, location) = self.completed(backup_id).await?;
+        let content = self.storage.read(&location).await?;
+        Ok((record, content))
+    }
+
+    /// Gets a completed backup together with its storage location.
+    async fn completed(&self, backup_id: &str) -> AppResult<(BackupRecord, String)> {
+        let record = self.get(backup_id).await?;
+        match (&record.status, record.location.clone()) {
+            (BackupStatus::Completed, Some(location)) => Ok((record, location)),
+            _ => Err(AppError::Conflict(format!(
+                "backup {} is not completed",
+                backup_id
+            ))),
+        }
+    }
+
+    /// Dumps to a local file and stores it; returns (location, size, table count).
+    async fn run(
+        &self,
+        backup_id: &str,
+        config: &ConnectionConfig,
+        database: &str,
+        req: &CreateBackupRequest,
+    ) -> AppResult<(String, u64, u32)> {
+        let path = self.storage.local_path(&config.id, backup_id).await?;
+
+        let result = match req.method {
+            BackupMethod::Logical => self.dump_logical(backup_id, config, database, req, &path).await,
+            BackupMethod::Native => self.dump_native(config, database, req, &path).await,
+        };
+        let table_count = match result {
+            Ok(count) => count,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&path).await;
+                return Err(e);
+            }
+        };
+
+        let size = tokio::fs::metadata(&path)
+            .await
+            .map(|m| m.len())
+            .map_err(|e| AppError::Internal(format!("Failed to stat dump {}: {}", path.display(), e)))?;
+        let location = self.storage.store(&config.id, &path).await?;
+        Ok((location, size, table_count))
+    }
+
+    async fn finish(&self, backup_id: &str, config: &ConnectionConfig, database: &str, result: AppResult<(String, u64, u32)>) {
+        let data = serde_json::json!({ "backup_id": backup_id, "connection_id": config.id, "database": database });
+        let notification = match &result {
+            Ok((location, size, tables)) => Notification::new(
+                kinds::BACKUP_COMPLETED,
+                format!("Backup of {} ({}) completed", config.name, database),
+                format!("{} tables, {} bytes, stored at {}", tables, size, location),
+                data,
+            ),
+            Err(e) => Notification::new(
+                kinds::BACKUP_FAILED,
+                format!("Backup of {} ({}) failed", config.name, database),
+                e.to_string(),
+                data,
+            ),
+        };
+        let mut event = notification.data.clone();
+        match &result {
+            Ok((location, size, tables)) => {
+                event["location"] = location.clone().into();
+                event["size_bytes"] = (*size).into();
+                event["table_count"] = (*tables).into();
+            }
+            Err(e) => event["error"] = e.to_string().into(),
+        }
+        self.events.publish(&notification.event, event);
+        self.notifier.publish(notification);
+
+        match &result {
+            Ok((location, size, _)) => tracing::info!(backup_id, location = %location, size, "Backup completed"),
+            Err(e) => tracing::error!(backup_id, error = %e, "Backup failed"),
+        }
+        let meta = self.pool_manager.meta_pool();
+        let statement = meta.sql(match &result {
+            Ok(_) => &Statement {
+                mysql: "UPDATE `backups` SET `status` = ?, `location` = ?, `size_bytes` = ?, `table_count` = ?, \
+                     `finished_at` = CURRENT_TIMESTAMP WHERE `id` = ?",
+                postgres: r#"UPDATE "backups" SET "status" = $1, "location" = $2, "size_bytes" = $3, "table_count" = $4,
+                     "finished_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') WHERE "id" = $5"#,
+                sqlite: r#"UPDATE "backups" SET "status" = ?, "location" = ?, "size_bytes" = ?, "table_count" = ?,
+                     "finished_at" = CURRENT_TIMESTAMP WHERE "id" = ?"#,
+            },
+            Err(_) => &Statement {
+                mysql: "UPDATE `backups` SET `status` = ?, `error` = ?, `finished_at` = CURRENT_TIMESTAMP WHERE `id` = ?",
+                postgres: r#"UPDATE "backups" SET "status" = $1, "error" = $2,
+                     "finished_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') WHERE "id" = $3"#,
+                sqlite: r#"UPDATE "backups" SET "status" = ?, "error" = ?, "finished_at" = CURRENT_TIMESTAMP WHERE "id" = ?"#,
+            },
+        });
+        let recorded = meta_query!(meta, |pool| {
+            let query = match &result {
+                Ok((location, size, tables)) => sqlx::query(statement)
+                    .bind(BackupStatus::Completed.as_str())
+                    .bind(location)
+                    .bind(meta::int(*size))
+                    .bind(i64::from(*tables))
+                    .bind(backup_id),
+                Err(e) => sqlx::query(statement)
+                    .bind(BackupStatus::Failed.as_str())
+                    .bind(e.to_string())
+                    .bind(backup_id),
+            };
+            query.execute(pool).await.map(|_| ())
+        });
+        if let Err(e) = recorded {
+            tracing::error!(backup_id, error = %e, "Failed to record backup result");
+        }
+        match result {
+            Ok((location, ..)) => self.job_store.finish(backup_id, |j| j.complete(Some(location))).await,
+            Err(e) => self.job_store.finish(backup_id, |j| j.fail(e.to_string())).await,
+        }
+        match self.get(backup_id).await {
+            Ok(record) => self.progress.publish(ProgressEvent::new(progress::kinds::BACKUP, backup_id, &record, true)),
+            Err(e) => tracing::warn!(backup_id, error = %e, "Failed to load backup for its progress event"),
+        }
+    }
+
+    // ============== Logical dump ==============
+
+    async fn dump_logical(
+        &self,
+        backup_id: &str,
+        config: &ConnectionConfig,
+        database: &str,
+        req: &CreateBackupRequest,
+        path: &Path,
+    ) -> AppResult<u32> {
+        let file = tokio::fs::File::create(path)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create dump {}: {}", path.display(), e)))?;
+        let mut out = BufWriter::new(file);
+
+        let header = format!(
+            "-- Logical dump of `{}` (connection {})\n-- Created at {}\n\n",
+            database,
+            config.id,
+            Utc::now().to_rfc3339()
+        );
+        write(&mut out, &header).await?;
+
+        // Native tools give no progress; logical dumps report the table being dumped
+        let on_table = |index: usize, total: usize, table: &str| {
+            let percent = index as f64 / total.max(1) as f64 * 100.0;
+            let detail = format!("dumping table {} ({}/{})", table, index + 1, total);
+            self.progress.advance(backup_id, Some(percent), Some(detail));
+        };
+        let count = match self.pool_manager.get_or_create_pool(&config.id).await? {
+            DatabasePool::MySQL(pool) => dump_mysql(&pool, database, req, &mut out, &on_table).await?,
+            DatabasePool::Postgres(pool) => dump_postgres(&pool, database, req, &mut out, &on_table).await?,
+            _ => {
+                return Err(AppError::UnsupportedDatabaseType(
+                    "Logical backups support MySQL and PostgreSQL only".into(),
+                ))
+            }
+        };
+
+        out.flush()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to write dump: {}", e)))?;
+        Ok(count)
+    }
+
+    // ============== Native dump ==============
+
+    async fn dump_native(
+        &self,
+        config: &ConnectionConfig,
+        database: &str,
+        req: &CreateBackupRequest,
+        path: &Path,
+    ) -> AppResult<u32> {
+        let program = native_tool(self.storage.config(), &config.db_type)?;
+        let config = &self.pool_manager.resolve_password(config).await?;
+        let host = config.host.clone().unwrap_or_else(|| "localhost".to_string());
+        let username = config.username.clone().unwrap_or_default();
+        let password = config.password.clone().unwrap_or_default();
+
+        let mut cmd = tokio::process::Command::new(program);
+        match config.db_type {
+            DbType::Postgres => {
+                cmd.arg("--host").arg(&host)
+                    .arg("--port").arg(config.port.unwrap_or(5432).to_string())
+                    .arg("--username").arg(&username)
+                    .arg("--dbname").arg(config.database.as_deref().unwrap_or("postgres"))
+                    .arg("--schema").arg(database)
+                    .arg("--no-owner")
+                    // INSERT statements instead of COPY, so the dump can be restored statement by statement
+                    .arg("--inserts")
+                    .env("PGPASSWORD", &password);
+                for table in &req.tables {
+                    cmd.arg("--table").arg(format!("{}.{}", database, table));
+                }
+                if !req.include_data {
+                    cmd.arg("--schema-only");
+                }
+            }
+            _ => {
+                cmd.arg("--host").arg(&host)
+                    .arg("--port").arg(config.port.unwrap_or(3306).to_string())
+                    .arg("--user").arg(&username)
+                    .arg("--single-transaction")
+                    .arg("--routines")
+                    .env("MYSQL_PWD", &password);
+                if !req.include_data {
+                    cmd.arg("--no-data");
+                }
+                cmd.arg(database).args(&req.tables);
+            }
+        }
+
+        let file = std::fs::File::create(path)
+            .map_err(|e| AppError::Internal(format!("Failed to create dump {}: {}", path.display(), e)))?;
+        let output = cmd
+            .stdin(Stdio::null())
+            .stdout(file)
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to run {}: {}", program, e)))?;
+        if !output.status.success() {
+            return Err(AppError::ExternalService(format!(
+                "{} exited with {}: {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(req.tables.len() as u32)
+    }
+}
+
+/// Returns the native dump binary configured for a database type.
+fn native_tool<'a>(config: &'a crate::backup_storage::BackupConfig, db_type: &DbType) -> AppResult<&'a str> {
+    let (tool, env) = match db_type {
+        DbType::Postgres => (&config.pg_dump_path, "PG_DUMP_PATH"),
+        _ => (&config.mysqldump_path, "MYSQLDUMP_PATH"),
+    };
+    tool.as_deref()
+        .ok_or_else(|| AppError::Configuration(format!("native backups require {} to be set", env)))
+}
+
+async fn write<W: AsyncWrite + Unpin>(out: &mut W, s: &str) -> AppResult<()> {
+    out.write_all(s.as_bytes())
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to write dump: {}", e)))
+}
+
+/// Keeps only the requested tables, failing on unknown names.
+fn select_tables(all: Vec<String>, requested: &[String]) -> AppResult<Vec<String>> {
+    if requested.is_empty() {
+        return Ok(all);
+    }
+    if let Some(missing) = requested.iter().find(|t| !all.contains(t)) {
+        return Err(AppError::NotFound(format!("table {}", missing)));
+    }
+    Ok(all.into_iter().filter(|t| requested.contains(t)).collect())
+}
+
+/// Writes buffered row tuples as one multi-row INSERT.
+async fn flush_insert<W: AsyncWrite + Unpin>(
+    out: &mut W,
+    target: &str,
+    columns: &str,
+    values: &mut Vec<String>,
+) -> AppResult<()> {
+    if values.is_empty() {
+        return Ok(());
+    }
+    let stmt = format!("INSERT INTO {} ({}) VALUES\n{};\n", target, columns, values.join(",\n"));
+    values.clear();
+    write(out, &stmt).await
+}
+
+async fn dump_mysql<W: AsyncWrite + Unpin>(
+    pool: &MySqlPool,
+    database: &str,
+    req: &CreateBackupRequest,
+    out: &mut W,
+    on_table: &(dyn Fn(usize, usize, &str) + Sync),
+) -> AppResult<u32> {
+    let tables: Vec<String> = sqlx::query(
+        "SELECT TABLE_NAME FROM information_schema.TABLES \
+         WHERE TABLE_SCHEMA = ? AND TABLE_TYPE = 'BASE TABLE' ORDER BY TABLE_NAME",
+    )
+    .bind(database)
+    .fetch_all(pool)
+    .await?
+    .iter()
+    .map(|r| row_string(r, "TABLE_NAME"))
+    .collect();
+    let tables = select_tables(tables, &req.tables)?;
+
+    let dialect = Dialect::MySql;
+    write(out, "SET NAMES utf8mb4;\nSET FOREIGN_KEY_CHECKS = 0;\n\n").await?;
+
+    for (index, table) in tables.iter().enumerate() {
+        on_table(index, tables.len(), table);
+        let qualified = format!("{}.{}", dialect.quote(database), dialect.quote(table));
+        let create = sqlx::query(&format!("SHOW CREATE TABLE {}", qualified))
+            .fetch_one(pool)
+            .await?;
+        let ddl = row_string(&create, "Create Table");
+        write(
+            out,
+            &format!(
+                "-- Table {}\nDROP TABLE IF EXISTS {};\n{};\n\n",
+                dialect.quote(table),
+                dialect.quote(table),
+                ddl
+            ),
+        )
+        .await?;
+
+        if !req.include_data {
+            continue;
+        }
+
+        // The text protocol (raw_sql) returns every value as its textual bytes.
+        let sql = format!("SELECT * FROM {}", qualified);
+        let mut rows = sqlx::raw_sql(&sql).fetch(pool);
+        let mut columns = String::new();
+        let mut values = Vec::with_capacity(INSERT_BATCH_ROWS);
+        while let Some(row) = rows.try_next().await? {
+            if columns.is_empty() {
+                columns = row
+                    .columns()
+                    .iter()
+                    .map(|c| dialect.quote(c.name()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+            }
+            let tuple = row
+                .columns()
+                .iter()
+                .map(|c| {
+                    let raw: Option<Vec<u8>> = row.try_get_unchecked(c.ordinal()).unwrap_or(None);
+                    mysql_literal(raw.as_deref(), c.type_info().name())
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            values.push(format!("({})", tuple));
+            if values.len() >= INSERT_BATCH_ROWS {
+                flush_insert(out, &dialect.quote(table), &columns, &mut values).await?;
+            }
+        }
+        flush_insert(out, &dialect.quote(table), &columns, &mut values).await?;
+        write(out, "\n").await?;
+    }
+
+    write(out, "SET FOREIGN_KEY_CHECKS = 1;\n").await?;
+    Ok(tables.len() as u32)
+}
+
+async fn dump_postgres<W: AsyncWrite + Unpin>(
+    pool: &PgPool,
+    schema: &str,
+    req: &CreateBackupRequest,
+    out: &mut W,
+    on_table: &(dyn Fn(usize, usize, &str) + Sync),
+) -> AppResult<u32> {
+    let defs = introspection::load_schema(&DatabasePool::Postgres(pool.clone()), schema).await?;
+    let names = select_tables(defs.iter().map(|t| t.name.clone()).collect(), &req.tables)?;
+    let defs: Vec<_> = defs.into_iter().filter(|t| names.contains(&t.name)).collect();
+
+    let dialect = Dialect::Postgres;
+    write(
+        out,
+        &format!(
+            "SET client_encoding = 'UTF8';\nSET standard_conforming_strings = on;\nSET search_path TO {};\n\n",
+            dialect.quote(schema)
+        ),
+    )
+    .await?;
+
+    for (index, table) in defs.iter().enumerate() {
+        on_table(index, defs.len(), &table.name);
+        write(
+            out,
+            &format!(
+                "-- Table {}\nDROP TABLE IF EXISTS {} CASCADE;\n{};\n\n",
+                dialect.quote(&table.name),
+                dialect.quote(&table.name),
+                dialect.create_table(table).join(";\n")
+            ),
+        )
+        .await?;
+
+        if !req.include_data {
+            continue;
+        }
+
+        // The simple query protocol (raw_sql) returns every value in text format.
+        let sql = format!("SELECT * FROM {}.{}", dialect.quote(schema), dialect.quote(&table.name));
+        let mut rows = sqlx::raw_sql(&sql).fetch(pool);
+        let columns = table
+            .columns
+            .iter()
+            .map(|c| dialect.quote(&c.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut values = Vec::with_capacity(INSERT_BATCH_ROWS);
+        while let Some(row) = rows.try_next().await? {
+            let tuple = (0..row.len())
+                .map(|i| {
+                    let raw: Option<String> = row.try_get_unchecked(i).unwrap_or(None);
+                    raw.map(|v| quote_string(&v, false)).unwrap_or_else(|| "NULL".to_string())
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            values.push(format!("({})", tuple));
+            if values.len() >= INSERT_BATCH_ROWS {
+                flush_insert(out, &dialect.quote(&table.name), &columns, &mut values).await?;
+            }
+        }
+        flush_insert(out, &dialect.quote(&table.name), &columns, &mut values).await?;
+        write(out, "\n").await?;
+    }
+
+    // Foreign keys last, so data loads in any table order.
+    for table in &defs {
+        for fk in &table.foreign_keys {
+            write(out, &format!("{};\n", dialect.add_foreign_key(&table.name, fk))).await?;
+        }
+    }
+
+    Ok(defs.len() as u32)
+}
+
+/// Renders a MySQL text-protocol value as an SQL literal.
+fn mysql_literal(raw: Option<&[u8]>, type_name: &str) -> String {
+    let Some(bytes) = raw else {
+        return "NULL".to_string();
+    };
+    let base = type_name.split_whitespace().next().unwrap_or("").to_uppercase();
+    match base.as_str() {
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "FLOAT" | "DOUBLE" | "DECIMAL"
+        | "YEAR" | "BOOLEAN" => String::from_utf8_lossy(bytes).to_string(),
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BIT" | "GEOMETRY" => {
+            if bytes.is_empty() {
+                "''".to_string()
+            } else {
+                format!("X'{}'", hex::encode(bytes))
+            }
+        }
+        _ => quote_string(&String::from_utf8_lossy(bytes), true),
+    }
+}
+
+/// Quotes a string literal; MySQL additionally escapes backslashes and control characters.
+fn quote_string(value: &str, mysql: bool) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for ch in value.chars() {
+        match ch {
+            '\'' => quoted.push_str("''"),
+            '\\' if mysql => quoted.push_str("\\\\"),
+            '\0' if mysql => quoted.push_str("\\0"),
+            '\n' if mysql => quoted.push_str("\\n"),
+            '\r' if mysql => quoted.push_str("\\r"),
+            '\u{1a}' if mysql => quoted.push_str("\\Z"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_mysql_literals_by_type() {
+        assert_eq!(mysql_literal(None, "VARCHAR"), "NULL");
+        assert_eq!(mysql_literal(Some(b"42"), "INT UNSIGNED"), "42");
+        assert_eq!(mysql_literal(Some(&[0x00, 0xff]), "BLOB"), "X'00ff'");
+        assert_eq!(mysql_literal(Some(b"it's a\\b\n"), "TEXT"), "'it''s a\\\\b\\n'");
+    }
+
+    #[test]
+    fn rejects_unknown_requested_tables() {
+        let all = vec!["orders".to_string(), "users".to_string()];
+        assert_eq!(select_tables(all.clone(), &["users".into()]).unwrap(), vec!["users"]);
+        assert!(select_tables(all, &["missing".into()]).is_err());
+    }
+}
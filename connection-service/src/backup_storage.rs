@@ -0,0 +1,312 @@
+//! Backup storage backends.
+//!
+//! Dumps are always written to the local backup directory first; with the S3
+//! backend they are uploaded afterwards (path-style, AWS Signature V4) and the
+//! local copy is removed.
+
+use std::path::{Path, PathBuf};
+
+use axum::body::Body;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use tokio_util::io::ReaderStream;
+
+use common::errors::{AppError, AppResult};
+
+const S3_LOCATION_PREFIX: &str = "s3://";
+
+/// Backup settings loaded from the environment.
+///
+/// - `BACKUP_DIR` - Local backup directory (default: "{DATA_DIR}/backups")
+/// - `BACKUP_STORAGE` - "local" or "s3" (default: "local")
+/// - `BACKUP_S3_BUCKET`, `BACKUP_S3_REGION` (default: "us-east-1"), `BACKUP_S3_ENDPOINT`,
+///   `BACKUP_S3_PREFIX`, `BACKUP_S3_ACCESS_KEY_ID`, `BACKUP_S3_SECRET_ACCESS_KEY`
+/// - `MYSQLDUMP_PATH` / `PG_DUMP_PATH` - Native dump tools (native method is disabled if unset)
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    /// Local backup directory.
+    pub dir: PathBuf,
+    /// S3 settings when `BACKUP_STORAGE=s3`.
+    pub s3: Option<S3Config>,
+    /// Path to the `mysqldump` binary.
+    pub mysqldump_path: Option<String>,
+    /// Path to the `pg_dump` binary.
+    pub pg_dump_path: Option<String>,
+}
+
+impl BackupConfig {
+    /// Loads backup settings from environment variables.
+    pub fn load(data_dir: &str) -> AppResult<Self> {
+        let dir = std::env::var("BACKUP_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| Path::new(data_dir).join("backups"));
+
+        let s3 = match std::env::var("BACKUP_STORAGE").unwrap_or_default().to_lowercase().as_str() {
+            "" | "local" => None,
+            "s3" => Some(S3Config::load()?),
+            other => {
+                return Err(AppError::Configuration(format!(
+                    "unknown BACKUP_STORAGE `{}` (expected local or s3)",
+                    other
+                )))
+            }
+        };
+
+        Ok(Self {
+            dir,
+            s3,
+            mysqldump_path: std::env::var("MYSQLDUMP_PATH").ok().filter(|v| !v.is_empty()),
+            pg_dump_path: std::env::var("PG_DUMP_PATH").ok().filter(|v| !v.is_empty()),
+        })
+    }
+}
+
+/// S3-compatible object storage settings.
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub bucket: String,
+    pub region: String,
+    /// Endpoint URL, e.g. `http://minio:9000` (default: AWS regional endpoint).
+    pub endpoint: String,
+    /// Key prefix for uploaded dumps.
+    pub prefix: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl S3Config {
+    fn load() -> AppResult<Self> {
+        let required = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| AppError::Configuration(format!("{} is required for S3 backup storage", key)))
+        };
+        let region = std::env::var("BACKUP_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        let endpoint = std::env::var("BACKUP_S3_ENDPOINT")
+            .unwrap_or_else(|_| format!("https://s3.{}.amazonaws.com", region));
+        Ok(Self {
+            bucket: required("BACKUP_S3_BUCKET")?,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            prefix: std::env::var("BACKUP_S3_PREFIX").unwrap_or_default(),
+            access_key_id: required("BACKUP_S3_ACCESS_KEY_ID")?,
+            secret_access_key: required("BACKUP_S3_SECRET_ACCESS_KEY")?,
+            region,
+        })
+    }
+}
+
+/// Where finished dumps are kept.
+pub struct BackupStorage {
+    config: BackupConfig,
+    http: reqwest::Client,
+}
+
+impl BackupStorage {
+    /// Creates the storage backend.
+    pub fn new(config: BackupConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Returns the storage backend name ("local" or "s3").
+    pub fn kind(&self) -> &'static str {
+        if self.config.s3.is_some() {
+            "s3"
+        } else {
+            "local"
+        }
+    }
+
+    /// Returns the backup settings.
+    pub fn config(&self) -> &BackupConfig {
+        &self.config
+    }
+
+    /// Local path the dump for a backup is written to.
+    pub async fn local_path(&self, connection_id: &str, backup_id: &str) -> AppResult<PathBuf> {
+        let dir = self.config.dir.join(connection_id);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create backup directory {}: {}", dir.display(), e)))?;
+        Ok(dir.join(format!("{}.sql", backup_id)))
+    }
+
+    /// Moves a finished local dump to its final location and returns that location.
+    pub async fn store(&self, connection_id: &str, local: &Path) -> AppResult<String> {
+        let Some(s3) = &self.config.s3 else {
+            return Ok(local.display().to_string());
+        };
+
+        let file_name = local
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let key = format!("{}{}/{}", s3.prefix, connection_id, file_name);
+        let body = tokio::fs::read(local)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to read dump {}: {}", local.display(), e)))?;
+
+        let request = sign_s3_request(self.http.put(s3_url(s3, &key)), s3, "PUT", &key, Utc::now())?;
+        let resp = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("S3 upload failed: {}", e)))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(AppError::ExternalService(format!("S3 upload failed ({}): {}", status, text)));
+        }
+
+        if let Err(e) = tokio::fs::remove_file(local).await {
+            tracing::warn!(path = %local.display(), error = %e, "Failed to remove local dump after upload");
+        }
+        Ok(format!("{}{}/{}", S3_LOCATION_PREFIX, s3.bucket, key))
+    }
+
+    /// Opens a stored dump as a response body.
+    pub async fn open(&self, location: &str) -> AppResult<Body> {
+        if location.starts_with(S3_LOCATION_PREFIX) {
+            return Ok(Body::from(self.read(location).await?));
+        }
+        let file = tokio::fs::File::open(location)
+            .await
+            .map_err(|e| AppError::NotFound(format!("backup file {}: {}", location, e)))?;
+        Ok(Body::from_stream(ReaderStream::new(file)))
+    }
+
+    /// Reads a stored dump fully into memory.
+    pub async fn read(&self, location: &str) -> AppResult<Vec<u8>> {
+        let Some(rest) = location.strip_prefix(S3_LOCATION_PREFIX) else {
+            return tokio::fs::read(location)
+                .await
+                .map_err(|e| AppError::NotFound(format!("backup file {}: {}", location, e)));
+        };
+
+        let s3 = self
+            .config
+            .s3
+            .as_ref()
+            .ok_or_else(|| AppError::Configuration("S3 backup storage is not configured".into()))?;
+        let key = rest
+            .strip_prefix(&format!("{}/", s3.bucket))
+            .ok_or_else(|| AppError::Configuration(format!("backup {} is not in bucket {}", location, s3.bucket)))?;
+
+        let request = sign_s3_request(self.http.get(s3_url(s3, key)), s3, "GET", key, Utc::now())?;
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("S3 download failed: {}", e)))?;
+        if !resp.status().is_success() {
+            return Err(AppError::ExternalService(format!("S3 download failed ({})", resp.status())));
+        }
+        let bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("S3 download failed: {}", e)))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+// ============== AWS Signature V4 ==============
+
+fn s3_url(s3: &S3Config, key: &str) -> String {
+    format!("{}{}", s3.endpoint, s3_canonical_uri(&s3.bucket, key))
+}
+
+/// Path-style object URI with each segment percent-encoded.
+fn s3_canonical_uri(bucket: &str, key: &str) -> String {
+    std::iter::once(bucket)
+        .chain(key.split('/'))
+        .map(uri_encode)
+        .fold(String::new(), |acc, seg| acc + "/" + &seg)
+}
+
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Adds SigV4 headers for an S3 object request with an unsigned payload.
+fn sign_s3_request(
+    builder: reqwest::RequestBuilder,
+    s3: &S3Config,
+    method: &str,
+    key: &str,
+    now: chrono::DateTime<Utc>,
+) -> AppResult<reqwest::RequestBuilder> {
+    let url = reqwest::Url::parse(&s3.endpoint)
+        .map_err(|e| AppError::Configuration(format!("invalid BACKUP_S3_ENDPOINT: {}", e)))?;
+    let host = match (url.host_str(), url.port()) {
+        (Some(h), Some(p)) => format!("{}:{}", h, p),
+        (Some(h), None) => h.to_string(),
+        (None, _) => return Err(AppError::Configuration("BACKUP_S3_ENDPOINT has no host".into())),
+    };
+    let authorization = s3_authorization(s3, method, &host, key, now);
+
+    Ok(builder
+        .header("host", host)
+        .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+        .header("x-amz-content-sha256", "UNSIGNED-PAYLOAD")
+        .header("authorization", authorization))
+}
+
+fn s3_authorization(s3: &S3Config, method: &str, host: &str, key: &str, now: chrono::DateTime<Utc>) -> String {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "{}\n{}\n\nhost:{}\nx-amz-content-sha256:UNSIGNED-PAYLOAD\nx-amz-date:{}\n\n{}\nUNSIGNED-PAYLOAD",
+        method,
+        s3_canonical_uri(&s3.bucket, key),
+        host,
+        amz_date,
+        signed_headers
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, s3.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{}", s3.secret_access_key).as_bytes(), &date);
+    let k_region = hmac_sha256(&k_date, &s3.region);
+    let k_service = hmac_sha256(&k_region, "s3");
+    let k_signing = hmac_sha256(&k_service, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&k_signing, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        s3.access_key_id, scope, signed_headers, signature
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_object_key_segments() {
+        assert_eq!(
+            s3_canonical_uri("dumps", "prod db/2024 01.sql"),
+            "/dumps/prod%20db/2024%2001.sql"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Staged connection diagnostics.
+//!
+//! A connection test runs these stages in order, each with its own latency and
+//! error, so a "connection failed" report shows where it failed:
+//! - `dns` - resolve the host
+//! - `tcp_connect` - open a TCP connection to the first reachable address
+//! - `tls` - whether the server offers TLS: the MySQL greeting's `CLIENT_SSL`
+//!   flag or the PostgreSQL `SSLRequest` answer (other types are not checked);
+//!   the handshake itself happens while logging in
+//! - `authentication` - open a pool, which connects and logs in
+//! - `query` - run a trivial command (`SELECT 1`, `PING`)
+//!
+//! Stages after a failed one are skipped; SQLite skips the network stages.
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::{ConnectionConfig, DbType};
+use common::models::diagnostics::{StageResult, StageStatus, TestStage};
+
+/// MySQL capability flag: the server supports TLS.
+const CLIENT_SSL: u16 = 0x0800;
+
+/// PostgreSQL `SSLRequest` message: length 8, request code 80877103.
+const PG_SSL_REQUEST: [u8; 8] = [0, 0, 0, 8, 0x04, 0xD2, 0x16, 0x2F];
+
+const STAGES: [TestStage; 5] = [
+    TestStage::Dns,
+    TestStage::TcpConnect,
+    TestStage::Tls,
+    TestStage::Authentication,
+    TestStage::Query,
+];
+
+/// Stage results collected while a test runs.
+#[derive(Default)]
+pub struct Diagnosis {
+    stages: Vec<StageResult>,
+}
+
+impl Diagnosis {
+    /// Runs a stage unless an earlier one failed, recording its latency and
+    /// outcome. The stage's value is returned on success.
+    pub async fn run<T>(&mut self, stage: TestStage, task: impl Future<Output = AppResult<T>>) -> Option<T> {
+        if self.failed() {
+            return None;
+        }
+        let start = Instant::now();
+        let result = task.await;
+        let latency_ms = Some(start.elapsed().as_millis() as u64);
+        match result {
+            Ok(value) => {
+                self.stages.push(StageResult { stage, status: StageStatus::Ok, latency_ms, detail: None, error: None });
+                Some(value)
+            }
+            Err(e) => {
+                self.stages.push(StageResult {
+                    stage,
+                    status: StageStatus::Failed,
+                    latency_ms,
+                    detail: None,
+                    error: Some(e.to_string()),
+                });
+                None
+            }
+        }
+    }
+
+    /// Attaches a detail to the last recorded stage.
+    pub fn note(&mut self, detail: impl Into<String>) {
+        if let Some(last) = self.stages.last_mut() {
+            last.detail = Some(detail.into());
+        }
+    }
+
+    /// Records a stage that does not apply.
+    pub fn skip(&mut self, stage: TestStage, reason: impl Into<String>) {
+        self.stages.push(StageResult {
+            stage,
+            status: StageStatus::Skipped,
+            latency_ms: None,
+            detail: Some(reason.into()),
+            error: None,
+        });
+    }
+
+    /// Whether a stage has failed.
+    pub fn failed(&self) -> bool {
+        self.stages.iter().any(|s| s.status == StageStatus::Failed)
+    }
+
+    /// Returns the results of all stages, marking the ones that did not run
+    /// after a failure as skipped.
+    pub fn finish(mut self) -> Vec<StageResult> {
+        for stage in STAGES {
+            if !self.stages.iter().any(|s| s.stage == stage) {
+                self.skip(stage, "an earlier stage failed");
+            }
+        }
+        self.stages
+    }
+}
+
+/// Runs the DNS, TCP and TLS stages against the server of a connection.
+pub async fn probe_network(config: &ConnectionConfig, timeout: Duration, diagnosis: &mut Diagnosis) {
+    if config.db_type == DbType::SQLite {
+        for stage in [TestStage::Dns, TestStage::TcpConnect, TestStage::Tls] {
+            diagnosis.skip(stage, "not applicable to SQLite");
+        }
+        return;
+    }
+    let host = config.host.clone().unwrap_or_default();
+    let port = config.port.or_else(|| config.db_type.default_port()).unwrap_or_default();
+
+    let Some(addrs) = diagnosis.run(TestStage::Dns, resolve(&host, port, timeout)).await else {
+        return;
+    };
+    diagnosis.note(addrs.iter().map(|a| a.ip().to_string()).collect::<Vec<_>>().join(", "));
+
+    let Some((mut stream, addr)) = diagnosis.run(TestStage::TcpConnect, connect(&addrs, timeout)).await else {
+        return;
+    };
+    diagnosis.note(addr.to_string());
+
+    let tls = match config.db_type {
+        DbType::MySQL | DbType::MariaDB => {
+            diagnosis.run(TestStage::Tls, with_timeout(timeout, mysql_tls(&mut stream))).await
+        }
+        DbType::Postgres => diagnosis.run(TestStage::Tls, with_timeout(timeout, postgres_tls(&mut stream))).await,
+        ref other => {
+            diagnosis.skip(TestStage::Tls, format!("not checked for {}", other));
+            return;
+        }
+    };
+    if let Some(tls) = tls {
+        diagnosis.note(tls.detail);
+        if !tls.offered {
+            // Not a failure: the login continues without encryption
+            if let Some(last) = diagnosis.stages.last_mut() {
+                last.status = StageStatus::Skipped;
+            }
+        }
+    }
+}
+
+async fn with_timeout<T>(timeout: Duration, task: impl Future<Output = AppResult<T>>) -> AppResult<T> {
+    tokio::time::timeout(timeout, task)
+        .await
+        .map_err(|_| AppError::Timeout(format!("no answer within {} s", timeout.as_secs())))?
+}
+
+async fn resolve(host: &str, port: u16, timeout: Duration) -> AppResult<Vec<SocketAddr>> {
+    if host.is_empty() {
+        return Err(AppError::Validation("the connection has no host".into()));
+    }
+    let addrs: Vec<SocketAddr> = with_timeout(timeout, async {
+        tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| AppError::DatabaseConnection(format!("cannot resolve {}: {}", host, e)))
+    })
+    .await?
+    .collect();
+    if addrs.is_empty() {
+        return Err(AppError::DatabaseConnection(format!("{} has no addresses", host)));
+    }
+    Ok(addrs)
+}
+
+/// Connects to the first address that accepts the connection.
+async fn connect(addrs: &[SocketAddr], timeout: Duration) -> AppResult<(TcpStream, SocketAddr)> {
+    let mut last_error = String::new();
+    for addr in addrs {
+        match tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(stream)) => return Ok((stream, *addr)),
+            Ok(Err(e)) => last_error = format!("{}: {}", addr, e),
+            Err(_) => last_error = format!("{}: timed out after {} s", addr, timeout.as_secs()),
+        }
+    }
+    Err(AppError::DatabaseConnection(last_error))
+}
+
+struct TlsSupport {
+    offered: bool,
+    detail: String,
+}
+
+impl TlsSupport {
+    fn new(offered: bool, server: Option<&str>) -> Self {
+        let mut detail = if offered {
+            "offered by the server".to_string()
+        } else {
+            "not offered by the server, the connection is unencrypted".to_string()
+        };
+        if let Some(server) = server {
+            detail = format!("{} ({})", detail, server);
+        }
+        Self { offered, detail }
+    }
+}
+
+/// Reads the MySQL initial handshake packet.
+async fn mysql_tls(stream: &mut TcpStream) -> AppResult<TlsSupport> {
+    let io = |e: std::io::Error| AppError::DatabaseConnection(format!("reading the server greeting: {}", e));
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await.map_err(io)?;
+    let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+    let mut payload = vec![0u8; len.min(64 * 1024)];
+    stream.read_exact(&mut payload).await.map_err(io)?;
+    parse_mysql_greeting(&payload)
+}
+
+fn parse_mysql_greeting(payload: &[u8]) -> AppResult<TlsSupport> {
+    let malformed = || AppError::DatabaseConnection("malformed MySQL server greeting".into());
+    match payload.first() {
+        // Error packet, e.g. "Host is blocked" or "Too many connections"
+        Some(0xFF) => {
+            // error code (2), then an optional `#` and 5-character SQLSTATE
+            let message = payload.get(3..).unwrap_or_default();
+            let message = match message.first() {
+                Some(b'#') => message.get(6..).unwrap_or_default(),
+                _ => message,
+            };
+            return Err(AppError::DatabaseConnection(format!(
+                "server refused the connection: {}",
+                String::from_utf8_lossy(message)
+            )));
+        }
+        Some(_) => {}
+        None => return Err(malformed()),
+    }
+    let version_end = payload[1..].iter().position(|b| *b == 0).ok_or_else(malformed)? + 1;
+    let version = String::from_utf8_lossy(&payload[1..version_end]);
+    // thread id (4), auth plugin data part 1 (8), filler (1), capability flags (2)
+    let flags_at = version_end + 1 + 4 + 8 + 1;
+    let flags = payload.get(flags_at..flags_at + 2).ok_or_else(malformed)?;
+    let capabilities = u16::from_le_bytes([flags[0], flags[1]]);
+    Ok(TlsSupport::new(capabilities & CLIENT_SSL != 0, Some(&DbType::describe_mysql_version(&version))))
+}
+
+/// Sends a PostgreSQL `SSLRequest` and reads the one-byte answer.
+async fn postgres_tls(stream: &mut TcpStream) -> AppResult<TlsSupport> {
+    let io = |e: std::io::Error| AppError::DatabaseConnection(format!("sending SSLRequest: {}", e));
+    stream.write_all(&PG_SSL_REQUEST).await.map_err(io)?;
+    let mut answer = [0u8; 1];
+    stream.read_exact(&mut answer).await.map_err(io)?;
+    match answer[0] {
+        b'S' => Ok(TlsSupport::new(true, None)),
+        b'N' => Ok(TlsSupport::new(false, None)),
+        other => Err(AppError::DatabaseConnection(format!(
+            "unexpected answer to SSLRequest: 0x{:02x}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tls_flag_from_mysql_greeting() {
+        let mut greeting = vec![10];
+        greeting.extend_from_slice(b"8.0.36\0");
+        greeting.extend_from_slice(&[1, 0, 0, 0]);
+        greeting.extend_from_slice(&[0; 8]);
+        greeting.push(0);
+        greeting.extend_from_slice(&(0xF7FFu16 | CLIENT_SSL).to_le_bytes());
+        let tls = parse_mysql_greeting(&greeting).unwrap();
+        assert!(tls.offered);
+        assert!(tls.detail.contains("MySQL 8.0.36"));
+
+        let len = greeting.len();
+        greeting[len - 2..].copy_from_slice(&(0xF7FFu16 & !CLIENT_SSL).to_le_bytes());
+        assert!(!parse_mysql_greeting(&greeting).unwrap().offered);
+        assert!(parse_mysql_greeting(&greeting[..10]).is_err());
+    }
+
+    #[tokio::test]
+    async fn skips_stages_after_a_failure() {
+        let mut diagnosis = Diagnosis::default();
+        assert_eq!(diagnosis.run(TestStage::Dns, async { Ok(1) }).await, Some(1));
+        diagnosis
+            .run(TestStage::TcpConnect, async { Err::<(), _>(AppError::DatabaseConnection("refused".into())) })
+            .await;
+        assert_eq!(diagnosis.run(TestStage::Tls, async { Ok(()) }).await, None);
+
+        let stages = diagnosis.finish();
+        let statuses: Vec<_> = stages.iter().map(|s| (s.stage, s.status)).collect();
+        assert_eq!(
+            statuses,
+            [
+                (TestStage::Dns, StageStatus::Ok),
+                (TestStage::TcpConnect, StageStatus::Failed),
+                (TestStage::Tls, StageStatus::Skipped),
+                (TestStage::Authentication, StageStatus::Skipped),
+                (TestStage::Query, StageStatus::Skipped),
+            ]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Per-user favorite and recently used connections.
+//!
+//! The `user_connections` metadata table keeps, per principal and connection,
+//! whether the connection is a favorite and when the principal last ran a
+//! query on it. `GET /api/connections?view=favorites|recent` uses it to return
+//! a personalized list. Requests without a principal are not tracked.
+//!
+//! Recording a use is best effort: failures are logged and never fail the
+//! query that triggered them.
+
+use std::sync::Arc;
+
+use common::errors::AppResult;
+use common::meta::Statement;
+use common::meta_query;
+
+use crate::pool_manager::PoolManager;
+
+/// Most recent connections returned by [`FavoriteStore::recent`].
+pub const MAX_RECENT: u32 = 100;
+
+/// Favorite flags and last use of connections, per principal.
+pub struct FavoriteStore {
+    pool_manager: Arc<PoolManager>,
+}
+
+impl FavoriteStore {
+    /// Creates the store.
+    pub fn new(pool_manager: Arc<PoolManager>) -> Self {
+        Self { pool_manager }
+    }
+
+    /// Marks or unmarks a connection as one of the principal's favorites.
+    pub async fn set_favorite(&self, principal: &str, connection_id: &str, favorite: bool) -> AppResult<()> {
+        if favorite {
+            let statement = self.pool_manager.meta_pool().sql(&Statement {
+                mysql: "INSERT INTO `user_connections` (`principal`, `connection_id`, `favorite`, `favorited_at`) \
+                     VALUES (?, ?, TRUE, UTC_TIMESTAMP()) \
+                     ON DUPLICATE KEY UPDATE `favorited_at` = CASE WHEN `favorite` = TRUE THEN `favorited_at` ELSE UTC_TIMESTAMP() END, \
+                     `favorite` = TRUE",
+                postgres: r#"INSERT INTO "user_connections" ("principal", "connection_id", "favorite", "favorited_at")
+                     VALUES ($1, $2, TRUE, to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'))
+                     ON CONFLICT ("principal", "connection_id") DO UPDATE SET "favorited_at" = CASE WHEN "user_connections"."favorite" = TRUE THEN "user_connections"."favorited_at" ELSE to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') END,
+                     "favorite" = TRUE"#,
+                sqlite: r#"INSERT INTO "user_connections" ("principal", "connection_id", "favorite", "favorited_at")
+                     VALUES (?, ?, TRUE, CURRENT_TIMESTAMP)
+                     ON CONFLICT ("principal", "connection_id") DO UPDATE SET "favorited_at" = CASE WHEN "favorite" = TRUE THEN "favorited_at" ELSE CURRENT_TIMESTAMP END,
+                     "favorite" = TRUE"#,
+            });
+            meta_query!(self.pool_manager.meta_pool(), |pool| {
+                sqlx::query(statement)
+                    .bind(principal)
+                    .bind(connection_id)
+                    .execute(pool)
+                    .await
+                    .map(|r| r.rows_affected())
+            })?;
+        } else {
+            let statement = self.pool_manager.meta_pool().sql(&Statement {
+                mysql: "UPDATE `user_connections` SET `favorite` = FALSE, `favorited_at` = NULL \
+                     WHERE `principal` = ? AND `connection_id` = ?",
+                postgres: r#"UPDATE "user_connections" SET "favorite" = FALSE, "favorited_at" = NULL
+                     WHERE "principal" = $1 AND "connection_id" = $2"#,
+                sqlite: r#"UPDATE "user_connections" SET "favorite" = FALSE, "favorited_at" = NULL
+                     WHERE "principal" = ? AND "connection_id" = ?"#,
+            });
+            meta_query!(self.pool_manager.meta_pool(), |pool| {
+                sqlx::query(statement)
+                    .bind(principal)
+                    .bind(connection_id)
+                    .execute(pool)
+                    .await
+                    .map(|r| r.rows_affected())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Records that the principal ran a query on a connection.
+    pub async fn record_use(&self, principal: Option<&str>, connection_id: &str) {
+        let Some(principal) = principal else {
+            return;
+        };
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `user_connections` (`principal`, `connection_id`, `last_used_at`, `use_count`) \
+                 VALUES (?, ?, UTC_TIMESTAMP(), 1) \
+                 ON DUPLICATE KEY UPDATE `last_used_at` = UTC_TIMESTAMP(), `use_count` = `use_count` + 1",
+            postgres: r#"INSERT INTO "user_connections" ("principal", "connection_id", "last_used_at", "use_count")
+                 VALUES ($1, $2, to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'), 1)
+                 ON CONFLICT ("principal", "connection_id") DO UPDATE SET "last_used_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'), "use_count" = "user_connections"."use_count" + 1"#,
+            sqlite: r#"INSERT INTO "user_connections" ("principal", "connection_id", "last_used_at", "use_count")
+                 VALUES (?, ?, CURRENT_TIMESTAMP, 1)
+                 ON CONFLICT ("principal", "connection_id") DO UPDATE SET "last_used_at" = CURRENT_TIMESTAMP, "use_count" = "use_count" + 1"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(principal)
+                .bind(connection_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        });
+        if let Err(e) = result {
+            tracing::warn!(principal, connection_id, error = %e, "Failed to record connection use");
+        }
+    }
+
+    /// IDs of the principal's favorite connections, most recently favorited first.
+    pub async fn favorites(&self, principal: &str) -> AppResult<Vec<String>> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `connection_id` FROM `user_connections` \
+                 WHERE `principal` = ? AND `favorite` = TRUE ORDER BY `favorited_at` DESC",
+            postgres: r#"SELECT "connection_id" FROM "user_connections"
+                 WHERE "principal" = $1 AND "favorite" = TRUE ORDER BY "favorited_at" DESC"#,
+            sqlite: r#"SELECT "connection_id" FROM "user_connections"
+                 WHERE "principal" = ? AND "favorite" = TRUE ORDER BY "favorited_at" DESC"#,
+        });
+        let rows: Vec<(String,)> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(principal)
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// IDs of the connections the principal queried most recently, newest first.
+    pub async fn recent(&self, principal: &str) -> AppResult<Vec<String>> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `connection_id` FROM `user_connections` \
+                 WHERE `principal` = ? AND `last_used_at` IS NOT NULL ORDER BY `last_used_at` DESC LIMIT ?",
+            postgres: r#"SELECT "connection_id" FROM "user_connections"
+                 WHERE "principal" = $1 AND "last_used_at" IS NOT NULL ORDER BY "last_used_at" DESC LIMIT $2"#,
+            sqlite: r#"SELECT "connection_id" FROM "user_connections"
+                 WHERE "principal" = ? AND "last_used_at" IS NOT NULL ORDER BY "last_used_at" DESC LIMIT ?"#,
+        });
+        let rows: Vec<(String,)> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(principal)
+                .bind(i64::from(MAX_RECENT))
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Forgets a deleted connection for every principal.
+    pub async fn forget(&self, connection_id: &str) {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `user_connections` WHERE `connection_id` = ?",
+            postgres: r#"DELETE FROM "user_connections" WHERE "connection_id" = $1"#,
+            sqlite: r#"DELETE FROM "user_connections" WHERE "connection_id" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(connection_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        });
+        if let Err(e) = result {
+            tracing::warn!(connection_id, error = %e, "Failed to forget connection favorites");
+        }
+    }
+}
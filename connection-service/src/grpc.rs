@@ -0,0 +1,129 @@
+//! 内部 gRPC 接口模块
+//!
+//! 实现 `common/proto/connection.proto` 中的 `dbm.connection.v1.ConnectionService`，
+//! 供 query-service 读取连接信息、执行查询。与 `/internal/pools/{id}`、
+//! `/internal/connections/{id}/execute`、`/internal/connections/{id}/changes`
+//! 共用同一套校验与执行逻辑，HTTP 接口保留用于外部兼容。
+//!
+//! 接口与 HTTP API 在同一端口提供（明文 h2c 或 TLS 上的 HTTP/2），同样经过签名
+//! 校验中间件，只允许 query-service 调用。发起请求的主体取自 `x-principal` 元数据。
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tonic::{Request, Response, Status};
+
+use common::grpc::proto::{self, connection_service_server};
+use common::middleware::auth::principal;
+use common::models::query::QueryLanguage;
+
+use crate::handlers::{self, ExecuteQueryBody};
+use crate::state::AppState;
+
+/// gRPC 方法的路由（`/dbm.connection.v1.ConnectionService/{method}`）
+pub fn route() -> String {
+    format!("/{}/{{*method}}", connection_service_server::SERVICE_NAME)
+}
+
+/// 创建 gRPC 服务
+pub fn service(state: AppState) -> connection_service_server::ConnectionServiceServer<ConnectionRpc> {
+    connection_service_server::ConnectionServiceServer::new(ConnectionRpc { state })
+}
+
+/// `ConnectionService` 的实现
+pub struct ConnectionRpc {
+    state: AppState,
+}
+
+#[tonic::async_trait]
+impl connection_service_server::ConnectionService for ConnectionRpc {
+    async fn get_pool_info(&self, request: Request<proto::PoolInfoRequest>) -> Result<Response<proto::PoolInfo>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let info = handlers::pool_info_on_behalf(&self.state, principal(&headers), &request.get_ref().connection_id).await?;
+        Ok(Response::new(proto::PoolInfo {
+            id: info.id,
+            db_type: info.db_type,
+            host: info.host,
+            port: info.port.map(u32::from),
+            database: info.database,
+            namespace: info.namespace,
+            allowlist_json: json(&info.allowlist),
+            query_timeout_ms: info.query_timeout_ms,
+            health_json: json(&info.health),
+            masking_json: json(&info.masking),
+            statement_policy_json: json(&info.statement_policy),
+            pool_status_json: json(&info.pool_status),
+        }))
+    }
+
+    async fn execute_query(&self, request: Request<proto::QueryRequest>) -> Result<Response<proto::QueryResult>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let request = request.into_inner();
+        let body = query_body(&request)?;
+        let result = handlers::query_on_behalf(&self.state, principal(&headers), &request.connection_id, &body).await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn execute_change(&self, request: Request<proto::QueryRequest>) -> Result<Response<proto::QueryResult>, Status> {
+        let headers = request.metadata().clone().into_headers();
+        let request = request.into_inner();
+        let body = query_body(&request)?;
+        let result = handlers::change_on_behalf(&self.state, principal(&headers), &request.connection_id, &body).await?;
+        Ok(Response::new(result.into()))
+    }
+
+    async fn health(&self, _request: Request<proto::HealthRequest>) -> Result<Response<proto::HealthResponse>, Status> {
+        let ready = self.state.warmup.status().await.ready;
+        Ok(Response::new(proto::HealthResponse { ready }))
+    }
+}
+
+/// 可选设置的 JSON 文本
+fn json<T: Serialize>(value: &Option<T>) -> Option<String> {
+    value.as_ref().and_then(|v| serde_json::to_string(v).ok())
+}
+
+/// 转换为内部 HTTP 接口的请求体，参数值须为 JSON 文本
+fn query_body(request: &proto::QueryRequest) -> Result<ExecuteQueryBody, Status> {
+    let parse = |value: &str| {
+        serde_json::from_str(value).map_err(|e| Status::invalid_argument(format!("参数不是有效的 JSON: {}", e)))
+    };
+    Ok(ExecuteQueryBody {
+        sql: request.sql.clone(),
+        database: request.database.clone(),
+        limit: request.limit.unwrap_or_else(handlers::default_limit),
+        params: request.params.iter().map(|v| parse(v)).collect::<Result<_, _>>()?,
+        named_params: request
+            .named_params
+            .iter()
+            .map(|(name, v)| Ok((name.clone(), parse(v)?)))
+            .collect::<Result<BTreeMap<_, _>, Status>>()?,
+        timeout_ms: request.timeout_ms,
+        query_language: QueryLanguage::from(request.query_language()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_query_requests() {
+        let request = proto::QueryRequest {
+            connection_id: "c1".into(),
+            sql: "SELECT * FROM users WHERE id = :id".into(),
+            named_params: [("id".to_string(), "42".to_string())].into(),
+            database: Some("app".into()),
+            query_language: proto::QueryLanguage::Cypher as i32,
+            ..Default::default()
+        };
+        let body = query_body(&request).unwrap();
+        assert_eq!(body.limit, 1000);
+        assert_eq!(body.named_params["id"], 42);
+        assert_eq!(body.database.as_deref(), Some("app"));
+        assert_eq!(body.query_language, QueryLanguage::Cypher);
+
+        let request = proto::QueryRequest { params: vec!["not json".into()], ..request };
+        assert_eq!(query_body(&request).err().unwrap().code(), tonic::Code::InvalidArgument);
+    }
+}
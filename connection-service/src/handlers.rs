@@ -1,36 +1,143 @@
 //! Handler模块
 
+use std::collections::{BTreeMap, HashMap};
+
 use axum::{
-    extract::{Path, State},
-    Json,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{AppendHeaders, IntoResponse, Response},
 };
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 use utoipa::ToSchema;
+use validator::Validate;
 
+use common::admin;
+use common::config::ConfigReload;
 use common::errors::AppError;
-use common::models::connection::{ConnectionItem, CreateConnectionRequest};
-use common::models::database::TableSchema;
-use common::models::monitor::{DatabaseInfo, MonitorOverview, ProcessInfo};
-use common::models::query::QueryResult;
+use common::events::{kinds, EventPublisher};
+use common::extract::Json;
+use common::jwt::principal_user_id;
+use common::middleware::auth::{principal, session_id, workspace};
+use common::middleware::csrf::{clear_session_cookies, session_cookies};
+use common::probes::{Liveness, ProbeCheck, Readiness};
+use common::models::backup::{BackupRecord, BackupStatus, CreateBackupRequest, RestoreJob, RestoreRequest, RestoreStatus};
+use common::models::connection::{
+    ConnectionAllowlist, ConnectionConfig, ConnectionItem, ConnectionPoolOptions, ConnectionRevision,
+    ConnectionView, CreateConnectionRequest, DbType, PinnedSettings,
+    QueryTimeoutSettings, RotatePasswordRequest, StatementPolicy,
+};
+use common::models::job::{Job, JobKind};
+use common::models::diagnostics::ConnectionTestResult;
+use common::models::database::{AutocompleteCatalog, GraphElementType, TableSchema, TableStats};
+use common::models::api_key::{
+    ApiKey, CreateApiKeyRequest, CreateGuestLinkRequest, CreatedApiKey, CreatedGuestLink, VerifyApiKeyRequest,
+};
+use common::models::key_value::{KeyValueEntry, SetKeyValueRequest};
+use common::models::masking::ConnectionMasking;
+use common::models::metadata::{ImportConflictPolicy, MetadataArchive, MetadataImportReport, MigrationReport};
+use common::models::policy::{AuthzDecision, AuthzRequest, Policy, PolicyDecisionLog, PolicyRequest};
+use common::models::monitor::{
+    CachedPool, ConnectionPoolStats, DatabaseInfo, MonitorOverview, PoolStatus, ProcessInfo, TargetHealth, WarmupStatus,
+};
+use common::models::query::{QueryDiffResult, QueryLanguage, QueryResult, SampleRequest, SampleResult};
+use common::models::schema_change::{SchemaChangeJob, SchemaChangeRequest};
+use common::models::scheduler::{CreateScheduledJobRequest, JobRun, ScheduledJob};
+use common::models::alert::{AlertEvaluation, AlertRule, CreateAlertRuleRequest};
+use common::models::schema_diff::{SchemaDiff, SchemaDiffRequest};
+use common::models::schema_graph::SchemaGraph;
+use common::models::seed::{SeedRequest, SeedResult};
+use common::models::auth::{AuthTokens, LoginLockout, LoginRequest, RefreshRequest, SetPasswordRequest, User};
+use common::models::session::{CreateSsoSessionRequest, RevokedSessions, Session, VerifySessionRequest};
+use common::models::snapshot::{CompareSnapshotsRequest, CreateSnapshotRequest, QuerySnapshot};
+use common::models::transfer::{TransferJob, TransferRequest};
+use common::models::usage::{UsageQuota, UsageReport, UserUsage};
+use common::models::workload::{StatementType, WorkloadBreakdown};
+use common::models::workspace::{
+    AddWorkspaceMemberRequest, CreateWorkspaceRequest, UpdateWorkspaceRequest, Workspace, WorkspaceDetail,
+    WorkspaceMember, WorkspaceResourceKind,
+};
+use common::progress::{self, ProgressEvent, ProgressSubscription};
 use common::response::ApiResponse;
-use crate::service::{ConnectionService, ConnectionServiceTrait};
+use common::utils::sql_lexer::Dialect;
+use common::utils::{ChangePreviewSql, CypherAnalyzer, PlaceholderStyle, SqlParams, SqlSplitter, SqlTableExtractor, SqlValidator};
+use dbm_core::drivers::influxdb;
+use crate::metadata;
+use crate::migrations;
+use crate::sampling;
+use crate::schema_diff;
+use crate::schema_graph;
+use crate::seed;
+use crate::snapshot;
+use crate::service::{ConnectionService, ConnectionServiceTrait, Viewer};
 use crate::state::AppState;
+use crate::table_stats;
+
+/// 以请求调用方的名义操作连接，变更记入连接历史
+fn connection_service(state: &AppState, headers: &HeaderMap) -> ConnectionService {
+    ConnectionService::new(state.pool_manager.clone(), state.revisions.clone(), principal(headers))
+}
+
+/// 请求的调用方：网关转发的 `X-Principal`，携带有效管理员令牌时可见全部连接
+fn viewer(headers: &HeaderMap) -> Viewer<'_> {
+    Viewer {
+        principal: principal(headers),
+        admin: admin::authorize(headers).is_ok(),
+    }
+}
+
+/// query-service 代为执行时的调用方：按转发的 `X-Principal` 检查连接归属
+fn on_behalf(principal: Option<&str>) -> Viewer<'_> {
+    Viewer { principal, admin: false }
+}
 
-/// 列出所有已保存的数据库连接
+/// 连接列表查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct ConnectionListQuery {
+    /// 个性化视图：`favorites` 为调用方收藏的连接，`recent` 为调用方最近查询过的连接；缺省返回全部可见连接
+    pub view: Option<ConnectionView>,
+}
+
+/// 列出调用方可见的数据库连接（未设归属的连接对所有人可见）
+///
+/// 指定 `view` 时只返回调用方收藏或最近使用的连接，按收藏 / 使用时间倒序排列；
+/// 携带 `X-Workspace-Id` 时只返回该工作区中的连接。
 #[utoipa::path(
     get,
     path = "/api/connections",
     tag = "connections",
+    params(ConnectionListQuery),
     responses(
-        (status = 200, description = "连接列表", body = ApiResponse<Vec<ConnectionItem>>)
+        (status = 200, description = "调用方可见的连接列表", body = ApiResponse<Vec<ConnectionItem>>),
+        (status = 401, description = "指定了 view 但请求未携带主体"),
+        (status = 404, description = "工作区不存在或调用方不是其成员")
     )
 )]
 pub async fn list_connections(
     State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ConnectionListQuery>,
 ) -> Result<Json<ApiResponse<Vec<ConnectionItem>>>, AppError> {
-    let service = ConnectionService::new(state.pool_manager);
-    let data = service.list().await;
+    let service = connection_service(&state, &headers);
+    let mut data = service.list(viewer(&headers)).await;
+    if let Some(view) = query.view {
+        let principal = principal(&headers).ok_or(AppError::Unauthorized)?;
+        let ids = match view {
+            ConnectionView::Favorites => state.favorites.favorites(principal).await?,
+            ConnectionView::Recent => state.favorites.recent(principal).await?,
+        };
+        // 只保留仍然可见的连接，顺序与收藏 / 使用记录一致
+        let mut visible: HashMap<String, ConnectionItem> =
+            data.into_iter().map(|item| (item.id.clone(), item)).collect();
+        data = ids.iter().filter_map(|id| visible.remove(id)).collect();
+    }
+    if let Some(workspace) = workspace(&headers) {
+        let ids = state
+            .workspaces
+            .resource_ids(workspace, WorkspaceResourceKind::Connection, viewer(&headers))
+            .await?;
+        data.retain(|item| ids.contains(&item.id));
+    }
     Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
@@ -41,15 +148,22 @@ pub async fn list_connections(
     tag = "connections",
     request_body = CreateConnectionRequest,
     responses(
-        (status = 200, description = "连接已创建", body = ApiResponse<ConnectionItem>)
+        (status = 200, description = "连接已创建", body = ApiResponse<ConnectionItem>),
+        (status = 400, description = "参数或连接串无效")
     )
 )]
 pub async fn create_connection(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<CreateConnectionRequest>,
 ) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
-    let service = ConnectionService::new(state.pool_manager);
-    let data = service.create(req).await?;
+    req.validate()?;
+    let service = connection_service(&state, &headers);
+    let data = service.create(req, viewer(&headers)).await?;
+    state.events.publish(
+        kinds::CONNECTION_CREATED,
+        serde_json::json!({ "connection_id": data.id, "name": data.name, "db_type": data.db_type }),
+    );
     Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
@@ -68,10 +182,11 @@ pub async fn create_connection(
 )]
 pub async fn get_connection(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
 ) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
-    let service = ConnectionService::new(state.pool_manager);
-    let data = service.get(&id).await?;
+    let service = connection_service(&state, &headers);
+    let data = service.get(&id, viewer(&headers)).await?;
     Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
@@ -90,241 +205,4294 @@ pub async fn get_connection(
 )]
 pub async fn delete_connection(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
 ) -> Result<Json<ApiResponse<bool>>, AppError> {
-    let service = ConnectionService::new(state.pool_manager);
-    service.delete(&id).await?;
+    let service = connection_service(&state, &headers);
+    service.delete(&id, viewer(&headers)).await?;
+    state.autocomplete.invalidate(&id).await;
+    state.health.forget(&id).await;
+    state.favorites.forget(&id).await;
+    state.workspaces.forget(WorkspaceResourceKind::Connection, &id).await;
+    state.events.publish(kinds::CONNECTION_DELETED, serde_json::json!({ "connection_id": id }));
     Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
 }
 
-/// 测试数据库连接
+/// 设置连接的库表白名单，限制可浏览与可查询的库和表（空白名单表示不限制）
 #[utoipa::path(
-    get,
-    path = "/api/connections/{id}/test",
+    put,
+    path = "/api/connections/{id}/allowlist",
     tag = "connections",
     params(
         ("id" = String, Path, description = "连接 ID")
     ),
+    request_body = ConnectionAllowlist,
     responses(
-        (status = 200, description = "连接测试结果", body = ApiResponse<ConnectionTestResult>),
+        (status = 200, description = "白名单已更新", body = ApiResponse<ConnectionItem>),
         (status = 404, description = "连接未找到")
     )
 )]
-pub async fn test_connection(
+pub async fn set_connection_allowlist(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<Json<ApiResponse<ConnectionTestResult>>, AppError> {
-    let service = ConnectionService::new(state.pool_manager);
-    match service.test(&id).await {
-        Ok(latency_ms) => Ok(Json(ApiResponse::ok_with_service(
-            ConnectionTestResult {
-                id,
-                success: true,
-                latency_ms: Some(latency_ms),
-                error: None,
-            },
-            "connection-service",
-        ))),
-        Err(e) => Ok(Json(ApiResponse::ok_with_service(
-            ConnectionTestResult {
-                id,
-                success: false,
-                latency_ms: None,
-                error: Some(e.to_string()),
-            },
-            "connection-service",
-        ))),
-    }
+    Json(allowlist): Json<ConnectionAllowlist>,
+) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
+    let service = connection_service(&state, &headers);
+    let data = service.set_allowlist(&id, allowlist, viewer(&headers)).await?;
+    publish_updated(&state.events, &id, "allowlist");
+    Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
-/// 健康检查端点
+/// 设置连接的查询结果脱敏规则：列名匹配规则的值按 hash / partial / redact 脱敏后返回，豁免主体除外（没有规则表示不脱敏）
 #[utoipa::path(
-    get,
-    path = "/api/health",
-    tag = "health",
+    put,
+    path = "/api/connections/{id}/masking",
+    tag = "connections",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    request_body = ConnectionMasking,
     responses(
-        (status = 200, description = "服务运行正常", body = HealthResponse)
+        (status = 200, description = "脱敏规则已更新", body = ApiResponse<ConnectionItem>),
+        (status = 400, description = "规则无效"),
+        (status = 404, description = "连接未找到")
     )
 )]
-pub async fn health_check(
+pub async fn set_connection_masking(
     State(state): State<AppState>,
-) -> Json<HealthResponse> {
-    Json(HealthResponse {
-        status: "healthy".to_string(),
-        service: "connection-service".to_string(),
-        version: env!("CARGO_PKG_VERSION").to_string(),
-        timestamp: Utc::now(),
-        connections: state.pool_manager.connection_count().await,
-    })
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(masking): Json<ConnectionMasking>,
+) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
+    let service = connection_service(&state, &headers);
+    let data = service.set_masking(&id, masking, viewer(&headers)).await?;
+    publish_updated(&state.events, &id, "masking");
+    Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
-/// 内部端点，供其他服务获取连接池信息
+/// 设置连接允许执行的语句类型（select / insert / update / delete / ddl / other），由查询服务执行前校验（空列表表示不限制）
 #[utoipa::path(
-    get,
-    path = "/internal/pools/{id}",
-    tag = "internal",
+    put,
+    path = "/api/connections/{id}/statement-policy",
+    tag = "connections",
     params(
         ("id" = String, Path, description = "连接 ID")
     ),
+    request_body = StatementPolicy,
     responses(
-        (status = 200, description = "连接池信息", body = ApiResponse<PoolInfo>),
+        (status = 200, description = "语句策略已更新", body = ApiResponse<ConnectionItem>),
         (status = 404, description = "连接未找到")
     )
 )]
-pub async fn get_pool_info(
+pub async fn set_connection_statement_policy(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<Json<ApiResponse<PoolInfo>>, AppError> {
-    let service = ConnectionService::new(state.pool_manager.clone());
-    let conn = service.get(&id).await?;
-    
-    Ok(Json(ApiResponse::ok(PoolInfo {
-        id: conn.id,
-        db_type: conn.db_type.to_string(),
-        host: conn.host,
-        port: conn.port,
-        database: conn.database,
-    })))
+    Json(policy): Json<StatementPolicy>,
+) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
+    let service = connection_service(&state, &headers);
+    let data = service.set_statement_policy(&id, policy, viewer(&headers)).await?;
+    publish_updated(&state.events, &id, "statement_policy");
+    Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct ConnectionTestResult {
-    pub id: String,
-    pub success: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub latency_ms: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
+/// 设置连接的默认查询超时（未指定 query_timeout_ms 时恢复服务默认值）
+#[utoipa::path(
+    put,
+    path = "/api/connections/{id}/query-timeout",
+    tag = "connections",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    request_body = QueryTimeoutSettings,
+    responses(
+        (status = 200, description = "默认查询超时已更新", body = ApiResponse<ConnectionItem>),
+        (status = 400, description = "超时时间无效"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn set_connection_query_timeout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(settings): Json<QueryTimeoutSettings>,
+) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
+    settings.validate()?;
+    let service = connection_service(&state, &headers);
+    let data = service.set_query_timeout(&id, settings.query_timeout_ms, viewer(&headers)).await?;
+    publish_updated(&state.events, &id, "query_timeout");
+    Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct HealthResponse {
-    pub status: String,
-    pub service: String,
-    pub version: String,
-    pub timestamp: DateTime<Utc>,
-    pub connections: usize,
+/// 固定或取消固定连接，固定的连接在服务启动时预热连接池与 Schema 缓存
+#[utoipa::path(
+    put,
+    path = "/api/connections/{id}/pinned",
+    tag = "connections",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    request_body = PinnedSettings,
+    responses(
+        (status = 200, description = "固定状态已更新", body = ApiResponse<ConnectionItem>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn set_connection_pinned(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(settings): Json<PinnedSettings>,
+) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
+    let service = connection_service(&state, &headers);
+    let data = service.set_pinned(&id, settings.pinned, viewer(&headers)).await?;
+    publish_updated(&state.events, &id, "pinned");
+    Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
-#[derive(Serialize, ToSchema)]
-pub struct PoolInfo {
-    pub id: String,
-    pub db_type: String,
-    pub host: Option<String>,
-    pub port: Option<u16>,
-    pub database: Option<String>,
+/// 设置连接池参数，未指定的字段使用服务默认值；已打开的连接池在下次使用时按新参数重建
+#[utoipa::path(
+    put,
+    path = "/api/connections/{id}/pool-options",
+    tag = "connections",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    request_body = ConnectionPoolOptions,
+    responses(
+        (status = 200, description = "连接池参数已更新", body = ApiResponse<ConnectionItem>),
+        (status = 400, description = "连接池参数无效"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn set_connection_pool_options(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(options): Json<ConnectionPoolOptions>,
+) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
+    options.validate()?;
+    let service = connection_service(&state, &headers);
+    let data = service.set_pool_options(&id, Some(options).filter(|o| !o.is_empty()), viewer(&headers)).await?;
+    publish_updated(&state.events, &id, "pool_options");
+    Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
-/// 获取连接的监控概览
+/// 轮换连接密码：先用新密码建立连接并验证，成功后才保存并替换连接池，
+/// 旧连接池上正在执行的查询不受影响
 #[utoipa::path(
-    get,
-    path = "/api/connections/{id}/stats",
-    tag = "monitor",
+    post,
+    path = "/api/connections/{id}/rotate-password",
+    tag = "connections",
     params(
         ("id" = String, Path, description = "连接 ID")
     ),
+    request_body = RotatePasswordRequest,
     responses(
-        (status = 200, description = "监控数据", body = ApiResponse<MonitorOverview>),
-        (status = 404, description = "连接未找到")
+        (status = 200, description = "密码已轮换", body = ApiResponse<ConnectionItem>),
+        (status = 400, description = "参数无效"),
+        (status = 404, description = "连接未找到"),
+        (status = 409, description = "密码来自外部密钥引用"),
+        (status = 502, description = "新密码无法连接目标库，原密码保持不变")
     )
 )]
-pub async fn get_connection_stats(
+pub async fn rotate_connection_password(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<Json<ApiResponse<MonitorOverview>>, AppError> {
-    let overview = state.pool_manager.get_monitor_overview(&id).await?;
-    Ok(Json(ApiResponse::ok_with_service(overview, "connection-service")))
+    Json(req): Json<RotatePasswordRequest>,
+) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
+    req.validate()?;
+    let service = connection_service(&state, &headers);
+    let data = service.rotate_password(&id, req.password, viewer(&headers)).await?;
+    state.health.forget(&id).await;
+    publish_updated(&state.events, &id, "password");
+    Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
-/// 获取连接上的数据库列表
+/// 查看连接的变更历史（最新在前）：每个版本记录操作人、时间与字段级差异，密码只显示为已修改
+///
+/// 已删除连接的历史需要 X-Admin-Token。
 #[utoipa::path(
     get,
-    path = "/api/connections/{id}/databases",
-    tag = "monitor",
+    path = "/api/connections/{id}/history",
+    tag = "connections",
     params(
         ("id" = String, Path, description = "连接 ID")
     ),
     responses(
-        (status = 200, description = "数据库列表", body = ApiResponse<Vec<DatabaseInfo>>),
+        (status = 200, description = "变更历史", body = ApiResponse<Vec<ConnectionRevision>>),
         (status = 404, description = "连接未找到")
     )
 )]
-pub async fn get_connection_databases(
+pub async fn get_connection_history(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<Json<ApiResponse<Vec<DatabaseInfo>>>, AppError> {
-    let databases = state.pool_manager.get_databases(&id).await?;
-    Ok(Json(ApiResponse::ok_with_service(databases, "connection-service")))
+) -> Result<Json<ApiResponse<Vec<ConnectionRevision>>>, AppError> {
+    let service = connection_service(&state, &headers);
+    let data = service.history(&id, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
 }
 
-/// 获取连接的数据库表结构（供 AI 服务使用）
-pub async fn get_connection_schema(
+/// 将连接恢复为某个历史版本的配置，并记为新的版本；保留当前密码与归属，
+/// 已删除的连接（需要 X-Admin-Token）按该版本重新创建，不含密码
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/history/{revision}/restore",
+    tag = "connections",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("revision" = u32, Path, description = "版本号")
+    ),
+    responses(
+        (status = 200, description = "连接已恢复", body = ApiResponse<ConnectionItem>),
+        (status = 400, description = "该版本删除了连接，无可恢复的配置"),
+        (status = 404, description = "连接或版本未找到")
+    )
+)]
+pub async fn restore_connection_revision(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, revision)): Path<(String, u32)>,
+) -> Result<Json<ApiResponse<ConnectionItem>>, AppError> {
+    let service = connection_service(&state, &headers);
+    let data = service.restore_revision(&id, revision, viewer(&headers)).await?;
+    state.schema_cache.invalidate(&id).await;
+    state.autocomplete.invalidate(&id).await;
+    state.health.forget(&id).await;
+    publish_updated(&state.events, &id, "revision");
+    Ok(Json(ApiResponse::ok_with_service(data, "connection-service")))
+}
+
+/// 将连接加入调用方的收藏
+#[utoipa::path(
+    put,
+    path = "/api/connections/{id}/favorite",
+    tag = "connections",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "连接已收藏", body = ApiResponse<bool>),
+        (status = 401, description = "请求未携带主体"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn add_connection_favorite(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<Json<ApiResponse<TableSchema>>, AppError> {
-    let schema = state.pool_manager.get_table_schema(&id).await?;
-    Ok(Json(ApiResponse::ok_with_service(schema, "connection-service")))
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    set_favorite(&state, &headers, &id, true).await
 }
 
-/// 执行 SQL 查询
-#[derive(serde::Deserialize)]
-pub struct ExecuteQueryBody {
-    pub sql: String,
-    #[serde(default = "default_limit")]
-    pub limit: u32,
+/// 将连接移出调用方的收藏
+#[utoipa::path(
+    delete,
+    path = "/api/connections/{id}/favorite",
+    tag = "connections",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "已取消收藏", body = ApiResponse<bool>),
+        (status = 401, description = "请求未携带主体"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn remove_connection_favorite(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    set_favorite(&state, &headers, &id, false).await
 }
 
-fn default_limit() -> u32 {
-    1000
+async fn set_favorite(
+    state: &AppState,
+    headers: &HeaderMap,
+    id: &str,
+    favorite: bool,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    let principal = principal(headers).ok_or(AppError::Unauthorized)?;
+    connection_service(state, headers).get(id, viewer(headers)).await?;
+    state.favorites.set_favorite(principal, id, favorite).await?;
+    Ok(Json(ApiResponse::ok_with_service(favorite, "connection-service")))
 }
 
-pub async fn execute_query(
+/// 分阶段测试尚未保存的连接配置（请求体与创建连接相同），不保存连接也不保留连接池；
+/// SQLite 文件不存在时不会创建
+#[utoipa::path(
+    post,
+    path = "/api/connections/test",
+    tag = "connections",
+    request_body = CreateConnectionRequest,
+    responses(
+        (status = 200, description = "连接测试结果", body = ApiResponse<ConnectionTestResult>),
+        (status = 400, description = "参数或连接串无效")
+    )
+)]
+pub async fn test_unsaved_connection(
     State(state): State<AppState>,
-    Path(id): Path<String>,
-    Json(body): Json<ExecuteQueryBody>,
-) -> Result<Json<ApiResponse<QueryResult>>, AppError> {
-    // 基础安全检查：禁止写操作（使用词边界匹配避免误判）
-    let sql_trimmed = body.sql.trim();
-    let sql_upper = sql_trimmed.to_uppercase();
-
-    // 检查 SQL 语句是否以危险关键词开头（忽略前导空白和注释）
-    let sql_no_comment = sql_upper
-        .trim_start_matches(|c: char| c.is_whitespace())
-        .trim_start_matches("--")
-        .trim_start();
-    let dangerous_starts = ["INSERT", "UPDATE", "DELETE", "DROP", "TRUNCATE", "ALTER", "CREATE"];
-    for kw in dangerous_starts {
-        if sql_no_comment.starts_with(kw) {
-            // 确认是完整关键词（后面是空格、括号或行尾）
-            let rest = &sql_no_comment[kw.len()..];
-            if rest.is_empty() || rest.starts_with(|c: char| c.is_whitespace() || c == '(' || c == ';') {
-                return Err(AppError::InvalidInput(format!("不允许执行 {} 操作，仅支持只读查询", kw)));
-            }
-        }
-    }
-
-    let result = state.pool_manager.execute_query(&id, &body.sql, body.limit).await?;
-    Ok(Json(ApiResponse::ok_with_service(result, "connection-service")))
+    headers: HeaderMap,
+    Json(req): Json<CreateConnectionRequest>,
+) -> Result<Json<ApiResponse<ConnectionTestResult>>, AppError> {
+    req.validate()?;
+    let service = connection_service(&state, &headers);
+    let stages = service.test_unsaved(req).await?;
+    Ok(Json(ApiResponse::ok_with_service(
+        ConnectionTestResult::from_stages(String::new(), stages),
+        "connection-service",
+    )))
 }
 
-/// 获取连接上的活跃进程
+/// 分阶段测试数据库连接（DNS、TCP、TLS、认证、查询），使用临时连接池，不影响已缓存的连接池
 #[utoipa::path(
     get,
-    path = "/api/connections/{id}/processes",
-    tag = "monitor",
+    path = "/api/connections/{id}/test",
+    tag = "connections",
     params(
         ("id" = String, Path, description = "连接 ID")
     ),
     responses(
-        (status = 200, description = "进程列表", body = ApiResponse<Vec<ProcessInfo>>),
+        (status = 200, description = "连接测试结果", body = ApiResponse<ConnectionTestResult>),
         (status = 404, description = "连接未找到")
     )
 )]
-pub async fn get_connection_processes(
+pub async fn test_connection(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Path(id): Path<String>,
-) -> Result<Json<ApiResponse<Vec<ProcessInfo>>>, AppError> {
-    let processes = state.pool_manager.get_processes(&id).await?;
-    Ok(Json(ApiResponse::ok_with_service(processes, "connection-service")))
+) -> Result<Json<ApiResponse<ConnectionTestResult>>, AppError> {
+    let service = connection_service(&state, &headers);
+    let stages = service.test(&id, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(
+        ConnectionTestResult::from_stages(id, stages),
+        "connection-service",
+    )))
+}
+
+/// 健康检查端点
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "服务运行正常", body = HealthResponse)
+    )
+)]
+pub async fn health_check(
+    State(state): State<AppState>,
+) -> Json<HealthResponse> {
+    // 元数据库不可用时连接列表为空，不能报告为健康
+    let status = match state.pool_manager.ping_metadata().await {
+        Ok(()) => "healthy",
+        Err(_) => "degraded",
+    };
+    Json(HealthResponse {
+        status: status.to_string(),
+        service: "connection-service".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        timestamp: Utc::now(),
+        connections: state.pool_manager.connection_count().await,
+    })
+}
+
+/// 就绪检查端点：固定连接预热完成（含失败）前返回 503，响应体带预热进度
+#[utoipa::path(
+    get,
+    path = "/api/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "预热完成", body = ApiResponse<WarmupStatus>),
+        (status = 503, description = "固定连接仍在预热", body = ApiResponse<WarmupStatus>)
+    )
+)]
+pub async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<ApiResponse<WarmupStatus>>) {
+    let status = state.warmup.status().await;
+    let code = if status.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (code, Json(ApiResponse::ok_with_service(status, "connection-service")))
 }
 
+/// 存活探针：进程能响应请求即返回 200
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, description = "服务存活", body = Liveness)
+    )
+)]
+pub async fn healthz() -> Json<Liveness> {
+    Json(Liveness::new("connection-service"))
+}
+
+/// 就绪探针：元数据库可用且固定连接预热完成时返回 200，否则返回 503 与未通过的检查项
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "服务就绪", body = Readiness),
+        (status = 503, description = "元数据库不可用或固定连接仍在预热", body = Readiness)
+    )
+)]
+pub async fn readyz(State(state): State<AppState>) -> Readiness {
+    let metadata = ProbeCheck::run("metadata_db", async {
+        state.pool_manager.ping_metadata().await.map_err(|e| e.to_string())
+    })
+    .await;
+    let warmup = ProbeCheck::run("warmup", async {
+        let status = state.warmup.status().await;
+        if status.ready {
+            Ok(())
+        } else {
+            Err(format!("{} of {} pinned connections warmed", status.completed, status.total))
+        }
+    })
+    .await;
+    Readiness::new("connection-service", vec![metadata, warmup])
+}
+
+/// 内部端点，供其他服务获取连接池信息
+#[utoipa::path(
+    get,
+    path = "/internal/pools/{id}",
+    tag = "internal",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "连接池信息", body = ApiResponse<PoolInfo>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_pool_info(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<PoolInfo>>, AppError> {
+    Ok(Json(ApiResponse::ok(pool_info(&state, &id, viewer(&headers)).await?)))
+}
+
+/// 连接配置与健康状态，供内部 HTTP 与 gRPC 接口使用
+///
+/// 只允许 query-service 调用；按转发的 `X-Principal` 检查连接归属，调用方看不到的连接视为不存在，
+/// query-service 据此在使用缓存的连接信息与查询结果前确认调用方有权访问该连接。
+pub(crate) async fn pool_info(state: &AppState, id: &str, viewer: Viewer<'_>) -> Result<PoolInfo, AppError> {
+    let conn = connection_config(state, id, viewer).await?;
+    let health = state.health.latest(id).await;
+    let pool_status = state.pool_manager.pool_status(id).await;
+
+    Ok(PoolInfo {
+        namespace: conn.default_namespace().map(str::to_string),
+        query_timeout_ms: state.pool_manager.query_timeout(&conn, None).as_millis() as u64,
+        id: conn.id,
+        db_type: conn.db_type.to_string(),
+        host: conn.host,
+        port: conn.port,
+        database: conn.database,
+        allowlist: conn.allowlist,
+        masking: conn.masking,
+        statement_policy: conn.statement_policy,
+        health,
+        pool_status,
+    })
+}
+
+/// 代 `principal` 读取连接信息，供 gRPC 接口使用
+pub(crate) async fn pool_info_on_behalf(state: &AppState, principal: Option<&str>, id: &str) -> Result<PoolInfo, AppError> {
+    pool_info(state, id, on_behalf(principal)).await
+}
+
+/// 列出本服务持有的连接池及其使用情况与自愈状态，需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/pools",
+    tag = "admin",
+    responses(
+        (status = 200, description = "连接池列表", body = ApiResponse<Vec<CachedPool>>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn list_pools(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<CachedPool>>>, AppError> {
+    admin::authorize(&headers)?;
+    let pools = state.pool_manager.list_pools().await?;
+    Ok(Json(ApiResponse::ok_with_service(pools, "connection-service")))
+}
+
+/// 丢弃连接的连接池并立即重建，用于网络故障后连接池状态异常的情况，需要 X-Admin-Token
+#[utoipa::path(
+    delete,
+    path = "/api/admin/pools/{id}",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "重建后的连接池状态", body = ApiResponse<ConnectionPoolStats>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn reset_pool(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<ConnectionPoolStats>>, AppError> {
+    admin::authorize(&headers)?;
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let stats = state.pool_manager.reset_pool(&id).await;
+    // 旧连接池已丢弃，重建失败时同样作废 query-service 缓存的连接状态
+    publish_updated(&state.events, &id, "pool");
+    Ok(Json(ApiResponse::ok_with_service(stats?, "connection-service")))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub status: String,
+    pub service: String,
+    pub version: String,
+    pub timestamp: DateTime<Utc>,
+    pub connections: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PoolInfo {
+    pub id: String,
+    pub db_type: String,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub database: Option<String>,
+    /// 未限定名称的表所属的库 / schema
+    pub namespace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowlist: Option<ConnectionAllowlist>,
+    /// 查询结果脱敏规则
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub masking: Option<ConnectionMasking>,
+    /// 允许执行的语句类型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement_policy: Option<StatementPolicy>,
+    /// 默认查询超时（毫秒，连接未设置时为服务默认值）
+    pub query_timeout_ms: u64,
+    /// 最近一次健康检查结果（尚未检查时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<TargetHealth>,
+    /// 连接池自愈状态（连接池尚未打开时为空）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_status: Option<PoolStatus>,
+}
+
+/// 发布 `connection.updated` 事件，`field` 为被修改的设置
+fn publish_updated(events: &EventPublisher, id: &str, field: &str) {
+    events.publish(
+        kinds::CONNECTION_UPDATED,
+        serde_json::json!({ "connection_id": id, "field": field }),
+    );
+}
+
+/// 路径中连接下的任务或备份须属于该连接，否则视为不存在
+fn belongs_to(connection_id: &str, id: &str, what: impl FnOnce() -> String) -> Result<(), AppError> {
+    if connection_id == id {
+        Ok(())
+    } else {
+        Err(AppError::NotFound(what()))
+    }
+}
+
+/// 获取调用方可以使用的连接配置，不存在或不可用时返回 ConnectionNotFound
+///
+/// 除可见的连接外，管理员限定到该连接的 API Key（含访客链接）也可以使用它
+async fn connection_config(state: &AppState, id: &str, viewer: Viewer<'_>) -> Result<ConnectionConfig, AppError> {
+    match state.pool_manager.get_connection(id).await {
+        Some(config) if viewer.can_see(&config) || state.api_keys.grants(viewer.principal, id).await => Ok(config),
+        _ => Err(AppError::ConnectionNotFound(id.to_string())),
+    }
+}
+
+/// 获取连接的监控概览
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/stats",
+    tag = "monitor",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "监控数据", body = ApiResponse<MonitorOverview>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_connection_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<MonitorOverview>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let overview = state.pool_manager.get_monitor_overview(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(overview, "connection-service")))
+}
+
+/// 立即检查目标库健康状况（连接数占用、复制状态），并更新缓存的检查结果
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/health",
+    tag = "monitor",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "健康检查结果", body = ApiResponse<TargetHealth>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_connection_health(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<TargetHealth>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let health = state.health.check(&id).await;
+    Ok(Json(ApiResponse::ok_with_service(health, "connection-service")))
+}
+
+/// 获取连接上的数据库列表
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/databases",
+    tag = "monitor",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "数据库列表", body = ApiResponse<Vec<DatabaseInfo>>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_connection_databases(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<DatabaseInfo>>>, AppError> {
+    let config = connection_config(&state, &id, viewer(&headers)).await?;
+    let mut databases = state.pool_manager.get_databases(&id).await?;
+    // PostgreSQL 白名单约束的是 schema，库列表不做过滤
+    if let (Some(allowlist), false) = (&config.allowlist, config.db_type == DbType::Postgres) {
+        databases.retain(|d| allowlist.allows_database(&d.name));
+    }
+    Ok(Json(ApiResponse::ok_with_service(databases, "connection-service")))
+}
+
+/// 表结构查询参数
+#[derive(Debug, serde::Deserialize)]
+pub struct SchemaQuery {
+    /// 跳过缓存并重新加载
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+/// 获取连接的数据库表结构（供 AI 服务使用，Redis 缓存）
+pub async fn get_connection_schema(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<SchemaQuery>,
+) -> Result<Json<ApiResponse<TableSchema>>, AppError> {
+    let config = connection_config(&state, &id, viewer(&headers)).await?;
+    let mut schema = state.schema_cache.get_table_schema(&id, query.refresh).await?;
+    // 缓存保存完整结构，白名单在读取时过滤，修改后立即生效
+    if let Some(allowlist) = &config.allowlist {
+        let namespace = config.default_namespace();
+        schema.tables.retain(|t| allowlist.allows_table(namespace, &t.name));
+    }
+    Ok(Json(ApiResponse::ok_with_service(schema, "connection-service")))
+}
+
+/// 使连接的表结构缓存及自动补全目录失效（可作为外部迁移工具的回调地址）
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/schema/invalidate",
+    tag = "schema",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "缓存已失效", body = ApiResponse<bool>)
+    )
+)]
+pub async fn invalidate_schema_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    state.autocomplete.invalidate(&id).await;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 自动补全目录查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct AutocompleteQuery {
+    /// 跳过缓存并重新加载（同时刷新表结构缓存）
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+/// 获取 SQL 编辑器自动补全目录：库 / schema、默认库的表与列、方言关键字与内置函数（内存缓存，按 TTL 过期）
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/autocomplete",
+    tag = "schema",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        AutocompleteQuery
+    ),
+    responses(
+        (status = 200, description = "自动补全目录", body = ApiResponse<AutocompleteCatalog>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_autocomplete(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<AutocompleteQuery>,
+) -> Result<Json<ApiResponse<AutocompleteCatalog>>, AppError> {
+    let config = connection_config(&state, &id, viewer(&headers)).await?;
+    let mut catalog = state.autocomplete.get(&id, query.refresh).await?;
+    // 缓存保存完整目录，白名单在读取时过滤
+    if let Some(allowlist) = &config.allowlist {
+        let namespace = config.default_namespace();
+        catalog.schemas.retain(|s| allowlist.allows_database(s));
+        catalog.tables.retain(|t| allowlist.allows_table(namespace, &t.name));
+    }
+    Ok(Json(ApiResponse::ok_with_service(catalog, "connection-service")))
+}
+
+/// 使连接的自动补全目录失效，下次读取时重新加载表结构
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/autocomplete/invalidate",
+    tag = "schema",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "目录已失效", body = ApiResponse<bool>)
+    )
+)]
+pub async fn invalidate_autocomplete(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    state.autocomplete.invalidate(&id).await;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 外键关系图查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct SchemaGraphQuery {
+    /// MySQL 库名 / PostgreSQL schema（默认使用连接的默认库 / public）
+    pub database: Option<String>,
+}
+
+/// 获取外键关系图：节点为表（含列），边为外键（含列映射与 ON DELETE / ON UPDATE 规则），用于绘制 ER 图
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/schema/graph",
+    tag = "schema",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        SchemaGraphQuery
+    ),
+    responses(
+        (status = 200, description = "外键关系图", body = ApiResponse<SchemaGraph>),
+        (status = 400, description = "数据库类型不支持"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_schema_graph(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<SchemaGraphQuery>,
+) -> Result<Json<ApiResponse<SchemaGraph>>, AppError> {
+    let config = connection_config(&state, &id, viewer(&headers)).await?;
+    let mut graph = schema_graph::load_graph(&state.pool_manager, &id, query.database.as_deref()).await?;
+    // 白名单外的表不出现在图中，指向它们的外键一并去掉
+    if let Some(allowlist) = &config.allowlist {
+        let database = graph.database.clone();
+        graph.nodes.retain(|n| allowlist.allows_table(Some(&database), &n.table));
+        graph.edges.retain(|e| {
+            allowlist.allows_table(Some(&database), &e.from_table)
+                && allowlist.allows_table(Some(e.to_database.as_deref().unwrap_or(&database)), &e.to_table)
+        });
+    }
+    Ok(Json(ApiResponse::ok_with_service(graph, "connection-service")))
+}
+
+/// 获取 Neo4j 图数据库的节点标签及各标签的节点数
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/graph/labels",
+    tag = "schema",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "节点标签列表", body = ApiResponse<Vec<GraphElementType>>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_graph_labels(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<GraphElementType>>>, AppError> {
+    let config = connection_config(&state, &id, viewer(&headers)).await?;
+    let mut labels = state.pool_manager.graph_labels(&id).await?;
+    // 白名单的表条目按标签名匹配
+    if let Some(allowlist) = &config.allowlist {
+        labels.retain(|l| allowlist.allows_table(config.default_namespace(), &l.name));
+    }
+    Ok(Json(ApiResponse::ok_with_service(labels, "connection-service")))
+}
+
+/// 获取 Neo4j 图数据库的关系类型及各类型的关系数
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/graph/relationship-types",
+    tag = "schema",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "关系类型列表", body = ApiResponse<Vec<GraphElementType>>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_graph_relationship_types(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<GraphElementType>>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let types = state.pool_manager.graph_relationship_types(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(types, "connection-service")))
+}
+
+/// 读取 Memcached 连接上的单个键，非 UTF-8 的值以 base64 返回
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/keys/{key}",
+    tag = "keys",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("key" = String, Path, description = "键")
+    ),
+    responses(
+        (status = 200, description = "键值", body = ApiResponse<KeyValueEntry>),
+        (status = 404, description = "连接或键不存在")
+    )
+)]
+pub async fn get_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, key)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<KeyValueEntry>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let entry = state.pool_manager.get_key(&id, &key).await?;
+    Ok(Json(ApiResponse::ok_with_service(entry, "connection-service")))
+}
+
+/// 写入 Memcached 连接上的单个键（覆盖已有值）
+#[utoipa::path(
+    put,
+    path = "/api/connections/{id}/keys/{key}",
+    tag = "keys",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("key" = String, Path, description = "键")
+    ),
+    request_body = SetKeyValueRequest,
+    responses(
+        (status = 200, description = "写入后的键值", body = ApiResponse<KeyValueEntry>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn set_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, key)): Path<(String, String)>,
+    Json(req): Json<SetKeyValueRequest>,
+) -> Result<Json<ApiResponse<KeyValueEntry>>, AppError> {
+    req.validate()?;
+    connection_config(&state, &id, viewer(&headers)).await?;
+    state.pool_manager.set_key(&id, &key, &req).await?;
+    let entry = state.pool_manager.get_key(&id, &key).await?;
+    Ok(Json(ApiResponse::ok_with_service(entry, "connection-service")))
+}
+
+/// 删除 Memcached 连接上的单个键
+#[utoipa::path(
+    delete,
+    path = "/api/connections/{id}/keys/{key}",
+    tag = "keys",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("key" = String, Path, description = "键")
+    ),
+    responses(
+        (status = 200, description = "键已删除", body = ApiResponse<bool>),
+        (status = 404, description = "连接或键不存在")
+    )
+)]
+pub async fn delete_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, key)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    state.pool_manager.delete_key(&id, &key).await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 表属性查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct TableStatsQuery {
+    /// MySQL 库名 / PostgreSQL schema（默认使用连接的默认库 / public）
+    pub database: Option<String>,
+}
+
+/// 获取表属性：估算行数、数据与索引大小、自增值、最后更新时间及各索引基数（仅 MySQL / PostgreSQL）
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/tables/{table}/stats",
+    tag = "schema",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("table" = String, Path, description = "表名"),
+        TableStatsQuery
+    ),
+    responses(
+        (status = 200, description = "表属性", body = ApiResponse<TableStats>),
+        (status = 400, description = "数据库类型不支持"),
+        (status = 403, description = "表不在连接白名单内"),
+        (status = 404, description = "连接或表未找到")
+    )
+)]
+pub async fn get_table_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, table)): Path<(String, String)>,
+    Query(query): Query<TableStatsQuery>,
+) -> Result<Json<ApiResponse<TableStats>>, AppError> {
+    let config = connection_config(&state, &id, viewer(&headers)).await?;
+    if let Some(allowlist) = &config.allowlist {
+        let namespace = query.database.as_deref().or(config.default_namespace());
+        if !allowlist.allows_table(namespace, &table) {
+            return Err(AppError::Forbidden(format!(
+                "table {} is not in the connection allowlist",
+                table
+            )));
+        }
+    }
+    let stats = table_stats::table_stats(&state.pool_manager, &id, query.database.as_deref(), &table).await?;
+    Ok(Json(ApiResponse::ok_with_service(stats, "connection-service")))
+}
+
+/// 为表生成模拟数据：按列类型与列名生成仿真行并分批插入，`dry_run` 时只返回预览（仅 MySQL / PostgreSQL）
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/tables/{table}/seed",
+    tag = "schema",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("table" = String, Path, description = "表名")
+    ),
+    request_body = SeedRequest,
+    responses(
+        (status = 200, description = "生成结果与预览", body = ApiResponse<SeedResult>),
+        (status = 400, description = "参数无效、数据库类型不支持或存在无法生成的列"),
+        (status = 403, description = "表或其引用的表不在连接白名单内"),
+        (status = 404, description = "连接或表未找到")
+    )
+)]
+pub async fn seed_table(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, table)): Path<(String, String)>,
+    Json(req): Json<SeedRequest>,
+) -> Result<Json<ApiResponse<SeedResult>>, AppError> {
+    req.validate()?;
+    let config = connection_config(&state, &id, viewer(&headers)).await?;
+    if let Some(allowlist) = &config.allowlist {
+        let namespace = req.database.as_deref().or(config.default_namespace());
+        if !allowlist.allows_table(namespace, &table) {
+            return Err(AppError::Forbidden(format!(
+                "table {} is not in the connection allowlist",
+                table
+            )));
+        }
+    }
+    let result = seed::seed(&state.pool_manager, &id, &table, &req).await?;
+    Ok(Json(ApiResponse::ok_with_service(result, "connection-service")))
+}
+
+/// 执行 SQL 查询
+#[derive(serde::Deserialize)]
+pub struct ExecuteQueryBody {
+    pub sql: String,
+    /// 在连接所在服务器上的哪个库中执行（缺省为连接的库）
+    #[serde(default)]
+    pub database: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+    /// 位置参数
+    #[serde(default)]
+    pub params: Vec<serde_json::Value>,
+    /// 命名参数（`:name` 占位符）
+    #[serde(default)]
+    pub named_params: BTreeMap<String, serde_json::Value>,
+    /// 查询超时（毫秒），缺省使用连接的默认超时
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// 查询语言，`cypher` 仅用于 Neo4j 连接
+    #[serde(default)]
+    pub query_language: QueryLanguage,
+}
+
+pub(crate) fn default_limit() -> u32 {
+    1000
+}
+
+pub async fn execute_query(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(body): Json<ExecuteQueryBody>,
+) -> Result<Json<ApiResponse<QueryResult>>, AppError> {
+    state.usage.check(principal(&headers)).await?;
+    let (config, mut result) = run_read_query(&state, &id, &body, viewer(&headers)).await?;
+    state.usage.record(principal(&headers), &result).await;
+    state.favorites.record_use(principal(&headers), &id).await;
+    mask_result(&config, &headers, &mut result);
+    Ok(Json(ApiResponse::ok_with_service(result, "connection-service")))
+}
+
+/// 内部端点：代其他服务在已打开的连接池上执行只读查询
+///
+/// 连接凭据只保存在本服务中，调用方只需提供连接 ID，规则与
+/// `/api/connections/{id}/query` 相同。调用方转发的 `X-Principal` 计入该主体的用量。
+pub async fn execute_on_behalf(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(body): Json<ExecuteQueryBody>,
+) -> Result<Json<ApiResponse<QueryResult>>, AppError> {
+    let result = query_on_behalf(&state, principal(&headers), &id, &body).await?;
+    Ok(Json(ApiResponse::ok_with_service(result, "connection-service")))
+}
+
+/// 代 `principal` 执行只读查询并计入其用量，供内部 HTTP 与 gRPC 接口使用
+pub(crate) async fn query_on_behalf(
+    state: &AppState,
+    principal: Option<&str>,
+    id: &str,
+    body: &ExecuteQueryBody,
+) -> Result<QueryResult, AppError> {
+    state.usage.check(principal).await?;
+    let (_, result) = run_read_query(state, id, body, on_behalf(principal)).await?;
+    state.usage.record(principal, &result).await;
+    state.favorites.record_use(principal, id).await;
+    Ok(result)
+}
+
+/// 校验并执行只读查询：拒绝写操作，按库表白名单检查，绑定参数后在连接池上执行
+///
+/// 返回执行所用的连接配置与结果；结果尚未脱敏。
+async fn run_read_query(
+    state: &AppState,
+    id: &str,
+    body: &ExecuteQueryBody,
+    viewer: Viewer<'_>,
+) -> Result<(ConnectionConfig, QueryResult), AppError> {
+    if body.query_language == QueryLanguage::Cypher {
+        return run_cypher_query(state, id, body, viewer).await;
+    }
+
+    let config = query_config(connection_config(state, id, viewer).await?, body.database.as_deref())?;
+    if is_sql(&config.db_type) {
+        // 按连接的 SQL 方言跳过字符串与注释后分类，注释开头的写语句与含写操作的 CTE 同样拒绝
+        SqlValidator::validate_read_only(&body.sql, Dialect::of(&config.db_type))?;
+    } else {
+        reject_write_prefix(&body.sql)?;
+    }
+    if let Some(policy) = &config.statement_policy {
+        policy.check_sql(&body.sql)?;
+    }
+    if let Some(allowlist) = &config.allowlist {
+        if config.db_type == DbType::Elasticsearch {
+            check_es_indices(allowlist, &body.sql, config.database.as_deref())?;
+        } else if config.db_type == DbType::InfluxDB {
+            check_influx_buckets(allowlist, &body.sql, config.database.as_deref())?;
+        } else {
+            allowlist.check_sql(&body.sql, config.default_namespace())?;
+        }
+    }
+
+    let (sql, params) = bind_body_params(&config, body)?;
+    if body.timeout_ms == Some(0) {
+        return Err(AppError::InvalidInput("timeout_ms 必须大于 0".to_string()));
+    }
+    let timeout = state.pool_manager.query_timeout(&config, body.timeout_ms);
+    let result = state
+        .pool_manager
+        .execute_query(id, body.database.as_deref(), &sql, body.limit, &params, Some(timeout))
+        .await?;
+    Ok((config, result))
+}
+
+/// 以 SQL 查询的连接类型
+fn is_sql(db_type: &DbType) -> bool {
+    matches!(
+        db_type,
+        DbType::MySQL
+            | DbType::MariaDB
+            | DbType::Postgres
+            | DbType::SQLite
+            | DbType::ClickHouse
+            | DbType::Oracle
+            | DbType::SqlServer
+            | DbType::DB2
+            | DbType::Cassandra
+    )
+}
+
+/// 非 SQL 连接（Elasticsearch、InfluxDB 等）的基础检查：拒绝以写操作关键词开头的语句
+fn reject_write_prefix(query: &str) -> Result<(), AppError> {
+    let upper = query.trim_start().to_uppercase();
+    let dangerous_starts = ["INSERT", "UPDATE", "DELETE", "DROP", "TRUNCATE", "ALTER", "CREATE", "RENAME"];
+    for kw in dangerous_starts {
+        if let Some(rest) = upper.strip_prefix(kw) {
+            // 确认是完整关键词（后面是空格、括号或行尾）
+            if rest.is_empty() || rest.starts_with(|c: char| c.is_whitespace() || c == '(' || c == ';') {
+                return Err(AppError::InvalidInput(format!("不允许执行 {} 操作，仅支持只读查询", kw)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 校验并执行只读 Cypher 查询：命名参数作为 `$name` 参数传给驱动，在始终回滚的事务中执行
+///
+/// 库表白名单只能限定 Neo4j 的库；白名单配置了表时无法按标签校验，拒绝执行。
+async fn run_cypher_query(
+    state: &AppState,
+    id: &str,
+    body: &ExecuteQueryBody,
+    viewer: Viewer<'_>,
+) -> Result<(ConnectionConfig, QueryResult), AppError> {
+    CypherAnalyzer::validate_read_only(&body.sql)?;
+    let config = query_config(connection_config(state, id, viewer).await?, body.database.as_deref())?;
+    if config.db_type != DbType::Neo4j {
+        return Err(AppError::InvalidInput("Cypher 查询仅支持 Neo4j 连接".to_string()));
+    }
+    if !body.params.is_empty() {
+        return Err(AppError::InvalidInput("Cypher 查询不支持位置参数，请使用 named_params".to_string()));
+    }
+    if let Some(policy) = &config.statement_policy {
+        policy.check(StatementType::Select)?;
+    }
+    if let Some(allowlist) = &config.allowlist {
+        if !allowlist.tables.is_empty() {
+            return Err(AppError::Forbidden("connection allowlist restricts tables, Cypher queries are not allowed".to_string()));
+        }
+        if let Some(database) = config.database.as_deref().filter(|d| !allowlist.allows_database(d)) {
+            return Err(AppError::Forbidden(format!("database {} is not in the connection allowlist", database)));
+        }
+    }
+
+    if body.timeout_ms == Some(0) {
+        return Err(AppError::InvalidInput("timeout_ms 必须大于 0".to_string()));
+    }
+    let timeout = state.pool_manager.query_timeout(&config, body.timeout_ms);
+    let result = state
+        .pool_manager
+        .execute_cypher(id, body.database.as_deref(), &body.sql, body.limit, &body.named_params, timeout)
+        .await?;
+    Ok((config, result))
+}
+
+/// Elasticsearch 的库表白名单按索引检查：Query DSL 检查目标索引，SQL 检查 FROM 中的索引
+fn check_es_indices(allowlist: &ConnectionAllowlist, query: &str, index: Option<&str>) -> Result<(), AppError> {
+    let indices: Vec<String> = if query.trim_start().starts_with('{') {
+        vec![index.unwrap_or("_all").to_string()]
+    } else {
+        SqlTableExtractor::extract(query).into_iter().map(|t| t.table).collect()
+    };
+    match indices.iter().find(|i| !allowlist.allows_database(i)) {
+        Some(index) => Err(AppError::Forbidden(format!("index {} is not in the connection allowlist", index))),
+        None => Ok(()),
+    }
+}
+
+/// InfluxDB 的库表白名单按 bucket 检查：Flux 检查 `from(bucket: ...)`，InfluxQL 检查查询的 bucket
+fn check_influx_buckets(allowlist: &ConnectionAllowlist, query: &str, bucket: Option<&str>) -> Result<(), AppError> {
+    let buckets = if influxdb::is_flux(query) {
+        influxdb::flux_buckets(query)
+    } else {
+        bucket.map(str::to_string).into_iter().collect()
+    };
+    match buckets.iter().find(|b| !allowlist.allows_database(b)) {
+        Some(bucket) => Err(AppError::Forbidden(format!("bucket {} is not in the connection allowlist", bucket))),
+        None => Ok(()),
+    }
+}
+
+/// 按连接的脱敏规则处理直接返回给用户的结果（豁免主体除外）
+fn mask_result(config: &ConnectionConfig, headers: &HeaderMap, result: &mut QueryResult) {
+    if let Some(masking) = config.masking.as_ref().filter(|m| m.applies_to(principal(headers))) {
+        masking.apply(result);
+    }
+}
+
+/// 请求指定 `database` 时，返回登录该库的连接配置（MySQL 未限定名称的表随之属于该库）
+///
+/// MySQL 的库（Elasticsearch 的索引、InfluxDB 的 bucket、Neo4j 的库）须在库表白名单内；PostgreSQL 白名单限定的是 schema，切换库后仍按 schema 检查。
+fn query_config(config: ConnectionConfig, database: Option<&str>) -> Result<ConnectionConfig, AppError> {
+    let Some(database) = database else {
+        return Ok(config);
+    };
+    if (config.db_type.is_mysql_family() || matches!(config.db_type, DbType::Elasticsearch | DbType::InfluxDB | DbType::Neo4j))
+        && config.allowlist.as_ref().is_some_and(|a| !a.allows_database(database))
+    {
+        return Err(AppError::Forbidden(format!(
+            "database {} is not in the connection allowlist",
+            database
+        )));
+    }
+    Ok(config.with_database(database))
+}
+
+/// 命名参数改写为驱动的位置占位符
+fn bind_body_params(
+    config: &ConnectionConfig,
+    body: &ExecuteQueryBody,
+) -> Result<(String, Vec<serde_json::Value>), AppError> {
+    if body.named_params.is_empty() {
+        return Ok((body.sql.clone(), body.params.clone()));
+    }
+    if !body.params.is_empty() {
+        return Err(AppError::InvalidInput("params 与 named_params 不能同时使用".to_string()));
+    }
+    let style = match config.db_type {
+        DbType::Postgres => PlaceholderStyle::Dollar,
+        DbType::Oracle => PlaceholderStyle::Colon,
+        _ => PlaceholderStyle::Question,
+    };
+    SqlParams::bind_named(&body.sql, &body.named_params, style)
+}
+
+/// 内部端点：执行查询服务已确认的单条 UPDATE/DELETE 或 DDL，返回影响行数
+///
+/// DDL 执行成功后清除连接的表结构与自动补全缓存。
+pub async fn execute_change(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(body): Json<ExecuteQueryBody>,
+) -> Result<Json<ApiResponse<QueryResult>>, AppError> {
+    let result = change_on_behalf(&state, principal(&headers), &id, &body).await?;
+    Ok(Json(ApiResponse::ok_with_service(result, "connection-service")))
+}
+
+/// 代 `principal` 执行已确认的变更并计入其用量，供内部 HTTP 与 gRPC 接口使用
+pub(crate) async fn change_on_behalf(
+    state: &AppState,
+    principal: Option<&str>,
+    id: &str,
+    body: &ExecuteQueryBody,
+) -> Result<QueryResult, AppError> {
+    let config = query_config(connection_config(state, id, on_behalf(principal)).await?, body.database.as_deref())?;
+    let ddl = SqlValidator::is_ddl(&body.sql);
+    if !ddl {
+        if ChangePreviewSql::to_select(&body.sql, Dialect::of(&config.db_type)).is_none() {
+            return Err(AppError::InvalidInput("仅支持执行单条 UPDATE / DELETE 语句".to_string()));
+        }
+        SqlValidator::validate_change(&body.sql)?;
+    }
+    if ddl && SqlSplitter::split(&body.sql, &config.db_type).len() != 1 {
+        return Err(AppError::InvalidInput("仅支持执行单条 DDL 语句".to_string()));
+    }
+    if let Some(policy) = &config.statement_policy {
+        policy.check_sql(&body.sql)?;
+    }
+    if let Some(allowlist) = &config.allowlist {
+        allowlist.check_sql(&body.sql, config.default_namespace())?;
+    }
+
+    let (sql, params) = bind_body_params(&config, body)?;
+    if body.timeout_ms == Some(0) {
+        return Err(AppError::InvalidInput("timeout_ms 必须大于 0".to_string()));
+    }
+    let timeout = state.pool_manager.query_timeout(&config, body.timeout_ms);
+    state.usage.check(principal).await?;
+    let result = state
+        .pool_manager
+        .execute_change(id, body.database.as_deref(), &sql, &params, timeout)
+        .await?;
+    state.usage.record(principal, &result).await;
+    state.favorites.record_use(principal, id).await;
+    if ddl {
+        state.autocomplete.invalidate(id).await;
+    }
+    Ok(result)
+}
+
+/// 随机抽样表数据：PostgreSQL 使用 TABLESAMPLE，MySQL 小表 ORDER BY RAND()、大表随机过滤并限制执行时间
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/sample",
+    tag = "connections",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    request_body = SampleRequest,
+    responses(
+        (status = 200, description = "抽样结果", body = ApiResponse<SampleResult>),
+        (status = 400, description = "参数无效或数据库类型不支持"),
+        (status = 404, description = "连接或表未找到")
+    )
+)]
+pub async fn sample_table(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<SampleRequest>,
+) -> Result<Json<ApiResponse<SampleResult>>, AppError> {
+    req.validate()?;
+    let config = connection_config(&state, &id, viewer(&headers)).await?;
+    if let Some(allowlist) = &config.allowlist {
+        let namespace = req.database.as_deref().or(config.default_namespace());
+        if !allowlist.allows_table(namespace, &req.table) {
+            return Err(AppError::Forbidden(format!(
+                "table {} is not in the connection allowlist",
+                req.table
+            )));
+        }
+    }
+    let mut sample = sampling::sample_table(&state.pool_manager, &id, &req).await?;
+    mask_result(&config, &headers, &mut sample.result);
+    Ok(Json(ApiResponse::ok_with_service(sample, "connection-service")))
+}
+
+/// 负载统计查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct WorkloadQuery {
+    /// 统计最近多少小时（默认 24，最大 720）
+    #[serde(default = "default_workload_hours")]
+    pub hours: u32,
+    /// 时间线粒度：hour（默认）或 day
+    #[serde(default)]
+    pub granularity: Option<String>,
+}
+
+fn default_workload_hours() -> u32 {
+    24
+}
+
+/// 获取连接的负载构成：按语句类型、表及时间段统计经本服务执行的语句
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/workload",
+    tag = "monitor",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        WorkloadQuery
+    ),
+    responses(
+        (status = 200, description = "负载统计", body = ApiResponse<WorkloadBreakdown>),
+        (status = 400, description = "参数无效"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_connection_workload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Query(query): Query<WorkloadQuery>,
+) -> Result<Json<ApiResponse<WorkloadBreakdown>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    if query.hours == 0 || query.hours > 720 {
+        return Err(AppError::InvalidInput("hours 取值范围为 1-720".to_string()));
+    }
+    let daily = match query.granularity.as_deref() {
+        None | Some("hour") => false,
+        Some("day") => true,
+        Some(other) => {
+            return Err(AppError::InvalidInput(format!("不支持的粒度: {}", other)));
+        }
+    };
+    let breakdown = state
+        .pool_manager
+        .workload()
+        .breakdown(&id, query.hours, daily)
+        .await?;
+    Ok(Json(ApiResponse::ok_with_service(breakdown, "connection-service")))
+}
+
+/// 获取连接上的活跃进程
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/processes",
+    tag = "monitor",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "进程列表", body = ApiResponse<Vec<ProcessInfo>>),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn get_connection_processes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ProcessInfo>>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let processes = state.pool_manager.get_processes(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(processes, "connection-service")))
+}
+
+
+/// 列出连接上的表结构变更任务
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/schema-changes",
+    tag = "schema-changes",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "变更任务列表", body = ApiResponse<Vec<SchemaChangeJob>>)
+    )
+)]
+pub async fn list_schema_changes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<SchemaChangeJob>>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let jobs = state.schema_changes.list(&id).await;
+    Ok(Json(ApiResponse::ok_with_service(jobs, "connection-service")))
+}
+
+/// 发起表结构变更（ALTER TABLE），支持直接执行或在线变更（影子表 + 触发器 + 分块复制）
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/schema-changes",
+    tag = "schema-changes",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    request_body = SchemaChangeRequest,
+    responses(
+        (status = 200, description = "变更任务已创建", body = ApiResponse<SchemaChangeJob>),
+        (status = 400, description = "参数无效或表不满足在线变更条件"),
+        (status = 404, description = "连接未找到"),
+        (status = 409, description = "该表已有进行中的变更任务")
+    )
+)]
+pub async fn start_schema_change(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<SchemaChangeRequest>,
+) -> Result<Json<ApiResponse<SchemaChangeJob>>, AppError> {
+    req.validate()?;
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let job = state.schema_changes.start(&id, req).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 查询表结构变更任务进度
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/schema-changes/{job_id}",
+    tag = "schema-changes",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("job_id" = String, Path, description = "变更任务 ID")
+    ),
+    responses(
+        (status = 200, description = "变更任务详情", body = ApiResponse<SchemaChangeJob>),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn get_schema_change(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, job_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<SchemaChangeJob>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let job = state.schema_changes.get(&job_id).await?;
+    belongs_to(&job.connection_id, &id, || format!("schema change job {}", job_id))?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 执行切换：用影子表原子替换原表
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/schema-changes/{job_id}/cutover",
+    tag = "schema-changes",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("job_id" = String, Path, description = "变更任务 ID")
+    ),
+    responses(
+        (status = 200, description = "切换完成", body = ApiResponse<SchemaChangeJob>),
+        (status = 404, description = "任务未找到"),
+        (status = 409, description = "任务尚未完成数据复制")
+    )
+)]
+pub async fn cutover_schema_change(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, job_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<SchemaChangeJob>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let job = state.schema_changes.get(&job_id).await?;
+    belongs_to(&job.connection_id, &id, || format!("schema change job {}", job_id))?;
+    let job = state.schema_changes.cutover(&job_id).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 取消表结构变更任务并清理影子表与触发器
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/schema-changes/{job_id}/cancel",
+    tag = "schema-changes",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("job_id" = String, Path, description = "变更任务 ID")
+    ),
+    responses(
+        (status = 200, description = "任务已取消", body = ApiResponse<SchemaChangeJob>),
+        (status = 404, description = "任务未找到"),
+        (status = 409, description = "任务已结束")
+    )
+)]
+pub async fn cancel_schema_change(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, job_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<SchemaChangeJob>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let job = state.schema_changes.get(&job_id).await?;
+    belongs_to(&job.connection_id, &id, || format!("schema change job {}", job_id))?;
+    let job = state.schema_changes.cancel(&job_id).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 调用方须能看到复制任务的源连接与目标连接
+async fn can_see_transfer(state: &AppState, job: &TransferJob, viewer: Viewer<'_>) -> bool {
+    connection_config(state, &job.source_connection_id, viewer).await.is_ok()
+        && connection_config(state, &job.target_connection_id, viewer).await.is_ok()
+}
+
+/// 获取调用方可见的复制任务，其他任务视为不存在
+async fn transfer(state: &AppState, id: &str, viewer: Viewer<'_>) -> Result<TransferJob, AppError> {
+    let job = state.transfers.get(id).await?;
+    if can_see_transfer(state, &job, viewer).await {
+        Ok(job)
+    } else {
+        Err(AppError::NotFound(format!("transfer job {}", id)))
+    }
+}
+
+/// 列出源连接与目标连接都对调用方可见的数据复制任务
+#[utoipa::path(
+    get,
+    path = "/api/transfers",
+    tag = "transfers",
+    responses(
+        (status = 200, description = "复制任务列表", body = ApiResponse<Vec<TransferJob>>)
+    )
+)]
+pub async fn list_transfers(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<TransferJob>>>, AppError> {
+    let viewer = viewer(&headers);
+    let mut jobs = Vec::new();
+    for job in state.transfers.list().await {
+        if can_see_transfer(&state, &job, viewer).await {
+            jobs.push(job);
+        }
+    }
+    Ok(Json(ApiResponse::ok_with_service(jobs, "connection-service")))
+}
+
+/// 发起跨连接数据复制：按过滤条件读取源表，分批写入目标连接上已存在的表
+///
+/// 两表按列名匹配，只复制共有的列；值按目标列类型转换，支持 MySQL、PostgreSQL、SQLite 互相复制。
+/// 源连接与目标连接都须对调用方可见；源连接的脱敏规则对调用方生效时，拒绝复制被脱敏的列。
+#[utoipa::path(
+    post,
+    path = "/api/transfers",
+    tag = "transfers",
+    request_body = TransferRequest,
+    responses(
+        (status = 200, description = "复制任务已创建", body = ApiResponse<TransferJob>),
+        (status = 400, description = "参数无效、数据库类型不支持或两表没有共有列"),
+        (status = 403, description = "表不在连接白名单内或复制的列被脱敏"),
+        (status = 404, description = "连接或表未找到"),
+        (status = 409, description = "目标表已有进行中的复制任务")
+    )
+)]
+pub async fn start_transfer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<TransferRequest>,
+) -> Result<Json<ApiResponse<TransferJob>>, AppError> {
+    req.validate()?;
+    let viewer = viewer(&headers);
+    let source = connection_config(&state, &req.source_connection_id, viewer).await?;
+    connection_config(&state, &req.target_connection_id, viewer).await?;
+    let masking = source.masking.as_ref().filter(|m| m.applies_to(viewer.principal));
+    let job = state.transfers.start(req, masking).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 查询数据复制任务进度
+#[utoipa::path(
+    get,
+    path = "/api/transfers/{id}",
+    tag = "transfers",
+    params(
+        ("id" = String, Path, description = "复制任务 ID")
+    ),
+    responses(
+        (status = 200, description = "复制任务详情", body = ApiResponse<TransferJob>),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn get_transfer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<TransferJob>>, AppError> {
+    let job = transfer(&state, &id, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 取消数据复制任务，当前批次写完后停止，已提交的批次保留
+#[utoipa::path(
+    post,
+    path = "/api/transfers/{id}/cancel",
+    tag = "transfers",
+    params(
+        ("id" = String, Path, description = "复制任务 ID")
+    ),
+    responses(
+        (status = 200, description = "已请求取消", body = ApiResponse<TransferJob>),
+        (status = 404, description = "任务未找到"),
+        (status = 409, description = "任务已结束")
+    )
+)]
+pub async fn cancel_transfer(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<TransferJob>>, AppError> {
+    transfer(&state, &id, viewer(&headers)).await?;
+    let job = state.transfers.cancel(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 快照列表查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct SnapshotListQuery {
+    /// 只列出该名称的快照
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 只列出该连接的快照
+    #[serde(default)]
+    pub connection_id: Option<String>,
+}
+
+/// 调用方须能看到快照所属的连接；管理员可见全部快照（含已删除连接的快照）
+async fn can_see_snapshot(state: &AppState, snapshot: &QuerySnapshot, viewer: Viewer<'_>) -> bool {
+    viewer.admin || connection_config(state, &snapshot.connection_id, viewer).await.is_ok()
+}
+
+/// 获取调用方可见的快照及其结果，其他快照视为不存在
+async fn visible_snapshot(state: &AppState, id: &str, viewer: Viewer<'_>) -> Result<QuerySnapshot, AppError> {
+    let snapshot = state.snapshots.get(id).await?;
+    if can_see_snapshot(state, &snapshot, viewer).await {
+        Ok(snapshot)
+    } else {
+        Err(AppError::NotFound(format!("Snapshot {}", id)))
+    }
+}
+
+/// 列出调用方可见连接上未过期的查询快照（不含结果，按创建时间倒序）；携带 `X-Workspace-Id` 时只返回该工作区中的快照
+#[utoipa::path(
+    get,
+    path = "/api/snapshots",
+    tag = "snapshots",
+    params(SnapshotListQuery),
+    responses(
+        (status = 200, description = "快照列表", body = ApiResponse<Vec<QuerySnapshot>>),
+        (status = 404, description = "工作区不存在或调用方不是其成员")
+    )
+)]
+pub async fn list_snapshots(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SnapshotListQuery>,
+) -> Result<Json<ApiResponse<Vec<QuerySnapshot>>>, AppError> {
+    let mut snapshots = Vec::new();
+    for snapshot in state
+        .snapshots
+        .list(query.name.as_deref(), query.connection_id.as_deref())
+        .await?
+    {
+        if can_see_snapshot(&state, &snapshot, viewer(&headers)).await {
+            snapshots.push(snapshot);
+        }
+    }
+    if let Some(workspace) = workspace(&headers) {
+        let ids = state
+            .workspaces
+            .resource_ids(workspace, WorkspaceResourceKind::Snapshot, viewer(&headers))
+            .await?;
+        snapshots.retain(|snapshot| ids.contains(&snapshot.id));
+    }
+    Ok(Json(ApiResponse::ok_with_service(snapshots, "connection-service")))
+}
+
+/// 保存查询快照：执行只读查询，把结果（按连接的脱敏规则处理后）以指定名称保存，到期自动删除
+#[utoipa::path(
+    post,
+    path = "/api/snapshots",
+    tag = "snapshots",
+    request_body = CreateSnapshotRequest,
+    responses(
+        (status = 200, description = "快照已保存（不含结果）", body = ApiResponse<QuerySnapshot>),
+        (status = 400, description = "参数无效或不是只读语句"),
+        (status = 403, description = "SQL 引用了白名单外的表"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn create_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateSnapshotRequest>,
+) -> Result<Json<ApiResponse<QuerySnapshot>>, AppError> {
+    req.validate()?;
+    let body = ExecuteQueryBody {
+        sql: req.sql.clone(),
+        database: req.database.clone(),
+        limit: req.limit.unwrap_or(snapshot::DEFAULT_ROW_LIMIT),
+        params: req.params.clone(),
+        named_params: req.named_params.clone(),
+        timeout_ms: None,
+        query_language: Default::default(),
+    };
+    let (config, mut result) = run_read_query(&state, &req.connection_id, &body, viewer(&headers)).await?;
+    mask_result(&config, &headers, &mut result);
+    let snapshot = state.snapshots.save(&req, result, principal(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(snapshot, "connection-service")))
+}
+
+/// 获取查询快照及其结果
+#[utoipa::path(
+    get,
+    path = "/api/snapshots/{id}",
+    tag = "snapshots",
+    params(
+        ("id" = String, Path, description = "快照 ID")
+    ),
+    responses(
+        (status = 200, description = "快照与结果", body = ApiResponse<QuerySnapshot>),
+        (status = 404, description = "快照不存在或已过期")
+    )
+)]
+pub async fn get_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<QuerySnapshot>>, AppError> {
+    let snapshot = visible_snapshot(&state, &id, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(snapshot, "connection-service")))
+}
+
+/// 删除查询快照
+#[utoipa::path(
+    delete,
+    path = "/api/snapshots/{id}",
+    tag = "snapshots",
+    params(
+        ("id" = String, Path, description = "快照 ID")
+    ),
+    responses(
+        (status = 200, description = "快照已删除", body = ApiResponse<bool>),
+        (status = 404, description = "快照不存在")
+    )
+)]
+pub async fn delete_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    visible_snapshot(&state, &id, viewer(&headers)).await?;
+    state.snapshots.delete(&id).await?;
+    state.workspaces.forget(WorkspaceResourceKind::Snapshot, &id).await;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 对比两个查询快照：按键列对齐行，返回新增、删除与变更的行
+#[utoipa::path(
+    post,
+    path = "/api/snapshots/compare",
+    tag = "snapshots",
+    request_body = CompareSnapshotsRequest,
+    responses(
+        (status = 200, description = "两个快照的行级差异", body = ApiResponse<QueryDiffResult>),
+        (status = 400, description = "键列缺失或不唯一"),
+        (status = 404, description = "快照不存在或已过期")
+    )
+)]
+pub async fn compare_snapshots(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CompareSnapshotsRequest>,
+) -> Result<Json<ApiResponse<QueryDiffResult>>, AppError> {
+    req.validate()?;
+    visible_snapshot(&state, &req.left_id, viewer(&headers)).await?;
+    visible_snapshot(&state, &req.right_id, viewer(&headers)).await?;
+    let diff = state.snapshots.compare(&req).await?;
+    Ok(Json(ApiResponse::ok_with_service(diff, "connection-service")))
+}
+
+/// 列出调用方所在的工作区（携带 X-Admin-Token 时列出全部），按名称排序
+#[utoipa::path(
+    get,
+    path = "/api/workspaces",
+    tag = "workspaces",
+    responses(
+        (status = 200, description = "工作区列表", body = ApiResponse<Vec<Workspace>>)
+    )
+)]
+pub async fn list_workspaces(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<Workspace>>>, AppError> {
+    let workspaces = state.workspaces.list(viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(workspaces, "connection-service")))
+}
+
+/// 创建工作区，调用方成为其所有者
+#[utoipa::path(
+    post,
+    path = "/api/workspaces",
+    tag = "workspaces",
+    request_body = CreateWorkspaceRequest,
+    responses(
+        (status = 200, description = "工作区已创建", body = ApiResponse<Workspace>),
+        (status = 401, description = "请求未携带主体")
+    )
+)]
+pub async fn create_workspace(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateWorkspaceRequest>,
+) -> Result<Json<ApiResponse<Workspace>>, AppError> {
+    req.validate()?;
+    let principal = principal(&headers).ok_or(AppError::Unauthorized)?;
+    let workspace = state.workspaces.create(req, principal).await?;
+    Ok(Json(ApiResponse::ok_with_service(workspace, "connection-service")))
+}
+
+/// 查询工作区详情：成员与其中的连接、快照
+#[utoipa::path(
+    get,
+    path = "/api/workspaces/{id}",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "工作区 ID")
+    ),
+    responses(
+        (status = 200, description = "工作区详情", body = ApiResponse<WorkspaceDetail>),
+        (status = 404, description = "工作区不存在或调用方不是其成员")
+    )
+)]
+pub async fn get_workspace(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<WorkspaceDetail>>, AppError> {
+    let detail = state.workspaces.get(&id, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(detail, "connection-service")))
+}
+
+/// 修改工作区名称或描述（仅所有者）
+#[utoipa::path(
+    put,
+    path = "/api/workspaces/{id}",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "工作区 ID")
+    ),
+    request_body = UpdateWorkspaceRequest,
+    responses(
+        (status = 200, description = "工作区已更新", body = ApiResponse<Workspace>),
+        (status = 403, description = "调用方不是所有者"),
+        (status = 404, description = "工作区不存在或调用方不是其成员")
+    )
+)]
+pub async fn update_workspace(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateWorkspaceRequest>,
+) -> Result<Json<ApiResponse<Workspace>>, AppError> {
+    req.validate()?;
+    let workspace = state.workspaces.update(&id, req, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(workspace, "connection-service")))
+}
+
+/// 删除工作区（仅所有者），其中的连接与快照保留
+#[utoipa::path(
+    delete,
+    path = "/api/workspaces/{id}",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "工作区 ID")
+    ),
+    responses(
+        (status = 200, description = "工作区已删除", body = ApiResponse<bool>),
+        (status = 403, description = "调用方不是所有者"),
+        (status = 404, description = "工作区不存在或调用方不是其成员")
+    )
+)]
+pub async fn delete_workspace(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    state.workspaces.delete(&id, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 添加工作区成员或修改成员角色（仅所有者），返回全部成员
+#[utoipa::path(
+    post,
+    path = "/api/workspaces/{id}/members",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "工作区 ID")
+    ),
+    request_body = AddWorkspaceMemberRequest,
+    responses(
+        (status = 200, description = "成员列表", body = ApiResponse<Vec<WorkspaceMember>>),
+        (status = 403, description = "调用方不是所有者"),
+        (status = 409, description = "工作区将没有所有者")
+    )
+)]
+pub async fn add_workspace_member(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<AddWorkspaceMemberRequest>,
+) -> Result<Json<ApiResponse<Vec<WorkspaceMember>>>, AppError> {
+    req.validate()?;
+    let members = state.workspaces.add_member(&id, req, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(members, "connection-service")))
+}
+
+/// 移除工作区成员：所有者可移除任何成员，成员可移除自己（退出），返回剩余成员
+#[utoipa::path(
+    delete,
+    path = "/api/workspaces/{id}/members/{principal}",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "工作区 ID"),
+        ("principal" = String, Path, description = "成员主体，如 `user:<id>`")
+    ),
+    responses(
+        (status = 200, description = "成员列表", body = ApiResponse<Vec<WorkspaceMember>>),
+        (status = 403, description = "调用方不是所有者"),
+        (status = 404, description = "工作区或成员不存在"),
+        (status = 409, description = "不能移除最后一个所有者")
+    )
+)]
+pub async fn remove_workspace_member(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, member)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<Vec<WorkspaceMember>>>, AppError> {
+    let members = state.workspaces.remove_member(&id, &member, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(members, "connection-service")))
+}
+
+/// 将连接加入工作区（调用方须能看到该连接）
+#[utoipa::path(
+    put,
+    path = "/api/workspaces/{id}/connections/{connection_id}",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "工作区 ID"),
+        ("connection_id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "连接已加入工作区", body = ApiResponse<bool>),
+        (status = 404, description = "工作区或连接不存在")
+    )
+)]
+pub async fn add_workspace_connection(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, connection_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    connection_service(&state, &headers).get(&connection_id, viewer(&headers)).await?;
+    state
+        .workspaces
+        .add_resource(&id, WorkspaceResourceKind::Connection, &connection_id, viewer(&headers))
+        .await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 将连接移出工作区，连接本身保留
+#[utoipa::path(
+    delete,
+    path = "/api/workspaces/{id}/connections/{connection_id}",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "工作区 ID"),
+        ("connection_id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "连接已移出工作区", body = ApiResponse<bool>),
+        (status = 404, description = "工作区不存在或调用方不是其成员")
+    )
+)]
+pub async fn remove_workspace_connection(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, connection_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    state
+        .workspaces
+        .remove_resource(&id, WorkspaceResourceKind::Connection, &connection_id, viewer(&headers))
+        .await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 将查询快照（保存的查询）加入工作区
+#[utoipa::path(
+    put,
+    path = "/api/workspaces/{id}/snapshots/{snapshot_id}",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "工作区 ID"),
+        ("snapshot_id" = String, Path, description = "快照 ID")
+    ),
+    responses(
+        (status = 200, description = "快照已加入工作区", body = ApiResponse<bool>),
+        (status = 404, description = "工作区或快照不存在")
+    )
+)]
+pub async fn add_workspace_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, snapshot_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    visible_snapshot(&state, &snapshot_id, viewer(&headers)).await?;
+    state
+        .workspaces
+        .add_resource(&id, WorkspaceResourceKind::Snapshot, &snapshot_id, viewer(&headers))
+        .await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 将查询快照移出工作区，快照本身保留
+#[utoipa::path(
+    delete,
+    path = "/api/workspaces/{id}/snapshots/{snapshot_id}",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "工作区 ID"),
+        ("snapshot_id" = String, Path, description = "快照 ID")
+    ),
+    responses(
+        (status = 200, description = "快照已移出工作区", body = ApiResponse<bool>),
+        (status = 404, description = "工作区不存在或调用方不是其成员")
+    )
+)]
+pub async fn remove_workspace_snapshot(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, snapshot_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    state
+        .workspaces
+        .remove_resource(&id, WorkspaceResourceKind::Snapshot, &snapshot_id, viewer(&headers))
+        .await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 对比两个连接（库/模式）的表结构，返回表、列、索引、外键差异，可选生成迁移 SQL
+#[utoipa::path(
+    post,
+    path = "/api/schema/diff",
+    tag = "schema",
+    request_body = SchemaDiffRequest,
+    responses(
+        (status = 200, description = "表结构差异（只包含两侧连接白名单内的表）", body = ApiResponse<SchemaDiff>),
+        (status = 400, description = "参数无效或数据库类型不支持"),
+        (status = 403, description = "对比的库不在连接白名单内"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn diff_schemas(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<SchemaDiffRequest>,
+) -> Result<Json<ApiResponse<SchemaDiff>>, AppError> {
+    req.validate()?;
+    connection_config(&state, &req.source.connection_id, viewer(&headers)).await?;
+    connection_config(&state, &req.target.connection_id, viewer(&headers)).await?;
+    let diff = schema_diff::compare(&state.pool_manager, &req).await?;
+    Ok(Json(ApiResponse::ok_with_service(diff, "connection-service")))
+}
+
+/// 列出连接的备份记录（按创建时间倒序）
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/backups",
+    tag = "backups",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "备份列表", body = ApiResponse<Vec<BackupRecord>>)
+    )
+)]
+pub async fn list_backups(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<BackupRecord>>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let backups = state.backups.list(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(backups, "connection-service")))
+}
+
+/// 发起备份：生成逻辑转储（DDL + INSERT）或调用 mysqldump/pg_dump，后台执行
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/backups",
+    tag = "backups",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    request_body = CreateBackupRequest,
+    responses(
+        (status = 200, description = "备份任务已创建", body = ApiResponse<BackupRecord>),
+        (status = 400, description = "参数无效或数据库类型不支持"),
+        (status = 404, description = "连接未找到"),
+        (status = 500, description = "未配置原生备份工具")
+    )
+)]
+pub async fn create_backup(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<CreateBackupRequest>,
+) -> Result<Json<ApiResponse<BackupRecord>>, AppError> {
+    req.validate()?;
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let backup = state.backups.start(&id, req).await?;
+    Ok(Json(ApiResponse::ok_with_service(backup, "connection-service")))
+}
+
+/// 查询备份任务状态
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/backups/{backup_id}",
+    tag = "backups",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("backup_id" = String, Path, description = "备份 ID")
+    ),
+    responses(
+        (status = 200, description = "备份详情", body = ApiResponse<BackupRecord>),
+        (status = 404, description = "备份未找到")
+    )
+)]
+pub async fn get_backup(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, backup_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<BackupRecord>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let backup = state.backups.get(&backup_id).await?;
+    belongs_to(&backup.connection_id, &id, || format!("backup {}", backup_id))?;
+    Ok(Json(ApiResponse::ok_with_service(backup, "connection-service")))
+}
+
+/// 下载备份文件（SQL）
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/backups/{backup_id}/download",
+    tag = "backups",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("backup_id" = String, Path, description = "备份 ID")
+    ),
+    responses(
+        (status = 200, description = "SQL 转储文件", content_type = "application/sql"),
+        (status = 404, description = "备份未找到"),
+        (status = 409, description = "备份尚未完成")
+    )
+)]
+pub async fn download_backup(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, backup_id)): Path<(String, String)>,
+) -> Result<Response, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let backup = state.backups.get(&backup_id).await?;
+    belongs_to(&backup.connection_id, &id, || format!("backup {}", backup_id))?;
+    let (backup, body) = state.backups.open(&backup_id).await?;
+    let disposition = format!(
+        "attachment; filename=\"{}-{}.sql\"",
+        backup.database, backup.id
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/sql".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// 恢复备份或上传的 SQL 脚本：按批执行并记录进度，单条语句失败默认跳过并记录
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/restore",
+    tag = "backups",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    request_body = RestoreRequest,
+    responses(
+        (status = 200, description = "恢复任务已创建", body = ApiResponse<RestoreJob>),
+        (status = 400, description = "参数无效或数据库类型不支持"),
+        (status = 404, description = "连接或备份未找到"),
+        (status = 409, description = "备份尚未完成")
+    )
+)]
+pub async fn start_restore(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<RestoreRequest>,
+) -> Result<Json<ApiResponse<RestoreJob>>, AppError> {
+    req.validate()?;
+    connection_config(&state, &id, viewer(&headers)).await?;
+    // 备份可以恢复到其他连接，但来源连接同样须对调用方可见
+    if let Some(backup_id) = &req.backup_id {
+        let backup = state.backups.get(backup_id).await?;
+        if !state.pool_manager.get_connection(&backup.connection_id).await.is_some_and(|c| viewer(&headers).can_see(&c)) {
+            return Err(AppError::NotFound(format!("backup {}", backup_id)));
+        }
+    }
+    let job = state.restores.start(&id, req).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 列出连接上的恢复任务
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/restore",
+    tag = "backups",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "恢复任务列表", body = ApiResponse<Vec<RestoreJob>>)
+    )
+)]
+pub async fn list_restores(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<RestoreJob>>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let jobs = state.restores.list(&id).await;
+    Ok(Json(ApiResponse::ok_with_service(jobs, "connection-service")))
+}
+
+/// 查询恢复任务进度及失败语句
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/restore/{job_id}",
+    tag = "backups",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("job_id" = String, Path, description = "恢复任务 ID")
+    ),
+    responses(
+        (status = 200, description = "恢复任务详情", body = ApiResponse<RestoreJob>),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn get_restore(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, job_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<RestoreJob>>, AppError> {
+    connection_config(&state, &id, viewer(&headers)).await?;
+    let job = state.restores.get(&job_id).await?;
+    belongs_to(&job.connection_id, &id, || format!("restore job {}", job_id))?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 任务列表查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct JobListQuery {
+    /// 只列出该类型的任务：`backup`、`restore` 或 `transfer`
+    pub kind: Option<JobKind>,
+    /// 返回条数（默认 100，最大 1000）
+    #[serde(default = "default_job_limit")]
+    pub limit: u32,
+}
+
+fn default_job_limit() -> u32 {
+    100
+}
+
+/// 任务涉及的连接：备份与恢复为所属连接，数据复制为源连接与目标连接；找不到对应记录时为空
+async fn job_connections(state: &AppState, id: &str) -> Vec<String> {
+    if let Ok(job) = state.transfers.get(id).await {
+        vec![job.source_connection_id, job.target_connection_id]
+    } else if let Ok(job) = state.restores.get(id).await {
+        vec![job.connection_id]
+    } else if let Ok(backup) = state.backups.get(id).await {
+        vec![backup.connection_id]
+    } else {
+        Vec::new()
+    }
+}
+
+/// 调用方须能看到任务涉及的全部连接；找不到对应记录的任务（如服务重启前的数据复制）只对管理员可见
+async fn can_see_job(state: &AppState, id: &str, viewer: Viewer<'_>) -> bool {
+    let connections = job_connections(state, id).await;
+    if connections.is_empty() {
+        return viewer.admin;
+    }
+    for connection_id in &connections {
+        if connection_config(state, connection_id, viewer).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// 调用方看不到的任务视为不存在
+async fn check_job(state: &AppState, id: &str, viewer: Viewer<'_>) -> Result<(), AppError> {
+    if can_see_job(state, id, viewer).await {
+        Ok(())
+    } else {
+        Err(AppError::NotFound(format!("job {}", id)))
+    }
+}
+
+/// 列出备份、恢复与数据复制任务的通用状态（最新在前）
+///
+/// 只返回调用方能看到其全部连接的任务。任务记录保存在元数据库 `jobs` 表中，服务重启时仍在运行的任务标记为失败，
+/// 结束超过 30 天的任务自动清理。
+#[utoipa::path(
+    get,
+    path = "/api/jobs",
+    tag = "jobs",
+    params(JobListQuery),
+    responses(
+        (status = 200, description = "任务列表", body = ApiResponse<Vec<Job>>)
+    )
+)]
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<JobListQuery>,
+) -> Result<Json<ApiResponse<Vec<Job>>>, AppError> {
+    let viewer = viewer(&headers);
+    if viewer.admin {
+        let jobs = state.jobs.list(query.kind, query.limit).await?;
+        return Ok(Json(ApiResponse::ok_with_service(jobs, "connection-service")));
+    }
+    // 先按上限读取再过滤，过滤后截取请求的条数
+    let mut jobs = Vec::new();
+    for job in state.jobs.list(query.kind, u32::MAX).await? {
+        if jobs.len() >= query.limit as usize {
+            break;
+        }
+        if can_see_job(&state, &job.id, viewer).await {
+            jobs.push(job);
+        }
+    }
+    Ok(Json(ApiResponse::ok_with_service(jobs, "connection-service")))
+}
+
+/// 查询任务的通用状态，`result_ref` 指向任务结果（备份存储位置、恢复或复制的目标）
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}",
+    tag = "jobs",
+    params(
+        ("id" = String, Path, description = "任务 ID，与备份 ID、恢复任务 ID 或数据复制任务 ID 相同")
+    ),
+    responses(
+        (status = 200, description = "任务详情", body = ApiResponse<Job>),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn get_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Job>>, AppError> {
+    check_job(&state, &id, viewer(&headers)).await?;
+    let job = state.jobs.get(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 取消任务：恢复与数据复制在当前批次结束后停止，已执行的部分保留；备份不支持取消
+#[utoipa::path(
+    post,
+    path = "/api/jobs/{id}/cancel",
+    tag = "jobs",
+    params(
+        ("id" = String, Path, description = "任务 ID")
+    ),
+    responses(
+        (status = 200, description = "已请求取消", body = ApiResponse<Job>),
+        (status = 404, description = "任务未找到"),
+        (status = 409, description = "任务已结束或不支持取消")
+    )
+)]
+pub async fn cancel_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Job>>, AppError> {
+    check_job(&state, &id, viewer(&headers)).await?;
+    let job = state.jobs.cancel(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 订阅备份、恢复与数据复制任务的进度事件（Server-Sent Events）
+///
+/// 先发送任务的最新状态，之后每次变化发送一个 `progress` 事件，任务结束时发送
+/// `finished` 事件并关闭连接；事件数据为 `ProgressEvent`，`job` 与任务查询端点的返回一致。
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}/events",
+    tag = "jobs",
+    params(
+        ("id" = String, Path, description = "备份 ID、恢复任务 ID 或数据复制任务 ID")
+    ),
+    responses(
+        (status = 200, description = "进度事件流", body = ProgressEvent, content_type = "text/event-stream"),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn job_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<ProgressSubscription, AppError> {
+    check_job(&state, &id, viewer(&headers)).await?;
+    if let Some(subscription) = state.progress.subscribe(&id) {
+        return Ok(subscription);
+    }
+    // 已结束较久的任务不在进度通道中，只发送最终状态
+    let event = if let Ok(job) = state.transfers.get(&id).await {
+        ProgressEvent::new(progress::kinds::TRANSFER, &id, &job, job.status.is_finished())
+    } else if let Ok(job) = state.restores.get(&id).await {
+        ProgressEvent::new(progress::kinds::RESTORE, &id, &job, job.status != RestoreStatus::Running)
+    } else {
+        let backup = state.backups.get(&id).await.map_err(|e| match e {
+            AppError::NotFound(_) => AppError::NotFound(format!("job {}", id)),
+            e => e,
+        })?;
+        ProgressEvent::new(progress::kinds::BACKUP, &id, &backup, backup.status != BackupStatus::Running)
+    };
+    Ok(ProgressSubscription::once(event))
+}
+
+/// 获取调用方可见连接上的定时任务，其他任务视为不存在
+async fn scheduled_job(state: &AppState, id: &str, viewer: Viewer<'_>) -> Result<ScheduledJob, AppError> {
+    let job = state.scheduler.get(id).await?;
+    match connection_config(state, &job.connection_id, viewer).await {
+        Ok(_) => Ok(job),
+        Err(_) => Err(AppError::NotFound(format!("scheduled job {}", id))),
+    }
+}
+
+/// 列出调用方可见连接上的定时任务
+#[utoipa::path(
+    get,
+    path = "/api/scheduled-jobs",
+    tag = "scheduler",
+    responses(
+        (status = 200, description = "定时任务列表", body = ApiResponse<Vec<ScheduledJob>>)
+    )
+)]
+pub async fn list_scheduled_jobs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<ScheduledJob>>>, AppError> {
+    let viewer = viewer(&headers);
+    let mut jobs = Vec::new();
+    for job in state.scheduler.list().await? {
+        if connection_config(&state, &job.connection_id, viewer).await.is_ok() {
+            jobs.push(job);
+        }
+    }
+    Ok(Json(ApiResponse::ok_with_service(jobs, "connection-service")))
+}
+
+/// 创建定时任务：按 cron 表达式（UTC）定期执行备份、连接健康检查或只读查询
+#[utoipa::path(
+    post,
+    path = "/api/scheduled-jobs",
+    tag = "scheduler",
+    request_body = CreateScheduledJobRequest,
+    responses(
+        (status = 200, description = "定时任务已创建", body = ApiResponse<ScheduledJob>),
+        (status = 400, description = "cron 表达式或任务参数无效"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn create_scheduled_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateScheduledJobRequest>,
+) -> Result<Json<ApiResponse<ScheduledJob>>, AppError> {
+    req.validate()?;
+    connection_config(&state, &req.connection_id, viewer(&headers)).await?;
+    let job = state.scheduler.create(req).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 查询定时任务详情
+#[utoipa::path(
+    get,
+    path = "/api/scheduled-jobs/{id}",
+    tag = "scheduler",
+    params(
+        ("id" = String, Path, description = "定时任务 ID")
+    ),
+    responses(
+        (status = 200, description = "定时任务详情", body = ApiResponse<ScheduledJob>),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn get_scheduled_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<ScheduledJob>>, AppError> {
+    let job = scheduled_job(&state, &id, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 删除定时任务及其执行历史
+#[utoipa::path(
+    delete,
+    path = "/api/scheduled-jobs/{id}",
+    tag = "scheduler",
+    params(
+        ("id" = String, Path, description = "定时任务 ID")
+    ),
+    responses(
+        (status = 200, description = "任务已删除", body = ApiResponse<bool>),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn delete_scheduled_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    scheduled_job(&state, &id, viewer(&headers)).await?;
+    state.scheduler.delete(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 启用定时任务（从当前时间重新计算下次执行时间）
+#[utoipa::path(
+    post,
+    path = "/api/scheduled-jobs/{id}/enable",
+    tag = "scheduler",
+    params(
+        ("id" = String, Path, description = "定时任务 ID")
+    ),
+    responses(
+        (status = 200, description = "任务已启用", body = ApiResponse<ScheduledJob>),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn enable_scheduled_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<ScheduledJob>>, AppError> {
+    scheduled_job(&state, &id, viewer(&headers)).await?;
+    let job = state.scheduler.set_enabled(&id, true).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 停用定时任务
+#[utoipa::path(
+    post,
+    path = "/api/scheduled-jobs/{id}/disable",
+    tag = "scheduler",
+    params(
+        ("id" = String, Path, description = "定时任务 ID")
+    ),
+    responses(
+        (status = 200, description = "任务已停用", body = ApiResponse<ScheduledJob>),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn disable_scheduled_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<ScheduledJob>>, AppError> {
+    scheduled_job(&state, &id, viewer(&headers)).await?;
+    let job = state.scheduler.set_enabled(&id, false).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "connection-service")))
+}
+
+/// 立即执行一次定时任务（不影响原有调度），返回执行记录
+#[utoipa::path(
+    post,
+    path = "/api/scheduled-jobs/{id}/run",
+    tag = "scheduler",
+    params(
+        ("id" = String, Path, description = "定时任务 ID")
+    ),
+    responses(
+        (status = 200, description = "执行记录", body = ApiResponse<JobRun>),
+        (status = 404, description = "任务未找到")
+    )
+)]
+pub async fn run_scheduled_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<JobRun>>, AppError> {
+    scheduled_job(&state, &id, viewer(&headers)).await?;
+    let run = state.scheduler.trigger(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(run, "connection-service")))
+}
+
+/// 查询定时任务的执行历史（最近 100 次）
+#[utoipa::path(
+    get,
+    path = "/api/scheduled-jobs/{id}/runs",
+    tag = "scheduler",
+    params(
+        ("id" = String, Path, description = "定时任务 ID")
+    ),
+    responses(
+        (status = 200, description = "执行历史", body = ApiResponse<Vec<JobRun>>)
+    )
+)]
+pub async fn list_scheduled_job_runs(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<JobRun>>>, AppError> {
+    scheduled_job(&state, &id, viewer(&headers)).await?;
+    let runs = state.scheduler.runs(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(runs, "connection-service")))
+}
+
+/// 获取调用方可见连接上的告警规则，其他规则视为不存在
+async fn alert_rule(state: &AppState, id: &str, viewer: Viewer<'_>) -> Result<AlertRule, AppError> {
+    let rule = state.alerts.get(id).await?;
+    match connection_config(state, &rule.connection_id, viewer).await {
+        Ok(_) => Ok(rule),
+        Err(_) => Err(AppError::NotFound(format!("alert rule {}", id))),
+    }
+}
+
+/// 列出调用方可见连接上的告警规则及其最近一次评估状态
+#[utoipa::path(
+    get,
+    path = "/api/alerts",
+    tag = "alerts",
+    responses(
+        (status = 200, description = "告警规则列表", body = ApiResponse<Vec<AlertRule>>)
+    )
+)]
+pub async fn list_alert_rules(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<AlertRule>>>, AppError> {
+    let viewer = viewer(&headers);
+    let mut rules = Vec::new();
+    for rule in state.alerts.list().await? {
+        if connection_config(&state, &rule.connection_id, viewer).await.is_ok() {
+            rules.push(rule);
+        }
+    }
+    Ok(Json(ApiResponse::ok_with_service(rules, "connection-service")))
+}
+
+/// 创建告警规则：按 cron 表达式（UTC）定期执行只读查询，条件成立时通知 webhook、Slack 或邮件
+#[utoipa::path(
+    post,
+    path = "/api/alerts",
+    tag = "alerts",
+    request_body = CreateAlertRuleRequest,
+    responses(
+        (status = 200, description = "告警规则已创建", body = ApiResponse<AlertRule>),
+        (status = 400, description = "cron 表达式、SQL 或通知渠道无效"),
+        (status = 403, description = "SQL 引用了白名单外的表"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn create_alert_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateAlertRuleRequest>,
+) -> Result<Json<ApiResponse<AlertRule>>, AppError> {
+    req.validate()?;
+    connection_config(&state, &req.connection_id, viewer(&headers)).await?;
+    let rule = state.alerts.create(req).await?;
+    Ok(Json(ApiResponse::ok_with_service(rule, "connection-service")))
+}
+
+/// 查询告警规则详情
+#[utoipa::path(
+    get,
+    path = "/api/alerts/{id}",
+    tag = "alerts",
+    params(
+        ("id" = String, Path, description = "告警规则 ID")
+    ),
+    responses(
+        (status = 200, description = "告警规则详情", body = ApiResponse<AlertRule>),
+        (status = 404, description = "规则未找到")
+    )
+)]
+pub async fn get_alert_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<AlertRule>>, AppError> {
+    let rule = alert_rule(&state, &id, viewer(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(rule, "connection-service")))
+}
+
+/// 删除告警规则
+#[utoipa::path(
+    delete,
+    path = "/api/alerts/{id}",
+    tag = "alerts",
+    params(
+        ("id" = String, Path, description = "告警规则 ID")
+    ),
+    responses(
+        (status = 200, description = "规则已删除", body = ApiResponse<bool>),
+        (status = 404, description = "规则未找到")
+    )
+)]
+pub async fn delete_alert_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    alert_rule(&state, &id, viewer(&headers)).await?;
+    state.alerts.delete(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 启用告警规则（从当前时间重新计算下次评估时间）
+#[utoipa::path(
+    post,
+    path = "/api/alerts/{id}/enable",
+    tag = "alerts",
+    params(
+        ("id" = String, Path, description = "告警规则 ID")
+    ),
+    responses(
+        (status = 200, description = "规则已启用", body = ApiResponse<AlertRule>),
+        (status = 404, description = "规则未找到")
+    )
+)]
+pub async fn enable_alert_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<AlertRule>>, AppError> {
+    alert_rule(&state, &id, viewer(&headers)).await?;
+    let rule = state.alerts.set_enabled(&id, true).await?;
+    Ok(Json(ApiResponse::ok_with_service(rule, "connection-service")))
+}
+
+/// 停用告警规则
+#[utoipa::path(
+    post,
+    path = "/api/alerts/{id}/disable",
+    tag = "alerts",
+    params(
+        ("id" = String, Path, description = "告警规则 ID")
+    ),
+    responses(
+        (status = 200, description = "规则已停用", body = ApiResponse<AlertRule>),
+        (status = 404, description = "规则未找到")
+    )
+)]
+pub async fn disable_alert_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<AlertRule>>, AppError> {
+    alert_rule(&state, &id, viewer(&headers)).await?;
+    let rule = state.alerts.set_enabled(&id, false).await?;
+    Ok(Json(ApiResponse::ok_with_service(rule, "connection-service")))
+}
+
+/// 立即评估一次告警规则（不影响原有调度），条件新成立时同样发送通知
+#[utoipa::path(
+    post,
+    path = "/api/alerts/{id}/evaluate",
+    tag = "alerts",
+    params(
+        ("id" = String, Path, description = "告警规则 ID")
+    ),
+    responses(
+        (status = 200, description = "评估结果", body = ApiResponse<AlertEvaluation>),
+        (status = 404, description = "规则未找到")
+    )
+)]
+pub async fn evaluate_alert_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<AlertEvaluation>>, AppError> {
+    alert_rule(&state, &id, viewer(&headers)).await?;
+    let evaluation = state.alerts.evaluate_now(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(evaluation, "connection-service")))
+}
+
+/// 元数据导出参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct MetadataExportQuery {
+    /// 是否包含连接密码（默认 false）
+    #[serde(default)]
+    pub include_secrets: bool,
+}
+
+/// 导出全部管理元数据（连接、定时任务）为版本化归档文件，需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/metadata/export",
+    tag = "admin",
+    params(MetadataExportQuery),
+    responses(
+        (status = 200, description = "元数据归档（JSON 文件）", body = MetadataArchive),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn export_metadata(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<MetadataExportQuery>,
+) -> Result<Response, AppError> {
+    admin::authorize(&headers)?;
+    let archive = metadata::export(&state.pool_manager, &state.scheduler, query.include_secrets).await?;
+    let disposition = format!(
+        "attachment; filename=\"metadata-{}.json\"",
+        Utc::now().format("%Y%m%d%H%M%S")
+    );
+    Ok((
+        [(header::CONTENT_DISPOSITION, disposition)],
+        Json(archive),
+    )
+        .into_response())
+}
+
+/// 元数据导入参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct MetadataImportQuery {
+    /// ID 已存在时的处理方式：skip（默认）或 overwrite
+    #[serde(default)]
+    pub on_conflict: ImportConflictPolicy,
+}
+
+/// 导入元数据归档（保留原 ID），需要 X-Admin-Token
+#[utoipa::path(
+    post,
+    path = "/api/admin/metadata/import",
+    tag = "admin",
+    params(MetadataImportQuery),
+    request_body = MetadataArchive,
+    responses(
+        (status = 200, description = "导入结果", body = ApiResponse<MetadataImportReport>),
+        (status = 400, description = "归档版本不受支持"),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn import_metadata(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<MetadataImportQuery>,
+    Json(archive): Json<MetadataArchive>,
+) -> Result<Json<ApiResponse<MetadataImportReport>>, AppError> {
+    admin::authorize(&headers)?;
+    let report = metadata::import(&state.pool_manager, &state.scheduler, &state.revisions, archive, query.on_conflict).await?;
+    Ok(Json(ApiResponse::ok_with_service(report, "connection-service")))
+}
+
+/// 查看元数据库的 schema 迁移状态（已应用、待应用与失败的版本），需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/migrations",
+    tag = "admin",
+    responses(
+        (status = 200, description = "迁移状态", body = ApiResponse<MigrationReport>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn get_migrations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<MigrationReport>>, AppError> {
+    admin::authorize(&headers)?;
+    let report = migrations::status(state.pool_manager.repository()).await?;
+    Ok(Json(ApiResponse::ok_with_service(report, "connection-service")))
+}
+
+/// 签发 API Key（只读 / 限定连接），密钥仅在响应中返回一次，需要 X-Admin-Token
+#[utoipa::path(
+    post,
+    path = "/api/admin/keys",
+    tag = "admin",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "已签发的 API Key", body = ApiResponse<CreatedApiKey>),
+        (status = 400, description = "参数无效"),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> Result<Json<ApiResponse<CreatedApiKey>>, AppError> {
+    admin::authorize(&headers)?;
+    req.validate()?;
+    let created = state.api_keys.create(req).await?;
+    Ok(Json(ApiResponse::ok_with_service(created, "connection-service")))
+}
+
+/// 列出全部 API Key（不含密钥），需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/keys",
+    tag = "admin",
+    responses(
+        (status = 200, description = "API Key 列表", body = ApiResponse<Vec<ApiKey>>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<ApiKey>>>, AppError> {
+    admin::authorize(&headers)?;
+    let keys = state.api_keys.list().await?;
+    Ok(Json(ApiResponse::ok_with_service(keys, "connection-service")))
+}
+
+/// 吊销 API Key，网关在验证缓存过期后拒绝该密钥，需要 X-Admin-Token
+#[utoipa::path(
+    delete,
+    path = "/api/admin/keys/{id}",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "API Key ID")
+    ),
+    responses(
+        (status = 200, description = "已吊销的 API Key", body = ApiResponse<ApiKey>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 404, description = "API Key 未找到")
+    )
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<ApiKey>>, AppError> {
+    admin::authorize(&headers)?;
+    let key = state.api_keys.revoke(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(key, "connection-service")))
+}
+
+/// 为连接签发访客链接：只读、仅限查询接口与指定库/schema，按小时过期，需要 X-Admin-Token
+#[utoipa::path(
+    post,
+    path = "/api/connections/{id}/guest-links",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    request_body = CreateGuestLinkRequest,
+    responses(
+        (status = 200, description = "已签发的访客链接", body = ApiResponse<CreatedGuestLink>),
+        (status = 400, description = "参数无效"),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn create_guest_link(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<CreateGuestLinkRequest>,
+) -> Result<Json<ApiResponse<CreatedGuestLink>>, AppError> {
+    admin::authorize(&headers)?;
+    req.validate()?;
+    let created = state.api_keys.create_guest_link(&id, req).await?;
+    Ok(Json(ApiResponse::ok_with_service(created, "connection-service")))
+}
+
+/// 列出连接的访客链接（含已过期与已吊销），需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/connections/{id}/guest-links",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "连接 ID")
+    ),
+    responses(
+        (status = 200, description = "访客链接列表", body = ApiResponse<Vec<ApiKey>>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn list_guest_links(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ApiKey>>>, AppError> {
+    admin::authorize(&headers)?;
+    let links = state.api_keys.list_guest_links(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(links, "connection-service")))
+}
+
+/// 吊销连接的访客链接，仅限属于该连接的访客链接，需要 X-Admin-Token
+#[utoipa::path(
+    delete,
+    path = "/api/connections/{id}/guest-links/{link_id}",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "连接 ID"),
+        ("link_id" = String, Path, description = "访客链接（API Key）ID")
+    ),
+    responses(
+        (status = 200, description = "已吊销的访客链接", body = ApiResponse<ApiKey>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 404, description = "该连接没有此访客链接")
+    )
+)]
+pub async fn revoke_guest_link(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, link_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<ApiKey>>, AppError> {
+    admin::authorize(&headers)?;
+    let link = state.api_keys.revoke_guest_link(&id, &link_id).await?;
+    Ok(Json(ApiResponse::ok_with_service(link, "connection-service")))
+}
+
+/// 内部端点，供网关验证 API Key
+#[utoipa::path(
+    post,
+    path = "/internal/api-keys/verify",
+    tag = "internal",
+    request_body = VerifyApiKeyRequest,
+    responses(
+        (status = 200, description = "有效的 API Key", body = ApiResponse<ApiKey>),
+        (status = 401, description = "API Key 无效、已吊销或已过期")
+    )
+)]
+pub async fn verify_api_key(
+    State(state): State<AppState>,
+    Json(req): Json<VerifyApiKeyRequest>,
+) -> Result<Json<ApiResponse<ApiKey>>, AppError> {
+    let key = state.api_keys.verify(&req.key).await?;
+    Ok(Json(ApiResponse::ok_with_service(key, "connection-service")))
+}
+
+/// 列出全部授权策略，需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/policies",
+    tag = "admin",
+    responses(
+        (status = 200, description = "策略列表", body = ApiResponse<Vec<Policy>>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn list_policies(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<Policy>>>, AppError> {
+    admin::authorize(&headers)?;
+    let policies = state.policies.list().await?;
+    Ok(Json(ApiResponse::ok_with_service(policies, "connection-service")))
+}
+
+/// 创建授权策略，立即参与网关的授权判定，需要 X-Admin-Token
+#[utoipa::path(
+    post,
+    path = "/api/admin/policies",
+    tag = "admin",
+    request_body = PolicyRequest,
+    responses(
+        (status = 200, description = "已创建的策略", body = ApiResponse<Policy>),
+        (status = 400, description = "参数无效"),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn create_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<PolicyRequest>,
+) -> Result<Json<ApiResponse<Policy>>, AppError> {
+    admin::authorize(&headers)?;
+    req.validate()?;
+    let policy = state.policies.create(req).await?;
+    Ok(Json(ApiResponse::ok_with_service(policy, "connection-service")))
+}
+
+/// 获取授权策略，需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/policies/{id}",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "策略 ID")
+    ),
+    responses(
+        (status = 200, description = "策略详情", body = ApiResponse<Policy>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 404, description = "策略不存在")
+    )
+)]
+pub async fn get_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Policy>>, AppError> {
+    admin::authorize(&headers)?;
+    let policy = state.policies.get(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(policy, "connection-service")))
+}
+
+/// 替换授权策略，需要 X-Admin-Token
+#[utoipa::path(
+    put,
+    path = "/api/admin/policies/{id}",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "策略 ID")
+    ),
+    request_body = PolicyRequest,
+    responses(
+        (status = 200, description = "更新后的策略", body = ApiResponse<Policy>),
+        (status = 400, description = "参数无效"),
+        (status = 401, description = "管理令牌无效"),
+        (status = 404, description = "策略不存在")
+    )
+)]
+pub async fn update_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(req): Json<PolicyRequest>,
+) -> Result<Json<ApiResponse<Policy>>, AppError> {
+    admin::authorize(&headers)?;
+    req.validate()?;
+    let policy = state.policies.update(&id, req).await?;
+    Ok(Json(ApiResponse::ok_with_service(policy, "connection-service")))
+}
+
+/// 删除授权策略，需要 X-Admin-Token
+#[utoipa::path(
+    delete,
+    path = "/api/admin/policies/{id}",
+    tag = "admin",
+    params(
+        ("id" = String, Path, description = "策略 ID")
+    ),
+    responses(
+        (status = 200, description = "策略已删除", body = ApiResponse<bool>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 404, description = "策略不存在")
+    )
+)]
+pub async fn delete_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    admin::authorize(&headers)?;
+    state.policies.delete(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 授权决策日志查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct DecisionLogQuery {
+    /// 只看该主体的决策，如 `key:<id>`
+    pub principal: Option<String>,
+    /// 只看被拒绝的决策（默认 false）
+    #[serde(default)]
+    pub denied_only: bool,
+    /// 返回条数（默认 100，最大 1000）
+    #[serde(default = "default_decision_limit")]
+    pub limit: u32,
+}
+
+fn default_decision_limit() -> u32 {
+    100
+}
+
+/// 查询授权决策日志（最新在前），需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/policy-decisions",
+    tag = "admin",
+    params(DecisionLogQuery),
+    responses(
+        (status = 200, description = "决策日志", body = ApiResponse<Vec<PolicyDecisionLog>>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn list_policy_decisions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DecisionLogQuery>,
+) -> Result<Json<ApiResponse<Vec<PolicyDecisionLog>>>, AppError> {
+    admin::authorize(&headers)?;
+    let decisions = state
+        .policies
+        .decisions(query.principal.as_deref(), query.denied_only, query.limit)
+        .await?;
+    Ok(Json(ApiResponse::ok_with_service(decisions, "connection-service")))
+}
+
+/// 查询当前主体今天的用量与配额
+#[utoipa::path(
+    get,
+    path = "/api/usage/me",
+    tag = "usage",
+    responses(
+        (status = 200, description = "今天的用量与生效的配额", body = ApiResponse<UserUsage>),
+        (status = 401, description = "请求未携带主体（未通过网关 API Key 认证）")
+    )
+)]
+pub async fn get_my_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<UserUsage>>, AppError> {
+    let principal = principal(&headers).ok_or(AppError::Unauthorized)?;
+    let usage = state.usage.usage(principal).await?;
+    Ok(Json(ApiResponse::ok_with_service(usage, "connection-service")))
+}
+
+/// 用量报表查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct UsageReportQuery {
+    /// 统计日期（UTC，`YYYY-MM-DD`），缺省为今天
+    pub date: Option<chrono::NaiveDate>,
+}
+
+/// 查询某天全部主体的用量（查询数最多在前），需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/usage",
+    tag = "usage",
+    params(UsageReportQuery),
+    responses(
+        (status = 200, description = "用量报表", body = ApiResponse<UsageReport>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn get_usage_report(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<UsageReportQuery>,
+) -> Result<Json<ApiResponse<UsageReport>>, AppError> {
+    admin::authorize(&headers)?;
+    let report = state.usage.report(query.date).await?;
+    Ok(Json(ApiResponse::ok_with_service(report, "connection-service")))
+}
+
+/// 设置主体自己的每日配额（未设置的项不限制），替代默认配额，需要 X-Admin-Token
+#[utoipa::path(
+    put,
+    path = "/api/admin/usage/quotas/{principal}",
+    tag = "usage",
+    params(
+        ("principal" = String, Path, description = "主体，如 `key:<id>`")
+    ),
+    request_body = UsageQuota,
+    responses(
+        (status = 200, description = "主体今天的用量与新的配额", body = ApiResponse<UserUsage>),
+        (status = 400, description = "参数无效"),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn set_usage_quota(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(principal): Path<String>,
+    Json(quota): Json<UsageQuota>,
+) -> Result<Json<ApiResponse<UserUsage>>, AppError> {
+    admin::authorize(&headers)?;
+    let usage = state.usage.set_quota(&principal, quota).await?;
+    Ok(Json(ApiResponse::ok_with_service(usage, "connection-service")))
+}
+
+/// 删除主体自己的配额，恢复使用默认配额，需要 X-Admin-Token
+#[utoipa::path(
+    delete,
+    path = "/api/admin/usage/quotas/{principal}",
+    tag = "usage",
+    params(
+        ("principal" = String, Path, description = "主体，如 `key:<id>`")
+    ),
+    responses(
+        (status = 200, description = "配额已删除", body = ApiResponse<bool>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 404, description = "主体没有自己的配额")
+    )
+)]
+pub async fn delete_usage_quota(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(principal): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    admin::authorize(&headers)?;
+    state.usage.delete_quota(&principal).await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 会话令牌登录的当前用户，其他请求返回 401
+fn session_user(headers: &HeaderMap) -> Result<&str, AppError> {
+    principal(headers)
+        .and_then(principal_user_id)
+        .ok_or(AppError::Unauthorized)
+}
+
+/// 列出当前用户的有效会话（最新在前），`current` 标记本次请求所用的会话
+#[utoipa::path(
+    get,
+    path = "/api/sessions",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "有效会话列表", body = ApiResponse<Vec<Session>>),
+        (status = 401, description = "请求未使用会话令牌")
+    )
+)]
+pub async fn list_my_sessions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<Session>>>, AppError> {
+    let user_id = session_user(&headers)?;
+    let sessions = state.sessions.list(user_id, session_id(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(sessions, "connection-service")))
+}
+
+/// 吊销当前用户的一个会话，网关在验证缓存过期后拒绝该会话的令牌
+#[utoipa::path(
+    delete,
+    path = "/api/sessions/{id}",
+    tag = "sessions",
+    params(
+        ("id" = String, Path, description = "会话 ID")
+    ),
+    responses(
+        (status = 200, description = "已吊销的会话", body = ApiResponse<Session>),
+        (status = 401, description = "请求未使用会话令牌"),
+        (status = 404, description = "会话不存在或不属于当前用户")
+    )
+)]
+pub async fn revoke_my_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Session>>, AppError> {
+    let user_id = session_user(&headers)?;
+    let session = state.sessions.revoke(&id, Some(user_id)).await?;
+    Ok(Json(ApiResponse::ok_with_service(session, "connection-service")))
+}
+
+/// 吊销当前用户除本次请求所用会话外的全部会话
+#[utoipa::path(
+    delete,
+    path = "/api/sessions",
+    tag = "sessions",
+    responses(
+        (status = 200, description = "吊销的会话数", body = ApiResponse<RevokedSessions>),
+        (status = 401, description = "请求未使用会话令牌")
+    )
+)]
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<RevokedSessions>>, AppError> {
+    let user_id = session_user(&headers)?;
+    let revoked = state.sessions.revoke_all(user_id, session_id(&headers)).await?;
+    Ok(Json(ApiResponse::ok_with_service(RevokedSessions { revoked }, "connection-service")))
+}
+
+/// 会话列表查询参数
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct SessionListQuery {
+    /// 只看该用户的会话
+    pub user_id: Option<String>,
+    /// 包含已吊销与已过期的会话（默认 false）
+    #[serde(default)]
+    pub include_ended: bool,
+    /// 返回条数（默认 100，最大 1000）
+    #[serde(default = "default_decision_limit")]
+    pub limit: u32,
+}
+
+/// 列出会话（最新在前），需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/sessions",
+    tag = "sessions",
+    params(SessionListQuery),
+    responses(
+        (status = 200, description = "会话列表", body = ApiResponse<Vec<Session>>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SessionListQuery>,
+) -> Result<Json<ApiResponse<Vec<Session>>>, AppError> {
+    admin::authorize(&headers)?;
+    let sessions = state
+        .sessions
+        .list_all(query.user_id.as_deref(), !query.include_ended, query.limit)
+        .await?;
+    Ok(Json(ApiResponse::ok_with_service(sessions, "connection-service")))
+}
+
+/// 吊销任意用户的会话（如令牌泄露），需要 X-Admin-Token
+#[utoipa::path(
+    delete,
+    path = "/api/admin/sessions/{id}",
+    tag = "sessions",
+    params(
+        ("id" = String, Path, description = "会话 ID")
+    ),
+    responses(
+        (status = 200, description = "已吊销的会话", body = ApiResponse<Session>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 404, description = "会话未找到")
+    )
+)]
+pub async fn revoke_session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<Session>>, AppError> {
+    admin::authorize(&headers)?;
+    let session = state.sessions.revoke(&id, None).await?;
+    Ok(Json(ApiResponse::ok_with_service(session, "connection-service")))
+}
+
+/// 内部端点，供网关检查会话令牌所属的会话仍然有效
+#[utoipa::path(
+    post,
+    path = "/internal/sessions/verify",
+    tag = "internal",
+    request_body = VerifySessionRequest,
+    responses(
+        (status = 200, description = "有效的会话", body = ApiResponse<Session>),
+        (status = 401, description = "会话不存在、已吊销或已过期")
+    )
+)]
+pub async fn verify_session(
+    State(state): State<AppState>,
+    Json(req): Json<VerifySessionRequest>,
+) -> Result<Json<ApiResponse<Session>>, AppError> {
+    let session = state.sessions.verify(&req.session_id).await?;
+    Ok(Json(ApiResponse::ok_with_service(session, "connection-service")))
+}
+
+/// 内部端点，网关完成单点登录后为身份提供方认证的用户创建会话并签发令牌
+#[utoipa::path(
+    post,
+    path = "/internal/auth/sso-session",
+    tag = "internal",
+    request_body = CreateSsoSessionRequest,
+    responses(
+        (status = 200, description = "新会话的令牌", body = ApiResponse<AuthTokens>),
+        (status = 400, description = "签发方或主体标识为空或过长"),
+        (status = 503, description = "未配置 JWT_SECRET，登录已禁用")
+    )
+)]
+pub async fn create_sso_session(
+    State(state): State<AppState>,
+    Json(req): Json<CreateSsoSessionRequest>,
+) -> Result<Json<ApiResponse<AuthTokens>>, AppError> {
+    let tokens = state.auth.sso_login(req).await?;
+    Ok(Json(ApiResponse::ok_with_service(tokens, "connection-service")))
+}
+
+/// 用户名密码登录，创建会话并返回访问令牌与刷新令牌
+///
+/// 启用 Cookie 会话（`SESSION_COOKIE_ENABLED`）时同时设置访问令牌与 CSRF 令牌的 Cookie。
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "登录成功", body = ApiResponse<AuthTokens>),
+        (status = 401, description = "用户名或密码错误"),
+        (status = 429, description = "该用户或地址登录失败次数过多，暂时锁定（LOGIN_LOCKED，响应带 Retry-After）"),
+        (status = 503, description = "未配置 JWT_SECRET，登录已禁用")
+    )
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    extensions: axum::http::Extensions,
+    Json(req): Json<LoginRequest>,
+) -> Result<Response, AppError> {
+    let user_agent = headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok());
+    // 网关把 X-Forwarded-For 替换为它确定的客户端地址，取最后一跳（由网关添加）；
+    // 不经网关时取连接的对端地址
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .rfind(|ip| !ip.is_empty())
+        .map(str::to_string);
+    let ip_address = forwarded.or_else(|| {
+        extensions
+            .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+            .map(|info| info.0.ip().to_string())
+    });
+    let tokens = state.auth.login(req, user_agent, ip_address.as_deref()).await?;
+    Ok(tokens_response(&state, tokens))
+}
+
+/// 用刷新令牌换取新的访问令牌，刷新令牌同时轮换，旧令牌失效
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "新的令牌", body = ApiResponse<AuthTokens>),
+        (status = 401, description = "刷新令牌无效、已被替换或会话已结束"),
+        (status = 503, description = "未配置 JWT_SECRET，登录已禁用")
+    )
+)]
+pub async fn refresh_token(
+    State(state): State<AppState>,
+    Json(req): Json<RefreshRequest>,
+) -> Result<Response, AppError> {
+    let tokens = state.auth.refresh(&req.refresh_token).await?;
+    Ok(tokens_response(&state, tokens))
+}
+
+/// 退出登录，吊销本次请求所用的会话，并删除会话 Cookie
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    responses(
+        (status = 200, description = "已吊销的会话", body = ApiResponse<Session>),
+        (status = 401, description = "请求未使用会话令牌")
+    )
+)]
+pub async fn logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let user_id = session_user(&headers)?;
+    let session_id = session_id(&headers).ok_or(AppError::Unauthorized)?;
+    let session = state.auth.logout(user_id, session_id).await?;
+    let body = Json(ApiResponse::ok_with_service(session, "connection-service"));
+    Ok(match &state.config.get().session_cookie {
+        Some(config) => (AppendHeaders(clear_session_cookies(config).map(|c| (header::SET_COOKIE, c))), body).into_response(),
+        None => body.into_response(),
+    })
+}
+
+/// 返回签发的令牌；启用 Cookie 会话时同时设置访问令牌与 CSRF 令牌的 Cookie
+fn tokens_response(state: &AppState, tokens: AuthTokens) -> Response {
+    let cookies = state
+        .config
+        .get()
+        .session_cookie
+        .as_ref()
+        .map(|config| session_cookies(config, &tokens.access_token, tokens.expires_in.max(0) as u64));
+    let body = Json(ApiResponse::ok_with_service(tokens, "connection-service"));
+    match cookies {
+        Some(cookies) => (AppendHeaders(cookies.map(|c| (header::SET_COOKIE, c))), body).into_response(),
+        None => body.into_response(),
+    }
+}
+
+/// 列出可登录的用户，需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/users",
+    tag = "auth",
+    responses(
+        (status = 200, description = "用户列表", body = ApiResponse<Vec<User>>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn list_users(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<User>>>, AppError> {
+    admin::authorize(&headers)?;
+    let users = state.auth.list_users().await?;
+    Ok(Json(ApiResponse::ok_with_service(users, "connection-service")))
+}
+
+/// 创建用户或重置密码，重置后该用户的会话全部吊销，需要 X-Admin-Token
+#[utoipa::path(
+    put,
+    path = "/api/admin/users/{username}",
+    tag = "auth",
+    params(
+        ("username" = String, Path, description = "用户名")
+    ),
+    request_body = SetPasswordRequest,
+    responses(
+        (status = 200, description = "用户信息", body = ApiResponse<User>),
+        (status = 400, description = "用户名或密码无效"),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn set_user_password(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(username): Path<String>,
+    Json(req): Json<SetPasswordRequest>,
+) -> Result<Json<ApiResponse<User>>, AppError> {
+    admin::authorize(&headers)?;
+    req.validate()?;
+    let user = state.auth.set_password(&username, req.password).await?;
+    Ok(Json(ApiResponse::ok_with_service(user, "connection-service")))
+}
+
+/// 删除用户并吊销其会话，需要 X-Admin-Token
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{username}",
+    tag = "auth",
+    params(
+        ("username" = String, Path, description = "用户名")
+    ),
+    responses(
+        (status = 200, description = "已删除", body = ApiResponse<bool>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 404, description = "用户未找到")
+    )
+)]
+pub async fn delete_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(username): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    admin::authorize(&headers)?;
+    state.auth.delete_user(&username).await?;
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 列出有登录失败记录的用户名与地址，锁定中的在前，需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/login-lockouts",
+    tag = "auth",
+    responses(
+        (status = 200, description = "登录失败记录", body = ApiResponse<Vec<LoginLockout>>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn list_login_lockouts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<Vec<LoginLockout>>>, AppError> {
+    admin::authorize(&headers)?;
+    let lockouts = state.login_throttle.list().await?;
+    Ok(Json(ApiResponse::ok_with_service(lockouts, "connection-service")))
+}
+
+/// 清除登录失败记录并解除锁定，需要 X-Admin-Token
+#[utoipa::path(
+    delete,
+    path = "/api/admin/login-lockouts/{key}",
+    tag = "auth",
+    params(
+        ("key" = String, Path, description = "user:<用户名> 或 ip:<地址>")
+    ),
+    responses(
+        (status = 200, description = "已清除", body = ApiResponse<bool>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 404, description = "没有该用户名或地址的失败记录")
+    )
+)]
+pub async fn clear_login_lockout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(key): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, AppError> {
+    admin::authorize(&headers)?;
+    if !state.login_throttle.clear(&key).await? {
+        return Err(AppError::NotFound(format!("Login lockout {}", key)));
+    }
+    Ok(Json(ApiResponse::ok_with_service(true, "connection-service")))
+}
+
+/// 重新加载配置（.env 文件与命令行覆盖），连接池默认大小、超时与行数上限对之后的使用生效，需要 X-Admin-Token
+#[utoipa::path(
+    post,
+    path = "/api/admin/config/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "已重新加载，返回变更的配置项与需重启才生效的配置项", body = ApiResponse<ConfigReload>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 500, description = "新配置无效，保留当前配置")
+    )
+)]
+pub async fn reload_config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<ConfigReload>>, AppError> {
+    admin::authorize(&headers)?;
+    let reload = state.config.reload()?;
+    Ok(Json(ApiResponse::ok_with_service(reload, "connection-service")))
+}
+
+/// 内部端点，供网关按授权策略判定请求并记录决策
+#[utoipa::path(
+    post,
+    path = "/internal/authz/decide",
+    tag = "internal",
+    request_body = AuthzRequest,
+    responses(
+        (status = 200, description = "授权决策", body = ApiResponse<AuthzDecision>)
+    )
+)]
+pub async fn decide_authz(
+    State(state): State<AppState>,
+    Json(req): Json<AuthzRequest>,
+) -> Result<Json<ApiResponse<AuthzDecision>>, AppError> {
+    let decision = state.policies.decide(&req).await;
+    Ok(Json(ApiResponse::ok_with_service(decision, "connection-service")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use common::config::ConfigLoader;
+    use common::middleware::auth::PRINCIPAL_HEADER;
+    use serde_json::json;
+
+    /// 元数据库与目标库都是临时目录下的 SQLite 文件
+    async fn state(dir: &std::path::Path) -> AppState {
+        let config = ConfigLoader::new("connection-service")
+            .args([
+                "--set".to_string(),
+                format!("DATABASE_URL=sqlite://{}", dir.join("metadata.db").display()),
+                "--set".to_string(),
+                format!("DATA_DIR={}", dir.display()),
+            ])
+            .load_shared()
+            .unwrap();
+        AppState::new(Arc::new(config)).await.unwrap()
+    }
+
+    fn caller(principal: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(PRINCIPAL_HEADER, principal.parse().unwrap());
+        headers
+    }
+
+    fn denied<T>(result: Result<T, AppError>) -> bool {
+        matches!(result, Err(AppError::ConnectionNotFound(_)))
+    }
+
+    fn not_found<T>(result: Result<T, AppError>) -> bool {
+        matches!(result, Err(AppError::NotFound(_)))
+    }
+
+    fn query(sql: &str) -> ExecuteQueryBody {
+        serde_json::from_value(json!({ "sql": sql })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn hides_connections_from_other_principals() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = state(&dir).await;
+        let req = serde_json::from_value(json!({
+            "name": "app",
+            "db_type": "sqlite",
+            "file_path": dir.join("app.db").display().to_string(),
+        }))
+        .unwrap();
+        let Json(created) = create_connection(State(state.clone()), caller("user:alice"), Json(req)).await.unwrap();
+        let id = created.data.unwrap().id;
+
+        let result = execute_query(State(state.clone()), caller("user:alice"), Path(id.clone()), Json(query("SELECT 1")))
+            .await
+            .unwrap();
+        assert_eq!(result.0.data.unwrap().row_count, 1);
+        list_backups(State(state.clone()), caller("user:alice"), Path(id.clone())).await.unwrap();
+
+        // 其他主体直接调用或经 query-service 代为执行，连接都视为不存在
+        assert!(denied(execute_query(State(state.clone()), caller("user:bob"), Path(id.clone()), Json(query("SELECT 1"))).await));
+        assert!(denied(query_on_behalf(&state, Some("user:bob"), &id, &query("SELECT 1")).await));
+        assert!(denied(change_on_behalf(&state, Some("user:bob"), &id, &query("DELETE FROM t WHERE id = 1")).await));
+        assert!(denied(list_backups(State(state.clone()), caller("user:bob"), Path(id.clone())).await));
+        let backup = serde_json::from_value(json!({})).unwrap();
+        assert!(denied(create_backup(State(state.clone()), caller("user:bob"), Path(id.clone()), Json(backup)).await));
+        assert!(denied(get_backup(State(state.clone()), caller("user:bob"), Path((id.clone(), "b1".to_string()))).await));
+        assert!(denied(get_connection_schema(
+            State(state.clone()),
+            caller("user:bob"),
+            Path(id.clone()),
+            Query(SchemaQuery { refresh: false }),
+        )
+        .await));
+
+        let rotate = serde_json::from_value(json!({ "password": "stolen" })).unwrap();
+        assert!(denied(rotate_connection_password(State(state.clone()), caller("user:bob"), Path(id.clone()), Json(rotate)).await));
+        assert_eq!(state.revisions.list(&id).await.unwrap().len(), 1);
+
+        // 管理员为该连接签发的访客链接仍可查询
+        let link = serde_json::from_value(json!({ "name": "on-call" })).unwrap();
+        let guest = state.api_keys.create_guest_link(&id, link).await.unwrap().api_key.principal();
+        query_on_behalf(&state, Some(&guest), &id, &query("SELECT 1")).await.unwrap();
+
+        // query-service 读取连接信息时同样按主体检查归属，缓存的结果不会交给其他主体
+        assert!(denied(pool_info_on_behalf(&state, Some("user:bob"), &id).await));
+        assert_eq!(pool_info_on_behalf(&state, Some("user:alice"), &id).await.unwrap().id, id);
+        assert_eq!(pool_info_on_behalf(&state, Some(&guest), &id).await.unwrap().id, id);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn hides_jobs_on_connections_of_other_principals() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = state(&dir).await;
+        let mut ids = Vec::new();
+        for (name, owner) in [("app", "user:alice"), ("scratch", "user:bob")] {
+            let req = serde_json::from_value(json!({
+                "name": name,
+                "db_type": "sqlite",
+                "file_path": dir.join(format!("{}.db", name)).display().to_string(),
+            }))
+            .unwrap();
+            let Json(created) = create_connection(State(state.clone()), caller(owner), Json(req)).await.unwrap();
+            ids.push(created.data.unwrap().id);
+        }
+        let (id, own) = (ids[0].clone(), ids[1].clone());
+
+        // 表结构对比的任一侧不可见都视为连接不存在
+        let diff = |source: &str, target: &str| {
+            serde_json::from_value::<SchemaDiffRequest>(json!({
+                "source": { "connection_id": source },
+                "target": { "connection_id": target },
+            }))
+            .unwrap()
+        };
+        assert!(denied(diff_schemas(State(state.clone()), caller("user:bob"), Json(diff(&id, &own))).await));
+        assert!(denied(diff_schemas(State(state.clone()), caller("user:bob"), Json(diff(&own, &id))).await));
+        assert!(!denied(diff_schemas(State(state.clone()), caller("user:bob"), Json(diff(&own, &own))).await));
+
+        // 定时任务：不能在他人的连接上创建，也看不到、改不了他人的任务
+        let scheduled = |connection_id: &str| {
+            serde_json::from_value::<CreateScheduledJobRequest>(json!({
+                "name": "ping",
+                "connection_id": connection_id,
+                "cron": "0 0 * * *",
+                "kind": "health_check",
+                "params": null,
+            }))
+            .unwrap()
+        };
+        assert!(denied(create_scheduled_job(State(state.clone()), caller("user:bob"), Json(scheduled(&id))).await));
+        let Json(job) = create_scheduled_job(State(state.clone()), caller("user:alice"), Json(scheduled(&id))).await.unwrap();
+        let job_id = job.data.unwrap().id;
+        let Json(jobs) = list_scheduled_jobs(State(state.clone()), caller("user:bob")).await.unwrap();
+        assert!(jobs.data.unwrap().is_empty());
+        assert!(not_found(get_scheduled_job(State(state.clone()), caller("user:bob"), Path(job_id.clone())).await));
+        assert!(not_found(run_scheduled_job(State(state.clone()), caller("user:bob"), Path(job_id.clone())).await));
+        assert!(not_found(list_scheduled_job_runs(State(state.clone()), caller("user:bob"), Path(job_id.clone())).await));
+        assert!(not_found(disable_scheduled_job(State(state.clone()), caller("user:bob"), Path(job_id.clone())).await));
+        assert!(not_found(delete_scheduled_job(State(state.clone()), caller("user:bob"), Path(job_id.clone())).await));
+        let Json(jobs) = list_scheduled_jobs(State(state.clone()), caller("user:alice")).await.unwrap();
+        assert_eq!(jobs.data.unwrap().len(), 1);
+
+        // 通用任务：他人连接上的复制任务不出现在列表中，按 ID 也查不到
+        for ddl in ["CREATE TABLE t (id INTEGER PRIMARY KEY)", "CREATE TABLE t_copy (id INTEGER PRIMARY KEY)"] {
+            change_on_behalf(&state, Some("user:alice"), &id, &query(ddl)).await.unwrap();
+        }
+        let transfer = serde_json::from_value(json!({
+            "source_connection_id": id,
+            "source_table": "t",
+            "target_connection_id": id,
+            "target_table": "t_copy",
+        }))
+        .unwrap();
+        let transfer_id = state.transfers.start(transfer, None).await.unwrap().id;
+        let list = |principal: &str| {
+            let state = state.clone();
+            let principal = principal.to_string();
+            async move {
+                let query = JobListQuery { kind: None, limit: 100 };
+                let Json(jobs) = list_jobs(State(state), caller(&principal), Query(query)).await.unwrap();
+                jobs.data.unwrap().into_iter().map(|job| job.id).collect::<Vec<_>>()
+            }
+        };
+        assert!(list("user:alice").await.contains(&transfer_id));
+        assert!(!list("user:bob").await.contains(&transfer_id));
+        get_job(State(state.clone()), caller("user:alice"), Path(transfer_id.clone())).await.unwrap();
+        assert!(not_found(get_job(State(state.clone()), caller("user:bob"), Path(transfer_id.clone())).await));
+        assert!(not_found(cancel_job(State(state.clone()), caller("user:bob"), Path(transfer_id.clone())).await));
+        assert!(not_found(job_events(State(state.clone()), caller("user:bob"), Path(transfer_id.clone())).await));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn transfers_require_both_connections_and_respect_masking() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = state(&dir).await;
+        let mut ids = Vec::new();
+        for (name, owner) in [("app", "user:alice"), ("scratch", "user:bob")] {
+            let req = serde_json::from_value(json!({
+                "name": name,
+                "db_type": "sqlite",
+                "file_path": dir.join(format!("{}.db", name)).display().to_string(),
+            }))
+            .unwrap();
+            let Json(created) = create_connection(State(state.clone()), caller(owner), Json(req)).await.unwrap();
+            let id = created.data.unwrap().id;
+            for ddl in ["CREATE TABLE t (id INTEGER PRIMARY KEY, email TEXT)", "CREATE TABLE t_copy (id INTEGER PRIMARY KEY, email TEXT)"] {
+                change_on_behalf(&state, Some(owner), &id, &query(ddl)).await.unwrap();
+            }
+            ids.push(id);
+        }
+        let (app, scratch) = (ids[0].clone(), ids[1].clone());
+        let transfer = |source: &str, target: &str| {
+            serde_json::from_value::<TransferRequest>(json!({
+                "source_connection_id": source,
+                "source_table": "t",
+                "target_connection_id": target,
+                "target_table": "t_copy",
+            }))
+            .unwrap()
+        };
+
+        // 源连接或目标连接不可见时按连接不存在处理
+        assert!(denied(start_transfer(State(state.clone()), caller("user:bob"), Json(transfer(&app, &scratch))).await));
+        assert!(denied(start_transfer(State(state.clone()), caller("user:bob"), Json(transfer(&scratch, &app))).await));
+
+        // 脱敏规则对调用方生效时不能复制被脱敏的列，豁免后可以
+        let masking = |exempt: &[&str]| {
+            serde_json::from_value::<ConnectionMasking>(json!({
+                "rules": [{ "pattern": "email", "strategy": "redact" }],
+                "exempt_principals": exempt,
+            }))
+            .unwrap()
+        };
+        set_connection_masking(State(state.clone()), caller("user:alice"), Path(app.clone()), Json(masking(&[])))
+            .await
+            .unwrap();
+        let result = start_transfer(State(state.clone()), caller("user:alice"), Json(transfer(&app, &app))).await;
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+        set_connection_masking(State(state.clone()), caller("user:alice"), Path(app.clone()), Json(masking(&["user:alice"])))
+            .await
+            .unwrap();
+        let Json(job) = start_transfer(State(state.clone()), caller("user:alice"), Json(transfer(&app, &app))).await.unwrap();
+        let job_id = job.data.unwrap().id;
+
+        // 其他主体看不到、取消不了该任务
+        let Json(jobs) = list_transfers(State(state.clone()), caller("user:bob")).await.unwrap();
+        assert!(jobs.data.unwrap().is_empty());
+        assert!(not_found(get_transfer(State(state.clone()), caller("user:bob"), Path(job_id.clone())).await));
+        assert!(not_found(cancel_transfer(State(state.clone()), caller("user:bob"), Path(job_id.clone())).await));
+        let Json(jobs) = list_transfers(State(state.clone()), caller("user:alice")).await.unwrap();
+        assert_eq!(jobs.data.unwrap().len(), 1);
+        get_transfer(State(state.clone()), caller("user:alice"), Path(job_id)).await.unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn hides_snapshots_of_connections_of_other_principals() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = state(&dir).await;
+        let req = serde_json::from_value(json!({
+            "name": "app",
+            "db_type": "sqlite",
+            "file_path": dir.join("app.db").display().to_string(),
+        }))
+        .unwrap();
+        let Json(created) = create_connection(State(state.clone()), caller("user:alice"), Json(req)).await.unwrap();
+        let id = created.data.unwrap().id;
+
+        let mut snapshot_ids = Vec::new();
+        for _ in 0..2 {
+            let req = serde_json::from_value(json!({ "name": "daily", "connection_id": id, "sql": "SELECT 1 AS n" })).unwrap();
+            let Json(saved) = create_snapshot(State(state.clone()), caller("user:alice"), Json(req)).await.unwrap();
+            snapshot_ids.push(saved.data.unwrap().id);
+        }
+        let snapshot_id = snapshot_ids[0].clone();
+        let compare = || {
+            serde_json::from_value::<CompareSnapshotsRequest>(json!({
+                "left_id": snapshot_ids[0],
+                "right_id": snapshot_ids[1],
+                "key_columns": ["n"],
+            }))
+            .unwrap()
+        };
+        let list = |principal: &str| {
+            let state = state.clone();
+            let headers = caller(principal);
+            async move {
+                let query = SnapshotListQuery { name: None, connection_id: None };
+                let Json(snapshots) = list_snapshots(State(state), headers, Query(query)).await.unwrap();
+                snapshots.data.unwrap().len()
+            }
+        };
+
+        // 其他主体列不出、读不到、对比不了也删不掉快照
+        assert_eq!(list("user:bob").await, 0);
+        assert!(not_found(get_snapshot(State(state.clone()), caller("user:bob"), Path(snapshot_id.clone())).await));
+        assert!(not_found(compare_snapshots(State(state.clone()), caller("user:bob"), Json(compare())).await));
+        assert!(not_found(delete_snapshot(State(state.clone()), caller("user:bob"), Path(snapshot_id.clone())).await));
+
+        assert_eq!(list("user:alice").await, 2);
+        get_snapshot(State(state.clone()), caller("user:alice"), Path(snapshot_id.clone())).await.unwrap();
+        compare_snapshots(State(state.clone()), caller("user:alice"), Json(compare())).await.unwrap();
+        delete_snapshot(State(state.clone()), caller("user:alice"), Path(snapshot_id)).await.unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn hides_alert_rules_on_connections_of_other_principals() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = state(&dir).await;
+        let req = serde_json::from_value(json!({
+            "name": "app",
+            "db_type": "sqlite",
+            "file_path": dir.join("app.db").display().to_string(),
+        }))
+        .unwrap();
+        let Json(created) = create_connection(State(state.clone()), caller("user:alice"), Json(req)).await.unwrap();
+        let id = created.data.unwrap().id;
+        let rule = || {
+            serde_json::from_value::<CreateAlertRuleRequest>(json!({
+                "name": "backlog",
+                "connection_id": id,
+                "sql": "SELECT 1",
+                "cron": "*/5 * * * *",
+                "condition": { "subject": "first_cell", "operator": "gt", "threshold": 100 },
+                "channels": [{ "type": "webhook", "url": "https://ops.example.com/alerts" }],
+                "enabled": false,
+            }))
+            .unwrap()
+        };
+
+        // 不能在他人的连接上创建规则，也看不到、评估不了他人的规则
+        assert!(denied(create_alert_rule(State(state.clone()), caller("user:bob"), Json(rule())).await));
+        let Json(created) = create_alert_rule(State(state.clone()), caller("user:alice"), Json(rule())).await.unwrap();
+        let rule_id = created.data.unwrap().id;
+        let Json(rules) = list_alert_rules(State(state.clone()), caller("user:bob")).await.unwrap();
+        assert!(rules.data.unwrap().is_empty());
+        assert!(not_found(get_alert_rule(State(state.clone()), caller("user:bob"), Path(rule_id.clone())).await));
+        assert!(not_found(evaluate_alert_rule(State(state.clone()), caller("user:bob"), Path(rule_id.clone())).await));
+        assert!(not_found(enable_alert_rule(State(state.clone()), caller("user:bob"), Path(rule_id.clone())).await));
+        assert!(not_found(delete_alert_rule(State(state.clone()), caller("user:bob"), Path(rule_id.clone())).await));
+
+        let Json(rules) = list_alert_rules(State(state.clone()), caller("user:alice")).await.unwrap();
+        assert_eq!(rules.data.unwrap().len(), 1);
+        evaluate_alert_rule(State(state.clone()), caller("user:alice"), Path(rule_id)).await.unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn read_queries_reject_hidden_writes() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = state(&dir).await;
+        let req = serde_json::from_value(json!({
+            "name": "app",
+            "db_type": "sqlite",
+            "file_path": dir.join("app.db").display().to_string(),
+        }))
+        .unwrap();
+        let Json(created) = create_connection(State(state.clone()), caller("user:alice"), Json(req)).await.unwrap();
+        let id = created.data.unwrap().id;
+        change_on_behalf(&state, Some("user:alice"), &id, &query("CREATE TABLE t (id INTEGER PRIMARY KEY)")).await.unwrap();
+
+        // 注释开头的写语句与含写操作的 CTE 都不是只读查询
+        for sql in [
+            "/* x */ DELETE FROM t",
+            "-- note\nDELETE FROM t",
+            "WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d",
+            "SELECT 1; DELETE FROM t",
+        ] {
+            let result = query_on_behalf(&state, Some("user:alice"), &id, &query(sql)).await;
+            assert!(matches!(result, Err(AppError::UnsafeSql(_))), "{}", sql);
+        }
+        let count = query_on_behalf(&state, Some("user:alice"), &id, &query("/* rows */ SELECT COUNT(*) AS n FROM t"))
+            .await
+            .unwrap();
+        assert_eq!(count.rows[0][0], json!(0));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn rejects_secret_references_outside_the_allowed_paths() {
+        let dir = std::env::temp_dir().join(format!("handlers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = state(&dir).await;
+        for reference in ["env:VAULT_TOKEN", "env:DATABASE_URL", "vault:kv/dbm/prod#password"] {
+            let req = serde_json::from_value(json!({
+                "name": "app",
+                "dsn": "mysql://app@127.0.0.1:3306/shop",
+                "password_ref": reference,
+            }))
+            .unwrap();
+            let result = create_connection(State(state.clone()), caller("user:alice"), Json(req)).await;
+            assert!(matches!(result, Err(AppError::Validation(_))), "{}", reference);
+        }
+        assert!(connection_service(&state, &caller("user:alice")).list(viewer(&caller("user:alice"))).await.is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn pool_admin_endpoints_accept_signed_gateway_requests() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use common::admin::ADMIN_TOKEN_HEADER;
+        use common::middleware::{signature_middleware, RequestSigner, SignatureVerifier};
+        use tower::ServiceExt;
+
+        std::env::set_var("METADATA_ADMIN_TOKEN", "pool-admin");
+        let dir = std::env::temp_dir().join(format!("handlers-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state = state(&dir).await;
+        let req = serde_json::from_value(json!({
+            "name": "app",
+            "db_type": "sqlite",
+            "file_path": dir.join("app.db").display().to_string(),
+        }))
+        .unwrap();
+        let Json(created) = create_connection(State(state.clone()), caller("user:alice"), Json(req)).await.unwrap();
+        let id = created.data.unwrap().id;
+
+        let verifier = Arc::new(SignatureVerifier::new(Some("secret"), 300));
+        let app = crate::routes::router()
+            .layer(axum::middleware::from_fn_with_state(verifier, signature_middleware))
+            .with_state(state);
+        // 按调用方签名后转成 axum 请求，经签名中间件与路由送达处理器
+        let send = |caller: &str, method: reqwest::Method, path: String| {
+            let mut request = reqwest::Client::new()
+                .request(method.clone(), format!("http://connection-service{}", path))
+                .build()
+                .unwrap();
+            RequestSigner::new(caller, Some("secret")).sign(&mut request);
+            let mut builder = Request::builder().method(method.as_str()).uri(path).header(ADMIN_TOKEN_HEADER, "pool-admin");
+            for (name, value) in request.headers() {
+                builder = builder.header(name, value);
+            }
+            let app = app.clone();
+            async move { app.oneshot(builder.body(Body::empty()).unwrap()).await.unwrap().status() }
+        };
+
+        assert_eq!(send("gateway", reqwest::Method::GET, "/api/admin/pools".to_string()).await, StatusCode::OK);
+        assert_eq!(send("gateway", reqwest::Method::DELETE, format!("/api/admin/pools/{}", id)).await, StatusCode::OK);
+        // 连接信息接口仍只对 query-service 开放
+        let info = format!("/internal/pools/{}", id);
+        assert_eq!(send("gateway", reqwest::Method::GET, info.clone()).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(send("query-service", reqwest::Method::GET, info).await, StatusCode::OK);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
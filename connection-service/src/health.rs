@@ -0,0 +1,234 @@
+//! Target database health checks.
+//!
+//! A background task periodically checks every connection with an open pool
+//! and marks it degraded when the server is close to its connection limit or,
+//! for replicas, when replication is stopped or lagging. The latest result is
+//! kept in memory and published through the internal pool info, so
+//! query-service can keep heavy queries away from degraded targets. Status
+//! changes are published as `connection.health_changed` events.
+//!
+//! Configuration:
+//! - `HEALTH_CHECK_INTERVAL_SECS` - check interval (default: 30)
+//! - `HEALTH_MAX_CONNECTION_USAGE` - connection usage fraction marking a target degraded (default: 0.9)
+//! - `HEALTH_MAX_REPLICATION_LAG_SECS` - replication lag marking a replica degraded (default: 300)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::{MySqlPool, PgPool, Row};
+use tokio::sync::RwLock;
+
+use common::errors::AppResult;
+use common::notify::{Notification, Notifier};
+use common::models::monitor::{TargetHealth, TargetHealthStatus};
+use dbm_core::DatabasePool;
+use dbm_core::drivers::mysql::row_opt_string;
+use crate::pool_manager::PoolManager;
+
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+const DEFAULT_MAX_CONNECTION_USAGE: f64 = 0.9;
+const DEFAULT_MAX_REPLICATION_LAG_SECS: u64 = 300;
+
+/// Replication state of a replica.
+struct ReplicationState {
+    running: bool,
+    lag_secs: Option<u64>,
+}
+
+/// Checks target databases and keeps the latest health per connection.
+pub struct HealthMonitor {
+    pool_manager: Arc<PoolManager>,
+    notifier: Arc<Notifier>,
+    interval: Duration,
+    max_connection_usage: f64,
+    max_replication_lag_secs: u64,
+    latest: RwLock<HashMap<String, TargetHealth>>,
+}
+
+impl HealthMonitor {
+    /// Creates the monitor, reading thresholds from the environment.
+    pub fn new(pool_manager: Arc<PoolManager>, notifier: Arc<Notifier>) -> Self {
+        fn env<T: std::str::FromStr>(key: &str, default: T) -> T {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            pool_manager,
+            notifier,
+            interval: Duration::from_secs(env("HEALTH_CHECK_INTERVAL_SECS", DEFAULT_INTERVAL_SECS).max(1)),
+            max_connection_usage: env("HEALTH_MAX_CONNECTION_USAGE", DEFAULT_MAX_CONNECTION_USAGE),
+            max_replication_lag_secs: env("HEALTH_MAX_REPLICATION_LAG_SECS", DEFAULT_MAX_REPLICATION_LAG_SECS),
+            latest: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Starts the periodic check task.
+    pub fn spawn(self: &Arc<Self>) {
+        let monitor = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(monitor.interval);
+            loop {
+                interval.tick().await;
+                for id in monitor.pool_manager.pooled_connection_ids().await {
+                    monitor.check(&id).await;
+                }
+            }
+        });
+    }
+
+    /// Returns the latest health of a connection, if it was checked.
+    pub async fn latest(&self, connection_id: &str) -> Option<TargetHealth> {
+        self.latest.read().await.get(connection_id).cloned()
+    }
+
+    /// Forgets the health of a removed connection.
+    pub async fn forget(&self, connection_id: &str) {
+        self.latest.write().await.remove(connection_id);
+    }
+
+    /// Checks a connection now and stores the result; a changed status (or
+    /// a first result that is not healthy) is published as an event.
+    pub async fn check(&self, connection_id: &str) -> TargetHealth {
+        let health = self.evaluate(connection_id).await;
+        if health.is_degraded() {
+            tracing::warn!(connection_id = %connection_id, reasons = ?health.reasons, "Target database degraded");
+        }
+        let previous = self
+            .latest
+            .write()
+            .await
+            .insert(connection_id.to_string(), health.clone())
+            .map_or(TargetHealthStatus::Healthy, |h| h.status);
+        if previous != health.status {
+            self.notifier.publish(Notification::new(
+                "connection.health_changed",
+                format!(
+                    "Connection {} is {} (was {})",
+                    connection_id,
+                    health.status.as_str(),
+                    previous.as_str()
+                ),
+                health.reasons.join("\n"),
+                serde_json::to_value(&health).unwrap_or_default(),
+            ));
+        }
+        health
+    }
+
+    async fn evaluate(&self, connection_id: &str) -> TargetHealth {
+        let mut health = TargetHealth {
+            connection_id: connection_id.to_string(),
+            status: TargetHealthStatus::Healthy,
+            reasons: Vec::new(),
+            connection_usage: None,
+            replication_running: None,
+            replication_lag_secs: None,
+            checked_at: Utc::now().to_rfc3339(),
+        };
+
+        let pool = match self.pool_manager.get_or_create_pool(connection_id).await {
+            Ok(pool) => pool,
+            Err(e) => return unknown(health, format!("connection unavailable: {}", e)),
+        };
+
+        match self.pool_manager.get_database_stats(connection_id).await {
+            Ok(stats) if stats.max_connections > 0 => {
+                let usage = f64::from(stats.active_connections) / f64::from(stats.max_connections);
+                health.connection_usage = Some(usage);
+                if usage >= self.max_connection_usage {
+                    health.reasons.push(format!(
+                        "{} of {} server connections in use",
+                        stats.active_connections, stats.max_connections
+                    ));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => return unknown(health, format!("statistics unavailable: {}", e)),
+        }
+
+        let replication = match &pool {
+            DatabasePool::MySQL(pool) => mysql_replication(pool).await,
+            DatabasePool::Postgres(pool) => postgres_replication(pool).await,
+            _ => Ok(None),
+        };
+        match replication {
+            Ok(Some(state)) => {
+                health.replication_running = Some(state.running);
+                health.replication_lag_secs = state.lag_secs;
+                if !state.running {
+                    health.reasons.push("replication is not running".to_string());
+                } else if let Some(lag) = state.lag_secs.filter(|lag| *lag > self.max_replication_lag_secs) {
+                    health.reasons.push(format!("replication lag {} s", lag));
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::debug!(connection_id = %connection_id, error = %e, "Replication status unavailable"),
+        }
+
+        if !health.reasons.is_empty() {
+            health.status = TargetHealthStatus::Degraded;
+        }
+        health
+    }
+}
+
+fn unknown(mut health: TargetHealth, reason: String) -> TargetHealth {
+    health.status = TargetHealthStatus::Unknown;
+    health.reasons.push(reason);
+    health
+}
+
+/// Reads the replica status; `None` if the server is not a replica.
+async fn mysql_replication(pool: &MySqlPool) -> AppResult<Option<ReplicationState>> {
+    // SHOW REPLICA STATUS needs MySQL 8.0.22+; older servers only know the SLAVE form.
+    let row = match sqlx::query("SHOW REPLICA STATUS").fetch_optional(pool).await {
+        Ok(row) => row,
+        Err(_) => sqlx::query("SHOW SLAVE STATUS").fetch_optional(pool).await?,
+    };
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let column = |new: &str, old: &str| {
+        row_opt_string(&row, new).or_else(|| row_opt_string(&row, old))
+    };
+    let io_running = column("Replica_IO_Running", "Slave_IO_Running");
+    let sql_running = column("Replica_SQL_Running", "Slave_SQL_Running");
+    let lag_secs = row
+        .try_get::<Option<u64>, _>("Seconds_Behind_Source")
+        .or_else(|_| row.try_get::<Option<u64>, _>("Seconds_Behind_Master"))
+        .ok()
+        .flatten();
+
+    Ok(Some(ReplicationState {
+        running: io_running.as_deref() == Some("Yes") && sql_running.as_deref() == Some("Yes"),
+        lag_secs,
+    }))
+}
+
+/// Reads the standby status; `None` if the server is not in recovery.
+async fn postgres_replication(pool: &PgPool) -> AppResult<Option<ReplicationState>> {
+    let in_recovery: bool = sqlx::query_scalar("SELECT pg_is_in_recovery()").fetch_one(pool).await?;
+    if !in_recovery {
+        return Ok(None);
+    }
+
+    let receivers: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pg_stat_wal_receiver WHERE status = 'streaming'")
+        .fetch_one(pool)
+        .await?;
+    let lag_secs: Option<f64> = sqlx::query_scalar(
+        "SELECT EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp())::float8",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Some(ReplicationState {
+        running: receivers > 0,
+        lag_secs: lag_secs.map(|lag| lag.max(0.0) as u64),
+    }))
+}
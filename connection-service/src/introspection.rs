@@ -0,0 +1,308 @@
+//! Detailed schema introspection.
+//!
+//! Loads tables, columns, indexes and foreign keys of a MySQL database or a
+//! PostgreSQL schema into [`TableDef`] snapshots.
+
+use std::collections::BTreeMap;
+
+use sqlx::{MySqlPool, PgPool, Row};
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::{ConnectionConfig, DbType};
+use common::models::schema_diff::{ColumnDef, ForeignKeyDef, IndexDef, TableDef};
+use dbm_core::DatabasePool;
+use dbm_core::drivers::mysql::{row_opt_string, row_string};
+
+/// Loads a schema snapshot from a connection pool.
+///
+/// `schema` is the MySQL database name or the PostgreSQL schema name.
+pub async fn load_schema(pool: &DatabasePool, schema: &str) -> AppResult<Vec<TableDef>> {
+    match pool {
+        DatabasePool::MySQL(p) => load_mysql_schema(p, schema).await,
+        DatabasePool::Postgres(p) => load_postgres_schema(p, schema).await,
+        _ => Err(AppError::UnsupportedDatabaseType(
+            "Schema introspection is only supported for MySQL and PostgreSQL".into(),
+        )),
+    }
+}
+
+/// Resolves the MySQL database / PostgreSQL schema to operate on.
+///
+/// Falls back to the connection's database on MySQL and to "public" on PostgreSQL.
+pub fn resolve_schema(config: &ConnectionConfig, requested: Option<&str>) -> AppResult<String> {
+    match (requested, &config.db_type) {
+        (Some(db), _) if !db.is_empty() => Ok(db.to_string()),
+        (_, DbType::Postgres) => Ok("public".to_string()),
+        _ => config
+            .database
+            .clone()
+            .filter(|d| !d.is_empty())
+            .ok_or_else(|| AppError::InvalidInput(format!("database is required for connection {}", config.id))),
+    }
+}
+
+/// Accumulates rows into tables keyed by name (sorted).
+#[derive(Default)]
+struct SchemaBuilder {
+    tables: BTreeMap<String, TableDef>,
+}
+
+impl SchemaBuilder {
+    fn table(&mut self, name: &str) -> &mut TableDef {
+        self.tables
+            .entry(name.to_string())
+            .or_insert_with(|| TableDef {
+                name: name.to_string(),
+                columns: vec![],
+                indexes: vec![],
+                foreign_keys: vec![],
+            })
+    }
+
+    fn add_index_column(&mut self, table: &str, index: &str, column: String, unique: bool, primary: bool) {
+        // Only tables that have columns are real tables (skip views etc.)
+        let Some(t) = self.tables.get_mut(table) else {
+            return;
+        };
+        match t.indexes.iter_mut().find(|i| i.name == index) {
+            Some(i) => i.columns.push(column),
+            None => t.indexes.push(IndexDef {
+                name: index.to_string(),
+                columns: vec![column],
+                unique,
+                primary,
+            }),
+        }
+    }
+
+    fn add_fk_column(&mut self, table: &str, name: &str, column: String, ref_table: String, ref_column: String) {
+        let Some(t) = self.tables.get_mut(table) else {
+            return;
+        };
+        match t.foreign_keys.iter_mut().find(|f| f.name == name) {
+            Some(f) => {
+                f.columns.push(column);
+                f.referenced_columns.push(ref_column);
+            }
+            None => t.foreign_keys.push(ForeignKeyDef {
+                name: name.to_string(),
+                columns: vec![column],
+                referenced_table: ref_table,
+                referenced_columns: vec![ref_column],
+            }),
+        }
+    }
+
+    fn build(self) -> Vec<TableDef> {
+        self.tables.into_values().collect()
+    }
+}
+
+// ============== MySQL ==============
+
+async fn load_mysql_schema(pool: &MySqlPool, database: &str) -> AppResult<Vec<TableDef>> {
+    let mut builder = SchemaBuilder::default();
+
+    let columns = sqlx::query(
+        "SELECT c.TABLE_NAME, c.COLUMN_NAME, c.COLUMN_TYPE, c.IS_NULLABLE, c.COLUMN_DEFAULT, c.EXTRA
+         FROM information_schema.COLUMNS c
+         JOIN information_schema.TABLES t
+           ON t.TABLE_SCHEMA = c.TABLE_SCHEMA AND t.TABLE_NAME = c.TABLE_NAME
+         WHERE c.TABLE_SCHEMA = ? AND t.TABLE_TYPE = 'BASE TABLE'
+         ORDER BY c.TABLE_NAME, c.ORDINAL_POSITION",
+    )
+    .bind(database)
+    .fetch_all(pool)
+    .await?;
+
+    for row in &columns {
+        let table = row_string(row, "TABLE_NAME");
+        let extra = row_string(row, "EXTRA");
+        let data_type = row_string(row, "COLUMN_TYPE");
+        let default = row_opt_string(row, "COLUMN_DEFAULT")
+            .map(|d| mysql_default_expr(&d, &data_type, &extra));
+        builder.table(&table).columns.push(ColumnDef {
+            name: row_string(row, "COLUMN_NAME"),
+            data_type,
+            nullable: row_string(row, "IS_NULLABLE") == "YES",
+            default,
+            extra: Some(extra.to_lowercase()).filter(|e| !e.is_empty()),
+        });
+    }
+
+    let indexes = sqlx::query(
+        "SELECT TABLE_NAME, INDEX_NAME, CAST(NON_UNIQUE AS SIGNED) AS NON_UNIQUE, COLUMN_NAME
+         FROM information_schema.STATISTICS
+         WHERE TABLE_SCHEMA = ?
+         ORDER BY TABLE_NAME, INDEX_NAME, SEQ_IN_INDEX",
+    )
+    .bind(database)
+    .fetch_all(pool)
+    .await?;
+
+    for row in &indexes {
+        let table = row_string(row, "TABLE_NAME");
+        let index = row_string(row, "INDEX_NAME");
+        let non_unique = row.try_get::<i64, _>("NON_UNIQUE").unwrap_or(1);
+        let column = row_string(row, "COLUMN_NAME");
+        let primary = index == "PRIMARY";
+        builder.add_index_column(&table, &index, column, non_unique == 0, primary);
+    }
+
+    let fks = sqlx::query(
+        "SELECT TABLE_NAME, CONSTRAINT_NAME, COLUMN_NAME, REFERENCED_TABLE_NAME, REFERENCED_COLUMN_NAME
+         FROM information_schema.KEY_COLUMN_USAGE
+         WHERE TABLE_SCHEMA = ? AND REFERENCED_TABLE_NAME IS NOT NULL
+         ORDER BY TABLE_NAME, CONSTRAINT_NAME, ORDINAL_POSITION",
+    )
+    .bind(database)
+    .fetch_all(pool)
+    .await?;
+
+    for row in &fks {
+        builder.add_fk_column(
+            &row_string(row, "TABLE_NAME"),
+            &row_string(row, "CONSTRAINT_NAME"),
+            row_string(row, "COLUMN_NAME"),
+            row_string(row, "REFERENCED_TABLE_NAME"),
+            row_string(row, "REFERENCED_COLUMN_NAME"),
+        );
+    }
+
+    Ok(builder.build())
+}
+
+/// Converts a MySQL `COLUMN_DEFAULT` value into a SQL expression.
+///
+/// information_schema reports string defaults unquoted, so literals are quoted
+/// unless the column is numeric or the default is an expression.
+fn mysql_default_expr(value: &str, data_type: &str, extra: &str) -> String {
+    let upper = value.to_uppercase();
+    let numeric_type = [
+        "int", "tinyint", "smallint", "mediumint", "bigint", "decimal", "float", "double", "bit",
+    ]
+    .iter()
+    .any(|t| data_type.to_lowercase().starts_with(t));
+    let is_expression = extra.to_uppercase().contains("DEFAULT_GENERATED")
+        || upper == "NULL"
+        || upper.starts_with("CURRENT_TIMESTAMP")
+        || upper.starts_with("NOW(")
+        || (value.starts_with('(') && value.ends_with(')'));
+
+    if is_expression || (numeric_type && value.parse::<f64>().is_ok()) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+}
+
+// ============== PostgreSQL ==============
+
+async fn load_postgres_schema(pool: &PgPool, schema: &str) -> AppResult<Vec<TableDef>> {
+    let mut builder = SchemaBuilder::default();
+
+    let columns = sqlx::query(
+        "SELECT c.relname AS table_name, a.attname AS column_name,
+                format_type(a.atttypid, a.atttypmod) AS data_type,
+                NOT a.attnotnull AS nullable,
+                pg_get_expr(d.adbin, d.adrelid) AS column_default,
+                CASE WHEN a.attidentity <> '' THEN 'identity' ELSE NULL END AS extra
+         FROM pg_attribute a
+         JOIN pg_class c ON c.oid = a.attrelid
+         JOIN pg_namespace n ON n.oid = c.relnamespace
+         LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+         WHERE n.nspname = $1 AND c.relkind IN ('r', 'p') AND a.attnum > 0 AND NOT a.attisdropped
+         ORDER BY c.relname, a.attnum",
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    for row in &columns {
+        let table: String = row.try_get("table_name").unwrap_or_default();
+        builder.table(&table).columns.push(ColumnDef {
+            name: row.try_get("column_name").unwrap_or_default(),
+            data_type: row.try_get("data_type").unwrap_or_default(),
+            nullable: row.try_get("nullable").unwrap_or(true),
+            default: row.try_get::<Option<String>, _>("column_default").unwrap_or(None),
+            extra: row.try_get::<Option<String>, _>("extra").unwrap_or(None),
+        });
+    }
+
+    let indexes = sqlx::query(
+        "SELECT t.relname AS table_name, i.relname AS index_name,
+                ix.indisunique AS is_unique, ix.indisprimary AS is_primary,
+                a.attname AS column_name
+         FROM pg_index ix
+         JOIN pg_class t ON t.oid = ix.indrelid
+         JOIN pg_class i ON i.oid = ix.indexrelid
+         JOIN pg_namespace n ON n.oid = t.relnamespace
+         JOIN LATERAL unnest(ix.indkey) WITH ORDINALITY AS k(attnum, ord) ON true
+         JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum
+         WHERE n.nspname = $1
+         ORDER BY t.relname, i.relname, k.ord",
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    for row in &indexes {
+        let table: String = row.try_get("table_name").unwrap_or_default();
+        let index: String = row.try_get("index_name").unwrap_or_default();
+        builder.add_index_column(
+            &table,
+            &index,
+            row.try_get("column_name").unwrap_or_default(),
+            row.try_get("is_unique").unwrap_or(false),
+            row.try_get("is_primary").unwrap_or(false),
+        );
+    }
+
+    let fks = sqlx::query(
+        "SELECT cl.relname AS table_name, con.conname AS constraint_name,
+                a.attname AS column_name, rcl.relname AS referenced_table,
+                ra.attname AS referenced_column
+         FROM pg_constraint con
+         JOIN pg_class cl ON cl.oid = con.conrelid
+         JOIN pg_namespace n ON n.oid = cl.relnamespace
+         JOIN pg_class rcl ON rcl.oid = con.confrelid
+         JOIN LATERAL unnest(con.conkey, con.confkey) WITH ORDINALITY AS k(attnum, refnum, ord) ON true
+         JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+         JOIN pg_attribute ra ON ra.attrelid = con.confrelid AND ra.attnum = k.refnum
+         WHERE con.contype = 'f' AND n.nspname = $1
+         ORDER BY cl.relname, con.conname, k.ord",
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    for row in &fks {
+        let table: String = row.try_get("table_name").unwrap_or_default();
+        let name: String = row.try_get("constraint_name").unwrap_or_default();
+        builder.add_fk_column(
+            &table,
+            &name,
+            row.try_get("column_name").unwrap_or_default(),
+            row.try_get("referenced_table").unwrap_or_default(),
+            row.try_get("referenced_column").unwrap_or_default(),
+        );
+    }
+
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_mysql_string_defaults() {
+        assert_eq!(mysql_default_expr("abc", "varchar(10)", ""), "'abc'");
+        assert_eq!(mysql_default_expr("0", "int", ""), "0");
+        assert_eq!(
+            mysql_default_expr("CURRENT_TIMESTAMP", "datetime", "DEFAULT_GENERATED"),
+            "CURRENT_TIMESTAMP"
+        );
+        assert_eq!(mysql_default_expr("it's", "text", ""), "'it''s'");
+    }
+}
@@ -0,0 +1,390 @@
+//! Brute-force protection for password logins.
+//!
+//! Failed logins are counted per username (`user:<username>`) and per client
+//! address (`ip:<address>`). Once a key reaches the failure threshold, logins
+//! for it are refused for a lockout that doubles with every further failure,
+//! up to a maximum. Counts are forgotten after a quiet window without
+//! failures; a successful login clears the username's count, but not the
+//! address's, so one valid account cannot reset an attacker's address.
+//!
+//! Unknown usernames are counted like known ones, so lockouts do not reveal
+//! which users exist. Counts live in Redis when configured, so every instance
+//! shares them, and in memory otherwise. If Redis fails, logins are not
+//! throttled rather than refused.
+//!
+//! Configuration:
+//! - `LOGIN_THROTTLE_REDIS_URL` (falls back to `REDIS_URL`) - in-memory counts if unset
+//! - `LOGIN_MAX_FAILURES` - failures before the first lockout (default: 5)
+//! - `LOGIN_LOCKOUT_BASE_SECS` - first lockout (default: 30)
+//! - `LOGIN_LOCKOUT_MAX_SECS` - longest lockout (default: 3600)
+//! - `LOGIN_FAILURE_WINDOW_SECS` - quiet period after which counts are forgotten (default: 3600)
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use redis::aio::ConnectionManager;
+use tokio::sync::Mutex;
+
+use common::errors::{AppError, AppResult};
+use common::models::auth::{LoginLocked, LoginLockout};
+
+const KEY_PREFIX: &str = "dbm:login:";
+const DEFAULT_MAX_FAILURES: u32 = 5;
+const DEFAULT_LOCKOUT_BASE_SECS: u64 = 30;
+const DEFAULT_LOCKOUT_MAX_SECS: u64 = 3600;
+const DEFAULT_FAILURE_WINDOW_SECS: u64 = 3600;
+
+/// In-memory entries kept before expired ones are pruned.
+const MAX_MEMORY_ENTRIES: usize = 10_000;
+
+/// Failed attempts recorded for one key.
+#[derive(Debug, Clone)]
+struct Attempts {
+    failures: u32,
+    last_failure_at: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+impl Attempts {
+    fn lockout(&self, key: &str) -> LoginLockout {
+        LoginLockout {
+            key: key.to_string(),
+            failures: self.failures,
+            last_failure_at: self.last_failure_at,
+            locked_until: self.locked_until,
+        }
+    }
+}
+
+enum Backend {
+    Redis(ConnectionManager),
+    Memory(Mutex<HashMap<String, Attempts>>),
+}
+
+/// Counts failed logins and locks out usernames and addresses.
+pub struct LoginThrottle {
+    backend: Backend,
+    max_failures: u32,
+    lockout_base_secs: u64,
+    lockout_max_secs: u64,
+    window: ChronoDuration,
+}
+
+impl LoginThrottle {
+    /// Creates the throttle, using Redis when configured and reachable.
+    pub async fn new() -> Self {
+        let env = |key: &str, default: u64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        let url = std::env::var("LOGIN_THROTTLE_REDIS_URL")
+            .or_else(|_| std::env::var("REDIS_URL"))
+            .ok()
+            .filter(|u| !u.is_empty());
+        let backend = match url {
+            Some(url) => match connect(&url).await {
+                Ok(manager) => {
+                    tracing::info!("Login throttle using Redis");
+                    Backend::Redis(manager)
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Login throttle using memory: Redis unavailable");
+                    Backend::Memory(Mutex::new(HashMap::new()))
+                }
+            },
+            None => Backend::Memory(Mutex::new(HashMap::new())),
+        };
+
+        Self {
+            backend,
+            max_failures: env("LOGIN_MAX_FAILURES", DEFAULT_MAX_FAILURES as u64).max(1) as u32,
+            lockout_base_secs: env("LOGIN_LOCKOUT_BASE_SECS", DEFAULT_LOCKOUT_BASE_SECS),
+            lockout_max_secs: env("LOGIN_LOCKOUT_MAX_SECS", DEFAULT_LOCKOUT_MAX_SECS),
+            window: ChronoDuration::seconds(env("LOGIN_FAILURE_WINDOW_SECS", DEFAULT_FAILURE_WINDOW_SECS) as i64),
+        }
+    }
+
+    /// Throttle key for a username.
+    pub fn user_key(username: &str) -> String {
+        format!("user:{}", username)
+    }
+
+    /// Throttle key for a client address.
+    pub fn ip_key(ip_address: &str) -> String {
+        format!("ip:{}", ip_address)
+    }
+
+    /// Refuses the login while any of the keys is locked out.
+    ///
+    /// # Errors
+    /// Returns `AppError::LoginLocked` with the latest lockout end among the keys.
+    pub async fn check(&self, keys: &[String]) -> AppResult<()> {
+        let now = Utc::now();
+        let mut locked_until: Option<DateTime<Utc>> = None;
+        for key in keys {
+            if let Some(until) = self.get(key).await.and_then(|a| a.locked_until) {
+                if until > now && locked_until.is_none_or(|current| until > current) {
+                    locked_until = Some(until);
+                }
+            }
+        }
+        match locked_until {
+            Some(until) => Err(AppError::LoginLocked(Box::new(LoginLocked {
+                retry_after_secs: (until - now).num_seconds().max(1) as u64,
+                locked_until: until,
+            }))),
+            None => Ok(()),
+        }
+    }
+
+    /// Records a failed login for each key, locking out keys over the threshold.
+    pub async fn record_failure(&self, keys: &[String]) {
+        let now = Utc::now();
+        for key in keys {
+            let previous = self.get(key).await.map(|a| a.failures).unwrap_or(0);
+            let failures = previous.saturating_add(1);
+            let locked_until = lockout_secs(failures, self.max_failures, self.lockout_base_secs, self.lockout_max_secs)
+                .map(|secs| now + ChronoDuration::seconds(secs as i64));
+            if let Some(until) = locked_until {
+                tracing::warn!(key = %key, failures, locked_until = %until, "Login locked out");
+            }
+            self.put(
+                key,
+                Attempts {
+                    failures,
+                    last_failure_at: now,
+                    locked_until,
+                },
+            )
+            .await;
+        }
+    }
+
+    /// Lists keys with recorded failures, locked-out keys first.
+    pub async fn list(&self) -> AppResult<Vec<LoginLockout>> {
+        let now = Utc::now();
+        let mut lockouts: Vec<LoginLockout> = match &self.backend {
+            Backend::Redis(redis) => {
+                let mut redis = redis.clone();
+                let mut keys: Vec<String> = Vec::new();
+                let mut cursor: u64 = 0;
+                loop {
+                    let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                        .arg(cursor)
+                        .arg("MATCH")
+                        .arg(format!("{}*", KEY_PREFIX))
+                        .arg("COUNT")
+                        .arg(500)
+                        .query_async(&mut redis)
+                        .await
+                        .map_err(|e| AppError::ExternalService(format!("Redis error: {}", e)))?;
+                    keys.extend(batch);
+                    if next == 0 {
+                        break;
+                    }
+                    cursor = next;
+                }
+                let mut lockouts = Vec::with_capacity(keys.len());
+                for redis_key in keys {
+                    let key = redis_key.trim_start_matches(KEY_PREFIX);
+                    if let Some(attempts) = self.get(key).await {
+                        lockouts.push(attempts.lockout(key));
+                    }
+                }
+                lockouts
+            }
+            Backend::Memory(entries) => entries
+                .lock()
+                .await
+                .iter()
+                .filter(|(_, a)| !self.expired(a, now))
+                .map(|(key, a)| a.lockout(key))
+                .collect(),
+        };
+        lockouts.sort_by(|a, b| {
+            let locked = |l: &LoginLockout| l.locked_until.is_some_and(|until| until > now);
+            locked(b)
+                .cmp(&locked(a))
+                .then(b.last_failure_at.cmp(&a.last_failure_at))
+        });
+        Ok(lockouts)
+    }
+
+    /// Forgets the failures of a key, lifting any lockout. Returns whether it had any.
+    pub async fn clear(&self, key: &str) -> AppResult<bool> {
+        match &self.backend {
+            Backend::Redis(redis) => {
+                let removed: u32 = redis::cmd("DEL")
+                    .arg(redis_key(key))
+                    .query_async(&mut redis.clone())
+                    .await
+                    .map_err(|e| AppError::ExternalService(format!("Redis error: {}", e)))?;
+                Ok(removed > 0)
+            }
+            Backend::Memory(entries) => Ok(entries.lock().await.remove(key).is_some()),
+        }
+    }
+
+    /// Current attempts for a key; `None` if there are none or they expired.
+    async fn get(&self, key: &str) -> Option<Attempts> {
+        match &self.backend {
+            Backend::Redis(redis) => {
+                let fields: HashMap<String, i64> = match redis::cmd("HGETALL")
+                    .arg(redis_key(key))
+                    .query_async(&mut redis.clone())
+                    .await
+                {
+                    Ok(fields) => fields,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Login throttle Redis read failed");
+                        return None;
+                    }
+                };
+                let failures = *fields.get("failures")?;
+                let last_failure_at = DateTime::from_timestamp(*fields.get("last_failure_at")?, 0)?;
+                let locked_until = fields
+                    .get("locked_until")
+                    .and_then(|ts| DateTime::from_timestamp(*ts, 0));
+                Some(Attempts {
+                    failures: failures.max(0) as u32,
+                    last_failure_at,
+                    locked_until,
+                })
+            }
+            Backend::Memory(entries) => {
+                let now = Utc::now();
+                entries
+                    .lock()
+                    .await
+                    .get(key)
+                    .filter(|a| !self.expired(a, now))
+                    .cloned()
+            }
+        }
+    }
+
+    async fn put(&self, key: &str, attempts: Attempts) {
+        match &self.backend {
+            Backend::Redis(redis) => {
+                // Kept for the quiet window, or until the lockout ends if that is later
+                let mut ttl = self.window;
+                if let Some(until) = attempts.locked_until {
+                    ttl = ttl.max(until - attempts.last_failure_at);
+                }
+                let redis_key = redis_key(key);
+                let mut pipe = redis::pipe();
+                pipe.atomic()
+                    .cmd("DEL")
+                    .arg(&redis_key)
+                    .ignore()
+                    .cmd("HSET")
+                    .arg(&redis_key)
+                    .arg("failures")
+                    .arg(attempts.failures)
+                    .arg("last_failure_at")
+                    .arg(attempts.last_failure_at.timestamp())
+                    .ignore();
+                if let Some(until) = attempts.locked_until {
+                    pipe.cmd("HSET")
+                        .arg(&redis_key)
+                        .arg("locked_until")
+                        .arg(until.timestamp())
+                        .ignore();
+                }
+                pipe.cmd("EXPIRE")
+                    .arg(&redis_key)
+                    .arg(ttl.num_seconds().max(1))
+                    .ignore();
+                if let Err(e) = pipe.query_async::<()>(&mut redis.clone()).await {
+                    tracing::warn!(error = %e, "Login throttle Redis write failed");
+                }
+            }
+            Backend::Memory(entries) => {
+                let now = Utc::now();
+                let mut entries = entries.lock().await;
+                if entries.len() >= MAX_MEMORY_ENTRIES {
+                    entries.retain(|_, a| !self.expired(a, now));
+                    if entries.len() >= MAX_MEMORY_ENTRIES {
+                        // Keep lockouts in force; drop counts below the threshold
+                        entries.retain(|_, a| a.locked_until.is_some_and(|until| until > now));
+                    }
+                }
+                entries.insert(key.to_string(), attempts);
+            }
+        }
+    }
+
+    /// Whether the attempts are past the quiet window and any lockout ended.
+    fn expired(&self, attempts: &Attempts, now: DateTime<Utc>) -> bool {
+        attempts.last_failure_at + self.window <= now
+            && attempts.locked_until.is_none_or(|until| until <= now)
+    }
+}
+
+/// Lockout after the given number of consecutive failures: none below
+/// `max_failures`, then `base_secs` doubling with each further failure, capped
+/// at `max_secs`.
+fn lockout_secs(failures: u32, max_failures: u32, base_secs: u64, max_secs: u64) -> Option<u64> {
+    if failures < max_failures || base_secs == 0 {
+        return None;
+    }
+    let doublings = failures - max_failures;
+    let secs = 1u64
+        .checked_shl(doublings)
+        .and_then(|factor| base_secs.checked_mul(factor))
+        .unwrap_or(u64::MAX);
+    Some(secs.min(max_secs.max(base_secs)))
+}
+
+async fn connect(url: &str) -> redis::RedisResult<ConnectionManager> {
+    let client = redis::Client::open(url)?;
+    ConnectionManager::new(client).await
+}
+
+fn redis_key(key: &str) -> String {
+    format!("{}{}", KEY_PREFIX, key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockout_doubles_up_to_the_cap() {
+        assert_eq!(lockout_secs(4, 5, 30, 3600), None);
+        assert_eq!(lockout_secs(5, 5, 30, 3600), Some(30));
+        assert_eq!(lockout_secs(6, 5, 30, 3600), Some(60));
+        assert_eq!(lockout_secs(8, 5, 30, 3600), Some(240));
+        assert_eq!(lockout_secs(12, 5, 30, 3600), Some(3600));
+        assert_eq!(lockout_secs(200, 5, 30, 3600), Some(3600));
+        assert_eq!(lockout_secs(9, 5, 0, 3600), None);
+    }
+
+    #[tokio::test]
+    async fn memory_backend_locks_and_clears() {
+        let throttle = LoginThrottle {
+            backend: Backend::Memory(Mutex::new(HashMap::new())),
+            max_failures: 2,
+            lockout_base_secs: 30,
+            lockout_max_secs: 3600,
+            window: ChronoDuration::seconds(3600),
+        };
+        let keys = vec![LoginThrottle::user_key("alice"), LoginThrottle::ip_key("10.0.0.1")];
+
+        throttle.record_failure(&keys).await;
+        assert!(throttle.check(&keys).await.is_ok());
+        throttle.record_failure(&keys).await;
+        match throttle.check(&keys).await {
+            Err(AppError::LoginLocked(locked)) => assert!(locked.retry_after_secs <= 30),
+            other => panic!("expected lockout, got {:?}", other),
+        }
+        assert_eq!(throttle.list().await.unwrap().len(), 2);
+
+        assert!(throttle.clear("user:alice").await.unwrap());
+        assert!(throttle.check(&keys[..1]).await.is_ok());
+        assert!(throttle.check(&keys).await.is_err());
+    }
+}
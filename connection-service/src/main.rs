@@ -4,22 +4,65 @@
 //! - 数据库连接的增删改查
 //! - 连接池管理
 //! - 连接测试
+//! - 备份（逻辑转储 / mysqldump / pg_dump，本地目录或 S3）与恢复
+//! - 定时任务（cron 驱动的备份、健康检查、查询）
+//! - 跨连接数据复制（MySQL、PostgreSQL、SQLite 互相复制）
+//! - 供 query-service 调用的内部 gRPC 接口
 
+mod alert;
+mod api_keys;
+mod auth;
+mod autocomplete;
+mod backup;
+mod backup_storage;
+mod diagnostics;
+mod favorites;
+mod grpc;
+mod health;
+mod introspection;
+mod login_throttle;
+mod metadata;
+mod migrations;
+mod policy;
 mod pool_manager;
+mod pool_state;
+mod repository;
+mod restore;
+mod revisions;
 mod routes;
+mod sampling;
+mod scheduler;
+mod schema_cache;
+mod schema_change;
+mod schema_diff;
+mod schema_graph;
+mod seed;
 mod service;
+mod sessions;
+mod snapshot;
 mod state;
+mod table_stats;
+mod transfer;
+mod usage;
+mod warmup;
+mod workload;
+mod workspaces;
 mod handlers;
 
-use axum::{middleware, routing::get, Json, Router};
+use std::sync::Arc;
+
+use axum::{extract::DefaultBodyLimit, middleware, routing::get, Json, Router};
 use common::config::AppConfig;
+use common::fallback::with_json_fallbacks;
+use common::middleware::cors::cors_layer;
 use common::middleware::request_id::request_id_middleware;
+use common::middleware::signing::{signature_middleware, SignatureVerifier};
+use common::openapi::ResponseExamples;
 use state::AppState;
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::compression::CompressionLayer;
 use tower_http::trace::TraceLayer;
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::OpenApi;
 
 const SERVICE_NAME: &str = "connection-service";
@@ -38,70 +81,357 @@ const DEFAULT_PORT: u16 = 8081;
         handlers::get_connection,
         handlers::delete_connection,
         handlers::test_connection,
+        handlers::test_unsaved_connection,
+        handlers::set_connection_allowlist,
+        handlers::set_connection_masking,
+        handlers::set_connection_statement_policy,
+        handlers::set_connection_query_timeout,
+        handlers::set_connection_pinned,
+        handlers::set_connection_pool_options,
+        handlers::rotate_connection_password,
+        handlers::get_connection_history,
+        handlers::restore_connection_revision,
+        handlers::add_connection_favorite,
+        handlers::remove_connection_favorite,
         handlers::health_check,
+        handlers::healthz,
+        handlers::readyz,
+        handlers::readiness,
         handlers::get_pool_info,
+        handlers::list_pools,
+        handlers::reset_pool,
+        handlers::get_connection_health,
+        handlers::sample_table,
+        handlers::get_connection_workload,
+        handlers::list_schema_changes,
+        handlers::start_schema_change,
+        handlers::get_schema_change,
+        handlers::cutover_schema_change,
+        handlers::cancel_schema_change,
+        handlers::list_transfers,
+        handlers::start_transfer,
+        handlers::get_transfer,
+        handlers::cancel_transfer,
+        handlers::list_snapshots,
+        handlers::create_snapshot,
+        handlers::get_snapshot,
+        handlers::delete_snapshot,
+        handlers::compare_snapshots,
+        handlers::list_workspaces,
+        handlers::create_workspace,
+        handlers::get_workspace,
+        handlers::update_workspace,
+        handlers::delete_workspace,
+        handlers::add_workspace_member,
+        handlers::remove_workspace_member,
+        handlers::add_workspace_connection,
+        handlers::remove_workspace_connection,
+        handlers::add_workspace_snapshot,
+        handlers::remove_workspace_snapshot,
+        handlers::diff_schemas,
+        handlers::invalidate_schema_cache,
+        handlers::get_autocomplete,
+        handlers::invalidate_autocomplete,
+        handlers::get_schema_graph,
+        handlers::get_graph_labels,
+        handlers::get_graph_relationship_types,
+        handlers::get_key,
+        handlers::set_key,
+        handlers::delete_key,
+        handlers::get_table_stats,
+        handlers::seed_table,
+        handlers::list_backups,
+        handlers::create_backup,
+        handlers::get_backup,
+        handlers::download_backup,
+        handlers::start_restore,
+        handlers::list_restores,
+        handlers::get_restore,
+        handlers::list_jobs,
+        handlers::get_job,
+        handlers::cancel_job,
+        handlers::job_events,
+        handlers::list_scheduled_jobs,
+        handlers::create_scheduled_job,
+        handlers::get_scheduled_job,
+        handlers::delete_scheduled_job,
+        handlers::enable_scheduled_job,
+        handlers::disable_scheduled_job,
+        handlers::run_scheduled_job,
+        handlers::list_scheduled_job_runs,
+        handlers::list_alert_rules,
+        handlers::create_alert_rule,
+        handlers::get_alert_rule,
+        handlers::delete_alert_rule,
+        handlers::enable_alert_rule,
+        handlers::disable_alert_rule,
+        handlers::evaluate_alert_rule,
+        handlers::export_metadata,
+        handlers::import_metadata,
+        handlers::get_migrations,
+        handlers::create_api_key,
+        handlers::list_api_keys,
+        handlers::revoke_api_key,
+        handlers::create_guest_link,
+        handlers::list_guest_links,
+        handlers::revoke_guest_link,
+        handlers::verify_api_key,
+        handlers::list_policies,
+        handlers::create_policy,
+        handlers::get_policy,
+        handlers::update_policy,
+        handlers::delete_policy,
+        handlers::list_policy_decisions,
+        handlers::get_my_usage,
+        handlers::get_usage_report,
+        handlers::set_usage_quota,
+        handlers::delete_usage_quota,
+        handlers::list_my_sessions,
+        handlers::revoke_my_session,
+        handlers::revoke_other_sessions,
+        handlers::list_sessions,
+        handlers::revoke_session,
+        handlers::verify_session,
+        handlers::create_sso_session,
+        handlers::login,
+        handlers::refresh_token,
+        handlers::logout,
+        handlers::list_users,
+        handlers::set_user_password,
+        handlers::delete_user,
+        handlers::list_login_lockouts,
+        handlers::clear_login_lockout,
+        handlers::reload_config,
+        handlers::decide_authz,
     ),
     components(schemas(
         common::models::ConnectionConfig,
         common::models::ConnectionItem,
+        common::models::ConnectionRevision,
+        common::models::FieldChange,
+        common::models::RevisionAction,
+        common::models::ConnectionView,
+        common::models::ConnectionAllowlist,
+        common::models::ConnectionMasking,
+        common::models::MaskingRule,
+        common::models::MaskingStrategy,
+        common::models::StatementPolicy,
+        common::models::QueryTimeoutSettings,
+        common::models::PinnedSettings,
+        common::models::ConnectionPoolOptions,
+        common::models::RotatePasswordRequest,
+        common::models::StageResult,
+        common::models::TestStage,
+        common::models::StageStatus,
+        common::models::PoolState,
+        common::models::PoolStatus,
+        common::models::CachedPool,
+        common::models::ConnectionPoolStats,
+        common::models::WarmupStatus,
+        common::models::WarmupEntry,
+        common::models::WarmupState,
         common::models::CreateConnectionRequest,
         common::models::DbType,
-        handlers::ConnectionTestResult,
+        common::models::QueryResult,
+        common::models::TruncatedCell,
+        common::models::ValueKind,
+        common::models::ColumnInfo,
+        common::models::SampleRequest,
+        common::models::SampleResult,
+        common::models::SampleMethod,
+        common::models::SchemaChangeRequest,
+        common::models::SchemaChangeJob,
+        common::models::SchemaChangeStatus,
+        common::models::SchemaChangeStrategy,
+        common::models::TransferRequest,
+        common::models::TransferJob,
+        common::progress::ProgressEvent,
+        common::models::Job,
+        common::models::JobKind,
+        common::models::JobStatus,
+        common::models::TransferStatus,
+        common::models::CreateSnapshotRequest,
+        common::models::QuerySnapshot,
+        common::models::Workspace,
+        common::models::WorkspaceDetail,
+        common::models::WorkspaceMember,
+        common::models::WorkspaceRole,
+        common::models::CreateWorkspaceRequest,
+        common::models::UpdateWorkspaceRequest,
+        common::models::AddWorkspaceMemberRequest,
+        common::models::CompareSnapshotsRequest,
+        common::models::QueryDiffResult,
+        common::models::ChangedRow,
+        common::models::SchemaDiffRequest,
+        common::models::SchemaRef,
+        common::models::SchemaDiff,
+        common::models::TableDiff,
+        common::models::TableDef,
+        common::models::ColumnDef,
+        common::models::ColumnChange,
+        common::models::IndexDef,
+        common::models::ForeignKeyDef,
+        common::models::SchemaGraph,
+        common::models::GraphNode,
+        common::models::GraphColumn,
+        common::models::GraphEdge,
+        common::models::GraphElementType,
+        common::models::KeyValueEntry,
+        common::models::SetKeyValueRequest,
+        common::models::ValueEncoding,
+        common::models::AutocompleteCatalog,
+        common::models::AutocompleteTable,
+        common::models::AutocompleteColumn,
+        common::models::TableStats,
+        common::models::IndexStats,
+        common::models::SeedRequest,
+        common::models::SeedColumn,
+        common::models::SeedResult,
+        common::models::CreateBackupRequest,
+        common::models::BackupRecord,
+        common::models::BackupMethod,
+        common::models::BackupStatus,
+        common::models::RestoreRequest,
+        common::models::RestoreJob,
+        common::models::RestoreStatus,
+        common::models::RestoreStatementError,
+        common::models::CreateScheduledJobRequest,
+        common::models::ScheduledJob,
+        common::models::ScheduledTaskKind,
+        common::models::JobRun,
+        common::models::JobRunStatus,
+        common::models::CreateAlertRuleRequest,
+        common::models::AlertRule,
+        common::models::AlertCondition,
+        common::models::AlertSubject,
+        common::models::AlertOperator,
+        common::models::NotificationTarget,
+        common::models::AlertState,
+        common::models::AlertEvaluation,
+        common::models::StatementType,
+        common::models::StatementTypeStats,
+        common::models::TableWorkloadStats,
+        common::models::WorkloadBucket,
+        common::models::WorkloadBreakdown,
+        common::models::MetadataArchive,
+        common::models::ArchivedConnection,
+        common::models::ImportConflictPolicy,
+        common::models::MetadataImportReport,
+        common::models::MigrationReport,
+        common::models::MigrationStatus,
+        common::models::MigrationState,
+        common::models::ApiKey,
+        common::models::CreateApiKeyRequest,
+        common::models::CreatedApiKey,
+        common::models::CreateGuestLinkRequest,
+        common::models::CreatedGuestLink,
+        common::models::VerifyApiKeyRequest,
+        common::models::Policy,
+        common::models::PolicyRequest,
+        common::models::PolicyEffect,
+        common::models::AuthzRequest,
+        common::models::AuthzDecision,
+        common::models::PolicyDecisionLog,
+        common::models::UsageCounters,
+        common::models::UsageQuota,
+        common::models::UserUsage,
+        common::models::UsageReport,
+        common::models::QuotaExceeded,
+        common::models::Session,
+        common::models::VerifySessionRequest,
+        common::models::CreateSsoSessionRequest,
+        common::models::RevokedSessions,
+        common::models::LoginRequest,
+        common::models::RefreshRequest,
+        common::models::AuthTokens,
+        common::models::User,
+        common::models::LoginLockout,
+        common::models::LoginLocked,
+        common::models::SetPasswordRequest,
+        common::config::ConfigReload,
+        common::models::TargetHealth,
+        common::models::TargetHealthStatus,
+        common::models::ConnectionTestResult,
         handlers::HealthResponse,
+        common::probes::Liveness,
+        common::probes::Readiness,
+        common::probes::ProbeCheck,
         handlers::PoolInfo,
     )),
     tags(
         (name = "connections", description = "连接管理端点"),
+        (name = "schema-changes", description = "在线表结构变更端点"),
+        (name = "transfers", description = "跨连接数据复制端点"),
+        (name = "snapshots", description = "查询快照端点"),
+        (name = "workspaces", description = "工作区、成员与工作区内的连接和快照"),
+        (name = "schema", description = "表结构对比与缓存端点"),
+        (name = "keys", description = "键值读写端点"),
+        (name = "backups", description = "备份与恢复端点"),
+        (name = "jobs", description = "长时间任务的通用状态、取消与进度事件"),
+        (name = "scheduler", description = "定时任务端点"),
+        (name = "alerts", description = "定时查询告警端点"),
+        (name = "monitor", description = "监控与负载统计端点"),
+        (name = "admin", description = "元数据导出导入、API Key 与授权策略管理端点"),
+        (name = "usage", description = "用量统计与配额端点"),
+        (name = "sessions", description = "登录会话端点"),
+        (name = "auth", description = "登录、令牌刷新与用户管理端点"),
         (name = "health", description = "健康检查端点")
-    )
+    ),
+    modifiers(&ResponseExamples)
 )]
 struct ApiDoc;
 
 #[tokio::main]
 async fn main() {
-    // Load .env file (if present) before anything else
-    load_dotenv();
-
-    // 初始化日志追踪
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".into()),
-        )
-        .init();
-
-    // 加载配置
-    let mut config = AppConfig::load_with_service(SERVICE_NAME);
-    config.port = std::env::var("SERVER_PORT")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(DEFAULT_PORT);
+    // 加载配置（.env 文件、环境变量、命令行覆盖），配置无效时列出问题并退出
+    let config = Arc::new(
+        AppConfig::loader(SERVICE_NAME)
+            .default_port(DEFAULT_PORT)
+            .startup_only(&["MAX_BODY_BYTES", "NOTIFY_*"])
+            .load_shared_or_exit(),
+    );
+
+    // 初始化日志追踪（LOG_FORMAT=json 时输出 JSON）
+    common::logging::init(&config.get());
 
     // 创建应用状态（连接元数据 MySQL 库）
     let state = AppState::new(config.clone()).await
         .expect("Failed to initialize application state (check DATABASE_URL)");
+    // 收到 SIGHUP 时重新加载配置
+    config.reload_on_sighup();
 
     // 创建路由
     let app = create_router(state);
 
     // 启动服务
-    let addr = format!("{}:{}", config.host, config.port);
+    let addr = config.get().addr();
     info!(service = SERVICE_NAME, address = %addr, "启动服务");
 
     let listener = TcpListener::bind(&addr).await.expect("绑定地址失败");
-    axum::serve(listener, app).await.expect("服务启动失败");
+    // 配置 SERVICE_ADVERTISE_URL 时向网关注册并定期发送心跳
+    common::discovery::spawn_registration(SERVICE_NAME);
+    // 配置 TLS_CERT_PATH 与 TLS_KEY_PATH 时以 HTTPS 提供服务
+    common::tls::serve(listener, app, config.get().tls.as_ref())
+        .await
+        .expect("服务启动失败");
 }
 
 fn create_router(state: AppState) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // 跨域设置只在启动时读取
+    let cors = cors_layer(&state.config.get().cors);
+
+    let body_limit = DefaultBodyLimit::max(state.config.get().max_body_bytes);
+    let signatures = Arc::new(SignatureVerifier::from_env());
 
-    Router::new()
+    let router = Router::new()
         .merge(routes::router())
-        .route("/api-docs/openapi.json", get(openapi_json))
+        .route_service(&grpc::route(), grpc::service(state.clone()))
+        .route("/api-docs/openapi.json", get(openapi_json));
+
+    with_json_fallbacks(router, "connection-service")
+        .layer(middleware::from_fn_with_state(signatures, signature_middleware))
+        .layer(body_limit)
+        .layer(CompressionLayer::new())
         .layer(middleware::from_fn(request_id_middleware))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
@@ -111,26 +441,3 @@ fn create_router(state: AppState) -> Router {
 async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
     Json(ApiDoc::openapi())
 }
-
-/// Load .env file from the working directory (best-effort, no error if missing).
-fn load_dotenv() {
-    let env_path = std::path::Path::new(".env");
-    if env_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(env_path) {
-            for line in content.lines() {
-                let line = line.trim();
-                if line.is_empty() || line.starts_with('#') {
-                    continue;
-                }
-                if let Some((key, value)) = line.split_once('=') {
-                    let key = key.trim();
-                    let value = value.trim();
-                    // Only set if not already set by the environment
-                    if std::env::var(key).is_err() {
-                        std::env::set_var(key, value);
-                    }
-                }
-            }
-        }
-    }
-}
@@ -0,0 +1,105 @@
+//! Management metadata export and import.
+//!
+//! Exports the saved connections and scheduled jobs as one versioned
+//! [`MetadataArchive`] and imports such an archive into another deployment
+//! (disaster recovery, promoting a configuration between environments). IDs
+//! are preserved so jobs keep pointing at their connections. Run history,
+//! backup records and workload statistics are deployment-local and not
+//! archived. The endpoints require the admin token (see [`common::admin`]).
+
+use chrono::Utc;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::ConnectionConfig;
+use common::models::metadata::{
+    ArchivedConnection, ImportConflictPolicy, MetadataArchive, MetadataImportReport,
+    METADATA_ARCHIVE_VERSION,
+};
+use common::models::connection::RevisionAction;
+use crate::pool_manager::PoolManager;
+use crate::revisions::RevisionStore;
+use crate::scheduler::Scheduler;
+
+/// Builds an archive of the current metadata.
+pub async fn export(
+    pool_manager: &PoolManager,
+    scheduler: &Scheduler,
+    include_secrets: bool,
+) -> AppResult<MetadataArchive> {
+    let connections = pool_manager
+        .list_connections()
+        .await
+        .into_iter()
+        .map(|c| ArchivedConnection::from_config(c, include_secrets))
+        .collect();
+    let scheduled_jobs = scheduler.list().await?;
+
+    Ok(MetadataArchive {
+        version: METADATA_ARCHIVE_VERSION,
+        exported_at: Utc::now().to_rfc3339(),
+        includes_secrets: include_secrets,
+        connections,
+        scheduled_jobs,
+    })
+}
+
+/// Imports an archive: connections first, then the jobs referring to them.
+///
+/// Imported connections are recorded in their change history. Invalid jobs
+/// are skipped with a warning instead of failing the import.
+pub async fn import(
+    pool_manager: &PoolManager,
+    scheduler: &Scheduler,
+    revisions: &RevisionStore,
+    archive: MetadataArchive,
+    policy: ImportConflictPolicy,
+) -> AppResult<MetadataImportReport> {
+    if archive.version == 0 || archive.version > METADATA_ARCHIVE_VERSION {
+        return Err(AppError::InvalidInput(format!(
+            "unsupported archive version {} (supported: 1-{})",
+            archive.version, METADATA_ARCHIVE_VERSION
+        )));
+    }
+    let overwrite = policy == ImportConflictPolicy::Overwrite;
+    let mut report = MetadataImportReport::default();
+
+    for archived in archive.connections {
+        let needs_password = archived.password.is_none() && archived.password_ref.is_none() && archived.username.is_some();
+        let config = ConnectionConfig::from(archived);
+        let before = pool_manager.get_connection(&config.id).await;
+        if pool_manager.import_connection(config.clone(), overwrite).await? {
+            revisions
+                .record(before.as_ref(), Some(&config), RevisionAction::Imported, None, None)
+                .await;
+            report.connections_imported += 1;
+            if needs_password {
+                report
+                    .warnings
+                    .push(format!("connection {} ({}) imported without a password", config.id, config.name));
+            }
+        } else {
+            report.connections_skipped += 1;
+        }
+    }
+
+    for job in archive.scheduled_jobs {
+        match scheduler.import(&job, overwrite).await {
+            Ok(true) => report.scheduled_jobs_imported += 1,
+            Ok(false) => report.scheduled_jobs_skipped += 1,
+            Err(e) => {
+                report.scheduled_jobs_skipped += 1;
+                report
+                    .warnings
+                    .push(format!("scheduled job {} ({}) skipped: {}", job.id, job.name, e));
+            }
+        }
+    }
+
+    tracing::info!(
+        connections = report.connections_imported,
+        scheduled_jobs = report.scheduled_jobs_imported,
+        warnings = report.warnings.len(),
+        "Metadata archive imported"
+    );
+    Ok(report)
+}
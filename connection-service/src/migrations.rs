@@ -0,0 +1,236 @@
+//! Metadata schema migrations.
+//!
+//! The metadata tables are created and changed by the versioned scripts in
+//! `connection-service/migrations/<backend>`, embedded in the binary at build
+//! time and applied at startup by the backend's [`MetadataRepository`]. Every
+//! backend has its own scripts with the same versions, written in its own SQL;
+//! applied versions are recorded in the `_sqlx_migrations` table. A schema
+//! change is a new script with the next version for every backend; applied
+//! scripts must not be edited, the service refuses to start when an applied
+//! script's checksum changed.
+
+use common::errors::{AppError, AppResult};
+use common::meta::{self, Statement};
+use common::models::metadata::{MigrationReport, MigrationState, MigrationStatus};
+
+use crate::repository::MetadataRepository;
+
+/// Columns added to tables before migrations were introduced, as
+/// `(table, column, definition)`. Tables created by those versions get them
+/// before the baseline migration is recorded.
+const LEGACY_COLUMNS: [(&str, &str, Statement); 14] = [
+    (
+        "connections",
+        "allowlist",
+        Statement {
+            mysql: "`allowlist` TEXT DEFAULT NULL AFTER `file_path`",
+            postgres: r#""allowlist" TEXT DEFAULT NULL"#,
+            sqlite: r#""allowlist" TEXT DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "query_timeout_ms",
+        Statement {
+            mysql: "`query_timeout_ms` INT UNSIGNED DEFAULT NULL AFTER `allowlist`",
+            postgres: r#""query_timeout_ms" BIGINT DEFAULT NULL"#,
+            sqlite: r#""query_timeout_ms" INTEGER DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "pinned",
+        Statement {
+            mysql: "`pinned` TINYINT(1) NOT NULL DEFAULT 0 AFTER `query_timeout_ms`",
+            postgres: r#""pinned" BOOLEAN NOT NULL DEFAULT FALSE"#,
+            sqlite: r#""pinned" INTEGER NOT NULL DEFAULT 0"#,
+        },
+    ),
+    (
+        "connections",
+        "pool_max_connections",
+        Statement {
+            mysql: "`pool_max_connections` INT UNSIGNED DEFAULT NULL AFTER `pinned`",
+            postgres: r#""pool_max_connections" BIGINT DEFAULT NULL"#,
+            sqlite: r#""pool_max_connections" INTEGER DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "pool_min_connections",
+        Statement {
+            mysql: "`pool_min_connections` INT UNSIGNED DEFAULT NULL AFTER `pool_max_connections`",
+            postgres: r#""pool_min_connections" BIGINT DEFAULT NULL"#,
+            sqlite: r#""pool_min_connections" INTEGER DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "pool_acquire_timeout_secs",
+        Statement {
+            mysql: "`pool_acquire_timeout_secs` INT UNSIGNED DEFAULT NULL AFTER `pool_min_connections`",
+            postgres: r#""pool_acquire_timeout_secs" BIGINT DEFAULT NULL"#,
+            sqlite: r#""pool_acquire_timeout_secs" INTEGER DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "pool_idle_timeout_secs",
+        Statement {
+            mysql: "`pool_idle_timeout_secs` INT UNSIGNED DEFAULT NULL AFTER `pool_acquire_timeout_secs`",
+            postgres: r#""pool_idle_timeout_secs" BIGINT DEFAULT NULL"#,
+            sqlite: r#""pool_idle_timeout_secs" INTEGER DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "owner_id",
+        Statement {
+            mysql: "`owner_id` VARCHAR(128) DEFAULT NULL AFTER `pool_idle_timeout_secs`",
+            postgres: r#""owner_id" VARCHAR(128) DEFAULT NULL"#,
+            sqlite: r#""owner_id" TEXT DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "password_ref",
+        Statement {
+            mysql: "`password_ref` VARCHAR(512) DEFAULT NULL AFTER `password`",
+            postgres: r#""password_ref" VARCHAR(512) DEFAULT NULL"#,
+            sqlite: r#""password_ref" TEXT DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "masking",
+        Statement {
+            mysql: "`masking` TEXT DEFAULT NULL AFTER `allowlist`",
+            postgres: r#""masking" TEXT DEFAULT NULL"#,
+            sqlite: r#""masking" TEXT DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "org",
+        Statement {
+            mysql: "`org` VARCHAR(128) DEFAULT NULL AFTER `file_path`",
+            postgres: r#""org" VARCHAR(128) DEFAULT NULL"#,
+            sqlite: r#""org" TEXT DEFAULT NULL"#,
+        },
+    ),
+    (
+        "connections",
+        "statement_policy",
+        Statement {
+            mysql: "`statement_policy` TEXT DEFAULT NULL AFTER `masking`",
+            postgres: r#""statement_policy" TEXT DEFAULT NULL"#,
+            sqlite: r#""statement_policy" TEXT DEFAULT NULL"#,
+        },
+    ),
+    (
+        "api_keys",
+        "guest",
+        Statement {
+            mysql: "`guest` TINYINT(1) NOT NULL DEFAULT 0 AFTER `connection_ids`",
+            postgres: r#""guest" BOOLEAN NOT NULL DEFAULT FALSE"#,
+            sqlite: r#""guest" INTEGER NOT NULL DEFAULT 0"#,
+        },
+    ),
+    (
+        "api_keys",
+        "schemas",
+        Statement {
+            mysql: "`schemas` TEXT DEFAULT NULL AFTER `guest`",
+            postgres: r#""schemas" TEXT DEFAULT NULL"#,
+            sqlite: r#""schemas" TEXT DEFAULT NULL"#,
+        },
+    ),
+];
+
+/// Brings the metadata schema up to date.
+pub async fn run(repository: &dyn MetadataRepository) -> AppResult<()> {
+    if !repository.has_table("_sqlx_migrations").await.map_err(failed)? {
+        upgrade_legacy_tables(repository).await.map_err(failed)?;
+    }
+    repository.migrate().await.map_err(failed)?;
+
+    tracing::info!(
+        version = repository.migrator().iter().map(|m| m.version).max(),
+        "Metadata schema up to date"
+    );
+    Ok(())
+}
+
+fn failed(e: impl std::fmt::Display) -> AppError {
+    AppError::DatabaseQuery(format!("Failed to migrate metadata database: {}", e))
+}
+
+/// Adds the columns that tables created before the migrations may lack.
+async fn upgrade_legacy_tables(repository: &dyn MetadataRepository) -> Result<(), sqlx::Error> {
+    for (table, column, definition) in &LEGACY_COLUMNS {
+        if repository.has_table(table).await? {
+            repository.add_column(table, column, definition).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Reports which embedded migrations have been applied.
+pub async fn status(repository: &dyn MetadataRepository) -> AppResult<MigrationReport> {
+    let applied = repository.applied_migrations().await?;
+
+    let migrations: Vec<MigrationStatus> = repository
+        .migrator()
+        .iter()
+        .map(|migration| {
+            let row = applied.iter().find(|row| row.version == migration.version);
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state: match row {
+                    Some(row) if row.success => MigrationState::Applied,
+                    Some(_) => MigrationState::Failed,
+                    None => MigrationState::Pending,
+                },
+                installed_on: row.map(|row| meta::timestamp(row.installed_on)),
+                // Execution time is recorded in nanoseconds, -1 while running
+                execution_time_ms: row
+                    .and_then(|row| u64::try_from(row.execution_time).ok())
+                    .map(|ns| ns / 1_000_000),
+            }
+        })
+        .collect();
+    Ok(MigrationReport {
+        backend: repository.backend().as_str().to_string(),
+        current_version: applied.iter().filter(|row| row.success).map(|row| row.version).max(),
+        pending: migrations
+            .iter()
+            .filter(|m| m.state == MigrationState::Pending)
+            .count(),
+        migrations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::meta::MetaPool;
+
+    #[tokio::test]
+    async fn applies_the_migrations_once() {
+        let meta_pool = MetaPool::connect("sqlite::memory:", 1).await.unwrap();
+        let repository = crate::repository::for_pool(&meta_pool);
+        run(repository.as_ref()).await.unwrap();
+        run(repository.as_ref()).await.unwrap();
+
+        let report = status(repository.as_ref()).await.unwrap();
+        assert_eq!(report.backend, "sqlite");
+        assert_eq!(report.pending, 0);
+        assert_eq!(report.current_version, repository.migrator().iter().map(|m| m.version).max());
+        assert!(report
+            .migrations
+            .iter()
+            .all(|m| m.state == MigrationState::Applied && m.installed_on.is_some()));
+        assert!(repository.has_table("connections").await.unwrap());
+    }
+}
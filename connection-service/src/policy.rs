@@ -0,0 +1,528 @@
+//! Authorization policies.
+//!
+//! Policies live in the `authz_policies` metadata table and are kept in
+//! memory for evaluation. The gateway asks for a decision on every request
+//! through the internal decide endpoint; decisions are evaluated by a
+//! [`PolicyEngine`] and written to the `authz_decisions` log.
+//!
+//! The built-in engine is deny-overrides: a matching deny policy wins over
+//! any allow, and requests no enabled policy matches get the default
+//! decision. Other engines (e.g. an OPA or Cedar adapter) can be plugged in
+//! by implementing [`PolicyEngine`].
+//!
+//! Configuration:
+//! - `AUTHZ_DEFAULT_DECISION` - `allow` or `deny` for requests no policy matches (default: allow)
+//! - `AUTHZ_DECISION_RETENTION_DAYS` - days of decision log kept (default: 30)
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::meta::{self, Statement};
+use common::meta_query;
+use common::models::policy::{
+    AuthzDecision, AuthzRequest, Policy, PolicyDecisionLog, PolicyEffect, PolicyRequest,
+};
+use crate::pool_manager::PoolManager;
+
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+/// Interval of the decision log cleanup.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Most decision log entries returned by one listing.
+pub const MAX_DECISION_LOG_LIMIT: u32 = 1000;
+
+/// MySQL DATETIME format used for stored timestamps (UTC).
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const SELECT_POLICY: Statement = Statement {
+    mysql: "SELECT `id`, `name`, `description`, `effect`, `principals`, `actions`, `resources`, \
+         `enabled`, CAST(`created_at` AS CHAR) AS created_at, CAST(`updated_at` AS CHAR) AS updated_at \
+         FROM `authz_policies`",
+    postgres: r#"SELECT "id", "name", "description", "effect", "principals", "actions", "resources",
+         "enabled", "created_at", "updated_at"
+         FROM "authz_policies""#,
+    sqlite: r#"SELECT "id", "name", "description", "effect", "principals", "actions", "resources",
+         "enabled", "created_at", "updated_at"
+         FROM "authz_policies""#,
+};
+
+/// Evaluates authorization requests against policies.
+pub trait PolicyEngine: Send + Sync {
+    /// Engine name, recorded in logs.
+    fn name(&self) -> &'static str;
+
+    /// Decides a request given the enabled policies.
+    fn evaluate(&self, policies: &[Policy], request: &AuthzRequest) -> AuthzDecision;
+}
+
+/// Deny-overrides engine: any matching deny wins, then any matching allow,
+/// otherwise the default decision.
+pub struct DenyOverridesEngine {
+    default_allow: bool,
+}
+
+impl DenyOverridesEngine {
+    /// Creates the engine with the decision for unmatched requests.
+    pub fn new(default_allow: bool) -> Self {
+        Self { default_allow }
+    }
+}
+
+impl PolicyEngine for DenyOverridesEngine {
+    fn name(&self) -> &'static str {
+        "deny-overrides"
+    }
+
+    fn evaluate(&self, policies: &[Policy], request: &AuthzRequest) -> AuthzDecision {
+        let mut allow = None;
+        for policy in policies.iter().filter(|p| p.matches(request)) {
+            match policy.effect {
+                PolicyEffect::Deny => {
+                    return AuthzDecision {
+                        allowed: false,
+                        policy_id: Some(policy.id.clone()),
+                        reason: format!("denied by policy {}", policy.name),
+                    }
+                }
+                PolicyEffect::Allow => {
+                    allow.get_or_insert(policy);
+                }
+            }
+        }
+        match allow {
+            Some(policy) => AuthzDecision {
+                allowed: true,
+                policy_id: Some(policy.id.clone()),
+                reason: format!("allowed by policy {}", policy.name),
+            },
+            None => AuthzDecision {
+                allowed: self.default_allow,
+                policy_id: None,
+                reason: format!(
+                    "no matching policy, default {}",
+                    if self.default_allow { "allow" } else { "deny" }
+                ),
+            },
+        }
+    }
+}
+
+/// Row from the `authz_policies` metadata table.
+#[derive(sqlx::FromRow)]
+struct PolicyRow {
+    id: String,
+    name: String,
+    description: Option<String>,
+    effect: String,
+    principals: String,
+    actions: String,
+    resources: String,
+    enabled: bool,
+    created_at: String,
+    updated_at: String,
+}
+
+impl PolicyRow {
+    fn into_policy(self) -> Policy {
+        let list = |s: &str| serde_json::from_str(s).unwrap_or_default();
+        Policy {
+            effect: if self.effect == PolicyEffect::Deny.as_str() {
+                PolicyEffect::Deny
+            } else {
+                PolicyEffect::Allow
+            },
+            principals: list(&self.principals),
+            actions: list(&self.actions),
+            resources: list(&self.resources),
+            id: self.id,
+            name: self.name,
+            description: self.description,
+            enabled: self.enabled,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+/// Row from the `authz_decisions` metadata table.
+#[derive(sqlx::FromRow)]
+struct DecisionRow {
+    id: meta::Unsigned<u64>,
+    principal: String,
+    action: String,
+    resource: Option<String>,
+    method: Option<String>,
+    path: Option<String>,
+    allowed: bool,
+    policy_id: Option<String>,
+    reason: String,
+    decided_at: String,
+}
+
+impl From<DecisionRow> for PolicyDecisionLog {
+    fn from(row: DecisionRow) -> Self {
+        Self {
+            id: row.id.0,
+            principal: row.principal,
+            action: row.action,
+            resource: row.resource,
+            method: row.method,
+            path: row.path,
+            allowed: row.allowed,
+            policy_id: row.policy_id,
+            reason: row.reason,
+            decided_at: row.decided_at,
+        }
+    }
+}
+
+/// Stores policies, decides requests and logs decisions.
+pub struct PolicyStore {
+    pool_manager: Arc<PoolManager>,
+    engine: Box<dyn PolicyEngine>,
+    retention: ChronoDuration,
+    /// Enabled policies, reloaded after every change.
+    policies: RwLock<Vec<Policy>>,
+}
+
+impl PolicyStore {
+    /// Creates the store and loads the policies.
+    pub async fn new(pool_manager: Arc<PoolManager>, engine: Box<dyn PolicyEngine>) -> AppResult<Self> {
+        let retention_days = std::env::var("AUTHZ_DECISION_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        let store = Self {
+            pool_manager,
+            engine,
+            retention: ChronoDuration::days(retention_days),
+            policies: RwLock::new(Vec::new()),
+        };
+        store.reload().await?;
+        tracing::info!(engine = store.engine.name(), "Authorization policies loaded");
+        Ok(store)
+    }
+
+    /// Reads the default decision from `AUTHZ_DEFAULT_DECISION`.
+    pub fn default_allow_from_env() -> bool {
+        !std::env::var("AUTHZ_DEFAULT_DECISION").is_ok_and(|v| v.eq_ignore_ascii_case("deny"))
+    }
+
+    /// Starts the periodic decision log cleanup.
+    pub fn spawn(self: &Arc<Self>) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = store.prune().await {
+                    tracing::warn!(error = %e, "Decision log cleanup failed");
+                }
+            }
+        });
+    }
+
+    /// Lists all policies.
+    pub async fn list(&self) -> AppResult<Vec<Policy>> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_POLICY,
+            &Statement {
+                mysql: "ORDER BY `created_at`",
+                postgres: r#"ORDER BY "created_at""#,
+                sqlite: r#"ORDER BY "created_at""#,
+            },
+        ]);
+        let rows: Vec<PolicyRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(PolicyRow::into_policy).collect())
+    }
+
+    /// Gets a policy by ID.
+    pub async fn get(&self, id: &str) -> AppResult<Policy> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_POLICY,
+            &Statement {
+                mysql: "WHERE `id` = ?",
+                postgres: r#"WHERE "id" = $1"#,
+                sqlite: r#"WHERE "id" = ?"#,
+            },
+        ]);
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as::<_, PolicyRow>(&statement)
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+        })?
+            .map(PolicyRow::into_policy)
+            .ok_or_else(|| AppError::NotFound(format!("Policy {}", id)))
+    }
+
+    /// Creates a policy.
+    pub async fn create(&self, req: PolicyRequest) -> AppResult<Policy> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now().format(DATETIME_FORMAT).to_string();
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `authz_policies` (`id`, `name`, `description`, `effect`, `principals`, `actions`, \
+                 `resources`, `enabled`, `created_at`, `updated_at`) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "authz_policies" ("id", "name", "description", "effect", "principals", "actions",
+                 "resources", "enabled", "created_at", "updated_at") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"#,
+            sqlite: r#"INSERT INTO "authz_policies" ("id", "name", "description", "effect", "principals", "actions",
+                 "resources", "enabled", "created_at", "updated_at") VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&id)
+                .bind(&req.name)
+                .bind(&req.description)
+                .bind(req.effect.as_str())
+                .bind(to_json(&req.principals))
+                .bind(to_json(&req.actions))
+                .bind(to_json(&req.resources))
+                .bind(req.enabled)
+                .bind(&now)
+                .bind(&now)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+
+        self.reload().await?;
+        tracing::info!(policy_id = %id, name = %req.name, effect = req.effect.as_str(), "Policy created");
+        self.get(&id).await
+    }
+
+    /// Replaces a policy.
+    pub async fn update(&self, id: &str, req: PolicyRequest) -> AppResult<Policy> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "UPDATE `authz_policies` SET `name` = ?, `description` = ?, `effect` = ?, `principals` = ?, \
+                 `actions` = ?, `resources` = ?, `enabled` = ?, `updated_at` = ? WHERE `id` = ?",
+            postgres: r#"UPDATE "authz_policies" SET "name" = $1, "description" = $2, "effect" = $3, "principals" = $4,
+                 "actions" = $5, "resources" = $6, "enabled" = $7, "updated_at" = $8 WHERE "id" = $9"#,
+            sqlite: r#"UPDATE "authz_policies" SET "name" = ?, "description" = ?, "effect" = ?, "principals" = ?,
+                 "actions" = ?, "resources" = ?, "enabled" = ?, "updated_at" = ? WHERE "id" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&req.name)
+                .bind(&req.description)
+                .bind(req.effect.as_str())
+                .bind(to_json(&req.principals))
+                .bind(to_json(&req.actions))
+                .bind(to_json(&req.resources))
+                .bind(req.enabled)
+                .bind(Utc::now().format(DATETIME_FORMAT).to_string())
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result == 0 {
+            // MySQL reports 0 rows for unchanged updates, so check existence
+            self.get(id).await?;
+        }
+
+        self.reload().await?;
+        tracing::info!(policy_id = %id, "Policy updated");
+        self.get(id).await
+    }
+
+    /// Deletes a policy.
+    pub async fn delete(&self, id: &str) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `authz_policies` WHERE `id` = ?",
+            postgres: r#"DELETE FROM "authz_policies" WHERE "id" = $1"#,
+            sqlite: r#"DELETE FROM "authz_policies" WHERE "id" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result == 0 {
+            return Err(AppError::NotFound(format!("Policy {}", id)));
+        }
+
+        self.reload().await?;
+        tracing::info!(policy_id = %id, "Policy deleted");
+        Ok(())
+    }
+
+    /// Decides a request and logs the decision.
+    pub async fn decide(&self, request: &AuthzRequest) -> AuthzDecision {
+        let decision = self.engine.evaluate(&self.policies.read().await, request);
+        if !decision.allowed {
+            tracing::info!(
+                principal = %request.principal,
+                action = %request.action,
+                resource = ?request.resource,
+                reason = %decision.reason,
+                "Request denied by policy"
+            );
+        }
+        if let Err(e) = self.log(request, &decision).await {
+            tracing::warn!(error = %e, "Failed to log authorization decision");
+        }
+        decision
+    }
+
+    /// Lists logged decisions, newest first.
+    pub async fn decisions(&self, principal: Option<&str>, denied_only: bool, limit: u32) -> AppResult<Vec<PolicyDecisionLog>> {
+        let meta = self.pool_manager.meta_pool();
+        let mut sql = String::from(meta.sql(&Statement {
+            mysql: "SELECT `id`, `principal`, `action`, `resource`, `method`, `path`, `allowed`, `policy_id`, `reason`, \
+                 CAST(`decided_at` AS CHAR) AS decided_at FROM `authz_decisions` WHERE 1 = 1",
+            postgres: r#"SELECT "id", "principal", "action", "resource", "method", "path", "allowed", "policy_id", "reason",
+                 "decided_at" FROM "authz_decisions" WHERE 1 = 1"#,
+            sqlite: r#"SELECT "id", "principal", "action", "resource", "method", "path", "allowed", "policy_id", "reason",
+                 "decided_at" FROM "authz_decisions" WHERE 1 = 1"#,
+        }));
+        let mut bound = 0;
+        if principal.is_some() {
+            bound += 1;
+            sql.push_str(meta.sql(&Statement {
+                mysql: " AND `principal` = ?",
+                postgres: r#" AND "principal" = $1"#,
+                sqlite: r#" AND "principal" = ?"#,
+            }));
+        }
+        if denied_only {
+            sql.push_str(meta.sql(&Statement {
+                mysql: " AND `allowed` = FALSE",
+                postgres: r#" AND "allowed" = FALSE"#,
+                sqlite: r#" AND "allowed" = FALSE"#,
+            }));
+        }
+        sql.push_str(meta.sql(&Statement {
+            mysql: " ORDER BY `id` DESC LIMIT ",
+            postgres: r#" ORDER BY "id" DESC LIMIT "#,
+            sqlite: r#" ORDER BY "id" DESC LIMIT "#,
+        }));
+        sql.push_str(&meta.placeholder(bound + 1));
+
+        let rows = meta_query!(meta, |pool| {
+            let mut query = sqlx::query_as::<_, DecisionRow>(&sql);
+            if let Some(principal) = principal {
+                query = query.bind(principal);
+            }
+            query
+                .bind(i64::from(limit.clamp(1, MAX_DECISION_LOG_LIMIT)))
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(PolicyDecisionLog::from).collect())
+    }
+
+    async fn reload(&self) -> AppResult<()> {
+        let policies = self.list().await?.into_iter().filter(|p| p.enabled).collect();
+        *self.policies.write().await = policies;
+        Ok(())
+    }
+
+    async fn log(&self, request: &AuthzRequest, decision: &AuthzDecision) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `authz_decisions` (`principal`, `action`, `resource`, `method`, `path`, `allowed`, \
+                 `policy_id`, `reason`, `decided_at`) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "authz_decisions" ("principal", "action", "resource", "method", "path", "allowed",
+                 "policy_id", "reason", "decided_at") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+            sqlite: r#"INSERT INTO "authz_decisions" ("principal", "action", "resource", "method", "path", "allowed",
+                 "policy_id", "reason", "decided_at") VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(truncate(&request.principal, 128))
+                .bind(truncate(&request.action, 32))
+                .bind(request.resource.as_deref().map(|r| truncate(r, 64)))
+                .bind(request.method.as_deref().map(|m| truncate(m, 10)))
+                .bind(request.path.as_deref().map(|p| truncate(p, 512)))
+                .bind(decision.allowed)
+                .bind(&decision.policy_id)
+                .bind(truncate(&decision.reason, 255))
+                .bind(Utc::now().format(DATETIME_FORMAT).to_string())
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        Ok(())
+    }
+
+    async fn prune(&self) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `authz_decisions` WHERE `decided_at` < ?",
+            postgres: r#"DELETE FROM "authz_decisions" WHERE "decided_at" < $1"#,
+            sqlite: r#"DELETE FROM "authz_decisions" WHERE "decided_at" < ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind((Utc::now() - self.retention).format(DATETIME_FORMAT).to_string())
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        Ok(())
+    }
+}
+
+fn to_json(values: &[String]) -> String {
+    serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn truncate(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(id: &str, effect: PolicyEffect, principal: &str, action: &str, resource: &str) -> Policy {
+        Policy {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: None,
+            effect,
+            principals: vec![principal.to_string()],
+            actions: vec![action.to_string()],
+            resources: vec![resource.to_string()],
+            enabled: true,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            updated_at: "2024-01-01 00:00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn deny_overrides_allow_and_default_applies() {
+        let policies = vec![
+            policy("allow-all", PolicyEffect::Allow, "key:*", "*", "*"),
+            policy("no-prod-writes", PolicyEffect::Deny, "*", "write", "prod-*"),
+        ];
+        let engine = DenyOverridesEngine::new(false);
+
+        let write_prod = AuthzRequest::for_http("key:k1", "DELETE", "/api/connections/prod-1", None);
+        let decision = engine.evaluate(&policies, &write_prod);
+        assert!(!decision.allowed);
+        assert_eq!(decision.policy_id.as_deref(), Some("no-prod-writes"));
+
+        let read_prod = AuthzRequest::for_http("key:k1", "GET", "/api/connections/prod-1/schema", None);
+        assert_eq!(engine.evaluate(&policies, &read_prod).policy_id.as_deref(), Some("allow-all"));
+
+        let anonymous = AuthzRequest::for_http("anonymous", "GET", "/api/connections", None);
+        let decision = engine.evaluate(&policies, &anonymous);
+        assert!(!decision.allowed);
+        assert!(decision.policy_id.is_none());
+        assert!(DenyOverridesEngine::new(true).evaluate(&policies, &anonymous).allowed);
+    }
+}
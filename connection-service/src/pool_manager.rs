@@ -1,208 +1,162 @@
 //! Database connection pool manager.
 //!
-//! Manages connection pools for different database types (MySQL, PostgreSQL, SQLite, Redis).
+//! Keeps the saved connections in the metadata database and runs them on the
+//! core engine: pools are opened and cached by a [`ConnectionManager`] and
+//! statements run through a [`QueryExecutor`] (see [`dbm_core`]). On top of
+//! the engine it tracks the self-healing state of the pools (see
+//! [`pool_state`](crate::pool_state)) and records the workload.
+//! Memcached keys are read and written through [`PoolManager::get_key`] and
+//! friends (see [`dbm_core::drivers::memcached`]).
+//! Neo4j pools are available with the `neo4j` feature and run Cypher through
+//! [`PoolManager::execute_cypher`] (see `dbm_core::drivers::neo4j`).
+//!
+//! Queries return at most `QUERY_MAX_ROWS` rows (see [`common::utils::SqlLimit`]),
+//! and their results are clipped before they are returned:
+//! - `QUERY_MAX_CELL_BYTES` - maximum size of a text / JSON cell (default: 64 KiB)
+//! - `QUERY_MAX_RESULT_BYTES` - maximum serialized size of the rows (default: 16 MiB)
+//!
+//! Pool sizing defaults and the row limit follow config reloads (see
+//! [`PoolManager::spawn_config_watch`]).
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use std::time::Duration;
 
-use common::config::AppConfig;
+use common::config::{AppConfig, SharedConfig};
 use common::errors::{AppError, AppResult};
-use common::models::connection::{ConnectionConfig, DbType};
-use common::models::database::{ColumnDetail, TableInfo, TableSchema};
+use common::models::connection::{ConnectionAllowlist, ConnectionConfig, ConnectionPoolOptions, DbType, StatementPolicy};
+use common::models::masking::ConnectionMasking;
+use common::models::database::{GraphElementType, TableSchema};
+use common::models::diagnostics::{StageResult, TestStage};
+use common::models::key_value::{KeyValueEntry, SetKeyValueRequest};
 use common::models::monitor::{
-    ConnectionPoolStats, DatabaseInfo, DatabaseStats, MonitorOverview, ProcessInfo,
+    CachedPool, ConnectionPoolStats, DatabaseInfo, DatabaseStats, MonitorOverview, PoolState, PoolStatus, ProcessInfo,
 };
-use common::models::query::{ColumnInfo, QueryResult};
-use mongodb::bson::doc;
-use redis::aio::ConnectionManager as RedisConnectionManager;
-use sqlx::{mysql::MySqlPoolOptions, mysql::MySqlRow, postgres::PgPoolOptions, postgres::PgRow, sqlite::SqlitePoolOptions, Row, Column, TypeInfo};
-use sqlx::{MySqlPool, PgPool, SqlitePool};
-use tokio::sync::RwLock;
-
-/// Row from the `connections` MySQL table.
-#[derive(sqlx::FromRow)]
-struct ConnectionRow {
-    id: String,
-    name: String,
-    db_type: String,
-    host: Option<String>,
-    port: Option<u16>,
-    username: Option<String>,
-    password: Option<String>,
-    database_name: Option<String>,
-    file_path: Option<String>,
-    created_at: String,
-}
-
-impl ConnectionRow {
-    fn into_config(self) -> ConnectionConfig {
-        ConnectionConfig {
-            id: self.id,
-            name: self.name,
-            db_type: parse_db_type(&self.db_type),
-            host: self.host,
-            port: self.port,
-            username: self.username,
-            password: self.password,
-            database: self.database_name,
-            file_path: self.file_path,
-            created_at: self.created_at,
-        }
-    }
-}
-
-fn parse_db_type(s: &str) -> DbType {
-    match s.to_lowercase().as_str() {
-        "mysql" => DbType::MySQL,
-        "postgres" => DbType::Postgres,
-        "sqlite" => DbType::SQLite,
-        "redis" => DbType::Redis,
-        "mongodb" => DbType::MongoDB,
-        "clickhouse" => DbType::ClickHouse,
-        "elasticsearch" => DbType::Elasticsearch,
-        "oracle" => DbType::Oracle,
-        "sqlserver" => DbType::SqlServer,
-        "mariadb" => DbType::MariaDB,
-        "cassandra" => DbType::Cassandra,
-        "influxdb" => DbType::InfluxDB,
-        "db2" => DbType::DB2,
-        "couchdb" => DbType::CouchDB,
-        "neo4j" => DbType::Neo4j,
-        "memcached" => DbType::Memcached,
-        "hbase" => DbType::HBase,
-        "milvus" => DbType::Milvus,
-        _ => DbType::MySQL, // fallback
+use common::models::query::QueryResult;
+use common::meta::MetaPool;
+use common::meta_query;
+use dbm_core::drivers::{DriverConnection, PoolUsage};
+use dbm_core::{ConnectionManager, DatabasePool, PoolDefaults, QueryExecutor, ResultLimits};
+use sqlx::MySqlPool;
+
+use crate::diagnostics::{self, Diagnosis};
+use crate::pool_state::PoolStates;
+use crate::repository::MetadataRepository;
+use crate::workload::WorkloadStats;
+
+const DEFAULT_MAX_DATABASE_POOLS: usize = 4;
+
+/// Pool sizing defaults of the service config (`MAX_CONNECTIONS` / `CONNECT_TIMEOUT`).
+fn pool_defaults(config: &AppConfig) -> PoolDefaults {
+    PoolDefaults {
+        max_connections: config.max_connections,
+        connect_timeout: Duration::from_secs(config.connect_timeout_secs),
+        ..PoolDefaults::default()
     }
 }
 
-/// Connection pool wrapper for different database types.
-#[derive(Clone)]
-pub enum DatabasePool {
-    /// MySQL connection pool.
-    MySQL(MySqlPool),
-    /// PostgreSQL connection pool.
-    Postgres(PgPool),
-    /// SQLite connection pool.
-    SQLite(SqlitePool),
-    /// Redis connection manager.
-    Redis(RedisConnectionManager),
-    /// MongoDB client.
-    MongoDB(mongodb::Client),
-    /// Unsupported database type.
-    Unsupported,
-}
-
 /// Manages database connection pools.
 ///
 /// Maintains a collection of connection pools, one for each active database connection.
-/// Connection configs are persisted in a MySQL metadata database.
+/// Connection configs are persisted in the metadata database through its
+/// [`MetadataRepository`].
 pub struct PoolManager {
-    config: AppConfig,
-    /// The MySQL pool for metadata persistence (connections table).
-    meta_pool: MySqlPool,
+    /// Service configuration; pool defaults, timeouts and the row limit follow reloads.
+    config: Arc<SharedConfig>,
+    /// The pool for metadata persistence, shared with the other stores.
+    meta_pool: MetaPool,
+    /// Storage of the saved connections.
+    repository: Arc<dyn MetadataRepository>,
     /// Runtime connection pools indexed by connection ID (cache only).
-    pools: RwLock<HashMap<String, DatabasePool>>,
+    connections: Arc<ConnectionManager>,
+    /// Runs statements on the cached pools.
+    executor: QueryExecutor,
+    /// Statistics of the statements executed through the service.
+    workload: Arc<WorkloadStats>,
+    /// Self-healing state of the opened pools.
+    states: PoolStates,
 }
 
 impl PoolManager {
-    /// Creates a new pool manager with MySQL metadata persistence.
-    /// Automatically creates the `connections` table and loads existing connections.
-    pub async fn new(config: AppConfig, meta_pool: MySqlPool) -> AppResult<Self> {
-        let mgr = Self {
+    /// Creates a new pool manager with metadata persistence.
+    /// Pools of saved connections are opened by the startup warm-up (see
+    /// `warmup`) or on first use.
+    pub fn new(config: Arc<SharedConfig>, meta_pool: MetaPool, repository: Arc<dyn MetadataRepository>) -> Self {
+        let workload = Arc::new(WorkloadStats::new(meta_pool.clone()));
+        let limit = |key: &str, default: usize| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(default)
+        };
+        let startup = config.get();
+        let connections = Arc::new(
+            ConnectionManager::new()
+                .with_defaults(pool_defaults(&startup))
+                .with_max_database_pools(limit("MAX_DATABASE_POOLS", DEFAULT_MAX_DATABASE_POOLS)),
+        );
+        let defaults = ResultLimits::default();
+        let executor = QueryExecutor::new(connections.clone()).with_limits(ResultLimits {
+            max_rows: startup.max_query_rows,
+            max_cell_bytes: limit("QUERY_MAX_CELL_BYTES", defaults.max_cell_bytes),
+            max_result_bytes: limit("QUERY_MAX_RESULT_BYTES", defaults.max_result_bytes),
+        });
+        Self {
             config,
             meta_pool,
-            pools: RwLock::new(HashMap::new()),
-        };
-
-        // Ensure the connections table exists
-        mgr.ensure_table().await?;
-
-        // Load existing connections from DB and try to create pools
-        mgr.load_connections_from_db().await;
-
-        Ok(mgr)
-    }
-
-    /// Creates the connections table if it does not exist.
-    async fn ensure_table(&self) -> AppResult<()> {
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS `connections` (
-                `id`            VARCHAR(64)   NOT NULL,
-                `name`          VARCHAR(100)  NOT NULL,
-                `db_type`       VARCHAR(32)   NOT NULL,
-                `host`          VARCHAR(255)  DEFAULT NULL,
-                `port`          SMALLINT UNSIGNED DEFAULT NULL,
-                `username`      VARCHAR(128)  DEFAULT NULL,
-                `password`      VARCHAR(512)  DEFAULT NULL,
-                `database_name` VARCHAR(128)  DEFAULT NULL,
-                `file_path`     VARCHAR(512)  DEFAULT NULL,
-                `created_at`    DATETIME      NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                `updated_at`    DATETIME      NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
-                PRIMARY KEY (`id`),
-                KEY `idx_db_type` (`db_type`),
-                KEY `idx_created_at` (`created_at`)
-            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci"
-        )
-        .execute(&self.meta_pool)
-        .await
-        .map_err(|e| AppError::DatabaseQuery(format!("Failed to create connections table: {}", e)))?;
-
-        tracing::info!("Metadata table `connections` ensured");
-        Ok(())
+            repository,
+            connections,
+            executor,
+            workload,
+            states: PoolStates::from_env(),
+        }
+    }
+
+    /// Starts the task applying reloaded pool defaults and row limits to the
+    /// engine; open pools keep their sizing until they are reopened.
+    pub fn spawn_config_watch(self: &Arc<Self>) {
+        let manager = self.clone();
+        let mut changes = self.config.subscribe();
+        tokio::spawn(async move {
+            while changes.changed().await.is_ok() {
+                let config = changes.borrow_and_update().clone();
+                manager.connections.set_defaults(pool_defaults(&config));
+                manager.executor.set_limits(ResultLimits {
+                    max_rows: config.max_query_rows,
+                    ..manager.executor.limits()
+                });
+            }
+        });
     }
 
-    /// Loads all connection configs from MySQL and tries to create pools for each.
-    async fn load_connections_from_db(&self) {
-        match self.list_connections().await {
-            configs if !configs.is_empty() => {
-                tracing::info!(count = configs.len(), "Loading saved connections from DB");
-                for config in configs {
-                    let id = config.id.clone();
-                    match self.try_create_pool(&config).await {
-                        Ok(pool) => {
-                            self.pools.write().await.insert(id.clone(), pool);
-                            tracing::info!(id = %id, name = %config.name, "Pool restored");
-                        }
-                        Err(e) => {
-                            tracing::warn!(id = %id, error = %e, "Saved connection pool creation failed (will retry on test)");
-                        }
-                    }
-                }
-            }
-            _ => {
-                tracing::info!("No saved connections found in DB");
-            }
+    /// Opens the pool of a saved connection unless it is already open.
+    pub async fn restore_pool(&self, config: &ConnectionConfig) -> AppResult<()> {
+        if self.connections.pool(&config.id).await.is_some() {
+            return Ok(());
         }
+        let pool = self.connections.open(config).await?;
+        self.cache_pool(config, pool).await;
+        tracing::info!(id = %config.id, name = %config.name, "Pool restored");
+        Ok(())
     }
 
     /// Adds a new database connection.
-    /// Saves the config to MySQL first, then attempts to create a connection pool.
+    /// Saves the config to the metadata database first, then attempts to create a connection pool.
+    /// A `password_ref` outside the allowed secrets paths is rejected before anything is saved.
     pub async fn add_connection(&self, config: ConnectionConfig) -> AppResult<()> {
+        self.connections.check_password_ref(&config)?;
         let id = config.id.clone();
 
-        // Persist to MySQL (created_at uses DEFAULT CURRENT_TIMESTAMP)
-        sqlx::query(
-            "INSERT INTO `connections` (`id`, `name`, `db_type`, `host`, `port`, `username`, `password`, `database_name`, `file_path`)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
-        )
-        .bind(&config.id)
-        .bind(&config.name)
-        .bind(config.db_type.to_string())
-        .bind(&config.host)
-        .bind(config.port)
-        .bind(&config.username)
-        .bind(&config.password)
-        .bind(&config.database)
-        .bind(&config.file_path)
-        .execute(&self.meta_pool)
-        .await
-        .map_err(|e| AppError::DatabaseQuery(format!("Failed to save connection: {}", e)))?;
+        // Persist the config (created_at uses the column default)
+        self.repository
+            .insert_connection(&config)
+            .await
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to save connection: {}", e)))?;
 
         // Then attempt to connect (non-fatal if it fails)
-        match self.try_create_pool(&config).await {
-            Ok(pool) => {
-                self.pools.write().await.insert(id, pool);
-            }
+        match self.connections.open(&config).await {
+            Ok(pool) => self.cache_pool(&config, pool).await,
             Err(e) => {
                 tracing::warn!(id = %id, error = %e, "Connection saved but pool creation failed (will retry on test)");
             }
@@ -210,841 +164,674 @@ impl PoolManager {
         Ok(())
     }
 
-    /// Attempts to create a database connection pool.
-    async fn try_create_pool(&self, config: &ConnectionConfig) -> AppResult<DatabasePool> {
-        let timeout = Duration::from_secs(self.config.connect_timeout_secs);
-        let max_connections = self.config.max_connections;
-
-        match &config.db_type {
-            DbType::MySQL => {
-                let url = self.build_mysql_url(config)?;
-                let pool = MySqlPoolOptions::new()
-                    .max_connections(max_connections)
-                    .acquire_timeout(timeout)
-                    .connect(&url)
-                    .await
-                    .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
-                Ok(DatabasePool::MySQL(pool))
-            }
-            DbType::Postgres => {
-                let url = self.build_postgres_url(config)?;
-                let pool = PgPoolOptions::new()
-                    .max_connections(max_connections)
-                    .acquire_timeout(timeout)
-                    .connect(&url)
-                    .await
-                    .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
-                Ok(DatabasePool::Postgres(pool))
-            }
-            DbType::SQLite => {
-                let path = config
-                    .file_path
-                    .as_deref()
-                    .ok_or_else(|| AppError::Validation("SQLite requires file_path".into()))?;
-                let url = format!("sqlite:{}?mode=rwc", path);
-                let pool = SqlitePoolOptions::new()
-                    .max_connections(1)
-                    .connect(&url)
-                    .await
-                    .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
-                Ok(DatabasePool::SQLite(pool))
-            }
-            DbType::Redis => {
-                let url = self.build_redis_url(config)?;
-                let client = redis::Client::open(url)
-                    .map_err(|e| AppError::RedisConnection(e.to_string()))?;
-                let manager = RedisConnectionManager::new(client)
-                    .await
-                    .map_err(|e| AppError::RedisConnection(e.to_string()))?;
-                Ok(DatabasePool::Redis(manager))
-            }
-            DbType::MongoDB => {
-                let url = self.build_mongodb_url(config)?;
-                let options = mongodb::options::ClientOptions::parse(&url)
-                    .await
-                    .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
-                let client = mongodb::Client::with_options(options)
-                    .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
-                // Verify connection by pinging
-                client
-                    .database("admin")
-                    .run_command(doc! { "ping": 1 })
-                    .await
-                    .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
-                Ok(DatabasePool::MongoDB(client))
-            }
-            _ => Ok(DatabasePool::Unsupported)
-        }
+    /// Returns the config with `password_ref` resolved into `password`.
+    ///
+    /// # Errors
+    /// Fails if the secret cannot be resolved.
+    pub async fn resolve_password(&self, config: &ConnectionConfig) -> AppResult<ConnectionConfig> {
+        self.connections.resolve_password(config).await
     }
 
     /// Tests a database connection.
     /// If no pool exists (e.g., initial connection failed), attempts to create one first.
     pub async fn test_connection(&self, id: &str) -> AppResult<Duration> {
         // If no pool exists, try to create one from saved config in DB
-        {
-            let pools = self.pools.read().await;
-            if !pools.contains_key(id) {
-                drop(pools);
-                if let Some(config) = self.get_connection(id).await {
-                    let pool = self.try_create_pool(&config).await?;
-                    self.pools.write().await.insert(id.to_string(), pool);
-                } else {
-                    return Err(AppError::ConnectionNotFound(id.to_string()));
-                }
-            }
-        }
-
-        let pools = self.pools.read().await;
-        let pool = pools
-            .get(id)
-            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
+        let pool = self.get_or_create_pool(id).await?;
 
         let start = std::time::Instant::now();
+        pool.connection().ping().await?;
+        Ok(start.elapsed())
+    }
 
-        match pool {
-            DatabasePool::MySQL(pool) => {
-                sqlx::query("SELECT 1")
-                    .execute(pool)
-                    .await
-                    .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-            }
-            DatabasePool::Postgres(pool) => {
-                sqlx::query("SELECT 1")
-                    .execute(pool)
-                    .await
-                    .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-            }
-            DatabasePool::SQLite(pool) => {
-                sqlx::query("SELECT 1")
-                    .execute(pool)
-                    .await
-                    .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-            }
-            DatabasePool::Redis(manager) => {
-                let mut conn = manager.clone();
-                redis::cmd("PING")
-                    .query_async::<String>(&mut conn)
-                    .await
-                    .map_err(|e| AppError::RedisOperation(e.to_string()))?;
-            }
-            DatabasePool::MongoDB(client) => {
-                client
-                    .database("admin")
-                    .run_command(doc! { "ping": 1 })
-                    .await
-                    .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-            }
-            DatabasePool::Unsupported => {
-                return Err(AppError::UnsupportedDatabaseType("Connection type not supported yet".into()));
+    /// Tests a connection config stage by stage (see [`diagnostics`]) on a
+    /// temporary pool that is closed afterwards; a cached pool is not used.
+    ///
+    /// SQLite files are not created; a missing file fails the login stage.
+    pub async fn diagnose(&self, config: &ConnectionConfig) -> Vec<StageResult> {
+        let timeout = Duration::from_secs(self.config.get().connect_timeout_secs);
+        let mut diagnosis = Diagnosis::default();
+        diagnostics::probe_network(config, timeout, &mut diagnosis).await;
+
+        let login = async {
+            if let (DbType::SQLite, Some(path)) = (&config.db_type, &config.file_path) {
+                if !std::path::Path::new(path).exists() {
+                    return Err(AppError::DatabaseConnection(format!(
+                        "SQLite file {} does not exist, it is created when the connection is saved",
+                        path
+                    )));
+                }
             }
+            self.connections.open(config).await
+        };
+        if let Some(pool) = diagnosis.run(TestStage::Authentication, login).await {
+            diagnosis.run(TestStage::Query, pool.connection().ping()).await;
+            pool.connection().close().await;
+        }
+        diagnosis.finish()
+    }
+
+    /// Replaces the stored password of a connection.
+    ///
+    /// The new password is checked by opening a pool with it first; only then
+    /// is it saved and the cached pool replaced. Queries running on the old pool
+    /// keep their connections, which close once the last of them finishes.
+    ///
+    /// # Errors
+    /// `ConnectionNotFound` for unknown IDs, `Conflict` if the password comes
+    /// from `password_ref`, and the connection error if the password is rejected.
+    pub async fn rotate_password(&self, id: &str, password: String) -> AppResult<ConnectionConfig> {
+        let mut config = self
+            .get_connection(id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
+        if config.password_ref.is_some() {
+            return Err(AppError::Conflict(
+                "the password is read from password_ref, rotate it in the secrets backend".into(),
+            ));
         }
+        config.password = Some(password);
+        let pool = self.connections.open(&config).await?;
+        pool.connection().ping().await?;
 
-        Ok(start.elapsed())
+        self.repository
+            .update_password(id, config.password.as_deref())
+            .await
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to update password: {}", e)))?;
+        self.cache_pool(&config, pool).await;
+        Ok(config)
     }
 
     /// Removes a database connection from DB and pool cache.
     pub async fn remove_connection(&self, id: &str) -> AppResult<()> {
-        self.pools.write().await.remove(id);
+        self.drop_pools(id).await;
+        self.states.forget(id).await;
 
-        let result = sqlx::query("DELETE FROM `connections` WHERE `id` = ?")
-            .bind(id)
-            .execute(&self.meta_pool)
+        let result = self
+            .repository
+            .delete_connection(id)
             .await
             .map_err(|e| AppError::DatabaseQuery(format!("Failed to delete connection: {}", e)))?;
 
-        if result.rows_affected() == 0 {
+        if result == 0 {
             return Err(AppError::ConnectionNotFound(id.to_string()));
         }
         Ok(())
     }
 
-    /// Gets all connection configurations from MySQL.
-    pub async fn list_connections(&self) -> Vec<ConnectionConfig> {
-        let rows = sqlx::query_as::<_, ConnectionRow>(
-            "SELECT `id`, `name`, `db_type`, `host`, `port`, `username`, `password`, `database_name`, `file_path`, CAST(`created_at` AS CHAR) as created_at FROM `connections` ORDER BY `created_at` DESC"
-        )
-        .fetch_all(&self.meta_pool)
-        .await
-        .unwrap_or_default();
-
-        rows.into_iter().map(|r| r.into_config()).collect()
-    }
+    /// Stores an imported connection, keeping its ID and creation time.
+    ///
+    /// Returns `false` without changes if the ID exists and `overwrite` is not set;
+    /// a `password_ref` outside the allowed secrets paths is rejected.
+    pub async fn import_connection(&self, config: ConnectionConfig, overwrite: bool) -> AppResult<bool> {
+        if self.get_connection(&config.id).await.is_some() && !overwrite {
+            return Ok(false);
+        }
+        self.connections.check_password_ref(&config)?;
+        self.repository
+            .import_connection(&config)
+            .await
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to import connection: {}", e)))?;
 
-    /// Gets a connection configuration by ID from MySQL.
-    pub async fn get_connection(&self, id: &str) -> Option<ConnectionConfig> {
-        sqlx::query_as::<_, ConnectionRow>(
-            "SELECT `id`, `name`, `db_type`, `host`, `port`, `username`, `password`, `database_name`, `file_path`, CAST(`created_at` AS CHAR) as created_at FROM `connections` WHERE `id` = ?"
-        )
-        .bind(id)
-        .fetch_optional(&self.meta_pool)
-        .await
-        .ok()
-        .flatten()
-        .map(|r| r.into_config())
+        // Drop any pool built from the previous settings; connect lazily if this fails.
+        self.drop_pools(&config.id).await;
+        match self.connections.open(&config).await {
+            Ok(pool) => self.cache_pool(&config, pool).await,
+            Err(e) => {
+                tracing::warn!(id = %config.id, error = %e, "Connection imported but pool creation failed");
+            }
+        }
+        Ok(true)
     }
 
-    /// Gets a connection pool by ID (from cache).
-    pub async fn get_pool(&self, id: &str) -> Option<DatabasePool> {
-        self.pools.read().await.get(id).cloned()
+    /// Replaces the allowlist of a connection; an empty allowlist removes it.
+    pub async fn set_allowlist(&self, id: &str, allowlist: ConnectionAllowlist) -> AppResult<ConnectionConfig> {
+        let allowlist = Some(allowlist).filter(|a| !a.is_empty());
+        let result = self
+            .repository
+            .update_allowlist(id, allowlist.as_ref())
+            .await
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to update allowlist: {}", e)))?;
+        if result == 0 && self.get_connection(id).await.is_none() {
+            return Err(AppError::ConnectionNotFound(id.to_string()));
+        }
+        self.get_connection(id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
     }
 
-    /// Checks if a connection exists in DB.
-    pub async fn connection_exists(&self, id: &str) -> bool {
-        self.get_connection(id).await.is_some()
+    /// Replaces the result masking of a connection; masking without rules removes it.
+    pub async fn set_masking(&self, id: &str, masking: ConnectionMasking) -> AppResult<ConnectionConfig> {
+        let masking = Some(masking).filter(|m| !m.is_empty());
+        let result = self
+            .repository
+            .update_masking(id, masking.as_ref())
+            .await
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to update masking: {}", e)))?;
+        if result == 0 && self.get_connection(id).await.is_none() {
+            return Err(AppError::ConnectionNotFound(id.to_string()));
+        }
+        self.get_connection(id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
     }
 
-    /// Gets the number of saved connections from DB.
-    pub async fn connection_count(&self) -> usize {
-        let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM `connections`")
-            .fetch_one(&self.meta_pool)
+    /// Replaces the statement policy of a connection; a policy without types removes it.
+    pub async fn set_statement_policy(&self, id: &str, policy: StatementPolicy) -> AppResult<ConnectionConfig> {
+        let policy = Some(policy).filter(|p| !p.is_empty());
+        let result = self
+            .repository
+            .update_statement_policy(id, policy.as_ref())
+            .await
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to update statement policy: {}", e)))?;
+        if result == 0 && self.get_connection(id).await.is_none() {
+            return Err(AppError::ConnectionNotFound(id.to_string()));
+        }
+        self.get_connection(id)
             .await
-            .unwrap_or((0,));
-        row.0 as usize
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
     }
 
-    // ============== URL Builders ==============
-
-    fn build_mysql_url(&self, config: &ConnectionConfig) -> AppResult<String> {
-        let host = config
-            .host
-            .as_deref()
-            .ok_or_else(|| AppError::Validation("MySQL requires host".into()))?;
-        let port = config.port.unwrap_or(3306);
-        let username = config.username.as_deref().unwrap_or("root");
-        let password = config.password.as_deref().unwrap_or("");
-        let database = config.database.as_deref().unwrap_or("");
-
-        Ok(format!(
-            "mysql://{}:{}@{}:{}/{}?charset=utf8mb4",
-            username, password, host, port, database
-        ))
+    /// Sets the default query timeout of a connection; `None` restores the service default.
+    pub async fn set_query_timeout(&self, id: &str, timeout_ms: Option<u64>) -> AppResult<ConnectionConfig> {
+        let result = self
+            .repository
+            .update_query_timeout(id, timeout_ms)
+            .await
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to update query timeout: {}", e)))?;
+        if result == 0 && self.get_connection(id).await.is_none() {
+            return Err(AppError::ConnectionNotFound(id.to_string()));
+        }
+        self.get_connection(id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
     }
 
-    fn build_postgres_url(&self, config: &ConnectionConfig) -> AppResult<String> {
-        let host = config
-            .host
-            .as_deref()
-            .ok_or_else(|| AppError::Validation("PostgreSQL requires host".into()))?;
-        let port = config.port.unwrap_or(5432);
-        let username = config.username.as_deref().unwrap_or("postgres");
-        let password = config.password.as_deref().unwrap_or("");
-        let database = config.database.as_deref().unwrap_or("postgres");
-
-        Ok(format!(
-            "postgres://{}:{}@{}:{}/{}",
-            username, password, host, port, database
-        ))
+    /// Pins or unpins a connection; pinned connections are warmed at service start.
+    pub async fn set_pinned(&self, id: &str, pinned: bool) -> AppResult<ConnectionConfig> {
+        let result = self
+            .repository
+            .update_pinned(id, pinned)
+            .await
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to update pinned flag: {}", e)))?;
+        if result == 0 && self.get_connection(id).await.is_none() {
+            return Err(AppError::ConnectionNotFound(id.to_string()));
+        }
+        self.get_connection(id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
     }
 
-    fn build_redis_url(&self, config: &ConnectionConfig) -> AppResult<String> {
-        let host = config
-            .host
-            .as_deref()
-            .ok_or_else(|| AppError::Validation("Redis requires host".into()))?;
-        let port = config.port.unwrap_or(6379);
-
-        if let Some(password) = &config.password {
-            Ok(format!("redis://:{}@{}:{}", password, host, port))
-        } else {
-            Ok(format!("redis://{}:{}", host, port))
+    /// Replaces the pool sizing of a connection; `None` restores the service defaults.
+    ///
+    /// The open pool is closed so the next use recreates it with the new options.
+    pub async fn set_pool_options(&self, id: &str, options: Option<ConnectionPoolOptions>) -> AppResult<ConnectionConfig> {
+        let result = self
+            .repository
+            .update_pool_options(id, &options.unwrap_or_default())
+            .await
+            .map_err(|e| AppError::DatabaseQuery(format!("Failed to update pool options: {}", e)))?;
+        if result == 0 && self.get_connection(id).await.is_none() {
+            return Err(AppError::ConnectionNotFound(id.to_string()));
         }
+        self.drop_pools(id).await;
+        self.get_connection(id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
     }
 
-    fn build_mongodb_url(&self, config: &ConnectionConfig) -> AppResult<String> {
-        let host = config
-            .host
-            .as_deref()
-            .ok_or_else(|| AppError::Validation("MongoDB requires host".into()))?;
-        let port = config.port.unwrap_or(27017);
-
-        let auth = match (&config.username, &config.password) {
-            (Some(user), Some(pass)) if !user.is_empty() => format!("{}:{}@", user, pass),
-            _ => String::new(),
-        };
-        let db = config.database.as_deref().unwrap_or("");
-        Ok(format!("mongodb://{}{}:{}/{}", auth, host, port, db))
+    /// Gets all connection configurations from the metadata database.
+    pub async fn list_connections(&self) -> Vec<ConnectionConfig> {
+        self.repository.list_connections().await.unwrap_or_default()
     }
 
-    // ============== Monitoring Methods ==============
+    /// Gets a connection configuration by ID from the metadata database.
+    pub async fn get_connection(&self, id: &str) -> Option<ConnectionConfig> {
+        self.repository.get_connection(id).await.ok().flatten()
+    }
 
-    /// Gets the connection pool stats for a given connection.
-    pub async fn get_pool_stats(&self, id: &str) -> AppResult<ConnectionPoolStats> {
-        let pools = self.pools.read().await;
-        match pools.get(id) {
-            Some(pool) => match pool {
-                DatabasePool::MySQL(p) => Ok(ConnectionPoolStats {
-                    active: p.size() as u32 - p.num_idle() as u32,
-                    idle: p.num_idle() as u32,
-                    max_size: self.config.max_connections,
-                    is_connected: true,
-                }),
-                DatabasePool::Postgres(p) => Ok(ConnectionPoolStats {
-                    active: p.size() as u32 - p.num_idle() as u32,
-                    idle: p.num_idle() as u32,
-                    max_size: self.config.max_connections,
-                    is_connected: true,
-                }),
-                DatabasePool::SQLite(p) => Ok(ConnectionPoolStats {
-                    active: p.size() as u32 - p.num_idle() as u32,
-                    idle: p.num_idle() as u32,
-                    max_size: 1,
-                    is_connected: true,
-                }),
-                DatabasePool::Redis(_) => Ok(ConnectionPoolStats {
-                    active: 1,
-                    idle: 0,
-                    max_size: 1,
-                    is_connected: true,
-                }),
-                DatabasePool::MongoDB(_) => Ok(ConnectionPoolStats {
-                    active: 1,
-                    idle: 0,
-                    max_size: self.config.max_connections,
-                    is_connected: true,
-                }),
-                DatabasePool::Unsupported => Ok(ConnectionPoolStats {
-                    active: 0,
-                    idle: 0,
-                    max_size: 0,
-                    is_connected: false,
-                }),
-            },
-            None => Ok(ConnectionPoolStats {
-                active: 0,
-                idle: 0,
-                max_size: self.config.max_connections,
-                is_connected: false,
-            }),
-        }
+    /// Resolves the timeout of a query: the requested one, else the
+    /// connection's default, else the service default.
+    pub fn query_timeout(&self, config: &ConnectionConfig, requested_ms: Option<u64>) -> Duration {
+        Duration::from_millis(
+            requested_ms
+                .or(config.query_timeout_ms)
+                .unwrap_or(self.config.get().query_timeout_ms),
+        )
     }
 
-    /// Gets database server statistics for a connection.
-    pub async fn get_database_stats(&self, id: &str) -> AppResult<DatabaseStats> {
-        let pools = self.pools.read().await;
-        let pool = pools
-            .get(id)
-            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
+    /// Returns the metadata database pool, for modules that keep their own tables.
+    pub fn meta_pool(&self) -> &MetaPool {
+        &self.meta_pool
+    }
 
-        match pool {
-            DatabasePool::MySQL(p) => self.get_mysql_stats(p).await,
-            DatabasePool::Postgres(p) => self.get_postgres_stats(p).await,
-            DatabasePool::SQLite(_) => Ok(DatabaseStats {
-                server_version: Some("SQLite (embedded)".to_string()),
-                ..Default::default()
-            }),
-            DatabasePool::Redis(manager) => self.get_redis_stats(manager).await,
-            DatabasePool::MongoDB(client) => self.get_mongodb_stats(client).await,
-            DatabasePool::Unsupported => Err(AppError::UnsupportedDatabaseType(
-                "Monitoring not supported".into(),
-            )),
-        }
+    /// Returns the metadata repository.
+    pub fn repository(&self) -> &dyn MetadataRepository {
+        self.repository.as_ref()
     }
 
-    /// Gets active processes for a connection.
-    pub async fn get_processes(&self, id: &str) -> AppResult<Vec<ProcessInfo>> {
-        let pools = self.pools.read().await;
-        let pool = pools
-            .get(id)
-            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
+    /// Returns the workload statistics recorder.
+    pub fn workload(&self) -> &Arc<WorkloadStats> {
+        &self.workload
+    }
 
-        match pool {
-            DatabasePool::MySQL(p) => self.get_mysql_processes(p).await,
-            DatabasePool::Postgres(p) => self.get_postgres_processes(p).await,
-            _ => Ok(vec![]),
-        }
+    /// Gets a connection pool by ID (from cache).
+    pub async fn get_pool(&self, id: &str) -> Option<DatabasePool> {
+        self.connections.pool(id).await
     }
 
-    /// Lists databases on the server for a connection.
-    pub async fn get_databases(&self, id: &str) -> AppResult<Vec<DatabaseInfo>> {
-        let pools = self.pools.read().await;
-        let pool = pools
-            .get(id)
-            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
+    /// Returns the IDs of connections with an open pool.
+    pub async fn pooled_connection_ids(&self) -> Vec<String> {
+        self.connections.connection_ids().await
+    }
 
-        match pool {
-            DatabasePool::MySQL(p) => self.get_mysql_databases(p).await,
-            DatabasePool::Postgres(p) => self.get_postgres_databases(p).await,
-            DatabasePool::MongoDB(client) => self.get_mongodb_databases(client).await,
-            _ => Ok(vec![]),
+    /// Gets a connection pool by ID, creating it from the saved config if not cached yet.
+    ///
+    /// Fails fast with `ServiceUnavailable` while a lost pool waits for its
+    /// next reconnect attempt (see [`pool_state`](crate::pool_state)).
+    pub async fn get_or_create_pool(&self, id: &str) -> AppResult<DatabasePool> {
+        if let Some(pool) = self.get_pool(id).await {
+            return Ok(pool);
+        }
+        if let Some(status) = self.states.waiting(id).await {
+            return Err(AppError::ServiceUnavailable(format!(
+                "connection {} is {:?} after {} failures, next attempt at {}: {}",
+                id,
+                status.state,
+                status.failures,
+                status.next_attempt_at.unwrap_or_default(),
+                status.last_error.unwrap_or_default()
+            )));
         }
-    }
 
-    /// Gets full monitoring overview.
-    pub async fn get_monitor_overview(&self, id: &str) -> AppResult<MonitorOverview> {
         let config = self
             .get_connection(id)
             .await
             .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
-
-        let stats = self.get_database_stats(id).await.unwrap_or_default();
-        let pool = self.get_pool_stats(id).await?;
-
-        Ok(MonitorOverview {
-            connection_id: id.to_string(),
-            connection_name: config.name.clone(),
-            db_type: config.db_type.to_string(),
-            stats,
-            pool,
-            timestamp: chrono::Utc::now().to_rfc3339(),
-        })
+        let pool = match self.connections.open(&config).await {
+            Ok(pool) => pool,
+            Err(e) => {
+                self.states.failed(id, e.to_string()).await;
+                return Err(e);
+            }
+        };
+        self.cache_pool(&config, pool.clone()).await;
+        Ok(pool)
     }
 
-    // ---- MySQL monitoring helpers ----
-
-    /// Robustly extract a String from a MySQL row.
-    /// Falls back to reading raw bytes if the String decode fails (e.g. binary collation).
-    fn mysql_get_string(row: &MySqlRow, col: &str) -> String {
-        row.try_get::<String, _>(col)
-            .unwrap_or_else(|_| {
-                row.try_get::<Vec<u8>, _>(col)
-                    .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
-                    .unwrap_or_default()
-            })
+    /// Caches an opened pool and marks the connection connected. Pools of
+    /// other databases built with the previous settings are dropped.
+    async fn cache_pool(&self, config: &ConnectionConfig, pool: DatabasePool) {
+        self.connections.insert(config.clone(), pool).await;
+        self.states.connected(&config.id).await;
     }
 
-    /// Robustly extract an optional String from a MySQL row.
-    fn mysql_get_opt_string(row: &MySqlRow, col: &str) -> Option<String> {
-        row.try_get::<Option<String>, _>(col)
-            .unwrap_or_else(|_| {
-                row.try_get::<Option<Vec<u8>>, _>(col)
-                    .ok()
-                    .flatten()
-                    .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
-            })
+    /// Drops the cached pools of a connection. Queries running on them keep
+    /// their connections until they finish.
+    async fn drop_pools(&self, id: &str) {
+        self.connections.remove(id).await;
     }
 
-    async fn get_mysql_stats(&self, pool: &MySqlPool) -> AppResult<DatabaseStats> {
-        let mut stats = DatabaseStats::default();
+    /// Lists the connections with an open pool or a tracked pool state, e.g.
+    /// pools lost and waiting to reconnect.
+    pub async fn list_pools(&self) -> AppResult<Vec<CachedPool>> {
+        let mut ids = self.pooled_connection_ids().await;
+        ids.extend(self.states.ids().await);
+        ids.sort();
+        ids.dedup();
 
-        // SHOW GLOBAL STATUS
-        let rows = sqlx::query("SHOW GLOBAL STATUS")
-            .fetch_all(pool)
+        let configs: HashMap<String, ConnectionConfig> = self
+            .list_connections()
             .await
-            .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        for row in &rows {
-            let name: String = Self::mysql_get_string(row, "Variable_name");
-            let value: String = Self::mysql_get_string(row, "Value");
-            match name.as_str() {
-                "Uptime" => stats.uptime_seconds = value.parse().unwrap_or(0),
-                "Questions" | "Queries" => {
-                    let v = value.parse().unwrap_or(0u64);
-                    if v > stats.total_queries {
-                        stats.total_queries = v;
-                    }
-                }
-                "Threads_connected" => {
-                    stats.active_connections = value.parse().unwrap_or(0)
-                }
-                "Slow_queries" => stats.slow_queries = value.parse().unwrap_or(0),
-                "Bytes_received" => stats.bytes_received = value.parse().unwrap_or(0),
-                "Bytes_sent" => stats.bytes_sent = value.parse().unwrap_or(0),
-                "Innodb_buffer_pool_pages_total" => {
-                    let pages: u64 = value.parse().unwrap_or(0);
-                    stats.buffer_pool_size = Some(pages * 16384); // 16KB per page
-                }
-                _ => {}
-            }
+            .into_iter()
+            .map(|config| (config.id.clone(), config))
+            .collect();
+        let mut database_pools = self.connections.database_pools().await;
+
+        let mut pools = Vec::with_capacity(ids.len());
+        for id in ids {
+            let Some(config) = configs.get(&id) else {
+                continue;
+            };
+            let mut databases = database_pools.remove(&id).unwrap_or_default();
+            databases.sort();
+            pools.push(CachedPool {
+                stats: self.get_pool_stats(&id).await?,
+                connection_id: id,
+                connection_name: config.name.clone(),
+                db_type: config.db_type.to_string(),
+                databases,
+            });
         }
+        Ok(pools)
+    }
 
-        // SHOW GLOBAL VARIABLES for max_connections and version
-        let vars = sqlx::query("SHOW GLOBAL VARIABLES WHERE Variable_name IN ('max_connections', 'version')")
-            .fetch_all(pool)
+    /// Drops the cached pools of a connection and opens its pool again,
+    /// resetting the self-healing state.
+    ///
+    /// The dropped pools are closed in the background once the queries running
+    /// on them finish. When reopening fails the connection is left without a
+    /// pool and the next request opens it again.
+    pub async fn reset_pool(&self, id: &str) -> AppResult<ConnectionPoolStats> {
+        let config = self
+            .get_connection(id)
             .await
-            .unwrap_or_default();
-
-        for row in &vars {
-            let name: String = Self::mysql_get_string(row, "Variable_name");
-            let value: String = Self::mysql_get_string(row, "Value");
-            match name.as_str() {
-                "max_connections" => stats.max_connections = value.parse().unwrap_or(0),
-                "version" => stats.server_version = Some(format!("MySQL {}", value)),
-                _ => {}
-            }
-        }
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
 
-        if stats.uptime_seconds > 0 {
-            stats.queries_per_second =
-                stats.total_queries as f64 / stats.uptime_seconds as f64;
-        }
+        let dropped = self.connections.remove(id).await;
+        self.states.forget(id).await;
+        tokio::spawn(async move {
+            for pool in dropped {
+                pool.connection().close().await;
+            }
+        });
+        tracing::info!(id = %id, name = %config.name, "Pool dropped for reset");
 
-        Ok(stats)
+        let pool = self.connections.open(&config).await?;
+        self.cache_pool(&config, pool).await;
+        tracing::info!(id = %id, name = %config.name, "Pool reopened");
+        self.get_pool_stats(id).await
     }
 
-    async fn get_mysql_processes(&self, pool: &MySqlPool) -> AppResult<Vec<ProcessInfo>> {
-        let rows = sqlx::query(
-            "SELECT ID, USER, HOST, DB, COMMAND, TIME, STATE, INFO
-             FROM information_schema.PROCESSLIST
-             ORDER BY TIME DESC"
-        )
-        .fetch_all(pool)
-        .await
-        .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let mut processes = Vec::new();
-        for row in &rows {
-            processes.push(ProcessInfo {
-                id: row.try_get::<u64, _>("ID").unwrap_or(0),
-                user: Self::mysql_get_string(row, "USER"),
-                host: Self::mysql_get_string(row, "HOST"),
-                db: Self::mysql_get_opt_string(row, "DB"),
-                command: Self::mysql_get_string(row, "COMMAND"),
-                time: row.try_get::<i32, _>("TIME").unwrap_or(0) as u64,
-                state: Self::mysql_get_opt_string(row, "STATE"),
-                info: Self::mysql_get_opt_string(row, "INFO"),
-            });
-        }
-        Ok(processes)
+    /// Self-healing state of a connection, if its pool was ever opened.
+    pub async fn pool_status(&self, id: &str) -> Option<PoolStatus> {
+        self.states.status(id).await
     }
 
-    async fn get_mysql_databases(&self, pool: &MySqlPool) -> AppResult<Vec<DatabaseInfo>> {
-        let rows = sqlx::query(
-            "SELECT 
-                s.SCHEMA_NAME,
-                COUNT(t.TABLE_NAME) as tables_count,
-                CAST(COALESCE(SUM(t.DATA_LENGTH + t.INDEX_LENGTH) / 1024 / 1024, 0) AS DOUBLE) as size_mb
-             FROM information_schema.SCHEMATA s
-             LEFT JOIN information_schema.TABLES t ON s.SCHEMA_NAME = t.TABLE_SCHEMA
-             GROUP BY s.SCHEMA_NAME
-             ORDER BY size_mb DESC"
-        )
-        .fetch_all(pool)
-        .await
-        .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let mut databases = Vec::new();
-        for row in &rows {
-            databases.push(DatabaseInfo {
-                name: Self::mysql_get_string(row, "SCHEMA_NAME"),
-                tables_count: row.try_get::<i64, _>("tables_count").unwrap_or(0) as u32,
-                size_mb: row.try_get::<f64, _>("size_mb").unwrap_or(0.0),
-            });
-        }
-        Ok(databases)
+    /// Starts the task that probes open pools and reconnects lost ones.
+    pub fn spawn_probe(self: &Arc<Self>) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(manager.states.probe_interval);
+            loop {
+                interval.tick().await;
+                manager.probe_pools().await;
+            }
+        });
     }
 
-    // ---- PostgreSQL monitoring helpers ----
-
-    async fn get_postgres_stats(&self, pool: &PgPool) -> AppResult<DatabaseStats> {
-        let mut stats = DatabaseStats::default();
-
-        // Server version
-        if let Ok(row) = sqlx::query("SHOW server_version").fetch_one(pool).await {
-            let ver: String = row.try_get("server_version").unwrap_or_default();
-            stats.server_version = Some(format!("PostgreSQL {}", ver));
+    /// Pings every open pool, dropping the ones that fail, then retries the
+    /// lost pools whose backoff has elapsed.
+    async fn probe_pools(&self) {
+        let timeout = Duration::from_secs(self.config.get().connect_timeout_secs);
+        for id in self.connections.connection_ids().await {
+            let Some(pool) = self.connections.pool(&id).await else {
+                continue;
+            };
+            // A pool with every connection in use is busy, not lost
+            if pool.connection().saturated() {
+                continue;
+            }
+            let result = tokio::time::timeout(timeout, pool.connection().ping())
+                .await
+                .unwrap_or_else(|_| Err(AppError::Timeout("health probe timed out".into())));
+            match result {
+                Ok(()) => self.states.connected(&id).await,
+                Err(e) => {
+                    self.drop_pools(&id).await;
+                    self.states.failed(&id, e.to_string()).await;
+                }
+            }
         }
 
-        // Active connections
-        if let Ok(row) = sqlx::query("SELECT count(*) as cnt FROM pg_stat_activity")
-            .fetch_one(pool)
-            .await
-        {
-            stats.active_connections = row.try_get::<i64, _>("cnt").unwrap_or(0) as u32;
+        for id in self.states.due().await {
+            let Some(config) = self.get_connection(&id).await else {
+                self.states.forget(&id).await;
+                continue;
+            };
+            let reconnect = async {
+                let pool = self.connections.open(&config).await?;
+                pool.connection().ping().await?;
+                Ok::<_, AppError>(pool)
+            };
+            match tokio::time::timeout(timeout, reconnect).await {
+                Ok(Ok(pool)) => self.cache_pool(&config, pool).await,
+                Ok(Err(e)) => self.states.failed(&id, e.to_string()).await,
+                Err(_) => self.states.failed(&id, "reconnect timed out".into()).await,
+            }
         }
+    }
 
-        // Max connections
-        if let Ok(row) = sqlx::query("SHOW max_connections").fetch_one(pool).await {
-            let val: String = row.try_get("max_connections").unwrap_or_default();
-            stats.max_connections = val.parse().unwrap_or(0);
+    /// Gets the MySQL pool for a connection, failing for other database types.
+    pub async fn get_mysql_pool(&self, id: &str) -> AppResult<MySqlPool> {
+        match self.get_or_create_pool(id).await? {
+            DatabasePool::MySQL(pool) => Ok(pool),
+            _ => Err(AppError::UnsupportedDatabaseType(
+                "This operation is only supported for MySQL connections".into(),
+            )),
         }
+    }
 
-        // Aggregated stats from pg_stat_database
-        if let Ok(row) = sqlx::query(
-            "SELECT COALESCE(SUM(xact_commit + xact_rollback), 0) as total_queries,
-                    COALESCE(SUM(blks_read), 0) as blks_read,
-                    COALESCE(SUM(blks_hit), 0) as blks_hit
-             FROM pg_stat_database"
-        )
-        .fetch_one(pool)
-        .await
-        {
-            stats.total_queries = row.try_get::<i64, _>("total_queries").unwrap_or(0) as u64;
-        }
+    /// Checks that the metadata database answers.
+    pub async fn ping_metadata(&self) -> AppResult<()> {
+        meta_query!(&self.meta_pool, |pool| {
+            sqlx::query("SELECT 1").execute(pool).await.map(|_| ())
+        })?;
+        Ok(())
+    }
 
-        // Uptime
-        if let Ok(row) = sqlx::query(
-            "SELECT EXTRACT(EPOCH FROM (now() - pg_postmaster_start_time()))::bigint as uptime"
-        )
-        .fetch_one(pool)
-        .await
-        {
-            stats.uptime_seconds = row.try_get::<i64, _>("uptime").unwrap_or(0) as u64;
-        }
+    /// Gets the number of saved connections from DB.
+    pub async fn connection_count(&self) -> usize {
+        self.repository.count_connections().await.unwrap_or(0) as usize
+    }
 
-        if stats.uptime_seconds > 0 {
-            stats.queries_per_second =
-                stats.total_queries as f64 / stats.uptime_seconds as f64;
-        }
+    // ============== Monitoring Methods ==============
 
+    /// Gets the connection pool stats for a given connection.
+    ///
+    /// Drivers that do not track their connections report the service's pool size limit.
+    pub async fn get_pool_stats(&self, id: &str) -> AppResult<ConnectionPoolStats> {
+        let status = self.states.status(id).await;
+        let usage = self.get_pool(id).await.map(|pool| {
+            pool.connection().usage().unwrap_or(PoolUsage {
+                active: 0,
+                idle: 0,
+                max_size: self.config.get().max_connections,
+            })
+        });
+        let mut stats = match usage {
+            Some(usage) => ConnectionPoolStats {
+                active: usage.active,
+                idle: usage.idle,
+                max_size: usage.max_size,
+                is_connected: true,
+                status: None,
+            },
+            None => ConnectionPoolStats {
+                active: 0,
+                idle: 0,
+                max_size: self.config.get().max_connections,
+                is_connected: false,
+                status: None,
+            },
+        };
+        stats.is_connected &= status.as_ref().is_none_or(|s| s.state == PoolState::Connected);
+        stats.status = status;
         Ok(stats)
     }
 
-    async fn get_postgres_processes(&self, pool: &PgPool) -> AppResult<Vec<ProcessInfo>> {
-        let rows = sqlx::query(
-            "SELECT pid, usename, client_addr, datname, state, query, 
-                    EXTRACT(EPOCH FROM (now() - query_start))::bigint as duration
-             FROM pg_stat_activity
-             WHERE state IS NOT NULL
-             ORDER BY duration DESC NULLS LAST
-             LIMIT 50"
-        )
-        .fetch_all(pool)
-        .await
-        .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let mut processes = Vec::new();
-        for row in &rows {
-            processes.push(ProcessInfo {
-                id: row.try_get::<i32, _>("pid").unwrap_or(0) as u64,
-                user: row.try_get::<String, _>("usename").unwrap_or_default(),
-                host: row
-                    .try_get::<Option<String>, _>("client_addr")
-                    .unwrap_or(None)
-                    .unwrap_or_else(|| "local".to_string()),
-                db: row.try_get::<Option<String>, _>("datname").unwrap_or(None),
-                command: row
-                    .try_get::<Option<String>, _>("state")
-                    .unwrap_or(None)
-                    .unwrap_or_else(|| "unknown".to_string()),
-                time: row.try_get::<i64, _>("duration").unwrap_or(0) as u64,
-                state: row.try_get::<Option<String>, _>("state").unwrap_or(None),
-                info: row.try_get::<Option<String>, _>("query").unwrap_or(None),
-            });
-        }
-        Ok(processes)
+    /// Gets database server statistics for a connection.
+    pub async fn get_database_stats(&self, id: &str) -> AppResult<DatabaseStats> {
+        self.open_connection(id).await?.connection().stats().await
     }
 
-    async fn get_postgres_databases(&self, pool: &PgPool) -> AppResult<Vec<DatabaseInfo>> {
-        let rows = sqlx::query(
-            "SELECT d.datname as name,
-                    (SELECT count(*) FROM information_schema.tables WHERE table_catalog = d.datname) as tables_count,
-                    pg_database_size(d.datname) / 1024.0 / 1024.0 as size_mb
-             FROM pg_database d
-             WHERE d.datistemplate = false
-             ORDER BY size_mb DESC"
-        )
-        .fetch_all(pool)
-        .await
-        .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let mut databases = Vec::new();
-        for row in &rows {
-            databases.push(DatabaseInfo {
-                name: row.try_get::<String, _>("name").unwrap_or_default(),
-                tables_count: row.try_get::<i64, _>("tables_count").unwrap_or(0) as u32,
-                size_mb: row.try_get::<f64, _>("size_mb").unwrap_or(0.0),
-            });
-        }
-        Ok(databases)
+    /// Gets active processes for a connection.
+    pub async fn get_processes(&self, id: &str) -> AppResult<Vec<ProcessInfo>> {
+        self.open_connection(id).await?.connection().processes().await
     }
 
-    // ============== Query Execution ==============
+    /// Lists databases on the server for a connection.
+    pub async fn get_databases(&self, id: &str) -> AppResult<Vec<DatabaseInfo>> {
+        self.connections.databases(id).await
+    }
 
-    /// Executes a SQL query against a connection and returns results.
-    pub async fn execute_query(&self, id: &str, sql: &str, limit: u32) -> AppResult<QueryResult> {
-        let start = std::time::Instant::now();
+    /// Cached pool of a connection; `ConnectionNotFound` if it is not open.
+    async fn open_connection(&self, id: &str) -> AppResult<DatabasePool> {
+        self.get_pool(id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
+    }
 
-        let pools = self.pools.read().await;
-        let pool = pools
-            .get(id)
+    /// Gets full monitoring overview.
+    pub async fn get_monitor_overview(&self, id: &str) -> AppResult<MonitorOverview> {
+        let config = self
+            .get_connection(id)
+            .await
             .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
 
-        match pool {
-            DatabasePool::MySQL(p) => self.execute_mysql_query(p, sql, limit, start).await,
-            DatabasePool::Postgres(p) => self.execute_postgres_query(p, sql, limit, start).await,
-            _ => Err(AppError::UnsupportedDatabaseType(
-                "SQL query execution is only supported for MySQL and PostgreSQL".to_string(),
-            )),
-        }
+        let stats = self.get_database_stats(id).await.unwrap_or_default();
+        let pool = self.get_pool_stats(id).await?;
+
+        Ok(MonitorOverview {
+            connection_id: id.to_string(),
+            connection_name: config.name.clone(),
+            db_type: config.db_type.to_string(),
+            stats,
+            pool,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
     }
 
-    async fn execute_mysql_query(
+    // ============== Query Execution ==============
+
+    /// Executes a SQL query against a connection and returns results.
+    ///
+    /// Runs on the [`QueryExecutor`]: the statement runs in `database` when
+    /// given (see [`ConnectionManager::query_pool`]; a connection keeps at most
+    /// `MAX_DATABASE_POOLS` pools of other databases), `params` are bound
+    /// positionally, and exceeding `timeout` yields `AppError::Timeout`.
+    /// Oversized cells are clipped and trailing rows dropped to keep the
+    /// result within the size limits.
+    pub async fn execute_query(
         &self,
-        pool: &MySqlPool,
+        id: &str,
+        database: Option<&str>,
         sql: &str,
         limit: u32,
-        start: std::time::Instant,
+        params: &[serde_json::Value],
+        timeout: Option<Duration>,
     ) -> AppResult<QueryResult> {
-        // Safety: add LIMIT if not present
-        let sql = Self::ensure_limit(sql, limit);
-
-        let rows: Vec<MySqlRow> = sqlx::query(&sql)
-            .fetch_all(pool)
-            .await
-            .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let execution_time_ms = start.elapsed().as_millis() as u64;
-
-        // Extract column info
-        let columns: Vec<ColumnInfo> = if let Some(first) = rows.first() {
-            first
-                .columns()
-                .iter()
-                .map(|c| ColumnInfo {
-                    name: c.name().to_string(),
-                    data_type: c.type_info().to_string(),
-                    nullable: None,
-                })
-                .collect()
-        } else {
-            vec![]
-        };
+        let start = std::time::Instant::now();
+        let result = self.executor.query(id, database, sql, limit, params, timeout).await;
+        self.record(id, sql, start, &result).await;
+        result
+    }
+
+    /// Executes a read-only Cypher query on a Neo4j connection.
+    ///
+    /// `params` are passed as `$name` parameters and the query runs in
+    /// `database` when given. Timeouts, workload recording and result clipping
+    /// follow [`Self::execute_query`].
+    #[cfg_attr(not(feature = "neo4j"), allow(unused_variables))]
+    pub async fn execute_cypher(
+        &self,
+        id: &str,
+        database: Option<&str>,
+        cypher: &str,
+        limit: u32,
+        params: &BTreeMap<String, serde_json::Value>,
+        timeout: Duration,
+    ) -> AppResult<QueryResult> {
+        let start = std::time::Instant::now();
+        #[cfg(feature = "neo4j")]
+        let result = self.executor.cypher(id, database, cypher, limit, params, timeout).await;
+        #[cfg(not(feature = "neo4j"))]
+        let result = Err(self.unsupported(id, "Cypher queries are only supported for Neo4j").await);
+        self.record(id, cypher, start, &result).await;
+        result
+    }
 
-        // Extract row data
-        let mut result_rows = Vec::new();
-        for row in &rows {
-            let mut values = Vec::new();
-            for idx in 0..row.columns().len() {
-                values.push(Self::mysql_value_to_json(row, idx));
-            }
-            result_rows.push(values);
+    /// Records an executed statement in the workload statistics. Statements
+    /// on unsupported databases never ran; everything else counts, failures included.
+    async fn record(&self, id: &str, sql: &str, start: std::time::Instant, result: &AppResult<QueryResult>) {
+        if !matches!(result, Err(AppError::UnsupportedDatabaseType(_))) {
+            self.workload.record(id, sql, start.elapsed(), result.is_ok()).await;
         }
+    }
 
-        let row_count = result_rows.len();
-        Ok(QueryResult {
-            columns,
-            rows: result_rows,
-            row_count,
-            affected_rows: None,
-            execution_time_ms,
-        })
+    /// Node labels of a Neo4j connection with their node counts.
+    pub async fn graph_labels(&self, id: &str) -> AppResult<Vec<GraphElementType>> {
+        const UNSUPPORTED: &str = "Graph labels are only available for Neo4j";
+        #[cfg(feature = "neo4j")]
+        return self.driver::<dbm_core::drivers::neo4j::Neo4jPool>(id, UNSUPPORTED).await?.labels().await;
+        #[cfg(not(feature = "neo4j"))]
+        Err(self.unsupported(id, UNSUPPORTED).await)
     }
 
-    async fn execute_postgres_query(
-        &self,
-        pool: &PgPool,
-        sql: &str,
-        limit: u32,
-        start: std::time::Instant,
-    ) -> AppResult<QueryResult> {
-        let sql = Self::ensure_limit(sql, limit);
+    /// Relationship types of a Neo4j connection with their relationship counts.
+    pub async fn graph_relationship_types(&self, id: &str) -> AppResult<Vec<GraphElementType>> {
+        const UNSUPPORTED: &str = "Relationship types are only available for Neo4j";
+        #[cfg(feature = "neo4j")]
+        return self
+            .driver::<dbm_core::drivers::neo4j::Neo4jPool>(id, UNSUPPORTED)
+            .await?
+            .relationship_types()
+            .await;
+        #[cfg(not(feature = "neo4j"))]
+        Err(self.unsupported(id, UNSUPPORTED).await)
+    }
 
-        let rows: Vec<PgRow> = sqlx::query(&sql)
-            .fetch_all(pool)
-            .await
-            .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let execution_time_ms = start.elapsed().as_millis() as u64;
-
-        let columns: Vec<ColumnInfo> = if let Some(first) = rows.first() {
-            first
-                .columns()
-                .iter()
-                .map(|c| ColumnInfo {
-                    name: c.name().to_string(),
-                    data_type: c.type_info().to_string(),
-                    nullable: None,
-                })
-                .collect()
-        } else {
-            vec![]
-        };
+    // ============== Key-Value Access ==============
 
-        let mut result_rows = Vec::new();
-        for row in &rows {
-            let mut values = Vec::new();
-            for idx in 0..row.columns().len() {
-                values.push(Self::pg_value_to_json(row, idx));
-            }
-            result_rows.push(values);
-        }
+    /// Value stored under `key` on a Memcached connection; `NotFound` for missing keys.
+    pub async fn get_key(&self, id: &str, key: &str) -> AppResult<KeyValueEntry> {
+        self.memcached(id)
+            .await?
+            .get(key)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("key {} on connection {}", key, id)))
+    }
 
-        let row_count = result_rows.len();
-        Ok(QueryResult {
-            columns,
-            rows: result_rows,
-            row_count,
-            affected_rows: None,
-            execution_time_ms,
-        })
+    /// Stores a value under `key` on a Memcached connection.
+    pub async fn set_key(&self, id: &str, key: &str, req: &SetKeyValueRequest) -> AppResult<()> {
+        self.memcached(id).await?.set(key, req).await
     }
 
-    /// Convert a MySQL row value at index to JSON
-    fn mysql_value_to_json(row: &MySqlRow, idx: usize) -> serde_json::Value {
-        // Try i64
-        if let Ok(v) = row.try_get::<Option<i64>, _>(idx) {
-            return match v {
-                Some(n) => serde_json::Value::Number(n.into()),
-                None => serde_json::Value::Null,
-            };
-        }
-        // Try f64
-        if let Ok(v) = row.try_get::<Option<f64>, _>(idx) {
-            return match v {
-                Some(n) => serde_json::Number::from_f64(n)
-                    .map(serde_json::Value::Number)
-                    .unwrap_or(serde_json::Value::String(n.to_string())),
-                None => serde_json::Value::Null,
-            };
-        }
-        // Try String
-        if let Ok(v) = row.try_get::<Option<String>, _>(idx) {
-            return match v {
-                Some(s) => serde_json::Value::String(s),
-                None => serde_json::Value::Null,
-            };
+    /// Deletes `key` on a Memcached connection; `NotFound` for missing keys.
+    pub async fn delete_key(&self, id: &str, key: &str) -> AppResult<()> {
+        if !self.memcached(id).await?.delete(key).await? {
+            return Err(AppError::NotFound(format!("key {} on connection {}", key, id)));
         }
-        // Try bytes as hex
-        if let Ok(v) = row.try_get::<Option<Vec<u8>>, _>(idx) {
-            return match v {
-                Some(b) => serde_json::Value::String(format!("0x{}", hex_encode(&b))),
-                None => serde_json::Value::Null,
-            };
-        }
-        serde_json::Value::Null
+        Ok(())
     }
 
-    /// Convert a Postgres row value at index to JSON
-    fn pg_value_to_json(row: &PgRow, idx: usize) -> serde_json::Value {
-        if let Ok(v) = row.try_get::<Option<i64>, _>(idx) {
-            return match v {
-                Some(n) => serde_json::Value::Number(n.into()),
-                None => serde_json::Value::Null,
-            };
-        }
-        if let Ok(v) = row.try_get::<Option<i32>, _>(idx) {
-            return match v {
-                Some(n) => serde_json::Value::Number(n.into()),
-                None => serde_json::Value::Null,
-            };
-        }
-        if let Ok(v) = row.try_get::<Option<f64>, _>(idx) {
-            return match v {
-                Some(n) => serde_json::Number::from_f64(n)
-                    .map(serde_json::Value::Number)
-                    .unwrap_or(serde_json::Value::String(n.to_string())),
-                None => serde_json::Value::Null,
-            };
-        }
-        if let Ok(v) = row.try_get::<Option<bool>, _>(idx) {
-            return match v {
-                Some(b) => serde_json::Value::Bool(b),
-                None => serde_json::Value::Null,
-            };
-        }
-        if let Ok(v) = row.try_get::<Option<String>, _>(idx) {
-            return match v {
-                Some(s) => serde_json::Value::String(s),
-                None => serde_json::Value::Null,
-            };
-        }
-        serde_json::Value::Null
+    async fn memcached(&self, id: &str) -> AppResult<dbm_core::drivers::memcached::MemcachedClient> {
+        self.driver(id, "Key access is only supported for Memcached").await
     }
 
-    /// Ensure SQL has a LIMIT clause
-    fn ensure_limit(sql: &str, limit: u32) -> String {
-        let upper = sql.to_uppercase();
-        if upper.contains("LIMIT") {
-            return sql.to_string();
-        }
-        
-        // 移除末尾空白和分号，确保添加 LIMIT 时有空格分隔
-        let trimmed = sql.trim_end().trim_end_matches(';');
-        if trimmed.is_empty() {
-            return sql.to_string();
+    /// Connection of `id` as the driver type `T`, for engine-specific operations.
+    ///
+    /// # Errors
+    /// `ConnectionNotFound` if the pool is not open, `UnsupportedDatabaseType`
+    /// with `unsupported` if the connection uses another driver.
+    async fn driver<T: DriverConnection + Clone>(&self, id: &str, unsupported: &str) -> AppResult<T> {
+        self.connections.driver(id, unsupported).await
+    }
+
+    /// Error for an operation the connection's driver does not offer.
+    #[cfg_attr(feature = "neo4j", allow(dead_code))]
+    async fn unsupported(&self, id: &str, message: &str) -> AppError {
+        match self.get_pool(id).await {
+            Some(_) => AppError::UnsupportedDatabaseType(message.to_string()),
+            None => AppError::ConnectionNotFound(id.to_string()),
         }
-        
-        format!("{} LIMIT {}", trimmed, limit)
+    }
+
+    /// Executes a confirmed UPDATE/DELETE or DDL statement and returns the affected row count.
+    ///
+    /// Callers are responsible for confirming the change first. The timeout is
+    /// enforced locally, and server-side on PostgreSQL via `statement_timeout`.
+    pub async fn execute_change(
+        &self,
+        id: &str,
+        database: Option<&str>,
+        sql: &str,
+        params: &[serde_json::Value],
+        timeout: Duration,
+    ) -> AppResult<QueryResult> {
+        let start = std::time::Instant::now();
+        let result = self.executor.execute(id, database, sql, params, timeout).await;
+        self.record(id, sql, start, &result).await;
+        result
     }
 
     // ============== Schema Methods ==============
@@ -1056,258 +843,13 @@ impl PoolManager {
             .await
             .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
 
-        let pools = self.pools.read().await;
-        let pool = pools
-            .get(id)
-            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
-
-        let database_name = config.database.clone().unwrap_or_default();
-
-        let tables = match pool {
-            DatabasePool::MySQL(p) => self.get_mysql_table_schema(p, &database_name).await?,
-            DatabasePool::Postgres(p) => self.get_postgres_table_schema(p).await?,
-            _ => vec![],
-        };
+        let pool = self.open_connection(id).await?;
+        let tables = pool.connection().table_schema(&config).await?;
 
         Ok(TableSchema {
-            database: database_name,
+            database: config.database.clone().unwrap_or_default(),
             db_type: config.db_type.to_string(),
             tables,
         })
     }
-
-    async fn get_mysql_table_schema(
-        &self,
-        pool: &MySqlPool,
-        database: &str,
-    ) -> AppResult<Vec<TableInfo>> {
-        let rows = sqlx::query(
-            "SELECT TABLE_NAME, COLUMN_NAME, COLUMN_TYPE, IS_NULLABLE, COLUMN_KEY
-             FROM information_schema.COLUMNS
-             WHERE TABLE_SCHEMA = ?
-             ORDER BY TABLE_NAME, ORDINAL_POSITION
-             LIMIT 500",
-        )
-        .bind(database)
-        .fetch_all(pool)
-        .await
-        .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let mut tables: Vec<TableInfo> = Vec::new();
-        let mut current_table: Option<String> = None;
-
-        for row in &rows {
-            let table_name: String = Self::mysql_get_string(row, "TABLE_NAME");
-            let col = ColumnDetail {
-                name: Self::mysql_get_string(row, "COLUMN_NAME"),
-                data_type: Self::mysql_get_string(row, "COLUMN_TYPE"),
-                nullable: Self::mysql_get_string(row, "IS_NULLABLE") == "YES",
-                key: {
-                    let k = Self::mysql_get_string(row, "COLUMN_KEY");
-                    if k.is_empty() { None } else { Some(k) }
-                },
-            };
-
-            if current_table.as_deref() != Some(&table_name) {
-                current_table = Some(table_name.clone());
-                tables.push(TableInfo {
-                    name: table_name,
-                    columns: vec![col],
-                });
-            } else if let Some(t) = tables.last_mut() {
-                t.columns.push(col);
-            }
-        }
-
-        Ok(tables)
-    }
-
-    async fn get_postgres_table_schema(
-        &self,
-        pool: &PgPool,
-    ) -> AppResult<Vec<TableInfo>> {
-        let rows = sqlx::query(
-            "SELECT c.table_name, c.column_name, c.data_type, c.is_nullable,
-                    CASE WHEN tc.constraint_type = 'PRIMARY KEY' THEN 'PRI'
-                         WHEN tc.constraint_type = 'UNIQUE' THEN 'UNI'
-                         ELSE NULL END AS column_key
-             FROM information_schema.columns c
-             LEFT JOIN information_schema.key_column_usage kcu
-                ON c.table_schema = kcu.table_schema AND c.table_name = kcu.table_name AND c.column_name = kcu.column_name
-             LEFT JOIN information_schema.table_constraints tc
-                ON kcu.constraint_name = tc.constraint_name AND kcu.table_schema = tc.table_schema
-             WHERE c.table_schema = 'public'
-             ORDER BY c.table_name, c.ordinal_position
-             LIMIT 500",
-        )
-        .fetch_all(pool)
-        .await
-        .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let mut tables: Vec<TableInfo> = Vec::new();
-        let mut current_table: Option<String> = None;
-
-        for row in &rows {
-            let table_name: String = row.try_get("table_name").unwrap_or_default();
-            let col = ColumnDetail {
-                name: row.try_get("column_name").unwrap_or_default(),
-                data_type: row.try_get("data_type").unwrap_or_default(),
-                nullable: row.try_get::<String, _>("is_nullable").unwrap_or_default() == "YES",
-                key: row.try_get::<Option<String>, _>("column_key").unwrap_or(None),
-            };
-
-            if current_table.as_deref() != Some(&table_name) {
-                current_table = Some(table_name.clone());
-                tables.push(TableInfo {
-                    name: table_name,
-                    columns: vec![col],
-                });
-            } else if let Some(t) = tables.last_mut() {
-                t.columns.push(col);
-            }
-        }
-
-        Ok(tables)
-    }
-
-    // ---- Redis monitoring helpers ----
-
-    async fn get_redis_stats(
-        &self,
-        manager: &RedisConnectionManager,
-    ) -> AppResult<DatabaseStats> {
-        let mut conn = manager.clone();
-        let info: String = redis::cmd("INFO")
-            .query_async(&mut conn)
-            .await
-            .map_err(|e| AppError::RedisOperation(e.to_string()))?;
-
-        let mut stats = DatabaseStats::default();
-        for line in info.lines() {
-            if let Some((key, val)) = line.split_once(':') {
-                match key {
-                    "uptime_in_seconds" => {
-                        stats.uptime_seconds = val.trim().parse().unwrap_or(0)
-                    }
-                    "connected_clients" => {
-                        stats.active_connections = val.trim().parse().unwrap_or(0)
-                    }
-                    "maxclients" => {
-                        stats.max_connections = val.trim().parse().unwrap_or(0)
-                    }
-                    "total_commands_processed" => {
-                        stats.total_queries = val.trim().parse().unwrap_or(0)
-                    }
-                    "used_memory" => {
-                        stats.buffer_pool_size =
-                            Some(val.trim().parse().unwrap_or(0));
-                    }
-                    "redis_version" => {
-                        stats.server_version =
-                            Some(format!("Redis {}", val.trim()));
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        if stats.uptime_seconds > 0 {
-            stats.queries_per_second =
-                stats.total_queries as f64 / stats.uptime_seconds as f64;
-        }
-
-        Ok(stats)
-    }
-
-    // ============== MongoDB Monitoring ==============
-
-    async fn get_mongodb_stats(
-        &self,
-        client: &mongodb::Client,
-    ) -> AppResult<DatabaseStats> {
-        let db = client.database("admin");
-        let result = db
-            .run_command(doc! { "serverStatus": 1 })
-            .await
-            .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let mut stats = DatabaseStats::default();
-
-        // Server version
-        if let Some(v) = result.get_str("version").ok() {
-            stats.server_version = Some(format!("MongoDB {}", v));
-        }
-
-        // Uptime
-        if let Some(up) = result.get_f64("uptime").ok() {
-            stats.uptime_seconds = up as u64;
-        }
-
-        // Connections
-        if let Some(conns) = result.get_document("connections").ok() {
-            stats.active_connections = conns.get_i32("current").unwrap_or(0) as u32;
-            stats.max_connections = conns.get_i32("available").unwrap_or(0) as u32
-                + stats.active_connections;
-        }
-
-        // Operations (opcounters)
-        if let Some(ops) = result.get_document("opcounters").ok() {
-            let insert = ops.get_i64("insert").or(ops.get_i32("insert").map(|v| v as i64)).unwrap_or(0);
-            let query = ops.get_i64("query").or(ops.get_i32("query").map(|v| v as i64)).unwrap_or(0);
-            let update = ops.get_i64("update").or(ops.get_i32("update").map(|v| v as i64)).unwrap_or(0);
-            let delete = ops.get_i64("delete").or(ops.get_i32("delete").map(|v| v as i64)).unwrap_or(0);
-            stats.total_queries = (insert + query + update + delete) as u64;
-        }
-
-        // Memory
-        if let Some(mem) = result.get_document("mem").ok() {
-            let resident_mb = mem.get_i32("resident").unwrap_or(0) as u64;
-            stats.buffer_pool_size = Some(resident_mb * 1024 * 1024); // MB -> bytes
-        }
-
-        if stats.uptime_seconds > 0 {
-            stats.queries_per_second =
-                stats.total_queries as f64 / stats.uptime_seconds as f64;
-        }
-
-        Ok(stats)
-    }
-
-    async fn get_mongodb_databases(
-        &self,
-        client: &mongodb::Client,
-    ) -> AppResult<Vec<DatabaseInfo>> {
-        let db_names = client
-            .list_database_names()
-            .await
-            .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
-
-        let mut databases = Vec::new();
-        for name in db_names {
-            let db = client.database(&name);
-            let stats_result = db.run_command(doc! { "dbStats": 1 }).await;
-            let size_mb = match stats_result {
-                Ok(doc) => {
-                    let data_size = doc.get_f64("dataSize")
-                        .or(doc.get_i64("dataSize").map(|v| v as f64))
-                        .or(doc.get_i32("dataSize").map(|v| v as f64))
-                        .unwrap_or(0.0);
-                    data_size / 1024.0 / 1024.0
-                }
-                Err(_) => 0.0,
-            };
-            databases.push(DatabaseInfo {
-                name: name.clone(),
-                size_mb,
-                tables_count: 0,
-            });
-        }
-
-        Ok(databases)
-    }
-}
-
-/// Simple hex encode for binary data display
-fn hex_encode(bytes: &[u8]) -> String {
-    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
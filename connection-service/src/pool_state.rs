@@ -0,0 +1,203 @@
+//! Connection pool self-healing state.
+//!
+//! Open pools are probed periodically (see `PoolManager::spawn_probe`). A pool
+//! whose probe fails, e.g. because the database restarted, is dropped and the
+//! connection moves to `reconnecting`; reconnect attempts then back off
+//! exponentially. After `POOL_RECONNECT_MAX_FAILURES` consecutive failures the
+//! connection is `failed`, and attempts continue at the longest interval.
+//! Requests for a connection that is waiting for its next attempt fail fast
+//! instead of each trying to connect.
+//!
+//! Configuration:
+//! - `POOL_PROBE_INTERVAL_SECS` - probe interval (default: 15)
+//! - `POOL_RECONNECT_BASE_SECS` - delay before the first reconnect attempt (default: 1)
+//! - `POOL_RECONNECT_MAX_SECS` - longest delay between attempts (default: 300)
+//! - `POOL_RECONNECT_MAX_FAILURES` - failures after which the state is `failed` (default: 10)
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use tokio::sync::RwLock;
+
+use common::models::monitor::{PoolState, PoolStatus};
+
+const DEFAULT_PROBE_INTERVAL_SECS: u64 = 15;
+const DEFAULT_RECONNECT_BASE_SECS: u64 = 1;
+const DEFAULT_RECONNECT_MAX_SECS: u64 = 300;
+const DEFAULT_RECONNECT_MAX_FAILURES: u32 = 10;
+
+struct Entry {
+    status: PoolStatus,
+    next_attempt: Option<Instant>,
+}
+
+/// Self-healing state of every connection whose pool was opened.
+pub struct PoolStates {
+    entries: RwLock<HashMap<String, Entry>>,
+    /// Interval between probes of open pools.
+    pub probe_interval: Duration,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_failures: u32,
+}
+
+impl PoolStates {
+    /// Reads the probe and backoff settings from the environment.
+    pub fn from_env() -> Self {
+        fn env<T: std::str::FromStr>(key: &str, default: T) -> T {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self::new(
+            Duration::from_secs(env("POOL_PROBE_INTERVAL_SECS", DEFAULT_PROBE_INTERVAL_SECS).max(1)),
+            Duration::from_secs(env("POOL_RECONNECT_BASE_SECS", DEFAULT_RECONNECT_BASE_SECS).max(1)),
+            Duration::from_secs(env("POOL_RECONNECT_MAX_SECS", DEFAULT_RECONNECT_MAX_SECS).max(1)),
+            env("POOL_RECONNECT_MAX_FAILURES", DEFAULT_RECONNECT_MAX_FAILURES).max(1),
+        )
+    }
+
+    fn new(probe_interval: Duration, base_delay: Duration, max_delay: Duration, max_failures: u32) -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            probe_interval,
+            base_delay,
+            max_delay: max_delay.max(base_delay),
+            max_failures,
+        }
+    }
+
+    /// Delay before the next attempt after `failures` consecutive failures.
+    fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Records a working pool.
+    pub async fn connected(&self, id: &str) {
+        let mut entries = self.entries.write().await;
+        match entries.get_mut(id) {
+            Some(entry) if entry.status.state == PoolState::Connected => {}
+            Some(entry) => {
+                tracing::info!(id = %id, failures = entry.status.failures, "Connection pool recovered");
+                *entry = connected_entry();
+            }
+            None => {
+                entries.insert(id.to_string(), connected_entry());
+            }
+        }
+    }
+
+    /// Records a failed probe or reconnect attempt and schedules the next
+    /// attempt. Connections whose pool was never opened are not tracked.
+    pub async fn failed(&self, id: &str, error: String) {
+        let mut entries = self.entries.write().await;
+        let Some(entry) = entries.get_mut(id) else {
+            return;
+        };
+        let failures = entry.status.failures + 1;
+        let delay = self.backoff(failures);
+        let state = if failures >= self.max_failures {
+            PoolState::Failed
+        } else {
+            PoolState::Reconnecting
+        };
+        if state != entry.status.state {
+            tracing::warn!(id = %id, ?state, error = %error, "Connection pool lost");
+            entry.status.since = Utc::now().to_rfc3339();
+        }
+        entry.status.state = state;
+        entry.status.failures = failures;
+        entry.status.last_error = Some(error);
+        entry.status.next_attempt_at = chrono::Duration::from_std(delay)
+            .ok()
+            .map(|d| (Utc::now() + d).to_rfc3339());
+        entry.next_attempt = Some(Instant::now() + delay);
+    }
+
+    /// Whether the connection waits for its next reconnect attempt; returns the
+    /// status to report in that case.
+    pub async fn waiting(&self, id: &str) -> Option<PoolStatus> {
+        self.entries
+            .read()
+            .await
+            .get(id)
+            .filter(|e| e.next_attempt.is_some_and(|at| Instant::now() < at))
+            .map(|e| e.status.clone())
+    }
+
+    /// IDs of connections that are due for a reconnect attempt.
+    pub async fn due(&self) -> Vec<String> {
+        let now = Instant::now();
+        self.entries
+            .read()
+            .await
+            .iter()
+            .filter(|(_, e)| e.next_attempt.is_some_and(|at| at <= now))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Current status of a connection, if its pool was ever opened.
+    pub async fn status(&self, id: &str) -> Option<PoolStatus> {
+        self.entries.read().await.get(id).map(|e| e.status.clone())
+    }
+
+    /// IDs of the tracked connections.
+    pub async fn ids(&self) -> Vec<String> {
+        self.entries.read().await.keys().cloned().collect()
+    }
+
+    /// Forgets a removed connection.
+    pub async fn forget(&self, id: &str) {
+        self.entries.write().await.remove(id);
+    }
+}
+
+fn connected_entry() -> Entry {
+    Entry {
+        status: PoolStatus {
+            state: PoolState::Connected,
+            failures: 0,
+            last_error: None,
+            next_attempt_at: None,
+            since: Utc::now().to_rfc3339(),
+        },
+        next_attempt: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn backs_off_until_failed_and_recovers() {
+        let states = PoolStates::new(Duration::from_secs(15), Duration::from_secs(1), Duration::from_secs(8), 3);
+        assert_eq!(states.backoff(1), Duration::from_secs(1));
+        assert_eq!(states.backoff(3), Duration::from_secs(4));
+        assert_eq!(states.backoff(10), Duration::from_secs(8));
+
+        states.failed("never-opened", "refused".into()).await;
+        assert!(states.status("never-opened").await.is_none());
+
+        states.connected("c1").await;
+        states.failed("c1", "refused".into()).await;
+        let status = states.waiting("c1").await.unwrap();
+        assert_eq!(status.state, PoolState::Reconnecting);
+        assert_eq!(status.failures, 1);
+        assert!(states.due().await.is_empty());
+
+        states.failed("c1", "refused".into()).await;
+        states.failed("c1", "refused".into()).await;
+        assert_eq!(states.status("c1").await.unwrap().state, PoolState::Failed);
+
+        states.connected("c1").await;
+        let status = states.status("c1").await.unwrap();
+        assert_eq!((status.state, status.failures), (PoolState::Connected, 0));
+        assert!(states.waiting("c1").await.is_none());
+    }
+}
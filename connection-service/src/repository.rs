@@ -0,0 +1,576 @@
+//! Storage of saved connections and of the metadata schema.
+//!
+//! [`MetadataRepository`] is what [`PoolManager`](crate::pool_manager::PoolManager)
+//! and the migrations need from the metadata database. Each backend has its own
+//! implementation with statements written in that backend's SQL and its own
+//! migration scripts under `connection-service/migrations/<backend>`.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::mysql::MySqlPool;
+use sqlx::postgres::PgPool;
+use sqlx::sqlite::SqlitePool;
+
+use common::meta::{self, MetaBackend, MetaPool, Statement};
+use common::models::connection::{ConnectionAllowlist, ConnectionConfig, ConnectionPoolOptions, DbType, StatementPolicy};
+use common::models::masking::ConnectionMasking;
+
+/// Metadata storage of one backend.
+#[async_trait]
+pub trait MetadataRepository: Send + Sync {
+    /// Backend the repository stores into.
+    fn backend(&self) -> MetaBackend;
+
+    /// The embedded migrations of the backend.
+    fn migrator(&self) -> &'static Migrator;
+
+    /// Applies the pending migrations.
+    async fn migrate(&self) -> Result<(), MigrateError>;
+
+    /// Whether a table exists.
+    async fn has_table(&self, table: &str) -> Result<bool, sqlx::Error>;
+
+    /// Adds a column to a table created by an earlier version unless it exists;
+    /// `definition` holds the column definition for each backend.
+    async fn add_column(&self, table: &str, column: &str, definition: &Statement) -> Result<(), sqlx::Error>;
+
+    /// Rows of the `_sqlx_migrations` table, by version.
+    async fn applied_migrations(&self) -> Result<Vec<AppliedMigration>, sqlx::Error>;
+
+    /// Stores a new connection; `created_at` is set by the database.
+    async fn insert_connection(&self, config: &ConnectionConfig) -> Result<(), sqlx::Error>;
+
+    /// Stores a connection, replacing the one with the same ID; a new row
+    /// takes `created_at` from the config.
+    async fn import_connection(&self, config: &ConnectionConfig) -> Result<(), sqlx::Error>;
+
+    /// Deletes a connection; returns the number of deleted rows.
+    async fn delete_connection(&self, id: &str) -> Result<u64, sqlx::Error>;
+
+    /// The update methods return the number of changed rows.
+    async fn update_password(&self, id: &str, password: Option<&str>) -> Result<u64, sqlx::Error>;
+
+    async fn update_allowlist(&self, id: &str, allowlist: Option<&ConnectionAllowlist>) -> Result<u64, sqlx::Error>;
+
+    async fn update_masking(&self, id: &str, masking: Option<&ConnectionMasking>) -> Result<u64, sqlx::Error>;
+
+    async fn update_statement_policy(&self, id: &str, policy: Option<&StatementPolicy>) -> Result<u64, sqlx::Error>;
+
+    async fn update_query_timeout(&self, id: &str, timeout_ms: Option<u64>) -> Result<u64, sqlx::Error>;
+
+    async fn update_pinned(&self, id: &str, pinned: bool) -> Result<u64, sqlx::Error>;
+
+    async fn update_pool_options(&self, id: &str, options: &ConnectionPoolOptions) -> Result<u64, sqlx::Error>;
+
+    /// All connections, newest first.
+    async fn list_connections(&self) -> Result<Vec<ConnectionConfig>, sqlx::Error>;
+
+    async fn get_connection(&self, id: &str) -> Result<Option<ConnectionConfig>, sqlx::Error>;
+
+    async fn count_connections(&self) -> Result<i64, sqlx::Error>;
+}
+
+/// Returns the repository of the metadata pool's backend.
+pub fn for_pool(meta_pool: &MetaPool) -> Arc<dyn MetadataRepository> {
+    match meta_pool {
+        MetaPool::MySql(pool) => Arc::new(MySqlRepository(pool.clone())),
+        MetaPool::Postgres(pool) => Arc::new(PostgresRepository(pool.clone())),
+        MetaPool::Sqlite(pool) => Arc::new(SqliteRepository(pool.clone())),
+    }
+}
+
+/// Row from the `_sqlx_migrations` table.
+#[derive(sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub success: bool,
+    pub installed_on: DateTime<Utc>,
+    /// Nanoseconds, -1 while the migration runs.
+    pub execution_time: i64,
+}
+
+/// Row from the `connections` metadata table.
+#[derive(sqlx::FromRow)]
+struct ConnectionRow {
+    id: String,
+    name: String,
+    db_type: String,
+    host: Option<String>,
+    port: Option<meta::Unsigned<u16>>,
+    username: Option<String>,
+    password: Option<String>,
+    password_ref: Option<String>,
+    database_name: Option<String>,
+    file_path: Option<String>,
+    org: Option<String>,
+    allowlist: Option<String>,
+    masking: Option<String>,
+    statement_policy: Option<String>,
+    query_timeout_ms: Option<meta::Unsigned<u64>>,
+    pinned: bool,
+    pool_max_connections: Option<meta::Unsigned<u32>>,
+    pool_min_connections: Option<meta::Unsigned<u32>>,
+    pool_acquire_timeout_secs: Option<meta::Unsigned<u64>>,
+    pool_idle_timeout_secs: Option<meta::Unsigned<u64>>,
+    owner_id: Option<String>,
+    created_at: String,
+}
+
+impl ConnectionRow {
+    fn into_config(self) -> ConnectionConfig {
+        ConnectionConfig {
+            id: self.id,
+            name: self.name,
+            db_type: parse_db_type(&self.db_type),
+            host: self.host,
+            port: self.port.map(|p| p.0),
+            username: self.username,
+            password: self.password,
+            password_ref: self.password_ref,
+            database: self.database_name,
+            file_path: self.file_path,
+            org: self.org,
+            allowlist: self
+                .allowlist
+                .and_then(|a| serde_json::from_str(&a).ok()),
+            masking: self
+                .masking
+                .and_then(|m| serde_json::from_str(&m).ok()),
+            statement_policy: self
+                .statement_policy
+                .and_then(|p| serde_json::from_str(&p).ok()),
+            query_timeout_ms: self.query_timeout_ms.map(|t| t.0),
+            pinned: self.pinned,
+            pool_options: Some(ConnectionPoolOptions {
+                max_connections: self.pool_max_connections.map(|n| n.0),
+                min_connections: self.pool_min_connections.map(|n| n.0),
+                acquire_timeout_secs: self.pool_acquire_timeout_secs.map(|s| s.0),
+                idle_timeout_secs: self.pool_idle_timeout_secs.map(|s| s.0),
+            })
+            .filter(|o| !o.is_empty()),
+            owner_id: self.owner_id,
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Converts a stored (`YYYY-MM-DD HH:MM:SS`) or RFC 3339 timestamp to the
+/// DATETIME format; unparsable values become the current time.
+fn normalize_timestamp(value: &str) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+    chrono::NaiveDateTime::parse_from_str(value, FORMAT)
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(value).map(|dt| dt.naive_utc()))
+        .unwrap_or_else(|_| chrono::Utc::now().naive_utc())
+        .format(FORMAT)
+        .to_string()
+}
+
+fn json<T: serde::Serialize>(value: Option<&T>) -> Option<String> {
+    value.and_then(|v| serde_json::to_string(v).ok())
+}
+
+fn parse_db_type(s: &str) -> DbType {
+    s.parse().unwrap_or(DbType::MySQL) // fallback
+}
+
+/// Implements [`MetadataRepository`] for `$name` with the statements of the
+/// `$sql` module; `$field` picks the backend's text of a [`Statement`].
+macro_rules! repository {
+    ($(#[$doc:meta])* $name:ident($pool:ty), $backend:expr, $sql:ident, $field:ident) => {
+        $(#[$doc])*
+        pub struct $name(pub $pool);
+
+        #[async_trait]
+        impl MetadataRepository for $name {
+            fn backend(&self) -> MetaBackend {
+                $backend
+            }
+
+            fn migrator(&self) -> &'static Migrator {
+                &$sql::MIGRATOR
+            }
+
+            async fn migrate(&self) -> Result<(), MigrateError> {
+                $sql::MIGRATOR.run(&self.0).await
+            }
+
+            async fn has_table(&self, table: &str) -> Result<bool, sqlx::Error> {
+                let (count,): (i64,) = sqlx::query_as($sql::HAS_TABLE)
+                    .bind(table)
+                    .fetch_one(&self.0)
+                    .await?;
+                Ok(count > 0)
+            }
+
+            async fn add_column(&self, table: &str, column: &str, definition: &Statement) -> Result<(), sqlx::Error> {
+                let (exists,): (i64,) = sqlx::query_as($sql::HAS_COLUMN)
+                    .bind(table)
+                    .bind(column)
+                    .fetch_one(&self.0)
+                    .await?;
+                if exists > 0 {
+                    return Ok(());
+                }
+                let statement = $sql::add_column(table, definition.$field);
+                sqlx::query(&statement).execute(&self.0).await.map(|_| ())
+            }
+
+            async fn applied_migrations(&self) -> Result<Vec<AppliedMigration>, sqlx::Error> {
+                sqlx::query_as($sql::APPLIED_MIGRATIONS).fetch_all(&self.0).await
+            }
+
+            async fn insert_connection(&self, config: &ConnectionConfig) -> Result<(), sqlx::Error> {
+                let pool_options = config.pool_options.clone().unwrap_or_default();
+                sqlx::query($sql::INSERT_CONNECTION)
+                    .bind(&config.id)
+                    .bind(&config.name)
+                    .bind(config.db_type.to_string())
+                    .bind(&config.host)
+                    .bind(config.port.map(i64::from))
+                    .bind(&config.username)
+                    .bind(&config.password)
+                    .bind(&config.password_ref)
+                    .bind(&config.database)
+                    .bind(&config.file_path)
+                    .bind(&config.org)
+                    .bind(json(config.allowlist.as_ref()))
+                    .bind(json(config.masking.as_ref()))
+                    .bind(json(config.statement_policy.as_ref()))
+                    .bind(config.query_timeout_ms.map(meta::int))
+                    .bind(config.pinned)
+                    .bind(pool_options.max_connections.map(i64::from))
+                    .bind(pool_options.min_connections.map(i64::from))
+                    .bind(pool_options.acquire_timeout_secs.map(meta::int))
+                    .bind(pool_options.idle_timeout_secs.map(meta::int))
+                    .bind(&config.owner_id)
+                    .execute(&self.0)
+                    .await
+                    .map(|_| ())
+            }
+
+            async fn import_connection(&self, config: &ConnectionConfig) -> Result<(), sqlx::Error> {
+                let pool_options = config.pool_options.clone().unwrap_or_default();
+                sqlx::query($sql::IMPORT_CONNECTION)
+                    .bind(&config.id)
+                    .bind(&config.name)
+                    .bind(config.db_type.to_string())
+                    .bind(&config.host)
+                    .bind(config.port.map(i64::from))
+                    .bind(&config.username)
+                    .bind(&config.password)
+                    .bind(&config.password_ref)
+                    .bind(&config.database)
+                    .bind(&config.file_path)
+                    .bind(&config.org)
+                    .bind(json(config.allowlist.as_ref()))
+                    .bind(json(config.masking.as_ref()))
+                    .bind(json(config.statement_policy.as_ref()))
+                    .bind(config.query_timeout_ms.map(meta::int))
+                    .bind(config.pinned)
+                    .bind(pool_options.max_connections.map(i64::from))
+                    .bind(pool_options.min_connections.map(i64::from))
+                    .bind(pool_options.acquire_timeout_secs.map(meta::int))
+                    .bind(pool_options.idle_timeout_secs.map(meta::int))
+                    .bind(&config.owner_id)
+                    .bind(normalize_timestamp(&config.created_at))
+                    .execute(&self.0)
+                    .await
+                    .map(|_| ())
+            }
+
+            async fn delete_connection(&self, id: &str) -> Result<u64, sqlx::Error> {
+                sqlx::query($sql::DELETE_CONNECTION)
+                    .bind(id)
+                    .execute(&self.0)
+                    .await
+                    .map(|r| r.rows_affected())
+            }
+
+            async fn update_password(&self, id: &str, password: Option<&str>) -> Result<u64, sqlx::Error> {
+                sqlx::query($sql::UPDATE_PASSWORD)
+                    .bind(password)
+                    .bind(id)
+                    .execute(&self.0)
+                    .await
+                    .map(|r| r.rows_affected())
+            }
+
+            async fn update_allowlist(&self, id: &str, allowlist: Option<&ConnectionAllowlist>) -> Result<u64, sqlx::Error> {
+                sqlx::query($sql::UPDATE_ALLOWLIST)
+                    .bind(json(allowlist))
+                    .bind(id)
+                    .execute(&self.0)
+                    .await
+                    .map(|r| r.rows_affected())
+            }
+
+            async fn update_masking(&self, id: &str, masking: Option<&ConnectionMasking>) -> Result<u64, sqlx::Error> {
+                sqlx::query($sql::UPDATE_MASKING)
+                    .bind(json(masking))
+                    .bind(id)
+                    .execute(&self.0)
+                    .await
+                    .map(|r| r.rows_affected())
+            }
+
+            async fn update_statement_policy(&self, id: &str, policy: Option<&StatementPolicy>) -> Result<u64, sqlx::Error> {
+                sqlx::query($sql::UPDATE_STATEMENT_POLICY)
+                    .bind(json(policy))
+                    .bind(id)
+                    .execute(&self.0)
+                    .await
+                    .map(|r| r.rows_affected())
+            }
+
+            async fn update_query_timeout(&self, id: &str, timeout_ms: Option<u64>) -> Result<u64, sqlx::Error> {
+                sqlx::query($sql::UPDATE_QUERY_TIMEOUT)
+                    .bind(timeout_ms.map(meta::int))
+                    .bind(id)
+                    .execute(&self.0)
+                    .await
+                    .map(|r| r.rows_affected())
+            }
+
+            async fn update_pinned(&self, id: &str, pinned: bool) -> Result<u64, sqlx::Error> {
+                sqlx::query($sql::UPDATE_PINNED)
+                    .bind(pinned)
+                    .bind(id)
+                    .execute(&self.0)
+                    .await
+                    .map(|r| r.rows_affected())
+            }
+
+            async fn update_pool_options(&self, id: &str, options: &ConnectionPoolOptions) -> Result<u64, sqlx::Error> {
+                sqlx::query($sql::UPDATE_POOL_OPTIONS)
+                    .bind(options.max_connections.map(i64::from))
+                    .bind(options.min_connections.map(i64::from))
+                    .bind(options.acquire_timeout_secs.map(meta::int))
+                    .bind(options.idle_timeout_secs.map(meta::int))
+                    .bind(id)
+                    .execute(&self.0)
+                    .await
+                    .map(|r| r.rows_affected())
+            }
+
+            async fn list_connections(&self) -> Result<Vec<ConnectionConfig>, sqlx::Error> {
+                let rows: Vec<ConnectionRow> = sqlx::query_as($sql::LIST_CONNECTIONS).fetch_all(&self.0).await?;
+                Ok(rows.into_iter().map(ConnectionRow::into_config).collect())
+            }
+
+            async fn get_connection(&self, id: &str) -> Result<Option<ConnectionConfig>, sqlx::Error> {
+                let row: Option<ConnectionRow> = sqlx::query_as($sql::GET_CONNECTION)
+                    .bind(id)
+                    .fetch_optional(&self.0)
+                    .await?;
+                Ok(row.map(ConnectionRow::into_config))
+            }
+
+            async fn count_connections(&self) -> Result<i64, sqlx::Error> {
+                let (count,): (i64,) = sqlx::query_as($sql::COUNT_CONNECTIONS).fetch_one(&self.0).await?;
+                Ok(count)
+            }
+        }
+    };
+}
+
+repository!(
+    /// Metadata stored in MySQL.
+    MySqlRepository(MySqlPool),
+    MetaBackend::MySql,
+    mysql,
+    mysql
+);
+repository!(
+    /// Metadata stored in PostgreSQL.
+    PostgresRepository(PgPool),
+    MetaBackend::Postgres,
+    postgres,
+    postgres
+);
+repository!(
+    /// Metadata stored in a SQLite file.
+    SqliteRepository(SqlitePool),
+    MetaBackend::Sqlite,
+    sqlite,
+    sqlite
+);
+
+mod mysql {
+    use sqlx::migrate::Migrator;
+
+    pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/mysql");
+
+    pub const HAS_TABLE: &str = "SELECT COUNT(*) FROM information_schema.TABLES \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?";
+    pub const HAS_COLUMN: &str = "SELECT COUNT(*) FROM information_schema.COLUMNS \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ? AND COLUMN_NAME = ?";
+    pub fn add_column(table: &str, definition: &str) -> String {
+        format!("ALTER TABLE `{}` ADD COLUMN {}", table, definition)
+    }
+
+    pub const APPLIED_MIGRATIONS: &str = "SELECT `version`, `success`, `installed_on`, `execution_time` \
+         FROM `_sqlx_migrations` ORDER BY `version`";
+
+    pub const INSERT_CONNECTION: &str = "INSERT INTO `connections` (`id`, `name`, `db_type`, `host`, `port`, `username`, `password`, `password_ref`, `database_name`, `file_path`, `org`, `allowlist`, `masking`, `statement_policy`, `query_timeout_ms`, `pinned`,
+            `pool_max_connections`, `pool_min_connections`, `pool_acquire_timeout_secs`, `pool_idle_timeout_secs`, `owner_id`)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+    pub const IMPORT_CONNECTION: &str = "INSERT INTO `connections` (`id`, `name`, `db_type`, `host`, `port`, `username`, `password`, `password_ref`, `database_name`, `file_path`, `org`, `allowlist`, `masking`, `statement_policy`, `query_timeout_ms`, `pinned`,
+            `pool_max_connections`, `pool_min_connections`, `pool_acquire_timeout_secs`, `pool_idle_timeout_secs`, `owner_id`, `created_at`)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON DUPLICATE KEY UPDATE `name` = VALUES(`name`), `db_type` = VALUES(`db_type`), `host` = VALUES(`host`),
+            `port` = VALUES(`port`), `username` = VALUES(`username`), `password` = VALUES(`password`),
+            `password_ref` = VALUES(`password_ref`),
+            `database_name` = VALUES(`database_name`), `file_path` = VALUES(`file_path`), `org` = VALUES(`org`), `allowlist` = VALUES(`allowlist`),
+            `masking` = VALUES(`masking`), `statement_policy` = VALUES(`statement_policy`),
+            `query_timeout_ms` = VALUES(`query_timeout_ms`), `pinned` = VALUES(`pinned`),
+            `pool_max_connections` = VALUES(`pool_max_connections`), `pool_min_connections` = VALUES(`pool_min_connections`),
+            `pool_acquire_timeout_secs` = VALUES(`pool_acquire_timeout_secs`), `pool_idle_timeout_secs` = VALUES(`pool_idle_timeout_secs`),
+            `owner_id` = VALUES(`owner_id`)";
+    pub const DELETE_CONNECTION: &str = "DELETE FROM `connections` WHERE `id` = ?";
+    pub const UPDATE_PASSWORD: &str = "UPDATE `connections` SET `password` = ? WHERE `id` = ?";
+    pub const UPDATE_ALLOWLIST: &str = "UPDATE `connections` SET `allowlist` = ? WHERE `id` = ?";
+    pub const UPDATE_MASKING: &str = "UPDATE `connections` SET `masking` = ? WHERE `id` = ?";
+    pub const UPDATE_STATEMENT_POLICY: &str = "UPDATE `connections` SET `statement_policy` = ? WHERE `id` = ?";
+    pub const UPDATE_QUERY_TIMEOUT: &str = "UPDATE `connections` SET `query_timeout_ms` = ? WHERE `id` = ?";
+    pub const UPDATE_PINNED: &str = "UPDATE `connections` SET `pinned` = ? WHERE `id` = ?";
+    pub const UPDATE_POOL_OPTIONS: &str = "UPDATE `connections` SET `pool_max_connections` = ?, `pool_min_connections` = ?,
+            `pool_acquire_timeout_secs` = ?, `pool_idle_timeout_secs` = ? WHERE `id` = ?";
+    pub const LIST_CONNECTIONS: &str = "SELECT `id`, `name`, `db_type`, `host`, `port`, `username`, `password`, `password_ref`, `database_name`, `file_path`, `org`, `allowlist`, `masking`, `statement_policy`, `query_timeout_ms`, `pinned`, `pool_max_connections`, `pool_min_connections`, `pool_acquire_timeout_secs`, `pool_idle_timeout_secs`, `owner_id`, CAST(`created_at` AS CHAR) as created_at FROM `connections` ORDER BY `created_at` DESC";
+    pub const GET_CONNECTION: &str = "SELECT `id`, `name`, `db_type`, `host`, `port`, `username`, `password`, `password_ref`, `database_name`, `file_path`, `org`, `allowlist`, `masking`, `statement_policy`, `query_timeout_ms`, `pinned`, `pool_max_connections`, `pool_min_connections`, `pool_acquire_timeout_secs`, `pool_idle_timeout_secs`, `owner_id`, CAST(`created_at` AS CHAR) as created_at FROM `connections` WHERE `id` = ?";
+    pub const COUNT_CONNECTIONS: &str = "SELECT COUNT(*) FROM `connections`";
+}
+
+mod postgres {
+    use sqlx::migrate::Migrator;
+
+    pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+    pub const HAS_TABLE: &str = "SELECT COUNT(*) FROM information_schema.tables \
+         WHERE table_schema = current_schema() AND table_name = $1";
+    pub const HAS_COLUMN: &str = "SELECT COUNT(*) FROM information_schema.columns \
+         WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2";
+    pub fn add_column(table: &str, definition: &str) -> String {
+        format!(r#"ALTER TABLE "{}" ADD COLUMN {}"#, table, definition)
+    }
+
+    pub const APPLIED_MIGRATIONS: &str = r#"SELECT "version", "success", "installed_on", "execution_time"
+         FROM "_sqlx_migrations" ORDER BY "version""#;
+
+    pub const INSERT_CONNECTION: &str = r#"INSERT INTO "connections" ("id", "name", "db_type", "host", "port", "username", "password", "password_ref", "database_name", "file_path", "org", "allowlist", "masking", "statement_policy", "query_timeout_ms", "pinned",
+            "pool_max_connections", "pool_min_connections", "pool_acquire_timeout_secs", "pool_idle_timeout_secs", "owner_id")
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21)"#;
+    pub const IMPORT_CONNECTION: &str = r#"INSERT INTO "connections" ("id", "name", "db_type", "host", "port", "username", "password", "password_ref", "database_name", "file_path", "org", "allowlist", "masking", "statement_policy", "query_timeout_ms", "pinned",
+            "pool_max_connections", "pool_min_connections", "pool_acquire_timeout_secs", "pool_idle_timeout_secs", "owner_id", "created_at")
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22)
+         ON CONFLICT ("id") DO UPDATE SET "name" = EXCLUDED."name", "db_type" = EXCLUDED."db_type", "host" = EXCLUDED."host",
+            "port" = EXCLUDED."port", "username" = EXCLUDED."username", "password" = EXCLUDED."password",
+            "password_ref" = EXCLUDED."password_ref",
+            "database_name" = EXCLUDED."database_name", "file_path" = EXCLUDED."file_path", "org" = EXCLUDED."org", "allowlist" = EXCLUDED."allowlist",
+            "masking" = EXCLUDED."masking", "statement_policy" = EXCLUDED."statement_policy",
+            "query_timeout_ms" = EXCLUDED."query_timeout_ms", "pinned" = EXCLUDED."pinned",
+            "pool_max_connections" = EXCLUDED."pool_max_connections", "pool_min_connections" = EXCLUDED."pool_min_connections",
+            "pool_acquire_timeout_secs" = EXCLUDED."pool_acquire_timeout_secs", "pool_idle_timeout_secs" = EXCLUDED."pool_idle_timeout_secs",
+            "owner_id" = EXCLUDED."owner_id""#;
+    pub const DELETE_CONNECTION: &str = r#"DELETE FROM "connections" WHERE "id" = $1"#;
+    pub const UPDATE_PASSWORD: &str = r#"UPDATE "connections" SET "password" = $1 WHERE "id" = $2"#;
+    pub const UPDATE_ALLOWLIST: &str = r#"UPDATE "connections" SET "allowlist" = $1 WHERE "id" = $2"#;
+    pub const UPDATE_MASKING: &str = r#"UPDATE "connections" SET "masking" = $1 WHERE "id" = $2"#;
+    pub const UPDATE_STATEMENT_POLICY: &str = r#"UPDATE "connections" SET "statement_policy" = $1 WHERE "id" = $2"#;
+    pub const UPDATE_QUERY_TIMEOUT: &str = r#"UPDATE "connections" SET "query_timeout_ms" = $1 WHERE "id" = $2"#;
+    pub const UPDATE_PINNED: &str = r#"UPDATE "connections" SET "pinned" = $1 WHERE "id" = $2"#;
+    pub const UPDATE_POOL_OPTIONS: &str = r#"UPDATE "connections" SET "pool_max_connections" = $1, "pool_min_connections" = $2,
+            "pool_acquire_timeout_secs" = $3, "pool_idle_timeout_secs" = $4 WHERE "id" = $5"#;
+    pub const LIST_CONNECTIONS: &str = r#"SELECT "id", "name", "db_type", "host", "port", "username", "password", "password_ref", "database_name", "file_path", "org", "allowlist", "masking", "statement_policy", "query_timeout_ms", "pinned", "pool_max_connections", "pool_min_connections", "pool_acquire_timeout_secs", "pool_idle_timeout_secs", "owner_id", "created_at" FROM "connections" ORDER BY "created_at" DESC"#;
+    pub const GET_CONNECTION: &str = r#"SELECT "id", "name", "db_type", "host", "port", "username", "password", "password_ref", "database_name", "file_path", "org", "allowlist", "masking", "statement_policy", "query_timeout_ms", "pinned", "pool_max_connections", "pool_min_connections", "pool_acquire_timeout_secs", "pool_idle_timeout_secs", "owner_id", "created_at" FROM "connections" WHERE "id" = $1"#;
+    pub const COUNT_CONNECTIONS: &str = r#"SELECT COUNT(*) FROM "connections""#;
+}
+
+mod sqlite {
+    use sqlx::migrate::Migrator;
+
+    pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+    pub const HAS_TABLE: &str = r#"SELECT COUNT(*) FROM sqlite_master WHERE "type" = 'table' AND "name" = ?"#;
+    pub const HAS_COLUMN: &str = r#"SELECT COUNT(*) FROM pragma_table_info(?) WHERE "name" = ?"#;
+    pub fn add_column(table: &str, definition: &str) -> String {
+        format!(r#"ALTER TABLE "{}" ADD COLUMN {}"#, table, definition)
+    }
+
+    pub const APPLIED_MIGRATIONS: &str = r#"SELECT "version", "success", "installed_on", "execution_time"
+         FROM "_sqlx_migrations" ORDER BY "version""#;
+
+    pub const INSERT_CONNECTION: &str = r#"INSERT INTO "connections" ("id", "name", "db_type", "host", "port", "username", "password", "password_ref", "database_name", "file_path", "org", "allowlist", "masking", "statement_policy", "query_timeout_ms", "pinned",
+            "pool_max_connections", "pool_min_connections", "pool_acquire_timeout_secs", "pool_idle_timeout_secs", "owner_id")
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#;
+    pub const IMPORT_CONNECTION: &str = r#"INSERT INTO "connections" ("id", "name", "db_type", "host", "port", "username", "password", "password_ref", "database_name", "file_path", "org", "allowlist", "masking", "statement_policy", "query_timeout_ms", "pinned",
+            "pool_max_connections", "pool_min_connections", "pool_acquire_timeout_secs", "pool_idle_timeout_secs", "owner_id", "created_at")
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT ("id") DO UPDATE SET "name" = excluded."name", "db_type" = excluded."db_type", "host" = excluded."host",
+            "port" = excluded."port", "username" = excluded."username", "password" = excluded."password",
+            "password_ref" = excluded."password_ref",
+            "database_name" = excluded."database_name", "file_path" = excluded."file_path", "org" = excluded."org", "allowlist" = excluded."allowlist",
+            "masking" = excluded."masking", "statement_policy" = excluded."statement_policy",
+            "query_timeout_ms" = excluded."query_timeout_ms", "pinned" = excluded."pinned",
+            "pool_max_connections" = excluded."pool_max_connections", "pool_min_connections" = excluded."pool_min_connections",
+            "pool_acquire_timeout_secs" = excluded."pool_acquire_timeout_secs", "pool_idle_timeout_secs" = excluded."pool_idle_timeout_secs",
+            "owner_id" = excluded."owner_id""#;
+    pub const DELETE_CONNECTION: &str = r#"DELETE FROM "connections" WHERE "id" = ?"#;
+    pub const UPDATE_PASSWORD: &str = r#"UPDATE "connections" SET "password" = ? WHERE "id" = ?"#;
+    pub const UPDATE_ALLOWLIST: &str = r#"UPDATE "connections" SET "allowlist" = ? WHERE "id" = ?"#;
+    pub const UPDATE_MASKING: &str = r#"UPDATE "connections" SET "masking" = ? WHERE "id" = ?"#;
+    pub const UPDATE_STATEMENT_POLICY: &str = r#"UPDATE "connections" SET "statement_policy" = ? WHERE "id" = ?"#;
+    pub const UPDATE_QUERY_TIMEOUT: &str = r#"UPDATE "connections" SET "query_timeout_ms" = ? WHERE "id" = ?"#;
+    pub const UPDATE_PINNED: &str = r#"UPDATE "connections" SET "pinned" = ? WHERE "id" = ?"#;
+    pub const UPDATE_POOL_OPTIONS: &str = r#"UPDATE "connections" SET "pool_max_connections" = ?, "pool_min_connections" = ?,
+            "pool_acquire_timeout_secs" = ?, "pool_idle_timeout_secs" = ? WHERE "id" = ?"#;
+    pub const LIST_CONNECTIONS: &str = r#"SELECT "id", "name", "db_type", "host", "port", "username", "password", "password_ref", "database_name", "file_path", "org", "allowlist", "masking", "statement_policy", "query_timeout_ms", "pinned", "pool_max_connections", "pool_min_connections", "pool_acquire_timeout_secs", "pool_idle_timeout_secs", "owner_id", "created_at" FROM "connections" ORDER BY "created_at" DESC"#;
+    pub const GET_CONNECTION: &str = r#"SELECT "id", "name", "db_type", "host", "port", "username", "password", "password_ref", "database_name", "file_path", "org", "allowlist", "masking", "statement_policy", "query_timeout_ms", "pinned", "pool_max_connections", "pool_min_connections", "pool_acquire_timeout_secs", "pool_idle_timeout_secs", "owner_id", "created_at" FROM "connections" WHERE "id" = ?"#;
+    pub const COUNT_CONNECTIONS: &str = r#"SELECT COUNT(*) FROM "connections""#;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common::models::connection::CreateConnectionRequest;
+
+    #[tokio::test]
+    async fn stores_connections_in_sqlite() {
+        let meta_pool = MetaPool::connect("sqlite::memory:", 1).await.unwrap();
+        let repository = for_pool(&meta_pool);
+        repository.migrate().await.unwrap();
+
+        let mut config = CreateConnectionRequest {
+            name: "local".to_string(),
+            db_type: Some(DbType::SQLite),
+            file_path: Some("local.db".to_string()),
+            ..Default::default()
+        }
+        .into_config("c1".to_string(), String::new())
+        .unwrap();
+        repository.insert_connection(&config).await.unwrap();
+        assert_eq!(repository.update_pinned("c1", true).await.unwrap(), 1);
+        assert_eq!(repository.update_query_timeout("c1", Some(5_000)).await.unwrap(), 1);
+
+        let stored = repository.get_connection("c1").await.unwrap().unwrap();
+        assert!(stored.pinned);
+        assert_eq!(stored.query_timeout_ms, Some(5_000));
+
+        config.name = "renamed".to_string();
+        repository.import_connection(&config).await.unwrap();
+        assert_eq!(repository.get_connection("c1").await.unwrap().unwrap().name, "renamed");
+
+        config.id = "c2".to_string();
+        config.created_at = "2024-01-02T03:04:05Z".to_string();
+        repository.import_connection(&config).await.unwrap();
+        let stored = repository.get_connection("c2").await.unwrap().unwrap();
+        assert_eq!(stored.created_at, "2024-01-02 03:04:05");
+        assert_eq!(repository.count_connections().await.unwrap(), 2);
+
+        assert_eq!(repository.delete_connection("c1").await.unwrap(), 1);
+        assert_eq!(repository.list_connections().await.unwrap().len(), 1);
+    }
+}
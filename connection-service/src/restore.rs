@@ -0,0 +1,330 @@
+//! Restoring SQL dumps into a connection.
+//!
+//! Splits a backup (or an uploaded script) into statements and executes them
+//! on a single connection, so session settings from the dump (`SET NAMES`,
+//! `FOREIGN_KEY_CHECKS`, `search_path`) apply to the following statements.
+//! Failing statements are recorded and skipped unless `stop_on_error` is set.
+//! A cancelled restore stops after its current batch.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::Utc;
+use sqlx::pool::PoolConnection;
+use sqlx::{Executor, MySql, Postgres};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use common::db_error::DbErrorDetails;
+use common::errors::{AppError, AppResult};
+use common::jobs::{CancellationToken, JobStore};
+use common::models::backup::{RestoreJob, RestoreRequest, RestoreStatementError, RestoreStatus};
+use common::models::connection::DbType;
+use common::models::job::{Job, JobKind};
+use common::progress::{kinds, ProgressEvent, ProgressHub};
+use common::utils::SqlSplitter;
+use dbm_core::DatabasePool;
+use crate::backup::BackupManager;
+use crate::pool_manager::PoolManager;
+use crate::schema_cache::SchemaCache;
+use crate::schema_diff::Dialect;
+
+/// Default statements executed between progress updates.
+const DEFAULT_BATCH_SIZE: u32 = 100;
+
+/// Failed statements kept on the job.
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Characters of a failed statement kept in the report.
+const STATEMENT_PREVIEW_CHARS: usize = 200;
+
+/// A dedicated connection the whole script runs on.
+enum RestoreConnection {
+    MySql(PoolConnection<MySql>),
+    Postgres(PoolConnection<Postgres>),
+}
+
+impl RestoreConnection {
+    async fn execute(&mut self, sql: &str) -> Result<(), sqlx::Error> {
+        match self {
+            RestoreConnection::MySql(conn) => conn.execute(sqlx::raw_sql(sql)).await.map(|_| ()),
+            RestoreConnection::Postgres(conn) => conn.execute(sqlx::raw_sql(sql)).await.map(|_| ()),
+        }
+    }
+}
+
+/// Runs and tracks restore jobs.
+pub struct RestoreManager {
+    pool_manager: Arc<PoolManager>,
+    backups: Arc<BackupManager>,
+    schema_cache: Arc<SchemaCache>,
+    progress: Arc<ProgressHub>,
+    job_store: Arc<JobStore>,
+    jobs: RwLock<HashMap<String, RestoreJob>>,
+}
+
+impl RestoreManager {
+    /// Creates a new restore manager.
+    pub fn new(
+        pool_manager: Arc<PoolManager>,
+        backups: Arc<BackupManager>,
+        schema_cache: Arc<SchemaCache>,
+        progress: Arc<ProgressHub>,
+        job_store: Arc<JobStore>,
+    ) -> Self {
+        Self {
+            pool_manager,
+            backups,
+            schema_cache,
+            progress,
+            job_store,
+            jobs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Starts restoring a backup or uploaded script; statements run in the background.
+    pub async fn start(self: &Arc<Self>, connection_id: &str, req: RestoreRequest) -> AppResult<RestoreJob> {
+        let config = self
+            .pool_manager
+            .get_connection(connection_id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+
+        let (script, backup_database) = match (&req.backup_id, req.sql) {
+            (Some(backup_id), None) => {
+                let (backup, content) = self.backups.read(backup_id).await?;
+                let script = String::from_utf8(content).map_err(|_| {
+                    AppError::InvalidInput(format!("backup {} is not valid UTF-8", backup_id))
+                })?;
+                (script, Some(backup.database))
+            }
+            (None, Some(sql)) => (sql, None),
+            _ => {
+                return Err(AppError::InvalidInput(
+                    "exactly one of backup_id and sql is required".into(),
+                ))
+            }
+        };
+        let database = req.database.filter(|d| !d.is_empty()).or(backup_database);
+
+        let mut conn = match self.pool_manager.get_or_create_pool(connection_id).await? {
+            DatabasePool::MySQL(pool) => RestoreConnection::MySql(pool.acquire().await?),
+            DatabasePool::Postgres(pool) => RestoreConnection::Postgres(pool.acquire().await?),
+            _ => {
+                return Err(AppError::UnsupportedDatabaseType(
+                    "Restore is only supported for MySQL and PostgreSQL".into(),
+                ))
+            }
+        };
+        if let Some(db) = &database {
+            let select = match config.db_type {
+                DbType::Postgres => format!("SET search_path TO {}", Dialect::Postgres.quote(db)),
+                _ => format!("USE {}", Dialect::MySql.quote(db)),
+            };
+            conn.execute(&select)
+                .await
+                .map_err(|e| AppError::InvalidInput(format!("cannot select database {}: {}", db, e)))?;
+        }
+
+        let statements = SqlSplitter::split(&script, &config.db_type);
+        drop(script);
+
+        let now = Utc::now().to_rfc3339();
+        let job = RestoreJob {
+            id: Uuid::new_v4().to_string(),
+            connection_id: connection_id.to_string(),
+            backup_id: req.backup_id,
+            database,
+            status: RestoreStatus::Running,
+            statements_total: statements.len(),
+            statements_executed: 0,
+            statements_failed: 0,
+            progress: 0.0,
+            errors: Vec::new(),
+            error: None,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        let cancelled = self.job_store.start_cancellable(Job::new(&job.id, JobKind::Restore)).await?;
+        self.jobs.write().await.insert(job.id.clone(), job.clone());
+        self.progress.publish(ProgressEvent::new(kinds::RESTORE, &job.id, &job, false));
+
+        tracing::info!(
+            job_id = %job.id,
+            connection_id,
+            statements = statements.len(),
+            "Restore started"
+        );
+
+        let mgr = self.clone();
+        let job_id = job.id.clone();
+        let batch_size = req.batch_size.unwrap_or(DEFAULT_BATCH_SIZE) as usize;
+        let stop_on_error = req.stop_on_error;
+        let connection = connection_id.to_string();
+        tokio::spawn(async move {
+            mgr.run(&job_id, &connection, conn, statements, batch_size, stop_on_error, &cancelled)
+                .await;
+            // Restored scripts usually contain DDL.
+            mgr.schema_cache.invalidate(&connection).await;
+        });
+
+        Ok(job)
+    }
+
+    /// Lists restore jobs of a connection (newest first).
+    pub async fn list(&self, connection_id: &str) -> Vec<RestoreJob> {
+        let mut jobs: Vec<RestoreJob> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|j| j.connection_id == connection_id)
+            .cloned()
+            .collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        jobs
+    }
+
+    /// Gets a restore job by ID.
+    pub async fn get(&self, job_id: &str) -> AppResult<RestoreJob> {
+        self.jobs
+            .read()
+            .await
+            .get(job_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("restore job {}", job_id)))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run(
+        &self,
+        job_id: &str,
+        connection_id: &str,
+        mut conn: RestoreConnection,
+        statements: Vec<String>,
+        batch_size: usize,
+        stop_on_error: bool,
+        cancelled: &CancellationToken,
+    ) {
+        let total = statements.len();
+        let mut executed = 0;
+
+        for batch in statements.chunks(batch_size) {
+            let mut failures = Vec::new();
+            let mut aborted = None;
+            for statement in batch {
+                let started = Instant::now();
+                let outcome = conn.execute(statement).await;
+                self.pool_manager
+                    .workload()
+                    .record(connection_id, statement, started.elapsed(), outcome.is_ok())
+                    .await;
+                if let Err(e) = outcome {
+                    failures.push(RestoreStatementError {
+                        index: executed,
+                        statement: preview(statement),
+                        error: e.to_string(),
+                        details: DbErrorDetails::from_sqlx(&e).map(|mut d| {
+                            d.locate(statement);
+                            d
+                        }),
+                    });
+                    if stop_on_error {
+                        aborted = Some(format!("statement {} failed: {}", executed, e));
+                    }
+                }
+                executed += 1;
+                if aborted.is_some() {
+                    break;
+                }
+            }
+
+            self.update(job_id, |j| {
+                j.statements_executed = executed;
+                j.statements_failed += failures.len();
+                let room = MAX_REPORTED_ERRORS.saturating_sub(j.errors.len());
+                j.errors.extend(failures.into_iter().take(room));
+                j.progress = restore_progress(executed, total);
+            })
+            .await;
+
+            if let Some(error) = aborted {
+                tracing::error!(job_id, error = %error, "Restore aborted");
+                self.update(job_id, |j| {
+                    j.status = RestoreStatus::Failed;
+                    j.error = Some(error);
+                })
+                .await;
+                return;
+            }
+            if cancelled.is_cancelled() && executed < total {
+                self.update(job_id, |j| j.status = RestoreStatus::Cancelled).await;
+                tracing::info!(job_id, executed, "Restore cancelled");
+                return;
+            }
+        }
+
+        self.update(job_id, |j| {
+            j.status = RestoreStatus::Completed;
+            j.progress = 100.0;
+        })
+        .await;
+        tracing::info!(job_id, statements = total, "Restore completed");
+    }
+
+    async fn update(&self, job_id: &str, f: impl FnOnce(&mut RestoreJob)) {
+        let job = {
+            let mut jobs = self.jobs.write().await;
+            let Some(job) = jobs.get_mut(job_id) else {
+                return;
+            };
+            f(job);
+            job.updated_at = Utc::now().to_rfc3339();
+            let finished = job.status != RestoreStatus::Running;
+            self.progress.publish(ProgressEvent::new(kinds::RESTORE, job_id, job, finished));
+            job.clone()
+        };
+        match job.status {
+            RestoreStatus::Running => self.job_store.progress(job_id, job.progress).await,
+            RestoreStatus::Completed => {
+                let target = job.connection_id.clone();
+                self.job_store.finish(job_id, |j| j.complete(Some(target))).await
+            }
+            RestoreStatus::Failed => {
+                let error = job.error.unwrap_or_default();
+                self.job_store.finish(job_id, |j| j.fail(error)).await
+            }
+            RestoreStatus::Cancelled => self.job_store.finish(job_id, Job::cancel).await,
+        }
+    }
+}
+
+/// Truncates a statement for error reports.
+fn preview(statement: &str) -> String {
+    if statement.chars().count() <= STATEMENT_PREVIEW_CHARS {
+        return statement.to_string();
+    }
+    let mut text: String = statement.chars().take(STATEMENT_PREVIEW_CHARS).collect();
+    text.push_str("...");
+    text
+}
+
+fn restore_progress(executed: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+    (executed as f64 / total as f64 * 100.0).min(100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_long_statements_in_reports() {
+        assert_eq!(preview("SELECT 1"), "SELECT 1");
+        let long = "x".repeat(STATEMENT_PREVIEW_CHARS + 10);
+        assert_eq!(preview(&long).len(), STATEMENT_PREVIEW_CHARS + 3);
+    }
+}
@@ -0,0 +1,183 @@
+//! Connection change history.
+//!
+//! Every change to a saved connection (creation, settings updates, password
+//! rotation, deletion, metadata import) is recorded in the
+//! `connection_revisions` metadata table with the principal that made it and
+//! a field-level diff. Password values never appear in the history: the diff
+//! only shows that the password changed, and snapshots are stored without it.
+//!
+//! Each revision keeps a snapshot of the connection after the change, so an
+//! earlier revision can be restored. Restoring keeps the current password,
+//! owner and creation time; restoring a deleted connection recreates it
+//! without a password.
+
+use std::sync::Arc;
+
+use common::errors::{AppError, AppResult};
+use common::meta::{self, Statement};
+use common::meta_query;
+use common::models::connection::{ConnectionConfig, ConnectionRevision, FieldChange, RevisionAction};
+use crate::pool_manager::PoolManager;
+
+const SELECT_REVISION: Statement = Statement {
+    mysql: "SELECT `connection_id`, `revision`, `action`, `actor`, `changes`, `restored_from`, \
+         CAST(`created_at` AS CHAR) AS created_at FROM `connection_revisions`",
+    postgres: r#"SELECT "connection_id", "revision", "action", "actor", "changes", "restored_from",
+         "created_at" FROM "connection_revisions""#,
+    sqlite: r#"SELECT "connection_id", "revision", "action", "actor", "changes", "restored_from",
+         "created_at" FROM "connection_revisions""#,
+};
+
+/// Row from the `connection_revisions` metadata table, without the snapshot.
+#[derive(sqlx::FromRow)]
+struct RevisionRow {
+    connection_id: String,
+    revision: meta::Unsigned<u32>,
+    action: String,
+    actor: Option<String>,
+    changes: String,
+    restored_from: Option<meta::Unsigned<u32>>,
+    created_at: String,
+}
+
+impl RevisionRow {
+    fn into_revision(self) -> ConnectionRevision {
+        ConnectionRevision {
+            connection_id: self.connection_id,
+            revision: self.revision.0,
+            action: self.action.parse().unwrap_or(RevisionAction::Updated),
+            actor: self.actor,
+            changes: serde_json::from_str(&self.changes).unwrap_or_default(),
+            restored_from: self.restored_from.map(|r| r.0),
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Records and restores connection revisions.
+pub struct RevisionStore {
+    pool_manager: Arc<PoolManager>,
+}
+
+impl RevisionStore {
+    /// Creates the store.
+    pub fn new(pool_manager: Arc<PoolManager>) -> Self {
+        Self { pool_manager }
+    }
+
+    /// Records a change between two versions of a connection (`None` before
+    /// creation and after deletion). Updates that change nothing are skipped.
+    ///
+    /// Failures are logged; the change itself has already been applied.
+    pub async fn record(
+        &self,
+        before: Option<&ConnectionConfig>,
+        after: Option<&ConnectionConfig>,
+        action: RevisionAction,
+        actor: Option<&str>,
+        restored_from: Option<u32>,
+    ) {
+        let Some(connection_id) = after.or(before).map(|c| c.id.as_str()) else {
+            return;
+        };
+        let changes = FieldChange::diff(before, after);
+        if changes.is_empty() && action == RevisionAction::Updated {
+            return;
+        }
+        // The password is not serialized, so snapshots never contain it
+        let snapshot = after.and_then(|c| serde_json::to_string(c).ok());
+
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `connection_revisions` \
+                 (`connection_id`, `revision`, `action`, `actor`, `changes`, `snapshot`, `restored_from`) \
+                 SELECT ?, COALESCE(MAX(`revision`), 0) + 1, ?, ?, ?, ?, ? \
+                 FROM `connection_revisions` WHERE `connection_id` = ?",
+            postgres: r#"INSERT INTO "connection_revisions"
+                 ("connection_id", "revision", "action", "actor", "changes", "snapshot", "restored_from")
+                 SELECT $1, COALESCE(MAX("revision"), 0) + 1, $2, $3, $4, $5, $6
+                 FROM "connection_revisions" WHERE "connection_id" = $7"#,
+            sqlite: r#"INSERT INTO "connection_revisions"
+                 ("connection_id", "revision", "action", "actor", "changes", "snapshot", "restored_from")
+                 SELECT ?, COALESCE(MAX("revision"), 0) + 1, ?, ?, ?, ?, ?
+                 FROM "connection_revisions" WHERE "connection_id" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(connection_id)
+                .bind(action.as_str())
+                .bind(actor)
+                .bind(serde_json::to_string(&changes).unwrap_or_else(|_| "[]".to_string()))
+                .bind(snapshot)
+                .bind(restored_from.map(i64::from))
+                .bind(connection_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        });
+        if let Err(e) = result {
+            tracing::warn!(connection_id, action = action.as_str(), error = %e, "Failed to record connection revision");
+        }
+    }
+
+    /// Lists the revisions of a connection, newest first.
+    pub async fn list(&self, connection_id: &str) -> AppResult<Vec<ConnectionRevision>> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_REVISION,
+            &Statement {
+                mysql: "WHERE `connection_id` = ? ORDER BY `revision` DESC",
+                postgres: r#"WHERE "connection_id" = $1 ORDER BY "revision" DESC"#,
+                sqlite: r#"WHERE "connection_id" = ? ORDER BY "revision" DESC"#,
+            },
+        ]);
+        let rows: Vec<RevisionRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .bind(connection_id)
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(RevisionRow::into_revision).collect())
+    }
+
+    /// Restores a connection to the state saved with a revision and records
+    /// the restore as a new revision.
+    ///
+    /// # Errors
+    /// `NotFound` for unknown revisions, `InvalidInput` for a revision that
+    /// deleted the connection.
+    pub async fn restore(&self, connection_id: &str, revision: u32, actor: Option<&str>) -> AppResult<ConnectionConfig> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `snapshot` FROM `connection_revisions` WHERE `connection_id` = ? AND `revision` = ?",
+            postgres: r#"SELECT "snapshot" FROM "connection_revisions" WHERE "connection_id" = $1 AND "revision" = $2"#,
+            sqlite: r#"SELECT "snapshot" FROM "connection_revisions" WHERE "connection_id" = ? AND "revision" = ?"#,
+        });
+        let snapshot: Option<(Option<String>,)> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(connection_id)
+                .bind(i64::from(revision))
+                .fetch_optional(pool)
+                .await
+        })?;
+        let snapshot = snapshot
+            .ok_or_else(|| AppError::NotFound(format!("revision {} of connection {}", revision, connection_id)))?
+            .0
+            .ok_or_else(|| {
+                AppError::InvalidInput(format!(
+                    "revision {} deleted the connection, restore an earlier revision",
+                    revision
+                ))
+            })?;
+        let mut config: ConnectionConfig = serde_json::from_str(&snapshot)
+            .map_err(|e| AppError::Internal(format!("Corrupt snapshot of revision {}: {}", revision, e)))?;
+
+        let current = self.pool_manager.get_connection(connection_id).await;
+        if let Some(current) = &current {
+            config.password = current.password.clone();
+            config.owner_id = current.owner_id.clone();
+            config.created_at = current.created_at.clone();
+        }
+        self.pool_manager.import_connection(config.clone(), true).await?;
+        self.record(current.as_ref(), Some(&config), RevisionAction::Restored, actor, Some(revision))
+            .await;
+        Ok(config)
+    }
+}
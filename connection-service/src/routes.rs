@@ -1,20 +1,120 @@
 //! 连接服务路由模块
 
-use axum::{routing::{get, post}, Router};
+use axum::{extract::DefaultBodyLimit, middleware, routing::{delete, get, post, put}, Router};
+use common::middleware::etag_middleware;
 use crate::handlers;
 use crate::state::AppState;
 
+/// 恢复接口允许上传的 SQL 脚本大小上限
+const RESTORE_BODY_LIMIT: usize = 256 * 1024 * 1024;
+
 /// 创建连接管理路由
 pub fn router() -> Router<AppState> {
     Router::new()
-        .route("/api/connections", get(handlers::list_connections).post(handlers::create_connection))
+        .route("/api/connections", get(handlers::list_connections).post(handlers::create_connection).layer(middleware::from_fn(etag_middleware)))
+        .route("/api/connections/test", post(handlers::test_unsaved_connection))
         .route("/api/connections/{id}", get(handlers::get_connection).delete(handlers::delete_connection))
         .route("/api/connections/{id}/test", get(handlers::test_connection))
+        .route("/api/connections/{id}/allowlist", put(handlers::set_connection_allowlist))
+        .route("/api/connections/{id}/masking", put(handlers::set_connection_masking))
+        .route("/api/connections/{id}/statement-policy", put(handlers::set_connection_statement_policy))
+        .route("/api/connections/{id}/query-timeout", put(handlers::set_connection_query_timeout))
+        .route("/api/connections/{id}/pinned", put(handlers::set_connection_pinned))
+        .route("/api/connections/{id}/pool-options", put(handlers::set_connection_pool_options))
+        .route("/api/connections/{id}/rotate-password", post(handlers::rotate_connection_password))
+        .route("/api/connections/{id}/history", get(handlers::get_connection_history))
+        .route("/api/connections/{id}/history/{revision}/restore", post(handlers::restore_connection_revision))
+        .route("/api/connections/{id}/favorite", put(handlers::add_connection_favorite).delete(handlers::remove_connection_favorite))
         .route("/api/connections/{id}/stats", get(handlers::get_connection_stats))
+        .route("/api/connections/{id}/health", get(handlers::get_connection_health))
         .route("/api/connections/{id}/databases", get(handlers::get_connection_databases))
-        .route("/api/connections/{id}/schema", get(handlers::get_connection_schema))
+        .route("/api/connections/{id}/workload", get(handlers::get_connection_workload))
+        .route("/api/connections/{id}/schema", get(handlers::get_connection_schema).layer(middleware::from_fn(etag_middleware)))
+        .route("/api/connections/{id}/schema/invalidate", post(handlers::invalidate_schema_cache))
+        .route("/api/connections/{id}/autocomplete", get(handlers::get_autocomplete).layer(middleware::from_fn(etag_middleware)))
+        .route("/api/connections/{id}/autocomplete/invalidate", post(handlers::invalidate_autocomplete))
+        .route("/api/connections/{id}/schema/graph", get(handlers::get_schema_graph).layer(middleware::from_fn(etag_middleware)))
+        .route("/api/connections/{id}/graph/labels", get(handlers::get_graph_labels))
+        .route("/api/connections/{id}/graph/relationship-types", get(handlers::get_graph_relationship_types))
+        .route("/api/connections/{id}/keys/{key}", get(handlers::get_key).put(handlers::set_key).delete(handlers::delete_key))
+        .route("/api/connections/{id}/tables/{table}/stats", get(handlers::get_table_stats))
+        .route("/api/connections/{id}/tables/{table}/seed", post(handlers::seed_table))
         .route("/api/connections/{id}/query", post(handlers::execute_query))
+        .route("/api/connections/{id}/sample", post(handlers::sample_table))
         .route("/api/connections/{id}/processes", get(handlers::get_connection_processes))
+        .route("/api/connections/{id}/schema-changes", get(handlers::list_schema_changes).post(handlers::start_schema_change))
+        .route("/api/connections/{id}/schema-changes/{job_id}", get(handlers::get_schema_change))
+        .route("/api/connections/{id}/schema-changes/{job_id}/cutover", post(handlers::cutover_schema_change))
+        .route("/api/connections/{id}/schema-changes/{job_id}/cancel", post(handlers::cancel_schema_change))
+        .route("/api/connections/{id}/backups", get(handlers::list_backups).post(handlers::create_backup))
+        .route("/api/connections/{id}/backups/{backup_id}", get(handlers::get_backup))
+        .route("/api/connections/{id}/backups/{backup_id}/download", get(handlers::download_backup))
+        .route("/api/connections/{id}/restore", get(handlers::list_restores).post(handlers::start_restore).layer(DefaultBodyLimit::max(RESTORE_BODY_LIMIT)))
+        .route("/api/connections/{id}/restore/{job_id}", get(handlers::get_restore))
+        .route("/api/connections/{id}/guest-links", get(handlers::list_guest_links).post(handlers::create_guest_link))
+        .route("/api/connections/{id}/guest-links/{link_id}", delete(handlers::revoke_guest_link))
+        .route("/api/scheduled-jobs", get(handlers::list_scheduled_jobs).post(handlers::create_scheduled_job))
+        .route("/api/scheduled-jobs/{id}", get(handlers::get_scheduled_job).delete(handlers::delete_scheduled_job))
+        .route("/api/scheduled-jobs/{id}/enable", post(handlers::enable_scheduled_job))
+        .route("/api/scheduled-jobs/{id}/disable", post(handlers::disable_scheduled_job))
+        .route("/api/scheduled-jobs/{id}/run", post(handlers::run_scheduled_job))
+        .route("/api/scheduled-jobs/{id}/runs", get(handlers::list_scheduled_job_runs))
+        .route("/api/alerts", get(handlers::list_alert_rules).post(handlers::create_alert_rule))
+        .route("/api/alerts/{id}", get(handlers::get_alert_rule).delete(handlers::delete_alert_rule))
+        .route("/api/alerts/{id}/enable", post(handlers::enable_alert_rule))
+        .route("/api/alerts/{id}/disable", post(handlers::disable_alert_rule))
+        .route("/api/alerts/{id}/evaluate", post(handlers::evaluate_alert_rule))
+        .route("/api/transfers", get(handlers::list_transfers).post(handlers::start_transfer))
+        .route("/api/transfers/{id}", get(handlers::get_transfer))
+        .route("/api/transfers/{id}/cancel", post(handlers::cancel_transfer))
+        .route("/api/jobs", get(handlers::list_jobs))
+        .route("/api/jobs/{id}", get(handlers::get_job))
+        .route("/api/jobs/{id}/cancel", post(handlers::cancel_job))
+        .route("/api/jobs/{id}/events", get(handlers::job_events))
+        .route("/api/snapshots", get(handlers::list_snapshots).post(handlers::create_snapshot))
+        .route("/api/snapshots/compare", post(handlers::compare_snapshots))
+        .route("/api/snapshots/{id}", get(handlers::get_snapshot).delete(handlers::delete_snapshot))
+        .route("/api/workspaces", get(handlers::list_workspaces).post(handlers::create_workspace))
+        .route("/api/workspaces/{id}", get(handlers::get_workspace).put(handlers::update_workspace).delete(handlers::delete_workspace))
+        .route("/api/workspaces/{id}/members", post(handlers::add_workspace_member))
+        .route("/api/workspaces/{id}/members/{principal}", delete(handlers::remove_workspace_member))
+        .route("/api/workspaces/{id}/connections/{connection_id}", put(handlers::add_workspace_connection).delete(handlers::remove_workspace_connection))
+        .route("/api/workspaces/{id}/snapshots/{snapshot_id}", put(handlers::add_workspace_snapshot).delete(handlers::remove_workspace_snapshot))
+        .route("/api/schema/diff", post(handlers::diff_schemas))
+        .route("/api/admin/metadata/export", get(handlers::export_metadata))
+        .route("/api/admin/metadata/import", post(handlers::import_metadata))
+        .route("/api/admin/migrations", get(handlers::get_migrations))
+        .route("/api/admin/keys", get(handlers::list_api_keys).post(handlers::create_api_key))
+        .route("/api/admin/keys/{id}", delete(handlers::revoke_api_key))
+        .route("/api/admin/policies", get(handlers::list_policies).post(handlers::create_policy))
+        .route("/api/admin/policies/{id}", get(handlers::get_policy).put(handlers::update_policy).delete(handlers::delete_policy))
+        .route("/api/admin/policy-decisions", get(handlers::list_policy_decisions))
+        .route("/api/admin/usage", get(handlers::get_usage_report))
+        .route("/api/admin/usage/quotas/{principal}", put(handlers::set_usage_quota).delete(handlers::delete_usage_quota))
+        .route("/api/admin/config/reload", post(handlers::reload_config))
+        .route("/api/usage/me", get(handlers::get_my_usage))
+        .route("/api/sessions", get(handlers::list_my_sessions).delete(handlers::revoke_other_sessions))
+        .route("/api/sessions/{id}", delete(handlers::revoke_my_session))
+        .route("/api/admin/sessions", get(handlers::list_sessions))
+        .route("/api/admin/sessions/{id}", delete(handlers::revoke_session))
+        .route("/api/auth/login", post(handlers::login))
+        .route("/api/auth/refresh", post(handlers::refresh_token))
+        .route("/api/auth/logout", post(handlers::logout))
+        .route("/api/admin/users", get(handlers::list_users))
+        .route("/api/admin/users/{username}", put(handlers::set_user_password).delete(handlers::delete_user))
+        .route("/api/admin/login-lockouts", get(handlers::list_login_lockouts))
+        .route("/api/admin/login-lockouts/{key}", delete(handlers::clear_login_lockout))
+        .route("/api/admin/pools", get(handlers::list_pools))
+        .route("/api/admin/pools/{id}", delete(handlers::reset_pool))
+        .route("/healthz", get(handlers::healthz))
+        .route("/readyz", get(handlers::readyz))
         .route("/api/health", get(handlers::health_check))
+        .route("/api/health/ready", get(handlers::readiness))
         .route("/internal/pools/{id}", get(handlers::get_pool_info))
+        .route("/internal/connections/{id}/execute", post(handlers::execute_on_behalf))
+        .route("/internal/connections/{id}/changes", post(handlers::execute_change))
+        .route("/internal/api-keys/verify", post(handlers::verify_api_key))
+        .route("/internal/sessions/verify", post(handlers::verify_session))
+        .route("/internal/auth/sso-session", post(handlers::create_sso_session))
+        .route("/internal/authz/decide", post(handlers::decide_authz))
 }
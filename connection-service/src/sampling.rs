@@ -0,0 +1,175 @@
+//! Random table sampling for quick data exploration.
+//!
+//! PostgreSQL uses `TABLESAMPLE`; MySQL has no equivalent, so small tables use
+//! `ORDER BY RAND()` and large tables a `WHERE RAND() < fraction` filter that
+//! avoids sorting the whole table and is capped by `MAX_EXECUTION_TIME`.
+
+use std::time::Instant;
+
+use sqlx::{MySqlPool, PgPool, Row};
+
+use common::errors::{AppError, AppResult};
+use common::models::query::{SampleMethod, SampleRequest, SampleResult};
+use dbm_core::DatabasePool;
+use dbm_core::drivers::{mysql, postgres};
+use crate::introspection;
+use crate::pool_manager::PoolManager;
+use crate::schema_diff::Dialect;
+
+/// Tables up to this many (estimated) rows are sampled with `ORDER BY random()`.
+const SMALL_TABLE_ROWS: u64 = 10_000;
+
+/// PostgreSQL tables below this size use row-level BERNOULLI sampling.
+const BERNOULLI_MAX_ROWS: u64 = 1_000_000;
+
+/// Oversampling factor, so the sample still fills `rows` after the random filter.
+const OVERSAMPLE: f64 = 2.0;
+
+/// Execution time cap for the MySQL random filter, in milliseconds.
+const MYSQL_SAMPLE_TIMEOUT_MS: u64 = 10_000;
+
+/// Fetches a random sample of a table.
+pub async fn sample_table(pool_manager: &PoolManager, connection_id: &str, req: &SampleRequest) -> AppResult<SampleResult> {
+    let config = pool_manager
+        .get_connection(connection_id)
+        .await
+        .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+    let schema = introspection::resolve_schema(&config, req.database.as_deref())?;
+
+    match pool_manager.get_or_create_pool(connection_id).await? {
+        DatabasePool::MySQL(pool) => sample_mysql(&pool, &schema, req).await,
+        DatabasePool::Postgres(pool) => sample_postgres(&pool, &schema, req).await,
+        _ => Err(AppError::UnsupportedDatabaseType(
+            "Table sampling is only supported for MySQL and PostgreSQL".into(),
+        )),
+    }
+}
+
+async fn sample_mysql(
+    pool: &MySqlPool,
+    database: &str,
+    req: &SampleRequest,
+) -> AppResult<SampleResult> {
+    let row = sqlx::query(
+        "SELECT CAST(TABLE_ROWS AS UNSIGNED) AS table_rows FROM information_schema.TABLES \
+         WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?",
+    )
+    .bind(database)
+    .bind(&req.table)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("table {}.{}", database, req.table)))?;
+    let estimated_rows = row.try_get::<Option<u64>, _>("table_rows").ok().flatten().unwrap_or(0);
+
+    let dialect = Dialect::MySql;
+    let table = format!("{}.{}", dialect.quote(database), dialect.quote(&req.table));
+    let (method, sql) = mysql_sample_sql(&table, estimated_rows, req.rows);
+
+    let result = mysql::run_query(pool, &sql, req.rows, &[], None, Instant::now()).await?;
+    Ok(SampleResult { method, estimated_rows, result })
+}
+
+async fn sample_postgres(
+    pool: &PgPool,
+    schema: &str,
+    req: &SampleRequest,
+) -> AppResult<SampleResult> {
+    let row = sqlx::query(
+        "SELECT c.reltuples::float8 AS reltuples FROM pg_catalog.pg_class c \
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+         WHERE n.nspname = $1 AND c.relname = $2 AND c.relkind IN ('r', 'p', 'm')",
+    )
+    .bind(schema)
+    .bind(&req.table)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("table {}.{}", schema, req.table)))?;
+    // reltuples is -1 for tables that were never analyzed.
+    let reltuples: f64 = row.try_get("reltuples").unwrap_or(-1.0);
+    let estimated_rows = if reltuples > 0.0 { reltuples as u64 } else { 0 };
+
+    let dialect = Dialect::Postgres;
+    let table = format!("{}.{}", dialect.quote(schema), dialect.quote(&req.table));
+    let (method, sql) = postgres_sample_sql(&table, estimated_rows, req.rows);
+
+    let result = postgres::run_query(pool, &sql, req.rows, &[], None, Instant::now()).await?;
+    Ok(SampleResult { method, estimated_rows, result })
+}
+
+/// Percentage of the table to read so that about `rows` rows come back.
+fn sample_percent(estimated_rows: u64, rows: u32) -> f64 {
+    if estimated_rows == 0 {
+        return 100.0;
+    }
+    (rows as f64 * OVERSAMPLE / estimated_rows as f64 * 100.0).clamp(0.0001, 100.0)
+}
+
+fn mysql_sample_sql(table: &str, estimated_rows: u64, rows: u32) -> (SampleMethod, String) {
+    if estimated_rows <= SMALL_TABLE_ROWS {
+        return (
+            SampleMethod::OrderByRandom,
+            format!("SELECT * FROM {} ORDER BY RAND() LIMIT {}", table, rows),
+        );
+    }
+    let fraction = sample_percent(estimated_rows, rows) / 100.0;
+    (
+        SampleMethod::RandomFilter,
+        format!(
+            "SELECT /*+ MAX_EXECUTION_TIME({}) */ * FROM {} WHERE RAND() < {:.8} LIMIT {}",
+            MYSQL_SAMPLE_TIMEOUT_MS, table, fraction, rows
+        ),
+    )
+}
+
+fn postgres_sample_sql(table: &str, estimated_rows: u64, rows: u32) -> (SampleMethod, String) {
+    if estimated_rows <= SMALL_TABLE_ROWS {
+        return (
+            SampleMethod::OrderByRandom,
+            format!("SELECT * FROM {} ORDER BY random() LIMIT {}", table, rows),
+        );
+    }
+    let percent = sample_percent(estimated_rows, rows);
+    let method = if estimated_rows < BERNOULLI_MAX_ROWS {
+        SampleMethod::TablesampleBernoulli
+    } else {
+        SampleMethod::TablesampleSystem
+    };
+    let keyword = match method {
+        SampleMethod::TablesampleBernoulli => "BERNOULLI",
+        _ => "SYSTEM",
+    };
+    (
+        method,
+        format!(
+            "SELECT * FROM {} TABLESAMPLE {} ({:.4}) LIMIT {}",
+            table, keyword, percent, rows
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_small_tables_and_filters_large_ones_on_mysql() {
+        let (method, sql) = mysql_sample_sql("`db`.`t`", 500, 100);
+        assert_eq!(method, SampleMethod::OrderByRandom);
+        assert_eq!(sql, "SELECT * FROM `db`.`t` ORDER BY RAND() LIMIT 100");
+
+        let (method, sql) = mysql_sample_sql("`db`.`t`", 10_000_000, 100);
+        assert_eq!(method, SampleMethod::RandomFilter);
+        assert!(sql.contains("WHERE RAND() < 0.00002000 LIMIT 100"));
+        assert!(!sql.contains("ORDER BY"));
+    }
+
+    #[test]
+    fn picks_tablesample_by_table_size() {
+        let (method, sql) = postgres_sample_sql("\"public\".\"t\"", 200_000, 100);
+        assert_eq!(method, SampleMethod::TablesampleBernoulli);
+        assert!(sql.contains("TABLESAMPLE BERNOULLI (0.1000)"));
+
+        let (method, _) = postgres_sample_sql("\"public\".\"t\"", 50_000_000, 100);
+        assert_eq!(method, SampleMethod::TablesampleSystem);
+    }
+}
@@ -0,0 +1,629 @@
+//! Cron scheduler for recurring tasks.
+//!
+//! Job definitions live in the `scheduled_jobs` metadata table and every run
+//! is recorded in `scheduled_job_runs`. A background loop polls for due jobs,
+//! claims each one by advancing `next_run_at` with a conditional update (so
+//! several service instances never run the same tick twice) and executes the
+//! task. All times are UTC.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::meta::Statement;
+use common::meta_query;
+use common::models::backup::CreateBackupRequest;
+use common::models::scheduler::{
+    CreateScheduledJobRequest, JobRun, JobRunStatus, ScheduledJob, ScheduledTaskKind,
+};
+use common::utils::SqlValidator;
+use crate::backup::BackupManager;
+use crate::pool_manager::PoolManager;
+
+/// How often the scheduler looks for due jobs.
+const TICK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Runs kept per job when listing history.
+const MAX_LISTED_RUNS: u32 = 100;
+
+/// MySQL DATETIME format used for stored timestamps (UTC).
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Parameters of a `query` task.
+#[derive(Debug, Deserialize)]
+struct QueryTaskParams {
+    sql: String,
+    #[serde(default = "default_query_limit")]
+    limit: u32,
+    #[serde(default)]
+    params: Vec<serde_json::Value>,
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+}
+
+fn default_query_limit() -> u32 {
+    1000
+}
+
+/// Row from the `scheduled_jobs` metadata table.
+#[derive(sqlx::FromRow)]
+struct ScheduledJobRow {
+    id: String,
+    name: String,
+    connection_id: String,
+    cron_expr: String,
+    kind: String,
+    params: Option<String>,
+    enabled: bool,
+    next_run_at: Option<String>,
+    last_run_at: Option<String>,
+    last_status: Option<String>,
+    created_at: String,
+}
+
+impl ScheduledJobRow {
+    fn into_job(self) -> ScheduledJob {
+        ScheduledJob {
+            id: self.id,
+            name: self.name,
+            connection_id: self.connection_id,
+            cron: self.cron_expr,
+            kind: ScheduledTaskKind::parse(&self.kind).unwrap_or(ScheduledTaskKind::HealthCheck),
+            params: self
+                .params
+                .and_then(|p| serde_json::from_str(&p).ok())
+                .unwrap_or(serde_json::Value::Null),
+            enabled: self.enabled,
+            next_run_at: self.next_run_at,
+            last_run_at: self.last_run_at,
+            last_status: self.last_status.as_deref().and_then(JobRunStatus::parse),
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Row from the `scheduled_job_runs` metadata table.
+#[derive(sqlx::FromRow)]
+struct JobRunRow {
+    id: String,
+    job_id: String,
+    status: String,
+    message: Option<String>,
+    started_at: String,
+    finished_at: Option<String>,
+}
+
+impl JobRunRow {
+    fn into_run(self) -> JobRun {
+        JobRun {
+            id: self.id,
+            job_id: self.job_id,
+            status: JobRunStatus::parse(&self.status).unwrap_or(JobRunStatus::Failed),
+            message: self.message,
+            started_at: self.started_at,
+            finished_at: self.finished_at,
+        }
+    }
+}
+
+const SELECT_JOB: Statement = Statement {
+    mysql: "SELECT `id`, `name`, `connection_id`, `cron_expr`, `kind`, `params`, `enabled`, \
+         CAST(`next_run_at` AS CHAR) AS next_run_at, CAST(`last_run_at` AS CHAR) AS last_run_at, `last_status`, \
+         CAST(`created_at` AS CHAR) AS created_at FROM `scheduled_jobs`",
+    postgres: r#"SELECT "id", "name", "connection_id", "cron_expr", "kind", "params", "enabled",
+         "next_run_at", "last_run_at", "last_status",
+         "created_at" FROM "scheduled_jobs""#,
+    sqlite: r#"SELECT "id", "name", "connection_id", "cron_expr", "kind", "params", "enabled",
+         "next_run_at", "last_run_at", "last_status",
+         "created_at" FROM "scheduled_jobs""#,
+};
+
+/// Stores scheduled jobs and runs them when due.
+pub struct Scheduler {
+    pool_manager: Arc<PoolManager>,
+    backups: Arc<BackupManager>,
+}
+
+impl Scheduler {
+    /// Creates the scheduler.
+    ///
+    /// Runs left running by a previous process are marked as failed.
+    pub async fn new(pool_manager: Arc<PoolManager>, backups: Arc<BackupManager>) -> AppResult<Self> {
+        let scheduler = Self { pool_manager, backups };
+
+        let statement = scheduler.pool_manager.meta_pool().sql(&Statement {
+            mysql: "UPDATE `scheduled_job_runs` SET `status` = 'failed', `message` = 'interrupted by service restart', \
+                 `finished_at` = UTC_TIMESTAMP() WHERE `status` = 'running'",
+            postgres: r#"UPDATE "scheduled_job_runs" SET "status" = 'failed', "message" = 'interrupted by service restart',
+                 "finished_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') WHERE "status" = 'running'"#,
+            sqlite: r#"UPDATE "scheduled_job_runs" SET "status" = 'failed', "message" = 'interrupted by service restart',
+                 "finished_at" = CURRENT_TIMESTAMP WHERE "status" = 'running'"#,
+        });
+        meta_query!(scheduler.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })
+        .map_err(|e| AppError::DatabaseQuery(format!("Failed to reset running job runs: {}", e)))?;
+
+        Ok(scheduler)
+    }
+
+    /// Spawns the polling loop.
+    pub fn spawn(self: &Arc<Self>) {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = scheduler.run_due_jobs().await {
+                    tracing::warn!(error = %e, "Scheduler tick failed");
+                }
+            }
+        });
+    }
+
+    /// Creates a scheduled job.
+    pub async fn create(&self, req: CreateScheduledJobRequest) -> AppResult<ScheduledJob> {
+        let schedule = parse_cron(&req.cron)?;
+        validate_params(req.kind, &req.params)?;
+        if self.pool_manager.get_connection(&req.connection_id).await.is_none() {
+            return Err(AppError::ConnectionNotFound(req.connection_id));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let next_run_at = next_run(&schedule, Utc::now());
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `scheduled_jobs` (`id`, `name`, `connection_id`, `cron_expr`, `kind`, `params`, `enabled`, `next_run_at`) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "scheduled_jobs" ("id", "name", "connection_id", "cron_expr", "kind", "params", "enabled", "next_run_at")
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+            sqlite: r#"INSERT INTO "scheduled_jobs" ("id", "name", "connection_id", "cron_expr", "kind", "params", "enabled", "next_run_at")
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&id)
+                .bind(&req.name)
+                .bind(&req.connection_id)
+                .bind(req.cron.trim())
+                .bind(req.kind.as_str())
+                .bind(req.params.to_string())
+                .bind(req.enabled)
+                .bind(next_run_at)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+
+        tracing::info!(job_id = %id, name = %req.name, cron = %req.cron, kind = req.kind.as_str(), "Scheduled job created");
+        self.get(&id).await
+    }
+
+    /// Stores an imported job, keeping its ID; the next run is recomputed.
+    ///
+    /// Returns `false` without changes if the ID exists and `overwrite` is not set.
+    pub async fn import(&self, job: &ScheduledJob, overwrite: bool) -> AppResult<bool> {
+        let schedule = parse_cron(&job.cron)?;
+        validate_params(job.kind, &job.params)?;
+        if self.pool_manager.get_connection(&job.connection_id).await.is_none() {
+            return Err(AppError::ConnectionNotFound(job.connection_id.clone()));
+        }
+        if !overwrite && self.get(&job.id).await.is_ok() {
+            return Ok(false);
+        }
+
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `scheduled_jobs` (`id`, `name`, `connection_id`, `cron_expr`, `kind`, `params`, `enabled`, `next_run_at`) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+                 ON DUPLICATE KEY UPDATE `name` = VALUES(`name`), `connection_id` = VALUES(`connection_id`), \
+                 `cron_expr` = VALUES(`cron_expr`), `kind` = VALUES(`kind`), `params` = VALUES(`params`), \
+                 `enabled` = VALUES(`enabled`), `next_run_at` = VALUES(`next_run_at`)",
+            postgres: r#"INSERT INTO "scheduled_jobs" ("id", "name", "connection_id", "cron_expr", "kind", "params", "enabled", "next_run_at")
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 ON CONFLICT ("id") DO UPDATE SET "name" = EXCLUDED."name", "connection_id" = EXCLUDED."connection_id",
+                 "cron_expr" = EXCLUDED."cron_expr", "kind" = EXCLUDED."kind", "params" = EXCLUDED."params",
+                 "enabled" = EXCLUDED."enabled", "next_run_at" = EXCLUDED."next_run_at""#,
+            sqlite: r#"INSERT INTO "scheduled_jobs" ("id", "name", "connection_id", "cron_expr", "kind", "params", "enabled", "next_run_at")
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT ("id") DO UPDATE SET "name" = excluded."name", "connection_id" = excluded."connection_id",
+                 "cron_expr" = excluded."cron_expr", "kind" = excluded."kind", "params" = excluded."params",
+                 "enabled" = excluded."enabled", "next_run_at" = excluded."next_run_at""#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&job.id)
+                .bind(&job.name)
+                .bind(&job.connection_id)
+                .bind(job.cron.trim())
+                .bind(job.kind.as_str())
+                .bind(job.params.to_string())
+                .bind(job.enabled)
+                .bind(next_run(&schedule, Utc::now()))
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        Ok(true)
+    }
+
+    /// Lists all scheduled jobs.
+    pub async fn list(&self) -> AppResult<Vec<ScheduledJob>> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_JOB,
+            &Statement {
+                mysql: "ORDER BY `created_at` DESC",
+                postgres: r#"ORDER BY "created_at" DESC"#,
+                sqlite: r#"ORDER BY "created_at" DESC"#,
+            },
+        ]);
+        let rows: Vec<ScheduledJobRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(ScheduledJobRow::into_job).collect())
+    }
+
+    /// Gets a scheduled job by ID.
+    pub async fn get(&self, job_id: &str) -> AppResult<ScheduledJob> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_JOB,
+            &Statement {
+                mysql: "WHERE `id` = ?",
+                postgres: r#"WHERE "id" = $1"#,
+                sqlite: r#"WHERE "id" = ?"#,
+            },
+        ]);
+        let row: Option<ScheduledJobRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .bind(job_id)
+                .fetch_optional(pool)
+                .await
+        })?;
+        row.map(ScheduledJobRow::into_job)
+            .ok_or_else(|| AppError::NotFound(format!("scheduled job {}", job_id)))
+    }
+
+    /// Deletes a scheduled job and its run history.
+    pub async fn delete(&self, job_id: &str) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `scheduled_jobs` WHERE `id` = ?",
+            postgres: r#"DELETE FROM "scheduled_jobs" WHERE "id" = $1"#,
+            sqlite: r#"DELETE FROM "scheduled_jobs" WHERE "id" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(job_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result == 0 {
+            return Err(AppError::NotFound(format!("scheduled job {}", job_id)));
+        }
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `scheduled_job_runs` WHERE `job_id` = ?",
+            postgres: r#"DELETE FROM "scheduled_job_runs" WHERE "job_id" = $1"#,
+            sqlite: r#"DELETE FROM "scheduled_job_runs" WHERE "job_id" = ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(job_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        Ok(())
+    }
+
+    /// Enables or disables a job; enabling reschedules it from now.
+    pub async fn set_enabled(&self, job_id: &str, enabled: bool) -> AppResult<ScheduledJob> {
+        let job = self.get(job_id).await?;
+        let next_run_at = if enabled {
+            next_run(&parse_cron(&job.cron)?, Utc::now())
+        } else {
+            None
+        };
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "UPDATE `scheduled_jobs` SET `enabled` = ?, `next_run_at` = ? WHERE `id` = ?",
+            postgres: r#"UPDATE "scheduled_jobs" SET "enabled" = $1, "next_run_at" = $2 WHERE "id" = $3"#,
+            sqlite: r#"UPDATE "scheduled_jobs" SET "enabled" = ?, "next_run_at" = ? WHERE "id" = ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(enabled)
+                .bind(next_run_at)
+                .bind(job_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        tracing::info!(job_id, enabled, "Scheduled job toggled");
+        self.get(job_id).await
+    }
+
+    /// Runs a job immediately, outside its schedule, and returns the finished run.
+    pub async fn trigger(&self, job_id: &str) -> AppResult<JobRun> {
+        let job = self.get(job_id).await?;
+        let run_id = self.execute(&job).await?;
+        self.get_run(&run_id).await
+    }
+
+    /// Lists the most recent runs of a job.
+    pub async fn runs(&self, job_id: &str) -> AppResult<Vec<JobRun>> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `id`, `job_id`, `status`, `message`, CAST(`started_at` AS CHAR) AS started_at, \
+                 CAST(`finished_at` AS CHAR) AS finished_at FROM `scheduled_job_runs` \
+                 WHERE `job_id` = ? ORDER BY `started_at` DESC LIMIT ?",
+            postgres: r#"SELECT "id", "job_id", "status", "message", "started_at",
+                 "finished_at" FROM "scheduled_job_runs"
+                 WHERE "job_id" = $1 ORDER BY "started_at" DESC LIMIT $2"#,
+            sqlite: r#"SELECT "id", "job_id", "status", "message", "started_at",
+                 "finished_at" FROM "scheduled_job_runs"
+                 WHERE "job_id" = ? ORDER BY "started_at" DESC LIMIT ?"#,
+        });
+        let rows: Vec<JobRunRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(job_id)
+                .bind(i64::from(MAX_LISTED_RUNS))
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(JobRunRow::into_run).collect())
+    }
+
+    async fn get_run(&self, run_id: &str) -> AppResult<JobRun> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `id`, `job_id`, `status`, `message`, CAST(`started_at` AS CHAR) AS started_at, \
+                 CAST(`finished_at` AS CHAR) AS finished_at FROM `scheduled_job_runs` WHERE `id` = ?",
+            postgres: r#"SELECT "id", "job_id", "status", "message", "started_at",
+                 "finished_at" FROM "scheduled_job_runs" WHERE "id" = $1"#,
+            sqlite: r#"SELECT "id", "job_id", "status", "message", "started_at",
+                 "finished_at" FROM "scheduled_job_runs" WHERE "id" = ?"#,
+        });
+        let row: Option<JobRunRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(run_id)
+                .fetch_optional(pool)
+                .await
+        })?;
+        row.map(JobRunRow::into_run)
+            .ok_or_else(|| AppError::NotFound(format!("job run {}", run_id)))
+    }
+
+    /// Claims and starts every enabled job whose `next_run_at` has passed.
+    async fn run_due_jobs(self: &Arc<Self>) -> AppResult<()> {
+        let now = Utc::now();
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_JOB,
+            &Statement {
+                mysql: "WHERE `enabled` = TRUE AND `next_run_at` <= ?",
+                postgres: r#"WHERE "enabled" = TRUE AND "next_run_at" <= $1"#,
+                sqlite: r#"WHERE "enabled" = TRUE AND "next_run_at" <= ?"#,
+            },
+        ]);
+        let due: Vec<ScheduledJobRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .bind(format_datetime(now))
+                .fetch_all(pool)
+                .await
+        })?;
+
+        for row in due {
+            let job = row.into_job();
+            let next = match parse_cron(&job.cron) {
+                Ok(schedule) => next_run(&schedule, now),
+                Err(e) => {
+                    tracing::warn!(job_id = %job.id, error = %e, "Disabling job with invalid cron expression");
+                    None
+                }
+            };
+
+            // Only the instance that moves next_run_at forward runs this tick.
+            let statement = self.pool_manager.meta_pool().sql(&Statement {
+                mysql: "UPDATE `scheduled_jobs` SET `enabled` = ?, `next_run_at` = ? WHERE `id` = ? AND `next_run_at` = ?",
+                postgres: r#"UPDATE "scheduled_jobs" SET "enabled" = $1, "next_run_at" = $2 WHERE "id" = $3 AND "next_run_at" = $4"#,
+                sqlite: r#"UPDATE "scheduled_jobs" SET "enabled" = ?, "next_run_at" = ? WHERE "id" = ? AND "next_run_at" = ?"#,
+            });
+            let claimed = meta_query!(self.pool_manager.meta_pool(), |pool| {
+                sqlx::query(statement)
+                    .bind(next.is_some())
+                    .bind(&next)
+                    .bind(&job.id)
+                    .bind(&job.next_run_at)
+                    .execute(pool)
+                    .await
+                    .map(|r| r.rows_affected())
+            })? == 1;
+            if !claimed {
+                continue;
+            }
+
+            let scheduler = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = scheduler.execute(&job).await {
+                    tracing::error!(job_id = %job.id, error = %e, "Failed to record scheduled job run");
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Executes a job's task and records the run; returns the run ID.
+    async fn execute(&self, job: &ScheduledJob) -> AppResult<String> {
+        let run_id = Uuid::new_v4().to_string();
+        let started_at = format_datetime(Utc::now());
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `scheduled_job_runs` (`id`, `job_id`, `status`, `started_at`) VALUES (?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "scheduled_job_runs" ("id", "job_id", "status", "started_at") VALUES ($1, $2, $3, $4)"#,
+            sqlite: r#"INSERT INTO "scheduled_job_runs" ("id", "job_id", "status", "started_at") VALUES (?, ?, ?, ?)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&run_id)
+                .bind(&job.id)
+                .bind(JobRunStatus::Running.as_str())
+                .bind(&started_at)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+
+        tracing::info!(job_id = %job.id, run_id = %run_id, kind = job.kind.as_str(), "Scheduled job started");
+        let (status, message) = match self.run_task(job).await {
+            Ok(message) => (JobRunStatus::Succeeded, message),
+            Err(e) => {
+                tracing::warn!(job_id = %job.id, run_id = %run_id, error = %e, "Scheduled job failed");
+                (JobRunStatus::Failed, e.to_string())
+            }
+        };
+
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "UPDATE `scheduled_job_runs` SET `status` = ?, `message` = ?, `finished_at` = ? WHERE `id` = ?",
+            postgres: r#"UPDATE "scheduled_job_runs" SET "status" = $1, "message" = $2, "finished_at" = $3 WHERE "id" = $4"#,
+            sqlite: r#"UPDATE "scheduled_job_runs" SET "status" = ?, "message" = ?, "finished_at" = ? WHERE "id" = ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(status.as_str())
+                .bind(&message)
+                .bind(format_datetime(Utc::now()))
+                .bind(&run_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "UPDATE `scheduled_jobs` SET `last_run_at` = ?, `last_status` = ? WHERE `id` = ?",
+            postgres: r#"UPDATE "scheduled_jobs" SET "last_run_at" = $1, "last_status" = $2 WHERE "id" = $3"#,
+            sqlite: r#"UPDATE "scheduled_jobs" SET "last_run_at" = ?, "last_status" = ? WHERE "id" = ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&started_at)
+                .bind(status.as_str())
+                .bind(&job.id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+
+        Ok(run_id)
+    }
+
+    /// Runs the task itself and returns a result summary.
+    async fn run_task(&self, job: &ScheduledJob) -> AppResult<String> {
+        match job.kind {
+            ScheduledTaskKind::HealthCheck => {
+                let latency = self.pool_manager.test_connection(&job.connection_id).await?;
+                Ok(format!("connection ok ({} ms)", latency.as_millis()))
+            }
+            ScheduledTaskKind::Backup => {
+                let req: CreateBackupRequest = serde_json::from_value(params_or_empty(&job.params))
+                    .map_err(|e| AppError::InvalidInput(format!("invalid backup params: {}", e)))?;
+                let backup = self.backups.start(&job.connection_id, req).await?;
+                Ok(format!("backup {} started", backup.id))
+            }
+            ScheduledTaskKind::Query => {
+                let params: QueryTaskParams = serde_json::from_value(job.params.clone())
+                    .map_err(|e| AppError::InvalidInput(format!("invalid query params: {}", e)))?;
+                SqlValidator::validate(&params.sql)?;
+                self.pool_manager.get_or_create_pool(&job.connection_id).await?;
+                let config = self
+                    .pool_manager
+                    .get_connection(&job.connection_id)
+                    .await
+                    .ok_or_else(|| AppError::ConnectionNotFound(job.connection_id.clone()))?;
+                let timeout = self.pool_manager.query_timeout(&config, params.timeout_ms);
+                let result = self
+                    .pool_manager
+                    .execute_query(&job.connection_id, None, &params.sql, params.limit, &params.params, Some(timeout))
+                    .await?;
+                Ok(format!(
+                    "{} rows in {} ms",
+                    result.row_count, result.execution_time_ms
+                ))
+            }
+        }
+    }
+}
+
+/// Parses a cron expression, accepting the common 5-field form by prepending a seconds field.
+pub(crate) fn parse_cron(expr: &str) -> AppResult<Schedule> {
+    let expr = expr.trim();
+    let normalized = if expr.split_whitespace().count() == 5 {
+        format!("0 {}", expr)
+    } else {
+        expr.to_string()
+    };
+    Schedule::from_str(&normalized)
+        .map_err(|e| AppError::InvalidInput(format!("invalid cron expression `{}`: {}", expr, e)))
+}
+
+/// Next fire time after `after`, formatted for the metadata database.
+pub(crate) fn next_run(schedule: &Schedule, after: DateTime<Utc>) -> Option<String> {
+    schedule.after(&after).next().map(format_datetime)
+}
+
+pub(crate) fn format_datetime(dt: DateTime<Utc>) -> String {
+    dt.format(DATETIME_FORMAT).to_string()
+}
+
+/// Checks task parameters when a job is created.
+fn validate_params(kind: ScheduledTaskKind, params: &serde_json::Value) -> AppResult<()> {
+    match kind {
+        ScheduledTaskKind::HealthCheck => Ok(()),
+        ScheduledTaskKind::Backup => serde_json::from_value::<CreateBackupRequest>(params_or_empty(params))
+            .map(|_| ())
+            .map_err(|e| AppError::InvalidInput(format!("invalid backup params: {}", e))),
+        ScheduledTaskKind::Query => {
+            let params: QueryTaskParams = serde_json::from_value(params.clone())
+                .map_err(|e| AppError::InvalidInput(format!("invalid query params: {}", e)))?;
+            SqlValidator::validate(&params.sql)
+        }
+    }
+}
+
+/// Treats missing parameters as an empty object, so all-default requests deserialize.
+fn params_or_empty(params: &serde_json::Value) -> serde_json::Value {
+    if params.is_null() {
+        serde_json::json!({})
+    } else {
+        params.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
+        NaiveDateTime::parse_from_str(s, DATETIME_FORMAT)
+            .ok()
+            .map(|dt| dt.and_utc())
+    }
+
+    #[test]
+    fn accepts_five_field_cron_expressions() {
+        let schedule = parse_cron("30 2 * * *").unwrap();
+        let after = parse_datetime("2024-01-01 03:00:00").unwrap();
+        assert_eq!(next_run(&schedule, after).as_deref(), Some("2024-01-02 02:30:00"));
+        assert!(parse_cron("not a cron").is_err());
+    }
+
+    #[test]
+    fn rejects_destructive_scheduled_queries() {
+        let params = serde_json::json!({ "sql": "DROP TABLE users" });
+        assert!(validate_params(ScheduledTaskKind::Query, &params).is_err());
+        assert!(validate_params(ScheduledTaskKind::Backup, &serde_json::Value::Null).is_ok());
+    }
+}
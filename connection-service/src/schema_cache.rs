@@ -0,0 +1,209 @@
+//! Redis-backed schema metadata cache.
+//!
+//! Caches the [`TableSchema`] of each connection in Redis with a TTL. Entries
+//! are dropped explicitly (invalidation endpoint, DDL run through this
+//! service) or when a cheap catalog fingerprint no longer matches, which
+//! catches DDL executed outside the service. The fingerprint is re-checked at
+//! most once per check interval per connection.
+//!
+//! Configuration:
+//! - `SCHEMA_CACHE_REDIS_URL` (falls back to `REDIS_URL`) - cache is disabled if unset
+//! - `SCHEMA_CACHE_TTL_SECS` - entry TTL (default: 600)
+//! - `SCHEMA_CACHE_CHECK_INTERVAL_SECS` - fingerprint re-check interval (default: 30)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use tokio::sync::RwLock;
+
+use common::errors::{AppError, AppResult};
+use common::models::database::TableSchema;
+use dbm_core::DatabasePool;
+use crate::pool_manager::PoolManager;
+
+const KEY_PREFIX: &str = "dbm:schema:";
+const DEFAULT_TTL_SECS: u64 = 600;
+const DEFAULT_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Cached entry stored in Redis.
+#[derive(Serialize, Deserialize)]
+struct CachedSchema {
+    fingerprint: String,
+    schema: TableSchema,
+}
+
+/// Schema metadata cache shared by handlers and DDL-running jobs.
+pub struct SchemaCache {
+    pool_manager: Arc<PoolManager>,
+    redis: Option<ConnectionManager>,
+    ttl: Duration,
+    check_interval: Duration,
+    /// Last fingerprint verification per connection.
+    checked_at: RwLock<HashMap<String, Instant>>,
+}
+
+impl SchemaCache {
+    /// Creates the cache; without a reachable Redis it passes every request through.
+    pub async fn new(pool_manager: Arc<PoolManager>) -> Self {
+        let env_secs = |key: &str, default: u64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        let url = std::env::var("SCHEMA_CACHE_REDIS_URL")
+            .or_else(|_| std::env::var("REDIS_URL"))
+            .ok()
+            .filter(|u| !u.is_empty());
+        let redis = match url {
+            Some(url) => match connect(&url).await {
+                Ok(manager) => {
+                    tracing::info!("Schema cache enabled (Redis)");
+                    Some(manager)
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Schema cache disabled: Redis unavailable");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        Self {
+            pool_manager,
+            redis,
+            ttl: Duration::from_secs(env_secs("SCHEMA_CACHE_TTL_SECS", DEFAULT_TTL_SECS)),
+            check_interval: Duration::from_secs(env_secs(
+                "SCHEMA_CACHE_CHECK_INTERVAL_SECS",
+                DEFAULT_CHECK_INTERVAL_SECS,
+            )),
+            checked_at: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the schema of a connection, from the cache when it is still current.
+    pub async fn get_table_schema(&self, connection_id: &str, refresh: bool) -> AppResult<TableSchema> {
+        let Some(redis) = &self.redis else {
+            return self.pool_manager.get_table_schema(connection_id).await;
+        };
+        let mut redis = redis.clone();
+        let key = cache_key(connection_id);
+
+        if !refresh {
+            match redis.get::<_, Option<String>>(&key).await {
+                Ok(Some(raw)) => {
+                    if let Ok(cached) = serde_json::from_str::<CachedSchema>(&raw) {
+                        if self.is_current(connection_id, &cached.fingerprint).await? {
+                            return Ok(cached.schema);
+                        }
+                        tracing::info!(connection_id, "Schema change detected, reloading schema cache");
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!(connection_id, error = %e, "Schema cache read failed"),
+            }
+        }
+
+        let fingerprint = self.fingerprint(connection_id).await?;
+        let schema = self.pool_manager.get_table_schema(connection_id).await?;
+        let entry = serde_json::to_string(&CachedSchema {
+            fingerprint,
+            schema: schema.clone(),
+        })
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+        if let Err(e) = redis.set_ex::<_, _, ()>(&key, entry, self.ttl.as_secs()).await {
+            tracing::warn!(connection_id, error = %e, "Schema cache write failed");
+        }
+        self.checked_at
+            .write()
+            .await
+            .insert(connection_id.to_string(), Instant::now());
+        Ok(schema)
+    }
+
+    /// Drops the cached schema of a connection.
+    pub async fn invalidate(&self, connection_id: &str) {
+        self.checked_at.write().await.remove(connection_id);
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        let mut redis = redis.clone();
+        match redis.del::<_, ()>(cache_key(connection_id)).await {
+            Ok(()) => tracing::info!(connection_id, "Schema cache invalidated"),
+            Err(e) => tracing::warn!(connection_id, error = %e, "Schema cache invalidation failed"),
+        }
+    }
+
+    /// Verifies the fingerprint if the check interval elapsed since the last check.
+    async fn is_current(&self, connection_id: &str, fingerprint: &str) -> AppResult<bool> {
+        let recently_checked = self
+            .checked_at
+            .read()
+            .await
+            .get(connection_id)
+            .is_some_and(|t| t.elapsed() < self.check_interval);
+        if recently_checked {
+            return Ok(true);
+        }
+
+        let current = self.fingerprint(connection_id).await?;
+        if current == fingerprint {
+            self.checked_at
+                .write()
+                .await
+                .insert(connection_id.to_string(), Instant::now());
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Single-row catalog checksum that changes with any column-level DDL.
+    async fn fingerprint(&self, connection_id: &str) -> AppResult<String> {
+        let config = self
+            .pool_manager
+            .get_connection(connection_id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+
+        let (count, checksum): (i64, i64) = match self.pool_manager.get_or_create_pool(connection_id).await? {
+            DatabasePool::MySQL(pool) => {
+                let row = sqlx::query(
+                    "SELECT COUNT(*) AS cnt, CAST(COALESCE(SUM(CRC32(CONCAT_WS('|', TABLE_NAME, COLUMN_NAME, \
+                     COLUMN_TYPE, IS_NULLABLE, COLUMN_KEY))), 0) AS SIGNED) AS checksum \
+                     FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = ?",
+                )
+                .bind(config.database.unwrap_or_default())
+                .fetch_one(&pool)
+                .await?;
+                (row.try_get("cnt")?, row.try_get("checksum")?)
+            }
+            DatabasePool::Postgres(pool) => {
+                let row = sqlx::query(
+                    "SELECT COUNT(*) AS cnt, COALESCE(SUM(hashtext(concat_ws('|', table_name, column_name, \
+                     data_type, is_nullable))), 0)::bigint AS checksum \
+                     FROM information_schema.columns WHERE table_schema = 'public'",
+                )
+                .fetch_one(&pool)
+                .await?;
+                (row.try_get("cnt")?, row.try_get("checksum")?)
+            }
+            _ => (0, 0),
+        };
+        Ok(format!("{}:{}", count, checksum))
+    }
+}
+
+async fn connect(url: &str) -> redis::RedisResult<ConnectionManager> {
+    let client = redis::Client::open(url)?;
+    ConnectionManager::new(client).await
+}
+
+fn cache_key(connection_id: &str) -> String {
+    format!("{}{}", KEY_PREFIX, connection_id)
+}
@@ -0,0 +1,702 @@
+//! Online schema change (OSC) for MySQL.
+//!
+//! Applies ALTER TABLE to large tables without holding a long table lock:
+//! 1. Create a shadow table (`_<table>_gho`) and apply the ALTER to it
+//! 2. Install triggers on the original table that replay writes into the shadow table
+//! 3. Copy existing rows in primary-key chunks (`INSERT IGNORE`, so trigger writes win)
+//! 4. Cutover: atomically `RENAME TABLE` original -> `_<table>_del`, shadow -> original
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use sqlx::{MySqlPool, Row};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::DbType;
+use common::models::schema_change::{
+    SchemaChangeJob, SchemaChangeRequest, SchemaChangeStatus, SchemaChangeStrategy,
+};
+use dbm_core::drivers::mysql::row_string;
+use crate::pool_manager::PoolManager;
+use crate::schema_cache::SchemaCache;
+
+/// Default rows copied per chunk.
+const DEFAULT_CHUNK_SIZE: u32 = 1000;
+
+/// MySQL identifier length limit.
+const MAX_IDENTIFIER_LEN: usize = 64;
+
+/// Tracked job together with its cancellation flag.
+struct JobEntry {
+    job: SchemaChangeJob,
+    cancelled: Arc<AtomicBool>,
+    drop_old_table: bool,
+}
+
+/// Runs and tracks schema change jobs.
+pub struct SchemaChangeManager {
+    pool_manager: Arc<PoolManager>,
+    schema_cache: Arc<SchemaCache>,
+    jobs: RwLock<HashMap<String, JobEntry>>,
+}
+
+impl SchemaChangeManager {
+    /// Creates a new schema change manager.
+    pub fn new(pool_manager: Arc<PoolManager>, schema_cache: Arc<SchemaCache>) -> Self {
+        Self {
+            pool_manager,
+            schema_cache,
+            jobs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Starts a schema change job on a MySQL connection.
+    ///
+    /// The direct strategy runs synchronously; the online strategy returns
+    /// immediately and copies rows in the background.
+    pub async fn start(
+        self: &Arc<Self>,
+        connection_id: &str,
+        req: SchemaChangeRequest,
+    ) -> AppResult<SchemaChangeJob> {
+        let config = self
+            .pool_manager
+            .get_connection(connection_id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+        let pool = self.pool_manager.get_mysql_pool(connection_id).await?;
+
+        let database = req
+            .database
+            .clone()
+            .or(config.database)
+            .filter(|d| !d.is_empty())
+            .ok_or_else(|| AppError::InvalidInput("database is required".into()))?;
+        validate_identifier(&database)?;
+        validate_identifier(&req.table)?;
+        validate_identifier(&shadow_table_name(&req.table))?;
+        validate_identifier(&trigger_name(&req.table, "ins"))?;
+
+        let alter = req.alter.trim().trim_end_matches(';').trim().to_string();
+        if alter.contains(';') {
+            return Err(AppError::InvalidInput(
+                "alter clause must be a single statement".into(),
+            ));
+        }
+
+        {
+            let jobs = self.jobs.read().await;
+            let busy = jobs.values().any(|e| {
+                e.job.connection_id == connection_id
+                    && e.job.database == database
+                    && e.job.table == req.table
+                    && !e.job.status.is_finished()
+            });
+            if busy {
+                return Err(AppError::Conflict(format!(
+                    "a schema change is already running on {}.{}",
+                    database, req.table
+                )));
+            }
+        }
+
+        let now = Utc::now().to_rfc3339();
+        let job = SchemaChangeJob {
+            id: Uuid::new_v4().to_string(),
+            connection_id: connection_id.to_string(),
+            database: database.clone(),
+            table: req.table.clone(),
+            alter: alter.clone(),
+            strategy: req.strategy,
+            status: SchemaChangeStatus::Pending,
+            rows_copied: 0,
+            rows_total: 0,
+            progress: 0.0,
+            shadow_table: None,
+            old_table: None,
+            error: None,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.jobs.write().await.insert(
+            job.id.clone(),
+            JobEntry {
+                job: job.clone(),
+                cancelled: cancelled.clone(),
+                drop_old_table: req.drop_old_table,
+            },
+        );
+
+        tracing::info!(
+            job_id = %job.id,
+            connection_id = %connection_id,
+            table = %format!("{}.{}", database, req.table),
+            strategy = ?req.strategy,
+            "Schema change started"
+        );
+
+        match req.strategy {
+            SchemaChangeStrategy::Direct => {
+                let sql = format!(
+                    "ALTER TABLE {}.{} {}",
+                    quote_ident(&database),
+                    quote_ident(&req.table),
+                    alter
+                );
+                match sqlx::raw_sql(&sql).execute(&pool).await {
+                    Ok(_) => {
+                        self.update(&job.id, |j| {
+                            j.status = SchemaChangeStatus::Completed;
+                            j.progress = 100.0;
+                        })
+                        .await;
+                        self.schema_cache.invalidate(connection_id).await;
+                    }
+                    Err(e) => {
+                        self.fail(&job.id, e.to_string()).await;
+                    }
+                }
+            }
+            SchemaChangeStrategy::Online => {
+                let manager = Arc::clone(self);
+                let job_id = job.id.clone();
+                let chunk_size = req.chunk_size.filter(|n| *n > 0).unwrap_or(DEFAULT_CHUNK_SIZE);
+                let chunk_sleep = Duration::from_millis(req.chunk_sleep_ms.unwrap_or(0));
+                let auto_cutover = req.auto_cutover;
+                tokio::spawn(async move {
+                    let result = manager
+                        .run_online(&pool, &job_id, chunk_size, chunk_sleep, &cancelled)
+                        .await;
+                    match result {
+                        Ok(()) if cancelled.load(Ordering::SeqCst) => {
+                            manager.abort(&pool, &job_id).await;
+                        }
+                        Ok(()) if auto_cutover => {
+                            if let Err(e) = manager.cutover(&job_id).await {
+                                tracing::error!(job_id = %job_id, error = %e, "Automatic cutover failed");
+                            }
+                        }
+                        Ok(()) => {}
+                        Err(e) => {
+                            manager.fail(&job_id, e.to_string()).await;
+                            manager.cleanup(&pool, &job_id).await;
+                        }
+                    }
+                });
+            }
+        }
+
+        self.get(&job.id).await
+    }
+
+    /// Lists schema change jobs of a connection (newest first).
+    pub async fn list(&self, connection_id: &str) -> Vec<SchemaChangeJob> {
+        let mut jobs: Vec<SchemaChangeJob> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|e| e.job.connection_id == connection_id)
+            .map(|e| e.job.clone())
+            .collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        jobs
+    }
+
+    /// Gets a schema change job by ID.
+    pub async fn get(&self, job_id: &str) -> AppResult<SchemaChangeJob> {
+        self.jobs
+            .read()
+            .await
+            .get(job_id)
+            .map(|e| e.job.clone())
+            .ok_or_else(|| AppError::NotFound(format!("schema change job {}", job_id)))
+    }
+
+    /// Swaps the shadow table in place of the original table.
+    ///
+    /// Only valid once the copy has finished (`ready_for_cutover`).
+    pub async fn cutover(&self, job_id: &str) -> AppResult<SchemaChangeJob> {
+        let (job, drop_old_table) = {
+            let mut jobs = self.jobs.write().await;
+            let entry = jobs
+                .get_mut(job_id)
+                .ok_or_else(|| AppError::NotFound(format!("schema change job {}", job_id)))?;
+            if entry.job.status != SchemaChangeStatus::ReadyForCutover {
+                return Err(AppError::Conflict(format!(
+                    "job is {:?}, cutover requires ready_for_cutover",
+                    entry.job.status
+                )));
+            }
+            entry.job.status = SchemaChangeStatus::CuttingOver;
+            entry.job.updated_at = Utc::now().to_rfc3339();
+            (entry.job.clone(), entry.drop_old_table)
+        };
+
+        let pool = self.pool_manager.get_mysql_pool(&job.connection_id).await?;
+        let db = quote_ident(&job.database);
+        let table = quote_ident(&job.table);
+        let shadow = quote_ident(&shadow_table_name(&job.table));
+        let old_name = old_table_name(&job.table);
+        let old = quote_ident(&old_name);
+
+        // Atomic swap: the triggers stay attached to the renamed original table,
+        // so application writes go to the new table from this point on.
+        let rename = format!(
+            "RENAME TABLE {db}.{table} TO {db}.{old}, {db}.{shadow} TO {db}.{table}"
+        );
+        if let Err(e) = sqlx::raw_sql(&rename).execute(&pool).await {
+            self.update(job_id, |j| {
+                j.status = SchemaChangeStatus::ReadyForCutover;
+                j.error = Some(format!("cutover failed: {}", e));
+            })
+            .await;
+            return Err(AppError::DatabaseQuery(e.to_string()));
+        }
+
+        drop_triggers(&pool, &job.database, &job.table).await;
+        if drop_old_table {
+            let drop = format!("DROP TABLE IF EXISTS {db}.{old}");
+            if let Err(e) = sqlx::raw_sql(&drop).execute(&pool).await {
+                tracing::warn!(job_id = %job_id, error = %e, "Failed to drop old table after cutover");
+            }
+        }
+
+        self.update(job_id, |j| {
+            j.status = SchemaChangeStatus::Completed;
+            j.progress = 100.0;
+            j.error = None;
+            j.old_table = if drop_old_table { None } else { Some(old_name) };
+        })
+        .await;
+        self.schema_cache.invalidate(&job.connection_id).await;
+        tracing::info!(job_id = %job_id, "Schema change cutover completed");
+
+        self.get(job_id).await
+    }
+
+    /// Cancels a running job and removes the shadow table and triggers.
+    pub async fn cancel(&self, job_id: &str) -> AppResult<SchemaChangeJob> {
+        let (job, cancelled) = {
+            let jobs = self.jobs.read().await;
+            let entry = jobs
+                .get(job_id)
+                .ok_or_else(|| AppError::NotFound(format!("schema change job {}", job_id)))?;
+            (entry.job.clone(), entry.cancelled.clone())
+        };
+
+        match job.status {
+            SchemaChangeStatus::Pending | SchemaChangeStatus::Copying => {
+                // The copy loop notices the flag and cleans up itself.
+                cancelled.store(true, Ordering::SeqCst);
+            }
+            SchemaChangeStatus::ReadyForCutover => {
+                let pool = self.pool_manager.get_mysql_pool(&job.connection_id).await?;
+                self.abort(&pool, job_id).await;
+            }
+            status => {
+                return Err(AppError::Conflict(format!(
+                    "job is {:?} and can no longer be cancelled",
+                    status
+                )));
+            }
+        }
+
+        self.get(job_id).await
+    }
+
+    // ============== Online strategy ==============
+
+    async fn run_online(
+        &self,
+        pool: &MySqlPool,
+        job_id: &str,
+        chunk_size: u32,
+        chunk_sleep: Duration,
+        cancelled: &AtomicBool,
+    ) -> AppResult<()> {
+        let job = self.get(job_id).await?;
+        let database = job.database.as_str();
+        let table = job.table.as_str();
+        let shadow_name = shadow_table_name(table);
+
+        let pk = primary_key_column(pool, database, table).await?;
+
+        let db = quote_ident(database);
+        let original = format!("{}.{}", db, quote_ident(table));
+        let shadow = format!("{}.{}", db, quote_ident(&shadow_name));
+
+        // 1. Shadow table with the new definition
+        sqlx::raw_sql(&format!("DROP TABLE IF EXISTS {}", shadow))
+            .execute(pool)
+            .await?;
+        sqlx::raw_sql(&format!("CREATE TABLE {} LIKE {}", shadow, original))
+            .execute(pool)
+            .await?;
+        sqlx::raw_sql(&format!("ALTER TABLE {} {}", shadow, job.alter))
+            .execute(pool)
+            .await?;
+
+        let old_columns = table_columns(pool, database, table).await?;
+        let new_columns = table_columns(pool, database, &shadow_name).await?;
+        let columns: Vec<String> = old_columns
+            .into_iter()
+            .filter(|c| new_columns.contains(c))
+            .collect();
+        if !columns.iter().any(|c| c == &pk) {
+            return Err(AppError::InvalidInput(format!(
+                "primary key column `{}` must survive the schema change",
+                pk
+            )));
+        }
+
+        self.update(job_id, |j| {
+            j.status = SchemaChangeStatus::Copying;
+            j.shadow_table = Some(shadow_name.clone());
+        })
+        .await;
+
+        // 2. Triggers keep the shadow table in sync while copying
+        for sql in trigger_statements(database, table, &shadow_name, &pk, &columns) {
+            sqlx::raw_sql(&sql).execute(pool).await?;
+        }
+
+        // 3. Chunked copy by primary key
+        let estimate: Option<u64> = sqlx::query(
+            "SELECT CAST(COALESCE(TABLE_ROWS, 0) AS UNSIGNED) AS cnt
+             FROM information_schema.TABLES WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?",
+        )
+        .bind(database)
+        .bind(table)
+        .fetch_optional(pool)
+        .await?
+        .and_then(|r| r.try_get::<u64, _>("cnt").ok());
+        self.update(job_id, |j| j.rows_total = estimate.unwrap_or(0)).await;
+
+        let pk_ident = quote_ident(&pk);
+        let bounds = sqlx::query(&format!(
+            "SELECT CAST(MIN({pk}) AS SIGNED) AS lo, CAST(MAX({pk}) AS SIGNED) AS hi FROM {original}",
+            pk = pk_ident
+        ))
+        .fetch_one(pool)
+        .await?;
+        let min: Option<i64> = bounds.try_get("lo").unwrap_or(None);
+        let max: Option<i64> = bounds.try_get("hi").unwrap_or(None);
+
+        if let (Some(min), Some(max)) = (min, max) {
+            let column_list = columns
+                .iter()
+                .map(|c| quote_ident(c))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let next_bound_sql = format!(
+                "SELECT CAST({pk} AS SIGNED) AS bound FROM {original} WHERE {pk} > ? ORDER BY {pk} LIMIT 1 OFFSET {offset}",
+                pk = pk_ident,
+                offset = chunk_size - 1
+            );
+            let copy_sql = format!(
+                "INSERT IGNORE INTO {shadow} ({cols}) SELECT {cols} FROM {original} \
+                 WHERE {pk} > ? AND {pk} <= ? LOCK IN SHARE MODE",
+                cols = column_list,
+                pk = pk_ident
+            );
+
+            let mut last = min - 1;
+            while last < max {
+                if cancelled.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
+
+                let upper = sqlx::query(&next_bound_sql)
+                    .bind(last)
+                    .fetch_optional(pool)
+                    .await?
+                    .and_then(|r| r.try_get::<i64, _>("bound").ok())
+                    .unwrap_or(max)
+                    .min(max);
+
+                let copied = sqlx::query(&copy_sql)
+                    .bind(last)
+                    .bind(upper)
+                    .execute(pool)
+                    .await?
+                    .rows_affected();
+                last = upper;
+
+                self.update(job_id, |j| {
+                    j.rows_copied += copied;
+                    j.rows_total = j.rows_total.max(j.rows_copied);
+                    j.progress = copy_progress(j.rows_copied, j.rows_total);
+                })
+                .await;
+
+                if !chunk_sleep.is_zero() {
+                    tokio::time::sleep(chunk_sleep).await;
+                }
+            }
+        }
+
+        if cancelled.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        self.update(job_id, |j| {
+            j.status = SchemaChangeStatus::ReadyForCutover;
+            j.progress = 100.0;
+        })
+        .await;
+        tracing::info!(job_id = %job_id, "Schema change copy finished, ready for cutover");
+        Ok(())
+    }
+
+    /// Drops triggers and the shadow table and marks the job cancelled.
+    async fn abort(&self, pool: &MySqlPool, job_id: &str) {
+        self.cleanup(pool, job_id).await;
+        self.update(job_id, |j| j.status = SchemaChangeStatus::Cancelled)
+            .await;
+        tracing::info!(job_id = %job_id, "Schema change cancelled");
+    }
+
+    /// Drops triggers and the shadow table of a job (best-effort).
+    async fn cleanup(&self, pool: &MySqlPool, job_id: &str) {
+        let Ok(job) = self.get(job_id).await else {
+            return;
+        };
+        drop_triggers(pool, &job.database, &job.table).await;
+        let drop = format!(
+            "DROP TABLE IF EXISTS {}.{}",
+            quote_ident(&job.database),
+            quote_ident(&shadow_table_name(&job.table))
+        );
+        if let Err(e) = sqlx::raw_sql(&drop).execute(pool).await {
+            tracing::warn!(job_id = %job_id, error = %e, "Failed to drop shadow table");
+        }
+    }
+
+    async fn fail(&self, job_id: &str, error: String) {
+        tracing::error!(job_id = %job_id, error = %error, "Schema change failed");
+        self.update(job_id, |j| {
+            j.status = SchemaChangeStatus::Failed;
+            j.error = Some(error);
+        })
+        .await;
+    }
+
+    async fn update(&self, job_id: &str, f: impl FnOnce(&mut SchemaChangeJob)) {
+        if let Some(entry) = self.jobs.write().await.get_mut(job_id) {
+            f(&mut entry.job);
+            entry.job.updated_at = Utc::now().to_rfc3339();
+        }
+    }
+}
+
+// ============== Helpers ==============
+
+/// Finds the single integer primary key column used for chunking.
+async fn primary_key_column(pool: &MySqlPool, database: &str, table: &str) -> AppResult<String> {
+    let rows = sqlx::query(
+        "SELECT k.COLUMN_NAME, c.DATA_TYPE
+         FROM information_schema.KEY_COLUMN_USAGE k
+         JOIN information_schema.COLUMNS c
+           ON c.TABLE_SCHEMA = k.TABLE_SCHEMA AND c.TABLE_NAME = k.TABLE_NAME AND c.COLUMN_NAME = k.COLUMN_NAME
+         WHERE k.TABLE_SCHEMA = ? AND k.TABLE_NAME = ? AND k.CONSTRAINT_NAME = 'PRIMARY'
+         ORDER BY k.ORDINAL_POSITION",
+    )
+    .bind(database)
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+
+    match rows.as_slice() {
+        [] => Err(AppError::InvalidInput(format!(
+            "table {}.{} has no primary key; online schema change requires one",
+            database, table
+        ))),
+        [row] => {
+            let column = row_string(row, "COLUMN_NAME");
+            let data_type = row_string(row, "DATA_TYPE").to_lowercase();
+            if !matches!(
+                data_type.as_str(),
+                "tinyint" | "smallint" | "mediumint" | "int" | "bigint"
+            ) {
+                return Err(AppError::InvalidInput(format!(
+                    "primary key `{}` is {}; online schema change requires an integer key",
+                    column, data_type
+                )));
+            }
+            Ok(column)
+        }
+        _ => Err(AppError::InvalidInput(
+            "composite primary keys are not supported by online schema change".into(),
+        )),
+    }
+}
+
+/// Lists column names of a table in ordinal order.
+async fn table_columns(pool: &MySqlPool, database: &str, table: &str) -> AppResult<Vec<String>> {
+    let rows = sqlx::query(
+        "SELECT COLUMN_NAME FROM information_schema.COLUMNS
+         WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?
+         ORDER BY ORDINAL_POSITION",
+    )
+    .bind(database)
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| row_string(r, "COLUMN_NAME"))
+        .collect())
+}
+
+/// Drops the sync triggers of a table (best-effort).
+async fn drop_triggers(pool: &MySqlPool, database: &str, table: &str) {
+    for suffix in ["ins", "upd", "del"] {
+        let sql = format!(
+            "DROP TRIGGER IF EXISTS {}.{}",
+            quote_ident(database),
+            quote_ident(&trigger_name(table, suffix))
+        );
+        if let Err(e) = sqlx::raw_sql(&sql).execute(pool).await {
+            tracing::warn!(table = %table, error = %e, "Failed to drop schema change trigger");
+        }
+    }
+}
+
+/// Builds the INSERT/UPDATE/DELETE triggers that mirror writes into the shadow table.
+fn trigger_statements(
+    database: &str,
+    table: &str,
+    shadow: &str,
+    pk: &str,
+    columns: &[String],
+) -> Vec<String> {
+    let db = quote_ident(database);
+    let original = format!("{}.{}", db, quote_ident(table));
+    let shadow = format!("{}.{}", db, quote_ident(shadow));
+    let pk = quote_ident(pk);
+    let column_list = columns
+        .iter()
+        .map(|c| quote_ident(c))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let new_values = columns
+        .iter()
+        .map(|c| format!("NEW.{}", quote_ident(c)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let replace = format!(
+        "REPLACE INTO {} ({}) VALUES ({})",
+        shadow, column_list, new_values
+    );
+
+    vec![
+        format!(
+            "CREATE TRIGGER {}.{} AFTER INSERT ON {} FOR EACH ROW {}",
+            db,
+            quote_ident(&trigger_name(table, "ins")),
+            original,
+            replace
+        ),
+        format!(
+            "CREATE TRIGGER {}.{} AFTER UPDATE ON {} FOR EACH ROW BEGIN \
+             DELETE IGNORE FROM {} WHERE {} = OLD.{}; {}; END",
+            db,
+            quote_ident(&trigger_name(table, "upd")),
+            original,
+            shadow,
+            pk,
+            pk,
+            replace
+        ),
+        format!(
+            "CREATE TRIGGER {}.{} AFTER DELETE ON {} FOR EACH ROW \
+             DELETE IGNORE FROM {} WHERE {} = OLD.{}",
+            db,
+            quote_ident(&trigger_name(table, "del")),
+            original,
+            shadow,
+            pk,
+            pk
+        ),
+    ]
+}
+
+fn shadow_table_name(table: &str) -> String {
+    format!("_{}_gho", table)
+}
+
+fn old_table_name(table: &str) -> String {
+    format!("_{}_del", table)
+}
+
+fn trigger_name(table: &str, suffix: &str) -> String {
+    format!("_{}_osc_{}", table, suffix)
+}
+
+/// Quotes a MySQL identifier with backticks.
+fn quote_ident(name: &str) -> String {
+    common::utils::quote_ident(&DbType::MySQL, name)
+}
+
+/// Accepts plain identifiers only (letters, digits, `_`, `$`), up to 64 characters.
+fn validate_identifier(name: &str) -> AppResult<()> {
+    if name.is_empty() || name.len() > MAX_IDENTIFIER_LEN {
+        return Err(AppError::InvalidInput(format!(
+            "identifier `{}` must be 1-{} characters",
+            name, MAX_IDENTIFIER_LEN
+        )));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+        return Err(AppError::InvalidInput(format!(
+            "identifier `{}` contains unsupported characters",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Copy progress, capped below 100 until the copy loop finishes.
+fn copy_progress(copied: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    ((copied as f64 / total as f64) * 100.0).min(99.9)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_identifier_with_backtick() {
+        assert!(validate_identifier("users").is_ok());
+        assert!(validate_identifier("us`ers").is_err());
+        assert!(validate_identifier(&"a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn builds_sync_triggers_for_shadow_table() {
+        let columns = vec!["id".to_string(), "name".to_string()];
+        let sql = trigger_statements("shop", "users", "_users_gho", "id", &columns);
+        assert_eq!(sql.len(), 3);
+        assert!(sql[0].contains("AFTER INSERT ON `shop`.`users`"));
+        assert!(sql[0].contains("REPLACE INTO `shop`.`_users_gho` (`id`, `name`) VALUES (NEW.`id`, NEW.`name`)"));
+        assert!(sql[1].contains("DELETE IGNORE FROM `shop`.`_users_gho` WHERE `id` = OLD.`id`"));
+        assert!(sql[2].contains("AFTER DELETE"));
+    }
+
+    #[test]
+    fn progress_is_capped_until_done() {
+        assert_eq!(copy_progress(0, 0), 0.0);
+        assert_eq!(copy_progress(50, 100), 50.0);
+        assert_eq!(copy_progress(150, 100), 99.9);
+    }
+}
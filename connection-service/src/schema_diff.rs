@@ -0,0 +1,493 @@
+//! Schema comparison.
+//!
+//! Compares two schema snapshots (see [`crate::introspection`]) and optionally
+//! generates the migration SQL that brings the target in line with the source.
+//! Each side only shows the tables its connection's allowlist makes visible.
+
+use std::collections::HashMap;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::{ConnectionAllowlist, DbType};
+use common::models::schema_diff::{
+    ColumnChange, ColumnDef, ForeignKeyDef, IndexDef, SchemaDiff, SchemaDiffRequest, SchemaRef,
+    TableDef, TableDiff,
+};
+use common::utils::quote_ident;
+use crate::introspection;
+use crate::pool_manager::PoolManager;
+
+/// Compares the source and target schemas of a request.
+pub async fn compare(pool_manager: &PoolManager, req: &SchemaDiffRequest) -> AppResult<SchemaDiff> {
+    let (source, _) = load_side(pool_manager, &req.source).await?;
+    let (target, target_type) = load_side(pool_manager, &req.target).await?;
+
+    let mut diff = diff_schemas(&source, &target);
+    if req.generate_sql {
+        let dialect = Dialect::from_db_type(&target_type)?;
+        diff.migration_sql = Some(migration_sql(&diff, dialect));
+    }
+    Ok(diff)
+}
+
+/// Loads the snapshot of one side of the comparison, limited to the tables
+/// the connection's allowlist makes visible.
+///
+/// # Errors
+/// Returns `AppError::Forbidden` if the allowlist hides the compared database / schema.
+async fn load_side(pool_manager: &PoolManager, side: &SchemaRef) -> AppResult<(Vec<TableDef>, DbType)> {
+    let config = pool_manager
+        .get_connection(&side.connection_id)
+        .await
+        .ok_or_else(|| AppError::ConnectionNotFound(side.connection_id.clone()))?;
+
+    let schema = introspection::resolve_schema(&config, side.database.as_deref())?;
+    if config.allowlist.as_ref().is_some_and(|a| !a.allows_database(&schema)) {
+        return Err(AppError::Forbidden(format!(
+            "database {} is not in the connection allowlist",
+            schema
+        )));
+    }
+
+    let pool = pool_manager.get_or_create_pool(&side.connection_id).await?;
+    let mut tables = introspection::load_schema(&pool, &schema).await?;
+    if let Some(allowlist) = &config.allowlist {
+        visible_tables(&mut tables, allowlist, &schema);
+    }
+    Ok((tables, config.db_type))
+}
+
+/// Drops the tables of `schema` the allowlist hides, and foreign keys referencing them.
+fn visible_tables(tables: &mut Vec<TableDef>, allowlist: &ConnectionAllowlist, schema: &str) {
+    tables.retain(|t| allowlist.allows_table(Some(schema), &t.name));
+    for table in tables.iter_mut() {
+        table
+            .foreign_keys
+            .retain(|f| allowlist.allows_table(Some(schema), &f.referenced_table));
+    }
+}
+
+/// Computes the structural differences between two snapshots.
+pub fn diff_schemas(source: &[TableDef], target: &[TableDef]) -> SchemaDiff {
+    let target_by_name: HashMap<&str, &TableDef> =
+        target.iter().map(|t| (t.name.as_str(), t)).collect();
+    let source_by_name: HashMap<&str, &TableDef> =
+        source.iter().map(|t| (t.name.as_str(), t)).collect();
+
+    let mut tables_added = Vec::new();
+    let mut tables_changed = Vec::new();
+    for table in source {
+        match target_by_name.get(table.name.as_str()) {
+            None => tables_added.push(table.clone()),
+            Some(other) => {
+                let diff = diff_table(table, other);
+                if !diff.is_empty() {
+                    tables_changed.push(diff);
+                }
+            }
+        }
+    }
+
+    let tables_removed: Vec<TableDef> = target
+        .iter()
+        .filter(|t| !source_by_name.contains_key(t.name.as_str()))
+        .cloned()
+        .collect();
+
+    SchemaDiff {
+        identical: tables_added.is_empty() && tables_removed.is_empty() && tables_changed.is_empty(),
+        tables_added,
+        tables_removed,
+        tables_changed,
+        migration_sql: None,
+    }
+}
+
+fn diff_table(source: &TableDef, target: &TableDef) -> TableDiff {
+    let mut diff = TableDiff {
+        table: source.name.clone(),
+        ..Default::default()
+    };
+
+    for col in &source.columns {
+        match target.columns.iter().find(|c| c.name == col.name) {
+            None => diff.columns_added.push(col.clone()),
+            Some(other) if !same_column(col, other) => diff.columns_changed.push(ColumnChange {
+                name: col.name.clone(),
+                source: col.clone(),
+                target: other.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    diff.columns_removed = target
+        .columns
+        .iter()
+        .filter(|c| !source.columns.iter().any(|s| s.name == c.name))
+        .cloned()
+        .collect();
+
+    // Redefined indexes/keys show up as removed + added.
+    diff.indexes_added = source
+        .indexes
+        .iter()
+        .filter(|i| !target.indexes.contains(i))
+        .cloned()
+        .collect();
+    diff.indexes_removed = target
+        .indexes
+        .iter()
+        .filter(|i| !source.indexes.contains(i))
+        .cloned()
+        .collect();
+    diff.foreign_keys_added = source
+        .foreign_keys
+        .iter()
+        .filter(|f| !target.foreign_keys.contains(f))
+        .cloned()
+        .collect();
+    diff.foreign_keys_removed = target
+        .foreign_keys
+        .iter()
+        .filter(|f| !source.foreign_keys.contains(f))
+        .cloned()
+        .collect();
+
+    diff
+}
+
+fn same_column(a: &ColumnDef, b: &ColumnDef) -> bool {
+    a.data_type.eq_ignore_ascii_case(&b.data_type)
+        && a.nullable == b.nullable
+        && a.default == b.default
+        && a.extra == b.extra
+}
+
+// ============== Migration SQL ==============
+
+/// SQL dialect of the migration target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    MySql,
+    Postgres,
+}
+
+impl Dialect {
+    fn from_db_type(db_type: &DbType) -> AppResult<Self> {
+        match db_type {
+            DbType::MySQL | DbType::MariaDB => Ok(Dialect::MySql),
+            DbType::Postgres => Ok(Dialect::Postgres),
+            other => Err(AppError::UnsupportedDatabaseType(format!(
+                "migration SQL generation is not supported for {}",
+                other
+            ))),
+        }
+    }
+
+    pub(crate) fn quote(&self, ident: &str) -> String {
+        match self {
+            Dialect::MySql => quote_ident(&DbType::MySQL, ident),
+            Dialect::Postgres => quote_ident(&DbType::Postgres, ident),
+        }
+    }
+
+    fn quote_list(&self, idents: &[String]) -> String {
+        idents
+            .iter()
+            .map(|i| self.quote(i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn column_def(&self, col: &ColumnDef) -> String {
+        let mut def = format!("{} {}", self.quote(&col.name), col.data_type);
+        if !col.nullable {
+            def.push_str(" NOT NULL");
+        }
+        if let Some(default) = &col.default {
+            def.push_str(&format!(" DEFAULT {}", default));
+        }
+        if *self == Dialect::MySql {
+            if let Some(extra) = &col.extra {
+                if extra.contains("auto_increment") {
+                    def.push_str(" AUTO_INCREMENT");
+                }
+            }
+        }
+        def
+    }
+
+    pub(crate) fn create_table(&self, table: &TableDef) -> Vec<String> {
+        let mut parts: Vec<String> = table.columns.iter().map(|c| self.column_def(c)).collect();
+        let mut extra = Vec::new();
+        for index in &table.indexes {
+            if index.primary {
+                parts.push(format!("PRIMARY KEY ({})", self.quote_list(&index.columns)));
+            } else if *self == Dialect::MySql {
+                parts.push(format!(
+                    "{}KEY {} ({})",
+                    if index.unique { "UNIQUE " } else { "" },
+                    self.quote(&index.name),
+                    self.quote_list(&index.columns)
+                ));
+            } else {
+                extra.push(self.create_index(&table.name, index));
+            }
+        }
+        let mut statements = vec![format!(
+            "CREATE TABLE {} (\n  {}\n)",
+            self.quote(&table.name),
+            parts.join(",\n  ")
+        )];
+        statements.extend(extra);
+        statements
+    }
+
+    fn create_index(&self, table: &str, index: &IndexDef) -> String {
+        if index.primary {
+            return match self {
+                Dialect::MySql => format!(
+                    "ALTER TABLE {} ADD PRIMARY KEY ({})",
+                    self.quote(table),
+                    self.quote_list(&index.columns)
+                ),
+                Dialect::Postgres => format!(
+                    "ALTER TABLE {} ADD CONSTRAINT {} PRIMARY KEY ({})",
+                    self.quote(table),
+                    self.quote(&index.name),
+                    self.quote_list(&index.columns)
+                ),
+            };
+        }
+        format!(
+            "CREATE {}INDEX {} ON {} ({})",
+            if index.unique { "UNIQUE " } else { "" },
+            self.quote(&index.name),
+            self.quote(table),
+            self.quote_list(&index.columns)
+        )
+    }
+
+    fn drop_index(&self, table: &str, index: &IndexDef) -> String {
+        match (self, index.primary) {
+            (Dialect::MySql, true) => format!("ALTER TABLE {} DROP PRIMARY KEY", self.quote(table)),
+            (Dialect::MySql, false) => format!(
+                "DROP INDEX {} ON {}",
+                self.quote(&index.name),
+                self.quote(table)
+            ),
+            (Dialect::Postgres, true) => format!(
+                "ALTER TABLE {} DROP CONSTRAINT {}",
+                self.quote(table),
+                self.quote(&index.name)
+            ),
+            (Dialect::Postgres, false) => format!("DROP INDEX {}", self.quote(&index.name)),
+        }
+    }
+
+    pub(crate) fn add_foreign_key(&self, table: &str, fk: &ForeignKeyDef) -> String {
+        format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+            self.quote(table),
+            self.quote(&fk.name),
+            self.quote_list(&fk.columns),
+            self.quote(&fk.referenced_table),
+            self.quote_list(&fk.referenced_columns)
+        )
+    }
+
+    fn drop_foreign_key(&self, table: &str, fk: &ForeignKeyDef) -> String {
+        match self {
+            Dialect::MySql => format!(
+                "ALTER TABLE {} DROP FOREIGN KEY {}",
+                self.quote(table),
+                self.quote(&fk.name)
+            ),
+            Dialect::Postgres => format!(
+                "ALTER TABLE {} DROP CONSTRAINT {}",
+                self.quote(table),
+                self.quote(&fk.name)
+            ),
+        }
+    }
+
+    fn alter_column(&self, table: &str, change: &ColumnChange) -> Vec<String> {
+        let t = self.quote(table);
+        match self {
+            Dialect::MySql => vec![format!(
+                "ALTER TABLE {} MODIFY COLUMN {}",
+                t,
+                self.column_def(&change.source)
+            )],
+            Dialect::Postgres => {
+                let c = self.quote(&change.name);
+                let (src, dst) = (&change.source, &change.target);
+                let mut statements = Vec::new();
+                if !src.data_type.eq_ignore_ascii_case(&dst.data_type) {
+                    statements.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{}",
+                        t, c, src.data_type, c, src.data_type
+                    ));
+                }
+                if src.nullable != dst.nullable {
+                    statements.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} {} NOT NULL",
+                        t,
+                        c,
+                        if src.nullable { "DROP" } else { "SET" }
+                    ));
+                }
+                if src.default != dst.default {
+                    statements.push(match &src.default {
+                        Some(d) => format!("ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {}", t, c, d),
+                        None => format!("ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT", t, c),
+                    });
+                }
+                statements
+            }
+        }
+    }
+}
+
+/// Generates migration statements that apply the diff to the target.
+///
+/// Order: drop stale foreign keys, create tables, alter columns/indexes,
+/// add foreign keys, then drop removed tables.
+pub fn migration_sql(diff: &SchemaDiff, dialect: Dialect) -> Vec<String> {
+    let mut statements = Vec::new();
+
+    for table in &diff.tables_changed {
+        for fk in &table.foreign_keys_removed {
+            statements.push(dialect.drop_foreign_key(&table.table, fk));
+        }
+    }
+
+    for table in &diff.tables_added {
+        statements.extend(dialect.create_table(table));
+    }
+
+    for table in &diff.tables_changed {
+        let t = dialect.quote(&table.table);
+        for index in &table.indexes_removed {
+            statements.push(dialect.drop_index(&table.table, index));
+        }
+        for col in &table.columns_added {
+            statements.push(format!("ALTER TABLE {} ADD COLUMN {}", t, dialect.column_def(col)));
+        }
+        for change in &table.columns_changed {
+            statements.extend(dialect.alter_column(&table.table, change));
+        }
+        for col in &table.columns_removed {
+            statements.push(format!("ALTER TABLE {} DROP COLUMN {}", t, dialect.quote(&col.name)));
+        }
+        for index in &table.indexes_added {
+            statements.push(dialect.create_index(&table.table, index));
+        }
+    }
+
+    for table in &diff.tables_added {
+        for fk in &table.foreign_keys {
+            statements.push(dialect.add_foreign_key(&table.name, fk));
+        }
+    }
+    for table in &diff.tables_changed {
+        for fk in &table.foreign_keys_added {
+            statements.push(dialect.add_foreign_key(&table.table, fk));
+        }
+    }
+
+    for table in &diff.tables_removed {
+        statements.push(format!("DROP TABLE {}", dialect.quote(&table.name)));
+    }
+
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str, nullable: bool) -> ColumnDef {
+        ColumnDef {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable,
+            default: None,
+            extra: None,
+        }
+    }
+
+    fn users(columns: Vec<ColumnDef>) -> TableDef {
+        TableDef {
+            name: "users".to_string(),
+            columns,
+            indexes: vec![IndexDef {
+                name: "PRIMARY".to_string(),
+                columns: vec!["id".to_string()],
+                unique: true,
+                primary: true,
+            }],
+            foreign_keys: vec![],
+        }
+    }
+
+    #[test]
+    fn identical_schemas_have_no_diff() {
+        let table = users(vec![column("id", "int", false)]);
+        let diff = diff_schemas(std::slice::from_ref(&table), std::slice::from_ref(&table));
+        assert!(diff.identical);
+        assert!(migration_sql(&diff, Dialect::MySql).is_empty());
+    }
+
+    #[test]
+    fn detects_added_changed_and_removed_columns() {
+        let source = users(vec![column("id", "bigint", false), column("email", "varchar(255)", false)]);
+        let target = users(vec![column("id", "int", false), column("legacy", "text", true)]);
+        let diff = diff_schemas(&[source], &[target]);
+
+        assert!(!diff.identical);
+        let table = &diff.tables_changed[0];
+        assert_eq!(table.columns_added[0].name, "email");
+        assert_eq!(table.columns_removed[0].name, "legacy");
+        assert_eq!(table.columns_changed[0].name, "id");
+
+        let sql = migration_sql(&diff, Dialect::MySql);
+        assert!(sql.contains(&"ALTER TABLE `users` ADD COLUMN `email` varchar(255) NOT NULL".to_string()));
+        assert!(sql.contains(&"ALTER TABLE `users` MODIFY COLUMN `id` bigint NOT NULL".to_string()));
+        assert!(sql.contains(&"ALTER TABLE `users` DROP COLUMN `legacy`".to_string()));
+    }
+
+    #[test]
+    fn creates_missing_tables_before_dropping_extra_ones() {
+        let source = users(vec![column("id", "integer", false)]);
+        let mut orders = users(vec![column("id", "integer", false)]);
+        orders.name = "orders".to_string();
+        let diff = diff_schemas(&[source], &[orders]);
+
+        let sql = migration_sql(&diff, Dialect::Postgres);
+        assert!(sql[0].starts_with("CREATE TABLE \"users\""));
+        assert!(sql[0].contains("PRIMARY KEY (\"id\")"));
+        assert_eq!(sql.last().unwrap(), "DROP TABLE \"orders\"");
+    }
+
+    #[test]
+    fn hides_tables_outside_the_allowlist() {
+        let mut orders = users(vec![column("id", "int", false)]);
+        orders.name = "orders".to_string();
+        orders.foreign_keys.push(ForeignKeyDef {
+            name: "fk_user".to_string(),
+            columns: vec!["user_id".to_string()],
+            referenced_table: "users".to_string(),
+            referenced_columns: vec!["id".to_string()],
+        });
+        let mut tables = vec![users(vec![column("id", "int", false)]), orders];
+        let allowlist = ConnectionAllowlist {
+            tables: vec!["shop.orders".to_string()],
+            ..Default::default()
+        };
+        visible_tables(&mut tables, &allowlist, "shop");
+
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "orders");
+        assert!(tables[0].foreign_keys.is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+//! Foreign key relationship graph.
+//!
+//! Builds a [`SchemaGraph`] of a MySQL database or PostgreSQL schema: tables
+//! with their columns come from the schema snapshot (see
+//! [`crate::introspection`]), foreign keys with their referential actions
+//! from `information_schema.REFERENTIAL_CONSTRAINTS` / `pg_constraint`.
+
+use std::collections::HashSet;
+
+use sqlx::{MySqlPool, PgPool, Row};
+
+use common::errors::{AppError, AppResult};
+use common::models::schema_graph::{GraphColumn, GraphEdge, GraphNode, SchemaGraph};
+use dbm_core::DatabasePool;
+use dbm_core::drivers::mysql::{row_opt_string, row_string};
+use crate::introspection;
+use crate::pool_manager::PoolManager;
+
+/// Loads the foreign key graph; `database` defaults to the connection's database / `public`.
+pub async fn load_graph(pool_manager: &PoolManager, connection_id: &str, database: Option<&str>) -> AppResult<SchemaGraph> {
+    let config = pool_manager
+        .get_connection(connection_id)
+        .await
+        .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+    let schema = introspection::resolve_schema(&config, database)?;
+
+    let pool = pool_manager.get_or_create_pool(connection_id).await?;
+    let edges = match &pool {
+        DatabasePool::MySQL(p) => mysql_edges(p, &schema).await?,
+        DatabasePool::Postgres(p) => postgres_edges(p, &schema).await?,
+        _ => {
+            return Err(AppError::UnsupportedDatabaseType(
+                "Relationship graphs are only supported for MySQL and PostgreSQL".into(),
+            ))
+        }
+    };
+    let tables = introspection::load_schema(&pool, &schema).await?;
+
+    let fk_columns: HashSet<(&str, &str)> = edges
+        .iter()
+        .flat_map(|e| e.from_columns.iter().map(move |c| (e.from_table.as_str(), c.as_str())))
+        .collect();
+    let nodes = tables
+        .iter()
+        .map(|table| {
+            let primary: Vec<&str> = table
+                .indexes
+                .iter()
+                .filter(|i| i.primary)
+                .flat_map(|i| i.columns.iter().map(String::as_str))
+                .collect();
+            GraphNode {
+                table: table.name.clone(),
+                columns: table
+                    .columns
+                    .iter()
+                    .map(|c| GraphColumn {
+                        name: c.name.clone(),
+                        data_type: c.data_type.clone(),
+                        nullable: c.nullable,
+                        primary_key: primary.contains(&c.name.as_str()),
+                        foreign_key: fk_columns.contains(&(table.name.as_str(), c.name.as_str())),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Ok(SchemaGraph { database: schema, nodes, edges })
+}
+
+/// Appends one key column to the edge of its constraint, creating the edge on first sight.
+fn add_edge_column(edges: &mut Vec<GraphEdge>, schema: &str, edge: GraphEdge) {
+    match edges.last_mut() {
+        Some(last) if last.name == edge.name && last.from_table == edge.from_table => {
+            last.from_columns.extend(edge.from_columns);
+            last.to_columns.extend(edge.to_columns);
+        }
+        _ => edges.push(GraphEdge {
+            to_database: edge.to_database.filter(|d| d != schema),
+            ..edge
+        }),
+    }
+}
+
+async fn mysql_edges(pool: &MySqlPool, database: &str) -> AppResult<Vec<GraphEdge>> {
+    let rows = sqlx::query(
+        "SELECT k.TABLE_NAME, k.CONSTRAINT_NAME, k.COLUMN_NAME, k.REFERENCED_TABLE_SCHEMA,
+                k.REFERENCED_TABLE_NAME, k.REFERENCED_COLUMN_NAME, r.DELETE_RULE, r.UPDATE_RULE
+         FROM information_schema.KEY_COLUMN_USAGE k
+         JOIN information_schema.REFERENTIAL_CONSTRAINTS r
+           ON r.CONSTRAINT_SCHEMA = k.CONSTRAINT_SCHEMA AND r.TABLE_NAME = k.TABLE_NAME
+          AND r.CONSTRAINT_NAME = k.CONSTRAINT_NAME
+         WHERE k.TABLE_SCHEMA = ? AND k.REFERENCED_TABLE_NAME IS NOT NULL
+         ORDER BY k.TABLE_NAME, k.CONSTRAINT_NAME, k.ORDINAL_POSITION",
+    )
+    .bind(database)
+    .fetch_all(pool)
+    .await?;
+
+    let mut edges = Vec::new();
+    for row in &rows {
+        let edge = GraphEdge {
+            name: row_string(row, "CONSTRAINT_NAME"),
+            from_table: row_string(row, "TABLE_NAME"),
+            from_columns: vec![row_string(row, "COLUMN_NAME")],
+            to_table: row_string(row, "REFERENCED_TABLE_NAME"),
+            to_database: row_opt_string(row, "REFERENCED_TABLE_SCHEMA"),
+            to_columns: vec![row_string(row, "REFERENCED_COLUMN_NAME")],
+            on_delete: row_string(row, "DELETE_RULE"),
+            on_update: row_string(row, "UPDATE_RULE"),
+        };
+        add_edge_column(&mut edges, database, edge);
+    }
+    Ok(edges)
+}
+
+async fn postgres_edges(pool: &PgPool, schema: &str) -> AppResult<Vec<GraphEdge>> {
+    let rows = sqlx::query(
+        "SELECT src.relname AS table_name, con.conname AS constraint_name, a.attname AS column_name,
+                tn.nspname AS referenced_schema, tgt.relname AS referenced_table, af.attname AS referenced_column,
+                CASE con.confdeltype WHEN 'c' THEN 'CASCADE' WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT'
+                     WHEN 'r' THEN 'RESTRICT' ELSE 'NO ACTION' END AS on_delete,
+                CASE con.confupdtype WHEN 'c' THEN 'CASCADE' WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT'
+                     WHEN 'r' THEN 'RESTRICT' ELSE 'NO ACTION' END AS on_update
+         FROM pg_constraint con
+         JOIN pg_class src ON src.oid = con.conrelid
+         JOIN pg_namespace n ON n.oid = src.relnamespace
+         JOIN pg_class tgt ON tgt.oid = con.confrelid
+         JOIN pg_namespace tn ON tn.oid = tgt.relnamespace
+         JOIN LATERAL unnest(con.conkey, con.confkey) WITH ORDINALITY AS k(attnum, fattnum, ord) ON true
+         JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+         JOIN pg_attribute af ON af.attrelid = con.confrelid AND af.attnum = k.fattnum
+         WHERE con.contype = 'f' AND n.nspname = $1
+         ORDER BY src.relname, con.conname, k.ord",
+    )
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    let mut edges = Vec::new();
+    for row in &rows {
+        let edge = GraphEdge {
+            name: row.try_get("constraint_name").unwrap_or_default(),
+            from_table: row.try_get("table_name").unwrap_or_default(),
+            from_columns: vec![row.try_get("column_name").unwrap_or_default()],
+            to_table: row.try_get("referenced_table").unwrap_or_default(),
+            to_database: row.try_get("referenced_schema").ok(),
+            to_columns: vec![row.try_get("referenced_column").unwrap_or_default()],
+            on_delete: row.try_get("on_delete").unwrap_or_default(),
+            on_update: row.try_get("on_update").unwrap_or_default(),
+        };
+        add_edge_column(&mut edges, schema, edge);
+    }
+    Ok(edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, table: &str, from: &str, to_database: &str, to: &str) -> GraphEdge {
+        GraphEdge {
+            name: name.into(),
+            from_table: table.into(),
+            from_columns: vec![from.into()],
+            to_table: "orders".into(),
+            to_database: Some(to_database.into()),
+            to_columns: vec![to.into()],
+            on_delete: "CASCADE".into(),
+            on_update: "NO ACTION".into(),
+        }
+    }
+
+    #[test]
+    fn composite_keys_become_one_edge() {
+        let mut edges = Vec::new();
+        add_edge_column(&mut edges, "shop", column("fk_item_order", "items", "order_id", "shop", "id"));
+        add_edge_column(&mut edges, "shop", column("fk_item_order", "items", "order_rev", "shop", "rev"));
+        add_edge_column(&mut edges, "shop", column("fk_audit_order", "audit", "order_id", "archive", "id"));
+
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0].from_columns, ["order_id", "order_rev"]);
+        assert_eq!(edges[0].to_columns, ["id", "rev"]);
+        assert_eq!(edges[0].to_database, None);
+        assert_eq!(edges[1].to_database.as_deref(), Some("archive"));
+    }
+}
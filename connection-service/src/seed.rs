@@ -0,0 +1,804 @@
+//! Seed data generation.
+//!
+//! Generates realistic fake rows for one table from its introspected columns:
+//! - values respect the column type: text length, integer range, decimal
+//!   precision and scale, enum members
+//! - text columns are filled by name (`email`, `first_name`, `city`, ...)
+//! - foreign keys reuse values that exist in the referenced table
+//! - single-column unique keys get distinct values (integers continue after the current maximum)
+//! - auto-increment, identity and serial columns are left to the database
+//!
+//! Generation is deterministic for a given seed, so a dry run previews exactly
+//! the rows a later run with the same seed inserts.
+
+use std::time::Instant;
+
+use base64::Engine as _;
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde_json::{json, Value};
+use sqlx::{Column, Row, TypeInfo};
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::{ConnectionConfig, DbType};
+use common::models::query::ValueKind;
+use common::models::schema_diff::{ColumnDef, ForeignKeyDef};
+use common::models::seed::{SeedColumn, SeedRequest, SeedResult};
+use common::utils::quote_ident;
+use dbm_core::DatabasePool;
+use dbm_core::type_mapping;
+use crate::introspection;
+use crate::pool_manager::PoolManager;
+use crate::transfer::{self, TableColumn};
+
+/// Default rows inserted per transaction.
+const DEFAULT_BATCH_SIZE: u32 = 500;
+
+/// Rows returned in the preview.
+const PREVIEW_ROWS: usize = 10;
+
+/// Share of NULLs in nullable columns.
+const NULL_RATE: f64 = 0.05;
+
+/// Distinct referenced values sampled per foreign key.
+const REFERENCE_SAMPLE: u32 = 1000;
+
+const FIRST_NAMES: &[&str] = &[
+    "James", "Mary", "John", "Patricia", "Robert", "Jennifer", "Michael", "Linda", "David", "Elizabeth",
+    "William", "Susan", "Richard", "Jessica", "Joseph", "Sarah", "Thomas", "Karen", "Wei", "Mei",
+];
+const LAST_NAMES: &[&str] = &[
+    "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis", "Martinez", "Lopez",
+    "Wilson", "Anderson", "Taylor", "Thomas", "Moore", "Jackson", "Martin", "Lee", "Wang", "Chen",
+];
+const CITIES: &[&str] = &[
+    "New York", "London", "Paris", "Berlin", "Tokyo", "Shanghai", "Sydney", "Toronto", "Madrid", "Seoul",
+    "Amsterdam", "Singapore", "Chicago", "Vienna", "Dublin",
+];
+const COUNTRIES: &[&str] = &[
+    "United States", "United Kingdom", "France", "Germany", "Japan", "China", "Australia", "Canada", "Spain",
+    "South Korea", "Netherlands", "Singapore", "Austria", "Ireland",
+];
+const STREETS: &[&str] = &["Main", "Oak", "Pine", "Maple", "Cedar", "Elm", "Washington", "Lake", "Hill", "Park"];
+const COMPANY_SUFFIXES: &[&str] = &["Inc", "LLC", "Group", "Labs", "Systems", "Partners", "Holdings"];
+const DOMAINS: &[&str] = &["example.com", "example.org", "example.net", "mail.test"];
+const WORDS: &[&str] = &[
+    "alpha", "bright", "cloud", "delta", "echo", "fast", "green", "harbor", "island", "jade", "kite", "lunar",
+    "maple", "north", "ocean", "prime", "quiet", "river", "solar", "timber", "ultra", "vivid", "willow", "zen",
+];
+
+/// SplitMix64; small, seedable and good enough for fake data.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform integer in `lo..=hi`.
+    fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        if hi <= lo {
+            return lo;
+        }
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        (lo as i128 + (self.next_u64() as u128 % span) as i128) as i64
+    }
+
+    /// Uniform float in `0..1`.
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.range(0, items.len() as i64 - 1) as usize]
+    }
+}
+
+/// Kind of text generated for a column, chosen by its name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextKind {
+    Email,
+    FirstName,
+    LastName,
+    FullName,
+    Username,
+    Phone,
+    City,
+    Country,
+    Address,
+    Url,
+    Company,
+    Uuid,
+    Title,
+    Sentence,
+    Word,
+}
+
+impl TextKind {
+    fn for_column(name: &str) -> Self {
+        let n = name.to_lowercase();
+        let has = |parts: &[&str]| parts.iter().any(|p| n.contains(p));
+        if has(&["email", "e_mail"]) {
+            TextKind::Email
+        } else if has(&["first_name", "firstname", "given_name"]) {
+            TextKind::FirstName
+        } else if has(&["last_name", "lastname", "surname", "family_name"]) {
+            TextKind::LastName
+        } else if has(&["username", "user_name", "login", "nickname"]) {
+            TextKind::Username
+        } else if has(&["phone", "mobile", "fax"]) {
+            TextKind::Phone
+        } else if has(&["city"]) {
+            TextKind::City
+        } else if has(&["country"]) {
+            TextKind::Country
+        } else if has(&["address", "street"]) {
+            TextKind::Address
+        } else if has(&["url", "website", "homepage", "link"]) {
+            TextKind::Url
+        } else if has(&["company", "organization", "organisation", "employer"]) {
+            TextKind::Company
+        } else if has(&["uuid", "guid"]) {
+            TextKind::Uuid
+        } else if n == "name" || has(&["full_name", "fullname", "display_name", "contact_name", "customer_name"]) {
+            TextKind::FullName
+        } else if has(&["title", "subject", "headline"]) || n.ends_with("_name") {
+            TextKind::Title
+        } else if has(&["description", "comment", "note", "content", "body", "bio", "summary", "remark", "message"]) {
+            TextKind::Sentence
+        } else {
+            TextKind::Word
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            TextKind::Email => "email",
+            TextKind::FirstName => "first_name",
+            TextKind::LastName => "last_name",
+            TextKind::FullName => "full_name",
+            TextKind::Username => "username",
+            TextKind::Phone => "phone",
+            TextKind::City => "city",
+            TextKind::Country => "country",
+            TextKind::Address => "address",
+            TextKind::Url => "url",
+            TextKind::Company => "company",
+            TextKind::Uuid => "uuid",
+            TextKind::Title => "title",
+            TextKind::Sentence => "sentence",
+            TextKind::Word => "word",
+        }
+    }
+}
+
+/// How the values of one column are generated.
+#[derive(Debug)]
+enum Generator {
+    Null,
+    Reference { label: String, kind: ValueKind, values: Vec<Value> },
+    Choice(Vec<String>),
+    Boolean,
+    Integer { min: i64, max: i64 },
+    Sequence(i64),
+    Decimal { max: i64, scale: u32 },
+    Float { max: f64 },
+    Date { years_back: (i64, i64) },
+    Time,
+    DateTime { utc: bool },
+    Json,
+    Uuid,
+    Binary(usize),
+    /// WKT `POINT`, or the `(x,y)` literal of the PostgreSQL `point` type.
+    Point { wkt: bool },
+    /// Text; unique columns append `-<nonce><row>`.
+    Text { kind: TextKind, max_len: Option<usize>, nonce: Option<String> },
+}
+
+impl Generator {
+    fn describe(&self) -> String {
+        match self {
+            Generator::Null => "null".to_string(),
+            Generator::Reference { label, .. } => format!("reference({})", label),
+            Generator::Choice(values) => format!("choice({})", values.join("|")),
+            Generator::Boolean => "boolean".to_string(),
+            Generator::Integer { min, max } => format!("integer({}..{})", min, max),
+            Generator::Sequence(start) => format!("sequence(from {})", start),
+            Generator::Decimal { max, scale } => format!("decimal(0..{}, scale {})", max, scale),
+            Generator::Float { max } => format!("float(0..{})", max),
+            Generator::Date { .. } => "date".to_string(),
+            Generator::Time => "time".to_string(),
+            Generator::DateTime { utc: false } => "datetime".to_string(),
+            Generator::DateTime { utc: true } => "timestamp".to_string(),
+            Generator::Json => "json".to_string(),
+            Generator::Uuid => "uuid".to_string(),
+            Generator::Binary(len) => format!("binary({})", len),
+            Generator::Point { .. } => "point".to_string(),
+            Generator::Text { kind, nonce, .. } => match nonce {
+                Some(_) => format!("{} (unique)", kind.as_str()),
+                None => kind.as_str().to_string(),
+            },
+        }
+    }
+
+    /// Generates the value for row `row` with the kind it is expressed in.
+    fn generate(&self, rng: &mut Rng, row: u64, now: NaiveDateTime, kind: ValueKind) -> (Value, ValueKind) {
+        let value = match self {
+            Generator::Null => Value::Null,
+            Generator::Reference { kind, values, .. } => {
+                let value = values[rng.range(0, values.len() as i64 - 1) as usize].clone();
+                return (value, *kind);
+            }
+            Generator::Choice(values) => Value::String(values[rng.range(0, values.len() as i64 - 1) as usize].clone()),
+            Generator::Boolean => Value::Bool(rng.range(0, 1) == 1),
+            Generator::Integer { min, max } => Value::from(rng.range(*min, *max)),
+            Generator::Sequence(start) => Value::from(start.saturating_add(row as i64)),
+            Generator::Decimal { max, scale } => {
+                let int = rng.range(0, *max);
+                match *scale {
+                    0 => Value::String(int.to_string()),
+                    scale => {
+                        let digits = scale.min(6);
+                        let frac = rng.range(0, 10i64.pow(digits) - 1);
+                        let padding = "0".repeat((scale - digits) as usize);
+                        Value::String(format!("{}.{:0width$}{}", int, frac, padding, width = digits as usize))
+                    }
+                }
+            }
+            Generator::Float { max } => json!((rng.unit() * max * 100.0).round() / 100.0),
+            Generator::Date { years_back: (lo, hi) } => {
+                let days = rng.range(lo * 365, hi * 365);
+                Value::String((now - Duration::days(days)).format("%Y-%m-%d").to_string())
+            }
+            Generator::Time => Value::String(format!(
+                "{:02}:{:02}:{:02}",
+                rng.range(0, 23),
+                rng.range(0, 59),
+                rng.range(0, 59)
+            )),
+            Generator::DateTime { utc } => {
+                let at = now - Duration::seconds(rng.range(0, 3 * 365 * 86400));
+                let format = if *utc { "%Y-%m-%dT%H:%M:%SZ" } else { "%Y-%m-%dT%H:%M:%S" };
+                Value::String(at.format(format).to_string())
+            }
+            Generator::Json => json!({ "id": rng.range(1, 1000), "tag": rng.pick(WORDS) }),
+            Generator::Uuid => Value::String(random_uuid(rng)),
+            Generator::Binary(len) => {
+                let bytes: Vec<u8> = (0..*len).map(|_| rng.next_u64() as u8).collect();
+                Value::String(base64::engine::general_purpose::STANDARD.encode(bytes))
+            }
+            Generator::Point { wkt } => {
+                let lon = rng.range(-1_800_000, 1_800_000) as f64 / 10_000.0;
+                let lat = rng.range(-900_000, 900_000) as f64 / 10_000.0;
+                match wkt {
+                    true => Value::String(format!("POINT({} {})", lon, lat)),
+                    false => Value::String(format!("({},{})", lon, lat)),
+                }
+            }
+            Generator::Text { kind, max_len, nonce } => {
+                let suffix = nonce.as_ref().map(|n| format!("{}{}", n, row));
+                Value::String(text(rng, *kind, suffix.as_deref(), *max_len))
+            }
+        };
+        (value, kind)
+    }
+}
+
+/// A generated column.
+struct Planned {
+    generator: Generator,
+    /// Some rows get NULL.
+    sparse: bool,
+}
+
+/// Generates `req.rows` rows for `table` and inserts them, or previews them on a dry run.
+pub async fn seed(
+    pool_manager: &PoolManager,
+    connection_id: &str,
+    table: &str,
+    req: &SeedRequest,
+) -> AppResult<SeedResult> {
+    let start = Instant::now();
+    let config = pool_manager
+        .get_connection(connection_id)
+        .await
+        .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+    if !matches!(config.db_type, DbType::MySQL | DbType::MariaDB | DbType::Postgres) {
+        return Err(AppError::UnsupportedDatabaseType(
+            "Seed data is only supported for MySQL, MariaDB and PostgreSQL".into(),
+        ));
+    }
+    let schema = introspection::resolve_schema(&config, req.database.as_deref())?;
+    let pool = pool_manager.get_or_create_pool(connection_id).await?;
+    let label = format!("{}.{}", schema, table);
+    let def = introspection::load_schema(&pool, &schema)
+        .await?
+        .into_iter()
+        .find(|t| t.name == table)
+        .ok_or_else(|| AppError::NotFound(format!("table {}", label)))?;
+    let quoted = format!(
+        "{}.{}",
+        quote_ident(&config.db_type, &schema),
+        quote_ident(&config.db_type, table)
+    );
+
+    let seed = req.seed.unwrap_or_else(|| Uuid::new_v4().as_u128() as u64);
+    let mut rng = Rng(seed);
+    let mut columns = Vec::new();
+    let mut planned = Vec::new();
+    let mut described = Vec::new();
+    let mut skipped_columns = Vec::new();
+    for column in transfer::load_columns(&pool, Some(&schema), table).await? {
+        let Some(def_column) = def.columns.iter().find(|c| c.name == column.name) else {
+            continue;
+        };
+        if filled_by_database(def_column) {
+            skipped_columns.push(column.name);
+            continue;
+        }
+        let unique = def
+            .indexes
+            .iter()
+            .any(|i| i.unique && i.columns.len() == 1 && i.columns[0] == column.name);
+        let foreign_key = def
+            .foreign_keys
+            .iter()
+            .find(|fk| fk.columns.len() == 1 && fk.columns[0] == column.name);
+        let generator = match foreign_key {
+            Some(fk) => Some(reference(&pool, &config, &schema, fk, def_column).await?),
+            None => generator_for(&pool, &config.db_type, &quoted, &column, def_column, unique, &mut rng).await?,
+        };
+        let Some(generator) = generator else {
+            skipped_columns.push(column.name);
+            continue;
+        };
+        described.push(SeedColumn {
+            name: column.name.clone(),
+            data_type: def_column.data_type.clone(),
+            generator: generator.describe(),
+        });
+        planned.push(Planned {
+            sparse: def_column.nullable && !unique && !matches!(generator, Generator::Null),
+            generator,
+        });
+        columns.push(column);
+    }
+    if columns.is_empty() {
+        return Err(AppError::InvalidInput(format!("table {} has no columns to generate", label)));
+    }
+
+    let batch_size = req.batch_size.filter(|n| *n > 0).unwrap_or(DEFAULT_BATCH_SIZE) as usize;
+    let total = if req.dry_run { (req.rows as usize).min(PREVIEW_ROWS) } else { req.rows as usize };
+    let now = Utc::now().naive_utc();
+    let mut preview = Vec::new();
+    let mut batch = Vec::with_capacity(batch_size.min(total));
+    let mut rows_inserted = 0u64;
+    for row in 0..total {
+        let values: Vec<(Value, ValueKind)> = planned
+            .iter()
+            .zip(&columns)
+            .map(|(p, column)| {
+                if p.sparse && rng.unit() < NULL_RATE {
+                    (Value::Null, column.kind)
+                } else {
+                    p.generator.generate(&mut rng, row as u64, now, column.kind)
+                }
+            })
+            .collect();
+        if preview.len() < PREVIEW_ROWS {
+            preview.push(values.iter().map(|(v, _)| v.clone()).collect());
+        }
+        if req.dry_run {
+            continue;
+        }
+
+        let cells = values
+            .into_iter()
+            .zip(&columns)
+            .map(|((value, kind), column)| {
+                transfer::coerce(value, kind, column.kind, &config.db_type)
+                    .map_err(|e| AppError::InvalidInput(format!("column {}: {}", column.name, e)))
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        batch.push(cells);
+        if batch.len() >= batch_size || row + 1 == total {
+            let rows = std::mem::take(&mut batch);
+            let count = rows.len() as u64;
+            transfer::insert_rows(&pool, &config.db_type, &quoted, &columns, rows).await?;
+            rows_inserted += count;
+        }
+    }
+
+    tracing::info!(
+        connection_id = %connection_id,
+        table = %label,
+        rows = rows_inserted,
+        dry_run = req.dry_run,
+        seed,
+        "Seed data generated"
+    );
+
+    Ok(SeedResult {
+        table: label,
+        columns: described,
+        skipped_columns,
+        preview,
+        rows_inserted,
+        dry_run: req.dry_run,
+        seed,
+        duration_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Auto-increment, identity, serial and generated columns.
+fn filled_by_database(column: &ColumnDef) -> bool {
+    let extra = column.extra.as_deref().unwrap_or("");
+    ["auto_increment", "identity", "generated"].iter().any(|e| extra.contains(e))
+        || column.default.as_deref().is_some_and(|d| d.starts_with("nextval("))
+}
+
+/// Samples existing values of the referenced column.
+async fn reference(
+    pool: &DatabasePool,
+    config: &ConnectionConfig,
+    schema: &str,
+    fk: &ForeignKeyDef,
+    column: &ColumnDef,
+) -> AppResult<Generator> {
+    let ref_column = &fk.referenced_columns[0];
+    let label = format!("{}.{}", fk.referenced_table, ref_column);
+    if let Some(allowlist) = &config.allowlist {
+        if !allowlist.allows_table(Some(schema), &fk.referenced_table) {
+            return Err(AppError::Forbidden(format!(
+                "table {} is not in the connection allowlist",
+                fk.referenced_table
+            )));
+        }
+    }
+
+    let db_type = &config.db_type;
+    let col = quote_ident(db_type, ref_column);
+    let sql = format!(
+        "SELECT DISTINCT {col} FROM {}.{} WHERE {col} IS NOT NULL LIMIT {}",
+        quote_ident(db_type, schema),
+        quote_ident(db_type, &fk.referenced_table),
+        REFERENCE_SAMPLE
+    );
+    let (kind, values) = match pool {
+        DatabasePool::MySQL(p) => {
+            let rows = sqlx::query(&sql).fetch_all(p).await?;
+            let kind = rows
+                .first()
+                .map_or(ValueKind::Text, |r| type_mapping::mysql_kind(r.column(0).type_info().name()));
+            (kind, rows.iter().map(|r| type_mapping::mysql_value(r, 0, kind)).collect::<Vec<_>>())
+        }
+        DatabasePool::Postgres(p) => {
+            let rows = sqlx::query(&sql).fetch_all(p).await?;
+            let kind = rows
+                .first()
+                .map_or(ValueKind::Text, |r| type_mapping::postgres_kind(r.column(0).type_info().name()));
+            (kind, rows.iter().map(|r| type_mapping::postgres_value(r, 0, kind)).collect::<Vec<_>>())
+        }
+        _ => (ValueKind::Text, Vec::new()),
+    };
+
+    if values.is_empty() {
+        if column.nullable {
+            return Ok(Generator::Null);
+        }
+        return Err(AppError::InvalidInput(format!(
+            "column {} references {}, which has no rows",
+            column.name, label
+        )));
+    }
+    Ok(Generator::Reference { label, kind, values })
+}
+
+/// Picks a generator from the column type and name.
+///
+/// Returns `None` for columns that cannot be generated but have a default,
+/// and `Generator::Null` for such columns that are nullable.
+async fn generator_for(
+    pool: &DatabasePool,
+    db_type: &DbType,
+    table: &str,
+    column: &TableColumn,
+    def: &ColumnDef,
+    unique: bool,
+    rng: &mut Rng,
+) -> AppResult<Option<Generator>> {
+    let data_type = def.data_type.to_lowercase();
+    let base = data_type.split(['(', ' ']).next().unwrap_or("");
+    let name = column.name.to_lowercase();
+
+    let generator = match column.kind {
+        _ if data_type.ends_with("[]") => None,
+        ValueKind::Integer if data_type.starts_with("tinyint(1)") || base == "bit" => Some(Generator::Boolean),
+        ValueKind::Integer if unique => {
+            let max = max_value(pool, db_type, table, &column.name).await?;
+            Some(Generator::Sequence(max.map_or(1, |m| m.saturating_add(1))))
+        }
+        ValueKind::Integer => {
+            let (type_min, type_max) = integer_range(&data_type);
+            let (min, max) = integer_hint(&name);
+            Some(Generator::Integer {
+                min: min.clamp(type_min, type_max),
+                max: max.clamp(type_min, type_max),
+            })
+        }
+        ValueKind::Boolean => Some(Generator::Boolean),
+        ValueKind::Decimal => {
+            let (precision, scale) = precision(&data_type);
+            let digits = precision.saturating_sub(scale).min(18);
+            let limit = 10i64.pow(digits) - 1;
+            Some(Generator::Decimal {
+                max: integer_hint(&name).1.min(limit),
+                scale,
+            })
+        }
+        ValueKind::Float => Some(Generator::Float { max: 1000.0 }),
+        ValueKind::Date if ["birth", "dob"].iter().any(|n| name.contains(n)) => {
+            Some(Generator::Date { years_back: (18, 80) })
+        }
+        ValueKind::Date => Some(Generator::Date { years_back: (0, 3) }),
+        ValueKind::Time => Some(Generator::Time),
+        ValueKind::DateTime => Some(Generator::DateTime { utc: false }),
+        ValueKind::DateTimeTz => Some(Generator::DateTime { utc: true }),
+        ValueKind::Json => Some(Generator::Json),
+        ValueKind::Uuid => Some(Generator::Uuid),
+        ValueKind::Binary => Some(Generator::Binary(type_length(&data_type).unwrap_or(16).min(16))),
+        ValueKind::Geometry => Some(Generator::Point { wkt: data_type != "point" }),
+        ValueKind::Text if base == "enum" || base == "set" => Some(Generator::Choice(enum_members(&def.data_type))),
+        ValueKind::Text if *db_type == DbType::Postgres && !postgres_text_type(&data_type) => {
+            let labels = postgres_enum_labels(pool, &def.data_type).await;
+            (!labels.is_empty()).then_some(Generator::Choice(labels))
+        }
+        ValueKind::Text => Some(Generator::Text {
+            kind: TextKind::for_column(&column.name),
+            max_len: type_length(&data_type),
+            nonce: unique.then(|| format!("{:04x}", rng.range(0, 0xffff))),
+        }),
+    };
+
+    match generator {
+        Some(generator) => Ok(Some(generator)),
+        None if def.nullable => Ok(Some(Generator::Null)),
+        None if def.default.is_some() => Ok(None),
+        None => Err(AppError::InvalidInput(format!(
+            "cannot generate values for column {} ({})",
+            column.name, def.data_type
+        ))),
+    }
+}
+
+/// Current maximum of an integer column of a (quoted) table, where sequential values continue.
+async fn max_value(pool: &DatabasePool, db_type: &DbType, table: &str, column: &str) -> AppResult<Option<i64>> {
+    let column = quote_ident(db_type, column);
+    let max = match pool {
+        DatabasePool::MySQL(p) => {
+            sqlx::query(&format!("SELECT CAST(MAX({}) AS SIGNED) FROM {}", column, table))
+                .fetch_one(p)
+                .await?
+                .try_get(0)?
+        }
+        DatabasePool::Postgres(p) => {
+            sqlx::query(&format!("SELECT MAX({})::bigint FROM {}", column, table))
+                .fetch_one(p)
+                .await?
+                .try_get(0)?
+        }
+        _ => None,
+    };
+    Ok(max)
+}
+
+/// Built-in PostgreSQL character types; other text-kind types are enums or unsupported.
+fn postgres_text_type(data_type: &str) -> bool {
+    data_type.starts_with("character") || matches!(data_type, "text" | "citext" | "name" | "\"char\"")
+}
+
+async fn postgres_enum_labels(pool: &DatabasePool, type_name: &str) -> Vec<String> {
+    let DatabasePool::Postgres(p) = pool else {
+        return Vec::new();
+    };
+    sqlx::query_scalar("SELECT enumlabel::text FROM pg_enum WHERE enumtypid = $1::regtype ORDER BY enumsortorder")
+        .bind(type_name)
+        .fetch_all(p)
+        .await
+        .unwrap_or_default()
+}
+
+/// Value range of an integer type.
+fn integer_range(data_type: &str) -> (i64, i64) {
+    let base = data_type.split(['(', ' ']).next().unwrap_or("");
+    let (min, max) = match base {
+        "tinyint" => (-128, 127),
+        "smallint" | "int2" => (-32_768, 32_767),
+        "mediumint" => (-8_388_608, 8_388_607),
+        "bigint" | "int8" => (i64::MIN, i64::MAX),
+        "year" => (1901, 2155),
+        _ => (i32::MIN as i64, i32::MAX as i64),
+    };
+    if data_type.contains("unsigned") {
+        (0, max.saturating_mul(2).saturating_add(1))
+    } else {
+        (min, max)
+    }
+}
+
+/// Realistic range for an integer column, by name.
+fn integer_hint(name: &str) -> (i64, i64) {
+    let has = |parts: &[&str]| parts.iter().any(|p| name.contains(p));
+    if name == "age" || name.ends_with("_age") {
+        (18, 80)
+    } else if has(&["year"]) {
+        (1990, 2030)
+    } else if has(&["rating", "stars", "score"]) {
+        (1, 5)
+    } else if has(&["qty", "quantity", "count", "stock"]) {
+        (0, 500)
+    } else if has(&["price", "amount", "total", "cost", "salary", "balance"]) {
+        (1, 10_000)
+    } else {
+        (1, 100_000)
+    }
+}
+
+/// Precision and scale of a DECIMAL / NUMERIC type (default 10, 2).
+fn precision(data_type: &str) -> (u32, u32) {
+    let args = type_args(data_type);
+    match args.as_slice() {
+        [p, s, ..] => (*p, *s),
+        [p] => (*p, 0),
+        [] => (10, 2),
+    }
+}
+
+/// Declared length of a character or binary type.
+fn type_length(data_type: &str) -> Option<usize> {
+    type_args(data_type).first().map(|n| *n as usize)
+}
+
+fn type_args(data_type: &str) -> Vec<u32> {
+    data_type
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(args, _)| args.split(',').filter_map(|a| a.trim().parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Members of a MySQL `enum('a','b')` / `set(...)` column type.
+fn enum_members(data_type: &str) -> Vec<String> {
+    let Some((_, rest)) = data_type.split_once('(') else {
+        return Vec::new();
+    };
+    let mut members = Vec::new();
+    let mut current = String::new();
+    let mut chars = rest.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('\'', true) if chars.peek() == Some(&'\'') => {
+                current.push('\'');
+                chars.next();
+            }
+            ('\'', true) => {
+                members.push(std::mem::take(&mut current));
+                quoted = false;
+            }
+            ('\'', false) => quoted = true,
+            (c, true) => current.push(c),
+            _ => {}
+        }
+    }
+    members
+}
+
+fn random_uuid(rng: &mut Rng) -> String {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&rng.next_u64().to_le_bytes());
+    bytes[8..].copy_from_slice(&rng.next_u64().to_le_bytes());
+    uuid::Builder::from_random_bytes(bytes).into_uuid().to_string()
+}
+
+/// Generates text of `kind`, keeping `suffix` (for unique columns) within `max_len`.
+fn text(rng: &mut Rng, kind: TextKind, suffix: Option<&str>, max_len: Option<usize>) -> String {
+    let first = rng.pick(FIRST_NAMES);
+    let last = rng.pick(LAST_NAMES);
+    let (base, tail) = match kind {
+        TextKind::Email => {
+            let domain = rng.pick(DOMAINS);
+            let name = format!("{}.{}", first, last).to_lowercase();
+            return match suffix {
+                Some(s) => fit(&name, &format!(".{}@{}", s, domain), max_len),
+                None => fit(&format!("{}{}", name, rng.range(1, 99)), &format!("@{}", domain), max_len),
+            };
+        }
+        TextKind::FirstName => (first.to_string(), None),
+        TextKind::LastName => (last.to_string(), None),
+        TextKind::FullName => (format!("{} {}", first, last), None),
+        TextKind::Username => (format!("{}{}{}", first.to_lowercase(), &last[..1].to_lowercase(), rng.range(1, 999)), None),
+        TextKind::Phone => (
+            format!("+1-{:03}-{:03}-{:04}", rng.range(200, 999), rng.range(200, 999), rng.range(0, 9999)),
+            None,
+        ),
+        TextKind::City => (rng.pick(CITIES).to_string(), None),
+        TextKind::Country => (rng.pick(COUNTRIES).to_string(), None),
+        TextKind::Address => (format!("{} {} St", rng.range(1, 9999), rng.pick(STREETS)), None),
+        TextKind::Url => (format!("https://www.{}{}.com", rng.pick(WORDS), rng.range(1, 999)), None),
+        TextKind::Company => (format!("{} {}", last, rng.pick(COMPANY_SUFFIXES)), None),
+        TextKind::Uuid => (random_uuid(rng), None),
+        TextKind::Title => (sentence(rng, 2, 5, false), None),
+        TextKind::Sentence => (sentence(rng, 6, 14, true), None),
+        TextKind::Word => (rng.pick(WORDS).to_string(), Some(rng.range(1, 9999).to_string())),
+    };
+    let tail = match (suffix, tail) {
+        (Some(s), _) => format!("-{}", s),
+        (None, Some(n)) => format!("-{}", n),
+        (None, None) => String::new(),
+    };
+    fit(&base, &tail, max_len)
+}
+
+/// Truncates `base` so that `base + tail` fits in `max_len` characters.
+fn fit(base: &str, tail: &str, max_len: Option<usize>) -> String {
+    let Some(max_len) = max_len else {
+        return format!("{}{}", base, tail);
+    };
+    let tail_len = tail.chars().count();
+    if tail_len >= max_len {
+        return tail.chars().skip(tail_len - max_len).collect();
+    }
+    let base: String = base.chars().take(max_len - tail_len).collect();
+    format!("{}{}", base.trim_end(), tail)
+}
+
+fn sentence(rng: &mut Rng, min_words: i64, max_words: i64, period: bool) -> String {
+    let count = rng.range(min_words, max_words);
+    let mut words: Vec<String> = (0..count).map(|_| rng.pick(WORDS).to_string()).collect();
+    if let Some(first) = words.first_mut() {
+        *first = first[..1].to_uppercase() + &first[1..];
+    }
+    let text = words.join(" ");
+    if period {
+        text + "."
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_column_types() {
+        assert_eq!(integer_range("tinyint(3) unsigned"), (0, 255));
+        assert_eq!(integer_range("smallint"), (-32_768, 32_767));
+        assert_eq!(precision("decimal(10,2)"), (10, 2));
+        assert_eq!(precision("numeric"), (10, 2));
+        assert_eq!(type_length("character varying(40)"), Some(40));
+        assert_eq!(enum_members("enum('new','it''s','done')"), vec!["new", "it's", "done"]);
+    }
+
+    #[test]
+    fn generates_reproducible_text_within_length() {
+        let now = Utc::now().naive_utc();
+        let generator = Generator::Text { kind: TextKind::Email, max_len: Some(24), nonce: Some("ab12".into()) };
+        let mut a = Rng(42);
+        let mut b = Rng(42);
+        for row in 0..50 {
+            let (value, _) = generator.generate(&mut a, row, now, ValueKind::Text);
+            assert_eq!(value, generator.generate(&mut b, row, now, ValueKind::Text).0);
+            let email = value.as_str().unwrap();
+            assert!(email.chars().count() <= 24 && email.contains('@'));
+            assert!(email.contains(&format!("ab12{}", row)));
+        }
+        assert_eq!(TextKind::for_column("customer_email"), TextKind::Email);
+        assert_eq!(TextKind::for_column("name"), TextKind::FullName);
+    }
+}
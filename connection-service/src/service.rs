@@ -6,80 +6,254 @@ use chrono::Utc;
 use uuid::Uuid;
 
 use common::errors::{AppError, AppResult};
-use common::models::connection::{ConnectionItem, CreateConnectionRequest};
+use common::models::connection::{
+    ConnectionAllowlist, ConnectionConfig, ConnectionItem, ConnectionPoolOptions, ConnectionRevision,
+    CreateConnectionRequest, RevisionAction, StatementPolicy,
+};
+use common::models::masking::ConnectionMasking;
+use common::models::diagnostics::StageResult;
 use crate::pool_manager::PoolManager;
+use crate::revisions::RevisionStore;
+
+/// 发起请求的调用方，决定可见的连接范围
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Viewer<'a> {
+    /// 网关认证后的调用方（`X-Principal`），匿名请求为 `None`
+    pub principal: Option<&'a str>,
+    /// 持有管理员令牌时可见全部连接
+    pub admin: bool,
+}
+
+impl Viewer<'_> {
+    /// 调用方能否查看、删除该连接
+    pub fn can_see(&self, config: &ConnectionConfig) -> bool {
+        self.admin || config.is_visible_to(self.principal)
+    }
+}
 
 /// 连接服务 Trait
 #[async_trait]
 pub trait ConnectionServiceTrait: Send + Sync {
-    /// 列出所有连接
-    async fn list(&self) -> Vec<ConnectionItem>;
+    /// 列出调用方可见的连接
+    async fn list(&self, viewer: Viewer<'_>) -> Vec<ConnectionItem>;
     
-    /// 创建新连接
-    async fn create(&self, req: CreateConnectionRequest) -> AppResult<ConnectionItem>;
+    /// 创建新连接，归属于调用方
+    async fn create(&self, req: CreateConnectionRequest, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
     
-    /// 根据 ID 获取连接
-    async fn get(&self, id: &str) -> AppResult<ConnectionItem>;
+    /// 根据 ID 获取连接（不可见的连接视为不存在）
+    async fn get(&self, id: &str, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
     
-    /// 根据 ID 删除连接
-    async fn delete(&self, id: &str) -> AppResult<()>;
+    /// 根据 ID 删除连接（不可见的连接视为不存在）
+    async fn delete(&self, id: &str, viewer: Viewer<'_>) -> AppResult<()>;
     
-    /// 测试连接
-    async fn test(&self, id: &str) -> AppResult<u64>;
+    /// 分阶段测试已保存的连接（不可见的连接视为不存在）
+    async fn test(&self, id: &str, viewer: Viewer<'_>) -> AppResult<Vec<StageResult>>;
+
+    /// 分阶段测试尚未保存的连接配置，不写入任何数据
+    async fn test_unsaved(&self, req: CreateConnectionRequest) -> AppResult<Vec<StageResult>>;
+
+    /// 设置连接的库表白名单（空白名单表示不限制）
+    ///
+    /// 以下设置类方法均要求连接对调用方可见，否则视为不存在
+    async fn set_allowlist(&self, id: &str, allowlist: ConnectionAllowlist, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
+
+    /// 设置连接的查询结果脱敏规则（没有规则表示不脱敏）
+    async fn set_masking(&self, id: &str, masking: ConnectionMasking, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
+
+    /// 设置连接允许执行的语句类型（空列表表示不限制）
+    async fn set_statement_policy(&self, id: &str, policy: StatementPolicy, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
+
+    /// 设置连接的默认查询超时（`None` 表示使用服务默认值）
+    async fn set_query_timeout(&self, id: &str, timeout_ms: Option<u64>, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
+
+    /// 固定或取消固定连接（固定的连接在服务启动时预热）
+    async fn set_pinned(&self, id: &str, pinned: bool, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
+
+    /// 设置连接池参数（None 恢复服务默认值），已打开的连接池在下次使用时按新参数重建
+    async fn set_pool_options(&self, id: &str, options: Option<ConnectionPoolOptions>, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
+
+    /// 轮换连接密码：先用新密码连通目标库，再保存密码并替换连接池（不可见的连接视为不存在）
+    async fn rotate_password(&self, id: &str, password: String, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
+
+    /// 列出连接的变更历史（最新在前）；已删除连接的历史仅管理员可见
+    async fn history(&self, id: &str, viewer: Viewer<'_>) -> AppResult<Vec<ConnectionRevision>>;
+
+    /// 将连接恢复为某个历史版本的配置，已删除的连接会被重新创建
+    async fn restore_revision(&self, id: &str, revision: u32, viewer: Viewer<'_>) -> AppResult<ConnectionItem>;
 }
 
 /// 数据库连接管理服务
 pub struct ConnectionService {
     pool_manager: Arc<PoolManager>,
+    revisions: Arc<RevisionStore>,
+    /// 发起变更的调用方，记入变更历史
+    actor: Option<String>,
 }
 
 impl ConnectionService {
-    /// 创建新的连接服务实例
-    pub fn new(pool_manager: Arc<PoolManager>) -> Self {
-        Self { pool_manager }
+    /// 创建新的连接服务实例，变更以 `actor` 的名义记入历史
+    pub fn new(pool_manager: Arc<PoolManager>, revisions: Arc<RevisionStore>, actor: Option<&str>) -> Self {
+        Self {
+            pool_manager,
+            revisions,
+            actor: actor.map(str::to_string),
+        }
+    }
+
+    /// 记录一次设置变更
+    async fn record_update(&self, before: ConnectionConfig, after: &ConnectionConfig) {
+        self.revisions
+            .record(Some(&before), Some(after), RevisionAction::Updated, self.actor.as_deref(), None)
+            .await;
+    }
+
+    /// 调用方可见的连接，不可见的连接视为不存在
+    async fn visible(&self, id: &str, viewer: Viewer<'_>) -> AppResult<ConnectionConfig> {
+        self.pool_manager
+            .get_connection(id)
+            .await
+            .filter(|config| viewer.can_see(config))
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
+    }
+
+    /// 调用方能否查看连接的历史：连接存在时须可见，已删除时须为管理员
+    async fn check_history_access(&self, id: &str, viewer: Viewer<'_>) -> AppResult<()> {
+        match self.pool_manager.get_connection(id).await {
+            Some(config) if viewer.can_see(&config) => Ok(()),
+            None if viewer.admin => Ok(()),
+            _ => Err(AppError::ConnectionNotFound(id.to_string())),
+        }
     }
 }
 
 #[async_trait]
 impl ConnectionServiceTrait for ConnectionService {
-    async fn list(&self) -> Vec<ConnectionItem> {
+    async fn list(&self, viewer: Viewer<'_>) -> Vec<ConnectionItem> {
         self.pool_manager
             .list_connections()
             .await
             .into_iter()
+            .filter(|config| viewer.can_see(config))
             .map(ConnectionItem::from)
             .collect()
     }
 
-    async fn create(&self, req: CreateConnectionRequest) -> AppResult<ConnectionItem> {
+    async fn create(&self, req: CreateConnectionRequest, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
         let id = Uuid::new_v4().to_string();
         let created_at = Utc::now().to_rfc3339();
-        let config = req.into_config(id.clone(), created_at);
+        let mut config = req.into_config(id.clone(), created_at)?;
+        config.owner_id = viewer.principal.map(str::to_string);
 
         // 添加到连接池管理器（会进行验证并建立连接）
         self.pool_manager.add_connection(config.clone()).await?;
+        self.revisions
+            .record(None, Some(&config), RevisionAction::Created, self.actor.as_deref(), None)
+            .await;
 
         tracing::info!(id = %id, name = %config.name, "连接已创建");
         Ok(ConnectionItem::from(config))
     }
 
-    async fn get(&self, id: &str) -> AppResult<ConnectionItem> {
-        self.pool_manager
-            .get_connection(id)
-            .await
-            .map(ConnectionItem::from)
-            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
+    async fn get(&self, id: &str, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
+        self.visible(id, viewer).await.map(ConnectionItem::from)
     }
 
-    async fn delete(&self, id: &str) -> AppResult<()> {
+    async fn delete(&self, id: &str, viewer: Viewer<'_>) -> AppResult<()> {
+        self.get(id, viewer).await?;
+        let before = self.pool_manager.get_connection(id).await;
         self.pool_manager.remove_connection(id).await?;
+        self.revisions
+            .record(before.as_ref(), None, RevisionAction::Deleted, self.actor.as_deref(), None)
+            .await;
         tracing::info!(id = %id, "连接已删除");
         Ok(())
     }
 
-    async fn test(&self, id: &str) -> AppResult<u64> {
-        let latency = self.pool_manager.test_connection(id).await?;
-        Ok(latency.as_millis() as u64)
+    async fn test(&self, id: &str, viewer: Viewer<'_>) -> AppResult<Vec<StageResult>> {
+        let config = self.visible(id, viewer).await?;
+        Ok(self.pool_manager.diagnose(&config).await)
+    }
+
+    async fn test_unsaved(&self, req: CreateConnectionRequest) -> AppResult<Vec<StageResult>> {
+        let config = req.into_config(String::new(), Utc::now().to_rfc3339())?;
+        Ok(self.pool_manager.diagnose(&config).await)
+    }
+
+    async fn set_allowlist(&self, id: &str, allowlist: ConnectionAllowlist, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
+        let before = self.visible(id, viewer).await?;
+        let config = self.pool_manager.set_allowlist(id, allowlist).await?;
+        self.record_update(before, &config).await;
+        tracing::info!(id = %id, restricted = config.allowlist.is_some(), "连接白名单已更新");
+        Ok(ConnectionItem::from(config))
+    }
+
+    async fn set_masking(&self, id: &str, masking: ConnectionMasking, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
+        masking.validate_rules()?;
+        let before = self.visible(id, viewer).await?;
+        let config = self.pool_manager.set_masking(id, masking).await?;
+        self.record_update(before, &config).await;
+        tracing::info!(
+            id = %id,
+            rules = config.masking.as_ref().map_or(0, |m| m.rules.len()),
+            "连接脱敏规则已更新"
+        );
+        Ok(ConnectionItem::from(config))
+    }
+
+    async fn set_statement_policy(&self, id: &str, policy: StatementPolicy, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
+        let before = self.visible(id, viewer).await?;
+        let config = self.pool_manager.set_statement_policy(id, policy).await?;
+        self.record_update(before, &config).await;
+        tracing::info!(
+            id = %id,
+            allowed = ?config.statement_policy.as_ref().map(|p| &p.allowed),
+            "连接语句策略已更新"
+        );
+        Ok(ConnectionItem::from(config))
+    }
+
+    async fn set_query_timeout(&self, id: &str, timeout_ms: Option<u64>, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
+        let before = self.visible(id, viewer).await?;
+        let config = self.pool_manager.set_query_timeout(id, timeout_ms).await?;
+        self.record_update(before, &config).await;
+        tracing::info!(id = %id, timeout_ms = ?config.query_timeout_ms, "连接默认查询超时已更新");
+        Ok(ConnectionItem::from(config))
+    }
+
+    async fn set_pinned(&self, id: &str, pinned: bool, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
+        let before = self.visible(id, viewer).await?;
+        let config = self.pool_manager.set_pinned(id, pinned).await?;
+        self.record_update(before, &config).await;
+        tracing::info!(id = %id, pinned, "连接固定状态已更新");
+        Ok(ConnectionItem::from(config))
+    }
+
+    async fn set_pool_options(&self, id: &str, options: Option<ConnectionPoolOptions>, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
+        let before = self.visible(id, viewer).await?;
+        let config = self.pool_manager.set_pool_options(id, options).await?;
+        self.record_update(before, &config).await;
+        tracing::info!(id = %id, options = ?config.pool_options, "连接池参数已更新");
+        Ok(ConnectionItem::from(config))
+    }
+
+    async fn rotate_password(&self, id: &str, password: String, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
+        let before = self.visible(id, viewer).await?;
+        let config = self.pool_manager.rotate_password(id, password).await?;
+        self.record_update(before, &config).await;
+        tracing::info!(id = %id, "连接密码已轮换");
+        Ok(ConnectionItem::from(config))
+    }
+
+    async fn history(&self, id: &str, viewer: Viewer<'_>) -> AppResult<Vec<ConnectionRevision>> {
+        self.check_history_access(id, viewer).await?;
+        self.revisions.list(id).await
+    }
+
+    async fn restore_revision(&self, id: &str, revision: u32, viewer: Viewer<'_>) -> AppResult<ConnectionItem> {
+        self.check_history_access(id, viewer).await?;
+        let config = self.revisions.restore(id, revision, self.actor.as_deref()).await?;
+        tracing::info!(id = %id, revision, "连接已恢复到历史版本");
+        Ok(ConnectionItem::from(config))
     }
 }
 
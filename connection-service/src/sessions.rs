@@ -0,0 +1,407 @@
+//! Login sessions.
+//!
+//! Sessions live in the `user_sessions` metadata table. Every session token
+//! (see `common::jwt`) names its session; the gateway checks through the
+//! internal verify endpoint that the session is still active before accepting
+//! the token, so revoking a session kills its tokens before they expire.
+//! Users list and revoke their own sessions; admins can revoke any session.
+//!
+//! Each session has one refresh token (see `auth`), stored as its SHA-256
+//! hash. Refreshing replaces it; presenting a replaced token again means it
+//! leaked, so the session is revoked.
+//!
+//! Sessions started through single sign-on keep the roles mapped at login,
+//! so refreshed tokens carry the same roles.
+//!
+//! Expired sessions are deleted after the retention period; until then they
+//! are kept for listing by admins.
+//!
+//! Configuration:
+//! - `SESSION_RETENTION_DAYS` - days expired sessions are kept (default: 7)
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::meta::Statement;
+use common::meta_query;
+use common::models::session::Session;
+use crate::pool_manager::PoolManager;
+
+const DEFAULT_RETENTION_DAYS: i64 = 7;
+
+/// Interval of the expired session cleanup.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Most sessions returned by one admin listing.
+const MAX_LISTED_SESSIONS: u32 = 1000;
+
+/// MySQL DATETIME format used for stored timestamps (UTC).
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+const SELECT_SESSION: Statement = Statement {
+    mysql: "SELECT `id`, `user_id`, CAST(`created_at` AS CHAR) AS created_at, \
+         CAST(`last_seen_at` AS CHAR) AS last_seen_at, CAST(`expires_at` AS CHAR) AS expires_at, \
+         CAST(`revoked_at` AS CHAR) AS revoked_at, `user_agent`, `ip_address`, `roles` FROM `user_sessions`",
+    postgres: r#"SELECT "id", "user_id", "created_at",
+         "last_seen_at", "expires_at",
+         "revoked_at", "user_agent", "ip_address", "roles" FROM "user_sessions""#,
+    sqlite: r#"SELECT "id", "user_id", "created_at",
+         "last_seen_at", "expires_at",
+         "revoked_at", "user_agent", "ip_address", "roles" FROM "user_sessions""#,
+};
+
+/// Longest user agent stored with a session.
+const MAX_USER_AGENT_LEN: usize = 255;
+
+/// Condition of sessions whose tokens are accepted.
+const ACTIVE: Statement = Statement {
+    mysql: "`revoked_at` IS NULL AND `expires_at` > UTC_TIMESTAMP()",
+    postgres: r#""revoked_at" IS NULL AND "expires_at" > to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')"#,
+    sqlite: r#""revoked_at" IS NULL AND "expires_at" > CURRENT_TIMESTAMP"#,
+};
+
+/// Row from the `user_sessions` metadata table.
+#[derive(sqlx::FromRow)]
+struct SessionRow {
+    id: String,
+    user_id: String,
+    created_at: String,
+    last_seen_at: Option<String>,
+    expires_at: String,
+    revoked_at: Option<String>,
+    user_agent: Option<String>,
+    ip_address: Option<String>,
+    roles: Option<String>,
+}
+
+impl From<SessionRow> for Session {
+    fn from(row: SessionRow) -> Self {
+        Session {
+            id: row.id,
+            user_id: row.user_id,
+            created_at: row.created_at,
+            last_seen_at: row.last_seen_at,
+            expires_at: row.expires_at,
+            revoked_at: row.revoked_at,
+            user_agent: row.user_agent,
+            ip_address: row.ip_address,
+            roles: row
+                .roles
+                .and_then(|r| serde_json::from_str(&r).ok())
+                .unwrap_or_default(),
+            current: false,
+        }
+    }
+}
+
+/// Lists, revokes and verifies login sessions.
+pub struct SessionStore {
+    pool_manager: Arc<PoolManager>,
+    retention: ChronoDuration,
+}
+
+impl SessionStore {
+    /// Creates the store.
+    pub fn new(pool_manager: Arc<PoolManager>) -> Self {
+        let retention_days = std::env::var("SESSION_RETENTION_DAYS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        Self {
+            pool_manager,
+            retention: ChronoDuration::days(retention_days),
+        }
+    }
+
+    /// Starts the periodic cleanup of expired sessions.
+    pub fn spawn(self: &Arc<Self>) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = store.prune().await {
+                    tracing::warn!(error = %e, "Session cleanup failed");
+                }
+            }
+        });
+    }
+
+    /// Creates a session for a user, returning it with its refresh token.
+    pub async fn create(
+        &self,
+        user_id: &str,
+        roles: &[String],
+        expires_at: DateTime<Utc>,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> AppResult<(Session, String)> {
+        let id = Uuid::new_v4().simple().to_string();
+        let refresh_token = new_refresh_token(&id);
+        let user_agent = user_agent.map(|ua| ua.chars().take(MAX_USER_AGENT_LEN).collect::<String>());
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `user_sessions` (`id`, `user_id`, `created_at`, `expires_at`, `user_agent`, `ip_address`, `refresh_token_hash`, `roles`) \
+                 VALUES (?, ?, UTC_TIMESTAMP(), ?, ?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "user_sessions" ("id", "user_id", "created_at", "expires_at", "user_agent", "ip_address", "refresh_token_hash", "roles")
+                 VALUES ($1, $2, to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'), $3, $4, $5, $6, $7)"#,
+            sqlite: r#"INSERT INTO "user_sessions" ("id", "user_id", "created_at", "expires_at", "user_agent", "ip_address", "refresh_token_hash", "roles")
+                 VALUES (?, ?, CURRENT_TIMESTAMP, ?, ?, ?, ?, ?)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&id)
+                .bind(user_id)
+                .bind(expires_at.format(DATETIME_FORMAT).to_string())
+                .bind(user_agent)
+                .bind(ip_address)
+                .bind(hash_token(&refresh_token))
+                .bind((!roles.is_empty()).then(|| serde_json::to_string(roles).unwrap_or_default()))
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        tracing::info!(session_id = %id, user_id, "Session created");
+        Ok((self.get(&id).await?, refresh_token))
+    }
+
+    /// Replaces the refresh token of an active session, returning the session with its new token.
+    ///
+    /// # Errors
+    /// Returns `AppError::Unauthorized` if the token is unknown, replaced or its session ended.
+    /// A replaced token revokes its session.
+    pub async fn rotate(&self, refresh_token: &str) -> AppResult<(Session, String)> {
+        let (id, _) = refresh_token.split_once('.').ok_or(AppError::Unauthorized)?;
+        let next = new_refresh_token(id);
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &Statement {
+                mysql: "UPDATE `user_sessions` SET `refresh_token_hash` = ? WHERE `id` = ? AND `refresh_token_hash` = ? AND",
+                postgres: r#"UPDATE "user_sessions" SET "refresh_token_hash" = $1 WHERE "id" = $2 AND "refresh_token_hash" = $3 AND"#,
+                sqlite: r#"UPDATE "user_sessions" SET "refresh_token_hash" = ? WHERE "id" = ? AND "refresh_token_hash" = ? AND"#,
+            },
+            &ACTIVE,
+        ]);
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(&statement)
+                .bind(hash_token(&next))
+                .bind(id)
+                .bind(hash_token(refresh_token))
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result == 0 {
+            // Active session but another token: an old token was replayed
+            if let Ok(session) = self.get(id).await {
+                if session.revoked_at.is_none() {
+                    tracing::warn!(session_id = %id, user_id = %session.user_id, "Replaced refresh token reused, revoking session");
+                    self.revoke(id, None).await?;
+                }
+            }
+            return Err(AppError::Unauthorized);
+        }
+        Ok((self.get(id).await?, next))
+    }
+
+    /// Lists the active sessions of a user, newest first; `current` marks the caller's session.
+    pub async fn list(&self, user_id: &str, current: Option<&str>) -> AppResult<Vec<Session>> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_SESSION,
+            &Statement {
+                mysql: "WHERE `user_id` = ? AND",
+                postgres: r#"WHERE "user_id" = $1 AND"#,
+                sqlite: r#"WHERE "user_id" = ? AND"#,
+            },
+            &ACTIVE,
+            &Statement {
+                mysql: "ORDER BY `created_at` DESC",
+                postgres: r#"ORDER BY "created_at" DESC"#,
+                sqlite: r#"ORDER BY "created_at" DESC"#,
+            },
+        ]);
+        let rows: Vec<SessionRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement).bind(user_id).fetch_all(pool).await
+        })?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let mut session = Session::from(row);
+                session.current = current == Some(session.id.as_str());
+                session
+            })
+            .collect())
+    }
+
+    /// Lists sessions for admins, newest first, optionally of one user and including ended ones.
+    pub async fn list_all(&self, user_id: Option<&str>, active_only: bool, limit: u32) -> AppResult<Vec<Session>> {
+        let meta = self.pool_manager.meta_pool();
+        let mut sql = format!("{} WHERE 1 = 1", meta.sql(&SELECT_SESSION));
+        let mut bound = 0;
+        if user_id.is_some() {
+            bound += 1;
+            sql.push_str(meta.sql(&Statement {
+                mysql: " AND `user_id` = ?",
+                postgres: r#" AND "user_id" = $1"#,
+                sqlite: r#" AND "user_id" = ?"#,
+            }));
+        }
+        if active_only {
+            sql.push_str(" AND ");
+            sql.push_str(meta.sql(&ACTIVE));
+        }
+        sql.push_str(meta.sql(&Statement {
+            mysql: " ORDER BY `created_at` DESC LIMIT ",
+            postgres: r#" ORDER BY "created_at" DESC LIMIT "#,
+            sqlite: r#" ORDER BY "created_at" DESC LIMIT "#,
+        }));
+        sql.push_str(&meta.placeholder(bound + 1));
+
+        let rows = meta_query!(meta, |pool| {
+            let mut query = sqlx::query_as::<_, SessionRow>(&sql);
+            if let Some(user_id) = user_id {
+                query = query.bind(user_id);
+            }
+            query
+                .bind(i64::from(limit.clamp(1, MAX_LISTED_SESSIONS)))
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(Session::from).collect())
+    }
+
+    /// Gets a session by ID.
+    pub async fn get(&self, id: &str) -> AppResult<Session> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_SESSION,
+            &Statement {
+                mysql: "WHERE `id` = ?",
+                postgres: r#"WHERE "id" = $1"#,
+                sqlite: r#"WHERE "id" = ?"#,
+            },
+        ]);
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as::<_, SessionRow>(&statement)
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+        })?
+        .map(Session::from)
+        .ok_or_else(|| AppError::NotFound(format!("Session {}", id)))
+    }
+
+    /// Revokes a session, limited to the user's own sessions when `user_id` is given.
+    ///
+    /// # Errors
+    /// Returns `AppError::NotFound` if the session does not exist or belongs to another user.
+    pub async fn revoke(&self, id: &str, user_id: Option<&str>) -> AppResult<Session> {
+        let session = self.get(id).await?;
+        if user_id.is_some_and(|user_id| user_id != session.user_id) {
+            return Err(AppError::NotFound(format!("Session {}", id)));
+        }
+        let statement = self
+            .pool_manager
+            .meta_pool()
+            .sql(&Statement {
+                mysql: "UPDATE `user_sessions` SET `revoked_at` = UTC_TIMESTAMP() WHERE `id` = ? AND `revoked_at` IS NULL",
+                postgres: r#"UPDATE "user_sessions" SET "revoked_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') WHERE "id" = $1 AND "revoked_at" IS NULL"#,
+                sqlite: r#"UPDATE "user_sessions" SET "revoked_at" = CURRENT_TIMESTAMP WHERE "id" = ? AND "revoked_at" IS NULL"#,
+            });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        tracing::info!(session_id = %id, user_id = %session.user_id, "Session revoked");
+        self.get(id).await
+    }
+
+    /// Revokes all active sessions of a user except `keep`, returning how many were revoked.
+    pub async fn revoke_all(&self, user_id: &str, keep: Option<&str>) -> AppResult<u64> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &Statement {
+                mysql: "UPDATE `user_sessions` SET `revoked_at` = UTC_TIMESTAMP() WHERE `user_id` = ? AND `id` <> ? AND",
+                postgres: r#"UPDATE "user_sessions" SET "revoked_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')
+                     WHERE "user_id" = $1 AND "id" <> $2 AND"#,
+                sqlite: r#"UPDATE "user_sessions" SET "revoked_at" = CURRENT_TIMESTAMP WHERE "user_id" = ? AND "id" <> ? AND"#,
+            },
+            &ACTIVE,
+        ]);
+        let revoked = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(&statement)
+                .bind(user_id)
+                .bind(keep.unwrap_or(""))
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        tracing::info!(user_id, revoked, "Sessions revoked");
+        Ok(revoked)
+    }
+
+    /// Checks that a session is active and records its use.
+    ///
+    /// # Errors
+    /// Returns `AppError::Unauthorized` if the session is unknown, revoked or expired.
+    pub async fn verify(&self, id: &str) -> AppResult<Session> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_SESSION,
+            &Statement {
+                mysql: "WHERE `id` = ? AND",
+                postgres: r#"WHERE "id" = $1 AND"#,
+                sqlite: r#"WHERE "id" = ? AND"#,
+            },
+            &ACTIVE,
+        ]);
+        let row: Option<SessionRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement).bind(id).fetch_optional(pool).await
+        })?;
+        let session = row.map(Session::from).ok_or(AppError::Unauthorized)?;
+
+        let statement = self
+            .pool_manager
+            .meta_pool()
+            .sql(&Statement {
+                mysql: "UPDATE `user_sessions` SET `last_seen_at` = UTC_TIMESTAMP() WHERE `id` = ?",
+                postgres: r#"UPDATE "user_sessions" SET "last_seen_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS') WHERE "id" = $1"#,
+                sqlite: r#"UPDATE "user_sessions" SET "last_seen_at" = CURRENT_TIMESTAMP WHERE "id" = ?"#,
+            });
+        let recorded = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement).bind(id).execute(pool).await.map(|_| ())
+        });
+        if let Err(e) = recorded {
+            tracing::warn!(session_id = %id, error = %e, "Failed to record session use");
+        }
+        Ok(session)
+    }
+
+    async fn prune(&self) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `user_sessions` WHERE `expires_at` < ?",
+            postgres: r#"DELETE FROM "user_sessions" WHERE "expires_at" < $1"#,
+            sqlite: r#"DELETE FROM "user_sessions" WHERE "expires_at" < ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind((Utc::now() - self.retention).format(DATETIME_FORMAT).to_string())
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        Ok(())
+    }
+}
+
+/// Refresh tokens name their session so rotation can find it.
+fn new_refresh_token(session_id: &str) -> String {
+    format!("{}.{}{}", session_id, Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,371 @@
+//! Query snapshots.
+//!
+//! A snapshot is a query result saved under a name in the `query_snapshots`
+//! metadata table. Saving the same query under the same name over time gives
+//! a series whose snapshots can be compared row by row, which is enough for
+//! lightweight data monitoring (e.g. a daily snapshot of a reference table).
+//!
+//! Every snapshot expires after its retention period; expired snapshots are
+//! removed hourly. `keep_last` additionally caps the number of snapshots
+//! kept per name and connection.
+//!
+//! Configuration:
+//! - `SNAPSHOT_RETENTION_DAYS` - default retention (default: 30)
+//! - `SNAPSHOT_MAX_BYTES` - maximum serialized size of a saved result (default: 8 MiB)
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::meta::{self, Statement};
+use common::meta_query;
+use common::models::query::{QueryDiffResult, QueryResult};
+use common::models::snapshot::{CompareSnapshotsRequest, CreateSnapshotRequest, QuerySnapshot};
+use common::utils::ResultDiff;
+use crate::pool_manager::PoolManager;
+
+const DEFAULT_RETENTION_DAYS: u32 = 30;
+const DEFAULT_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// Default number of rows saved.
+pub const DEFAULT_ROW_LIMIT: u32 = 10_000;
+
+/// Interval of the expired snapshot cleanup.
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// MySQL DATETIME format used for stored timestamps (UTC).
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+const SELECT_SNAPSHOT: Statement = Statement {
+    mysql: "SELECT `id`, `name`, `connection_id`, `database_name`, `sql_text`, `row_count`, \
+         `truncated`, `size_bytes`, `created_by`, CAST(`created_at` AS CHAR) AS created_at, \
+         CAST(`expires_at` AS CHAR) AS expires_at FROM `query_snapshots`",
+    postgres: r#"SELECT "id", "name", "connection_id", "database_name", "sql_text", "row_count",
+         "truncated", "size_bytes", "created_by", "created_at",
+         "expires_at" FROM "query_snapshots""#,
+    sqlite: r#"SELECT "id", "name", "connection_id", "database_name", "sql_text", "row_count",
+         "truncated", "size_bytes", "created_by", "created_at",
+         "expires_at" FROM "query_snapshots""#,
+};
+
+/// Row from the `query_snapshots` metadata table, without the result.
+#[derive(sqlx::FromRow)]
+struct SnapshotRow {
+    id: String,
+    name: String,
+    connection_id: String,
+    database_name: Option<String>,
+    sql_text: String,
+    row_count: meta::Unsigned<u64>,
+    truncated: bool,
+    size_bytes: meta::Unsigned<u64>,
+    created_by: Option<String>,
+    created_at: String,
+    expires_at: String,
+}
+
+impl From<SnapshotRow> for QuerySnapshot {
+    fn from(row: SnapshotRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            connection_id: row.connection_id,
+            database: row.database_name,
+            sql: row.sql_text,
+            row_count: row.row_count.0,
+            truncated: row.truncated,
+            size_bytes: row.size_bytes.0,
+            created_by: row.created_by,
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            result: None,
+        }
+    }
+}
+
+/// Saves, lists and compares query snapshots.
+pub struct SnapshotStore {
+    pool_manager: Arc<PoolManager>,
+    retention_days: u32,
+    max_bytes: usize,
+}
+
+impl SnapshotStore {
+    /// Creates the store.
+    pub fn new(pool_manager: Arc<PoolManager>) -> Self {
+        fn env<T: std::str::FromStr>(key: &str, default: T) -> T {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+        Self {
+            pool_manager,
+            retention_days: env("SNAPSHOT_RETENTION_DAYS", DEFAULT_RETENTION_DAYS).max(1),
+            max_bytes: env("SNAPSHOT_MAX_BYTES", DEFAULT_MAX_BYTES),
+        }
+    }
+
+    /// Starts the periodic cleanup of expired snapshots.
+    pub fn spawn(self: &Arc<Self>) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PURGE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = store.purge_expired().await {
+                    tracing::warn!(error = %e, "Snapshot cleanup failed");
+                }
+            }
+        });
+    }
+
+    /// Saves `result` as a snapshot of `req`, clipping rows over the size limit,
+    /// then applies `keep_last` to the snapshot's series. Returns the snapshot
+    /// without its result.
+    pub async fn save(
+        &self,
+        req: &CreateSnapshotRequest,
+        mut result: QueryResult,
+        created_by: Option<&str>,
+    ) -> AppResult<QuerySnapshot> {
+        let limit = req.limit.unwrap_or(DEFAULT_ROW_LIMIT) as usize;
+        let truncated = result.clip_rows(self.max_bytes) || result.truncated || result.rows.len() >= limit;
+        let json = serde_json::to_string(&result)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize snapshot: {}", e)))?;
+
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let retention_days = req.retention_days.unwrap_or(self.retention_days);
+        let expires_at = now + ChronoDuration::days(i64::from(retention_days));
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `query_snapshots` (`id`, `name`, `connection_id`, `database_name`, `sql_text`, `result`, \
+                 `row_count`, `truncated`, `size_bytes`, `created_by`, `created_at`, `expires_at`) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            postgres: r#"INSERT INTO "query_snapshots" ("id", "name", "connection_id", "database_name", "sql_text", "result",
+                 "row_count", "truncated", "size_bytes", "created_by", "created_at", "expires_at")
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"#,
+            sqlite: r#"INSERT INTO "query_snapshots" ("id", "name", "connection_id", "database_name", "sql_text", "result",
+                 "row_count", "truncated", "size_bytes", "created_by", "created_at", "expires_at")
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(&id)
+                .bind(&req.name)
+                .bind(&req.connection_id)
+                .bind(&req.database)
+                .bind(&req.sql)
+                .bind(&json)
+                .bind(result.rows.len() as i64)
+                .bind(truncated)
+                .bind(json.len() as i64)
+                .bind(created_by)
+                .bind(now.format(DATETIME_FORMAT).to_string())
+                .bind(expires_at.format(DATETIME_FORMAT).to_string())
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+
+        if let Some(keep_last) = req.keep_last {
+            self.trim_series(&req.connection_id, &req.name, keep_last).await?;
+        }
+        tracing::info!(
+            snapshot_id = %id,
+            name = %req.name,
+            connection_id = %req.connection_id,
+            rows = result.rows.len(),
+            truncated,
+            "Query snapshot saved"
+        );
+        Ok(QuerySnapshot {
+            id,
+            name: req.name.clone(),
+            connection_id: req.connection_id.clone(),
+            database: req.database.clone(),
+            sql: req.sql.clone(),
+            row_count: result.rows.len() as u64,
+            truncated,
+            size_bytes: json.len() as u64,
+            created_by: created_by.map(str::to_string),
+            created_at: now.format(DATETIME_FORMAT).to_string(),
+            expires_at: expires_at.format(DATETIME_FORMAT).to_string(),
+            result: None,
+        })
+    }
+
+    /// Lists live snapshots without their results, newest first.
+    pub async fn list(&self, name: Option<&str>, connection_id: Option<&str>) -> AppResult<Vec<QuerySnapshot>> {
+        let meta = self.pool_manager.meta_pool();
+        let mut sql = meta.concat(&[
+            &SELECT_SNAPSHOT,
+            &Statement {
+                mysql: "WHERE `expires_at` > ?",
+                postgres: r#"WHERE "expires_at" > $1"#,
+                sqlite: r#"WHERE "expires_at" > ?"#,
+            },
+        ]);
+        let mut bound = 1;
+        if name.is_some() {
+            bound += 1;
+            sql.push_str(meta.sql(&Statement {
+                mysql: " AND `name` = ",
+                postgres: r#" AND "name" = "#,
+                sqlite: r#" AND "name" = "#,
+            }));
+            sql.push_str(&meta.placeholder(bound));
+        }
+        if connection_id.is_some() {
+            bound += 1;
+            sql.push_str(meta.sql(&Statement {
+                mysql: " AND `connection_id` = ",
+                postgres: r#" AND "connection_id" = "#,
+                sqlite: r#" AND "connection_id" = "#,
+            }));
+            sql.push_str(&meta.placeholder(bound));
+        }
+        sql.push_str(meta.sql(&Statement {
+            mysql: " ORDER BY `created_at` DESC",
+            postgres: r#" ORDER BY "created_at" DESC"#,
+            sqlite: r#" ORDER BY "created_at" DESC"#,
+        }));
+
+        let rows = meta_query!(meta, |pool| {
+            let mut query = sqlx::query_as::<_, SnapshotRow>(&sql).bind(now());
+            for value in [name, connection_id].into_iter().flatten() {
+                query = query.bind(value);
+            }
+            query.fetch_all(pool).await
+        })?;
+        Ok(rows.into_iter().map(QuerySnapshot::from).collect())
+    }
+
+    /// Gets a live snapshot with its result.
+    pub async fn get(&self, id: &str) -> AppResult<QuerySnapshot> {
+        let (mut snapshot, result) = self.load(id).await?;
+        snapshot.result = Some(result);
+        Ok(snapshot)
+    }
+
+    /// Deletes a snapshot.
+    pub async fn delete(&self, id: &str) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `query_snapshots` WHERE `id` = ?",
+            postgres: r#"DELETE FROM "query_snapshots" WHERE "id" = $1"#,
+            sqlite: r#"DELETE FROM "query_snapshots" WHERE "id" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result == 0 {
+            return Err(AppError::NotFound(format!("Snapshot {}", id)));
+        }
+        tracing::info!(snapshot_id = %id, "Query snapshot deleted");
+        Ok(())
+    }
+
+    /// Compares two snapshots row by row; `truncated` is set if either was truncated.
+    pub async fn compare(&self, req: &CompareSnapshotsRequest) -> AppResult<QueryDiffResult> {
+        let start = std::time::Instant::now();
+        let (left, left_result) = self.load(&req.left_id).await?;
+        let (right, right_result) = self.load(&req.right_id).await?;
+        let mut diff = ResultDiff::compare(&left_result, &right_result, &req.key_columns)?;
+        diff.truncated = left.truncated || right.truncated;
+        diff.duration_ms = start.elapsed().as_millis() as u64;
+        Ok(diff)
+    }
+
+    async fn load(&self, id: &str) -> AppResult<(QuerySnapshot, QueryResult)> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_SNAPSHOT,
+            &Statement {
+                mysql: "WHERE `id` = ? AND `expires_at` > ?",
+                postgres: r#"WHERE "id" = $1 AND "expires_at" > $2"#,
+                sqlite: r#"WHERE "id" = ? AND "expires_at" > ?"#,
+            },
+        ]);
+        let row = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as::<_, SnapshotRow>(&statement)
+                .bind(id)
+                .bind(now())
+                .fetch_optional(pool)
+                .await
+        })?
+            .ok_or_else(|| AppError::NotFound(format!("Snapshot {}", id)))?;
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `result` FROM `query_snapshots` WHERE `id` = ?",
+            postgres: r#"SELECT "result" FROM "query_snapshots" WHERE "id" = $1"#,
+            sqlite: r#"SELECT "result" FROM "query_snapshots" WHERE "id" = ?"#,
+        });
+        let (json,): (String,) = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(id)
+                .fetch_one(pool)
+                .await
+        })?;
+        let result = serde_json::from_str(&json)
+            .map_err(|e| AppError::Internal(format!("Snapshot {} is corrupted: {}", id, e)))?;
+        Ok((QuerySnapshot::from(row), result))
+    }
+
+    /// Deletes all but the newest `keep_last` snapshots of a series.
+    async fn trim_series(&self, connection_id: &str, name: &str, keep_last: u32) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `query_snapshots` WHERE `connection_id` = ? AND `name` = ? AND `id` NOT IN (\
+                 SELECT `id` FROM (SELECT `id` FROM `query_snapshots` WHERE `connection_id` = ? AND `name` = ? \
+                 ORDER BY `created_at` DESC, `id` DESC LIMIT ?) AS `kept`)",
+            postgres: r#"DELETE FROM "query_snapshots" WHERE "connection_id" = $1 AND "name" = $2 AND "id" NOT IN (
+                 SELECT "id" FROM (SELECT "id" FROM "query_snapshots" WHERE "connection_id" = $3 AND "name" = $4
+                 ORDER BY "created_at" DESC, "id" DESC LIMIT $5) AS "kept")"#,
+            sqlite: r#"DELETE FROM "query_snapshots" WHERE "connection_id" = ? AND "name" = ? AND "id" NOT IN (
+                 SELECT "id" FROM (SELECT "id" FROM "query_snapshots" WHERE "connection_id" = ? AND "name" = ?
+                 ORDER BY "created_at" DESC, "id" DESC LIMIT ?) AS "kept")"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(connection_id)
+                .bind(name)
+                .bind(connection_id)
+                .bind(name)
+                .bind(i64::from(keep_last))
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result > 0 {
+            tracing::info!(connection_id, name, deleted = result, "Old query snapshots deleted");
+        }
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `query_snapshots` WHERE `expires_at` <= ?",
+            postgres: r#"DELETE FROM "query_snapshots" WHERE "expires_at" <= $1"#,
+            sqlite: r#"DELETE FROM "query_snapshots" WHERE "expires_at" <= ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(now())
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result > 0 {
+            tracing::info!(deleted = result, "Expired query snapshots deleted");
+        }
+        Ok(())
+    }
+}
+
+fn now() -> String {
+    Utc::now().format(DATETIME_FORMAT).to_string()
+}
@@ -1,37 +1,165 @@
 //! Application state for connection service.
 
 use std::sync::Arc;
-use common::config::AppConfig;
+use common::config::SharedConfig;
 use common::errors::AppResult;
-use sqlx::mysql::MySqlPoolOptions;
+use common::events::EventPublisher;
+use common::jobs::JobStore;
+use common::meta::MetaPool;
+use common::notify::Notifier;
+use common::progress::ProgressHub;
+use crate::alert::AlertManager;
+use crate::api_keys::ApiKeyStore;
+use crate::auth::AuthService;
+use crate::login_throttle::LoginThrottle;
+use crate::autocomplete::AutocompleteCache;
+use crate::backup::BackupManager;
+use crate::backup_storage::{BackupConfig, BackupStorage};
+use crate::favorites::FavoriteStore;
+use crate::health::HealthMonitor;
+use crate::policy::{DenyOverridesEngine, PolicyStore};
 use crate::pool_manager::PoolManager;
+use crate::restore::RestoreManager;
+use crate::revisions::RevisionStore;
+use crate::scheduler::Scheduler;
+use crate::sessions::SessionStore;
+use crate::schema_cache::SchemaCache;
+use crate::schema_change::SchemaChangeManager;
+use crate::snapshot::SnapshotStore;
+use crate::transfer::TransferManager;
+use crate::usage::UsageTracker;
+use crate::warmup::Warmup;
+use crate::workspaces::WorkspaceStore;
 
 /// Application state shared across handlers.
 #[derive(Clone)]
 pub struct AppState {
-    pub config: AppConfig,
+    pub config: Arc<SharedConfig>,
     pub pool_manager: Arc<PoolManager>,
+    pub schema_cache: Arc<SchemaCache>,
+    pub autocomplete: Arc<AutocompleteCache>,
+    pub schema_changes: Arc<SchemaChangeManager>,
+    pub backups: Arc<BackupManager>,
+    pub restores: Arc<RestoreManager>,
+    pub transfers: Arc<TransferManager>,
+    pub snapshots: Arc<SnapshotStore>,
+    pub scheduler: Arc<Scheduler>,
+    pub alerts: Arc<AlertManager>,
+    pub api_keys: Arc<ApiKeyStore>,
+    pub health: Arc<HealthMonitor>,
+    pub policies: Arc<PolicyStore>,
+    pub usage: Arc<UsageTracker>,
+    pub sessions: Arc<SessionStore>,
+    pub auth: Arc<AuthService>,
+    pub login_throttle: Arc<LoginThrottle>,
+    pub warmup: Arc<Warmup>,
+    pub events: Arc<EventPublisher>,
+    pub progress: Arc<ProgressHub>,
+    pub jobs: Arc<JobStore>,
+    pub revisions: Arc<RevisionStore>,
+    pub favorites: Arc<FavoriteStore>,
+    pub workspaces: Arc<WorkspaceStore>,
 }
 
 impl AppState {
     /// Creates a new application state.
-    /// Connects to the metadata MySQL database and initializes the pool manager.
-    pub async fn new(config: AppConfig) -> AppResult<Self> {
-        // Connect to the management MySQL database
-        let meta_pool = MySqlPoolOptions::new()
-            .max_connections(5)
-            .connect(&config.database_url)
-            .await
-            .map_err(|e| common::errors::AppError::DatabaseConnection(
-                format!("Failed to connect to metadata DB ({}): {}", config.database_url, e)
-            ))?;
+    /// Connects to the metadata database (MySQL, PostgreSQL or SQLite), applies pending
+    /// schema migrations and initializes the pool manager.
+    pub async fn new(config: Arc<SharedConfig>) -> AppResult<Self> {
+        // Settings applied only at startup
+        let startup = config.get();
 
-        tracing::info!(url = %config.database_url, "Connected to metadata MySQL database");
+        // Connect to the metadata database; the DATABASE_URL scheme selects MySQL, PostgreSQL or SQLite
+        let meta_pool = MetaPool::connect(&startup.database_url, 5).await?;
 
-        let pool_manager = PoolManager::new(config.clone(), meta_pool).await?;
+        tracing::info!(
+            url = %startup.database_url,
+            backend = meta_pool.backend().as_str(),
+            "Connected to metadata database"
+        );
+        let repository = crate::repository::for_pool(&meta_pool);
+        crate::migrations::run(repository.as_ref()).await?;
+
+        let pool_manager = Arc::new(PoolManager::new(config.clone(), meta_pool, repository));
+        pool_manager.workload().spawn();
+        pool_manager.spawn_probe();
+        pool_manager.spawn_config_watch();
+        let revisions = Arc::new(RevisionStore::new(pool_manager.clone()));
+        let schema_cache = Arc::new(SchemaCache::new(pool_manager.clone()).await);
+        let autocomplete = Arc::new(AutocompleteCache::new(pool_manager.clone(), schema_cache.clone()));
+        let warmup = Arc::new(Warmup::new(pool_manager.clone(), schema_cache.clone()));
+        warmup.spawn();
+        let schema_changes = Arc::new(SchemaChangeManager::new(pool_manager.clone(), schema_cache.clone()));
+        let notifier = Arc::new(Notifier::new(&startup.notifications));
+        let events = Arc::new(EventPublisher::from_env(startup.service_name.clone()).await);
+        let progress = Arc::new(ProgressHub::new());
+        let jobs = Arc::new(JobStore::new(pool_manager.meta_pool().clone()).await?);
+        let storage = BackupStorage::new(BackupConfig::load(&startup.data_dir)?);
+        let backups = Arc::new(
+            BackupManager::new(
+                pool_manager.clone(),
+                storage,
+                notifier.clone(),
+                events.clone(),
+                progress.clone(),
+                jobs.clone(),
+            )
+            .await?,
+        );
+        let restores = Arc::new(RestoreManager::new(
+            pool_manager.clone(),
+            backups.clone(),
+            schema_cache.clone(),
+            progress.clone(),
+            jobs.clone(),
+        ));
+        let transfers = Arc::new(TransferManager::new(pool_manager.clone(), progress.clone(), jobs.clone()));
+        let snapshots = Arc::new(SnapshotStore::new(pool_manager.clone()));
+        snapshots.spawn();
+        let scheduler = Arc::new(Scheduler::new(pool_manager.clone(), backups.clone()).await?);
+        scheduler.spawn();
+        let alerts = Arc::new(AlertManager::new(pool_manager.clone(), notifier.clone()));
+        alerts.spawn();
+        let api_keys = Arc::new(ApiKeyStore::new(pool_manager.clone()));
+        let health = Arc::new(HealthMonitor::new(pool_manager.clone(), notifier.clone()));
+        health.spawn();
+        let engine = DenyOverridesEngine::new(PolicyStore::default_allow_from_env());
+        let policies = Arc::new(PolicyStore::new(pool_manager.clone(), Box::new(engine)).await?);
+        policies.spawn();
+        let usage = Arc::new(UsageTracker::new(pool_manager.clone()).await?);
+        usage.spawn();
+        let favorites = Arc::new(FavoriteStore::new(pool_manager.clone()));
+        let workspaces = Arc::new(WorkspaceStore::new(pool_manager.clone()));
+        let sessions = Arc::new(SessionStore::new(pool_manager.clone()));
+        sessions.spawn();
+        let login_throttle = Arc::new(LoginThrottle::new().await);
+        let auth = Arc::new(AuthService::new(pool_manager.clone(), sessions.clone(), login_throttle.clone()));
 
         Ok(Self {
-            pool_manager: Arc::new(pool_manager),
+            pool_manager,
+            schema_cache,
+            autocomplete,
+            schema_changes,
+            backups,
+            restores,
+            transfers,
+            snapshots,
+            scheduler,
+            alerts,
+            api_keys,
+            health,
+            policies,
+            usage,
+            sessions,
+            auth,
+            login_throttle,
+            warmup,
+            events,
+            progress,
+            jobs,
+            revisions,
+            favorites,
+            workspaces,
             config,
         })
     }
@@ -0,0 +1,202 @@
+//! Table properties from the database catalog.
+//!
+//! Reads row estimates, sizes, the next auto-increment value and per-index
+//! cardinality of one table without scanning it: MySQL from
+//! `information_schema.TABLES` / `STATISTICS`, PostgreSQL from `pg_class`,
+//! `pg_stat_user_tables` and `pg_stats`. All numbers are estimates maintained
+//! by the server and may lag behind until the table is analyzed.
+
+use sqlx::{MySqlPool, PgPool, Row};
+
+use common::errors::{AppError, AppResult};
+use common::models::database::{IndexStats, TableStats};
+use dbm_core::DatabasePool;
+use dbm_core::drivers::mysql::{row_opt_string, row_string};
+use crate::introspection;
+use crate::pool_manager::PoolManager;
+
+/// Loads the statistics of `table`; `database` defaults to the connection's database / `public`.
+pub async fn table_stats(
+    pool_manager: &PoolManager,
+    connection_id: &str,
+    database: Option<&str>,
+    table: &str,
+) -> AppResult<TableStats> {
+    let config = pool_manager
+        .get_connection(connection_id)
+        .await
+        .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+    let schema = introspection::resolve_schema(&config, database)?;
+
+    match pool_manager.get_or_create_pool(connection_id).await? {
+        DatabasePool::MySQL(pool) => mysql_table_stats(&pool, &schema, table).await,
+        DatabasePool::Postgres(pool) => postgres_table_stats(&pool, &schema, table).await,
+        _ => Err(AppError::UnsupportedDatabaseType(
+            "Table statistics are only supported for MySQL and PostgreSQL".into(),
+        )),
+    }
+}
+
+async fn mysql_table_stats(pool: &MySqlPool, database: &str, table: &str) -> AppResult<TableStats> {
+    let row = sqlx::query(
+        "SELECT CAST(TABLE_ROWS AS UNSIGNED) AS table_rows, CAST(DATA_LENGTH AS UNSIGNED) AS data_length,
+                CAST(INDEX_LENGTH AS UNSIGNED) AS index_length, CAST(AUTO_INCREMENT AS UNSIGNED) AS auto_increment,
+                CAST(UPDATE_TIME AS CHAR) AS update_time
+         FROM information_schema.TABLES
+         WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?",
+    )
+    .bind(database)
+    .bind(table)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("table {}.{}", database, table)))?;
+
+    let rows = sqlx::query(
+        "SELECT INDEX_NAME, CAST(NON_UNIQUE AS SIGNED) AS NON_UNIQUE, COLUMN_NAME,
+                CAST(CARDINALITY AS UNSIGNED) AS CARDINALITY
+         FROM information_schema.STATISTICS
+         WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?
+         ORDER BY INDEX_NAME, SEQ_IN_INDEX",
+    )
+    .bind(database)
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+
+    let mut indexes: Vec<IndexStats> = Vec::new();
+    for row in &rows {
+        let name = row_string(row, "INDEX_NAME");
+        // Functional key parts (MySQL 8) have no column name.
+        let column = row_opt_string(row, "COLUMN_NAME").unwrap_or_else(|| "(expression)".into());
+        // The cardinality of the last key part is that of the whole index.
+        let cardinality = row.try_get::<Option<u64>, _>("CARDINALITY").ok().flatten();
+        match indexes.iter_mut().find(|i| i.name == name) {
+            Some(index) => {
+                index.columns.push(column);
+                index.cardinality = cardinality.or(index.cardinality);
+            }
+            None => indexes.push(IndexStats {
+                primary: name == "PRIMARY",
+                unique: row.try_get::<i64, _>("NON_UNIQUE").unwrap_or(1) == 0,
+                name,
+                columns: vec![column],
+                cardinality,
+            }),
+        }
+    }
+
+    let get = |col: &str| row.try_get::<Option<u64>, _>(col).ok().flatten();
+    Ok(TableStats {
+        database: database.to_string(),
+        table: table.to_string(),
+        estimated_rows: get("table_rows").unwrap_or(0),
+        data_bytes: get("data_length"),
+        index_bytes: get("index_length"),
+        auto_increment: get("auto_increment"),
+        updated_at: row_opt_string(&row, "update_time"),
+        analyzed_at: None,
+        indexes,
+    })
+}
+
+async fn postgres_table_stats(pool: &PgPool, schema: &str, table: &str) -> AppResult<TableStats> {
+    let row = sqlx::query(
+        "SELECT c.oid::int8 AS oid, c.reltuples::float8 AS reltuples,
+                pg_table_size(c.oid)::int8 AS data_bytes, pg_indexes_size(c.oid)::int8 AS index_bytes,
+                GREATEST(s.last_analyze, s.last_autoanalyze)::text AS analyzed_at
+         FROM pg_catalog.pg_class c
+         JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+         LEFT JOIN pg_catalog.pg_stat_user_tables s ON s.relid = c.oid
+         WHERE n.nspname = $1 AND c.relname = $2 AND c.relkind IN ('r', 'p', 'm')",
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("table {}.{}", schema, table)))?;
+    let oid: i64 = row.try_get("oid")?;
+    // reltuples is -1 for tables that were never analyzed.
+    let reltuples: f64 = row.try_get("reltuples").unwrap_or(-1.0);
+    let estimated_rows = if reltuples > 0.0 { reltuples as u64 } else { 0 };
+
+    // Next value of the first serial / identity column's sequence.
+    let sequence = sqlx::query(
+        "SELECT seq.last_value::int8 AS last_value
+         FROM pg_catalog.pg_attribute a
+         JOIN pg_catalog.pg_sequences seq
+           ON format('%I.%I', seq.schemaname, seq.sequencename)::regclass
+              = pg_get_serial_sequence(format('%I.%I', $1::text, $2::text), a.attname)::regclass
+         WHERE a.attrelid = $3::int8::oid AND a.attnum > 0 AND NOT a.attisdropped
+         ORDER BY a.attnum
+         LIMIT 1",
+    )
+    .bind(schema)
+    .bind(table)
+    .bind(oid)
+    .fetch_optional(pool)
+    .await?;
+    let auto_increment = sequence.map(|r| {
+        r.try_get::<Option<i64>, _>("last_value")
+            .ok()
+            .flatten()
+            .map_or(1, |v| v.max(0) as u64 + 1)
+    });
+
+    let rows = sqlx::query(
+        "SELECT i.relname AS index_name, ix.indisunique AS is_unique, ix.indisprimary AS is_primary,
+                COALESCE(a.attname::text, pg_get_indexdef(ix.indexrelid, k.ord::int, true)) AS column_name,
+                st.n_distinct::float8 AS n_distinct
+         FROM pg_catalog.pg_index ix
+         JOIN pg_catalog.pg_class i ON i.oid = ix.indexrelid
+         JOIN LATERAL unnest(ix.indkey) WITH ORDINALITY AS k(attnum, ord) ON true
+         LEFT JOIN pg_catalog.pg_attribute a ON a.attrelid = ix.indrelid AND a.attnum = k.attnum
+         LEFT JOIN pg_catalog.pg_stats st ON st.schemaname = $1 AND st.tablename = $2 AND st.attname = a.attname
+         WHERE ix.indrelid = $3::int8::oid
+         ORDER BY i.relname, k.ord",
+    )
+    .bind(schema)
+    .bind(table)
+    .bind(oid)
+    .fetch_all(pool)
+    .await?;
+
+    let mut indexes: Vec<IndexStats> = Vec::new();
+    for row in &rows {
+        let name: String = row.try_get("index_name").unwrap_or_default();
+        let column: String = row.try_get("column_name").unwrap_or_default();
+        match indexes.iter_mut().find(|i| i.name == name) {
+            Some(index) => index.columns.push(column),
+            None => {
+                let unique: bool = row.try_get("is_unique").unwrap_or(false);
+                // Unique indexes have one key per row; others use the leading column's
+                // n_distinct, which is negative when stored as a fraction of the rows.
+                let cardinality = if unique {
+                    Some(estimated_rows)
+                } else {
+                    row.try_get::<Option<f64>, _>("n_distinct").ok().flatten().map(|n| {
+                        if n < 0.0 { (-n * estimated_rows as f64) as u64 } else { n as u64 }
+                    })
+                };
+                indexes.push(IndexStats {
+                    name,
+                    columns: vec![column],
+                    unique,
+                    primary: row.try_get("is_primary").unwrap_or(false),
+                    cardinality,
+                });
+            }
+        }
+    }
+
+    Ok(TableStats {
+        database: schema.to_string(),
+        table: table.to_string(),
+        estimated_rows,
+        data_bytes: row.try_get::<Option<i64>, _>("data_bytes").ok().flatten().map(|v| v.max(0) as u64),
+        index_bytes: row.try_get::<Option<i64>, _>("index_bytes").ok().flatten().map(|v| v.max(0) as u64),
+        auto_increment,
+        updated_at: None,
+        analyzed_at: row.try_get::<Option<String>, _>("analyzed_at").ok().flatten(),
+        indexes,
+    })
+}
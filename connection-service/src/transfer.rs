@@ -0,0 +1,866 @@
+//! Copying table rows between connections.
+//!
+//! A transfer streams the rows of a source table (optionally filtered) into an
+//! existing table of another connection; MySQL, PostgreSQL and SQLite can be
+//! mixed freely:
+//! 1. Columns are matched by name; only columns present in both tables are copied
+//! 2. Source values are read through [`type_mapping`](dbm_core::type_mapping)
+//! 3. Each value is coerced to the kind of its target column
+//! 4. Rows are written with multi-row INSERTs, one transaction per batch
+//!
+//! Batches committed before a failure or cancellation stay in the target table.
+//!
+//! Masking rules of the source connection are not applied to copied rows, so a
+//! caller the rules apply to cannot copy a column they would see masked.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use base64::Engine as _;
+use chrono::Utc;
+use futures::TryStreamExt;
+use serde_json::Value;
+use sqlx::query::Query;
+use sqlx::{Column, Database, Encode, Row, Type, TypeInfo};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::jobs::{CancellationToken, JobStore};
+use common::models::connection::{ConnectionAllowlist, DbType};
+use common::models::masking::ConnectionMasking;
+use common::models::job::{Job, JobKind};
+use common::models::query::ValueKind;
+use common::models::transfer::{TransferJob, TransferRequest, TransferStatus};
+use common::progress::{kinds, ProgressEvent, ProgressHub};
+use common::utils::{quote_ident, SqlValidator};
+use dbm_core::DatabasePool;
+use dbm_core::drivers::mysql::row_string;
+use dbm_core::type_mapping;
+use crate::pool_manager::PoolManager;
+
+/// Default rows inserted per transaction.
+const DEFAULT_BATCH_SIZE: u32 = 1000;
+
+/// Bind parameters per INSERT statement, below the limits of all supported
+/// databases (SQLite 32766, MySQL and PostgreSQL 65535).
+const MAX_BIND_PARAMS: usize = 30000;
+
+/// Tracked job together with its cancellation token.
+struct JobEntry {
+    job: TransferJob,
+    cancelled: CancellationToken,
+}
+
+/// One side of a transfer.
+struct Table {
+    pool: DatabasePool,
+    db_type: DbType,
+    allowlist: Option<ConnectionAllowlist>,
+    /// Database / schema the table lives in.
+    namespace: Option<String>,
+    /// Quoted, qualified name for SQL.
+    name: String,
+    /// Name shown on the job.
+    label: String,
+    columns: Vec<TableColumn>,
+}
+
+/// A column of a transfer table.
+pub(crate) struct TableColumn {
+    pub(crate) name: String,
+    pub(crate) kind: ValueKind,
+    /// Declared type; PostgreSQL INSERT parameters are cast to it.
+    pub(crate) sql_type: String,
+}
+
+/// Everything a running transfer needs.
+struct Plan {
+    source: Table,
+    target: Table,
+    /// Target columns in the order of the SELECT list.
+    columns: Vec<TableColumn>,
+    select: String,
+    batch_size: usize,
+    truncate: bool,
+}
+
+/// A value coerced for binding to a target column.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Cell {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+/// Runs and tracks data transfer jobs.
+pub struct TransferManager {
+    pool_manager: Arc<PoolManager>,
+    progress: Arc<ProgressHub>,
+    job_store: Arc<JobStore>,
+    jobs: RwLock<HashMap<String, JobEntry>>,
+}
+
+impl TransferManager {
+    /// Creates a new transfer manager.
+    pub fn new(pool_manager: Arc<PoolManager>, progress: Arc<ProgressHub>, job_store: Arc<JobStore>) -> Self {
+        Self {
+            pool_manager,
+            progress,
+            job_store,
+            jobs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Validates a transfer and starts copying in the background.
+    ///
+    /// Both tables must exist and share at least one column name; the source
+    /// rows are counted up front, so an invalid filter fails here. `masking`
+    /// holds the source rules that apply to the caller; copying a column they
+    /// mask is refused.
+    pub async fn start(
+        self: &Arc<Self>,
+        req: TransferRequest,
+        masking: Option<&ConnectionMasking>,
+    ) -> AppResult<TransferJob> {
+        let filter = req.filter.as_deref().map(str::trim).filter(|f| !f.is_empty());
+        if filter.is_some_and(|f| f.contains(';')) {
+            return Err(AppError::InvalidInput("filter must be a single condition".into()));
+        }
+
+        let source = self
+            .open(&req.source_connection_id, req.source_database.as_deref(), &req.source_table)
+            .await?;
+        let target = self
+            .open(&req.target_connection_id, req.target_database.as_deref(), &req.target_table)
+            .await?;
+        if req.source_connection_id == req.target_connection_id && source.name == target.name {
+            return Err(AppError::InvalidInput("source and target must be different tables".into()));
+        }
+
+        let mut source_names = Vec::new();
+        let mut columns = Vec::new();
+        for column in &target.columns {
+            if let Some(s) = source.columns.iter().find(|s| s.name.eq_ignore_ascii_case(&column.name)) {
+                if masking.is_some_and(|m| m.strategy_for(&s.name).is_some()) {
+                    return Err(AppError::Forbidden(format!(
+                        "column {} of {} is masked and cannot be copied",
+                        s.name, source.label
+                    )));
+                }
+                source_names.push(quote_ident(&source.db_type, &s.name));
+                columns.push(TableColumn {
+                    name: column.name.clone(),
+                    kind: column.kind,
+                    sql_type: column.sql_type.clone(),
+                });
+            }
+        }
+        if columns.is_empty() {
+            return Err(AppError::InvalidInput(format!(
+                "{} and {} have no columns in common",
+                source.label, target.label
+            )));
+        }
+
+        let condition = filter.map(|f| format!(" WHERE {}", f)).unwrap_or_default();
+        let select = format!("SELECT {} FROM {}{}", source_names.join(", "), source.name, condition);
+        SqlValidator::validate(&select)?;
+        if let Some(allowlist) = &source.allowlist {
+            allowlist.check_sql(&select, source.namespace.as_deref())?;
+        }
+        let count_sql = format!("SELECT COUNT(*) FROM {}{}", source.name, condition);
+        let rows_total = count(&source.pool, &count_sql)
+            .await
+            .map_err(|e| AppError::from(e).with_sql(&count_sql))?;
+
+        {
+            let jobs = self.jobs.read().await;
+            let busy = jobs.values().any(|e| {
+                e.job.target_connection_id == req.target_connection_id
+                    && e.job.target_table == target.label
+                    && !e.job.status.is_finished()
+            });
+            if busy {
+                return Err(AppError::Conflict(format!(
+                    "a transfer into {} is already running",
+                    target.label
+                )));
+            }
+        }
+
+        let now = Utc::now().to_rfc3339();
+        let job = TransferJob {
+            id: Uuid::new_v4().to_string(),
+            source_connection_id: req.source_connection_id.clone(),
+            source_table: source.label.clone(),
+            target_connection_id: req.target_connection_id.clone(),
+            target_table: target.label.clone(),
+            columns: columns.iter().map(|c| c.name.clone()).collect(),
+            status: TransferStatus::Running,
+            rows_total,
+            rows_copied: 0,
+            progress: 0.0,
+            error: None,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        let cancelled = self.job_store.start_cancellable(Job::new(&job.id, JobKind::Transfer)).await?;
+        self.jobs.write().await.insert(
+            job.id.clone(),
+            JobEntry {
+                job: job.clone(),
+                cancelled: cancelled.clone(),
+            },
+        );
+        self.progress.publish(ProgressEvent::new(kinds::TRANSFER, &job.id, &job, false));
+
+        tracing::info!(
+            job_id = %job.id,
+            source = %format!("{}/{}", job.source_connection_id, job.source_table),
+            target = %format!("{}/{}", job.target_connection_id, job.target_table),
+            rows_total,
+            "Data transfer started"
+        );
+
+        let plan = Plan {
+            source,
+            target,
+            columns,
+            select,
+            batch_size: req.batch_size.filter(|n| *n > 0).unwrap_or(DEFAULT_BATCH_SIZE) as usize,
+            truncate: req.truncate_target,
+        };
+        let manager = Arc::clone(self);
+        let job_id = job.id.clone();
+        tokio::spawn(async move {
+            match manager.run(&job_id, &plan, &cancelled).await {
+                Ok(true) => {
+                    manager
+                        .update(&job_id, |j| {
+                            j.status = TransferStatus::Completed;
+                            j.progress = 100.0;
+                        })
+                        .await;
+                    tracing::info!(job_id = %job_id, "Data transfer completed");
+                }
+                Ok(false) => {
+                    manager.update(&job_id, |j| j.status = TransferStatus::Cancelled).await;
+                    tracing::info!(job_id = %job_id, "Data transfer cancelled");
+                }
+                Err(e) => manager.fail(&job_id, e.to_string()).await,
+            }
+        });
+
+        Ok(job)
+    }
+
+    /// Lists transfer jobs (newest first).
+    pub async fn list(&self) -> Vec<TransferJob> {
+        let mut jobs: Vec<TransferJob> = self.jobs.read().await.values().map(|e| e.job.clone()).collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        jobs
+    }
+
+    /// Gets a transfer job by ID.
+    pub async fn get(&self, job_id: &str) -> AppResult<TransferJob> {
+        self.jobs
+            .read()
+            .await
+            .get(job_id)
+            .map(|e| e.job.clone())
+            .ok_or_else(|| AppError::NotFound(format!("transfer job {}", job_id)))
+    }
+
+    /// Asks a running job to stop after its current batch.
+    pub async fn cancel(&self, job_id: &str) -> AppResult<TransferJob> {
+        {
+            let jobs = self.jobs.read().await;
+            let entry = jobs
+                .get(job_id)
+                .ok_or_else(|| AppError::NotFound(format!("transfer job {}", job_id)))?;
+            if entry.job.status.is_finished() {
+                return Err(AppError::Conflict(format!(
+                    "job is {:?} and can no longer be cancelled",
+                    entry.job.status
+                )));
+            }
+            entry.cancelled.cancel();
+        }
+        self.get(job_id).await
+    }
+
+    /// Resolves a table of a connection and loads its columns.
+    async fn open(&self, connection_id: &str, database: Option<&str>, table: &str) -> AppResult<Table> {
+        let config = self
+            .pool_manager
+            .get_connection(connection_id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))?;
+        let namespace = match config.db_type {
+            DbType::MySQL | DbType::MariaDB => database
+                .map(str::to_string)
+                .or_else(|| config.database.clone())
+                .filter(|d| !d.is_empty()),
+            DbType::Postgres => Some(database.unwrap_or("public").to_string()),
+            DbType::SQLite if database.is_some() => {
+                return Err(AppError::InvalidInput("SQLite connections have no databases".into()));
+            }
+            DbType::SQLite => None,
+            _ => {
+                return Err(AppError::UnsupportedDatabaseType(
+                    "Data transfer is only supported for MySQL, PostgreSQL and SQLite".to_string(),
+                ))
+            }
+        };
+        if let Some(allowlist) = &config.allowlist {
+            if !allowlist.allows_table(namespace.as_deref(), table) {
+                return Err(AppError::Forbidden(format!(
+                    "table {} is not in the connection allowlist",
+                    table
+                )));
+            }
+        }
+
+        let pool = self.pool_manager.get_or_create_pool(connection_id).await?;
+        let label = match &namespace {
+            Some(ns) => format!("{}.{}", ns, table),
+            None => table.to_string(),
+        };
+        let columns = load_columns(&pool, namespace.as_deref(), table).await?;
+        if columns.is_empty() {
+            return Err(AppError::NotFound(format!("table {}", label)));
+        }
+        let name = match &namespace {
+            Some(ns) => format!("{}.{}", quote_ident(&config.db_type, ns), quote_ident(&config.db_type, table)),
+            None => quote_ident(&config.db_type, table),
+        };
+
+        Ok(Table {
+            pool,
+            db_type: config.db_type,
+            allowlist: config.allowlist,
+            namespace,
+            name,
+            label,
+            columns,
+        })
+    }
+
+    /// Copies all rows; returns `false` when stopped by a cancellation.
+    async fn run(&self, job_id: &str, plan: &Plan, cancelled: &CancellationToken) -> AppResult<bool> {
+        if plan.truncate {
+            let sql = match plan.target.db_type {
+                DbType::SQLite => format!("DELETE FROM {}", plan.target.name),
+                _ => format!("TRUNCATE TABLE {}", plan.target.name),
+            };
+            execute(&plan.target.pool, &sql).await?;
+        }
+
+        let mut writer = BatchWriter {
+            manager: self,
+            job_id,
+            plan,
+            batch: Vec::with_capacity(plan.batch_size),
+            copied: 0,
+        };
+        match &plan.source.pool {
+            DatabasePool::MySQL(p) => {
+                let mut rows = sqlx::query(&plan.select).fetch(p);
+                while let Some(row) = rows.try_next().await? {
+                    let values = row
+                        .columns()
+                        .iter()
+                        .map(|c| {
+                            let kind = type_mapping::mysql_kind(c.type_info().name());
+                            (type_mapping::mysql_value(&row, c.ordinal(), kind), kind)
+                        })
+                        .collect();
+                    if !writer.push(values, cancelled).await? {
+                        return Ok(false);
+                    }
+                }
+            }
+            DatabasePool::Postgres(p) => {
+                let mut rows = sqlx::query(&plan.select).fetch(p);
+                while let Some(row) = rows.try_next().await? {
+                    let values = row
+                        .columns()
+                        .iter()
+                        .map(|c| {
+                            let kind = type_mapping::postgres_kind(c.type_info().name());
+                            (type_mapping::postgres_value(&row, c.ordinal(), kind), kind)
+                        })
+                        .collect();
+                    if !writer.push(values, cancelled).await? {
+                        return Ok(false);
+                    }
+                }
+            }
+            DatabasePool::SQLite(p) => {
+                let mut rows = sqlx::query(&plan.select).fetch(p);
+                while let Some(row) = rows.try_next().await? {
+                    let values = row
+                        .columns()
+                        .iter()
+                        .map(|c| {
+                            let kind = type_mapping::sqlite_kind(c.type_info().name());
+                            (type_mapping::sqlite_value(&row, c.ordinal(), kind), kind)
+                        })
+                        .collect();
+                    if !writer.push(values, cancelled).await? {
+                        return Ok(false);
+                    }
+                }
+            }
+            _ => unreachable!("open() only accepts SQL databases"),
+        }
+        writer.flush().await?;
+        Ok(true)
+    }
+
+    async fn fail(&self, job_id: &str, error: String) {
+        tracing::error!(job_id = %job_id, error = %error, "Data transfer failed");
+        self.update(job_id, |j| {
+            j.status = TransferStatus::Failed;
+            j.error = Some(error);
+        })
+        .await;
+    }
+
+    async fn update(&self, job_id: &str, f: impl FnOnce(&mut TransferJob)) {
+        let job = {
+            let mut jobs = self.jobs.write().await;
+            let Some(entry) = jobs.get_mut(job_id) else {
+                return;
+            };
+            f(&mut entry.job);
+            entry.job.updated_at = Utc::now().to_rfc3339();
+            let finished = entry.job.status.is_finished();
+            self.progress.publish(ProgressEvent::new(kinds::TRANSFER, job_id, &entry.job, finished));
+            entry.job.clone()
+        };
+        match job.status {
+            TransferStatus::Running => self.job_store.progress(job_id, job.progress).await,
+            TransferStatus::Completed => {
+                let target = format!("{}/{}", job.target_connection_id, job.target_table);
+                self.job_store.finish(job_id, |j| j.complete(Some(target))).await
+            }
+            TransferStatus::Failed => {
+                let error = job.error.unwrap_or_default();
+                self.job_store.finish(job_id, |j| j.fail(error)).await
+            }
+            TransferStatus::Cancelled => self.job_store.finish(job_id, Job::cancel).await,
+        }
+    }
+}
+
+/// Collects coerced rows and writes them to the target table batch by batch.
+struct BatchWriter<'a> {
+    manager: &'a TransferManager,
+    job_id: &'a str,
+    plan: &'a Plan,
+    batch: Vec<Vec<Cell>>,
+    copied: u64,
+}
+
+impl BatchWriter<'_> {
+    /// Adds a source row; returns `false` once a flush notices a cancellation.
+    async fn push(&mut self, values: Vec<(Value, ValueKind)>, cancelled: &CancellationToken) -> AppResult<bool> {
+        let row_number = self.copied + self.batch.len() as u64 + 1;
+        let row = values
+            .into_iter()
+            .zip(&self.plan.columns)
+            .map(|((value, kind), column)| {
+                coerce(value, kind, column.kind, &self.plan.target.db_type).map_err(|e| {
+                    AppError::InvalidInput(format!("row {}, column {}: {}", row_number, column.name, e))
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        self.batch.push(row);
+
+        if self.batch.len() >= self.plan.batch_size {
+            self.flush().await?;
+            if cancelled.is_cancelled() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn flush(&mut self) -> AppResult<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(&mut self.batch);
+        self.copied += rows.len() as u64;
+        let target = &self.plan.target;
+        insert_rows(&target.pool, &target.db_type, &target.name, &self.plan.columns, rows).await?;
+
+        let copied = self.copied;
+        self.manager
+            .update(self.job_id, |j| {
+                j.rows_copied = copied;
+                j.progress = if j.rows_total == 0 {
+                    100.0
+                } else {
+                    (copied as f64 / j.rows_total as f64 * 100.0).min(100.0)
+                };
+            })
+            .await;
+        Ok(())
+    }
+}
+
+// ============== Helpers ==============
+
+/// Loads the writable columns of a table in declaration order (empty if the table does not exist).
+pub(crate) async fn load_columns(pool: &DatabasePool, namespace: Option<&str>, table: &str) -> AppResult<Vec<TableColumn>> {
+    match pool {
+        DatabasePool::MySQL(p) => {
+            let rows = sqlx::query(
+                "SELECT COLUMN_NAME, DATA_TYPE, COLUMN_TYPE FROM information_schema.COLUMNS
+                 WHERE TABLE_SCHEMA = COALESCE(?, DATABASE()) AND TABLE_NAME = ?
+                   AND EXTRA NOT LIKE '%GENERATED%'
+                 ORDER BY ORDINAL_POSITION",
+            )
+            .bind(namespace)
+            .bind(table)
+            .fetch_all(p)
+            .await?;
+            Ok(rows
+                .iter()
+                .map(|r| TableColumn {
+                    name: row_string(r, "COLUMN_NAME"),
+                    kind: mysql_column_kind(&row_string(r, "DATA_TYPE")),
+                    sql_type: row_string(r, "COLUMN_TYPE"),
+                })
+                .collect())
+        }
+        DatabasePool::Postgres(p) => {
+            let rows = sqlx::query(
+                "SELECT a.attname::text AS name, format_type(a.atttypid, a.atttypmod) AS sql_type,
+                        t.typname::text AS type_name
+                 FROM pg_attribute a
+                 JOIN pg_class c ON c.oid = a.attrelid
+                 JOIN pg_namespace n ON n.oid = c.relnamespace
+                 JOIN pg_type t ON t.oid = a.atttypid
+                 WHERE n.nspname = $1 AND c.relname = $2
+                   AND a.attnum > 0 AND NOT a.attisdropped AND a.attgenerated = ''
+                 ORDER BY a.attnum",
+            )
+            .bind(namespace.unwrap_or("public"))
+            .bind(table)
+            .fetch_all(p)
+            .await?;
+            rows.iter()
+                .map(|r| {
+                    let sql_type: String = r.try_get("sql_type")?;
+                    let type_name: String = r.try_get("type_name")?;
+                    Ok(TableColumn {
+                        name: r.try_get("name")?,
+                        kind: postgres_column_kind(&sql_type, &type_name),
+                        sql_type,
+                    })
+                })
+                .collect()
+        }
+        DatabasePool::SQLite(p) => {
+            let rows = sqlx::query("SELECT name, type FROM pragma_table_info(?)")
+                .bind(table)
+                .fetch_all(p)
+                .await?;
+            rows.iter()
+                .map(|r| {
+                    let sql_type: String = r.try_get("type")?;
+                    Ok(TableColumn {
+                        name: r.try_get("name")?,
+                        kind: sqlite_column_kind(&sql_type),
+                        sql_type,
+                    })
+                })
+                .collect()
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Classifies a MySQL column by its `information_schema` data type.
+fn mysql_column_kind(data_type: &str) -> ValueKind {
+    match data_type.to_uppercase().as_str() {
+        "POINT" | "LINESTRING" | "POLYGON" | "MULTIPOINT" | "MULTILINESTRING" | "MULTIPOLYGON"
+        | "GEOMETRYCOLLECTION" => ValueKind::Geometry,
+        name => type_mapping::mysql_kind(name),
+    }
+}
+
+/// Classifies a PostgreSQL column by its formatted type and `pg_type` name.
+fn postgres_column_kind(sql_type: &str, type_name: &str) -> ValueKind {
+    if sql_type.ends_with("[]") {
+        return ValueKind::Json;
+    }
+    match type_name {
+        "geometry" | "geography" => type_mapping::postgres_kind(type_name),
+        _ => type_mapping::postgres_kind(&type_name.to_uppercase()),
+    }
+}
+
+/// Classifies a SQLite column by the affinity of its declared type.
+fn sqlite_column_kind(declared: &str) -> ValueKind {
+    let declared = declared.to_uppercase();
+    if declared.contains("INT") {
+        ValueKind::Integer
+    } else if declared.contains("BOOL") {
+        ValueKind::Boolean
+    } else if declared.contains("BLOB") {
+        ValueKind::Binary
+    } else if ["REAL", "FLOA", "DOUB"].iter().any(|t| declared.contains(t)) {
+        ValueKind::Float
+    } else {
+        ValueKind::Text
+    }
+}
+
+async fn count(pool: &DatabasePool, sql: &str) -> Result<u64, sqlx::Error> {
+    let total: i64 = match pool {
+        DatabasePool::MySQL(p) => sqlx::query(sql).fetch_one(p).await?.try_get(0)?,
+        DatabasePool::Postgres(p) => sqlx::query(sql).fetch_one(p).await?.try_get(0)?,
+        DatabasePool::SQLite(p) => sqlx::query(sql).fetch_one(p).await?.try_get(0)?,
+        _ => 0,
+    };
+    Ok(total.max(0) as u64)
+}
+
+async fn execute(pool: &DatabasePool, sql: &str) -> Result<(), sqlx::Error> {
+    match pool {
+        DatabasePool::MySQL(p) => sqlx::query(sql).execute(p).await.map(|_| ()),
+        DatabasePool::Postgres(p) => sqlx::query(sql).execute(p).await.map(|_| ()),
+        DatabasePool::SQLite(p) => sqlx::query(sql).execute(p).await.map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+/// Inserts rows into a (quoted) table in a single transaction, split into
+/// statements by the bind parameter limit.
+pub(crate) async fn insert_rows(
+    pool: &DatabasePool,
+    db_type: &DbType,
+    table: &str,
+    columns: &[TableColumn],
+    mut rows: Vec<Vec<Cell>>,
+) -> AppResult<()> {
+    let per_statement = (MAX_BIND_PARAMS / columns.len()).max(1);
+    let column_list = columns
+        .iter()
+        .map(|c| quote_ident(db_type, &c.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut statements = Vec::new();
+    while !rows.is_empty() {
+        let rest = rows.split_off(per_statement.min(rows.len()));
+        let chunk = std::mem::replace(&mut rows, rest);
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            table,
+            column_list,
+            placeholders(db_type, columns, chunk.len())
+        );
+        statements.push((sql, chunk));
+    }
+
+    match pool {
+        DatabasePool::MySQL(p) => {
+            let mut tx = p.begin().await?;
+            for (sql, chunk) in statements {
+                bind_cells(sqlx::query(&sql), chunk).execute(&mut *tx).await?;
+            }
+            tx.commit().await?;
+        }
+        DatabasePool::Postgres(p) => {
+            let mut tx = p.begin().await?;
+            for (sql, chunk) in statements {
+                bind_cells(sqlx::query(&sql), chunk).execute(&mut *tx).await?;
+            }
+            tx.commit().await?;
+        }
+        DatabasePool::SQLite(p) => {
+            let mut tx = p.begin().await?;
+            for (sql, chunk) in statements {
+                bind_cells(sqlx::query(&sql), chunk).execute(&mut *tx).await?;
+            }
+            tx.commit().await?;
+        }
+        _ => {
+            return Err(AppError::UnsupportedDatabaseType(
+                "Inserts are only supported for MySQL, PostgreSQL and SQLite".to_string(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Builds the VALUES tuples of a multi-row INSERT.
+///
+/// PostgreSQL parameters are cast to the column type, since values are bound
+/// as text, integers or floats; geometries are sent as WKT.
+fn placeholders(db_type: &DbType, columns: &[TableColumn], rows: usize) -> String {
+    let mut tuples = Vec::with_capacity(rows);
+    let mut n = 0;
+    for _ in 0..rows {
+        let mut values = Vec::with_capacity(columns.len());
+        for column in columns {
+            n += 1;
+            let geometry = column.kind == ValueKind::Geometry;
+            values.push(match db_type {
+                DbType::Postgres if geometry && column.sql_type != "point" => {
+                    format!("ST_GeomFromText(${})::{}", n, column.sql_type)
+                }
+                DbType::Postgres => format!("${}::{}", n, column.sql_type),
+                DbType::MySQL | DbType::MariaDB if geometry => "ST_GeomFromText(?)".to_string(),
+                _ => "?".to_string(),
+            });
+        }
+        tuples.push(format!("({})", values.join(", ")));
+    }
+    tuples.join(", ")
+}
+
+fn bind_cells<'q, DB>(
+    mut query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    rows: Vec<Vec<Cell>>,
+) -> Query<'q, DB, <DB as Database>::Arguments<'q>>
+where
+    DB: Database,
+    Option<String>: Encode<'q, DB> + Type<DB>,
+    String: Encode<'q, DB> + Type<DB>,
+    bool: Encode<'q, DB> + Type<DB>,
+    i64: Encode<'q, DB> + Type<DB>,
+    f64: Encode<'q, DB> + Type<DB>,
+    Vec<u8>: Encode<'q, DB> + Type<DB>,
+{
+    for cell in rows.into_iter().flatten() {
+        query = match cell {
+            Cell::Null => query.bind(None::<String>),
+            Cell::Bool(b) => query.bind(b),
+            Cell::Int(i) => query.bind(i),
+            Cell::Float(f) => query.bind(f),
+            Cell::Text(s) => query.bind(s),
+            Cell::Bytes(b) => query.bind(b),
+        };
+    }
+    query
+}
+
+/// Converts a source value (as produced by `type_mapping`) for a target column.
+pub(crate) fn coerce(value: Value, source: ValueKind, target: ValueKind, target_db: &DbType) -> Result<Cell, String> {
+    let cell = match (value, target) {
+        (Value::Null, _) => Cell::Null,
+        (Value::String(s), ValueKind::Binary) if source == ValueKind::Binary => Cell::Bytes(
+            base64::engine::general_purpose::STANDARD
+                .decode(s)
+                .map_err(|e| format!("invalid binary value: {}", e))?,
+        ),
+        (Value::String(s), ValueKind::Binary) => Cell::Bytes(s.into_bytes()),
+        (value, ValueKind::Binary) => Cell::Bytes(value.to_string().into_bytes()),
+        (Value::Bool(b), ValueKind::Boolean) => Cell::Bool(b),
+        (Value::Number(n), ValueKind::Boolean) => Cell::Bool(n.as_f64() != Some(0.0)),
+        (Value::String(s), ValueKind::Boolean) => {
+            Cell::Bool(parse_bool(&s).ok_or_else(|| format!("`{}` is not a boolean", s))?)
+        }
+        (Value::Bool(b), ValueKind::Integer) => Cell::Int(b as i64),
+        (Value::Number(n), ValueKind::Integer) => {
+            integer(&n.to_string()).ok_or_else(|| format!("{} is not an integer", n))?
+        }
+        (Value::String(s), ValueKind::Integer) => {
+            integer(s.trim()).ok_or_else(|| format!("`{}` is not an integer", s))?
+        }
+        (Value::Bool(b), ValueKind::Float) => Cell::Float(if b { 1.0 } else { 0.0 }),
+        (Value::Number(n), ValueKind::Float) => Cell::Float(n.as_f64().unwrap_or_default()),
+        (Value::String(s), ValueKind::Float) => Cell::Float(
+            s.trim().parse().map_err(|_| format!("`{}` is not a number", s))?,
+        ),
+        (Value::Bool(b), ValueKind::Decimal) => Cell::Text(if b { "1" } else { "0" }.to_string()),
+        (value, ValueKind::Json) if source == ValueKind::Json => Cell::Text(value.to_string()),
+        // Text holding a JSON document is kept as is; anything else becomes a JSON string.
+        (Value::String(s), ValueKind::Json) if serde_json::from_str::<Value>(&s).is_ok() => Cell::Text(s),
+        (value, ValueKind::Json) => Cell::Text(value.to_string()),
+        // MySQL rejects the ISO 8601 `T` separator and `Z` suffix in DATETIME/TIMESTAMP literals.
+        (Value::String(s), ValueKind::DateTime | ValueKind::DateTimeTz) if target_db.is_mysql_family() => {
+            Cell::Text(s.trim_end_matches('Z').replacen('T', " ", 1))
+        }
+        (Value::String(s), _) => Cell::Text(s),
+        (value, _) => Cell::Text(value.to_string()),
+    };
+    Ok(cell)
+}
+
+/// Parses an integer; values beyond `i64` (MySQL `BIGINT UNSIGNED`) stay text.
+fn integer(text: &str) -> Option<Cell> {
+    if let Ok(i) = text.parse::<i64>() {
+        return Some(Cell::Int(i));
+    }
+    if text.parse::<u64>().is_ok() {
+        return Some(Cell::Text(text.to_string()));
+    }
+    text.parse::<f64>()
+        .ok()
+        .filter(|f| f.fract() == 0.0 && f.abs() < i64::MAX as f64)
+        .map(|f| Cell::Int(f as i64))
+}
+
+fn parse_bool(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "true" | "t" | "yes" | "y" | "on" | "1" => Some(true),
+        "false" | "f" | "no" | "n" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn coerces_values_to_target_kinds() {
+        let pg = DbType::Postgres;
+        assert_eq!(coerce(json!("42"), ValueKind::Text, ValueKind::Integer, &pg), Ok(Cell::Int(42)));
+        assert_eq!(coerce(json!(3.0), ValueKind::Float, ValueKind::Integer, &pg), Ok(Cell::Int(3)));
+        assert_eq!(coerce(json!(1), ValueKind::Integer, ValueKind::Boolean, &pg), Ok(Cell::Bool(true)));
+        assert_eq!(coerce(json!("no"), ValueKind::Text, ValueKind::Boolean, &pg), Ok(Cell::Bool(false)));
+        assert_eq!(coerce(json!("aGk="), ValueKind::Binary, ValueKind::Binary, &pg), Ok(Cell::Bytes(b"hi".to_vec())));
+        assert_eq!(coerce(json!({"a": 1}), ValueKind::Json, ValueKind::Json, &pg), Ok(Cell::Text(r#"{"a":1}"#.into())));
+        assert_eq!(coerce(json!("plain"), ValueKind::Text, ValueKind::Json, &pg), Ok(Cell::Text(r#""plain""#.into())));
+        assert_eq!(coerce(Value::Null, ValueKind::Text, ValueKind::Integer, &pg), Ok(Cell::Null));
+        assert!(coerce(json!("abc"), ValueKind::Text, ValueKind::Integer, &pg).is_err());
+    }
+
+    #[test]
+    fn normalizes_timestamps_for_mysql() {
+        let value = json!("2024-01-31T13:45:00Z");
+        assert_eq!(
+            coerce(value.clone(), ValueKind::DateTimeTz, ValueKind::DateTime, &DbType::MySQL),
+            Ok(Cell::Text("2024-01-31 13:45:00".into()))
+        );
+        assert_eq!(
+            coerce(value, ValueKind::DateTimeTz, ValueKind::DateTimeTz, &DbType::Postgres),
+            Ok(Cell::Text("2024-01-31T13:45:00Z".into()))
+        );
+    }
+
+    #[test]
+    fn casts_postgres_placeholders() {
+        let columns = vec![
+            TableColumn { name: "id".into(), kind: ValueKind::Integer, sql_type: "integer".into() },
+            TableColumn { name: "geom".into(), kind: ValueKind::Geometry, sql_type: "geometry(Point,4326)".into() },
+        ];
+        assert_eq!(
+            placeholders(&DbType::Postgres, &columns, 2),
+            "($1::integer, ST_GeomFromText($2)::geometry(Point,4326)), ($3::integer, ST_GeomFromText($4)::geometry(Point,4326))"
+        );
+        assert_eq!(placeholders(&DbType::MySQL, &columns, 1), "(?, ST_GeomFromText(?))");
+    }
+}
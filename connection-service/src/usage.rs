@@ -0,0 +1,377 @@
+//! Per-user usage accounting and quotas.
+//!
+//! Statements executed through the query endpoints are counted per principal
+//! (the `X-Principal` the gateway sets after verifying an API key) and UTC
+//! day in the `user_usage` metadata table: statements, rows scanned (rows
+//! returned plus rows affected) and execution time. Requests without a
+//! principal are neither counted nor limited.
+//!
+//! Before a statement runs, the principal's usage today is compared with its
+//! quota: its own row in `user_quotas`, else the default quota from the
+//! environment. A used-up quota rejects the statement with `QUOTA_EXCEEDED`
+//! until the next UTC midnight. Usage is recorded after the statement
+//! succeeds, so the statement that crosses a limit still completes.
+//!
+//! Configuration:
+//! - `USAGE_QUOTA_QUERIES` - default statements per day (unset = unlimited)
+//! - `USAGE_QUOTA_ROWS_SCANNED` - default rows scanned per day (unset = unlimited)
+//! - `USAGE_QUOTA_EXECUTION_MS` - default execution time per day in ms (unset = unlimited)
+//! - `USAGE_RETENTION_DAYS` - days of usage kept (default: 90)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use tokio::sync::RwLock;
+
+use common::errors::{AppError, AppResult};
+use common::meta::{self, Statement};
+use common::meta_query;
+use common::models::query::QueryResult;
+use common::models::usage::{QuotaExceeded, UsageCounters, UsageQuota, UsageReport, UserUsage};
+
+use crate::pool_manager::PoolManager;
+
+const DEFAULT_RETENTION_DAYS: u64 = 90;
+
+/// Interval of the usage cleanup.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Longest principal stored.
+const MAX_PRINCIPAL_CHARS: usize = 128;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Row from the `user_quotas` metadata table.
+#[derive(sqlx::FromRow)]
+struct QuotaRow {
+    principal: String,
+    max_queries: Option<meta::Unsigned<u64>>,
+    max_rows_scanned: Option<meta::Unsigned<u64>>,
+    max_execution_ms: Option<meta::Unsigned<u64>>,
+}
+
+/// Row from the `user_usage` metadata table.
+#[derive(sqlx::FromRow)]
+struct UsageRow {
+    principal: String,
+    queries: meta::Unsigned<u64>,
+    rows_scanned: meta::Unsigned<u64>,
+    execution_ms: meta::Unsigned<u64>,
+}
+
+impl UsageRow {
+    fn counters(&self) -> UsageCounters {
+        UsageCounters {
+            queries: self.queries.0,
+            rows_scanned: self.rows_scanned.0,
+            execution_ms: self.execution_ms.0,
+        }
+    }
+}
+
+/// Usage recorder and quota checker.
+pub struct UsageTracker {
+    pool_manager: Arc<PoolManager>,
+    default_quota: UsageQuota,
+    retention_days: u64,
+    /// Quotas of principals with their own, reloaded after every change.
+    quotas: RwLock<HashMap<String, UsageQuota>>,
+}
+
+impl UsageTracker {
+    /// Creates the tracker and loads the quotas.
+    pub async fn new(pool_manager: Arc<PoolManager>) -> AppResult<Self> {
+        let env = |key: &str| std::env::var(key).ok().and_then(|v| v.parse::<u64>().ok());
+        let tracker = Self {
+            pool_manager,
+            default_quota: UsageQuota {
+                max_queries: env("USAGE_QUOTA_QUERIES"),
+                max_rows_scanned: env("USAGE_QUOTA_ROWS_SCANNED"),
+                max_execution_ms: env("USAGE_QUOTA_EXECUTION_MS"),
+            },
+            retention_days: env("USAGE_RETENTION_DAYS").unwrap_or(DEFAULT_RETENTION_DAYS),
+            quotas: RwLock::new(HashMap::new()),
+        };
+        tracker.reload().await?;
+        Ok(tracker)
+    }
+
+    /// Starts the periodic cleanup of old usage.
+    pub fn spawn(self: &Arc<Self>) {
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = tracker.prune().await {
+                    tracing::warn!(error = %e, "Usage cleanup failed");
+                }
+            }
+        });
+    }
+
+    /// Rejects the request with `QUOTA_EXCEEDED` when the principal's quota is used up.
+    pub async fn check(&self, principal: Option<&str>) -> AppResult<()> {
+        let Some(principal) = principal else {
+            return Ok(());
+        };
+        let (quota, _) = self.quota(principal).await;
+        if quota.is_unlimited() {
+            return Ok(());
+        }
+        let today = Utc::now().date_naive();
+        let usage = self.counters(principal, today).await?;
+        match quota.exceeded(&usage) {
+            Some((limit, used, allowed)) => {
+                tracing::info!(principal, limit, used, allowed, "Usage quota exceeded");
+                Err(AppError::QuotaExceeded(Box::new(QuotaExceeded {
+                    principal: principal.to_string(),
+                    limit: limit.to_string(),
+                    used,
+                    allowed,
+                    resets_at: resets_at(today),
+                })))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Adds an executed statement to the principal's usage today.
+    ///
+    /// Failures are logged: the statement has already run.
+    pub async fn record(&self, principal: Option<&str>, result: &QueryResult) {
+        let Some(principal) = principal else {
+            return;
+        };
+        let rows = result.row_count as u64 + result.affected_rows.unwrap_or(0);
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `user_usage` (`principal`, `usage_date`, `queries`, `rows_scanned`, `execution_ms`) \
+                 VALUES (?, ?, 1, ?, ?) \
+                 ON DUPLICATE KEY UPDATE `queries` = `queries` + 1, \
+                 `rows_scanned` = `rows_scanned` + VALUES(`rows_scanned`), \
+                 `execution_ms` = `execution_ms` + VALUES(`execution_ms`)",
+            postgres: r#"INSERT INTO "user_usage" ("principal", "usage_date", "queries", "rows_scanned", "execution_ms")
+                 VALUES ($1, $2, 1, $3, $4)
+                 ON CONFLICT ("principal", "usage_date") DO UPDATE SET "queries" = "user_usage"."queries" + 1,
+                 "rows_scanned" = "user_usage"."rows_scanned" + EXCLUDED."rows_scanned",
+                 "execution_ms" = "user_usage"."execution_ms" + EXCLUDED."execution_ms""#,
+            sqlite: r#"INSERT INTO "user_usage" ("principal", "usage_date", "queries", "rows_scanned", "execution_ms")
+                 VALUES (?, ?, 1, ?, ?)
+                 ON CONFLICT ("principal", "usage_date") DO UPDATE SET "queries" = "queries" + 1,
+                 "rows_scanned" = "rows_scanned" + excluded."rows_scanned",
+                 "execution_ms" = "execution_ms" + excluded."execution_ms""#,
+        });
+        let recorded = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(principal)
+                .bind(Utc::now().date_naive().format(DATE_FORMAT).to_string())
+                .bind(meta::int(rows))
+                .bind(meta::int(result.execution_time_ms))
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        });
+        if let Err(e) = recorded {
+            tracing::warn!(principal, error = %e, "Failed to record usage");
+        }
+    }
+
+    /// Returns the principal's usage today with its quota.
+    pub async fn usage(&self, principal: &str) -> AppResult<UserUsage> {
+        let today = Utc::now().date_naive();
+        let usage = self.counters(principal, today).await?;
+        let (quota, custom_quota) = self.quota(principal).await;
+        Ok(UserUsage {
+            principal: principal.to_string(),
+            date: today,
+            usage,
+            quota,
+            custom_quota,
+            resets_at: resets_at(today),
+        })
+    }
+
+    /// Returns the usage of all principals on a day (default: today).
+    pub async fn report(&self, date: Option<NaiveDate>) -> AppResult<UsageReport> {
+        let date = date.unwrap_or_else(|| Utc::now().date_naive());
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `principal`, `queries`, `rows_scanned`, `execution_ms` FROM `user_usage` \
+                 WHERE `usage_date` = ? ORDER BY `queries` DESC, `principal`",
+            postgres: r#"SELECT "principal", "queries", "rows_scanned", "execution_ms" FROM "user_usage"
+                 WHERE "usage_date" = $1 ORDER BY "queries" DESC, "principal""#,
+            sqlite: r#"SELECT "principal", "queries", "rows_scanned", "execution_ms" FROM "user_usage"
+                 WHERE "usage_date" = ? ORDER BY "queries" DESC, "principal""#,
+        });
+        let rows: Vec<UsageRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(date.format(DATE_FORMAT).to_string())
+                .fetch_all(pool)
+                .await
+        })?;
+
+        let mut total = UsageCounters::default();
+        let mut users = Vec::with_capacity(rows.len());
+        for row in rows {
+            let usage = row.counters();
+            total.queries += usage.queries;
+            total.rows_scanned += usage.rows_scanned;
+            total.execution_ms += usage.execution_ms;
+            let (quota, custom_quota) = self.quota(&row.principal).await;
+            users.push(UserUsage {
+                principal: row.principal,
+                date,
+                usage,
+                quota,
+                custom_quota,
+                resets_at: resets_at(date),
+            });
+        }
+        Ok(UsageReport {
+            date,
+            default_quota: self.default_quota,
+            users,
+            total,
+        })
+    }
+
+    /// Sets the principal's own quota, replacing the default for it.
+    pub async fn set_quota(&self, principal: &str, quota: UsageQuota) -> AppResult<UserUsage> {
+        if principal.is_empty() || principal.chars().count() > MAX_PRINCIPAL_CHARS {
+            return Err(AppError::InvalidInput(format!(
+                "Principal must be 1-{} characters",
+                MAX_PRINCIPAL_CHARS
+            )));
+        }
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `user_quotas` (`principal`, `max_queries`, `max_rows_scanned`, `max_execution_ms`, `updated_at`) \
+                 VALUES (?, ?, ?, ?, ?) \
+                 ON DUPLICATE KEY UPDATE `max_queries` = VALUES(`max_queries`), \
+                 `max_rows_scanned` = VALUES(`max_rows_scanned`), `max_execution_ms` = VALUES(`max_execution_ms`), \
+                 `updated_at` = VALUES(`updated_at`)",
+            postgres: r#"INSERT INTO "user_quotas" ("principal", "max_queries", "max_rows_scanned", "max_execution_ms", "updated_at")
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT ("principal") DO UPDATE SET "max_queries" = EXCLUDED."max_queries",
+                 "max_rows_scanned" = EXCLUDED."max_rows_scanned", "max_execution_ms" = EXCLUDED."max_execution_ms",
+                 "updated_at" = EXCLUDED."updated_at""#,
+            sqlite: r#"INSERT INTO "user_quotas" ("principal", "max_queries", "max_rows_scanned", "max_execution_ms", "updated_at")
+                 VALUES (?, ?, ?, ?, ?)
+                 ON CONFLICT ("principal") DO UPDATE SET "max_queries" = excluded."max_queries",
+                 "max_rows_scanned" = excluded."max_rows_scanned", "max_execution_ms" = excluded."max_execution_ms",
+                 "updated_at" = excluded."updated_at""#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(principal)
+                .bind(quota.max_queries.map(meta::int))
+                .bind(quota.max_rows_scanned.map(meta::int))
+                .bind(quota.max_execution_ms.map(meta::int))
+                .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+
+        self.reload().await?;
+        tracing::info!(principal, ?quota, "Usage quota set");
+        self.usage(principal).await
+    }
+
+    /// Removes the principal's own quota; the default applies again.
+    pub async fn delete_quota(&self, principal: &str) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `user_quotas` WHERE `principal` = ?",
+            postgres: r#"DELETE FROM "user_quotas" WHERE "principal" = $1"#,
+            sqlite: r#"DELETE FROM "user_quotas" WHERE "principal" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(principal)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if result == 0 {
+            return Err(AppError::NotFound(format!("Usage quota of {}", principal)));
+        }
+
+        self.reload().await?;
+        tracing::info!(principal, "Usage quota removed");
+        Ok(())
+    }
+
+    /// Effective quota of a principal and whether it is the principal's own.
+    async fn quota(&self, principal: &str) -> (UsageQuota, bool) {
+        match self.quotas.read().await.get(principal) {
+            Some(quota) => (*quota, true),
+            None => (self.default_quota, false),
+        }
+    }
+
+    async fn counters(&self, principal: &str, date: NaiveDate) -> AppResult<UsageCounters> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `principal`, `queries`, `rows_scanned`, `execution_ms` FROM `user_usage` \
+                 WHERE `principal` = ? AND `usage_date` = ?",
+            postgres: r#"SELECT "principal", "queries", "rows_scanned", "execution_ms" FROM "user_usage"
+                 WHERE "principal" = $1 AND "usage_date" = $2"#,
+            sqlite: r#"SELECT "principal", "queries", "rows_scanned", "execution_ms" FROM "user_usage"
+                 WHERE "principal" = ? AND "usage_date" = ?"#,
+        });
+        let row: Option<UsageRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(principal)
+                .bind(date.format(DATE_FORMAT).to_string())
+                .fetch_optional(pool)
+                .await
+        })?;
+        Ok(row.map(|r| r.counters()).unwrap_or_default())
+    }
+
+    async fn reload(&self) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `principal`, `max_queries`, `max_rows_scanned`, `max_execution_ms` FROM `user_quotas`",
+            postgres: r#"SELECT "principal", "max_queries", "max_rows_scanned", "max_execution_ms" FROM "user_quotas""#,
+            sqlite: r#"SELECT "principal", "max_queries", "max_rows_scanned", "max_execution_ms" FROM "user_quotas""#,
+        });
+        let rows: Vec<QuotaRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .fetch_all(pool)
+                .await
+        })?;
+        *self.quotas.write().await = rows
+            .into_iter()
+            .map(|r| {
+                let quota = UsageQuota {
+                    max_queries: r.max_queries.map(|n| n.0),
+                    max_rows_scanned: r.max_rows_scanned.map(|n| n.0),
+                    max_execution_ms: r.max_execution_ms.map(|n| n.0),
+                };
+                (r.principal, quota)
+            })
+            .collect();
+        Ok(())
+    }
+
+    async fn prune(&self) -> AppResult<()> {
+        let cutoff = Utc::now().date_naive() - Days::new(self.retention_days);
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `user_usage` WHERE `usage_date` < ?",
+            postgres: r#"DELETE FROM "user_usage" WHERE "usage_date" < $1"#,
+            sqlite: r#"DELETE FROM "user_usage" WHERE "usage_date" < ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(cutoff.format(DATE_FORMAT).to_string())
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        Ok(())
+    }
+}
+
+/// Start of the UTC day after `date`, when its counters stop applying.
+fn resets_at(date: NaiveDate) -> DateTime<Utc> {
+    (date + Days::new(1)).and_time(chrono::NaiveTime::MIN).and_utc()
+}
@@ -0,0 +1,133 @@
+//! Startup warm-up of pinned connections.
+//!
+//! When the service starts, a background task opens the pool of every pinned
+//! connection and loads its schema metadata into the schema cache, so the
+//! first request of the day does not pay for either. Pinned connections are
+//! warmed first, one after another; pools of the remaining saved connections
+//! are restored afterwards. Progress is published through the readiness
+//! endpoint (`GET /api/health/ready`), which reports ready once every pinned
+//! connection has been warmed or has failed.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::Utc;
+use tokio::sync::RwLock;
+
+use common::models::connection::ConnectionConfig;
+use common::models::monitor::{WarmupEntry, WarmupState, WarmupStatus};
+use crate::pool_manager::PoolManager;
+use crate::schema_cache::SchemaCache;
+
+/// Warms pinned connections at service start and tracks the progress.
+pub struct Warmup {
+    pool_manager: Arc<PoolManager>,
+    schema_cache: Arc<SchemaCache>,
+    status: RwLock<WarmupStatus>,
+}
+
+impl Warmup {
+    pub fn new(pool_manager: Arc<PoolManager>, schema_cache: Arc<SchemaCache>) -> Self {
+        Self {
+            pool_manager,
+            schema_cache,
+            status: RwLock::new(WarmupStatus {
+                ready: false,
+                completed: 0,
+                total: 0,
+                started_at: Utc::now().to_rfc3339(),
+                finished_at: None,
+                connections: Vec::new(),
+            }),
+        }
+    }
+
+    /// Starts the warm-up task.
+    pub fn spawn(self: &Arc<Self>) {
+        let warmup = self.clone();
+        tokio::spawn(async move { warmup.run().await });
+    }
+
+    /// Returns the current warm-up progress.
+    pub async fn status(&self) -> WarmupStatus {
+        self.status.read().await.clone()
+    }
+
+    async fn run(&self) {
+        let (pinned, others): (Vec<_>, Vec<_>) = self
+            .pool_manager
+            .list_connections()
+            .await
+            .into_iter()
+            .partition(|c| c.pinned);
+
+        {
+            let mut status = self.status.write().await;
+            status.total = pinned.len();
+            status.connections = pinned.iter().map(pending_entry).collect();
+        }
+        tracing::info!(pinned = pinned.len(), others = others.len(), "开始预热常驻连接");
+
+        for (index, config) in pinned.iter().enumerate() {
+            self.update(index, |entry| entry.state = WarmupState::Warming).await;
+            let started = Instant::now();
+            let result = self.warm(config).await;
+            let duration_ms = started.elapsed().as_millis() as u64;
+            match &result {
+                Ok(tables) => tracing::info!(id = %config.id, tables, duration_ms, "常驻连接预热完成"),
+                Err(e) => tracing::warn!(id = %config.id, error = %e, "常驻连接预热失败"),
+            }
+            self.update(index, |entry| {
+                entry.duration_ms = Some(duration_ms);
+                match result {
+                    Ok(tables) => {
+                        entry.state = WarmupState::Ready;
+                        entry.tables = Some(tables);
+                    }
+                    Err(e) => {
+                        entry.state = WarmupState::Failed;
+                        entry.error = Some(e.to_string());
+                    }
+                }
+            })
+            .await;
+            self.status.write().await.completed += 1;
+        }
+
+        {
+            let mut status = self.status.write().await;
+            status.ready = true;
+            status.finished_at = Some(Utc::now().to_rfc3339());
+        }
+
+        for config in &others {
+            if let Err(e) = self.pool_manager.restore_pool(config).await {
+                tracing::warn!(id = %config.id, error = %e, "Saved connection pool creation failed (will retry on test)");
+            }
+        }
+    }
+
+    /// Opens the pool and loads the schema; returns the number of tables.
+    async fn warm(&self, config: &ConnectionConfig) -> common::errors::AppResult<usize> {
+        self.pool_manager.restore_pool(config).await?;
+        let schema = self.schema_cache.get_table_schema(&config.id, false).await?;
+        Ok(schema.tables.len())
+    }
+
+    async fn update(&self, index: usize, apply: impl FnOnce(&mut WarmupEntry)) {
+        if let Some(entry) = self.status.write().await.connections.get_mut(index) {
+            apply(entry);
+        }
+    }
+}
+
+fn pending_entry(config: &ConnectionConfig) -> WarmupEntry {
+    WarmupEntry {
+        connection_id: config.id.clone(),
+        name: config.name.clone(),
+        state: WarmupState::Pending,
+        tables: None,
+        duration_ms: None,
+        error: None,
+    }
+}
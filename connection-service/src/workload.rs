@@ -0,0 +1,356 @@
+//! Statement workload statistics.
+//!
+//! Counts the statements executed through the service per connection, hour,
+//! statement type and referenced table. Counters are accumulated in memory and
+//! flushed to the `workload_stats` metadata table periodically (and before a
+//! breakdown is read); buckets older than the retention period are purged.
+//!
+//! Each statement is stored once with an empty table name (type totals) and
+//! once per referenced table, so per-type totals are not inflated by joins.
+//!
+//! Configuration:
+//! - `WORKLOAD_FLUSH_INTERVAL_SECS` - flush interval (default: 30)
+//! - `WORKLOAD_RETENTION_DAYS` - days of statistics kept (default: 30)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Duration as ChronoDuration, DurationRound, Utc};
+use tokio::sync::Mutex;
+
+use common::errors::AppResult;
+use common::meta::{self, MetaBackend, MetaPool, Statement, Unsigned};
+use common::meta_query;
+use common::models::workload::{
+    StatementType, StatementTypeStats, TableWorkloadStats, WorkloadBreakdown, WorkloadBucket,
+};
+use common::utils::SqlTableExtractor;
+
+const DEFAULT_FLUSH_INTERVAL_SECS: u64 = 30;
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+/// Only the head of a statement is scanned for table names; bulk INSERTs
+/// name their table long before the values.
+const TABLE_SCAN_CHARS: usize = 4096;
+/// Tables returned in a breakdown.
+const TOP_TABLES: i64 = 50;
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Summed counter from the `workload_stats` table.
+type Count = Unsigned<u64>;
+
+/// Aggregation key of a counter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CounterKey {
+    connection_id: String,
+    bucket_start: DateTime<Utc>,
+    statement_type: StatementType,
+    /// Empty for the per-statement total.
+    table: String,
+}
+
+#[derive(Debug, Default)]
+struct Counter {
+    executions: u64,
+    errors: u64,
+    total_ms: u64,
+}
+
+/// Workload statistics recorder and reader.
+pub struct WorkloadStats {
+    meta_pool: MetaPool,
+    pending: Mutex<HashMap<CounterKey, Counter>>,
+    flush_interval: Duration,
+    retention: ChronoDuration,
+}
+
+impl WorkloadStats {
+    /// Creates the recorder.
+    pub fn new(meta_pool: MetaPool) -> Self {
+        let env = |key: &str, default: i64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+
+        Self {
+            meta_pool,
+            pending: Mutex::new(HashMap::new()),
+            flush_interval: Duration::from_secs(
+                env("WORKLOAD_FLUSH_INTERVAL_SECS", DEFAULT_FLUSH_INTERVAL_SECS as i64).max(1) as u64,
+            ),
+            retention: ChronoDuration::days(env("WORKLOAD_RETENTION_DAYS", DEFAULT_RETENTION_DAYS)),
+        }
+    }
+
+    /// Starts the periodic flush task.
+    pub fn spawn(self: &Arc<Self>) {
+        let stats = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(stats.flush_interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = stats.flush().await {
+                    tracing::warn!(error = %e, "Workload statistics flush failed");
+                }
+            }
+        });
+    }
+
+    /// Records one executed statement.
+    pub async fn record(&self, connection_id: &str, sql: &str, elapsed: Duration, success: bool) {
+        let statement_type = StatementType::classify(sql);
+        let head = match sql.char_indices().nth(TABLE_SCAN_CHARS) {
+            Some((end, _)) => &sql[..end],
+            None => sql,
+        };
+        let tables = SqlTableExtractor::extract(head).into_iter().map(|t| match t.database {
+            Some(db) => format!("{}.{}", db, t.table),
+            None => t.table,
+        });
+
+        let bucket_start = Utc::now()
+            .duration_trunc(ChronoDuration::hours(1))
+            .unwrap_or_else(|_| Utc::now());
+        let elapsed_ms = elapsed.as_millis() as u64;
+
+        let mut pending = self.pending.lock().await;
+        for table in std::iter::once(String::new()).chain(tables) {
+            let key = CounterKey {
+                connection_id: connection_id.to_string(),
+                bucket_start,
+                statement_type,
+                table: truncate_name(table),
+            };
+            let counter = pending.entry(key).or_default();
+            counter.executions += 1;
+            counter.errors += u64::from(!success);
+            counter.total_ms += elapsed_ms;
+        }
+    }
+
+    /// Writes pending counters to the metadata table and purges expired buckets.
+    pub async fn flush(&self) -> AppResult<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().await);
+        if !pending.is_empty() {
+            let statement = self.meta_pool.sql(&Statement {
+                mysql: "INSERT INTO `workload_stats` \
+                     (`connection_id`, `bucket_start`, `statement_type`, `table_name`, `executions`, `errors`, `total_ms`) \
+                     VALUES (?, ?, ?, ?, ?, ?, ?) \
+                     ON DUPLICATE KEY UPDATE `executions` = `executions` + VALUES(`executions`), \
+                     `errors` = `errors` + VALUES(`errors`), `total_ms` = `total_ms` + VALUES(`total_ms`)",
+                postgres: r#"INSERT INTO "workload_stats"
+                     ("connection_id", "bucket_start", "statement_type", "table_name", "executions", "errors", "total_ms")
+                     VALUES ($1, $2, $3, $4, $5, $6, $7)
+                     ON CONFLICT ("connection_id", "bucket_start", "statement_type", "table_name")
+                     DO UPDATE SET "executions" = "workload_stats"."executions" + EXCLUDED."executions",
+                     "errors" = "workload_stats"."errors" + EXCLUDED."errors", "total_ms" = "workload_stats"."total_ms" + EXCLUDED."total_ms""#,
+                sqlite: r#"INSERT INTO "workload_stats"
+                     ("connection_id", "bucket_start", "statement_type", "table_name", "executions", "errors", "total_ms")
+                     VALUES (?, ?, ?, ?, ?, ?, ?)
+                     ON CONFLICT ("connection_id", "bucket_start", "statement_type", "table_name")
+                     DO UPDATE SET "executions" = "executions" + excluded."executions",
+                     "errors" = "errors" + excluded."errors", "total_ms" = "total_ms" + excluded."total_ms""#,
+            });
+            meta_query!(&self.meta_pool, |pool| {
+                let mut tx = pool.begin().await?;
+                for (key, counter) in &pending {
+                    sqlx::query(statement)
+                        .bind(&key.connection_id)
+                        .bind(key.bucket_start.format(TIME_FORMAT).to_string())
+                        .bind(key.statement_type.as_str())
+                        .bind(&key.table)
+                        .bind(meta::int(counter.executions))
+                        .bind(meta::int(counter.errors))
+                        .bind(meta::int(counter.total_ms))
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                tx.commit().await?;
+            });
+        }
+
+        let statement = self.meta_pool.sql(&Statement {
+            mysql: "DELETE FROM `workload_stats` WHERE `bucket_start` < ?",
+            postgres: r#"DELETE FROM "workload_stats" WHERE "bucket_start" < $1"#,
+            sqlite: r#"DELETE FROM "workload_stats" WHERE "bucket_start" < ?"#,
+        });
+        meta_query!(&self.meta_pool, |pool| {
+            sqlx::query(statement)
+                .bind((Utc::now() - self.retention).format(TIME_FORMAT).to_string())
+                .execute(pool)
+                .await?;
+        });
+        Ok(())
+    }
+
+    /// Returns the workload breakdown of a connection over the last `hours`.
+    pub async fn breakdown(&self, connection_id: &str, hours: u32, daily: bool) -> AppResult<WorkloadBreakdown> {
+        self.flush().await?;
+
+        let to = Utc::now();
+        let from = to
+            .duration_trunc(ChronoDuration::hours(1))
+            .unwrap_or(to)
+            - ChronoDuration::hours(i64::from(hours.saturating_sub(1)));
+        let from_str = from.format(TIME_FORMAT).to_string();
+
+        let statement = self.meta_pool.sql(&Statement {
+            mysql: "SELECT `statement_type`, CAST(SUM(`executions`) AS UNSIGNED), CAST(SUM(`errors`) AS UNSIGNED), \
+                 CAST(SUM(`total_ms`) AS UNSIGNED) FROM `workload_stats` \
+                 WHERE `connection_id` = ? AND `bucket_start` >= ? AND `table_name` = '' \
+                 GROUP BY `statement_type`",
+            postgres: r#"SELECT "statement_type", CAST(SUM("executions") AS BIGINT), CAST(SUM("errors") AS BIGINT),
+                 CAST(SUM("total_ms") AS BIGINT) FROM "workload_stats"
+                 WHERE "connection_id" = $1 AND "bucket_start" >= $2 AND "table_name" = ''
+                 GROUP BY "statement_type""#,
+            sqlite: r#"SELECT "statement_type", CAST(SUM("executions") AS INTEGER), CAST(SUM("errors") AS INTEGER),
+                 CAST(SUM("total_ms") AS INTEGER) FROM "workload_stats"
+                 WHERE "connection_id" = ? AND "bucket_start" >= ? AND "table_name" = ''
+                 GROUP BY "statement_type""#,
+        });
+        let type_rows: Vec<(String, Count, Count, Count)> = meta_query!(&self.meta_pool, |pool| {
+            sqlx::query_as(statement)
+                .bind(connection_id)
+                .bind(&from_str)
+                .fetch_all(pool)
+                .await
+        })?;
+
+        let statement = self.meta_pool.sql(&Statement {
+            mysql: "SELECT `table_name`, `statement_type`, CAST(SUM(`executions`) AS UNSIGNED) AS executions, \
+                 CAST(SUM(`total_ms`) AS UNSIGNED) FROM `workload_stats` \
+                 WHERE `connection_id` = ? AND `bucket_start` >= ? AND `table_name` <> '' \
+                 GROUP BY `table_name`, `statement_type` ORDER BY executions DESC LIMIT ?",
+            postgres: r#"SELECT "table_name", "statement_type", CAST(SUM("executions") AS BIGINT) AS executions,
+                 CAST(SUM("total_ms") AS BIGINT) FROM "workload_stats"
+                 WHERE "connection_id" = $1 AND "bucket_start" >= $2 AND "table_name" <> ''
+                 GROUP BY "table_name", "statement_type" ORDER BY executions DESC LIMIT $3"#,
+            sqlite: r#"SELECT "table_name", "statement_type", CAST(SUM("executions") AS INTEGER) AS executions,
+                 CAST(SUM("total_ms") AS INTEGER) FROM "workload_stats"
+                 WHERE "connection_id" = ? AND "bucket_start" >= ? AND "table_name" <> ''
+                 GROUP BY "table_name", "statement_type" ORDER BY executions DESC LIMIT ?"#,
+        });
+        let table_rows: Vec<(String, String, Count, Count)> = meta_query!(&self.meta_pool, |pool| {
+            sqlx::query_as(statement)
+                .bind(connection_id)
+                .bind(&from_str)
+                .bind(TOP_TABLES)
+                .fetch_all(pool)
+                .await
+        })?;
+
+        let format = if daily { "%Y-%m-%d 00:00:00" } else { "%Y-%m-%d %H:00:00" };
+        let bucket_expr = match self.meta_pool.backend() {
+            MetaBackend::MySql => format!("DATE_FORMAT(`bucket_start`, '{}')", format),
+            MetaBackend::Postgres => format!(
+                r#"to_char(CAST("bucket_start" AS TIMESTAMP), '{}')"#,
+                if daily { "YYYY-MM-DD 00:00:00" } else { "YYYY-MM-DD HH24:00:00" }
+            ),
+            MetaBackend::Sqlite => format!(r#"strftime('{}', "bucket_start")"#, format),
+        };
+        let statement = format!(
+            "SELECT {} AS bucket, {}",
+            bucket_expr,
+            self.meta_pool.sql(&Statement {
+                mysql: "`statement_type`, CAST(SUM(`executions`) AS UNSIGNED), \
+                     CAST(SUM(`errors`) AS UNSIGNED), CAST(SUM(`total_ms`) AS UNSIGNED) FROM `workload_stats` \
+                     WHERE `connection_id` = ? AND `bucket_start` >= ? AND `table_name` = '' \
+                     GROUP BY bucket, `statement_type` ORDER BY bucket",
+                postgres: r#""statement_type", CAST(SUM("executions") AS BIGINT),
+                     CAST(SUM("errors") AS BIGINT), CAST(SUM("total_ms") AS BIGINT) FROM "workload_stats"
+                     WHERE "connection_id" = $1 AND "bucket_start" >= $2 AND "table_name" = ''
+                     GROUP BY bucket, "statement_type" ORDER BY bucket"#,
+                sqlite: r#""statement_type", CAST(SUM("executions") AS INTEGER),
+                     CAST(SUM("errors") AS INTEGER), CAST(SUM("total_ms") AS INTEGER) FROM "workload_stats"
+                     WHERE "connection_id" = ? AND "bucket_start" >= ? AND "table_name" = ''
+                     GROUP BY bucket, "statement_type" ORDER BY bucket"#,
+            }),
+        );
+        let timeline_rows: Vec<(String, String, Count, Count, Count)> = meta_query!(&self.meta_pool, |pool| {
+            sqlx::query_as(&statement)
+                .bind(connection_id)
+                .bind(&from_str)
+                .fetch_all(pool)
+                .await
+        })?;
+
+        let by_type = sorted_type_stats(
+            type_rows
+                .into_iter()
+                .filter_map(|(t, executions, errors, total_ms)| {
+                    Some(type_stats(StatementType::parse(&t)?, executions.0, errors.0, total_ms.0))
+                })
+                .collect(),
+        );
+
+        let by_table = table_rows
+            .into_iter()
+            .filter_map(|(table, t, executions, total_time_ms)| {
+                Some(TableWorkloadStats {
+                    table,
+                    statement_type: StatementType::parse(&t)?,
+                    executions: executions.0,
+                    total_time_ms: total_time_ms.0,
+                })
+            })
+            .collect();
+
+        let mut timeline: Vec<WorkloadBucket> = Vec::new();
+        for (bucket, t, executions, errors, total_ms) in timeline_rows {
+            let Some(statement_type) = StatementType::parse(&t) else {
+                continue;
+            };
+            let stats = type_stats(statement_type, executions.0, errors.0, total_ms.0);
+            match timeline.last_mut() {
+                Some(last) if last.bucket_start == bucket => last.by_type.push(stats),
+                _ => timeline.push(WorkloadBucket {
+                    bucket_start: bucket,
+                    by_type: vec![stats],
+                }),
+            }
+        }
+        for bucket in &mut timeline {
+            bucket.by_type = sorted_type_stats(std::mem::take(&mut bucket.by_type));
+        }
+
+        Ok(WorkloadBreakdown {
+            connection_id: connection_id.to_string(),
+            from: from_str,
+            to: to.format(TIME_FORMAT).to_string(),
+            granularity: if daily { "day" } else { "hour" }.to_string(),
+            by_type,
+            by_table,
+            timeline,
+        })
+    }
+}
+
+fn type_stats(statement_type: StatementType, executions: u64, errors: u64, total_ms: u64) -> StatementTypeStats {
+    StatementTypeStats {
+        statement_type,
+        executions,
+        errors,
+        total_time_ms: total_ms,
+        avg_time_ms: if executions > 0 { total_ms as f64 / executions as f64 } else { 0.0 },
+    }
+}
+
+/// Orders statistics by `StatementType::ALL`.
+fn sorted_type_stats(mut stats: Vec<StatementTypeStats>) -> Vec<StatementTypeStats> {
+    stats.sort_by_key(|s| StatementType::ALL.iter().position(|t| *t == s.statement_type));
+    stats
+}
+
+/// Keeps table names within the column width.
+fn truncate_name(mut name: String) -> String {
+    if name.len() > 255 {
+        let mut end = 255;
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        name.truncate(end);
+    }
+    name
+}
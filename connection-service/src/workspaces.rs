@@ -0,0 +1,504 @@
+//! Workspaces.
+//!
+//! A workspace groups connections and saved queries (query snapshots) for a
+//! team sharing one deployment. Workspaces, their members and the grouped
+//! resources are kept in the `workspaces`, `workspace_members` and
+//! `workspace_resources` metadata tables.
+//!
+//! The creator becomes the first owner. Owners rename and delete the
+//! workspace and manage members; every member can add and remove resources
+//! and select the workspace (`X-Workspace-Id`) to narrow the connection and
+//! snapshot lists. Principals outside a workspace see it as nonexistent; the
+//! admin token grants access to every workspace.
+//!
+//! Grouping does not change access: a connection is only listed when the
+//! caller could see it anyway.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::meta::Statement;
+use common::meta_query;
+use common::models::workspace::{
+    AddWorkspaceMemberRequest, CreateWorkspaceRequest, UpdateWorkspaceRequest, Workspace, WorkspaceDetail,
+    WorkspaceMember, WorkspaceResourceKind, WorkspaceRole,
+};
+
+use crate::pool_manager::PoolManager;
+use crate::service::Viewer;
+
+const SELECT_WORKSPACE: Statement = Statement {
+    mysql: "SELECT w.`id`, w.`name`, w.`description`, w.`created_by`, \
+         CAST(w.`created_at` AS CHAR) AS created_at, CAST(w.`updated_at` AS CHAR) AS updated_at \
+         FROM `workspaces` w",
+    postgres: r#"SELECT w."id", w."name", w."description", w."created_by",
+         w."created_at", w."updated_at"
+         FROM "workspaces" w"#,
+    sqlite: r#"SELECT w."id", w."name", w."description", w."created_by",
+         w."created_at", w."updated_at"
+         FROM "workspaces" w"#,
+};
+
+/// Row from the `workspaces` metadata table.
+#[derive(sqlx::FromRow)]
+struct WorkspaceRow {
+    id: String,
+    name: String,
+    description: Option<String>,
+    created_by: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+impl From<WorkspaceRow> for Workspace {
+    fn from(row: WorkspaceRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            created_by: row.created_by,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Row from the `workspace_members` metadata table.
+#[derive(sqlx::FromRow)]
+struct MemberRow {
+    principal: String,
+    role: String,
+    added_at: String,
+}
+
+impl From<MemberRow> for WorkspaceMember {
+    fn from(row: MemberRow) -> Self {
+        Self {
+            principal: row.principal,
+            role: row.role.parse().unwrap_or_default(),
+            added_at: row.added_at,
+        }
+    }
+}
+
+/// Workspaces, their members and grouped resources.
+pub struct WorkspaceStore {
+    pool_manager: Arc<PoolManager>,
+}
+
+impl WorkspaceStore {
+    /// Creates the store.
+    pub fn new(pool_manager: Arc<PoolManager>) -> Self {
+        Self { pool_manager }
+    }
+
+    /// Creates a workspace owned by `principal`.
+    pub async fn create(&self, req: CreateWorkspaceRequest, principal: &str) -> AppResult<Workspace> {
+        let id = Uuid::new_v4().to_string();
+        let meta = self.pool_manager.meta_pool();
+        let insert_workspace = meta.sql(&Statement {
+            mysql: "INSERT INTO `workspaces` (`id`, `name`, `description`, `created_by`, `created_at`, `updated_at`) \
+                 VALUES (?, ?, ?, ?, UTC_TIMESTAMP(), UTC_TIMESTAMP())",
+            postgres: r#"INSERT INTO "workspaces" ("id", "name", "description", "created_by", "created_at", "updated_at")
+                 VALUES ($1, $2, $3, $4, to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'), to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'))"#,
+            sqlite: r#"INSERT INTO "workspaces" ("id", "name", "description", "created_by", "created_at", "updated_at")
+                 VALUES (?, ?, ?, ?, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)"#,
+        });
+        let insert_owner = meta.sql(&Statement {
+            mysql: "INSERT INTO `workspace_members` (`workspace_id`, `principal`, `role`, `added_at`) \
+                 VALUES (?, ?, ?, UTC_TIMESTAMP())",
+            postgres: r#"INSERT INTO "workspace_members" ("workspace_id", "principal", "role", "added_at")
+                 VALUES ($1, $2, $3, to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'))"#,
+            sqlite: r#"INSERT INTO "workspace_members" ("workspace_id", "principal", "role", "added_at")
+                 VALUES (?, ?, ?, CURRENT_TIMESTAMP)"#,
+        });
+        meta_query!(meta, |pool| {
+            let mut tx = pool.begin().await?;
+            sqlx::query(insert_workspace)
+                .bind(&id)
+                .bind(req.name.trim())
+                .bind(req.description.as_deref().filter(|d| !d.is_empty()))
+                .bind(principal)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(insert_owner)
+                .bind(&id)
+                .bind(principal)
+                .bind(WorkspaceRole::Owner.as_str())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+        });
+        self.find(&id).await
+    }
+
+    /// Lists the workspaces the caller is a member of (all with the admin token), by name.
+    pub async fn list(&self, viewer: Viewer<'_>) -> AppResult<Vec<Workspace>> {
+        let rows: Vec<WorkspaceRow> = if viewer.admin {
+            let statement = self.pool_manager.meta_pool().concat(&[
+                &SELECT_WORKSPACE,
+                &Statement {
+                    mysql: "ORDER BY w.`name`",
+                    postgres: r#"ORDER BY w."name""#,
+                    sqlite: r#"ORDER BY w."name""#,
+                },
+            ]);
+            meta_query!(self.pool_manager.meta_pool(), |pool| {
+                sqlx::query_as(&statement)
+                    .fetch_all(pool)
+                    .await
+            })?
+        } else {
+            let Some(principal) = viewer.principal else {
+                return Ok(Vec::new());
+            };
+            let statement = self.pool_manager.meta_pool().concat(&[
+                &SELECT_WORKSPACE,
+                &Statement {
+                    mysql: "JOIN `workspace_members` m ON m.`workspace_id` = w.`id` WHERE m.`principal` = ? ORDER BY w.`name`",
+                    postgres: r#"JOIN "workspace_members" m ON m."workspace_id" = w."id" WHERE m."principal" = $1 ORDER BY w."name""#,
+                    sqlite: r#"JOIN "workspace_members" m ON m."workspace_id" = w."id" WHERE m."principal" = ? ORDER BY w."name""#,
+                },
+            ]);
+            meta_query!(self.pool_manager.meta_pool(), |pool| {
+                sqlx::query_as(&statement)
+                    .bind(principal)
+                    .fetch_all(pool)
+                    .await
+            })?
+        };
+        Ok(rows.into_iter().map(Workspace::from).collect())
+    }
+
+    /// Gets a workspace with its members and resources.
+    pub async fn get(&self, id: &str, viewer: Viewer<'_>) -> AppResult<WorkspaceDetail> {
+        self.authorize(id, viewer, WorkspaceRole::Member).await?;
+        Ok(WorkspaceDetail {
+            workspace: self.find(id).await?,
+            members: self.members(id).await?,
+            connection_ids: self.resources(id, WorkspaceResourceKind::Connection).await?,
+            snapshot_ids: self.resources(id, WorkspaceResourceKind::Snapshot).await?,
+        })
+    }
+
+    /// Renames or re-describes a workspace (owners only).
+    pub async fn update(&self, id: &str, req: UpdateWorkspaceRequest, viewer: Viewer<'_>) -> AppResult<Workspace> {
+        self.authorize(id, viewer, WorkspaceRole::Owner).await?;
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "UPDATE `workspaces` SET `name` = COALESCE(?, `name`), \
+                 `description` = CASE WHEN ? IS NULL THEN `description` ELSE NULLIF(?, '') END, \
+                 `updated_at` = UTC_TIMESTAMP() \
+                 WHERE `id` = ?",
+            postgres: r#"UPDATE "workspaces" SET "name" = COALESCE($1, "name"),
+                 "description" = CASE WHEN $2 IS NULL THEN "description" ELSE NULLIF($3, '') END,
+                 "updated_at" = to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')
+                 WHERE "id" = $4"#,
+            sqlite: r#"UPDATE "workspaces" SET "name" = COALESCE(?, "name"),
+                 "description" = CASE WHEN ? IS NULL THEN "description" ELSE NULLIF(?, '') END,
+                 "updated_at" = CURRENT_TIMESTAMP
+                 WHERE "id" = ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(req.name.as_deref().map(str::trim))
+                .bind(&req.description)
+                .bind(&req.description)
+                .bind(id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        self.find(id).await
+    }
+
+    /// Deletes a workspace with its memberships and groupings (owners only).
+    ///
+    /// The grouped connections and snapshots themselves are kept.
+    pub async fn delete(&self, id: &str, viewer: Viewer<'_>) -> AppResult<()> {
+        self.authorize(id, viewer, WorkspaceRole::Owner).await?;
+        let meta = self.pool_manager.meta_pool();
+        let statements = [
+            meta.sql(&Statement {
+                mysql: "DELETE FROM `workspace_resources` WHERE `workspace_id` = ?",
+                postgres: r#"DELETE FROM "workspace_resources" WHERE "workspace_id" = $1"#,
+                sqlite: r#"DELETE FROM "workspace_resources" WHERE "workspace_id" = ?"#,
+            }),
+            meta.sql(&Statement {
+                mysql: "DELETE FROM `workspace_members` WHERE `workspace_id` = ?",
+                postgres: r#"DELETE FROM "workspace_members" WHERE "workspace_id" = $1"#,
+                sqlite: r#"DELETE FROM "workspace_members" WHERE "workspace_id" = ?"#,
+            }),
+            meta.sql(&Statement {
+                mysql: "DELETE FROM `workspaces` WHERE `id` = ?",
+                postgres: r#"DELETE FROM "workspaces" WHERE "id" = $1"#,
+                sqlite: r#"DELETE FROM "workspaces" WHERE "id" = ?"#,
+            }),
+        ];
+        meta_query!(meta, |pool| {
+            let mut tx = pool.begin().await?;
+            for statement in &statements {
+                sqlx::query(statement).bind(id).execute(&mut *tx).await?;
+            }
+            tx.commit().await?;
+        });
+        Ok(())
+    }
+
+    /// Adds a member or changes a member's role (owners only).
+    ///
+    /// # Errors
+    /// `Conflict` when the change would leave the workspace without an owner.
+    pub async fn add_member(
+        &self,
+        id: &str,
+        req: AddWorkspaceMemberRequest,
+        viewer: Viewer<'_>,
+    ) -> AppResult<Vec<WorkspaceMember>> {
+        self.authorize(id, viewer, WorkspaceRole::Owner).await?;
+        let principal = req.principal.trim();
+        if req.role != WorkspaceRole::Owner {
+            self.ensure_other_owner(id, principal).await?;
+        }
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT INTO `workspace_members` (`workspace_id`, `principal`, `role`, `added_at`) \
+                 VALUES (?, ?, ?, UTC_TIMESTAMP()) ON DUPLICATE KEY UPDATE `role` = VALUES(`role`)",
+            postgres: r#"INSERT INTO "workspace_members" ("workspace_id", "principal", "role", "added_at")
+                 VALUES ($1, $2, $3, to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')) ON CONFLICT ("workspace_id", "principal") DO UPDATE SET "role" = EXCLUDED."role""#,
+            sqlite: r#"INSERT INTO "workspace_members" ("workspace_id", "principal", "role", "added_at")
+                 VALUES (?, ?, ?, CURRENT_TIMESTAMP) ON CONFLICT ("workspace_id", "principal") DO UPDATE SET "role" = excluded."role""#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(id)
+                .bind(principal)
+                .bind(req.role.as_str())
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        self.members(id).await
+    }
+
+    /// Removes a member; owners may remove anyone, members only themselves.
+    ///
+    /// # Errors
+    /// `Conflict` when the last owner would be removed.
+    pub async fn remove_member(&self, id: &str, principal: &str, viewer: Viewer<'_>) -> AppResult<Vec<WorkspaceMember>> {
+        let required = if viewer.principal == Some(principal) {
+            WorkspaceRole::Member
+        } else {
+            WorkspaceRole::Owner
+        };
+        self.authorize(id, viewer, required).await?;
+        self.ensure_other_owner(id, principal).await?;
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `workspace_members` WHERE `workspace_id` = ? AND `principal` = ?",
+            postgres: r#"DELETE FROM "workspace_members" WHERE "workspace_id" = $1 AND "principal" = $2"#,
+            sqlite: r#"DELETE FROM "workspace_members" WHERE "workspace_id" = ? AND "principal" = ?"#,
+        });
+        let removed = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(id)
+                .bind(principal)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        if removed == 0 {
+            return Err(AppError::NotFound(format!("member {} of workspace {}", principal, id)));
+        }
+        self.members(id).await
+    }
+
+    /// Groups a resource in a workspace (members only); grouping it again is a no-op.
+    pub async fn add_resource(
+        &self,
+        id: &str,
+        kind: WorkspaceResourceKind,
+        resource_id: &str,
+        viewer: Viewer<'_>,
+    ) -> AppResult<()> {
+        self.authorize(id, viewer, WorkspaceRole::Member).await?;
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "INSERT IGNORE INTO `workspace_resources` (`workspace_id`, `kind`, `resource_id`, `added_at`) \
+                 VALUES (?, ?, ?, UTC_TIMESTAMP())",
+            postgres: r#"INSERT INTO "workspace_resources" ("workspace_id", "kind", "resource_id", "added_at")
+                 VALUES ($1, $2, $3, to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')) ON CONFLICT DO NOTHING"#,
+            sqlite: r#"INSERT OR IGNORE INTO "workspace_resources" ("workspace_id", "kind", "resource_id", "added_at")
+                 VALUES (?, ?, ?, CURRENT_TIMESTAMP)"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(id)
+                .bind(kind.as_str())
+                .bind(resource_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        Ok(())
+    }
+
+    /// Removes a resource from a workspace (members only).
+    pub async fn remove_resource(
+        &self,
+        id: &str,
+        kind: WorkspaceResourceKind,
+        resource_id: &str,
+        viewer: Viewer<'_>,
+    ) -> AppResult<()> {
+        self.authorize(id, viewer, WorkspaceRole::Member).await?;
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `workspace_resources` WHERE `workspace_id` = ? AND `kind` = ? AND `resource_id` = ?",
+            postgres: r#"DELETE FROM "workspace_resources" WHERE "workspace_id" = $1 AND "kind" = $2 AND "resource_id" = $3"#,
+            sqlite: r#"DELETE FROM "workspace_resources" WHERE "workspace_id" = ? AND "kind" = ? AND "resource_id" = ?"#,
+        });
+        meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(id)
+                .bind(kind.as_str())
+                .bind(resource_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        })?;
+        Ok(())
+    }
+
+    /// IDs of the resources of one kind grouped in the caller's active workspace.
+    pub async fn resource_ids(
+        &self,
+        id: &str,
+        kind: WorkspaceResourceKind,
+        viewer: Viewer<'_>,
+    ) -> AppResult<HashSet<String>> {
+        self.authorize(id, viewer, WorkspaceRole::Member).await?;
+        Ok(self.resources(id, kind).await?.into_iter().collect())
+    }
+
+    /// Removes a deleted resource from every workspace.
+    pub async fn forget(&self, kind: WorkspaceResourceKind, resource_id: &str) {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "DELETE FROM `workspace_resources` WHERE `kind` = ? AND `resource_id` = ?",
+            postgres: r#"DELETE FROM "workspace_resources" WHERE "kind" = $1 AND "resource_id" = $2"#,
+            sqlite: r#"DELETE FROM "workspace_resources" WHERE "kind" = ? AND "resource_id" = ?"#,
+        });
+        let result = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query(statement)
+                .bind(kind.as_str())
+                .bind(resource_id)
+                .execute(pool)
+                .await
+                .map(|r| r.rows_affected())
+        });
+        if let Err(e) = result {
+            tracing::warn!(kind = kind.as_str(), resource_id, error = %e, "Failed to remove resource from workspaces");
+        }
+    }
+
+    /// Checks that the caller holds at least `required` in the workspace.
+    ///
+    /// Non-members get `NotFound` so workspace IDs are not disclosed; members
+    /// lacking the owner role get `Forbidden`.
+    async fn authorize(&self, id: &str, viewer: Viewer<'_>, required: WorkspaceRole) -> AppResult<()> {
+        let not_found = || AppError::NotFound(format!("workspace {}", id));
+        if viewer.admin {
+            return self.find(id).await.map(|_| ());
+        }
+        let principal = viewer.principal.ok_or_else(not_found)?;
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `role` FROM `workspace_members` WHERE `workspace_id` = ? AND `principal` = ?",
+            postgres: r#"SELECT "role" FROM "workspace_members" WHERE "workspace_id" = $1 AND "principal" = $2"#,
+            sqlite: r#"SELECT "role" FROM "workspace_members" WHERE "workspace_id" = ? AND "principal" = ?"#,
+        });
+        let role: Option<(String,)> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(id)
+                .bind(principal)
+                .fetch_optional(pool)
+                .await
+        })?;
+        let role: WorkspaceRole = role.ok_or_else(not_found)?.0.parse().unwrap_or_default();
+        if required == WorkspaceRole::Owner && role != WorkspaceRole::Owner {
+            return Err(AppError::Forbidden(format!("only owners of workspace {} may do this", id)));
+        }
+        Ok(())
+    }
+
+    /// Fails when `principal` is the workspace's only owner.
+    async fn ensure_other_owner(&self, id: &str, principal: &str) -> AppResult<()> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT COUNT(*) FROM `workspace_members` WHERE `workspace_id` = ? AND `role` = ? AND `principal` <> ?",
+            postgres: r#"SELECT COUNT(*) FROM "workspace_members" WHERE "workspace_id" = $1 AND "role" = $2 AND "principal" <> $3"#,
+            sqlite: r#"SELECT COUNT(*) FROM "workspace_members" WHERE "workspace_id" = ? AND "role" = ? AND "principal" <> ?"#,
+        });
+        let (others,): (i64,) = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(id)
+                .bind(WorkspaceRole::Owner.as_str())
+                .bind(principal)
+                .fetch_one(pool)
+                .await
+        })?;
+        if others == 0 {
+            return Err(AppError::Conflict(format!("workspace {} must keep at least one owner", id)));
+        }
+        Ok(())
+    }
+
+    async fn find(&self, id: &str) -> AppResult<Workspace> {
+        let statement = self.pool_manager.meta_pool().concat(&[
+            &SELECT_WORKSPACE,
+            &Statement {
+                mysql: "WHERE w.`id` = ?",
+                postgres: r#"WHERE w."id" = $1"#,
+                sqlite: r#"WHERE w."id" = ?"#,
+            },
+        ]);
+        let row: Option<WorkspaceRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(&statement)
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+        })?;
+        row.map(Workspace::from)
+            .ok_or_else(|| AppError::NotFound(format!("workspace {}", id)))
+    }
+
+    async fn members(&self, id: &str) -> AppResult<Vec<WorkspaceMember>> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `principal`, `role`, CAST(`added_at` AS CHAR) AS added_at FROM `workspace_members` \
+                 WHERE `workspace_id` = ? ORDER BY `role` = 'owner' DESC, `added_at`",
+            postgres: r#"SELECT "principal", "role", "added_at" FROM "workspace_members"
+                 WHERE "workspace_id" = $1 ORDER BY "role" = 'owner' DESC, "added_at""#,
+            sqlite: r#"SELECT "principal", "role", "added_at" FROM "workspace_members"
+                 WHERE "workspace_id" = ? ORDER BY "role" = 'owner' DESC, "added_at""#,
+        });
+        let rows: Vec<MemberRow> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(id)
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(WorkspaceMember::from).collect())
+    }
+
+    async fn resources(&self, id: &str, kind: WorkspaceResourceKind) -> AppResult<Vec<String>> {
+        let statement = self.pool_manager.meta_pool().sql(&Statement {
+            mysql: "SELECT `resource_id` FROM `workspace_resources` WHERE `workspace_id` = ? AND `kind` = ? ORDER BY `added_at`",
+            postgres: r#"SELECT "resource_id" FROM "workspace_resources" WHERE "workspace_id" = $1 AND "kind" = $2 ORDER BY "added_at""#,
+            sqlite: r#"SELECT "resource_id" FROM "workspace_resources" WHERE "workspace_id" = ? AND "kind" = ? ORDER BY "added_at""#,
+        });
+        let rows: Vec<(String,)> = meta_query!(self.pool_manager.meta_pool(), |pool| {
+            sqlx::query_as(statement)
+                .bind(id)
+                .bind(kind.as_str())
+                .fetch_all(pool)
+                .await
+        })?;
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+}
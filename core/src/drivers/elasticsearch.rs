@@ -0,0 +1,419 @@
+//! Elasticsearch connections.
+//!
+//! Elasticsearch is reached over its REST API. Indices are listed as
+//! databases and their mappings as the schema (one table per index, nested
+//! fields as dotted column names). A query is either Query DSL — a JSON
+//! object, run as a `_search` on the requested index — or SQL run through the
+//! `_sql` API; both are mapped into a [`QueryResult`].
+//!
+//! The connection's `host` may include the scheme (`https://es.example.com`);
+//! without one, plain HTTP is used. Hidden indices (names starting with `.`)
+//! are not listed.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use reqwest::Method;
+use serde_json::{json, Map, Value};
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::ConnectionConfig;
+use common::models::database::{ColumnDetail, TableInfo};
+use common::models::monitor::{DatabaseInfo, DatabaseStats};
+use common::models::query::{ColumnInfo, QueryResult, ValueKind};
+use super::{DatabaseDriver, DriverConnection, DriverQuery, PoolSettings};
+use crate::DatabasePool;
+use crate::type_mapping;
+
+/// Largest page the `_sql` API is asked for.
+const MAX_FETCH_SIZE: u32 = 10_000;
+
+/// REST client of an Elasticsearch cluster.
+#[derive(Clone)]
+pub struct EsClient {
+    http: reqwest::Client,
+    base_url: String,
+    username: Option<String>,
+    password: Option<String>,
+    /// Index searched when a Query DSL request names none.
+    default_index: Option<String>,
+}
+
+impl EsClient {
+    /// Creates the client and checks the cluster health.
+    pub async fn connect(config: &ConnectionConfig, timeout: Duration) -> AppResult<Self> {
+        let host = config
+            .host
+            .as_deref()
+            .ok_or_else(|| AppError::Validation("Elasticsearch requires host".into()))?
+            .trim_end_matches('/');
+        let port = config.port.unwrap_or(9200);
+        let base_url = if host.starts_with("http://") || host.starts_with("https://") {
+            format!("{}:{}", host, port)
+        } else {
+            format!("http://{}:{}", host, port)
+        };
+        let http = reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .build()
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        let client = Self {
+            http,
+            base_url,
+            username: config.username.clone().filter(|u| !u.is_empty()),
+            password: config.password.clone(),
+            default_index: config.database.clone().filter(|d| !d.is_empty()),
+        };
+        client
+            .health()
+            .await
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        Ok(client)
+    }
+
+    /// Returns the cluster health status; a `red` cluster is an error.
+    pub async fn health(&self) -> AppResult<String> {
+        let health = self.send(Method::GET, "/_cluster/health", None).await?;
+        let status = health["status"].as_str().unwrap_or_default().to_string();
+        if status == "red" {
+            return Err(AppError::DatabaseConnection("cluster health is red".into()));
+        }
+        Ok(status)
+    }
+
+    /// Lists the open, visible indices.
+    pub async fn indices(&self) -> AppResult<Vec<DatabaseInfo>> {
+        let indices = self
+            .send(Method::GET, "/_cat/indices?format=json&bytes=b&h=index,store.size&expand_wildcards=open", None)
+            .await?;
+        let mut databases: Vec<DatabaseInfo> = indices
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|index| {
+                let name = index["index"].as_str()?;
+                let bytes: f64 = index["store.size"].as_str().and_then(|s| s.parse().ok()).unwrap_or(0.0);
+                (!name.starts_with('.')).then(|| DatabaseInfo {
+                    name: name.to_string(),
+                    tables_count: 1,
+                    size_mb: bytes / 1024.0 / 1024.0,
+                })
+            })
+            .collect();
+        databases.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(databases)
+    }
+
+    /// Field mappings of `index` (default: every visible index), one table per index.
+    pub async fn mappings(&self, index: Option<&str>) -> AppResult<Vec<TableInfo>> {
+        let path = match index {
+            Some(index) => format!("/{}/_mapping", index),
+            None => "/_mapping".to_string(),
+        };
+        let mappings = self.send(Method::GET, &path, None).await?;
+        let mut tables: Vec<TableInfo> = mappings
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(name, _)| !name.starts_with('.'))
+            .map(|(name, mapping)| {
+                let mut columns = Vec::new();
+                if let Some(properties) = mapping["mappings"]["properties"].as_object() {
+                    flatten_properties("", properties, &mut columns);
+                }
+                TableInfo { name: name.clone(), columns }
+            })
+            .collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tables)
+    }
+
+    async fn search(&self, mut body: Value, index: Option<&str>, limit: u32, start: Instant) -> AppResult<QueryResult> {
+        let Some(object) = body.as_object_mut() else {
+            return Err(AppError::InvalidInput("Query DSL must be a JSON object".into()));
+        };
+        let size = object.get("size").and_then(Value::as_u64).map_or(limit, |s| s.min(limit as u64) as u32);
+        object.insert("size".into(), json!(size));
+
+        let index = index.or(self.default_index.as_deref()).unwrap_or("_all");
+        let response = self.send(Method::POST, &format!("/{}/_search", index), Some(&body)).await?;
+        let mut result = search_result(&response);
+        result.execution_time_ms = start.elapsed().as_millis() as u64;
+        Ok(result)
+    }
+
+    async fn sql(&self, sql: &str, limit: u32, params: &[Value], start: Instant) -> AppResult<QueryResult> {
+        let mut body = json!({ "query": sql.trim_end_matches(';'), "fetch_size": limit.clamp(1, MAX_FETCH_SIZE) });
+        if !params.is_empty() {
+            body["params"] = Value::Array(params.to_vec());
+        }
+        let response = self.send(Method::POST, "/_sql?format=json", Some(&body)).await?;
+        if let Some(cursor) = response["cursor"].as_str() {
+            // Only the first page is returned; release the server-side cursor
+            let close = json!({ "cursor": cursor });
+            if let Err(e) = self.send(Method::POST, "/_sql/close", Some(&close)).await {
+                tracing::debug!(error = %e, "Failed to close Elasticsearch SQL cursor");
+            }
+        }
+
+        let columns: Vec<ColumnInfo> = response["columns"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|c| {
+                let data_type = c["type"].as_str().unwrap_or_default();
+                ColumnInfo {
+                    name: c["name"].as_str().unwrap_or_default().to_string(),
+                    data_type: data_type.to_string(),
+                    nullable: None,
+                    kind: Some(type_mapping::elasticsearch_kind(data_type)),
+                }
+            })
+            .collect();
+        let rows: Vec<Vec<Value>> = response["rows"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .take(limit as usize)
+            .map(|row| row.as_array().cloned().unwrap_or_default())
+            .collect();
+
+        let row_count = rows.len();
+        Ok(QueryResult {
+            columns,
+            rows,
+            row_count,
+            affected_rows: None,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+            truncated: false,
+            truncated_cells: Vec::new(),
+        })
+    }
+
+    /// Sends a request and returns the JSON body; error responses carry the server's reason.
+    async fn send(&self, method: Method, path: &str, body: Option<&Value>) -> AppResult<Value> {
+        let mut request = self.http.request(method, format!("{}{}", self.base_url, path));
+        if let Some(username) = &self.username {
+            request = request.basic_auth(username, self.password.as_deref());
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::DatabaseConnection(format!("Elasticsearch request failed: {}", e)))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let reason = body["error"]["reason"]
+                .as_str()
+                .or_else(|| body["error"].as_str())
+                .unwrap_or_else(|| status.canonical_reason().unwrap_or("request failed"));
+            return Err(match status.as_u16() {
+                400 => AppError::InvalidInput(reason.to_string()),
+                401 | 403 => AppError::DatabaseConnection(reason.to_string()),
+                404 => AppError::NotFound(reason.to_string()),
+                _ => AppError::DatabaseQuery(reason.to_string()),
+            });
+        }
+        Ok(body)
+    }
+}
+
+/// Driver registered for `DbType::Elasticsearch`.
+pub struct ElasticsearchDriver;
+
+#[async_trait]
+impl DatabaseDriver for ElasticsearchDriver {
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let client = EsClient::connect(config, settings.connect_timeout).await?;
+        Ok(DatabasePool::Driver(Arc::new(client)))
+    }
+}
+
+#[async_trait]
+impl DriverConnection for EsClient {
+    async fn ping(&self) -> AppResult<()> {
+        self.health().await.map(|_| ())
+    }
+
+    /// Server version and cluster health as database statistics.
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        let info = self.send(Method::GET, "/", None).await?;
+        let health = self.send(Method::GET, "/_cluster/health", None).await?;
+        let mut stats = DatabaseStats {
+            server_version: info["version"]["number"].as_str().map(|v| format!("Elasticsearch {}", v)),
+            ..Default::default()
+        };
+        for key in ["cluster_name", "status", "number_of_nodes", "active_shards", "unassigned_shards"] {
+            match &health[key] {
+                Value::Null => {}
+                Value::String(s) => {
+                    stats.extra.insert(key.to_string(), s.clone());
+                }
+                other => {
+                    stats.extra.insert(key.to_string(), other.to_string());
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    async fn databases(&self) -> AppResult<Vec<DatabaseInfo>> {
+        self.indices().await
+    }
+
+    /// Runs Query DSL (a JSON object) on `index`, or SQL through the `_sql` API.
+    async fn query(&self, query: &DriverQuery<'_>) -> AppResult<QueryResult> {
+        let DriverQuery { sql, database: index, limit, params, start, .. } = *query;
+        let query = sql.trim();
+        if query.starts_with('{') {
+            if !params.is_empty() {
+                return Err(AppError::InvalidInput("Query DSL does not take bind parameters".into()));
+            }
+            let body: Value = serde_json::from_str(query)
+                .map_err(|e| AppError::InvalidInput(format!("Invalid Query DSL: {}", e)))?;
+            self.search(body, index, limit, start).await
+        } else {
+            self.sql(query, limit, params, start).await
+        }
+    }
+
+    async fn table_schema(&self, config: &ConnectionConfig) -> AppResult<Vec<TableInfo>> {
+        self.mappings(config.database.as_deref()).await
+    }
+
+    /// The index is chosen per request; one client serves all of them.
+    fn database_per_query(&self) -> bool {
+        true
+    }
+}
+
+/// Appends the fields of a mapping's `properties`, nested objects as dotted names.
+fn flatten_properties(prefix: &str, properties: &Map<String, Value>, columns: &mut Vec<ColumnDetail>) {
+    for (name, field) in properties {
+        let path = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        match (field["type"].as_str(), field["properties"].as_object()) {
+            // `nested` fields keep their own type; plain objects only have properties
+            (Some(data_type), Some(children)) => {
+                columns.push(ColumnDetail { name: path.clone(), data_type: data_type.to_string(), nullable: true, key: None });
+                flatten_properties(&path, children, columns);
+            }
+            (None, Some(children)) => flatten_properties(&path, children, columns),
+            (data_type, None) => columns.push(ColumnDetail {
+                name: path,
+                data_type: data_type.unwrap_or("object").to_string(),
+                nullable: true,
+                key: None,
+            }),
+        }
+    }
+}
+
+/// Maps `_search` hits to rows: `_index`, `_id`, then the top-level `_source`
+/// fields in order of first appearance.
+///
+/// A search without hits but with aggregations returns them as one
+/// `aggregations` JSON cell.
+fn search_result(response: &Value) -> QueryResult {
+    let hits = response["hits"]["hits"].as_array().cloned().unwrap_or_default();
+    if hits.is_empty() {
+        if let Some(aggregations) = response.get("aggregations") {
+            let mut result = QueryResult::empty();
+            result.columns = vec![ColumnInfo {
+                name: "aggregations".into(),
+                data_type: "object".into(),
+                nullable: None,
+                kind: Some(ValueKind::Json),
+            }];
+            result.rows = vec![vec![aggregations.clone()]];
+            result.row_count = 1;
+            return result;
+        }
+    }
+
+    let mut fields: Vec<String> = Vec::new();
+    for hit in &hits {
+        for key in hit["_source"].as_object().into_iter().flat_map(|s| s.keys()) {
+            if !fields.contains(key) {
+                fields.push(key.clone());
+            }
+        }
+    }
+
+    let rows: Vec<Vec<Value>> = hits
+        .iter()
+        .map(|hit| {
+            let mut row = vec![hit["_index"].clone(), hit["_id"].clone()];
+            row.extend(fields.iter().map(|f| hit["_source"].get(f).cloned().unwrap_or(Value::Null)));
+            row
+        })
+        .collect();
+
+    let mut columns = vec![
+        ColumnInfo { name: "_index".into(), data_type: "keyword".into(), nullable: None, kind: Some(ValueKind::Text) },
+        ColumnInfo { name: "_id".into(), data_type: "keyword".into(), nullable: None, kind: Some(ValueKind::Text) },
+    ];
+    for (i, field) in fields.iter().enumerate() {
+        let kind = rows.iter().map(|row| &row[i + 2]).find(|v| !v.is_null()).map_or(ValueKind::Text, type_mapping::json_kind);
+        columns.push(ColumnInfo { name: field.clone(), data_type: "source".into(), nullable: None, kind: Some(kind) });
+    }
+
+    let row_count = rows.len();
+    QueryResult {
+        columns,
+        rows,
+        row_count,
+        affected_rows: None,
+        execution_time_ms: 0,
+        truncated: false,
+        truncated_cells: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flattens_nested_mapping_properties() {
+        let properties = json!({
+            "title": { "type": "text" },
+            "author": { "properties": { "name": { "type": "keyword" }, "age": { "type": "integer" } } },
+            "comments": { "type": "nested", "properties": { "body": { "type": "text" } } }
+        });
+        let mut columns = Vec::new();
+        flatten_properties("", properties.as_object().unwrap(), &mut columns);
+        let names: Vec<(&str, &str)> = columns.iter().map(|c| (c.name.as_str(), c.data_type.as_str())).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("title", "text"),
+                ("author.name", "keyword"),
+                ("author.age", "integer"),
+                ("comments", "nested"),
+                ("comments.body", "text"),
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_search_hits_to_rows() {
+        let response = json!({
+            "hits": { "hits": [
+                { "_index": "logs", "_id": "1", "_source": { "level": "error", "code": 500 } },
+                { "_index": "logs", "_id": "2", "_source": { "level": "info", "tags": ["a"] } }
+            ] }
+        });
+        let result = search_result(&response);
+        let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["_index", "_id", "level", "code", "tags"]);
+        assert_eq!(result.columns[3].kind, Some(ValueKind::Integer));
+        assert_eq!(result.rows[1], vec![json!("logs"), json!("2"), json!("info"), Value::Null, json!(["a"])]);
+
+        let aggregations = search_result(&json!({ "hits": { "hits": [] }, "aggregations": { "n": { "value": 3 } } }));
+        assert_eq!(aggregations.rows, vec![vec![json!({ "n": { "value": 3 } })]]);
+    }
+}
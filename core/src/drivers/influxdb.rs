@@ -0,0 +1,581 @@
+//! InfluxDB connections.
+//!
+//! InfluxDB is reached over its HTTP API and supports both query languages:
+//! - Flux (InfluxDB 2) through `/api/v2/query`; requires the connection's `org`
+//! - InfluxQL (InfluxDB 1, or 2 with DBRP mappings) through `/query`
+//!
+//! The bucket (v1: database) is the connection's `database`. With a username
+//! the credentials are sent as basic auth (v1, or the v2 compatibility API);
+//! otherwise `password` is the API token.
+//!
+//! Queries are read-only: InfluxQL must be `SELECT` / `SHOW` without `INTO`,
+//! and Flux must not write with `to()`. Time columns are RFC 3339 timestamps.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::header::{HeaderValue, ACCEPT, AUTHORIZATION};
+use reqwest::Method;
+use serde_json::{json, Value};
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::ConnectionConfig;
+use common::models::monitor::{DatabaseInfo, DatabaseStats};
+use common::models::query::{ColumnInfo, QueryResult, ValueKind};
+use super::{DatabaseDriver, DriverConnection, DriverQuery, PoolSettings};
+use crate::DatabasePool;
+use crate::type_mapping;
+
+/// Credentials sent with every request.
+#[derive(Clone)]
+enum Credentials {
+    None,
+    Basic { username: String, password: Option<String> },
+    Token(String),
+}
+
+/// HTTP client of an InfluxDB server.
+#[derive(Clone)]
+pub struct InfluxClient {
+    http: reqwest::Client,
+    base_url: String,
+    credentials: Credentials,
+    org: Option<String>,
+    /// Bucket queried when a request names none.
+    bucket: Option<String>,
+}
+
+impl InfluxClient {
+    /// Creates the client, checks that the server answers and that the credentials can list buckets.
+    pub async fn connect(config: &ConnectionConfig, timeout: Duration) -> AppResult<Self> {
+        let host = config
+            .host
+            .as_deref()
+            .ok_or_else(|| AppError::Validation("InfluxDB requires host".into()))?
+            .trim_end_matches('/');
+        let port = config.port.unwrap_or(8086);
+        let base_url = if host.starts_with("http://") || host.starts_with("https://") {
+            format!("{}:{}", host, port)
+        } else {
+            format!("http://{}:{}", host, port)
+        };
+        let credentials = match (config.username.as_deref().filter(|u| !u.is_empty()), &config.password) {
+            (Some(username), password) => Credentials::Basic { username: username.to_string(), password: password.clone() },
+            (None, Some(token)) if !token.is_empty() => Credentials::Token(token.clone()),
+            _ => Credentials::None,
+        };
+        let http = reqwest::Client::builder()
+            .connect_timeout(timeout)
+            .build()
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        let client = Self {
+            http,
+            base_url,
+            credentials,
+            org: config.org.clone().filter(|o| !o.is_empty()),
+            bucket: config.database.clone().filter(|d| !d.is_empty()),
+        };
+        client
+            .version()
+            .await
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        client
+            .buckets()
+            .await
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        Ok(client)
+    }
+
+    /// Checks that the server answers and returns its version.
+    pub async fn version(&self) -> AppResult<Option<String>> {
+        let response = self.request(Method::GET, "/ping").send().await.map_err(request_error)?;
+        if !response.status().is_success() {
+            return Err(AppError::DatabaseConnection(format!("ping returned {}", response.status())));
+        }
+        Ok(response
+            .headers()
+            .get("X-Influxdb-Version")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string))
+    }
+
+    /// Lists the buckets of the organization (without `org`: the v1 databases).
+    ///
+    /// System buckets (`_monitoring`, `_tasks`, v1 `_internal`) are not listed.
+    pub async fn buckets(&self) -> AppResult<Vec<DatabaseInfo>> {
+        let names: Vec<String> = match &self.org {
+            Some(org) => {
+                let response = self
+                    .request(Method::GET, "/api/v2/buckets")
+                    .query(&[("org", org.as_str()), ("limit", "100")])
+                    .send()
+                    .await
+                    .map_err(request_error)?;
+                let body = json_body(response).await?;
+                body["buckets"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|b| b["name"].as_str().map(str::to_string))
+                    .collect()
+            }
+            None => {
+                let body = self.influxql("SHOW DATABASES", None).await?;
+                let result = influxql_result(&body, u32::MAX)?;
+                result.rows.iter().filter_map(|row| row.last()?.as_str().map(str::to_string)).collect()
+            }
+        };
+        let mut buckets: Vec<DatabaseInfo> = names
+            .into_iter()
+            .filter(|name| !name.starts_with('_'))
+            .map(|name| DatabaseInfo { name, tables_count: 0, size_mb: 0.0 })
+            .collect();
+        buckets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(buckets)
+    }
+
+    /// Runs an InfluxQL query through `/query` and returns the JSON body.
+    async fn influxql(&self, query: &str, database: Option<&str>) -> AppResult<Value> {
+        let mut request = self.request(Method::GET, "/query").query(&[("q", query)]);
+        if let Some(database) = database {
+            request = request.query(&[("db", database)]);
+        }
+        json_body(request.send().await.map_err(request_error)?).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.credentials {
+            Credentials::None => request,
+            Credentials::Basic { username, password } => request.basic_auth(username, password.as_deref()),
+            Credentials::Token(token) => match HeaderValue::from_str(&format!("Token {}", token)) {
+                Ok(value) => request.header(AUTHORIZATION, value),
+                Err(_) => request,
+            },
+        }
+    }
+}
+
+/// Driver registered for `DbType::InfluxDB`.
+pub struct InfluxDriver;
+
+#[async_trait]
+impl DatabaseDriver for InfluxDriver {
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let client = InfluxClient::connect(config, settings.connect_timeout).await?;
+        Ok(DatabasePool::Driver(Arc::new(client)))
+    }
+}
+
+#[async_trait]
+impl DriverConnection for InfluxClient {
+    async fn ping(&self) -> AppResult<()> {
+        self.version().await.map(|_| ())
+    }
+
+    /// Server version as database statistics.
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        let version = self.version().await?;
+        let mut stats = DatabaseStats {
+            server_version: version.map(|v| format!("InfluxDB {}", v)),
+            ..Default::default()
+        };
+        if let Some(org) = &self.org {
+            stats.extra.insert("org".to_string(), org.clone());
+        }
+        Ok(stats)
+    }
+
+    async fn databases(&self) -> AppResult<Vec<DatabaseInfo>> {
+        self.buckets().await
+    }
+
+    /// Runs a Flux or InfluxQL query on `bucket` and returns at most `limit` rows.
+    async fn query(&self, query: &DriverQuery<'_>) -> AppResult<QueryResult> {
+        let DriverQuery { sql: query, database: bucket, limit, params, start, .. } = *query;
+        if !params.is_empty() {
+            return Err(AppError::InvalidInput("InfluxDB queries do not take bind parameters".into()));
+        }
+        let query = query.trim();
+        let mut result = if is_flux(query) {
+            ensure_flux_read_only(query)?;
+            let org = self
+                .org
+                .as_deref()
+                .ok_or_else(|| AppError::Validation("Flux queries require the connection's org".into()))?;
+            let response = self
+                .request(Method::POST, "/api/v2/query")
+                .query(&[("org", org)])
+                .header(ACCEPT, "application/csv")
+                .json(&json!({ "query": query, "type": "flux", "dialect": { "annotations": ["datatype"], "header": true } }))
+                .send()
+                .await
+                .map_err(request_error)?;
+            if !response.status().is_success() {
+                return Err(status_error(response.status(), &json_body_lossy(response).await));
+            }
+            let csv = response.text().await.map_err(request_error)?;
+            flux_result(&csv, limit)?
+        } else {
+            ensure_influxql_read_only(query)?;
+            let body = self.influxql(query, bucket.or(self.bucket.as_deref())).await?;
+            influxql_result(&body, limit)?
+        };
+        result.execution_time_ms = start.elapsed().as_millis() as u64;
+        Ok(result)
+    }
+
+    /// The bucket is chosen per request; one client serves all of them.
+    fn database_per_query(&self) -> bool {
+        true
+    }
+}
+
+fn request_error(e: reqwest::Error) -> AppError {
+    AppError::DatabaseConnection(format!("InfluxDB request failed: {}", e))
+}
+
+/// Returns the JSON body, or the server's error message for error responses.
+async fn json_body(response: reqwest::Response) -> AppResult<Value> {
+    let status = response.status();
+    let body = json_body_lossy(response).await;
+    if !status.is_success() {
+        return Err(status_error(status, &body));
+    }
+    Ok(body)
+}
+
+async fn json_body_lossy(response: reqwest::Response) -> Value {
+    response.json().await.unwrap_or(Value::Null)
+}
+
+fn status_error(status: reqwest::StatusCode, body: &Value) -> AppError {
+    // v2 reports `message`, v1 `error`
+    let reason = body["message"]
+        .as_str()
+        .or_else(|| body["error"].as_str())
+        .unwrap_or_else(|| status.canonical_reason().unwrap_or("request failed"))
+        .to_string();
+    match status.as_u16() {
+        400 => AppError::InvalidInput(reason),
+        401 | 403 => AppError::DatabaseConnection(reason),
+        404 => AppError::NotFound(reason),
+        _ => AppError::DatabaseQuery(reason),
+    }
+}
+
+/// Whether a query is Flux rather than InfluxQL.
+pub fn is_flux(query: &str) -> bool {
+    let query = query.trim();
+    query.starts_with("from(") || query.starts_with("import ") || query.contains("|>")
+}
+
+fn ensure_flux_read_only(query: &str) -> AppResult<()> {
+    let compact: String = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.contains("|>to(") || compact.contains("experimental.to(") {
+        return Err(AppError::InvalidInput("Flux queries must not write with to()".into()));
+    }
+    Ok(())
+}
+
+fn ensure_influxql_read_only(query: &str) -> AppResult<()> {
+    for statement in query.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        let upper = statement.to_uppercase();
+        let read = upper.starts_with("SELECT") || upper.starts_with("SHOW");
+        if !read || upper.split_whitespace().any(|word| word == "INTO") {
+            return Err(AppError::InvalidInput("Only SELECT and SHOW InfluxQL statements are allowed".into()));
+        }
+    }
+    Ok(())
+}
+
+/// Maps an InfluxQL response: `measurement`, the series tags, then the series columns.
+///
+/// Series of all statements are appended; columns missing from a series are null.
+fn influxql_result(body: &Value, limit: u32) -> AppResult<QueryResult> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rows: Vec<Vec<(usize, Value)>> = Vec::new();
+    fn column(names: &mut Vec<String>, name: &str) -> usize {
+        match names.iter().position(|n| n == name) {
+            Some(idx) => idx,
+            None => {
+                names.push(name.to_string());
+                names.len() - 1
+            }
+        }
+    }
+
+    for statement in body["results"].as_array().into_iter().flatten() {
+        if let Some(error) = statement["error"].as_str() {
+            return Err(AppError::DatabaseQuery(error.to_string()));
+        }
+        for series in statement["series"].as_array().into_iter().flatten() {
+            let mut fixed: Vec<(usize, Value)> = Vec::new();
+            if let Some(name) = series["name"].as_str() {
+                fixed.push((column(&mut names, "measurement"), json!(name)));
+            }
+            for (tag, value) in series["tags"].as_object().into_iter().flatten() {
+                fixed.push((column(&mut names, tag), value.clone()));
+            }
+            let columns: Vec<usize> = series["columns"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|c| column(&mut names, c.as_str().unwrap_or_default()))
+                .collect();
+            for values in series["values"].as_array().into_iter().flatten() {
+                if rows.len() >= limit as usize {
+                    break;
+                }
+                let mut row = fixed.clone();
+                row.extend(columns.iter().copied().zip(values.as_array().cloned().unwrap_or_default()));
+                rows.push(row);
+            }
+        }
+    }
+
+    let rows: Vec<Vec<Value>> = rows
+        .into_iter()
+        .map(|cells| {
+            let mut row = vec![Value::Null; names.len()];
+            for (idx, value) in cells {
+                row[idx] = value;
+            }
+            row
+        })
+        .collect();
+    let columns = names
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            let kind = if name == "time" {
+                ValueKind::DateTimeTz
+            } else {
+                rows.iter().map(|r| &r[idx]).find(|v| !v.is_null()).map_or(ValueKind::Text, type_mapping::json_kind)
+            };
+            ColumnInfo { name: name.clone(), data_type: "influxql".into(), nullable: None, kind: Some(kind) }
+        })
+        .collect();
+
+    let row_count = rows.len();
+    Ok(QueryResult {
+        columns,
+        rows,
+        row_count,
+        affected_rows: None,
+        execution_time_ms: 0,
+        truncated: false,
+        truncated_cells: Vec::new(),
+    })
+}
+
+/// Maps a Flux annotated CSV response (`#datatype` annotation, header rows).
+///
+/// Flux returns one CSV table per group, each with its own header; columns
+/// are merged by name. The annotation column and `result` are dropped, and
+/// `table` identifies the series.
+fn flux_result(csv: &str, limit: u32) -> AppResult<QueryResult> {
+    let mut columns: Vec<ColumnInfo> = Vec::new();
+    let mut rows: Vec<Vec<(usize, Value)>> = Vec::new();
+    let mut datatypes: Vec<String> = Vec::new();
+    let mut header: Option<Vec<Option<usize>>> = None;
+
+    for line in csv.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() {
+            header = None;
+            continue;
+        }
+        let fields = split_csv_line(line);
+        if fields.first().map(String::as_str) == Some("#datatype") {
+            datatypes = fields;
+            header = None;
+            continue;
+        }
+        if fields.first().is_some_and(|f| f.starts_with('#')) {
+            continue;
+        }
+        let Some(positions) = &header else {
+            if fields.iter().any(|f| f == "error") && fields.iter().any(|f| f == "reference") {
+                // Error table: the next row carries the message
+                header = Some(Vec::new());
+                datatypes.clear();
+                continue;
+            }
+            let mut positions = Vec::with_capacity(fields.len());
+            for (idx, name) in fields.iter().enumerate() {
+                if idx == 0 || name == "result" {
+                    positions.push(None);
+                    continue;
+                }
+                let position = match columns.iter().position(|c| &c.name == name) {
+                    Some(position) => position,
+                    None => {
+                        let datatype = datatypes.get(idx).cloned().unwrap_or_else(|| "string".to_string());
+                        columns.push(ColumnInfo {
+                            name: name.clone(),
+                            kind: Some(type_mapping::flux_kind(&datatype)),
+                            data_type: datatype,
+                            nullable: None,
+                        });
+                        columns.len() - 1
+                    }
+                };
+                positions.push(Some(position));
+            }
+            header = Some(positions);
+            continue;
+        };
+        if positions.is_empty() {
+            let message = fields.get(1).cloned().unwrap_or_default();
+            return Err(AppError::DatabaseQuery(message));
+        }
+        if rows.len() >= limit as usize {
+            continue;
+        }
+        let row = positions
+            .iter()
+            .zip(&fields)
+            .filter_map(|(position, raw)| {
+                let position = (*position)?;
+                let kind = columns[position].kind.unwrap_or(ValueKind::Text);
+                Some((position, flux_value(raw, kind)))
+            })
+            .collect();
+        rows.push(row);
+    }
+
+    let rows: Vec<Vec<Value>> = rows
+        .into_iter()
+        .map(|cells| {
+            let mut row = vec![Value::Null; columns.len()];
+            for (idx, value) in cells {
+                row[idx] = value;
+            }
+            row
+        })
+        .collect();
+    let row_count = rows.len();
+    Ok(QueryResult {
+        columns,
+        rows,
+        row_count,
+        affected_rows: None,
+        execution_time_ms: 0,
+        truncated: false,
+        truncated_cells: Vec::new(),
+    })
+}
+
+/// Converts a Flux CSV cell; empty cells are null.
+fn flux_value(raw: &str, kind: ValueKind) -> Value {
+    if raw.is_empty() {
+        return Value::Null;
+    }
+    let value = match kind {
+        ValueKind::Integer => raw.parse::<i64>().map(Value::from).or_else(|_| raw.parse::<u64>().map(Value::from)).ok(),
+        ValueKind::Float => raw.parse::<f64>().ok().and_then(|f| serde_json::Number::from_f64(f).map(Value::Number)),
+        ValueKind::Boolean => raw.parse::<bool>().ok().map(Value::Bool),
+        _ => None,
+    };
+    value.unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// Splits a CSV line, honouring double-quoted fields.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Bucket names read by `from(bucket: "...")` calls of a Flux query.
+pub fn flux_buckets(query: &str) -> Vec<String> {
+    let mut buckets = Vec::new();
+    let mut rest = query;
+    while let Some(idx) = rest.find("bucket") {
+        rest = &rest[idx + "bucket".len()..];
+        let Some(value) = rest.trim_start().strip_prefix(':') else {
+            continue;
+        };
+        let Some(value) = value.trim_start().strip_prefix('"') else {
+            continue;
+        };
+        if let Some(end) = value.find('"') {
+            buckets.push(value[..end].to_string());
+        }
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_flux_tables_with_timestamps() {
+        let csv = "#datatype,string,long,dateTime:RFC3339,double,string\r\n\
+                   ,result,table,_time,_value,host\r\n\
+                   ,_result,0,2024-01-01T00:00:00Z,1.5,a\r\n\
+                   ,_result,0,2024-01-01T00:01:00Z,,a\r\n\
+                   \r\n\
+                   #datatype,string,long,dateTime:RFC3339,long\r\n\
+                   ,result,table,_time,count\r\n\
+                   ,_result,1,2024-01-01T00:00:00Z,7\r\n";
+        let result = flux_result(csv, 10).unwrap();
+        let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["table", "_time", "_value", "host", "count"]);
+        assert_eq!(result.columns[1].kind, Some(ValueKind::DateTimeTz));
+        assert_eq!(result.rows[0], vec![json!(0), json!("2024-01-01T00:00:00Z"), json!(1.5), json!("a"), Value::Null]);
+        assert_eq!(result.rows[1][2], Value::Null);
+        assert_eq!(result.rows[2], vec![json!(1), json!("2024-01-01T00:00:00Z"), Value::Null, Value::Null, json!(7)]);
+        assert_eq!(flux_result(csv, 1).unwrap().row_count, 1);
+
+        let error = "#datatype,string,string\r\n,error,reference\r\n,\"bucket \"\"x\"\" not found\",\r\n";
+        assert!(matches!(flux_result(error, 10), Err(AppError::DatabaseQuery(m)) if m == "bucket \"x\" not found"));
+    }
+
+    #[test]
+    fn maps_influxql_series() {
+        let body = json!({ "results": [{ "statement_id": 0, "series": [
+            { "name": "cpu", "tags": { "host": "a" }, "columns": ["time", "usage"], "values": [["2024-01-01T00:00:00Z", 12]] },
+            { "name": "cpu", "tags": { "host": "b" }, "columns": ["time", "usage"], "values": [["2024-01-01T00:00:00Z", 3.5]] }
+        ] }] });
+        let result = influxql_result(&body, 10).unwrap();
+        let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["measurement", "host", "time", "usage"]);
+        assert_eq!(result.columns[2].kind, Some(ValueKind::DateTimeTz));
+        assert_eq!(result.rows[1], vec![json!("cpu"), json!("b"), json!("2024-01-01T00:00:00Z"), json!(3.5)]);
+
+        let error = json!({ "results": [{ "statement_id": 0, "error": "database not found: x" }] });
+        assert!(influxql_result(&error, 10).is_err());
+    }
+
+    #[test]
+    fn rejects_writes_and_finds_buckets() {
+        assert!(ensure_influxql_read_only("SELECT * FROM cpu; SHOW MEASUREMENTS").is_ok());
+        assert!(ensure_influxql_read_only("SELECT * INTO copy FROM cpu").is_err());
+        assert!(ensure_influxql_read_only("DROP MEASUREMENT cpu").is_err());
+        assert!(ensure_flux_read_only("from(bucket: \"a\") |> to(bucket: \"b\")").is_err());
+        assert_eq!(
+            flux_buckets("from(bucket: \"metrics\") |> range(start: -1h)\nfrom(bucket:\"logs\")"),
+            vec!["metrics", "logs"]
+        );
+        assert!(is_flux("  from(bucket: \"a\")"));
+        assert!(!is_flux("SELECT * FROM cpu"));
+    }
+}
@@ -0,0 +1,348 @@
+//! Memcached connections.
+//!
+//! Memcached is reached over its text protocol on TCP (default port 11211,
+//! no authentication). Connections are reused across requests, keeping at
+//! most the pool size idle, and dropped when a command fails.
+//!
+//! There is no query language: connections support the connection test,
+//! server statistics (`stats`) and get / set / delete of single keys. Values
+//! that are not valid UTF-8 are returned base64 encoded.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::Engine as _;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream};
+use tokio::net::TcpStream;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::ConnectionConfig;
+use common::models::key_value::{KeyValueEntry, SetKeyValueRequest, ValueEncoding};
+use common::models::monitor::DatabaseStats;
+use super::{DatabaseDriver, DriverConnection, PoolSettings, PoolUsage};
+use crate::DatabasePool;
+
+/// Longest key the server accepts, in bytes.
+const MAX_KEY_BYTES: usize = 250;
+
+type Connection = BufStream<TcpStream>;
+
+/// Response of the server to a command.
+#[derive(Debug, PartialEq)]
+enum Reply {
+    /// Status or `STAT` line, without the line terminator.
+    Line(String),
+    /// Item returned by `gets`.
+    Value { flags: u32, cas: Option<u64>, data: Vec<u8> },
+}
+
+/// Client of a Memcached server.
+#[derive(Clone)]
+pub struct MemcachedClient {
+    addr: String,
+    timeout: Duration,
+    idle: Arc<Mutex<Vec<Connection>>>,
+    max_idle: usize,
+}
+
+impl MemcachedClient {
+    /// Creates the client and checks that the server answers.
+    pub async fn connect(config: &ConnectionConfig, max_connections: u32, timeout: Duration) -> AppResult<Self> {
+        let host = config
+            .host
+            .as_deref()
+            .ok_or_else(|| AppError::Validation("Memcached requires host".into()))?;
+        let client = Self {
+            addr: format!("{}:{}", host, config.port.unwrap_or(11211)),
+            timeout,
+            idle: Arc::new(Mutex::new(Vec::new())),
+            max_idle: max_connections.max(1) as usize,
+        };
+        client
+            .version()
+            .await
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        Ok(client)
+    }
+
+    /// Server version.
+    pub async fn version(&self) -> AppResult<String> {
+        match self.request(b"version\r\n".to_vec(), false).await?.pop() {
+            Some(Reply::Line(line)) => Ok(line.trim_start_matches("VERSION ").to_string()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Value stored under `key`, or `None` when the key does not exist.
+    pub async fn get(&self, key: &str) -> AppResult<Option<KeyValueEntry>> {
+        check_key(key)?;
+        let replies = self.request(format!("gets {}\r\n", key).into_bytes(), true).await?;
+        Ok(replies.into_iter().find_map(|reply| match reply {
+            Reply::Value { flags, cas, data } => Some(entry(key, flags, cas, data)),
+            Reply::Line(_) => None,
+        }))
+    }
+
+    /// Stores a value under `key`, replacing any existing one.
+    pub async fn set(&self, key: &str, req: &SetKeyValueRequest) -> AppResult<()> {
+        check_key(key)?;
+        let data = match req.encoding {
+            ValueEncoding::Text => req.value.clone().into_bytes(),
+            ValueEncoding::Base64 => base64::engine::general_purpose::STANDARD
+                .decode(&req.value)
+                .map_err(|e| AppError::InvalidInput(format!("Invalid base64 value: {}", e)))?,
+        };
+        let mut command = format!("set {} {} {} {}\r\n", key, req.flags, req.ttl_secs.unwrap_or(0), data.len()).into_bytes();
+        command.extend_from_slice(&data);
+        command.extend_from_slice(b"\r\n");
+        match self.request(command, false).await?.pop() {
+            Some(Reply::Line(line)) if line == "STORED" => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Deletes `key`; returns whether it existed.
+    pub async fn delete(&self, key: &str) -> AppResult<bool> {
+        check_key(key)?;
+        match self.request(format!("delete {}\r\n", key).into_bytes(), false).await?.pop() {
+            Some(Reply::Line(line)) if line == "DELETED" => Ok(true),
+            Some(Reply::Line(line)) if line == "NOT_FOUND" => Ok(false),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Sends a command and reads its replies: one line, or with `until_end`
+    /// every line up to `END`. Server errors are returned as `DatabaseQuery`.
+    async fn request(&self, command: Vec<u8>, until_end: bool) -> AppResult<Vec<Reply>> {
+        let mut conn = self.checkout().await?;
+        let replies = tokio::time::timeout(self.timeout, exchange(&mut conn, &command, until_end))
+            .await
+            .map_err(|_| AppError::Timeout(format!("Memcached did not answer within {} ms", self.timeout.as_millis())))?
+            .map_err(|e| AppError::DatabaseQuery(format!("Memcached request failed: {}", e)))?;
+        // The connection is still in sync after a complete reply, errors included
+        self.checkin(conn);
+        match replies.last() {
+            Some(Reply::Line(line)) if is_error(line) => Err(AppError::DatabaseQuery(line.clone())),
+            _ => Ok(replies),
+        }
+    }
+
+    async fn checkout(&self) -> AppResult<Connection> {
+        let idle = self.idle.lock().map(|mut idle| idle.pop()).unwrap_or(None);
+        if let Some(conn) = idle {
+            return Ok(conn);
+        }
+        let stream = tokio::time::timeout(self.timeout, TcpStream::connect(&self.addr))
+            .await
+            .map_err(|_| AppError::DatabaseConnection(format!("Connecting to {} timed out", self.addr)))?
+            .map_err(|e| AppError::DatabaseConnection(format!("Cannot connect to {}: {}", self.addr, e)))?;
+        stream
+            .set_nodelay(true)
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        Ok(BufStream::new(stream))
+    }
+
+    fn checkin(&self, conn: Connection) {
+        if let Ok(mut idle) = self.idle.lock() {
+            if idle.len() < self.max_idle {
+                idle.push(conn);
+            }
+        }
+    }
+}
+
+/// Driver registered for `DbType::Memcached`.
+pub struct MemcachedDriver;
+
+#[async_trait]
+impl DatabaseDriver for MemcachedDriver {
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let client = MemcachedClient::connect(config, settings.max_connections, settings.connect_timeout).await?;
+        Ok(DatabasePool::Driver(Arc::new(client)))
+    }
+}
+
+#[async_trait]
+impl DriverConnection for MemcachedClient {
+    async fn ping(&self) -> AppResult<()> {
+        self.version().await.map(|_| ())
+    }
+
+    /// Connections are opened on demand and not counted; only the limit is known.
+    fn usage(&self) -> Option<PoolUsage> {
+        Some(PoolUsage { active: 0, idle: 0, max_size: self.max_idle as u32 })
+    }
+
+    /// Server statistics from `stats`.
+    ///
+    /// Cache-specific counters (items, hits, misses, evictions, memory limit
+    /// and hit ratio) are in `extra`.
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        let replies = self.request(b"stats\r\n".to_vec(), true).await?;
+        let values: Vec<(String, String)> = replies
+            .iter()
+            .filter_map(|reply| match reply {
+                Reply::Line(line) => line.strip_prefix("STAT "),
+                Reply::Value { .. } => None,
+            })
+            .filter_map(|stat| stat.split_once(' '))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Ok(stats_from(&values))
+    }
+}
+
+async fn exchange(conn: &mut Connection, command: &[u8], until_end: bool) -> std::io::Result<Vec<Reply>> {
+    conn.write_all(command).await?;
+    conn.flush().await?;
+
+    let mut replies = Vec::new();
+    loop {
+        let mut line = Vec::new();
+        if conn.read_until(b'\n', &mut line).await? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        let line = String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string();
+        if let Some(header) = line.strip_prefix("VALUE ") {
+            let (flags, bytes, cas) = parse_value_header(header)
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid reply: {}", line)))?;
+            let mut data = vec![0; bytes + 2];
+            conn.read_exact(&mut data).await?;
+            data.truncate(bytes);
+            replies.push(Reply::Value { flags, cas, data });
+            continue;
+        }
+        let done = !until_end || line == "END" || is_error(&line);
+        replies.push(Reply::Line(line));
+        if done {
+            return Ok(replies);
+        }
+    }
+}
+
+/// Parses `<key> <flags> <bytes> [<cas>]` of a `VALUE` line.
+fn parse_value_header(header: &str) -> Option<(u32, usize, Option<u64>)> {
+    let mut fields = header.split(' ').skip(1);
+    let flags = fields.next()?.parse().ok()?;
+    let bytes = fields.next()?.parse().ok()?;
+    let cas = fields.next().and_then(|cas| cas.parse().ok());
+    Some((flags, bytes, cas))
+}
+
+fn is_error(line: &str) -> bool {
+    line == "ERROR" || line.starts_with("CLIENT_ERROR") || line.starts_with("SERVER_ERROR")
+}
+
+fn unexpected(reply: Option<Reply>) -> AppError {
+    AppError::DatabaseQuery(format!("Unexpected Memcached reply: {:?}", reply))
+}
+
+/// Keys are 1-250 bytes without spaces or control characters.
+fn check_key(key: &str) -> AppResult<()> {
+    if key.is_empty() || key.len() > MAX_KEY_BYTES || key.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(AppError::InvalidInput(format!(
+            "Memcached keys must be 1-{} bytes without spaces or control characters",
+            MAX_KEY_BYTES
+        )));
+    }
+    Ok(())
+}
+
+fn entry(key: &str, flags: u32, cas: Option<u64>, data: Vec<u8>) -> KeyValueEntry {
+    let size_bytes = data.len() as u64;
+    let (value, encoding) = match String::from_utf8(data) {
+        Ok(text) => (text, ValueEncoding::Text),
+        Err(e) => (base64::engine::general_purpose::STANDARD.encode(e.as_bytes()), ValueEncoding::Base64),
+    };
+    KeyValueEntry { key: key.to_string(), value, encoding, size_bytes, flags, cas }
+}
+
+fn stats_from(values: &[(String, String)]) -> DatabaseStats {
+    let get = |name: &str| values.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+    let number = |name: &str| get(name).and_then(|v| v.parse::<u64>().ok()).unwrap_or(0);
+
+    let uptime = number("uptime");
+    let total_queries = number("cmd_get") + number("cmd_set");
+    let mut stats = DatabaseStats {
+        uptime_seconds: uptime,
+        total_queries,
+        active_connections: number("curr_connections") as u32,
+        max_connections: number("max_connections") as u32,
+        queries_per_second: if uptime > 0 { total_queries as f64 / uptime as f64 } else { 0.0 },
+        bytes_received: number("bytes_read"),
+        bytes_sent: number("bytes_written"),
+        server_version: get("version").map(|v| format!("Memcached {}", v)),
+        ..Default::default()
+    };
+    for name in ["curr_items", "get_hits", "get_misses", "evictions", "limit_maxbytes", "bytes"] {
+        if let Some(value) = get(name) {
+            stats.extra.insert(name.to_string(), value.to_string());
+        }
+    }
+    let (hits, misses) = (number("get_hits"), number("get_misses"));
+    if hits + misses > 0 {
+        stats
+            .extra
+            .insert("hit_ratio".to_string(), format!("{:.4}", hits as f64 / (hits + misses) as f64));
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn reads_replies_until_end() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 64];
+            let _ = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(b"VALUE greeting 5 10 42\r\nhello\r\nend\r\nEND\r\n")
+                .await
+                .unwrap();
+        });
+
+        let mut conn = BufStream::new(TcpStream::connect(addr).await.unwrap());
+        let replies = exchange(&mut conn, b"gets greeting\r\n", true).await.unwrap();
+        assert_eq!(
+            replies,
+            vec![
+                Reply::Value { flags: 5, cas: Some(42), data: b"hello\r\nend".to_vec() },
+                Reply::Line("END".to_string()),
+            ]
+        );
+        assert!(check_key("user:1").is_ok());
+        assert!(check_key("bad key").is_err());
+    }
+
+    #[test]
+    fn maps_stats_and_binary_values() {
+        let values: Vec<(String, String)> = [
+            ("version", "1.6.21"),
+            ("uptime", "100"),
+            ("cmd_get", "150"),
+            ("cmd_set", "50"),
+            ("get_hits", "120"),
+            ("get_misses", "30"),
+            ("curr_connections", "3"),
+        ]
+        .iter()
+        .map(|(n, v)| (n.to_string(), v.to_string()))
+        .collect();
+        let stats = stats_from(&values);
+        assert_eq!(stats.server_version.as_deref(), Some("Memcached 1.6.21"));
+        assert_eq!(stats.total_queries, 200);
+        assert_eq!(stats.queries_per_second, 2.0);
+        assert_eq!(stats.extra["hit_ratio"], "0.8000");
+
+        let binary = entry("k", 0, None, vec![0xff, 0x00]);
+        assert_eq!(binary.encoding, ValueEncoding::Base64);
+        assert_eq!(binary.value, "/wA=");
+        assert_eq!(binary.size_bytes, 2);
+    }
+}
@@ -0,0 +1,269 @@
+//! Pluggable database drivers.
+//!
+//! Every database type is served by a [`DatabaseDriver`] registered for its
+//! [`DbType`] in the [`DriverRegistry`]. A driver opens a [`DatabasePool`]:
+//! the sqlx pools (MySQL, PostgreSQL, SQLite) keep their own variants because
+//! backups, transfers and schema tools work on them directly; every other
+//! engine is a [`DatabasePool::Driver`] holding its [`DriverConnection`].
+//!
+//! The [`ConnectionManager`](crate::ConnectionManager) and the
+//! [`QueryExecutor`](crate::QueryExecutor) only talk to [`DriverConnection`]:
+//! connection tests, monitoring, queries, data changes and schema reads. Operations a driver
+//! does not support return `UnsupportedDatabaseType` (or an empty list), so a
+//! new engine is a module implementing the two traits plus one
+//! [`DriverRegistry::register`] call. Engine-specific APIs (Memcached keys,
+//! Neo4j Cypher) are reached by downcasting the connection, see
+//! [`DriverConnection::downcast_ref`].
+
+pub mod elasticsearch;
+pub mod influxdb;
+pub mod memcached;
+pub mod mongodb;
+pub mod mysql;
+#[cfg(feature = "neo4j")]
+pub mod neo4j;
+#[cfg(feature = "oracle")]
+pub mod oracle;
+pub mod postgres;
+pub mod redis;
+pub mod sqlite;
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use sqlx::query::Query;
+use sqlx::{Database, Encode, Type};
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::{ConnectionConfig, DbType};
+use common::models::database::TableInfo;
+use common::models::monitor::{DatabaseInfo, DatabaseStats, ProcessInfo};
+use common::models::query::QueryResult;
+use crate::DatabasePool;
+
+/// Pool sizing resolved from the connection's `pool_options` and the [`PoolDefaults`](crate::PoolDefaults).
+#[derive(Debug, Clone, Copy)]
+pub struct PoolSettings {
+    /// Maximum number of connections.
+    pub max_connections: u32,
+    /// Connections kept open while idle.
+    pub min_connections: u32,
+    /// Timeout of connecting / acquiring a connection.
+    pub connect_timeout: Duration,
+    /// Idle connections are closed after this long.
+    pub idle_timeout: Duration,
+}
+
+/// Connection usage of an open pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolUsage {
+    /// Connections in use.
+    pub active: u32,
+    /// Open idle connections.
+    pub idle: u32,
+    /// Pool size limit.
+    pub max_size: u32,
+}
+
+/// A read query handed to a driver.
+#[derive(Debug, Clone, Copy)]
+pub struct DriverQuery<'a> {
+    /// Statement in the driver's query language.
+    pub sql: &'a str,
+    /// Database (index, bucket) to run in, for drivers that choose it per
+    /// query (see [`DriverConnection::database_per_query`]).
+    pub database: Option<&'a str>,
+    /// Maximum number of rows returned.
+    pub limit: u32,
+    /// Positional bind parameters.
+    pub params: &'a [serde_json::Value],
+    /// Server-side timeout, where the driver supports one.
+    pub timeout: Option<Duration>,
+    /// When the request started, for `execution_time_ms`.
+    pub start: Instant,
+}
+
+/// Opens pools for one or more database types.
+#[async_trait]
+pub trait DatabaseDriver: Send + Sync {
+    /// Opens a pool for the connection and checks that it can log in.
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool>;
+}
+
+/// An open pool (or client) of a database connection.
+#[async_trait]
+pub trait DriverConnection: Any + Send + Sync {
+    /// Runs a trivial command to check that the server answers.
+    async fn ping(&self) -> AppResult<()>;
+
+    /// Connection usage; `None` when the driver does not track it.
+    fn usage(&self) -> Option<PoolUsage> {
+        None
+    }
+
+    /// Whether every connection is in use, so a failed probe means busy rather than lost.
+    fn saturated(&self) -> bool {
+        false
+    }
+
+    /// Closes the connections; otherwise they close with the last handle.
+    async fn close(&self) {}
+
+    /// Server statistics.
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        Err(AppError::UnsupportedDatabaseType("Monitoring not supported".into()))
+    }
+
+    /// Running sessions of the server.
+    async fn processes(&self) -> AppResult<Vec<ProcessInfo>> {
+        Ok(vec![])
+    }
+
+    /// Databases on the server.
+    async fn databases(&self) -> AppResult<Vec<DatabaseInfo>> {
+        Ok(vec![])
+    }
+
+    /// Runs a read query and returns at most `query.limit` rows.
+    async fn query(&self, _query: &DriverQuery<'_>) -> AppResult<QueryResult> {
+        Err(AppError::UnsupportedDatabaseType(
+            "SQL query execution is only supported for MySQL, PostgreSQL, SQLite, Elasticsearch and InfluxDB".to_string(),
+        ))
+    }
+
+    /// Executes a data change or DDL statement and returns the affected row count.
+    async fn execute(&self, _sql: &str, _params: &[serde_json::Value], _timeout: Duration) -> AppResult<u64> {
+        Err(AppError::UnsupportedDatabaseType(
+            "Data changes are only supported for MySQL, PostgreSQL, SQLite and Oracle".to_string(),
+        ))
+    }
+
+    /// Tables and columns of the connection's database (for AI context).
+    async fn table_schema(&self, _config: &ConnectionConfig) -> AppResult<Vec<TableInfo>> {
+        Ok(vec![])
+    }
+
+    /// Whether one pool serves every database, chosen per query. Otherwise
+    /// other databases get pools of their own (see [`ConnectionManager::query_pool`](crate::ConnectionManager::query_pool)).
+    fn database_per_query(&self) -> bool {
+        false
+    }
+}
+
+impl dyn DriverConnection {
+    /// The connection as the driver's own type, for engine-specific operations.
+    pub fn downcast_ref<T: DriverConnection>(&self) -> Option<&T> {
+        (self as &dyn Any).downcast_ref()
+    }
+}
+
+/// Drivers by database type.
+#[derive(Default)]
+pub struct DriverRegistry {
+    drivers: HashMap<DbType, Arc<dyn DatabaseDriver>>,
+}
+
+impl DriverRegistry {
+    /// Creates a registry with the built-in drivers (Oracle and Neo4j with
+    /// their features).
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::default();
+        registry.register(DbType::MySQL, Arc::new(mysql::MySqlDriver));
+        registry.register(DbType::MariaDB, Arc::new(mysql::MySqlDriver));
+        registry.register(DbType::Postgres, Arc::new(postgres::PostgresDriver));
+        registry.register(DbType::SQLite, Arc::new(sqlite::SqliteDriver));
+        registry.register(DbType::Redis, Arc::new(self::redis::RedisDriver));
+        registry.register(DbType::MongoDB, Arc::new(self::mongodb::MongoDriver));
+        registry.register(DbType::Elasticsearch, Arc::new(elasticsearch::ElasticsearchDriver));
+        registry.register(DbType::InfluxDB, Arc::new(influxdb::InfluxDriver));
+        registry.register(DbType::Memcached, Arc::new(memcached::MemcachedDriver));
+        #[cfg(feature = "oracle")]
+        registry.register(DbType::Oracle, Arc::new(self::oracle::OracleDriver));
+        #[cfg(feature = "neo4j")]
+        registry.register(DbType::Neo4j, Arc::new(neo4j::Neo4jDriver));
+        registry
+    }
+
+    /// Registers the driver of a database type, replacing any previous one.
+    pub fn register(&mut self, db_type: DbType, driver: Arc<dyn DatabaseDriver>) {
+        self.drivers.insert(db_type, driver);
+    }
+
+    /// Driver of a database type.
+    pub fn get(&self, db_type: &DbType) -> Option<&Arc<dyn DatabaseDriver>> {
+        self.drivers.get(db_type)
+    }
+}
+
+/// Usage of a sqlx pool.
+pub(crate) fn sqlx_usage<DB: Database>(pool: &sqlx::Pool<DB>) -> PoolUsage {
+    let idle = pool.num_idle() as u32;
+    PoolUsage {
+        active: pool.size().saturating_sub(idle),
+        idle,
+        max_size: pool.options().get_max_connections(),
+    }
+}
+
+/// Whether every connection of a sqlx pool is in use.
+pub(crate) fn sqlx_saturated<DB: Database>(pool: &sqlx::Pool<DB>) -> bool {
+    pool.num_idle() == 0 && pool.size() >= pool.options().get_max_connections()
+}
+
+/// Binds JSON parameters positionally: numbers as integers or floats, strings
+/// as text, arrays and objects as their JSON text.
+pub(crate) fn bind_params<'q, DB>(
+    mut query: Query<'q, DB, <DB as Database>::Arguments<'q>>,
+    params: &[serde_json::Value],
+) -> Query<'q, DB, <DB as Database>::Arguments<'q>>
+where
+    DB: Database,
+    Option<String>: Encode<'q, DB> + Type<DB>,
+    String: Encode<'q, DB> + Type<DB>,
+    bool: Encode<'q, DB> + Type<DB>,
+    i64: Encode<'q, DB> + Type<DB>,
+    f64: Encode<'q, DB> + Type<DB>,
+{
+    for param in params {
+        query = match param {
+            serde_json::Value::Null => query.bind(None::<String>),
+            serde_json::Value::Bool(b) => query.bind(*b),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => query.bind(i),
+                None => query.bind(n.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(s) => query.bind(s.clone()),
+            other => query.bind(other.to_string()),
+        };
+    }
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NullDriver;
+
+    #[async_trait]
+    impl DatabaseDriver for NullDriver {
+        async fn connect(&self, _config: &ConnectionConfig, _settings: &PoolSettings) -> AppResult<DatabasePool> {
+            Err(AppError::DatabaseConnection("not connected".into()))
+        }
+    }
+
+    #[test]
+    fn registry_resolves_drivers_by_type() {
+        let mut registry = DriverRegistry::with_defaults();
+        assert!(registry.get(&DbType::MySQL).is_some());
+        assert!(registry.get(&DbType::MariaDB).is_some());
+        assert!(registry.get(&DbType::Memcached).is_some());
+        assert!(registry.get(&DbType::ClickHouse).is_none());
+
+        registry.register(DbType::ClickHouse, Arc::new(NullDriver));
+        assert!(registry.get(&DbType::ClickHouse).is_some());
+    }
+}
@@ -0,0 +1,151 @@
+//! MongoDB connections.
+//!
+//! A MongoDB connection is a [`Client`], which pools its connections
+//! internally. The connection test, server statistics (`serverStatus`) and
+//! the database list are supported.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mongodb::bson::doc;
+use mongodb::options::ClientOptions;
+use mongodb::Client;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::ConnectionConfig;
+use common::models::monitor::{DatabaseInfo, DatabaseStats};
+use common::utils::dsn::encode_userinfo;
+use super::{DatabaseDriver, DriverConnection, PoolSettings};
+use crate::DatabasePool;
+
+/// Driver registered for `DbType::MongoDB`.
+pub struct MongoDriver;
+
+#[async_trait]
+impl DatabaseDriver for MongoDriver {
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let mut client_options = ClientOptions::parse(&url(config)?)
+            .await
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        client_options.max_pool_size = Some(settings.max_connections);
+        client_options.min_pool_size = Some(settings.min_connections);
+        client_options.connect_timeout = Some(settings.connect_timeout);
+        client_options.max_idle_time = Some(settings.idle_timeout);
+        let client = Client::with_options(client_options)
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        // Verify connection by pinging
+        client
+            .database("admin")
+            .run_command(doc! { "ping": 1 })
+            .await
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        Ok(DatabasePool::Driver(Arc::new(client)))
+    }
+}
+
+fn url(config: &ConnectionConfig) -> AppResult<String> {
+    let host = config
+        .host
+        .as_deref()
+        .ok_or_else(|| AppError::Validation("MongoDB requires host".into()))?;
+    let port = config.port.unwrap_or(27017);
+
+    let auth = match (&config.username, &config.password) {
+        (Some(user), Some(pass)) if !user.is_empty() => {
+            format!("{}:{}@", encode_userinfo(user), encode_userinfo(pass))
+        }
+        _ => String::new(),
+    };
+    let db = config.database.as_deref().unwrap_or("");
+    Ok(format!("mongodb://{}{}:{}/{}", auth, host, port, db))
+}
+
+#[async_trait]
+impl DriverConnection for Client {
+    async fn ping(&self) -> AppResult<()> {
+        self.database("admin")
+            .run_command(doc! { "ping": 1 })
+            .await
+            .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        let db = self.database("admin");
+        let result = db
+            .run_command(doc! { "serverStatus": 1 })
+            .await
+            .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
+
+        let mut stats = DatabaseStats::default();
+
+        // Server version
+        if let Ok(v) = result.get_str("version") {
+            stats.server_version = Some(format!("MongoDB {}", v));
+        }
+
+        // Uptime
+        if let Ok(up) = result.get_f64("uptime") {
+            stats.uptime_seconds = up as u64;
+        }
+
+        // Connections
+        if let Ok(conns) = result.get_document("connections") {
+            stats.active_connections = conns.get_i32("current").unwrap_or(0) as u32;
+            stats.max_connections = conns.get_i32("available").unwrap_or(0) as u32
+                + stats.active_connections;
+        }
+
+        // Operations (opcounters)
+        if let Ok(ops) = result.get_document("opcounters") {
+            let insert = ops.get_i64("insert").or(ops.get_i32("insert").map(|v| v as i64)).unwrap_or(0);
+            let query = ops.get_i64("query").or(ops.get_i32("query").map(|v| v as i64)).unwrap_or(0);
+            let update = ops.get_i64("update").or(ops.get_i32("update").map(|v| v as i64)).unwrap_or(0);
+            let delete = ops.get_i64("delete").or(ops.get_i32("delete").map(|v| v as i64)).unwrap_or(0);
+            stats.total_queries = (insert + query + update + delete) as u64;
+        }
+
+        // Memory
+        if let Ok(mem) = result.get_document("mem") {
+            let resident_mb = mem.get_i32("resident").unwrap_or(0) as u64;
+            stats.buffer_pool_size = Some(resident_mb * 1024 * 1024); // MB -> bytes
+        }
+
+        if stats.uptime_seconds > 0 {
+            stats.queries_per_second =
+                stats.total_queries as f64 / stats.uptime_seconds as f64;
+        }
+
+        Ok(stats)
+    }
+
+    async fn databases(&self) -> AppResult<Vec<DatabaseInfo>> {
+        let db_names = self
+            .list_database_names()
+            .await
+            .map_err(|e| AppError::DatabaseQuery(e.to_string()))?;
+
+        let mut databases = Vec::new();
+        for name in db_names {
+            let db = self.database(&name);
+            let stats_result = db.run_command(doc! { "dbStats": 1 }).await;
+            let size_mb = match stats_result {
+                Ok(doc) => {
+                    let data_size = doc.get_f64("dataSize")
+                        .or(doc.get_i64("dataSize").map(|v| v as f64))
+                        .or(doc.get_i32("dataSize").map(|v| v as f64))
+                        .unwrap_or(0.0);
+                    data_size / 1024.0 / 1024.0
+                }
+                Err(_) => 0.0,
+            };
+            databases.push(DatabaseInfo {
+                name: name.clone(),
+                size_mb,
+                tables_count: 0,
+            });
+        }
+
+        Ok(databases)
+    }
+}
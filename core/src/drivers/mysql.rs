@@ -0,0 +1,335 @@
+//! MySQL and MariaDB connections.
+//!
+//! Both speak the MySQL protocol through a sqlx [`MySqlPool`]; only the
+//! defaults in error messages differ. SELECT statements get a
+//! `MAX_EXECUTION_TIME` hint when the query has a timeout.
+
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use sqlx::mysql::{MySqlPoolOptions, MySqlRow};
+use sqlx::{Column, MySqlPool, Row, TypeInfo};
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::{ConnectionConfig, DbType};
+use common::models::database::{ColumnDetail, TableInfo};
+use common::models::monitor::{DatabaseInfo, DatabaseStats, ProcessInfo};
+use common::models::query::{ColumnInfo, QueryResult, ValueKind};
+use common::utils::dsn::encode_userinfo;
+use common::utils::SqlLimit;
+use super::{bind_params, sqlx_saturated, sqlx_usage, DatabaseDriver, DriverConnection, DriverQuery, PoolSettings, PoolUsage};
+use crate::DatabasePool;
+use crate::type_mapping;
+
+/// Driver registered for `DbType::MySQL` and `DbType::MariaDB`.
+pub struct MySqlDriver;
+
+#[async_trait]
+impl DatabaseDriver for MySqlDriver {
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let pool = MySqlPoolOptions::new()
+            .max_connections(settings.max_connections)
+            .min_connections(settings.min_connections)
+            .acquire_timeout(settings.connect_timeout)
+            .idle_timeout(settings.idle_timeout)
+            .connect(&url(config)?)
+            .await?;
+        Ok(DatabasePool::MySQL(pool))
+    }
+}
+
+fn url(config: &ConnectionConfig) -> AppResult<String> {
+    let name = if config.db_type == DbType::MariaDB { "MariaDB" } else { "MySQL" };
+    let host = config
+        .host
+        .as_deref()
+        .ok_or_else(|| AppError::Validation(format!("{} requires host", name)))?;
+    let port = config.port.unwrap_or(3306);
+    let username = encode_userinfo(config.username.as_deref().unwrap_or("root"));
+    let password = encode_userinfo(config.password.as_deref().unwrap_or(""));
+    let database = config.database.as_deref().unwrap_or("");
+
+    Ok(format!(
+        "mysql://{}:{}@{}:{}/{}?charset=utf8mb4",
+        username, password, host, port, database
+    ))
+}
+
+#[async_trait]
+impl DriverConnection for MySqlPool {
+    async fn ping(&self) -> AppResult<()> {
+        sqlx::query("SELECT 1").execute(self).await?;
+        Ok(())
+    }
+
+    fn usage(&self) -> Option<PoolUsage> {
+        Some(sqlx_usage(self))
+    }
+
+    fn saturated(&self) -> bool {
+        sqlx_saturated(self)
+    }
+
+    async fn close(&self) {
+        MySqlPool::close(self).await
+    }
+
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        let mut stats = DatabaseStats::default();
+
+        // SHOW GLOBAL STATUS
+        let rows = sqlx::query("SHOW GLOBAL STATUS")
+            .fetch_all(self)
+            .await?;
+
+        for row in &rows {
+            let name: String = row_string(row, "Variable_name");
+            let value: String = row_string(row, "Value");
+            match name.as_str() {
+                "Uptime" => stats.uptime_seconds = value.parse().unwrap_or(0),
+                "Questions" | "Queries" => {
+                    let v = value.parse().unwrap_or(0u64);
+                    if v > stats.total_queries {
+                        stats.total_queries = v;
+                    }
+                }
+                "Threads_connected" => {
+                    stats.active_connections = value.parse().unwrap_or(0)
+                }
+                "Slow_queries" => stats.slow_queries = value.parse().unwrap_or(0),
+                "Bytes_received" => stats.bytes_received = value.parse().unwrap_or(0),
+                "Bytes_sent" => stats.bytes_sent = value.parse().unwrap_or(0),
+                "Innodb_buffer_pool_pages_total" => {
+                    let pages: u64 = value.parse().unwrap_or(0);
+                    stats.buffer_pool_size = Some(pages * 16384); // 16KB per page
+                }
+                _ => {}
+            }
+        }
+
+        // SHOW GLOBAL VARIABLES for max_connections and version
+        let vars = sqlx::query("SHOW GLOBAL VARIABLES WHERE Variable_name IN ('max_connections', 'version')")
+            .fetch_all(self)
+            .await
+            .unwrap_or_default();
+
+        for row in &vars {
+            let name: String = row_string(row, "Variable_name");
+            let value: String = row_string(row, "Value");
+            match name.as_str() {
+                "max_connections" => stats.max_connections = value.parse().unwrap_or(0),
+                "version" => stats.server_version = Some(DbType::describe_mysql_version(&value)),
+                _ => {}
+            }
+        }
+
+        if stats.uptime_seconds > 0 {
+            stats.queries_per_second =
+                stats.total_queries as f64 / stats.uptime_seconds as f64;
+        }
+
+        Ok(stats)
+    }
+
+    async fn processes(&self) -> AppResult<Vec<ProcessInfo>> {
+        let rows = sqlx::query(
+            "SELECT ID, USER, HOST, DB, COMMAND, TIME, STATE, INFO
+             FROM information_schema.PROCESSLIST
+             ORDER BY TIME DESC"
+        )
+        .fetch_all(self)
+        .await?;
+
+        let mut processes = Vec::new();
+        for row in &rows {
+            processes.push(ProcessInfo {
+                id: row.try_get::<u64, _>("ID").unwrap_or(0),
+                user: row_string(row, "USER"),
+                host: row_string(row, "HOST"),
+                db: row_opt_string(row, "DB"),
+                command: row_string(row, "COMMAND"),
+                time: row.try_get::<i32, _>("TIME").unwrap_or(0) as u64,
+                state: row_opt_string(row, "STATE"),
+                info: row_opt_string(row, "INFO"),
+            });
+        }
+        Ok(processes)
+    }
+
+    async fn databases(&self) -> AppResult<Vec<DatabaseInfo>> {
+        let rows = sqlx::query(
+            "SELECT
+                s.SCHEMA_NAME,
+                COUNT(t.TABLE_NAME) as tables_count,
+                CAST(COALESCE(SUM(t.DATA_LENGTH + t.INDEX_LENGTH) / 1024 / 1024, 0) AS DOUBLE) as size_mb
+             FROM information_schema.SCHEMATA s
+             LEFT JOIN information_schema.TABLES t ON s.SCHEMA_NAME = t.TABLE_SCHEMA
+             GROUP BY s.SCHEMA_NAME
+             ORDER BY size_mb DESC"
+        )
+        .fetch_all(self)
+        .await?;
+
+        let mut databases = Vec::new();
+        for row in &rows {
+            databases.push(DatabaseInfo {
+                name: row_string(row, "SCHEMA_NAME"),
+                tables_count: row.try_get::<i64, _>("tables_count").unwrap_or(0) as u32,
+                size_mb: row.try_get::<f64, _>("size_mb").unwrap_or(0.0),
+            });
+        }
+        Ok(databases)
+    }
+
+    async fn query(&self, query: &DriverQuery<'_>) -> AppResult<QueryResult> {
+        let timeout_ms = query.timeout.map(|t| t.as_millis().max(1) as u64);
+        run_query(self, query.sql, query.limit, query.params, timeout_ms, query.start).await
+    }
+
+    async fn execute(&self, sql: &str, params: &[serde_json::Value], _timeout: Duration) -> AppResult<u64> {
+        bind_params(sqlx::query(sql), params)
+            .execute(self)
+            .await
+            .map(|r| r.rows_affected())
+            .map_err(|e| AppError::from(e).with_sql(sql))
+    }
+
+    async fn table_schema(&self, config: &ConnectionConfig) -> AppResult<Vec<TableInfo>> {
+        let rows = sqlx::query(
+            "SELECT TABLE_NAME, COLUMN_NAME, COLUMN_TYPE, IS_NULLABLE, COLUMN_KEY
+             FROM information_schema.COLUMNS
+             WHERE TABLE_SCHEMA = ?
+             ORDER BY TABLE_NAME, ORDINAL_POSITION
+             LIMIT 500",
+        )
+        .bind(config.database.as_deref().unwrap_or_default())
+        .fetch_all(self)
+        .await?;
+
+        let mut tables: Vec<TableInfo> = Vec::new();
+        let mut current_table: Option<String> = None;
+
+        for row in &rows {
+            let table_name: String = row_string(row, "TABLE_NAME");
+            let col = ColumnDetail {
+                name: row_string(row, "COLUMN_NAME"),
+                data_type: row_string(row, "COLUMN_TYPE"),
+                nullable: row_string(row, "IS_NULLABLE") == "YES",
+                key: {
+                    let k = row_string(row, "COLUMN_KEY");
+                    if k.is_empty() { None } else { Some(k) }
+                },
+            };
+
+            if current_table.as_deref() != Some(&table_name) {
+                current_table = Some(table_name.clone());
+                tables.push(TableInfo {
+                    name: table_name,
+                    columns: vec![col],
+                });
+            } else if let Some(t) = tables.last_mut() {
+                t.columns.push(col);
+            }
+        }
+
+        Ok(tables)
+    }
+}
+
+/// Runs a query on a MySQL pool and returns at most `limit` rows.
+pub async fn run_query(
+    pool: &MySqlPool,
+    sql: &str,
+    limit: u32,
+    params: &[serde_json::Value],
+    timeout_ms: Option<u64>,
+    start: Instant,
+) -> AppResult<QueryResult> {
+    // Safety: add a LIMIT or cap the statement's own
+    let sql = SqlLimit::enforce(sql, limit, &DbType::MySQL);
+    let sql = match timeout_ms {
+        Some(ms) => with_max_execution_time(&sql, ms),
+        None => sql,
+    };
+
+    let rows: Vec<MySqlRow> = bind_params(sqlx::query(&sql), params)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::from(e).with_sql(&sql))?;
+
+    let execution_time_ms = start.elapsed().as_millis() as u64;
+
+    // Extract column info
+    let columns: Vec<ColumnInfo> = if let Some(first) = rows.first() {
+        first
+            .columns()
+            .iter()
+            .map(|c| ColumnInfo {
+                name: c.name().to_string(),
+                data_type: c.type_info().to_string(),
+                nullable: None,
+                kind: Some(type_mapping::mysql_kind(c.type_info().name())),
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    // Extract row data
+    let mut result_rows = Vec::new();
+    for row in &rows {
+        let mut values = Vec::new();
+        for (idx, column) in columns.iter().enumerate() {
+            let kind = column.kind.unwrap_or(ValueKind::Text);
+            values.push(type_mapping::mysql_value(row, idx, kind));
+        }
+        result_rows.push(values);
+    }
+
+    let row_count = result_rows.len();
+    Ok(QueryResult {
+        columns,
+        rows: result_rows,
+        row_count,
+        affected_rows: None,
+        execution_time_ms,
+        truncated: false,
+        truncated_cells: Vec::new(),
+    })
+}
+
+/// Reads a text column of a MySQL row, falling back to its raw bytes when
+/// the text decode fails (e.g. binary collations); missing values are empty.
+pub fn row_string(row: &MySqlRow, col: &str) -> String {
+    row.try_get::<String, _>(col).unwrap_or_else(|_| {
+        row.try_get::<Vec<u8>, _>(col)
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .unwrap_or_default()
+    })
+}
+
+/// Reads a nullable text column of a MySQL row like [`row_string`].
+pub fn row_opt_string(row: &MySqlRow, col: &str) -> Option<String> {
+    row.try_get::<Option<String>, _>(col).unwrap_or_else(|_| {
+        row.try_get::<Option<Vec<u8>>, _>(col)
+            .ok()
+            .flatten()
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+    })
+}
+
+/// Adds a MySQL `MAX_EXECUTION_TIME` optimizer hint to a SELECT statement.
+///
+/// MySQL only honours the hint directly after the leading SELECT keyword;
+/// other statements are returned unchanged.
+fn with_max_execution_time(sql: &str, timeout_ms: u64) -> String {
+    let trimmed = sql.trim_start();
+    let is_select = trimmed
+        .get(..6)
+        .is_some_and(|k| k.eq_ignore_ascii_case("select"))
+        && !trimmed[6..].starts_with(|c: char| c.is_alphanumeric() || c == '_');
+    if !is_select {
+        return sql.to_string();
+    }
+    format!("{} /*+ MAX_EXECUTION_TIME({}) */{}", &trimmed[..6], timeout_ms, &trimmed[6..])
+}
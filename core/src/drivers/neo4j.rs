@@ -0,0 +1,309 @@
+//! Neo4j connections (feature `neo4j`).
+//!
+//! Backed by the `neo4rs` Bolt driver. The URI is `bolt://host:port` (default
+//! port 7687); a host given with a scheme (`neo4j://`, `bolt+s://`, ...) is
+//! used as is. The connection's `database` selects the graph database
+//! (default: the server's home database).
+//!
+//! Queries are Cypher with `$name` parameters and run in a transaction that is
+//! always rolled back, so a statement slipping past the read-only check never
+//! commits. The driver does not report column order; it is recovered from the
+//! final `RETURN` clause (see [`CypherAnalyzer::return_columns`]).
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use neo4rs::{query, BoltType, ConfigBuilder, Graph, Query, Txn};
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::ConnectionConfig;
+use common::models::database::GraphElementType;
+use common::models::monitor::{DatabaseInfo, DatabaseStats};
+use common::models::query::{ColumnInfo, QueryResult, ValueKind};
+use common::utils::CypherAnalyzer;
+use super::{DatabaseDriver, DriverConnection, PoolSettings, PoolUsage};
+use crate::DatabasePool;
+use crate::type_mapping;
+
+/// Bolt connection pool of a Neo4j connection.
+#[derive(Clone)]
+pub struct Neo4jPool {
+    graph: Arc<Graph>,
+    /// Database used when a request names none.
+    database: Option<String>,
+    max_connections: u32,
+}
+
+impl Neo4jPool {
+    /// Opens the connection pool and checks that the server answers.
+    pub async fn connect(config: &ConnectionConfig, max_connections: u32, timeout: Duration) -> AppResult<Self> {
+        let host = config
+            .host
+            .as_deref()
+            .ok_or_else(|| AppError::Validation("Neo4j requires host".into()))?
+            .trim_end_matches('/');
+        let port = config.port.unwrap_or(7687);
+        let uri = if host.contains("://") {
+            format!("{}:{}", host, port)
+        } else {
+            format!("bolt://{}:{}", host, port)
+        };
+        let database = config.database.clone().filter(|d| !d.is_empty());
+        let mut builder = ConfigBuilder::default()
+            .uri(uri)
+            .user(config.username.clone().unwrap_or_default())
+            .password(config.password.clone().unwrap_or_default())
+            .max_connections(max_connections as usize);
+        if let Some(database) = &database {
+            builder = builder.db(database.as_str());
+        }
+        let driver_config = builder.build().map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+
+        let graph = tokio::time::timeout(timeout, Graph::connect(driver_config))
+            .await
+            .map_err(|_| AppError::DatabaseConnection("Neo4j connection timed out".into()))?
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        let pool = Self { graph: Arc::new(graph), database, max_connections };
+        tokio::time::timeout(timeout, pool.ping())
+            .await
+            .map_err(|_| AppError::DatabaseConnection("Neo4j connection timed out".into()))?
+            .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        Ok(pool)
+    }
+
+    /// Runs a read-only Cypher query and returns at most `limit` rows.
+    ///
+    /// `params` are passed as `$name` parameters. Columns follow the `RETURN`
+    /// clause; columns it does not name (`RETURN *`) come after, sorted.
+    pub async fn cypher(
+        &self,
+        cypher: &str,
+        database: Option<&str>,
+        limit: u32,
+        params: &BTreeMap<String, serde_json::Value>,
+        start: Instant,
+    ) -> AppResult<QueryResult> {
+        let mut statement = query(cypher.trim().trim_end_matches(';'));
+        for (name, value) in params {
+            let value = BoltType::try_from(value.clone())
+                .map_err(|e| AppError::InvalidInput(format!("Invalid parameter {}: {}", name, e)))?;
+            statement = statement.param(name, value);
+        }
+        let rows = self.rows(statement, database, limit).await?;
+
+        let mut names = CypherAnalyzer::return_columns(cypher);
+        let mut rest: Vec<&String> = rows
+            .iter()
+            .flat_map(|row| row.keys())
+            .filter(|key| !names.contains(key))
+            .collect();
+        rest.sort();
+        rest.dedup();
+        names.extend(rest.into_iter().cloned());
+        names.retain(|name| rows.is_empty() || rows.iter().any(|row| row.contains_key(name)));
+
+        let columns = names
+            .iter()
+            .map(|name| {
+                let sample = rows.iter().filter_map(|row| row.get(name)).find(|v| !matches!(v, BoltType::Null(_)));
+                ColumnInfo {
+                    name: name.clone(),
+                    data_type: sample.map(bolt_type_name).unwrap_or("Null").to_string(),
+                    nullable: None,
+                    kind: Some(sample.map_or(ValueKind::Text, type_mapping::neo4j_kind)),
+                }
+            })
+            .collect();
+        let result_rows: Vec<Vec<serde_json::Value>> = rows
+            .iter()
+            .map(|row| names.iter().map(|name| field(row, name)).collect())
+            .collect();
+
+        let row_count = result_rows.len();
+        Ok(QueryResult {
+            columns,
+            rows: result_rows,
+            row_count,
+            affected_rows: None,
+            execution_time_ms: start.elapsed().as_millis() as u64,
+            truncated: false,
+            truncated_cells: Vec::new(),
+        })
+    }
+
+    /// Node labels with the number of nodes carrying each.
+    pub async fn labels(&self) -> AppResult<Vec<GraphElementType>> {
+        let names = self.names(query("CALL db.labels() YIELD label RETURN label"), "label").await?;
+        let mut labels = Vec::with_capacity(names.len());
+        for name in names {
+            let count = self.count(&format!("MATCH (:`{}`) RETURN count(*) AS count", escape_name(&name))).await?;
+            labels.push(GraphElementType { name, count });
+        }
+        Ok(labels)
+    }
+
+    /// Relationship types with the number of relationships of each.
+    pub async fn relationship_types(&self) -> AppResult<Vec<GraphElementType>> {
+        let names = self
+            .names(query("CALL db.relationshipTypes() YIELD relationshipType RETURN relationshipType"), "relationshipType")
+            .await?;
+        let mut types = Vec::with_capacity(names.len());
+        for name in names {
+            let count = self
+                .count(&format!("MATCH ()-[:`{}`]->() RETURN count(*) AS count", escape_name(&name)))
+                .await?;
+            types.push(GraphElementType { name, count });
+        }
+        Ok(types)
+    }
+
+    async fn names(&self, statement: Query, column: &str) -> AppResult<Vec<String>> {
+        let rows = self.rows(statement, None, u32::MAX).await?;
+        let mut names: Vec<String> = rows
+            .iter()
+            .filter_map(|row| field(row, column).as_str().map(str::to_string))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    async fn count(&self, cypher: &str) -> AppResult<u64> {
+        let rows = self.rows(query(cypher), None, 1).await?;
+        Ok(rows
+            .first()
+            .and_then(|row| field(row, "count").as_u64())
+            .unwrap_or(0))
+    }
+
+    /// Runs a statement in a rolled-back transaction and returns at most `limit` rows.
+    async fn rows(&self, statement: Query, database: Option<&str>, limit: u32) -> AppResult<Vec<HashMap<String, BoltType>>> {
+        let mut txn = match database.or(self.database.as_deref()) {
+            Some(database) => self.graph.start_txn_on(database).await,
+            None => self.graph.start_txn().await,
+        }
+        .map_err(query_error)?;
+        let rows = fetch(&mut txn, statement, limit).await;
+        // A connection is reset when it returns to the pool, so a failed rollback leaves nothing open
+        let _ = txn.rollback().await;
+        rows
+    }
+}
+
+/// Driver registered for `DbType::Neo4j`.
+pub struct Neo4jDriver;
+
+#[async_trait]
+impl DatabaseDriver for Neo4jDriver {
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let pool = Neo4jPool::connect(config, settings.max_connections, settings.connect_timeout).await?;
+        Ok(DatabasePool::Driver(Arc::new(pool)))
+    }
+}
+
+#[async_trait]
+impl DriverConnection for Neo4jPool {
+    /// Checks that the server answers.
+    async fn ping(&self) -> AppResult<()> {
+        self.rows(query("RETURN 1 AS ok"), None, 1).await.map(|_| ())
+    }
+
+    /// The driver does not report connections in use; only the limit is known.
+    fn usage(&self) -> Option<PoolUsage> {
+        Some(PoolUsage { active: 0, idle: 0, max_size: self.max_connections })
+    }
+
+    /// Server version and edition as database statistics.
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        let rows = self
+            .rows(query("CALL dbms.components() YIELD name, versions, edition"), None, 1)
+            .await?;
+        let mut stats = DatabaseStats::default();
+        if let Some(row) = rows.first() {
+            let name = field(row, "name");
+            let version = field(row, "versions");
+            stats.server_version = Some(format!(
+                "{} {}",
+                name.as_str().unwrap_or("Neo4j"),
+                version[0].as_str().unwrap_or_default()
+            ));
+            if let Some(edition) = field(row, "edition").as_str() {
+                stats.extra.insert("edition".to_string(), edition.to_string());
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Lists the online databases of the server (the `system` database excluded).
+    async fn databases(&self) -> AppResult<Vec<DatabaseInfo>> {
+        let rows = self
+            .rows(query("SHOW DATABASES YIELD name, currentStatus"), Some("system"), u32::MAX)
+            .await?;
+        Ok(rows
+            .iter()
+            .filter(|row| field(row, "currentStatus") == "online")
+            .filter_map(|row| field(row, "name").as_str().map(str::to_string))
+            .filter(|name| name != "system")
+            .map(|name| DatabaseInfo { name, tables_count: 0, size_mb: 0.0 })
+            .collect())
+    }
+
+    /// The graph database is chosen per request (see [`Neo4jPool::cypher`]).
+    fn database_per_query(&self) -> bool {
+        true
+    }
+}
+
+async fn fetch(txn: &mut Txn, statement: Query, limit: u32) -> AppResult<Vec<HashMap<String, BoltType>>> {
+    let mut stream = txn.execute(statement).await.map_err(query_error)?;
+    let mut rows = Vec::new();
+    while rows.len() < limit as usize {
+        let Some(row) = stream.next(txn.handle()).await.map_err(query_error)? else {
+            break;
+        };
+        rows.push(
+            row.to::<HashMap<String, BoltType>>()
+                .map_err(|e| AppError::DatabaseQuery(e.to_string()))?,
+        );
+    }
+    Ok(rows)
+}
+
+/// JSON value of a row field; `null` when the row has no such field.
+fn field(row: &HashMap<String, BoltType>, name: &str) -> serde_json::Value {
+    row.get(name).map_or(serde_json::Value::Null, type_mapping::neo4j_value)
+}
+
+/// Escapes a label or relationship type for use between backticks.
+fn escape_name(name: &str) -> String {
+    name.replace('`', "``")
+}
+
+/// Cypher type name of a value, reported as the column's data type.
+fn bolt_type_name(value: &BoltType) -> &'static str {
+    match value {
+        BoltType::String(_) => "String",
+        BoltType::Boolean(_) => "Boolean",
+        BoltType::Map(_) => "Map",
+        BoltType::Null(_) => "Null",
+        BoltType::Integer(_) => "Integer",
+        BoltType::Float(_) => "Float",
+        BoltType::List(_) => "List",
+        BoltType::Node(_) => "Node",
+        BoltType::Relation(_) | BoltType::UnboundedRelation(_) => "Relationship",
+        BoltType::Point2D(_) | BoltType::Point3D(_) => "Point",
+        BoltType::Bytes(_) => "ByteArray",
+        BoltType::Path(_) => "Path",
+        BoltType::Duration(_) => "Duration",
+        BoltType::Date(_) => "Date",
+        BoltType::Time(_) => "Time",
+        BoltType::LocalTime(_) => "LocalTime",
+        BoltType::DateTime(_) | BoltType::DateTimeZoneId(_) => "DateTime",
+        BoltType::LocalDateTime(_) => "LocalDateTime",
+    }
+}
+
+fn query_error(e: neo4rs::Error) -> AppError {
+    AppError::DatabaseQuery(e.to_string())
+}
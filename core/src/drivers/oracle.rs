@@ -0,0 +1,248 @@
+//! Oracle connections (feature `oracle`).
+//!
+//! Backed by the `oracle` crate (ODPI-C), which loads the Oracle Instant
+//! Client libraries at runtime. The driver is blocking, so every call runs on
+//! the blocking thread pool.
+//!
+//! The connect string is `//host:port/service`, where the connection's
+//! `database` is the service name (default: `ORCL`). Statements use `:1`,
+//! `:2`, ... placeholders; named parameters are rewritten to that form.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use oracle::pool::{Pool, PoolBuilder};
+use oracle::sql_type::ToSql;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::ConnectionConfig;
+use common::models::database::{ColumnDetail, TableInfo};
+use common::models::query::{ColumnInfo, QueryResult, ValueKind};
+use super::{DatabaseDriver, DriverConnection, DriverQuery, PoolSettings, PoolUsage};
+use crate::DatabasePool;
+use crate::type_mapping;
+
+/// Service name used when the connection sets no database.
+const DEFAULT_SERVICE_NAME: &str = "ORCL";
+
+/// Session pool of an Oracle connection.
+#[derive(Clone)]
+pub struct OraclePool {
+    pool: Arc<Pool>,
+    max_connections: u32,
+}
+
+impl OraclePool {
+    /// Opens a session pool and checks that a session can be acquired.
+    pub async fn connect(config: &ConnectionConfig, max_connections: u32, min_connections: u32) -> AppResult<Self> {
+        let host = config
+            .host
+            .as_deref()
+            .ok_or_else(|| AppError::Validation("Oracle requires host".into()))?;
+        let connect_string = format!(
+            "//{}:{}/{}",
+            host,
+            config.port.unwrap_or(1521),
+            config.database.as_deref().filter(|d| !d.is_empty()).unwrap_or(DEFAULT_SERVICE_NAME)
+        );
+        let username = config.username.clone().unwrap_or_default();
+        let password = config.password.clone().unwrap_or_default();
+
+        let pool = blocking(move || {
+            let pool = PoolBuilder::new(username, password, connect_string)
+                .min_connections(min_connections)
+                .max_connections(max_connections)
+                .build()?;
+            pool.get()?.ping()?;
+            Ok(pool)
+        })
+        .await
+        .map_err(|e| AppError::DatabaseConnection(e.to_string()))?;
+        Ok(Self { pool: Arc::new(pool), max_connections })
+    }
+}
+
+/// Driver registered for `DbType::Oracle`.
+pub struct OracleDriver;
+
+#[async_trait]
+impl DatabaseDriver for OracleDriver {
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let pool = OraclePool::connect(config, settings.max_connections, settings.min_connections).await?;
+        Ok(DatabasePool::Driver(Arc::new(pool)))
+    }
+}
+
+#[async_trait]
+impl DriverConnection for OraclePool {
+    /// Checks that the server answers.
+    async fn ping(&self) -> AppResult<()> {
+        let pool = self.pool.clone();
+        blocking(move || pool.get()?.ping()).await
+    }
+
+    fn usage(&self) -> Option<PoolUsage> {
+        let busy = self.pool.busy_count().unwrap_or(0);
+        let open = self.pool.open_count().unwrap_or(0);
+        Some(PoolUsage { active: busy, idle: open.saturating_sub(busy), max_size: self.max_connections })
+    }
+
+    /// Runs a query and returns at most `limit` rows.
+    ///
+    /// With a `timeout` the round trips to the server are limited to it.
+    async fn query(&self, query: &DriverQuery<'_>) -> AppResult<QueryResult> {
+        let DriverQuery { limit, timeout, start, .. } = *query;
+        let pool = self.pool.clone();
+        let sql = query.sql.trim().trim_end_matches(';').to_string();
+        let params = query.params.to_vec();
+
+        blocking(move || {
+            let conn = pool.get()?;
+            conn.set_call_timeout(timeout)?;
+            let values = bind_values(&params);
+            let rows = conn.query(&sql, &refs(&values))?;
+
+            let columns: Vec<ColumnInfo> = rows
+                .column_info()
+                .iter()
+                .map(|c| ColumnInfo {
+                    name: c.name().to_string(),
+                    data_type: c.oracle_type().to_string(),
+                    nullable: Some(c.nullable()),
+                    kind: Some(type_mapping::oracle_kind(c.oracle_type())),
+                })
+                .collect();
+
+            let mut result_rows = Vec::new();
+            for row in rows.take(limit as usize) {
+                let row = row?;
+                let values = columns
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, column)| type_mapping::oracle_value(&row, idx, column.kind.unwrap_or(ValueKind::Text)))
+                    .collect();
+                result_rows.push(values);
+            }
+
+            let row_count = result_rows.len();
+            Ok(QueryResult {
+                columns,
+                rows: result_rows,
+                row_count,
+                affected_rows: None,
+                execution_time_ms: start.elapsed().as_millis() as u64,
+                truncated: false,
+                truncated_cells: Vec::new(),
+            })
+        })
+        .await
+    }
+
+    /// Executes a data change in its own transaction and returns the affected row count.
+    ///
+    /// DDL statements commit implicitly on Oracle.
+    async fn execute(&self, sql: &str, params: &[serde_json::Value], timeout: Duration) -> AppResult<u64> {
+        let pool = self.pool.clone();
+        let sql = sql.trim().trim_end_matches(';').to_string();
+        let params = params.to_vec();
+
+        blocking(move || {
+            let conn = pool.get()?;
+            conn.set_call_timeout(Some(timeout))?;
+            let values = bind_values(&params);
+            let affected = match conn.execute(&sql, &refs(&values)) {
+                Ok(statement) => statement.row_count()?,
+                Err(e) => {
+                    let _ = conn.rollback();
+                    return Err(e);
+                }
+            };
+            conn.commit()?;
+            Ok(affected)
+        })
+        .await
+    }
+
+    /// Lists the tables and columns owned by the connection's user from `ALL_TABLES`.
+    async fn table_schema(&self, config: &ConnectionConfig) -> AppResult<Vec<TableInfo>> {
+        let pool = self.pool.clone();
+        let owner = config.username.as_deref().map(str::to_uppercase);
+
+        blocking(move || {
+            let conn = pool.get()?;
+            let owner = match owner {
+                Some(owner) => owner,
+                None => conn.query_row_as::<String>("SELECT USER FROM DUAL", &[])?,
+            };
+            let rows = conn.query(
+                "SELECT c.TABLE_NAME, c.COLUMN_NAME, c.DATA_TYPE, c.NULLABLE, \
+                        (SELECT MIN(k.CONSTRAINT_TYPE) FROM ALL_CONS_COLUMNS cc \
+                           JOIN ALL_CONSTRAINTS k ON k.OWNER = cc.OWNER AND k.CONSTRAINT_NAME = cc.CONSTRAINT_NAME \
+                          WHERE cc.OWNER = c.OWNER AND cc.TABLE_NAME = c.TABLE_NAME \
+                            AND cc.COLUMN_NAME = c.COLUMN_NAME AND k.CONSTRAINT_TYPE IN ('P', 'U')) AS KEY_TYPE \
+                   FROM ALL_TABLES t \
+                   JOIN ALL_TAB_COLUMNS c ON c.OWNER = t.OWNER AND c.TABLE_NAME = t.TABLE_NAME \
+                  WHERE t.OWNER = :1 \
+                  ORDER BY c.TABLE_NAME, c.COLUMN_ID \
+                  FETCH FIRST 500 ROWS ONLY",
+                &[&owner],
+            )?;
+
+            let mut tables: Vec<TableInfo> = Vec::new();
+            for row in rows {
+                let row = row?;
+                let table_name: String = row.get(0usize)?;
+                let key: Option<String> = row.get(4usize)?;
+                let column = ColumnDetail {
+                    name: row.get(1usize)?,
+                    data_type: row.get(2usize)?,
+                    nullable: row.get::<_, String>(3usize)? == "Y",
+                    key: key.map(|k| if k == "P" { "PRI".to_string() } else { "UNI".to_string() }),
+                };
+                match tables.last_mut() {
+                    Some(table) if table.name == table_name => table.columns.push(column),
+                    _ => tables.push(TableInfo { name: table_name, columns: vec![column] }),
+                }
+            }
+            Ok(tables)
+        })
+        .await
+    }
+}
+
+/// Runs a blocking driver call on the blocking thread pool.
+async fn blocking<T, F>(f: F) -> AppResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> oracle::Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Internal(format!("Oracle worker failed: {}", e)))?
+        // Driver errors carry the `ORA-nnnnn` code in their message
+        .map_err(|e| AppError::DatabaseQuery(e.to_string()))
+}
+
+/// Converts JSON parameters to bind values; booleans become 1 / 0.
+fn bind_values(params: &[serde_json::Value]) -> Vec<Box<dyn ToSql>> {
+    params
+        .iter()
+        .map(|param| -> Box<dyn ToSql> {
+            match param {
+                serde_json::Value::Null => Box::new(None::<String>),
+                serde_json::Value::Bool(b) => Box::new(i64::from(*b)),
+                serde_json::Value::Number(n) => match n.as_i64() {
+                    Some(i) => Box::new(i),
+                    None => Box::new(n.as_f64().unwrap_or_default()),
+                },
+                serde_json::Value::String(s) => Box::new(s.clone()),
+                other => Box::new(other.to_string()),
+            }
+        })
+        .collect()
+}
+
+fn refs(values: &[Box<dyn ToSql>]) -> Vec<&dyn ToSql> {
+    values.iter().map(|v| v.as_ref() as &dyn ToSql).collect()
+}
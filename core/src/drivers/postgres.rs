@@ -0,0 +1,320 @@
+//! PostgreSQL connections.
+//!
+//! Backed by a sqlx [`PgPool`]. Timeouts are applied with `SET LOCAL
+//! statement_timeout` inside a transaction, so pooled sessions keep their
+//! defaults.
+
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use sqlx::postgres::{PgPoolOptions, PgRow};
+use sqlx::{Column, PgPool, Row, TypeInfo};
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::{ConnectionConfig, DbType};
+use common::models::database::{ColumnDetail, TableInfo};
+use common::models::monitor::{DatabaseInfo, DatabaseStats, ProcessInfo};
+use common::models::query::{ColumnInfo, QueryResult, ValueKind};
+use common::utils::dsn::encode_userinfo;
+use common::utils::SqlLimit;
+use super::{bind_params, sqlx_saturated, sqlx_usage, DatabaseDriver, DriverConnection, DriverQuery, PoolSettings, PoolUsage};
+use crate::DatabasePool;
+use crate::type_mapping;
+
+/// Driver registered for `DbType::Postgres`.
+pub struct PostgresDriver;
+
+#[async_trait]
+impl DatabaseDriver for PostgresDriver {
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let pool = PgPoolOptions::new()
+            .max_connections(settings.max_connections)
+            .min_connections(settings.min_connections)
+            .acquire_timeout(settings.connect_timeout)
+            .idle_timeout(settings.idle_timeout)
+            .connect(&url(config)?)
+            .await?;
+        Ok(DatabasePool::Postgres(pool))
+    }
+}
+
+fn url(config: &ConnectionConfig) -> AppResult<String> {
+    let host = config
+        .host
+        .as_deref()
+        .ok_or_else(|| AppError::Validation("PostgreSQL requires host".into()))?;
+    let port = config.port.unwrap_or(5432);
+    let username = encode_userinfo(config.username.as_deref().unwrap_or("postgres"));
+    let password = encode_userinfo(config.password.as_deref().unwrap_or(""));
+    let database = config.database.as_deref().unwrap_or("postgres");
+
+    Ok(format!(
+        "postgres://{}:{}@{}:{}/{}",
+        username, password, host, port, database
+    ))
+}
+
+#[async_trait]
+impl DriverConnection for PgPool {
+    async fn ping(&self) -> AppResult<()> {
+        sqlx::query("SELECT 1").execute(self).await?;
+        Ok(())
+    }
+
+    fn usage(&self) -> Option<PoolUsage> {
+        Some(sqlx_usage(self))
+    }
+
+    fn saturated(&self) -> bool {
+        sqlx_saturated(self)
+    }
+
+    async fn close(&self) {
+        PgPool::close(self).await
+    }
+
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        let mut stats = DatabaseStats::default();
+
+        // Server version
+        if let Ok(row) = sqlx::query("SHOW server_version").fetch_one(self).await {
+            let ver: String = row.try_get("server_version").unwrap_or_default();
+            stats.server_version = Some(format!("PostgreSQL {}", ver));
+        }
+
+        // Active connections
+        if let Ok(row) = sqlx::query("SELECT count(*) as cnt FROM pg_stat_activity")
+            .fetch_one(self)
+            .await
+        {
+            stats.active_connections = row.try_get::<i64, _>("cnt").unwrap_or(0) as u32;
+        }
+
+        // Max connections
+        if let Ok(row) = sqlx::query("SHOW max_connections").fetch_one(self).await {
+            let val: String = row.try_get("max_connections").unwrap_or_default();
+            stats.max_connections = val.parse().unwrap_or(0);
+        }
+
+        // Aggregated stats from pg_stat_database
+        if let Ok(row) = sqlx::query(
+            "SELECT COALESCE(SUM(xact_commit + xact_rollback), 0) as total_queries,
+                    COALESCE(SUM(blks_read), 0) as blks_read,
+                    COALESCE(SUM(blks_hit), 0) as blks_hit
+             FROM pg_stat_database"
+        )
+        .fetch_one(self)
+        .await
+        {
+            stats.total_queries = row.try_get::<i64, _>("total_queries").unwrap_or(0) as u64;
+        }
+
+        // Uptime
+        if let Ok(row) = sqlx::query(
+            "SELECT EXTRACT(EPOCH FROM (now() - pg_postmaster_start_time()))::bigint as uptime"
+        )
+        .fetch_one(self)
+        .await
+        {
+            stats.uptime_seconds = row.try_get::<i64, _>("uptime").unwrap_or(0) as u64;
+        }
+
+        if stats.uptime_seconds > 0 {
+            stats.queries_per_second =
+                stats.total_queries as f64 / stats.uptime_seconds as f64;
+        }
+
+        Ok(stats)
+    }
+
+    async fn processes(&self) -> AppResult<Vec<ProcessInfo>> {
+        let rows = sqlx::query(
+            "SELECT pid, usename, client_addr, datname, state, query,
+                    EXTRACT(EPOCH FROM (now() - query_start))::bigint as duration
+             FROM pg_stat_activity
+             WHERE state IS NOT NULL
+             ORDER BY duration DESC NULLS LAST
+             LIMIT 50"
+        )
+        .fetch_all(self)
+        .await?;
+
+        let mut processes = Vec::new();
+        for row in &rows {
+            processes.push(ProcessInfo {
+                id: row.try_get::<i32, _>("pid").unwrap_or(0) as u64,
+                user: row.try_get::<String, _>("usename").unwrap_or_default(),
+                host: row
+                    .try_get::<Option<String>, _>("client_addr")
+                    .unwrap_or(None)
+                    .unwrap_or_else(|| "local".to_string()),
+                db: row.try_get::<Option<String>, _>("datname").unwrap_or(None),
+                command: row
+                    .try_get::<Option<String>, _>("state")
+                    .unwrap_or(None)
+                    .unwrap_or_else(|| "unknown".to_string()),
+                time: row.try_get::<i64, _>("duration").unwrap_or(0) as u64,
+                state: row.try_get::<Option<String>, _>("state").unwrap_or(None),
+                info: row.try_get::<Option<String>, _>("query").unwrap_or(None),
+            });
+        }
+        Ok(processes)
+    }
+
+    async fn databases(&self) -> AppResult<Vec<DatabaseInfo>> {
+        let rows = sqlx::query(
+            "SELECT d.datname as name,
+                    (SELECT count(*) FROM information_schema.tables WHERE table_catalog = d.datname) as tables_count,
+                    pg_database_size(d.datname) / 1024.0 / 1024.0 as size_mb
+             FROM pg_database d
+             WHERE d.datistemplate = false
+             ORDER BY size_mb DESC"
+        )
+        .fetch_all(self)
+        .await?;
+
+        let mut databases = Vec::new();
+        for row in &rows {
+            databases.push(DatabaseInfo {
+                name: row.try_get::<String, _>("name").unwrap_or_default(),
+                tables_count: row.try_get::<i64, _>("tables_count").unwrap_or(0) as u32,
+                size_mb: row.try_get::<f64, _>("size_mb").unwrap_or(0.0),
+            });
+        }
+        Ok(databases)
+    }
+
+    async fn query(&self, query: &DriverQuery<'_>) -> AppResult<QueryResult> {
+        let timeout_ms = query.timeout.map(|t| t.as_millis().max(1) as u64);
+        run_query(self, query.sql, query.limit, query.params, timeout_ms, query.start).await
+    }
+
+    async fn execute(&self, sql: &str, params: &[serde_json::Value], timeout: Duration) -> AppResult<u64> {
+        let mut tx = self.begin().await?;
+        sqlx::query(&format!("SET LOCAL statement_timeout = {}", timeout.as_millis().max(1)))
+            .execute(&mut *tx)
+            .await?;
+        let affected = bind_params(sqlx::query(sql), params)
+            .execute(&mut *tx)
+            .await
+            .map(|r| r.rows_affected())
+            .map_err(|e| AppError::from(e).with_sql(sql))?;
+        tx.commit().await?;
+        Ok(affected)
+    }
+
+    async fn table_schema(&self, _config: &ConnectionConfig) -> AppResult<Vec<TableInfo>> {
+        let rows = sqlx::query(
+            "SELECT c.table_name, c.column_name, c.data_type, c.is_nullable,
+                    CASE WHEN tc.constraint_type = 'PRIMARY KEY' THEN 'PRI'
+                         WHEN tc.constraint_type = 'UNIQUE' THEN 'UNI'
+                         ELSE NULL END AS column_key
+             FROM information_schema.columns c
+             LEFT JOIN information_schema.key_column_usage kcu
+                ON c.table_schema = kcu.table_schema AND c.table_name = kcu.table_name AND c.column_name = kcu.column_name
+             LEFT JOIN information_schema.table_constraints tc
+                ON kcu.constraint_name = tc.constraint_name AND kcu.table_schema = tc.table_schema
+             WHERE c.table_schema = 'public'
+             ORDER BY c.table_name, c.ordinal_position
+             LIMIT 500",
+        )
+        .fetch_all(self)
+        .await?;
+
+        let mut tables: Vec<TableInfo> = Vec::new();
+        let mut current_table: Option<String> = None;
+
+        for row in &rows {
+            let table_name: String = row.try_get("table_name").unwrap_or_default();
+            let col = ColumnDetail {
+                name: row.try_get("column_name").unwrap_or_default(),
+                data_type: row.try_get("data_type").unwrap_or_default(),
+                nullable: row.try_get::<String, _>("is_nullable").unwrap_or_default() == "YES",
+                key: row.try_get::<Option<String>, _>("column_key").unwrap_or(None),
+            };
+
+            if current_table.as_deref() != Some(&table_name) {
+                current_table = Some(table_name.clone());
+                tables.push(TableInfo {
+                    name: table_name,
+                    columns: vec![col],
+                });
+            } else if let Some(t) = tables.last_mut() {
+                t.columns.push(col);
+            }
+        }
+
+        Ok(tables)
+    }
+}
+
+/// Runs a query on a PostgreSQL pool and returns at most `limit` rows.
+pub async fn run_query(
+    pool: &PgPool,
+    sql: &str,
+    limit: u32,
+    params: &[serde_json::Value],
+    timeout_ms: Option<u64>,
+    start: Instant,
+) -> AppResult<QueryResult> {
+    let sql = SqlLimit::enforce(sql, limit, &DbType::Postgres);
+
+    let rows: Vec<PgRow> = match timeout_ms {
+        // SET LOCAL scopes the timeout to this transaction, so the pooled
+        // connection keeps its default.
+        Some(ms) => {
+            let mut tx = pool.begin().await?;
+            sqlx::query(&format!("SET LOCAL statement_timeout = {}", ms))
+                .execute(&mut *tx)
+                .await?;
+            let rows = bind_params(sqlx::query(&sql), params)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| AppError::from(e).with_sql(&sql))?;
+            tx.commit().await?;
+            rows
+        }
+        None => bind_params(sqlx::query(&sql), params)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| AppError::from(e).with_sql(&sql))?,
+    };
+
+    let execution_time_ms = start.elapsed().as_millis() as u64;
+
+    let columns: Vec<ColumnInfo> = if let Some(first) = rows.first() {
+        first
+            .columns()
+            .iter()
+            .map(|c| ColumnInfo {
+                name: c.name().to_string(),
+                data_type: c.type_info().to_string(),
+                nullable: None,
+                kind: Some(type_mapping::postgres_kind(c.type_info().name())),
+            })
+            .collect()
+    } else {
+        vec![]
+    };
+
+    let mut result_rows = Vec::new();
+    for row in &rows {
+        let mut values = Vec::new();
+        for (idx, column) in columns.iter().enumerate() {
+            let kind = column.kind.unwrap_or(ValueKind::Text);
+            values.push(type_mapping::postgres_value(row, idx, kind));
+        }
+        result_rows.push(values);
+    }
+
+    let row_count = result_rows.len();
+    Ok(QueryResult {
+        columns,
+        rows: result_rows,
+        row_count,
+        affected_rows: None,
+        execution_time_ms,
+        truncated: false,
+        truncated_cells: Vec::new(),
+    })
+}
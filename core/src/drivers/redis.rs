@@ -0,0 +1,101 @@
+//! Redis connections.
+//!
+//! A Redis connection is one multiplexed [`ConnectionManager`] that
+//! reconnects on its own. Only the connection test and server statistics
+//! (`INFO`) are supported.
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::ConnectionConfig;
+use common::models::monitor::DatabaseStats;
+use common::utils::dsn::encode_userinfo;
+use super::{DatabaseDriver, DriverConnection, PoolSettings, PoolUsage};
+use crate::DatabasePool;
+
+/// Driver registered for `DbType::Redis`.
+pub struct RedisDriver;
+
+#[async_trait]
+impl DatabaseDriver for RedisDriver {
+    async fn connect(&self, config: &ConnectionConfig, _settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let client = redis::Client::open(url(config)?)
+            .map_err(|e| AppError::RedisConnection(e.to_string()))?;
+        let manager = ConnectionManager::new(client)
+            .await?;
+        Ok(DatabasePool::Driver(std::sync::Arc::new(manager)))
+    }
+}
+
+fn url(config: &ConnectionConfig) -> AppResult<String> {
+    let host = config
+        .host
+        .as_deref()
+        .ok_or_else(|| AppError::Validation("Redis requires host".into()))?;
+    let port = config.port.unwrap_or(6379);
+
+    if let Some(password) = &config.password {
+        Ok(format!("redis://:{}@{}:{}", encode_userinfo(password), host, port))
+    } else {
+        Ok(format!("redis://{}:{}", host, port))
+    }
+}
+
+#[async_trait]
+impl DriverConnection for ConnectionManager {
+    async fn ping(&self) -> AppResult<()> {
+        let mut conn = self.clone();
+        redis::cmd("PING")
+            .query_async::<String>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    fn usage(&self) -> Option<PoolUsage> {
+        Some(PoolUsage { active: 1, idle: 0, max_size: 1 })
+    }
+
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        let mut conn = self.clone();
+        let info: String = redis::cmd("INFO")
+            .query_async(&mut conn)
+            .await?;
+
+        let mut stats = DatabaseStats::default();
+        for line in info.lines() {
+            if let Some((key, val)) = line.split_once(':') {
+                match key {
+                    "uptime_in_seconds" => {
+                        stats.uptime_seconds = val.trim().parse().unwrap_or(0)
+                    }
+                    "connected_clients" => {
+                        stats.active_connections = val.trim().parse().unwrap_or(0)
+                    }
+                    "maxclients" => {
+                        stats.max_connections = val.trim().parse().unwrap_or(0)
+                    }
+                    "total_commands_processed" => {
+                        stats.total_queries = val.trim().parse().unwrap_or(0)
+                    }
+                    "used_memory" => {
+                        stats.buffer_pool_size =
+                            Some(val.trim().parse().unwrap_or(0));
+                    }
+                    "redis_version" => {
+                        stats.server_version =
+                            Some(format!("Redis {}", val.trim()));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if stats.uptime_seconds > 0 {
+            stats.queries_per_second =
+                stats.total_queries as f64 / stats.uptime_seconds as f64;
+        }
+
+        Ok(stats)
+    }
+}
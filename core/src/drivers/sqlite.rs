@@ -0,0 +1,122 @@
+//! SQLite connections.
+//!
+//! Backed by a single-connection sqlx [`SqlitePool`] on the connection's
+//! `file_path`; the file is created when missing.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use sqlx::sqlite::{SqlitePoolOptions, SqliteRow};
+use sqlx::{Column, Row, SqlitePool, TypeInfo};
+
+use common::errors::{AppError, AppResult};
+use common::models::connection::{ConnectionConfig, DbType};
+use common::models::monitor::DatabaseStats;
+use common::models::query::{ColumnInfo, QueryResult, ValueKind};
+use common::utils::SqlLimit;
+use super::{bind_params, sqlx_saturated, sqlx_usage, DatabaseDriver, DriverConnection, DriverQuery, PoolSettings, PoolUsage};
+use crate::DatabasePool;
+use crate::type_mapping;
+
+/// Driver registered for `DbType::SQLite`.
+pub struct SqliteDriver;
+
+#[async_trait]
+impl DatabaseDriver for SqliteDriver {
+    async fn connect(&self, config: &ConnectionConfig, settings: &PoolSettings) -> AppResult<DatabasePool> {
+        let path = config
+            .file_path
+            .as_deref()
+            .ok_or_else(|| AppError::Validation("SQLite requires file_path".into()))?;
+        let url = format!("sqlite:{}?mode=rwc", path);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(settings.connect_timeout)
+            .idle_timeout(settings.idle_timeout)
+            .connect(&url)
+            .await?;
+        Ok(DatabasePool::SQLite(pool))
+    }
+}
+
+#[async_trait]
+impl DriverConnection for SqlitePool {
+    async fn ping(&self) -> AppResult<()> {
+        sqlx::query("SELECT 1").execute(self).await?;
+        Ok(())
+    }
+
+    fn usage(&self) -> Option<PoolUsage> {
+        Some(sqlx_usage(self))
+    }
+
+    fn saturated(&self) -> bool {
+        sqlx_saturated(self)
+    }
+
+    async fn close(&self) {
+        SqlitePool::close(self).await
+    }
+
+    async fn stats(&self) -> AppResult<DatabaseStats> {
+        Ok(DatabaseStats {
+            server_version: Some("SQLite (embedded)".to_string()),
+            ..Default::default()
+        })
+    }
+
+    async fn query(&self, query: &DriverQuery<'_>) -> AppResult<QueryResult> {
+        let sql = SqlLimit::enforce(query.sql, query.limit, &DbType::SQLite);
+
+        let rows: Vec<SqliteRow> = bind_params(sqlx::query(&sql), query.params)
+            .fetch_all(self)
+            .await
+            .map_err(|e| AppError::from(e).with_sql(&sql))?;
+
+        let execution_time_ms = query.start.elapsed().as_millis() as u64;
+
+        let columns: Vec<ColumnInfo> = if let Some(first) = rows.first() {
+            first
+                .columns()
+                .iter()
+                .map(|c| ColumnInfo {
+                    name: c.name().to_string(),
+                    data_type: c.type_info().to_string(),
+                    nullable: None,
+                    kind: Some(type_mapping::sqlite_kind(c.type_info().name())),
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let mut result_rows = Vec::new();
+        for row in &rows {
+            let mut values = Vec::new();
+            for (idx, column) in columns.iter().enumerate() {
+                let kind = column.kind.unwrap_or(ValueKind::Text);
+                values.push(type_mapping::sqlite_value(row, idx, kind));
+            }
+            result_rows.push(values);
+        }
+
+        let row_count = result_rows.len();
+        Ok(QueryResult {
+            columns,
+            rows: result_rows,
+            row_count,
+            affected_rows: None,
+            execution_time_ms,
+            truncated: false,
+            truncated_cells: Vec::new(),
+        })
+    }
+
+    async fn execute(&self, sql: &str, params: &[serde_json::Value], _timeout: Duration) -> AppResult<u64> {
+        bind_params(sqlx::query(sql), params)
+            .execute(self)
+            .await
+            .map(|r| r.rows_affected())
+            .map_err(|e| AppError::from(e).with_sql(sql))
+    }
+}
@@ -0,0 +1,249 @@
+//! Query execution.
+//!
+//! [`QueryExecutor`] runs statements on the pools of a [`ConnectionManager`]
+//! and bounds what they cost:
+//! - a timeout, enforced locally and server-side where the driver supports it
+//!   (MySQL `MAX_EXECUTION_TIME` for SELECTs, PostgreSQL `statement_timeout`)
+//! - at most [`ResultLimits::max_rows`] rows per result
+//! - oversized cells clipped and trailing rows dropped to keep the result
+//!   within [`ResultLimits::max_cell_bytes`] / [`ResultLimits::max_result_bytes`]
+
+use std::sync::Arc;
+#[cfg(feature = "neo4j")]
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use common::db_error::DbErrorCategory;
+use common::errors::{AppError, AppResult};
+use common::models::query::QueryResult;
+
+use crate::drivers::DriverQuery;
+use crate::ConnectionManager;
+
+/// Size limits applied to query results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResultLimits {
+    /// Maximum rows a query may return; smaller requested limits win.
+    pub max_rows: u32,
+    /// Maximum size of a text / JSON cell, in bytes.
+    pub max_cell_bytes: usize,
+    /// Maximum serialized size of a result's rows, in bytes.
+    pub max_result_bytes: usize,
+}
+
+impl Default for ResultLimits {
+    fn default() -> Self {
+        Self {
+            max_rows: 100_000,
+            max_cell_bytes: 64 * 1024,
+            max_result_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// Runs statements on the open connections of a [`ConnectionManager`].
+///
+/// ```no_run
+/// # async fn example(connections: std::sync::Arc<dbm_core::ConnectionManager>) -> common::errors::AppResult<()> {
+/// use std::time::Duration;
+/// use dbm_core::QueryExecutor;
+///
+/// let executor = QueryExecutor::new(connections);
+/// let result = executor
+///     .query("local", None, "SELECT * FROM users", 100, &[], Some(Duration::from_secs(30)))
+///     .await?;
+/// println!("{} rows", result.row_count);
+/// # Ok(())
+/// # }
+/// ```
+pub struct QueryExecutor {
+    connections: Arc<ConnectionManager>,
+    /// Result limits; replaced when the embedding service reloads its config.
+    limits: std::sync::RwLock<ResultLimits>,
+}
+
+impl QueryExecutor {
+    /// Creates an executor with the default [`ResultLimits`].
+    pub fn new(connections: Arc<ConnectionManager>) -> Self {
+        Self {
+            connections,
+            limits: std::sync::RwLock::new(ResultLimits::default()),
+        }
+    }
+
+    /// Sets the result limits.
+    pub fn with_limits(self, limits: ResultLimits) -> Self {
+        self.set_limits(limits);
+        self
+    }
+
+    /// The connection manager the statements run on.
+    pub fn connections(&self) -> &Arc<ConnectionManager> {
+        &self.connections
+    }
+
+    /// Result limits.
+    pub fn limits(&self) -> ResultLimits {
+        *self.limits.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replaces the result limits; statements started afterwards use them.
+    pub fn set_limits(&self, limits: ResultLimits) {
+        *self.limits.write().unwrap_or_else(|e| e.into_inner()) = limits;
+    }
+
+    /// Runs a query on an open connection and returns at most `limit` rows.
+    ///
+    /// The statement runs in `database` when given (see
+    /// [`ConnectionManager::query_pool`]). `params` are bound positionally to
+    /// the statement's placeholders. Exceeding `timeout` yields `AppError::Timeout`.
+    ///
+    /// # Errors
+    /// `ConnectionNotFound` if the connection's pool is not open, and the
+    /// driver's error if the statement fails.
+    pub async fn query(
+        &self,
+        id: &str,
+        database: Option<&str>,
+        sql: &str,
+        limit: u32,
+        params: &[serde_json::Value],
+        timeout: Option<Duration>,
+    ) -> AppResult<QueryResult> {
+        let start = Instant::now();
+        let limits = self.limits();
+        let limit = limit.min(limits.max_rows);
+        let pool = self.connections.query_pool(id, database).await?;
+
+        let query = DriverQuery { sql, database, limit, params, timeout, start };
+        let run = pool.connection().query(&query);
+        let result = match timeout {
+            Some(timeout) => with_timeout(timeout, run).await,
+            None => run.await,
+        };
+        result.map(|mut result| {
+            result.limit_rows(limit as usize);
+            result.clip_cells(limits.max_cell_bytes);
+            result.clip_rows(limits.max_result_bytes);
+            result
+        })
+    }
+
+    /// Runs a data change or DDL statement and returns the affected row count.
+    ///
+    /// Callers are responsible for confirming the change first. The timeout is
+    /// enforced locally, and server-side on PostgreSQL via `statement_timeout`.
+    pub async fn execute(
+        &self,
+        id: &str,
+        database: Option<&str>,
+        sql: &str,
+        params: &[serde_json::Value],
+        timeout: Duration,
+    ) -> AppResult<QueryResult> {
+        let start = Instant::now();
+        let pool = self.connections.query_pool(id, database).await?;
+
+        let run = async {
+            let rows = pool.connection().execute(sql, params, timeout).await?;
+            Ok(QueryResult::affected(rows, start.elapsed().as_millis() as u64))
+        };
+        with_timeout(timeout, run).await
+    }
+
+    /// Runs a read-only Cypher query on a Neo4j connection.
+    ///
+    /// `params` are passed as `$name` parameters and the query runs in
+    /// `database` when given. Timeouts and result limits follow [`Self::query`].
+    ///
+    /// # Errors
+    /// `UnsupportedDatabaseType` if the connection is not a Neo4j one.
+    #[cfg(feature = "neo4j")]
+    pub async fn cypher(
+        &self,
+        id: &str,
+        database: Option<&str>,
+        cypher: &str,
+        limit: u32,
+        params: &BTreeMap<String, serde_json::Value>,
+        timeout: Duration,
+    ) -> AppResult<QueryResult> {
+        let start = Instant::now();
+        let limits = self.limits();
+        let limit = limit.min(limits.max_rows);
+        let pool = self.connections.pool(id).await.ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))?;
+
+        let run = async {
+            match pool.connection().downcast_ref::<crate::drivers::neo4j::Neo4jPool>() {
+                Some(p) => p.cypher(cypher, database, limit, params, start).await,
+                None => Err(AppError::UnsupportedDatabaseType(
+                    "Cypher queries are only supported for Neo4j".to_string(),
+                )),
+            }
+        };
+        with_timeout(timeout, run).await.map(|mut result| {
+            result.clip_cells(limits.max_cell_bytes);
+            result.clip_rows(limits.max_result_bytes);
+            result
+        })
+    }
+}
+
+/// Runs a statement within `timeout`; a timeout reported by the server (e.g.
+/// `statement_timeout`) yields the same `AppError::Timeout` as the local one.
+async fn with_timeout<F>(timeout: Duration, run: F) -> AppResult<QueryResult>
+where
+    F: std::future::Future<Output = AppResult<QueryResult>>,
+{
+    let timeout_ms = timeout.as_millis().max(1) as u64;
+    match tokio::time::timeout(timeout, run).await {
+        Ok(Err(AppError::Database(details))) if details.category == DbErrorCategory::Timeout => {
+            Err(query_timeout_error(timeout_ms))
+        }
+        Ok(result) => result,
+        Err(_) => Err(query_timeout_error(timeout_ms)),
+    }
+}
+
+fn query_timeout_error(timeout_ms: u64) -> AppError {
+    AppError::Timeout(format!("query exceeded the timeout of {} ms", timeout_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common::models::connection::{CreateConnectionRequest, DbType};
+
+    async fn executor(limits: ResultLimits) -> QueryExecutor {
+        let config = CreateConnectionRequest {
+            name: "local".to_string(),
+            db_type: Some(DbType::SQLite),
+            file_path: Some(":memory:".to_string()),
+            ..Default::default()
+        }
+        .into_config("local".to_string(), String::new())
+        .unwrap();
+        let connections = Arc::new(ConnectionManager::new());
+        connections.connect(config).await.unwrap();
+        QueryExecutor::new(connections).with_limits(limits)
+    }
+
+    #[tokio::test]
+    async fn caps_rows_at_the_limits() {
+        let executor = executor(ResultLimits { max_rows: 2, ..ResultLimits::default() }).await;
+        let sql = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 5) SELECT i FROM n";
+        let result = executor.query("local", None, sql, 10, &[], None).await.unwrap();
+        assert_eq!(result.rows.len(), 2);
+
+        let result = executor.query("local", None, "SELECT ? AS v", 10, &[serde_json::json!(7)], None).await.unwrap();
+        assert_eq!(result.rows[0][0], serde_json::json!(7));
+    }
+
+    #[tokio::test]
+    async fn reports_unknown_connections() {
+        let executor = executor(ResultLimits::default()).await;
+        let result = executor.execute("missing", None, "DELETE FROM t", &[], Duration::from_secs(1)).await;
+        assert!(matches!(result, Err(AppError::ConnectionNotFound(_))));
+    }
+}
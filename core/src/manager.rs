@@ -0,0 +1,439 @@
+//! Connection pool management.
+//!
+//! [`ConnectionManager`] opens pools through the [`DriverRegistry`] and keeps
+//! the open ones by connection ID, along with the config they were opened
+//! with. It does not store connections anywhere: services persist their
+//! configs themselves and hand them over when a pool is opened.
+//!
+//! Passwords referenced through `password_ref` are resolved from the secrets
+//! backends (see [`common::secrets`]) each time a pool is opened.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use common::db_error::DbErrorCategory;
+use common::errors::{AppError, AppResult};
+use common::models::connection::{ConnectionConfig, DbType};
+use common::models::monitor::DatabaseInfo;
+use common::secrets::SecretResolver;
+use sqlx::{MySqlPool, PgPool, SqlitePool};
+use tokio::sync::RwLock;
+
+use crate::drivers::{DriverConnection, DriverRegistry, PoolSettings};
+
+const DEFAULT_MAX_CONNECTIONS: u32 = 10;
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 30;
+/// Idle timeout of pooled connections when the connection sets none.
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 600;
+const DEFAULT_MAX_DATABASE_POOLS: usize = 4;
+
+/// Connection pool of a database connection.
+///
+/// The sqlx pools keep their own variants for the modules that run SQL on
+/// them directly; every other driver's connection is a [`DatabasePool::Driver`].
+#[derive(Clone)]
+pub enum DatabasePool {
+    /// MySQL connection pool.
+    MySQL(MySqlPool),
+    /// PostgreSQL connection pool.
+    Postgres(PgPool),
+    /// SQLite connection pool.
+    SQLite(SqlitePool),
+    /// Connection of another registered driver.
+    Driver(Arc<dyn DriverConnection>),
+}
+
+impl DatabasePool {
+    /// The pool as a driver connection.
+    pub fn connection(&self) -> &dyn DriverConnection {
+        match self {
+            DatabasePool::MySQL(pool) => pool,
+            DatabasePool::Postgres(pool) => pool,
+            DatabasePool::SQLite(pool) => pool,
+            DatabasePool::Driver(connection) => connection.as_ref(),
+        }
+    }
+}
+
+/// Pool sizing used where a connection's `pool_options` leave it unset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolDefaults {
+    /// Maximum number of connections per pool.
+    pub max_connections: u32,
+    /// Timeout of connecting / acquiring a connection.
+    pub connect_timeout: Duration,
+    /// Idle connections are closed after this long.
+    pub idle_timeout: Duration,
+}
+
+impl Default for PoolDefaults {
+    fn default() -> Self {
+        Self {
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            idle_timeout: Duration::from_secs(DEFAULT_IDLE_TIMEOUT_SECS),
+        }
+    }
+}
+
+/// An open pool and the config it was opened with.
+struct OpenConnection {
+    config: ConnectionConfig,
+    pool: DatabasePool,
+}
+
+/// Opens and caches the pools of database connections.
+///
+/// ```no_run
+/// # async fn example() -> common::errors::AppResult<()> {
+/// use dbm_core::ConnectionManager;
+/// use common::models::connection::CreateConnectionRequest;
+///
+/// let connections = ConnectionManager::new();
+/// let config = CreateConnectionRequest {
+///     name: "local".to_string(),
+///     dsn: Some("sqlite:///data/app.db".to_string()),
+///     ..Default::default()
+/// }
+/// .into_config("local".to_string(), String::new())?;
+/// connections.connect(config).await?;
+/// let latency = connections.ping("local").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ConnectionManager {
+    /// Drivers opening the pools, by database type.
+    drivers: DriverRegistry,
+    /// Resolves `password_ref` of connections.
+    secrets: SecretResolver,
+    /// Pool sizing defaults; replaced when the embedding service reloads its config.
+    defaults: std::sync::RwLock<PoolDefaults>,
+    /// Per-connection limit of pools logged into other databases.
+    max_database_pools: usize,
+    /// Open pools indexed by connection ID.
+    connections: RwLock<HashMap<String, OpenConnection>>,
+    /// Pools logged into other databases on a connection's server, indexed by
+    /// connection ID and database (see [`ConnectionManager::query_pool`]).
+    database_pools: RwLock<HashMap<(String, String), DatabasePool>>,
+}
+
+impl Default for ConnectionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectionManager {
+    /// Creates a manager with the built-in drivers, the default pool sizing
+    /// and the secrets backends configured in the environment.
+    pub fn new() -> Self {
+        Self {
+            drivers: DriverRegistry::with_defaults(),
+            secrets: SecretResolver::from_env(),
+            defaults: std::sync::RwLock::new(PoolDefaults::default()),
+            max_database_pools: DEFAULT_MAX_DATABASE_POOLS,
+            connections: RwLock::new(HashMap::new()),
+            database_pools: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Uses `drivers` instead of the built-in ones.
+    pub fn with_drivers(mut self, drivers: DriverRegistry) -> Self {
+        self.drivers = drivers;
+        self
+    }
+
+    /// Resolves `password_ref` through `secrets`.
+    pub fn with_secrets(mut self, secrets: SecretResolver) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// Sets the pool sizing defaults.
+    pub fn with_defaults(self, defaults: PoolDefaults) -> Self {
+        self.set_defaults(defaults);
+        self
+    }
+
+    /// Sets how many pools of other databases a connection keeps open (default: 4).
+    pub fn with_max_database_pools(mut self, max: usize) -> Self {
+        self.max_database_pools = max.max(1);
+        self
+    }
+
+    /// Pool sizing defaults.
+    pub fn defaults(&self) -> PoolDefaults {
+        *self.defaults.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replaces the pool sizing defaults; pools opened afterwards use them.
+    pub fn set_defaults(&self, defaults: PoolDefaults) {
+        *self.defaults.write().unwrap_or_else(|e| e.into_inner()) = defaults;
+    }
+
+    /// Checks the config's `password_ref` against the secrets backends and
+    /// their allowed paths without fetching it, see [`SecretResolver::check`].
+    ///
+    /// # Errors
+    /// `AppError::Validation` if the reference may not be used.
+    pub fn check_password_ref(&self, config: &ConnectionConfig) -> AppResult<()> {
+        match &config.password_ref {
+            Some(reference) => self.secrets.check(reference).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the config with `password_ref` resolved into `password`.
+    ///
+    /// # Errors
+    /// Fails if the secret cannot be resolved.
+    pub async fn resolve_password(&self, config: &ConnectionConfig) -> AppResult<ConnectionConfig> {
+        let mut config = config.clone();
+        if let Some(reference) = &config.password_ref {
+            config.password = Some(self.secrets.resolve(reference).await?);
+        }
+        Ok(config)
+    }
+
+    /// Opens a pool for the config without caching it.
+    ///
+    /// A referenced password that the server rejects is fetched again once,
+    /// in case the secret was rotated after it was cached.
+    pub async fn open(&self, config: &ConnectionConfig) -> AppResult<DatabasePool> {
+        let Some(reference) = &config.password_ref else {
+            return self.open_pool(config).await;
+        };
+        match self.open_pool(&self.resolve_password(config).await?).await {
+            Err(AppError::Database(details)) if details.category == DbErrorCategory::AuthFailed => {
+                tracing::info!(id = %config.id, "Password rejected, fetching the secret again");
+                self.secrets.invalidate(reference).await;
+                self.open_pool(&self.resolve_password(config).await?).await
+            }
+            result => result,
+        }
+    }
+
+    /// Opens a pool with the config's credentials through the driver of its type.
+    ///
+    /// Pool sizing comes from the connection's `pool_options`, falling back to
+    /// the [`PoolDefaults`].
+    ///
+    /// # Errors
+    /// `UnsupportedDatabaseType` if no driver is registered for the type.
+    async fn open_pool(&self, config: &ConnectionConfig) -> AppResult<DatabasePool> {
+        let driver = self.drivers.get(&config.db_type).ok_or_else(|| {
+            AppError::UnsupportedDatabaseType(format!("{} connections are not supported yet", config.db_type))
+        })?;
+        let options = config.pool_options.clone().unwrap_or_default();
+        let defaults = self.defaults();
+        let max_connections = options.max_connections.unwrap_or(defaults.max_connections);
+        let settings = PoolSettings {
+            max_connections,
+            min_connections: options.min_connections.unwrap_or(0).min(max_connections),
+            connect_timeout: options
+                .acquire_timeout_secs
+                .map_or(defaults.connect_timeout, Duration::from_secs),
+            idle_timeout: options.idle_timeout_secs.map_or(defaults.idle_timeout, Duration::from_secs),
+        };
+        driver.connect(config, &settings).await
+    }
+
+    /// Pool of the connection, opened and cached if it is not open yet.
+    ///
+    /// An open pool is returned as is; [`remove`](Self::remove) it first to
+    /// apply a changed config.
+    pub async fn connect(&self, config: ConnectionConfig) -> AppResult<DatabasePool> {
+        if let Some(pool) = self.pool(&config.id).await {
+            return Ok(pool);
+        }
+        let pool = self.open(&config).await?;
+        self.insert(config, pool.clone()).await;
+        Ok(pool)
+    }
+
+    /// Caches an opened pool of a connection, replacing its previous pool.
+    /// Pools of other databases built with the previous settings are dropped.
+    pub async fn insert(&self, config: ConnectionConfig, pool: DatabasePool) {
+        let id = config.id.clone();
+        self.connections.write().await.insert(id.clone(), OpenConnection { config, pool });
+        self.database_pools.write().await.retain(|(conn, _), _| *conn != id);
+    }
+
+    /// Drops the cached pools of a connection and returns them. Queries
+    /// running on them keep their connections until they finish; close the
+    /// returned pools to end them sooner.
+    pub async fn remove(&self, id: &str) -> Vec<DatabasePool> {
+        let mut dropped: Vec<DatabasePool> = self
+            .connections
+            .write()
+            .await
+            .remove(id)
+            .map(|open| open.pool)
+            .into_iter()
+            .collect();
+        self.database_pools.write().await.retain(|(conn, _), pool| {
+            if conn == id {
+                dropped.push(pool.clone());
+            }
+            conn != id
+        });
+        dropped
+    }
+
+    /// Cached pool of a connection.
+    pub async fn pool(&self, id: &str) -> Option<DatabasePool> {
+        self.connections.read().await.get(id).map(|open| open.pool.clone())
+    }
+
+    /// Config a cached pool was opened with.
+    pub async fn config(&self, id: &str) -> Option<ConnectionConfig> {
+        self.connections.read().await.get(id).map(|open| open.config.clone())
+    }
+
+    /// IDs of the connections with an open pool.
+    pub async fn connection_ids(&self) -> Vec<String> {
+        self.connections.read().await.keys().cloned().collect()
+    }
+
+    /// Databases with an open pool of their own, by connection ID.
+    pub async fn database_pools(&self) -> HashMap<String, Vec<String>> {
+        let mut databases: HashMap<String, Vec<String>> = HashMap::new();
+        for (id, database) in self.database_pools.read().await.keys() {
+            databases.entry(id.clone()).or_default().push(database.clone());
+        }
+        databases
+    }
+
+    /// Pings the cached pool of a connection and returns the round trip time.
+    ///
+    /// # Errors
+    /// `ConnectionNotFound` if the pool is not open, and the driver's error
+    /// if the server does not answer.
+    pub async fn ping(&self, id: &str) -> AppResult<Duration> {
+        let pool = self.open_connection(id).await?;
+        let start = Instant::now();
+        pool.connection().ping().await?;
+        Ok(start.elapsed())
+    }
+
+    /// Lists the databases on the server of an open connection.
+    pub async fn databases(&self, id: &str) -> AppResult<Vec<DatabaseInfo>> {
+        self.open_connection(id).await?.connection().databases().await
+    }
+
+    /// Cached pool of a connection; `ConnectionNotFound` if it is not open.
+    pub(crate) async fn open_connection(&self, id: &str) -> AppResult<DatabasePool> {
+        self.pool(id)
+            .await
+            .ok_or_else(|| AppError::ConnectionNotFound(id.to_string()))
+    }
+
+    /// Pool to run statements in `database` on the connection's server.
+    ///
+    /// Without a database, or with the connection's own one, this is the
+    /// connection's pool. Otherwise a separate pool logged into that database
+    /// is opened on first use (MySQL and PostgreSQL only), so pooled sessions
+    /// never switch databases. The database must exist on the server; a
+    /// connection keeps at most `max_database_pools` such pools.
+    ///
+    /// # Errors
+    /// `ConnectionNotFound` if the connection's pool is not open,
+    /// `UnsupportedDatabaseType` for other database types and `NotFound` for
+    /// unknown databases.
+    pub async fn query_pool(&self, id: &str, database: Option<&str>) -> AppResult<DatabasePool> {
+        let (config, pool) = match self.connections.read().await.get(id) {
+            Some(open) => (open.config.clone(), open.pool.clone()),
+            None => return Err(AppError::ConnectionNotFound(id.to_string())),
+        };
+        let Some(database) = database else {
+            return Ok(pool);
+        };
+        if pool.connection().database_per_query() || config.database.as_deref() == Some(database) {
+            return Ok(pool);
+        }
+        let key = (id.to_string(), database.to_string());
+        if let Some(pool) = self.database_pools.read().await.get(&key) {
+            return Ok(pool.clone());
+        }
+
+        if !(config.db_type.is_mysql_family() || config.db_type == DbType::Postgres) {
+            return Err(AppError::UnsupportedDatabaseType(
+                "Switching databases is only supported for MySQL, MariaDB and PostgreSQL".to_string(),
+            ));
+        }
+        if !pool.connection().databases().await?.iter().any(|d| d.name == database) {
+            return Err(AppError::NotFound(format!("database {} on connection {}", database, id)));
+        }
+
+        let pool = self.open(&config.with_database(database)).await?;
+        let mut pools = self.database_pools.write().await;
+        let opened = pools.keys().filter(|(conn, _)| conn == id).count();
+        if opened >= self.max_database_pools && !pools.contains_key(&key) {
+            // Make room by dropping another database's pool of the connection
+            if let Some(evicted) = pools.keys().find(|(conn, _)| conn == id).cloned() {
+                pools.remove(&evicted);
+            }
+        }
+        Ok(pools.entry(key).or_insert(pool).clone())
+    }
+
+    /// Connection of `id` as the driver type `T`, for engine-specific operations.
+    ///
+    /// # Errors
+    /// `ConnectionNotFound` if the pool is not open, `UnsupportedDatabaseType`
+    /// with `unsupported` if the connection uses another driver.
+    pub async fn driver<T: DriverConnection + Clone>(&self, id: &str, unsupported: &str) -> AppResult<T> {
+        let pool = self.open_connection(id).await?;
+        pool.connection()
+            .downcast_ref::<T>()
+            .cloned()
+            .ok_or_else(|| AppError::UnsupportedDatabaseType(unsupported.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use common::models::connection::CreateConnectionRequest;
+
+    fn sqlite(id: &str) -> ConnectionConfig {
+        CreateConnectionRequest {
+            name: id.to_string(),
+            db_type: Some(DbType::SQLite),
+            file_path: Some(":memory:".to_string()),
+            ..Default::default()
+        }
+        .into_config(id.to_string(), String::new())
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn caches_pools_by_connection() {
+        let connections = ConnectionManager::new();
+        assert!(matches!(connections.ping("a").await, Err(AppError::ConnectionNotFound(_))));
+
+        connections.connect(sqlite("a")).await.unwrap();
+        assert!(connections.ping("a").await.is_ok());
+        assert_eq!(connections.connection_ids().await, vec!["a"]);
+        assert_eq!(connections.config("a").await.unwrap().db_type, DbType::SQLite);
+        assert!(matches!(
+            connections.query_pool("a", Some("other")).await,
+            Err(AppError::UnsupportedDatabaseType(_))
+        ));
+
+        assert_eq!(connections.remove("a").await.len(), 1);
+        assert!(connections.pool("a").await.is_none());
+    }
+
+    #[test]
+    fn defaults_can_be_replaced() {
+        let defaults = PoolDefaults {
+            max_connections: 3,
+            ..PoolDefaults::default()
+        };
+        let connections = ConnectionManager::new().with_defaults(defaults);
+        assert_eq!(connections.defaults().max_connections, 3);
+    }
+}
@@ -0,0 +1,691 @@
+//! Result value type mapping.
+//!
+//! Converts driver values into JSON cells and classifies each result column
+//! with a [`ValueKind`], so clients can format values without parsing the
+//! database type names themselves:
+//! - binary values (BLOB, BYTEA) are base64 encoded
+//! - exact numbers (DECIMAL, NUMERIC) are strings, keeping their precision
+//! - date/times are ISO 8601; values with a time zone are RFC 3339 in UTC
+//! - JSON columns are nested JSON values
+//! - geometries (MySQL spatial types, PostGIS, PostgreSQL `point`) are WKT
+//!
+//! Oracle values (feature `oracle`) follow the same representations.
+//! Neo4j values (feature `neo4j`) do too; nodes, relationships and paths are
+//! structured JSON objects.
+//! Elasticsearch and InfluxDB return JSON or CSV text already; only their
+//! column kinds are mapped.
+//!
+//! SQLite is dynamically typed: the kind follows the declared column type,
+//! while each value is converted from its actual storage class.
+
+use std::fmt::Write as _;
+
+use base64::Engine as _;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, Utc};
+use serde_json::Value;
+use sqlx::mysql::types::MySqlTime;
+use sqlx::mysql::MySqlRow;
+use sqlx::postgres::types::PgInterval;
+use sqlx::postgres::PgRow;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, TypeInfo, ValueRef};
+
+use common::models::query::ValueKind;
+
+/// Classifies a MySQL column by its type name.
+pub fn mysql_kind(type_name: &str) -> ValueKind {
+    match type_name {
+        "BOOLEAN" | "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "YEAR" | "BIT" => ValueKind::Integer,
+        name if name.ends_with(" UNSIGNED") => ValueKind::Integer,
+        "FLOAT" | "DOUBLE" => ValueKind::Float,
+        "DECIMAL" => ValueKind::Decimal,
+        "DATE" => ValueKind::Date,
+        "TIME" => ValueKind::Time,
+        "DATETIME" => ValueKind::DateTime,
+        // The session time zone is UTC, so TIMESTAMP values are read as UTC instants.
+        "TIMESTAMP" => ValueKind::DateTimeTz,
+        "JSON" => ValueKind::Json,
+        "GEOMETRY" => ValueKind::Geometry,
+        "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => ValueKind::Binary,
+        _ => ValueKind::Text,
+    }
+}
+
+/// Converts a MySQL value of a column of `kind` to JSON.
+pub fn mysql_value(row: &MySqlRow, idx: usize, kind: ValueKind) -> Value {
+    if row.try_get_raw(idx).map_or(true, |v| v.is_null()) {
+        return Value::Null;
+    }
+    let value = match kind {
+        ValueKind::Integer => row
+            .try_get::<i64, _>(idx)
+            .map(Value::from)
+            .or_else(|_| row.try_get::<u64, _>(idx).map(Value::from))
+            .ok(),
+        ValueKind::Float => row.try_get::<f64, _>(idx).ok().map(float),
+        ValueKind::Date => row.try_get::<NaiveDate, _>(idx).ok().map(|d| Value::String(d.to_string())),
+        ValueKind::Time => row
+            .try_get::<NaiveTime, _>(idx)
+            .map(|t| t.to_string())
+            // TIME also holds durations outside 00:00-24:00
+            .or_else(|_| row.try_get::<MySqlTime, _>(idx).map(|t| t.to_string()))
+            .ok()
+            .map(Value::String),
+        ValueKind::DateTime => row.try_get::<NaiveDateTime, _>(idx).ok().map(date_time),
+        ValueKind::DateTimeTz => row.try_get::<DateTime<Utc>, _>(idx).ok().map(date_time_tz),
+        // DECIMAL and JSON are sent as text
+        ValueKind::Decimal => row.try_get_unchecked::<String, _>(idx).ok().map(Value::String),
+        ValueKind::Json => row.try_get_unchecked::<String, _>(idx).ok().map(json),
+        // MySQL prefixes the WKB with a 4-byte SRID
+        ValueKind::Geometry => row
+            .try_get_unchecked::<Vec<u8>, _>(idx)
+            .ok()
+            .map(|b| geometry(b.get(4..).unwrap_or_default(), &b)),
+        ValueKind::Binary => row.try_get_unchecked::<Vec<u8>, _>(idx).ok().map(|b| base64(&b)),
+        _ => row
+            .try_get::<String, _>(idx)
+            .or_else(|_| row.try_get_unchecked::<String, _>(idx))
+            .ok()
+            .map(Value::String),
+    };
+    value.unwrap_or(Value::Null)
+}
+
+/// Classifies a PostgreSQL column by its type name.
+pub fn postgres_kind(type_name: &str) -> ValueKind {
+    match type_name {
+        "INT2" | "INT4" | "INT8" | "OID" => ValueKind::Integer,
+        "FLOAT4" | "FLOAT8" => ValueKind::Float,
+        "BOOL" => ValueKind::Boolean,
+        "NUMERIC" | "MONEY" => ValueKind::Decimal,
+        "DATE" => ValueKind::Date,
+        "TIME" => ValueKind::Time,
+        "TIMESTAMP" => ValueKind::DateTime,
+        "TIMESTAMPTZ" => ValueKind::DateTimeTz,
+        "JSON" | "JSONB" => ValueKind::Json,
+        "UUID" => ValueKind::Uuid,
+        "BYTEA" => ValueKind::Binary,
+        "POINT" | "geometry" | "geography" => ValueKind::Geometry,
+        name if name.ends_with("[]") => ValueKind::Json,
+        _ => ValueKind::Text,
+    }
+}
+
+/// Converts a PostgreSQL value of a column of `kind` to JSON.
+pub fn postgres_value(row: &PgRow, idx: usize, kind: ValueKind) -> Value {
+    let Ok(raw) = row.try_get_raw(idx) else {
+        return Value::Null;
+    };
+    if raw.is_null() {
+        return Value::Null;
+    }
+    let type_name = raw.type_info().name().to_string();
+    let value = match kind {
+        ValueKind::Integer => row
+            .try_get::<i64, _>(idx)
+            .or_else(|_| row.try_get::<i32, _>(idx).map(i64::from))
+            .or_else(|_| row.try_get::<i16, _>(idx).map(i64::from))
+            .or_else(|_| row.try_get::<sqlx::postgres::types::Oid, _>(idx).map(|o| i64::from(o.0)))
+            .ok()
+            .map(Value::from),
+        ValueKind::Float => row
+            .try_get::<f64, _>(idx)
+            .or_else(|_| row.try_get::<f32, _>(idx).map(f64::from))
+            .ok()
+            .map(float),
+        ValueKind::Boolean => row.try_get::<bool, _>(idx).ok().map(Value::Bool),
+        ValueKind::Decimal if type_name == "MONEY" => row
+            .try_get::<sqlx::postgres::types::PgMoney, _>(idx)
+            .ok()
+            .map(|m| Value::String(decimal_from_scaled(m.0, 2))),
+        ValueKind::Decimal => row
+            .try_get_unchecked::<Vec<u8>, _>(idx)
+            .ok()
+            .and_then(|b| pg_numeric(&b))
+            .map(Value::String),
+        ValueKind::Date => row.try_get::<NaiveDate, _>(idx).ok().map(|d| Value::String(d.to_string())),
+        ValueKind::Time => row.try_get::<NaiveTime, _>(idx).ok().map(|t| Value::String(t.to_string())),
+        ValueKind::DateTime => row.try_get::<NaiveDateTime, _>(idx).ok().map(date_time),
+        ValueKind::DateTimeTz => row.try_get::<DateTime<Utc>, _>(idx).ok().map(date_time_tz),
+        ValueKind::Json if type_name.ends_with("[]") => pg_array(row, idx),
+        ValueKind::Json => row.try_get::<Value, _>(idx).ok(),
+        ValueKind::Uuid => row.try_get::<uuid::Uuid, _>(idx).ok().map(|u| Value::String(u.to_string())),
+        ValueKind::Binary => row.try_get::<Vec<u8>, _>(idx).ok().map(|b| base64(&b)),
+        ValueKind::Geometry if type_name == "POINT" => row
+            .try_get_unchecked::<Vec<u8>, _>(idx)
+            .ok()
+            .and_then(|b| Some(Value::String(format!("POINT({} {})", be_f64(&b, 0)?, be_f64(&b, 8)?)))),
+        // PostGIS sends EWKB
+        ValueKind::Geometry => row.try_get_unchecked::<Vec<u8>, _>(idx).ok().map(|b| geometry(&b, &b)),
+        _ if type_name == "INTERVAL" => row.try_get::<PgInterval, _>(idx).ok().map(interval),
+        _ => row
+            .try_get::<String, _>(idx)
+            .ok()
+            // Enums, domains and extension text types (citext) are sent as text
+            .or_else(|| {
+                let custom = type_name.chars().any(|c| c.is_ascii_lowercase());
+                custom.then(|| row.try_get_unchecked::<String, _>(idx).ok()).flatten()
+            })
+            .map(Value::String),
+    };
+    value.unwrap_or(Value::Null)
+}
+
+/// Classifies a SQLite column by its declared type.
+pub fn sqlite_kind(type_name: &str) -> ValueKind {
+    match type_name {
+        "INTEGER" => ValueKind::Integer,
+        "REAL" => ValueKind::Float,
+        "BOOLEAN" => ValueKind::Boolean,
+        "BLOB" => ValueKind::Binary,
+        _ => ValueKind::Text,
+    }
+}
+
+/// Converts a SQLite value to JSON by its storage class.
+pub fn sqlite_value(row: &SqliteRow, idx: usize, kind: ValueKind) -> Value {
+    let Ok(raw) = row.try_get_raw(idx) else {
+        return Value::Null;
+    };
+    if raw.is_null() {
+        return Value::Null;
+    }
+    let value = match raw.type_info().name() {
+        "INTEGER" if kind == ValueKind::Boolean => row.try_get::<bool, _>(idx).ok().map(Value::Bool),
+        "INTEGER" => row.try_get::<i64, _>(idx).ok().map(Value::from),
+        "REAL" => row.try_get::<f64, _>(idx).ok().map(float),
+        "BLOB" => row.try_get::<Vec<u8>, _>(idx).ok().map(|b| base64(&b)),
+        _ => row.try_get::<String, _>(idx).ok().map(Value::String),
+    };
+    value.unwrap_or(Value::Null)
+}
+
+/// Classifies an Elasticsearch SQL column by its type name.
+pub fn elasticsearch_kind(type_name: &str) -> ValueKind {
+    match type_name {
+        "byte" | "short" | "integer" | "long" | "unsigned_long" => ValueKind::Integer,
+        "half_float" | "float" | "double" | "scaled_float" => ValueKind::Float,
+        "boolean" => ValueKind::Boolean,
+        "datetime" | "date" | "date_nanos" => ValueKind::DateTimeTz,
+        "object" | "nested" | "flattened" => ValueKind::Json,
+        "binary" => ValueKind::Binary,
+        "geo_point" | "geo_shape" | "shape" | "point" => ValueKind::Geometry,
+        _ => ValueKind::Text,
+    }
+}
+
+/// Classifies a Flux result column by its `#datatype` annotation.
+pub fn flux_kind(datatype: &str) -> ValueKind {
+    match datatype {
+        "long" | "unsignedLong" => ValueKind::Integer,
+        "double" => ValueKind::Float,
+        "boolean" => ValueKind::Boolean,
+        "dateTime:RFC3339" | "dateTime:RFC3339Nano" => ValueKind::DateTimeTz,
+        "base64Binary" => ValueKind::Binary,
+        _ => ValueKind::Text,
+    }
+}
+
+/// Classifies a column without a declared type by one of its JSON values.
+pub fn json_kind(value: &Value) -> ValueKind {
+    match value {
+        Value::Bool(_) => ValueKind::Boolean,
+        Value::Number(n) if n.is_i64() || n.is_u64() => ValueKind::Integer,
+        Value::Number(_) => ValueKind::Float,
+        Value::Array(_) | Value::Object(_) => ValueKind::Json,
+        _ => ValueKind::Text,
+    }
+}
+
+/// Classifies an Oracle column by its type.
+///
+/// `NUMBER(p)` with up to 18 digits and no scale is an integer; other
+/// `NUMBER`s are exact decimals. Oracle `DATE` carries a time of day.
+#[cfg(feature = "oracle")]
+pub fn oracle_kind(oracle_type: &oracle::sql_type::OracleType) -> ValueKind {
+    use oracle::sql_type::OracleType;
+
+    match oracle_type {
+        OracleType::Int64 | OracleType::UInt64 => ValueKind::Integer,
+        OracleType::Number(precision, 0) if (1..=18).contains(precision) => ValueKind::Integer,
+        OracleType::Number(_, _) => ValueKind::Decimal,
+        OracleType::BinaryFloat | OracleType::BinaryDouble | OracleType::Float(_) => ValueKind::Float,
+        OracleType::Boolean => ValueKind::Boolean,
+        OracleType::Date | OracleType::Timestamp(_) => ValueKind::DateTime,
+        OracleType::TimestampTZ(_) | OracleType::TimestampLTZ(_) => ValueKind::DateTimeTz,
+        OracleType::Raw(_) | OracleType::LongRaw | OracleType::BLOB => ValueKind::Binary,
+        _ => ValueKind::Text,
+    }
+}
+
+/// Converts an Oracle value to JSON by its column kind.
+#[cfg(feature = "oracle")]
+pub fn oracle_value(row: &oracle::Row, idx: usize, kind: ValueKind) -> Value {
+    let value = match kind {
+        ValueKind::Integer => row.get::<_, Option<i64>>(idx).ok().flatten().map(Value::from),
+        ValueKind::Float => row.get::<_, Option<f64>>(idx).ok().flatten().map(float),
+        ValueKind::Boolean => row.get::<_, Option<bool>>(idx).ok().flatten().map(Value::Bool),
+        ValueKind::DateTime => row.get::<_, Option<NaiveDateTime>>(idx).ok().flatten().map(date_time),
+        ValueKind::DateTimeTz => row.get::<_, Option<DateTime<Utc>>>(idx).ok().flatten().map(date_time_tz),
+        ValueKind::Binary => row.get::<_, Option<Vec<u8>>>(idx).ok().flatten().map(|b| base64(&b)),
+        _ => row.get::<_, Option<String>>(idx).ok().flatten().map(Value::String),
+    };
+    value.unwrap_or(Value::Null)
+}
+
+/// Classifies a Neo4j result column by one of its values.
+#[cfg(feature = "neo4j")]
+pub fn neo4j_kind(value: &neo4rs::BoltType) -> ValueKind {
+    use neo4rs::BoltType;
+
+    match value {
+        BoltType::Node(_) | BoltType::Relation(_) | BoltType::UnboundedRelation(_) | BoltType::Path(_) => ValueKind::Json,
+        BoltType::Point2D(_) | BoltType::Point3D(_) => ValueKind::Geometry,
+        BoltType::Bytes(_) => ValueKind::Binary,
+        BoltType::Date(_) => ValueKind::Date,
+        BoltType::Time(_) | BoltType::LocalTime(_) => ValueKind::Time,
+        BoltType::LocalDateTime(_) => ValueKind::DateTime,
+        BoltType::DateTime(_) | BoltType::DateTimeZoneId(_) => ValueKind::DateTimeTz,
+        BoltType::Duration(_) => ValueKind::Text,
+        other => json_kind(&neo4j_value(other)),
+    }
+}
+
+/// Converts a Neo4j value to JSON.
+///
+/// Nodes are `{id, labels, properties}`, relationships `{id, type, start,
+/// end, properties}`, paths `{nodes, relationships}` and points `{srid, x,
+/// y[, z]}`. Durations are ISO 8601 (`PT90.5S`), with months counted as
+/// 30.44 days.
+#[cfg(feature = "neo4j")]
+pub fn neo4j_value(value: &neo4rs::BoltType) -> Value {
+    use neo4rs::BoltType;
+
+    let map = |map: &neo4rs::BoltMap| -> Value {
+        map.value
+            .iter()
+            .map(|(k, v)| (k.value.clone(), neo4j_value(v)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    };
+    let list = |list: &neo4rs::BoltList| Value::Array(list.value.iter().map(neo4j_value).collect());
+    match value {
+        BoltType::Null(_) => Value::Null,
+        BoltType::String(s) => Value::String(s.value.clone()),
+        BoltType::Boolean(b) => Value::Bool(b.value),
+        BoltType::Integer(i) => Value::from(i.value),
+        BoltType::Float(f) => float(f.value),
+        BoltType::List(l) => list(l),
+        BoltType::Map(m) => map(m),
+        BoltType::Node(n) => serde_json::json!({
+            "id": n.id.value,
+            "labels": list(&n.labels),
+            "properties": map(&n.properties),
+        }),
+        BoltType::Relation(r) => serde_json::json!({
+            "id": r.id.value,
+            "type": r.typ.value,
+            "start": r.start_node_id.value,
+            "end": r.end_node_id.value,
+            "properties": map(&r.properties),
+        }),
+        BoltType::UnboundedRelation(r) => serde_json::json!({
+            "id": r.id.value,
+            "type": r.typ.value,
+            "properties": map(&r.properties),
+        }),
+        BoltType::Path(p) => serde_json::json!({
+            "nodes": list(&p.nodes),
+            "relationships": list(&p.rels),
+        }),
+        BoltType::Point2D(p) => serde_json::json!({ "srid": p.sr_id.value, "x": float(p.x.value), "y": float(p.y.value) }),
+        BoltType::Point3D(p) => serde_json::json!({
+            "srid": p.sr_id.value,
+            "x": float(p.x.value),
+            "y": float(p.y.value),
+            "z": float(p.z.value),
+        }),
+        BoltType::Bytes(b) => base64(&b.value),
+        BoltType::Duration(d) => {
+            let duration = std::time::Duration::from(d.clone());
+            Value::String(format!("PT{}S", duration.as_secs_f64()))
+        }
+        BoltType::Date(d) => NaiveDate::try_from(d).map_or(Value::Null, |d| Value::String(d.to_string())),
+        BoltType::Time(t) => {
+            let (time, offset) = <(NaiveTime, chrono::FixedOffset)>::from(t);
+            Value::String(format!("{}{}", time.format("%H:%M:%S%.f"), offset))
+        }
+        BoltType::LocalTime(t) => Value::String(NaiveTime::from(t).format("%H:%M:%S%.f").to_string()),
+        BoltType::LocalDateTime(d) => NaiveDateTime::try_from(d).map_or(Value::Null, date_time),
+        BoltType::DateTime(d) => DateTime::<chrono::FixedOffset>::try_from(d)
+            .map_or(Value::Null, |d| date_time_tz(d.with_timezone(&Utc))),
+        BoltType::DateTimeZoneId(d) => DateTime::<chrono::FixedOffset>::try_from(d)
+            .map_or(Value::Null, |d| date_time_tz(d.with_timezone(&Utc))),
+    }
+}
+
+fn float(n: f64) -> Value {
+    // NaN and infinities have no JSON number form
+    serde_json::Number::from_f64(n)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(n.to_string()))
+}
+
+fn date_time(value: NaiveDateTime) -> Value {
+    Value::String(value.format("%Y-%m-%dT%H:%M:%S%.f").to_string())
+}
+
+fn date_time_tz(value: DateTime<Utc>) -> Value {
+    Value::String(value.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+fn json(text: String) -> Value {
+    serde_json::from_str(&text).unwrap_or(Value::String(text))
+}
+
+fn base64(bytes: &[u8]) -> Value {
+    Value::String(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+/// WKT of a geometry, or the original bytes as base64 when they are not valid WKB.
+fn geometry(wkb: &[u8], original: &[u8]) -> Value {
+    match wkb_to_wkt(wkb) {
+        Some(wkt) => Value::String(wkt),
+        None => base64(original),
+    }
+}
+
+fn pg_array(row: &PgRow, idx: usize) -> Option<Value> {
+    fn array<T: serde::Serialize>(values: Vec<Option<T>>) -> Option<Value> {
+        serde_json::to_value(values).ok()
+    }
+    row.try_get::<Vec<Option<i64>>, _>(idx)
+        .ok()
+        .and_then(array)
+        .or_else(|| row.try_get::<Vec<Option<i32>>, _>(idx).ok().and_then(array))
+        .or_else(|| row.try_get::<Vec<Option<f64>>, _>(idx).ok().and_then(array))
+        .or_else(|| row.try_get::<Vec<Option<bool>>, _>(idx).ok().and_then(array))
+        .or_else(|| row.try_get::<Vec<Option<String>>, _>(idx).ok().and_then(array))
+        .or_else(|| row.try_get::<Vec<Option<Value>>, _>(idx).ok().and_then(array))
+}
+
+/// ISO 8601 duration of an interval, e.g. `P1Y2M3DT4H5M6.5S`.
+fn interval(value: PgInterval) -> Value {
+    let mut out = String::from("P");
+    let (years, months) = (value.months / 12, value.months % 12);
+    for (n, unit) in [(years, 'Y'), (months, 'M'), (value.days, 'D')] {
+        if n != 0 {
+            let _ = write!(out, "{}{}", n, unit);
+        }
+    }
+    let micros = value.microseconds;
+    if micros != 0 {
+        out.push('T');
+        let (hours, rest) = (micros / 3_600_000_000, micros % 3_600_000_000);
+        let (minutes, rest) = (rest / 60_000_000, rest % 60_000_000);
+        if hours != 0 {
+            let _ = write!(out, "{}H", hours);
+        }
+        if minutes != 0 {
+            let _ = write!(out, "{}M", minutes);
+        }
+        if rest != 0 {
+            let _ = write!(out, "{}S", decimal_from_scaled(rest, 6).trim_end_matches('0').trim_end_matches('.'));
+        }
+    }
+    if out == "P" {
+        out.push_str("T0S");
+    }
+    Value::String(out)
+}
+
+/// Decimal text of `value / 10^scale`.
+fn decimal_from_scaled(value: i64, scale: u32) -> String {
+    let divisor = 10u64.pow(scale);
+    let abs = value.unsigned_abs();
+    let sign = if value < 0 { "-" } else { "" };
+    format!("{}{}.{:0width$}", sign, abs / divisor, abs % divisor, width = scale as usize)
+}
+
+/// Text of a PostgreSQL `NUMERIC` in binary format: digit count, weight,
+/// sign and display scale, followed by base-10000 digits.
+fn pg_numeric(bytes: &[u8]) -> Option<String> {
+    let word = |i: usize| bytes.get(i * 2..i * 2 + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    let (ndigits, weight, sign, dscale) = (word(0)? as usize, word(1)? as i16 as i64, word(2)?, word(3)? as usize);
+    match sign {
+        0xC000 => return Some("NaN".to_string()),
+        0xD000 => return Some("Infinity".to_string()),
+        0xF000 => return Some("-Infinity".to_string()),
+        _ => {}
+    }
+    let digits: Vec<u16> = (0..ndigits).map(|i| word(4 + i)).collect::<Option<_>>()?;
+    let digit = |i: i64| usize::try_from(i).ok().and_then(|i| digits.get(i)).copied().unwrap_or(0);
+
+    let mut out = String::new();
+    if sign == 0x4000 {
+        out.push('-');
+    }
+    if weight < 0 {
+        out.push('0');
+    }
+    for i in 0..=weight {
+        let _ = if i == 0 { write!(out, "{}", digit(i)) } else { write!(out, "{:04}", digit(i)) };
+    }
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let mut i = weight + 1;
+        while fraction.len() < dscale {
+            let _ = write!(fraction, "{:04}", digit(i));
+            i += 1;
+        }
+        fraction.truncate(dscale);
+        out.push('.');
+        out.push_str(&fraction);
+    }
+    Some(out)
+}
+
+fn be_f64(bytes: &[u8], at: usize) -> Option<f64> {
+    Some(f64::from_be_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Converts WKB (ISO or PostGIS EWKB, with optional Z / M) to WKT.
+pub fn wkb_to_wkt(bytes: &[u8]) -> Option<String> {
+    let mut reader = WkbReader { bytes, pos: 0 };
+    let (name, dims, body) = reader.geometry()?;
+    (reader.pos == bytes.len()).then(|| wkt(name, dims, body))
+}
+
+fn wkt(name: &str, dims: &str, body: Option<String>) -> String {
+    match body {
+        Some(body) => format!("{}{}({})", name, dims, body),
+        None => format!("{}{} EMPTY", name, dims),
+    }
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl WkbReader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let chunk = self.bytes.get(self.pos..self.pos + N)?.try_into().ok()?;
+        self.pos += N;
+        Some(chunk)
+    }
+
+    fn u32(&mut self, little: bool) -> Option<u32> {
+        let b = self.take::<4>()?;
+        Some(if little { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    }
+
+    fn f64(&mut self, little: bool) -> Option<f64> {
+        let b = self.take::<8>()?;
+        Some(if little { f64::from_le_bytes(b) } else { f64::from_be_bytes(b) })
+    }
+
+    /// Reads one geometry: its WKT name, dimension suffix and body (`None` when empty).
+    fn geometry(&mut self) -> Option<(&'static str, &'static str, Option<String>)> {
+        let little = self.take::<1>()?[0] == 1;
+        let raw = self.u32(little)?;
+        // EWKB flags, or ISO codes 1000 (Z), 2000 (M), 3000 (ZM) above the base type
+        let (mut z, mut m) = (raw & 0x8000_0000 != 0, raw & 0x4000_0000 != 0);
+        if raw & 0x2000_0000 != 0 {
+            self.u32(little)?;
+        }
+        let code = raw & 0x0FFF_FFFF;
+        match code / 1000 {
+            1 => z = true,
+            2 => m = true,
+            3 => (z, m) = (true, true),
+            _ => {}
+        }
+        let ordinates = 2 + usize::from(z) + usize::from(m);
+        let dims = match (z, m) {
+            (true, true) => " ZM ",
+            (true, false) => " Z ",
+            (false, true) => " M ",
+            (false, false) => "",
+        };
+
+        let (name, body) = match code % 1000 {
+            1 => {
+                let point = self.point(little, ordinates)?;
+                // An empty point is encoded with NaN coordinates
+                ("POINT", (!point.contains("NaN")).then_some(point))
+            }
+            2 => ("LINESTRING", self.points(little, ordinates)?),
+            3 => ("POLYGON", self.rings(little, ordinates)?),
+            n @ 4..=7 => {
+                let count = self.u32(little)?;
+                let mut parts = Vec::new();
+                for _ in 0..count {
+                    let (part_name, part_dims, part) = self.geometry()?;
+                    parts.push(match (n, part) {
+                        (7, part) => wkt(part_name, part_dims, part),
+                        (_, Some(part)) => format!("({})", part),
+                        (_, None) => "EMPTY".to_string(),
+                    });
+                }
+                let name = ["MULTIPOINT", "MULTILINESTRING", "MULTIPOLYGON", "GEOMETRYCOLLECTION"][n as usize - 4];
+                (name, (!parts.is_empty()).then(|| parts.join(",")))
+            }
+            _ => return None,
+        };
+        Some((name, if dims.is_empty() { "" } else { dims.trim_end() }, body))
+    }
+
+    fn point(&mut self, little: bool, ordinates: usize) -> Option<String> {
+        let coords: Vec<String> = (0..ordinates)
+            .map(|_| self.f64(little).map(|c| c.to_string()))
+            .collect::<Option<_>>()?;
+        Some(coords.join(" "))
+    }
+
+    fn points(&mut self, little: bool, ordinates: usize) -> Option<Option<String>> {
+        let count = self.u32(little)?;
+        let points: Vec<String> = (0..count).map(|_| self.point(little, ordinates)).collect::<Option<_>>()?;
+        Some((!points.is_empty()).then(|| points.join(",")))
+    }
+
+    fn rings(&mut self, little: bool, ordinates: usize) -> Option<Option<String>> {
+        let count = self.u32(little)?;
+        let mut rings = Vec::new();
+        for _ in 0..count {
+            rings.push(format!("({})", self.points(little, ordinates)?.unwrap_or_default()));
+        }
+        Some((!rings.is_empty()).then(|| rings.join(",")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numeric(ndigits: u16, weight: i16, sign: u16, dscale: u16, digits: &[u16]) -> Vec<u8> {
+        [ndigits, weight as u16, sign, dscale]
+            .iter()
+            .chain(digits)
+            .flat_map(|w| w.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn decodes_postgres_numeric() {
+        assert_eq!(pg_numeric(&numeric(3, 1, 0, 3, &[1, 2345, 6780])).as_deref(), Some("12345.678"));
+        assert_eq!(pg_numeric(&numeric(1, -1, 0x4000, 2, &[500])).as_deref(), Some("-0.05"));
+        assert_eq!(pg_numeric(&numeric(1, 1, 0, 0, &[7])).as_deref(), Some("70000"));
+        assert_eq!(pg_numeric(&numeric(0, 0, 0, 2, &[])).as_deref(), Some("0.00"));
+        assert_eq!(pg_numeric(&numeric(0, 0, 0xC000, 0, &[])).as_deref(), Some("NaN"));
+        assert_eq!(decimal_from_scaled(-1205, 2), "-12.05");
+    }
+
+    #[test]
+    fn converts_wkb_to_wkt() {
+        let mut point = vec![1u8];
+        point.extend(1u32.to_le_bytes());
+        point.extend(1.5f64.to_le_bytes());
+        point.extend(2f64.to_le_bytes());
+        assert_eq!(wkb_to_wkt(&point).as_deref(), Some("POINT(1.5 2)"));
+
+        // Big-endian EWKB polygon with SRID 4326 and Z
+        let mut polygon = vec![0u8];
+        polygon.extend((0xA000_0003u32).to_be_bytes());
+        polygon.extend(4326u32.to_be_bytes());
+        polygon.extend(1u32.to_be_bytes());
+        polygon.extend(4u32.to_be_bytes());
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)] {
+            for c in [x, y, 5.0f64] {
+                polygon.extend(c.to_be_bytes());
+            }
+        }
+        assert_eq!(wkb_to_wkt(&polygon).as_deref(), Some("POLYGON Z((0 0 5,1 0 5,1 1 5,0 0 5))"));
+
+        let mut multi = vec![1u8];
+        multi.extend(4u32.to_le_bytes());
+        multi.extend(2u32.to_le_bytes());
+        multi.extend(&point);
+        multi.extend(&point);
+        assert_eq!(wkb_to_wkt(&multi).as_deref(), Some("MULTIPOINT((1.5 2),(1.5 2))"));
+        assert_eq!(wkb_to_wkt(&point[..10]), None);
+    }
+
+    #[test]
+    fn classifies_columns() {
+        assert_eq!(mysql_kind("BIGINT UNSIGNED"), ValueKind::Integer);
+        assert_eq!(mysql_kind("LONGBLOB"), ValueKind::Binary);
+        assert_eq!(mysql_kind("LONGTEXT"), ValueKind::Text);
+        assert_eq!(postgres_kind("TIMESTAMPTZ"), ValueKind::DateTimeTz);
+        assert_eq!(postgres_kind("TEXT[]"), ValueKind::Json);
+        assert_eq!(postgres_kind("geometry"), ValueKind::Geometry);
+        assert_eq!(elasticsearch_kind("long"), ValueKind::Integer);
+        assert_eq!(elasticsearch_kind("datetime"), ValueKind::DateTimeTz);
+        assert_eq!(elasticsearch_kind("geo_point"), ValueKind::Geometry);
+        assert_eq!(flux_kind("dateTime:RFC3339"), ValueKind::DateTimeTz);
+        assert_eq!(flux_kind("unsignedLong"), ValueKind::Integer);
+        assert_eq!(
+            interval(PgInterval { months: 14, days: 3, microseconds: 3_600_000_000 + 6_500_000 }),
+            Value::String("P1Y2M3DT1H6.5S".into())
+        );
+    }
+
+    #[cfg(feature = "neo4j")]
+    #[test]
+    fn maps_graph_elements_to_objects() {
+        use neo4rs::{BoltList, BoltMap, BoltNode, BoltType};
+
+        let mut labels = BoltList::new();
+        labels.push("Person".into());
+        let mut properties = BoltMap::new();
+        properties.put("name".into(), "Ada".into());
+        let node = BoltType::from(BoltNode::new(7.into(), labels, properties));
+        assert_eq!(neo4j_kind(&node), ValueKind::Json);
+        assert_eq!(
+            neo4j_value(&node),
+            serde_json::json!({ "id": 7, "labels": ["Person"], "properties": { "name": "Ada" } })
+        );
+        assert_eq!(neo4j_kind(&BoltType::from(std::time::Duration::from_millis(90_500))), ValueKind::Text);
+        assert_eq!(neo4j_value(&BoltType::from(std::time::Duration::from_millis(90_500))), "PT90.5S");
+    }
+}
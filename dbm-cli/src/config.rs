@@ -0,0 +1,121 @@
+//! CLI configuration file.
+//!
+//! The file (`~/.config/dbm/config.toml` unless `--config` is given) holds
+//! named profiles with the gateway URL and credentials:
+//!
+//! ```toml
+//! [default]
+//! url = "http://localhost:8080"
+//! api_key = "dbm_..."
+//!
+//! [prod]
+//! url = "https://dbm.example.com"
+//! token = "eyJ..."
+//! workspace = "7c0e..."
+//! ```
+//!
+//! Command-line options and `DBM_*` environment variables override the
+//! values of the selected profile.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+
+/// Profile used when `--profile` is not given.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Gateway URL used when neither the profile nor the options set one.
+pub const DEFAULT_URL: &str = "http://localhost:8080";
+
+/// Gateway URL and credentials.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Gateway URL.
+    pub url: Option<String>,
+    /// Access token, sent as `Authorization: Bearer`.
+    pub token: Option<String>,
+    /// API key, sent as `X-Api-Key`.
+    pub api_key: Option<String>,
+    /// Workspace ID, sent as `X-Workspace-Id`.
+    pub workspace: Option<String>,
+}
+
+/// Default location of the configuration file.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("dbm").join("config.toml"))
+}
+
+/// Loads a profile.
+///
+/// A missing default file or default profile yields an empty profile, so the
+/// CLI works with options and environment variables alone; an explicitly
+/// named file or profile must exist.
+pub fn load(path: Option<&Path>, profile: Option<&str>) -> anyhow::Result<Profile> {
+    let (path, explicit_path) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Profile::default()),
+        },
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && !explicit_path => {
+            return select(HashMap::new(), profile);
+        }
+        Err(e) => return Err(e).with_context(|| format!("cannot read {}", path.display())),
+    };
+    let profiles = parse(&text).with_context(|| format!("invalid configuration file {}", path.display()))?;
+    select(profiles, profile)
+}
+
+fn parse(text: &str) -> anyhow::Result<HashMap<String, Profile>> {
+    Ok(toml::from_str(text)?)
+}
+
+fn select(mut profiles: HashMap<String, Profile>, name: Option<&str>) -> anyhow::Result<Profile> {
+    match name {
+        None => Ok(profiles.remove(DEFAULT_PROFILE).unwrap_or_default()),
+        Some(name) => match profiles.remove(name) {
+            Some(profile) => Ok(profile),
+            None => bail!("profile `{}` not found in the configuration file", name),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [default]
+        url = "http://localhost:8080"
+        api_key = "dbm_local"
+
+        [prod]
+        url = "https://dbm.example.com"
+        token = "eyJ"
+    "#;
+
+    #[test]
+    fn selects_profiles() {
+        let profiles = parse(CONFIG).unwrap();
+        assert_eq!(select(profiles.clone(), None).unwrap().api_key.as_deref(), Some("dbm_local"));
+
+        let prod = select(profiles.clone(), Some("prod")).unwrap();
+        assert_eq!(prod.url.as_deref(), Some("https://dbm.example.com"));
+        assert_eq!(prod.token.as_deref(), Some("eyJ"));
+        assert!(prod.api_key.is_none());
+
+        assert!(select(profiles, Some("staging")).is_err());
+        assert_eq!(select(HashMap::new(), None).unwrap(), Profile::default());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(parse("[default]\nurl = \"http://x\"\napikey = \"typo\"\n").is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Rendering of command results.
+//!
+//! Every command produces [`Rows`], printed as a table for people, or as CSV
+//! or JSON (an array of objects keyed by column name, in column order) for
+//! scripts.
+
+use std::io::Write;
+
+use clap::ValueEnum;
+use comfy_table::Table;
+use serde_json::Value;
+
+use common::models::query::QueryResult;
+
+/// Output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// 表格
+    Table,
+    /// CSV，首行为列名
+    Csv,
+    /// JSON 对象数组
+    Json,
+}
+
+/// Result of a command: named columns and rows of values.
+#[derive(Debug, Default)]
+pub struct Rows {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Rows {
+    /// Creates an empty result with the given columns.
+    pub fn new(columns: &[&str]) -> Self {
+        Self {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Appends a row.
+    pub fn push(&mut self, row: Vec<Value>) {
+        self.rows.push(row);
+    }
+
+    /// Splits the rows into pages of at most `size` rows, each with the columns.
+    pub fn pages(&self, size: usize) -> Vec<Rows> {
+        self.rows
+            .chunks(size.max(1))
+            .map(|rows| Rows {
+                columns: self.columns.clone(),
+                rows: rows.to_vec(),
+            })
+            .collect()
+    }
+
+    /// Writes the result in `format`.
+    pub fn render(&self, format: Format, out: &mut impl Write) -> anyhow::Result<()> {
+        match format {
+            Format::Table => {
+                let mut table = Table::new();
+                table.set_header(&self.columns);
+                for row in &self.rows {
+                    table.add_row(row.iter().map(|value| text(value, "NULL")));
+                }
+                writeln!(out, "{}", table)?;
+            }
+            Format::Csv => {
+                let mut writer = csv::Writer::from_writer(out);
+                writer.write_record(&self.columns)?;
+                for row in &self.rows {
+                    writer.write_record(row.iter().map(|value| text(value, "")))?;
+                }
+                writer.flush()?;
+            }
+            Format::Json => {
+                // Objects are written by hand to keep the column order
+                writeln!(out, "[")?;
+                for (i, row) in self.rows.iter().enumerate() {
+                    let fields = self
+                        .columns
+                        .iter()
+                        .zip(row)
+                        .map(|(column, value)| Ok(format!("{}: {}", serde_json::to_string(column)?, serde_json::to_string(value)?)))
+                        .collect::<Result<Vec<_>, serde_json::Error>>()?;
+                    let separator = if i + 1 < self.rows.len() { "," } else { "" };
+                    writeln!(out, "  {{{}}}{}", fields.join(", "), separator)?;
+                }
+                writeln!(out, "]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<QueryResult> for Rows {
+    fn from(result: QueryResult) -> Self {
+        Self {
+            columns: result.columns.into_iter().map(|c| c.name).collect(),
+            rows: result.rows,
+        }
+    }
+}
+
+/// Summary of a query result, e.g. `3 rows (12 ms)`.
+pub fn summary(result: &QueryResult) -> String {
+    match result.affected_rows {
+        Some(affected) => format!("{} rows affected ({} ms)", affected, result.execution_time_ms),
+        None => format!("{} rows ({} ms)", result.row_count, result.execution_time_ms),
+    }
+}
+
+/// Text of a cell; strings are written without quotes.
+fn text(value: &Value, null: &str) -> String {
+    match value {
+        Value::Null => null.to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn rows() -> Rows {
+        let mut rows = Rows::new(&["name", "id", "note"]);
+        rows.push(vec![json!("b, \"quoted\""), json!(2), json!(null)]);
+        rows.push(vec![json!("a"), json!(1), json!({"k": true})]);
+        rows
+    }
+
+    fn render(format: Format) -> String {
+        let mut out = Vec::new();
+        rows().render(format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn renders_csv() {
+        assert_eq!(render(Format::Csv), "name,id,note\n\"b, \"\"quoted\"\"\",2,\na,1,\"{\"\"k\"\":true}\"\n");
+    }
+
+    #[test]
+    fn renders_json_in_column_order() {
+        let text = render(Format::Json);
+        assert!(text.starts_with("[\n  {\"name\": \"b, \\\"quoted\\\"\", \"id\": 2, \"note\": null},\n"));
+        let parsed: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed[1], json!({"name": "a", "id": 1, "note": {"k": true}}));
+        assert_eq!(render_empty(Format::Json), "[\n]\n");
+    }
+
+    #[test]
+    fn renders_tables_with_null_markers() {
+        let text = render(Format::Table);
+        assert!(text.contains("NULL"));
+        assert!(text.contains("name"));
+    }
+
+    #[test]
+    fn splits_pages_with_the_columns() {
+        let pages = rows().pages(1);
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].columns, vec!["name", "id", "note"]);
+        assert_eq!(pages[1].rows, vec![vec![json!("a"), json!(1), json!({"k": true})]]);
+        assert_eq!(rows().pages(50).len(), 1);
+        assert!(Rows::new(&["a"]).pages(50).is_empty());
+    }
+
+    fn render_empty(format: Format) -> String {
+        let mut out = Vec::new();
+        Rows::new(&["a"]).render(format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+}
@@ -0,0 +1,389 @@
+//! Interactive SQL shell.
+//!
+//! `dbm shell` keeps a session on the client: the current connection and
+//! database, the output format, the row limit and the page size. Statements
+//! end with `;` and run as soon as one is complete, so several may share a
+//! line and one may span lines. Lines starting with `\` are shell commands
+//! modelled on psql (`\?` lists them), and `USE <database>` switches the
+//! database as in mysql.
+//!
+//! Statements go through the gateway like `dbm query`, each in autocommit
+//! mode on a pooled server connection. Transaction control statements are
+//! therefore rejected rather than run on a connection the next statement
+//! may not get. Dangerous statements are confirmed at the prompt, and
+//! tables longer than the page size are shown page by page.
+
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use clap::ValueEnum;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use serde_json::json;
+
+use client::Client;
+use common::models::connection::{ConnectionItem, DbType};
+use common::models::query::{QueryRequest, QueryResult};
+use common::utils::SqlSplitter;
+
+use crate::output::{self, Format, Rows};
+
+/// Rows per page of a table unless `\pager` changes it.
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Row limit of a statement unless `\limit` changes it.
+const DEFAULT_LIMIT: u32 = 1000;
+
+const HELP: &str = "\
+Statements end with `;`; Ctrl-C discards the statement being typed.
+
+  \\c <connection> [database]   switch connection (ID or name) and database
+  \\conninfo                    show the session
+  \\connections                 list connections
+  \\l                           list databases of the connection
+  USE <database>;              switch database
+  \\format table|csv|json       set the output format
+  \\limit <rows>                set the row limit of statements
+  \\pager <rows>|off            set the page size of tables
+  \\?                           show this help
+  \\q                           quit (also quit, exit or Ctrl-D)
+";
+
+/// State of a shell session.
+struct Session {
+    connection: Option<ConnectionItem>,
+    /// Database chosen with `\c` or `USE`; `None` uses the connection's.
+    database: Option<String>,
+    format: Format,
+    limit: u32,
+    /// Rows per page of a table; 0 disables paging.
+    page_size: usize,
+}
+
+impl Session {
+    /// Database statements run in.
+    fn database(&self) -> Option<&str> {
+        self.database
+            .as_deref()
+            .or_else(|| self.connection.as_ref().and_then(|c| c.database.as_deref()))
+    }
+
+    /// Prompt naming the connection and database; `->` continues a statement.
+    fn prompt(&self, continuation: bool) -> String {
+        let name = match (&self.connection, self.database()) {
+            (Some(connection), Some(database)) => format!("{}/{}", connection.name, database),
+            (Some(connection), None) => connection.name.clone(),
+            (None, _) => "dbm".to_string(),
+        };
+        format!("{}{} ", name, if continuation { "->" } else { "=>" })
+    }
+
+    /// Dialect statements are split in.
+    fn db_type(&self) -> DbType {
+        self.connection.as_ref().map_or(DbType::Postgres, |c| c.db_type.clone())
+    }
+}
+
+/// Whether a line typed at the start of a statement is a shell command.
+fn is_command(line: &str) -> bool {
+    let line = line.trim().trim_end_matches(';');
+    line.starts_with('\\') || line.eq_ignore_ascii_case("quit") || line.eq_ignore_ascii_case("exit")
+}
+
+/// Database named by a mysql-style `USE <database>` statement.
+fn use_database(sql: &str) -> Option<String> {
+    let (keyword, name) = sql.trim().split_once(char::is_whitespace)?;
+    if !keyword.eq_ignore_ascii_case("USE") {
+        return None;
+    }
+    let name = name.trim();
+    let quoted = ['`', '"'].iter().find_map(|quote| name.strip_prefix(*quote)?.strip_suffix(*quote));
+    match quoted {
+        Some(name) => (!name.is_empty()).then(|| name.to_string()),
+        None => (!name.contains(char::is_whitespace)).then(|| name.to_string()),
+    }
+}
+
+/// Whether a statement controls transactions or autocommit.
+fn is_transaction_control(sql: &str) -> bool {
+    let words: Vec<String> = sql.split_whitespace().take(2).map(|w| w.to_ascii_uppercase()).collect();
+    let second = words.get(1).map(String::as_str);
+    match words.first().map(String::as_str) {
+        Some("BEGIN" | "COMMIT" | "ROLLBACK" | "SAVEPOINT" | "RELEASE" | "ABORT") => true,
+        Some("START") => second == Some("TRANSACTION"),
+        Some("END") => matches!(second, None | Some("TRANSACTION" | "WORK")),
+        Some("SET") => second.is_some_and(|w| w.starts_with("AUTOCOMMIT")),
+        _ => false,
+    }
+}
+
+/// Location of the shell's history file.
+fn history_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("dbm").join("history"))
+}
+
+enum Flow {
+    Continue,
+    Quit,
+}
+
+/// Interactive shell over a gateway client.
+pub struct Shell<'a> {
+    client: &'a Client,
+    editor: DefaultEditor,
+    session: Session,
+}
+
+impl<'a> Shell<'a> {
+    /// Creates a shell printing results in `format`, without a connection.
+    pub fn new(client: &'a Client, format: Format) -> anyhow::Result<Self> {
+        Ok(Self {
+            client,
+            editor: DefaultEditor::new().context("cannot open the terminal")?,
+            session: Session {
+                connection: None,
+                database: None,
+                format,
+                limit: DEFAULT_LIMIT,
+                page_size: DEFAULT_PAGE_SIZE,
+            },
+        })
+    }
+
+    /// Switches to a connection, given by ID or name, and optionally a
+    /// database on its server.
+    pub async fn connect(&mut self, connection: &str, database: Option<String>) -> anyhow::Result<()> {
+        let connections = self.client.list_connections(None).await?;
+        let Some(item) = connections.into_iter().find(|c| c.id == connection || c.name == connection) else {
+            bail!("connection `{}` not found", connection);
+        };
+        println!("connected to {} ({})", item.name, item.db_type);
+        self.session.connection = Some(item);
+        self.session.database = database;
+        Ok(())
+    }
+
+    /// Reads and runs statements and commands until `\q` or end of input.
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        let history = history_path();
+        if let Some(path) = &history {
+            let _ = self.editor.load_history(path);
+        }
+        println!("dbm shell; \\? for help, \\q to quit");
+
+        let mut buffer = String::new();
+        loop {
+            let prompt = self.session.prompt(!buffer.is_empty());
+            let line = match self.editor.readline(&prompt) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => {
+                    buffer.clear();
+                    continue;
+                }
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
+            };
+
+            if buffer.is_empty() && is_command(&line) {
+                let _ = self.editor.add_history_entry(line.trim());
+                match self.command(line.trim()).await {
+                    Ok(Flow::Quit) => break,
+                    Ok(Flow::Continue) => {}
+                    Err(e) => eprintln!("error: {:#}", e),
+                }
+                continue;
+            }
+
+            buffer.push_str(&line);
+            buffer.push('\n');
+            let (statements, rest) = SqlSplitter::split_terminated(&buffer, &self.session.db_type());
+            if statements.is_empty() {
+                if buffer.trim().is_empty() {
+                    buffer.clear();
+                }
+                continue;
+            }
+            let _ = self.editor.add_history_entry(buffer.trim());
+            buffer = rest.trim_start().to_string();
+            // Like a script, the statements after a failed one are skipped
+            for statement in statements {
+                if let Err(e) = self.execute(&statement).await {
+                    eprintln!("error: {:#}", e);
+                    break;
+                }
+            }
+        }
+
+        if let Some(path) = &history {
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            let _ = self.editor.save_history(path);
+        }
+        Ok(())
+    }
+
+    async fn command(&mut self, line: &str) -> anyhow::Result<Flow> {
+        let mut words = line.trim_end_matches(';').split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+        if name.eq_ignore_ascii_case("quit") || name.eq_ignore_ascii_case("exit") {
+            return Ok(Flow::Quit);
+        }
+        match (name, args.as_slice()) {
+            ("\\q", []) => return Ok(Flow::Quit),
+            ("\\?" | "\\h", []) => print!("{}", HELP),
+            ("\\c" | "\\connect", [connection]) => self.connect(connection, None).await?,
+            ("\\c" | "\\connect", [connection, database]) => self.connect(connection, Some(database.to_string())).await?,
+            ("\\c" | "\\connect" | "\\conninfo", []) => self.describe(),
+            ("\\connections", []) => {
+                let connections = self.client.list_connections(None).await?;
+                crate::print(&crate::connection_rows(&connections), self.session.format)?;
+            }
+            ("\\l", []) => {
+                let Some(connection) = &self.session.connection else {
+                    bail!("no connection selected; use \\c <connection>");
+                };
+                let mut rows = Rows::new(&["name", "tables", "size_mb"]);
+                for database in self.client.list_databases(&connection.id).await? {
+                    rows.push(vec![json!(database.name), json!(database.tables_count), json!(database.size_mb)]);
+                }
+                crate::print(&rows, self.session.format)?;
+            }
+            ("\\format", [format]) => {
+                self.session.format = Format::from_str(format, true).map_err(|_| anyhow::anyhow!("unknown format `{}`; use table, csv or json", format))?;
+            }
+            ("\\limit", [limit]) => {
+                self.session.limit = limit
+                    .parse()
+                    .ok()
+                    .filter(|limit| *limit > 0)
+                    .with_context(|| format!("invalid row limit `{}`", limit))?;
+            }
+            ("\\pager", ["off"]) => self.session.page_size = 0,
+            ("\\pager", [size]) => {
+                self.session.page_size = size.parse().with_context(|| format!("invalid page size `{}`", size))?;
+            }
+            _ => bail!("unknown command or arguments `{}`; \\? lists the commands", line),
+        }
+        Ok(Flow::Continue)
+    }
+
+    fn describe(&self) {
+        let session = &self.session;
+        match &session.connection {
+            Some(connection) => println!("connection: {} ({}, {})", connection.name, connection.id, connection.db_type),
+            None => println!("connection: none"),
+        }
+        println!("database: {}", session.database().unwrap_or("(default)"));
+        let pager = match session.page_size {
+            0 => "off".to_string(),
+            size => format!("{} rows", size),
+        };
+        let format = session.format.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+        println!("format: {}, limit: {} rows, pager: {}", format, session.limit, pager);
+        println!("transaction: autocommit (every statement commits on its own)");
+    }
+
+    async fn execute(&mut self, sql: &str) -> anyhow::Result<()> {
+        if let Some(database) = use_database(sql) {
+            self.session.database = Some(database);
+            println!("database changed");
+            return Ok(());
+        }
+        if is_transaction_control(sql) {
+            bail!("transactions are not available in the shell: every statement runs in autocommit mode on a pooled server connection");
+        }
+        let Some(connection) = &self.session.connection else {
+            bail!("no connection selected; use \\c <connection>");
+        };
+
+        let mut req = QueryRequest::new(connection.id.clone(), sql);
+        req.database = self.session.database.clone();
+        req.limit = Some(self.session.limit);
+        let result = match self.client.execute_query(&req).await {
+            Ok(result) => result,
+            Err(e) => {
+                let Some((message, Some(token))) = crate::confirmation(&e) else {
+                    return Err(e.into());
+                };
+                eprintln!("{}", message);
+                let token = token.to_string();
+                let answer = self.editor.readline("execute it? [y/N] ");
+                if !matches!(answer.as_deref().map(str::trim), Ok("y" | "Y" | "yes")) {
+                    println!("cancelled");
+                    return Ok(());
+                }
+                req.confirmation_token = Some(token);
+                self.client.execute_query(&req).await?
+            }
+        };
+        self.show(result)
+    }
+
+    /// Prints a result, a page at a time when it is a table longer than the
+    /// page size on a terminal.
+    fn show(&mut self, result: QueryResult) -> anyhow::Result<()> {
+        let summary = output::summary(&result);
+        let truncated = result.truncated;
+        if !result.columns.is_empty() {
+            let rows = Rows::from(result);
+            let page_size = self.session.page_size;
+            let paged = self.session.format == Format::Table
+                && page_size > 0
+                && rows.rows.len() > page_size
+                && std::io::stdout().is_terminal();
+            if paged {
+                let pages = rows.pages(page_size);
+                for (i, page) in pages.iter().enumerate() {
+                    crate::print(page, Format::Table)?;
+                    if i + 1 == pages.len() {
+                        break;
+                    }
+                    let more = self
+                        .editor
+                        .readline(&format!("-- page {}/{}: Enter for the next page, q to stop -- ", i + 1, pages.len()));
+                    if !matches!(more.as_deref().map(str::trim), Ok("")) {
+                        break;
+                    }
+                }
+            } else {
+                crate::print(&rows, self.session.format)?;
+            }
+        }
+        println!("{}", summary);
+        if truncated {
+            eprintln!("{}", crate::TRUNCATED_WARNING);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_commands_and_use() {
+        assert!(is_command("\\c local"));
+        assert!(is_command(" QUIT;"));
+        assert!(!is_command("SELECT 1;"));
+
+        assert_eq!(use_database("USE app").as_deref(), Some("app"));
+        assert_eq!(use_database("use `my app`").as_deref(), Some("my app"));
+        assert_eq!(use_database("use \"shop\"\n").as_deref(), Some("shop"));
+        assert_eq!(use_database("USER app"), None);
+        assert_eq!(use_database("use a b"), None);
+    }
+
+    #[test]
+    fn recognises_transaction_control() {
+        for sql in ["BEGIN", "start transaction", "COMMIT WORK", "rollback to savepoint a", "END", "SET autocommit = 0"] {
+            assert!(is_transaction_control(sql), "{}", sql);
+        }
+        for sql in ["SELECT 1", "START SLAVE", "SET NAMES utf8mb4", "UPDATE t SET end = 1"] {
+            assert!(!is_transaction_control(sql), "{}", sql);
+        }
+    }
+}
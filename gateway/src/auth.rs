@@ -0,0 +1,417 @@
+//! API Key 认证模块
+//!
+//! 客户端可在 `X-Api-Key` 头中携带 API Key。网关通过连接服务的内部端点
+//! 验证密钥（结果按密钥哈希短时缓存，吊销在缓存过期后生效），并按密钥的
+//! 范围（只读、限定连接）拦截越权请求。限定连接的密钥访问未在路径中指明
+//! 连接的接口时，从 JSON 请求体的 `connection_id` 字段判断目标连接。访客链接
+//! 只能调用查询接口，并按请求体中的 `sql`（或采样的 `database`）校验所访问的
+//! 库/schema 是否在链接的范围内。只读密钥与访客链接提交的 UPDATE/DELETE 与 DDL
+//! 在网关即被拒绝，不会得到危险语句的确认令牌。
+//! 未携带 API Key 时接受会话令牌（见 `session` 模块），单点登录的用户按令牌中的
+//! 角色匹配 `role:<name>` 授权策略。启用 Cookie 会话（`SESSION_COOKIE_ENABLED`）时，
+//! 未携带 `Authorization` 头的请求从会话 Cookie 读取令牌；这类请求若会修改状态
+//! （GET/HEAD/OPTIONS 以外的方法），须在 CSRF 头中回传 CSRF Cookie 的值，否则返回 403。
+//! 启用授权策略时，认证通过后再按策略判定（见 `authz` 模块）。
+//! 认证通过的请求以 `X-Principal: key:<id>`（会话令牌为 `user:<id>`）转发，
+//! 下游服务据此区分连接的所有者；客户端自带的 `X-Principal` 与 `X-Session-Id` 一律移除。
+//!
+//! 配置：
+//! - `GATEWAY_REQUIRE_API_KEY` - 为 true 时，除健康检查与登录外的 `/api/**` 请求必须携带有效密钥或会话令牌（默认 false）
+//! - `GATEWAY_API_KEY_CACHE_SECS` - 验证结果缓存时间（默认 30）
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use common::errors::{AppError, AppResult};
+use common::logging;
+use common::middleware::auth::{extract_api_key, extract_bearer_token, PRINCIPAL_HEADER, SESSION_HEADER};
+use common::middleware::csrf::{session_cookie_token, verify_csrf};
+use common::middleware::{RequestSigner, SendSigned};
+use common::models::api_key::{path_connection_id, ApiKey};
+use common::models::policy::{AuthzRequest, ANONYMOUS_PRINCIPAL};
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use crate::state::AppState;
+
+const DEFAULT_CACHE_SECS: u64 = 30;
+/// 无需凭证即可调用的登录端点（登录与刷新令牌本身就是获取凭证）
+const PUBLIC_PATHS: [&str; 4] = [
+    "/api/auth/login",
+    "/api/auth/refresh",
+    "/api/auth/oidc/login",
+    "/api/auth/oidc/callback",
+];
+/// 缓存条目上限，超过时先清理过期条目
+const MAX_CACHE_ENTRIES: usize = 10_000;
+/// 为判断目标连接而读取的请求体大小上限
+const MAX_INSPECTED_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// API Key 验证器
+pub struct ApiKeyVerifier {
+    connection_service_url: String,
+    http_client: reqwest::Client,
+    signer: RequestSigner,
+    required: bool,
+    cache_ttl: Duration,
+    /// 密钥哈希 → (缓存时间, 验证结果；`None` 表示无效)
+    cache: RwLock<HashMap<String, (Instant, Option<ApiKey>)>>,
+}
+
+impl ApiKeyVerifier {
+    /// 创建验证器，从环境变量读取配置
+    pub fn new(connection_service_url: String, http_client: reqwest::Client, signer: RequestSigner) -> Self {
+        let required = std::env::var("GATEWAY_REQUIRE_API_KEY")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        let cache_secs = std::env::var("GATEWAY_API_KEY_CACHE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_SECS);
+        Self {
+            connection_service_url,
+            http_client,
+            signer,
+            required,
+            cache_ttl: Duration::from_secs(cache_secs),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 验证密钥，返回密钥信息
+    ///
+    /// # Errors
+    /// 密钥无效、已吊销或已过期时返回 `AppError::Unauthorized`；连接服务不可用时返回 `AppError::ServiceUnavailable`。
+    pub async fn verify(&self, key: &str) -> AppResult<ApiKey> {
+        let hash = hex::encode(Sha256::digest(key.as_bytes()));
+        if let Some((cached_at, result)) = self.cache.read().await.get(&hash) {
+            if cached_at.elapsed() < self.cache_ttl {
+                return result.clone().ok_or(AppError::Unauthorized);
+            }
+        }
+
+        let result = self.fetch(key).await?;
+        let mut cache = self.cache.write().await;
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            let ttl = self.cache_ttl;
+            cache.retain(|_, (cached_at, _)| cached_at.elapsed() < ttl);
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.clear();
+            }
+        }
+        cache.insert(hash, (Instant::now(), result.clone()));
+        result.ok_or(AppError::Unauthorized)
+    }
+
+    /// 调用连接服务验证密钥；密钥无效时返回 `Ok(None)`
+    async fn fetch(&self, key: &str) -> AppResult<Option<ApiKey>> {
+        let url = format!("{}/internal/api-keys/verify", self.connection_service_url);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(&serde_json::json!({ "key": key }))
+            .send_signed(&self.signer)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("无法验证 API Key: {}", e)))?;
+
+        match response.status().as_u16() {
+            401 => Ok(None),
+            status if status >= 400 => Err(AppError::ServiceUnavailable(format!(
+                "无法验证 API Key: 连接服务返回 {}",
+                status
+            ))),
+            _ => {
+                let body: serde_json::Value = response
+                    .json()
+                    .await
+                    .map_err(|e| AppError::ExternalService(format!("连接服务返回无效响应: {}", e)))?;
+                serde_json::from_value(body["data"].clone())
+                    .map(Some)
+                    .map_err(|e| AppError::ExternalService(format!("连接服务返回无效结果: {}", e)))
+            }
+        }
+    }
+}
+
+/// API Key 认证中间件
+pub async fn api_key_middleware(State(state): State<AppState>, req: Request<Body>, next: Next) -> Response {
+    match authenticate(&state, req).await {
+        Ok(req) => next.run(req).await,
+        Err(e) => e.into_response(),
+    }
+}
+
+/// 请求体中出现的全部目标连接，每个都要通过密钥范围与授权策略检查。
+///
+/// 扇出查询列出多个连接，数据复制涉及源与目标两个连接，结果对比涉及左右两条查询的连接，
+/// 批量查询的每条查询各自指定连接；这些字段与顶层 `connection_id` 同时出现时取并集，
+/// 否则在允许的连接旁附带其他字段即可绕过检查。列表项缺少连接 ID 时记为 `None`，
+/// 由限定连接的密钥拒绝；请求体不含任何连接时返回单个 `None`，仍做不限连接的检查。
+fn body_targets(body: &serde_json::Value) -> Vec<Option<&str>> {
+    let singles = ["connection_id", "source_connection_id", "target_connection_id"]
+        .into_iter()
+        .map(|field| &body[field])
+        .chain(["left", "right"].into_iter().map(|side| &body[side]["connection_id"]))
+        .filter(|id| !id.is_null())
+        .map(|id| id.as_str());
+    let listed = body["connection_ids"].as_array().into_iter().flatten().map(|id| id.as_str());
+    let batch = body["queries"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|query| query["connection_id"].as_str());
+    let mut targets: Vec<Option<&str>> = Vec::new();
+    for target in singles.chain(listed).chain(batch) {
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    if targets.is_empty() {
+        targets.push(None);
+    }
+    targets
+}
+
+/// 请求体中的全部 SQL：顶层 `sql`、结果对比左右两侧与批量查询的每条查询；Cypher 查询不计入
+fn body_statements(body: &serde_json::Value) -> Vec<&str> {
+    let batch = body["queries"].as_array().into_iter().flatten();
+    [body, &body["left"], &body["right"]]
+        .into_iter()
+        .chain(batch)
+        .filter(|query| query["query_language"].as_str() != Some("cypher"))
+        .filter_map(|query| query["sql"].as_str())
+        .collect()
+}
+
+/// 只读密钥与访客链接不能提交变更与 DDL，否则查询服务会为其签发确认令牌，重新提交即可执行
+fn check_read_only(api_key: &ApiKey, body: &serde_json::Value) -> AppResult<()> {
+    body_statements(body)
+        .into_iter()
+        .try_for_each(|sql| api_key.check_sql_read_only(sql))
+}
+
+async fn authenticate(state: &AppState, mut req: Request<Body>) -> AppResult<Request<Body>> {
+    // 身份只能由网关写入，不信任客户端自带的值
+    req.headers_mut().remove(PRINCIPAL_HEADER);
+    req.headers_mut().remove(SESSION_HEADER);
+    let path = req.uri().path();
+    if !path.starts_with("/api/") || path.starts_with("/api/health") || PUBLIC_PATHS.contains(&path) {
+        return Ok(req);
+    }
+    let api_key = match extract_api_key(&req) {
+        Some(key) => Some(state.api_keys.verify(key).await?),
+        None => None,
+    };
+    let config = state.config.get();
+    let cookie_token = match &config.session_cookie {
+        Some(cookies) if extract_bearer_token(&req).is_none() => session_cookie_token(cookies, req.headers()),
+        _ => None,
+    };
+    let session = match extract_bearer_token(&req).or(cookie_token) {
+        Some(token) if api_key.is_none() && state.sessions.enabled() => Some(state.sessions.verify(token).await?),
+        _ => None,
+    };
+    // 浏览器会为跨站请求自动附带 Cookie，修改状态的请求须证明来自能读取 CSRF Cookie 的页面
+    if let (Some(cookies), Some(_), Some(_)) = (&config.session_cookie, &session, cookie_token) {
+        verify_csrf(cookies, req.method(), req.headers())?;
+    }
+    if api_key.is_none() && session.is_none() && state.api_keys.required {
+        return Err(AppError::Unauthorized);
+    }
+    let enforce_policies = state.policies.enforced;
+    if api_key.is_none() && session.is_none() && !enforce_policies {
+        return Ok(req);
+    }
+
+    let method = req.method().as_str().to_string();
+    let path = path.to_string();
+
+    // 限定连接的密钥与授权策略需要从请求体读取目标连接，限定 schema 的访客链接与只读密钥还需读取 SQL
+    let scoped = api_key.as_ref().is_some_and(|k| !k.connection_ids.is_empty());
+    let schema_scoped = api_key.as_ref().is_some_and(|k| !k.schemas.is_empty());
+    let read_only = api_key.as_ref().is_some_and(|k| k.read_only || k.guest);
+    let (mut req, body) = if req.method() != axum::http::Method::GET
+        && (schema_scoped || read_only || ((scoped || enforce_policies) && path_connection_id(&path).is_none()))
+    {
+        let (parts, body) = req.into_parts();
+        let bytes = axum::body::to_bytes(body, MAX_INSPECTED_BODY_BYTES)
+            .await
+            .map_err(|e| AppError::InvalidInput(format!("读取请求体失败: {}", e)))?;
+        let value = serde_json::from_slice::<serde_json::Value>(&bytes).ok();
+        (Request::from_parts(parts, Body::from(bytes)), value)
+    } else {
+        (req, None)
+    };
+    let body_field = |field: &str| body.as_ref().and_then(|v| v[field].as_str());
+    let body_connection_id = if path_connection_id(&path).is_none() {
+        body_field("connection_id")
+    } else {
+        None
+    };
+    let batch: Vec<&serde_json::Value> = body
+        .as_ref()
+        .and_then(|v| v["queries"].as_array())
+        .map(|queries| queries.iter().collect())
+        .unwrap_or_default();
+    let targets = match &body {
+        Some(body) if path_connection_id(&path).is_none() => body_targets(body),
+        _ => vec![None],
+    };
+
+    if let Some(api_key) = &api_key {
+        for target in &targets {
+            api_key.check_access(&method, &path, *target)?;
+        }
+        if let Some(body) = &body {
+            check_read_only(api_key, body)?;
+        }
+        if schema_scoped {
+            if path.ends_with("/sample") {
+                api_key.check_database_schema(body_field("database"))?;
+            } else if let Some(sql) = body_field("sql") {
+                // 查询切换到其他库时，该库同样须在访客链接的 schema 范围内
+                if let Some(database) = body_field("database") {
+                    api_key.check_database_schema(Some(database))?;
+                }
+                api_key.check_sql_schemas(sql)?;
+            }
+            let sides = body.iter().flat_map(|v| [&v["left"], &v["right"]]);
+            for query in sides.chain(batch.iter().copied()) {
+                if let Some(sql) = query["sql"].as_str() {
+                    if let Some(database) = query["database"].as_str() {
+                        api_key.check_database_schema(Some(database))?;
+                    }
+                    api_key.check_sql_schemas(sql)?;
+                }
+            }
+        }
+        tracing::debug!(key_id = %api_key.id, method = %method, path = %path, "API Key 认证通过");
+    }
+    let principal = api_key
+        .as_ref()
+        .map(ApiKey::principal)
+        .or_else(|| session.as_ref().map(|claims| claims.principal()));
+    if enforce_policies {
+        let principal = principal.clone().unwrap_or_else(|| ANONYMOUS_PRINCIPAL.to_string());
+        let roles = session.as_ref().map(|claims| claims.roles.clone()).unwrap_or_default();
+        for target in &targets {
+            let request = AuthzRequest::for_http(principal.clone(), &method, &path, *target).with_roles(roles.clone());
+            state.policies.authorize(&request).await?;
+        }
+    }
+    if let Some(id) = body_connection_id {
+        logging::record_connection_id(id);
+    }
+    if let Some(principal) = &principal {
+        logging::record_user(principal);
+        if let Ok(value) = HeaderValue::from_str(principal) {
+            req.headers_mut().insert(PRINCIPAL_HEADER, value);
+        }
+    }
+    if let Some(claims) = &session {
+        if let Ok(value) = HeaderValue::from_str(&claims.sid) {
+            req.headers_mut().insert(SESSION_HEADER, value);
+        }
+    }
+    Ok(req)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scoped_key(connection_ids: &[&str]) -> ApiKey {
+        ApiKey {
+            id: "k1".to_string(),
+            name: "ci".to_string(),
+            prefix: "dbm_12345678".to_string(),
+            read_only: false,
+            connection_ids: connection_ids.iter().map(|c| c.to_string()).collect(),
+            guest: false,
+            schemas: vec![],
+            default_namespace: None,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            expires_at: None,
+            revoked_at: None,
+            last_used_at: None,
+        }
+    }
+
+    fn check(key: &ApiKey, path: &str, body: serde_json::Value) -> AppResult<()> {
+        body_targets(&body).into_iter().try_for_each(|target| key.check_access("POST", path, target))
+    }
+
+    #[test]
+    fn fanout_checks_every_listed_connection() {
+        let key = scoped_key(&["c1", "c2"]);
+        assert!(check(&key, "/api/query/fanout", json!({ "connection_ids": ["c1", "c2"], "sql": "SELECT 1" })).is_ok());
+        assert!(check(&key, "/api/query/fanout", json!({ "connection_ids": ["c1", "c3"], "sql": "SELECT 1" })).is_err());
+        // 顶层的允许连接不能掩护列表中的其他连接
+        let decoy = json!({ "connection_id": "c1", "connection_ids": ["c3"], "sql": "SELECT 1" });
+        assert_eq!(body_targets(&decoy), vec![Some("c1"), Some("c3")]);
+        assert!(check(&key, "/api/query/fanout", decoy).is_err());
+        assert!(check(&key, "/api/query", json!({ "sql": "SELECT 1" })).is_err());
+    }
+
+    #[test]
+    fn transfer_checks_source_and_target() {
+        let key = scoped_key(&["c1", "c2"]);
+        let transfer = |source: &str, target: &str| {
+            json!({ "source_connection_id": source, "source_table": "t", "target_connection_id": target, "target_table": "t" })
+        };
+        assert!(check(&key, "/api/transfers", transfer("c1", "c2")).is_ok());
+        assert!(check(&key, "/api/transfers", transfer("c3", "c2")).is_err());
+        assert!(check(&key, "/api/transfers", transfer("c1", "c3")).is_err());
+        let mut decoy = transfer("c3", "c1");
+        decoy["connection_id"] = "c1".into();
+        assert!(check(&key, "/api/transfers", decoy).is_err());
+    }
+
+    #[test]
+    fn diff_checks_both_sides() {
+        let key = scoped_key(&["c1"]);
+        let side = |id: &str| json!({ "connection_id": id, "sql": "SELECT 1" });
+        let diff = |left: &str, right: &str| json!({ "left": side(left), "right": side(right), "key_columns": ["id"] });
+        assert!(check(&key, "/api/query/diff", diff("c1", "c1")).is_ok());
+        assert!(check(&key, "/api/query/diff", diff("c1", "c2")).is_err());
+        let mut decoy = diff("c2", "c1");
+        decoy["connection_id"] = "c1".into();
+        assert!(check(&key, "/api/query/diff", decoy).is_err());
+    }
+
+    #[test]
+    fn read_only_key_cannot_request_confirmation() {
+        let key = ApiKey { read_only: true, ..scoped_key(&[]) };
+        let query = |sql: &str| json!({ "connection_id": "c1", "sql": sql });
+        assert!(check_read_only(&key, &query("SELECT * FROM logs")).is_ok());
+        // 不带 WHERE 的 DELETE 与 DDL 会返回 428 与确认令牌，网关须在转发前拒绝
+        assert!(check_read_only(&key, &query("DELETE FROM logs")).is_err());
+        assert!(check_read_only(&key, &query("DROP TABLE logs")).is_err());
+        let mut confirmed = query("DELETE FROM logs");
+        confirmed["confirmation_token"] = "token".into();
+        assert!(check_read_only(&key, &confirmed).is_err());
+        assert!(check_read_only(&key, &json!({ "queries": [query("SELECT 1"), query("TRUNCATE logs")] })).is_err());
+        let guest = ApiKey { guest: true, ..scoped_key(&["c1"]) };
+        assert!(check_read_only(&guest, &query("UPDATE users SET admin = TRUE")).is_err());
+        assert!(check_read_only(&scoped_key(&[]), &query("DROP TABLE logs")).is_ok());
+    }
+
+    #[test]
+    fn batch_checks_every_query() {
+        let key = scoped_key(&["c1"]);
+        let query = |id: &str| json!({ "connection_id": id, "sql": "SELECT 1" });
+        assert!(check(&key, "/api/query/batch", json!({ "queries": [query("c1"), query("c1")] })).is_ok());
+        assert!(check(&key, "/api/query/batch", json!({ "queries": [query("c1"), query("c2")] })).is_err());
+        assert!(check(&key, "/api/query/batch", json!({ "queries": [{ "sql": "SELECT 1" }] })).is_err());
+        let decoy = json!({ "connection_id": "c1", "queries": [query("c2")] });
+        assert!(check(&key, "/api/query/batch", decoy).is_err());
+    }
+}
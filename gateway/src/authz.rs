@@ -0,0 +1,79 @@
+//! 授权策略模块
+//!
+//! 启用后，网关对每个 `/api/**` 请求（健康检查除外）向连接服务询问授权决策：
+//! 主体为 API Key（`key:<id>`）或匿名（`anonymous`），动作为 read / write /
+//! admin，资源为目标连接。策略的评估与决策日志都在连接服务完成，网关只按
+//! 结果放行或返回 403。连接服务不可用时拒绝请求。
+//!
+//! 配置：
+//! - `GATEWAY_POLICY_ENFORCEMENT` - 为 true 时启用授权策略（默认 false）
+
+use common::errors::{AppError, AppResult};
+use common::middleware::{RequestSigner, SendSigned};
+use common::models::policy::{AuthzDecision, AuthzRequest};
+
+/// 授权策略客户端
+pub struct PolicyClient {
+    connection_service_url: String,
+    http_client: reqwest::Client,
+    signer: RequestSigner,
+    pub enforced: bool,
+}
+
+impl PolicyClient {
+    /// 创建客户端，从环境变量读取是否启用
+    pub fn new(connection_service_url: String, http_client: reqwest::Client, signer: RequestSigner) -> Self {
+        let enforced = std::env::var("GATEWAY_POLICY_ENFORCEMENT")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        Self {
+            connection_service_url,
+            http_client,
+            signer,
+            enforced,
+        }
+    }
+
+    /// 判定请求，拒绝时返回错误
+    ///
+    /// # Errors
+    /// 策略拒绝时返回 `AppError::Forbidden`；连接服务不可用时返回 `AppError::ServiceUnavailable`。
+    pub async fn authorize(&self, request: &AuthzRequest) -> AppResult<()> {
+        let decision = self.decide(request).await?;
+        if decision.allowed {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!(
+                "{} 无权执行 {} {}: {}",
+                request.principal,
+                request.method.as_deref().unwrap_or_default(),
+                request.path.as_deref().unwrap_or_default(),
+                decision.reason
+            )))
+        }
+    }
+
+    async fn decide(&self, request: &AuthzRequest) -> AppResult<AuthzDecision> {
+        let url = format!("{}/internal/authz/decide", self.connection_service_url);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(request)
+            .send_signed(&self.signer)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("无法获取授权决策: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(AppError::ServiceUnavailable(format!(
+                "无法获取授权决策: 连接服务返回 {}",
+                response.status().as_u16()
+            )));
+        }
+
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("连接服务返回无效响应: {}", e)))?;
+        serde_json::from_value(body["data"].clone())
+            .map_err(|e| AppError::ExternalService(format!("连接服务返回无效决策: {}", e)))
+    }
+}
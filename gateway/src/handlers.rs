@@ -1,14 +1,31 @@
 //! Handler模块
 
 use axum::{
-    extract::State,
+    extract::{Query, State},
+    http::{header, Extensions, HeaderMap, StatusCode},
+    response::{AppendHeaders, IntoResponse, Redirect, Response},
     Json,
 };
 use chrono::{DateTime, Utc};
-use serde::Serialize;
-use utoipa::ToSchema;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
+use common::admin;
+use common::config::ConfigReload;
+use common::discovery::ServiceRegistration;
+use common::config::OidcConfig;
+use common::errors::{AppError, AppResult};
+use common::middleware::csrf::{cookie_value, session_cookies};
+use common::models::auth::AuthTokens;
+use common::response::ApiResponse;
+use common::probes::{Liveness, ProbeCheck, Readiness};
+
+use crate::health::AggregatedHealth;
+use crate::oidc::{LOGIN_TTL, STATE_COOKIE};
+use crate::proxy::client_ip;
+use crate::registry::ServiceInstance;
 use crate::state::AppState;
+use crate::upstream::UpstreamReport;
 
 /// 网关健康检查
 #[utoipa::path(
@@ -28,7 +45,56 @@ pub async fn health_check() -> Json<HealthResponse> {
     })
 }
 
-/// 聚合健康检查 - 检查所有微服务的健康状态
+/// 存活探针：进程能响应请求即返回 200
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, description = "网关存活", body = Liveness)
+    )
+)]
+pub async fn healthz() -> Json<Liveness> {
+    Json(Liveness::new("gateway"))
+}
+
+/// 就绪探针：核心服务（connection-service、query-service）可达时返回 200，否则返回 503 与未通过的检查项
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "网关就绪", body = Readiness),
+        (status = 503, description = "核心服务不可达", body = Readiness)
+    )
+)]
+pub async fn readyz(State(state): State<AppState>) -> Readiness {
+    let (conn_url, query_url) = tokio::join!(
+        state.registry.resolve_or("connection-service", &state.service_urls.connection_service),
+        state.registry.resolve_or("query-service", &state.service_urls.query_service),
+    );
+    let (conn, query) = tokio::join!(
+        ProbeCheck::run("connection-service", check_upstream(&state.http_client, &conn_url)),
+        ProbeCheck::run("query-service", check_upstream(&state.http_client, &query_url)),
+    );
+    Readiness::new("gateway", vec![conn, query])
+}
+
+/// 上游服务的存活探针是否返回成功
+async fn check_upstream(client: &reqwest::Client, url: &str) -> Result<(), String> {
+    let response = client
+        .get(format!("{}/healthz", url))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", response.status()))
+    }
+}
+
+/// 聚合健康检查 - 检查所有微服务（含注册表中的实例）的健康状态
 #[utoipa::path(
     get,
     path = "/api/health/aggregated",
@@ -40,53 +106,252 @@ pub async fn health_check() -> Json<HealthResponse> {
 pub async fn aggregated_health(
     State(state): State<AppState>,
 ) -> Json<AggregatedHealth> {
-    // Only check core services (connection-service + query-service)
-    // ai-service is optional and excluded from health checks
-    let (conn_health, query_health) = tokio::join!(
-        check_service_health(&state.http_client, "connection-service", &state.service_urls.connection_service),
-        check_service_health(&state.http_client, "query-service", &state.service_urls.query_service),
-    );
+    Json(state.health.check(&state.http_client, &state.service_urls, &state.registry).await)
+}
+
+/// 服务注册与心跳：实例已存在时刷新心跳（200），新实例返回 201
+///
+/// 请求须带内部签名；未配置 INTERNAL_SIGNING_SECRET 时不接受注册。
+#[utoipa::path(
+    post,
+    path = "/internal/registry",
+    tag = "registry",
+    request_body = ServiceRegistration,
+    responses(
+        (status = 200, description = "心跳已刷新"),
+        (status = 201, description = "实例已注册"),
+        (status = 400, description = "名称或地址无效"),
+        (status = 401, description = "签名无效"),
+        (status = 403, description = "网关未启用内部签名")
+    )
+)]
+pub async fn register_service(
+    State(state): State<AppState>,
+    Json(registration): Json<ServiceRegistration>,
+) -> AppResult<StatusCode> {
+    require_signing(&state)?;
+    if registration.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("服务名称不能为空".to_string()));
+    }
+    let valid_url = reqwest::Url::parse(&registration.url)
+        .is_ok_and(|u| matches!(u.scheme(), "http" | "https"));
+    if !valid_url {
+        return Err(AppError::InvalidInput(format!("服务地址无效: {}", registration.url)));
+    }
+    if state.registry.register(registration).await {
+        Ok(StatusCode::CREATED)
+    } else {
+        Ok(StatusCode::OK)
+    }
+}
 
-    let services = vec![conn_health, query_health];
+/// 注销服务实例
+#[utoipa::path(
+    delete,
+    path = "/internal/registry",
+    tag = "registry",
+    request_body = ServiceRegistration,
+    responses(
+        (status = 204, description = "实例已注销"),
+        (status = 401, description = "签名无效"),
+        (status = 403, description = "网关未启用内部签名"),
+        (status = 404, description = "实例不存在")
+    )
+)]
+pub async fn deregister_service(
+    State(state): State<AppState>,
+    Json(registration): Json<ServiceRegistration>,
+) -> AppResult<StatusCode> {
+    require_signing(&state)?;
+    if state.registry.deregister(&registration.name, &registration.url).await {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(AppError::NotFound(format!("服务实例 {} {}", registration.name, registration.url)))
+    }
+}
 
-    let all_healthy = services.iter().all(|s| s.healthy);
+/// 注册表中的存活服务实例
+#[utoipa::path(
+    get,
+    path = "/api/registry",
+    tag = "registry",
+    responses(
+        (status = 200, description = "存活的服务实例", body = Vec<ServiceInstance>)
+    )
+)]
+pub async fn list_services(State(state): State<AppState>) -> Json<Vec<ServiceInstance>> {
+    Json(state.registry.list().await)
+}
 
-    Json(AggregatedHealth {
-        status: if all_healthy { "healthy" } else { "degraded" }.to_string(),
-        timestamp: Utc::now(),
-        services,
-    })
+/// 重新加载网关配置（.env 文件与命令行覆盖），请求体上限与路由表立即生效，需要 X-Admin-Token
+#[utoipa::path(
+    post,
+    path = "/api/admin/config/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "已重新加载，返回变更的配置项与需重启才生效的配置项", body = ApiResponse<ConfigReload>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 500, description = "新配置无效，保留当前配置")
+    )
+)]
+pub async fn reload_config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<ApiResponse<ConfigReload>>> {
+    admin::authorize(&headers)?;
+    let reload = state.config.reload()?;
+    Ok(Json(ApiResponse::ok_with_service(reload, "gateway")))
+}
+
+/// 上游连接设置与统计：新建连接数、各上游的请求数、进行中请求、失败数、延迟与 HTTP 版本，需要 X-Admin-Token
+#[utoipa::path(
+    get,
+    path = "/api/admin/upstreams",
+    tag = "admin",
+    responses(
+        (status = 200, description = "上游连接统计", body = ApiResponse<UpstreamReport>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用")
+    )
+)]
+pub async fn upstream_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<ApiResponse<UpstreamReport>>> {
+    admin::authorize(&headers)?;
+    let report = state.upstream_stats.report(&state.upstream_settings);
+    Ok(Json(ApiResponse::ok_with_service(report, "gateway")))
+}
+
+/// 单点登录：跳转到身份提供方的登录页
+#[utoipa::path(
+    get,
+    path = "/api/auth/oidc/login",
+    tag = "auth",
+    responses(
+        (status = 303, description = "跳转到身份提供方"),
+        (status = 403, description = "未配置单点登录（OIDC_ISSUER）"),
+        (status = 503, description = "无法读取身份提供方发现文档")
+    )
+)]
+pub async fn oidc_login(State(state): State<AppState>) -> AppResult<Response> {
+    let config = state.config.get();
+    let oidc = sso_config(&config.oidc)?;
+    let (url, login_state) = state.oidc.start(oidc).await?;
+    let cookie = state_cookie(oidc, &login_state, LOGIN_TTL.as_secs());
+    Ok(([(header::SET_COOKIE, cookie)], Redirect::to(&url)).into_response())
+}
+
+/// 身份提供方回调参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct OidcCallbackQuery {
+    /// 授权码
+    pub code: Option<String>,
+    /// 发起登录时生成的 state
+    pub state: Option<String>,
+    /// 身份提供方返回的错误
+    pub error: Option<String>,
+    /// 错误说明
+    pub error_description: Option<String>,
+}
+
+/// 单点登录回调：校验身份提供方的结果并签发令牌
+///
+/// 配置了 `OIDC_POST_LOGIN_REDIRECT` 时跳转到该页面，令牌放在 URL 片段中；否则直接返回令牌。
+/// 启用 Cookie 会话时同时设置访问令牌与 CSRF 令牌的 Cookie。
+#[utoipa::path(
+    get,
+    path = "/api/auth/oidc/callback",
+    tag = "auth",
+    params(OidcCallbackQuery),
+    responses(
+        (status = 200, description = "登录成功", body = ApiResponse<AuthTokens>),
+        (status = 303, description = "登录成功，跳转到 OIDC_POST_LOGIN_REDIRECT"),
+        (status = 400, description = "登录请求无效、已过期或被身份提供方拒绝"),
+        (status = 401, description = "授权码或 ID 令牌无效"),
+        (status = 403, description = "未配置单点登录（OIDC_ISSUER）")
+    )
+)]
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    extensions: Extensions,
+    Query(query): Query<OidcCallbackQuery>,
+) -> AppResult<Response> {
+    let config = state.config.get();
+    let oidc = sso_config(&config.oidc)?;
+    if let Some(error) = query.error {
+        return Err(AppError::InvalidInput(format!(
+            "身份提供方拒绝登录: {} {}",
+            error,
+            query.error_description.unwrap_or_default()
+        )));
+    }
+    let (Some(code), Some(login_state)) = (query.code, query.state) else {
+        return Err(AppError::InvalidInput("回调缺少 code 或 state 参数".to_string()));
+    };
+    // state 须与发起登录的浏览器 Cookie 一致，防止把他人的登录结果注入当前浏览器
+    if cookie_value(&headers, STATE_COOKIE) != Some(login_state.as_str()) {
+        return Err(AppError::InvalidInput("登录请求无效或已过期，请重新登录".to_string()));
+    }
+
+    let identity = state.oidc.finish(oidc, &code, &login_state).await?;
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let ip_address = client_ip(&headers, &extensions, &config.trusted_proxies).map(|ip| ip.to_string());
+    let tokens = state.oidc.create_session(identity, user_agent, ip_address).await?;
+
+    let mut cookies = vec![state_cookie(oidc, "", 0)];
+    if let Some(config) = &config.session_cookie {
+        cookies.extend(session_cookies(config, &tokens.access_token, tokens.expires_in.max(0) as u64));
+    }
+    let cookies = AppendHeaders(cookies.into_iter().map(|c| (header::SET_COOKIE, c)));
+    match &oidc.post_login_redirect {
+        Some(page) => {
+            // 令牌放在片段中，不会随请求发送到页面所在的服务器
+            let fragment = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("access_token", &tokens.access_token)
+                .append_pair("token_type", &tokens.token_type)
+                .append_pair("expires_in", &tokens.expires_in.to_string())
+                .append_pair("refresh_token", &tokens.refresh_token)
+                .append_pair("session_id", &tokens.session_id)
+                .append_pair("username", &tokens.username)
+                .finish();
+            let target = format!("{}#{}", page, fragment);
+            Ok((cookies, Redirect::to(&target)).into_response())
+        }
+        None => Ok((
+            cookies,
+            Json(ApiResponse::ok_with_service(tokens, "gateway")),
+        )
+            .into_response()),
+    }
+}
+
+fn sso_config(config: &Option<OidcConfig>) -> AppResult<&OidcConfig> {
+    config
+        .as_ref()
+        .ok_or_else(|| AppError::Forbidden("网关未配置 OIDC_ISSUER，单点登录已禁用".to_string()))
 }
 
-async fn check_service_health(
-    client: &reqwest::Client,
-    name: &str,
-    url: &str,
-) -> ServiceHealth {
-    let health_url = format!("{}/api/health", url);
-    
-    // Use a short timeout for health checks so the aggregated endpoint responds quickly
-    match client.get(&health_url)
-        .timeout(std::time::Duration::from_secs(3))
-        .send().await {
-        Ok(response) if response.status().is_success() => ServiceHealth {
-            name: name.to_string(),
-            url: url.to_string(),
-            healthy: true,
-            error: None,
-        },
-        Ok(response) => ServiceHealth {
-            name: name.to_string(),
-            url: url.to_string(),
-            healthy: false,
-            error: Some(format!("HTTP {}", response.status())),
-        },
-        Err(e) => ServiceHealth {
-            name: name.to_string(),
-            url: url.to_string(),
-            healthy: false,
-            error: Some(e.to_string()),
-        },
+/// 登录 state 的 Cookie，仅在回调路径上发送；`max_age` 为 0 时删除
+fn state_cookie(config: &OidcConfig, value: &str, max_age: u64) -> String {
+    let secure = if config.redirect_url.starts_with("https://") { "; Secure" } else { "" };
+    format!(
+        "{}={}; Path=/api/auth/oidc; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        STATE_COOKIE, value, max_age, secure
+    )
+}
+
+/// 注册请求只能经签名校验后接受，否则任何客户端都能劫持网关的转发目标
+fn require_signing(state: &AppState) -> AppResult<()> {
+    if state.signatures.is_enabled() {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden("网关未配置 INTERNAL_SIGNING_SECRET，不接受服务注册".to_string()))
     }
 }
 
@@ -97,19 +362,3 @@ pub struct HealthResponse {
     pub version: String,
     pub timestamp: DateTime<Utc>,
 }
-
-#[derive(Serialize, ToSchema)]
-pub struct AggregatedHealth {
-    pub status: String,
-    pub timestamp: DateTime<Utc>,
-    pub services: Vec<ServiceHealth>,
-}
-
-#[derive(Serialize, ToSchema)]
-pub struct ServiceHealth {
-    pub name: String,
-    pub url: String,
-    pub healthy: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
-}
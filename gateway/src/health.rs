@@ -0,0 +1,196 @@
+//! 聚合健康检查模块
+//!
+//! 并发检查服务注册表中的全部存活实例（按各自注册的健康检查路径）；核心服务
+//! （connection-service、query-service）没有注册实例时检查静态配置地址的
+//! `/api/health`。每个实例的检查超过 `GATEWAY_HEALTH_TIMEOUT_MS` 即视为不健康，
+//! 整体耗时不超过该超时。结果缓存 `GATEWAY_HEALTH_CACHE_SECS` 秒，期间的请求直接返回缓存，
+//! 频繁轮询不会放大到下游；缓存过期时并发到达的请求只触发一次检查。
+//!
+//! 配置：
+//! - `GATEWAY_HEALTH_TIMEOUT_MS` - 单个服务的检查超时（默认 2000 毫秒）
+//! - `GATEWAY_HEALTH_CACHE_SECS` - 检查结果缓存时间（默认 5 秒，0 表示不缓存）
+
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+use common::config::ServiceUrls;
+use common::discovery::DEFAULT_HEALTH_PATH;
+
+use crate::registry::ServiceRegistry;
+
+const DEFAULT_TIMEOUT_MS: u64 = 2000;
+const DEFAULT_CACHE_SECS: u64 = 5;
+
+/// 聚合健康检查结果
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AggregatedHealth {
+    pub status: String,
+    /// 检查时间，命中缓存时为缓存结果的检查时间
+    pub timestamp: DateTime<Utc>,
+    pub services: Vec<ServiceHealth>,
+}
+
+/// 单个服务的健康状况
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ServiceHealth {
+    pub name: String,
+    pub url: String,
+    pub healthy: bool,
+    /// 检查耗时
+    pub latency_ms: u64,
+    /// 服务报告的版本
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 带缓存的聚合健康检查
+pub struct HealthChecker {
+    timeout: Duration,
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, AggregatedHealth)>>,
+}
+
+impl HealthChecker {
+    /// 从环境变量读取超时与缓存时间
+    pub fn from_env() -> Self {
+        let env_u64 = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            timeout: Duration::from_millis(env_u64("GATEWAY_HEALTH_TIMEOUT_MS", DEFAULT_TIMEOUT_MS).max(1)),
+            ttl: Duration::from_secs(env_u64("GATEWAY_HEALTH_CACHE_SECS", DEFAULT_CACHE_SECS)),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// 返回缓存的检查结果，过期时重新检查
+    pub async fn check(&self, client: &reqwest::Client, urls: &ServiceUrls, registry: &ServiceRegistry) -> AggregatedHealth {
+        // 持锁检查，缓存过期时并发请求等待同一次检查
+        let mut cached = self.cached.lock().await;
+        if let Some((at, health)) = cached.as_ref() {
+            if at.elapsed() < self.ttl {
+                return health.clone();
+            }
+        }
+
+        // 注册的实例，加上没有注册实例的核心服务的静态地址
+        let mut targets: Vec<(String, String, String)> = registry
+            .list()
+            .await
+            .into_iter()
+            .map(|i| (i.name, i.url, i.health_path))
+            .collect();
+        for (name, url) in [
+            ("connection-service", &urls.connection_service),
+            ("query-service", &urls.query_service),
+        ] {
+            if !targets.iter().any(|(n, _, _)| n == name) {
+                targets.push((name.to_string(), url.clone(), DEFAULT_HEALTH_PATH.to_string()));
+            }
+        }
+
+        let services = join_all(
+            targets
+                .iter()
+                .map(|(name, url, path)| self.check_service(client, name, url, path)),
+        )
+        .await;
+        let all_healthy = services.iter().all(|s| s.healthy);
+        let health = AggregatedHealth {
+            status: if all_healthy { "healthy" } else { "degraded" }.to_string(),
+            timestamp: Utc::now(),
+            services,
+        };
+        *cached = Some((Instant::now(), health.clone()));
+        health
+    }
+
+    async fn check_service(&self, client: &reqwest::Client, name: &str, url: &str, health_path: &str) -> ServiceHealth {
+        let start = Instant::now();
+        let result = async {
+            let response = client
+                .get(format!("{}{}", url, health_path))
+                .timeout(self.timeout)
+                .send()
+                .await
+                .map_err(|e| (e.to_string(), None))?;
+            let status = response.status();
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            let version = body["version"].as_str().map(str::to_string);
+            if !status.is_success() {
+                return Err((format!("HTTP {}", status), version));
+            }
+            // 服务自身报告降级（如元数据库不可用）同样视为不健康
+            match body["status"].as_str() {
+                Some("degraded") => Err(("service reported degraded".to_string(), version)),
+                _ => Ok(version),
+            }
+        };
+        // 超时同时覆盖读取响应体
+        let result = tokio::time::timeout(self.timeout, result)
+            .await
+            .unwrap_or_else(|_| Err((format!("timed out after {}ms", self.timeout.as_millis()), None)));
+
+        let latency_ms = start.elapsed().as_millis() as u64;
+        match result {
+            Ok(version) => ServiceHealth {
+                name: name.to_string(),
+                url: url.to_string(),
+                healthy: true,
+                latency_ms,
+                version,
+                error: None,
+            },
+            Err((error, version)) => ServiceHealth {
+                name: name.to_string(),
+                url: url.to_string(),
+                healthy: false,
+                latency_ms,
+                version,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn unreachable_services_are_degraded_and_cached() {
+        let checker = HealthChecker {
+            timeout: Duration::from_millis(500),
+            ttl: Duration::from_secs(60),
+            cached: Mutex::new(None),
+        };
+        let urls = ServiceUrls {
+            gateway: String::new(),
+            connection_service: "http://127.0.0.1:1".to_string(),
+            query_service: "http://127.0.0.1:1".to_string(),
+            ai_service: String::new(),
+        };
+        let client = reqwest::Client::new();
+        let registry = ServiceRegistry::from_env();
+
+        let first = checker.check(&client, &urls, &registry).await;
+        assert_eq!(first.status, "degraded");
+        assert_eq!(first.services.len(), 2);
+        assert!(first.services.iter().all(|s| !s.healthy && s.error.is_some()));
+
+        // 缓存期内返回同一次检查的结果
+        let second = checker.check(&client, &urls, &registry).await;
+        assert_eq!(second.timestamp, first.timestamp);
+    }
+}
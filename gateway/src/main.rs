@@ -1,30 +1,46 @@
 //! API 网关服务
 //!
 //! 作为所有客户端请求的入口点，提供以下功能：
-//! - 请求路由转发到对应的微服务
-//! - 身份认证与授权
+//! - 请求路由转发到对应的微服务（含 WebSocket 连接）
+//! - 身份认证与授权（API Key）
 //! - 限流与熔断
 //! - 请求/响应日志记录
 
+mod auth;
+mod authz;
+mod health;
+mod oidc;
 mod proxy;
+mod registry;
+mod retry;
+mod routing;
+mod session;
+mod upstream;
+mod websocket;
 mod routes;
 mod state;
 mod handlers;
 
+use std::sync::Arc;
+
 use axum::{middleware, routing::get, Json, Router, response::Html};
 use common::config::AppConfig;
+use common::fallback;
+use common::middleware::cors::cors_layer;
+use common::middleware::security_headers::SecurityHeadersLayer;
 use common::middleware::request_id::request_id_middleware;
+use common::openapi::ResponseExamples;
 use state::AppState;
 use tokio::net::TcpListener;    
-use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tower_http::compression::CompressionLayer;
-use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing::{error, info};
 use utoipa::OpenApi;
 
 const SERVICE_NAME: &str = "gateway";
 const DEFAULT_PORT: u16 = 8080;
+/// 网关默认请求体上限，需容纳连接服务的备份恢复请求
+const DEFAULT_MAX_BODY_BYTES: usize = 256 * 1024 * 1024;
 
 #[derive(OpenApi)]
 #[openapi(
@@ -36,67 +52,108 @@ const DEFAULT_PORT: u16 = 8080;
     paths(
         handlers::health_check,
         handlers::aggregated_health,
+        handlers::healthz,
+        handlers::readyz,
+        handlers::register_service,
+        handlers::deregister_service,
+        handlers::list_services,
+        handlers::reload_config,
+        handlers::upstream_stats,
+        handlers::oidc_login,
+        handlers::oidc_callback,
     ),
     components(schemas(
         handlers::HealthResponse,
-        handlers::AggregatedHealth,
-        handlers::ServiceHealth,
+        common::probes::Liveness,
+        common::probes::Readiness,
+        common::probes::ProbeCheck,
+        health::AggregatedHealth,
+        health::ServiceHealth,
+        registry::ServiceInstance,
+        common::discovery::ServiceRegistration,
+        common::config::ConfigReload,
+        upstream::UpstreamReport,
+        upstream::UpstreamSettings,
+        upstream::UpstreamStat,
     )),
     tags(
         (name = "gateway", description = "网关端点"),
-        (name = "health", description = "健康检查端点")
-    )
+        (name = "health", description = "健康检查端点"),
+        (name = "registry", description = "服务注册与发现"),
+        (name = "admin", description = "配置重新加载与上游连接统计"),
+        (name = "auth", description = "单点登录")
+    ),
+    modifiers(&ResponseExamples)
 )]
 struct ApiDoc;
 
 #[tokio::main]
 async fn main() {
-    // 初始化日志追踪
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".into()),
-        )
-        .init();
+    // 加载配置（.env 文件、环境变量、命令行覆盖），配置无效时列出问题并退出
+    let config = Arc::new(
+        AppConfig::loader(SERVICE_NAME)
+            .default_port(DEFAULT_PORT)
+            .default_max_body_bytes(DEFAULT_MAX_BODY_BYTES)
+            .startup_only(&["HSTS_MAX_AGE_SECS", "CONTENT_SECURITY_POLICY"])
+            .load_shared_or_exit(),
+    );
 
-    // 加载配置
-    let mut config = AppConfig::load_with_service(SERVICE_NAME);
-    config.port = std::env::var("SERVER_PORT")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(DEFAULT_PORT);
+    // 初始化日志追踪（LOG_FORMAT=json 时输出 JSON）
+    common::logging::init(&config.get());
 
     // 创建应用状态
     let state = AppState::new(config.clone());
+    // 收到 SIGHUP 时重新加载配置
+    config.reload_on_sighup();
 
     // 创建路由
     let app = create_router(state);
 
     // 启动服务
-    let addr = format!("{}:{}", config.host, config.port);
+    let addr = config.get().addr();
     info!(service = SERVICE_NAME, address = %addr, "启动 API 网关");
 
     let listener = TcpListener::bind(&addr).await.expect("绑定地址失败");
-    axum::serve(listener, app).await.expect("服务启动失败");
+    let startup = config.get();
+    // 启用 TLS 时可另开明文端口，把 HTTP 请求重定向到 HTTPS
+    if let Some(redirect_port) = startup.tls.as_ref().and_then(|tls| tls.http_redirect_port) {
+        let redirect_addr = format!("{}:{}", startup.host, redirect_port);
+        let redirect_listener = TcpListener::bind(&redirect_addr).await.expect("绑定重定向地址失败");
+        info!(address = %redirect_addr, "HTTP 请求重定向到 HTTPS");
+        let redirect = common::tls::https_redirect(startup.port);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(redirect_listener, redirect).await {
+                error!(error = %e, "HTTPS 重定向服务退出");
+            }
+        });
+    }
+    common::tls::serve(listener, app, startup.tls.as_ref())
+        .await
+        .expect("服务启动失败");
 }
 
 fn create_router(state: AppState) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // 跨域与安全响应头设置只在启动时读取
+    let config = state.config.get();
+    let cors = cors_layer(&config.cors);
+    let security_headers = SecurityHeadersLayer::new(&config.security_headers);
 
     Router::new()
         .merge(routes::router())
+        .merge(routes::internal_router(state.signatures.clone()))
         .merge(proxy::router())
         .route("/api-docs/openapi.json", get(openapi_json))
         .route("/swagger-ui", get(swagger_ui))
         .route("/docs", get(swagger_ui))
+        .method_not_allowed_fallback(|req: axum::extract::Request| async move {
+            fallback::method_not_allowed("gateway", &req)
+        })
+        .layer(middleware::from_fn_with_state(state.clone(), auth::api_key_middleware))
         .layer(CompressionLayer::new())
         .layer(middleware::from_fn(request_id_middleware))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
+        .layer(security_headers)
         .with_state(state)
 }
 
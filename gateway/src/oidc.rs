@@ -0,0 +1,397 @@
+//! 单点登录模块（OpenID Connect 授权码流程）
+//!
+//! 配置 `OIDC_ISSUER` 等设置后（见 `common::config::OidcConfig`），用户可通过
+//! 外部身份提供方登录：
+//! 1. `/api/auth/oidc/login` 读取身份提供方的发现文档，生成 state、nonce 与 PKCE
+//!    校验码，以 Cookie 绑定发起登录的浏览器后跳转到身份提供方
+//! 2. `/api/auth/oidc/callback` 核对 state 与 Cookie，用授权码向令牌端点换取
+//!    ID 令牌，校验签发方、受众、过期时间与 nonce
+//! 3. 按配置从 ID 令牌取用户名，并把角色声明映射为本地角色，由连接服务创建会话、
+//!    签发访问令牌与刷新令牌（与密码登录相同）
+//!
+//! ID 令牌直接通过 TLS 从令牌端点取得，按 OIDC Core 3.1.3.7 以 TLS 服务端校验
+//! 代替签名校验，因此签发方须为 https 地址（本机调试除外）。进行中的登录保存在
+//! 网关内存中，多实例部署时回调须落在发起登录的实例上（会话保持）。
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use common::config::OidcConfig;
+use common::errors::{AppError, AppResult};
+use common::middleware::{RequestSigner, SendSigned};
+use common::models::auth::AuthTokens;
+use common::models::session::CreateSsoSessionRequest;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+/// 从跳转到身份提供方到回调的最长时间
+pub const LOGIN_TTL: Duration = Duration::from_secs(600);
+/// 绑定登录流程与浏览器的 Cookie
+pub const STATE_COOKIE: &str = "dbm_oidc_state";
+/// 发现文档缓存时间
+const DISCOVERY_TTL: Duration = Duration::from_secs(3600);
+/// 进行中的登录上限，超过时先清理过期条目
+const MAX_PENDING_LOGINS: usize = 10_000;
+
+/// 身份提供方发现文档中用到的字段
+#[derive(Debug, Clone, Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+/// 进行中的登录
+struct PendingLogin {
+    nonce: String,
+    code_verifier: String,
+    started_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// 身份提供方认证的用户
+///
+/// 用户由签发方与 `sub` 声明确定，主体为 `sso:<签发方>:<sub>`；用户名只用于显示，
+/// 与本地用户同名时也不会取得该用户的主体。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsoIdentity {
+    pub issuer: String,
+    pub subject: String,
+    pub username: String,
+    pub roles: Vec<String>,
+}
+
+/// OpenID Connect 客户端
+pub struct OidcClient {
+    connection_service_url: String,
+    http_client: reqwest::Client,
+    signer: RequestSigner,
+    /// 签发方 → (获取时间, 发现文档)
+    discovery: RwLock<HashMap<String, (Instant, Discovery)>>,
+    /// state → 进行中的登录
+    pending: Mutex<HashMap<String, PendingLogin>>,
+}
+
+impl OidcClient {
+    pub fn new(connection_service_url: String, http_client: reqwest::Client, signer: RequestSigner) -> Self {
+        Self {
+            connection_service_url,
+            http_client,
+            signer,
+            discovery: RwLock::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 开始登录，返回身份提供方的授权地址与本次登录的 state
+    ///
+    /// # Errors
+    /// 无法读取发现文档时返回 `AppError::ServiceUnavailable`。
+    pub async fn start(&self, config: &OidcConfig) -> AppResult<(String, String)> {
+        let discovery = self.discover(config).await?;
+        let state = random_token();
+        let nonce = random_token();
+        let code_verifier = format!("{}{}", random_token(), random_token());
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let url = reqwest::Url::parse_with_params(
+            &discovery.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", config.client_id.as_str()),
+                ("redirect_uri", config.redirect_url.as_str()),
+                ("scope", config.scopes.as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", code_challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| AppError::ExternalService(format!("身份提供方授权地址无效: {}", e)))?;
+
+        let mut pending = self.pending.lock().await;
+        if pending.len() >= MAX_PENDING_LOGINS {
+            pending.retain(|_, login| login.started_at.elapsed() < LOGIN_TTL);
+            if pending.len() >= MAX_PENDING_LOGINS {
+                return Err(AppError::ServiceUnavailable("进行中的登录过多，请稍后重试".to_string()));
+            }
+        }
+        pending.insert(
+            state.clone(),
+            PendingLogin {
+                nonce,
+                code_verifier,
+                started_at: Instant::now(),
+            },
+        );
+        Ok((url.into(), state))
+    }
+
+    /// 完成登录：用授权码换取 ID 令牌并校验，返回认证的用户
+    ///
+    /// # Errors
+    /// state 未知或已过期时返回 `AppError::InvalidInput`；授权码或 ID 令牌无效时返回 `AppError::Unauthorized`。
+    pub async fn finish(&self, config: &OidcConfig, code: &str, state: &str) -> AppResult<SsoIdentity> {
+        let pending = self
+            .pending
+            .lock()
+            .await
+            .remove(state)
+            .filter(|login| login.started_at.elapsed() < LOGIN_TTL)
+            .ok_or_else(|| AppError::InvalidInput("登录请求无效或已过期，请重新登录".to_string()))?;
+        let discovery = self.discover(config).await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("client_id", config.client_id.as_str()),
+            ("code_verifier", pending.code_verifier.as_str()),
+        ];
+        if let Some(secret) = &config.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        let response = self
+            .http_client
+            .post(&discovery.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("无法连接身份提供方: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            tracing::warn!(status = %status, body = %body, "身份提供方拒绝授权码");
+            return Err(AppError::Unauthorized);
+        }
+        let tokens: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("身份提供方返回无效的令牌响应: {}", e)))?;
+
+        let claims = validate_id_token(
+            &tokens.id_token,
+            &discovery.issuer,
+            &config.client_id,
+            &pending.nonce,
+            chrono::Utc::now().timestamp(),
+        )?;
+        identity(config, &claims)
+    }
+
+    /// 由连接服务为认证的用户创建会话并签发令牌
+    pub async fn create_session(
+        &self,
+        identity: SsoIdentity,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+    ) -> AppResult<AuthTokens> {
+        let url = format!("{}/internal/auth/sso-session", self.connection_service_url);
+        let request = CreateSsoSessionRequest {
+            issuer: identity.issuer,
+            subject: identity.subject,
+            username: identity.username,
+            roles: identity.roles,
+            user_agent,
+            ip_address,
+        };
+        let response = self
+            .http_client
+            .post(&url)
+            .json(&request)
+            .send_signed(&self.signer)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("无法创建会话: {}", e)))?;
+
+        let status = response.status().as_u16();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("连接服务返回无效响应: {}", e)))?;
+        let message = body["error"]["message"].as_str().unwrap_or_default().to_string();
+        match status {
+            400 => Err(AppError::Validation(message)),
+            503 => Err(AppError::ServiceUnavailable(message)),
+            status if status >= 400 => Err(AppError::ExternalService(format!(
+                "创建会话失败: 连接服务返回 {} {}",
+                status, message
+            ))),
+            _ => serde_json::from_value(body["data"].clone())
+                .map_err(|e| AppError::ExternalService(format!("连接服务返回无效结果: {}", e))),
+        }
+    }
+
+    /// 读取身份提供方的发现文档（按签发方缓存）
+    async fn discover(&self, config: &OidcConfig) -> AppResult<Discovery> {
+        if let Some((fetched_at, discovery)) = self.discovery.read().await.get(&config.issuer) {
+            if fetched_at.elapsed() < DISCOVERY_TTL {
+                return Ok(discovery.clone());
+            }
+        }
+
+        let url = format!("{}/.well-known/openid-configuration", config.issuer);
+        let response = self
+            .http_client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| AppError::ServiceUnavailable(format!("无法读取身份提供方发现文档: {}", e)))?;
+        let discovery: Discovery = response
+            .json()
+            .await
+            .map_err(|e| AppError::ExternalService(format!("身份提供方发现文档无效: {}", e)))?;
+        if discovery.issuer.trim_end_matches('/') != config.issuer {
+            return Err(AppError::ExternalService(format!(
+                "身份提供方发现文档的 issuer {} 与配置的 {} 不一致",
+                discovery.issuer, config.issuer
+            )));
+        }
+
+        self.discovery
+            .write()
+            .await
+            .insert(config.issuer.clone(), (Instant::now(), discovery.clone()));
+        Ok(discovery)
+    }
+}
+
+fn random_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// 校验 ID 令牌的签发方、受众、过期时间与 nonce，返回其声明
+fn validate_id_token(
+    id_token: &str,
+    issuer: &str,
+    client_id: &str,
+    nonce: &str,
+    now: i64,
+) -> AppResult<Map<String, Value>> {
+    let reject = |reason: &str| {
+        tracing::warn!(reason, "ID 令牌校验失败");
+        AppError::Unauthorized
+    };
+    let payload = id_token.split('.').nth(1).ok_or_else(|| reject("格式无效"))?;
+    let payload = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|_| reject("格式无效"))?;
+    let claims: Map<String, Value> = serde_json::from_slice(&payload).map_err(|_| reject("格式无效"))?;
+
+    if claims.get("iss").and_then(Value::as_str) != Some(issuer) {
+        return Err(reject("签发方不一致"));
+    }
+    let audiences: Vec<&str> = match claims.get("aud") {
+        Some(Value::String(aud)) => vec![aud.as_str()],
+        Some(Value::Array(auds)) => auds.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !audiences.contains(&client_id) {
+        return Err(reject("受众不包含本客户端"));
+    }
+    if audiences.len() > 1 && claims.get("azp").and_then(Value::as_str) != Some(client_id) {
+        return Err(reject("授权方不是本客户端"));
+    }
+    if claims.get("exp").and_then(Value::as_i64).is_none_or(|exp| exp <= now) {
+        return Err(reject("已过期"));
+    }
+    if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+        return Err(reject("nonce 不一致"));
+    }
+    Ok(claims)
+}
+
+/// 按配置从 ID 令牌声明中取主体标识、用户名与本地角色
+fn identity(config: &OidcConfig, claims: &Map<String, Value>) -> AppResult<SsoIdentity> {
+    let subject = claims
+        .get("sub")
+        .and_then(Value::as_str)
+        .filter(|sub| !sub.is_empty())
+        .ok_or_else(|| AppError::Validation("ID 令牌缺少 sub 声明".to_string()))?;
+    let username = claims
+        .get(&config.username_claim)
+        .and_then(Value::as_str)
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| AppError::Validation(format!("ID 令牌缺少用户名声明 {}", config.username_claim)))?;
+    let values: Vec<&str> = match claims.get(&config.roles_claim) {
+        Some(Value::String(value)) => vec![value.as_str()],
+        Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    Ok(SsoIdentity {
+        issuer: config.issuer.clone(),
+        subject: subject.to_string(),
+        username: username.to_string(),
+        roles: config.map_roles(values),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id_token(claims: Value) -> String {
+        format!("eyJhbGciOiJSUzI1NiJ9.{}.sig", URL_SAFE_NO_PAD.encode(claims.to_string()))
+    }
+
+    #[test]
+    fn validates_id_tokens_and_maps_claims() {
+        let config = OidcConfig {
+            issuer: "https://idp.example.com".to_string(),
+            client_id: "dbm".to_string(),
+            client_secret: None,
+            redirect_url: "https://dbm.example.com/api/auth/oidc/callback".to_string(),
+            scopes: "openid".to_string(),
+            username_claim: "preferred_username".to_string(),
+            roles_claim: "groups".to_string(),
+            role_mapping: HashMap::from([("dba-team".to_string(), vec!["admin".to_string()])]),
+            post_login_redirect: None,
+        };
+        let claims = serde_json::json!({
+            "iss": "https://idp.example.com",
+            "aud": ["dbm", "account"],
+            "azp": "dbm",
+            "exp": 2000,
+            "nonce": "n1",
+            "sub": "248289761001",
+            "preferred_username": "alice",
+            "groups": ["dba-team", "marketing"],
+        });
+        let valid = validate_id_token(&id_token(claims.clone()), &config.issuer, "dbm", "n1", 1000).unwrap();
+        assert_eq!(
+            identity(&config, &valid).unwrap(),
+            SsoIdentity {
+                issuer: "https://idp.example.com".to_string(),
+                subject: "248289761001".to_string(),
+                username: "alice".to_string(),
+                roles: vec!["admin".to_string()],
+            }
+        );
+
+        let check = |claims: &Value, nonce: &str, now: i64| validate_id_token(&id_token(claims.clone()), &config.issuer, "dbm", nonce, now);
+        assert!(check(&claims, "n2", 1000).is_err());
+        assert!(check(&claims, "n1", 2000).is_err());
+        let mut other_issuer = claims.clone();
+        other_issuer["iss"] = "https://evil.example.com".into();
+        assert!(check(&other_issuer, "n1", 1000).is_err());
+        let mut other_party = claims.clone();
+        other_party["azp"] = "account".into();
+        assert!(check(&other_party, "n1", 1000).is_err());
+
+        let mut nameless = valid.clone();
+        nameless.remove("preferred_username");
+        assert!(identity(&config, &nameless).is_err());
+        let mut subjectless = valid;
+        subjectless.remove("sub");
+        assert!(identity(&config, &subjectless).is_err());
+    }
+}
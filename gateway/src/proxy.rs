@@ -2,15 +2,27 @@
 
 use axum::{
     body::Body,
-    extract::{Request, State},
-    http::StatusCode,
+    extract::{ConnectInfo, Request, State},
+    http::{header::{HeaderName, CONTENT_LENGTH, HOST}, Extensions, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     routing::{any, get, post},
     Router,
 };
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use common::errors::AppError;
+use common::fallback;
 use common::middleware::request_id::REQUEST_ID_HEADER;
+use common::middleware::SendSigned;
+use common::response::RetryInfo;
 
+use crate::retry::RetryPolicy;
 use crate::state::AppState;
+use crate::websocket;
+
+/// 转发给上游服务的客户端地址头
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
 
 /// 创建代理路由
 pub fn router() -> Router<AppState> {
@@ -18,14 +30,61 @@ pub fn router() -> Router<AppState> {
         // 连接服务路由
         .route("/api/connections", get(proxy_to_connection_service).post(proxy_to_connection_service))
         .route("/api/connections/{*path}", any(proxy_to_connection_service))
+        .route("/api/schema/{*path}", any(proxy_to_connection_service))
+        .route("/api/scheduled-jobs", any(proxy_to_connection_service))
+        .route("/api/scheduled-jobs/{*path}", any(proxy_to_connection_service))
+        .route("/api/transfers", any(proxy_to_connection_service))
+        .route("/api/transfers/{*path}", any(proxy_to_connection_service))
+        .route("/api/snapshots", any(proxy_to_connection_service))
+        .route("/api/alerts", any(proxy_to_connection_service))
+        .route("/api/alerts/{*path}", any(proxy_to_connection_service))
+        .route("/api/snapshots/{*path}", any(proxy_to_connection_service))
+        .route("/api/workspaces", any(proxy_to_connection_service))
+        .route("/api/workspaces/{*path}", any(proxy_to_connection_service))
+        .route("/api/admin/metadata/{*path}", any(proxy_to_connection_service))
+        .route("/api/admin/keys", any(proxy_to_connection_service))
+        .route("/api/admin/keys/{*path}", any(proxy_to_connection_service))
+        .route("/api/admin/policies", any(proxy_to_connection_service))
+        .route("/api/admin/policies/{*path}", any(proxy_to_connection_service))
+        .route("/api/admin/policy-decisions", any(proxy_to_connection_service))
+        .route("/api/admin/usage", any(proxy_to_connection_service))
+        .route("/api/admin/migrations", get(proxy_to_connection_service))
+        .route("/api/admin/usage/{*path}", any(proxy_to_connection_service))
+        .route("/api/usage/me", get(proxy_to_connection_service))
+        .route("/api/sessions", any(proxy_to_connection_service))
+        .route("/api/auth/{*path}", any(proxy_to_connection_service))
+        .route("/api/admin/users", any(proxy_to_connection_service))
+        .route("/api/admin/users/{*path}", any(proxy_to_connection_service))
+        .route("/api/admin/login-lockouts", any(proxy_to_connection_service))
+        .route("/api/admin/login-lockouts/{*path}", any(proxy_to_connection_service))
+        .route("/api/admin/pools", any(proxy_to_connection_service))
+        .route("/api/admin/pools/{*path}", any(proxy_to_connection_service))
+        .route("/api/sessions/{*path}", any(proxy_to_connection_service))
+        .route("/api/admin/sessions", any(proxy_to_connection_service))
+        .route("/api/admin/sessions/{*path}", any(proxy_to_connection_service))
+        // 通用任务状态与取消（连接服务）；进度事件流来自连接服务或查询服务
+        .route("/api/jobs", get(proxy_to_connection_service))
+        .route("/api/jobs/{id}", get(proxy_to_connection_service))
+        .route("/api/jobs/{id}/cancel", post(proxy_to_connection_service))
+        .route("/api/jobs/{id}/events", get(proxy_job_events))
         // 查询服务路由
         .route("/api/query", post(proxy_to_query_service))
+        .route("/api/query/preview", post(proxy_to_query_service))
+        .route("/api/query/analyze", post(proxy_to_query_service))
+        .route("/api/query/format", post(proxy_to_query_service))
+        .route("/api/query/async", post(proxy_to_query_service))
+        .route("/api/query/fanout", post(proxy_to_query_service))
+        .route("/api/query/batch", post(proxy_to_query_service))
+        .route("/api/query/diff", post(proxy_to_query_service))
+        .route("/api/query/jobs/{id}", get(proxy_to_query_service))
         .route("/api/databases", post(proxy_to_query_service))
         // AI 服务路由
         .route("/api/ai/query", post(proxy_to_ai_service))
         .route("/api/ai/clarify", post(proxy_to_ai_service))
         .route("/api/ai/validate", post(proxy_to_ai_service))
         .route("/api/ai/{*path}", any(proxy_to_ai_service))
+        // 路由表中的其他前缀
+        .fallback(proxy_by_route_table)
 }
 
 /// 转发请求到连接服务
@@ -33,7 +92,8 @@ async fn proxy_to_connection_service(
     State(state): State<AppState>,
     req: Request<Body>,
 ) -> Response {
-    proxy_request(&state, &state.service_urls.connection_service, req).await
+    let base = state.registry.resolve_or("connection-service", &state.service_urls.connection_service).await;
+    proxy_request(&state, Some(&base), req).await
 }
 
 /// 转发请求到查询服务
@@ -41,7 +101,8 @@ async fn proxy_to_query_service(
     State(state): State<AppState>,
     req: Request<Body>,
 ) -> Response {
-    proxy_request(&state, &state.service_urls.query_service, req).await
+    let base = state.registry.resolve_or("query-service", &state.service_urls.query_service).await;
+    proxy_request(&state, Some(&base), req).await
 }
 
 /// 转发请求到 AI 服务
@@ -49,22 +110,152 @@ async fn proxy_to_ai_service(
     State(state): State<AppState>,
     req: Request<Body>,
 ) -> Response {
-    proxy_request(&state, &state.service_urls.ai_service, req).await
+    let base = state.registry.resolve_or("ai-service", &state.service_urls.ai_service).await;
+    proxy_request(&state, Some(&base), req).await
+}
+
+/// 进度事件流的最长转发时间，到期后断开，客户端（EventSource）自动重连并从最新状态继续
+const JOB_EVENTS_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// 转发任务进度事件流（Server-Sent Events）
+///
+/// 任务 ID 不包含所属服务：先向连接服务（备份、恢复、数据复制）订阅，任务不存在时再向
+/// 查询服务（异步查询）订阅，前一个服务不可达时同样继续尝试。事件流逐块转发，不缓冲
+/// 响应体，也不重试。
+async fn proxy_job_events(
+    State(state): State<AppState>,
+    mut req: Request<Body>,
+) -> Response {
+    let client = client_ip(req.headers(), req.extensions(), &state.config.get().trusted_proxies);
+    set_forwarded_for(req.headers_mut(), client);
+    let (conn_url, query_url) = tokio::join!(
+        state.registry.resolve_or("connection-service", &state.service_urls.connection_service),
+        state.registry.resolve_or("query-service", &state.service_urls.query_service),
+    );
+    let path = req.uri().path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+
+    let mut upstream_response = None;
+    let mut last_error = None;
+    for base in [conn_url, query_url] {
+        let target_url = format!("{}{}", base, path);
+        let mut proxy_req = state.http_client.get(&target_url).timeout(JOB_EVENTS_TIMEOUT);
+        for (name, value) in req.headers().iter().filter(|(name, _)| *name != HOST) {
+            proxy_req = proxy_req.header(name.clone(), value.clone());
+        }
+        let upstream = state.upstream_stats.start(&target_url);
+        match proxy_req.send_signed(&state.signer).await {
+            Ok(resp) => {
+                upstream.response(&resp);
+                let found = resp.status() != StatusCode::NOT_FOUND;
+                upstream_response = Some(resp);
+                if found {
+                    break;
+                }
+            }
+            Err(e) => {
+                upstream.error();
+                tracing::warn!(error = %e, target = %target_url, "订阅任务进度失败");
+                last_error = Some(e);
+            }
+        }
+    }
+    let Some(response) = upstream_response else {
+        let error = last_error.map(|e| e.to_string()).unwrap_or_default();
+        return (StatusCode::BAD_GATEWAY, format!("服务不可用: {}", error)).into_response();
+    };
+
+    let mut builder = Response::builder().status(response.status());
+    for (name, value) in response.headers().iter().filter(|(name, _)| *name != CONTENT_LENGTH) {
+        builder = builder.header(name, value);
+    }
+    let chunks = futures::stream::unfold(Some(response), |response| async move {
+        let mut response = response?;
+        match response.chunk().await {
+            Ok(Some(chunk)) => Some((Ok(chunk), Some(response))),
+            Ok(None) => None,
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+    builder
+        .body(Body::from_stream(chunks))
+        .unwrap_or_else(|_| (StatusCode::INTERNAL_SERVER_ERROR, "构建响应失败").into_response())
+}
+
+/// 客户端地址
+///
+/// 取连接的对端地址；对端是受信任的代理（`GATEWAY_TRUSTED_PROXIES`）时，从右向左读取
+/// `X-Forwarded-For`，取第一个不是受信任代理的地址。客户端自己填写的
+/// `X-Forwarded-For` 因此不会被当作客户端地址。请求没有连接信息时返回 `None`。
+pub(crate) fn client_ip(headers: &HeaderMap, extensions: &Extensions, trusted: &[IpAddr]) -> Option<IpAddr> {
+    let mut client = extensions.get::<ConnectInfo<SocketAddr>>()?.0.ip();
+    let hops: Vec<&str> = headers
+        .get_all(&X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .collect();
+    for hop in hops.into_iter().rev() {
+        if !trusted.contains(&client) {
+            break;
+        }
+        match hop.trim().parse() {
+            Ok(ip) => client = ip,
+            Err(_) => break,
+        }
+    }
+    Some(client)
+}
+
+/// 用网关确定的客户端地址替换请求中的 `X-Forwarded-For`，上游服务只信任这一个地址
+fn set_forwarded_for(headers: &mut HeaderMap, client: Option<IpAddr>) {
+    headers.remove(&X_FORWARDED_FOR);
+    if let Some(client) = client {
+        if let Ok(value) = HeaderValue::from_str(&client.to_string()) {
+            headers.insert(X_FORWARDED_FOR, value);
+        }
+    }
+}
+
+/// 按路由表转发内置路由以外的请求
+async fn proxy_by_route_table(
+    State(state): State<AppState>,
+    req: Request<Body>,
+) -> Response {
+    proxy_request(&state, None, req).await
 }
 
 /// 转发请求到目标服务
+///
+/// 路由表中有匹配的前缀时按路由表转发，否则转发到 `default_base`；
+/// 两者都没有时返回统一结构的 404（`NOT_FOUND`）。WebSocket 升级请求交给
+/// `websocket` 模块转发。
 async fn proxy_request(
     state: &AppState,
-    target_base: &str,
+    default_base: Option<&str>,
     req: Request<Body>,
 ) -> Response {
-    let (parts, body) = req.into_parts();
-    
+    let (mut parts, body) = req.into_parts();
+    let client = client_ip(&parts.headers, &parts.extensions, &state.config.get().trusted_proxies);
+    set_forwarded_for(&mut parts.headers, client);
+
     // 构建目标 URL
     let path = parts.uri.path_and_query()
         .map(|pq| pq.as_str())
         .unwrap_or("/");
-    let target_url = format!("{}{}", target_base, path);
+    let (target_url, timeout, retries) = match state.routing.find(parts.uri.path()).await {
+        Some(route) => (
+            route.upstream_url(path),
+            route.timeout_secs.map(Duration::from_secs),
+            route.retries,
+        ),
+        None => match default_base {
+            Some(base) => (format!("{}{}", base, path), None, None),
+            None => return fallback::not_found("gateway", &Request::from_parts(parts, body)),
+        },
+    };
+    if websocket::is_upgrade(&parts.headers) {
+        return state.websocket.proxy(&target_url, Request::from_parts(parts, body)).await;
+    }
 
     // 从原始请求获取请求 ID
     let request_id = parts.headers
@@ -72,48 +263,90 @@ async fn proxy_request(
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
-    // 将请求体转换为字节
-    let body_bytes = match axum::body::to_bytes(body, usize::MAX).await {
+    // 将请求体转换为字节，超过上限时返回 413
+    let limit = state.config.get().max_body_bytes;
+    let declared_length = parts.headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared_length.is_some_and(|length| length > limit) {
+        return payload_too_large(limit);
+    }
+    let body_bytes = match axum::body::to_bytes(body, limit).await {
         Ok(bytes) => bytes,
+        Err(e) if is_length_limit_error(&e) => return payload_too_large(limit),
         Err(e) => {
             tracing::error!(error = %e, "读取请求体失败");
-            return (StatusCode::BAD_REQUEST, "读取请求体失败").into_response();
+            return AppError::InvalidInput(format!("读取请求体失败: {}", e)).into_response();
         }
     };
 
-    // 构建代理请求
-    let mut proxy_req = state.http_client
-        .request(parts.method.clone(), &target_url);
+    // 发送请求，幂等请求在连接失败或上游 502/503 时按重试策略重试
+    let max_retries = if RetryPolicy::is_retryable_method(&parts.method) {
+        retries.unwrap_or(state.retry.max_retries)
+    } else {
+        0
+    };
+    let mut retry = RetryInfo { attempts: 0, backoff_ms: 0, reasons: Vec::new() };
+    let response = loop {
+        retry.attempts += 1;
 
-    // 复制请求头（排除 host）
-    for (name, value) in parts.headers.iter() {
-        if name != "host" {
-            proxy_req = proxy_req.header(name.clone(), value.clone());
+        // 构建代理请求
+        let mut proxy_req = state.http_client
+            .request(parts.method.clone(), &target_url);
+
+        // 复制请求头（排除 host）
+        for (name, value) in parts.headers.iter() {
+            if name != "host" {
+                proxy_req = proxy_req.header(name.clone(), value.clone());
+            }
         }
-    }
 
-    // 添加请求 ID 头
-    if !request_id.is_empty() {
-        proxy_req = proxy_req.header(REQUEST_ID_HEADER.as_str(), request_id);
-    }
+        // 添加请求 ID 头
+        if !request_id.is_empty() {
+            proxy_req = proxy_req.header(REQUEST_ID_HEADER.as_str(), request_id);
+        }
+        if let Some(timeout) = timeout {
+            proxy_req = proxy_req.timeout(timeout);
+        }
 
-    // 发送请求
-    let response = match proxy_req.body(body_bytes.to_vec()).send().await {
-        Ok(resp) => resp,
-        Err(e) => {
-            tracing::error!(error = %e, target = %target_url, "代理请求失败");
-            return (
-                StatusCode::BAD_GATEWAY,
-                format!("服务不可用: {}", e),
-            ).into_response();
+        let can_retry = retry.attempts <= max_retries;
+        let upstream = state.upstream_stats.start(&target_url);
+        let result = proxy_req.body(body_bytes.clone()).send_signed(&state.signer).await;
+        match &result {
+            Ok(resp) => upstream.response(resp),
+            Err(_) => upstream.error(),
         }
+        let reason = match result {
+            Ok(resp) if can_retry && RetryPolicy::is_retryable_status(resp.status()) => {
+                resp.status().as_u16().to_string()
+            }
+            Ok(resp) => break resp,
+            Err(e) if can_retry && e.is_connect() => {
+                tracing::warn!(error = %e, target = %target_url, "连接上游失败");
+                "connect".to_string()
+            }
+            Err(e) => {
+                tracing::error!(error = %e, target = %target_url, attempts = retry.attempts, "代理请求失败");
+                return (
+                    StatusCode::BAD_GATEWAY,
+                    format!("服务不可用: {}", e),
+                ).into_response();
+            }
+        };
+
+        let delay = state.retry.backoff(retry.attempts);
+        tracing::warn!(target = %target_url, attempt = retry.attempts, reason = %reason, delay_ms = delay.as_millis() as u64, "重试上游请求");
+        retry.reasons.push(reason);
+        retry.backoff_ms += delay.as_millis() as u64;
+        tokio::time::sleep(delay).await;
     };
 
     // 转换响应
     let status = response.status();
-    let headers = response.headers().clone();
+    let mut headers = response.headers().clone();
     
-    let body_bytes = match response.bytes().await {
+    let mut body_bytes = match response.bytes().await {
         Ok(bytes) => bytes,
         Err(e) => {
             tracing::error!(error = %e, "读取响应体失败");
@@ -121,6 +354,14 @@ async fn proxy_request(
         }
     };
 
+    // 发生过重试时在 JSON 响应的 meta 中记录重试信息
+    if retry.attempts > 1 {
+        if let Some(bytes) = with_retry_meta(&body_bytes, &retry) {
+            body_bytes = bytes.into();
+            headers.remove(CONTENT_LENGTH);
+        }
+    }
+
     // 构建响应
     let mut builder = Response::builder().status(status);
     
@@ -133,3 +374,103 @@ async fn proxy_request(
         .unwrap_or_else(|_| (StatusCode::INTERNAL_SERVER_ERROR, "构建响应失败").into_response())
 }
 
+fn payload_too_large(limit: usize) -> Response {
+    AppError::PayloadTooLarge(format!("请求体超过 {} 字节上限", limit)).into_response()
+}
+
+fn is_length_limit_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(error);
+    while let Some(e) = source {
+        if e.is::<http_body_util::LengthLimitError>() {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// 把重试信息写入响应体的 `meta.retry`，响应体不是带 `meta` 的 JSON 对象时返回 `None`
+fn with_retry_meta(body: &[u8], retry: &RetryInfo) -> Option<Vec<u8>> {
+    let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let meta = value.get_mut("meta")?.as_object_mut()?;
+    meta.insert("retry".to_string(), serde_json::to_value(retry).ok()?);
+    serde_json::to_vec(&value).ok()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use axum::http::Uri;
+    use common::config::ConfigLoader;
+    use tokio::net::TcpListener;
+    use tower::ServiceExt;
+
+    /// 把收到的路径原样返回的上游
+    async fn path_echo_upstream() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().fallback(|uri: Uri| async move { uri.path().to_string() });
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn forwards_admin_routes_to_connection_service() {
+        let config = ConfigLoader::new("gateway").args(Vec::<String>::new()).load_shared().unwrap();
+        let mut state = AppState::new(Arc::new(config));
+        state.service_urls.connection_service = path_echo_upstream().await;
+        let app = router().with_state(state);
+
+        for (method, path) in [("GET", "/api/admin/migrations"), ("GET", "/api/admin/pools"), ("DELETE", "/api/admin/pools/c1")] {
+            let req = Request::builder().method(method).uri(path).body(Body::empty()).unwrap();
+            let response = app.clone().oneshot(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{} {}", method, path);
+            let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+            assert_eq!(body, path.as_bytes());
+        }
+    }
+
+    #[tokio::test]
+    async fn replaces_client_supplied_forwarded_for() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let upstream = Router::new().fallback(|headers: HeaderMap| async move {
+            let values: Vec<&str> = headers.get_all(&X_FORWARDED_FOR).iter().filter_map(|v| v.to_str().ok()).collect();
+            values.join(";")
+        });
+        tokio::spawn(async move { axum::serve(listener, upstream).await.unwrap() });
+        let config = ConfigLoader::new("gateway").args(Vec::<String>::new()).load_shared().unwrap();
+        let mut state = AppState::new(Arc::new(config));
+        state.service_urls.connection_service = format!("http://{}", addr);
+        let app = router().with_state(state);
+
+        let mut req = Request::builder()
+            .method("POST")
+            .uri("/api/auth/login")
+            .header(&X_FORWARDED_FOR, "203.0.113.7")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut().insert(ConnectInfo(SocketAddr::from(([198, 51, 100, 2], 50000))));
+        let response = app.oneshot(req).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), 1024).await.unwrap();
+        assert_eq!(body, "198.51.100.2".as_bytes());
+    }
+
+    #[test]
+    fn client_ip_follows_only_trusted_proxies() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::new(proxy, 443)));
+        let mut headers = HeaderMap::new();
+        headers.insert(X_FORWARDED_FOR, "203.0.113.7, 198.51.100.2, 10.0.0.1".parse().unwrap());
+
+        // 对端不受信任时忽略 X-Forwarded-For
+        assert_eq!(client_ip(&headers, &extensions, &[]), Some(proxy));
+        // 只跳过受信任的代理，客户端伪造的更左侧地址不被采用
+        assert_eq!(client_ip(&headers, &extensions, &[proxy]), Some("198.51.100.2".parse().unwrap()));
+        assert_eq!(client_ip(&headers, &Extensions::new(), &[proxy]), None);
+    }
+}
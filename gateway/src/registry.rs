@@ -0,0 +1,205 @@
+//! 服务注册表模块
+//!
+//! 下游服务启动后向网关注册（名称、地址、健康检查路径）并定期发送心跳
+//! （见 `common::discovery`），网关在内存中保存各服务的实例。超过
+//! `GATEWAY_REGISTRY_TTL_SECS` 未收到心跳的实例视为下线，不再参与转发与健康
+//! 检查；同一服务有多个实例时轮流转发。服务没有存活实例时回退到环境变量中
+//! 配置的静态地址（`ServiceUrls`），未启用注册的部署行为不变。
+//!
+//! 注册表不持久化，网关重启后由下一次心跳重新填充。
+//!
+//! 配置：
+//! - `GATEWAY_REGISTRY_TTL_SECS` - 实例在最后一次心跳后保持存活的时间（默认 30）
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+
+use common::discovery::ServiceRegistration;
+
+const DEFAULT_TTL_SECS: u64 = 30;
+
+/// 注册的服务实例
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ServiceInstance {
+    pub name: String,
+    pub url: String,
+    pub health_path: String,
+    /// 首次注册时间
+    pub registered_at: DateTime<Utc>,
+    /// 最后一次心跳时间
+    pub last_heartbeat: DateTime<Utc>,
+    #[serde(skip)]
+    seen: Option<Instant>,
+}
+
+impl ServiceInstance {
+    fn alive(&self, ttl: Duration) -> bool {
+        self.seen.is_some_and(|seen| seen.elapsed() < ttl)
+    }
+}
+
+/// 服务注册表
+pub struct ServiceRegistry {
+    ttl: Duration,
+    /// 按服务名保存的实例
+    instances: RwLock<HashMap<String, Vec<ServiceInstance>>>,
+    /// 轮流选择实例的计数
+    next: AtomicUsize,
+}
+
+impl ServiceRegistry {
+    /// 从环境变量读取实例存活时间
+    pub fn from_env() -> Self {
+        let ttl = std::env::var("GATEWAY_REGISTRY_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TTL_SECS);
+        Self::new(Duration::from_secs(ttl.max(1)))
+    }
+
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            instances: RwLock::new(HashMap::new()),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// 注册实例或刷新其心跳，返回是否为新实例
+    pub async fn register(&self, mut registration: ServiceRegistration) -> bool {
+        let now = Utc::now();
+        let url = registration.url.trim_end_matches('/').to_string();
+        if !registration.health_path.starts_with('/') {
+            registration.health_path.insert(0, '/');
+        }
+        let mut instances = self.instances.write().await;
+        self.prune(&mut instances);
+
+        let list = instances.entry(registration.name.clone()).or_default();
+        if let Some(instance) = list.iter_mut().find(|i| i.url == url) {
+            instance.health_path = registration.health_path;
+            instance.last_heartbeat = now;
+            instance.seen = Some(Instant::now());
+            return false;
+        }
+        tracing::info!(service = %registration.name, url = %url, "服务实例已注册");
+        list.push(ServiceInstance {
+            name: registration.name,
+            url,
+            health_path: registration.health_path,
+            registered_at: now,
+            last_heartbeat: now,
+            seen: Some(Instant::now()),
+        });
+        true
+    }
+
+    /// 注销实例，返回实例是否存在
+    pub async fn deregister(&self, name: &str, url: &str) -> bool {
+        let url = url.trim_end_matches('/');
+        let mut instances = self.instances.write().await;
+        let Some(list) = instances.get_mut(name) else {
+            return false;
+        };
+        let before = list.len();
+        list.retain(|i| i.url != url);
+        let removed = list.len() < before;
+        if list.is_empty() {
+            instances.remove(name);
+        }
+        if removed {
+            tracing::info!(service = %name, url = %url, "服务实例已注销");
+        }
+        removed
+    }
+
+    /// 选择服务的一个存活实例地址
+    pub async fn resolve(&self, name: &str) -> Option<String> {
+        let instances = self.instances.read().await;
+        let alive: Vec<&ServiceInstance> = instances
+            .get(name)?
+            .iter()
+            .filter(|i| i.alive(self.ttl))
+            .collect();
+        if alive.is_empty() {
+            return None;
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % alive.len();
+        Some(alive[index].url.clone())
+    }
+
+    /// 选择服务的存活实例，没有时使用静态配置的地址
+    pub async fn resolve_or(&self, name: &str, fallback: &str) -> String {
+        self.resolve(name).await.unwrap_or_else(|| fallback.to_string())
+    }
+
+    /// 全部存活实例，按服务名与地址排序
+    pub async fn list(&self) -> Vec<ServiceInstance> {
+        let mut list: Vec<ServiceInstance> = self
+            .instances
+            .read()
+            .await
+            .values()
+            .flatten()
+            .filter(|i| i.alive(self.ttl))
+            .cloned()
+            .collect();
+        list.sort_by(|a, b| (&a.name, &a.url).cmp(&(&b.name, &b.url)));
+        list
+    }
+
+    /// 移除下线的实例
+    fn prune(&self, instances: &mut HashMap<String, Vec<ServiceInstance>>) {
+        for (name, list) in instances.iter_mut() {
+            list.retain(|i| {
+                let alive = i.alive(self.ttl);
+                if !alive {
+                    tracing::warn!(service = %name, url = %i.url, "服务实例心跳超时，已移除");
+                }
+                alive
+            });
+        }
+        instances.retain(|_, list| !list.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(name: &str, url: &str) -> ServiceRegistration {
+        ServiceRegistration {
+            name: name.to_string(),
+            url: url.to_string(),
+            health_path: "/api/health".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_live_instances_in_turn() {
+        let registry = ServiceRegistry::new(Duration::from_secs(30));
+        assert!(registry.register(registration("query-service", "http://q1:8082/")).await);
+        assert!(registry.register(registration("query-service", "http://q2:8082")).await);
+        assert!(!registry.register(registration("query-service", "http://q1:8082")).await);
+
+        let first = registry.resolve("query-service").await.unwrap();
+        let second = registry.resolve("query-service").await.unwrap();
+        assert_ne!(first, second);
+        assert!(registry.resolve("ai-service").await.is_none());
+
+        assert!(registry.deregister("query-service", "http://q1:8082").await);
+        assert_eq!(registry.resolve("query-service").await.as_deref(), Some("http://q2:8082"));
+
+        // 心跳超时的实例不再参与转发
+        registry.instances.write().await.get_mut("query-service").unwrap()[0].seen =
+            Some(Instant::now() - Duration::from_secs(60));
+        assert!(registry.resolve("query-service").await.is_none());
+        assert!(registry.list().await.is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+//! 重试策略模块
+//!
+//! 网关只重试幂等请求（GET / HEAD），触发条件为连接上游失败或上游返回
+//! 502 / 503；POST、PUT、PATCH、DELETE 等请求无论失败原因都不重试，避免
+//! 重复执行写操作。两次尝试之间按指数退避等待，并加入随机抖动，避免大量
+//! 请求在上游恢复时同时重试。发生过重试的 JSON 响应在 `meta.retry` 中
+//! 记录尝试次数、总等待时间与每次重试的原因。
+//!
+//! 配置：
+//! - `GATEWAY_RETRY_MAX` - 最大重试次数（默认 2，路由表中的 `retries` 可按路由覆盖）
+//! - `GATEWAY_RETRY_BASE_MS` - 首次重试的基础等待时间（默认 100 毫秒）
+//! - `GATEWAY_RETRY_MAX_DELAY_MS` - 单次等待时间上限（默认 2000 毫秒）
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+
+const DEFAULT_MAX_RETRIES: u32 = 2;
+const DEFAULT_BASE_DELAY_MS: u64 = 100;
+const DEFAULT_MAX_DELAY_MS: u64 = 2000;
+
+/// 网关重试策略
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// 从环境变量读取重试策略
+    pub fn from_env() -> Self {
+        let env_u64 = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            max_retries: env_u64("GATEWAY_RETRY_MAX", DEFAULT_MAX_RETRIES.into()) as u32,
+            base_delay: Duration::from_millis(env_u64("GATEWAY_RETRY_BASE_MS", DEFAULT_BASE_DELAY_MS)),
+            max_delay: Duration::from_millis(env_u64("GATEWAY_RETRY_MAX_DELAY_MS", DEFAULT_MAX_DELAY_MS)),
+        }
+    }
+
+    /// 请求方法是否允许重试
+    pub fn is_retryable_method(method: &Method) -> bool {
+        matches!(*method, Method::GET | Method::HEAD)
+    }
+
+    /// 上游响应状态是否值得重试
+    pub fn is_retryable_status(status: StatusCode) -> bool {
+        matches!(status, StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE)
+    }
+
+    /// 第 `attempt` 次重试（从 1 开始）前的等待时间
+    ///
+    /// 指数退避 `base * 2^(attempt-1)`，不超过上限，实际等待在其一半到全额之间随机取值。
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        let half = exponential / 2;
+        let jitter_range = (exponential - half).as_millis() as u64;
+        let jitter = if jitter_range == 0 { 0 } else { random_u64() % (jitter_range + 1) };
+        half + Duration::from_millis(jitter)
+    }
+}
+
+/// 不引入随机数依赖的随机值（每个 `RandomState` 使用随机种子）
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_only_idempotent_requests_with_bounded_backoff() {
+        assert!(RetryPolicy::is_retryable_method(&Method::GET));
+        assert!(RetryPolicy::is_retryable_method(&Method::HEAD));
+        assert!(!RetryPolicy::is_retryable_method(&Method::POST));
+        assert!(!RetryPolicy::is_retryable_method(&Method::PATCH));
+        assert!(RetryPolicy::is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!RetryPolicy::is_retryable_status(StatusCode::INTERNAL_SERVER_ERROR));
+
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+        for _ in 0..50 {
+            let first = policy.backoff(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let second = policy.backoff(2);
+            assert!(second >= Duration::from_millis(100) && second <= Duration::from_millis(200));
+            // 超过上限后固定在上限范围内
+            let capped = policy.backoff(10);
+            assert!(capped >= Duration::from_millis(150) && capped <= Duration::from_millis(300));
+        }
+    }
+}
@@ -1,14 +1,36 @@
 //! 路由模块
 
+use std::sync::Arc;
+
 use axum::{
-    routing::get,
+    middleware,
+    routing::{get, post},
     Router,
 };
+use common::middleware::{signature_middleware, SignatureVerifier};
+
 use crate::handlers;
 use crate::state::AppState;
 
 pub fn router() -> Router<AppState> {
     Router::new()
+        .route("/healthz", get(handlers::healthz))
+        .route("/readyz", get(handlers::readyz))
         .route("/api/health", get(handlers::health_check))
         .route("/api/health/aggregated", get(handlers::aggregated_health))
+        .route("/api/registry", get(handlers::list_services))
+        .route("/api/admin/config/reload", post(handlers::reload_config))
+        .route("/api/admin/upstreams", get(handlers::upstream_stats))
+        .route("/api/auth/oidc/login", get(handlers::oidc_login))
+        .route("/api/auth/oidc/callback", get(handlers::oidc_callback))
+}
+
+/// 服务调用的内部路由，校验内部签名
+pub fn internal_router(signatures: Arc<SignatureVerifier>) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/internal/registry",
+            post(handlers::register_service).delete(handlers::deregister_service),
+        )
+        .route_layer(middleware::from_fn_with_state(signatures, signature_middleware))
 }
@@ -0,0 +1,107 @@
+//! 路由表模块
+//!
+//! 路由表文件（TOML）按路径前缀把请求转发到上游服务，可配置是否去掉前缀、
+//! 上游超时与最大重试次数，新增服务无需修改代码。表中的前缀优先于内置
+//! 路由（可用于覆盖内置服务地址），按最长前缀匹配。网关定期检查文件修改
+//! 时间并热加载；新文件解析失败时保留当前路由表。配置重新加载（改变
+//! `GATEWAY_ROUTES_FILE`）后切换到新配置的路由表。
+//!
+//! 配置：
+//! - `GATEWAY_ROUTES_FILE` - 路由表文件路径（未设置时只使用内置路由）
+//! - `GATEWAY_ROUTES_RELOAD_SECS` - 检查文件变更的间隔（默认 5）
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use common::config::{load_routes, AppConfig, RouteConfig};
+use tokio::sync::{watch, RwLock};
+
+const DEFAULT_RELOAD_SECS: u64 = 5;
+
+/// 可热加载的路由表
+pub struct RoutingTable {
+    file: RwLock<Option<String>>,
+    reload_interval: Duration,
+    routes: RwLock<Arc<Vec<RouteConfig>>>,
+    modified: RwLock<Option<SystemTime>>,
+}
+
+impl RoutingTable {
+    /// 以启动时加载的路由创建路由表
+    pub fn new(config: &AppConfig) -> Self {
+        let reload_secs = std::env::var("GATEWAY_ROUTES_RELOAD_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_RELOAD_SECS);
+        Self {
+            modified: RwLock::new(config.routes_file.as_deref().and_then(modified_time)),
+            file: RwLock::new(config.routes_file.clone()),
+            reload_interval: Duration::from_secs(reload_secs.max(1)),
+            routes: RwLock::new(Arc::new(config.routes.clone())),
+        }
+    }
+
+    /// 启动文件变更检查任务，并在配置重新加载后切换路由表
+    pub fn spawn(self: &Arc<Self>, mut changes: watch::Receiver<Arc<AppConfig>>) {
+        let table = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(table.reload_interval);
+            let mut watching = true;
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => table.reload_if_changed().await,
+                    changed = changes.changed(), if watching => match changed {
+                        Ok(()) => {
+                            let config = changes.borrow_and_update().clone();
+                            table.apply(&config).await;
+                        }
+                        Err(_) => watching = false,
+                    },
+                }
+            }
+        });
+    }
+
+    /// 查找匹配请求路径的路由
+    pub async fn find(&self, path: &str) -> Option<RouteConfig> {
+        // 路由已按前缀长度降序排列，第一个匹配即最长前缀
+        self.routes.read().await.iter().find(|r| r.matches(path)).cloned()
+    }
+
+    /// 使用重新加载的配置中的路由表（已在加载配置时解析）
+    async fn apply(&self, config: &AppConfig) {
+        let mut file = self.file.write().await;
+        let mut routes = self.routes.write().await;
+        if *file == config.routes_file && **routes == config.routes {
+            return;
+        }
+        *file = config.routes_file.clone();
+        *self.modified.write().await = config.routes_file.as_deref().and_then(modified_time);
+        *routes = Arc::new(config.routes.clone());
+        tracing::info!(file = ?config.routes_file, routes = routes.len(), "配置重新加载，已切换路由表");
+    }
+
+    async fn reload_if_changed(&self) {
+        let Some(file) = self.file.read().await.clone() else {
+            return;
+        };
+        let file = file.as_str();
+        let modified = modified_time(file);
+        if modified.is_none() || modified == *self.modified.read().await {
+            return;
+        }
+        *self.modified.write().await = modified;
+
+        match load_routes(file) {
+            Ok(routes) => {
+                tracing::info!(file = %file, routes = routes.len(), "路由表已重新加载");
+                *self.routes.write().await = Arc::new(routes);
+            }
+            Err(e) => tracing::warn!(file = %file, error = %e, "路由表解析失败，保留当前路由"),
+        }
+    }
+}
+
+fn modified_time(file: &str) -> Option<SystemTime> {
+    std::fs::metadata(file).and_then(|m| m.modified()).ok()
+}
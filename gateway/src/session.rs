@@ -0,0 +1,122 @@
+//! 会话令牌认证模块
+//!
+//! 配置 `JWT_SECRET` 后，网关接受 `Authorization: Bearer <token>` 形式的会话令牌
+//! （见 `common::jwt`）：先在本地校验签名与过期时间，再通过连接服务的内部端点
+//! 确认令牌所属的会话仍然有效（未吊销、未过期）。会话检查结果短时缓存，
+//! 吊销在缓存过期后生效。认证通过的请求以 `X-Principal: user:<id>` 与
+//! `X-Session-Id` 转发。
+//!
+//! 配置：
+//! - `JWT_SECRET` - 会话令牌签名密钥（未设置时不接受会话令牌）
+//! - `GATEWAY_SESSION_CACHE_SECS` - 会话检查结果缓存时间（默认 5）
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use common::errors::{AppError, AppResult};
+use common::jwt::{JwtKeys, TokenClaims};
+use common::middleware::{RequestSigner, SendSigned};
+use tokio::sync::RwLock;
+
+const DEFAULT_CACHE_SECS: u64 = 5;
+/// 缓存条目上限，超过时先清理过期条目
+const MAX_CACHE_ENTRIES: usize = 10_000;
+
+/// 会话令牌验证器
+pub struct SessionVerifier {
+    keys: Option<JwtKeys>,
+    connection_service_url: String,
+    http_client: reqwest::Client,
+    signer: RequestSigner,
+    cache_ttl: Duration,
+    /// 会话 ID → (缓存时间, 会话所属用户；`None` 表示会话无效)
+    cache: RwLock<HashMap<String, (Instant, Option<String>)>>,
+}
+
+impl SessionVerifier {
+    /// 创建验证器，从环境变量读取配置
+    pub fn new(connection_service_url: String, http_client: reqwest::Client, signer: RequestSigner) -> Self {
+        let cache_secs = std::env::var("GATEWAY_SESSION_CACHE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_SECS);
+        Self {
+            keys: JwtKeys::from_env(),
+            connection_service_url,
+            http_client,
+            signer,
+            cache_ttl: Duration::from_secs(cache_secs),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 是否接受会话令牌
+    pub fn enabled(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    /// 验证会话令牌，返回令牌声明
+    ///
+    /// # Errors
+    /// 令牌无效、已过期或会话已吊销时返回 `AppError::Unauthorized`；连接服务不可用时返回 `AppError::ServiceUnavailable`。
+    pub async fn verify(&self, token: &str) -> AppResult<TokenClaims> {
+        let claims = self.keys.as_ref().ok_or(AppError::Unauthorized)?.decode(token)?;
+        let user_id = self.session_user(&claims.sid).await?;
+        // 会话须属于令牌中的用户
+        if user_id.as_deref() != Some(claims.sub.as_str()) {
+            return Err(AppError::Unauthorized);
+        }
+        Ok(claims)
+    }
+
+    /// 有效会话所属的用户，会话无效时返回 `None`
+    async fn session_user(&self, session_id: &str) -> AppResult<Option<String>> {
+        if let Some((cached_at, user_id)) = self.cache.read().await.get(session_id) {
+            if cached_at.elapsed() < self.cache_ttl {
+                return Ok(user_id.clone());
+            }
+        }
+
+        let user_id = self.fetch(session_id).await?;
+        let mut cache = self.cache.write().await;
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            let ttl = self.cache_ttl;
+            cache.retain(|_, (cached_at, _)| cached_at.elapsed() < ttl);
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.clear();
+            }
+        }
+        cache.insert(session_id.to_string(), (Instant::now(), user_id.clone()));
+        Ok(user_id)
+    }
+
+    /// 调用连接服务检查会话；会话无效时返回 `Ok(None)`
+    async fn fetch(&self, session_id: &str) -> AppResult<Option<String>> {
+        let url = format!("{}/internal/sessions/verify", self.connection_service_url);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(&serde_json::json!({ "session_id": session_id }))
+            .send_signed(&self.signer)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("无法验证会话: {}", e)))?;
+
+        match response.status().as_u16() {
+            401 => Ok(None),
+            status if status >= 400 => Err(AppError::ServiceUnavailable(format!(
+                "无法验证会话: 连接服务返回 {}",
+                status
+            ))),
+            _ => {
+                let body: serde_json::Value = response
+                    .json()
+                    .await
+                    .map_err(|e| AppError::ExternalService(format!("连接服务返回无效响应: {}", e)))?;
+                body["data"]["user_id"]
+                    .as_str()
+                    .map(|user_id| Some(user_id.to_string()))
+                    .ok_or_else(|| AppError::ExternalService("连接服务返回无效结果: 缺少 user_id".to_string()))
+            }
+        }
+    }
+}
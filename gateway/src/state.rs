@@ -1,27 +1,103 @@
 //! Application state for gateway service.
 
-use common::config::{AppConfig, ServiceUrls};
+use std::sync::Arc;
+
+use common::config::{ServiceUrls, SharedConfig};
+use common::middleware::{RequestSigner, SignatureVerifier};
+
+use crate::auth::ApiKeyVerifier;
+use crate::authz::PolicyClient;
+use crate::health::HealthChecker;
+use crate::oidc::OidcClient;
+use crate::registry::ServiceRegistry;
+use crate::retry::RetryPolicy;
+use crate::routing::RoutingTable;
+use crate::session::SessionVerifier;
+use crate::upstream::{UpstreamSettings, UpstreamStats};
+use crate::websocket::WebSocketProxy;
 
 /// Application state shared across handlers.
 #[derive(Clone)]
 pub struct AppState {
-    pub config: AppConfig,
+    pub config: Arc<SharedConfig>,
     pub service_urls: ServiceUrls,
+    /// 访问上游服务的客户端（连接池、HTTP/2 等按 `UpstreamSettings` 设置）
     pub http_client: reqwest::Client,
+    pub upstream_settings: UpstreamSettings,
+    pub upstream_stats: Arc<UpstreamStats>,
+    /// 转发 WebSocket 升级请求
+    pub websocket: WebSocketProxy,
+    pub signer: RequestSigner,
+    pub api_keys: Arc<ApiKeyVerifier>,
+    pub sessions: Arc<SessionVerifier>,
+    pub oidc: Arc<OidcClient>,
+    pub policies: Arc<PolicyClient>,
+    pub routing: Arc<RoutingTable>,
+    pub retry: RetryPolicy,
+    pub health: Arc<HealthChecker>,
+    pub registry: Arc<ServiceRegistry>,
+    /// 校验服务注册请求的签名
+    pub signatures: Arc<SignatureVerifier>,
 }
 
 impl AppState {
     /// Creates a new application state.
-    pub fn new(config: AppConfig) -> Self {
-        let http_client = reqwest::Client::builder()
+    pub fn new(config: Arc<SharedConfig>) -> Self {
+        let upstream_settings = UpstreamSettings::from_env();
+        let upstream_stats = Arc::new(UpstreamStats::default());
+        let http_client = upstream_settings
+            .build_client(upstream_stats.clone())
+            .expect("Failed to create HTTP client");
+        // 单点登录还要访问外部身份提供方，不能沿用上游客户端的 HTTP/2 先验知识
+        let oidc_client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(30))
             .build()
             .expect("Failed to create HTTP client");
 
+        let service_urls = ServiceUrls::load();
+        let signer = RequestSigner::from_env(config.get().service_name.clone());
+        let websocket = WebSocketProxy::new(&upstream_settings, upstream_stats.clone(), signer.clone());
+        let api_keys = Arc::new(ApiKeyVerifier::new(
+            service_urls.connection_service.clone(),
+            http_client.clone(),
+            signer.clone(),
+        ));
+        let sessions = Arc::new(SessionVerifier::new(
+            service_urls.connection_service.clone(),
+            http_client.clone(),
+            signer.clone(),
+        ));
+        let oidc = Arc::new(OidcClient::new(
+            service_urls.connection_service.clone(),
+            oidc_client,
+            signer.clone(),
+        ));
+        let policies = Arc::new(PolicyClient::new(
+            service_urls.connection_service.clone(),
+            http_client.clone(),
+            signer.clone(),
+        ));
+
+        let routing = Arc::new(RoutingTable::new(&config.get()));
+        routing.spawn(config.subscribe());
+
         Self {
             config,
-            service_urls: ServiceUrls::load(),
+            service_urls,
             http_client,
+            upstream_settings,
+            upstream_stats,
+            websocket,
+            signer,
+            api_keys,
+            sessions,
+            oidc,
+            policies,
+            routing,
+            retry: RetryPolicy::from_env(),
+            health: Arc::new(HealthChecker::from_env()),
+            registry: Arc::new(ServiceRegistry::from_env()),
+            signatures: Arc::new(SignatureVerifier::from_env()),
         }
     }
 }
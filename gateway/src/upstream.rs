@@ -0,0 +1,359 @@
+//! 上游连接模块
+//!
+//! 网关转发请求使用的 HTTP 客户端：按主机保留空闲连接复用，关闭 Nagle 算法
+//! （TCP_NODELAY）降低小请求的延迟，并开启 TCP keep-alive 及时发现断开的连接。
+//! 启用 HTTP/2 时以先验知识（prior knowledge）直接使用 HTTP/2 连接上游，同一
+//! 连接上并发多个请求，高负载下不再为每个并发请求新建连接；上游须支持 HTTP/2
+//! （本项目的服务均支持）。
+//!
+//! 网关同时统计上游连接与请求：新建连接数与失败数在连接器中计数，每个上游
+//! 地址的请求数、进行中请求数、失败数、延迟与使用的 HTTP 版本在转发时记录，
+//! 由 `GET /api/admin/upstreams` 查看。请求数远大于新建连接数说明连接得到复用。
+//!
+//! 配置：
+//! - `GATEWAY_UPSTREAM_POOL_MAX_IDLE` - 每个上游主机保留的空闲连接数上限（默认 32）
+//! - `GATEWAY_UPSTREAM_POOL_IDLE_TIMEOUT_SECS` - 空闲连接保留时间（默认 90 秒）
+//! - `GATEWAY_UPSTREAM_HTTP2` - 是否以 HTTP/2 连接上游（默认 false）
+//! - `GATEWAY_UPSTREAM_HTTP2_KEEPALIVE_SECS` - HTTP/2 连接的 PING 间隔（默认 30 秒，0 表示不发送）
+//! - `GATEWAY_UPSTREAM_TCP_KEEPALIVE_SECS` - TCP keep-alive 间隔（默认 60 秒，0 表示关闭）
+//! - `GATEWAY_UPSTREAM_CONNECT_TIMEOUT_SECS` - 建立连接的超时（默认 5 秒）
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use reqwest::Version;
+use serde::Serialize;
+use tower::{Layer, Service};
+use utoipa::ToSchema;
+
+const DEFAULT_POOL_MAX_IDLE: usize = 32;
+const DEFAULT_POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+const DEFAULT_HTTP2_KEEPALIVE_SECS: u64 = 30;
+const DEFAULT_TCP_KEEPALIVE_SECS: u64 = 60;
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 5;
+/// 未按路由设置超时的请求的总超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 上游连接设置
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UpstreamSettings {
+    /// 每个上游主机保留的空闲连接数上限
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接保留时间（秒）
+    pub pool_idle_timeout_secs: u64,
+    /// 是否以 HTTP/2 连接上游
+    pub http2: bool,
+    /// HTTP/2 连接的 PING 间隔（秒），0 表示不发送
+    pub http2_keepalive_secs: u64,
+    /// TCP keep-alive 间隔（秒），0 表示关闭
+    pub tcp_keepalive_secs: u64,
+    /// 建立连接的超时（秒）
+    pub connect_timeout_secs: u64,
+}
+
+impl UpstreamSettings {
+    /// 从环境变量读取上游连接设置
+    pub fn from_env() -> Self {
+        let env_u64 = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            pool_max_idle_per_host: env_u64("GATEWAY_UPSTREAM_POOL_MAX_IDLE", DEFAULT_POOL_MAX_IDLE as u64) as usize,
+            pool_idle_timeout_secs: env_u64("GATEWAY_UPSTREAM_POOL_IDLE_TIMEOUT_SECS", DEFAULT_POOL_IDLE_TIMEOUT_SECS),
+            http2: std::env::var("GATEWAY_UPSTREAM_HTTP2")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            http2_keepalive_secs: env_u64("GATEWAY_UPSTREAM_HTTP2_KEEPALIVE_SECS", DEFAULT_HTTP2_KEEPALIVE_SECS),
+            tcp_keepalive_secs: env_u64("GATEWAY_UPSTREAM_TCP_KEEPALIVE_SECS", DEFAULT_TCP_KEEPALIVE_SECS),
+            connect_timeout_secs: env_u64("GATEWAY_UPSTREAM_CONNECT_TIMEOUT_SECS", DEFAULT_CONNECT_TIMEOUT_SECS),
+        }
+    }
+
+    /// 按设置创建转发客户端，新建的连接计入 `stats`
+    pub fn build_client(&self, stats: Arc<UpstreamStats>) -> reqwest::Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(self.pool_idle_timeout_secs))
+            .tcp_nodelay(true)
+            .tcp_keepalive(non_zero_secs(self.tcp_keepalive_secs))
+            .connector_layer(ConnectionCounterLayer { stats });
+        if self.http2 {
+            builder = builder
+                .http2_prior_knowledge()
+                .http2_adaptive_window(true)
+                .http2_keep_alive_interval(non_zero_secs(self.http2_keepalive_secs))
+                .http2_keep_alive_while_idle(true);
+        } else {
+            builder = builder.http1_only();
+        }
+        builder.build()
+    }
+
+    /// 创建转发 WebSocket 升级请求的客户端：只用 HTTP/1.1，不设总超时
+    pub fn build_websocket_client(&self, stats: Arc<UpstreamStats>) -> reqwest::Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .tcp_nodelay(true)
+            .tcp_keepalive(non_zero_secs(self.tcp_keepalive_secs))
+            .connector_layer(ConnectionCounterLayer { stats })
+            .http1_only()
+            .build()
+    }
+}
+
+fn non_zero_secs(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// 上游连接与请求统计
+#[derive(Default)]
+pub struct UpstreamStats {
+    connections_opened: AtomicU64,
+    connections_failed: AtomicU64,
+    /// 按上游地址（`scheme://host:port`）保存的请求统计
+    upstreams: Mutex<HashMap<String, RequestCounters>>,
+}
+
+#[derive(Default)]
+struct RequestCounters {
+    requests: u64,
+    in_flight: u64,
+    errors: u64,
+    server_errors: u64,
+    total_latency_ms: u64,
+    max_latency_ms: u64,
+    http1: u64,
+    http2: u64,
+}
+
+impl UpstreamStats {
+    /// 开始一次上游请求，返回的记录在完成或丢弃时计入统计
+    pub fn start(self: &Arc<Self>, target_url: &str) -> UpstreamRequest {
+        let upstream = upstream_of(target_url);
+        self.update(&upstream, |c| {
+            c.requests += 1;
+            c.in_flight += 1;
+        });
+        UpstreamRequest {
+            stats: self.clone(),
+            upstream,
+            started: Instant::now(),
+            done: false,
+        }
+    }
+
+    /// 当前统计
+    pub fn report(&self, settings: &UpstreamSettings) -> UpstreamReport {
+        let upstreams = self.upstreams.lock().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<UpstreamStat> = upstreams
+            .iter()
+            .map(|(upstream, c)| {
+                let completed = c.requests - c.in_flight;
+                UpstreamStat {
+                    upstream: upstream.clone(),
+                    requests: c.requests,
+                    in_flight: c.in_flight,
+                    errors: c.errors,
+                    server_errors: c.server_errors,
+                    avg_latency_ms: c.total_latency_ms.checked_div(completed).unwrap_or(0),
+                    max_latency_ms: c.max_latency_ms,
+                    http1_responses: c.http1,
+                    http2_responses: c.http2,
+                }
+            })
+            .collect();
+        list.sort_by(|a, b| a.upstream.cmp(&b.upstream));
+        UpstreamReport {
+            settings: settings.clone(),
+            connections_opened: self.connections_opened.load(Ordering::Relaxed),
+            connections_failed: self.connections_failed.load(Ordering::Relaxed),
+            upstreams: list,
+        }
+    }
+
+    fn update(&self, upstream: &str, f: impl FnOnce(&mut RequestCounters)) {
+        let mut upstreams = self.upstreams.lock().unwrap_or_else(|e| e.into_inner());
+        match upstreams.get_mut(upstream) {
+            Some(counters) => f(counters),
+            None => f(upstreams.entry(upstream.to_string()).or_default()),
+        }
+    }
+}
+
+/// 进行中的上游请求
+pub struct UpstreamRequest {
+    stats: Arc<UpstreamStats>,
+    upstream: String,
+    started: Instant,
+    done: bool,
+}
+
+impl UpstreamRequest {
+    /// 记录上游的响应
+    pub fn response(mut self, response: &reqwest::Response) {
+        let server_error = response.status().is_server_error();
+        let http2 = response.version() == Version::HTTP_2;
+        self.finish(|c| {
+            if server_error {
+                c.server_errors += 1;
+            }
+            if http2 {
+                c.http2 += 1;
+            } else {
+                c.http1 += 1;
+            }
+        });
+    }
+
+    /// 记录连接失败、超时等没有得到响应的请求
+    pub fn error(mut self) {
+        self.finish(|c| c.errors += 1);
+    }
+
+    fn finish(&mut self, f: impl FnOnce(&mut RequestCounters)) {
+        self.done = true;
+        let latency_ms = self.started.elapsed().as_millis() as u64;
+        self.stats.update(&self.upstream, |c| {
+            c.in_flight = c.in_flight.saturating_sub(1);
+            c.total_latency_ms += latency_ms;
+            c.max_latency_ms = c.max_latency_ms.max(latency_ms);
+            f(c);
+        });
+    }
+}
+
+impl Drop for UpstreamRequest {
+    // 客户端断开时请求被取消，计为失败
+    fn drop(&mut self) {
+        if !self.done {
+            self.finish(|c| c.errors += 1);
+        }
+    }
+}
+
+/// 请求 URL 的上游地址（`scheme://host:port`）
+fn upstream_of(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(url) => format!(
+            "{}://{}:{}",
+            url.scheme(),
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        ),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// 上游连接统计
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UpstreamReport {
+    /// 当前生效的连接设置
+    pub settings: UpstreamSettings,
+    /// 启动以来新建的连接数
+    pub connections_opened: u64,
+    /// 启动以来建立失败的连接数
+    pub connections_failed: u64,
+    pub upstreams: Vec<UpstreamStat>,
+}
+
+/// 单个上游地址的请求统计
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UpstreamStat {
+    /// 上游地址（`scheme://host:port`）
+    pub upstream: String,
+    /// 转发的请求数（含重试）
+    pub requests: u64,
+    /// 进行中的请求数
+    pub in_flight: u64,
+    /// 没有得到响应的请求数（连接失败、超时、客户端断开）
+    pub errors: u64,
+    /// 上游返回 5xx 的请求数
+    pub server_errors: u64,
+    /// 已完成请求的平均耗时（毫秒，至收到响应头）
+    pub avg_latency_ms: u64,
+    /// 已完成请求的最大耗时（毫秒）
+    pub max_latency_ms: u64,
+    /// 以 HTTP/1.1 返回的响应数
+    pub http1_responses: u64,
+    /// 以 HTTP/2 返回的响应数
+    pub http2_responses: u64,
+}
+
+/// 为客户端的连接器计数新建连接
+#[derive(Clone)]
+struct ConnectionCounterLayer {
+    stats: Arc<UpstreamStats>,
+}
+
+impl<S> Layer<S> for ConnectionCounterLayer {
+    type Service = ConnectionCounter<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConnectionCounter { inner, stats: self.stats.clone() }
+    }
+}
+
+#[derive(Clone)]
+struct ConnectionCounter<S> {
+    inner: S,
+    stats: Arc<UpstreamStats>,
+}
+
+impl<S, R> Service<R> for ConnectionCounter<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let connecting = self.inner.call(request);
+        let stats = self.stats.clone();
+        Box::pin(async move {
+            let result = connecting.await;
+            let counter = if result.is_ok() { &stats.connections_opened } else { &stats.connections_failed };
+            counter.fetch_add(1, Ordering::Relaxed);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_requests_by_upstream() {
+        assert_eq!(upstream_of("http://query-service:8082/api/query?x=1"), "http://query-service:8082");
+        assert_eq!(upstream_of("https://db.example.com/api"), "https://db.example.com:443");
+
+        let stats = Arc::new(UpstreamStats::default());
+        stats.start("http://a:1/x").error();
+        let pending = stats.start("http://a:1/y");
+        drop(stats.start("http://b:2/"));
+
+        let report = stats.report(&UpstreamSettings::from_env());
+        assert_eq!(report.upstreams.len(), 2);
+        let a = &report.upstreams[0];
+        assert_eq!((a.requests, a.in_flight, a.errors), (2, 1, 1));
+        let b = &report.upstreams[1];
+        assert_eq!((b.requests, b.in_flight, b.errors), (1, 0, 1));
+        drop(pending);
+    }
+}
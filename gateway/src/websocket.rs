@@ -0,0 +1,228 @@
+//! WebSocket 代理模块
+//!
+//! 下游服务的 WebSocket 端点（监控、流式结果等）经网关访问：升级请求
+//! （`Connection: upgrade`、`Upgrade: websocket`）连同 `Sec-WebSocket-*` 握手头原样
+//! 转发到上游，上游同意升级（101）后把它的握手响应返回客户端，之后在两端之间逐字节
+//! 转发帧。子协议（`Sec-WebSocket-Protocol`）与扩展（如 permessage-deflate）由客户端
+//! 与上游直接协商，网关不解析帧。请求 ID 随握手请求传给上游。
+//!
+//! 上游拒绝升级时原样返回它的响应。升级后的连接不受转发超时限制，任一端关闭即结束。
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{
+        header::{CONNECTION, CONTENT_LENGTH, HOST, UPGRADE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+
+use common::middleware::{RequestId, RequestSigner, SendSigned, REQUEST_ID_HEADER};
+
+use crate::upstream::{UpstreamSettings, UpstreamStats};
+
+/// 是否为 WebSocket 升级请求
+pub fn is_upgrade(headers: &HeaderMap) -> bool {
+    let has_token = |name, token: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case(token))
+    };
+    has_token(CONNECTION, "upgrade") && has_token(UPGRADE, "websocket")
+}
+
+/// WebSocket 连接转发
+#[derive(Clone)]
+pub struct WebSocketProxy {
+    /// 只用 HTTP/1.1 连接上游（升级不能经 HTTP/2 先验知识的连接），不设总超时
+    client: reqwest::Client,
+    signer: RequestSigner,
+    stats: Arc<UpstreamStats>,
+}
+
+impl WebSocketProxy {
+    pub fn new(settings: &UpstreamSettings, stats: Arc<UpstreamStats>, signer: RequestSigner) -> Self {
+        let client = settings
+            .build_websocket_client(stats.clone())
+            .expect("Failed to create HTTP client");
+        Self { client, signer, stats }
+    }
+
+    /// 把升级请求转发到 `target_url`，上游同意升级后在客户端与上游之间转发数据
+    pub async fn proxy(&self, target_url: &str, mut req: Request<Body>) -> Response {
+        let client_upgrade = hyper::upgrade::on(&mut req);
+
+        let mut upstream_req = self.client.get(target_url);
+        for (name, value) in req.headers().iter().filter(|(name, _)| *name != HOST) {
+            upstream_req = upstream_req.header(name.clone(), value.clone());
+        }
+        if let Some(RequestId(request_id)) = req.extensions().get::<RequestId>() {
+            upstream_req = upstream_req.header(REQUEST_ID_HEADER.as_str(), request_id.as_str());
+        }
+
+        let upstream = self.stats.start(target_url);
+        let response = match upstream_req.send_signed(&self.signer).await {
+            Ok(resp) => {
+                upstream.response(&resp);
+                resp
+            }
+            Err(e) => {
+                upstream.error();
+                tracing::error!(error = %e, target = %target_url, "WebSocket 握手转发失败");
+                return (StatusCode::BAD_GATEWAY, format!("服务不可用: {}", e)).into_response();
+            }
+        };
+
+        let status = response.status();
+        let mut builder = Response::builder().status(status);
+        for (name, value) in response.headers().iter().filter(|(name, _)| *name != CONTENT_LENGTH) {
+            builder = builder.header(name, value);
+        }
+        if status != StatusCode::SWITCHING_PROTOCOLS {
+            // 上游拒绝升级，原样返回它的响应
+            let body = response.bytes().await.unwrap_or_default();
+            return builder
+                .body(Body::from(body))
+                .unwrap_or_else(|_| (StatusCode::INTERNAL_SERVER_ERROR, "构建响应失败").into_response());
+        }
+
+        let target = target_url.to_string();
+        tokio::spawn(async move {
+            let mut upstream = match response.upgrade().await {
+                Ok(upstream) => upstream,
+                Err(e) => {
+                    tracing::warn!(error = %e, target = %target, "上游 WebSocket 升级失败");
+                    return;
+                }
+            };
+            let mut client = match client_upgrade.await {
+                Ok(client) => reqwest::Upgraded::from(client),
+                Err(e) => {
+                    tracing::warn!(error = %e, target = %target, "客户端 WebSocket 升级失败");
+                    return;
+                }
+            };
+            match tokio::io::copy_bidirectional(&mut client, &mut upstream).await {
+                Ok((sent, received)) => {
+                    tracing::debug!(target = %target, sent, received, "WebSocket 连接结束");
+                }
+                Err(e) => tracing::debug!(error = %e, target = %target, "WebSocket 连接中断"),
+            }
+        });
+
+        builder
+            .body(Body::empty())
+            .unwrap_or_else(|_| (StatusCode::INTERNAL_SERVER_ERROR, "构建响应失败").into_response())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::routing::any;
+    use axum::Router;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::handshake::server::{Request as Handshake, Response as HandshakeResponse};
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// 回显消息的上游，选择客户端提供的第一个子协议，并把收到的请求 ID 作为第一条消息发回
+    async fn echo_upstream() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut request_id = String::new();
+            // 错误类型由 tungstenite 的握手回调决定
+            #[allow(clippy::result_large_err)]
+            let callback = |req: &Handshake, mut resp: HandshakeResponse| {
+                request_id = req.headers()[&REQUEST_ID_HEADER].to_str().unwrap().to_string();
+                if let Some(protocols) = req.headers().get("sec-websocket-protocol") {
+                    let first = protocols.to_str().unwrap().split(',').next().unwrap().trim().to_string();
+                    resp.headers_mut().insert("sec-websocket-protocol", first.parse().unwrap());
+                }
+                Ok(resp)
+            };
+            let mut ws = tokio_tungstenite::accept_hdr_async(stream, callback).await.unwrap();
+            ws.send(Message::text(request_id)).await.unwrap();
+            while let Some(Ok(message)) = ws.next().await {
+                if message.is_close() {
+                    break;
+                }
+                ws.send(message).await.unwrap();
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    async fn gateway(upstream: String) -> String {
+        let proxy = WebSocketProxy::new(
+            &UpstreamSettings::from_env(),
+            Arc::new(UpstreamStats::default()),
+            RequestSigner::new("gateway", None),
+        );
+        let app = Router::new().route(
+            "/ws/{*path}",
+            any(move |req: Request| {
+                let (proxy, target) = (proxy.clone(), format!("{}{}", upstream, req.uri().path()));
+                async move { proxy.proxy(&target, req).await }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("ws://{}", addr)
+    }
+
+    #[test]
+    fn detects_upgrade_requests() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONNECTION, "keep-alive, Upgrade".parse().unwrap());
+        assert!(!is_upgrade(&headers));
+        headers.insert(UPGRADE, "WebSocket".parse().unwrap());
+        assert!(is_upgrade(&headers));
+        headers.insert(CONNECTION, "keep-alive".parse().unwrap());
+        assert!(!is_upgrade(&headers));
+    }
+
+    #[tokio::test]
+    async fn relays_frames_with_subprotocol_and_request_id() {
+        let gateway = gateway(echo_upstream().await).await;
+
+        let mut request = format!("{}/ws/monitor", gateway).into_client_request().unwrap();
+        request.headers_mut().insert("sec-websocket-protocol", "dbm.v1, dbm.v0".parse().unwrap());
+        request.headers_mut().insert(&REQUEST_ID_HEADER, "req-42".parse().unwrap());
+        let (mut ws, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+        assert_eq!(response.headers()["sec-websocket-protocol"], "dbm.v1");
+
+        assert_eq!(ws.next().await.unwrap().unwrap(), Message::text("req-42"));
+        ws.send(Message::text("ping")).await.unwrap();
+        assert_eq!(ws.next().await.unwrap().unwrap(), Message::text("ping"));
+        ws.send(Message::binary(vec![0u8, 1, 2])).await.unwrap();
+        assert_eq!(ws.next().await.unwrap().unwrap(), Message::binary(vec![0u8, 1, 2]));
+        ws.close(None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn returns_the_upstream_response_when_upgrade_is_refused() {
+        // 上游是普通 HTTP 服务，不接受升级
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, Router::new()).await.unwrap() });
+        let gateway = gateway(upstream).await;
+
+        let error = tokio_tungstenite::connect_async(format!("{}/ws/monitor", gateway)).await.unwrap_err();
+        match error {
+            tokio_tungstenite::tungstenite::Error::Http(response) => assert_eq!(response.status(), StatusCode::NOT_FOUND),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! 索引建议模块
+//!
+//! 对语句运行 `EXPLAIN`，找出全表扫描、全索引扫描与额外排序，再结合语句中
+//! 各表的条件列与排序列给出 `CREATE INDEX` 建议。索引列依次为等值条件列、
+//! 第一个范围条件列（没有范围条件且需要排序时为排序列）；表上已有以这些列
+//! 开头的索引时不再建议。仅支持 MySQL / MariaDB 与 PostgreSQL。
+
+mod plan;
+mod predicates;
+
+use common::models::analysis::{IndexSuggestion, PlanFinding, PlanFindingKind};
+use common::models::connection::DbType;
+use common::models::database::IndexStats;
+use common::models::query::QueryResult;
+use common::utils::quote_ident;
+
+pub use predicates::StatementColumns;
+
+/// 单个索引建议的最多列数
+const MAX_INDEX_COLUMNS: usize = 5;
+
+/// 支持索引建议的 SQL 方言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    MySql,
+    Postgres,
+}
+
+impl Dialect {
+    /// 按连接的数据库类型选择方言，不支持的类型返回 `None`
+    pub fn from_db_type(db_type: &str) -> Option<Self> {
+        match db_type {
+            "mysql" | "mariadb" => Some(Self::MySql),
+            "postgres" => Some(Self::Postgres),
+            _ => None,
+        }
+    }
+
+    /// 查看语句执行计划的 `EXPLAIN` 语句（PostgreSQL 使用文本格式）
+    pub fn explain(self, sql: &str) -> String {
+        format!("EXPLAIN {}", sql.trim().trim_end_matches(';').trim_end())
+    }
+
+    /// 解析 `EXPLAIN` 结果
+    pub fn findings(self, plan: &QueryResult) -> Vec<PlanFinding> {
+        match self {
+            Self::MySql => plan::mysql_findings(plan),
+            Self::Postgres => plan::postgres_findings(plan),
+        }
+    }
+
+    fn quote(self, ident: &str) -> String {
+        match self {
+            Self::MySql => quote_ident(&DbType::MySQL, ident),
+            Self::Postgres => quote_ident(&DbType::Postgres, ident),
+        }
+    }
+
+    fn max_identifier_len(self) -> usize {
+        match self {
+            Self::MySql => 64,
+            Self::Postgres => 63,
+        }
+    }
+}
+
+/// 将计划中的别名替换为语句中的表名
+pub fn resolve_findings(findings: &mut [PlanFinding], stmt: &StatementColumns) {
+    for finding in findings {
+        if let Some(table) = finding.table.as_deref().and_then(|t| stmt.resolve_table(t)) {
+            finding.table = Some(table.to_string());
+        }
+    }
+}
+
+/// 计划中需要索引处理的问题（按表汇总）
+#[derive(Default)]
+struct TableProblems {
+    scan: Option<PlanFindingKind>,
+    scan_rows: Option<u64>,
+    sort: bool,
+}
+
+/// 根据计划问题与列使用情况生成索引建议，尚未排除表上已有的索引
+pub fn suggest(dialect: Dialect, stmt: &StatementColumns, findings: &[PlanFinding]) -> Vec<IndexSuggestion> {
+    fn entry<'a>(problems: &'a mut Vec<(String, TableProblems)>, table: &str) -> &'a mut TableProblems {
+        let i = match problems.iter().position(|(t, _)| t.eq_ignore_ascii_case(table)) {
+            Some(i) => i,
+            None => {
+                problems.push((table.to_string(), TableProblems::default()));
+                problems.len() - 1
+            }
+        };
+        &mut problems[i].1
+    }
+
+    let mut problems: Vec<(String, TableProblems)> = Vec::new();
+
+    for finding in findings {
+        // 小表扫描和排序的代价很低，不值得额外维护索引
+        if finding.estimated_rows.is_some_and(|rows| rows < plan::MIN_SCAN_ROWS) {
+            continue;
+        }
+        match (finding.kind, finding.table.as_deref()) {
+            (PlanFindingKind::FullScan | PlanFindingKind::FullIndexScan, Some(table)) => {
+                let entry = entry(&mut problems, table);
+                entry.scan.get_or_insert(finding.kind);
+                entry.scan_rows = entry.scan_rows.max(finding.estimated_rows);
+            }
+            (PlanFindingKind::Sort, Some(table)) => {
+                entry(&mut problems, table).sort = true;
+            }
+            // PostgreSQL 的 Sort 节点不标明表，归到有排序列的表
+            (PlanFindingKind::Sort, None) => {
+                for table in stmt.tables() {
+                    if stmt.usage(table).is_some_and(|u| !u.order.is_empty()) {
+                        entry(&mut problems, table).sort = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    problems
+        .into_iter()
+        .filter_map(|(table, found)| {
+            let usage = stmt.usage(&table)?;
+            let range = found.scan.and(usage.range.first());
+            let mut columns: Vec<String> = Vec::new();
+            let mut add = |column: &String| {
+                if !columns.iter().any(|c| c.eq_ignore_ascii_case(column)) {
+                    columns.push(column.clone());
+                }
+            };
+            usage.equality.iter().for_each(&mut add);
+            let sorted = range.is_none() && found.sort && !usage.order.is_empty();
+            match range {
+                Some(column) => add(column),
+                None if sorted => usage.order.iter().for_each(&mut add),
+                None => {}
+            }
+            columns.truncate(MAX_INDEX_COLUMNS);
+            if columns.is_empty() {
+                return None;
+            }
+
+            let mut reasons = Vec::new();
+            if let Some(kind) = found.scan {
+                let scan = if kind == PlanFindingKind::FullScan { "避免全表扫描" } else { "避免全索引扫描" };
+                reasons.push(match found.scan_rows {
+                    Some(rows) => format!("{}（约 {} 行）", scan, rows),
+                    None => scan.to_string(),
+                });
+            }
+            if sorted {
+                reasons.push("消除额外排序".to_string());
+            }
+            if reasons.is_empty() {
+                return None;
+            }
+
+            Some(IndexSuggestion {
+                statement: create_index(dialect, stmt.database(&table), &table, &columns),
+                reason: format!("{}：{}", table, reasons.join("，")),
+                table,
+                columns,
+            })
+        })
+        .collect()
+}
+
+/// 表上是否已有以 `columns` 开头的索引
+pub fn is_covered(columns: &[String], existing: &[IndexStats]) -> bool {
+    existing.iter().any(|index| {
+        index.columns.len() >= columns.len()
+            && index.columns.iter().zip(columns).all(|(a, b)| a.eq_ignore_ascii_case(b))
+    })
+}
+
+fn create_index(dialect: Dialect, database: Option<&str>, table: &str, columns: &[String]) -> String {
+    let mut name: String = format!("idx_{}_{}", table, columns.join("_"))
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    name.truncate(dialect.max_identifier_len());
+
+    let target = match database {
+        Some(database) => format!("{}.{}", dialect.quote(database), dialect.quote(table)),
+        None => dialect.quote(table),
+    };
+    let columns: Vec<String> = columns.iter().map(|c| dialect.quote(c)).collect();
+    format!("CREATE INDEX {} ON {} ({})", dialect.quote(&name), target, columns.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(kind: PlanFindingKind, table: Option<&str>, rows: u64) -> PlanFinding {
+        PlanFinding {
+            kind,
+            table: table.map(str::to_string),
+            estimated_rows: Some(rows),
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn suggests_equality_then_range_columns() {
+        let sql = "SELECT * FROM shop.orders o JOIN customers c ON c.id = o.customer_id \
+                   WHERE o.status = ? AND o.created_at > ? ORDER BY o.created_at";
+        let stmt = StatementColumns::parse(sql);
+        let mut findings = vec![
+            finding(PlanFindingKind::FullScan, Some("o"), 80_000),
+            finding(PlanFindingKind::Sort, Some("o"), 80_000),
+            finding(PlanFindingKind::FullScan, Some("c"), 20),
+        ];
+        resolve_findings(&mut findings, &stmt);
+        assert_eq!(findings[0].table.as_deref(), Some("orders"));
+
+        let suggestions = suggest(Dialect::MySql, &stmt, &findings);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].columns, ["customer_id", "status", "created_at"]);
+        assert_eq!(
+            suggestions[0].statement,
+            "CREATE INDEX `idx_orders_customer_id_status_created_at` ON `shop`.`orders` (`customer_id`, `status`, `created_at`)"
+        );
+    }
+
+    #[test]
+    fn postgres_sort_uses_order_columns() {
+        let stmt = StatementColumns::parse("SELECT * FROM events WHERE kind = $1 ORDER BY \"createdAt\" DESC");
+        let findings = vec![
+            finding(PlanFindingKind::Sort, None, 5_000),
+            finding(PlanFindingKind::FullScan, Some("events"), 5_000),
+        ];
+        let suggestions = suggest(Dialect::Postgres, &stmt, &findings);
+        assert_eq!(suggestions[0].columns, ["kind", "createdAt"]);
+        assert_eq!(
+            suggestions[0].statement,
+            "CREATE INDEX \"idx_events_kind_createdat\" ON \"events\" (\"kind\", \"createdAt\")"
+        );
+
+        let existing = IndexStats {
+            name: "events_kind_created".into(),
+            columns: vec!["kind".into(), "createdAt".into(), "id".into()],
+            unique: false,
+            primary: false,
+            cardinality: None,
+        };
+        assert!(is_covered(&suggestions[0].columns, &[existing]));
+    }
+}
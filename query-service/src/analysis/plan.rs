@@ -0,0 +1,165 @@
+//! 执行计划解析
+//!
+//! MySQL 读取表格形式 `EXPLAIN` 的 `table`、`type`、`rows` 与 `Extra` 列；
+//! PostgreSQL 读取文本形式 `EXPLAIN` 的各个节点行（`Seq Scan on ...`、`Sort`）。
+//! 计划中的表名可能是别名，由调用方按语句解析为真实表名。
+
+use common::models::analysis::{PlanFinding, PlanFindingKind};
+use common::models::query::QueryResult;
+
+/// 值得报告的最少预估扫描行数，小表全表扫描通常比走索引更快
+pub const MIN_SCAN_ROWS: u64 = 1000;
+
+/// 解析 MySQL / MariaDB 的 `EXPLAIN` 结果
+pub fn mysql_findings(plan: &QueryResult) -> Vec<PlanFinding> {
+    let column = |name: &str| plan.columns.iter().position(|c| c.name.eq_ignore_ascii_case(name));
+    let (table_idx, type_idx, rows_idx, extra_idx) =
+        (column("table"), column("type"), column("rows"), column("Extra"));
+
+    let mut findings = Vec::new();
+    for row in &plan.rows {
+        let text = |idx: Option<usize>| idx.and_then(|i| row.get(i)).and_then(|v| v.as_str()).unwrap_or("");
+        // `<derived2>`、`<union1,2>` 等是派生表，无法建索引
+        let table = Some(text(table_idx))
+            .filter(|t| !t.is_empty() && !t.starts_with('<'))
+            .map(str::to_string);
+        let rows = rows_idx.and_then(|i| row.get(i)).and_then(json_u64);
+
+        match text(type_idx) {
+            "ALL" if table.is_some() => findings.push(PlanFinding {
+                kind: PlanFindingKind::FullScan,
+                table: table.clone(),
+                estimated_rows: rows,
+                detail: "全表扫描，没有可用的索引".to_string(),
+            }),
+            "index" if table.is_some() => findings.push(PlanFinding {
+                kind: PlanFindingKind::FullIndexScan,
+                table: table.clone(),
+                estimated_rows: rows,
+                detail: "扫描整个索引，条件未能用于索引查找".to_string(),
+            }),
+            _ => {}
+        }
+        let extra = text(extra_idx);
+        if extra.contains("Using filesort") {
+            findings.push(PlanFinding {
+                kind: PlanFindingKind::Sort,
+                table: table.clone(),
+                estimated_rows: rows,
+                detail: "结果需要额外排序（Using filesort）".to_string(),
+            });
+        }
+        if extra.contains("Using temporary") {
+            findings.push(PlanFinding {
+                kind: PlanFindingKind::TemporaryTable,
+                table,
+                estimated_rows: rows,
+                detail: "需要创建内部临时表（Using temporary）".to_string(),
+            });
+        }
+    }
+    findings
+}
+
+/// 解析 PostgreSQL 的文本 `EXPLAIN` 结果（每行一个节点）
+pub fn postgres_findings(plan: &QueryResult) -> Vec<PlanFinding> {
+    let mut findings = Vec::new();
+    for line in plan.rows.iter().filter_map(|r| r.first()).filter_map(|v| v.as_str()) {
+        let node = line.trim_start().trim_start_matches("->").trim_start();
+        let rows = estimated_rows(node);
+        let label = node.split("  (").next().unwrap_or(node);
+        if let Some(rest) = node.split_once("Seq Scan on ").map(|(_, r)| r) {
+            findings.push(PlanFinding {
+                kind: PlanFindingKind::FullScan,
+                table: scan_target(rest),
+                estimated_rows: rows,
+                detail: "顺序扫描（Seq Scan），没有可用的索引".to_string(),
+            });
+        } else if label == "Sort" || label == "Incremental Sort" {
+            findings.push(PlanFinding {
+                kind: PlanFindingKind::Sort,
+                table: None,
+                estimated_rows: rows,
+                detail: "结果需要额外排序（Sort）".to_string(),
+            });
+        }
+    }
+    findings
+}
+
+/// `Seq Scan on` 之后的表名，去掉 schema 限定与引号
+fn scan_target(rest: &str) -> Option<String> {
+    let name = rest.split_whitespace().next()?;
+    let name = name.rsplit('.').next().unwrap_or(name).trim_matches('"');
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// 节点代价估算中的 `rows=N`
+fn estimated_rows(node: &str) -> Option<u64> {
+    let (_, rest) = node.split_once("rows=")?;
+    rest.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+}
+
+fn json_u64(value: &serde_json::Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::query::ColumnInfo;
+    use serde_json::json;
+
+    fn result(columns: &[&str], rows: Vec<Vec<serde_json::Value>>) -> QueryResult {
+        QueryResult {
+            columns: columns
+                .iter()
+                .map(|name| ColumnInfo { name: name.to_string(), data_type: "TEXT".into(), nullable: None, kind: None })
+                .collect(),
+            row_count: rows.len(),
+            rows,
+            affected_rows: None,
+            execution_time_ms: 0,
+            truncated: false,
+            truncated_cells: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn mysql_scans_and_extras() {
+        let plan = result(
+            &["id", "select_type", "table", "type", "key", "rows", "Extra"],
+            vec![
+                vec![json!(1), json!("SIMPLE"), json!("o"), json!("ALL"), json!(null), json!(50000), json!("Using where; Using temporary; Using filesort")],
+                vec![json!(1), json!("SIMPLE"), json!("c"), json!("eq_ref"), json!("PRIMARY"), json!(1), json!(null)],
+                vec![json!(2), json!("DERIVED"), json!("<derived2>"), json!("ALL"), json!(null), json!(10), json!(null)],
+            ],
+        );
+        let findings = mysql_findings(&plan);
+        let kinds: Vec<_> = findings.iter().map(|f| f.kind).collect();
+        assert_eq!(kinds, [PlanFindingKind::FullScan, PlanFindingKind::Sort, PlanFindingKind::TemporaryTable]);
+        assert_eq!(findings[0].table.as_deref(), Some("o"));
+        assert_eq!(findings[0].estimated_rows, Some(50000));
+    }
+
+    #[test]
+    fn postgres_plan_lines() {
+        let plan = result(
+            &["QUERY PLAN"],
+            vec![
+                vec![json!("Sort  (cost=1.00..2.00 rows=120 width=8)")],
+                vec![json!("  Sort Key: created_at")],
+                vec![json!("  ->  Seq Scan on public.\"Orders\" o  (cost=0.00..1.50 rows=120 width=8)")],
+                vec![json!("        Filter: (status = 'paid'::text)")],
+            ],
+        );
+        let findings = postgres_findings(&plan);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].kind, PlanFindingKind::Sort);
+        assert_eq!(findings[1].kind, PlanFindingKind::FullScan);
+        assert_eq!(findings[1].table.as_deref(), Some("Orders"));
+        assert_eq!(findings[1].estimated_rows, Some(120));
+    }
+}
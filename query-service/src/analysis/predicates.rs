@@ -0,0 +1,413 @@
+//! 谓词列提取
+//!
+//! 轻量扫描 SQL，找出 FROM / JOIN 引用的表及别名，以及 WHERE / ON 条件和
+//! ORDER BY 中按表归类的列。只识别可以走索引的简单形式：`列 = 值`、
+//! `列 IN (...)`、`列 IS NULL`、范围比较、`BETWEEN` 与前缀 `LIKE`；
+//! 被函数包裹的列、`<>` 与 `NOT IN` 等无法利用索引的条件被忽略。
+
+use std::collections::HashMap;
+
+use common::utils::sql_lexer::{Dialect, Skip, SqlLexer};
+
+/// 一个表上被条件或排序引用的列（按出现顺序去重）
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ColumnUsage {
+    /// 等值条件列（`=`、`IN`、`IS NULL`，含 JOIN 连接列）
+    pub equality: Vec<String>,
+    /// 范围条件列
+    pub range: Vec<String>,
+    /// ORDER BY 列
+    pub order: Vec<String>,
+}
+
+impl ColumnUsage {
+    fn push(list: &mut Vec<String>, column: &str) {
+        if !list.iter().any(|c| c.eq_ignore_ascii_case(column)) {
+            list.push(column.to_string());
+        }
+    }
+}
+
+/// 语句引用的表与各表的列使用情况
+#[derive(Debug, Default)]
+pub struct StatementColumns {
+    /// 别名（小写）到表名
+    aliases: HashMap<String, String>,
+    /// 表名（小写）到列使用情况
+    usage: HashMap<String, ColumnUsage>,
+    /// 表名（小写）到限定的库名 / schema
+    databases: HashMap<String, String>,
+    /// 引用的表名（按出现顺序）
+    tables: Vec<String>,
+}
+
+impl StatementColumns {
+    /// 解析 SQL 语句
+    pub fn parse(sql: &str) -> Self {
+        let tokens = tokenize(sql);
+        let mut stmt = Self::default();
+        stmt.collect_tables(&tokens);
+        stmt.collect_columns(&tokens);
+        stmt
+    }
+
+    /// 表名或别名对应的表名
+    pub fn resolve_table(&self, name: &str) -> Option<&str> {
+        self.aliases.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    /// 语句引用的表（按出现顺序）
+    pub fn tables(&self) -> &[String] {
+        &self.tables
+    }
+
+    /// 表名限定的库名 / schema（未限定时为 `None`）
+    pub fn database(&self, table: &str) -> Option<&str> {
+        self.databases.get(&table.to_lowercase()).map(String::as_str)
+    }
+
+    /// 某个表的列使用情况
+    pub fn usage(&self, table: &str) -> Option<&ColumnUsage> {
+        self.usage.get(&table.to_lowercase())
+    }
+
+    fn collect_tables(&mut self, tokens: &[Token]) {
+        let mut i = 0;
+        let mut in_from = false;
+        while i < tokens.len() {
+            let starts_ref = match &tokens[i] {
+                Token::Word(w) => {
+                    let upper = w.to_ascii_uppercase();
+                    match upper.as_str() {
+                        "FROM" | "JOIN" | "UPDATE" | "INTO" => {
+                            in_from = upper != "UPDATE" && upper != "INTO";
+                            true
+                        }
+                        _ if is_clause_end(&upper) => {
+                            in_from = false;
+                            false
+                        }
+                        _ => false,
+                    }
+                }
+                Token::Symbol(s) if s == "," && in_from => true,
+                Token::Symbol(s) if s == "(" || s == ")" => {
+                    in_from = false;
+                    false
+                }
+                _ => false,
+            };
+            i += 1;
+            if starts_ref {
+                i = self.table_ref(tokens, i);
+            }
+        }
+    }
+
+    /// 解析 `[库.]表 [AS] [别名]`，返回其后的位置
+    fn table_ref(&mut self, tokens: &[Token], mut i: usize) -> usize {
+        let Some(first) = tokens.get(i).and_then(Token::ident) else {
+            return i;
+        };
+        let mut table = first;
+        let mut database = None;
+        i += 1;
+        if tokens.get(i).is_some_and(|t| t.is_symbol(".")) {
+            if let Some(name) = tokens.get(i + 1).and_then(Token::ident) {
+                database = Some(std::mem::replace(&mut table, name));
+                i += 2;
+            }
+        }
+        // 表函数
+        if tokens.get(i).is_some_and(|t| t.is_symbol("(")) {
+            return i;
+        }
+        if tokens.get(i).is_some_and(|t| t.is_keyword("AS")) {
+            i += 1;
+        }
+        let alias = match tokens.get(i) {
+            Some(token @ (Token::Word(_) | Token::Quoted(_))) if !token.is_reserved() => {
+                i += 1;
+                token.ident()
+            }
+            _ => None,
+        };
+
+        let key = table.to_lowercase();
+        if let Some(database) = database {
+            self.databases.insert(key.clone(), database);
+        }
+        self.aliases.insert(key.clone(), table.clone());
+        if let Some(alias) = alias {
+            self.aliases.insert(alias.to_lowercase(), table.clone());
+        }
+        if !self.tables.iter().any(|t| t.eq_ignore_ascii_case(&table)) {
+            self.tables.push(table);
+        }
+        self.usage.entry(key).or_default();
+        i
+    }
+
+    fn collect_columns(&mut self, tokens: &[Token]) {
+        let mut clause = Clause::Other;
+        let mut i = 0;
+        while i < tokens.len() {
+            if let Token::Word(w) = &tokens[i] {
+                let upper = w.to_ascii_uppercase();
+                let next_is_by = tokens.get(i + 1).is_some_and(|t| t.is_keyword("BY"));
+                match upper.as_str() {
+                    "WHERE" | "ON" | "HAVING" => clause = Clause::Condition,
+                    "ORDER" if next_is_by => {
+                        clause = Clause::OrderBy;
+                        i += 2;
+                        self.order_column(tokens, i);
+                        continue;
+                    }
+                    _ if is_clause_end(&upper) || upper == "SELECT" || upper == "FROM" => clause = Clause::Other,
+                    _ => {}
+                }
+            }
+            match clause {
+                Clause::Condition => self.predicate(tokens, i),
+                Clause::OrderBy if tokens[i].is_symbol(",") => self.order_column(tokens, i + 1),
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    /// 若 `i` 处是比较运算符，记录两侧的列
+    fn predicate(&mut self, tokens: &[Token], i: usize) {
+        let kind = match &tokens[i] {
+            Token::Symbol(s) => match s.as_str() {
+                "=" | "<=>" => Predicate::Equality,
+                "<" | ">" | "<=" | ">=" => Predicate::Range,
+                _ => return,
+            },
+            Token::Word(w) => match w.to_ascii_uppercase().as_str() {
+                "IN" | "IS" => Predicate::Equality,
+                "BETWEEN" => Predicate::Range,
+                // 以 % 开头的 LIKE 无法使用索引
+                "LIKE" => match tokens.get(i + 1) {
+                    Some(Token::Literal(s)) if !s.starts_with('%') && !s.starts_with('_') => Predicate::Range,
+                    _ => return,
+                },
+                _ => return,
+            },
+            _ => return,
+        };
+        // `IS NOT NULL` 选择性差，`NOT IN` / `NOT LIKE` 不能走索引
+        if tokens.get(i + 1).is_some_and(|t| t.is_keyword("NOT")) || (i > 0 && tokens[i - 1].is_keyword("NOT")) {
+            return;
+        }
+        let left = column_before(tokens, i);
+        let right = if matches!(kind, Predicate::Equality) { column_after(tokens, i + 1) } else { None };
+        for (qualifier, column) in left.into_iter().chain(right) {
+            self.record(qualifier, column, kind);
+        }
+    }
+
+    fn order_column(&mut self, tokens: &[Token], i: usize) {
+        if let Some((qualifier, column)) = column_after(tokens, i) {
+            self.record(qualifier, column, Predicate::Order);
+        }
+    }
+
+    fn record(&mut self, qualifier: Option<String>, column: String, kind: Predicate) {
+        let table = match qualifier {
+            Some(q) => match self.resolve_table(&q) {
+                Some(t) => t.to_lowercase(),
+                None => return,
+            },
+            // 未限定的列只在单表语句中可以确定归属
+            None if self.tables.len() == 1 => self.tables[0].to_lowercase(),
+            None => return,
+        };
+        let usage = self.usage.entry(table).or_default();
+        let list = match kind {
+            Predicate::Equality => &mut usage.equality,
+            Predicate::Range => &mut usage.range,
+            Predicate::Order => &mut usage.order,
+        };
+        ColumnUsage::push(list, &column);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Clause {
+    Condition,
+    OrderBy,
+    Other,
+}
+
+#[derive(Clone, Copy)]
+enum Predicate {
+    Equality,
+    Range,
+    Order,
+}
+
+/// 结束当前子句的关键字
+fn is_clause_end(upper: &str) -> bool {
+    matches!(
+        upper,
+        "WHERE" | "ON" | "USING" | "GROUP" | "ORDER" | "HAVING" | "LIMIT" | "OFFSET" | "UNION" | "EXCEPT"
+            | "INTERSECT" | "SET" | "VALUES" | "WINDOW" | "FOR" | "RETURNING"
+    )
+}
+
+/// 不能作为别名或列名的关键字
+const RESERVED: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "JOIN", "INNER", "LEFT", "RIGHT", "FULL", "OUTER", "CROSS", "NATURAL", "ON",
+    "USING", "GROUP", "ORDER", "BY", "HAVING", "LIMIT", "OFFSET", "UNION", "EXCEPT", "INTERSECT", "AND", "OR",
+    "NOT", "IN", "IS", "NULL", "LIKE", "BETWEEN", "AS", "SET", "VALUES", "WINDOW", "FOR", "RETURNING", "CASE",
+    "WHEN", "THEN", "ELSE", "END", "ASC", "DESC", "EXISTS", "TRUE", "FALSE", "STRAIGHT_JOIN", "LATERAL",
+];
+
+/// 运算符 `i` 左侧的 `[限定名.]列`
+fn column_before(tokens: &[Token], i: usize) -> Option<(Option<String>, String)> {
+    let column = tokens.get(i.checked_sub(1)?)?;
+    if column.is_reserved() {
+        return None;
+    }
+    let column = column.ident()?;
+    if i >= 3 && tokens[i - 2].is_symbol(".") {
+        return Some((tokens[i - 3].ident(), column));
+    }
+    Some((None, column))
+}
+
+/// 从 `i` 开始的 `[限定名.]列`（后面不能跟函数调用的括号或继续的运算）
+fn column_after(tokens: &[Token], i: usize) -> Option<(Option<String>, String)> {
+    let first = tokens.get(i)?;
+    if first.is_reserved() {
+        return None;
+    }
+    let first = first.ident()?;
+    let (qualifier, column, next) = if tokens.get(i + 1).is_some_and(|t| t.is_symbol(".")) {
+        (Some(first), tokens.get(i + 2)?.ident()?, i + 3)
+    } else {
+        (None, first, i + 1)
+    };
+    if tokens.get(next).is_some_and(|t| t.is_symbol("(") || t.is_symbol(".")) {
+        return None;
+    }
+    Some((qualifier, column))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// 未加引号的标识符或关键字
+    Word(String),
+    /// 加引号的标识符
+    Quoted(String),
+    /// 字符串字面量（不含引号）
+    Literal(String),
+    /// 数字或参数占位符
+    Value,
+    Symbol(String),
+}
+
+impl Token {
+    fn ident(&self) -> Option<String> {
+        match self {
+            Token::Word(w) | Token::Quoted(w) => Some(w.clone()),
+            _ => None,
+        }
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self, Token::Symbol(s) if s == symbol)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn is_reserved(&self) -> bool {
+        matches!(self, Token::Word(w) if RESERVED.iter().any(|k| w.eq_ignore_ascii_case(k)))
+    }
+}
+
+fn tokenize(sql: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while let Some(c) = sql[i..].chars().next() {
+        if let Some((kind, end)) = SqlLexer::skip_at(sql, i, Dialect::ANY) {
+            let text = || SqlLexer::unquote(&sql[i..end], Dialect::ANY);
+            match kind {
+                Skip::Comment => {}
+                Skip::String => tokens.push(Token::Literal(text())),
+                Skip::Identifier => tokens.push(Token::Quoted(text())),
+            }
+            i = end;
+            continue;
+        }
+        let run = |i: usize, part: fn(char) -> bool| sql[i..].find(|c: char| !part(c)).map_or(sql.len(), |p| i + p);
+        match c {
+            _ if c.is_whitespace() => i += c.len_utf8(),
+            '?' => {
+                tokens.push(Token::Value);
+                i += 1;
+            }
+            '$' | ':' if sql[i + 1..].starts_with(|n: char| n.is_alphanumeric() || n == '_') => {
+                i = run(i + 1, |c| c.is_alphanumeric() || c == '_');
+                tokens.push(Token::Value);
+            }
+            _ if c.is_ascii_digit() => {
+                i = run(i, |c| c.is_ascii_alphanumeric() || c == '.');
+                tokens.push(Token::Value);
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let end = run(i, |c| c.is_alphanumeric() || c == '_' || c == '$');
+                tokens.push(Token::Word(sql[i..end].to_string()));
+                i = end;
+            }
+            '<' | '>' | '!' | '=' => {
+                let end = run(i, |c| matches!(c, '<' | '>' | '!' | '='));
+                tokens.push(Token::Symbol(sql[i..end].to_string()));
+                i = end;
+            }
+            _ => {
+                tokens.push(Token::Symbol(c.to_string()));
+                i += c.len_utf8();
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_predicate_and_order_columns_per_table() {
+        let stmt = StatementColumns::parse(
+            "SELECT o.id, c.name FROM shop.orders AS o JOIN customers c ON c.id = o.customer_id \
+             WHERE o.status = 'paid' AND o.created_at >= ? AND LOWER(c.email) = 'x' AND c.name LIKE '%a' \
+             AND o.note <> '' ORDER BY o.created_at DESC, o.id",
+        );
+        assert_eq!(stmt.resolve_table("o"), Some("orders"));
+        assert_eq!(stmt.database("orders"), Some("shop"));
+        let orders = stmt.usage("orders").unwrap();
+        assert_eq!(orders.equality, ["customer_id", "status"]);
+        assert_eq!(orders.range, ["created_at"]);
+        assert_eq!(orders.order, ["created_at", "id"]);
+        let customers = stmt.usage("customers").unwrap();
+        assert_eq!(customers.equality, ["id"]);
+        assert!(customers.range.is_empty());
+    }
+
+    #[test]
+    fn unqualified_columns_need_a_single_table() {
+        let single = StatementColumns::parse("SELECT * FROM `users` WHERE email = :email AND age BETWEEN 1 AND 9");
+        let users = single.usage("users").unwrap();
+        assert_eq!(users.equality, ["email"]);
+        assert_eq!(users.range, ["age"]);
+
+        let joined = StatementColumns::parse("SELECT * FROM a, b WHERE x = 1");
+        assert!(joined.usage("a").unwrap().equality.is_empty());
+        assert!(joined.usage("b").unwrap().equality.is_empty());
+    }
+}
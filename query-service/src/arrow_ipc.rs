@@ -0,0 +1,226 @@
+//! Arrow IPC 流编码模块
+//!
+//! 用 `arrow-ipc` 的 `StreamWriter` 把查询结果编码为 Arrow IPC 流格式
+//! （`application/vnd.apache.arrow.stream`）：先写 Schema 消息，再按批写
+//! RecordBatch 消息，最后写结束标记。每写一条消息即可取走已编码的字节，
+//! 响应按消息分块输出。
+//!
+//! 列类型按 [`ValueKind`] 映射：整数为 Int64（超出 i64 范围时整列退化为 Utf8），
+//! 浮点为 Float64，布尔为 Bool，其余均为 Utf8；JSON 值写为 JSON 文本。
+//! 所有列均可为空。
+
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, RecordBatchOptions, StringArray};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use common::models::query::{ColumnInfo, ValueKind};
+use serde_json::Value;
+
+/// Arrow 列类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrowType {
+    Int64,
+    Float64,
+    Bool,
+    Utf8,
+}
+
+impl ArrowType {
+    /// 按列的值类型与实际值确定 Arrow 类型，整批结果共用同一 Schema
+    pub fn of(column: &ColumnInfo, index: usize, rows: &[Vec<Value>]) -> Self {
+        match column.kind {
+            Some(ValueKind::Integer) => {
+                let fits = rows
+                    .iter()
+                    .all(|row| row.get(index).is_none_or(|v| v.is_null() || v.is_i64()));
+                if fits {
+                    ArrowType::Int64
+                } else {
+                    ArrowType::Utf8
+                }
+            }
+            Some(ValueKind::Float) => ArrowType::Float64,
+            Some(ValueKind::Boolean) => ArrowType::Bool,
+            _ => ArrowType::Utf8,
+        }
+    }
+
+    fn data_type(self) -> DataType {
+        match self {
+            ArrowType::Int64 => DataType::Int64,
+            ArrowType::Float64 => DataType::Float64,
+            ArrowType::Bool => DataType::Boolean,
+            ArrowType::Utf8 => DataType::Utf8,
+        }
+    }
+
+    /// 一列的值；与类型不符的值写为空值
+    fn array<'a>(self, values: impl Iterator<Item = &'a Value>) -> ArrayRef {
+        match self {
+            ArrowType::Int64 => Arc::new(values.map(Value::as_i64).collect::<Int64Array>()),
+            ArrowType::Float64 => Arc::new(values.map(float).collect::<Float64Array>()),
+            ArrowType::Bool => Arc::new(values.map(Value::as_bool).collect::<BooleanArray>()),
+            ArrowType::Utf8 => Arc::new(
+                values
+                    .map(|value| match value {
+                        Value::Null => None,
+                        Value::String(s) => Some(s.clone()),
+                        other => Some(other.to_string()),
+                    })
+                    .collect::<StringArray>(),
+            ),
+        }
+    }
+}
+
+/// 按批写出的 Arrow IPC 流
+pub struct ArrowStream {
+    writer: StreamWriter<Vec<u8>>,
+    schema: SchemaRef,
+    types: Vec<ArrowType>,
+}
+
+impl ArrowStream {
+    /// 写出 Schema 消息，用 [`take`](Self::take) 取走
+    pub fn new(columns: &[ColumnInfo], types: Vec<ArrowType>) -> Result<Self, ArrowError> {
+        let fields: Vec<Field> = columns
+            .iter()
+            .zip(&types)
+            .map(|(column, ty)| Field::new(column.name.as_str(), ty.data_type(), true))
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+        let writer = StreamWriter::try_new(Vec::new(), &schema)?;
+        Ok(Self { writer, schema, types })
+    }
+
+    /// 写出一批行的 RecordBatch 消息
+    pub fn write(&mut self, rows: &[Vec<Value>]) -> Result<(), ArrowError> {
+        let columns = self
+            .types
+            .iter()
+            .enumerate()
+            .map(|(index, ty)| ty.array(rows.iter().map(|row| row.get(index).unwrap_or(&Value::Null))))
+            .collect();
+        // 没有列的结果仍需记录行数
+        let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+        let batch = RecordBatch::try_new_with_options(self.schema.clone(), columns, &options)?;
+        self.writer.write(&batch)
+    }
+
+    /// 写出流结束标记
+    pub fn finish(&mut self) -> Result<(), ArrowError> {
+        self.writer.finish()
+    }
+
+    /// 取走已编码的字节
+    pub fn take(&mut self) -> Vec<u8> {
+        std::mem::take(self.writer.get_mut())
+    }
+}
+
+/// 浮点值；NaN、Infinity 以字符串表示
+fn float(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, Int64Type};
+    use arrow_array::Array;
+    use arrow_ipc::reader::StreamReader;
+    use serde_json::json;
+
+    fn column(name: &str, kind: ValueKind) -> ColumnInfo {
+        ColumnInfo { name: name.into(), data_type: String::new(), nullable: None, kind: Some(kind) }
+    }
+
+    /// 编码整个流后用 `arrow-ipc` 的 StreamReader 读回
+    fn round_trip(columns: &[ColumnInfo], batches: &[Vec<Vec<Value>>]) -> (SchemaRef, Vec<RecordBatch>) {
+        let rows: Vec<Vec<Value>> = batches.concat();
+        let types = columns.iter().enumerate().map(|(i, c)| ArrowType::of(c, i, &rows)).collect();
+        let mut stream = ArrowStream::new(columns, types).unwrap();
+        let mut bytes = stream.take();
+        for batch in batches {
+            stream.write(batch).unwrap();
+            bytes.extend(stream.take());
+        }
+        stream.finish().unwrap();
+        bytes.extend(stream.take());
+
+        let reader = StreamReader::try_new(std::io::Cursor::new(bytes), None).unwrap();
+        let schema = reader.schema();
+        (schema, reader.collect::<Result<_, _>>().unwrap())
+    }
+
+    #[test]
+    fn round_trips_every_type_with_nulls() {
+        let columns = vec![
+            column("id", ValueKind::Integer),
+            column("big", ValueKind::Integer),
+            column("score", ValueKind::Float),
+            column("active", ValueKind::Boolean),
+            column("name", ValueKind::Text),
+            column("doc", ValueKind::Json),
+        ];
+        let batches = vec![
+            vec![
+                vec![json!(1), json!(1), json!(1.5), json!(true), json!("a"), json!({"k": 1})],
+                vec![Value::Null, json!(u64::MAX), json!("NaN"), Value::Null, Value::Null, Value::Null],
+            ],
+            // 空批
+            vec![],
+            vec![vec![json!(-3), Value::Null, Value::Null, json!(false), json!("é"), json!([1, 2])]],
+        ];
+
+        let (schema, read) = round_trip(&columns, &batches);
+        let types: Vec<&DataType> = schema.fields().iter().map(|f| f.data_type()).collect();
+        assert_eq!(
+            types,
+            [&DataType::Int64, &DataType::Utf8, &DataType::Float64, &DataType::Boolean, &DataType::Utf8, &DataType::Utf8]
+        );
+        assert!(schema.fields().iter().all(|f| f.is_nullable()));
+        assert_eq!(schema.field(4).name(), "name");
+        assert_eq!(read.iter().map(RecordBatch::num_rows).collect::<Vec<_>>(), [2, 0, 1]);
+
+        let first = &read[0];
+        let ids = first.column(0).as_primitive::<Int64Type>();
+        assert_eq!((ids.value(0), ids.is_null(1)), (1, true));
+        let big = first.column(1).as_string::<i32>();
+        assert_eq!(big.value(1), u64::MAX.to_string());
+        let scores = first.column(2).as_primitive::<Float64Type>();
+        assert_eq!(scores.value(0), 1.5);
+        assert!(scores.value(1).is_nan());
+        let active = first.column(3).as_boolean();
+        assert!(active.value(0) && active.is_null(1));
+        let names = first.column(4).as_string::<i32>();
+        assert_eq!((names.value(0), names.null_count()), ("a", 1));
+        assert_eq!(first.column(5).as_string::<i32>().value(0), r#"{"k":1}"#);
+
+        let last = &read[2];
+        assert_eq!(last.column(0).as_primitive::<Int64Type>().value(0), -3);
+        assert!(last.column(2).is_null(0));
+        assert!(!last.column(3).as_boolean().value(0));
+        assert_eq!(last.column(4).as_string::<i32>().value(0), "é");
+        assert_eq!(last.column(5).as_string::<i32>().value(0), "[1,2]");
+    }
+
+    #[test]
+    fn round_trips_empty_results() {
+        let columns = vec![column("id", ValueKind::Integer), column("name", ValueKind::Text)];
+        let (schema, read) = round_trip(&columns, &[]);
+        assert_eq!(schema.fields().len(), 2);
+        assert!(read.is_empty());
+
+        // 没有列的结果
+        let (schema, read) = round_trip(&[], &[vec![vec![], vec![]]]);
+        assert!(schema.fields().is_empty());
+        assert_eq!(read[0].num_rows(), 2);
+    }
+}
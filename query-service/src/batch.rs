@@ -0,0 +1,145 @@
+//! 批量查询模块
+//!
+//! 一次请求执行多条只读查询（可以在不同连接上），例如仪表盘一次加载多个
+//! 面板的数据，省去逐条请求的往返。每条查询按普通查询校验白名单、降级保护、
+//! 超时并使用结果缓存，互不影响：单条查询无效或失败只记入该条的结果。同时
+//! 执行的查询数有上限，避免一次请求占满连接服务。
+//!
+//! 配置：
+//! - `QUERY_BATCH_CONCURRENCY` - 同时执行的查询数（默认 4）
+
+use std::time::Instant;
+
+use futures::stream::{self, StreamExt};
+use validator::Validate;
+
+use common::errors::{AppError, AppResult};
+use common::models::query::{BatchQueryEntry, BatchQueryRequest, BatchQueryResult, QueryLanguage, QueryRequest};
+use common::utils::sql_lexer::Dialect;
+use common::utils::{ChangePreviewSql, SqlValidator};
+
+use crate::service::{QueryOutcome, QueryService};
+
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// 批量查询执行器
+#[derive(Debug, Clone, Copy)]
+pub struct Batch {
+    concurrency: usize,
+}
+
+impl Batch {
+    /// 从环境变量读取并发上限
+    pub fn from_env() -> Self {
+        let concurrency = std::env::var("QUERY_BATCH_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_CONCURRENCY);
+        Self { concurrency }
+    }
+
+    /// 执行每条查询，按请求中的顺序返回各自的结果或错误
+    pub async fn run(&self, service: &QueryService, req: BatchQueryRequest) -> BatchQueryResult {
+        let results: Vec<BatchQueryEntry> = stream::iter(req.queries)
+            .map(|query| async move {
+                let start = Instant::now();
+                let mut entry = BatchQueryEntry {
+                    connection_id: query.connection_id.clone(),
+                    result: None,
+                    cache: None,
+                    error_code: None,
+                    error: None,
+                    error_details: None,
+                    warning: None,
+                    duration_ms: 0,
+                };
+                match execute(service, query).await {
+                    Ok(outcome) => {
+                        entry.result = Some(outcome.result);
+                        entry.cache = outcome.cache;
+                        entry.warning = outcome.warning;
+                    }
+                    Err(e) => {
+                        tracing::warn!(connection_id = %entry.connection_id, error = %e, "Batch query failed");
+                        entry.error_code = Some(e.code().to_string());
+                        entry.error_details = e.details();
+                        entry.error = Some(e.to_string());
+                    }
+                }
+                entry.duration_ms = start.elapsed().as_millis() as u64;
+                entry
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        let succeeded = results.iter().filter(|e| e.result.is_some()).count();
+        tracing::info!(queries = results.len(), succeeded, "Batch query finished");
+        BatchQueryResult {
+            failed: results.len() - succeeded,
+            succeeded,
+            results,
+        }
+    }
+}
+
+/// 执行一条查询；只接受只读语句，UPDATE/DELETE 与 DDL 需要逐条预览确认，不能批量执行
+async fn execute(service: &QueryService, query: QueryRequest) -> AppResult<QueryOutcome> {
+    query.validate()?;
+    if query.query_language == QueryLanguage::Sql
+        && (ChangePreviewSql::is_change(&query.sql, Dialect::ANY) || SqlValidator::is_ddl(&query.sql))
+    {
+        return Err(AppError::InvalidInput("批量查询仅支持只读语句".to_string()));
+    }
+    service.execute(query).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use common::middleware::RequestSigner;
+
+    use crate::cache::QueryCache;
+    use crate::connections::ConnectionClient;
+    use crate::guard::TargetGuard;
+    use crate::preview::ChangePreviewStore;
+
+    #[tokio::test]
+    async fn reports_each_query_in_order() {
+        // 连接服务不可达：只读查询在请求连接信息时失败，其余查询在此之前就被拒绝
+        let connections = ConnectionClient::new(
+            "http://127.0.0.1:9",
+            reqwest::Client::new(),
+            RequestSigner::new("query-service", None),
+        )
+        .unwrap();
+        let service = QueryService::new(
+            connections,
+            Arc::new(QueryCache::new().await),
+            30_000,
+            TargetGuard::from_env(),
+            Arc::new(ChangePreviewStore::from_env()),
+        );
+        let req = BatchQueryRequest {
+            queries: vec![
+                QueryRequest::new("a", "DELETE FROM users"),
+                QueryRequest::new("b", ""),
+                QueryRequest::new("c", "SELECT 1"),
+                QueryRequest::new("d", "DROP TABLE users"),
+            ],
+        };
+
+        let result = Batch { concurrency: 2 }.run(&service, req).await;
+        let ids: Vec<&str> = result.results.iter().map(|e| e.connection_id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c", "d"]);
+        assert_eq!((result.succeeded, result.failed), (0, 4));
+        assert_eq!(result.results[0].error_code.as_deref(), Some("INVALID_INPUT"));
+        assert_eq!(result.results[1].error_code.as_deref(), Some("VALIDATION_ERROR"));
+        assert_ne!(result.results[2].error_code.as_deref(), Some("INVALID_INPUT"));
+        assert_eq!(result.results[3].error_code.as_deref(), Some("INVALID_INPUT"));
+    }
+}
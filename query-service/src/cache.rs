@@ -0,0 +1,357 @@
+//! 查询结果缓存模块
+//!
+//! 仪表盘等场景会反复执行相同的只读查询。请求携带 `cache_ttl_secs` 时，结果按
+//! 连接 ID + 规范化 SQL + 行数上限缓存：先查进程内 LRU，再查 Redis（如已配置），
+//! 未命中时执行查询并写入两级缓存。仅缓存只读语句，且结果超过大小上限时不缓存。
+//!
+//! 配置：
+//! - `QUERY_CACHE_REDIS_URL`（未设置时使用 `REDIS_URL`）- 未设置时仅使用内存缓存
+//! - `QUERY_CACHE_MAX_ENTRIES` - 内存 LRU 的最大条目数（默认 256，0 表示关闭内存缓存）
+//! - `QUERY_CACHE_MAX_TTL_SECS` - 请求 TTL 的上限（默认 3600）
+//! - `QUERY_CACHE_MAX_RESULT_BYTES` - 可缓存结果的最大字节数（默认 1 MiB）
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use common::models::query::{QueryRequest, QueryResult};
+use common::response::CacheInfo;
+use common::utils::sql_lexer::{Dialect, Skip, SqlLexer};
+
+const KEY_PREFIX: &str = "dbm:query:";
+const DEFAULT_MAX_ENTRIES: usize = 256;
+const DEFAULT_MAX_TTL_SECS: u64 = 3600;
+const DEFAULT_MAX_RESULT_BYTES: usize = 1024 * 1024;
+
+/// 可缓存的只读语句前缀
+const CACHEABLE_STATEMENTS: &[&str] = &["SELECT", "WITH", "SHOW", "DESCRIBE", "DESC", "EXPLAIN"];
+
+/// 缓存条目（同时作为 Redis 中的存储格式）
+#[derive(Clone, Serialize, Deserialize)]
+struct CachedResult {
+    result: QueryResult,
+    cached_at: DateTime<Utc>,
+    ttl_secs: u64,
+}
+
+/// 内存 LRU 条目
+struct MemoryEntry {
+    cached: CachedResult,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+/// 进程内 LRU：按访问序号淘汰最久未使用的条目
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, MemoryEntry>,
+    tick: u64,
+}
+
+/// 缓存键：连接、规范化 SQL、绑定参数与行数上限
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// 根据查询请求与目标库方言生成缓存键；非只读语句及 Cypher 查询返回 `None`
+    pub fn new(req: &QueryRequest, dialect: Dialect) -> Option<Self> {
+        if !req.query_language.is_sql() {
+            return None;
+        }
+        let normalized = normalize_sql(&req.sql, dialect);
+        let first = normalized
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .next()
+            .unwrap_or_default();
+        if !CACHEABLE_STATEMENTS.iter().any(|s| s.eq_ignore_ascii_case(first)) {
+            return None;
+        }
+        let params = serde_json::to_string(&(&req.params, &req.named_params)).unwrap_or_default();
+        let digest = Sha256::digest(
+            format!(
+                "{}\n{}\n{}\n{}",
+                req.limit.unwrap_or(0),
+                req.database.as_deref().unwrap_or_default(),
+                params,
+                normalized
+            )
+            .as_bytes(),
+        );
+        Some(Self(format!("{}{}:{}", KEY_PREFIX, req.connection_id, hex::encode(digest))))
+    }
+}
+
+/// 查询结果缓存
+pub struct QueryCache {
+    memory: Mutex<Lru>,
+    redis: Option<ConnectionManager>,
+    max_entries: usize,
+    max_ttl_secs: u64,
+    max_result_bytes: usize,
+}
+
+impl QueryCache {
+    /// 创建缓存，Redis 不可用时仅使用内存缓存
+    pub async fn new() -> Self {
+        fn env<T: std::str::FromStr>(key: &str, default: T) -> T {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let url = std::env::var("QUERY_CACHE_REDIS_URL")
+            .or_else(|_| std::env::var("REDIS_URL"))
+            .ok()
+            .filter(|u| !u.is_empty());
+        let redis = match url {
+            Some(url) => match connect(&url).await {
+                Ok(manager) => {
+                    tracing::info!("Query result cache: Redis enabled");
+                    Some(manager)
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Query result cache: Redis unavailable, using memory only");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        Self {
+            memory: Mutex::new(Lru::default()),
+            redis,
+            max_entries: env("QUERY_CACHE_MAX_ENTRIES", DEFAULT_MAX_ENTRIES),
+            max_ttl_secs: env("QUERY_CACHE_MAX_TTL_SECS", DEFAULT_MAX_TTL_SECS),
+            max_result_bytes: env("QUERY_CACHE_MAX_RESULT_BYTES", DEFAULT_MAX_RESULT_BYTES),
+        }
+    }
+
+    /// 将请求的 TTL 限制在上限内，0 表示不缓存
+    pub fn effective_ttl(&self, requested_secs: u64) -> u64 {
+        requested_secs.min(self.max_ttl_secs)
+    }
+
+    /// 读取缓存结果及命中信息
+    pub async fn get(&self, key: &CacheKey) -> Option<(QueryResult, CacheInfo)> {
+        if let Some(cached) = self.memory_get(key).await {
+            return Some(hit(cached, "memory"));
+        }
+
+        let mut redis = self.redis.clone()?;
+        let raw = match redis.get::<_, Option<String>>(&key.0).await {
+            Ok(raw) => raw?,
+            Err(e) => {
+                tracing::warn!(error = %e, "Query cache read failed");
+                return None;
+            }
+        };
+        let cached: CachedResult = serde_json::from_str(&raw).ok()?;
+        let remaining = (cached.cached_at + chrono::Duration::seconds(cached.ttl_secs as i64) - Utc::now())
+            .to_std()
+            .ok()?;
+        self.memory_put(key, cached.clone(), remaining).await;
+        Some(hit(cached, "redis"))
+    }
+
+    /// 写入缓存，返回未命中的缓存信息；结果过大时不缓存
+    pub async fn put(&self, key: &CacheKey, result: &QueryResult, ttl_secs: u64) -> CacheInfo {
+        let miss = CacheInfo {
+            hit: false,
+            layer: None,
+            cached_at: None,
+            ttl_secs,
+        };
+        let cached = CachedResult {
+            result: result.clone(),
+            cached_at: Utc::now(),
+            ttl_secs,
+        };
+        let Ok(raw) = serde_json::to_string(&cached) else {
+            return miss;
+        };
+        if raw.len() > self.max_result_bytes {
+            tracing::debug!(bytes = raw.len(), "Query result too large to cache");
+            return CacheInfo { ttl_secs: 0, ..miss };
+        }
+
+        self.memory_put(key, cached, Duration::from_secs(ttl_secs)).await;
+        if let Some(mut redis) = self.redis.clone() {
+            if let Err(e) = redis.set_ex::<_, _, ()>(&key.0, raw, ttl_secs).await {
+                tracing::warn!(error = %e, "Query cache write failed");
+            }
+        }
+        miss
+    }
+
+    async fn memory_get(&self, key: &CacheKey) -> Option<CachedResult> {
+        let mut lru = self.memory.lock().await;
+        lru.tick += 1;
+        let tick = lru.tick;
+        let entry = lru.entries.get_mut(&key.0)?;
+        if entry.expires_at <= Instant::now() {
+            lru.entries.remove(&key.0);
+            return None;
+        }
+        entry.last_used = tick;
+        Some(entry.cached.clone())
+    }
+
+    async fn memory_put(&self, key: &CacheKey, cached: CachedResult, ttl: Duration) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut lru = self.memory.lock().await;
+        lru.tick += 1;
+        let now = Instant::now();
+        lru.entries.retain(|_, e| e.expires_at > now);
+        if lru.entries.len() >= self.max_entries && !lru.entries.contains_key(&key.0) {
+            let oldest = lru
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                lru.entries.remove(&oldest);
+            }
+        }
+        let entry = MemoryEntry {
+            cached,
+            expires_at: now + ttl,
+            last_used: lru.tick,
+        };
+        lru.entries.insert(key.0.clone(), entry);
+    }
+}
+
+fn hit(cached: CachedResult, layer: &str) -> (QueryResult, CacheInfo) {
+    let info = CacheInfo {
+        hit: true,
+        layer: Some(layer.to_string()),
+        cached_at: Some(cached.cached_at),
+        ttl_secs: cached.ttl_secs,
+    };
+    (cached.result, info)
+}
+
+async fn connect(url: &str) -> redis::RedisResult<ConnectionManager> {
+    let client = redis::Client::open(url)?;
+    ConnectionManager::new(client).await
+}
+
+/// 规范化 SQL：去除注释、合并空白、去掉末尾分号，字符串与引用标识符保持原样
+///
+/// 注释、字符串与引用标识符按 `dialect` 识别（如 PostgreSQL 的 `$$...$$` 字符串、
+/// MySQL 的 `#` 注释），不同语句不会规范化为同一文本。
+pub(crate) fn normalize_sql(sql: &str, dialect: Dialect) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut pending_space = false;
+    let mut i = 0;
+
+    while let Some(c) = sql[i..].chars().next() {
+        if c.is_whitespace() {
+            pending_space = true;
+            i += c.len_utf8();
+            continue;
+        }
+        let skip = SqlLexer::skip_at(sql, i, dialect);
+        if let Some((Skip::Comment, end)) = skip {
+            pending_space = true;
+            i = end;
+            continue;
+        }
+        if pending_space && !out.is_empty() {
+            out.push(' ');
+        }
+        pending_space = false;
+
+        match skip {
+            Some((_, end)) => {
+                out.push_str(&sql[i..end]);
+                i = end;
+            }
+            None => {
+                out.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+
+    out.trim_end_matches(|c: char| c == ';' || c.is_whitespace()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::connection::DbType;
+
+    #[test]
+    fn normalizes_whitespace_and_comments_but_not_literals() {
+        assert_eq!(
+            normalize_sql("  SELECT *\n\tFROM t -- note\n WHERE name = 'a  b' /* x */ ;", Dialect::ANY),
+            "SELECT * FROM t WHERE name = 'a  b'"
+        );
+        let request = |sql: &str, limit: u32, params: Vec<serde_json::Value>| QueryRequest {
+            connection_id: "c1".to_string(),
+            sql: sql.to_string(),
+            database: None,
+            limit: Some(limit),
+            params,
+            named_params: Default::default(),
+            timeout_ms: None,
+            cache_ttl_secs: Some(60),
+            confirmation_token: None,
+            query_language: Default::default(),
+        };
+        let key = |req: QueryRequest| CacheKey::new(&req, Dialect::ANY);
+        assert_eq!(key(request("select 1", 10, vec![])), key(request("select   1;", 10, vec![])));
+        assert_ne!(key(request("select 1", 10, vec![])), key(request("select 1", 20, vec![])));
+        assert_ne!(
+            key(request("select ?", 10, vec![serde_json::json!(1)])),
+            key(request("select ?", 10, vec![serde_json::json!(2)]))
+        );
+        let other_database = QueryRequest {
+            database: Some("archive".to_string()),
+            ..request("select 1", 10, vec![])
+        };
+        assert_ne!(key(request("select 1", 10, vec![])), key(other_database));
+        assert!(key(request("DELETE FROM t", 10, vec![])).is_none());
+    }
+
+    #[test]
+    fn dollar_quoted_text_is_part_of_the_postgres_cache_key() {
+        let postgres = Dialect::of(&DbType::Postgres);
+        // `--` 在 $$...$$ 中是字符串内容，不是注释
+        assert_eq!(
+            normalize_sql("SELECT $$a -- b$$ AS x", postgres),
+            "SELECT $$a -- b$$ AS x"
+        );
+        let request = |sql: &str| QueryRequest {
+            connection_id: "c1".to_string(),
+            sql: sql.to_string(),
+            database: None,
+            limit: Some(10),
+            params: vec![],
+            named_params: Default::default(),
+            timeout_ms: None,
+            cache_ttl_secs: Some(60),
+            confirmation_token: None,
+            query_language: Default::default(),
+        };
+        assert_ne!(
+            CacheKey::new(&request("SELECT $$a -- b$$ AS x"), postgres),
+            CacheKey::new(&request("SELECT $$a -- c$$ AS x"), postgres)
+        );
+        // PostgreSQL 中 `#` 是运算符
+        assert_ne!(
+            CacheKey::new(&request("SELECT 5 # 1"), postgres),
+            CacheKey::new(&request("SELECT 5 # 2"), postgres)
+        );
+    }
+}
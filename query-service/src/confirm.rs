@@ -0,0 +1,105 @@
+//! 危险语句确认模块
+//!
+//! 不带 WHERE 的 UPDATE/DELETE 与 DDL（CREATE、ALTER、DROP、TRUNCATE、RENAME）
+//! 不直接执行：首次提交返回 `REQUIRES_CONFIRMATION`（HTTP 428），附带风险说明、
+//! 预估影响行数与确认令牌，客户端携带令牌重新提交同一语句才会执行。令牌与变更
+//! 预览共用 [`ChangePreviewStore`](crate::preview::ChangePreviewStore)，同样绑定
+//! 连接、语句与参数，只能使用一次。
+
+use common::models::connection::DbType;
+use common::models::query::DangerousStatementKind;
+use common::utils::sql_lexer::Dialect;
+use common::utils::{quote_ident, ChangePreviewSql, SqlTableExtractor, SqlValidator};
+
+/// 需要确认的语句及原因
+pub struct Danger {
+    pub kind: DangerousStatementKind,
+    pub reason: String,
+}
+
+/// 判断语句是否需要确认，普通语句返回 `None`
+pub fn classify(sql: &str, dialect: Dialect) -> Option<Danger> {
+    let verb = leading_keyword(sql);
+    if SqlValidator::is_ddl(sql) {
+        let reason = match verb.as_str() {
+            "DROP" => "DROP 将删除对象及其全部数据，且无法回滚",
+            "TRUNCATE" => "TRUNCATE 将清空表中全部数据，且无法回滚",
+            "ALTER" => "ALTER 将修改表结构，可能重写全表并长时间锁表",
+            "RENAME" => "RENAME 将重命名表，依赖原表名的查询会失败",
+            _ => "CREATE 将创建新的数据库对象",
+        };
+        return Some(Danger {
+            kind: DangerousStatementKind::Ddl,
+            reason: reason.to_string(),
+        });
+    }
+    ChangePreviewSql::is_unfiltered(sql, dialect).then(|| Danger {
+        kind: DangerousStatementKind::UnfilteredChange,
+        reason: format!("{} 没有 WHERE 条件，将修改表中的所有行", verb),
+    })
+}
+
+/// 预估影响行数的查询，结果列为 `affected_rows`；无法预估时返回 `None`
+///
+/// UPDATE/DELETE 统计匹配的行数；DROP / TRUNCATE 统计所涉及各表的总行数，
+/// ALTER 统计被修改表的行数；CREATE 与 RENAME 不涉及已有数据。
+pub fn estimate_sql(sql: &str, db_type: &str) -> Option<String> {
+    let dialect = Dialect::named(db_type);
+    if ChangePreviewSql::is_change(sql, dialect) {
+        return ChangePreviewSql::to_count(sql, dialect);
+    }
+    let tables = SqlTableExtractor::extract(sql);
+    let tables = match leading_keyword(sql).as_str() {
+        "DROP" | "TRUNCATE" => &tables[..],
+        "ALTER" => &tables[..tables.len().min(1)],
+        _ => return None,
+    };
+    if tables.is_empty() {
+        return None;
+    }
+    let db_type = db_type.parse::<DbType>().ok()?;
+    let counts: Vec<String> = tables
+        .iter()
+        .map(|t| {
+            let name = match &t.database {
+                Some(database) => format!("{}.{}", quote_ident(&db_type, database), quote_ident(&db_type, &t.table)),
+                None => quote_ident(&db_type, &t.table),
+            };
+            format!("(SELECT COUNT(*) FROM {})", name)
+        })
+        .collect();
+    Some(format!("SELECT {} AS affected_rows", counts.join(" + ")))
+}
+
+fn leading_keyword(sql: &str) -> String {
+    sql.split_whitespace().next().unwrap_or_default().to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_and_estimates_dangerous_statements() {
+        assert!(classify("UPDATE users SET active = 0 WHERE id = 1", Dialect::ANY).is_none());
+        assert!(classify("SELECT * FROM users", Dialect::ANY).is_none());
+        let danger = classify("delete from logs", Dialect::ANY).unwrap();
+        assert_eq!(danger.kind, DangerousStatementKind::UnfilteredChange);
+        assert!(danger.reason.starts_with("DELETE"));
+        assert_eq!(classify("TRUNCATE TABLE logs", Dialect::ANY).unwrap().kind, DangerousStatementKind::Ddl);
+
+        assert_eq!(
+            estimate_sql("DROP TABLE IF EXISTS shop.orders, Archive", "postgres").as_deref(),
+            Some("SELECT (SELECT COUNT(*) FROM \"shop\".\"orders\") + (SELECT COUNT(*) FROM \"Archive\") AS affected_rows")
+        );
+        assert_eq!(
+            estimate_sql("ALTER TABLE orders ADD COLUMN note TEXT", "mysql").as_deref(),
+            Some("SELECT (SELECT COUNT(*) FROM `orders`) AS affected_rows")
+        );
+        assert_eq!(estimate_sql("CREATE TABLE t (id INT)", "mysql"), None);
+        assert_eq!(
+            estimate_sql("DELETE FROM logs", "mysql").as_deref(),
+            Some("SELECT COUNT(*) AS affected_rows FROM (SELECT 1 AS matched FROM logs) AS matched_rows")
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! 连接服务客户端模块
+//!
+//! 经连接服务的内部 gRPC 接口（`common/proto/connection.proto`）读取连接信息、
+//! 执行只读查询与已确认的变更。调用带有内部签名，发起请求的主体放在
+//! `x-principal` 元数据中，连接服务按该主体统计用量并检查配额。连接只在
+//! 首次调用时建立，HTTP/2 连接在调用之间复用。
+//!
+//! 表统计信息（索引建议读取已有索引）没有对应的 RPC，仍走连接服务的 HTTP API。
+
+use common::errors::{AppError, AppResult};
+use common::grpc::{self, proto, ConnectionServiceClient};
+use common::middleware::auth::PRINCIPAL_HEADER;
+use common::middleware::{RequestSigner, SendSigned};
+use common::models::database::TableStats;
+use common::models::query::{QueryRequest, QueryResult};
+use tonic::metadata::MetadataValue;
+use tonic::{Code, Status};
+
+/// 请求未指定 `limit` 时返回的最大行数
+const DEFAULT_LIMIT: u32 = 1000;
+
+/// 连接服务客户端
+#[derive(Clone)]
+pub struct ConnectionClient {
+    client: ConnectionServiceClient,
+    url: String,
+    http_client: reqwest::Client,
+    signer: RequestSigner,
+}
+
+impl ConnectionClient {
+    /// 创建连接服务 `url` 的客户端
+    ///
+    /// # Errors
+    /// `url` 不是有效地址时返回 `AppError::Configuration`
+    pub fn new(url: &str, http_client: reqwest::Client, signer: RequestSigner) -> AppResult<Self> {
+        let client = grpc::connection_client(url, signer.clone())
+            .map_err(|e| AppError::Configuration(format!("连接服务地址 {} 无效: {}", url, e)))?;
+        Ok(Self {
+            client,
+            url: url.to_string(),
+            http_client,
+            signer,
+        })
+    }
+
+    /// 代 `principal` 读取连接信息；连接不存在或对其不可见时返回 `ConnectionNotFound`，
+    /// 连接服务不可用时返回 `ExternalService`
+    pub async fn pool_info(&self, connection_id: &str, principal: Option<&str>) -> AppResult<proto::PoolInfo> {
+        let mut request = tonic::Request::new(proto::PoolInfoRequest {
+            connection_id: connection_id.to_string(),
+        });
+        if let Some(principal) = principal.and_then(|p| MetadataValue::try_from(p).ok()) {
+            request.metadata_mut().insert(PRINCIPAL_HEADER, principal);
+        }
+        match self.client.clone().get_pool_info(request).await {
+            Ok(response) => Ok(response.into_inner()),
+            Err(status) if status.code() == Code::NotFound => Err(AppError::ConnectionNotFound(connection_id.to_string())),
+            Err(status) => Err(AppError::ExternalService(format!("无法从连接服务获取连接信息: {}", status.message()))),
+        }
+    }
+
+    /// 代 `principal` 执行只读查询
+    pub async fn execute(&self, req: &QueryRequest, principal: Option<&str>, timeout_ms: u64) -> Result<QueryResult, Status> {
+        let response = self.client.clone().execute_query(request(req, principal, timeout_ms)).await?;
+        result(response.into_inner())
+    }
+
+    /// 代 `principal` 执行已确认的 UPDATE/DELETE 或 DDL
+    pub async fn execute_change(&self, req: &QueryRequest, principal: Option<&str>, timeout_ms: u64) -> Result<QueryResult, Status> {
+        let response = self.client.clone().execute_change(request(req, principal, timeout_ms)).await?;
+        result(response.into_inner())
+    }
+
+    /// 代 `principal` 读取表统计信息，失败时返回 `None`
+    pub async fn table_stats(
+        &self,
+        connection_id: &str,
+        principal: Option<&str>,
+        database: Option<&str>,
+        table: &str,
+    ) -> Option<TableStats> {
+        let mut url = reqwest::Url::parse(&self.url).ok()?;
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments
+                .pop_if_empty()
+                .extend(["api", "connections", connection_id, "tables", table, "stats"]);
+        }
+        let mut request = self.http_client.get(url);
+        if let Some(database) = database {
+            request = request.query(&[("database", database)]);
+        }
+        if let Some(principal) = principal {
+            request = request.header(PRINCIPAL_HEADER, principal);
+        }
+
+        match request.send_signed(&self.signer).await {
+            Ok(response) => response.json::<serde_json::Value>().await.ok().and_then(|body| {
+                serde_json::from_value::<TableStats>(body["data"].clone()).ok()
+            }),
+            Err(e) => {
+                tracing::warn!(connection_id, table, error = %e, "Failed to load table stats");
+                None
+            }
+        }
+    }
+}
+
+/// 构造查询请求，主体放在 `x-principal` 元数据中
+fn request(req: &QueryRequest, principal: Option<&str>, timeout_ms: u64) -> tonic::Request<proto::QueryRequest> {
+    let mut request = tonic::Request::new(proto::QueryRequest {
+        connection_id: req.connection_id.clone(),
+        sql: req.sql.clone(),
+        params: req.params.iter().map(serde_json::Value::to_string).collect(),
+        named_params: req
+            .named_params
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect(),
+        limit: Some(req.limit.unwrap_or(DEFAULT_LIMIT)),
+        timeout_ms: Some(timeout_ms),
+        database: req.database.clone(),
+        query_language: proto::QueryLanguage::from(req.query_language) as i32,
+    });
+    if let Some(principal) = principal.and_then(|p| MetadataValue::try_from(p).ok()) {
+        request.metadata_mut().insert(PRINCIPAL_HEADER, principal);
+    }
+    request
+}
+
+fn result(result: proto::QueryResult) -> Result<QueryResult, Status> {
+    QueryResult::try_from(result).map_err(|e| Status::internal(format!("连接服务返回无效结果: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn builds_query_requests() {
+        let mut req = QueryRequest::new("c1", "SELECT * FROM users WHERE id = ? AND name = ?");
+        req.params = vec![json!(1), json!("O'Brien")];
+        req.limit = None;
+        let request = request(&req, Some("user:alice"), 5000);
+        assert_eq!(request.metadata().get(PRINCIPAL_HEADER).unwrap(), "user:alice");
+
+        let message = request.into_inner();
+        assert_eq!(message.params, ["1", "\"O'Brien\""]);
+        assert_eq!((message.limit, message.timeout_ms), (Some(DEFAULT_LIMIT), Some(5000)));
+        assert_eq!(message.query_language(), proto::QueryLanguage::Sql);
+    }
+}
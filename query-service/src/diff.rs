@@ -0,0 +1,51 @@
+//! 查询结果对比模块
+//!
+//! 执行两条只读查询（可以在不同连接上，例如发布前后的快照表或预发与生产），
+//! 按指定的键列对齐两边的行，返回只在右边出现的行（新增）、只在左边出现的
+//! 行（删除）以及两边都有但共有列取值不同的行（变更）。
+//!
+//! 两边各自按普通查询校验白名单、降级保护、超时与脱敏；每边最多读取
+//! `limit` 行，超出部分不参与对比。
+
+use std::time::Instant;
+
+use common::errors::{AppError, AppResult};
+use common::models::query::{QueryDiffRequest, QueryDiffResult};
+use common::utils::sql_lexer::Dialect;
+use common::utils::{ChangePreviewSql, ResultDiff, SqlValidator};
+
+use crate::service::QueryService;
+
+/// 执行两边的查询并对比结果
+pub async fn diff(service: &QueryService, req: &QueryDiffRequest) -> AppResult<QueryDiffResult> {
+    for side in [&req.left, &req.right] {
+        if ChangePreviewSql::is_change(&side.sql, Dialect::ANY) || SqlValidator::is_ddl(&side.sql) {
+            return Err(AppError::InvalidInput("结果对比仅支持只读语句".to_string()));
+        }
+        SqlValidator::validate(&side.sql)?;
+    }
+
+    let start = Instant::now();
+    let (left, right) = futures::try_join!(
+        service.execute(req.query(&req.left)),
+        service.execute(req.query(&req.right)),
+    )?;
+    let limit = req.limit.unwrap_or(QueryDiffRequest::DEFAULT_LIMIT) as usize;
+    let truncated = [&left.result, &right.result]
+        .iter()
+        .any(|r| r.truncated || r.rows.len() >= limit);
+
+    let mut result = ResultDiff::compare(&left.result, &right.result, &req.key_columns)?;
+    result.truncated = truncated;
+    result.duration_ms = start.elapsed().as_millis() as u64;
+    tracing::info!(
+        left = %req.left.connection_id,
+        right = %req.right.connection_id,
+        added = result.added.len(),
+        removed = result.removed.len(),
+        changed = result.changed.len(),
+        truncated,
+        "Query diff finished"
+    );
+    Ok(result)
+}
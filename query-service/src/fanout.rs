@@ -0,0 +1,90 @@
+//! 扇出查询模块
+//!
+//! 同一条只读语句在多个连接上执行（例如在开发、预发、生产副本上跑同一项
+//! 检查），每个连接按普通查询校验白名单、降级保护与超时，互不影响：单个
+//! 连接失败只记入该连接的结果。同时执行的连接数有上限，避免一次请求占满
+//! 连接服务。
+//!
+//! 配置：
+//! - `QUERY_FANOUT_CONCURRENCY` - 同时执行的连接数（默认 4）
+
+use std::time::Instant;
+
+use futures::stream::{self, StreamExt};
+
+use common::errors::{AppError, AppResult};
+use common::models::query::{FanOutEntry, FanOutQueryRequest, FanOutResult};
+use common::utils::sql_lexer::Dialect;
+use common::utils::{ChangePreviewSql, SqlValidator};
+
+use crate::service::QueryService;
+
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// 扇出查询执行器
+#[derive(Debug, Clone, Copy)]
+pub struct FanOut {
+    concurrency: usize,
+}
+
+impl FanOut {
+    /// 从环境变量读取并发上限
+    pub fn from_env() -> Self {
+        let concurrency = std::env::var("QUERY_FANOUT_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_CONCURRENCY);
+        Self { concurrency }
+    }
+
+    /// 在每个连接上执行语句，按请求中的连接顺序返回各自的结果
+    ///
+    /// 只接受只读语句；UPDATE/DELETE 与 DDL 需要逐个连接预览确认，不能扇出。
+    pub async fn run(&self, service: &QueryService, req: &FanOutQueryRequest) -> AppResult<FanOutResult> {
+        if ChangePreviewSql::is_change(&req.sql, Dialect::ANY) || SqlValidator::is_ddl(&req.sql) {
+            return Err(AppError::InvalidInput("扇出查询仅支持只读语句".to_string()));
+        }
+        SqlValidator::validate(&req.sql)?;
+
+        let connection_ids: Vec<String> = req.unique_connection_ids().into_iter().map(str::to_string).collect();
+        let results: Vec<FanOutEntry> = stream::iter(connection_ids)
+            .map(|connection_id| async move {
+                let start = Instant::now();
+                let outcome = service.execute(req.for_connection(&connection_id)).await;
+                let mut entry = FanOutEntry {
+                    connection_id,
+                    result: None,
+                    error_code: None,
+                    error: None,
+                    error_details: None,
+                    warning: None,
+                    duration_ms: start.elapsed().as_millis() as u64,
+                };
+                match outcome {
+                    Ok(outcome) => {
+                        entry.result = Some(outcome.result);
+                        entry.warning = outcome.warning;
+                    }
+                    Err(e) => {
+                        tracing::warn!(connection_id = %entry.connection_id, error = %e, "Fan-out query failed on connection");
+                        entry.error_code = Some(e.code().to_string());
+                        entry.error_details = e.details();
+                        entry.error = Some(e.to_string());
+                    }
+                }
+                entry
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        let succeeded = results.iter().filter(|e| e.result.is_some()).count();
+        tracing::info!(connections = results.len(), succeeded, "Fan-out query finished");
+        Ok(FanOutResult {
+            failed: results.len() - succeeded,
+            succeeded,
+            results,
+        })
+    }
+}
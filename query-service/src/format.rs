@@ -0,0 +1,62 @@
+//! SQL 格式化模块
+//!
+//! 按方言的引号与注释规则拆分语句，再逐条交给 `sqlformat` 排版：子句换行、
+//! 缩进与关键字大写。只调整空白与关键字大小写，不改变语句语义；占位符、
+//! 字符串与加引号的标识符保持原样。
+
+use common::models::connection::DbType;
+use common::models::query::{FormatSqlRequest, FormattedSql};
+use common::utils::SqlSplitter;
+use sqlformat::{FormatOptions, Indent, QueryParams};
+
+/// 格式化 SQL 文本，多条语句之间空一行
+pub fn format_sql(req: &FormatSqlRequest) -> FormattedSql {
+    let dialect = req.dialect.clone().unwrap_or(DbType::Postgres);
+    let options = FormatOptions {
+        indent: if req.use_tabs { Indent::Tabs } else { Indent::Spaces(req.indent) },
+        uppercase: req.uppercase,
+        lines_between_queries: 1,
+    };
+
+    let statements: Vec<String> = SqlSplitter::split(&req.sql, &dialect)
+        .iter()
+        .map(|statement| {
+            let formatted = sqlformat::format(statement, &QueryParams::None, options);
+            format!("{};", formatted.trim_end())
+        })
+        .collect();
+
+    FormattedSql {
+        sql: statements.join("\n\n"),
+        statement_count: statements.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(sql: &str, dialect: Option<DbType>) -> FormatSqlRequest {
+        FormatSqlRequest {
+            sql: sql.to_string(),
+            dialect,
+            indent: 4,
+            use_tabs: false,
+            uppercase: true,
+        }
+    }
+
+    #[test]
+    fn formats_each_statement_and_keeps_literals() {
+        let formatted = format_sql(&request(
+            "select id, name from `users` where note = 'a;b' and id = ?; delete from t where id = 1",
+            Some(DbType::MySQL),
+        ));
+        assert_eq!(formatted.statement_count, 2);
+        assert_eq!(
+            formatted.sql,
+            "SELECT\n    id,\n    name\nFROM\n    `users`\nWHERE\n    note = 'a;b'\n    AND id = ?;\n\n\
+             DELETE FROM\n    t\nWHERE\n    id = 1;"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! 降级目标保护模块
+//!
+//! 连接服务定期检查目标库的连接数占用与复制状态，并在连接池信息中返回最近
+//! 一次检查结果。目标库处于降级状态时，按策略对重查询（大结果集、联表、
+//! 聚合、排序、无条件全表扫描以及所有异步查询）追加告警或直接拒绝，避免
+//! 把压力继续压到已经吃紧的库上。轻量查询不受影响。
+//!
+//! 配置：
+//! - `DEGRADED_TARGET_POLICY` - `off` / `warn` / `reject`（默认 warn）
+//! - `QUERY_HEAVY_ROW_LIMIT` - 行数上限超过该值的查询视为重查询（默认 10000）
+
+use common::errors::{AppError, AppResult};
+use common::models::monitor::TargetHealth;
+
+const DEFAULT_HEAVY_ROW_LIMIT: u32 = 10_000;
+
+/// 请求未指定行数上限时连接服务使用的默认值
+const DEFAULT_QUERY_LIMIT: u32 = 1000;
+
+/// 视为重查询的 SQL 关键字
+const HEAVY_KEYWORDS: [&str; 5] = ["JOIN", "GROUP", "DISTINCT", "UNION", "ORDER"];
+
+/// 目标库降级时的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DegradedTargetPolicy {
+    /// 不检查
+    Off,
+    /// 照常执行，在响应中附加告警
+    Warn,
+    /// 拒绝执行，返回 DEGRADED_TARGET
+    Reject,
+}
+
+/// 降级目标保护
+#[derive(Debug, Clone)]
+pub struct TargetGuard {
+    policy: DegradedTargetPolicy,
+    heavy_row_limit: u32,
+}
+
+impl TargetGuard {
+    /// 创建保护策略
+    pub fn new(policy: DegradedTargetPolicy, heavy_row_limit: u32) -> Self {
+        Self { policy, heavy_row_limit }
+    }
+
+    /// 从环境变量读取策略
+    pub fn from_env() -> Self {
+        let policy = match std::env::var("DEGRADED_TARGET_POLICY")
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "off" => DegradedTargetPolicy::Off,
+            "reject" => DegradedTargetPolicy::Reject,
+            _ => DegradedTargetPolicy::Warn,
+        };
+        let heavy_row_limit = std::env::var("QUERY_HEAVY_ROW_LIMIT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_HEAVY_ROW_LIMIT);
+        Self::new(policy, heavy_row_limit)
+    }
+
+    /// 检查查询能否在目标库上执行，返回需要附加到响应的告警
+    ///
+    /// `always_heavy` 为 true 时（异步查询）不再分析 SQL，直接视为重查询。
+    ///
+    /// # Errors
+    /// 策略为 reject 且目标库降级、查询为重查询时返回 `AppError::DegradedTarget`。
+    pub fn check(
+        &self,
+        health: Option<&TargetHealth>,
+        sql: &str,
+        limit: Option<u32>,
+        always_heavy: bool,
+    ) -> AppResult<Option<String>> {
+        let Some(health) = health.filter(|h| h.is_degraded()) else {
+            return Ok(None);
+        };
+        if self.policy == DegradedTargetPolicy::Off || !(always_heavy || self.is_heavy(sql, limit)) {
+            return Ok(None);
+        }
+
+        tracing::warn!(
+            connection_id = %health.connection_id,
+            reasons = ?health.reasons,
+            policy = ?self.policy,
+            "重查询命中降级目标库"
+        );
+        match self.policy {
+            DegradedTargetPolicy::Reject => Err(AppError::DegradedTarget(Box::new(health.clone()))),
+            _ => Ok(Some(format!(
+                "目标库 {} 处于降级状态（{}），重查询可能加重负载",
+                health.connection_id,
+                health.reasons.join("；")
+            ))),
+        }
+    }
+
+    /// 判断查询是否为重查询
+    pub fn is_heavy(&self, sql: &str, limit: Option<u32>) -> bool {
+        if limit.unwrap_or(DEFAULT_QUERY_LIMIT) > self.heavy_row_limit {
+            return true;
+        }
+        let upper = sql.to_ascii_uppercase();
+        let words: Vec<&str> = upper
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .filter(|w| !w.is_empty())
+            .collect();
+        if words.iter().any(|w| HEAVY_KEYWORDS.contains(w)) {
+            return true;
+        }
+        words.contains(&"FROM") && !words.contains(&"WHERE")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::monitor::TargetHealthStatus;
+
+    fn health(status: TargetHealthStatus) -> TargetHealth {
+        TargetHealth {
+            connection_id: "c1".to_string(),
+            status,
+            reasons: vec!["replication lag 600 s".to_string()],
+            connection_usage: Some(0.5),
+            replication_running: Some(true),
+            replication_lag_secs: Some(600),
+            checked_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn heavy_queries_on_degraded_targets_follow_policy() {
+        let guard = TargetGuard::new(DegradedTargetPolicy::Reject, 10_000);
+        assert!(!guard.is_heavy("SELECT * FROM users WHERE id = 1", None));
+        assert!(guard.is_heavy("SELECT * FROM users", None));
+        assert!(guard.is_heavy("SELECT * FROM a JOIN b ON a.id = b.id WHERE a.x = 1", None));
+        assert!(guard.is_heavy("SELECT * FROM users WHERE id = 1", Some(50_000)));
+
+        let degraded = health(TargetHealthStatus::Degraded);
+        let light = "SELECT * FROM users WHERE id = 1";
+        assert!(guard.check(Some(&degraded), light, None, false).unwrap().is_none());
+        assert!(guard.check(Some(&degraded), light, None, true).is_err());
+        assert!(guard.check(Some(&degraded), "SELECT * FROM users", None, false).is_err());
+        assert!(guard
+            .check(Some(&health(TargetHealthStatus::Healthy)), "SELECT * FROM users", None, false)
+            .unwrap()
+            .is_none());
+
+        let warn = TargetGuard::new(DegradedTargetPolicy::Warn, 10_000);
+        assert!(warn.check(Some(&degraded), "SELECT * FROM users", None, false).unwrap().is_some());
+    }
+}
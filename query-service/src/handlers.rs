@@ -1,19 +1,45 @@
 //! Handler模块
 
-use axum::{
-    extract::State,
-    Json,
-};
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
 use chrono::{DateTime, Utc};
 use serde::Serialize;
 use utoipa::ToSchema;
+use validator::Validate;
 
+use common::admin;
+use common::config::ConfigReload;
 use common::errors::AppError;
-use common::models::query::{QueryRequest, QueryResult};
+use common::extract::Json;
+use common::logging;
+use common::probes::{Liveness, Readiness};
+use common::middleware::auth::principal;
+use common::models::analysis::IndexAdvice;
+use common::models::query::{
+    BatchQueryRequest, BatchQueryResult, ChangePreview, FanOutQueryRequest, FanOutResult, FormatSqlRequest, FormattedSql, QueryDiffRequest,
+    QueryDiffResult, QueryJob, QueryRequest, QueryResult,
+};
+use common::progress::{ProgressEvent, ProgressSubscription};
 use common::response::ApiResponse;
+use crate::diff;
+use crate::format;
+use crate::result_format::{self, ResultFormat};
 use crate::service::QueryService;
 use crate::state::AppState;
 
+fn query_service(state: &AppState) -> QueryService {
+    QueryService::new(
+        state.connections.clone(),
+        state.query_cache.clone(),
+        state.config.get().query_timeout_ms,
+        state.target_guard.clone(),
+        state.change_previews.clone(),
+    )
+    .with_targets(state.targets.clone())
+    .with_events(state.events.clone())
+}
+
 /// 执行 SQL 查询哦
 #[utoipa::path(
     post,
@@ -21,25 +47,316 @@ use crate::state::AppState;
     tag = "query",
     request_body = QueryRequest,
     responses(
-        (status = 200, description = "查询执行成功", body = ApiResponse<QueryResult>),
+        (status = 200, description = "查询执行成功；按 Accept 返回 JSON、NDJSON 或 Arrow IPC 流", content(
+            (ApiResponse<QueryResult> = "application/json"),
+            (String = "application/x-ndjson"),
+            (Vec<u8> = "application/vnd.apache.arrow.stream")
+        )),
         (status = 400, description = "SQL 无效或校验错误"),
-        (status = 404, description = "连接未找到")
+        (status = 403, description = "UPDATE/DELETE 缺少有效的确认令牌"),
+        (status = 404, description = "连接未找到"),
+        (status = 428, description = "不带 WHERE 的 UPDATE/DELETE 或 DDL 需要确认，返回预估影响行数与确认令牌"),
+        (status = 503, description = "目标库降级，重查询被拒绝"),
+        (status = 504, description = "查询超时")
     )
 )]
 // 测试
 pub async fn execute_query(
     State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<QueryRequest>,
+) -> Result<Response, AppError> {
+    req.validate()?;
+    logging::record_connection_id(&req.connection_id);
+    let format = ResultFormat::negotiate(&headers);
+    let outcome = query_service(&state)
+        .with_principal(principal(&headers))
+        .execute(req)
+        .await?;
+    if format != ResultFormat::Json {
+        return Ok(result_format::stream(outcome.result, format));
+    }
+    let mut response = ApiResponse::ok_with_service(outcome.result, "query-service");
+    if let Some(cache) = outcome.cache {
+        response = response.with_cache(cache);
+    }
+    if let Some(warning) = outcome.warning {
+        response = response.with_warning(warning);
+    }
+    Ok(Json(response).into_response())
+}
+
+/// 预览 UPDATE/DELETE：执行等价的 SELECT 返回将被修改的行（不超过上限），并签发执行所需的确认令牌
+#[utoipa::path(
+    post,
+    path = "/api/query/preview",
+    tag = "query",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "将被修改的行与确认令牌", body = ApiResponse<ChangePreview>),
+        (status = 400, description = "不是可预览的 UPDATE/DELETE 语句"),
+        (status = 403, description = "SQL 引用了白名单外的表"),
+        (status = 404, description = "连接未找到"),
+        (status = 503, description = "目标库降级，预览查询被拒绝")
+    )
+)]
+pub async fn preview_change(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<QueryRequest>,
+) -> Result<Json<ApiResponse<ChangePreview>>, AppError> {
+    req.validate()?;
+    logging::record_connection_id(&req.connection_id);
+    let (preview, warning) = query_service(&state)
+        .with_principal(principal(&headers))
+        .preview(req)
+        .await?;
+    let response = ApiResponse::ok_with_service(preview, "query-service");
+    Ok(Json(match warning {
+        Some(warning) => response.with_warning(warning),
+        None => response,
+    }))
+}
+
+/// 索引建议：运行 EXPLAIN 找出全表扫描与额外排序，返回可消除它们的 CREATE INDEX 语句（MySQL / PostgreSQL）
+#[utoipa::path(
+    post,
+    path = "/api/query/analyze",
+    tag = "query",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "执行计划、发现的问题与索引建议", body = ApiResponse<IndexAdvice>),
+        (status = 400, description = "不是可分析的 SELECT / UPDATE / DELETE 语句，或数据库类型不支持"),
+        (status = 403, description = "SQL 引用了白名单外的表"),
+        (status = 404, description = "连接未找到")
+    )
+)]
+pub async fn analyze_query(
+    State(state): State<AppState>,
+    Json(req): Json<QueryRequest>,
+) -> Result<Json<ApiResponse<IndexAdvice>>, AppError> {
+    req.validate()?;
+    logging::record_connection_id(&req.connection_id);
+    let advice = query_service(&state).advise_indexes(req).await?;
+    Ok(Json(ApiResponse::ok_with_service(advice, "query-service")))
+}
+
+/// 格式化 SQL：按方言拆分语句后排版缩进与关键字大小写，不访问数据库
+#[utoipa::path(
+    post,
+    path = "/api/query/format",
+    tag = "query",
+    request_body = FormatSqlRequest,
+    responses(
+        (status = 200, description = "格式化后的 SQL", body = ApiResponse<FormattedSql>),
+        (status = 400, description = "参数校验错误")
+    )
+)]
+pub async fn format_sql(Json(req): Json<FormatSqlRequest>) -> Result<Json<ApiResponse<FormattedSql>>, AppError> {
+    req.validate()?;
+    Ok(Json(ApiResponse::ok_with_service(format::format_sql(&req), "query-service")))
+}
+
+/// 提交异步查询：立即返回任务 ID，查询在后台执行，适用于超过网关超时的长查询
+#[utoipa::path(
+    post,
+    path = "/api/query/async",
+    tag = "query",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "任务已提交", body = ApiResponse<QueryJob>),
+        (status = 400, description = "SQL 无效或校验错误"),
+        (status = 403, description = "SQL 引用了白名单外的表"),
+        (status = 503, description = "目标库降级，异步查询被拒绝")
+    )
+)]
+pub async fn submit_async_query(
+    State(state): State<AppState>,
+    headers: HeaderMap,
     Json(req): Json<QueryRequest>,
-) -> Result<Json<ApiResponse<QueryResult>>, AppError> {
-    let service = QueryService::new(
-        state.service_urls.connection_service.clone(),
-        state.http_client.clone(),
-    );
-    
-    let result = service.execute(req).await?;
+) -> Result<Json<ApiResponse<QueryJob>>, AppError> {
+    req.validate()?;
+    logging::record_connection_id(&req.connection_id);
+    // 提交前校验白名单与目标库状况，越权或被拒绝的查询直接返回错误而不是生成失败任务
+    let (warning, masking) = query_service(&state)
+        .with_principal(principal(&headers))
+        .authorize_async(&req)
+        .await?;
+    let job = state
+        .query_jobs
+        .submit(req, masking, principal(&headers).map(str::to_string))
+        .await?;
+    let response = ApiResponse::ok_with_service(job, "query-service");
+    Ok(Json(match warning {
+        Some(warning) => response.with_warning(warning),
+        None => response,
+    }))
+}
+
+/// 扇出查询：同一条只读语句在多个连接上并发执行（并发数有上限），按连接返回各自的结果或错误
+#[utoipa::path(
+    post,
+    path = "/api/query/fanout",
+    tag = "query",
+    request_body = FanOutQueryRequest,
+    responses(
+        (status = 200, description = "各连接的查询结果；单个连接失败记入该连接的 error", body = ApiResponse<FanOutResult>),
+        (status = 400, description = "SQL 无效、不是只读语句或校验错误")
+    )
+)]
+pub async fn fan_out_query(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<FanOutQueryRequest>,
+) -> Result<Json<ApiResponse<FanOutResult>>, AppError> {
+    req.validate()?;
+    let service = query_service(&state).with_principal(principal(&headers));
+    let result = state.fan_out.run(&service, &req).await?;
+    Ok(Json(ApiResponse::ok_with_service(result, "query-service")))
+}
+
+/// 批量查询：一次执行多条只读查询（可在不同连接上，并发数有上限），按请求顺序返回每条的结果或错误
+#[utoipa::path(
+    post,
+    path = "/api/query/batch",
+    tag = "query",
+    request_body = BatchQueryRequest,
+    responses(
+        (status = 200, description = "各条查询的结果；单条查询无效、不是只读语句或执行失败记入该条的 error", body = ApiResponse<BatchQueryResult>),
+        (status = 400, description = "查询数不在 1-50 之间")
+    )
+)]
+pub async fn batch_query(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<BatchQueryRequest>,
+) -> Result<Json<ApiResponse<BatchQueryResult>>, AppError> {
+    req.validate()?;
+    let service = query_service(&state).with_principal(principal(&headers));
+    let result = state.batch.run(&service, req).await;
+    Ok(Json(ApiResponse::ok_with_service(result, "query-service")))
+}
+
+/// 结果对比：执行两条只读查询（可在不同连接上），按键列对齐行，返回新增、删除与变更的行
+#[utoipa::path(
+    post,
+    path = "/api/query/diff",
+    tag = "query",
+    request_body = QueryDiffRequest,
+    responses(
+        (status = 200, description = "两边结果的行级差异", body = ApiResponse<QueryDiffResult>),
+        (status = 400, description = "SQL 无效、不是只读语句、键列缺失或不唯一"),
+        (status = 403, description = "SQL 引用了白名单外的表"),
+        (status = 404, description = "连接未找到"),
+        (status = 504, description = "查询超时")
+    )
+)]
+pub async fn diff_queries(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<QueryDiffRequest>,
+) -> Result<Json<ApiResponse<QueryDiffResult>>, AppError> {
+    req.validate()?;
+    let service = query_service(&state).with_principal(principal(&headers));
+    let result = diff::diff(&service, &req).await?;
     Ok(Json(ApiResponse::ok_with_service(result, "query-service")))
 }
 
+/// 查询异步任务状态，完成后返回结果
+#[utoipa::path(
+    get,
+    path = "/api/query/jobs/{id}",
+    tag = "query",
+    params(
+        ("id" = String, Path, description = "任务 ID")
+    ),
+    responses(
+        (status = 200, description = "任务状态与结果", body = ApiResponse<QueryJob>),
+        (status = 404, description = "任务不存在或已过期")
+    )
+)]
+pub async fn get_query_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<QueryJob>>, AppError> {
+    let job = state.query_jobs.get(&id).await?;
+    Ok(Json(ApiResponse::ok_with_service(job, "query-service")))
+}
+
+/// 订阅异步查询任务的进度事件（Server-Sent Events）
+///
+/// 先发送任务的最新状态，任务结束时发送 `finished` 事件并关闭连接；事件中不含结果行，
+/// 结束后通过 `/api/query/jobs/{id}` 读取结果。
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}/events",
+    tag = "query",
+    params(
+        ("id" = String, Path, description = "任务 ID")
+    ),
+    responses(
+        (status = 200, description = "进度事件流", body = ProgressEvent, content_type = "text/event-stream"),
+        (status = 404, description = "任务不存在或已过期")
+    )
+)]
+pub async fn query_job_events(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<ProgressSubscription, AppError> {
+    if let Some(subscription) = state.progress.subscribe(&id) {
+        return Ok(subscription);
+    }
+    let event = state.query_jobs.progress_event(&id).await?;
+    Ok(ProgressSubscription::once(event))
+}
+
+/// 存活探针：进程能响应请求即返回 200
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, description = "服务存活", body = Liveness)
+    )
+)]
+pub async fn healthz() -> Json<Liveness> {
+    Json(Liveness::new("query-service"))
+}
+
+/// 就绪探针：本服务不依赖需要预先检查的存储，启动完成即就绪
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "服务就绪", body = Readiness)
+    )
+)]
+pub async fn readyz() -> Readiness {
+    Readiness::new("query-service", Vec::new())
+}
+
+/// 重新加载配置（.env 文件与命令行覆盖），默认查询超时对之后的查询生效，需要 X-Admin-Token
+#[utoipa::path(
+    post,
+    path = "/api/admin/config/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "已重新加载，返回变更的配置项与需重启才生效的配置项", body = ApiResponse<ConfigReload>),
+        (status = 401, description = "管理令牌无效"),
+        (status = 403, description = "未配置 METADATA_ADMIN_TOKEN，管理端点已禁用"),
+        (status = 500, description = "新配置无效，保留当前配置")
+    )
+)]
+pub async fn reload_config(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<ConfigReload>>, AppError> {
+    admin::authorize(&headers)?;
+    let reload = state.config.reload()?;
+    Ok(Json(ApiResponse::ok_with_service(reload, "query-service")))
+}
+
 /// 健康检查端点
 #[utoipa::path(
     get,
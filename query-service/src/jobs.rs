@@ -0,0 +1,193 @@
+//! 异步查询任务模块
+//!
+//! 分析类查询可能执行数分钟，超出网关 30 秒的请求超时。异步任务在后台
+//! 经连接服务的 gRPC 接口执行查询，结果暂存在内存中（超过大小上限时截断行），客户端
+//! 通过任务 ID 轮询状态与结果，或订阅 `/api/jobs/{id}/events` 的进度事件（事件中不含
+//! 结果行，任务结束后再按任务 ID 读取结果）。已结束的任务在保留期后清理。
+//!
+//! 配置：
+//! - `QUERY_JOB_TIMEOUT_SECS` - 单个查询的最长执行时间（默认 1800）
+//! - `QUERY_JOB_MAX_RESULT_BYTES` - 保存结果的最大字节数（默认 16 MiB）
+//! - `QUERY_JOB_RETENTION_SECS` - 已结束任务的保留时间（默认 3600）
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::grpc;
+use common::models::masking::ConnectionMasking;
+use common::models::query::{QueryJob, QueryJobStatus, QueryRequest, QueryResult};
+use common::progress::{kinds, ProgressEvent, ProgressHub};
+use common::utils::SqlValidator;
+
+use crate::connections::ConnectionClient;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 1800;
+const DEFAULT_MAX_RESULT_BYTES: usize = 16 * 1024 * 1024;
+const DEFAULT_RETENTION_SECS: i64 = 3600;
+
+/// 异步查询任务管理器
+pub struct QueryJobManager {
+    connections: ConnectionClient,
+    timeout: Duration,
+    max_result_bytes: usize,
+    retention: chrono::Duration,
+    progress: Arc<ProgressHub>,
+    jobs: RwLock<HashMap<String, QueryJob>>,
+}
+
+impl QueryJobManager {
+    /// 创建任务管理器，从环境变量读取超时、结果大小与保留时间
+    pub fn new(connections: ConnectionClient, progress: Arc<ProgressHub>) -> Self {
+        fn env<T: std::str::FromStr>(key: &str, default: T) -> T {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self {
+            connections,
+            timeout: Duration::from_secs(env("QUERY_JOB_TIMEOUT_SECS", DEFAULT_TIMEOUT_SECS)),
+            max_result_bytes: env("QUERY_JOB_MAX_RESULT_BYTES", DEFAULT_MAX_RESULT_BYTES),
+            retention: chrono::Duration::seconds(env("QUERY_JOB_RETENTION_SECS", DEFAULT_RETENTION_SECS)),
+            progress,
+            jobs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 提交异步查询，立即返回任务，查询在后台执行；结果保存前按 `masking` 脱敏
+    ///
+    /// 查询计入 `principal` 的用量，配额用尽时任务失败并附带配额详情。
+    pub async fn submit(
+        self: &Arc<Self>,
+        req: QueryRequest,
+        masking: Option<ConnectionMasking>,
+        principal: Option<String>,
+    ) -> AppResult<QueryJob> {
+        SqlValidator::validate(&req.sql)?;
+        self.purge_expired().await;
+
+        let job = QueryJob {
+            id: Uuid::new_v4().to_string(),
+            connection_id: req.connection_id.clone(),
+            sql: req.sql.clone(),
+            status: QueryJobStatus::Running,
+            result: None,
+            truncated: false,
+            error: None,
+            error_details: None,
+            created_at: Utc::now().to_rfc3339(),
+            finished_at: None,
+        };
+        self.jobs.write().await.insert(job.id.clone(), job.clone());
+        self.progress.publish(ProgressEvent::new(kinds::QUERY, &job.id, &job, false));
+        tracing::info!(job_id = %job.id, connection_id = %job.connection_id, "Async query submitted");
+
+        let mgr = self.clone();
+        let job_id = job.id.clone();
+        tokio::spawn(async move {
+            let outcome = mgr.run(&req, principal.as_deref()).await.map(|mut result| {
+                if let Some(masking) = &masking {
+                    masking.apply(&mut result);
+                }
+                result
+            });
+            mgr.finish(&job_id, outcome).await;
+        });
+
+        Ok(job)
+    }
+
+    /// 查询任务状态与结果
+    pub async fn get(&self, job_id: &str) -> AppResult<QueryJob> {
+        self.jobs
+            .read()
+            .await
+            .get(job_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("query job {}", job_id)))
+    }
+
+    /// 调用连接服务执行查询，失败时返回错误信息与结构化详情
+    async fn run(&self, req: &QueryRequest, principal: Option<&str>) -> Result<QueryResult, (String, Option<serde_json::Value>)> {
+        let job_timeout_ms = self.timeout.as_millis() as u64;
+        // 任务超时是上限，请求指定的超时只能更短
+        let timeout_ms = req.timeout_ms.map_or(job_timeout_ms, |ms| ms.min(job_timeout_ms));
+        let call = self.connections.execute(req, principal, timeout_ms);
+        match tokio::time::timeout(self.timeout, call).await {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(status)) => {
+                let error = grpc::error_body(&status);
+                let message = error["message"].as_str().unwrap_or("查询失败").to_string();
+                Err((message, error.get("details").cloned()))
+            }
+            Err(_) => Err((AppError::Timeout(format!("查询超过 {} ms 超时限制", job_timeout_ms)).to_string(), None)),
+        }
+    }
+
+    async fn finish(&self, job_id: &str, outcome: Result<QueryResult, (String, Option<serde_json::Value>)>) {
+        let mut jobs = self.jobs.write().await;
+        let Some(job) = jobs.get_mut(job_id) else {
+            return;
+        };
+        job.finished_at = Some(Utc::now().to_rfc3339());
+        match outcome {
+            Ok(mut result) => {
+                job.truncated = result.clip_rows(self.max_result_bytes);
+                tracing::info!(job_id, rows = result.row_count, truncated = job.truncated, "Async query completed");
+                job.status = QueryJobStatus::Completed;
+                job.result = Some(result);
+            }
+            Err((error, details)) => {
+                tracing::warn!(job_id, error = %error, "Async query failed");
+                job.status = QueryJobStatus::Failed;
+                job.error = Some(error);
+                job.error_details = details;
+            }
+        }
+        // 结果行不随事件发送
+        let result = job.result.take();
+        self.progress.publish(ProgressEvent::new(kinds::QUERY, job_id, job, true));
+        job.result = result;
+    }
+
+    /// 任务当前状态对应的进度事件（不含结果行）
+    pub async fn progress_event(&self, job_id: &str) -> AppResult<ProgressEvent> {
+        let job = QueryJob { result: None, ..self.get(job_id).await? };
+        let finished = job.status != QueryJobStatus::Running;
+        Ok(ProgressEvent::new(kinds::QUERY, job_id, &job, finished))
+    }
+
+    /// 清理超过保留期的已结束任务
+    async fn purge_expired(&self) {
+        let cutoff = (Utc::now() - self.retention).to_rfc3339();
+        self.jobs.write().await.retain(|_, job| {
+            job.status == QueryJobStatus::Running
+                || job.finished_at.as_deref().is_none_or(|finished| finished > cutoff.as_str())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn truncates_results_over_the_size_limit() {
+        let mut result = QueryResult::empty();
+        result.rows = (0..100).map(|i| vec![json!(i), json!("x".repeat(100))]).collect();
+        result.row_count = 100;
+
+        assert!(!result.clone().clip_rows(usize::MAX));
+        assert!(result.clip_rows(1024));
+        assert!(result.row_count > 0 && result.row_count < 10);
+        assert_eq!(result.rows.len(), result.row_count);
+    }
+}
@@ -4,21 +4,52 @@
 //! - 在已连接的数据库上执行查询
 //! - 结果解析与格式化
 //! - 查询语句校验
+//! - 长时间查询的异步执行与结果轮询
+//! - 重复查询的结果缓存
+//! - 经连接服务的内部 gRPC 接口读取连接信息、执行查询
+//! - 连接信息缓存，按连接事件作废
+//! - 目标库降级时对重查询告警或拒绝
+//! - UPDATE/DELETE 执行前预览受影响的行并确认
+//! - 根据执行计划给出索引建议
+//! - SQL 格式化
+//! - 按 Accept 以 NDJSON 或 Arrow IPC 流返回查询结果
+//! - 同一条只读语句在多个连接上扇出执行
+//! - 一次请求批量执行多条只读查询
+//! - 按键列对比两条查询结果的行级差异
 
+mod analysis;
+mod arrow_ipc;
+mod batch;
+mod cache;
+mod confirm;
+mod connections;
+mod diff;
+mod fanout;
+mod format;
+mod guard;
+mod jobs;
+mod preview;
+mod result_format;
 mod routes;
 mod service;
 mod state;
+mod targets;
 mod handlers;
 
-use axum::{middleware, routing::get, Json, Router};
+use std::sync::Arc;
+
+use axum::{extract::DefaultBodyLimit, middleware, routing::get, Json, Router};
 use common::config::AppConfig;
+use common::fallback::with_json_fallbacks;
+use common::middleware::cors::cors_layer;
 use common::middleware::request_id::request_id_middleware;
+use common::middleware::signing::{signature_middleware, SignatureVerifier};
+use common::openapi::ResponseExamples;
 use state::AppState;
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::compression::CompressionLayer;
 use tower_http::trace::TraceLayer;
 use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::OpenApi;
 
 const SERVICE_NAME: &str = "query-service";
@@ -33,63 +64,115 @@ const DEFAULT_PORT: u16 = 8082;
     ),
     paths(
         handlers::execute_query,
+        handlers::preview_change,
+        handlers::analyze_query,
+        handlers::format_sql,
+        handlers::submit_async_query,
+        handlers::fan_out_query,
+        handlers::batch_query,
+        handlers::diff_queries,
+        handlers::get_query_job,
+        handlers::query_job_events,
         handlers::health_check,
+        handlers::healthz,
+        handlers::readyz,
+        handlers::reload_config,
         handlers::hello_test,
     ),
     components(schemas(
         common::models::QueryRequest,
+        common::models::QueryLanguage,
         common::models::QueryResult,
+        common::models::TruncatedCell,
+        common::models::ValueKind,
         common::models::ColumnInfo,
+        common::models::ChangePreview,
+        common::models::ConfirmationRequired,
+        common::models::DangerousStatementKind,
+        common::models::IndexAdvice,
+        common::models::PlanFinding,
+        common::models::PlanFindingKind,
+        common::models::IndexSuggestion,
+        common::models::FormatSqlRequest,
+        common::models::FormattedSql,
+        common::models::QueryJob,
+        common::models::QueryJobStatus,
+        common::progress::ProgressEvent,
+        common::models::FanOutQueryRequest,
+        common::models::FanOutEntry,
+        common::models::FanOutResult,
+        common::models::BatchQueryRequest,
+        common::models::BatchQueryEntry,
+        common::models::BatchQueryResult,
+        common::models::QueryDiffRequest,
+        common::models::QueryDiffSide,
+        common::models::QueryDiffResult,
+        common::models::ChangedRow,
+        common::response::CacheInfo,
         handlers::HealthResponse,
+        common::probes::Liveness,
+        common::probes::Readiness,
+        common::probes::ProbeCheck,
+        common::config::ConfigReload,
     )),
     tags(
         (name = "query", description = "查询执行端点"),
-        (name = "health", description = "健康检查端点")
-    )
+        (name = "health", description = "健康检查端点"),
+        (name = "admin", description = "配置重新加载")
+    ),
+    modifiers(&ResponseExamples)
 )]
 struct ApiDoc;
 
 #[tokio::main]
 async fn main() {
-    // 初始化日志追踪
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".into()),
-        )
-        .init();
-
-    // 加载配置
-    let mut config = AppConfig::load_with_service(SERVICE_NAME);
-    config.port = std::env::var("SERVER_PORT")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(DEFAULT_PORT);
+    // 加载配置（.env 文件、环境变量、命令行覆盖），配置无效时列出问题并退出
+    let config = Arc::new(
+        AppConfig::loader(SERVICE_NAME)
+            .default_port(DEFAULT_PORT)
+            .startup_only(&["MAX_BODY_BYTES"])
+            .load_shared_or_exit(),
+    );
+
+    // 初始化日志追踪（LOG_FORMAT=json 时输出 JSON）
+    common::logging::init(&config.get());
 
     // 创建应用状态
-    let state = AppState::new(config.clone());
+    let state = AppState::new(config.clone()).await;
+    // 收到 SIGHUP 时重新加载配置
+    config.reload_on_sighup();
 
     // 创建路由
     let app = create_router(state);
 
     // 启动服务
-    let addr = format!("{}:{}", config.host, config.port);
+    let addr = config.get().addr();
     info!(service = SERVICE_NAME, address = %addr, "启动服务");
 
     let listener = TcpListener::bind(&addr).await.expect("绑定地址失败");
-    axum::serve(listener, app).await.expect("服务启动失败");
+    // 配置 SERVICE_ADVERTISE_URL 时向网关注册并定期发送心跳
+    common::discovery::spawn_registration(SERVICE_NAME);
+    // 配置 TLS_CERT_PATH 与 TLS_KEY_PATH 时以 HTTPS 提供服务
+    common::tls::serve(listener, app, config.get().tls.as_ref())
+        .await
+        .expect("服务启动失败");
 }
 
 fn create_router(state: AppState) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    // 跨域设置只在启动时读取
+    let cors = cors_layer(&state.config.get().cors);
 
-    Router::new()
+    let body_limit = DefaultBodyLimit::max(state.config.get().max_body_bytes);
+    let signatures = Arc::new(SignatureVerifier::from_env());
+
+    let router = Router::new()
         .merge(routes::router())
-        .route("/api-docs/openapi.json", get(openapi_json))
+        .route("/api-docs/openapi.json", get(openapi_json));
+
+    with_json_fallbacks(router, "query-service")
+        .layer(middleware::from_fn_with_state(signatures, signature_middleware))
+        .layer(body_limit)
+        .layer(CompressionLayer::new())
         .layer(middleware::from_fn(request_id_middleware))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
@@ -0,0 +1,173 @@
+//! 变更预览模块
+//!
+//! UPDATE/DELETE 执行前先运行等价的 SELECT，返回将被修改的行（不超过上限）
+//! 和一个确认令牌。令牌绑定连接、规范化 SQL 与绑定参数，只能使用一次，过期
+//! 作废；执行 UPDATE/DELETE 时必须携带有效令牌。危险语句的确认令牌（见
+//! [`confirm`](crate::confirm)）同样由这里签发和核对。令牌保存在内存中，服务
+//! 重启后需要重新获取。
+//!
+//! 配置：
+//! - `CHANGE_PREVIEW_MAX_ROWS` - 预览返回的最大行数（默认 100）
+//! - `CHANGE_PREVIEW_TOKEN_TTL_SECS` - 确认令牌有效期（默认 300）
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use common::errors::{AppError, AppResult};
+use common::models::query::QueryRequest;
+
+use common::utils::sql_lexer::Dialect;
+
+use crate::cache::normalize_sql;
+
+const DEFAULT_MAX_ROWS: u32 = 100;
+const DEFAULT_TOKEN_TTL_SECS: i64 = 300;
+
+/// 已预览、等待确认的变更
+struct PendingChange {
+    fingerprint: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// 变更确认令牌存储
+pub struct ChangePreviewStore {
+    max_rows: u32,
+    ttl: chrono::Duration,
+    pending: Mutex<HashMap<String, PendingChange>>,
+}
+
+impl ChangePreviewStore {
+    /// 创建令牌存储
+    pub fn new(max_rows: u32, ttl_secs: i64) -> Self {
+        Self {
+            max_rows: max_rows.max(1),
+            ttl: chrono::Duration::seconds(ttl_secs.max(1)),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 从环境变量读取行数上限与令牌有效期
+    pub fn from_env() -> Self {
+        fn env<T: std::str::FromStr>(key: &str, default: T) -> T {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self::new(
+            env("CHANGE_PREVIEW_MAX_ROWS", DEFAULT_MAX_ROWS),
+            env("CHANGE_PREVIEW_TOKEN_TTL_SECS", DEFAULT_TOKEN_TTL_SECS),
+        )
+    }
+
+    /// 预览返回的最大行数
+    pub fn max_rows(&self) -> u32 {
+        self.max_rows
+    }
+
+    /// 为预览过的变更或待确认的危险语句签发确认令牌，返回令牌与过期时间
+    ///
+    /// `dialect` 为目标库的方言，用于规范化语句。
+    pub async fn issue(&self, req: &QueryRequest, dialect: Dialect) -> (String, DateTime<Utc>) {
+        let now = Utc::now();
+        let token = Uuid::new_v4().to_string();
+        let expires_at = now + self.ttl;
+
+        let mut pending = self.pending.lock().await;
+        pending.retain(|_, change| change.expires_at > now);
+        pending.insert(
+            token.clone(),
+            PendingChange {
+                fingerprint: fingerprint(req, dialect),
+                expires_at,
+            },
+        );
+        (token, expires_at)
+    }
+
+    /// 核对并消耗请求携带的确认令牌
+    ///
+    /// # Errors
+    /// 未携带令牌、令牌不存在或已过期、令牌与连接/语句/参数不匹配时返回
+    /// `AppError::Forbidden`。
+    pub async fn confirm(&self, req: &QueryRequest, dialect: Dialect) -> AppResult<()> {
+        let token = req.confirmation_token.as_deref().ok_or_else(|| {
+            AppError::Forbidden("UPDATE/DELETE 须先调用 /api/query/preview 预览并携带 confirmation_token".to_string())
+        })?;
+
+        let mut pending = self.pending.lock().await;
+        let change = pending
+            .remove(token)
+            .filter(|change| change.expires_at > Utc::now())
+            .ok_or_else(|| AppError::Forbidden("确认令牌无效或已过期，请重新预览或提交".to_string()))?;
+        if change.fingerprint != fingerprint(req, dialect) {
+            return Err(AppError::Forbidden("确认令牌与签发时的连接、语句或参数不一致".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// 令牌绑定的变更指纹：连接、规范化 SQL 与绑定参数
+fn fingerprint(req: &QueryRequest, dialect: Dialect) -> String {
+    let params = serde_json::to_string(&(&req.params, &req.named_params)).unwrap_or_default();
+    let digest = Sha256::digest(
+        format!(
+            "{}\n{}\n{}\n{}",
+            req.connection_id,
+            req.database.as_deref().unwrap_or_default(),
+            params,
+            normalize_sql(&req.sql, dialect)
+        )
+        .as_bytes(),
+    );
+    hex::encode(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(sql: &str, token: Option<String>) -> QueryRequest {
+        QueryRequest {
+            connection_id: "c1".to_string(),
+            sql: sql.to_string(),
+            database: None,
+            limit: None,
+            params: vec![],
+            named_params: Default::default(),
+            timeout_ms: None,
+            cache_ttl_secs: None,
+            confirmation_token: token,
+            query_language: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn tokens_are_single_use_and_bound_to_the_statement() {
+        let store = ChangePreviewStore::new(100, 300);
+        let sql = "UPDATE users SET active = 0 WHERE id = 1";
+        assert!(store.confirm(&request(sql, None), Dialect::ANY).await.is_err());
+
+        let (token, _) = store.issue(&request(sql, None), Dialect::ANY).await;
+        assert!(store
+            .confirm(&request("UPDATE users SET active = 0 WHERE id = 2", Some(token)), Dialect::ANY)
+            .await
+            .is_err());
+
+        let (token, _) = store.issue(&request(sql, None), Dialect::ANY).await;
+        let other_database = QueryRequest {
+            database: Some("archive".to_string()),
+            ..request(sql, Some(token))
+        };
+        assert!(store.confirm(&other_database, Dialect::ANY).await.is_err());
+
+        let (token, _) = store.issue(&request(sql, None), Dialect::ANY).await;
+        assert!(store.confirm(&request("UPDATE users  SET active = 0 WHERE id = 1;", Some(token.clone())), Dialect::ANY).await.is_ok());
+        assert!(store.confirm(&request(sql, Some(token)), Dialect::ANY).await.is_err());
+    }
+}
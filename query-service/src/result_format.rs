@@ -0,0 +1,151 @@
+//! 查询结果格式协商模块
+//!
+//! `POST /api/query` 按 `Accept` 头选择结果格式：
+//! - `application/json`（默认）：统一的 `ApiResponse<QueryResult>` 文档
+//! - `application/x-ndjson`：每行一个 JSON 对象，键为列名
+//! - `application/vnd.apache.arrow.stream`：Arrow IPC 流，见 [`arrow_ipc`](crate::arrow_ipc)
+//!
+//! 后两种格式按批分块输出，行数、耗时与是否截断放在响应头中；缓存与降级
+//! 告警等元信息只在 JSON 格式中返回。
+
+use arrow_schema::ArrowError;
+use axum::body::Body;
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use common::models::query::{ColumnInfo, QueryResult};
+use serde_json::Value;
+
+use crate::arrow_ipc::{ArrowStream, ArrowType};
+
+pub const NDJSON: &str = "application/x-ndjson";
+pub const ARROW_STREAM: &str = "application/vnd.apache.arrow.stream";
+
+/// 每个分块（NDJSON）或 RecordBatch（Arrow）包含的行数
+const BATCH_ROWS: usize = 1024;
+
+/// 查询结果格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultFormat {
+    Json,
+    NdJson,
+    Arrow,
+}
+
+impl ResultFormat {
+    /// 按 `Accept` 中媒体类型出现的顺序选择第一个支持的格式，均不支持时为 JSON
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        for media in accept.split(',') {
+            let media = media.split(';').next().unwrap_or_default().trim();
+            if media.eq_ignore_ascii_case(NDJSON) || media.eq_ignore_ascii_case("application/ndjson") {
+                return ResultFormat::NdJson;
+            }
+            if media.eq_ignore_ascii_case(ARROW_STREAM) {
+                return ResultFormat::Arrow;
+            }
+            if media.eq_ignore_ascii_case("application/json") || media == "*/*" {
+                return ResultFormat::Json;
+            }
+        }
+        ResultFormat::Json
+    }
+}
+
+/// 以 NDJSON 或 Arrow IPC 流返回结果
+pub fn stream(result: QueryResult, format: ResultFormat) -> Response {
+    let headers = [
+        (header::CONTENT_TYPE, HeaderValue::from_static(if format == ResultFormat::Arrow { ARROW_STREAM } else { NDJSON })),
+        (HeaderName::from_static("x-row-count"), HeaderValue::from(result.row_count)),
+        (HeaderName::from_static("x-execution-time-ms"), HeaderValue::from(result.execution_time_ms)),
+        (HeaderName::from_static("x-truncated"), HeaderValue::from_static(if result.truncated { "true" } else { "false" })),
+    ];
+    let QueryResult { columns, rows, .. } = result;
+
+    let chunks: Box<dyn Iterator<Item = Result<Vec<u8>, ArrowError>> + Send> = match format {
+        ResultFormat::Arrow => {
+            let types = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| ArrowType::of(column, i, &rows))
+                .collect();
+            match ArrowStream::new(&columns, types) {
+                Ok(stream) => Box::new(arrow_chunks(stream, rows)),
+                Err(e) => Box::new(std::iter::once(Err(e))),
+            }
+        }
+        _ => Box::new(batches(rows).map(move |batch| Ok(ndjson(&columns, &batch)))),
+    };
+    // 编码失败时中断响应体，客户端收到不完整的流
+    let body = Body::from_stream(futures::stream::iter(chunks));
+    (headers, body).into_response()
+}
+
+/// Schema 消息、每批的 RecordBatch 消息与结束标记，各为一个分块
+fn arrow_chunks(mut stream: ArrowStream, rows: Vec<Vec<Value>>) -> impl Iterator<Item = Result<Vec<u8>, ArrowError>> + Send {
+    let schema = stream.take();
+    let mut batches = batches(rows);
+    let mut finished = false;
+    let messages = std::iter::from_fn(move || {
+        if finished {
+            return None;
+        }
+        let written = match batches.next() {
+            Some(batch) => stream.write(&batch),
+            None => {
+                finished = true;
+                stream.finish()
+            }
+        };
+        Some(written.map(|()| stream.take()))
+    });
+    std::iter::once(Ok(schema)).chain(messages)
+}
+
+fn batches(rows: Vec<Vec<Value>>) -> impl Iterator<Item = Vec<Vec<Value>>> + Send {
+    let mut rows = rows.into_iter();
+    std::iter::from_fn(move || {
+        let batch: Vec<_> = rows.by_ref().take(BATCH_ROWS).collect();
+        (!batch.is_empty()).then_some(batch)
+    })
+}
+
+/// 每行一个 JSON 对象；列名重复时保留后一列的值
+fn ndjson(columns: &[ColumnInfo], rows: &[Vec<Value>]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for row in rows {
+        let object: serde_json::Map<String, Value> = columns
+            .iter()
+            .zip(row)
+            .map(|(column, value)| (column.name.clone(), value.clone()))
+            .collect();
+        // Map 序列化不会失败
+        let _ = serde_json::to_writer(&mut out, &object);
+        out.push(b'\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn negotiates_format_from_accept() {
+        let accept = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+            ResultFormat::negotiate(&headers)
+        };
+        assert_eq!(ResultFormat::negotiate(&HeaderMap::new()), ResultFormat::Json);
+        assert_eq!(accept("application/x-ndjson"), ResultFormat::NdJson);
+        assert_eq!(accept("text/html, application/vnd.apache.arrow.stream;q=0.9"), ResultFormat::Arrow);
+        assert_eq!(accept("application/json, application/x-ndjson"), ResultFormat::Json);
+
+        let columns = vec![ColumnInfo { name: "id".into(), data_type: "INT".into(), nullable: None, kind: None }];
+        assert_eq!(ndjson(&columns, &[vec![json!(1)], vec![json!(2)]]), b"{\"id\":1}\n{\"id\":2}\n");
+    }
+}
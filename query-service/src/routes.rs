@@ -10,6 +10,18 @@ use crate::state::AppState;
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/api/query", post(handlers::execute_query))
+        .route("/api/query/preview", post(handlers::preview_change))
+        .route("/api/query/analyze", post(handlers::analyze_query))
+        .route("/api/query/format", post(handlers::format_sql))
+        .route("/api/query/async", post(handlers::submit_async_query))
+        .route("/api/query/fanout", post(handlers::fan_out_query))
+        .route("/api/query/batch", post(handlers::batch_query))
+        .route("/api/query/diff", post(handlers::diff_queries))
+        .route("/api/query/jobs/{id}", get(handlers::get_query_job))
+        .route("/api/jobs/{id}/events", get(handlers::query_job_events))
+        .route("/api/admin/config/reload", post(handlers::reload_config))
+        .route("/healthz", get(handlers::healthz))
+        .route("/readyz", get(handlers::readyz))
         .route("/api/health", get(handlers::health_check))
         .route("/api/test", get(handlers::hello_test))
 }
@@ -1,72 +1,514 @@
 //! 查询执行服务模块
 
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
 use common::errors::{AppError, AppResult};
-use common::models::query::{QueryRequest, QueryResult};
-use common::utils::SqlValidator;
+use common::events::{kinds, EventPublisher};
+use common::grpc::{self, proto::PoolInfo};
+use common::models::connection::{ConnectionAllowlist, DbType, StatementPolicy};
+use common::models::masking::ConnectionMasking;
+use common::models::analysis::IndexAdvice;
+use common::models::database::IndexStats;
+use common::models::monitor::TargetHealth;
+use common::models::query::{ChangePreview, ConfirmationRequired, QueryLanguage, QueryRequest, QueryResult};
+use common::models::workload::StatementType;
+use common::response::CacheInfo;
+use common::utils::sql_lexer;
+use common::utils::{ChangePreviewSql, CypherAnalyzer, SqlValidator};
+
+use crate::analysis::{self, Dialect, StatementColumns};
+use crate::cache::{CacheKey, QueryCache};
+use crate::confirm::{self, Danger};
+use crate::connections::ConnectionClient;
+use crate::guard::TargetGuard;
+use crate::preview::ChangePreviewStore;
+use crate::targets::TargetCache;
+
+/// 本地超时在查询超时之外预留的余量，让连接服务先返回数据库端的超时错误
+const TIMEOUT_GRACE: Duration = Duration::from_secs(2);
+
+/// 查询执行结果
+pub struct QueryOutcome {
+    pub result: QueryResult,
+    /// 缓存信息（未启用缓存时为 `None`）
+    pub cache: Option<CacheInfo>,
+    /// 需要附加到响应的告警
+    pub warning: Option<String>,
+}
+
+/// 连接服务返回的目标连接信息
+struct TargetInfo {
+    /// 数据库类型
+    db_type: String,
+    /// 默认库 / schema
+    namespace: Option<String>,
+    /// 连接默认查询超时（毫秒）
+    timeout_ms: u64,
+    /// 最近一次健康检查结果
+    health: Option<TargetHealth>,
+    /// 对当前主体生效的脱敏规则（不脱敏时为 `None`）
+    masking: Option<ConnectionMasking>,
+}
 
 /// SQL 查询执行服务
 pub struct QueryService {
-    connection_service_url: String,
-    http_client: reqwest::Client,
+    connections: ConnectionClient,
+    cache: Arc<QueryCache>,
+    /// 连接信息缓存（未设置时不缓存）
+    targets: Arc<TargetCache>,
+    /// 连接服务未返回连接默认超时时使用的超时（毫秒）
+    default_timeout_ms: u64,
+    guard: TargetGuard,
+    previews: Arc<ChangePreviewStore>,
+    /// 发起请求的主体，决定是否豁免连接的脱敏规则
+    principal: Option<String>,
+    /// 查询执行事件的发布端（未设置时不发布）
+    events: Option<Arc<EventPublisher>>,
 }
 
 impl QueryService {
     /// 创建新的查询服务实例
-    pub fn new(connection_service_url: String, http_client: reqwest::Client) -> Self {
+    pub fn new(
+        connections: ConnectionClient,
+        cache: Arc<QueryCache>,
+        default_timeout_ms: u64,
+        guard: TargetGuard,
+        previews: Arc<ChangePreviewStore>,
+    ) -> Self {
         Self {
-            connection_service_url,
-            http_client,
-        }
-    }
-
-    /// 执行 SQL 查询
-    pub async fn execute(&self, req: QueryRequest) -> AppResult<QueryResult> {
-        // 校验 SQL
-        SqlValidator::validate(&req.sql)?;
-
-        // 从连接服务获取连接信息
-        let _pool_info = self.get_pool_info(&req.connection_id).await?;
-
-        // TODO: 实现实际的查询执行逻辑
-        // 目前返回占位结果
-        let start = std::time::Instant::now();
-        
-        // 占位实现 - 实际实现需要：
-        // 1. 从连接服务获取数据库连接
-        // 2. 执行 SQL 查询
-        // 3. 解析并返回结果
-        
-        let execution_time_ms = start.elapsed().as_millis() as u64;
-        
-        Ok(QueryResult {
-            columns: vec![],
-            rows: vec![],
-            row_count: 0,
-            affected_rows: None,
-            execution_time_ms,
+            connections,
+            cache,
+            targets: Arc::new(TargetCache::new(0, 0)),
+            default_timeout_ms,
+            guard,
+            previews,
+            principal: None,
+            events: None,
+        }
+    }
+
+    /// 设置发起请求的主体（网关注入的 `X-Principal`）
+    pub fn with_principal(mut self, principal: Option<&str>) -> Self {
+        self.principal = principal.map(str::to_string);
+        self
+    }
+
+    /// 设置连接信息缓存
+    pub fn with_targets(mut self, targets: Arc<TargetCache>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    /// 设置事件发布端，执行成功后发布 `query.executed` 事件
+    pub fn with_events(mut self, events: Arc<EventPublisher>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 执行 SQL 查询；请求指定 `cache_ttl_secs` 时优先返回缓存结果
+    ///
+    /// 缓存未命中且目标库降级时按降级策略检查重查询。UPDATE/DELETE 与 DDL
+    /// 须携带确认令牌。缓存保存未脱敏的结果，返回前按主体脱敏。
+    pub async fn execute(&self, req: QueryRequest) -> AppResult<QueryOutcome> {
+        let cypher = req.query_language == QueryLanguage::Cypher;
+        if cypher {
+            // Cypher 仅支持只读查询，不走变更确认流程
+            CypherAnalyzer::validate_read_only(&req.sql)?;
+        } else if ChangePreviewSql::is_change(&req.sql, sql_lexer::Dialect::ANY) || SqlValidator::is_ddl(&req.sql) {
+            return self.execute_change(req).await;
+        } else {
+            // 校验 SQL
+            SqlValidator::validate(&req.sql)?;
+        }
+
+        // 从连接服务获取连接信息并校验库表白名单（缓存命中时同样校验）
+        let target = self.check_connection(&req, &req.sql).await?;
+        if cypher && target.db_type != "neo4j" {
+            return Err(AppError::InvalidInput("Cypher 查询仅支持 Neo4j 连接".to_string()));
+        }
+        let timeout_ms = req.timeout_ms.unwrap_or(target.timeout_ms);
+
+        let ttl = self.cache.effective_ttl(req.cache_ttl_secs.unwrap_or(0));
+        let key = (ttl > 0)
+            .then(|| CacheKey::new(&req, sql_lexer::Dialect::named(&target.db_type)))
+            .flatten();
+
+        // 缓存命中不访问目标库，无需降级检查
+        if let Some(key) = &key {
+            if let Some((result, info)) = self.cache.get(key).await {
+                tracing::debug!(connection_id = %req.connection_id, layer = ?info.layer, "Query cache hit");
+                self.publish_executed(&req, &result, true);
+                return Ok(QueryOutcome {
+                    result: mask(&target, result),
+                    cache: Some(info),
+                    warning: None,
+                });
+            }
+        }
+
+        let warning = self
+            .guard
+            .check(target.health.as_ref(), &req.sql, req.limit, false)?;
+        let result = self.run(&req, timeout_ms).await?;
+        self.publish_executed(&req, &result, false);
+        let cache = match &key {
+            Some(key) => Some(self.cache.put(key, &result, ttl).await),
+            None => None,
+        };
+        Ok(QueryOutcome {
+            result: mask(&target, result),
+            cache,
+            warning,
         })
     }
 
-    /// 从连接服务获取连接池信息
-    async fn get_pool_info(&self, connection_id: &str) -> AppResult<serde_json::Value> {
-        let url = format!("{}/internal/pools/{}", self.connection_service_url, connection_id);
-        
-        let response = self.http_client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| AppError::ExternalService(format!("无法连接到连接服务: {}", e)))?;
+    /// 预览 UPDATE/DELETE 将修改的行，并签发执行该语句所需的确认令牌
+    ///
+    /// 预览查询按普通查询接受降级检查，返回需要附加到响应的告警。
+    pub async fn preview(&self, req: QueryRequest) -> AppResult<(ChangePreview, Option<String>)> {
+        let target = self.check_connection(&req, &req.sql).await?;
+        let dialect = sql_lexer::Dialect::named(&target.db_type);
+        let preview_sql = ChangePreviewSql::to_select(&req.sql, dialect).ok_or_else(|| {
+            AppError::InvalidInput("仅支持预览单条 UPDATE / DELETE 语句".to_string())
+        })?;
+        // SET 子句不进入预览查询，其中的位置参数会使后续参数错位
+        if !req.params.is_empty() && !req.sql.trim_start().to_ascii_uppercase().starts_with("DELETE") {
+            return Err(AppError::InvalidInput(
+                "UPDATE 预览不支持位置参数，请使用 named_params".to_string(),
+            ));
+        }
+        SqlValidator::validate_change(&req.sql)?;
+
+        let timeout_ms = req.timeout_ms.unwrap_or(target.timeout_ms);
+        let max_rows = self.previews.max_rows();
+        let warning = self
+            .guard
+            .check(target.health.as_ref(), &preview_sql, Some(max_rows), false)?;
+
+        // 多取一行用于判断是否还有更多匹配行
+        let query = QueryRequest {
+            sql: preview_sql.clone(),
+            limit: Some(max_rows + 1),
+            ..req.clone()
+        };
+        let mut result = mask(&target, self.run(&query, timeout_ms).await?);
+        let truncated = result.rows.len() > max_rows as usize;
+        result.rows.truncate(max_rows as usize);
+        result.row_count = result.rows.len();
+        result.truncated_cells.retain(|cell| cell.row < max_rows as usize);
+
+        let (confirmation_token, expires_at) = self.previews.issue(&req, dialect).await;
+        tracing::info!(connection_id = %req.connection_id, rows = result.row_count, truncated, "Change previewed");
+        Ok((
+            ChangePreview {
+                preview_sql,
+                result,
+                truncated,
+                confirmation_token,
+                expires_at: expires_at.to_rfc3339(),
+            },
+            warning,
+        ))
+    }
 
-        if !response.status().is_success() {
-            return Err(AppError::ConnectionNotFound(connection_id.to_string()));
+    /// 运行 EXPLAIN 分析语句的执行计划，返回计划问题与索引建议
+    ///
+    /// UPDATE/DELETE 按等价的 SELECT 分析，不会修改数据。表上已有以建议列
+    /// 开头的索引时不再建议。
+    pub async fn advise_indexes(&self, req: QueryRequest) -> AppResult<IndexAdvice> {
+        let sql = match ChangePreviewSql::to_select(&req.sql, sql_lexer::Dialect::ANY) {
+            Some(select) => select,
+            None if SqlValidator::is_select(&req.sql) => req.sql.clone(),
+            None => {
+                return Err(AppError::InvalidInput(
+                    "仅支持分析单条 SELECT / UPDATE / DELETE 语句".to_string(),
+                ))
+            }
+        };
+        // 与变更预览相同，SET 子句中的位置参数会使等价 SELECT 的参数错位
+        if !req.params.is_empty() && req.sql.trim_start().to_ascii_uppercase().starts_with("UPDATE") {
+            return Err(AppError::InvalidInput(
+                "UPDATE 分析不支持位置参数，请使用 named_params".to_string(),
+            ));
         }
+        SqlValidator::validate(&sql)?;
 
-        let json: serde_json::Value = response
-            .json()
+        let target = self.check_connection(&req, &sql).await?;
+        let dialect = Dialect::from_db_type(&target.db_type).ok_or_else(|| {
+            AppError::UnsupportedDatabaseType(format!("索引建议仅支持 MySQL 与 PostgreSQL，当前为 {}", target.db_type))
+        })?;
+        let timeout_ms = req.timeout_ms.unwrap_or(target.timeout_ms);
+
+        let explain_sql = dialect.explain(&sql);
+        let explain = QueryRequest {
+            sql: explain_sql.clone(),
+            ..req.clone()
+        };
+        let plan = self.run(&explain, timeout_ms).await?;
+
+        let stmt = StatementColumns::parse(&sql);
+        let mut findings = dialect.findings(&plan);
+        analysis::resolve_findings(&mut findings, &stmt);
+
+        let mut suggestions = Vec::new();
+        for suggestion in analysis::suggest(dialect, &stmt, &findings) {
+            let database = stmt.database(&suggestion.table).or(target.namespace.as_deref());
+            let existing = self
+                .table_indexes(&req.connection_id, database, &suggestion.table)
+                .await;
+            if !analysis::is_covered(&suggestion.columns, &existing) {
+                suggestions.push(suggestion);
+            }
+        }
+
+        tracing::info!(
+            connection_id = %req.connection_id,
+            findings = findings.len(),
+            suggestions = suggestions.len(),
+            "Index advice generated"
+        );
+        Ok(IndexAdvice {
+            db_type: target.db_type,
+            explain_sql,
+            plan,
+            findings,
+            suggestions,
+        })
+    }
+
+    /// 从连接服务读取表上已有的索引，读取失败时按没有索引处理
+    async fn table_indexes(&self, connection_id: &str, database: Option<&str>, table: &str) -> Vec<IndexStats> {
+        self.connections
+            .table_stats(connection_id, self.principal.as_deref(), database, table)
             .await
-            .map_err(|e| AppError::ExternalService(format!("连接服务返回无效响应: {}", e)))?;
+            .map(|s| s.indexes)
+            .unwrap_or_default()
+    }
 
-        Ok(json)
+    /// 执行已确认的 UPDATE/DELETE 或 DDL
+    ///
+    /// 未携带令牌的危险语句（不带 WHERE 的 UPDATE/DELETE、DDL）返回确认要求并
+    /// 签发令牌；其余 UPDATE/DELETE 须携带变更预览签发的令牌。
+    async fn execute_change(&self, req: QueryRequest) -> AppResult<QueryOutcome> {
+        let target = self.check_connection(&req, &req.sql).await?;
+        let dialect = sql_lexer::Dialect::named(&target.db_type);
+        if !SqlValidator::is_ddl(&req.sql) {
+            if ChangePreviewSql::to_select(&req.sql, dialect).is_none() {
+                return Err(AppError::InvalidInput("仅支持执行单条 UPDATE / DELETE 语句".to_string()));
+            }
+            SqlValidator::validate_change(&req.sql)?;
+        }
+        check_returning(&req.sql, &target.db_type)?;
+        if req.confirmation_token.is_none() {
+            if let Some(danger) = confirm::classify(&req.sql, dialect) {
+                return Err(self.require_confirmation(&req, &target, danger).await);
+            }
+        }
+        self.previews.confirm(&req, dialect).await?;
+
+        let timeout_ms = req.timeout_ms.unwrap_or(target.timeout_ms);
+        let result = within(
+            timeout_ms,
+            self.connections.execute_change(&req, self.principal.as_deref(), timeout_ms),
+        )
+        .await?;
+        tracing::info!(connection_id = %req.connection_id, affected_rows = ?result.affected_rows, "Confirmed change executed");
+        self.publish_executed(&req, &result, false);
+        Ok(QueryOutcome {
+            result,
+            cache: None,
+            warning: None,
+        })
+    }
+
+    /// 发布 `query.executed` 事件；事件不含参数值与结果行
+    fn publish_executed(&self, req: &QueryRequest, result: &QueryResult, cached: bool) {
+        let Some(events) = &self.events else {
+            return;
+        };
+        events.publish(
+            kinds::QUERY_EXECUTED,
+            serde_json::json!({
+                "connection_id": req.connection_id,
+                "database": req.database,
+                "sql": req.sql,
+                "principal": self.principal,
+                "row_count": result.row_count,
+                "affected_rows": result.affected_rows,
+                "execution_time_ms": result.execution_time_ms,
+                "cached": cached,
+            }),
+        );
+    }
+
+    /// 为危险语句签发确认令牌，返回附带预估影响行数的确认要求
+    async fn require_confirmation(&self, req: &QueryRequest, target: &TargetInfo, danger: Danger) -> AppError {
+        let estimated_affected_rows = self.estimate_affected_rows(req, target).await;
+        let (confirmation_token, expires_at) = self.previews.issue(req, sql_lexer::Dialect::named(&target.db_type)).await;
+        tracing::info!(
+            connection_id = %req.connection_id,
+            kind = ?danger.kind,
+            estimated_affected_rows = ?estimated_affected_rows,
+            "Dangerous statement requires confirmation"
+        );
+        AppError::ConfirmationRequired(Box::new(ConfirmationRequired {
+            requires_confirmation: true,
+            kind: danger.kind,
+            reason: danger.reason,
+            estimated_affected_rows,
+            confirmation_token,
+            expires_at: expires_at.to_rfc3339(),
+        }))
+    }
+
+    /// 统计危险语句将影响的行数；无法预估、目标库降级或统计失败时返回 `None`
+    async fn estimate_affected_rows(&self, req: &QueryRequest, target: &TargetInfo) -> Option<u64> {
+        let sql = confirm::estimate_sql(&req.sql, &target.db_type)?;
+        // SET 子句不进入统计查询，其中的位置参数会使后续参数错位
+        let change = ChangePreviewSql::is_change(&req.sql, sql_lexer::Dialect::named(&target.db_type));
+        if change && !req.params.is_empty() && !req.sql.trim_start().to_ascii_uppercase().starts_with("DELETE") {
+            return None;
+        }
+        self.guard.check(target.health.as_ref(), &sql, Some(1), false).ok()?;
+
+        let query = QueryRequest {
+            sql,
+            limit: Some(1),
+            params: if change { req.params.clone() } else { Vec::new() },
+            named_params: if change { req.named_params.clone() } else { Default::default() },
+            ..req.clone()
+        };
+        let timeout_ms = req.timeout_ms.unwrap_or(target.timeout_ms);
+        match self.run(&query, timeout_ms).await {
+            Ok(result) => result
+                .rows
+                .first()
+                .and_then(|row| row.first())
+                .and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok()))),
+            Err(e) => {
+                tracing::warn!(connection_id = %req.connection_id, error = %e, "Failed to estimate affected rows");
+                None
+            }
+        }
+    }
+
+    /// 调用连接服务执行只读查询
+    async fn run(&self, req: &QueryRequest, timeout_ms: u64) -> AppResult<QueryResult> {
+        within(timeout_ms, self.connections.execute(req, self.principal.as_deref(), timeout_ms)).await
+    }
+
+    /// 校验异步查询：SQL 引用的表须在连接的库表白名单内，异步查询一律按重查询
+    /// 接受降级检查。返回需要附加到响应的告警，以及任务结果须应用的脱敏规则。
+    pub async fn authorize_async(&self, req: &QueryRequest) -> AppResult<(Option<String>, Option<ConnectionMasking>)> {
+        let target = self.check_connection(req, &req.sql).await?;
+        let warning = self.guard.check(target.health.as_ref(), &req.sql, req.limit, true)?;
+        Ok((warning, target.masking))
+    }
+
+    /// 校验连接的语句策略与库表白名单，并返回连接的默认查询超时与健康状况
+    ///
+    /// MySQL / MariaDB 请求指定 `database` 时，未限定名称的表属于该库。
+    async fn check_connection(&self, req: &QueryRequest, sql: &str) -> AppResult<TargetInfo> {
+        let info = self.get_pool_info(&req.connection_id).await?;
+        if let Some(policy) = parse::<StatementPolicy>(&info.statement_policy_json) {
+            // Cypher 仅支持只读查询，按 SELECT 校验
+            if req.query_language.is_sql() {
+                policy.check_sql(sql)?;
+            } else {
+                policy.check(StatementType::Select)?;
+            }
+        }
+        let db_type = info.db_type.clone();
+        let namespace = match req.database.as_deref() {
+            Some(database) if matches!(db_type.as_str(), "mysql" | "mariadb") => Some(database),
+            _ => info.namespace.as_deref(),
+        };
+        // Cypher 无法按 SQL 解析表名，白名单由连接服务执行查询时校验
+        if let Some(allowlist) = parse::<ConnectionAllowlist>(&info.allowlist_json).filter(|_| req.query_language.is_sql()) {
+            allowlist.check_sql(sql, namespace)?;
+        }
+        Ok(TargetInfo {
+            namespace: namespace.map(str::to_string),
+            db_type,
+            timeout_ms: Some(info.query_timeout_ms)
+                .filter(|ms| *ms > 0)
+                .unwrap_or(self.default_timeout_ms),
+            health: parse(&info.health_json),
+            masking: parse::<ConnectionMasking>(&info.masking_json).filter(|m| m.applies_to(self.principal.as_deref())),
+        })
+    }
+
+    /// 获取连接池信息，优先使用缓存
+    ///
+    /// 连接信息由连接服务按当前主体检查归属后返回，缓存也按主体区分，因此命中缓存
+    /// 同样说明该主体有权访问连接，之后才会读取查询结果缓存。连接服务不可用时退回到
+    /// 过期不久的缓存条目；连接不存在时作废缓存。
+    async fn get_pool_info(&self, connection_id: &str) -> AppResult<PoolInfo> {
+        let principal = self.principal.as_deref();
+        if let Some(info) = self.targets.get(connection_id, principal).await {
+            return Ok(info);
+        }
+        match self.connections.pool_info(connection_id, principal).await {
+            Ok(info) => {
+                self.targets.put(connection_id, principal, info.clone()).await;
+                Ok(info)
+            }
+            Err(AppError::ExternalService(message)) => {
+                if let Some(info) = self.targets.get_stale(connection_id, principal).await {
+                    tracing::warn!(connection_id, error = %message, "Connection service unavailable, using cached connection info");
+                    return Ok(info);
+                }
+                Err(AppError::ExternalService(message))
+            }
+            Err(e) => {
+                self.targets.invalidate(connection_id).await;
+                Err(e)
+            }
+        }
     }
 }
 
+
+/// 按目标连接对当前主体生效的脱敏规则处理结果
+fn mask(target: &TargetInfo, mut result: QueryResult) -> QueryResult {
+    if let Some(masking) = &target.masking {
+        let columns = masking.apply(&mut result);
+        if !columns.is_empty() {
+            tracing::debug!(columns = ?columns, "Query result masked");
+        }
+    }
+    result
+}
+
+/// 目标库方言不支持语句中的 `RETURNING` 子句时提前拒绝（如 MySQL，或 MariaDB 的 UPDATE）
+fn check_returning(sql: &str, db_type: &str) -> AppResult<()> {
+    if !ChangePreviewSql::has_returning(sql, sql_lexer::Dialect::named(db_type)) {
+        return Ok(());
+    }
+    let dialect = db_type.parse::<DbType>().map(|t| t.dialect()).unwrap_or_default();
+    let delete = sql.trim_start().to_ascii_uppercase().starts_with("DELETE");
+    let supported = if delete { dialect.delete_returning } else { dialect.update_returning };
+    if supported {
+        return Ok(());
+    }
+    Err(AppError::InvalidInput(format!(
+        "{} 不支持 {} ... RETURNING 语句",
+        db_type,
+        if delete { "DELETE" } else { "UPDATE" }
+    )))
+}
+
+/// 等待连接服务返回，超过 `timeout_ms`（加余量）未返回时中止并返回超时错误
+async fn within(timeout_ms: u64, call: impl Future<Output = Result<QueryResult, tonic::Status>>) -> AppResult<QueryResult> {
+    tokio::time::timeout(Duration::from_millis(timeout_ms) + TIMEOUT_GRACE, call)
+        .await
+        .map_err(|_| AppError::Timeout(format!("查询超过 {} ms 超时限制", timeout_ms)))?
+        .map_err(|status| grpc::app_error(&status))
+}
+
+/// 连接信息中以 JSON 文本提供的设置，无法解析时按未设置处理
+fn parse<T: serde::de::DeserializeOwned>(json: &Option<String>) -> Option<T> {
+    json.as_deref().and_then(|j| serde_json::from_str(j).ok())
+}
@@ -1,22 +1,67 @@
 //! Application state for query service.
 
-use common::config::{AppConfig, ServiceUrls};
+use std::sync::Arc;
+
+use common::config::{ServiceUrls, SharedConfig};
+use common::events::EventPublisher;
+use common::middleware::RequestSigner;
+use common::progress::ProgressHub;
+use crate::batch::Batch;
+use crate::cache::QueryCache;
+use crate::connections::ConnectionClient;
+use crate::fanout::FanOut;
+use crate::guard::TargetGuard;
+use crate::jobs::QueryJobManager;
+use crate::preview::ChangePreviewStore;
+use crate::targets::TargetCache;
 
 /// Application state shared across handlers.
 #[derive(Clone)]
 pub struct AppState {
-    pub config: AppConfig,
-    pub service_urls: ServiceUrls,
-    pub http_client: reqwest::Client,
+    pub config: Arc<SharedConfig>,
+    pub connections: ConnectionClient,
+    pub query_jobs: Arc<QueryJobManager>,
+    pub query_cache: Arc<QueryCache>,
+    pub targets: Arc<TargetCache>,
+    pub target_guard: TargetGuard,
+    pub change_previews: Arc<ChangePreviewStore>,
+    pub fan_out: FanOut,
+    pub batch: Batch,
+    pub events: Arc<EventPublisher>,
+    pub progress: Arc<ProgressHub>,
 }
 
 impl AppState {
     /// Creates a new application state.
-    pub fn new(config: AppConfig) -> Self {
+    pub async fn new(config: Arc<SharedConfig>) -> Self {
+        let service_name = config.get().service_name.clone();
+        let service_urls = ServiceUrls::load();
+        let connections = ConnectionClient::new(
+            &service_urls.connection_service,
+            reqwest::Client::new(),
+            RequestSigner::from_env(service_name.clone()),
+        )
+        .expect("Invalid CONNECTION_SERVICE_URL");
+        let progress = Arc::new(ProgressHub::new());
+        let query_jobs = Arc::new(QueryJobManager::new(connections.clone(), progress.clone()));
+        let query_cache = Arc::new(QueryCache::new().await);
+        let events = Arc::new(EventPublisher::from_env(service_name).await);
+        let targets = Arc::new(TargetCache::from_env());
+        if let Some(bus) = events.bus() {
+            targets.spawn_invalidation(bus.clone());
+        }
         Self {
             config,
-            service_urls: ServiceUrls::load(),
-            http_client: reqwest::Client::new(),
+            connections,
+            query_jobs,
+            query_cache,
+            targets,
+            target_guard: TargetGuard::from_env(),
+            change_previews: Arc::new(ChangePreviewStore::from_env()),
+            fan_out: FanOut::from_env(),
+            batch: Batch::from_env(),
+            events,
+            progress,
         }
     }
 }
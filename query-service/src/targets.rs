@@ -0,0 +1,216 @@
+//! 连接信息缓存模块
+//!
+//! 每条查询执行前都要从连接服务读取连接信息（库类型、库表白名单、脱敏规则、
+//! 默认超时与健康状况）。这里缓存这些信息，有效期内的请求不再访问连接服务。
+//! 连接信息由连接服务按调用方检查归属后返回，条目按连接与主体分别缓存，一个
+//! 主体读到的信息不会用于另一个主体的请求。连接被修改或删除时连接服务发布
+//! `connection.updated` / `connection.deleted` 事件，收到后立即作废该连接的
+//! 全部条目；订阅中断期间可能漏掉
+//! 事件，因此重新订阅前清空整个缓存。未启用事件总线时条目只按有效期过期。
+//!
+//! 连接服务暂时不可用时，过期不久的条目仍可用于执行前的校验。缓存的是连接
+//! 服务返回的连接信息，不含连接池与凭据：语句仍由连接服务执行，连接服务不可
+//! 用时查询照常失败。
+//!
+//! 配置：
+//! - `QUERY_TARGET_CACHE_TTL_SECS` - 条目有效期（默认 30，0 表示不缓存）
+//! - `QUERY_TARGET_CACHE_STALE_SECS` - 连接服务不可用时过期条目的可用时长（默认 300）
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use tokio::sync::RwLock;
+
+use common::events::{kinds, EventBus};
+use common::grpc::proto::PoolInfo;
+
+const DEFAULT_TTL_SECS: u64 = 30;
+const DEFAULT_STALE_SECS: u64 = 300;
+
+/// 订阅中断后重新订阅的间隔
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// 缓存条目
+struct Entry {
+    info: PoolInfo,
+    fetched_at: Instant,
+}
+
+/// 缓存键：连接 ID 与发起请求的主体
+type Key = (String, Option<String>);
+
+fn key(connection_id: &str, principal: Option<&str>) -> Key {
+    (connection_id.to_string(), principal.map(str::to_string))
+}
+
+/// 按连接与主体缓存的连接信息
+pub struct TargetCache {
+    ttl: Duration,
+    stale: Duration,
+    entries: RwLock<HashMap<Key, Entry>>,
+}
+
+impl TargetCache {
+    /// 创建缓存，`ttl_secs` 为 0 时不缓存
+    pub fn new(ttl_secs: u64, stale_secs: u64) -> Self {
+        Self {
+            ttl: Duration::from_secs(ttl_secs),
+            stale: Duration::from_secs(stale_secs),
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 从环境变量读取有效期
+    pub fn from_env() -> Self {
+        fn env(key: &str, default: u64) -> u64 {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        Self::new(
+            env("QUERY_TARGET_CACHE_TTL_SECS", DEFAULT_TTL_SECS),
+            env("QUERY_TARGET_CACHE_STALE_SECS", DEFAULT_STALE_SECS),
+        )
+    }
+
+    fn enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// 返回有效期内 `principal` 读取过的连接信息
+    pub async fn get(&self, connection_id: &str, principal: Option<&str>) -> Option<PoolInfo> {
+        self.lookup(connection_id, principal, self.ttl).await
+    }
+
+    /// 返回过期不超过 `QUERY_TARGET_CACHE_STALE_SECS` 的连接信息，仅在连接服务
+    /// 不可用时使用
+    pub async fn get_stale(&self, connection_id: &str, principal: Option<&str>) -> Option<PoolInfo> {
+        self.lookup(connection_id, principal, self.ttl + self.stale).await
+    }
+
+    async fn lookup(&self, connection_id: &str, principal: Option<&str>, max_age: Duration) -> Option<PoolInfo> {
+        if !self.enabled() {
+            return None;
+        }
+        self.entries
+            .read()
+            .await
+            .get(&key(connection_id, principal))
+            .filter(|entry| entry.fetched_at.elapsed() < max_age)
+            .map(|entry| entry.info.clone())
+    }
+
+    /// 保存连接服务为 `principal` 返回的连接信息，顺带清理彻底过期的条目
+    pub async fn put(&self, connection_id: &str, principal: Option<&str>, info: PoolInfo) {
+        if !self.enabled() {
+            return;
+        }
+        let max_age = self.ttl + self.stale;
+        let mut entries = self.entries.write().await;
+        entries.retain(|_, entry| entry.fetched_at.elapsed() < max_age);
+        entries.insert(
+            key(connection_id, principal),
+            Entry {
+                info,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    /// 作废一个连接对所有主体的缓存信息
+    pub async fn invalidate(&self, connection_id: &str) {
+        self.entries.write().await.retain(|(id, _), _| id != connection_id);
+    }
+
+    /// 清空缓存
+    pub async fn clear(&self) {
+        self.entries.write().await.clear();
+    }
+
+    /// 启动后台任务，订阅连接事件并作废被修改或删除的连接
+    pub fn spawn_invalidation(self: &Arc<Self>, bus: Arc<dyn EventBus>) {
+        if !self.enabled() {
+            return;
+        }
+        let cache = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match bus.subscribe("connection.*").await {
+                    Ok(mut events) => {
+                        // 订阅建立前的事件已无法收到
+                        cache.clear().await;
+                        while let Some(event) = events.next().await {
+                            if event.kind != kinds::CONNECTION_UPDATED && event.kind != kinds::CONNECTION_DELETED {
+                                continue;
+                            }
+                            if let Some(id) = event.data["connection_id"].as_str() {
+                                tracing::debug!(connection_id = id, kind = %event.kind, "Connection info invalidated");
+                                cache.invalidate(id).await;
+                            }
+                        }
+                        tracing::warn!(bus = bus.name(), "Connection event subscription ended, resubscribing");
+                    }
+                    Err(e) => {
+                        tracing::warn!(bus = bus.name(), error = %e, "Failed to subscribe to connection events");
+                    }
+                }
+                cache.clear().await;
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(db_type: &str) -> PoolInfo {
+        PoolInfo { db_type: db_type.to_string(), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn entries_expire_and_can_be_invalidated() {
+        let cache = TargetCache::new(60, 0);
+        cache.put("c1", None, info("mysql")).await;
+        cache.put("c1", Some("user:alice"), info("mysql")).await;
+        cache.put("c2", None, info("postgres")).await;
+        assert_eq!(cache.get("c1", None).await.unwrap().db_type, "mysql");
+
+        cache.invalidate("c1").await;
+        assert!(cache.get("c1", None).await.is_none());
+        assert!(cache.get("c1", Some("user:alice")).await.is_none());
+        assert!(cache.get_stale("c1", None).await.is_none());
+        assert!(cache.get("c2", None).await.is_some());
+
+        cache.clear().await;
+        assert!(cache.get("c2", None).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn stale_entries_are_only_served_by_get_stale() {
+        let cache = TargetCache::new(60, 300);
+        cache.put("c1", None, info("mysql")).await;
+        cache.entries.write().await.get_mut(&key("c1", None)).unwrap().fetched_at -= Duration::from_secs(120);
+        assert!(cache.get("c1", None).await.is_none());
+        assert!(cache.get_stale("c1", None).await.is_some());
+
+        let disabled = TargetCache::new(0, 300);
+        disabled.put("c1", None, info("mysql")).await;
+        assert!(disabled.get_stale("c1", None).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn entries_are_not_shared_between_principals() {
+        let cache = TargetCache::new(60, 300);
+        cache.put("c1", Some("user:alice"), info("mysql")).await;
+        assert!(cache.get("c1", Some("user:alice")).await.is_some());
+        assert!(cache.get("c1", Some("user:bob")).await.is_none());
+        assert!(cache.get_stale("c1", Some("user:bob")).await.is_none());
+        assert!(cache.get("c1", None).await.is_none());
+    }
+}